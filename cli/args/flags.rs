@@ -4,6 +4,7 @@ use std::borrow::Cow;
 use std::collections::HashSet;
 use std::env;
 use std::ffi::OsString;
+use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::num::NonZeroU32;
 use std::num::NonZeroU8;
@@ -18,6 +19,7 @@ use clap::error::ErrorKind;
 use clap::value_parser;
 use clap::Arg;
 use clap::ArgAction;
+use clap::ArgGroup;
 use clap::ArgMatches;
 use clap::ColorChoice;
 use clap::Command;
@@ -125,6 +127,10 @@ pub struct CompileFlags {
   pub no_terminal: bool,
   pub icon: Option<String>,
   pub include: Vec<String>,
+  pub embed_ca_files: Vec<String>,
+  pub tls_min_version: Option<String>,
+  pub tls_alpn_protocols: Option<Vec<String>>,
+  pub verify_assets: Option<String>,
 }
 
 impl CompileFlags {
@@ -590,6 +596,36 @@ pub struct InternalFlags {
   pub lockfile_skip_write: bool,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TickBudgetFlag {
+  pub budget_ms: u64,
+  /// Interrupt ticks that overrun the budget instead of only warning.
+  pub yield_on_overrun: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DnsServerFlag {
+  pub addr: SocketAddr,
+  pub tcp: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DnsResolverFlag {
+  /// `--dns-server`: query these name servers, in order.
+  Servers {
+    servers: Vec<DnsServerFlag>,
+    rotate: bool,
+  },
+  /// `--dns-over-https`: query this DNS-over-HTTPS endpoint.
+  Https { url: Url, bootstrap: Vec<IpAddr> },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DnsFlags {
+  pub resolver: DnsResolverFlag,
+  pub timeout_ms: Option<u64>,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 pub struct Flags {
   /// Vector of CLI arguments - these are user script arguments, all Deno
@@ -604,6 +640,7 @@ pub struct Flags {
   pub cached_only: bool,
   pub type_check_mode: TypeCheckMode,
   pub config_flag: ConfigFlag,
+  pub dns: Option<DnsFlags>,
   pub node_modules_dir: Option<NodeModulesDirMode>,
   pub vendor: Option<bool>,
   pub enable_op_summary_metrics: bool,
@@ -626,6 +663,7 @@ pub struct Flags {
   pub reload: bool,
   pub seed: Option<u64>,
   pub strace_ops: Option<Vec<String>>,
+  pub tick_budget: Option<TickBudgetFlag>,
   pub unstable_config: UnstableConfig,
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
   pub v8_flags: Vec<String>,
@@ -653,6 +691,8 @@ pub struct PermissionFlags {
   pub deny_write: Option<Vec<String>>,
   pub no_prompt: bool,
   pub allow_import: Option<Vec<String>>,
+  pub allow_read_frozen: Option<Vec<String>>,
+  pub frozen_read_audit: bool,
 }
 
 impl PermissionFlags {
@@ -673,6 +713,7 @@ impl PermissionFlags {
       || self.allow_write.is_some()
       || self.deny_write.is_some()
       || self.allow_import.is_some()
+      || self.allow_read_frozen.is_some()
   }
 
   pub fn to_options(&self, cli_arg_urls: &[Cow<Url>]) -> PermissionsOptions {
@@ -688,6 +729,23 @@ impl PermissionFlags {
       }
     }
 
+    // frozen paths are readable, the snapshot only restricts what is visible
+    fn handle_allow_read_frozen(
+      allow_read: Option<Vec<String>>,
+      frozen: Option<&[String]>,
+    ) -> Option<Vec<String>> {
+      match (allow_read, frozen) {
+        // already allowed to read everything
+        (Some(allow_read), _) if allow_read.is_empty() => Some(allow_read),
+        (allow_read, Some(frozen)) => {
+          let mut allow_read = allow_read.unwrap_or_default();
+          allow_read.extend(frozen.iter().cloned());
+          Some(allow_read)
+        }
+        (allow_read, None) => allow_read,
+      }
+    }
+
     fn handle_imports(
       cli_arg_urls: &[Cow<Url>],
       imports: Option<Vec<String>>,
@@ -732,7 +790,10 @@ impl PermissionFlags {
       deny_net: self.deny_net.clone(),
       allow_ffi: handle_allow(self.allow_all, self.allow_ffi.clone()),
       deny_ffi: self.deny_ffi.clone(),
-      allow_read: handle_allow(self.allow_all, self.allow_read.clone()),
+      allow_read: handle_allow_read_frozen(
+        handle_allow(self.allow_all, self.allow_read.clone()),
+        self.allow_read_frozen.as_deref(),
+      ),
       deny_read: self.deny_read.clone(),
       allow_run: handle_allow(self.allow_all, self.allow_run.clone()),
       deny_run: self.deny_run.clone(),
@@ -797,6 +858,14 @@ impl Flags {
       _ => {}
     }
 
+    if let Some(frozen) = &self.permissions.allow_read_frozen {
+      let s = format!("--allow-read-frozen={}", join_paths(frozen, ","));
+      args.push(s);
+      if self.permissions.frozen_read_audit {
+        args.push("--frozen-read-audit".to_string());
+      }
+    }
+
     match &self.permissions.deny_read {
       Some(read_denylist) if read_denylist.is_empty() => {
         args.push("--deny-read".to_string());
@@ -1935,6 +2004,49 @@ On the first invocation with deno will download the proper binary and cache it i
           .value_parser(value_parser!(String))
           .help_heading(COMPILE_HEADING),
       )
+      .arg(
+        Arg::new("embed-ca-file")
+          .long("embed-ca-file")
+          .value_name("FILE")
+          .help(
+            cstr!("Embeds a PEM encoded CA bundle used to verify TLS connections.
+  <p(245)>The certificates are trusted in addition to the default store. A DENO_CERT
+  set when running the executable takes precedence over them. This flag can
+  be passed multiple times.</>",
+          ))
+          .action(ArgAction::Append)
+          .value_hint(ValueHint::FilePath)
+          .help_heading(COMPILE_HEADING),
+      )
+      .arg(
+        Arg::new("tls-min-version")
+          .long("tls-min-version")
+          .help("Minimum TLS version for Deno.connectTls and Deno.startTls")
+          .value_parser(["1.2", "1.3"])
+          .help_heading(COMPILE_HEADING),
+      )
+      .arg(
+        Arg::new("tls-alpn-protocols")
+          .long("tls-alpn-protocols")
+          .help("Default ALPN protocols for Deno.connectTls and Deno.startTls")
+          .value_name("PROTOCOLS")
+          .require_equals(true)
+          .num_args(1..)
+          .use_value_delimiter(true)
+          .help_heading(COMPILE_HEADING),
+      )
+      .arg(
+        Arg::new("verify-assets")
+          .long("verify-assets")
+          .value_name("DIR")
+          .help(
+            cstr!("Embeds an integrity manifest of an asset directory, which is verified at startup.
+  <p(245)>The executable expects a directory of the same name next to it and refuses to
+  start when a file in it was added, removed or modified.</>",
+          ))
+          .value_hint(ValueHint::DirPath)
+          .help_heading(COMPILE_HEADING),
+      )
       .arg(executable_ext_arg())
       .arg(env_file_arg())
       .arg(
@@ -3268,6 +3380,9 @@ fn permission_args(app: Command, requires: Option<&'static str>) -> Command {
                                              <p(245)>Can also be set via the DENO_NO_PROMPT environment variable.</>
   <g>-R, --allow-read[=<<PATH>...]</>             Allow file system read access. Optionally specify allowed paths.
                                              <p(245)>--allow-read  |  --allow-read="/etc,/var/log.txt"</>
+      <g>--allow-read-frozen=<<PATH>...</>       Allow file system read access to paths as they were at startup.
+                                             <p(245)>Files created later are not found, removed files raise StaleRead.</>
+      <g>--frozen-read-audit</>                  Log reads that violate --allow-read-frozen instead of failing them.
   <g>-W, --allow-write[=<<PATH>...]</>            Allow file system write access. Optionally specify allowed paths.
                                              <p(245)>--allow-write  |  --allow-write="/etc,/var/log.txt"</>
   <g>-I, --allow-import[=<<IP_OR_HOSTNAME>...]</> Allow importing from remote hosts. Optionally specify allowed IP addresses and host names, with ports as necessary.
@@ -3342,6 +3457,31 @@ fn permission_args(app: Command, requires: Option<&'static str>) -> Command {
         arg
       }
     )
+    .arg(
+      {
+        let mut arg = Arg::new("allow-read-frozen")
+          .long("allow-read-frozen")
+          .num_args(1..)
+          .action(ArgAction::Append)
+          .require_equals(true)
+          .value_name("PATH")
+          .help("Allow file system read access to paths as they were at startup")
+          .value_hint(ValueHint::AnyPath)
+          .hide(true);
+        if let Some(requires) = requires {
+          arg = arg.requires(requires)
+        }
+        arg
+      }
+    )
+    .arg(
+      Arg::new("frozen-read-audit")
+        .long("frozen-read-audit")
+        .action(ArgAction::SetTrue)
+        .requires("allow-read-frozen")
+        .help("Log reads that violate --allow-read-frozen instead of failing them")
+        .hide(true),
+    )
     .arg(
       {
         let mut arg = Arg::new("allow-write")
@@ -3680,6 +3820,14 @@ fn runtime_misc_args(app: Command) -> Command {
     .arg(seed_arg())
     .arg(enable_testing_features_arg())
     .arg(strace_ops_arg())
+    .arg(tick_budget_arg())
+    .arg(tick_budget_yield_arg())
+    .arg(dns_server_arg())
+    .arg(dns_rotate_arg())
+    .arg(dns_over_https_arg())
+    .arg(dns_bootstrap_arg())
+    .arg(dns_timeout_arg())
+    .group(ArgGroup::new("dns-resolver").args(["dns-server", "dns-over-https"]))
 }
 
 fn allow_import_arg() -> Arg {
@@ -3867,6 +4015,98 @@ fn strace_ops_arg() -> Arg {
     .hide(true)
 }
 
+fn tick_budget_arg() -> Arg {
+  Arg::new("tick-budget")
+    .long("tick-budget")
+    .require_equals(true)
+    .value_name("MILLISECONDS")
+    .help("Warn, with the running JavaScript stack, when an event loop tick runs for longer than this")
+    .value_parser(value_parser!(u64).range(1..))
+}
+
+fn tick_budget_yield_arg() -> Arg {
+  Arg::new("tick-budget-yield")
+    .long("tick-budget-yield")
+    .requires("tick-budget")
+    .action(ArgAction::SetTrue)
+    .help("Terminate the running code when a tick overruns --tick-budget, discarding queued microtasks")
+}
+
+fn dns_server_arg() -> Arg {
+  Arg::new("dns-server")
+    .long("dns-server")
+    .num_args(1..)
+    .use_value_delimiter(true)
+    .require_equals(true)
+    .value_name("ADDRESSES")
+    .help(cstr!("Resolve names with these name servers instead of the system resolver
+  <p(245)>Servers are asked in order; prefix an address with tcp:// to use TCP.</>
+  <p(245)>Example: --dns-server=10.0.0.53,tcp://[fd00::53]:5353</>"))
+    .value_parser(parse_dns_server)
+}
+
+fn dns_rotate_arg() -> Arg {
+  Arg::new("dns-rotate")
+    .long("dns-rotate")
+    .requires("dns-server")
+    .action(ArgAction::SetTrue)
+    .help("Spread queries over the --dns-server servers, preferring the fastest, instead of asking them in order")
+}
+
+fn dns_over_https_arg() -> Arg {
+  Arg::new("dns-over-https")
+    .long("dns-over-https")
+    .require_equals(true)
+    .value_name("URL")
+    .help(cstr!("Resolve names with this DNS-over-HTTPS endpoint instead of the system resolver
+  <p(245)>Requires --dns-bootstrap unless the host of the URL is an IP address.</>
+  <p(245)>Example: --dns-over-https=https://dns.example/dns-query</>"))
+    .value_parser(value_parser!(Url))
+}
+
+fn dns_bootstrap_arg() -> Arg {
+  Arg::new("dns-bootstrap")
+    .long("dns-bootstrap")
+    .requires("dns-over-https")
+    .num_args(1..)
+    .use_value_delimiter(true)
+    .require_equals(true)
+    .value_name("IP_ADDRESSES")
+    .help("Addresses of the --dns-over-https host, which can't be resolved through the endpoint itself")
+    .value_parser(value_parser!(IpAddr))
+}
+
+fn dns_timeout_arg() -> Arg {
+  Arg::new("dns-timeout")
+    .long("dns-timeout")
+    .requires("dns-resolver")
+    .require_equals(true)
+    .value_name("MILLISECONDS")
+    .help("Time to wait for a DNS server before trying the next one")
+    .value_parser(value_parser!(u64).range(1..))
+}
+
+fn parse_dns_server(s: &str) -> Result<DnsServerFlag, String> {
+  let (tcp, addr) = match s.split_once("://") {
+    Some(("udp", addr)) => (false, addr),
+    Some(("tcp", addr)) => (true, addr),
+    Some((scheme, _)) => {
+      return Err(format!(
+        "unsupported protocol '{scheme}', expected udp or tcp"
+      ))
+    }
+    None => (false, s),
+  };
+  let addr = match addr.parse::<SocketAddr>() {
+    Ok(addr) => addr,
+    Err(_) => match addr.parse::<IpAddr>() {
+      Ok(ip) => SocketAddr::new(ip, 53),
+      Err(_) => return Err(format!("invalid name server address '{addr}'")),
+    },
+  };
+  Ok(DnsServerFlag { addr, tcp })
+}
+
 fn v8_flags_arg() -> Arg {
   Arg::new("v8-flags")
     .long("v8-flags")
@@ -4410,6 +4650,15 @@ fn compile_parse(
     Some(f) => f.collect(),
     None => vec![],
   };
+  let embed_ca_files = match matches.remove_many::<String>("embed-ca-file") {
+    Some(f) => f.collect(),
+    None => vec![],
+  };
+  let tls_min_version = matches.remove_one::<String>("tls-min-version");
+  let tls_alpn_protocols = matches
+    .remove_many::<String>("tls-alpn-protocols")
+    .map(|p| p.collect());
+  let verify_assets = matches.remove_one::<String>("verify-assets");
   ext_arg_parse(flags, matches);
 
   flags.subcommand = DenoSubcommand::Compile(CompileFlags {
@@ -4420,6 +4669,10 @@ fn compile_parse(
     no_terminal,
     icon,
     include,
+    embed_ca_files,
+    tls_min_version,
+    tls_alpn_protocols,
+    verify_assets,
   });
 
   Ok(())
@@ -4903,6 +5156,8 @@ fn repl_parse(
   enable_testing_features_arg_parse(flags, matches);
   env_file_arg_parse(flags, matches);
   strace_ops_parse(flags, matches);
+  tick_budget_arg_parse(flags, matches);
+  dns_arg_parse(flags, matches);
 
   let eval_files = matches
     .remove_many::<String>("eval-file")
@@ -5310,6 +5565,15 @@ fn permission_args_parse(
     flags.permissions.allow_read = Some(read_wl);
   }
 
+  if let Some(frozen_wl) = matches.remove_many::<String>("allow-read-frozen") {
+    let frozen_wl = frozen_wl
+      .flat_map(flat_escape_split_commas)
+      .collect::<Result<Vec<_>, _>>()?;
+    flags.permissions.allow_read_frozen = Some(frozen_wl);
+  }
+
+  flags.permissions.frozen_read_audit = matches.get_flag("frozen-read-audit");
+
   if let Some(read_wl) = matches.remove_many::<String>("deny-read") {
     let read_wl = read_wl
       .flat_map(flat_escape_split_commas)
@@ -5456,6 +5720,8 @@ fn runtime_args_parse(
   enable_testing_features_arg_parse(flags, matches);
   env_file_arg_parse(flags, matches);
   strace_ops_parse(flags, matches);
+  tick_budget_arg_parse(flags, matches);
+  dns_arg_parse(flags, matches);
   Ok(())
 }
 
@@ -5513,6 +5779,39 @@ fn strace_ops_parse(flags: &mut Flags, matches: &mut ArgMatches) {
   }
 }
 
+fn tick_budget_arg_parse(flags: &mut Flags, matches: &mut ArgMatches) {
+  if let Some(budget_ms) = matches.remove_one::<u64>("tick-budget") {
+    flags.tick_budget = Some(TickBudgetFlag {
+      budget_ms,
+      yield_on_overrun: matches.get_flag("tick-budget-yield"),
+    });
+  }
+}
+
+fn dns_arg_parse(flags: &mut Flags, matches: &mut ArgMatches) {
+  let resolver =
+    if let Some(servers) = matches.remove_many::<DnsServerFlag>("dns-server") {
+      DnsResolverFlag::Servers {
+        servers: servers.collect(),
+        rotate: matches.get_flag("dns-rotate"),
+      }
+    } else if let Some(url) = matches.remove_one::<Url>("dns-over-https") {
+      DnsResolverFlag::Https {
+        url,
+        bootstrap: matches
+          .remove_many::<IpAddr>("dns-bootstrap")
+          .map(|ips| ips.collect())
+          .unwrap_or_default(),
+      }
+    } else {
+      return;
+    };
+  flags.dns = Some(DnsFlags {
+    resolver,
+    timeout_ms: matches.remove_one::<u64>("dns-timeout"),
+  });
+}
+
 fn cached_only_arg_parse(flags: &mut Flags, matches: &mut ArgMatches) {
   if matches.get_flag("cached-only") {
     flags.cached_only = true;
@@ -6447,6 +6746,45 @@ mod tests {
     );
   }
 
+  #[test]
+  fn allow_read_frozen() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--allow-read-frozen=./vendor,./data",
+      "--frozen-read-audit",
+      "gist.ts"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags::new_default(
+          "gist.ts".to_string()
+        )),
+        permissions: PermissionFlags {
+          allow_read_frozen: Some(svec!["./vendor", "./data"]),
+          frozen_read_audit: true,
+          ..Default::default()
+        },
+        code_cache_enabled: true,
+        ..Flags::default()
+      }
+    );
+    assert_eq!(
+      flags.permissions.to_options(&[]).allow_read,
+      Some(svec!["./vendor", "./data"])
+    );
+    assert_eq!(
+      flags.to_permission_args(),
+      svec!["--allow-read-frozen=./vendor,./data", "--frozen-read-audit"]
+    );
+
+    let r =
+      flags_from_vec(svec!["deno", "run", "--frozen-read-audit", "gist.ts"]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn short_permission_flags() {
     let r = flags_from_vec(svec!["deno", "run", "-RNESWI", "gist.ts"]);
@@ -8258,6 +8596,117 @@ mod tests {
     );
   }
 
+  #[test]
+  fn run_tick_budget() {
+    let r =
+      flags_from_vec(svec!["deno", "run", "--tick-budget=50", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags::new_default(
+          "script.ts".to_string(),
+        )),
+        tick_budget: Some(TickBudgetFlag {
+          budget_ms: 50,
+          yield_on_overrun: false,
+        }),
+        code_cache_enabled: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--tick-budget=50",
+      "--tick-budget-yield",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap().tick_budget,
+      Some(TickBudgetFlag {
+        budget_ms: 50,
+        yield_on_overrun: true,
+      })
+    );
+
+    let r =
+      flags_from_vec(svec!["deno", "run", "--tick-budget-yield", "script.ts"]);
+    assert!(r.is_err());
+    let r =
+      flags_from_vec(svec!["deno", "run", "--tick-budget=0", "script.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_dns() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--dns-server=10.0.0.53,tcp://[::1]:5353",
+      "--dns-rotate",
+      "--dns-timeout=500",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap().dns,
+      Some(DnsFlags {
+        resolver: DnsResolverFlag::Servers {
+          servers: vec![
+            DnsServerFlag {
+              addr: "10.0.0.53:53".parse().unwrap(),
+              tcp: false,
+            },
+            DnsServerFlag {
+              addr: "[::1]:5353".parse().unwrap(),
+              tcp: true,
+            },
+          ],
+          rotate: true,
+        },
+        timeout_ms: Some(500),
+      })
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--dns-over-https=https://dns.example/dns-query",
+      "--dns-bootstrap=192.0.2.1,192.0.2.2",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap().dns,
+      Some(DnsFlags {
+        resolver: DnsResolverFlag::Https {
+          url: Url::parse("https://dns.example/dns-query").unwrap(),
+          bootstrap: vec![
+            "192.0.2.1".parse().unwrap(),
+            "192.0.2.2".parse().unwrap()
+          ],
+        },
+        timeout_ms: None,
+      })
+    );
+
+    for args in [
+      svec!["deno", "run", "--dns-server=quic://1.1.1.1", "script.ts"],
+      svec!["deno", "run", "--dns-server=dns.example", "script.ts"],
+      svec!["deno", "run", "--dns-rotate", "script.ts"],
+      svec!["deno", "run", "--dns-timeout=100", "script.ts"],
+      svec!["deno", "run", "--dns-bootstrap=192.0.2.1", "script.ts"],
+      svec![
+        "deno",
+        "run",
+        "--dns-server=1.1.1.1",
+        "--dns-over-https=https://1.1.1.1/dns-query",
+        "script.ts"
+      ],
+    ] {
+      assert!(flags_from_vec(args).is_err());
+    }
+  }
+
   #[test]
   fn run_seed_with_v8_flags() {
     let r = flags_from_vec(svec![
@@ -9995,7 +10444,87 @@ mod tests {
           target: None,
           no_terminal: false,
           icon: None,
-          include: vec![]
+          include: vec![],
+          embed_ca_files: vec![],
+          tls_min_version: None,
+          tls_alpn_protocols: None,
+          verify_assets: None,
+        }),
+        type_check_mode: TypeCheckMode::Local,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn compile_with_tls_options() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "compile",
+      "--embed-ca-file",
+      "internal_ca.pem",
+      "--embed-ca-file",
+      "partner_ca.pem",
+      "--tls-min-version",
+      "1.3",
+      "--tls-alpn-protocols=h2,http/1.1",
+      "main.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Compile(CompileFlags {
+          source_file: "main.ts".to_string(),
+          output: None,
+          args: vec![],
+          target: None,
+          no_terminal: false,
+          icon: None,
+          include: vec![],
+          embed_ca_files: svec!["internal_ca.pem", "partner_ca.pem"],
+          tls_min_version: Some("1.3".to_string()),
+          tls_alpn_protocols: Some(svec!["h2", "http/1.1"]),
+          verify_assets: None,
+        }),
+        type_check_mode: TypeCheckMode::Local,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "compile",
+      "--tls-min-version",
+      "1.1",
+      "main.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn compile_with_verify_assets() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "compile",
+      "--verify-assets",
+      "plugins",
+      "main.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Compile(CompileFlags {
+          source_file: "main.ts".to_string(),
+          output: None,
+          args: vec![],
+          target: None,
+          no_terminal: false,
+          icon: None,
+          include: vec![],
+          embed_ca_files: vec![],
+          tls_min_version: None,
+          tls_alpn_protocols: None,
+          verify_assets: Some("plugins".to_string()),
         }),
         type_check_mode: TypeCheckMode::Local,
         ..Flags::default()
@@ -10018,7 +10547,11 @@ mod tests {
           target: None,
           no_terminal: true,
           icon: Some(String::from("favicon.ico")),
-          include: vec![]
+          include: vec![],
+          embed_ca_files: vec![],
+          tls_min_version: None,
+          tls_alpn_protocols: None,
+          verify_assets: None,
         }),
         import_map_path: Some("import_map.json".to_string()),
         no_remote: true,
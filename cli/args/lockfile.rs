@@ -1,20 +1,31 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::cell::RefCell;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use deno_config::deno_json::ConfigFile;
 use deno_config::workspace::Workspace;
 use deno_core::anyhow::Context;
+use deno_core::error::type_error;
 use deno_core::error::AnyError;
+use deno_core::op2;
 use deno_core::parking_lot::Mutex;
 use deno_core::parking_lot::MutexGuard;
+use deno_core::serde::Serialize;
+use deno_core::ModuleSpecifier;
+use deno_core::OpState;
 use deno_lockfile::WorkspaceMemberConfig;
 use deno_package_json::PackageJsonDepValue;
 use deno_runtime::deno_node::PackageJson;
+use deno_runtime::deno_permissions::PermissionsContainer;
 use deno_semver::jsr::JsrDepPackageReq;
 
 use crate::cache;
+use crate::file_fetcher::FileFetcher;
+use crate::util::checksum;
 use crate::util::fs::atomic_write_file_with_retries;
 use crate::Flags;
 
@@ -58,6 +69,28 @@ impl<'a, T> std::ops::DerefMut for Guard<'a, T> {
   }
 }
 
+/// How a remote module differs from its entry in the lockfile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteModuleChange {
+  /// The module has no checksum in the lockfile.
+  Added,
+  /// The module doesn't match its checksum in the lockfile.
+  Changed,
+  Unchanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteModuleDiff {
+  pub specifier: String,
+  /// The checksum in the lockfile.
+  pub expected: Option<String>,
+  /// The checksum of the module's source.
+  pub actual: String,
+  pub change: RemoteModuleChange,
+}
+
 impl CliLockfile {
   /// Get the inner deno_lockfile::Lockfile.
   pub fn lock(&self) -> Guard<Lockfile> {
@@ -66,6 +99,34 @@ impl CliLockfile {
     }
   }
 
+  /// Compares the source of a remote module with its checksum in the
+  /// lockfile, without updating the lockfile. The source is hashed in
+  /// place, so large modules aren't copied.
+  pub fn diff_remote_module(
+    &self,
+    specifier: &ModuleSpecifier,
+    source: &[u8],
+  ) -> RemoteModuleDiff {
+    let actual = checksum::gen(&[source]);
+    let expected = self
+      .lockfile
+      .lock()
+      .remote()
+      .get(specifier.as_str())
+      .cloned();
+    let change = match &expected {
+      None => RemoteModuleChange::Added,
+      Some(expected) if *expected != actual => RemoteModuleChange::Changed,
+      Some(_) => RemoteModuleChange::Unchanged,
+    };
+    RemoteModuleDiff {
+      specifier: specifier.to_string(),
+      expected,
+      actual,
+      change,
+    }
+  }
+
   pub fn set_workspace_config(
     &self,
     options: deno_lockfile::SetWorkspaceConfigOptions,
@@ -257,11 +318,128 @@ impl CliLockfile {
       let diff = crate::util::diff::diff(&contents, &new_contents);
       // has an extra newline at the end
       let diff = diff.trim_end();
+      let added = added_remote_modules(&lockfile, &contents);
+      let added = if added.is_empty() {
+        String::new()
+      } else {
+        format!(
+          "\nremote modules not in the lockfile:\n{}",
+          added
+            .iter()
+            .map(|specifier| format!("  {specifier}\n"))
+            .collect::<String>()
+            .trim_end()
+        )
+      };
       Err(deno_core::anyhow::anyhow!(
-        "The lockfile is out of date. Run `deno install --frozen=false`, or rerun with `--frozen=false` to update it.\nchanges:\n{diff}"
+        "The lockfile is out of date. Run `deno install --frozen=false`, or rerun with `--frozen=false` to update it.{added}\nchanges:\n{diff}"
       ))
     } else {
       Ok(())
     }
   }
 }
+
+/// Remote modules that were loaded but have no checksum in the lockfile on
+/// disk. Modules whose checksum changed never get this far, loading them
+/// fails with an integrity error.
+fn added_remote_modules(lockfile: &Lockfile, contents: &str) -> Vec<String> {
+  let on_disk = Lockfile::new(deno_lockfile::NewLockfileOptions {
+    file_path: lockfile.filename.clone(),
+    content: contents,
+    overwrite: false,
+  })
+  .ok();
+  let mut added = lockfile
+    .remote()
+    .keys()
+    .filter(|specifier| {
+      !on_disk
+        .as_ref()
+        .is_some_and(|on_disk| on_disk.remote().contains_key(*specifier))
+    })
+    .cloned()
+    .collect::<Vec<_>>();
+  added.sort();
+  added
+}
+
+deno_core::extension!(deno_lock,
+  ops = [
+    op_lockfile_diff,
+  ],
+  options = {
+    lockfile: Arc<CliLockfile>,
+    file_fetcher: Arc<FileFetcher>,
+  },
+  state = |state, options| {
+    state.put(options.lockfile);
+    state.put(options.file_fetcher);
+  },
+);
+
+/// Fetches the given remote modules and compares them with their checksums
+/// in the lockfile, without updating it. Local modules aren't locked, so
+/// they are skipped.
+#[op2(async)]
+#[serde]
+async fn op_lockfile_diff(
+  state: Rc<RefCell<OpState>>,
+  #[serde] specifiers: Vec<String>,
+) -> Result<Vec<RemoteModuleDiff>, AnyError> {
+  let (lockfile, file_fetcher, permissions) = {
+    let state = state.borrow();
+    (
+      state.borrow::<Arc<CliLockfile>>().clone(),
+      state.borrow::<Arc<FileFetcher>>().clone(),
+      state.borrow::<PermissionsContainer>().clone(),
+    )
+  };
+  let mut diffs = Vec::with_capacity(specifiers.len());
+  for specifier in specifiers {
+    let specifier = ModuleSpecifier::parse(&specifier).map_err(|err| {
+      type_error(format!("Invalid specifier \"{specifier}\": {err}"))
+    })?;
+    if !matches!(specifier.scheme(), "http" | "https") {
+      continue;
+    }
+    let file = file_fetcher.fetch(&specifier, &permissions).await?;
+    diffs.push(lockfile.diff_remote_module(&file.specifier, &file.source));
+  }
+  Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn diff_remote_module() {
+    let lockfile =
+      CliLockfile::read_from_path(CliLockfileReadFromPathOptions {
+        file_path: PathBuf::from("/deno.lock"),
+        frozen: false,
+        skip_write: true,
+      })
+      .unwrap();
+    let hello = ModuleSpecifier::parse("https://deno.land/x/hello.ts").unwrap();
+    let other = ModuleSpecifier::parse("https://deno.land/x/other.ts").unwrap();
+    lockfile
+      .lock()
+      .insert_remote(hello.to_string(), checksum::gen(&[b"hello"]));
+
+    let diff = lockfile.diff_remote_module(&hello, b"hello");
+    assert_eq!(diff.change, RemoteModuleChange::Unchanged);
+    assert_eq!(diff.expected.as_deref(), Some(diff.actual.as_str()));
+
+    let diff = lockfile.diff_remote_module(&hello, b"hello!");
+    assert_eq!(diff.change, RemoteModuleChange::Changed);
+    assert_eq!(diff.actual, checksum::gen(&[b"hello!"]));
+
+    let diff = lockfile.diff_remote_module(&other, b"other");
+    assert_eq!(diff.change, RemoteModuleChange::Added);
+    assert_eq!(diff.expected, None);
+    // diffing doesn't update the lockfile
+    assert!(!lockfile.lock().remote().contains_key(other.as_str()));
+  }
+}
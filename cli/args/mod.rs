@@ -43,8 +43,10 @@ pub use deno_config::deno_json::TsTypeLib;
 pub use deno_config::glob::FilePatterns;
 pub use deno_json::check_warn_tsconfig;
 pub use flags::*;
+pub use lockfile::deno_lock;
 pub use lockfile::CliLockfile;
 pub use lockfile::CliLockfileReadFromPathOptions;
+pub use lockfile::RemoteModuleDiff;
 pub use package_json::NpmInstallDepsProvider;
 
 use deno_ast::ModuleSpecifier;
@@ -53,6 +55,10 @@ use deno_core::anyhow::Context;
 use deno_core::error::AnyError;
 use deno_core::serde_json;
 use deno_core::url::Url;
+use deno_runtime::deno_fs;
+use deno_runtime::deno_fs::FsLimitScopeOptions;
+use deno_runtime::deno_fs::FsLimits;
+use deno_runtime::deno_fs::FS_LIMITS_ENV_VAR_NAME;
 use deno_runtime::deno_permissions::PermissionsOptions;
 use deno_runtime::deno_tls::deno_native_certs::load_native_certs;
 use deno_runtime::deno_tls::rustls;
@@ -77,6 +83,7 @@ use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 use crate::cache;
@@ -1548,6 +1555,73 @@ impl CliOptions {
     &self.flags.strace_ops
   }
 
+  pub fn tick_budget(
+    &self,
+  ) -> Option<deno_runtime::event_loop_monitor::TickBudget> {
+    use deno_runtime::event_loop_monitor::TickBudget;
+    use deno_runtime::event_loop_monitor::TickBudgetMode;
+
+    self.flags.tick_budget.map(|flag| TickBudget {
+      budget: Duration::from_millis(flag.budget_ms),
+      mode: if flag.yield_on_overrun {
+        TickBudgetMode::Yield
+      } else {
+        TickBudgetMode::Warn
+      },
+    })
+  }
+
+  pub fn dns_resolver(
+    &self,
+  ) -> Result<deno_runtime::deno_net::resolver::DnsResolverConfig, AnyError> {
+    use deno_runtime::deno_net::resolver::DnsResolverConfig;
+    use deno_runtime::deno_net::resolver::DohConfig;
+    use deno_runtime::deno_net::resolver::NameServer;
+    use deno_runtime::deno_net::resolver::NameServerProtocol;
+    use deno_runtime::deno_net::resolver::NameServersConfig;
+
+    let Some(dns) = &self.flags.dns else {
+      return Ok(DnsResolverConfig::System);
+    };
+    let timeout = dns.timeout_ms.map(Duration::from_millis);
+    Ok(match &dns.resolver {
+      DnsResolverFlag::Servers { servers, rotate } => {
+        let servers = servers
+          .iter()
+          .map(|server| NameServer {
+            addr: server.addr,
+            protocol: if server.tcp {
+              NameServerProtocol::Tcp
+            } else {
+              NameServerProtocol::Udp
+            },
+          })
+          .collect();
+        let mut config = NameServersConfig::new(servers)?.rotate(*rotate);
+        if let Some(timeout) = timeout {
+          config = config.timeout(timeout);
+        }
+        DnsResolverConfig::NameServers(config)
+      }
+      DnsResolverFlag::Https { url, bootstrap } => {
+        let mut config = DohConfig::new(url.clone(), bootstrap.clone())?;
+        if let Some(timeout) = timeout {
+          config = config.timeout(timeout);
+        }
+        DnsResolverConfig::Https(config)
+      }
+    })
+  }
+
+  pub fn sigint_grace_period(&self) -> Option<Duration> {
+    match self.sub_command() {
+      DenoSubcommand::Run(run_flags) if run_flags.watch.is_none() => {
+        Some(deno_runtime::ops::signal::sigint_grace_period_from_env())
+      }
+      _ => None,
+    }
+  }
+
   pub fn take_binary_npm_command_name(&self) -> Option<String> {
     match self.sub_command() {
       DenoSubcommand::Run(flags) => {
@@ -1841,6 +1915,20 @@ pub fn resolve_no_prompt(flags: &PermissionFlags) -> bool {
   flags.no_prompt || has_flag_env_var("DENO_NO_PROMPT")
 }
 
+/// Resolves the file system limits configured with the `DENO_FS_LIMITS`
+/// environment variable. Scope paths are resolved against the working
+/// directory of `fs`.
+pub fn fs_limits_from_env(
+  fs: &dyn deno_fs::FileSystem,
+) -> Result<Option<deno_fs::SharedFsLimits>, AnyError> {
+  let Ok(value) = env::var(FS_LIMITS_ENV_VAR_NAME) else {
+    return Ok(None);
+  };
+  let scopes = serde_json::from_str::<Vec<FsLimitScopeOptions>>(&value)
+    .with_context(|| format!("Invalid {FS_LIMITS_ENV_VAR_NAME}"))?;
+  Ok(Some(Arc::new(FsLimits::new(scopes, fs))))
+}
+
 pub fn has_flag_env_var(name: &str) -> bool {
   let value = env::var(name);
   matches!(value.as_ref().map(|s| s.as_str()), Ok("1"))
@@ -0,0 +1,73 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+// Compares `Deno.hashTree` against walking, reading and hashing the same
+// tree in JS, on a generated tree of small files.
+//
+// Run with `deno run --unstable-fs -A cli/bench/hash_tree.js [count]`.
+let [count] = Deno.args;
+count = count ? parseInt(count, 10) : 5000;
+
+const root = Deno.makeTempDirSync();
+for (let i = 0; i < count; i++) {
+  const dir = `${root}/${i % 50}/${i % 7}`;
+  Deno.mkdirSync(dir, { recursive: true });
+  const content = `export const n = ${i};\n`.repeat(20);
+  Deno.writeTextFileSync(`${dir}/file_${i}.ts`, content);
+}
+
+function framed(bytes) {
+  const out = new Uint8Array(8 + bytes.length);
+  new DataView(out.buffer).setBigUint64(0, BigInt(bytes.length), true);
+  out.set(bytes, 8);
+  return out;
+}
+
+// Produces the same digest as `Deno.hashTree(root)`.
+async function hashTreeJs(root) {
+  const encoder = new TextEncoder();
+  const parts = [];
+  async function walk(dir, prefix) {
+    const entries = [];
+    for await (const entry of Deno.readDir(dir)) entries.push(entry);
+    entries.sort((a, b) => a.name < b.name ? -1 : a.name > b.name ? 1 : 0);
+    for (const entry of entries) {
+      const relative = prefix ? `${prefix}/${entry.name}` : entry.name;
+      if (entry.isDirectory) {
+        await walk(`${dir}/${entry.name}`, relative);
+      } else if (entry.isFile) {
+        const content = await Deno.readFile(`${dir}/${entry.name}`);
+        parts.push(
+          encoder.encode("f"),
+          framed(encoder.encode(relative)),
+          framed(content),
+        );
+      }
+    }
+  }
+  await walk(root, "");
+  const input = new Uint8Array(await new Blob(parts).arrayBuffer());
+  const digest = await crypto.subtle.digest("SHA-256", input);
+  return Array.from(
+    new Uint8Array(digest),
+    (byte) => byte.toString(16).padStart(2, "0"),
+  ).join("");
+}
+
+async function bench(name, fun) {
+  const start = Date.now();
+  const digest = await fun();
+  const elapsed = Date.now() - start;
+  console.log(`${name}: time ${elapsed} ms digest ${digest}`);
+}
+
+await bench("JS walk + crypto.subtle", () => hashTreeJs(root));
+for (const algorithm of ["sha256", "blake3"]) {
+  await bench(
+    `Deno.hashTree (${algorithm})`,
+    async () => (await Deno.hashTree(root, { algorithm })).digest,
+  );
+}
+
+Deno.removeSync(root, { recursive: true });
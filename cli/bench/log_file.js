@@ -0,0 +1,39 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+// Compares appending many small lines through `Deno.openLogFile` against
+// writing each line to a plain `Deno.FsFile`.
+//
+// Run with `deno run --unstable-fs -A cli/bench/log_file.js [count]`.
+let [count] = Deno.args;
+count = count ? parseInt(count, 10) : 100000;
+
+const dir = Deno.makeTempDirSync();
+const line = new TextEncoder().encode('{"level":"info","msg":"hello"}\n');
+
+async function bench(name, fun) {
+  const start = Date.now();
+  await fun();
+  const elapsed = Date.now() - start;
+  const rate = Math.floor(count / (elapsed / 1000));
+  console.log(`${name}: time ${elapsed} ms rate ${rate}`);
+}
+
+await bench("FsFile", async () => {
+  using file = await Deno.open(`${dir}/plain.log`, {
+    create: true,
+    append: true,
+  });
+  for (let i = 0; i < count; i++) await file.write(line);
+});
+
+for (const sync of ["none", "interval"]) {
+  await bench(`LogFile (sync: ${sync})`, async () => {
+    using log = await Deno.openLogFile(`${dir}/${sync}.log`, { sync });
+    for (let i = 0; i < count; i++) log.write(line);
+    await log.flush();
+  });
+}
+
+Deno.removeSync(dir, { recursive: true });
@@ -118,6 +118,17 @@ const EXEC_TIME_BENCHMARKS: &[(&str, &[&str], Option<i32>)] = &[
     &["run", "tests/testdata/benches/response_string_perf.js"],
     None,
   ),
+  (
+    "test_permissions",
+    &[
+      "test",
+      "--allow-read",
+      "--allow-net",
+      "--allow-env",
+      "tests/testdata/benches/test_permissions_perf.js",
+    ],
+    None,
+  ),
   (
     "check",
     &[
@@ -0,0 +1,60 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+// Compares reading a large NDJSON file through `Deno.openNdjsonReader`
+// against splitting lines with `TextLineStream` and calling `JSON.parse`.
+//
+// Run with `deno run --unstable-fs -A cli/bench/ndjson.js [count]`.
+import { TextLineStream } from "jsr:@std/streams@1/text-line-stream";
+
+let [count] = Deno.args;
+count = count ? parseInt(count, 10) : 1000000;
+
+const dir = Deno.makeTempDirSync();
+const path = `${dir}/data.ndjson`;
+{
+  await using writer = await Deno.openNdjsonWriter(path);
+  const batch = [];
+  for (let i = 0; i < count; i++) {
+    batch.push({ id: i, name: `item ${i}`, tags: ["a", "b"], ok: true });
+    if (batch.length === 10000) {
+      await writer.write(batch);
+      batch.length = 0;
+    }
+  }
+  await writer.write(batch);
+}
+
+async function bench(name, fun) {
+  const start = Date.now();
+  const n = await fun();
+  const elapsed = Date.now() - start;
+  const rate = Math.floor(n / (elapsed / 1000));
+  console.log(`${name}: time ${elapsed} ms rate ${rate}`);
+}
+
+await bench("TextLineStream + JSON.parse", async () => {
+  using file = await Deno.open(path);
+  let n = 0;
+  const lines = file.readable
+    .pipeThrough(new TextDecoderStream())
+    .pipeThrough(new TextLineStream());
+  for await (const line of lines) {
+    if (line.length > 0) {
+      JSON.parse(line);
+      n++;
+    }
+  }
+  return n;
+});
+
+await bench("NdjsonReader", async () => {
+  let n = 0;
+  for await (const batch of await Deno.openNdjsonReader(path)) {
+    n += batch.values.length;
+  }
+  return n;
+});
+
+Deno.removeSync(dir, { recursive: true });
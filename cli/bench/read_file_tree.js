@@ -0,0 +1,45 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// Reads every file of a 10k file tree, the way a bundler scans a project.
+// Compare IO backends by running it with different values of
+// `DENO_FS_IO_BACKEND` (blocking, io_uring, overlapped), e.g.:
+//
+//   DENO_FS_IO_BACKEND=io_uring deno run -A cli/bench/read_file_tree.js
+
+// deno-lint-ignore-file no-console
+
+const [fileCount = "10000", fileSize = "4096", rounds = "5"] = Deno.args;
+
+const root = Deno.makeTempDirSync({ prefix: "deno_read_file_tree_" });
+const data = new Uint8Array(parseInt(fileSize, 10)).fill(97);
+const paths = [];
+for (let i = 0; i < parseInt(fileCount, 10); i++) {
+  const dir = `${root}/${i % 100}`;
+  if (i < 100) {
+    Deno.mkdirSync(dir);
+  }
+  const path = `${dir}/${i}.txt`;
+  Deno.writeFileSync(path, data);
+  paths.push(path);
+}
+
+try {
+  for (let round = 0; round < parseInt(rounds, 10); round++) {
+    const start = performance.now();
+    const files = await Promise.all(paths.map((path) => Deno.readFile(path)));
+    const elapsed = performance.now() - start;
+    const bytes = files.reduce((total, file) => total + file.byteLength, 0);
+    console.log(
+      `read ${files.length} files (${bytes} bytes) in ${elapsed.toFixed(1)} ms`,
+    );
+    const statStart = performance.now();
+    await Promise.all(paths.map((path) => Deno.stat(path)));
+    console.log(
+      `stat ${paths.length} files in ${
+        (performance.now() - statStart).toFixed(1)
+      } ms`,
+    );
+  }
+} finally {
+  Deno.removeSync(root, { recursive: true });
+}
@@ -0,0 +1,66 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+// Compares `Deno.sendFile` against a JS read/write loop for sending a file
+// over a loopback TCP connection.
+//
+// Run with `deno run --unstable-net -A cli/bench/sendfile.js [megabytes]`.
+let [megabytes] = Deno.args;
+megabytes = megabytes ? parseInt(megabytes, 10) : 100;
+
+const path = Deno.makeTempFileSync();
+{
+  using file = Deno.openSync(path, { write: true });
+  const chunk = new Uint8Array(1024 * 1024).map((_, i) => i % 256);
+  for (let i = 0; i < megabytes; i++) file.writeSync(chunk);
+}
+
+async function sendLoop(conn, file) {
+  const buf = new Uint8Array(64 * 1024);
+  let sent = 0;
+  let n;
+  while ((n = await file.read(buf)) !== null) {
+    let written = 0;
+    while (written < n) {
+      written += await conn.write(buf.subarray(written, n));
+    }
+    sent += n;
+  }
+  return sent;
+}
+
+async function drain(conn) {
+  const buf = new Uint8Array(256 * 1024);
+  let received = 0;
+  let n;
+  while ((n = await conn.read(buf)) !== null) received += n;
+  return received;
+}
+
+async function bench(name, send) {
+  const listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+  const client = await Deno.connect({
+    hostname: "127.0.0.1",
+    port: listener.addr.port,
+  });
+  const server = await listener.accept();
+  listener.close();
+  using file = await Deno.open(path);
+
+  const start = Date.now();
+  const received = drain(client);
+  const sent = await send(server, file);
+  server.close();
+  const total = await received;
+  const elapsed = Date.now() - start;
+  client.close();
+  if (sent !== total) throw new Error(`sent ${sent} but received ${total}`);
+  const rate = (total / 1024 / 1024 / (elapsed / 1000)).toFixed(0);
+  console.log(`${name}: time ${elapsed} ms (${rate} MB/s)`);
+}
+
+await bench("JS read/write loop", sendLoop);
+await bench("Deno.sendFile", (conn, file) => Deno.sendFile(conn, file));
+
+Deno.removeSync(path);
@@ -0,0 +1,63 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+// Compares `Deno.searchText` against reading every file and matching each
+// line with a `RegExp` in JS, on a generated corpus of source files.
+//
+// Run with `deno run --unstable-fs -A cli/bench/text_search.js [files]`.
+let [files] = Deno.args;
+files = files ? parseInt(files, 10) : 2000;
+
+const root = Deno.makeTempDirSync();
+const lines = [];
+for (let i = 0; i < 500; i++) {
+  lines.push(`export function fn_${i}(a, b) { return a + b * ${i}; }`);
+}
+for (let i = 0; i < files; i++) {
+  const dir = `${root}/src/mod_${i % 50}`;
+  Deno.mkdirSync(dir, { recursive: true });
+  const text = [...lines];
+  text[i % lines.length] = `// TODO(#${i}): remove this`;
+  Deno.writeTextFileSync(`${dir}/file_${i}.js`, text.join("\n"));
+}
+
+const pattern = String.raw`TODO\(#\d+\)`;
+
+async function searchJs(dir) {
+  const regex = new RegExp(pattern);
+  let count = 0;
+  for await (const entry of Deno.readDir(dir)) {
+    const path = `${dir}/${entry.name}`;
+    if (entry.isDirectory) {
+      count += await searchJs(path);
+    } else if (entry.isFile && path.endsWith(".js")) {
+      const text = await Deno.readTextFile(path);
+      for (const line of text.split("\n")) {
+        if (regex.test(line)) count++;
+      }
+    }
+  }
+  return count;
+}
+
+async function searchOp(dir) {
+  let count = 0;
+  const options = { include: ["**/*.js"] };
+  for await (const _match of Deno.searchText(dir, pattern, options)) {
+    count++;
+  }
+  return count;
+}
+
+async function bench(name, fun) {
+  const start = Date.now();
+  const count = await fun();
+  const elapsed = Date.now() - start;
+  console.log(`${name}: time ${elapsed} ms, ${count} matches`);
+}
+
+await bench("JS Deno.readDir + RegExp", () => searchJs(root));
+await bench("Deno.searchText", () => searchOp(root));
+
+Deno.removeSync(root, { recursive: true });
@@ -0,0 +1,60 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+// Compares `Deno.walkDir` against a recursive `Deno.readDir` walk, on a
+// generated tree shaped like a `node_modules` directory.
+//
+// Run with `deno run --unstable-fs -A cli/bench/walk_dir.js [packages]`.
+let [packages] = Deno.args;
+packages = packages ? parseInt(packages, 10) : 1000;
+
+const root = Deno.makeTempDirSync();
+for (let i = 0; i < packages; i++) {
+  const pkg = `${root}/node_modules/pkg_${i}`;
+  Deno.mkdirSync(`${pkg}/lib/internal`, { recursive: true });
+  Deno.mkdirSync(`${pkg}/types`, { recursive: true });
+  Deno.writeTextFileSync(`${pkg}/package.json`, "{}");
+  Deno.writeTextFileSync(`${pkg}/README.md`, "");
+  for (let j = 0; j < 5; j++) {
+    Deno.writeTextFileSync(`${pkg}/lib/mod_${j}.js`, "");
+    Deno.writeTextFileSync(`${pkg}/lib/internal/util_${j}.js`, "");
+    Deno.writeTextFileSync(`${pkg}/types/mod_${j}.d.ts`, "");
+  }
+}
+
+async function walkJs(dir) {
+  let count = 0;
+  for await (const entry of Deno.readDir(dir)) {
+    const path = `${dir}/${entry.name}`;
+    if (entry.isDirectory) {
+      count += await walkJs(path);
+    } else if (entry.isFile && path.endsWith(".js")) {
+      await Deno.lstat(path);
+      count++;
+    }
+  }
+  return count;
+}
+
+async function walkOp(dir) {
+  let count = 0;
+  const options = { include: ["**/*.js"], types: ["file"] };
+  for await (const _entry of Deno.walkDir(dir, options)) {
+    count++;
+  }
+  return count;
+}
+
+async function bench(name, fun) {
+  const start = Date.now();
+  const count = await fun();
+  const elapsed = Date.now() - start;
+  console.log(`${name}: time ${elapsed} ms, ${count} .js files`);
+}
+
+// Both report the size and mtime of every matching file.
+await bench("JS Deno.readDir + Deno.lstat", () => walkJs(root));
+await bench("Deno.walkDir", () => walkOp(root));
+
+Deno.removeSync(root, { recursive: true });
@@ -3,6 +3,8 @@
 use once_cell::sync::OnceCell;
 
 use super::DiskCache;
+use crate::util::checksum;
+use deno_core::ModuleSpecifier;
 
 use std::env;
 use std::path::PathBuf;
@@ -137,6 +139,23 @@ impl DenoDir {
     self.root.join("location_data")
   }
 
+  /// Folder used for the content-addressed blob store.
+  pub fn blob_store_folder_path(&self) -> PathBuf {
+    self.root.join("blob_store")
+  }
+
+  /// File in which `Deno.schedule()` keeps the last runs of the schedules
+  /// of a main module.
+  pub fn schedule_state_file_path(
+    &self,
+    main_module: &ModuleSpecifier,
+  ) -> PathBuf {
+    self.root.join("schedules").join(format!(
+      "{}.json",
+      checksum::gen(&[main_module.as_str().as_bytes()])
+    ))
+  }
+
   /// File used for the upgrade checker.
   pub fn upgrade_check_file_path(&self) -> PathBuf {
     self.root.join("latest.txt")
@@ -43,6 +43,7 @@ mod emit;
 mod fast_check;
 mod incremental;
 mod module_info;
+mod module_source;
 mod node;
 mod parsed_source;
 
@@ -59,6 +60,8 @@ pub use emit::EmitCache;
 pub use fast_check::FastCheckCache;
 pub use incremental::IncrementalCache;
 pub use module_info::ModuleInfoCache;
+pub use module_source::ModuleSourceCache;
+pub use module_source::ModuleSourceCacheMetrics;
 pub use node::NodeAnalysisCache;
 pub use parsed_source::EsmOrCjsChecker;
 pub use parsed_source::LazyGraphSourceParser;
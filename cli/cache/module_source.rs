@@ -0,0 +1,280 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use deno_ast::ModuleSpecifier;
+use deno_core::parking_lot::Mutex;
+use deno_core::serde::Serialize;
+use indexmap::IndexMap;
+
+/// Environment variable to change the byte budget of the shared
+/// [`ModuleSourceCache`]. Set it to `0` to disable the cache.
+pub const MODULE_SOURCE_CACHE_BYTES_ENV_VAR_NAME: &str =
+  "DENO_MODULE_SOURCE_CACHE_BYTES";
+
+const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// `data:` URLs larger than this are not cached, because the whole source
+/// is part of the key.
+const MAX_DATA_URL_SOURCE_BYTES: usize = 16 * 1024;
+
+static SHARED: OnceLock<Arc<ModuleSourceCache>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleSourceCacheMetrics {
+  pub hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+  /// Number of modules that were transpiled, because they were found in
+  /// neither this cache nor the emit cache.
+  pub transpiles: u64,
+  pub entries: usize,
+  pub bytes: usize,
+}
+
+struct Entry {
+  source_hash: u64,
+  code: Arc<[u8]>,
+}
+
+#[derive(Default)]
+struct Entries {
+  /// Least recently used first.
+  entries: IndexMap<ModuleSpecifier, Entry>,
+  bytes: usize,
+}
+
+/// An in-memory LRU cache of emitted module sources (with inline source
+/// maps), shared by all workers of the process so that a module imported by
+/// several workers is only transpiled once. It is checked before the emit
+/// cache on disk.
+///
+/// There is one entry per specifier, valid for one source hash, so a stale
+/// entry is never returned. Entries for changed files are still evicted in
+/// watch mode, to not keep them around until they fall out of the cache.
+pub struct ModuleSourceCache {
+  max_bytes: usize,
+  entries: Mutex<Entries>,
+  hits: AtomicU64,
+  misses: AtomicU64,
+  evictions: AtomicU64,
+  transpiles: AtomicU64,
+}
+
+impl ModuleSourceCache {
+  pub fn new(max_bytes: usize) -> Self {
+    Self {
+      max_bytes,
+      entries: Default::default(),
+      hits: Default::default(),
+      misses: Default::default(),
+      evictions: Default::default(),
+      transpiles: Default::default(),
+    }
+  }
+
+  /// The cache of this process, which also outlives restarts in watch mode.
+  pub fn shared() -> Arc<ModuleSourceCache> {
+    SHARED
+      .get_or_init(|| {
+        let max_bytes = std::env::var(MODULE_SOURCE_CACHE_BYTES_ENV_VAR_NAME)
+          .ok()
+          .and_then(|value| value.parse().ok())
+          .unwrap_or(DEFAULT_MAX_BYTES);
+        Arc::new(ModuleSourceCache::new(max_bytes))
+      })
+      .clone()
+  }
+
+  fn is_cacheable(&self, specifier: &ModuleSpecifier, source: &str) -> bool {
+    self.max_bytes > 0
+      && (specifier.scheme() != "data"
+        || source.len() <= MAX_DATA_URL_SOURCE_BYTES)
+  }
+
+  pub fn get(
+    &self,
+    specifier: &ModuleSpecifier,
+    source: &str,
+    source_hash: u64,
+  ) -> Option<Arc<[u8]>> {
+    if !self.is_cacheable(specifier, source) {
+      return None;
+    }
+    let mut entries = self.entries.lock();
+    let code = entries
+      .entries
+      .get_full(specifier)
+      .filter(|(_, _, entry)| entry.source_hash == source_hash)
+      .map(|(index, _, entry)| (index, entry.code.clone()));
+    match code {
+      Some((index, code)) => {
+        let last = entries.entries.len() - 1;
+        entries.entries.move_index(index, last);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(code)
+      }
+      None => {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+      }
+    }
+  }
+
+  pub fn insert(
+    &self,
+    specifier: &ModuleSpecifier,
+    source: &str,
+    source_hash: u64,
+    code: Arc<[u8]>,
+  ) {
+    if !self.is_cacheable(specifier, source) || code.len() > self.max_bytes {
+      return;
+    }
+    let mut entries = self.entries.lock();
+    entries.bytes += code.len();
+    if let Some(old) = entries
+      .entries
+      .insert(specifier.clone(), Entry { source_hash, code })
+    {
+      entries.bytes -= old.code.len();
+    }
+    // moves an updated entry to the end
+    let last = entries.entries.len() - 1;
+    let index = entries.entries.get_index_of(specifier).unwrap();
+    entries.entries.move_index(index, last);
+
+    while entries.bytes > self.max_bytes {
+      let Some((_, evicted)) = entries.entries.shift_remove_index(0) else {
+        break;
+      };
+      entries.bytes -= evicted.code.len();
+      self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+
+  /// Counts a transpile that happened because of a miss.
+  pub fn record_transpile(&self) {
+    self.transpiles.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn evict(&self, specifier: &ModuleSpecifier) {
+    let mut entries = self.entries.lock();
+    if let Some(evicted) = entries.entries.shift_remove(specifier) {
+      entries.bytes -= evicted.code.len();
+      self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+
+  /// Evicts the modules of files reported as changed by the file watcher.
+  pub fn evict_paths(&self, paths: &[PathBuf]) {
+    for path in paths {
+      if let Ok(specifier) = ModuleSpecifier::from_file_path(path) {
+        self.evict(&specifier);
+      }
+    }
+  }
+
+  pub fn metrics(&self) -> ModuleSourceCacheMetrics {
+    let entries = self.entries.lock();
+    ModuleSourceCacheMetrics {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      evictions: self.evictions.load(Ordering::Relaxed),
+      transpiles: self.transpiles.load(Ordering::Relaxed),
+      entries: entries.entries.len(),
+      bytes: entries.bytes,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn specifier(name: &str) -> ModuleSpecifier {
+    let path = std::env::temp_dir().join(format!("{name}.ts"));
+    ModuleSpecifier::from_file_path(path).unwrap()
+  }
+
+  fn code(len: usize) -> Arc<[u8]> {
+    vec![b'a'; len].into()
+  }
+
+  #[test]
+  fn hit_and_miss() {
+    let cache = ModuleSourceCache::new(1024);
+    let a = specifier("a");
+    assert!(cache.get(&a, "", 1).is_none());
+    cache.insert(&a, "", 1, code(10));
+    assert_eq!(cache.get(&a, "", 1).unwrap().len(), 10);
+    // a different source hash is a miss
+    assert!(cache.get(&a, "", 2).is_none());
+    cache.insert(&a, "", 2, code(20));
+    assert_eq!(cache.get(&a, "", 2).unwrap().len(), 20);
+    assert_eq!(
+      cache.metrics(),
+      ModuleSourceCacheMetrics {
+        hits: 2,
+        misses: 2,
+        evictions: 0,
+        transpiles: 0,
+        entries: 1,
+        bytes: 20,
+      }
+    );
+  }
+
+  #[test]
+  fn evicts_least_recently_used() {
+    let cache = ModuleSourceCache::new(30);
+    let (a, b, c) = (specifier("a"), specifier("b"), specifier("c"));
+    cache.insert(&a, "", 1, code(10));
+    cache.insert(&b, "", 1, code(10));
+    cache.insert(&c, "", 1, code(10));
+    // makes `b` the least recently used
+    assert!(cache.get(&a, "", 1).is_some());
+    cache.insert(&specifier("d"), "", 1, code(10));
+    assert!(cache.get(&b, "", 1).is_none());
+    assert!(cache.get(&a, "", 1).is_some());
+    assert!(cache.get(&c, "", 1).is_some());
+    let metrics = cache.metrics();
+    assert_eq!(metrics.evictions, 1);
+    assert_eq!(metrics.entries, 3);
+    assert_eq!(metrics.bytes, 30);
+
+    // larger than the whole budget
+    cache.insert(&specifier("e"), "", 1, code(31));
+    assert_eq!(cache.metrics().entries, 3);
+
+    cache.evict_paths(&[a.to_file_path().unwrap()]);
+    assert!(cache.get(&a, "", 1).is_none());
+    assert_eq!(cache.metrics().evictions, 2);
+  }
+
+  #[test]
+  fn large_data_urls_and_zero_budget() {
+    let cache = ModuleSourceCache::new(1024 * 1024);
+    let small = ModuleSpecifier::parse("data:text/typescript,small").unwrap();
+    cache.insert(&small, "small", 1, code(5));
+    assert!(cache.get(&small, "small", 1).is_some());
+
+    let source = "a".repeat(MAX_DATA_URL_SOURCE_BYTES + 1);
+    let large =
+      ModuleSpecifier::parse(&format!("data:text/typescript,{source}"))
+        .unwrap();
+    cache.insert(&large, &source, 1, code(5));
+    assert!(cache.get(&large, &source, 1).is_none());
+    assert_eq!(cache.metrics().entries, 1);
+
+    let cache = ModuleSourceCache::new(0);
+    cache.insert(&small, "small", 1, code(5));
+    assert!(cache.get(&small, "small", 1).is_none());
+    assert_eq!(cache.metrics(), ModuleSourceCacheMetrics::default());
+  }
+}
@@ -2,6 +2,7 @@
 
 use crate::cache::EmitCache;
 use crate::cache::FastInsecureHasher;
+use crate::cache::ModuleSourceCache;
 use crate::cache::ParsedSourceCache;
 
 use deno_ast::SourceMapOption;
@@ -22,6 +23,7 @@ use std::sync::Arc;
 
 pub struct Emitter {
   emit_cache: Arc<EmitCache>,
+  module_source_cache: Arc<ModuleSourceCache>,
   parsed_source_cache: Arc<ParsedSourceCache>,
   transpile_and_emit_options:
     Arc<(deno_ast::TranspileOptions, deno_ast::EmitOptions)>,
@@ -32,6 +34,7 @@ pub struct Emitter {
 impl Emitter {
   pub fn new(
     emit_cache: Arc<EmitCache>,
+    module_source_cache: Arc<ModuleSourceCache>,
     parsed_source_cache: Arc<ParsedSourceCache>,
     transpile_options: deno_ast::TranspileOptions,
     emit_options: deno_ast::EmitOptions,
@@ -44,6 +47,7 @@ impl Emitter {
     };
     Self {
       emit_cache,
+      module_source_cache,
       parsed_source_cache,
       transpile_and_emit_options: Arc::new((transpile_options, emit_options)),
       transpile_and_emit_options_hash,
@@ -131,6 +135,7 @@ impl Emitter {
         .unwrap()?;
         Ok(helper.post_emit_parsed_source(
           specifier,
+          source,
           transpile_result,
           source_hash,
         ))
@@ -159,6 +164,7 @@ impl Emitter {
         )?;
         Ok(helper.post_emit_parsed_source(
           specifier,
+          source,
           transpile_result,
           source_hash,
         ))
@@ -243,10 +249,25 @@ impl<'a> EmitParsedSourceHelper<'a> {
     let source_hash = self.0.get_source_hash(source);
 
     if let Some(emit_code) =
+      self
+        .0
+        .module_source_cache
+        .get(specifier, source, source_hash)
+    {
+      PreEmitResult::Cached(Box::<[u8]>::from(&*emit_code).into())
+    } else if let Some(emit_code) =
       self.0.emit_cache.get_emit_code(specifier, source_hash)
     {
-      PreEmitResult::Cached(emit_code.into_boxed_slice().into())
+      let emit_code = Arc::<[u8]>::from(emit_code);
+      self.0.module_source_cache.insert(
+        specifier,
+        source,
+        source_hash,
+        emit_code.clone(),
+      );
+      PreEmitResult::Cached(Box::<[u8]>::from(&*emit_code).into())
     } else {
+      self.0.module_source_cache.record_transpile();
       PreEmitResult::NotCached { source_hash }
     }
   }
@@ -270,6 +291,7 @@ impl<'a> EmitParsedSourceHelper<'a> {
   pub fn post_emit_parsed_source(
     &self,
     specifier: &ModuleSpecifier,
+    source: &str,
     transpile_result: TranspileResult,
     source_hash: u64,
   ) -> ModuleCodeBytes {
@@ -286,7 +308,14 @@ impl<'a> EmitParsedSourceHelper<'a> {
       source_hash,
       &transpiled_source.source,
     );
-    transpiled_source.source.into_boxed_slice().into()
+    let code = transpiled_source.source.into_boxed_slice();
+    self.0.module_source_cache.insert(
+      specifier,
+      source,
+      source_hash,
+      Arc::from(&*code),
+    );
+    code.into()
   }
 }
 
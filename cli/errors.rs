@@ -108,3 +108,10 @@ pub fn get_error_class_name(e: &AnyError) -> &'static str {
     })
     .unwrap_or("Error")
 }
+
+/// The `get_error_class_fn` of workers: like `get_error_class_name`, but
+/// errors with structured details are routed through the builder that
+/// attaches them to the JS error.
+pub fn get_op_error_class_name(e: &AnyError) -> &'static str {
+  deno_runtime::errors::get_op_error_class_name(get_error_class_name(e), e)
+}
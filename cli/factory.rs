@@ -1,6 +1,7 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 use crate::args::check_warn_tsconfig;
+use crate::args::fs_limits_from_env;
 use crate::args::get_root_cert_store;
 use crate::args::CaData;
 use crate::args::CliOptions;
@@ -19,6 +20,7 @@ use crate::cache::GlobalHttpCache;
 use crate::cache::HttpCache;
 use crate::cache::LocalHttpCache;
 use crate::cache::ModuleInfoCache;
+use crate::cache::ModuleSourceCache;
 use crate::cache::NodeAnalysisCache;
 use crate::cache::ParsedSourceCache;
 use crate::emit::Emitter;
@@ -46,6 +48,7 @@ use crate::resolver::CliNodeResolver;
 use crate::resolver::CliSloppyImportsResolver;
 use crate::resolver::NpmModuleLoader;
 use crate::resolver::SloppyImportsCachedFs;
+use crate::standalone::build_frozen_read_snapshot;
 use crate::standalone::DenoCompileBinaryWriter;
 use crate::tools::check::TypeChecker;
 use crate::tools::coverage::CoverageCollector;
@@ -64,6 +67,7 @@ use deno_config::workspace::WorkspaceResolver;
 use deno_core::error::AnyError;
 use deno_core::futures::FutureExt;
 use deno_core::FeatureChecker;
+use deno_path_util::normalize_path;
 
 use deno_runtime::deno_fs;
 use deno_runtime::deno_node::DenoFsNodeResolverEnv;
@@ -542,6 +546,7 @@ impl CliFactory {
         )?;
       Ok(Arc::new(Emitter::new(
         self.emit_cache()?.clone(),
+        ModuleSourceCache::shared(),
         self.parsed_source_cache().clone(),
         transpile_options,
         emit_options,
@@ -778,12 +783,26 @@ impl CliFactory {
       .services
       .root_permissions_container
       .get_or_try_init(|| {
+        let cli_options = self.cli_options()?;
         let desc_parser = self.permission_desc_parser()?.clone();
         let permissions = Permissions::from_options(
           desc_parser.as_ref(),
-          &self.cli_options()?.permissions_options(),
+          &cli_options.permissions_options(),
         )?;
-        Ok(PermissionsContainer::new(desc_parser, permissions))
+        let container = PermissionsContainer::new(desc_parser, permissions);
+        let flags = cli_options.permission_flags();
+        match &flags.allow_read_frozen {
+          Some(paths) => {
+            let roots = paths
+              .iter()
+              .map(|path| normalize_path(cli_options.initial_cwd().join(path)))
+              .collect::<Vec<_>>();
+            let snapshot =
+              build_frozen_read_snapshot(&roots, flags.frozen_read_audit)?;
+            Ok(container.with_frozen_read(Arc::new(snapshot)))
+          }
+          None => Ok(container),
+        }
       })
   }
 
@@ -812,6 +831,7 @@ impl CliFactory {
       },
       self.feature_checker()?.clone(),
       self.fs().clone(),
+      Some(self.file_fetcher()?.clone()),
       maybe_file_watcher_communicator,
       self.maybe_inspector_server()?.clone(),
       cli_options.maybe_lockfile().cloned(),
@@ -900,13 +920,25 @@ impl CliFactory {
         .or(std::env::args().next()),
       node_debug: std::env::var("NODE_DEBUG").ok(),
       origin_data_folder_path: Some(self.deno_dir()?.origin_data_folder_path()),
+      blob_store_folder_path: Some(self.deno_dir()?.blob_store_folder_path()),
+      schedule_state_path: match cli_options.resolve_main_module() {
+        Ok(main_module) => {
+          Some(self.deno_dir()?.schedule_state_file_path(main_module))
+        }
+        Err(_) => None,
+      },
       seed: cli_options.seed(),
+      sigint_grace_period: cli_options.sigint_grace_period(),
+      tick_budget: cli_options.tick_budget(),
+      dns_resolver: cli_options.dns_resolver()?,
+      fs_limits: fs_limits_from_env(&**self.fs())?,
       unsafely_ignore_certificate_errors: cli_options
         .unsafely_ignore_certificate_errors()
         .clone(),
       create_hmr_runner,
       create_coverage_collector,
       node_ipc: cli_options.node_ipc_fd(),
+      spawn_self_ipc: deno_runtime::ops::process::spawn_self_ipc_fd_from_env(),
       serve_port: cli_options.serve_port(),
       serve_host: cli_options.serve_host(),
       unstable_detect_cjs: cli_options.unstable_detect_cjs(),
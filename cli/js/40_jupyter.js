@@ -337,7 +337,11 @@ async function formatInner(obj, raw) {
 internals.jupyter = { formatInner };
 
 function enableJupyter() {
-  const { op_jupyter_broadcast, op_jupyter_input } = core.ops;
+  const {
+    op_jupyter_broadcast,
+    op_jupyter_input,
+    op_jupyter_input_request,
+  } = core.ops;
 
   function input(
     prompt,
@@ -456,6 +460,22 @@ function enableJupyter() {
     return answer;
   }
 
+  /**
+   * Request input from the frontend on the stdin channel, without blocking
+   * the event loop (in Jupyter Notebook context).
+   * @param {string} message - The message to display.
+   * @param {object} options Options
+   * @param {boolean} options.password Hide the output characters
+   * @param {number} options.timeout Milliseconds to wait for the input
+   * @returns {Promise<string>} The user input.
+   */
+  function jupyterPrompt(
+    message = "",
+    { password = false, timeout } = { __proto__: null },
+  ) {
+    return op_jupyter_input_request(`${message}`, password, timeout);
+  }
+
   globalThis.confirm = confirm;
   globalThis.prompt = prompt;
   globalThis.Deno.jupyter = {
@@ -465,6 +485,7 @@ function enableJupyter() {
     md,
     html,
     svg,
+    prompt: jupyterPrompt,
     $display,
   };
 }
@@ -7,6 +7,7 @@ import { escapeName, withPermissions } from "ext:cli/40_test_common.js";
 const {
   op_register_test_step,
   op_register_test,
+  op_reset_test_permissions_pool,
   op_test_event_step_result_failed,
   op_test_event_step_result_ignored,
   op_test_event_step_result_ok,
@@ -21,6 +22,7 @@ const {
   Map,
   MapPrototypeGet,
   MapPrototypeSet,
+  ObjectGetOwnPropertyNames,
   SafeArrayIterator,
   SymbolToStringTag,
   TypeError,
@@ -498,6 +500,30 @@ function createTestContext(desc) {
   };
 }
 
+/**
+ * Test permissions are pooled between tests. A test that added or removed
+ * globals may have left state behind that the pool can't see, so the next
+ * test gets freshly created permissions.
+ * @param fn {TestFunction}
+ */
+function withGlobalsCheck(fn) {
+  return async function checkGlobals(...params) {
+    const before = ObjectGetOwnPropertyNames(globalThis);
+    try {
+      return await fn(...new SafeArrayIterator(params));
+    } finally {
+      const after = ObjectGetOwnPropertyNames(globalThis);
+      let changed = before.length !== after.length;
+      for (let i = 0; !changed && i < after.length; ++i) {
+        changed = before[i] !== after[i];
+      }
+      if (changed) {
+        op_reset_test_permissions_pool();
+      }
+    }
+  };
+}
+
 /**
  * Wrap a user test function in one which returns a structured result.
  * @template T {Function}
@@ -511,7 +537,7 @@ function wrapTest(desc) {
     testFn = assertExit(testFn, true);
   }
   if (!("parent" in desc) && desc.permissions) {
-    testFn = withPermissions(testFn, desc.permissions);
+    testFn = withGlobalsCheck(withPermissions(testFn, desc.permissions));
   }
   return wrapOuter(testFn, desc);
 }
@@ -396,6 +396,8 @@ pub fn main() {
   setup_panic_hook();
 
   util::unix::raise_fd_limit();
+  // Best effort, listeners work without the reserved descriptor.
+  let _ = deno_runtime::deno_io::fd_exhaustion::reserve_fd_from_env();
   util::windows::ensure_stdio_open();
   #[cfg(windows)]
   colors::enable_ansi(); // For Windows 10
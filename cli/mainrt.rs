@@ -91,6 +91,8 @@ fn main() {
       Ok(Some(data)) => {
         util::logger::init(data.metadata.log_level);
         load_env_vars(&data.metadata.env_vars_from_env_file);
+        // Best effort, listeners work without the reserved descriptor.
+        let _ = deno_runtime::deno_io::fd_exhaustion::reserve_fd_from_env();
         let exit_code = standalone::run(data).await?;
         std::process::exit(exit_code);
       }
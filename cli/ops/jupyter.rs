@@ -7,6 +7,7 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use jupyter_runtime::InputRequest;
 use jupyter_runtime::JupyterMessage;
@@ -14,6 +15,7 @@ use jupyter_runtime::JupyterMessageContent;
 use jupyter_runtime::KernelIoPubConnection;
 use jupyter_runtime::StreamContent;
 
+use deno_core::error::custom_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::parking_lot::Mutex;
@@ -27,6 +29,7 @@ deno_core::extension!(deno_jupyter,
   ops = [
     op_jupyter_broadcast,
     op_jupyter_input,
+    op_jupyter_input_request,
   ],
   options = {
     sender: mpsc::UnboundedSender<StreamContent>,
@@ -40,6 +43,63 @@ deno_core::extension!(deno_jupyter,
   },
 );
 
+/// Builds an `input_request` for the execution that is currently running.
+fn new_input_request(
+  state: &OpState,
+  prompt: String,
+  password: bool,
+) -> Result<JupyterMessage, AnyError> {
+  let last_request = state
+    .borrow::<Arc<Mutex<Option<JupyterMessage>>>>()
+    .lock()
+    .clone();
+  let Some(last_request) = last_request else {
+    return Err(custom_error(
+      "NotSupported",
+      "Input can only be requested while a cell is executing",
+    ));
+  };
+  let JupyterMessageContent::ExecuteRequest(msg) = &last_request.content else {
+    return Err(custom_error(
+      "NotSupported",
+      "Input can only be requested while a cell is executing",
+    ));
+  };
+  if !msg.allow_stdin {
+    return Err(custom_error(
+      "NotSupported",
+      "The frontend doesn't support input requests for this execution (allow_stdin is false)",
+    ));
+  }
+  let content = InputRequest { prompt, password };
+  Ok(JupyterMessage::new(content, Some(&last_request)))
+}
+
+fn input_request_pending() -> AnyError {
+  custom_error(
+    "Busy",
+    "An input request is already pending for this execution",
+  )
+}
+
+fn stdin_closed() -> AnyError {
+  custom_error("BrokenPipe", "The stdin channel of the kernel is closed")
+}
+
+/// Returns the value of `reply` if it is the `input_reply` to the request
+/// with `msg_id`. Replies to earlier requests, e.g. ones that timed out, are
+/// skipped.
+fn input_reply_value(reply: JupyterMessage, msg_id: &str) -> Option<String> {
+  let is_reply = reply
+    .parent_header
+    .as_ref()
+    .is_some_and(|parent| parent.msg_id == msg_id);
+  match reply.content {
+    JupyterMessageContent::InputReply(reply) if is_reply => Some(reply.value),
+    _ => None,
+  }
+}
+
 #[op2]
 #[string]
 pub fn op_jupyter_input(
@@ -47,52 +107,81 @@ pub fn op_jupyter_input(
   #[string] prompt: String,
   is_password: bool,
 ) -> Result<Option<String>, AnyError> {
-  let (last_execution_request, stdin_connection_proxy) = {
-    (
-      state.borrow::<Arc<Mutex<Option<JupyterMessage>>>>().clone(),
-      state.borrow::<Arc<Mutex<StdinConnectionProxy>>>().clone(),
-    )
+  // `prompt()` and `confirm()` behave like without a terminal when input
+  // can't be requested
+  let Ok(request) = new_input_request(state, prompt, is_password) else {
+    return Ok(None);
   };
+  let stdin_connection_proxy =
+    state.borrow::<Arc<Mutex<StdinConnectionProxy>>>().clone();
+  // held by a pending `Deno.jupyter.prompt()`, which can't make progress
+  // while this op blocks
+  if stdin_connection_proxy.try_lock().is_none() {
+    return Err(input_request_pending());
+  }
 
-  let maybe_last_request = last_execution_request.lock().clone();
-  if let Some(last_request) = maybe_last_request {
-    let JupyterMessageContent::ExecuteRequest(msg) = &last_request.content
-    else {
-      return Ok(None);
-    };
+  let msg_id = request.header.msg_id.clone();
+  let Ok(()) = stdin_connection_proxy.lock().tx.send(request) else {
+    return Ok(None);
+  };
 
-    if !msg.allow_stdin {
-      return Ok(None);
+  // Need to spawn a separate thread here, because `blocking_recv()` can't
+  // be used from the Tokio runtime context.
+  let join_handle = std::thread::spawn(move || {
+    let mut proxy = stdin_connection_proxy.lock();
+    while let Some(reply) = proxy.rx.blocking_recv() {
+      if let Some(value) = input_reply_value(reply, &msg_id) {
+        return Some(value);
+      }
     }
+    None
+  });
+  Ok(join_handle.join().ok().flatten())
+}
 
-    let content = InputRequest {
-      prompt,
-      password: is_password,
-    };
-
-    let msg = JupyterMessage::new(content, Some(&last_request));
-
-    let Ok(()) = stdin_connection_proxy.lock().tx.send(msg) else {
-      return Ok(None);
-    };
-
-    // Need to spawn a separate thread here, because `blocking_recv()` can't
-    // be used from the Tokio runtime context.
-    let join_handle = std::thread::spawn(move || {
-      stdin_connection_proxy.lock().rx.blocking_recv()
-    });
-    let Ok(Some(response)) = join_handle.join() else {
-      return Ok(None);
-    };
-
-    let JupyterMessageContent::InputReply(msg) = response.content else {
-      return Ok(None);
-    };
+#[op2(async)]
+#[string]
+pub async fn op_jupyter_input_request(
+  state: Rc<RefCell<OpState>>,
+  #[string] prompt: String,
+  password: bool,
+  #[serde] timeout_ms: Option<u64>,
+) -> Result<String, AnyError> {
+  let (request, stdin_connection_proxy) = {
+    let state = state.borrow();
+    (
+      new_input_request(&state, prompt, password)?,
+      state.borrow::<Arc<Mutex<StdinConnectionProxy>>>().clone(),
+    )
+  };
+  // only one request can wait for a reply at a time
+  let Some(mut proxy) = stdin_connection_proxy.try_lock() else {
+    return Err(input_request_pending());
+  };
 
-    return Ok(Some(msg.value));
+  let msg_id = request.header.msg_id.clone();
+  proxy.tx.send(request).map_err(|_| stdin_closed())?;
+  let reply = async {
+    while let Some(reply) = proxy.rx.recv().await {
+      if let Some(value) = input_reply_value(reply, &msg_id) {
+        return Ok(value);
+      }
+    }
+    Err(stdin_closed())
+  };
+  match timeout_ms {
+    Some(timeout_ms) => {
+      tokio::time::timeout(Duration::from_millis(timeout_ms), reply)
+        .await
+        .map_err(|_| {
+          custom_error(
+            "TimedOut",
+            format!("No input_reply was received within {timeout_ms}ms"),
+          )
+        })?
+    }
+    None => reply.await,
   }
-
-  Ok(None)
 }
 
 #[op2(async)]
@@ -13,11 +13,14 @@ use deno_core::error::generic_error;
 use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
+use deno_core::serde_json;
 use deno_core::v8;
 use deno_core::ModuleSpecifier;
 use deno_core::OpState;
 use deno_runtime::deno_permissions::ChildPermissionsArg;
+use deno_runtime::deno_permissions::Permissions;
 use deno_runtime::deno_permissions::PermissionsContainer;
+use std::collections::VecDeque;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use uuid::Uuid;
@@ -26,6 +29,7 @@ deno_core::extension!(deno_test,
   ops = [
     op_pledge_test_permissions,
     op_restore_test_permissions,
+    op_reset_test_permissions_pool,
     op_register_test,
     op_register_test_step,
     op_test_get_origin,
@@ -40,22 +44,84 @@ deno_core::extension!(deno_test,
   state = |state, options| {
     state.put(options.sender);
     state.put(TestContainer::default());
+    state.put(TestPermissionsPool::default());
   },
 );
 
 #[derive(Clone)]
 struct PermissionsHolder(Uuid, PermissionsContainer);
 
+/// Maximum number of distinct permission sets kept by a
+/// `TestPermissionsPool`.
+const TEST_PERMISSIONS_POOL_CAPACITY: usize = 16;
+
+struct TestPermissionsPoolEntry {
+  key: String,
+  parent: Permissions,
+  child: Permissions,
+}
+
+/// Caches the permissions derived from the `permissions` option of the tests
+/// in a file, so tests that ask for the same permissions don't parse and
+/// resolve the same descriptors again.
+///
+/// Every test gets its own copy of the cached permissions, so grants and
+/// revocations made by one test never reach the next. An entry is only reused
+/// while the permissions it was derived from are unchanged, and the runner
+/// clears the pool after a test fails, leaks ops or resources, or adds or
+/// removes globals.
+#[derive(Default)]
+pub struct TestPermissionsPool {
+  /// Least recently used first.
+  entries: VecDeque<TestPermissionsPoolEntry>,
+}
+
+impl TestPermissionsPool {
+  fn get_or_create(
+    &mut self,
+    parent: &PermissionsContainer,
+    args: serde_json::Value,
+  ) -> Result<PermissionsContainer, AnyError> {
+    let key = args.to_string();
+    let parent_snapshot = parent.snapshot();
+    if let Some(index) = self.entries.iter().position(|e| e.key == key) {
+      let entry = self.entries.remove(index).unwrap();
+      if entry.parent == parent_snapshot {
+        let permissions = parent.with_permissions(entry.child.clone());
+        self.entries.push_back(entry);
+        return Ok(permissions);
+      }
+    }
+
+    let args: ChildPermissionsArg = serde_json::from_value(args)?;
+    let permissions = parent.create_child_permissions(args)?;
+    if self.entries.len() >= TEST_PERMISSIONS_POOL_CAPACITY {
+      self.entries.pop_front();
+    }
+    self.entries.push_back(TestPermissionsPoolEntry {
+      key,
+      parent: parent_snapshot,
+      child: permissions.snapshot(),
+    });
+    Ok(permissions)
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
 #[op2]
 #[serde]
 pub fn op_pledge_test_permissions(
   state: &mut OpState,
-  #[serde] args: ChildPermissionsArg,
+  #[serde] args: serde_json::Value,
 ) -> Result<Uuid, AnyError> {
   let token = Uuid::new_v4();
-  let parent_permissions = state.borrow_mut::<PermissionsContainer>();
-  let worker_permissions = parent_permissions.create_child_permissions(args)?;
-  let parent_permissions = parent_permissions.clone();
+  let parent_permissions = state.borrow::<PermissionsContainer>().clone();
+  let worker_permissions = state
+    .borrow_mut::<TestPermissionsPool>()
+    .get_or_create(&parent_permissions, args)?;
 
   if state.try_take::<PermissionsHolder>().is_some() {
     panic!("pledge test permissions called before restoring previous pledge");
@@ -86,6 +152,12 @@ pub fn op_restore_test_permissions(
   }
 }
 
+/// Called by the test harness when a test changed the set of globals.
+#[op2(fast)]
+fn op_reset_test_permissions_pool(state: &mut OpState) {
+  state.borrow_mut::<TestPermissionsPool>().clear();
+}
+
 static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
 
 #[allow(clippy::too_many_arguments)]
@@ -218,3 +290,64 @@ fn op_test_event_step_result_failed(
     ))
     .ok();
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::serde_json::json;
+  use deno_runtime::deno_fs::RealFs;
+  use deno_runtime::permissions::RuntimePermissionDescriptorParser;
+  use std::sync::Arc;
+
+  fn parent(permissions: Permissions) -> PermissionsContainer {
+    PermissionsContainer::new(
+      Arc::new(RuntimePermissionDescriptorParser::new(Arc::new(RealFs))),
+      permissions,
+    )
+  }
+
+  #[test]
+  fn pool_reuses_entries_and_hands_out_copies() {
+    let parent = parent(Permissions::allow_all());
+    let mut pool = TestPermissionsPool::default();
+    let a = pool
+      .get_or_create(&parent, json!({ "read": true, "net": false }))
+      .unwrap();
+    let b = pool
+      .get_or_create(&parent, json!({ "read": true, "net": false }))
+      .unwrap();
+    assert_eq!(pool.entries.len(), 1);
+    assert_eq!(a.snapshot(), b.snapshot());
+    assert_ne!(a.snapshot(), parent.snapshot());
+  }
+
+  #[test]
+  fn pool_recreates_entries_when_parent_changes() {
+    let mut pool = TestPermissionsPool::default();
+    let args = json!({ "read": "inherit" });
+    let first = pool
+      .get_or_create(&parent(Permissions::allow_all()), args.clone())
+      .unwrap();
+    let restricted = parent(Permissions::none_without_prompt());
+    let second = pool.get_or_create(&restricted, args).unwrap();
+    assert_eq!(pool.entries.len(), 1);
+    assert_ne!(first.snapshot(), second.snapshot());
+    assert_eq!(pool.entries[0].parent, restricted.snapshot());
+  }
+
+  #[test]
+  fn pool_is_bounded() {
+    let parent = parent(Permissions::allow_all());
+    let mut pool = TestPermissionsPool::default();
+    for i in 0..TEST_PERMISSIONS_POOL_CAPACITY + 4 {
+      pool
+        .get_or_create(&parent, json!({ "env": [format!("VAR_{i}")] }))
+        .unwrap();
+    }
+    assert_eq!(pool.entries.len(), TEST_PERMISSIONS_POOL_CAPACITY);
+    // The least recently used entries were evicted.
+    assert_eq!(pool.entries[0].key, json!({ "env": ["VAR_4"] }).to_string());
+    pool.clear();
+    assert!(pool.entries.is_empty());
+  }
+}
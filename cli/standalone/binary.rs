@@ -46,6 +46,8 @@ use deno_runtime::deno_fs::FileSystem;
 use deno_runtime::deno_fs::RealFs;
 use deno_runtime::deno_io::fs::FsError;
 use deno_runtime::deno_node::PackageJson;
+use deno_runtime::deno_tls::TlsClientDefaults;
+use deno_runtime::deno_tls::TlsVersion;
 use deno_semver::npm::NpmVersionReqParseError;
 use deno_semver::package::PackageReq;
 use deno_semver::Version;
@@ -177,12 +179,45 @@ pub struct Metadata {
   pub log_level: Option<Level>,
   pub ca_stores: Option<Vec<String>>,
   pub ca_data: Option<Vec<u8>>,
+  pub tls_client_defaults: TlsClientDefaults,
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
   pub env_vars_from_env_file: IndexMap<String, String>,
   pub workspace_resolver: SerializedWorkspaceResolver,
   pub entrypoint_key: String,
   pub node_modules: Option<NodeModules>,
   pub unstable_config: UnstableConfig,
+  pub verified_assets: Option<VerifiedAssets>,
+}
+
+/// An asset directory next to the executable, verified against its
+/// integrity manifest at startup.
+#[derive(Deserialize, Serialize)]
+pub struct VerifiedAssets {
+  /// The name of the directory, relative to the executable's directory.
+  pub dir: String,
+  pub manifest: deno_fs::IntegrityManifest,
+}
+
+async fn create_verified_assets(dir: &str) -> Result<VerifiedAssets, AnyError> {
+  let path = Path::new(dir);
+  let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+    bail!("Invalid asset directory: {dir}");
+  };
+  if !path.is_dir() {
+    bail!("Asset directory not found: {dir}");
+  }
+  let manifest = deno_fs::create_integrity_manifest(
+    &RealFs,
+    path,
+    &Default::default(),
+    &Default::default(),
+  )
+  .await
+  .with_context(|| format!("Creating integrity manifest of: {dir}"))?;
+  Ok(VerifiedAssets {
+    dir: name.to_string(),
+    manifest,
+  })
 }
 
 fn write_binary_bytes(
@@ -530,7 +565,7 @@ impl<'a> DenoCompileBinaryWriter<'a> {
     cli_options: &CliOptions,
     compile_flags: &CompileFlags,
   ) -> Result<(), AnyError> {
-    let ca_data = match cli_options.ca_data() {
+    let mut ca_data = match cli_options.ca_data() {
       Some(CaData::File(ca_file)) => Some(
         std::fs::read(ca_file)
           .with_context(|| format!("Reading: {ca_file}"))?,
@@ -538,6 +573,28 @@ impl<'a> DenoCompileBinaryWriter<'a> {
       Some(CaData::Bytes(bytes)) => Some(bytes.clone()),
       None => None,
     };
+    for ca_file in &compile_flags.embed_ca_files {
+      let bytes = std::fs::read(ca_file)
+        .with_context(|| format!("Reading: {ca_file}"))?;
+      let ca_data = ca_data.get_or_insert_with(Vec::new);
+      if !ca_data.is_empty() && !ca_data.ends_with(b"\n") {
+        ca_data.push(b'\n');
+      }
+      ca_data.extend_from_slice(&bytes);
+    }
+    let tls_client_defaults = TlsClientDefaults {
+      min_version: compile_flags
+        .tls_min_version
+        .as_deref()
+        .map(|version| version.parse::<TlsVersion>())
+        .transpose()
+        .map_err(AnyError::msg)?,
+      alpn_protocols: compile_flags.tls_alpn_protocols.clone(),
+    };
+    let verified_assets = match compile_flags.verify_assets.as_deref() {
+      Some(dir) => Some(create_verified_assets(dir).await?),
+      None => None,
+    };
     let root_path = root_dir_url.inner().to_file_path().unwrap();
     let (maybe_npm_vfs, node_modules, npm_snapshot) = match self
       .npm_resolver
@@ -665,6 +722,7 @@ impl<'a> DenoCompileBinaryWriter<'a> {
       log_level: cli_options.log_level(),
       ca_stores: cli_options.ca_stores().clone(),
       ca_data,
+      tls_client_defaults,
       env_vars_from_env_file,
       entrypoint_key: root_dir_url.specifier_key(entrypoint).into_owned(),
       workspace_resolver: SerializedWorkspaceResolver {
@@ -711,6 +769,7 @@ impl<'a> DenoCompileBinaryWriter<'a> {
         sloppy_imports: cli_options.unstable_sloppy_imports(),
         features: cli_options.unstable_features(),
       },
+      verified_assets,
     };
 
     write_binary_bytes(
@@ -35,6 +35,7 @@ use deno_runtime::deno_permissions::Permissions;
 use deno_runtime::deno_permissions::PermissionsContainer;
 use deno_runtime::deno_tls::rustls::RootCertStore;
 use deno_runtime::deno_tls::RootCertStoreProvider;
+use deno_runtime::deno_tls::TlsClientDefaults;
 use deno_runtime::deno_web::BlobStore;
 use deno_runtime::permissions::RuntimePermissionDescriptorParser;
 use deno_runtime::WorkerExecutionMode;
@@ -44,10 +45,13 @@ use import_map::parse_from_json;
 use node_resolver::analyze::NodeCodeTranslator;
 use node_resolver::NodeResolutionMode;
 use std::borrow::Cow;
+use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use crate::args::create_default_npmrc;
+use crate::args::fs_limits_from_env;
 use crate::args::get_root_cert_store;
 use crate::args::npm_pkg_req_ref_to_binary_command;
 use crate::args::CaData;
@@ -72,6 +76,7 @@ use crate::resolver::CliNodeResolver;
 use crate::resolver::NpmModuleLoader;
 use crate::util::progress_bar::ProgressBar;
 use crate::util::progress_bar::ProgressBarStyle;
+use crate::util::text_encoding::source_map_from_code;
 use crate::util::v8::construct_v8_flags;
 use crate::worker::CliMainWorkerFactory;
 use crate::worker::CliMainWorkerOptions;
@@ -86,6 +91,7 @@ mod virtual_fs;
 pub use binary::extract_standalone;
 pub use binary::is_standalone_binary;
 pub use binary::DenoCompileBinaryWriter;
+pub use virtual_fs::build_frozen_read_snapshot;
 
 use self::binary::Metadata;
 use self::file_system::DenoCompileFileSystem;
@@ -337,6 +343,13 @@ impl ModuleLoader for EmbeddedModuleLoader {
       ))),
     }
   }
+
+  fn get_source_map(&self, file_name: &str) -> Option<Vec<u8>> {
+    // Transpiled modules are embedded with an inline source map.
+    let specifier = ModuleSpecifier::parse(file_name).ok()?;
+    let module = self.shared.modules.read(&specifier).ok()??;
+    source_map_from_code(&module.data)
+  }
 }
 
 struct StandaloneModuleLoaderFactory {
@@ -368,19 +381,105 @@ impl ModuleLoaderFactory for StandaloneModuleLoaderFactory {
   }
 }
 
+/// Resolves the root cert store of a compiled binary on first use, so that
+/// the embedded CA bundle is only parsed when a TLS connection is made.
+///
+/// Extra certificates come from, in increasing order of precedence:
+/// 1. the CA bundle embedded by `deno compile`,
+/// 2. a `DENO_CERT` set when running the binary, which replaces (1),
+/// 3. the `caCerts` of an individual connection, which the TLS ops add on top
+///    of this store.
 struct StandaloneRootCertStoreProvider {
   ca_stores: Option<Vec<String>>,
-  ca_data: Option<CaData>,
+  embedded_ca_data: Option<Vec<u8>>,
+  client_defaults: TlsClientDefaults,
   cell: once_cell::sync::OnceCell<RootCertStore>,
 }
 
 impl RootCertStoreProvider for StandaloneRootCertStoreProvider {
   fn get_or_try_init(&self) -> Result<&RootCertStore, AnyError> {
     self.cell.get_or_try_init(|| {
-      get_root_cert_store(None, self.ca_stores.clone(), self.ca_data.clone())
+      let ca_data = resolve_ca_data(
+        self.embedded_ca_data.clone(),
+        std::env::var("DENO_CERT").ok(),
+      );
+      get_root_cert_store(None, self.ca_stores.clone(), ca_data)
         .map_err(|err| err.into())
     })
   }
+
+  fn client_defaults(&self) -> Option<&TlsClientDefaults> {
+    Some(&self.client_defaults)
+  }
+}
+
+fn resolve_ca_data(
+  embedded_ca_data: Option<Vec<u8>>,
+  deno_cert: Option<String>,
+) -> Option<CaData> {
+  match deno_cert {
+    Some(ca_file) => Some(CaData::File(ca_file)),
+    None => embedded_ca_data.map(CaData::Bytes),
+  }
+}
+
+/// Schedules of a compiled binary are kept next to it, so they follow the
+/// binary when it's moved, or in the deno dir if that isn't writable.
+fn schedule_state_path(
+  deno_dir_provider: &DenoDirProvider,
+  main_module: &ModuleSpecifier,
+) -> Option<PathBuf> {
+  let exe = std::env::current_exe().ok()?;
+  let mut path = exe.clone().into_os_string();
+  path.push(".schedules.json");
+  let path = PathBuf::from(path);
+  let writable = if path.exists() {
+    std::fs::OpenOptions::new().append(true).open(&path).is_ok()
+  } else {
+    exe
+      .parent()
+      .is_some_and(|dir| tempfile::NamedTempFile::new_in(dir).is_ok())
+  };
+  if writable {
+    return Some(path);
+  }
+  let deno_dir = deno_dir_provider.get_or_create().ok()?;
+  Some(deno_dir.schedule_state_file_path(main_module))
+}
+
+/// Checks the asset directory next to the executable against the manifest
+/// embedded by `deno compile --verify-assets`.
+async fn verify_assets(
+  assets: &binary::VerifiedAssets,
+) -> Result<(), AnyError> {
+  let exe = std::env::current_exe()?;
+  let dir = exe.parent().unwrap_or(Path::new(".")).join(&assets.dir);
+  let report = deno_fs::verify_integrity_manifest(
+    &deno_fs::RealFs,
+    &dir,
+    &assets.manifest,
+    &Default::default(),
+    &Default::default(),
+  )
+  .await
+  .with_context(|| format!("Failed verifying assets in {}", dir.display()))?;
+  if report.ok {
+    return Ok(());
+  }
+  let mut message = format!(
+    "Assets in {} don't match the ones this executable was built with",
+    dir.display()
+  );
+  for (label, paths) in [
+    ("missing", &report.missing),
+    ("unexpected", &report.extra),
+    ("modified", &report.modified),
+  ] {
+    for path in paths {
+      message.push_str(&format!("\n    {label}: {path}"));
+    }
+  }
+  Err(generic_error(message))
 }
 
 pub async fn run(data: StandaloneData) -> Result<i32, AnyError> {
@@ -392,10 +491,14 @@ pub async fn run(data: StandaloneData) -> Result<i32, AnyError> {
     root_path,
     vfs,
   } = data;
+  if let Some(assets) = &metadata.verified_assets {
+    verify_assets(assets).await?;
+  }
   let deno_dir_provider = Arc::new(DenoDirProvider::new(None));
   let root_cert_store_provider = Arc::new(StandaloneRootCertStoreProvider {
     ca_stores: metadata.ca_stores,
-    ca_data: metadata.ca_data.map(CaData::Bytes),
+    embedded_ca_data: metadata.ca_data,
+    client_defaults: metadata.tls_client_defaults,
     cell: Default::default(),
   });
   let progress_bar = ProgressBar::new(ProgressBarStyle::TextOnly);
@@ -607,6 +710,7 @@ pub async fn run(data: StandaloneData) -> Result<i32, AnyError> {
     }
     checker
   });
+  let fs_limits = fs_limits_from_env(&*fs)?;
   let worker_factory = CliMainWorkerFactory::new(
     Arc::new(BlobStore::default()),
     cjs_resolutions,
@@ -617,6 +721,7 @@ pub async fn run(data: StandaloneData) -> Result<i32, AnyError> {
     None,
     None,
     None,
+    None,
     Box::new(module_loader_factory),
     node_resolver,
     npm_resolver,
@@ -644,12 +749,24 @@ pub async fn run(data: StandaloneData) -> Result<i32, AnyError> {
         .or(std::env::args().next()),
       node_debug: std::env::var("NODE_DEBUG").ok(),
       origin_data_folder_path: None,
+      blob_store_folder_path: None,
+      schedule_state_path: schedule_state_path(
+        &deno_dir_provider,
+        &main_module,
+      ),
       seed: metadata.seed,
+      sigint_grace_period: Some(
+        deno_runtime::ops::signal::sigint_grace_period_from_env(),
+      ),
+      tick_budget: None,
+      dns_resolver: Default::default(),
+      fs_limits,
       unsafely_ignore_certificate_errors: metadata
         .unsafely_ignore_certificate_errors,
       create_hmr_runner: None,
       create_coverage_collector: None,
       node_ipc: None,
+      spawn_self_ipc: deno_runtime::ops::process::spawn_self_ipc_fd_from_env(),
       serve_port: None,
       serve_host: None,
       unstable_detect_cjs: metadata.unstable_config.detect_cjs,
@@ -20,11 +20,19 @@ use deno_core::parking_lot::Mutex;
 use deno_core::BufMutView;
 use deno_core::BufView;
 use deno_core::ResourceHandleFd;
+use deno_runtime::deno_fs::walk_dir_recursive;
 use deno_runtime::deno_fs::FsDirEntry;
+use deno_runtime::deno_fs::RealFs;
+use deno_runtime::deno_fs::WalkEntryKind;
+use deno_runtime::deno_fs::WalkError;
+use deno_runtime::deno_fs::WalkOptions;
 use deno_runtime::deno_io;
 use deno_runtime::deno_io::fs::FsError;
 use deno_runtime::deno_io::fs::FsResult;
 use deno_runtime::deno_io::fs::FsStat;
+use deno_runtime::deno_permissions::frozen_read::resolve_frozen_root;
+use deno_runtime::deno_permissions::frozen_read::FrozenEntryKind;
+use deno_runtime::deno_permissions::frozen_read::FrozenReadSnapshot;
 use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
@@ -41,6 +49,43 @@ pub struct StripRootError {
   target: PathBuf,
 }
 
+/// Records the listing of the `--allow-read-frozen` paths. Roots that don't
+/// exist are still frozen, so nothing created there later becomes visible.
+/// Roots are resolved first, so a symlinked root freezes the tree it points
+/// to.
+pub fn build_frozen_read_snapshot(
+  roots: &[PathBuf],
+  audit: bool,
+) -> Result<FrozenReadSnapshot, AnyError> {
+  let mut snapshot = FrozenReadSnapshot::new(audit);
+  for root in roots {
+    let root = &resolve_frozen_root(root);
+    snapshot.add_root(root.clone());
+    let metadata = match std::fs::symlink_metadata(root) {
+      Ok(metadata) => metadata,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+      Err(err) => {
+        return Err(err).with_context(|| format!("Reading {}", root.display()))
+      }
+    };
+    if root.is_dir() {
+      let options = WalkOptions::default();
+      walk_dir_recursive(&RealFs, root, &options, &mut |path, kind| {
+        let kind = match kind {
+          WalkEntryKind::Dir => FrozenEntryKind::Dir,
+          WalkEntryKind::File => FrozenEntryKind::File,
+          WalkEntryKind::Symlink => FrozenEntryKind::Symlink,
+        };
+        snapshot.insert(path.to_path_buf(), kind);
+        Ok(())
+      })?;
+    }
+    snapshot.insert(root.clone(), metadata.file_type().into());
+  }
+  log::debug!("Frozen {} paths for reading", snapshot.len());
+  Ok(snapshot)
+}
+
 pub struct VfsBuilder {
   root_path: PathBuf,
   root_dir: VirtualDirectory,
@@ -107,61 +152,57 @@ impl VfsBuilder {
     &mut self,
     path: &Path,
   ) -> Result<(), AnyError> {
-    self.add_dir(path)?;
-    let read_dir = std::fs::read_dir(path)
-      .with_context(|| format!("Reading {}", path.display()))?;
+    let options = WalkOptions::default();
+    walk_dir_recursive(&RealFs, path, &options, &mut |path, kind| match kind {
+      WalkEntryKind::Dir => {
+        self.add_dir(path)?;
+        Ok(())
+      }
+      WalkEntryKind::File => self.add_file_at_path_not_symlink(path),
+      WalkEntryKind::Symlink => self.add_symlink_or_inline(path),
+    })
+    .map_err(|err| match err {
+      WalkError::Visit(err) => err,
+      err => err.into(),
+    })
+  }
 
-    let mut dir_entries =
-      read_dir.into_iter().collect::<Result<Vec<_>, _>>()?;
-    dir_entries.sort_by_cached_key(|entry| entry.file_name()); // determinism
-
-    for entry in dir_entries {
-      let file_type = entry.file_type()?;
-      let path = entry.path();
-
-      if file_type.is_dir() {
-        self.add_dir_recursive_internal(&path)?;
-      } else if file_type.is_file() {
-        self.add_file_at_path_not_symlink(&path)?;
-      } else if file_type.is_symlink() {
-        match util::fs::canonicalize_path(&path) {
-          Ok(target) => {
-            if let Err(StripRootError { .. }) = self.add_symlink(&path, &target)
-            {
-              if target.is_file() {
-                // this may change behavior, so warn the user about it
-                log::warn!(
-                  "{} Symlink target is outside '{}'. Inlining symlink at '{}' to '{}' as file.",
-                  crate::colors::yellow("Warning"),
-                  self.root_path.display(),
-                  path.display(),
-                  target.display(),
-                );
-                // inline the symlink and make the target file
-                let file_bytes = std::fs::read(&target)
-                  .with_context(|| format!("Reading {}", path.display()))?;
-                self.add_file_with_data_inner(&path, file_bytes)?;
-              } else {
-                log::warn!(
-                  "{} Symlink target is outside '{}'. Excluding symlink at '{}' with target '{}'.",
-                  crate::colors::yellow("Warning"),
-                  self.root_path.display(),
-                  path.display(),
-                  target.display(),
-                );
-              }
-            }
-          }
-          Err(err) => {
+  fn add_symlink_or_inline(&mut self, path: &Path) -> Result<(), AnyError> {
+    match util::fs::canonicalize_path(path) {
+      Ok(target) => {
+        if let Err(StripRootError { .. }) = self.add_symlink(path, &target) {
+          if target.is_file() {
+            // this may change behavior, so warn the user about it
             log::warn!(
-              "{} Failed resolving symlink. Ignoring.\n    Path: {}\n    Message: {:#}",
+              "{} Symlink target is outside '{}'. Inlining symlink at '{}' to '{}' as file.",
               crate::colors::yellow("Warning"),
+              self.root_path.display(),
               path.display(),
-              err
+              target.display(),
+            );
+            // inline the symlink and make the target file
+            let file_bytes = std::fs::read(&target)
+              .with_context(|| format!("Reading {}", path.display()))?;
+            self.add_file_with_data_inner(path, file_bytes)?;
+          } else {
+            log::warn!(
+              "{} Symlink target is outside '{}'. Excluding symlink at '{}' with target '{}'.",
+              crate::colors::yellow("Warning"),
+              self.root_path.display(),
+              path.display(),
+              target.display(),
             );
           }
         }
       }
+      Err(err) => {
+        log::warn!(
+          "{} Failed resolving symlink. Ignoring.\n    Path: {}\n    Message: {:#}",
+          crate::colors::yellow("Warning"),
+          path.display(),
+          err
+        );
+      }
     }
 
     Ok(())
@@ -1075,6 +1116,39 @@ mod test {
     );
   }
 
+  #[test]
+  fn test_frozen_read_snapshot() {
+    let temp_dir = TempDir::new();
+    let temp_dir_path = temp_dir.path().canonicalize();
+    temp_dir.create_dir_all("src/nested");
+    temp_dir.write("src/a.txt", "data");
+    temp_dir.write("src/nested/b.txt", "data");
+    util::fs::symlink_dir(
+      temp_dir_path.join("src/nested").as_path(),
+      temp_dir_path.join("src/nested_link").as_path(),
+    )
+    .unwrap();
+
+    let src_path = temp_dir_path.join("src").to_path_buf();
+    let missing_path = temp_dir_path.join("missing").to_path_buf();
+    let snapshot = build_frozen_read_snapshot(
+      &[src_path.clone(), missing_path.clone()],
+      false,
+    )
+    .unwrap();
+    // src, a.txt, nested, nested/b.txt, nested_link
+    assert_eq!(snapshot.len(), 5);
+    assert!(snapshot.check(&src_path.join("nested/b.txt")).is_ok());
+    assert!(snapshot.check(&src_path.join("nested_link")).is_ok());
+    // read through the symlinked directory
+    assert!(snapshot.check(&src_path.join("nested_link/b.txt")).is_ok());
+
+    temp_dir.write("src/c.txt", "data");
+    temp_dir.write("missing", "data");
+    assert!(snapshot.check(&src_path.join("c.txt")).is_err());
+    assert!(snapshot.check(&missing_path).is_err());
+  }
+
   fn into_virtual_fs(
     builder: VfsBuilder,
     temp_dir: &TempDir,
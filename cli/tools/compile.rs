@@ -361,6 +361,10 @@ mod test {
         no_terminal: false,
         icon: None,
         include: vec![],
+        embed_ca_files: vec![],
+        tls_min_version: None,
+        tls_alpn_protocols: None,
+        verify_assets: None,
       },
       &std::env::current_dir().unwrap(),
     )
@@ -386,6 +390,10 @@ mod test {
         include: vec![],
         icon: None,
         no_terminal: false,
+        embed_ca_files: vec![],
+        tls_min_version: None,
+        tls_alpn_protocols: None,
+        verify_assets: None,
       },
       &std::env::current_dir().unwrap(),
     )
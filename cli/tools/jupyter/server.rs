@@ -109,21 +109,29 @@ impl JupyterServer {
       repl_session_proxy,
     };
 
+    // Requests and replies are forwarded independently, so that a request
+    // that never gets a reply doesn't block later ones. Replies are matched
+    // to their request by the ops.
     let stdin_fut = deno_core::unsync::spawn(async move {
       loop {
-        let Some(msg) = stdin_rx1.recv().await else {
-          return;
-        };
-        let Ok(()) = stdin_connection.send(msg).await else {
-          return;
-        };
-
-        let Ok(msg) = stdin_connection.read().await else {
-          return;
-        };
-        let Ok(()) = stdin_tx2.send(msg) else {
-          return;
-        };
+        tokio::select! {
+          msg = stdin_rx1.recv() => {
+            let Some(msg) = msg else {
+              return;
+            };
+            let Ok(()) = stdin_connection.send(msg).await else {
+              return;
+            };
+          }
+          msg = stdin_connection.read() => {
+            let Ok(msg) = msg else {
+              return;
+            };
+            let Ok(()) = stdin_tx2.send(msg) else {
+              return;
+            };
+          }
+        }
       }
     });
 
@@ -317,7 +317,6 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_fs_chmod_async" => ["change the permissions of a file", "awaiting the result of a `Deno.chmod` call"],
   "op_fs_chown_async" => ["change the owner of a file", "awaiting the result of a `Deno.chown` call"],
   "op_fs_copy_file_async" => ["copy a file", "awaiting the result of a `Deno.copyFile` call"],
-  "op_fs_events_poll" => ["get the next file system event", "breaking out of a for await loop looping over `Deno.FsEvents`"],
   "op_fs_file_sync_data_async" => ["flush pending data operations for a file to disk", "awaiting the result of a `Deno.FsFile.prototype.syncData` call"],
   "op_fs_file_stat_async" => ["get file metadata", "awaiting the result of a `Deno.FsFile.prototype.stat` call"],
   "op_fs_flock_async" => ["lock a file", "awaiting the result of a `Deno.FsFile.lock` call"],
@@ -364,6 +363,7 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_run_status" => ["get the status of a subprocess", "awaiting the result of a `Deno.Process#status` call"],
   "op_signal_poll" => ["get the next signal", "un-registering a OS signal handler"],
   "op_spawn_wait" => ["wait for a subprocess to exit", "awaiting the result of a `Deno.Process#status` call"],
+  "op_stream_next" => ["get the next item of a stream", "breaking out of a for await loop looping over the stream, or closing it"],
   "op_tls_handshake" => ["perform a TLS handshake", "awaiting a `Deno.TlsConn#handshake` call"],
   "op_tls_start" => ["start a TLS connection", "awaiting a `Deno.startTls` call"],
   "op_utime_async" => ["change file timestamps", "awaiting the result of a `Deno.utime` call"],
@@ -876,6 +876,17 @@ async fn run_tests_for_worker_inner(
     .iter()
     .position(|op| *op == "op_host_recv_ctrl")
     .unwrap();
+  // `Worker#terminate()` used to be synchronous, so its result is often not
+  // awaited.
+  let op_id_host_terminate_worker_gracefully = ops
+    .iter()
+    .position(|op| *op == "op_host_terminate_worker_gracefully")
+    .unwrap();
+  // Waits for `localStorage` changes of other contexts for the whole run.
+  let op_id_webstorage_subscribe = ops
+    .iter()
+    .position(|op| *op == "op_webstorage_subscribe")
+    .unwrap();
 
   // For consistency between tests with and without sanitizers, we _always_ include
   // the actual sanitizer capture before and after a test, but a test that ignores resource
@@ -886,6 +897,8 @@ async fn run_tests_for_worker_inner(
   filter = filter.with_timers();
   filter = filter.omit_op(op_id_host_recv_ctrl as _);
   filter = filter.omit_op(op_id_host_recv_message as _);
+  filter = filter.omit_op(op_id_host_terminate_worker_gracefully as _);
+  filter = filter.omit_op(op_id_webstorage_subscribe as _);
 
   // Count the top-level stats so we can filter them out if they complete and restart within
   // a test.
@@ -1009,6 +1022,7 @@ async fn run_tests_for_worker_inner(
       serde_v8::from_v8::<TestResult>(scope, result)?
     };
     if matches!(result, TestResult::Failed(_)) {
+      reset_test_permissions_pool(&state_rc);
       fail_fast_tracker.add_failure();
       let elapsed = earlier.elapsed().as_millis();
       send_test_event(
@@ -1032,6 +1046,9 @@ async fn run_tests_for_worker_inner(
     {
       let (formatted, trailer_notes) = format_sanitizer_diff(diff);
       if !formatted.is_empty() {
+        // The leaked ops or resources may still hold on to the permissions
+        // the test ran with; make sure the next test starts from scratch.
+        reset_test_permissions_pool(&state_rc);
         let failure = TestFailure::Leaked(formatted, trailer_notes);
         fail_fast_tracker.add_failure();
         let elapsed = earlier.elapsed().as_millis();
@@ -1056,6 +1073,15 @@ async fn run_tests_for_worker_inner(
   Ok(())
 }
 
+fn reset_test_permissions_pool(op_state: &RefCell<OpState>) {
+  if let Some(pool) = op_state
+    .borrow_mut()
+    .try_borrow_mut::<ops::testing::TestPermissionsPool>()
+  {
+    pool.clear();
+  }
+}
+
 /// The sanitizer must ignore ops, resources and timers that were started at the top-level, but
 /// completed and restarted, replacing themselves with the same "thing". For example, if you run a
 /// `Deno.serve` server at the top level and make fetch requests to it during the test, those ops
@@ -31,16 +31,110 @@ delete Object.prototype.__proto__;
 
   /** @type {ReadonlySet<string>} */
   const unstableDenoProps = new Set([
+    "AcceptAnyOptions",
+    "AcceptAnyResult",
     "AtomicOperation",
+    "BeforeShutdown",
+    "Channel",
+    "ChannelOptions",
+    "ConfigWatchEvent",
+    "ConfigWatchOptions",
+    "ConfigWatcher",
     "DatagramConn",
+    "DownloadOptions",
+    "DownloadProgress",
+    "DownloadResult",
+    "EnvPolicy",
+    "EnvPolicyDiagnostics",
+    "FsLimitUsage",
+    "GlobMatcher",
+    "GlobMatcherOptions",
+    "HashTreeOptions",
+    "HashTreeResult",
+    "IntegrityManifest",
+    "IntegrityManifestOptions",
+    "IntegrityReport",
+    "IpcChannel",
     "Kv",
     "KvListIterator",
     "KvU64",
+    "LogFile",
+    "LogFileOptions",
+    "MapFileOptions",
+    "MappedFile",
+    "NdjsonBatch",
+    "NdjsonLineError",
+    "NdjsonReader",
+    "NdjsonReaderOptions",
+    "NdjsonWriter",
+    "NdjsonWriterOptions",
+    "PasswordWrapOptions",
+    "ProxyOptions",
+    "ProxyResult",
+    "Readline",
+    "ReadlineOptions",
+    "ReadlineResult",
+    "SaveResponseOptions",
+    "ScheduleOptions",
+    "SearchTextMatch",
+    "SearchTextOptions",
+    "SendFileOptions",
+    "SpawnSelfChildProcess",
+    "SpawnSelfOptions",
+    "SshPrivateKey",
+    "SshPublicKey",
+    "TempDir",
+    "TempDirOptions",
+    "TempFile",
+    "TempFileOptions",
+    "TlsPinningOptions",
+    "TtyEvent",
+    "TtyEvents",
+    "TtyEventsOptions",
+    "UnhandledRejection",
     "UnixConnectOptions",
     "UnixListenOptions",
+    "VerifyIntegrityManifestOptions",
+    "WalkDirEntry",
+    "WalkDirOptions",
+    "WriteFileAtomicOptions",
+    "acceptAny",
+    "addLifecycleHook",
+    "certificateSpkiHash",
+    "createIntegrityManifest",
+    "createReadline",
+    "createTempDir",
+    "createTempFile",
+    "download",
+    "fsLimitUsage",
+    "globMatcher",
+    "hashTree",
     "listen",
     "listenDatagram",
+    "mapFile",
     "openKv",
+    "openLogFile",
+    "openNdjsonReader",
+    "openNdjsonWriter",
+    "openTtyEvents",
+    "parentIpc",
+    "parseSshPrivateKey",
+    "parseSshPublicKey",
+    "path",
+    "proxy",
+    "removeLifecycleHook",
+    "saveResponse",
+    "schedule",
+    "searchText",
+    "sendFile",
+    "spawnSelf",
+    "unwrapWithPassword",
+    "verifyIntegrityManifest",
+    "walkDir",
+    "watchConfig",
+    "wrapWithPassword",
+    "writeFileAtomic",
+    "writeFileAtomicSync",
   ]);
   const unstableMsgSuggestion =
     "If not, try changing the 'lib' compiler option to include 'deno.unstable' " +
@@ -328,6 +328,20 @@ declare namespace Deno {
      * @category Errors */
     export class NotCapable extends Error {}
 
+    /**
+     * Raised when reading a path below an `--allow-read-frozen` root that
+     * existed when the process started but has since been removed.
+     *
+     * @category Errors */
+    export class StaleRead extends Error {}
+
+    /**
+     * Raised when a file operation would exceed a limit configured with the
+     * `DENO_FS_LIMITS` environment variable.
+     *
+     * @category Errors */
+    export class LimitExceeded extends Error {}
+
     export {}; // only export exports
   }
 
@@ -488,6 +502,84 @@ declare namespace Deno {
     swapFree: number;
   }
 
+  /**
+   * Returns the number of logical and physical CPUs in the system, and how
+   * many of them this process can actually use.
+   *
+   * Inside a container, the CPU count of the host overstates the CPUs
+   * available to the process. `effectiveParallelism` accounts for the CPU
+   * affinity mask and, on Linux, for the CPU quota and cpuset of the
+   * process's cgroup, and is what `navigator.hardwareConcurrency` reports.
+   *
+   * ```ts
+   * const { logical, effectiveParallelism } = Deno.systemCpuInfo();
+   * console.log(`using ${effectiveParallelism} of ${logical} CPUs`);
+   * ```
+   *
+   * Requires `allow-sys` permission.
+   *
+   * @tags allow-sys
+   * @category Runtime
+   */
+  export function systemCpuInfo(): SystemCpuInfo;
+
+  /**
+   * Information returned from a call to {@linkcode Deno.systemCpuInfo}.
+   *
+   * @category Runtime
+   */
+  export interface SystemCpuInfo {
+    /** Logical CPUs online in the system. */
+    logical: number;
+    /** Physical cores in the system, or the number of logical CPUs where
+     * they can't be told apart. */
+    physical: number;
+    /** Current frequency of each logical CPU in MHz. Empty where the system
+     * doesn't report it. */
+    frequenciesMhz: number[];
+    /** The number of CPUs this process can keep busy, at least 1. */
+    effectiveParallelism: number;
+  }
+
+  /**
+   * Returns the limits on open file descriptors and how many are in use, so
+   * that a server can monitor its headroom.
+   *
+   * ```ts
+   * const { soft, inUse } = Deno.fdLimits();
+   * if (soft !== null && inUse > soft * 0.9) {
+   *   console.warn(`${inUse} of ${soft} file descriptors in use`);
+   * }
+   * ```
+   *
+   * When the limit is reached, `Deno.open()` and accepting connections retry
+   * for a short while before failing with `EMFILE`. Set `DENO_RESERVE_FD=1` to
+   * have listeners also accept and immediately close pending connections when
+   * that happens, instead of leaving them in the backlog.
+   *
+   * Requires `allow-sys` permission.
+   *
+   * @tags allow-sys
+   * @category Runtime
+   */
+  export function fdLimits(): FdLimits;
+
+  /**
+   * Information returned from a call to {@linkcode Deno.fdLimits}.
+   *
+   * @category Runtime
+   */
+  export interface FdLimits {
+    /** The soft limit, which can be raised up to the hard limit. `null` when
+     * there is no limit, and always on Windows. */
+    soft: number | null;
+    /** The hard limit. `null` when there is no limit, and always on Windows. */
+    hard: number | null;
+    /** Descriptors currently open in the process. Where the system doesn't
+     * list them, only the descriptors behind Deno resources are counted. */
+    inUse: number;
+  }
+
   /** Reflects the `NO_COLOR` environment variable at program start.
    *
    * When the value is `true`, the Deno CLI will attempt to not send color codes
@@ -4043,6 +4135,8 @@ declare namespace Deno {
       | "loadavg"
       | "hostname"
       | "systemMemoryInfo"
+      | "systemCpuInfo"
+      | "fdLimits"
       | "networkInterfaces"
       | "osRelease"
       | "osUptime"
@@ -523,6 +523,20 @@ interface WorkerOptions {
   name?: string;
 }
 
+/** @category Workers */
+interface WorkerTerminateOptions {
+  /** How many milliseconds the worker gets to clean up after the
+   * `"beforeterminate"` event before it is killed. Defaults to 2000. */
+  deadline?: number;
+}
+
+/** @category Workers */
+interface WorkerTerminateResult {
+  /** Whether the worker shut down by itself before the deadline, either
+   * because its event loop was done or because it called `close()`. */
+  graceful: boolean;
+}
+
 /** @category Workers */
 interface Worker extends EventTarget {
   onerror: (this: Worker, e: ErrorEvent) => any | null;
@@ -550,7 +564,10 @@ interface Worker extends EventTarget {
     listener: EventListenerOrEventListenerObject,
     options?: boolean | EventListenerOptions,
   ): void;
-  terminate(): void;
+  /** Terminates the worker. A `"beforeterminate"` event is dispatched in the
+   * worker, after its own workers were terminated, and the worker is killed
+   * if it didn't shut down once `options.deadline` passed. */
+  terminate(options?: WorkerTerminateOptions): Promise<WorkerTerminateResult>;
 }
 
 /** @category Workers */
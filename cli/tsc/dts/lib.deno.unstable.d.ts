@@ -195,6 +195,2307 @@ declare namespace Deno {
     options: UnixListenOptions & { transport: "unixpacket" },
   ): DatagramConn;
 
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Pinning options for TLS client connections, shared by
+   * {@linkcode Deno.connectTls} and {@linkcode Deno.startTls}.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface TlsPinningOptions {
+    /** Base64 encoded SHA-256 hashes of the SubjectPublicKeyInfo of
+     * certificates to pin the connection to, as computed by
+     * {@linkcode Deno.certificateSpkiHash}.
+     *
+     * After the certificate chain is verified as usual, the handshake fails
+     * unless the leaf or an intermediate certificate presented by the server
+     * has one of these public keys. The error lists the hashes the server
+     * presented. Pinned connections never resume a TLS session, so every
+     * handshake is checked. */
+    pins?: string[];
+    /** When `true`, a connection that does not match `pins` is only logged
+     * as a warning instead of failing, to roll out new pins safely.
+     *
+     * @default {false} */
+    pinsReportOnly?: boolean;
+  }
+
+  /**
+   * @category Network
+   * @experimental
+   */
+  export interface ConnectTlsOptions extends TlsPinningOptions {}
+
+  /**
+   * @category Network
+   * @experimental
+   */
+  export interface StartTlsOptions extends TlsPinningOptions {}
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Returns the base64 encoded SHA-256 hash of the SubjectPublicKeyInfo of a
+   * certificate, the value expected by the `pins` option of
+   * {@linkcode Deno.connectTls} and {@linkcode Deno.startTls}. A string is read
+   * as a PEM encoded certificate, of which only the first one is hashed, and
+   * a buffer as a DER encoded certificate.
+   *
+   * ```ts
+   * const cert = await Deno.readTextFile("./server.crt");
+   * const conn = await Deno.connectTls({
+   *   hostname: "example.com",
+   *   port: 443,
+   *   pins: [Deno.certificateSpkiHash(cert)],
+   * });
+   * ```
+   *
+   * @category Network
+   * @experimental
+   */
+  export function certificateSpkiHash(cert: string | Uint8Array): string;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.acceptAny}.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface AcceptAnyOptions {
+    /** Aborting the signal rejects the pending call with the signal's
+     * reason. */
+    signal?: AbortSignal;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A connection accepted by {@linkcode Deno.acceptAny}.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface AcceptAnyResult {
+    /** The accepted connection. */
+    conn: TcpConn;
+    /** The listener that accepted the connection. */
+    listener: TcpListener;
+    /** The index of `listener` in the array passed to `Deno.acceptAny()`. */
+    index: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Waits for a connection on any of the given TCP listeners, so that a
+   * single accept loop can serve several sockets. The listener that is
+   * checked first rotates from call to call, so a busy listener can't starve
+   * the others.
+   *
+   * A listener that is closed while waiting is skipped; the call only rejects
+   * with {@linkcode Deno.errors.BadResource} once all listeners are closed.
+   *
+   * ```ts
+   * const listeners = [
+   *   Deno.listen({ port: 8080 }),
+   *   Deno.listen({ port: 8443 }),
+   * ];
+   * while (true) {
+   *   const { conn, index } = await Deno.acceptAny(listeners);
+   *   console.log("connection on listener", index);
+   *   conn.close();
+   * }
+   * ```
+   *
+   * @category Network
+   * @experimental
+   */
+  export function acceptAny(
+    listeners: TcpListener[],
+    options?: AcceptAnyOptions,
+  ): Promise<AcceptAnyResult>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options which can be set when listening on a Windows named pipe via
+   * {@linkcode Deno.listen}.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface NamedPipeListenOptions {
+    /** The name of the pipe, in the form `\\.\pipe\<name>`. */
+    path: string;
+    /** The maximum number of connections to the pipe at the same time,
+     * between 1 and 254. Clients connecting while all instances are in use
+     * wait for one to be closed.
+     *
+     * @default {unlimited} */
+    maxInstances?: number;
+    /** The security descriptor of the pipe in the Security Descriptor
+     * Definition Language (SDDL), which controls who may connect.
+     *
+     * @default {"D:P(A;;GA;;;OW)"} which only allows the current user. */
+    securityDescriptor?: string;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Specialized listener that accepts Windows named pipe connections.
+   *
+   * @category Network
+   * @experimental
+   */
+  export type NamedPipeListener = Listener<Conn<NamedPipeAddr>, NamedPipeAddr>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Listen on a Windows named pipe. Fails with
+   * {@linkcode Deno.errors.AddrInUse} if another server already created the
+   * pipe, and with {@linkcode Deno.errors.NotSupported} on other platforms.
+   *
+   * ```ts
+   * const listener = Deno.listen({
+   *   path: "\\.\pipe\my-pipe",
+   *   transport: "namedPipe",
+   * });
+   * ```
+   *
+   * Requires `allow-read` and `allow-write` permission.
+   *
+   * @tags allow-read, allow-write
+   * @category Network
+   * @experimental
+   */
+  // deno-lint-ignore adjacent-overload-signatures
+  export function listen(
+    options: NamedPipeListenOptions & { transport: "namedPipe" },
+  ): NamedPipeListener;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface NamedPipeConnectOptions {
+    transport: "namedPipe";
+    /** The name of the pipe, in the form `\\.\pipe\<name>`. */
+    path: string;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Connects to a Windows named pipe. While all instances of the pipe are
+   * connected to other clients, it keeps retrying for up to 30 seconds.
+   * Fails with {@linkcode Deno.errors.NotSupported} on other platforms.
+   *
+   * ```ts
+   * const conn = await Deno.connect({
+   *   path: "\\.\pipe\docker_engine",
+   *   transport: "namedPipe",
+   * });
+   * ```
+   *
+   * Requires `allow-read` and `allow-write` permission.
+   *
+   * @tags allow-read, allow-write
+   * @category Network
+   * @experimental
+   */
+  // deno-lint-ignore adjacent-overload-signatures
+  export function connect(
+    options: NamedPipeConnectOptions,
+  ): Promise<Conn<NamedPipeAddr>>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.proxy}.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface ProxyOptions {
+    /** Reject with {@linkcode Deno.errors.TimedOut} when no data is copied
+     * in either direction for this many milliseconds. */
+    idleTimeoutMs?: number;
+    /** Aborting the signal tears down both directions and rejects with the
+     * signal's reason. */
+    signal?: AbortSignal;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The number of bytes copied by {@linkcode Deno.proxy} in each direction.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface ProxyResult {
+    aToB: number;
+    bToA: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Copies data between two connections in both directions until both
+   * reach EOF, without the data passing through JavaScript.
+   *
+   * When one side closes its write half, the write half of the other side
+   * is closed, and data keeps flowing in the opposite direction until it is
+   * closed too.
+   *
+   * Both connections are taken over by the call and closed when it
+   * completes; they must not be used afterwards.
+   *
+   * ```ts
+   * const listener = Deno.listen({ port: 8080 });
+   * for await (const conn of listener) {
+   *   const upstream = await Deno.connect({ port: 9090 });
+   *   Deno.proxy(conn, upstream, { idleTimeoutMs: 60_000 }).catch(
+   *     console.error,
+   *   );
+   * }
+   * ```
+   *
+   * @category Network
+   * @experimental
+   */
+  export function proxy(
+    a: Conn,
+    b: Conn,
+    options?: ProxyOptions,
+  ): Promise<ProxyResult>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.sendFile}.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface SendFileOptions {
+    /** The position in the file to start sending from.
+     *
+     * @default {0} */
+    offset?: number;
+    /** The number of bytes to send. Sending stops early at the end of the
+     * file. Defaults to the rest of the file. */
+    length?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Writes a window of an open file to a connection without the data
+   * passing through JavaScript, and resolves with the number of bytes sent.
+   *
+   * On Linux and macOS plain TCP connections are served by the kernel with
+   * `sendfile(2)`. Other connections, like TLS connections, fall back to
+   * reading the file in chunks and writing them to the connection.
+   *
+   * The file's seek position is not changed.
+   *
+   * ```ts
+   * using file = await Deno.open("./index.html");
+   * const { size } = await file.stat();
+   * await conn.write(new TextEncoder().encode(
+   *   `HTTP/1.1 200 OK\r\nContent-Length: ${size}\r\n\r\n`,
+   * ));
+   * await Deno.sendFile(conn, file);
+   * ```
+   *
+   * @category Network
+   * @experimental
+   */
+  export function sendFile(
+    conn: Conn,
+    file: FsFile,
+    options?: SendFileOptions,
+  ): Promise<number>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.openLogFile}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface LogFileOptions {
+    /** How often buffered writes are written to the file.
+     *
+     * @default {1000} */
+    flushIntervalMs?: number;
+    /** Buffer size that triggers a write before the interval elapses.
+     *
+     * @default {65536} */
+    maxBufferBytes?: number;
+    /** When data is synced to disk:
+     *
+     * - `"none"`: never. Up to `flushIntervalMs` of data is lost if the
+     *   process crashes.
+     * - `"interval"`: after every flush. At most `flushIntervalMs` of data is
+     *   lost if the process or the machine crashes.
+     * - `"always"`: every {@linkcode Deno.LogFile.write} call is written and
+     *   synced before it returns.
+     *
+     * @default {"none"} */
+    sync?: "none" | "interval" | "always";
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An append-only file that buffers small writes and writes them to disk in
+   * batches. Created with {@linkcode Deno.openLogFile}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class LogFile implements Disposable {
+    /** Append lines to the buffer. Lines are written as given; no newline is
+     * added. Unless `sync` is `"always"`, the writes of a task are handed to
+     * the buffer together at the end of the task. Throws the error of a
+     * previous failed flush or write, if any. */
+    write(...lines: (string | Uint8Array)[]): void;
+    /** Write everything buffered so far. Rejects with the error of a previous
+     * failed flush, if any. */
+    flush(): Promise<void>;
+    /** Close the file, writing what is still buffered. If a flush is in
+     * progress, the remaining lines are written right after it instead.
+     * Errors of that last write are not reported; call
+     * {@linkcode Deno.LogFile.flush} first to observe them. */
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Open (or create) a file for appending log lines with write coalescing.
+   *
+   * ```ts
+   * using log = await Deno.openLogFile("./app.log", { sync: "interval" });
+   * log.write(JSON.stringify({ msg: "started" }) + "\n");
+   * ```
+   *
+   * Requires `allow-write` permission.
+   *
+   * @tags allow-write
+   * @category File System
+   * @experimental
+   */
+  export function openLogFile(
+    path: string | URL,
+    options?: LogFileOptions,
+  ): Promise<LogFile>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.createTempFile}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface TempFileOptions {
+    /** Directory to create the file in. Defaults to the OS temp directory. */
+    dir?: string | URL;
+    /** String to put before the random part of the file name. */
+    prefix?: string;
+    /** String to put after the random part of the file name. */
+    suffix?: string;
+    /** Permissions of the file, masked by the process umask. Ignored on
+     * Windows.
+     *
+     * @default {0o600} */
+    mode?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A file that is removed when it is closed, unless it was persisted first.
+   * Created with {@linkcode Deno.createTempFile}. Files that are still open
+   * when the program exits are removed as well.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class TempFile extends FsFile {
+    /** Path of the file. */
+    get path(): string;
+    /** Move the file to `path`, replacing what is there, and keep it when
+     * closed. The file stays open. Throws if it was persisted before.
+     *
+     * Requires `allow-write` permission. */
+    persist(path: string | URL): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Create a temporary file that is removed when it is closed.
+   *
+   * ```ts
+   * using file = await Deno.createTempFile({ suffix: ".json" });
+   * await file.write(new TextEncoder().encode("{}"));
+   * file.persist("./config.json");
+   * ```
+   *
+   * Requires `allow-write` permission.
+   *
+   * @tags allow-write
+   * @category File System
+   * @experimental
+   */
+  export function createTempFile(options?: TempFileOptions): Promise<TempFile>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.createTempDir}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface TempDirOptions {
+    /** Directory to create the directory in. Defaults to the OS temp
+     * directory. */
+    dir?: string | URL;
+    /** String to put before the random part of the directory name. */
+    prefix?: string;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A directory that is removed, with everything in it, when it is closed,
+   * unless it was persisted first. Created with {@linkcode
+   * Deno.createTempDir}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class TempDir implements Disposable {
+    /** Path of the directory. */
+    get path(): string;
+    /** Move the directory to `path` and keep it when closed. Throws if it
+     * was persisted before.
+     *
+     * Requires `allow-write` permission. */
+    persist(path: string | URL): void;
+    /** Remove the directory, unless it was persisted. */
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Create a temporary directory that is removed when it is closed.
+   *
+   * ```ts
+   * using dir = await Deno.createTempDir({ prefix: "build-" });
+   * await Deno.writeTextFile(`${dir.path}/out.txt`, "done");
+   * ```
+   *
+   * Requires `allow-write` permission.
+   *
+   * @tags allow-write
+   * @category File System
+   * @experimental
+   */
+  export function createTempDir(options?: TempDirOptions): Promise<TempDir>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.writeFileAtomic} and
+   * {@linkcode Deno.writeFileAtomicSync}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface WriteFileAtomicOptions {
+    /** Permissions of the new file. Ignored on Windows. Defaults to the
+     * permissions of the file being replaced, or `0o666` minus the umask for
+     * a new file. */
+    mode?: number;
+    /** Number of previous versions to keep, as `<path>.bak.1` (the most
+     * recent) up to `<path>.bak.<backup>`.
+     *
+     * @default {0} */
+    backup?: number;
+    /** Sync the new contents and the directory entry to disk before
+     * returning, so the write survives a power loss.
+     *
+     * @default {true} */
+    fsync?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Replace the contents of a file atomically. The data is written to a
+   * temporary file next to `path`, which is then renamed over it, so readers
+   * and the file after a crash see either the old or the new contents, never
+   * a partial write. Data can be given as a list of chunks.
+   *
+   * If `path` is a symlink, the link itself is replaced with a regular file.
+   *
+   * ```ts
+   * await Deno.writeFileAtomic("./config.json", JSON.stringify(config), {
+   *   backup: 2,
+   * });
+   * ```
+   *
+   * Requires `allow-write` permission for the directory containing `path`,
+   * where the temporary file and backups are created.
+   *
+   * @tags allow-write
+   * @category File System
+   * @experimental
+   */
+  export function writeFileAtomic(
+    path: string | URL,
+    data: string | Uint8Array | (string | Uint8Array)[],
+    options?: WriteFileAtomicOptions,
+  ): Promise<void>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Synchronously replace the contents of a file atomically. See
+   * {@linkcode Deno.writeFileAtomic}.
+   *
+   * Requires `allow-write` permission for the directory containing `path`.
+   *
+   * @tags allow-write
+   * @category File System
+   * @experimental
+   */
+  export function writeFileAtomicSync(
+    path: string | URL,
+    data: string | Uint8Array | (string | Uint8Array)[],
+    options?: WriteFileAtomicOptions,
+  ): void;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.Channel.send} and
+   * {@linkcode Deno.Channel.recv}.
+   *
+   * @category Workers
+   * @experimental
+   */
+  export interface ChannelOptions {
+    /** Reject with {@linkcode Deno.errors.TimedOut} if the operation hasn't
+     * completed after this many milliseconds. Waits indefinitely by default. */
+    timeoutMs?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A bounded queue shared by all workers of the process. Any number of
+   * workers can send and receive; each value is received exactly once.
+   * Values are copied with the structured clone algorithm.
+   *
+   * Hand the {@linkcode Deno.Channel.token} to another worker, for example
+   * with `postMessage`, and connect to the same channel there with
+   * {@linkcode Deno.Channel.fromToken}.
+   *
+   * ```ts
+   * const jobs = new Deno.Channel(16);
+   * const worker = new Worker(import.meta.resolve("./worker.ts"), {
+   *   type: "module",
+   * });
+   * worker.postMessage(jobs.token);
+   * await jobs.send({ id: 1 });
+   *
+   * // worker.ts
+   * self.onmessage = async (e) => {
+   *   const jobs = Deno.Channel.fromToken(e.data);
+   *   const job = await jobs.recv();
+   * };
+   * ```
+   *
+   * @category Workers
+   * @experimental
+   */
+  export class Channel {
+    /** Create a channel that holds at most `capacity` values. */
+    constructor(capacity: number);
+    /** Connect to an existing channel. */
+    static fromToken(token: string): Channel;
+    /** Identifies the channel across workers. Tokens are random, so only
+     * workers that were handed the token can use the channel. */
+    readonly token: string;
+    /** Queue a value, waiting while the channel is full. Senders waiting for
+     * space are served in the order they called `send`. */
+    send(value: unknown, options?: ChannelOptions): Promise<void>;
+    /** Take the oldest value, waiting while the channel is empty. Receivers
+     * are served in the order they called `recv`. */
+    recv(options?: ChannelOptions): Promise<unknown>;
+    /** Close the channel for all workers. Queued values are dropped, and
+     * pending and later calls reject with {@linkcode Deno.errors.BadResource}.
+     * A channel is also closed when the worker that created it exits.
+     */
+    close(): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.openNdjsonReader}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface NdjsonReaderOptions {
+    /** Maximum number of lines returned per batch.
+     *
+     * @default {1024} */
+    batchSize?: number;
+    /** Longer lines are reported as errors and skipped.
+     *
+     * @default {16777216} */
+    maxLineLength?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A line that couldn't be parsed.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface NdjsonLineError {
+    /** 1-based line number in the input. */
+    line: number;
+    message: string;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Parsed values of consecutive lines, and the lines among them that
+   * couldn't be parsed.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface NdjsonBatch {
+    values: unknown[];
+    errors: NdjsonLineError[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Reads newline-delimited JSON in batches. Created with
+   * {@linkcode Deno.openNdjsonReader}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class NdjsonReader implements AsyncIterable<NdjsonBatch>, Disposable {
+    /** Read the next batch, or `null` at the end of the input. */
+    readBatch(): Promise<NdjsonBatch | null>;
+    /** Iterate over the remaining batches. The reader is closed afterwards. */
+    [Symbol.asyncIterator](): AsyncIterator<NdjsonBatch>;
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Read newline-delimited JSON from a file, an open {@linkcode Deno.FsFile}
+   * or connection, or a byte stream. Lines are parsed in native code and
+   * returned in batches; `\r\n` line endings and blank lines are accepted.
+   *
+   * ```ts
+   * using reader = await Deno.openNdjsonReader("./events.ndjson");
+   * for await (const { values, errors } of reader) {
+   *   for (const { line, message } of errors) {
+   *     console.error(`line ${line}: ${message}`);
+   *   }
+   *   process(values);
+   * }
+   * ```
+   *
+   * Requires `allow-read` permission when given a path.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function openNdjsonReader(
+    source:
+      | string
+      | URL
+      | FsFile
+      | Conn
+      | ReadableStream<Uint8Array>,
+    options?: NdjsonReaderOptions,
+  ): Promise<NdjsonReader>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.openNdjsonWriter}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface NdjsonWriterOptions {
+    /** Compress the output with gzip.
+     *
+     * @default {false} */
+    gzip?: boolean;
+    /** Append to the file instead of truncating it. Only applies to paths.
+     *
+     * @default {false} */
+    append?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Writes values as newline-delimited JSON. Created with
+   * {@linkcode Deno.openNdjsonWriter}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class NdjsonWriter implements AsyncDisposable {
+    /** Serialize `values` with `JSON` semantics, one line each. */
+    write(values: unknown[]): Promise<void>;
+    /** Flush and close the destination. */
+    close(): Promise<void>;
+    [Symbol.asyncDispose](): Promise<void>;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Write newline-delimited JSON to a file or a byte stream.
+   *
+   * ```ts
+   * await using writer = await Deno.openNdjsonWriter("./out.ndjson.gz", {
+   *   gzip: true,
+   * });
+   * await writer.write([{ id: 1 }, { id: 2 }]);
+   * ```
+   *
+   * Requires `allow-write` permission when given a path.
+   *
+   * @tags allow-write
+   * @category File System
+   * @experimental
+   */
+  export function openNdjsonWriter(
+    destination: string | URL | WritableStream<Uint8Array>,
+    options?: NdjsonWriterOptions,
+  ): Promise<NdjsonWriter>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.hashTree}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface HashTreeOptions {
+    /** The hash algorithm of the digest.
+     *
+     * @default {"sha256"} */
+    algorithm?: "sha256" | "blake3";
+    /** Only hash files whose path relative to the root, with `/` separators,
+     * matches one of these glob patterns. All files are hashed if empty. */
+    include?: string[];
+    /** Skip files and directories whose path relative to the root, with `/`
+     * separators, matches one of these glob patterns. A matching directory
+     * is skipped with everything below it. */
+    exclude?: string[];
+    /** Hash the targets of symlinks instead of the symlinks themselves.
+     *
+     * @default {false} */
+    followSymlinks?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The result of {@linkcode Deno.hashTree}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface HashTreeResult {
+    /** Hex encoded digest of the tree. */
+    digest: string;
+    /** The number of files that were hashed. */
+    files: number;
+    /** The total size of the files that were hashed. */
+    bytes: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Hash the contents of a directory tree, e.g. to derive a cache key or to
+   * find out if anything changed since the last build.
+   *
+   * Files are hashed in a fixed order together with their path relative to
+   * `path`, so the digest is the same for identical trees at different
+   * locations and on different platforms. It changes when a file is added,
+   * removed, renamed or modified. Empty directories are not part of the
+   * digest.
+   *
+   * ```ts
+   * const { digest } = await Deno.hashTree("./src", {
+   *   exclude: ["node_modules", "dist"],
+   * });
+   * ```
+   *
+   * Rejects with {@linkcode Deno.errors.FilesystemLoop} when following a
+   * symlink that points to a directory containing it, and with
+   * {@linkcode Deno.errors.Interrupted} when the process receives `SIGINT`
+   * while a `SIGINT` listener is installed.
+   *
+   * Requires `allow-read` permission.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function hashTree(
+    path: string | URL,
+    options?: HashTreeOptions,
+  ): Promise<HashTreeResult>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.createIntegrityManifest}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface IntegrityManifestOptions {
+    /** The hash algorithm of the integrity strings.
+     *
+     * @default {"sha256"} */
+    algorithm?: "sha256" | "sha384" | "sha512";
+    /** Only list files whose path relative to the root, with `/` separators,
+     * matches one of these glob patterns. All files are listed if empty. */
+    include?: string[];
+    /** Skip files and directories whose path relative to the root, with `/`
+     * separators, matches one of these glob patterns. A matching directory
+     * is skipped with everything below it. */
+    exclude?: string[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A manifest created by {@linkcode Deno.createIntegrityManifest}. It can be
+   * stored with `JSON.stringify()` and checked later with
+   * {@linkcode Deno.verifyIntegrityManifest}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface IntegrityManifest {
+    /** The version of the manifest format, currently `1`. */
+    version: number;
+    algorithm: "sha256" | "sha384" | "sha512";
+    include: string[];
+    exclude: string[];
+    /** The subresource integrity string (e.g. `sha256-<base64>`) of every
+     * file, keyed by its path relative to the root with `/` separators. */
+    files: Record<string, string>;
+    /** The integrity string of all entries of `files`, in order. */
+    digest: string;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.verifyIntegrityManifest}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface VerifyIntegrityManifestOptions {
+    /** Stop at the first file that doesn't match the manifest, and only
+     * report that one.
+     *
+     * @default {false} */
+    failFast?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The result of {@linkcode Deno.verifyIntegrityManifest}. All lists are
+   * sorted.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface IntegrityReport {
+    /** Whether the tree matches the manifest. */
+    ok: boolean;
+    /** Files in the manifest that don't exist. */
+    missing: string[];
+    /** Files that aren't in the manifest. */
+    extra: string[];
+    /** Files whose contents differ from the manifest. */
+    modified: string[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Create an integrity manifest of a directory tree, listing the
+   * subresource integrity string of every file, e.g. to detect tampering
+   * with assets shipped next to a program.
+   *
+   * Symlinks are not followed, they are recorded with the hash of their
+   * target path.
+   *
+   * ```ts
+   * const manifest = await Deno.createIntegrityManifest("./assets", {
+   *   exclude: ["**\/*.map"],
+   * });
+   * await Deno.writeTextFile("assets.json", JSON.stringify(manifest));
+   * ```
+   *
+   * Rejects with {@linkcode Deno.errors.Interrupted} when the process
+   * receives `SIGINT` while a `SIGINT` listener is installed.
+   *
+   * Requires `allow-read` permission.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function createIntegrityManifest(
+    path: string | URL,
+    options?: IntegrityManifestOptions,
+  ): Promise<IntegrityManifest>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Check a directory tree against a manifest created by
+   * {@linkcode Deno.createIntegrityManifest}, with the same include and
+   * exclude patterns.
+   *
+   * ```ts
+   * const manifest = JSON.parse(await Deno.readTextFile("assets.json"));
+   * const report = await Deno.verifyIntegrityManifest("./assets", manifest);
+   * if (!report.ok) {
+   *   console.error("Modified assets:", report.modified);
+   * }
+   * ```
+   *
+   * Rejects with a `TypeError` when the manifest has an unsupported version
+   * or its `digest` doesn't match its `files`, and with
+   * {@linkcode Deno.errors.Interrupted} when the process receives `SIGINT`
+   * while a `SIGINT` listener is installed.
+   *
+   * Requires `allow-read` permission.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function verifyIntegrityManifest(
+    path: string | URL,
+    manifest: IntegrityManifest,
+    options?: VerifyIntegrityManifestOptions,
+  ): Promise<IntegrityReport>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A progress event of {@linkcode Deno.copyDir} or of a recursive
+   * {@linkcode Deno.remove}. Events are sent after every few entries; when
+   * the callback falls behind, intermediate events are dropped.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface FsProgress {
+    /** The number of entries copied or removed so far. */
+    processed: number;
+    /** The number of entries of the whole tree, including its root. */
+    total: number;
+    /** The entry that was processed last. */
+    currentPath: string;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options of a recursive {@linkcode Deno.remove} that reports progress
+   * or can be canceled. Requires `--unstable-fs` when either is set.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface RemoveOptions {
+    /** Stops the removal after the entries being removed. The promise then
+     * rejects with the abort reason, and the last progress event tells how
+     * many entries were removed. */
+    signal?: AbortSignal;
+    /** Called with the progress of the removal. */
+    onProgress?: (progress: FsProgress) => void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.copyDir}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface CopyDirOptions {
+    /** Whether symlinks are copied as symlinks with the same target, or
+     * replaced by copies of the files and directories they point to.
+     *
+     * @default {"preserve"} */
+    symlinks?: "preserve" | "follow";
+    /** Stops the copy after the entries being copied. The promise then
+     * resolves with `canceled` set, and the destination contains what was
+     * copied so far. */
+    signal?: AbortSignal;
+    /** Called with the progress of the copy. */
+    onProgress?: (progress: FsProgress) => void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The result of {@linkcode Deno.copyDir}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface CopyDirResult {
+    /** The number of directories created, including the destination. */
+    dirs: number;
+    /** The number of files copied. */
+    files: number;
+    /** The number of symlinks created. */
+    symlinks: number;
+    /** The total size of the files that were copied. */
+    bytes: number;
+    /** Whether the copy was stopped by the `signal` before it was done. */
+    canceled: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Recursively copy the directory `from` to `to`. Directories that don't
+   * exist in `to` are created and existing files are overwritten.
+   *
+   * ```ts
+   * const controller = new AbortController();
+   * const result = await Deno.copyDir("./assets", "./dist/assets", {
+   *   signal: controller.signal,
+   *   onProgress: ({ processed, total }) => console.log(processed, total),
+   * });
+   * ```
+   *
+   * Rejects with {@linkcode Deno.errors.Interrupted} when the process
+   * receives `SIGINT` while a `SIGINT` listener is installed.
+   *
+   * Requires `allow-read` permission for `from`, and for the targets of
+   * followed symlinks, and `allow-write` permission for `to`.
+   *
+   * @tags allow-read, allow-write
+   * @category File System
+   * @experimental
+   */
+  export function copyDir(
+    from: string | URL,
+    to: string | URL,
+    options?: CopyDirOptions,
+  ): Promise<CopyDirResult>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.globMatcher}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface GlobMatcherOptions {
+    /** Match letters case sensitively, on every platform.
+     *
+     * @default {true} */
+    caseSensitive?: boolean;
+    /** Expand `{a,b}` alternatives, which may nest.
+     *
+     * @default {false} */
+    extended?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Glob patterns compiled by {@linkcode Deno.globMatcher}. It can also be
+   * passed as `include` or `exclude` to {@linkcode Deno.walkDir} and
+   * {@linkcode Deno.searchText}, so the patterns aren't compiled again.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class GlobMatcher implements Disposable {
+    /** Whether `path`, or one of its parent directories, matches one of the
+     * patterns. */
+    match(path: string): boolean;
+    /** Returns the paths that {@linkcode Deno.GlobMatcher.match} matches, in
+     * order. Faster than calling it for every path. */
+    filter(paths: string[]): string[];
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Compile glob patterns with exactly the semantics of the `include` and
+   * `exclude` options of the config file, which {@linkcode Deno.walkDir}
+   * uses too:
+   *
+   * - Paths are relative to the directory the patterns are for, with `/`
+   *   separators. On Windows, `\` is a separator too.
+   * - `*` and `?` never match a `/`. `**` as a whole path component
+   *   matches any number of directories, including none.
+   * - Wildcards match a leading dot, so `*` matches `.env`.
+   * - A leading `./` and a trailing `/` of a pattern are ignored.
+   * - A pattern that matches a directory matches everything below it.
+   * - Special characters are escaped with brackets: `[*]` matches a literal
+   *   `*`, and `[!a]` matches any character but `a`.
+   * - `{a,b}` is only expanded with `extended`. Braces and commas inside
+   *   brackets are literal.
+   *
+   * ```ts
+   * using matcher = Deno.globMatcher(["src", "**\/*.md"]);
+   * matcher.match("src/main.ts"); // true
+   * matcher.filter(["README.md", "deno.json"]); // ["README.md"]
+   * ```
+   *
+   * @category File System
+   * @experimental
+   */
+  export function globMatcher(
+    patterns: string[],
+    options?: GlobMatcherOptions,
+  ): GlobMatcher;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.mapFile}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface MapFileOptions {
+    /** Hold a shared lock on the file while it is mapped, and throw
+     * {@linkcode Deno.errors.Busy} if another process holds an exclusive
+     * lock. The lock is advisory, it only keeps out writers that lock the
+     * file with {@linkcode Deno.FsFile.lock} before truncating it.
+     *
+     * @default {false} */
+    lock?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A file mapped into memory by {@linkcode Deno.mapFile}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export class MappedFile implements Disposable {
+    /** The contents of the file. Writes to it are private to the process
+     * and never reach the file. The buffer is detached when the mapping is
+     * closed. */
+    readonly buffer: ArrayBuffer;
+    /** Detaches {@linkcode MappedFile.buffer} and unmaps the file, once ops
+     * that are still using the buffer are done with it. */
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Maps a file into memory read-only, so that large files can be indexed
+   * without reading them into memory first. Pages are loaded from the file
+   * as they are accessed. The mapping is released when the file is closed,
+   * or when the buffer is garbage collected.
+   *
+   * The file must not be truncated while it is mapped: on Unix, accessing a
+   * page past the new end of the file kills the process with `SIGBUS`. Use
+   * the `lock` option when writers cooperate with locks. Files on network
+   * filesystems are refused on Linux, and files that can't be mapped, like
+   * directories, throw {@linkcode Deno.errors.NotSupported}.
+   *
+   * ```ts
+   * using file = Deno.mapFile("./dataset.bin", { lock: true });
+   * const header = new DataView(file.buffer, 0, 16);
+   * ```
+   *
+   * Requires `allow-read` permission when given a path.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function mapFile(
+    path: string | URL | FsFile,
+    options?: MapFileOptions,
+  ): MappedFile;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Usage of a scope configured with the `DENO_FS_LIMITS` environment
+   * variable, as returned by {@linkcode Deno.fsLimitUsage}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface FsLimitUsage {
+    /** The canonical path of the scope. */
+    path: string;
+    /** Files below the scope that are currently open. */
+    openFiles: number;
+    maxOpenFiles: number | null;
+    /** Bytes read from files below the scope since the process started. */
+    bytesRead: number;
+    maxBytesRead: number | null;
+    /** Whether reads stop at `maxBytesRead`, instead of only being counted. */
+    hard: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Returns the usage of every scope configured with the `DENO_FS_LIMITS`
+   * environment variable, or an empty array if it isn't set.
+   *
+   * `DENO_FS_LIMITS` is a JSON array of scopes, each capping the files that
+   * may be open below a path at once, and optionally the bytes read from
+   * them. Exceeding a limit throws {@linkcode Deno.errors.LimitExceeded}.
+   * Files opened with {@linkcode Deno.open} and whole-file reads like
+   * {@linkcode Deno.readFile} are counted, counters are shared by all
+   * workers.
+   *
+   * ```sh
+   * DENO_FS_LIMITS='[{ "path": "./data", "maxOpenFiles": 16, "maxBytesRead": 1048576, "hard": true }]' \
+   *   deno run --unstable-fs --allow-read=./data main.ts
+   * ```
+   *
+   * ```ts
+   * for (const { path, openFiles, bytesRead } of Deno.fsLimitUsage()) {
+   *   console.log(path, openFiles, bytesRead);
+   * }
+   * ```
+   *
+   * @category File System
+   * @experimental
+   */
+  export function fsLimitUsage(): FsLimitUsage[];
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.walkDir}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface WalkDirOptions {
+    /** Only yield entries whose path relative to the root, with `/`
+     * separators, matches one of these glob patterns. A pattern that matches
+     * a directory matches everything below it, like the `include` option of
+     * the config file. All entries are yielded if empty. */
+    include?: string[] | GlobMatcher;
+    /** Skip entries whose path relative to the root, with `/` separators,
+     * matches one of these glob patterns. A matching directory is skipped
+     * with everything below it without being read. */
+    exclude?: string[] | GlobMatcher;
+    /** How many levels below the root to walk. Entries directly in the root
+     * are at depth 1. Unlimited if not set. */
+    maxDepth?: number;
+    /** Walk into symlinks that point to directories inside the root, and
+     * report the type, size and modification time of the targets of
+     * symlinks. Symlinks pointing outside of the root, and symlinks pointing
+     * to a directory that contains them, are reported but not followed.
+     *
+     * @default {false} */
+    followSymlinks?: boolean;
+    /** Only yield entries of these types. All types are yielded if not set. */
+    types?: ("file" | "dir" | "symlink")[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An entry yielded by {@linkcode Deno.walkDir}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface WalkDirEntry {
+    /** The absolute path of the entry. */
+    path: string;
+    isFile: boolean;
+    isDir: boolean;
+    isSymlink: boolean;
+    /** The size of the entry in bytes. */
+    size: number;
+    /** The last modification time of the entry, if available. */
+    mtime: Date | null;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Recursively walk the directory tree at `path`.
+   *
+   * The directories are read in parallel on background threads and the
+   * matching entries are handed to JS in batches, which is considerably
+   * faster than walking a large tree with {@linkcode Deno.readDir}. Entries
+   * are yielded in no particular order, and the first ones are available
+   * before the walk is done. The root itself is not yielded.
+   *
+   * ```ts
+   * for await (
+   *   const entry of Deno.walkDir(".", {
+   *     include: ["src"],
+   *     exclude: ["**\/*_test.ts"],
+   *     types: ["file"],
+   *   })
+   * ) {
+   *   console.log(entry.path);
+   * }
+   * ```
+   *
+   * Breaking out of the loop stops the walk.
+   *
+   * Requires `allow-read` permission.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function walkDir(
+    path: string | URL,
+    options?: WalkDirOptions,
+  ): AsyncIterable<WalkDirEntry>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.searchText}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface SearchTextOptions {
+    /** Ignore case when matching.
+     *
+     * @default {false} */
+    caseInsensitive?: boolean;
+    /** Let matches span lines, and report every match instead of only the
+     * first one of each line. The whole file or stream is read into memory
+     * before it is searched.
+     *
+     * @default {false} */
+    multiline?: boolean;
+    /** Search for the pattern literally instead of as a regular expression.
+     *
+     * @default {false} */
+    fixedString?: boolean;
+    /** Stop after this many matches. Unlimited if not set. */
+    maxMatches?: number;
+    /** How many lines before and after each match to report. */
+    context?: { before?: number; after?: number };
+    /** Only search files whose path relative to a searched directory
+     * matches one of these glob patterns, like
+     * {@linkcode WalkDirOptions.include}. */
+    include?: string[] | GlobMatcher;
+    /** Skip files whose path relative to a searched directory matches one
+     * of these glob patterns, like {@linkcode WalkDirOptions.exclude}. */
+    exclude?: string[] | GlobMatcher;
+    /** Also search files that contain a NUL byte in their first 8 KiB.
+     * These are considered binary and skipped by default.
+     *
+     * @default {false} */
+    binary?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A match yielded by {@linkcode Deno.searchText}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface SearchTextMatch {
+    /** The path of the file, or `null` when searching a stream. */
+    path: string | null;
+    /** The 1-based number of the line the match starts on. */
+    lineNumber: number;
+    /** Where the match starts, in bytes from the start of the file or
+     * stream. */
+    byteOffset: number;
+    /** The line of the match, or all lines it spans, without the final line
+     * break. */
+    lineText: string;
+    /** The lines around the match, as requested with
+     * {@linkcode SearchTextOptions.context}. */
+    contextLines: { before: string[]; after: string[] };
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Search files or a stream for a regular expression, like `ripgrep`.
+   *
+   * `source` is a file or directory, a list of them, or a readable stream
+   * or file to search. Directories are walked like with
+   * {@linkcode Deno.walkDir} and their files are searched in parallel on
+   * background threads, so matches from different files are yielded in no
+   * particular order. Matches within a file are yielded in order.
+   *
+   * ```ts
+   * for await (
+   *   const match of Deno.searchText(["src"], String.raw`\bTODO\b`, {
+   *     include: ["**\/*.ts"],
+   *     context: { after: 1 },
+   *   })
+   * ) {
+   *   console.log(`${match.path}:${match.lineNumber}: ${match.lineText}`);
+   * }
+   * ```
+   *
+   * Breaking out of the loop stops the search.
+   *
+   * Requires `allow-read` permission for the searched paths.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function searchText(
+    source:
+      | string
+      | URL
+      | (string | URL)[]
+      | ReadableStream<Uint8Array>
+      | FsFile,
+    pattern: string,
+    options?: SearchTextOptions,
+  ): AsyncIterable<SearchTextMatch>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.openTtyEvents}.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export interface TtyEventsOptions {
+    /** Report mouse presses, releases, movement and scrolling.
+     *
+     * @default {false} */
+    mouse?: boolean;
+    /** Report pasted text as a single `"paste"` event instead of as typed
+     * keys. Not supported on Windows.
+     *
+     * @default {false} */
+    bracketedPaste?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An event read from the terminal by {@linkcode Deno.openTtyEvents}.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export interface TtyEvent {
+    kind: "key" | "mouse" | "paste" | "resize";
+    /** For key events, the key, named like `KeyboardEvent.key`, e.g. `"a"`,
+     * `"Enter"` or `"ArrowUp"`. For mouse events, the button: `"left"`,
+     * `"middle"`, `"right"` or `"wheelUp"`, `"wheelDown"`, `"wheelLeft"`,
+     * `"wheelRight"`, or `null` if no button is involved. */
+    key: string | null;
+    /** The modifier keys held down. */
+    modifiers: { shift: boolean; alt: boolean; ctrl: boolean; meta: boolean };
+    /** The 0-based column of a mouse event, or the number of columns of the
+     * terminal after a resize. */
+    x: number | null;
+    /** The 0-based row of a mouse event, or the number of rows of the
+     * terminal after a resize. */
+    y: number | null;
+    /** The text typed by a key event, or the pasted text. */
+    text: string | null;
+    /** What the mouse did. */
+    action: "press" | "release" | "drag" | "move" | "scroll" | null;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The events of the terminal, as returned by
+   * {@linkcode Deno.openTtyEvents}.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export interface TtyEvents extends AsyncIterable<TtyEvent>, Disposable {
+    /** Turn mouse reporting on or off. */
+    setMouseReporting(enabled: boolean): void;
+    /** Turn bracketed paste on or off. Not supported on Windows. */
+    setBracketedPaste(enabled: boolean): void;
+    /** Stop reading events, and turn off the modes turned on through this
+     * object. */
+    close(): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Read key, mouse, paste and resize events from the terminal attached to
+   * stdin. Escape sequences are decoded, so arrow keys, function keys and
+   * modifier combinations arrive as named keys.
+   *
+   * Put stdin in raw mode first, or the terminal will buffer input by line
+   * and handle keys like Ctrl+C itself.
+   *
+   * ```ts
+   * Deno.stdin.setRaw(true);
+   * using events = Deno.openTtyEvents({ mouse: true });
+   * for await (const event of events) {
+   *   if (event.kind === "key" && event.key === "q") break;
+   *   console.log(event);
+   * }
+   * ```
+   *
+   * Mouse reporting and bracketed paste are turned off again when the
+   * events are closed or the process exits.
+   *
+   * Throws a `Deno.errors.BadResource` error if stdin is not a terminal.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export function openTtyEvents(options?: TtyEventsOptions): TtyEvents;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.createReadline}.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export interface ReadlineOptions {
+    /** Written before each line. It may contain color escape sequences.
+     *
+     * @default {""} */
+    prompt?: string;
+    /** A file to load the history from, which is written back when the
+     * readline is closed. Requires read and write permission for the file. */
+    historyPath?: string | URL;
+    /** The number of lines kept in the history. `0` turns off the history.
+     *
+     * @default {1000} */
+    maxHistory?: number;
+    /** Called when Tab is pressed, with the line and the cursor position.
+     * Returns the candidates for the word before the cursor: a single one
+     * replaces the word, several are inserted as far as they agree or listed
+     * below the line. */
+    complete?(
+      line: string,
+      cursor: number,
+    ): Iterable<string> | Promise<Iterable<string>>;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The result of {@linkcode Deno.Readline.read}: an entered line, Ctrl+C,
+   * or the end of the input, which is Ctrl+D on an empty line.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export type ReadlineResult =
+    | { kind: "line"; line: string }
+    | { kind: "interrupt" }
+    | { kind: "eof" };
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A line editor, as returned by {@linkcode Deno.createReadline}.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export interface Readline extends Disposable {
+    /** Read a line. The terminal is in raw mode until the line is entered,
+     * and the other input of stdin should not be read meanwhile.
+     *
+     * Rejects with the reason of `signal` if it is aborted, leaving the line
+     * unfinished. */
+    read(options?: { signal?: AbortSignal }): Promise<ReadlineResult>;
+    /** Stop reading, and write the history. A pending `read()` resolves with
+     * `{ kind: "eof" }`. */
+    close(): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Create a line editor reading from the terminal attached to stdin, for
+   * building REPL-like programs. It supports the usual editing keys, such as
+   * the arrow keys, Home, End, Ctrl+A, Ctrl+E, Ctrl+U, Ctrl+K and Ctrl+W,
+   * browsing the history with the up and down arrows, and completion with
+   * Tab.
+   *
+   * ```ts
+   * using rl = Deno.createReadline({
+   *   prompt: "> ",
+   *   historyPath: ".repl_history",
+   *   complete: (line, cursor) =>
+   *     ["help", "quit"].filter((c) => c.startsWith(line.slice(0, cursor))),
+   * });
+   * while (true) {
+   *   const result = await rl.read();
+   *   if (result.kind === "eof") break;
+   *   if (result.kind === "line") console.log(`You typed ${result.line}`);
+   * }
+   * ```
+   *
+   * Throws a `Deno.errors.BadResource` error if stdin is not a terminal.
+   *
+   * @category I/O
+   * @experimental
+   */
+  export function createReadline(options?: ReadlineOptions): Readline;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.watchConfig}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface ConfigWatchOptions {
+    /** How long to wait after a change for further changes before the file
+     * is read again, in milliseconds.
+     *
+     * @default {100} */
+    debounceMs?: number;
+    /** How the contents of the file are turned into a value. `"raw"` yields
+     * the bytes of the file as they are.
+     *
+     * @default {"json"} */
+    parser?: "json" | "toml" | "raw";
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An event yielded by a {@linkcode Deno.ConfigWatcher}.
+   *
+   * The first event is `"initial"`, or `"error"` when the file can't be read
+   * or parsed. When the file can't be read or parsed after a change,
+   * `previous` holds the last value that could, so the caller can keep using
+   * it.
+   *
+   * @category File System
+   * @experimental
+   */
+  export type ConfigWatchEvent<T = unknown> =
+    | { kind: "initial"; value: T }
+    | { kind: "update"; value: T }
+    | { kind: "error"; error: Error; previous: T | undefined };
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Returned by {@linkcode Deno.watchConfig}.
+   *
+   * @category File System
+   * @experimental
+   */
+  export interface ConfigWatcher<T = unknown>
+    extends AsyncIterable<ConfigWatchEvent<T>>, Disposable {
+    /** Stops watching the file. */
+    close(): void;
+    /** Stops watching the file. */
+    return?(value?: any): Promise<IteratorResult<ConfigWatchEvent<T>>>;
+    [Symbol.asyncIterator](): AsyncIterableIterator<ConfigWatchEvent<T>>;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Watch the file at `path` and yield its parsed contents, first when the
+   * watcher is created and then each time the file changes.
+   *
+   * Changes are debounced, and a change that leaves the contents as they
+   * were yields no event. The file may be replaced by renaming another file
+   * over it, as many editors do when saving, and may be missing for a while.
+   *
+   * ```ts
+   * let config = {};
+   * for await (const event of Deno.watchConfig("./config.json")) {
+   *   if (event.kind === "error") {
+   *     console.error(event.error);
+   *   } else {
+   *     config = event.value;
+   *   }
+   * }
+   * ```
+   *
+   * Requires `allow-read` permission.
+   *
+   * @tags allow-read
+   * @category File System
+   * @experimental
+   */
+  export function watchConfig<T = unknown>(
+    path: string | URL,
+    options?: ConfigWatchOptions,
+  ): ConfigWatcher<T>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Conversions between path representations, in particular for Windows
+   * paths with UNC and verbatim (`\\?\`) prefixes.
+   *
+   * @category File System
+   * @experimental
+   */
+  export namespace path {
+    /** **UNSTABLE**: New API, yet to be vetted.
+     *
+     * Resolves `path` to an absolute path with all symlinks resolved, like
+     * {@linkcode Deno.realPath}. Inside a binary created with `deno
+     * compile`, paths of embedded files are resolved in the embedded file
+     * system.
+     *
+     * With `longPath`, the result is returned in verbatim form on Windows
+     * (e.g. `\\?\C:\foo`), which can be used with paths longer than 260
+     * characters. It is ignored on other platforms.
+     *
+     * Requires `allow-read` permission.
+     *
+     * @tags allow-read
+     * @category File System
+     * @experimental
+     */
+    export function realPath(
+      path: string | URL,
+      options?: { longPath?: boolean },
+    ): Promise<string>;
+
+    /** **UNSTABLE**: New API, yet to be vetted.
+     *
+     * Returns the relative path from `from` to `to`, with the separator of
+     * the platform. Relative paths are resolved against the current working
+     * directory. Paths are compared case-insensitively on Windows. Returns
+     * `to` if it is on a different drive or share, and an empty string if
+     * both paths are the same.
+     *
+     * ```ts
+     * Deno.path.relative("/a/b/c", "/a/d"); // "../../d"
+     * ```
+     *
+     * Requires `allow-read` permission for the current working directory if
+     * either path is relative.
+     *
+     * @category File System
+     * @experimental
+     */
+    export function relative(from: string | URL, to: string | URL): string;
+
+    /** **UNSTABLE**: New API, yet to be vetted.
+     *
+     * Normalizes a Windows path on any platform: `/` separators become `\`,
+     * `.` and `..` segments are resolved and trailing dots and spaces are
+     * removed from segments.
+     *
+     * With `longPath`, the path is returned in verbatim form, e.g.
+     * `\\?\C:\foo` or `\\?\UNC\server\share\foo`, and it throws a
+     * `TypeError` if the path is not absolute. Otherwise the verbatim prefix
+     * of drive and UNC paths is removed. Verbatim paths are not normalized
+     * otherwise, because Windows doesn't normalize them either.
+     *
+     * ```ts
+     * Deno.path.normalizeWindows("C:/foo/../bar", { longPath: true });
+     * // "\\\\?\\C:\\bar"
+     * ```
+     *
+     * @category File System
+     * @experimental
+     */
+    export function normalizeWindows(
+      path: string,
+      options?: { longPath?: boolean },
+    ): string;
+
+    /** **UNSTABLE**: New API, yet to be vetted.
+     *
+     * Returns whether `path` is a UNC path, like `\\server\share\foo` or
+     * `\\?\UNC\server\share\foo`. Device paths like `\\.\pipe\foo` are not.
+     *
+     * @category File System
+     * @experimental
+     */
+    export function isUNC(path: string): boolean;
+
+    /** **UNSTABLE**: New API, yet to be vetted.
+     *
+     * Returns whether `path` has a verbatim (`\\?\`) prefix.
+     *
+     * @category File System
+     * @experimental
+     */
+    export function isVerbatim(path: string): boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Progress of a {@linkcode Deno.download}.
+   *
+   * @category Fetch
+   * @experimental
+   */
+  export interface DownloadProgress {
+    /** Bytes of the destination file written so far, including bytes kept
+     * from an earlier download when resuming. */
+    received: number;
+    /** Size of the whole file, or `null` if the server didn't announce it. */
+    total: number | null;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.download}.
+   *
+   * @category Fetch
+   * @experimental
+   */
+  export interface DownloadOptions {
+    /** Continue a partial file at the destination with a range request,
+     * instead of replacing it. If the server doesn't support ranges, the
+     * file is downloaded from the start.
+     *
+     * @default {false} */
+    resume?: boolean;
+    /** Hex encoded sha256 digest of the complete file. If the downloaded
+     * file doesn't match, it is deleted and the download rejects with
+     * {@linkcode Deno.errors.InvalidData}. */
+    sha256?: string;
+    /** Additional request headers. `Range`, `If-Range`, `Accept-Encoding`,
+     * `Content-Length` and `Host` are set by the download. */
+    headers?: HeadersInit;
+    /** Retry failed attempts with exponential backoff. Include
+     * `"interrupted"` in `retryOn` to continue a download whose connection
+     * was closed before it completed. */
+    retry?: RetryOptions;
+    /** The maximum number of redirects to follow.
+     *
+     * @default {20} */
+    maxRedirects?: number;
+    /** Called as the download progresses. When calls fall behind, some
+     * intermediate updates are skipped. */
+    onProgress?: (progress: DownloadProgress) => void;
+    /** Aborts the download, including any pending backoff. The partial file
+     * is kept, so the download can be resumed. */
+    signal?: AbortSignal;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The result of {@linkcode Deno.download}.
+   *
+   * @category Fetch
+   * @experimental
+   */
+  export interface DownloadResult {
+    /** The URL the file was downloaded from, after redirects. */
+    url: string;
+    /** The size of the file. */
+    size: number;
+    /** Bytes of an existing partial file that were kept when resuming. */
+    resumedFrom: number;
+    /** Hex encoded sha256 digest of the file. */
+    sha256: string;
+    /** The number of attempts, including the first one. */
+    attempts: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Download a URL to a file. The response is written to the file as it
+   * arrives, without going through JavaScript.
+   *
+   * ```ts
+   * await Deno.download("https://example.com/model.bin", "./model.bin", {
+   *   resume: true,
+   *   sha256: "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae",
+   *   retry: {
+   *     maxAttempts: 5,
+   *     baseDelayMs: 500,
+   *     maxDelayMs: 10_000,
+   *     retryOn: ["refused", "timedOut", "interrupted"],
+   *   },
+   *   onProgress: ({ received, total }) => console.log(received, total),
+   * });
+   * ```
+   *
+   * A failed download keeps the partial file, so it can be resumed with
+   * `resume: true`. Rejects with {@linkcode Deno.errors.Http} for error
+   * responses, too many redirects and responses whose length headers
+   * disagree, and with {@linkcode Deno.errors.UnexpectedEof} when the
+   * connection is closed before the download completed.
+   *
+   * Requires `allow-net` permission for the URL and every redirect target,
+   * and `allow-write` permission for the destination.
+   *
+   * @tags allow-net, allow-write
+   * @category Fetch
+   * @experimental
+   */
+  export function download(
+    url: string | URL,
+    path: string | URL,
+    options?: DownloadOptions,
+  ): Promise<DownloadResult>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.saveResponse}.
+   *
+   * @category Fetch
+   * @experimental
+   */
+  export interface SaveResponseOptions {
+    /** Append the body to the file instead of replacing its contents.
+     *
+     * @default {false} */
+    append?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Write the body of `response` to a file and resolve with the number of
+   * bytes written. The body of a `fetch` response that wasn't read from yet
+   * is written as it arrives, without going through JavaScript. Like
+   * `response.body`, the file receives the body after its
+   * `Content-Encoding` was decoded.
+   *
+   * ```ts
+   * const response = await fetch("https://example.com/model.bin");
+   * const size = await Deno.saveResponse(response, "./model.bin");
+   * ```
+   *
+   * The body is consumed, so reading it afterwards throws a `TypeError`, as
+   * does passing a response whose body was already consumed. When reading
+   * the body fails midway, the bytes received so far are kept in the file
+   * and the promise rejects with a `TypeError` whose message includes their
+   * count.
+   *
+   * Requires `allow-write` permission for the destination.
+   *
+   * @tags allow-write
+   * @category Fetch
+   * @experimental
+   */
+  export function saveResponse(
+    response: Response,
+    path: string | URL,
+    options?: SaveResponseOptions,
+  ): Promise<number>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.spawnSelf}.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export interface SpawnSelfOptions {
+    /** The working directory of the process. Defaults to the current
+     * working directory. */
+    cwd?: string | URL;
+    /** Clear environmental variables from parent process.
+     *
+     * @default {false} */
+    clearEnv?: boolean;
+    /** Environmental variables to pass to the subprocess. */
+    env?: Record<string, string>;
+    /** An {@linkcode AbortSignal} that allows closing the process using the
+     * corresponding {@linkcode AbortController} by sending the process a
+     * SIGTERM signal. */
+    signal?: AbortSignal;
+    /** How `stdin` of the spawned process should be handled.
+     *
+     * @default {"inherit"} */
+    stdin?: "piped" | "inherit" | "null";
+    /** How `stdout` of the spawned process should be handled.
+     *
+     * @default {"inherit"} */
+    stdout?: "piped" | "inherit" | "null";
+    /** How `stderr` of the spawned process should be handled.
+     *
+     * @default {"inherit"} */
+    stderr?: "piped" | "inherit" | "null";
+    /** Set up an {@linkcode Deno.IpcChannel} between the two processes. The
+     * child gets its end from {@linkcode Deno.parentIpc}.
+     *
+     * @default {false} */
+    ipc?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A process spawned by {@linkcode Deno.spawnSelf}.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export interface SpawnSelfChildProcess extends ChildProcess {
+    /** The channel to the child process, or `null` if it was spawned without
+     * `ipc: true`. */
+    readonly ipc: IpcChannel | null;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A message channel between a process and a child spawned with
+   * {@linkcode Deno.spawnSelf}. Messages are copied with the structured
+   * clone algorithm; transferring objects is not supported.
+   *
+   * Incoming messages are delivered once a `message` listener is added, and
+   * keep the process alive until the channel is closed, either locally or by
+   * the other process exiting, or until {@linkcode Deno.IpcChannel.unref} is
+   * called.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export class IpcChannel extends EventTarget {
+    /** Send a message to the other process. Messages sent after the channel
+     * was closed are dropped. */
+    postMessage(message: unknown): void;
+    /** Start delivering messages. Called implicitly when a `message`
+     * listener is added. */
+    start(): void;
+    /** Close the channel. Messages that were already sent are still
+     * delivered. */
+    close(): void;
+    ref(): void;
+    unref(): void;
+    onmessage: ((this: IpcChannel, ev: MessageEvent) => any) | null;
+    onmessageerror: ((this: IpcChannel, ev: MessageEvent) => any) | null;
+    onclose: ((this: IpcChannel, ev: Event) => any) | null;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Spawn the current executable as a subprocess. By default the child
+   * shares the stdio of this process.
+   *
+   * For `deno` itself, `args` are command line arguments such as
+   * `["run", "-A", "worker.ts"]`. In a program built with `deno compile`
+   * the program itself is spawned and `args` become its `Deno.args`. Set the
+   * `DENO_EXEC_PATH` environment variable to spawn a different executable.
+   *
+   * Requires `allow-run` permission for the resolved executable.
+   *
+   * ```ts
+   * const child = Deno.spawnSelf(["run", "-A", "--unstable-process", "worker.ts"], {
+   *   ipc: true,
+   * });
+   * child.ipc!.onmessage = (e) => console.log(e.data);
+   * child.ipc!.postMessage({ job: 1 });
+   *
+   * // worker.ts
+   * const parent = Deno.parentIpc()!;
+   * parent.onmessage = (e) => {
+   *   parent.postMessage({ done: e.data.job });
+   *   parent.close();
+   * };
+   * ```
+   *
+   * @tags allow-run
+   * @category Subprocess
+   * @experimental
+   */
+  export function spawnSelf(
+    args?: string[],
+    options?: SpawnSelfOptions,
+  ): SpawnSelfChildProcess;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The channel to the parent process if this process was spawned with
+   * `Deno.spawnSelf(args, { ipc: true })`, otherwise `null`. Every call
+   * returns the same channel.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export function parentIpc(): IpcChannel | null;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The argument passed to `"unhandledRejection"` lifecycle hooks.
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export interface UnhandledRejection {
+    /** The rejected promise. */
+    readonly promise: Promise<unknown>;
+    /** The rejection reason. */
+    readonly reason: unknown;
+    /** Whether a hook already marked the rejection as handled. */
+    readonly handled: boolean;
+    /** Marks the rejection as handled, so that the runtime doesn't exit with
+     * an error. The remaining hooks are not called. */
+    markHandled(): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The argument passed to `"beforeShutdown"` lifecycle hooks.
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export interface BeforeShutdown {
+    /** The exit code the process will exit with. */
+    readonly exitCode: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The argument passed to `"beforeTerminate"` lifecycle hooks.
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export interface BeforeTerminate {
+    /** How many milliseconds are left before the worker is killed. */
+    readonly deadline: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Registers a hook that is called at a point of the runtime's lifecycle:
+   *
+   * - `"unhandledRejection"`: a promise rejection was not handled by any
+   *   `"unhandledrejection"` event listener. A hook can call
+   *   `markHandled()` to keep the program running instead of exiting with an
+   *   error.
+   * - `"beforeShutdown"`: the event loop is done and the program is about to
+   *   exit, right before the `"unload"` event.
+   * - `"beforeTerminate"`: the parent of the worker called
+   *   `Worker.terminate()`, right before the `"beforeterminate"` event.
+   *
+   * Hooks registered by the runtime's extensions are called first, then the
+   * hooks registered with this function, in registration order.
+   *
+   * ```ts
+   * Deno.addLifecycleHook("unhandledRejection", (rejection) => {
+   *   console.error("ignoring", rejection.reason);
+   *   rejection.markHandled();
+   * });
+   * Promise.reject(new Error("boom"));
+   * ```
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export function addLifecycleHook(
+    event: "unhandledRejection",
+    hook: (rejection: UnhandledRejection) => void,
+  ): void;
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export function addLifecycleHook(
+    event: "beforeShutdown",
+    hook: (shutdown: BeforeShutdown) => void,
+  ): void;
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export function addLifecycleHook(
+    event: "beforeTerminate",
+    hook: (termination: BeforeTerminate) => void,
+  ): void;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Removes a hook registered with {@linkcode Deno.addLifecycleHook}.
+   *
+   * @category Runtime
+   * @experimental
+   */
+  export function removeLifecycleHook(
+    event: "unhandledRejection" | "beforeShutdown" | "beforeTerminate",
+    // deno-lint-ignore no-explicit-any
+    hook: (arg: any) => void,
+  ): void;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Describes the environment of a subprocess or worker in terms of the
+   * environment of the process or worker that spawns it. The fields apply in
+   * order: `inherit` selects the variables to start from, `remove` drops
+   * variables and `set` sets variables, also ones listed in `remove`.
+   *
+   * Inherited variables are only forwarded if env permission is granted for
+   * them, so `--allow-env=FOO,BAR` also limits what can be inherited. Other
+   * inherited variables are dropped without prompting and reported in
+   * {@linkcode Deno.ChildProcess.envDiagnostics}.
+   *
+   * ```ts
+   * const child = new Deno.Command("deno", {
+   *   args: ["test"],
+   *   envPolicy: {
+   *     inherit: ["PATH", "HOME"],
+   *     set: { NO_COLOR: "1" },
+   *   },
+   * }).spawn();
+   * console.log(child.envDiagnostics?.denied);
+   * ```
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export interface EnvPolicy {
+    /** The variables to inherit: all of them, none of them or the listed
+     * ones.
+     *
+     * @default {"all"} */
+    inherit?: "all" | "none" | string[];
+    /** Variables to set, overriding inherited values. */
+    set?: Record<string, string>;
+    /** Inherited variables to drop. */
+    remove?: string[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * How a {@linkcode Deno.EnvPolicy} was applied.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export interface EnvPolicyDiagnostics {
+    /** Names of inherited variables that were dropped because env permission
+     * wasn't granted for them, sorted. */
+    denied: string[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export interface CommandOptions {
+    /** Build the environment of the subprocess from a
+     * {@linkcode Deno.EnvPolicy}. Can't be combined with `env` or
+     * `clearEnv`. */
+    envPolicy?: EnvPolicy;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * @category Subprocess
+   * @experimental
+   */
+  export interface ChildProcess {
+    /** How the `envPolicy` of the command was applied, `undefined` if it was
+     * spawned without one. */
+    readonly envDiagnostics: EnvPolicyDiagnostics | undefined;
+  }
+
   /** **UNSTABLE**: New API, yet to be vetted.
    *
    * Open a new {@linkcode Deno.Kv} connection to persist data.
@@ -305,6 +2606,241 @@ declare namespace Deno {
     handler: () => Promise<void> | void,
   ): Promise<void>;
 
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.schedule}.
+   *
+   * @category Cloud
+   * @experimental
+   */
+  export interface ScheduleOptions {
+    /** What happens to occurrences that come due while the handler is still
+     * running:
+     *
+     * - `"skip"`: they are dropped.
+     * - `"queue"`: they run one after another once the handler returns, up to
+     *   16 of them.
+     * - `"concurrent"`: they run right away, next to the running handler.
+     *
+     * @default {"skip"} */
+    overlap?: "skip" | "queue" | "concurrent";
+    /** Which occurrences that were missed while the program wasn't running
+     * are run when the schedule is created again:
+     *
+     * - `"none"`: none of them.
+     * - `"latest"`: the most recent one.
+     * - `"all"`: all of them, up to 16, oldest first.
+     *
+     * @default {"none"} */
+    catchUp?: "none" | "latest" | "all";
+    /** Stops the schedule when aborted. */
+    signal?: AbortSignal;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Run a handler on a schedule inside the running process. `schedule` is a
+   * cron expression, a {@linkcode CronSchedule}, both in UTC, or a fixed
+   * interval of at least 1000 milliseconds as `{ every }`.
+   *
+   * Unlike {@linkcode Deno.cron}, the last finished run of each schedule is
+   * kept in a state file in the deno dir, or next to a compiled binary when
+   * its directory is writable. When the program starts again,
+   * `options.catchUp` decides which of the occurrences missed in between are
+   * run. A run counts as finished once the handler returns or throws.
+   *
+   * ```ts
+   * Deno.schedule("cleanup", { every: 5 * 60 * 1000 }, {
+   *   catchUp: "latest",
+   * }, async ({ scheduledTime }) => {
+   *   await cleanup(scheduledTime);
+   * });
+   * ```
+   *
+   * @category Cloud
+   * @experimental
+   */
+  export function schedule(
+    name: string,
+    schedule: string | CronSchedule | { every: number },
+    handler: (info: { scheduledTime: Date }) => Promise<void> | void,
+  ): Promise<void>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Run a handler on a schedule inside the running process, with options.
+   * See the overload without options for details.
+   *
+   * @category Cloud
+   * @experimental
+   */
+  export function schedule(
+    name: string,
+    schedule: string | CronSchedule | { every: number },
+    options: ScheduleOptions,
+    handler: (info: { scheduledTime: Date }) => Promise<void> | void,
+  ): Promise<void>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Options for {@linkcode Deno.wrapWithPassword}.
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export interface PasswordWrapOptions {
+    /** The number of PBKDF2 iterations, between 1 and 10000000.
+     *
+     * @default {600000} */
+    iterations?: number;
+    /** The length of the random salt in bytes, between 8 and 255.
+     *
+     * @default {16} */
+    saltLength?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Wraps `data` with a key derived from `password`, for storing secrets at
+   * rest. The key is derived with PBKDF2-HMAC-SHA256 and a random salt, and
+   * the data is wrapped with AES-256 key wrap with padding (RFC 5649).
+   *
+   * The returned envelope records the format version, the iteration count
+   * and the salt, so it can be opened with
+   * {@linkcode Deno.unwrapWithPassword} and the password alone.
+   *
+   * ```ts
+   * const envelope = await Deno.wrapWithPassword(secret, "correct horse");
+   * await Deno.writeFile("secret.bin", envelope);
+   * ```
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export function wrapWithPassword(
+    data: BufferSource,
+    password: string | BufferSource,
+    options?: PasswordWrapOptions,
+  ): Promise<Uint8Array>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Opens an envelope created by {@linkcode Deno.wrapWithPassword}.
+   *
+   * Rejects with an `OperationError` `DOMException` if the password is wrong
+   * or the envelope was corrupted. The two cases can't be told apart.
+   *
+   * ```ts
+   * const envelope = await Deno.readFile("secret.bin");
+   * const secret = await Deno.unwrapWithPassword(envelope, "correct horse");
+   * ```
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export function unwrapWithPassword(
+    envelope: BufferSource,
+    password: string | BufferSource,
+  ): Promise<Uint8Array>;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An SSH public key returned by {@linkcode Deno.parseSshPublicKey}, for
+   * verifying signatures made with `ssh-keygen -Y sign` or by git.
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export class SshPublicKey implements Disposable {
+    /** The SSH algorithm name, like `"ssh-ed25519"`. */
+    readonly algorithm: string;
+    /** The comment after the key, usually `user@host`. */
+    readonly comment: string;
+    /** The SHA-256 fingerprint as printed by `ssh-keygen -l`. */
+    readonly fingerprint: string;
+    /** The options in front of an `authorized_keys` entry, like
+     * `no-pty` or `command="..."`. */
+    readonly options: string[];
+
+    /** Verifies an armored SSHSIG `signature` of `message`.
+     *
+     * Returns `false` if the signature was made by another key, over another
+     * message or in another `namespace`. Throws a `TypeError` if the
+     * signature is malformed.
+     */
+    verify(
+      namespace: string,
+      message: string | BufferSource,
+      signature: string,
+    ): boolean;
+    /** Releases the key. */
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An SSH private key returned by {@linkcode Deno.parseSshPrivateKey}.
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export class SshPrivateKey implements Disposable {
+    /** The SSH algorithm name, like `"ssh-ed25519"`. */
+    readonly algorithm: string;
+    /** The comment stored with the key. */
+    readonly comment: string;
+    /** The SHA-256 fingerprint as printed by `ssh-keygen -l`. */
+    readonly fingerprint: string;
+    /** The public key in the OpenSSH single-line format. */
+    readonly publicKey: string;
+
+    /** Signs `message` in `namespace`, returning an armored SSHSIG
+     * signature that `ssh-keygen -Y verify` accepts. */
+    sign(namespace: string, message: string | BufferSource): string;
+    /** Releases the key. */
+    close(): void;
+    [Symbol.dispose](): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Parses an SSH public key, either in the OpenSSH single-line format of
+   * `.pub` files or as a line of an `authorized_keys` file. Ed25519, ECDSA
+   * P-256 and RSA keys are supported.
+   *
+   * ```ts
+   * using key = Deno.parseSshPublicKey(await Deno.readTextFile("id.pub"));
+   * const ok = key.verify("file", message, await Deno.readTextFile("a.sig"));
+   * ```
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export function parseSshPublicKey(text: string): SshPublicKey;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Parses a private key in the OpenSSH format, decrypting it with
+   * `passphrase` if it is encrypted.
+   *
+   * Rejects with a `TypeError` if the key is malformed or a passphrase is
+   * required, and with an `OperationError` `DOMException` if the passphrase
+   * is wrong.
+   *
+   * ```ts
+   * using key = await Deno.parseSshPrivateKey(pem, passphrase);
+   * const signature = key.sign("file", message);
+   * ```
+   *
+   * @category Crypto
+   * @experimental
+   */
+  export function parseSshPrivateKey(
+    pem: string,
+    passphrase?: string,
+  ): Promise<SshPrivateKey>;
+
   /** **UNSTABLE**: New API, yet to be vetted.
    *
    * A key to be persisted in a {@linkcode Deno.Kv}. A key is a sequence
@@ -1222,6 +3758,41 @@ declare namespace Deno {
       },
     ): Promise<void>;
 
+    /**
+     * Options for {@linkcode Deno.jupyter.prompt}.
+     *
+     * @category Jupyter
+     * @experimental
+     */
+    export interface PromptOptions {
+      /** Ask the frontend to hide the input, e.g. for passwords. */
+      password?: boolean;
+      /** Reject with {@linkcode Deno.errors.TimedOut} if no input was
+       * received within this many milliseconds. */
+      timeout?: number;
+    }
+
+    /**
+     * Request input from the user of the frontend on the stdin channel.
+     * Unlike `prompt()`, this doesn't block the event loop while waiting.
+     *
+     * ```
+     * const name = await Deno.jupyter.prompt("What is your name?");
+     * ```
+     *
+     * Rejects with {@linkcode Deno.errors.NotSupported} if the frontend
+     * doesn't support input requests for the executing cell, and with
+     * {@linkcode Deno.errors.Busy} if another input request of the cell is
+     * still pending.
+     *
+     * @category Jupyter
+     * @experimental
+     */
+    export function prompt(
+      message?: string,
+      options?: PromptOptions,
+    ): Promise<string>;
+
     export {}; // only export exports
   }
 
@@ -1266,6 +3837,11 @@ interface WorkerOptions {
   deno?: {
     /** Set to `"none"` to disable all the permissions in the worker. */
     permissions?: Deno.PermissionOptions;
+    /** The environment the worker sees through `Deno.env`, built from the
+     * environment of this thread. Changes made by the worker don't affect
+     * the process environment. By default the worker shares the
+     * environment of this thread. */
+    env?: Deno.EnvPolicy;
   };
 }
 
@@ -12,6 +12,7 @@ interface WindowEventMap {
   "error": ErrorEvent;
   "unhandledrejection": PromiseRejectionEvent;
   "rejectionhandled": PromiseRejectionEvent;
+  "storage": StorageEvent;
 }
 
 /** @category Platform */
@@ -22,6 +23,8 @@ interface Window extends EventTarget {
   onload: ((this: Window, ev: Event) => any) | null;
   onbeforeunload: ((this: Window, ev: Event) => any) | null;
   onunload: ((this: Window, ev: Event) => any) | null;
+  oninterrupt: ((this: Window, ev: Event) => any) | null;
+  onstorage: ((this: Window, ev: StorageEvent) => any) | null;
   onunhandledrejection:
     | ((this: Window, ev: PromiseRejectionEvent) => any)
     | null;
@@ -93,10 +96,21 @@ declare var onload: ((this: Window, ev: Event) => any) | null;
 declare var onbeforeunload: ((this: Window, ev: Event) => any) | null;
 /** @category Events */
 declare var onunload: ((this: Window, ev: Event) => any) | null;
+/** Called on Ctrl+C (SIGINT). Unless the event is canceled, the process exits
+ * with code 130 if it doesn't exit on its own within a grace period, or when
+ * Ctrl+C is pressed again.
+ *
+ * @category Events */
+declare var oninterrupt: ((this: Window, ev: Event) => any) | null;
 /** @category Events */
 declare var onunhandledrejection:
   | ((this: Window, ev: PromiseRejectionEvent) => any)
   | null;
+/** Called when another context of the same origin, such as another test file
+ * of a parallel `deno test` run, changed `localStorage`.
+ *
+ * @category Events */
+declare var onstorage: ((this: Window, ev: StorageEvent) => any) | null;
 /** @category Storage */
 declare var localStorage: Storage;
 /** @category Storage */
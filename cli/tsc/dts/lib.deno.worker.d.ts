@@ -8,6 +8,7 @@
 
 /** @category Workers */
 declare interface WorkerGlobalScopeEventMap {
+  "beforeterminate": Event;
   "error": ErrorEvent;
   "unhandledrejection": PromiseRejectionEvent;
 }
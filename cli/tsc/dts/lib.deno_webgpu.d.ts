@@ -987,6 +987,16 @@ declare class GPUComputePassEncoder
     indirectOffset: number,
   ): undefined;
 
+  /** Non-standard: starts counting the pipeline statistics of the query
+   * set at `queryIndex`. Requires the `"pipeline-statistics-query"`
+   * feature. */
+  beginPipelineStatisticsQuery(
+    querySet: GPUQuerySet,
+    queryIndex: number,
+  ): undefined;
+  /** Non-standard: ends the pipeline statistics query begun last. */
+  endPipelineStatisticsQuery(): undefined;
+
   end(): undefined;
 }
 
@@ -1113,6 +1123,16 @@ declare class GPURenderPassEncoder
   beginOcclusionQuery(queryIndex: number): undefined;
   endOcclusionQuery(): undefined;
 
+  /** Non-standard: starts counting the pipeline statistics of the query
+   * set at `queryIndex`. Requires the `"pipeline-statistics-query"`
+   * feature. */
+  beginPipelineStatisticsQuery(
+    querySet: GPUQuerySet,
+    queryIndex: number,
+  ): undefined;
+  /** Non-standard: ends the pipeline statistics query begun last. */
+  endPipelineStatisticsQuery(): undefined;
+
   executeBundles(bundles: GPURenderBundle[]): undefined;
   end(): undefined;
 }
@@ -1246,6 +1266,11 @@ declare class GPUQueue implements GPUObjectBase {
 
   onSubmittedWorkDone(): Promise<undefined>;
 
+  /** Non-standard: the number of nanoseconds per tick of the values that
+   * timestamp queries resolve to. Multiply resolved timestamps by it to
+   * convert them to nanoseconds. */
+  getTimestampPeriod(): number;
+
   writeBuffer(
     buffer: GPUBuffer,
     bufferOffset: number,
@@ -1276,10 +1301,23 @@ declare class GPUQuerySet implements GPUObjectBase {
 interface GPUQuerySetDescriptor extends GPUObjectDescriptorBase {
   type: GPUQueryType;
   count: number;
+  /** Non-standard: the statistics that `"pipeline-statistics"` queries
+   * count. Each query resolves to one 64-bit value per statistic, in the
+   * order of {@linkcode GPUPipelineStatisticName}, regardless of the order
+   * they are listed in. */
+  pipelineStatistics?: GPUPipelineStatisticName[];
 }
 
 /** @category GPU */
-type GPUQueryType = "occlusion" | "timestamp";
+type GPUQueryType = "occlusion" | "timestamp" | "pipeline-statistics";
+
+/** @category GPU */
+type GPUPipelineStatisticName =
+  | "vertex-shader-invocations"
+  | "clipper-invocations"
+  | "clipper-primitives-out"
+  | "fragment-shader-invocations"
+  | "compute-shader-invocations";
 
 /** @category GPU */
 type GPUDeviceLostReason = "destroyed";
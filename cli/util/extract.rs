@@ -328,6 +328,8 @@ impl Visit for ExportCollector {
           return;
         }
 
+        // A namespace may be declared several times and merged by TypeScript;
+        // the set records it as a single export.
         match &ts_module.id {
           ast::TsModuleName::Ident(ident) => {
             self.named_exports.insert(ident.sym.clone());
@@ -389,6 +391,13 @@ impl Visit for ExportCollector {
     }
 
     match &export_named_specifier.exported {
+      // `export { foo as default }` is the same as `export default foo`.
+      Some(exported) if &*get_atom(exported) == "default" => {
+        if let ast::ModuleExportName::Ident(orig) = &export_named_specifier.orig
+        {
+          self.default_export = Some(orig.sym.clone());
+        }
+      }
       Some(exported) => {
         self.named_exports.insert(get_atom(exported));
       }
@@ -400,6 +409,23 @@ impl Visit for ExportCollector {
     }
   }
 
+  fn visit_ts_export_assignment(
+    &mut self,
+    export_assignment: &ast::TsExportAssignment,
+  ) {
+    // `export = foo` is imported as `import foo from "..."`. The name comes
+    // from the assigned expression, so anonymous values can't be imported.
+    let name = match &*export_assignment.expr {
+      ast::Expr::Ident(ident) => Some(&ident.sym),
+      ast::Expr::Class(class) => class.ident.as_ref().map(|i| &i.sym),
+      ast::Expr::Fn(func) => func.ident.as_ref().map(|i| &i.sym),
+      _ => None,
+    };
+    if let Some(name) = name {
+      self.default_export = Some(name.clone());
+    }
+  }
+
   fn visit_named_export(&mut self, named_export: &ast::NamedExport) {
     // ExportCollector does not handle re-exports
     if named_export.src.is_some() {
@@ -879,6 +905,30 @@ export default class Bar {}
 Deno.test("file:///main.ts$3-6.ts", async ()=>{
     foo();
 });
+"#,
+          specifier: "file:///main.ts$3-6.ts",
+          media_type: MediaType::TypeScript,
+        }],
+      },
+      Test {
+        input: Input {
+          source: r#"
+/**
+ * ```ts
+ * greet("world");
+ * ```
+ */
+function greet(name: string) {}
+
+export = greet;
+"#,
+          specifier: "file:///main.ts",
+        },
+        expected: vec![Expected {
+          source: r#"import greet from "file:///main.ts";
+Deno.test("file:///main.ts$3-6.ts", async ()=>{
+    greet("world");
+});
 "#,
           specifier: "file:///main.ts$3-6.ts",
           media_type: MediaType::TypeScript,
@@ -1641,6 +1691,56 @@ export default Foo
         named_expected: atom_set!("Foo"),
         default_expected: Some("Foo".into()),
       },
+      Test {
+        input: r#"
+function foo() {}
+export = foo;
+"#,
+        named_expected: atom_set!(),
+        default_expected: Some("foo".into()),
+      },
+      Test {
+        input: r#"export = class Foo {};"#,
+        named_expected: atom_set!(),
+        default_expected: Some("Foo".into()),
+      },
+      // There is no name to import an anonymous export assignment with.
+      Test {
+        input: r#"export = { value: 42 };"#,
+        named_expected: atom_set!(),
+        default_expected: None,
+      },
+      Test {
+        input: r#"
+export namespace Foo {
+  export const a = 1;
+}
+export namespace Foo {
+  export const b = 2;
+}
+export namespace Foo.Bar {
+  export const c = 3;
+}
+"#,
+        named_expected: atom_set!("Foo"),
+        default_expected: None,
+      },
+      Test {
+        input: r#"
+export const foo = 42;
+export { foo };
+"#,
+        named_expected: atom_set!("foo"),
+        default_expected: None,
+      },
+      Test {
+        input: r#"
+const foo = 42;
+export { foo as default };
+"#,
+        named_expected: atom_set!(),
+        default_expected: Some("foo".into()),
+      },
     ];
 
     for test in tests {
@@ -1649,4 +1749,36 @@ export default Foo
       assert_eq!(got.default_export, test.default_expected);
     }
   }
+
+  #[test]
+  fn test_export_collector_import_specifiers_are_unique() {
+    let mut collector = ExportCollector::default();
+    let parsed = deno_ast::parse_module(deno_ast::ParseParams {
+      specifier: deno_ast::ModuleSpecifier::parse("file:///main.ts").unwrap(),
+      text: r#"
+export class Foo {}
+export { Foo, Foo as default };
+export const bar = 1;
+export { bar };
+"#
+      .into(),
+      media_type: deno_ast::MediaType::TypeScript,
+      capture_tokens: false,
+      scope_analysis: false,
+      maybe_syntax: None,
+    })
+    .unwrap();
+    collector.visit_program(parsed.program_ref());
+
+    let symbols = collector
+      .to_import_specifiers(&AHashSet::default())
+      .into_iter()
+      .map(|specifier| match specifier {
+        ast::ImportSpecifier::Named(named) => named.local.sym,
+        ast::ImportSpecifier::Default(default) => default.local.sym,
+        ast::ImportSpecifier::Namespace(ns) => ns.local.sym,
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(symbols, vec![Atom::from("Foo"), Atom::from("bar")]);
+  }
 }
@@ -1,6 +1,7 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 use crate::args::Flags;
+use crate::cache::ModuleSourceCache;
 use crate::colors;
 use crate::util::fs::canonicalize_path;
 
@@ -281,6 +282,9 @@ where
   deno_core::unsync::spawn(async move {
     loop {
       let received_changed_paths = watcher_receiver.recv().await;
+      if let Some(paths) = &received_changed_paths {
+        ModuleSourceCache::shared().evict_paths(paths);
+      }
       changed_paths_
         .borrow_mut()
         .clone_from(&received_changed_paths);
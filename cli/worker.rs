@@ -4,11 +4,13 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use deno_ast::ModuleSpecifier;
 use deno_core::anyhow::bail;
 use deno_core::error::AnyError;
 use deno_core::futures::FutureExt;
+use deno_core::op2;
 use deno_core::url::Url;
 use deno_core::v8;
 use deno_core::CompiledWasmModuleStore;
@@ -16,20 +18,27 @@ use deno_core::Extension;
 use deno_core::FeatureChecker;
 use deno_core::ModuleId;
 use deno_core::ModuleLoader;
+use deno_core::OpState;
 use deno_core::PollEventLoopOptions;
 use deno_core::SharedArrayBufferStore;
 use deno_runtime::code_cache;
 use deno_runtime::deno_broadcast_channel::InMemoryBroadcastChannel;
 use deno_runtime::deno_fs;
+use deno_runtime::deno_net::resolver::DnsResolverConfig;
 use deno_runtime::deno_node;
 use deno_runtime::deno_node::NodeExtInitServices;
 use deno_runtime::deno_node::NodeResolver;
 use deno_runtime::deno_permissions::PermissionsContainer;
 use deno_runtime::deno_tls::RootCertStoreProvider;
 use deno_runtime::deno_web::BlobStore;
+use deno_runtime::deno_webstorage::StorageEventChannel;
+use deno_runtime::deprecated_ops::DeprecatedOpsOptions;
+use deno_runtime::event_loop_monitor::TickBudget;
 use deno_runtime::fmt_errors::format_js_error;
 use deno_runtime::inspector_server::InspectorServer;
+use deno_runtime::ops::blob_store::ContentStore;
 use deno_runtime::ops::process::NpmProcessStateProviderRc;
+use deno_runtime::ops::storage_archive::StorageDirs;
 use deno_runtime::ops::worker_host::CreateWebWorkerCb;
 use deno_runtime::web_worker::WebWorker;
 use deno_runtime::web_worker::WebWorkerOptions;
@@ -46,10 +55,14 @@ use node_resolver::NodeResolution;
 use node_resolver::NodeResolutionMode;
 use tokio::select;
 
+use crate::args::deno_lock;
 use crate::args::CliLockfile;
 use crate::args::DenoSubcommand;
 use crate::args::StorageKeyResolver;
+use crate::cache::ModuleSourceCache;
+use crate::cache::ModuleSourceCacheMetrics;
 use crate::errors;
+use crate::file_fetcher::FileFetcher;
 use crate::npm::CliNpmResolver;
 use crate::resolver::CjsResolutionStore;
 use crate::util::checksum;
@@ -58,6 +71,10 @@ use crate::util::file_watcher::WatcherRestartMode;
 use crate::util::path::specifier_has_extension;
 use crate::version;
 
+/// How long the resources of a finished program get to close asynchronously,
+/// e.g. to send a TLS close_notify, before the process exits anyway.
+const RESOURCE_CLOSE_DEADLINE: Duration = Duration::from_secs(2);
+
 pub struct ModuleLoaderAndSourceMapGetter {
   pub module_loader: Rc<dyn ModuleLoader>,
 }
@@ -114,12 +131,19 @@ pub struct CliMainWorkerOptions {
   pub argv0: Option<String>,
   pub node_debug: Option<String>,
   pub origin_data_folder_path: Option<PathBuf>,
+  pub blob_store_folder_path: Option<PathBuf>,
+  pub schedule_state_path: Option<PathBuf>,
   pub seed: Option<u64>,
+  pub sigint_grace_period: Option<Duration>,
+  pub tick_budget: Option<TickBudget>,
+  pub dns_resolver: DnsResolverConfig,
+  pub fs_limits: Option<deno_fs::SharedFsLimits>,
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
   pub skip_op_registration: bool,
   pub create_hmr_runner: Option<CreateHmrRunnerCb>,
   pub create_coverage_collector: Option<CreateCoverageCollectorCb>,
   pub node_ipc: Option<i64>,
+  pub spawn_self_ipc: Option<i64>,
   pub serve_port: Option<u16>,
   pub serve_host: Option<String>,
   pub unstable_detect_cjs: bool,
@@ -131,8 +155,10 @@ struct SharedWorkerState {
   cjs_resolution_store: Arc<CjsResolutionStore>,
   code_cache: Option<Arc<dyn code_cache::CodeCache>>,
   compiled_wasm_module_store: CompiledWasmModuleStore,
+  content_store: Option<Arc<ContentStore>>,
   feature_checker: Arc<FeatureChecker>,
   fs: Arc<dyn deno_fs::FileSystem>,
+  maybe_file_fetcher: Option<Arc<FileFetcher>>,
   maybe_file_watcher_communicator: Option<Arc<WatcherCommunicator>>,
   maybe_inspector_server: Option<Arc<InspectorServer>>,
   maybe_lockfile: Option<Arc<CliLockfile>>,
@@ -142,6 +168,7 @@ struct SharedWorkerState {
   root_cert_store_provider: Arc<dyn RootCertStoreProvider>,
   root_permissions: PermissionsContainer,
   shared_array_buffer_store: SharedArrayBufferStore,
+  storage_event_channel: StorageEventChannel,
   storage_key_resolver: StorageKeyResolver,
   options: CliMainWorkerOptions,
   subcommand: DenoSubcommand,
@@ -260,6 +287,10 @@ impl CliMainWorker {
         .await?;
     }
 
+    if !self.worker.close_resources(RESOURCE_CLOSE_DEADLINE).await {
+      log::debug!("Resources were not closed before the deadline");
+    }
+
     Ok(self.worker.exit_code())
   }
 
@@ -430,6 +461,7 @@ impl CliMainWorkerFactory {
     code_cache: Option<Arc<dyn code_cache::CodeCache>>,
     feature_checker: Arc<FeatureChecker>,
     fs: Arc<dyn deno_fs::FileSystem>,
+    maybe_file_fetcher: Option<Arc<FileFetcher>>,
     maybe_file_watcher_communicator: Option<Arc<WatcherCommunicator>>,
     maybe_inspector_server: Option<Arc<InspectorServer>>,
     maybe_lockfile: Option<Arc<CliLockfile>>,
@@ -449,8 +481,13 @@ impl CliMainWorkerFactory {
         cjs_resolution_store,
         code_cache,
         compiled_wasm_module_store: Default::default(),
+        content_store: options
+          .blob_store_folder_path
+          .clone()
+          .map(|path| Arc::new(ContentStore::new(path))),
         feature_checker,
         fs,
+        maybe_file_fetcher,
         maybe_file_watcher_communicator,
         maybe_inspector_server,
         maybe_lockfile,
@@ -460,6 +497,7 @@ impl CliMainWorkerFactory {
         root_cert_store_provider,
         root_permissions,
         shared_array_buffer_store: Default::default(),
+        storage_event_channel: Default::default(),
         storage_key_resolver,
         options,
         subcommand,
@@ -593,29 +631,38 @@ impl CliMainWorkerFactory {
       }
     }
 
+    let lockfile_extension = shared
+      .maybe_lockfile
+      .clone()
+      .zip(shared.maybe_file_fetcher.clone())
+      .map(|(lockfile, file_fetcher)| {
+        deno_lock::init_ops(lockfile, file_fetcher)
+      });
+
     let services = WorkerServiceOptions {
       root_cert_store_provider: Some(shared.root_cert_store_provider.clone()),
       module_loader,
       fs: shared.fs.clone(),
+      fs_limits: shared.options.fs_limits.clone(),
       node_services: Some(shared.create_node_init_services()),
       npm_process_state_provider: Some(shared.npm_process_state_provider()),
       blob_store: shared.blob_store.clone(),
       broadcast_channel: shared.broadcast_channel.clone(),
+      content_store: shared.content_store.clone(),
       shared_array_buffer_store: Some(shared.shared_array_buffer_store.clone()),
       compiled_wasm_module_store: Some(
         shared.compiled_wasm_module_store.clone(),
       ),
       feature_checker,
       permissions,
+      storage_event_channel: shared.storage_event_channel.clone(),
       v8_code_cache: shared.code_cache.clone(),
     };
     let options = WorkerOptions {
       bootstrap: BootstrapOptions {
         deno_version: crate::version::DENO_VERSION_INFO.deno.to_string(),
         args: shared.options.argv.clone(),
-        cpu_count: std::thread::available_parallelism()
-          .map(|p| p.get())
-          .unwrap_or(1),
+        cpu_count: deno_runtime::ops::os::cpu_info::hardware_concurrency(),
         log_level: shared.options.log_level,
         enable_op_summary_metrics: shared.options.enable_op_summary_metrics,
         enable_testing_features: shared.options.enable_testing_features,
@@ -632,27 +679,47 @@ impl CliMainWorkerFactory {
         argv0: shared.options.argv0.clone(),
         node_debug: shared.options.node_debug.clone(),
         node_ipc_fd: shared.options.node_ipc,
+        spawn_self_ipc_fd: shared.options.spawn_self_ipc,
         mode,
         serve_port: shared.options.serve_port,
         serve_host: shared.options.serve_host.clone(),
       },
-      extensions: custom_extensions,
+      extensions: custom_extensions
+        .into_iter()
+        .chain(std::iter::once(deno_metrics::init_ops(
+          ModuleSourceCache::shared(),
+        )))
+        .chain(lockfile_extension)
+        .collect(),
       startup_snapshot: crate::js::deno_isolate_init(),
       create_params: None,
       unsafely_ignore_certificate_errors: shared
         .options
         .unsafely_ignore_certificate_errors
         .clone(),
+      dns_resolver: shared.options.dns_resolver.clone(),
       seed: shared.options.seed,
+      cpu_time_quota: None,
+      sigint_grace_period: shared.options.sigint_grace_period,
+      tick_budget: shared.options.tick_budget,
       format_js_error_fn: Some(Arc::new(format_js_error)),
       create_web_worker_cb,
       maybe_inspector_server,
       should_break_on_first_statement: shared.options.inspect_brk,
       should_wait_for_inspector_session: shared.options.inspect_wait,
       strace_ops: shared.options.strace_ops.clone(),
-      get_error_class_fn: Some(&errors::get_error_class_name),
+      otel: deno_runtime::otel::OtelConfig::from_env(),
+      deprecated_ops: DeprecatedOpsOptions::from_env(),
+      get_error_class_fn: Some(&errors::get_op_error_class_name),
       cache_storage_dir,
       origin_storage_dir,
+      schedule_state_path: shared.options.schedule_state_path.clone(),
+      storage_dirs: shared.options.origin_data_folder_path.clone().map(
+        |origin_data_dir| StorageDirs {
+          origin_data_dir,
+          cache_dir: Some(std::env::temp_dir().join("deno_cache")),
+        },
+      ),
       stdio,
       skip_op_registration: shared.options.skip_op_registration,
     };
@@ -754,6 +821,24 @@ impl CliMainWorkerFactory {
   }
 }
 
+deno_core::extension!(deno_metrics,
+  ops = [op_module_source_cache_metrics],
+  options = {
+    module_source_cache: Arc<ModuleSourceCache>,
+  },
+  state = |state, options| {
+    state.put(options.module_source_cache);
+  },
+);
+
+#[op2]
+#[serde]
+fn op_module_source_cache_metrics(
+  state: &mut OpState,
+) -> ModuleSourceCacheMetrics {
+  state.borrow::<Arc<ModuleSourceCache>>().metrics()
+}
+
 fn create_web_worker_callback(
   shared: Arc<SharedWorkerState>,
   stdio: deno_runtime::deno_io::Stdio,
@@ -795,9 +880,11 @@ fn create_web_worker_callback(
       root_cert_store_provider: Some(shared.root_cert_store_provider.clone()),
       module_loader,
       fs: shared.fs.clone(),
+      fs_limits: shared.options.fs_limits.clone(),
       node_services: Some(shared.create_node_init_services()),
       blob_store: shared.blob_store.clone(),
       broadcast_channel: shared.broadcast_channel.clone(),
+      content_store: shared.content_store.clone(),
       shared_array_buffer_store: Some(shared.shared_array_buffer_store.clone()),
       compiled_wasm_module_store: Some(
         shared.compiled_wasm_module_store.clone(),
@@ -814,9 +901,7 @@ fn create_web_worker_callback(
       bootstrap: BootstrapOptions {
         deno_version: crate::version::DENO_VERSION_INFO.deno.to_string(),
         args: shared.options.argv.clone(),
-        cpu_count: std::thread::available_parallelism()
-          .map(|p| p.get())
-          .unwrap_or(1),
+        cpu_count: deno_runtime::ops::os::cpu_info::hardware_concurrency(),
         log_level: shared.options.log_level,
         enable_op_summary_metrics: shared.options.enable_op_summary_metrics,
         enable_testing_features: shared.options.enable_testing_features,
@@ -833,24 +918,28 @@ fn create_web_worker_callback(
         argv0: shared.options.argv0.clone(),
         node_debug: shared.options.node_debug.clone(),
         node_ipc_fd: None,
+        spawn_self_ipc_fd: None,
         mode: WorkerExecutionMode::Worker,
         serve_port: shared.options.serve_port,
         serve_host: shared.options.serve_host.clone(),
       },
-      extensions: vec![],
+      extensions: vec![deno_metrics::init_ops(ModuleSourceCache::shared())],
       startup_snapshot: crate::js::deno_isolate_init(),
       unsafely_ignore_certificate_errors: shared
         .options
         .unsafely_ignore_certificate_errors
         .clone(),
+      dns_resolver: shared.options.dns_resolver.clone(),
       seed: shared.options.seed,
+      cpu_time_quota: None,
       create_web_worker_cb,
       format_js_error_fn: Some(Arc::new(format_js_error)),
       worker_type: args.worker_type,
-      get_error_class_fn: Some(&errors::get_error_class_name),
+      get_error_class_fn: Some(&errors::get_op_error_class_name),
       stdio: stdio.clone(),
       cache_storage_dir,
       strace_ops: shared.options.strace_ops.clone(),
+      deprecated_ops: DeprecatedOpsOptions::from_env(),
       close_on_idle: args.close_on_idle,
       maybe_worker_metadata: args.maybe_worker_metadata,
     };
@@ -864,22 +953,34 @@ fn create_web_worker_callback(
 #[cfg(test)]
 mod tests {
   use super::*;
+  use deno_core::parking_lot::Mutex;
   use deno_core::resolve_path;
   use deno_core::FsModuleLoader;
   use deno_fs::RealFs;
   use deno_runtime::deno_permissions::Permissions;
+  use deno_runtime::deprecated_ops::deprecated_op_call_counts;
+  use deno_runtime::deprecated_ops::DeprecatedOp;
+  use deno_runtime::deprecated_ops::DeprecatedOpCall;
+  use deno_runtime::fatal_error::FatalError;
+  use deno_runtime::fatal_error::FatalErrorDecision;
+  use deno_runtime::fatal_error::FatalErrorKind;
+  use deno_runtime::fatal_error::UnhandledRejectionPolicy;
   use deno_runtime::permissions::RuntimePermissionDescriptorParser;
+  use test_util::TempDir;
 
   fn create_test_worker() -> MainWorker {
+    create_test_worker_with_options(WorkerOptions {
+      startup_snapshot: crate::js::deno_isolate_init(),
+      ..Default::default()
+    })
+  }
+
+  fn create_test_worker_with_options(options: WorkerOptions) -> MainWorker {
     let main_module =
       resolve_path("./hello.js", &std::env::current_dir().unwrap()).unwrap();
     let fs = Arc::new(RealFs);
     let permission_desc_parser =
       Arc::new(RuntimePermissionDescriptorParser::new(fs.clone()));
-    let options = WorkerOptions {
-      startup_snapshot: crate::js::deno_isolate_init(),
-      ..Default::default()
-    };
 
     MainWorker::bootstrap_from_options(
       main_module,
@@ -891,14 +992,17 @@ mod tests {
         ),
         blob_store: Default::default(),
         broadcast_channel: Default::default(),
+        content_store: Default::default(),
         feature_checker: Default::default(),
         node_services: Default::default(),
         npm_process_state_provider: Default::default(),
         root_cert_store_provider: Default::default(),
         shared_array_buffer_store: Default::default(),
         compiled_wasm_module_store: Default::default(),
+        storage_event_channel: Default::default(),
         v8_code_cache: Default::default(),
         fs,
+        fs_limits: None,
       },
       options,
     )
@@ -956,4 +1060,254 @@ mod tests {
     let result = worker.execute_main_module(&module_specifier).await;
     assert!(result.is_ok());
   }
+
+  /// Creates a worker whose fatal error callback records the errors and
+  /// answers with `decision`.
+  fn create_fatal_error_worker(
+    decision: FatalErrorDecision,
+    unhandled_rejection_policy: UnhandledRejectionPolicy,
+  ) -> (MainWorker, Arc<Mutex<Vec<FatalError>>>) {
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let worker = create_test_worker_with_options(WorkerOptions {
+      startup_snapshot: crate::js::deno_isolate_init(),
+      on_fatal_error: Some(Arc::new({
+        let errors = errors.clone();
+        move |error: &FatalError| {
+          errors.lock().push(error.clone());
+          decision
+        }
+      })),
+      unhandled_rejection_policy,
+      ..Default::default()
+    });
+    (worker, errors)
+  }
+
+  #[tokio::test]
+  async fn fatal_error_from_main_module() {
+    let temp_dir = TempDir::new();
+    temp_dir.write(
+      "main.js",
+      r#"function fail() {
+  const error = new TypeError("bad input");
+  error.code = "ERR_BAD_INPUT";
+  throw error;
+}
+fail();
+"#,
+    );
+    let main_module = temp_dir.path().join("main.js").url_file();
+    let (mut worker, errors) = create_fatal_error_worker(
+      FatalErrorDecision::Rethrow,
+      UnhandledRejectionPolicy::Exit,
+    );
+
+    let result = worker.execute_main_module(&main_module).await;
+    assert!(result.is_err());
+
+    let errors = errors.lock();
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.kind, FatalErrorKind::UncaughtException);
+    assert_eq!(error.class.as_deref(), Some("TypeError"));
+    assert_eq!(error.message, "bad input");
+    assert_eq!(error.code.as_deref(), Some("ERR_BAD_INPUT"));
+    let frame = &error.frames[0];
+    assert_eq!(frame.function_name.as_deref(), Some("fail"));
+    assert_eq!(frame.file_name.as_deref(), Some(main_module.as_str()));
+    assert_eq!(
+      (frame.line_number, frame.column_number),
+      (Some(2), Some(17))
+    );
+  }
+
+  #[tokio::test]
+  async fn fatal_error_continue_keeps_event_loop_alive() {
+    let temp_dir = TempDir::new();
+    temp_dir.write(
+      "main.js",
+      r#"Promise.reject(new RangeError("rejected"));
+setTimeout(() => {
+  globalThis.timerRan = true;
+}, 10);
+"#,
+    );
+    let main_module = temp_dir.path().join("main.js").url_file();
+    let (mut worker, errors) = create_fatal_error_worker(
+      FatalErrorDecision::Continue,
+      UnhandledRejectionPolicy::Exit,
+    );
+
+    worker.execute_main_module(&main_module).await.unwrap();
+    worker.run_event_loop(false).await.unwrap();
+
+    let errors = errors.lock();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, FatalErrorKind::UnhandledRejection);
+    assert_eq!(errors[0].class.as_deref(), Some("RangeError"));
+    assert_eq!(errors[0].message, "rejected");
+    assert_eq!(errors[0].code, None);
+    worker
+      .execute_script(
+        "check",
+        "if (!globalThis.timerRan) throw new Error('timer did not run')".into(),
+      )
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn fatal_error_silenced_rejection() {
+    let temp_dir = TempDir::new();
+    temp_dir.write("main.js", "Promise.reject(new Error('ignored'));");
+    let main_module = temp_dir.path().join("main.js").url_file();
+    let (mut worker, errors) = create_fatal_error_worker(
+      FatalErrorDecision::Rethrow,
+      UnhandledRejectionPolicy::Silence,
+    );
+
+    worker.execute_main_module(&main_module).await.unwrap();
+    worker.run_event_loop(false).await.unwrap();
+    assert!(errors.lock().is_empty());
+  }
+  #[op2(fast)]
+  fn op_test_deprecated_warn() -> u32 {
+    42
+  }
+
+  #[op2(fast)]
+  fn op_test_deprecated_strict() -> u32 {
+    42
+  }
+
+  deno_core::extension!(
+    deprecated_ops_test,
+    ops = [op_test_deprecated_warn, op_test_deprecated_strict]
+  );
+
+  /// Creates a worker that treats the ops of `deprecated_ops_test` as
+  /// deprecated, and returns the calls reported for them.
+  fn create_deprecated_ops_worker(
+    strict: bool,
+  ) -> (MainWorker, Arc<Mutex<Vec<DeprecatedOpCall>>>) {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let worker = create_test_worker_with_options(WorkerOptions {
+      startup_snapshot: crate::js::deno_isolate_init(),
+      extensions: vec![deprecated_ops_test::init_ops()],
+      get_error_class_fn: Some(&errors::get_op_error_class_name),
+      deprecated_ops: Some(DeprecatedOpsOptions {
+        ops: vec![
+          DeprecatedOp::new("op_test_deprecated_warn", None, "3.0.0"),
+          DeprecatedOp::new(
+            "op_test_deprecated_strict",
+            Some("op_test_replacement"),
+            "3.0.0",
+          ),
+        ],
+        report_limit: 1,
+        sink: Some(Arc::new({
+          let calls = calls.clone();
+          move |call: &DeprecatedOpCall| calls.lock().push(*call)
+        })),
+        strict,
+      }),
+      ..Default::default()
+    });
+    (worker, calls)
+  }
+
+  #[tokio::test]
+  async fn deprecated_op_calls_are_reported_once() {
+    let (mut worker, calls) = create_deprecated_ops_worker(false);
+    worker
+      .execute_script(
+        "deprecated",
+        r#"const { ops } = Deno[Deno.internal].core;
+for (let i = 0; i < 3; i++) {
+  if (ops.op_test_deprecated_warn() !== 42) throw new Error("wrong result");
+}"#
+          .into(),
+      )
+      .unwrap();
+
+    let calls = calls.lock();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].op.name, "op_test_deprecated_warn");
+    assert_eq!(calls[0].count, 1);
+    assert!(
+      deprecated_op_call_counts().contains(&("op_test_deprecated_warn", 3))
+    );
+  }
+
+  #[tokio::test]
+  async fn deprecated_op_throws_in_strict_mode() {
+    let (mut worker, calls) = create_deprecated_ops_worker(true);
+    worker
+      .execute_script(
+        "deprecated",
+        r#"let error;
+try {
+  Deno[Deno.internal].core.ops.op_test_deprecated_strict();
+} catch (e) {
+  error = e;
+}
+if (!(error instanceof Deno.errors.NotSupported)) {
+  throw new Error("expected NotSupported, got " + error);
+}
+if (!error.message.includes('"op_test_deprecated_strict"')) {
+  throw new Error("unexpected message: " + error.message);
+}
+if (!error.message.includes("use op_test_replacement instead")) {
+  throw new Error("unexpected message: " + error.message);
+}"#
+          .into(),
+      )
+      .unwrap();
+    assert_eq!(calls.lock().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn virtual_module_imported_by_real_module() {
+    let temp_dir = TempDir::new();
+    temp_dir.write(
+      "main.js",
+      r#"import { name } from "app:config";
+globalThis.configName = name;
+"#,
+    );
+    let main_module = temp_dir.path().join("main.js").url_file();
+    let config = ModuleSpecifier::parse("app:config").unwrap();
+    let mut worker = create_test_worker();
+    worker
+      .add_virtual_module(
+        config.clone(),
+        "export const name: string = 'virtual';".to_string(),
+        deno_ast::MediaType::TypeScript,
+      )
+      .unwrap();
+
+    worker.execute_main_module(&main_module).await.unwrap();
+    worker
+      .execute_script(
+        "check",
+        r#"if (globalThis.configName !== "virtual") {
+  throw new Error("unexpected name: " + globalThis.configName);
+}
+const { modules } = Deno[Deno.internal].core.ops.op_module_graph_snapshot();
+if (!modules.some((module) => module.specifier === "app:config")) {
+  throw new Error("missing virtual module");
+}"#
+          .into(),
+      )
+      .unwrap();
+
+    let snapshot = worker.module_graph_snapshot();
+    let main = snapshot.get(&main_module).unwrap();
+    assert_eq!(main.dependencies, vec![config.clone()]);
+    assert_eq!(main.size, 65);
+    assert!(!main.is_virtual);
+    let virtual_module = snapshot.get(&config).unwrap();
+    assert!(virtual_module.is_virtual);
+    assert!(virtual_module.dependencies.is_empty());
+    assert!(virtual_module.size > 0);
+  }
 }
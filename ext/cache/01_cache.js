@@ -176,7 +176,15 @@ class Cache {
       prefix,
       "Argument 1",
     );
-    const p = await this[_matchAll](request, options);
+    let ifNoneMatch = null;
+    if (options?.ifNoneMatch !== undefined) {
+      ifNoneMatch = webidl.converters.ByteString(
+        options.ifNoneMatch,
+        prefix,
+        "Argument 2",
+      );
+    }
+    const p = await this[_matchAll](request, { ifNoneMatch });
     if (p.length > 0) {
       return p[0];
     } else {
@@ -221,7 +229,7 @@ class Cache {
    *
    * The function will return an array of responses.
    */
-  async [_matchAll](request, _options) {
+  async [_matchAll](request, options) {
     // Step 1.
     let r = null;
     // Step 2.
@@ -258,6 +266,7 @@ class Cache {
           // deno-lint-ignore prefer-primordials
           requestUrl: url.toString(),
           requestHeaders: innerRequest.headerList,
+          ifNoneMatch: options?.ifNoneMatch ?? null,
         },
       );
       if (matchResult) {
@@ -266,14 +275,28 @@ class Cache {
         if (responseBodyRid !== null) {
           body = readableStreamForRid(responseBodyRid);
         }
-        const response = new Response(
-          body,
-          {
-            headers: meta.responseHeaders,
-            status: meta.responseStatus,
-            statusText: meta.responseStatusText,
-          },
-        );
+        const headers = meta.responseHeaders;
+        // Conditional matches expose the ETag to send next time.
+        if (
+          options?.ifNoneMatch != null && meta.responseEtag !== null &&
+          getHeader(headers, "etag") === null
+        ) {
+          ArrayPrototypePush(headers, ["etag", meta.responseEtag]);
+        }
+        const response = meta.notModified
+          ? new Response(null, {
+            headers,
+            status: 304,
+            statusText: "Not Modified",
+          })
+          : new Response(
+            body,
+            {
+              headers,
+              status: meta.responseStatus,
+              statusText: meta.responseStatusText,
+            },
+          );
         ArrayPrototypePush(responses, response);
       }
     }
@@ -34,6 +34,8 @@ interface Cache {
    * How is the API different from browsers?
    * 1. You cannot match cache objects using by relative paths.
    * 2. You cannot pass options like `ignoreVary`, `ignoreMethod`, `ignoreSearch`.
+   * 3. You can pass `ifNoneMatch` to revalidate a response without reading
+   *    its body, see {@linkcode CacheQueryOptions.ifNoneMatch}.
    */
   match(
     request: RequestInfo | URL,
@@ -69,4 +71,14 @@ interface CacheQueryOptions {
   ignoreMethod?: boolean;
   ignoreSearch?: boolean;
   ignoreVary?: boolean;
+  /**
+   * Only supported by `Cache.match()`. An `If-None-Match` style list of
+   * ETags, compared with the ETag Deno computed from the stored body using
+   * the weak comparison. When one matches, `match()` resolves to a
+   * `304 Not Modified` response without a body, and the body isn't read.
+   *
+   * Responses to such conditional matches carry the computed ETag in their
+   * `etag` header, unless the stored response has one already.
+   */
+  ifNoneMatch?: string;
 }
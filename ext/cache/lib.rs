@@ -79,6 +79,11 @@ pub struct CacheMatchRequest {
   pub cache_id: i64,
   pub request_url: String,
   pub request_headers: Vec<(ByteString, ByteString)>,
+  /// `If-None-Match` style list of ETags, usually the one returned by `put`.
+  /// When it matches the stored body, the match is reported as not modified
+  /// and the body isn't opened.
+  #[serde(default)]
+  pub if_none_match: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -92,6 +97,13 @@ pub struct CacheMatchResponseMeta {
   pub response_status_text: String,
   pub request_headers: Vec<(ByteString, ByteString)>,
   pub response_headers: Vec<(ByteString, ByteString)>,
+  /// Strong ETag of the stored body (sha256 of its contents).
+  #[serde(default)]
+  pub response_etag: Option<String>,
+  /// Set when the request's `if_none_match` matched `response_etag`. No body
+  /// is returned in that case.
+  #[serde(default)]
+  pub not_modified: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -112,12 +124,13 @@ pub trait Cache: Clone + 'static {
     cache_name: String,
   ) -> Result<bool, CacheError>;
 
-  /// Put a resource into the cache.
+  /// Put a resource into the cache. Returns the strong ETag of the stored
+  /// body, if there is one.
   async fn put(
     &self,
     request_response: CachePutRequest,
     resource: Option<Rc<dyn Resource>>,
-  ) -> Result<(), CacheError>;
+  ) -> Result<Option<String>, CacheError>;
 
   async fn r#match(
     &self,
@@ -170,10 +183,11 @@ where
 }
 
 #[op2(async)]
+#[serde]
 pub async fn op_cache_put<CA>(
   state: Rc<RefCell<OpState>>,
   #[serde] request_response: CachePutRequest,
-) -> Result<(), CacheError>
+) -> Result<Option<String>, CacheError>
 where
   CA: Cache,
 {
@@ -350,6 +364,33 @@ fn test_get_header() {
   assert_eq!(value, Some(ByteString::from("accept-encoding")));
 }
 
+/// Check if an `If-None-Match` style value matches an ETag. The value may be
+/// `*` or a comma separated list of ETags. Like `If-None-Match`, this uses the
+/// weak comparison: a `W/` prefix is ignored on either side.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+  fn opaque_tag(etag: &str) -> &str {
+    etag.strip_prefix("W/").unwrap_or(etag)
+  }
+  let etag = opaque_tag(etag);
+  if_none_match
+    .split(',')
+    .map(|s| s.trim())
+    .any(|candidate| candidate == "*" || opaque_tag(candidate) == etag)
+}
+
+#[test]
+fn test_etag_matches() {
+  let etag = "\"abc\"";
+  assert!(etag_matches("\"abc\"", etag));
+  assert!(etag_matches("\"x\", \"abc\"", etag));
+  assert!(etag_matches("*", etag));
+  assert!(etag_matches("W/\"abc\"", etag));
+  assert!(etag_matches("\"abc\"", "W/\"abc\""));
+  assert!(etag_matches("W/\"abc\"", "W/\"abc\""));
+  assert!(!etag_matches("\"abd\"", etag));
+  assert!(!etag_matches("", etag));
+}
+
 /// Serialize headers into bytes.
 pub fn serialize_headers(headers: &[(ByteString, ByteString)]) -> Vec<u8> {
   let mut serialized_headers = Vec::new();
@@ -3,6 +3,8 @@ use std::borrow::Cow;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
@@ -20,11 +22,13 @@ use deno_core::Resource;
 use rusqlite::params;
 use rusqlite::Connection;
 use rusqlite::OptionalExtension;
+use sha2::Digest;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
 
 use crate::deserialize_headers;
+use crate::etag_matches;
 use crate::get_header;
 use crate::serialize_headers;
 use crate::vary_header_matches;
@@ -39,6 +43,9 @@ use crate::CachePutRequest;
 pub struct SqliteBackedCache {
   pub connection: Arc<Mutex<Connection>>,
   pub cache_storage_dir: PathBuf,
+  /// Number of times a response body was opened for reading, either to be
+  /// returned from `match` or to backfill its ETag.
+  body_reads: Arc<AtomicU64>,
 }
 
 impl SqliteBackedCache {
@@ -80,6 +87,7 @@ impl SqliteBackedCache {
                     response_status        INTEGER NOT NULL,
                     response_status_text   TEXT,
                     response_body_key      TEXT,
+                    response_body_etag     TEXT,
                     last_inserted_at       INTEGER UNSIGNED NOT NULL,
                     FOREIGN KEY (cache_id) REFERENCES cache_storage(id) ON DELETE CASCADE,
 
@@ -88,9 +96,27 @@ impl SqliteBackedCache {
           (),
         )
         .expect("failed to create request_response_list table");
+      // Caches created before ETags were introduced lack the column. Their
+      // rows are backfilled lazily, on the first conditional match.
+      let has_etag_column = connection
+        .prepare(
+          "SELECT 1 FROM pragma_table_info('request_response_list')
+                WHERE name = 'response_body_etag'",
+        )
+        .and_then(|mut stmt| stmt.exists(()))
+        .expect("failed to inspect request_response_list table");
+      if !has_etag_column {
+        connection
+          .execute(
+            "ALTER TABLE request_response_list ADD COLUMN response_body_etag TEXT",
+            (),
+          )
+          .expect("failed to migrate request_response_list table");
+      }
       SqliteBackedCache {
         connection: Arc::new(Mutex::new(connection)),
         cache_storage_dir,
+        body_reads: Default::default(),
       }
     }
   }
@@ -180,7 +206,7 @@ impl Cache for SqliteBackedCache {
     &self,
     request_response: CachePutRequest,
     resource: Option<Rc<dyn Resource>>,
-  ) -> Result<(), CacheError> {
+  ) -> Result<Option<String>, CacheError> {
     let db = self.connection.clone();
     let cache_storage_dir = self.cache_storage_dir.clone();
     let now = SystemTime::now()
@@ -197,6 +223,8 @@ impl Cache for SqliteBackedCache {
         get_responses_dir(cache_storage_dir, request_response.cache_id);
       let response_path = responses_dir.join(&body_key);
       let mut file = tokio::fs::File::create(response_path).await?;
+      // The ETag is computed while streaming so the body is never buffered.
+      let mut hasher = sha2::Sha256::new();
       let mut buf = BufMutView::new(64 * 1024);
       loop {
         let (size, buf2) = resource
@@ -208,6 +236,7 @@ impl Cache for SqliteBackedCache {
           break;
         }
         buf = buf2;
+        hasher.update(&buf[..size]);
 
         // Use poll_write to avoid holding a slice across await points
         poll_fn(|cx| Pin::new(&mut file).poll_write(cx, &buf[..size])).await?;
@@ -216,17 +245,24 @@ impl Cache for SqliteBackedCache {
       file.flush().await?;
       file.sync_all().await?;
 
+      let etag = format_etag(hasher);
       assert_eq!(
-        insert_cache_asset(db, request_response, Some(body_key.clone()),)
-          .await?,
+        insert_cache_asset(
+          db,
+          request_response,
+          Some(body_key.clone()),
+          Some(etag.clone())
+        )
+        .await?,
         Some(body_key)
       );
+      Ok(Some(etag))
     } else {
-      assert!(insert_cache_asset(db, request_response, None)
+      assert!(insert_cache_asset(db, request_response, None, None)
         .await?
         .is_none());
+      Ok(None)
     }
-    Ok(())
   }
 
   async fn r#match(
@@ -241,7 +277,7 @@ impl Cache for SqliteBackedCache {
     let (query_result, request) = spawn_blocking(move || {
       let db = db.lock();
       let result = db.query_row(
-        "SELECT response_body_key, response_headers, response_status, response_status_text, request_headers
+        "SELECT response_body_key, response_headers, response_status, response_status_text, request_headers, response_body_etag
              FROM request_response_list
              WHERE cache_id = ?1 AND request_url = ?2",
         (request.cache_id, &request.request_url),
@@ -251,13 +287,16 @@ impl Cache for SqliteBackedCache {
           let response_status: u16 = row.get(2)?;
           let response_status_text: String = row.get(3)?;
           let request_headers: Vec<u8> = row.get(4)?;
+          let response_etag: Option<String> = row.get(5)?;
           let response_headers: Vec<(ByteString, ByteString)> = deserialize_headers(&response_headers);
           let request_headers: Vec<(ByteString, ByteString)> = deserialize_headers(&request_headers);
           Ok((CacheMatchResponseMeta {
             request_headers,
             response_headers,
             response_status,
-            response_status_text},
+            response_status_text,
+            response_etag,
+            not_modified: false},
             response_body_key
           ))
         },
@@ -268,7 +307,7 @@ impl Cache for SqliteBackedCache {
     .await??;
 
     match query_result {
-      Some((cache_meta, Some(response_body_key))) => {
+      Some((mut cache_meta, Some(response_body_key))) => {
         // From https://w3c.github.io/ServiceWorker/#request-matches-cached-item-algorithm
         // If there's Vary header in the response, ensure all the
         // headers of the cached request match the query request.
@@ -285,7 +324,29 @@ impl Cache for SqliteBackedCache {
         }
         let response_path =
           get_responses_dir(cache_storage_dir, request.cache_id)
-            .join(response_body_key);
+            .join(&response_body_key);
+        if let Some(if_none_match) = &request.if_none_match {
+          let etag = match &cache_meta.response_etag {
+            Some(etag) => Some(etag.clone()),
+            None => {
+              self
+                .backfill_etag(
+                  request.cache_id,
+                  response_body_key,
+                  response_path.clone(),
+                )
+                .await?
+            }
+          };
+          if let Some(etag) = etag {
+            cache_meta.not_modified = etag_matches(if_none_match, &etag);
+            cache_meta.response_etag = Some(etag);
+            if cache_meta.not_modified {
+              return Ok(Some((cache_meta, None)));
+            }
+          }
+        }
+        self.body_reads.fetch_add(1, Ordering::Relaxed);
         let file = match tokio::fs::File::open(response_path).await {
           Ok(file) => file,
           Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
@@ -325,10 +386,46 @@ impl Cache for SqliteBackedCache {
   }
 }
 
+impl SqliteBackedCache {
+  /// Compute and store the ETag of a body that was put before ETags were
+  /// introduced. Returns `None` if the body is gone.
+  async fn backfill_etag(
+    &self,
+    cache_id: i64,
+    response_body_key: String,
+    response_path: PathBuf,
+  ) -> Result<Option<String>, CacheError> {
+    let db = self.connection.clone();
+    let body_reads = self.body_reads.clone();
+    spawn_blocking(move || {
+      body_reads.fetch_add(1, Ordering::Relaxed);
+      let mut file = match std::fs::File::open(response_path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+          return Ok(None);
+        }
+        Err(err) => return Err(err.into()),
+      };
+      let mut hasher = sha2::Sha256::new();
+      std::io::copy(&mut file, &mut hasher)?;
+      let etag = format_etag(hasher);
+      let db = db.lock();
+      db.execute(
+        "UPDATE request_response_list SET response_body_etag = ?1
+             WHERE cache_id = ?2 AND response_body_key = ?3",
+        (&etag, cache_id, &response_body_key),
+      )?;
+      Ok::<Option<String>, CacheError>(Some(etag))
+    })
+    .await?
+  }
+}
+
 async fn insert_cache_asset(
   db: Arc<Mutex<Connection>>,
   put: CachePutRequest,
   response_body_key: Option<String>,
+  response_body_etag: Option<String>,
 ) -> Result<Option<String>, CacheError> {
   spawn_blocking(move || {
     let maybe_response_body = {
@@ -336,8 +433,9 @@ async fn insert_cache_asset(
       db.query_row(
         "INSERT OR REPLACE INTO request_response_list
              (cache_id, request_url, request_headers, response_headers,
-              response_body_key, response_status, response_status_text, last_inserted_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+              response_body_key, response_status, response_status_text, last_inserted_at,
+              response_body_etag)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
              RETURNING response_body_key",
         (
           put.cache_id,
@@ -348,6 +446,7 @@ async fn insert_cache_asset(
           put.response_status,
           put.response_status_text,
           SystemTime::now().duration_since(UNIX_EPOCH).expect("SystemTime is before unix epoch").as_secs(),
+          response_body_etag,
         ),
         |row| {
           let response_body_key: Option<String> = row.get(0)?;
@@ -400,6 +499,137 @@ impl Resource for CacheResponseResource {
 }
 
 pub fn hash(token: &str) -> String {
-  use sha2::Digest;
   format!("{:x}", sha2::Sha256::digest(token.as_bytes()))
 }
+
+/// Format a body digest as a strong ETag.
+fn format_etag(hasher: sha2::Sha256) -> String {
+  format!("\"{:x}\"", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::RefCell;
+
+  use deno_core::BufView;
+
+  use super::*;
+
+  struct TestBody(RefCell<Option<Vec<u8>>>);
+
+  impl Resource for TestBody {
+    fn name(&self) -> Cow<str> {
+      "testBody".into()
+    }
+
+    fn read(self: Rc<Self>, _limit: usize) -> AsyncResult<BufView> {
+      let chunk = self.0.borrow_mut().take().unwrap_or_default();
+      Box::pin(async move { Ok(BufView::from(chunk)) })
+    }
+  }
+
+  /// The cache is backed by the returned directory, which is removed when
+  /// it is dropped.
+  fn test_cache() -> (tempfile::TempDir, SqliteBackedCache) {
+    let dir = tempfile::TempDir::new().unwrap();
+    let cache = SqliteBackedCache::new(dir.path().to_path_buf());
+    (dir, cache)
+  }
+
+  async fn put(
+    cache: &SqliteBackedCache,
+    cache_id: i64,
+    body: &[u8],
+  ) -> String {
+    let request = CachePutRequest {
+      cache_id,
+      request_url: "https://deno.land/".to_string(),
+      request_headers: vec![],
+      response_headers: vec![],
+      response_status: 200,
+      response_status_text: "OK".to_string(),
+      response_rid: None,
+    };
+    let body = Rc::new(TestBody(RefCell::new(Some(body.to_vec()))));
+    cache.put(request, Some(body)).await.unwrap().unwrap()
+  }
+
+  async fn conditional_match(
+    cache: &SqliteBackedCache,
+    cache_id: i64,
+    if_none_match: &str,
+  ) -> (CacheMatchResponseMeta, Option<CacheResponseResource>) {
+    cache
+      .r#match(CacheMatchRequest {
+        cache_id,
+        request_url: "https://deno.land/".to_string(),
+        request_headers: vec![],
+        if_none_match: Some(if_none_match.to_string()),
+      })
+      .await
+      .unwrap()
+      .unwrap()
+  }
+
+  fn body_reads(cache: &SqliteBackedCache) -> u64 {
+    cache.body_reads.load(Ordering::Relaxed)
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn not_modified_skips_body_read() {
+    let (_dir, cache) = test_cache();
+    let cache_id = cache.storage_open("v1".to_string()).await.unwrap();
+    let etag = put(&cache, cache_id, b"hello world").await;
+    assert_eq!(
+      etag,
+      format!("\"{:x}\"", sha2::Sha256::digest(b"hello world"))
+    );
+
+    let (meta, body) = conditional_match(&cache, cache_id, &etag).await;
+    assert!(meta.not_modified);
+    assert_eq!(meta.response_etag.as_deref(), Some(etag.as_str()));
+    assert!(body.is_none());
+    assert_eq!(body_reads(&cache), 0);
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn mismatched_etag_streams_body() {
+    let (_dir, cache) = test_cache();
+    let cache_id = cache.storage_open("v1".to_string()).await.unwrap();
+    let etag = put(&cache, cache_id, b"hello world").await;
+
+    let (meta, body) = conditional_match(&cache, cache_id, "\"stale\"").await;
+    assert!(!meta.not_modified);
+    assert_eq!(meta.response_etag.as_deref(), Some(etag.as_str()));
+    assert_eq!(body_reads(&cache), 1);
+    let body = Rc::new(body.unwrap());
+    let mut buf = [0; 64];
+    let nread = body.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..nread], b"hello world");
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn missing_etag_is_backfilled_on_first_match() {
+    let (_dir, cache) = test_cache();
+    let cache_id = cache.storage_open("v1".to_string()).await.unwrap();
+    let etag = put(&cache, cache_id, b"hello world").await;
+    // Simulate a row written before ETags existed.
+    cache
+      .connection
+      .lock()
+      .execute(
+        "UPDATE request_response_list SET response_body_etag = NULL",
+        (),
+      )
+      .unwrap();
+
+    let (meta, body) = conditional_match(&cache, cache_id, &etag).await;
+    assert!(meta.not_modified);
+    assert!(body.is_none());
+    assert_eq!(body_reads(&cache), 1);
+
+    let (meta, _) = conditional_match(&cache, cache_id, &etag).await;
+    assert!(meta.not_modified);
+    assert_eq!(body_reads(&cache), 1);
+  }
+}
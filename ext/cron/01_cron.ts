@@ -4,7 +4,13 @@ import { core, internals, primordials } from "ext:core/mod.js";
 const {
   isPromise,
 } = core;
-import { op_cron_create, op_cron_next } from "ext:core/ops";
+import {
+  op_cron_create,
+  op_cron_next,
+  op_schedule_ack,
+  op_schedule_create,
+  op_schedule_next,
+} from "ext:core/ops";
 const {
   ArrayPrototypeJoin,
   NumberPrototypeToString,
@@ -173,8 +179,89 @@ function cron(
   })();
 }
 
+function schedule(
+  name: string,
+  spec: string | Deno.CronSchedule | { every: number },
+  handlerOrOptions1:
+    | ((info: { scheduledTime: Date }) => Promise<void> | void)
+    | Deno.ScheduleOptions,
+  handler2?: (info: { scheduledTime: Date }) => Promise<void> | void,
+) {
+  if (name === undefined) {
+    throw new TypeError(
+      "Cannot create schedule, a unique name is required: received 'undefined'",
+    );
+  }
+  if (spec === undefined) {
+    throw new TypeError(
+      "Cannot create schedule, a schedule is required: received 'undefined'",
+    );
+  }
+
+  let handler: (info: { scheduledTime: Date }) => Promise<void> | void;
+  let options: Deno.ScheduleOptions | undefined = undefined;
+  if (typeof handlerOrOptions1 === "function") {
+    handler = handlerOrOptions1;
+    if (handler2 !== undefined) {
+      throw new TypeError(
+        "Cannot create schedule, a single handler is required: two handlers were specified",
+      );
+    }
+  } else if (typeof handler2 === "function") {
+    handler = handler2;
+    options = handlerOrOptions1;
+  } else {
+    throw new TypeError("Cannot create schedule: a handler is required");
+  }
+
+  const every = typeof spec === "object"
+    ? (spec as { every?: number }).every
+    : undefined;
+  const rid = op_schedule_create(
+    name,
+    every !== undefined
+      ? { interval: every }
+      : { cron: parseScheduleToString(spec as string | Deno.CronSchedule) },
+    {
+      overlapPolicy: options?.overlap ?? "skip",
+      catchUp: options?.catchUp ?? "none",
+    },
+  );
+  const concurrent = options?.overlap === "concurrent";
+
+  if (options?.signal) {
+    const signal = options.signal;
+    signal.addEventListener("abort", () => core.close(rid), { once: true });
+  }
+
+  const run = async (occurrence: number) => {
+    try {
+      const result = handler({ scheduledTime: new Date(occurrence) });
+      const _res = isPromise(result) ? (await result) : result;
+    } catch (error) {
+      // deno-lint-ignore no-console
+      console.error(`Exception in schedule handler ${name}`, error);
+    }
+    // A run counts as done whether the handler succeeded or not.
+    await op_schedule_ack(rid, occurrence);
+  };
+
+  return (async () => {
+    while (true) {
+      const occurrence = await op_schedule_next(rid);
+      if (occurrence === null) {
+        break;
+      }
+      const done = run(occurrence);
+      if (!concurrent) {
+        await done;
+      }
+    }
+  })();
+}
+
 // For testing
 internals.formatToCronSchedule = formatToCronSchedule;
 internals.parseScheduleToString = parseScheduleToString;
 
-export { cron };
+export { cron, schedule };
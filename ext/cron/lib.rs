@@ -2,9 +2,11 @@
 
 mod interface;
 pub mod local;
+pub mod schedule;
 
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 pub use crate::interface::*;
@@ -14,6 +16,11 @@ use deno_core::OpState;
 use deno_core::Resource;
 use deno_core::ResourceId;
 
+use crate::schedule::ScheduleOptions;
+use crate::schedule::ScheduleSpec;
+use crate::schedule::ScheduleStore;
+use crate::schedule::ScheduledJob;
+
 pub const UNSTABLE_FEATURE_NAME: &str = "cron";
 
 deno_core::extension!(deno_cron,
@@ -22,13 +29,18 @@ deno_core::extension!(deno_cron,
   ops = [
     op_cron_create<C>,
     op_cron_next<C>,
+    op_schedule_create,
+    op_schedule_next,
+    op_schedule_ack,
   ],
   esm = [ "01_cron.ts" ],
   options = {
     cron_handler: C,
+    schedule_state_path: Option<PathBuf>,
   },
   state = |state, options| {
     state.put(Rc::new(options.cron_handler));
+    state.put(Rc::new(ScheduleStore::new(options.schedule_state_path)));
   }
 );
 
@@ -62,6 +74,10 @@ pub enum CronError {
   InvalidCron,
   #[error("Invalid backoff schedule")]
   InvalidBackoff,
+  #[error("Invalid schedule interval: must be at least 1000 milliseconds")]
+  InvalidInterval,
+  #[error("Failed to access the schedule state file: {0}")]
+  StateFile(std::io::Error),
   #[error(transparent)]
   AcquireError(#[from] tokio::sync::AcquireError),
   #[error(transparent)]
@@ -131,6 +147,81 @@ where
   cron_handler.next(prev_success).await
 }
 
+struct ScheduleResource {
+  job: Rc<ScheduledJob>,
+}
+
+impl Resource for ScheduleResource {
+  fn name(&self) -> Cow<str> {
+    "schedule".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.job.close();
+  }
+}
+
+#[op2]
+#[smi]
+fn op_schedule_create(
+  state: &mut OpState,
+  #[string] name: String,
+  #[serde] spec: ScheduleSpec,
+  #[serde] options: Option<ScheduleOptions>,
+) -> Result<ResourceId, CronError> {
+  state
+    .feature_checker
+    .check_or_exit(UNSTABLE_FEATURE_NAME, "Deno.schedule");
+  validate_cron_name(&name)?;
+  let job = ScheduledJob::create(
+    state.borrow::<Rc<ScheduleStore>>().clone(),
+    deno_web::clock(state),
+    name,
+    &spec,
+    options.unwrap_or_default(),
+  )?;
+  Ok(
+    state
+      .resource_table
+      .add(ScheduleResource { job: Rc::new(job) }),
+  )
+}
+
+/// Resolves with the time of the next occurrence, in milliseconds since the
+/// epoch, or `null` once the schedule is closed.
+#[op2(async)]
+#[serde]
+async fn op_schedule_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<f64>, CronError> {
+  let Ok(resource) = state.borrow().resource_table.get::<ScheduleResource>(rid)
+  else {
+    return Ok(None);
+  };
+  let occurrence = resource.job.next().await;
+  Ok(occurrence.map(|time| time.timestamp_millis() as f64))
+}
+
+#[op2(async)]
+async fn op_schedule_ack(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  occurrence: f64,
+) -> Result<(), CronError> {
+  // Runs that finish after the schedule was closed aren't recorded.
+  let Ok(resource) = state.borrow().resource_table.get::<ScheduleResource>(rid)
+  else {
+    return Ok(());
+  };
+  let Some(save) = resource.job.ack(occurrence as i64) else {
+    return Ok(());
+  };
+  deno_core::unsync::spawn_blocking(save)
+    .await
+    .map_err(|err| CronError::Other(err.into()))?
+}
+
 fn validate_cron_name(name: &str) -> Result<(), CronError> {
   if name.len() > 64 {
     return Err(CronError::NameExceeded(name.len()));
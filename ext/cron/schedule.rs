@@ -0,0 +1,759 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! In-process schedules for `Deno.schedule()`.
+//!
+//! Unlike `Deno.cron()`, which hands jobs to a `CronHandler`, a schedule is
+//! driven entirely from JS: `op_schedule_next` resolves with the time of the
+//! next occurrence once it is due, and `op_schedule_ack` records that the
+//! run finished. Time is read and slept through the `Clock` in the
+//! `OpState`, so tests can run schedules on a virtual clock.
+//!
+//! The last completed occurrence of each schedule is kept in a small JSON
+//! state file, so that after a restart the catch-up policy can decide which
+//! occurrences were missed while the process was down. Cron expressions are
+//! evaluated in UTC.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use chrono::DateTime;
+use chrono::TimeDelta;
+use chrono::Utc;
+use deno_web::SharedClock;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::CronError;
+
+/// Most occurrences that are run back to back when a schedule is behind,
+/// either after a restart or because runs take longer than the interval.
+/// Older ones are dropped.
+pub const MAX_PENDING_RUNS: usize = 16;
+
+const MIN_INTERVAL_MS: u64 = 1_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScheduleSpec {
+  /// A cron expression, evaluated in UTC.
+  Cron(String),
+  /// A fixed interval in milliseconds.
+  Interval(u64),
+}
+
+/// What happens to occurrences that come due while a run is in progress.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlapPolicy {
+  /// Drop them.
+  #[default]
+  Skip,
+  /// Run them one after another once the current run finishes.
+  Queue,
+  /// Run them right away, next to the current run.
+  Concurrent,
+}
+
+/// Which occurrences that were missed while the process wasn't running are
+/// run on startup.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CatchUpPolicy {
+  #[default]
+  None,
+  /// Run the most recent missed occurrence.
+  Latest,
+  /// Run every missed occurrence, up to `MAX_PENDING_RUNS`.
+  All,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleOptions {
+  #[serde(default)]
+  pub overlap_policy: OverlapPolicy,
+  #[serde(default)]
+  pub catch_up: CatchUpPolicy,
+}
+
+enum Schedule {
+  Cron(saffron::Cron),
+  /// Occurrences at `anchor + n * every`.
+  Interval {
+    anchor: DateTime<Utc>,
+    every: TimeDelta,
+  },
+}
+
+impl Schedule {
+  fn new(
+    spec: &ScheduleSpec,
+    anchor: DateTime<Utc>,
+  ) -> Result<Self, CronError> {
+    match spec {
+      ScheduleSpec::Cron(expression) => expression
+        .parse::<saffron::Cron>()
+        .map(Schedule::Cron)
+        .map_err(|_| CronError::InvalidCron),
+      ScheduleSpec::Interval(ms) => {
+        if *ms < MIN_INTERVAL_MS || *ms > i64::MAX as u64 {
+          return Err(CronError::InvalidInterval);
+        }
+        Ok(Schedule::Interval {
+          anchor,
+          every: TimeDelta::milliseconds(*ms as i64),
+        })
+      }
+    }
+  }
+
+  /// The first occurrence strictly after `time`.
+  fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match self {
+      Schedule::Cron(cron) => cron.next_after(time),
+      Schedule::Interval { anchor, every } => {
+        if time < *anchor {
+          return Some(*anchor);
+        }
+        let elapsed = (time - *anchor).num_milliseconds();
+        let every_ms = every.num_milliseconds();
+        let n = elapsed / every_ms + 1;
+        anchor.checked_add_signed(TimeDelta::milliseconds(n * every_ms))
+      }
+    }
+  }
+
+  /// The last `limit` occurrences in `(after, until]`, oldest first.
+  fn occurrences(
+    &self,
+    after: DateTime<Utc>,
+    until: DateTime<Utc>,
+    limit: usize,
+  ) -> VecDeque<DateTime<Utc>> {
+    let mut cursor = after;
+    if let Schedule::Interval { every, .. } = self {
+      // Skip ahead instead of stepping through a long downtime.
+      let start = every
+        .checked_mul(limit as i32)
+        .and_then(|window| until.checked_sub_signed(window));
+      if let Some(start) = start {
+        cursor = cursor.max(start);
+      }
+    }
+    let mut occurrences = VecDeque::with_capacity(limit);
+    while let Some(next) = self.next_after(cursor) {
+      if next > until {
+        break;
+      }
+      if occurrences.len() == limit {
+        occurrences.pop_front();
+      }
+      occurrences.push_back(next);
+      cursor = next;
+    }
+    occurrences
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduleRecord {
+  /// When the schedule was first created, in milliseconds since the epoch.
+  /// Intervals are counted from here.
+  created: i64,
+  /// The last occurrence whose run finished.
+  last_run: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StateFile {
+  schedules: HashMap<String, ScheduleRecord>,
+}
+
+/// The schedules of a runtime and the file their state is kept in. Without a
+/// path, state is only kept in memory and nothing is caught up on restart.
+pub struct ScheduleStore {
+  path: Option<PathBuf>,
+  active: RefCell<HashSet<String>>,
+  // Serializes writes from concurrent acks, which run on blocking threads.
+  write_lock: Arc<Mutex<()>>,
+}
+
+impl ScheduleStore {
+  pub fn new(path: Option<PathBuf>) -> Self {
+    Self {
+      path,
+      active: Default::default(),
+      write_lock: Default::default(),
+    }
+  }
+
+  fn load(&self, name: &str) -> Result<Option<ScheduleRecord>, CronError> {
+    let Some(path) = &self.path else {
+      return Ok(None);
+    };
+    Ok(read_state_file(path)?.schedules.get(name).copied())
+  }
+
+  /// Returns a closure that writes the record, to be run on a blocking
+  /// thread.
+  fn saver(
+    &self,
+    name: String,
+    record: ScheduleRecord,
+  ) -> impl FnOnce() -> Result<(), CronError> + Send + 'static {
+    let path = self.path.clone();
+    let write_lock = self.write_lock.clone();
+    move || {
+      let Some(path) = path else {
+        return Ok(());
+      };
+      let _guard = write_lock.lock().unwrap();
+      // Other runtimes may share the file, so only this entry is replaced.
+      let mut state = read_state_file(&path)?;
+      let entry = state.schedules.entry(name).or_insert(record);
+      entry.last_run = entry.last_run.max(record.last_run);
+      write_state_file(&path, &state)
+    }
+  }
+}
+
+fn read_state_file(path: &Path) -> Result<StateFile, CronError> {
+  match std::fs::read(path) {
+    Ok(data) if data.is_empty() => Ok(StateFile::default()),
+    Ok(data) => serde_json::from_slice(&data).map_err(|err| {
+      CronError::StateFile(io::Error::new(io::ErrorKind::InvalidData, err))
+    }),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+      Ok(StateFile::default())
+    }
+    Err(err) => Err(CronError::StateFile(err)),
+  }
+}
+
+fn write_state_file(path: &Path, state: &StateFile) -> Result<(), CronError> {
+  let write = || -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    let data = serde_json::to_vec_pretty(state)?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", std::process::id()));
+    let mut file = std::fs::File::create(&temp_path)?;
+    file.write_all(&data)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&temp_path, path)
+  };
+  write().map_err(CronError::StateFile)
+}
+
+fn to_millis(time: DateTime<Utc>) -> i64 {
+  time.timestamp_millis()
+}
+
+fn from_millis(ms: i64) -> DateTime<Utc> {
+  DateTime::from_timestamp_millis(ms).unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+struct JobState {
+  /// Live occurrences at or before this time have been handled.
+  cursor: DateTime<Utc>,
+  /// Occurrences due to be run before the live ones.
+  backlog: VecDeque<DateTime<Utc>>,
+  /// Occurrences handed out by `next` and not acknowledged yet.
+  running: BTreeSet<i64>,
+  last_run: Option<i64>,
+  closed: bool,
+}
+
+pub struct ScheduledJob {
+  name: String,
+  created: i64,
+  schedule: Schedule,
+  overlap_policy: OverlapPolicy,
+  clock: SharedClock,
+  store: Rc<ScheduleStore>,
+  state: RefCell<JobState>,
+  changed: Notify,
+}
+
+/// What [`ScheduledJob::ack`] needs written to the state file.
+pub type PendingSave = Box<dyn FnOnce() -> Result<(), CronError> + Send>;
+
+impl ScheduledJob {
+  /// Registers a schedule and works out which missed occurrences to catch
+  /// up on. Writes the state file when the schedule is new.
+  pub fn create(
+    store: Rc<ScheduleStore>,
+    clock: SharedClock,
+    name: String,
+    spec: &ScheduleSpec,
+    options: ScheduleOptions,
+  ) -> Result<Self, CronError> {
+    if store.active.borrow().contains(&name) {
+      return Err(CronError::AlreadyExists);
+    }
+    let now = DateTime::<Utc>::from(clock.now());
+    let stored = store.load(&name)?;
+    let record = stored.unwrap_or(ScheduleRecord {
+      created: to_millis(now),
+      last_run: None,
+    });
+    let schedule = Schedule::new(spec, from_millis(record.created))?;
+    if stored.is_none() {
+      store.saver(name.clone(), record)()?;
+    }
+
+    // Occurrences after the last finished run, or after the schedule was
+    // created if it never ran, were missed.
+    let missed_since = from_millis(record.last_run.unwrap_or(record.created));
+    let backlog = match options.catch_up {
+      CatchUpPolicy::None => VecDeque::new(),
+      CatchUpPolicy::Latest => schedule.occurrences(missed_since, now, 1),
+      CatchUpPolicy::All => {
+        schedule.occurrences(missed_since, now, MAX_PENDING_RUNS)
+      }
+    };
+
+    store.active.borrow_mut().insert(name.clone());
+    Ok(Self {
+      name,
+      created: record.created,
+      schedule,
+      overlap_policy: options.overlap_policy,
+      clock,
+      store,
+      state: RefCell::new(JobState {
+        cursor: now,
+        backlog,
+        running: BTreeSet::new(),
+        last_run: record.last_run,
+        closed: false,
+      }),
+      changed: Notify::new(),
+    })
+  }
+
+  fn now(&self) -> DateTime<Utc> {
+    DateTime::from(self.clock.now())
+  }
+
+  /// Waits until an occurrence should run and returns it, or `None` once the
+  /// schedule is closed or has no further occurrences.
+  pub async fn next(&self) -> Option<DateTime<Utc>> {
+    loop {
+      let changed = self.changed.notified();
+      let delay = {
+        let mut state = self.state.borrow_mut();
+        if state.closed {
+          return None;
+        }
+        if self.overlap_policy != OverlapPolicy::Concurrent
+          && !state.running.is_empty()
+        {
+          None
+        } else {
+          let now = self.now();
+          if state.backlog.is_empty() {
+            let next = self.schedule.next_after(state.cursor)?;
+            if next <= now {
+              // Several occurrences are due if runs overran or the process
+              // was suspended. Skipping keeps only the latest.
+              let limit = match self.overlap_policy {
+                OverlapPolicy::Skip => 1,
+                _ => MAX_PENDING_RUNS,
+              };
+              state.backlog =
+                self.schedule.occurrences(state.cursor, now, limit);
+              state.cursor = now;
+            }
+          }
+          match state.backlog.pop_front() {
+            Some(occurrence) => {
+              state.running.insert(to_millis(occurrence));
+              return Some(occurrence);
+            }
+            None => {
+              let next = self.schedule.next_after(state.cursor)?;
+              Some((next - now).to_std().unwrap_or_default())
+            }
+          }
+        }
+      };
+      match delay {
+        Some(delay) => {
+          tokio::select! {
+            _ = self.clock.sleep(delay) => {}
+            _ = changed => {}
+          }
+        }
+        None => changed.await,
+      }
+    }
+  }
+
+  /// Records that the run of `occurrence` finished. Returns the write of the
+  /// state file if the last run moved forward.
+  pub fn ack(&self, occurrence: i64) -> Option<PendingSave> {
+    let mut state = self.state.borrow_mut();
+    if !state.running.remove(&occurrence) {
+      return None;
+    }
+    if self.overlap_policy == OverlapPolicy::Skip {
+      // Drop what came due during the run.
+      state.cursor = state.cursor.max(self.now());
+    }
+    self.changed.notify_waiters();
+    if state.last_run >= Some(occurrence) {
+      return None;
+    }
+    state.last_run = Some(occurrence);
+    let record = ScheduleRecord {
+      created: self.created,
+      last_run: Some(occurrence),
+    };
+    Some(Box::new(self.store.saver(self.name.clone(), record)))
+  }
+
+  pub fn close(&self) {
+    let mut state = self.state.borrow_mut();
+    if !state.closed {
+      state.closed = true;
+      self.store.active.borrow_mut().remove(&self.name);
+      self.changed.notify_waiters();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use std::time::SystemTime;
+
+  use deno_core::futures::future::LocalBoxFuture;
+  use deno_core::futures::FutureExt;
+  use deno_web::Clock;
+
+  use super::*;
+
+  /// Wall-clock time that moves with tokio's paused test time.
+  struct VirtualClock {
+    base: SystemTime,
+    start: tokio::time::Instant,
+  }
+
+  impl VirtualClock {
+    fn at(time: &str) -> Rc<Self> {
+      Rc::new(Self {
+        base: date(time).into(),
+        start: tokio::time::Instant::now(),
+      })
+    }
+  }
+
+  impl Clock for VirtualClock {
+    fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()> {
+      tokio::time::sleep(duration).boxed_local()
+    }
+
+    fn now(&self) -> SystemTime {
+      self.base + self.start.elapsed()
+    }
+  }
+
+  fn date(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().into()
+  }
+
+  fn cron(expression: &str) -> Schedule {
+    Schedule::new(&ScheduleSpec::Cron(expression.into()), Utc::now()).unwrap()
+  }
+
+  fn create(
+    store: &Rc<ScheduleStore>,
+    clock: &Rc<VirtualClock>,
+    spec: ScheduleSpec,
+    overlap_policy: OverlapPolicy,
+    catch_up: CatchUpPolicy,
+  ) -> ScheduledJob {
+    ScheduledJob::create(
+      store.clone(),
+      clock.clone(),
+      "job".into(),
+      &spec,
+      ScheduleOptions {
+        overlap_policy,
+        catch_up,
+      },
+    )
+    .unwrap()
+  }
+
+  fn ack(job: &ScheduledJob, occurrence: DateTime<Utc>) {
+    if let Some(save) = job.ack(to_millis(occurrence)) {
+      save().unwrap();
+    }
+  }
+
+  #[test]
+  fn cron_end_of_month() {
+    let schedule = cron("0 0 31 * *");
+    // February, April and June have no 31st.
+    assert_eq!(
+      schedule.next_after(date("2024-01-31T00:00:00Z")),
+      Some(date("2024-03-31T00:00:00Z"))
+    );
+    assert_eq!(
+      schedule.next_after(date("2024-03-31T00:00:00Z")),
+      Some(date("2024-05-31T00:00:00Z"))
+    );
+    let schedule = cron("30 12 29 2 *");
+    assert_eq!(
+      schedule.next_after(date("2024-03-01T00:00:00Z")),
+      Some(date("2028-02-29T12:30:00Z"))
+    );
+  }
+
+  #[test]
+  fn cron_is_unaffected_by_dst() {
+    // Schedules are in UTC, so a daily job stays 24 hours apart across the
+    // days on which local clocks change.
+    let schedule = cron("30 2 * * *");
+    for (before, after) in [
+      ("2024-03-10T02:30:00Z", "2024-03-11T02:30:00Z"),
+      ("2024-03-31T02:30:00Z", "2024-04-01T02:30:00Z"),
+      ("2024-10-27T02:30:00Z", "2024-10-28T02:30:00Z"),
+      ("2024-11-03T02:30:00Z", "2024-11-04T02:30:00Z"),
+    ] {
+      assert_eq!(schedule.next_after(date(before)), Some(date(after)));
+    }
+  }
+
+  #[test]
+  fn invalid_specs() {
+    let now = Utc::now();
+    for spec in [
+      ScheduleSpec::Cron("bogus".into()),
+      ScheduleSpec::Cron("* * *".into()),
+      ScheduleSpec::Cron("61 * * * *".into()),
+      ScheduleSpec::Interval(0),
+      ScheduleSpec::Interval(999),
+    ] {
+      assert!(Schedule::new(&spec, now).is_err(), "{spec:?}");
+    }
+  }
+
+  #[test]
+  fn interval_is_anchored() {
+    let schedule = Schedule::new(
+      &ScheduleSpec::Interval(60_000),
+      date("2024-01-01T00:00:30Z"),
+    )
+    .unwrap();
+    assert_eq!(
+      schedule.next_after(date("2024-01-01T00:05:00Z")),
+      Some(date("2024-01-01T00:05:30Z"))
+    );
+    assert_eq!(
+      schedule.next_after(date("2024-01-01T00:05:30Z")),
+      Some(date("2024-01-01T00:06:30Z"))
+    );
+    let occurrences = schedule.occurrences(
+      date("2024-01-01T00:00:00Z"),
+      date("2025-01-01T00:00:00Z"),
+      2,
+    );
+    assert_eq!(
+      occurrences,
+      [date("2024-12-31T23:58:30Z"), date("2024-12-31T23:59:30Z")]
+    );
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn runs_on_schedule() {
+    let clock = VirtualClock::at("2024-01-01T00:00:30Z");
+    let store = Rc::new(ScheduleStore::new(None));
+    let job = create(
+      &store,
+      &clock,
+      ScheduleSpec::Cron("* * * * *".into()),
+      OverlapPolicy::Skip,
+      CatchUpPolicy::None,
+    );
+    let first = job.next().await.unwrap();
+    assert_eq!(first, date("2024-01-01T00:01:00Z"));
+    assert_eq!(job.now(), first);
+    ack(&job, first);
+    assert_eq!(job.next().await, Some(date("2024-01-01T00:02:00Z")));
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn skip_drops_overlapping_occurrences() {
+    let clock = VirtualClock::at("2024-01-01T00:00:00Z");
+    let store = Rc::new(ScheduleStore::new(None));
+    let job = create(
+      &store,
+      &clock,
+      ScheduleSpec::Interval(10_000),
+      OverlapPolicy::Skip,
+      CatchUpPolicy::None,
+    );
+    let first = job.next().await.unwrap();
+    assert_eq!(first, date("2024-01-01T00:00:10Z"));
+    // The run takes 25 seconds, over two further occurrences.
+    tokio::time::sleep(Duration::from_secs(25)).await;
+    ack(&job, first);
+    assert_eq!(job.next().await, Some(date("2024-01-01T00:00:40Z")));
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn queue_runs_overlapping_occurrences_after() {
+    let clock = VirtualClock::at("2024-01-01T00:00:00Z");
+    let store = Rc::new(ScheduleStore::new(None));
+    let job = Rc::new(create(
+      &store,
+      &clock,
+      ScheduleSpec::Interval(10_000),
+      OverlapPolicy::Queue,
+      CatchUpPolicy::None,
+    ));
+    let first = job.next().await.unwrap();
+    // Nothing is handed out while the first run is in progress.
+    let waiting = Rc::new(std::cell::Cell::new(true));
+    let second = deno_core::unsync::spawn({
+      let job = job.clone();
+      let waiting = waiting.clone();
+      async move {
+        let next = job.next().await;
+        waiting.set(false);
+        next
+      }
+    });
+    tokio::time::sleep(Duration::from_secs(25)).await;
+    assert!(waiting.get());
+    ack(&job, first);
+    let second = second.await.unwrap().unwrap();
+    assert_eq!(second, date("2024-01-01T00:00:20Z"));
+    ack(&job, second);
+    let third = job.next().await.unwrap();
+    assert_eq!(third, date("2024-01-01T00:00:30Z"));
+    assert_eq!(job.now(), date("2024-01-01T00:00:35Z"));
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn concurrent_runs_on_time() {
+    let clock = VirtualClock::at("2024-01-01T00:00:00Z");
+    let store = Rc::new(ScheduleStore::new(None));
+    let job = create(
+      &store,
+      &clock,
+      ScheduleSpec::Interval(10_000),
+      OverlapPolicy::Concurrent,
+      CatchUpPolicy::None,
+    );
+    let first = job.next().await.unwrap();
+    let second = job.next().await.unwrap();
+    assert_eq!(second - first, TimeDelta::seconds(10));
+    assert_eq!(job.now(), second);
+    // Acknowledged out of order, the last run doesn't move back.
+    ack(&job, second);
+    assert!(job.ack(to_millis(first)).is_none());
+    assert_eq!(job.state.borrow().last_run, Some(to_millis(second)));
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn close_wakes_next() {
+    let clock = VirtualClock::at("2024-01-01T00:00:00Z");
+    let store = Rc::new(ScheduleStore::new(None));
+    let job = Rc::new(create(
+      &store,
+      &clock,
+      ScheduleSpec::Cron("0 0 1 1 *".into()),
+      OverlapPolicy::Skip,
+      CatchUpPolicy::None,
+    ));
+    let next = deno_core::unsync::spawn({
+      let job = job.clone();
+      async move { job.next().await }
+    });
+    tokio::task::yield_now().await;
+    job.close();
+    assert_eq!(next.await.unwrap(), None);
+    // The name can be used again.
+    create(
+      &store,
+      &clock,
+      ScheduleSpec::Interval(1_000),
+      OverlapPolicy::Skip,
+      CatchUpPolicy::None,
+    );
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn catches_up_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("schedules.json");
+    let spec = ScheduleSpec::Cron("0 * * * *".into());
+
+    let run = |start: &str, catch_up| {
+      let store = Rc::new(ScheduleStore::new(Some(path.clone())));
+      let job = create(
+        &store,
+        &VirtualClock::at(start),
+        spec.clone(),
+        OverlapPolicy::Skip,
+        catch_up,
+      );
+      job.state.borrow().backlog.clone()
+    };
+
+    // The first start records when the schedule was created.
+    assert!(run("2024-01-01T00:30:00Z", CatchUpPolicy::All).is_empty());
+    // Restarted after 01:00, 02:00 and 03:00 were missed.
+    assert!(run("2024-01-01T03:10:00Z", CatchUpPolicy::None).is_empty());
+    assert_eq!(
+      run("2024-01-01T03:10:00Z", CatchUpPolicy::Latest),
+      [date("2024-01-01T03:00:00Z")]
+    );
+    assert_eq!(
+      run("2024-01-01T03:10:00Z", CatchUpPolicy::All),
+      [
+        date("2024-01-01T01:00:00Z"),
+        date("2024-01-01T02:00:00Z"),
+        date("2024-01-01T03:00:00Z"),
+      ]
+    );
+
+    // Finished runs aren't run again.
+    let store = Rc::new(ScheduleStore::new(Some(path.clone())));
+    let job = create(
+      &store,
+      &VirtualClock::at("2024-01-01T03:10:00Z"),
+      spec.clone(),
+      OverlapPolicy::Skip,
+      CatchUpPolicy::All,
+    );
+    for hour in ["01", "02"] {
+      let occurrence = job.next().await.unwrap();
+      assert_eq!(occurrence, date(&format!("2024-01-01T{hour}:00:00Z")));
+      ack(&job, occurrence);
+    }
+    drop(job);
+    assert_eq!(
+      run("2024-01-01T03:20:00Z", CatchUpPolicy::All),
+      [date("2024-01-01T03:00:00Z")]
+    );
+  }
+}
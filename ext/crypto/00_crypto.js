@@ -40,6 +40,8 @@ import {
   op_crypto_import_spki_x25519,
   op_crypto_import_spki_x448,
   op_crypto_jwk_x_ed25519,
+  op_crypto_password_unwrap,
+  op_crypto_password_wrap,
   op_crypto_random_uuid,
   op_crypto_sign_ed25519,
   op_crypto_sign_key,
@@ -48,6 +50,10 @@ import {
   op_crypto_verify_ed25519,
   op_crypto_verify_key,
   op_crypto_wrap_key,
+  op_ssh_parse_private_key,
+  op_ssh_parse_public_key,
+  op_ssh_sign,
+  op_ssh_verify_signature,
 } from "ext:core/ops";
 const {
   ArrayBufferIsView,
@@ -73,6 +79,7 @@ const {
   StringPrototypeToLowerCase,
   StringPrototypeToUpperCase,
   Symbol,
+  SymbolDispose,
   SymbolFor,
   SyntaxError,
   TypeError,
@@ -740,10 +747,10 @@ class SubtleCrypto {
 
         // 3. We only support 96-bit and 128-bit nonce.
         if (
-          ArrayPrototypeIncludes(
+          !ArrayPrototypeIncludes(
             [12, 16],
             TypedArrayPrototypeGetByteLength(normalizedAlgorithm.iv),
-          ) === undefined
+          )
         ) {
           throw new DOMException(
             "Initialization vector length not supported",
@@ -1024,47 +1031,41 @@ class SubtleCrypto {
 
     const algorithmName = key[_algorithm].name;
 
-    let result;
-
+    // 5.
+    let exportKeyAlgorithm;
     switch (algorithmName) {
-      case "HMAC": {
-        result = exportKeyHMAC(format, key, innerKey);
+      case "HMAC":
+        exportKeyAlgorithm = exportKeyHMAC;
         break;
-      }
       case "RSASSA-PKCS1-v1_5":
       case "RSA-PSS":
-      case "RSA-OAEP": {
-        result = exportKeyRSA(format, key, innerKey);
+      case "RSA-OAEP":
+        exportKeyAlgorithm = exportKeyRSA;
         break;
-      }
       case "ECDH":
-      case "ECDSA": {
-        result = exportKeyEC(format, key, innerKey);
+      case "ECDSA":
+        exportKeyAlgorithm = exportKeyEC;
         break;
-      }
-      case "Ed25519": {
-        result = exportKeyEd25519(format, key, innerKey);
+      case "Ed25519":
+        exportKeyAlgorithm = exportKeyEd25519;
         break;
-      }
-      case "X448": {
-        result = exportKeyX448(format, key, innerKey);
+      case "X448":
+        exportKeyAlgorithm = exportKeyX448;
         break;
-      }
-      case "X25519": {
-        result = exportKeyX25519(format, key, innerKey);
+      case "X25519":
+        exportKeyAlgorithm = exportKeyX25519;
         break;
-      }
       case "AES-CTR":
       case "AES-CBC":
       case "AES-GCM":
-      case "AES-KW": {
-        result = exportKeyAES(format, key, innerKey);
+      case "AES-KW":
+        exportKeyAlgorithm = exportKeyAES;
         break;
-      }
       default:
         throw new DOMException("Not implemented", "NotSupportedError");
     }
 
+    // 6.
     if (key.extractable === false) {
       throw new DOMException(
         "Key is not extractable",
@@ -1072,7 +1073,8 @@ class SubtleCrypto {
       );
     }
 
-    return result;
+    // 7.
+    return exportKeyAlgorithm(format, key, innerKey);
   }
 
   /**
@@ -3481,6 +3483,14 @@ function importKeyEC(
         }
       }
 
+      // 9.2.
+      if (jwk.crv !== normalizedAlgorithm.namedCurve) {
+        throw new DOMException(
+          "'crv' property of JsonWebKey must match the named curve",
+          "DataError",
+        );
+      }
+
       // Validate that this is a valid public key.
       if (jwk.x === undefined) {
         throw new DOMException(
@@ -3704,6 +3714,7 @@ function importKeyRSA(
           algorithm: normalizedAlgorithm.name,
           // Needed to perform step 7 without normalization.
           hash: normalizedAlgorithm.hash.name,
+          allowInsecureKeySizes: normalizedAlgorithm.allowInsecureKeySizes,
         },
         { pkcs8: keyData },
       );
@@ -3749,6 +3760,7 @@ function importKeyRSA(
           algorithm: normalizedAlgorithm.name,
           // Needed to perform step 7 without normalization.
           hash: normalizedAlgorithm.hash.name,
+          allowInsecureKeySizes: normalizedAlgorithm.allowInsecureKeySizes,
         },
         { spki: keyData },
       );
@@ -3962,24 +3974,13 @@ function importKeyRSA(
               "DataError",
             );
           }
-          if (jwk.dp === undefined) {
-            throw new DOMException(
-              "'dp' property of JsonWebKey is required for private keys",
-              "DataError",
-            );
-          }
-          if (jwk.dq === undefined) {
-            throw new DOMException(
-              "'dq' property of JsonWebKey is required for private keys",
-              "DataError",
-            );
-          }
-          if (jwk.qi === undefined) {
+          if (jwk.p === undefined) {
             throw new DOMException(
-              "'qi' property of JsonWebKey is required for private keys",
+              "'p' property of JsonWebKey is required for private keys",
               "DataError",
             );
           }
+          // Missing 'dp', 'dq' and 'qi' are recomputed from 'p', 'q' and 'd'.
           if (jwk.oth !== undefined) {
             throw new DOMException(
               "'oth' property of JsonWebKey is not supported",
@@ -3997,6 +3998,7 @@ function importKeyRSA(
           {
             algorithm: normalizedAlgorithm.name,
             hash: normalizedAlgorithm.hash.name,
+            allowInsecureKeySizes: normalizedAlgorithm.allowInsecureKeySizes,
           },
           { jwkPrivateRsa: jwk },
         );
@@ -4039,6 +4041,7 @@ function importKeyRSA(
           {
             algorithm: normalizedAlgorithm.name,
             hash: normalizedAlgorithm.hash.name,
+            allowInsecureKeySizes: normalizedAlgorithm.allowInsecureKeySizes,
           },
           { jwkPublicRsa: jwk },
         );
@@ -5054,10 +5057,10 @@ async function encrypt(normalizedAlgorithm, key, data) {
       // 2.
       // We only support 96-bit and 128-bit nonce.
       if (
-        ArrayPrototypeIncludes(
+        !ArrayPrototypeIncludes(
           [12, 16],
           TypedArrayPrototypeGetByteLength(normalizedAlgorithm.iv),
-        ) === undefined
+        )
       ) {
         throw new DOMException(
           "Initialization vector length not supported",
@@ -5296,6 +5299,12 @@ const dictRsaHashedImportParams = [
     converter: webidl.converters.HashAlgorithmIdentifier,
     required: true,
   },
+  // Non-standard: allow importing RSA keys with a modulus below 2048 bits.
+  {
+    key: "allowInsecureKeySizes",
+    converter: webidl.converters.boolean,
+    defaultValue: false,
+  },
 ];
 
 webidl.converters.RsaHashedImportParams = webidl.createDictionaryConverter(
@@ -5687,4 +5696,239 @@ const dictEcdhKeyDeriveParams = [
 webidl.converters.EcdhKeyDeriveParams = webidl
   .createDictionaryConverter("EcdhKeyDeriveParams", dictEcdhKeyDeriveParams);
 
-export { Crypto, crypto, CryptoKey, SubtleCrypto };
+/** @type {webidl.Dictionary} */
+const dictPasswordWrapOptions = [
+  {
+    key: "iterations",
+    converter: (V, prefix, context, opts) =>
+      webidl.converters["unsigned long"](V, prefix, context, {
+        ...opts,
+        enforceRange: true,
+      }),
+  },
+  {
+    key: "saltLength",
+    converter: (V, prefix, context, opts) =>
+      webidl.converters["unsigned long"](V, prefix, context, {
+        ...opts,
+        enforceRange: true,
+      }),
+  },
+];
+
+webidl.converters.PasswordWrapOptions = webidl
+  .createDictionaryConverter("PasswordWrapOptions", dictPasswordWrapOptions);
+
+/**
+ * @param {string | BufferSource} password
+ * @param {string} prefix
+ * @returns {Uint8Array}
+ */
+function passwordBytes(password, prefix) {
+  if (typeof password === "string") {
+    return core.encode(password);
+  }
+  return copyBuffer(
+    webidl.converters.BufferSource(password, prefix, "Argument 2"),
+  );
+}
+
+/**
+ * @param {BufferSource} data
+ * @param {string | BufferSource} password
+ * @param {{ iterations?: number, saltLength?: number }} options
+ * @returns {Promise<Uint8Array>}
+ */
+async function wrapWithPassword(data, password, options = { __proto__: null }) {
+  const prefix = "Failed to execute 'Deno.wrapWithPassword'";
+  webidl.requiredArguments(arguments.length, 2, prefix);
+  data = copyBuffer(webidl.converters.BufferSource(data, prefix, "Argument 1"));
+  password = passwordBytes(password, prefix);
+  options = webidl.converters.PasswordWrapOptions(
+    options,
+    prefix,
+    "Argument 3",
+  );
+  return await op_crypto_password_wrap(data, password, options);
+}
+
+/**
+ * @param {BufferSource} envelope
+ * @param {string | BufferSource} password
+ * @returns {Promise<Uint8Array>}
+ */
+async function unwrapWithPassword(envelope, password) {
+  const prefix = "Failed to execute 'Deno.unwrapWithPassword'";
+  webidl.requiredArguments(arguments.length, 2, prefix);
+  envelope = copyBuffer(
+    webidl.converters.BufferSource(envelope, prefix, "Argument 1"),
+  );
+  password = passwordBytes(password, prefix);
+  return await op_crypto_password_unwrap(envelope, password);
+}
+
+/**
+ * @param {string | BufferSource} message
+ * @param {string} prefix
+ * @param {string} context
+ * @returns {Uint8Array}
+ */
+function sshMessageBytes(message, prefix, context) {
+  if (typeof message === "string") {
+    return core.encode(message);
+  }
+  return copyBuffer(webidl.converters.BufferSource(message, prefix, context));
+}
+
+class SshPublicKey {
+  #rid = 0;
+  #algorithm;
+  #comment;
+  #fingerprint;
+  #options;
+
+  constructor(info, symbol) {
+    if (!symbol || symbol !== SymbolFor("Deno.internal.SshPublicKey")) {
+      throw new TypeError(
+        "`Deno.SshPublicKey` cannot be constructed, use `Deno.parseSshPublicKey()` instead.",
+      );
+    }
+    this.#rid = info.rid;
+    this.#algorithm = info.algorithm;
+    this.#comment = info.comment;
+    this.#fingerprint = info.fingerprint;
+    this.#options = info.options;
+  }
+
+  get algorithm() {
+    return this.#algorithm;
+  }
+
+  get comment() {
+    return this.#comment;
+  }
+
+  get fingerprint() {
+    return this.#fingerprint;
+  }
+
+  get options() {
+    return [...new SafeArrayIterator(this.#options)];
+  }
+
+  verify(namespace, message, signature) {
+    const prefix = "Failed to execute 'verify' on 'SshPublicKey'";
+    webidl.requiredArguments(arguments.length, 3, prefix);
+    namespace = webidl.converters.DOMString(namespace, prefix, "Argument 1");
+    message = sshMessageBytes(message, prefix, "Argument 2");
+    signature = webidl.converters.DOMString(signature, prefix, "Argument 3");
+    return op_ssh_verify_signature(this.#rid, namespace, message, signature);
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+class SshPrivateKey {
+  #rid = 0;
+  #algorithm;
+  #comment;
+  #fingerprint;
+  #publicKey;
+
+  constructor(info, symbol) {
+    if (!symbol || symbol !== SymbolFor("Deno.internal.SshPrivateKey")) {
+      throw new TypeError(
+        "`Deno.SshPrivateKey` cannot be constructed, use `Deno.parseSshPrivateKey()` instead.",
+      );
+    }
+    this.#rid = info.rid;
+    this.#algorithm = info.algorithm;
+    this.#comment = info.comment;
+    this.#fingerprint = info.fingerprint;
+    this.#publicKey = info.publicKey;
+  }
+
+  get algorithm() {
+    return this.#algorithm;
+  }
+
+  get comment() {
+    return this.#comment;
+  }
+
+  get fingerprint() {
+    return this.#fingerprint;
+  }
+
+  /** The public key in the OpenSSH single-line format. */
+  get publicKey() {
+    return this.#publicKey;
+  }
+
+  sign(namespace, message) {
+    const prefix = "Failed to execute 'sign' on 'SshPrivateKey'";
+    webidl.requiredArguments(arguments.length, 2, prefix);
+    namespace = webidl.converters.DOMString(namespace, prefix, "Argument 1");
+    message = sshMessageBytes(message, prefix, "Argument 2");
+    return op_ssh_sign(this.#rid, namespace, message);
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+/**
+ * @param {string} text
+ * @returns {SshPublicKey}
+ */
+function parseSshPublicKey(text) {
+  const prefix = "Failed to execute 'Deno.parseSshPublicKey'";
+  webidl.requiredArguments(arguments.length, 1, prefix);
+  text = webidl.converters.DOMString(text, prefix, "Argument 1");
+  return new SshPublicKey(
+    op_ssh_parse_public_key(text),
+    SymbolFor("Deno.internal.SshPublicKey"),
+  );
+}
+
+/**
+ * @param {string} pem
+ * @param {string} [passphrase]
+ * @returns {Promise<SshPrivateKey>}
+ */
+async function parseSshPrivateKey(pem, passphrase = undefined) {
+  const prefix = "Failed to execute 'Deno.parseSshPrivateKey'";
+  webidl.requiredArguments(arguments.length, 1, prefix);
+  pem = webidl.converters.DOMString(pem, prefix, "Argument 1");
+  if (passphrase !== undefined) {
+    passphrase = webidl.converters.DOMString(passphrase, prefix, "Argument 2");
+  }
+  return new SshPrivateKey(
+    await op_ssh_parse_private_key(pem, passphrase),
+    SymbolFor("Deno.internal.SshPrivateKey"),
+  );
+}
+
+export {
+  Crypto,
+  crypto,
+  CryptoKey,
+  parseSshPrivateKey,
+  parseSshPublicKey,
+  SshPrivateKey,
+  SshPublicKey,
+  SubtleCrypto,
+  unwrapWithPassword,
+  wrapWithPassword,
+};
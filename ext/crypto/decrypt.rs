@@ -22,11 +22,8 @@ use deno_core::JsBuffer;
 use deno_core::ToJsBuffer;
 use rsa::pkcs1::DecodeRsaPrivateKey;
 use serde::Deserialize;
-use sha1::Sha1;
-use sha2::Sha256;
-use sha2::Sha384;
-use sha2::Sha512;
 
+use crate::rsa_oaep;
 use crate::shared::*;
 
 #[derive(Deserialize)]
@@ -90,8 +87,6 @@ pub enum DecryptError {
   TooMuchData,
   #[error("iv length not equal to 12 or 16")]
   InvalidIvLength,
-  #[error("{0}")]
-  Rsa(rsa::Error),
 }
 
 #[op2(async)]
@@ -133,34 +128,9 @@ fn decrypt_rsa_oaep(
   let key = key.as_rsa_private_key()?;
 
   let private_key = rsa::RsaPrivateKey::from_pkcs1_der(key)?;
-  let label = Some(String::from_utf8_lossy(&label).to_string());
-
-  let padding = match hash {
-    ShaHash::Sha1 => rsa::Oaep {
-      digest: Box::<Sha1>::default(),
-      mgf_digest: Box::<Sha1>::default(),
-      label,
-    },
-    ShaHash::Sha256 => rsa::Oaep {
-      digest: Box::<Sha256>::default(),
-      mgf_digest: Box::<Sha256>::default(),
-      label,
-    },
-    ShaHash::Sha384 => rsa::Oaep {
-      digest: Box::<Sha384>::default(),
-      mgf_digest: Box::<Sha384>::default(),
-      label,
-    },
-    ShaHash::Sha512 => rsa::Oaep {
-      digest: Box::<Sha512>::default(),
-      mgf_digest: Box::<Sha512>::default(),
-      label,
-    },
-  };
-
-  private_key
-    .decrypt(padding, data)
-    .map_err(DecryptError::Rsa)
+  // Every way the ciphertext can be invalid results in the same error.
+  rsa_oaep::decrypt(&private_key, hash, &label, data)
+    .map_err(|_| DecryptError::Failed)
 }
 
 fn decrypt_aes_cbc(
@@ -331,7 +301,10 @@ fn decrypt_aes_gcm(
     return Err(DecryptError::InvalidTagLength);
   }
 
-  let sep = data.len() - (tag_length / 8);
+  let sep = data
+    .len()
+    .checked_sub(tag_length / 8)
+    .ok_or(DecryptError::Failed)?;
   let tag = &data[sep..];
 
   // The actual ciphertext, called plaintext because it is reused in place.
@@ -0,0 +1,303 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Incremental digest and HMAC computation, for inputs that are too large to
+//! be passed to `op_crypto_subtle_digest` or `op_crypto_sign_key` as a single
+//! buffer.
+
+use std::cell::RefCell;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::unsync::spawn_blocking;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::ResourceTable;
+use deno_core::ToJsBuffer;
+use ring::digest;
+use ring::hmac;
+
+use crate::key::CryptoHash;
+use crate::shared::V8RawKeyData;
+use crate::CryptoPermissions;
+use crate::SharedError;
+
+/// Size of the reads done by `op_crypto_digest_file`.
+const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DigestError {
+  #[error(transparent)]
+  General(#[from] SharedError),
+  #[error(transparent)]
+  Resource(deno_core::error::AnyError),
+  #[error(transparent)]
+  Permission(deno_core::error::AnyError),
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+  #[error(transparent)]
+  JoinError(#[from] tokio::task::JoinError),
+  #[error("Digest context has already been finalized")]
+  Finalized,
+}
+
+trait StreamingContext: 'static {
+  const NAME: &'static str;
+
+  fn update(&mut self, data: &[u8]);
+  fn finish(self) -> Vec<u8>;
+}
+
+impl StreamingContext for digest::Context {
+  const NAME: &'static str = "cryptoDigestContext";
+
+  fn update(&mut self, data: &[u8]) {
+    digest::Context::update(self, data);
+  }
+
+  fn finish(self) -> Vec<u8> {
+    digest::Context::finish(self).as_ref().to_vec()
+  }
+}
+
+impl StreamingContext for hmac::Context {
+  const NAME: &'static str = "cryptoHmacContext";
+
+  fn update(&mut self, data: &[u8]) {
+    hmac::Context::update(self, data);
+  }
+
+  fn finish(self) -> Vec<u8> {
+    self.sign().as_ref().to_vec()
+  }
+}
+
+/// A digest or HMAC computation in progress. The context is taken out when
+/// the computation is finalized.
+struct ContextResource<T>(RefCell<Option<T>>);
+
+impl<T: StreamingContext> Resource for ContextResource<T> {
+  fn name(&self) -> std::borrow::Cow<str> {
+    T::NAME.into()
+  }
+}
+
+fn add_context<T: StreamingContext>(
+  table: &mut ResourceTable,
+  context: T,
+) -> ResourceId {
+  table.add(ContextResource(RefCell::new(Some(context))))
+}
+
+fn update_context<T: StreamingContext>(
+  table: &ResourceTable,
+  rid: ResourceId,
+  chunks: &[impl AsRef<[u8]>],
+) -> Result<(), DigestError> {
+  let resource = table
+    .get::<ContextResource<T>>(rid)
+    .map_err(DigestError::Resource)?;
+  let mut context = resource.0.borrow_mut();
+  let context = context.as_mut().ok_or(DigestError::Finalized)?;
+  for chunk in chunks {
+    context.update(chunk.as_ref());
+  }
+  Ok(())
+}
+
+fn finish_context<T: StreamingContext>(
+  table: &mut ResourceTable,
+  rid: ResourceId,
+) -> Result<Vec<u8>, DigestError> {
+  let resource = table
+    .take::<ContextResource<T>>(rid)
+    .map_err(DigestError::Resource)?;
+  let context = resource.0.borrow_mut().take();
+  context.map(T::finish).ok_or(DigestError::Finalized)
+}
+
+fn digest_file(
+  algorithm: &'static digest::Algorithm,
+  path: &Path,
+) -> std::io::Result<Vec<u8>> {
+  let mut file = std::fs::File::open(path)?;
+  let mut context = digest::Context::new(algorithm);
+  let mut buf = vec![0; FILE_CHUNK_SIZE];
+  loop {
+    let n = match file.read(&mut buf) {
+      Ok(0) => break,
+      Ok(n) => n,
+      Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+      Err(err) => return Err(err),
+    };
+    context.update(&buf[..n]);
+  }
+  Ok(context.finish().as_ref().to_vec())
+}
+
+#[op2]
+#[smi]
+pub fn op_crypto_digest_init(
+  state: &mut OpState,
+  #[serde] algorithm: CryptoHash,
+) -> ResourceId {
+  let context = digest::Context::new(algorithm.into());
+  add_context(&mut state.resource_table, context)
+}
+
+/// Absorbs `chunks`, in order, into the digest context `rid`.
+#[op2]
+pub fn op_crypto_digest_update(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[serde] chunks: Vec<JsBuffer>,
+) -> Result<(), DigestError> {
+  update_context::<digest::Context>(&state.resource_table, rid, &chunks)
+}
+
+/// Returns the digest of the data absorbed so far and closes `rid`.
+#[op2]
+#[serde]
+pub fn op_crypto_digest_final(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<ToJsBuffer, DigestError> {
+  finish_context::<digest::Context>(&mut state.resource_table, rid)
+    .map(Into::into)
+}
+
+#[op2]
+#[smi]
+pub fn op_crypto_hmac_init(
+  state: &mut OpState,
+  #[serde] key: V8RawKeyData,
+  #[serde] hash: CryptoHash,
+) -> Result<ResourceId, DigestError> {
+  let key = hmac::Key::new(hash.into(), key.as_secret_key()?);
+  let context = hmac::Context::with_key(&key);
+  Ok(add_context(&mut state.resource_table, context))
+}
+
+/// Absorbs `chunks`, in order, into the HMAC context `rid`.
+#[op2]
+pub fn op_crypto_hmac_update(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[serde] chunks: Vec<JsBuffer>,
+) -> Result<(), DigestError> {
+  update_context::<hmac::Context>(&state.resource_table, rid, &chunks)
+}
+
+/// Returns the signature of the data absorbed so far and closes `rid`.
+#[op2]
+#[serde]
+pub fn op_crypto_hmac_final(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<ToJsBuffer, DigestError> {
+  finish_context::<hmac::Context>(&mut state.resource_table, rid)
+    .map(Into::into)
+}
+
+/// Hashes the file at `path` on the blocking pool, without reading it into
+/// memory as a whole.
+#[op2(async)]
+#[serde]
+pub async fn op_crypto_digest_file<P>(
+  state: Rc<RefCell<OpState>>,
+  #[serde] algorithm: CryptoHash,
+  #[string] path: String,
+) -> Result<ToJsBuffer, DigestError>
+where
+  P: CryptoPermissions + 'static,
+{
+  let path = {
+    let mut state = state.borrow_mut();
+    let permissions = state.borrow_mut::<P>();
+    permissions
+      .check_read(&PathBuf::from(path), "crypto.digestFile()")
+      .map_err(DigestError::Permission)?
+      .into_owned()
+  };
+  let output =
+    spawn_blocking(move || digest_file(algorithm.into(), &path)).await??;
+  Ok(output.into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SHA256: &str =
+    "fe2aaf82bfa2ffec207a0c6fa7ce7d4af268d67e2672fdaec675f3f9b65d0854";
+  const HMAC_SHA256: &str =
+    "4a90e267fced616819e37cea7652e496b84ea8aaaaa222d40e645494b48f5e86";
+
+  // Spans several `FILE_CHUNK_SIZE` reads and ends in a partial one.
+  fn large_input() -> Vec<u8> {
+    (0..3 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect()
+  }
+
+  fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+  }
+
+  #[test]
+  fn digest_file_matches_streaming_digest() {
+    let input = large_input();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("large.bin");
+    std::fs::write(&path, &input).unwrap();
+
+    let from_file = digest_file(&digest::SHA256, &path).unwrap();
+    assert_eq!(hex(&from_file), SHA256);
+
+    let mut table = ResourceTable::default();
+    let rid = add_context(&mut table, digest::Context::new(&digest::SHA256));
+    for chunks in input.chunks(10_000).collect::<Vec<_>>().chunks(7) {
+      update_context::<digest::Context>(&table, rid, chunks).unwrap();
+    }
+    let streamed = finish_context::<digest::Context>(&mut table, rid).unwrap();
+    assert_eq!(streamed, from_file);
+  }
+
+  #[test]
+  fn streaming_hmac() {
+    let input = large_input();
+    let key = hmac::Key::new(hmac::HMAC_SHA256, b"deno");
+    let mut table = ResourceTable::default();
+    let rid = add_context(&mut table, hmac::Context::with_key(&key));
+    for chunk in input.chunks(65_537) {
+      update_context::<hmac::Context>(&table, rid, &[chunk]).unwrap();
+    }
+    let signature = finish_context::<hmac::Context>(&mut table, rid).unwrap();
+    assert_eq!(hex(&signature), HMAC_SHA256);
+  }
+
+  #[test]
+  fn update_after_final_errors() {
+    let mut table = ResourceTable::default();
+    let rid = add_context(&mut table, digest::Context::new(&digest::SHA256));
+    finish_context::<digest::Context>(&mut table, rid).unwrap();
+    assert!(matches!(
+      update_context::<digest::Context>(&table, rid, &[b"data"]),
+      Err(DigestError::Resource(_))
+    ));
+    assert!(matches!(
+      finish_context::<digest::Context>(&mut table, rid),
+      Err(DigestError::Resource(_))
+    ));
+  }
+
+  #[test]
+  fn contexts_are_not_interchangeable() {
+    let mut table = ResourceTable::default();
+    let rid = add_context(&mut table, digest::Context::new(&digest::SHA256));
+    assert!(update_context::<hmac::Context>(&table, rid, &[b"data"]).is_err());
+    assert!(finish_context::<digest::Context>(&mut table, rid).is_ok());
+  }
+}
@@ -3,16 +3,17 @@
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use base64::Engine;
 use deno_core::op2;
+use deno_core::OpState;
 use deno_core::ToJsBuffer;
 use elliptic_curve::pkcs8::PrivateKeyInfo;
-use rand::rngs::OsRng;
-use rand::RngCore;
 use ring::signature::Ed25519KeyPair;
 use ring::signature::KeyPair;
 use spki::der::asn1::BitString;
 use spki::der::Decode;
 use spki::der::Encode;
 
+use crate::shared::fill_key_material;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Ed25519Error {
   #[error("Failed to export key")]
@@ -25,11 +26,11 @@ pub enum Ed25519Error {
 
 #[op2(fast)]
 pub fn op_crypto_generate_ed25519_keypair(
+  state: &mut OpState,
   #[buffer] pkey: &mut [u8],
   #[buffer] pubkey: &mut [u8],
 ) -> bool {
-  let mut rng = OsRng;
-  rng.fill_bytes(pkey);
+  fill_key_material(state, pkey);
 
   let pair = match Ed25519KeyPair::from_seed_unchecked(pkey) {
     Ok(p) => p,
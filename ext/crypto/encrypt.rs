@@ -20,14 +20,10 @@ use deno_core::op2;
 use deno_core::unsync::spawn_blocking;
 use deno_core::JsBuffer;
 use deno_core::ToJsBuffer;
-use rand::rngs::OsRng;
 use rsa::pkcs1::DecodeRsaPublicKey;
 use serde::Deserialize;
-use sha1::Sha1;
-use sha2::Sha256;
-use sha2::Sha384;
-use sha2::Sha512;
 
+use crate::rsa_oaep;
 use crate::shared::*;
 
 #[derive(Deserialize)]
@@ -125,38 +121,11 @@ fn encrypt_rsa_oaep(
   label: Vec<u8>,
   data: &[u8],
 ) -> Result<Vec<u8>, EncryptError> {
-  let label = String::from_utf8_lossy(&label).to_string();
-
   let public_key = key.as_rsa_public_key()?;
   let public_key = rsa::RsaPublicKey::from_pkcs1_der(&public_key)
     .map_err(|_| SharedError::FailedDecodePublicKey)?;
-  let mut rng = OsRng;
-  let padding = match hash {
-    ShaHash::Sha1 => rsa::Oaep {
-      digest: Box::<Sha1>::default(),
-      mgf_digest: Box::<Sha1>::default(),
-      label: Some(label),
-    },
-    ShaHash::Sha256 => rsa::Oaep {
-      digest: Box::<Sha256>::default(),
-      mgf_digest: Box::<Sha256>::default(),
-      label: Some(label),
-    },
-    ShaHash::Sha384 => rsa::Oaep {
-      digest: Box::<Sha384>::default(),
-      mgf_digest: Box::<Sha384>::default(),
-      label: Some(label),
-    },
-    ShaHash::Sha512 => rsa::Oaep {
-      digest: Box::<Sha512>::default(),
-      mgf_digest: Box::<Sha512>::default(),
-      label: Some(label),
-    },
-  };
-  let encrypted = public_key
-    .encrypt(&mut rng, padding, data)
-    .map_err(|_| EncryptError::Failed)?;
-  Ok(encrypted)
+  rsa_oaep::encrypt(&public_key, hash, &label, data)
+    .map_err(|_| EncryptError::Failed)
 }
 
 fn encrypt_aes_cbc(
@@ -1,13 +1,19 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use deno_core::op2;
 use deno_core::unsync::spawn_blocking;
+use deno_core::OpState;
 use deno_core::ToJsBuffer;
+use elliptic_curve::rand_core::CryptoRngCore;
 use elliptic_curve::rand_core::OsRng;
 use num_traits::FromPrimitive;
 use once_cell::sync::Lazy;
-use ring::rand::SecureRandom;
-use ring::signature::EcdsaKeyPair;
+use p256::pkcs8::EncodePrivateKey;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rsa::pkcs1::EncodeRsaPrivateKey;
 use rsa::BigUint;
 use rsa::RsaPrivateKey;
@@ -64,24 +70,39 @@ pub enum GenerateKeyOptions {
 #[op2(async)]
 #[serde]
 pub async fn op_crypto_generate_key(
+  state: Rc<RefCell<OpState>>,
   #[serde] opts: GenerateKeyOptions,
 ) -> Result<ToJsBuffer, GenerateKeyError> {
-  let fun = || match opts {
+  // Drawn before the key is generated on another thread, so that seeded keys
+  // only depend on the order of the calls.
+  let seed = key_seed(&mut state.borrow_mut());
+  let fun = move || match seed {
+    Some(seed) => generate_key(opts, &mut StdRng::from_seed(seed)),
+    None => generate_key(opts, &mut OsRng),
+  };
+  let buf = spawn_blocking(fun).await.unwrap()?;
+  Ok(buf.into())
+}
+
+fn generate_key(
+  opts: GenerateKeyOptions,
+  rng: &mut impl CryptoRngCore,
+) -> Result<Vec<u8>, GenerateKeyError> {
+  match opts {
     GenerateKeyOptions::Rsa {
       modulus_length,
       public_exponent,
-    } => generate_key_rsa(modulus_length, &public_exponent),
-    GenerateKeyOptions::Ec { named_curve } => generate_key_ec(named_curve),
-    GenerateKeyOptions::Aes { length } => generate_key_aes(length),
+    } => generate_key_rsa(rng, modulus_length, &public_exponent),
+    GenerateKeyOptions::Ec { named_curve } => generate_key_ec(rng, named_curve),
+    GenerateKeyOptions::Aes { length } => generate_key_aes(rng, length),
     GenerateKeyOptions::Hmac { hash, length } => {
-      generate_key_hmac(hash, length)
+      generate_key_hmac(rng, hash, length)
     }
-  };
-  let buf = spawn_blocking(fun).await.unwrap()?;
-  Ok(buf.into())
+  }
 }
 
 fn generate_key_rsa(
+  rng: &mut impl CryptoRngCore,
   modulus_length: u32,
   public_exponent: &[u8],
 ) -> Result<Vec<u8>, GenerateKeyError> {
@@ -90,10 +111,8 @@ fn generate_key_rsa(
     return Err(GenerateKeyError::BadPublicExponent);
   }
 
-  let mut rng = OsRng;
-
   let private_key =
-    RsaPrivateKey::new_with_exp(&mut rng, modulus_length as usize, &exponent)
+    RsaPrivateKey::new_with_exp(rng, modulus_length as usize, &exponent)
       .map_err(|_| GenerateKeyError::FailedRSAKeyGeneration)?;
 
   let private_key = private_key
@@ -103,44 +122,43 @@ fn generate_key_rsa(
   Ok(private_key.as_bytes().to_vec())
 }
 
-fn generate_key_ec_p521() -> Vec<u8> {
-  let mut rng = OsRng;
-  let key = p521::SecretKey::random(&mut rng);
-  key.to_nonzero_scalar().to_bytes().to_vec()
-}
-
 fn generate_key_ec(
+  rng: &mut impl CryptoRngCore,
   named_curve: EcNamedCurve,
 ) -> Result<Vec<u8>, GenerateKeyError> {
-  let curve = match named_curve {
-    EcNamedCurve::P256 => &ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING,
-    EcNamedCurve::P384 => &ring::signature::ECDSA_P384_SHA384_FIXED_SIGNING,
-    EcNamedCurve::P521 => return Ok(generate_key_ec_p521()),
+  // P-256 and P-384 keys are stored as PKCS#8, like keys generated by ring
+  // would be, but ring can only generate them from the system RNG.
+  let pkcs8 = match named_curve {
+    EcNamedCurve::P256 => p256::SecretKey::random(rng).to_pkcs8_der(),
+    EcNamedCurve::P384 => p384::SecretKey::random(rng).to_pkcs8_der(),
+    EcNamedCurve::P521 => {
+      let key = p521::SecretKey::random(rng);
+      return Ok(key.to_nonzero_scalar().to_bytes().to_vec());
+    }
   };
+  let pkcs8 = pkcs8.map_err(|_| GenerateKeyError::FailedECKeyGeneration)?;
 
-  let rng = ring::rand::SystemRandom::new();
-
-  let pkcs8 = EcdsaKeyPair::generate_pkcs8(curve, &rng)
-    .map_err(|_| GenerateKeyError::FailedECKeyGeneration)?;
-
-  Ok(pkcs8.as_ref().to_vec())
+  Ok(pkcs8.as_bytes().to_vec())
 }
 
-fn generate_key_aes(length: usize) -> Result<Vec<u8>, GenerateKeyError> {
+fn generate_key_aes(
+  rng: &mut impl CryptoRngCore,
+  length: usize,
+) -> Result<Vec<u8>, GenerateKeyError> {
   if length % 8 != 0 || length > 256 {
     return Err(GenerateKeyError::InvalidAESKeyLength);
   }
 
   let mut key = vec![0u8; length / 8];
-  let rng = ring::rand::SystemRandom::new();
   rng
-    .fill(&mut key)
+    .try_fill_bytes(&mut key)
     .map_err(|_| GenerateKeyError::FailedKeyGeneration)?;
 
   Ok(key)
 }
 
 fn generate_key_hmac(
+  rng: &mut impl CryptoRngCore,
   hash: ShaHash,
   length: Option<usize>,
 ) -> Result<Vec<u8>, GenerateKeyError> {
@@ -152,7 +170,7 @@ fn generate_key_hmac(
   };
 
   let length = if let Some(length) = length {
-    if length % 8 != 0 {
+    if length == 0 || length % 8 != 0 {
       return Err(GenerateKeyError::InvalidHMACKeyLength);
     }
 
@@ -166,11 +184,102 @@ fn generate_key_hmac(
     hash.digest_algorithm().block_len()
   };
 
-  let rng = ring::rand::SystemRandom::new();
   let mut key = vec![0u8; length];
   rng
-    .fill(&mut key)
+    .try_fill_bytes(&mut key)
     .map_err(|_| GenerateKeyError::FailedKeyGeneration)?;
 
   Ok(key)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hmac_key_length() {
+    let len =
+      |hash, length| generate_key_hmac(&mut OsRng, hash, length).unwrap().len();
+
+    // The default is the block size of the hash.
+    assert_eq!(len(ShaHash::Sha256, None), 64);
+    assert_eq!(len(ShaHash::Sha512, None), 128);
+
+    // An explicit length does not have to match the hash.
+    assert_eq!(len(ShaHash::Sha256, Some(512)), 64);
+    assert_eq!(len(ShaHash::Sha512, Some(128)), 16);
+
+    for length in [0, 100, 2048] {
+      assert!(matches!(
+        generate_key_hmac(&mut OsRng, ShaHash::Sha256, Some(length)),
+        Err(GenerateKeyError::InvalidHMACKeyLength)
+      ));
+    }
+  }
+
+  #[test]
+  fn hmac_key_is_random() {
+    let a = generate_key_hmac(&mut OsRng, ShaHash::Sha256, Some(256)).unwrap();
+    let b = generate_key_hmac(&mut OsRng, ShaHash::Sha256, Some(256)).unwrap();
+    assert_ne!(a, b);
+  }
+
+  fn all_options() -> Vec<GenerateKeyOptions> {
+    vec![
+      GenerateKeyOptions::Rsa {
+        modulus_length: 1024,
+        public_exponent: vec![1, 0, 1],
+      },
+      GenerateKeyOptions::Ec {
+        named_curve: EcNamedCurve::P256,
+      },
+      GenerateKeyOptions::Ec {
+        named_curve: EcNamedCurve::P384,
+      },
+      GenerateKeyOptions::Ec {
+        named_curve: EcNamedCurve::P521,
+      },
+      GenerateKeyOptions::Aes { length: 256 },
+      GenerateKeyOptions::Hmac {
+        hash: ShaHash::Sha256,
+        length: None,
+      },
+    ]
+  }
+
+  #[test]
+  fn seeded_keys_are_reproducible() {
+    let generate = |seed, options| {
+      generate_key(options, &mut StdRng::seed_from_u64(seed)).unwrap()
+    };
+    for ((a, b), c) in all_options()
+      .into_iter()
+      .zip(all_options())
+      .zip(all_options())
+    {
+      let key = generate(42, a);
+      assert_eq!(key, generate(42, b));
+      assert_ne!(key, generate(43, c));
+    }
+  }
+
+  #[test]
+  fn ec_keys_are_accepted_by_ring() {
+    let rng = ring::rand::SystemRandom::new();
+    for (curve, alg) in [
+      (
+        EcNamedCurve::P256,
+        &ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+      ),
+      (
+        EcNamedCurve::P384,
+        &ring::signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+      ),
+    ] {
+      let pkcs8 = generate_key_ec(&mut OsRng, curve).unwrap();
+      assert!(
+        ring::signature::EcdsaKeyPair::from_pkcs8(alg, &pkcs8, &rng).is_ok()
+      );
+    }
+  }
+}
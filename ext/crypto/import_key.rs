@@ -5,9 +5,14 @@ use deno_core::op2;
 use deno_core::JsBuffer;
 use deno_core::ToJsBuffer;
 use elliptic_curve::pkcs8::PrivateKeyInfo;
+use elliptic_curve::sec1::ToEncodedPoint;
+use num_bigint_dig::prime::probably_prime;
+use num_traits::One;
+use p256::pkcs8::DecodePrivateKey;
 use p256::pkcs8::EncodePrivateKey;
 use rsa::pkcs1::UintRef;
 use rsa::pkcs8::der::Encode;
+use rsa::BigUint;
 use serde::Deserialize;
 use serde::Serialize;
 use spki::der::Decode;
@@ -40,6 +45,14 @@ pub enum ImportKeyError {
   InvalidRSAPublicKey,
   #[error("invalid RSA private key")]
   InvalidRSAPrivateKey,
+  #[error("RSA modulus length must be at least 2048 bits")]
+  InsecureRSAModulusLength,
+  #[error("unsupported RSA modulus length")]
+  UnsupportedRSAModulusLength,
+  #[error("invalid RSA prime factors")]
+  InvalidRSAPrimeFactors,
+  #[error("inconsistent RSA private key parameters")]
+  InconsistentRSAPrivateKey,
   #[error("unsupported algorithm")]
   UnsupportedAlgorithm,
   #[error("public key is invalid (too long)")]
@@ -66,6 +79,8 @@ pub enum ImportKeyError {
   InvalidKeyData,
   #[error("invalid JWK private key")]
   InvalidJWKPrivateKey,
+  #[error("public key does not match the private key")]
+  PublicKeyMismatch,
   #[error(transparent)]
   EllipticCurve(#[from] elliptic_curve::Error),
   #[error("expected valid PKCS#8 data")]
@@ -97,18 +112,16 @@ pub enum KeyData {
     d: String,
     p: String,
     q: String,
-    dp: String,
-    dq: String,
-    qi: String,
+    dp: Option<String>,
+    dq: Option<String>,
+    qi: Option<String>,
   },
   JwkPublicEc {
     x: String,
     y: String,
   },
   JwkPrivateEc {
-    #[allow(dead_code)]
     x: String,
-    #[allow(dead_code)]
     y: String,
     d: String,
   },
@@ -117,12 +130,21 @@ pub enum KeyData {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", tag = "algorithm")]
 pub enum ImportKeyOptions {
-  #[serde(rename = "RSASSA-PKCS1-v1_5")]
-  RsassaPkcs1v15 {},
-  #[serde(rename = "RSA-PSS")]
-  RsaPss {},
-  #[serde(rename = "RSA-OAEP")]
-  RsaOaep {},
+  #[serde(rename = "RSASSA-PKCS1-v1_5", rename_all = "camelCase")]
+  RsassaPkcs1v15 {
+    #[serde(default)]
+    allow_insecure_key_sizes: bool,
+  },
+  #[serde(rename = "RSA-PSS", rename_all = "camelCase")]
+  RsaPss {
+    #[serde(default)]
+    allow_insecure_key_sizes: bool,
+  },
+  #[serde(rename = "RSA-OAEP", rename_all = "camelCase")]
+  RsaOaep {
+    #[serde(default)]
+    allow_insecure_key_sizes: bool,
+  },
   #[serde(rename = "ECDSA", rename_all = "camelCase")]
   Ecdsa { named_curve: EcNamedCurve },
   #[serde(rename = "ECDH", rename_all = "camelCase")]
@@ -158,9 +180,15 @@ pub fn op_crypto_import_key(
   #[serde] key_data: KeyData,
 ) -> Result<ImportKeyResult, ImportKeyError> {
   match opts {
-    ImportKeyOptions::RsassaPkcs1v15 {} => import_key_rsassa(key_data),
-    ImportKeyOptions::RsaPss {} => import_key_rsapss(key_data),
-    ImportKeyOptions::RsaOaep {} => import_key_rsaoaep(key_data),
+    ImportKeyOptions::RsassaPkcs1v15 {
+      allow_insecure_key_sizes,
+    } => import_key_rsassa(key_data, allow_insecure_key_sizes),
+    ImportKeyOptions::RsaPss {
+      allow_insecure_key_sizes,
+    } => import_key_rsapss(key_data, allow_insecure_key_sizes),
+    ImportKeyOptions::RsaOaep {
+      allow_insecure_key_sizes,
+    } => import_key_rsaoaep(key_data, allow_insecure_key_sizes),
     ImportKeyOptions::Ecdsa { named_curve }
     | ImportKeyOptions::Ecdh { named_curve } => {
       import_key_ec(key_data, named_curve)
@@ -190,12 +218,19 @@ macro_rules! jwt_b64_int_or_err {
 
 fn import_key_rsa_jwk(
   key_data: KeyData,
+  allow_insecure_key_sizes: bool,
 ) -> Result<ImportKeyResult, ImportKeyError> {
   match key_data {
     KeyData::JwkPublicRsa { n, e } => {
       jwt_b64_int_or_err!(modulus, &n, InvalidModulus);
       jwt_b64_int_or_err!(public_exponent, &e, InvalidPublicExponent);
 
+      let modulus_length = check_rsa_public_key(
+        &modulus,
+        &public_exponent,
+        allow_insecure_key_sizes,
+      )?;
+
       let public_key = rsa::pkcs1::RsaPublicKey {
         modulus,
         public_exponent,
@@ -208,7 +243,6 @@ fn import_key_rsa_jwk(
 
       let public_exponent =
         public_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = public_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
         raw_data: RustRawKeyData::Public(data.into()),
@@ -231,30 +265,45 @@ fn import_key_rsa_jwk(
       jwt_b64_int_or_err!(private_exponent, &d, InvalidPrivateExponent);
       jwt_b64_int_or_err!(prime1, &p, InvalidFirstPrimeFactor);
       jwt_b64_int_or_err!(prime2, &q, InvalidSecondPrimeFactor);
-      jwt_b64_int_or_err!(exponent1, &dp, InvalidFirstCRTExponent);
-      jwt_b64_int_or_err!(exponent2, &dq, InvalidSecondCRTExponent);
-      jwt_b64_int_or_err!(coefficient, &qi, InvalidCRTCoefficient);
-
-      let private_key = rsa::pkcs1::RsaPrivateKey {
+      let exponent1 = dp
+        .map(|dp| BASE64_URL_SAFE_FORGIVING.decode(dp))
+        .transpose()
+        .map_err(|_| ImportKeyError::InvalidFirstCRTExponent)?;
+      let exponent2 = dq
+        .map(|dq| BASE64_URL_SAFE_FORGIVING.decode(dq))
+        .transpose()
+        .map_err(|_| ImportKeyError::InvalidSecondCRTExponent)?;
+      let coefficient = qi
+        .map(|qi| BASE64_URL_SAFE_FORGIVING.decode(qi))
+        .transpose()
+        .map_err(|_| ImportKeyError::InvalidCRTCoefficient)?;
+
+      let components = RsaPrivateKeyComponents {
         modulus,
         public_exponent,
         private_exponent,
         prime1,
         prime2,
-        exponent1,
-        exponent2,
-        coefficient,
-        other_prime_infos: None,
+        exponent1: exponent1
+          .as_deref()
+          .map(UintRef::new)
+          .transpose()
+          .map_err(|_| ImportKeyError::InvalidFirstCRTExponent)?,
+        exponent2: exponent2
+          .as_deref()
+          .map(UintRef::new)
+          .transpose()
+          .map_err(|_| ImportKeyError::InvalidSecondCRTExponent)?,
+        coefficient: coefficient
+          .as_deref()
+          .map(UintRef::new)
+          .transpose()
+          .map_err(|_| ImportKeyError::InvalidCRTCoefficient)?,
       };
 
-      let mut data = Vec::new();
-      private_key
-        .encode_to_vec(&mut data)
-        .map_err(|_| ImportKeyError::InvalidRSAPrivateKey)?;
-
-      let public_exponent =
-        private_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = private_key.modulus.as_bytes().len() * 8;
+      let public_exponent = public_exponent.as_bytes().to_vec().into();
+      let (data, modulus_length) =
+        normalize_rsa_private_key(components, allow_insecure_key_sizes)?;
 
       Ok(ImportKeyResult::Rsa {
         raw_data: RustRawKeyData::Private(data.into()),
@@ -266,8 +315,187 @@ fn import_key_rsa_jwk(
   }
 }
 
+/// Smallest RSA modulus accepted unless `allowInsecureKeySizes` is set.
+const MIN_RSA_MODULUS_BITS: usize = 2048;
+/// Smallest RSA modulus accepted even with `allowInsecureKeySizes`.
+const MIN_INSECURE_RSA_MODULUS_BITS: usize = 512;
+const MAX_RSA_MODULUS_BITS: usize = 16384;
+/// Same upper bound as the `rsa` crate uses for public exponents.
+const MAX_RSA_PUBLIC_EXPONENT_BITS: usize = 33;
+/// Miller-Rabin rounds run on each prime factor, on top of the Lucas test
+/// `probably_prime` always performs.
+const RSA_PRIME_CHECK_ROUNDS: usize = 2;
+
+/// The parameters of a two-prime RSA private key. Missing CRT parameters are
+/// recomputed by `normalize_rsa_private_key`.
+struct RsaPrivateKeyComponents<'a> {
+  modulus: UintRef<'a>,
+  public_exponent: UintRef<'a>,
+  private_exponent: UintRef<'a>,
+  prime1: UintRef<'a>,
+  prime2: UintRef<'a>,
+  exponent1: Option<UintRef<'a>>,
+  exponent2: Option<UintRef<'a>>,
+  coefficient: Option<UintRef<'a>>,
+}
+
+impl<'a> TryFrom<&rsa::pkcs1::RsaPrivateKey<'a>>
+  for RsaPrivateKeyComponents<'a>
+{
+  type Error = ImportKeyError;
+
+  fn try_from(
+    key: &rsa::pkcs1::RsaPrivateKey<'a>,
+  ) -> Result<Self, ImportKeyError> {
+    // Multi-prime keys are not supported.
+    if key.other_prime_infos.is_some() {
+      return Err(ImportKeyError::InvalidRSAPrivateKey);
+    }
+    Ok(Self {
+      modulus: key.modulus,
+      public_exponent: key.public_exponent,
+      private_exponent: key.private_exponent,
+      prime1: key.prime1,
+      prime2: key.prime2,
+      exponent1: Some(key.exponent1),
+      exponent2: Some(key.exponent2),
+      coefficient: Some(key.coefficient),
+    })
+  }
+}
+
+fn to_biguint(value: &UintRef) -> BigUint {
+  BigUint::from_bytes_be(value.as_bytes())
+}
+
+fn is_even(value: &UintRef) -> bool {
+  !matches!(value.as_bytes().last(), Some(byte) if byte & 1 == 1)
+}
+
+/// Checks the modulus and public exponent of an RSA key and returns the
+/// modulus length in bits.
+fn check_rsa_public_key(
+  modulus: &UintRef,
+  public_exponent: &UintRef,
+  allow_insecure_key_sizes: bool,
+) -> Result<usize, ImportKeyError> {
+  let n = to_biguint(modulus);
+  let modulus_length = n.bits();
+  if !(MIN_INSECURE_RSA_MODULUS_BITS..=MAX_RSA_MODULUS_BITS)
+    .contains(&modulus_length)
+  {
+    return Err(ImportKeyError::UnsupportedRSAModulusLength);
+  }
+  if modulus_length < MIN_RSA_MODULUS_BITS && !allow_insecure_key_sizes {
+    return Err(ImportKeyError::InsecureRSAModulusLength);
+  }
+  if is_even(modulus) {
+    return Err(ImportKeyError::InvalidModulus);
+  }
+
+  let e = to_biguint(public_exponent);
+  if is_even(public_exponent)
+    || e.bits() < 2
+    || e.bits() > MAX_RSA_PUBLIC_EXPONENT_BITS
+    || e >= n
+  {
+    return Err(ImportKeyError::InvalidPublicExponent);
+  }
+
+  Ok(modulus_length)
+}
+
+/// Validates a two-prime RSA private key and re-encodes it as PKCS#1 with all
+/// CRT parameters present. Returns the encoded key and the modulus length in
+/// bits.
+///
+/// Keys that would make later operations misbehave are rejected: the factors
+/// must be distinct primes whose product is the modulus, `d * e` must be
+/// `1 mod λ(n)` and any CRT parameters that were supplied must match the ones
+/// derived from `p`, `q` and `d`. The errors never include key material.
+fn normalize_rsa_private_key(
+  key: RsaPrivateKeyComponents,
+  allow_insecure_key_sizes: bool,
+) -> Result<(Vec<u8>, usize), ImportKeyError> {
+  let modulus_length = check_rsa_public_key(
+    &key.modulus,
+    &key.public_exponent,
+    allow_insecure_key_sizes,
+  )?;
+
+  let n = to_biguint(&key.modulus);
+  let e = to_biguint(&key.public_exponent);
+  let d = to_biguint(&key.private_exponent);
+  let p = to_biguint(&key.prime1);
+  let q = to_biguint(&key.prime2);
+  let one = BigUint::one();
+  let two = BigUint::from(2u32);
+
+  if d <= one || d >= n {
+    return Err(ImportKeyError::InvalidPrivateExponent);
+  }
+  if &p * &q != n {
+    return Err(ImportKeyError::InconsistentRSAPrivateKey);
+  }
+  if p <= two
+    || q <= two
+    || p == q
+    || !probably_prime(&p, RSA_PRIME_CHECK_ROUNDS)
+    || !probably_prime(&q, RSA_PRIME_CHECK_ROUNDS)
+  {
+    return Err(ImportKeyError::InvalidRSAPrimeFactors);
+  }
+
+  // λ(n) = lcm(p - 1, q - 1), so checking both factors separately is
+  // equivalent to checking `d * e ≡ 1 mod λ(n)`.
+  let p1 = &p - &one;
+  let q1 = &q - &one;
+  let de = &d * &e;
+  if &de % &p1 != one || &de % &q1 != one {
+    return Err(ImportKeyError::InconsistentRSAPrivateKey);
+  }
+
+  let exponent1 = &d % &p1;
+  let exponent2 = &d % &q1;
+  // p is prime, so q^-1 mod p = q^(p - 2) mod p.
+  let coefficient = q.modpow(&(&p - &two), &p);
+
+  for (supplied, derived) in [
+    (key.exponent1.as_ref(), &exponent1),
+    (key.exponent2.as_ref(), &exponent2),
+    (key.coefficient.as_ref(), &coefficient),
+  ] {
+    if supplied.is_some_and(|supplied| to_biguint(supplied) != *derived) {
+      return Err(ImportKeyError::InconsistentRSAPrivateKey);
+    }
+  }
+
+  let exponent1 = exponent1.to_bytes_be();
+  let exponent2 = exponent2.to_bytes_be();
+  let coefficient = coefficient.to_bytes_be();
+  let private_key = rsa::pkcs1::RsaPrivateKey {
+    modulus: key.modulus,
+    public_exponent: key.public_exponent,
+    private_exponent: key.private_exponent,
+    prime1: key.prime1,
+    prime2: key.prime2,
+    exponent1: UintRef::new(&exponent1)?,
+    exponent2: UintRef::new(&exponent2)?,
+    coefficient: UintRef::new(&coefficient)?,
+    other_prime_infos: None,
+  };
+
+  let mut data = Vec::new();
+  private_key
+    .encode_to_vec(&mut data)
+    .map_err(|_| ImportKeyError::InvalidRSAPrivateKey)?;
+
+  Ok((data, modulus_length))
+}
+
 fn import_key_rsassa(
   key_data: KeyData,
+  allow_insecure_key_sizes: bool,
 ) -> Result<ImportKeyResult, ImportKeyError> {
   match key_data {
     KeyData::Spki(data) => {
@@ -297,10 +525,15 @@ fn import_key_rsassa(
         return Err(ImportKeyError::PublicKeyTooLong);
       }
 
+      let modulus_length = check_rsa_public_key(
+        &public_key.modulus,
+        &public_key.public_exponent,
+        allow_insecure_key_sizes,
+      )?;
+
       let data = pk_info.subject_public_key.raw_bytes().to_vec().into();
       let public_exponent =
         public_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = public_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
         raw_data: RustRawKeyData::Public(data),
@@ -332,19 +565,21 @@ fn import_key_rsassa(
         return Err(ImportKeyError::PrivateKeyTooLong);
       }
 
-      let data = pk_info.private_key.to_vec().into();
+      let (data, modulus_length) = normalize_rsa_private_key(
+        (&private_key).try_into()?,
+        allow_insecure_key_sizes,
+      )?;
       let public_exponent =
         private_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = private_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
-        raw_data: RustRawKeyData::Private(data),
+        raw_data: RustRawKeyData::Private(data.into()),
         modulus_length,
         public_exponent,
       })
     }
     KeyData::JwkPublicRsa { .. } | KeyData::JwkPrivateRsa { .. } => {
-      import_key_rsa_jwk(key_data)
+      import_key_rsa_jwk(key_data, allow_insecure_key_sizes)
     }
     _ => Err(SharedError::UnsupportedFormat.into()),
   }
@@ -352,6 +587,7 @@ fn import_key_rsassa(
 
 fn import_key_rsapss(
   key_data: KeyData,
+  allow_insecure_key_sizes: bool,
 ) -> Result<ImportKeyResult, ImportKeyError> {
   match key_data {
     KeyData::Spki(data) => {
@@ -381,10 +617,15 @@ fn import_key_rsapss(
         return Err(ImportKeyError::PublicKeyTooLong);
       }
 
+      let modulus_length = check_rsa_public_key(
+        &public_key.modulus,
+        &public_key.public_exponent,
+        allow_insecure_key_sizes,
+      )?;
+
       let data = pk_info.subject_public_key.raw_bytes().to_vec().into();
       let public_exponent =
         public_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = public_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
         raw_data: RustRawKeyData::Public(data),
@@ -416,19 +657,21 @@ fn import_key_rsapss(
         return Err(ImportKeyError::PrivateKeyTooLong);
       }
 
-      let data = pk_info.private_key.to_vec().into();
+      let (data, modulus_length) = normalize_rsa_private_key(
+        (&private_key).try_into()?,
+        allow_insecure_key_sizes,
+      )?;
       let public_exponent =
         private_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = private_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
-        raw_data: RustRawKeyData::Private(data),
+        raw_data: RustRawKeyData::Private(data.into()),
         modulus_length,
         public_exponent,
       })
     }
     KeyData::JwkPublicRsa { .. } | KeyData::JwkPrivateRsa { .. } => {
-      import_key_rsa_jwk(key_data)
+      import_key_rsa_jwk(key_data, allow_insecure_key_sizes)
     }
     _ => Err(SharedError::UnsupportedFormat.into()),
   }
@@ -436,6 +679,7 @@ fn import_key_rsapss(
 
 fn import_key_rsaoaep(
   key_data: KeyData,
+  allow_insecure_key_sizes: bool,
 ) -> Result<ImportKeyResult, ImportKeyError> {
   match key_data {
     KeyData::Spki(data) => {
@@ -465,10 +709,15 @@ fn import_key_rsaoaep(
         return Err(ImportKeyError::PublicKeyTooLong);
       }
 
+      let modulus_length = check_rsa_public_key(
+        &public_key.modulus,
+        &public_key.public_exponent,
+        allow_insecure_key_sizes,
+      )?;
+
       let data = pk_info.subject_public_key.raw_bytes().to_vec().into();
       let public_exponent =
         public_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = public_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
         raw_data: RustRawKeyData::Public(data),
@@ -500,19 +749,21 @@ fn import_key_rsaoaep(
         return Err(ImportKeyError::PrivateKeyTooLong);
       }
 
-      let data = pk_info.private_key.to_vec().into();
+      let (data, modulus_length) = normalize_rsa_private_key(
+        (&private_key).try_into()?,
+        allow_insecure_key_sizes,
+      )?;
       let public_exponent =
         private_key.public_exponent.as_bytes().to_vec().into();
-      let modulus_length = private_key.modulus.as_bytes().len() * 8;
 
       Ok(ImportKeyResult::Rsa {
-        raw_data: RustRawKeyData::Private(data),
+        raw_data: RustRawKeyData::Private(data.into()),
         modulus_length,
         public_exponent,
       })
     }
     KeyData::JwkPublicRsa { .. } | KeyData::JwkPrivateRsa { .. } => {
-      import_key_rsa_jwk(key_data)
+      import_key_rsa_jwk(key_data, allow_insecure_key_sizes)
     }
     _ => Err(SharedError::UnsupportedFormat.into()),
   }
@@ -546,24 +797,36 @@ fn import_key_ec_jwk_to_point(
   y: String,
   named_curve: EcNamedCurve,
 ) -> Result<Vec<u8>, ImportKeyError> {
+  // `from_affine_coordinates` doesn't validate anything, so the point is
+  // parsed as a public key to reject points that are not on the curve
+  // (including the identity).
   let point_bytes = match named_curve {
     EcNamedCurve::P256 => {
       let x = decode_b64url_to_field_bytes::<p256::NistP256>(&x)?;
       let y = decode_b64url_to_field_bytes::<p256::NistP256>(&y)?;
 
-      p256::EncodedPoint::from_affine_coordinates(&x, &y, false).to_bytes()
+      let point = p256::EncodedPoint::from_affine_coordinates(&x, &y, false);
+      p256::PublicKey::from_sec1_bytes(point.as_bytes())
+        .map_err(|_| ImportKeyError::InvalidP256ECPoint)?;
+      point.to_bytes()
     }
     EcNamedCurve::P384 => {
       let x = decode_b64url_to_field_bytes::<p384::NistP384>(&x)?;
       let y = decode_b64url_to_field_bytes::<p384::NistP384>(&y)?;
 
-      p384::EncodedPoint::from_affine_coordinates(&x, &y, false).to_bytes()
+      let point = p384::EncodedPoint::from_affine_coordinates(&x, &y, false);
+      p384::PublicKey::from_sec1_bytes(point.as_bytes())
+        .map_err(|_| ImportKeyError::InvalidP384ECPoint)?;
+      point.to_bytes()
     }
     EcNamedCurve::P521 => {
       let x = decode_b64url_to_field_bytes::<p521::NistP521>(&x)?;
       let y = decode_b64url_to_field_bytes::<p521::NistP521>(&y)?;
 
-      p521::EncodedPoint::from_affine_coordinates(&x, &y, false).to_bytes()
+      let point = p521::EncodedPoint::from_affine_coordinates(&x, &y, false);
+      p521::PublicKey::from_sec1_bytes(point.as_bytes())
+        .map_err(|_| ImportKeyError::InvalidP521ECPoint)?;
+      point.to_bytes()
     }
   };
 
@@ -582,31 +845,51 @@ fn import_key_ec_jwk(
         raw_data: RustRawKeyData::Public(point_bytes.into()),
       })
     }
-    KeyData::JwkPrivateEc { d, .. } => {
-      let pkcs8_der = match named_curve {
+    KeyData::JwkPrivateEc { x, y, d } => {
+      let point_bytes = import_key_ec_jwk_to_point(x, y, named_curve)?;
+
+      // `SecretKey::from_bytes` rejects scalars that are zero or not smaller
+      // than the curve order. The public point must belong to `d`.
+      let (pkcs8_der, expected_point) = match named_curve {
         EcNamedCurve::P256 => {
           let d = decode_b64url_to_field_bytes::<p256::NistP256>(&d)?;
-          let pk = p256::SecretKey::from_bytes(&d)?;
-
-          pk.to_pkcs8_der()
-            .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?
+          let pk = p256::SecretKey::from_bytes(&d)
+            .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?;
+
+          (
+            pk.to_pkcs8_der()
+              .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?,
+            pk.public_key().to_encoded_point(false).to_bytes(),
+          )
         }
         EcNamedCurve::P384 => {
           let d = decode_b64url_to_field_bytes::<p384::NistP384>(&d)?;
-          let pk = p384::SecretKey::from_bytes(&d)?;
-
-          pk.to_pkcs8_der()
-            .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?
+          let pk = p384::SecretKey::from_bytes(&d)
+            .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?;
+
+          (
+            pk.to_pkcs8_der()
+              .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?,
+            pk.public_key().to_encoded_point(false).to_bytes(),
+          )
         }
         EcNamedCurve::P521 => {
           let d = decode_b64url_to_field_bytes::<p521::NistP521>(&d)?;
-          let pk = p521::SecretKey::from_bytes(&d)?;
-
-          pk.to_pkcs8_der()
-            .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?
+          let pk = p521::SecretKey::from_bytes(&d)
+            .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?;
+
+          (
+            pk.to_pkcs8_der()
+              .map_err(|_| ImportKeyError::InvalidJWKPrivateKey)?,
+            pk.public_key().to_encoded_point(false).to_bytes(),
+          )
         }
       };
 
+      if *expected_point != *point_bytes {
+        return Err(ImportKeyError::PublicKeyMismatch);
+      }
+
       Ok(ImportKeyResult::Ec {
         raw_data: RustRawKeyData::Private(pkcs8_der.as_bytes().to_vec().into()),
       })
@@ -673,14 +956,22 @@ fn import_key_ec(
       })
     }
     KeyData::Pkcs8(data) => {
+      // 2-3.
       let pk = PrivateKeyInfo::from_der(data.as_ref())
         .map_err(|_| ImportKeyError::ExpectedValidPkcs8Data)?;
-      let named_curve_alg = pk
+
+      // 4.
+      if pk.algorithm.oid != elliptic_curve::ALGORITHM_OID {
+        return Err(ImportKeyError::UnsupportedAlgorithm);
+      }
+
+      // 5-7.
+      let named_curve_alg: const_oid::ObjectIdentifier = pk
         .algorithm
         .parameters
         .ok_or(ImportKeyError::MalformedParameters)?
         .try_into()
-        .unwrap();
+        .map_err(|_| ImportKeyError::MalformedParameters)?;
 
       let pk_named_curve = match named_curve_alg {
         // id-secp256r1
@@ -696,6 +987,16 @@ fn import_key_ec(
         return Err(ImportKeyError::CurveMismatch);
       }
 
+      // 8-9. The ECPrivateKey structure must hold a valid private key.
+      let valid = match named_curve {
+        EcNamedCurve::P256 => p256::SecretKey::from_pkcs8_der(&data).is_ok(),
+        EcNamedCurve::P384 => p384::SecretKey::from_pkcs8_der(&data).is_ok(),
+        EcNamedCurve::P521 => p521::SecretKey::from_pkcs8_der(&data).is_ok(),
+      };
+      if !valid {
+        return Err(ImportKeyError::InvalidKeyData);
+      }
+
       Ok(ImportKeyResult::Ec {
         raw_data: RustRawKeyData::Private(data.to_vec().into()),
       })
@@ -826,3 +1127,192 @@ fn import_key_hmac(
     _ => return Err(SharedError::UnsupportedFormat.into()),
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use std::time::Instant;
+
+  use elliptic_curve::rand_core::OsRng;
+  use once_cell::sync::Lazy;
+  use rsa::pkcs1::DecodeRsaPrivateKey;
+  use rsa::pkcs1::EncodeRsaPrivateKey;
+
+  use super::*;
+
+  static KEY_2048: Lazy<Vec<u8>> = Lazy::new(|| generate(2048));
+
+  fn generate(bits: usize) -> Vec<u8> {
+    let key = rsa::RsaPrivateKey::new(&mut OsRng, bits).unwrap();
+    key.to_pkcs1_der().unwrap().as_bytes().to_vec()
+  }
+
+  fn import(der: &[u8]) -> Result<(Vec<u8>, usize), ImportKeyError> {
+    let key = rsa::pkcs1::RsaPrivateKey::from_der(der)?;
+    normalize_rsa_private_key((&key).try_into()?, false)
+  }
+
+  /// Flip a single bit of `value`, returning the new big-endian bytes.
+  fn flip_bit(value: &UintRef, bit: usize) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    let index = bytes.len() - 1 - bit / 8;
+    bytes[index] ^= 1 << (bit % 8);
+    bytes
+  }
+
+  #[test]
+  fn valid_key_is_unchanged() {
+    let (data, modulus_length) = import(&KEY_2048).unwrap();
+    assert_eq!(data, *KEY_2048);
+    assert_eq!(modulus_length, 2048);
+  }
+
+  #[test]
+  fn missing_crt_parameters_are_recomputed() {
+    let key = rsa::pkcs1::RsaPrivateKey::from_der(&KEY_2048).unwrap();
+    let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+    components.exponent1 = None;
+    components.exponent2 = None;
+    components.coefficient = None;
+    let (data, _) = normalize_rsa_private_key(components, false).unwrap();
+    assert_eq!(data, *KEY_2048);
+  }
+
+  #[test]
+  fn mutated_keys_are_rejected() {
+    let key = rsa::pkcs1::RsaPrivateKey::from_der(&KEY_2048).unwrap();
+    let check = |components: RsaPrivateKeyComponents| {
+      normalize_rsa_private_key(components, false).unwrap_err()
+    };
+
+    for bit in [0, 1, 7, 64, 1000, 2040] {
+      let d = flip_bit(&key.private_exponent, bit);
+      let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+      components.private_exponent = UintRef::new(&d).unwrap();
+      check(components);
+    }
+
+    // Swapping the factors invalidates the supplied CRT parameters...
+    let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+    std::mem::swap(&mut components.prime1, &mut components.prime2);
+    assert!(matches!(
+      check(components),
+      ImportKeyError::InconsistentRSAPrivateKey
+    ));
+    // ...but is fine if they are recomputed.
+    let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+    std::mem::swap(&mut components.prime1, &mut components.prime2);
+    components.exponent1 = None;
+    components.exponent2 = None;
+    components.coefficient = None;
+    normalize_rsa_private_key(components, false).unwrap();
+
+    let n = key.modulus.as_bytes();
+    let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+    components.modulus = UintRef::new(&n[..n.len() - 1]).unwrap();
+    check(components);
+
+    let p = key.prime1.as_bytes();
+    let p_squared =
+      (to_biguint(&key.prime1) * to_biguint(&key.prime1)).to_bytes_be();
+    let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+    components.modulus = UintRef::new(&p_squared).unwrap();
+    components.prime2 = UintRef::new(p).unwrap();
+    check(components);
+
+    let qi = flip_bit(&key.coefficient, 3);
+    let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+    components.coefficient = UintRef::new(&qi).unwrap();
+    assert!(matches!(
+      check(components),
+      ImportKeyError::InconsistentRSAPrivateKey
+    ));
+
+    for e in [
+      &[0x01, 0x00, 0x00][..],
+      &[0x01],
+      &[0x02, 0x00, 0x00, 0x00, 0x01],
+    ] {
+      let mut components = RsaPrivateKeyComponents::try_from(&key).unwrap();
+      components.public_exponent = UintRef::new(e).unwrap();
+      assert!(matches!(
+        check(components),
+        ImportKeyError::InvalidPublicExponent
+      ));
+    }
+  }
+
+  #[test]
+  fn random_mutations_do_not_panic() {
+    // xorshift, so failures are reproducible.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      state as usize
+    };
+    for _ in 0..200 {
+      let mut der = KEY_2048.clone();
+      for _ in 0..1 + next() % 4 {
+        let index = next() % der.len();
+        der[index] ^= 1 << (next() % 8);
+      }
+      if let Ok((data, _)) = import(&der) {
+        // Anything that is accepted must be usable.
+        let key = rsa::RsaPrivateKey::from_pkcs1_der(&data).unwrap();
+        key
+          .sign(rsa::Pkcs1v15Sign::new_unprefixed(), &[1, 2, 3])
+          .unwrap();
+      }
+    }
+  }
+
+  #[test]
+  fn insecure_key_sizes() {
+    let der = generate(1024);
+    let key = rsa::pkcs1::RsaPrivateKey::from_der(&der).unwrap();
+    assert!(matches!(
+      normalize_rsa_private_key((&key).try_into().unwrap(), false),
+      Err(ImportKeyError::InsecureRSAModulusLength)
+    ));
+    let (_, modulus_length) =
+      normalize_rsa_private_key((&key).try_into().unwrap(), true).unwrap();
+    assert_eq!(modulus_length, 1024);
+
+    let modulus = [0xff; 32];
+    assert!(matches!(
+      check_rsa_public_key(
+        &UintRef::new(&modulus).unwrap(),
+        &UintRef::new(&[3]).unwrap(),
+        true
+      ),
+      Err(ImportKeyError::UnsupportedRSAModulusLength)
+    ));
+  }
+
+  #[test]
+  fn validation_cost_is_reasonable() {
+    const RUNS: u32 = 5;
+    let key = rsa::RsaPrivateKey::from_pkcs1_der(&KEY_2048).unwrap();
+    let mut sign = Duration::ZERO;
+    let mut validate = Duration::ZERO;
+    for _ in 0..RUNS {
+      let start = Instant::now();
+      key
+        .sign(rsa::Pkcs1v15Sign::new_unprefixed(), &[1, 2, 3])
+        .unwrap();
+      sign += start.elapsed();
+
+      let start = Instant::now();
+      import(&KEY_2048).unwrap();
+      validate += start.elapsed();
+    }
+    // Validation runs a handful of modular exponentiations, comparable to a
+    // few private key operations. Leave plenty of headroom for noisy CI.
+    assert!(
+      validate < sign * 25,
+      "validation took {validate:?}, signing took {sign:?}"
+    );
+  }
+}
@@ -111,6 +111,11 @@ interface EcdsaParams extends Algorithm {
 /** @category Crypto */
 interface RsaHashedImportParams extends Algorithm {
   hash: HashAlgorithmIdentifier;
+  /** Non-standard. Allow importing keys with a modulus shorter than 2048
+   * bits. Keys below 512 bits are always rejected.
+   *
+   * @default {false} */
+  allowInsecureKeySizes?: boolean;
 }
 
 /** @category Crypto */
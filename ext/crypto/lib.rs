@@ -46,24 +46,32 @@ use sha2::Digest;
 use sha2::Sha256;
 use sha2::Sha384;
 use sha2::Sha512;
+use std::borrow::Cow;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::path::PathBuf;
 
 pub use rand; // Re-export rand
 
+mod bcrypt_pbkdf;
 mod decrypt;
+mod digest_context;
 mod ed25519;
 mod encrypt;
 mod export_key;
 mod generate_key;
 mod import_key;
 mod key;
+mod password_wrap;
+mod rsa_oaep;
 mod shared;
+mod ssh;
 mod x25519;
 mod x448;
 
 pub use crate::decrypt::op_crypto_decrypt;
 pub use crate::decrypt::DecryptError;
+pub use crate::digest_context::DigestError;
 pub use crate::ed25519::Ed25519Error;
 pub use crate::encrypt::op_crypto_encrypt;
 pub use crate::encrypt::EncryptError;
@@ -77,13 +85,19 @@ use crate::key::Algorithm;
 use crate::key::CryptoHash;
 use crate::key::CryptoNamedCurve;
 use crate::key::HkdfOutput;
+pub use crate::password_wrap::PasswordWrapError;
+use crate::shared::EcNamedCurve;
 pub use crate::shared::SharedError;
 use crate::shared::V8RawKeyData;
+pub use crate::ssh::SshError;
 pub use crate::x25519::X25519Error;
 pub use crate::x448::X448Error;
 
+pub const UNSTABLE_FEATURE_NAME: &str = "crypto";
+
 deno_core::extension!(deno_crypto,
   deps = [ deno_webidl, deno_web ],
+  parameters = [P: CryptoPermissions],
   ops = [
     op_crypto_get_random_values,
     op_crypto_generate_key,
@@ -95,9 +109,22 @@ deno_core::extension!(deno_crypto,
     op_crypto_encrypt,
     op_crypto_decrypt,
     op_crypto_subtle_digest,
+    digest_context::op_crypto_digest_init,
+    digest_context::op_crypto_digest_update,
+    digest_context::op_crypto_digest_final,
+    digest_context::op_crypto_hmac_init,
+    digest_context::op_crypto_hmac_update,
+    digest_context::op_crypto_hmac_final,
+    digest_context::op_crypto_digest_file<P>,
     op_crypto_random_uuid,
     op_crypto_wrap_key,
     op_crypto_unwrap_key,
+    password_wrap::op_crypto_password_wrap,
+    password_wrap::op_crypto_password_unwrap,
+    ssh::op_ssh_parse_public_key,
+    ssh::op_ssh_verify_signature,
+    ssh::op_ssh_parse_private_key,
+    ssh::op_ssh_sign,
     op_crypto_base64url_decode,
     op_crypto_base64url_encode,
     x25519::op_crypto_generate_x25519_keypair,
@@ -132,6 +159,30 @@ deno_core::extension!(deno_crypto,
   },
 );
 
+pub trait CryptoPermissions {
+  #[must_use = "the resolved return value to mitigate time-of-check to time-of-use issues"]
+  fn check_read<'a>(
+    &mut self,
+    p: &'a Path,
+    api_name: &str,
+  ) -> Result<Cow<'a, Path>, deno_core::error::AnyError>;
+}
+
+impl CryptoPermissions for deno_permissions::PermissionsContainer {
+  #[inline(always)]
+  fn check_read<'a>(
+    &mut self,
+    path: &'a Path,
+    api_name: &str,
+  ) -> Result<Cow<'a, Path>, deno_core::error::AnyError> {
+    deno_permissions::PermissionsContainer::check_read_path(
+      self,
+      path,
+      Some(api_name),
+    )
+  }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
   #[error(transparent)]
@@ -386,8 +437,12 @@ pub async fn op_crypto_verify_key(
         use rsa::pkcs1v15::Signature;
         use rsa::pkcs1v15::VerifyingKey;
         let public_key = read_rsa_public_key(args.key)?;
-        let signature: Signature = args.signature.as_ref().try_into()?;
-        match args.hash.ok_or_else(|| Error::MissingArgumentHash)? {
+        let hash = args.hash.ok_or_else(|| Error::MissingArgumentHash)?;
+        // A malformed signature doesn't verify, it isn't an error.
+        let Ok(signature) = Signature::try_from(args.signature.as_ref()) else {
+          return Ok(false);
+        };
+        match hash {
           CryptoHash::Sha1 => {
             let verifying_key = VerifyingKey::<Sha1>::new(public_key);
             verifying_key.verify(data, &signature).is_ok()
@@ -493,7 +548,7 @@ pub struct DeriveKeyArg {
   iterations: Option<u32>,
   // ECDH
   public_key: Option<KeyData>,
-  named_curve: Option<CryptoNamedCurve>,
+  named_curve: Option<EcNamedCurve>,
   // HKDF
   info: Option<JsBuffer>,
 }
@@ -545,7 +600,7 @@ pub async fn op_crypto_derive_bits(
           .ok_or_else(|| Error::MissingArgumentPublicKey)?;
 
         match named_curve {
-          CryptoNamedCurve::P256 => {
+          EcNamedCurve::P256 => {
             let secret_key = p256::SecretKey::from_pkcs8_der(&args.key.data)
               .map_err(|_| Error::DecodePrivateKey)?;
 
@@ -578,7 +633,7 @@ pub async fn op_crypto_derive_bits(
             // raw serialized x-coordinate of the computed point
             Ok(shared_secret.raw_secret_bytes().to_vec().into())
           }
-          CryptoNamedCurve::P384 => {
+          EcNamedCurve::P384 => {
             let secret_key = p384::SecretKey::from_pkcs8_der(&args.key.data)
               .map_err(|_| Error::DecodePrivateKey)?;
 
@@ -608,6 +663,39 @@ pub async fn op_crypto_derive_bits(
               public_key.as_affine(),
             );
 
+            // raw serialized x-coordinate of the computed point
+            Ok(shared_secret.raw_secret_bytes().to_vec().into())
+          }
+          EcNamedCurve::P521 => {
+            let secret_key = p521::SecretKey::from_pkcs8_der(&args.key.data)
+              .map_err(|_| Error::DecodePrivateKey)?;
+
+            let public_key = match public_key.r#type {
+              KeyType::Private => {
+                p521::SecretKey::from_pkcs8_der(&public_key.data)
+                  .map_err(|_| Error::DecodePrivateKey)?
+                  .public_key()
+              }
+              KeyType::Public => {
+                let point = p521::EncodedPoint::from_bytes(public_key.data)
+                  .map_err(|_| Error::DecodePrivateKey)?;
+
+                let pk = p521::PublicKey::from_encoded_point(&point);
+                // pk is a constant time Option.
+                if pk.is_some().into() {
+                  pk.unwrap()
+                } else {
+                  return Err(Error::DecodePrivateKey);
+                }
+              }
+              _ => unreachable!(),
+            };
+
+            let shared_secret = p521::elliptic_curve::ecdh::diffie_hellman(
+              secret_key.to_nonzero_scalar(),
+              public_key.as_affine(),
+            );
+
             // raw serialized x-coordinate of the computed point
             Ok(shared_secret.raw_secret_bytes().to_vec().into())
           }
@@ -0,0 +1,354 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Password based wrapping of secrets for storage at rest.
+//!
+//! The key encryption key is derived from the password with
+//! PBKDF2-HMAC-SHA256 and a random salt, and the data is wrapped with
+//! AES-256 key wrap with padding (RFC 5649), so inputs of any length can be
+//! wrapped. The result is a self-describing envelope:
+//!
+//! ```text
+//! | version (1) | iterations (4, BE) | salt length (1) | salt | wrapped data |
+//! ```
+//!
+//! The version byte identifies the KDF and wrapping scheme, so that envelopes
+//! written today can still be opened after a future upgrade.
+
+use std::cell::RefCell;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+use aes_kw::KekAes256;
+use deno_core::op2;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_core::ToJsBuffer;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::pbkdf2;
+use serde::Deserialize;
+
+/// PBKDF2-HMAC-SHA256 and AES-256-KWP.
+const ENVELOPE_VERSION_1: u8 = 1;
+const HEADER_LEN: usize = 1 + 4 + 1;
+
+const DEFAULT_ITERATIONS: u32 = 600_000;
+/// Upper bound on the iteration count, so that opening a crafted envelope
+/// can't tie up a thread for minutes.
+const MAX_ITERATIONS: u32 = 10_000_000;
+const DEFAULT_SALT_LENGTH: usize = 16;
+const MIN_SALT_LENGTH: usize = 8;
+const MAX_SALT_LENGTH: usize = u8::MAX as usize;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PasswordWrapError {
+  #[error("iterations must be between 1 and {MAX_ITERATIONS}")]
+  InvalidIterations,
+  #[error(
+    "saltLength must be between {MIN_SALT_LENGTH} and {MAX_SALT_LENGTH}"
+  )]
+  InvalidSaltLength,
+  #[error("data must not be empty")]
+  EmptyData,
+  #[error("Failed to wrap data")]
+  WrapFailed,
+  /// Deliberately the same for a wrong password, a corrupted envelope and an
+  /// unknown envelope version.
+  #[error("Failed to unwrap data: wrong password or corrupted envelope")]
+  UnwrapFailed,
+  #[error(transparent)]
+  JoinError(#[from] tokio::task::JoinError),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordWrapOptions {
+  iterations: Option<u32>,
+  salt_length: Option<usize>,
+}
+
+fn check_unstable(state: &Rc<RefCell<OpState>>, api_name: &str) {
+  state
+    .borrow()
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, api_name);
+}
+
+fn derive_kek(
+  password: &[u8],
+  salt: &[u8],
+  iterations: NonZeroU32,
+) -> [u8; 32] {
+  let mut kek = [0; 32];
+  pbkdf2::derive(
+    pbkdf2::PBKDF2_HMAC_SHA256,
+    iterations,
+    salt,
+    password,
+    &mut kek,
+  );
+  kek
+}
+
+pub fn password_wrap(
+  data: &[u8],
+  password: &[u8],
+  options: PasswordWrapOptions,
+) -> Result<Vec<u8>, PasswordWrapError> {
+  let iterations = options.iterations.unwrap_or(DEFAULT_ITERATIONS);
+  let iterations = NonZeroU32::new(iterations)
+    .filter(|i| i.get() <= MAX_ITERATIONS)
+    .ok_or(PasswordWrapError::InvalidIterations)?;
+  let salt_length = options.salt_length.unwrap_or(DEFAULT_SALT_LENGTH);
+  if !(MIN_SALT_LENGTH..=MAX_SALT_LENGTH).contains(&salt_length) {
+    return Err(PasswordWrapError::InvalidSaltLength);
+  }
+  if data.is_empty() {
+    return Err(PasswordWrapError::EmptyData);
+  }
+
+  let mut salt = vec![0; salt_length];
+  OsRng.fill_bytes(&mut salt);
+
+  let kek = derive_kek(password, &salt, iterations);
+  let wrapped = KekAes256::new((&kek[..]).into())
+    .wrap_with_padding_vec(data)
+    .map_err(|_| PasswordWrapError::WrapFailed)?;
+
+  let mut envelope =
+    Vec::with_capacity(HEADER_LEN + salt_length + wrapped.len());
+  envelope.push(ENVELOPE_VERSION_1);
+  envelope.extend_from_slice(&iterations.get().to_be_bytes());
+  envelope.push(salt_length as u8);
+  envelope.extend_from_slice(&salt);
+  envelope.extend_from_slice(&wrapped);
+  Ok(envelope)
+}
+
+pub fn password_unwrap(
+  envelope: &[u8],
+  password: &[u8],
+) -> Result<Vec<u8>, PasswordWrapError> {
+  let (header, rest) = envelope
+    .split_first_chunk::<HEADER_LEN>()
+    .ok_or(PasswordWrapError::UnwrapFailed)?;
+  if header[0] != ENVELOPE_VERSION_1 {
+    return Err(PasswordWrapError::UnwrapFailed);
+  }
+  let iterations =
+    u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+  let iterations = NonZeroU32::new(iterations)
+    .filter(|i| i.get() <= MAX_ITERATIONS)
+    .ok_or(PasswordWrapError::UnwrapFailed)?;
+  let salt_length = header[5] as usize;
+  if rest.len() < salt_length {
+    return Err(PasswordWrapError::UnwrapFailed);
+  }
+  let (salt, wrapped) = rest.split_at(salt_length);
+
+  let kek = derive_kek(password, salt, iterations);
+  KekAes256::new((&kek[..]).into())
+    .unwrap_with_padding_vec(wrapped)
+    .map_err(|_| PasswordWrapError::UnwrapFailed)
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_crypto_password_wrap(
+  state: Rc<RefCell<OpState>>,
+  #[buffer] data: JsBuffer,
+  #[buffer] password: JsBuffer,
+  #[serde] options: Option<PasswordWrapOptions>,
+) -> Result<ToJsBuffer, PasswordWrapError> {
+  check_unstable(&state, "Deno.wrapWithPassword");
+  let options = options.unwrap_or_default();
+  let envelope = deno_core::unsync::spawn_blocking(move || {
+    password_wrap(&data, &password, options)
+  })
+  .await??;
+  Ok(envelope.into())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_crypto_password_unwrap(
+  state: Rc<RefCell<OpState>>,
+  #[buffer] envelope: JsBuffer,
+  #[buffer] password: JsBuffer,
+) -> Result<ToJsBuffer, PasswordWrapError> {
+  check_unstable(&state, "Deno.unwrapWithPassword");
+  let data = deno_core::unsync::spawn_blocking(move || {
+    password_unwrap(&envelope, &password)
+  })
+  .await??;
+  Ok(data.into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aes_kw::KekAes128;
+  use aes_kw::KekAes192;
+  use rand::Rng;
+
+  fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+      .collect()
+  }
+
+  fn fast_options() -> PasswordWrapOptions {
+    PasswordWrapOptions {
+      iterations: Some(16),
+      salt_length: None,
+    }
+  }
+
+  #[test]
+  fn rfc3394_vectors() {
+    // Section 4.1: 128 bits of key data with a 128-bit KEK.
+    let kek = hex("000102030405060708090A0B0C0D0E0F");
+    let wrapped = KekAes128::new((&kek[..]).into())
+      .wrap_vec(&hex("00112233445566778899AABBCCDDEEFF"))
+      .unwrap();
+    assert_eq!(
+      wrapped,
+      hex("1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5")
+    );
+
+    // Section 4.6: 256 bits of key data with a 256-bit KEK.
+    let kek =
+      hex("000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F");
+    let data =
+      hex("00112233445566778899AABBCCDDEEFF000102030405060708090A0B0C0D0E0F");
+    let kek = KekAes256::new((&kek[..]).into());
+    let wrapped = kek.wrap_vec(&data).unwrap();
+    assert_eq!(
+      wrapped,
+      hex(
+        "28C9F404C4B810F4CBCCB35CFB87F8263F5786E2D80ED326CBC7F0E71A99F43BFB988B9B7A02DD21"
+      )
+    );
+    assert_eq!(kek.unwrap_vec(&wrapped).unwrap(), data);
+  }
+
+  #[test]
+  fn rfc5649_vectors() {
+    let kek = hex("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8");
+    let kek = KekAes192::new((&kek[..]).into());
+
+    let data = hex("c37b7e6492584340bed12207808941155068f738");
+    let wrapped = kek.wrap_with_padding_vec(&data).unwrap();
+    assert_eq!(
+      wrapped,
+      hex("138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a")
+    );
+    assert_eq!(kek.unwrap_with_padding_vec(&wrapped).unwrap(), data);
+
+    let data = hex("466f7250617369");
+    let wrapped = kek.wrap_with_padding_vec(&data).unwrap();
+    assert_eq!(wrapped, hex("afbeb0f07dfbf5419200f2ccb50bb24f"));
+    assert_eq!(kek.unwrap_with_padding_vec(&wrapped).unwrap(), data);
+  }
+
+  #[test]
+  fn envelope_layout() {
+    let envelope = password_wrap(
+      b"secret",
+      b"hunter2",
+      PasswordWrapOptions {
+        iterations: Some(1000),
+        salt_length: Some(20),
+      },
+    )
+    .unwrap();
+    assert_eq!(envelope[0], ENVELOPE_VERSION_1);
+    assert_eq!(&envelope[1..5], &1000u32.to_be_bytes());
+    assert_eq!(envelope[5], 20);
+    // 6 bytes of data are padded to one 8 byte block, plus the IV block.
+    assert_eq!(envelope.len(), HEADER_LEN + 20 + 16);
+  }
+
+  #[test]
+  fn round_trip_random_lengths() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..64 {
+      let len = rng.gen_range(1..300);
+      let mut data = vec![0; len];
+      rng.fill_bytes(&mut data);
+      let mut password = vec![0; rng.gen_range(0..32)];
+      rng.fill_bytes(&mut password);
+
+      let envelope = password_wrap(&data, &password, fast_options()).unwrap();
+      assert_eq!(password_unwrap(&envelope, &password).unwrap(), data);
+    }
+  }
+
+  #[test]
+  fn salt_is_random() {
+    let a = password_wrap(b"secret", b"pw", fast_options()).unwrap();
+    let b = password_wrap(b"secret", b"pw", fast_options()).unwrap();
+    assert_ne!(a, b);
+  }
+
+  #[test]
+  fn wrong_password_fails() {
+    let envelope = password_wrap(b"secret", b"pw", fast_options()).unwrap();
+    assert!(matches!(
+      password_unwrap(&envelope, b"wrong"),
+      Err(PasswordWrapError::UnwrapFailed)
+    ));
+  }
+
+  #[test]
+  fn corrupted_envelope_fails() {
+    let envelope =
+      password_wrap(b"a secret of some length", b"pw", fast_options()).unwrap();
+    for i in 0..envelope.len() {
+      let mut corrupted = envelope.clone();
+      corrupted[i] ^= 0x01;
+      assert!(
+        matches!(
+          password_unwrap(&corrupted, b"pw"),
+          Err(PasswordWrapError::UnwrapFailed)
+        ),
+        "corruption at byte {i} was not detected"
+      );
+    }
+    for len in 0..envelope.len() {
+      assert!(matches!(
+        password_unwrap(&envelope[..len], b"pw"),
+        Err(PasswordWrapError::UnwrapFailed)
+      ));
+    }
+  }
+
+  #[test]
+  fn invalid_options() {
+    let options = |iterations, salt_length| PasswordWrapOptions {
+      iterations: Some(iterations),
+      salt_length: Some(salt_length),
+    };
+    assert!(matches!(
+      password_wrap(b"x", b"pw", options(0, 16)),
+      Err(PasswordWrapError::InvalidIterations)
+    ));
+    assert!(matches!(
+      password_wrap(b"x", b"pw", options(MAX_ITERATIONS + 1, 16)),
+      Err(PasswordWrapError::InvalidIterations)
+    ));
+    assert!(matches!(
+      password_wrap(b"x", b"pw", options(16, 4)),
+      Err(PasswordWrapError::InvalidSaltLength)
+    ));
+    assert!(matches!(
+      password_wrap(b"x", b"pw", options(16, 256)),
+      Err(PasswordWrapError::InvalidSaltLength)
+    ));
+    assert!(matches!(
+      password_wrap(b"", b"pw", fast_options()),
+      Err(PasswordWrapError::EmptyData)
+    ));
+  }
+}
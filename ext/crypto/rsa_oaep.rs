@@ -0,0 +1,236 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! RSAES-OAEP, RFC 8017 section 7.1, with labels of arbitrary bytes.
+//!
+//! `rsa::Oaep` takes the label as a `String`, so a WebCrypto label that
+//! isn't UTF-8 can't be passed through it. The padding is done here instead,
+//! on top of the raw RSA primitives.
+//!
+//! Decryption doesn't branch on the ciphertext: a ciphertext of the wrong
+//! length is still decrypted, and the checks of the padding are accumulated
+//! in constant time and only looked at once the whole message has been
+//! decoded. Every invalid ciphertext thus fails with the same error after the
+//! same amount of work, so the error can't be used as a padding oracle.
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rsa::hazmat::rsa_decrypt_and_check;
+use rsa::hazmat::rsa_encrypt;
+use rsa::traits::PublicKeyParts;
+use rsa::BigUint;
+use rsa::RsaPrivateKey;
+use rsa::RsaPublicKey;
+use sha1::Sha1;
+use sha2::Digest;
+use sha2::Sha256;
+use sha2::Sha384;
+use sha2::Sha512;
+use subtle::Choice;
+use subtle::ConditionallySelectable;
+use subtle::ConstantTimeEq;
+
+use crate::shared::ShaHash;
+
+/// Deliberately carries no detail about what went wrong.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OaepError;
+
+fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+  let mut hasher = D::new();
+  for part in parts {
+    hasher.update(part);
+  }
+  hasher.finalize().to_vec()
+}
+
+fn hash(hash: ShaHash, parts: &[&[u8]]) -> Vec<u8> {
+  match hash {
+    ShaHash::Sha1 => digest::<Sha1>(parts),
+    ShaHash::Sha256 => digest::<Sha256>(parts),
+    ShaHash::Sha384 => digest::<Sha384>(parts),
+    ShaHash::Sha512 => digest::<Sha512>(parts),
+  }
+}
+
+fn hash_len(hash: ShaHash) -> usize {
+  match hash {
+    ShaHash::Sha1 => 20,
+    ShaHash::Sha256 => 32,
+    ShaHash::Sha384 => 48,
+    ShaHash::Sha512 => 64,
+  }
+}
+
+/// XORs `out` with the MGF1 mask generated from `seed`.
+fn mgf1_xor(hash_alg: ShaHash, seed: &[u8], out: &mut [u8]) {
+  for (counter, chunk) in out.chunks_mut(hash_len(hash_alg)).enumerate() {
+    let mask = hash(hash_alg, &[seed, &(counter as u32).to_be_bytes()]);
+    for (byte, mask) in chunk.iter_mut().zip(mask) {
+      *byte ^= mask;
+    }
+  }
+}
+
+/// Big-endian bytes of `n`, left padded with zeros to `len` bytes.
+fn to_padded_bytes(n: &BigUint, len: usize) -> Vec<u8> {
+  let bytes = n.to_bytes_be();
+  let mut out = vec![0; len.saturating_sub(bytes.len())];
+  out.extend_from_slice(&bytes);
+  out
+}
+
+pub fn encrypt(
+  key: &RsaPublicKey,
+  hash_alg: ShaHash,
+  label: &[u8],
+  message: &[u8],
+) -> Result<Vec<u8>, OaepError> {
+  let k = key.size();
+  let h_len = hash_len(hash_alg);
+  if k < 2 * h_len + 2 || message.len() > k - 2 * h_len - 2 {
+    return Err(OaepError);
+  }
+
+  // EM = 0x00 || maskedSeed || maskedDB
+  // DB = lHash || PS || 0x01 || M
+  let mut em = vec![0; k];
+  let (seed, db) = em[1..].split_at_mut(h_len);
+  db[..h_len].copy_from_slice(&hash(hash_alg, &[label]));
+  let message_start = db.len() - message.len();
+  db[message_start - 1] = 1;
+  db[message_start..].copy_from_slice(message);
+  OsRng.fill_bytes(seed);
+  mgf1_xor(hash_alg, seed, db);
+  mgf1_xor(hash_alg, db, seed);
+
+  let c =
+    rsa_encrypt(key, &BigUint::from_bytes_be(&em)).map_err(|_| OaepError)?;
+  Ok(to_padded_bytes(&c, k))
+}
+
+pub fn decrypt(
+  key: &RsaPrivateKey,
+  hash_alg: ShaHash,
+  label: &[u8],
+  ciphertext: &[u8],
+) -> Result<Vec<u8>, OaepError> {
+  let k = key.size();
+  let h_len = hash_len(hash_alg);
+  // depends on the key only
+  if k < 2 * h_len + 2 {
+    return Err(OaepError);
+  }
+
+  let mut valid = (ciphertext.len() as u64).ct_eq(&(k as u64));
+  let mut c = vec![0; k];
+  let n = ciphertext.len().min(k);
+  c[..n].copy_from_slice(&ciphertext[..n]);
+  // Fails for c >= n only, which the caller can tell from the public key.
+  let (decrypted, mut em) = match rsa_decrypt_and_check(
+    key,
+    Some(&mut OsRng),
+    &BigUint::from_bytes_be(&c),
+  ) {
+    Ok(m) => (Choice::from(1), to_padded_bytes(&m, k)),
+    Err(_) => (Choice::from(0), vec![0; k]),
+  };
+  valid &= decrypted;
+
+  let (y, rest) = em.split_at_mut(1);
+  let (seed, db) = rest.split_at_mut(h_len);
+  mgf1_xor(hash_alg, db, seed);
+  mgf1_xor(hash_alg, seed, db);
+
+  valid &= y[0].ct_eq(&0);
+  valid &= db[..h_len].ct_eq(&hash(hash_alg, &[label]));
+
+  // PS is zero or more 0x00 bytes, terminated by 0x01.
+  let mut looking = Choice::from(1);
+  let mut separator = 0u32;
+  let mut bad_padding = Choice::from(0);
+  for (i, byte) in db[h_len..].iter().enumerate() {
+    let is_zero = byte.ct_eq(&0);
+    let is_one = byte.ct_eq(&1);
+    separator.conditional_assign(&(i as u32), looking & is_one);
+    bad_padding |= looking & !is_zero & !is_one;
+    looking &= !is_one;
+  }
+  valid &= !looking & !bad_padding;
+
+  if !bool::from(valid) {
+    return Err(OaepError);
+  }
+  Ok(db[h_len + separator as usize + 1..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn new_key() -> RsaPrivateKey {
+    RsaPrivateKey::new(&mut OsRng, 1024).unwrap()
+  }
+
+  #[test]
+  fn round_trip_with_binary_label() {
+    let key = new_key();
+    let label = [0xff, 0xfe, 0x00, 0x80];
+    for hash_alg in [ShaHash::Sha1, ShaHash::Sha256, ShaHash::Sha384] {
+      let messages: [&[u8]; 3] = [b"", b"deno", &[7; 1024 / 8 - 2 * 48 - 2]];
+      for message in messages {
+        let c =
+          encrypt(&key.to_public_key(), hash_alg, &label, message).unwrap();
+        assert_eq!(decrypt(&key, hash_alg, &label, &c).unwrap(), message);
+      }
+    }
+  }
+
+  #[test]
+  fn interoperates_with_rsa_crate() {
+    let key = new_key();
+    let padding = || rsa::Oaep {
+      digest: Box::<Sha256>::default(),
+      mgf_digest: Box::<Sha256>::default(),
+      label: Some("deno".to_string()),
+    };
+    let c = key
+      .to_public_key()
+      .encrypt(&mut OsRng, padding(), b"message")
+      .unwrap();
+    assert_eq!(
+      decrypt(&key, ShaHash::Sha256, b"deno", &c).unwrap(),
+      b"message"
+    );
+
+    let c = encrypt(&key.to_public_key(), ShaHash::Sha256, b"deno", b"message")
+      .unwrap();
+    assert_eq!(key.decrypt(padding(), &c).unwrap(), b"message");
+  }
+
+  #[test]
+  fn tampering_fails_with_the_same_error() {
+    let key = new_key();
+    let c =
+      encrypt(&key.to_public_key(), ShaHash::Sha256, b"label", b"message")
+        .unwrap();
+
+    let mut flipped = c.clone();
+    flipped[10] ^= 1;
+    let truncated = &c[..c.len() - 1];
+    let mut extended = c.clone();
+    extended.push(0);
+
+    let other_key = new_key();
+    let cases: [(ShaHash, &[u8], &[u8], &RsaPrivateKey); 6] = [
+      (ShaHash::Sha256, b"label", &flipped, &key),
+      (ShaHash::Sha256, b"label", truncated, &key),
+      (ShaHash::Sha256, b"label", &extended, &key),
+      (ShaHash::Sha256, b"other", &c, &key),
+      (ShaHash::Sha1, b"label", &c, &key),
+      (ShaHash::Sha256, b"label", &c, &other_key),
+    ];
+    for (hash_alg, label, ciphertext, key) in cases {
+      assert_eq!(decrypt(key, hash_alg, label, ciphertext), Err(OaepError));
+    }
+  }
+}
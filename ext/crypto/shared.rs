@@ -3,9 +3,14 @@
 use std::borrow::Cow;
 
 use deno_core::JsBuffer;
+use deno_core::OpState;
 use deno_core::ToJsBuffer;
 use elliptic_curve::sec1::ToEncodedPoint;
 use p256::pkcs8::DecodePrivateKey;
+use rand::rngs::OsRng;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::RngCore;
 use rsa::pkcs1::DecodeRsaPrivateKey;
 use rsa::pkcs1::EncodeRsaPublicKey;
 use rsa::RsaPrivateKey;
@@ -177,3 +182,18 @@ impl V8RawKeyData {
     }
   }
 }
+
+/// A seed for the RNG of one key generation, drawn from the RNG seeded with
+/// `--seed` if there is one.
+pub fn key_seed(state: &mut OpState) -> Option<[u8; 32]> {
+  state.try_borrow_mut::<StdRng>().map(|rng| rng.gen())
+}
+
+/// Fills `buf` with key material, from the RNG seeded with `--seed` if there
+/// is one and from the system otherwise.
+pub fn fill_key_material(state: &mut OpState, buf: &mut [u8]) {
+  match state.try_borrow_mut::<StdRng>() {
+    Some(rng) => rng.fill_bytes(buf),
+    None => OsRng.fill_bytes(buf),
+  }
+}
@@ -2,15 +2,16 @@
 
 use curve25519_dalek::montgomery::MontgomeryPoint;
 use deno_core::op2;
+use deno_core::OpState;
 use deno_core::ToJsBuffer;
 use elliptic_curve::pkcs8::PrivateKeyInfo;
 use elliptic_curve::subtle::ConstantTimeEq;
-use rand::rngs::OsRng;
-use rand::RngCore;
 use spki::der::asn1::BitString;
 use spki::der::Decode;
 use spki::der::Encode;
 
+use crate::shared::fill_key_material;
+
 #[derive(Debug, thiserror::Error)]
 pub enum X25519Error {
   #[error("Failed to export key")]
@@ -21,6 +22,7 @@ pub enum X25519Error {
 
 #[op2(fast)]
 pub fn op_crypto_generate_x25519_keypair(
+  state: &mut OpState,
   #[buffer] pkey: &mut [u8],
   #[buffer] pubkey: &mut [u8],
 ) {
@@ -29,8 +31,7 @@ pub fn op_crypto_generate_x25519_keypair(
     9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0,
   ];
-  let mut rng = OsRng;
-  rng.fill_bytes(pkey);
+  fill_key_material(state, pkey);
   // https://www.rfc-editor.org/rfc/rfc7748#section-6.1
   // pubkey = x25519(a, 9) which is constant-time Montgomery ladder.
   //   https://eprint.iacr.org/2014/140.pdf page 4
@@ -1,17 +1,18 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 use deno_core::op2;
+use deno_core::OpState;
 use deno_core::ToJsBuffer;
 use ed448_goldilocks::curve::MontgomeryPoint;
 use ed448_goldilocks::Scalar;
 use elliptic_curve::pkcs8::PrivateKeyInfo;
 use elliptic_curve::subtle::ConstantTimeEq;
-use rand::rngs::OsRng;
-use rand::RngCore;
 use spki::der::asn1::BitString;
 use spki::der::Decode;
 use spki::der::Encode;
 
+use crate::shared::fill_key_material;
+
 #[derive(Debug, thiserror::Error)]
 pub enum X448Error {
   #[error("Failed to export key")]
@@ -22,11 +23,11 @@ pub enum X448Error {
 
 #[op2(fast)]
 pub fn op_crypto_generate_x448_keypair(
+  state: &mut OpState,
   #[buffer] pkey: &mut [u8],
   #[buffer] pubkey: &mut [u8],
 ) {
-  let mut rng = OsRng;
-  rng.fill_bytes(pkey);
+  fill_key_material(state, pkey);
 
   // x448(pkey, 5)
   let point = &MontgomeryPoint::generator()
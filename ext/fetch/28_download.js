@@ -0,0 +1,156 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+/// <reference path="../../core/internal.d.ts" />
+
+import { core } from "ext:core/mod.js";
+const { createCancelHandle } = core;
+import {
+  op_download,
+  op_download_progress_new,
+  op_download_progress_next,
+  op_fetch_body_to_file,
+  op_fetch_body_unread,
+  op_retry_policy_new,
+} from "ext:core/ops";
+import * as webidl from "ext:deno_webidl/00_webidl.js";
+import { pathFromURL } from "ext:deno_web/00_infra.js";
+import * as abortSignal from "ext:deno_web/03_abort_signal.js";
+import {
+  errorReadableStream,
+  getReadableStreamResourceBacking,
+  readableStreamClose,
+  readableStreamDisturb,
+  ReadableStream,
+  readableStreamThrowIfErrored,
+  resourceForReadableStream,
+} from "ext:deno_web/06_streams.js";
+import { headerListFromHeaders, Headers } from "ext:deno_fetch/20_headers.js";
+import {
+  ResponsePrototype,
+  toInnerResponse,
+} from "ext:deno_fetch/23_response.js";
+
+async function receiveProgress(rid, onProgress, state) {
+  try {
+    while (true) {
+      const progress = await op_download_progress_next(rid);
+      if (progress === null) {
+        return;
+      }
+      onProgress(progress);
+    }
+  } catch (error) {
+    state.error = error;
+  }
+}
+
+async function download(url, path, options = { __proto__: null }) {
+  url = `${url}`;
+  path = pathFromURL(path);
+  const { signal, onProgress } = options;
+  signal?.throwIfAborted();
+  const headers = options.headers
+    ? headerListFromHeaders(new Headers(options.headers))
+    : [];
+
+  let retryPolicyRid;
+  let cancelRid;
+  let abortHandler;
+  let progressRid;
+  let progressDone;
+  const progressState = { error: undefined };
+  if (options.retry) {
+    retryPolicyRid = op_retry_policy_new(options.retry);
+  }
+  if (signal) {
+    cancelRid = createCancelHandle();
+    abortHandler = () => core.tryClose(cancelRid);
+    signal[abortSignal.add](abortHandler);
+  }
+  if (onProgress) {
+    progressRid = op_download_progress_new();
+    progressDone = receiveProgress(progressRid, onProgress, progressState);
+  }
+  let result;
+  try {
+    result = await op_download(url, path, {
+      resume: options.resume ?? false,
+      sha256: options.sha256,
+      progressRid,
+      retryPolicyRid,
+      cancelRid,
+      headers,
+      maxRedirects: options.maxRedirects,
+    });
+  } finally {
+    if (progressDone) {
+      await progressDone;
+      core.tryClose(progressRid);
+    }
+    if (retryPolicyRid !== undefined) {
+      core.tryClose(retryPolicyRid);
+    }
+    if (signal) {
+      signal[abortSignal.remove](abortHandler);
+      core.tryClose(cancelRid);
+
+      // always throw the abort error when aborted
+      signal.throwIfAborted();
+    }
+  }
+  // errors thrown by `onProgress` are reported once the download is done
+  if (progressState.error !== undefined) {
+    throw progressState.error;
+  }
+  return result;
+}
+
+async function saveResponse(response, path, options = { __proto__: null }) {
+  webidl.assertBranded(response, ResponsePrototype);
+  path = pathFromURL(path);
+  const opOptions = { append: options.append ?? false };
+  const body = toInnerResponse(response).body;
+  if (body === null) {
+    const empty = new ReadableStream({ start: (c) => c.close() });
+    return await op_fetch_body_to_file(
+      resourceForReadableStream(empty),
+      path,
+      opOptions,
+    );
+  }
+  if (body.unusable()) {
+    throw new TypeError("Body already consumed");
+  }
+
+  const stream = body.stream;
+  const resourceBacking = getReadableStreamResourceBacking(stream);
+  if (!resourceBacking || !op_fetch_body_unread(resourceBacking.rid)) {
+    // the body isn't a fetch response body, or JS already read part of it
+    return await op_fetch_body_to_file(
+      resourceForReadableStream(stream),
+      path,
+      opOptions,
+    );
+  }
+
+  // fast path, the body is written to the file without going through JS
+  stream.getReader();
+  readableStreamDisturb(stream);
+  let written;
+  try {
+    written = await op_fetch_body_to_file(
+      resourceBacking.rid,
+      path,
+      opOptions,
+    );
+  } catch (err) {
+    // an aborted request errors the stream with the abort reason
+    readableStreamThrowIfErrored(stream);
+    errorReadableStream(stream, err);
+    throw err;
+  }
+  readableStreamClose(stream);
+  return written;
+}
+
+export { download, saveResponse };
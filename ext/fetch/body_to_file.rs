@@ -0,0 +1,220 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Writing a response body to a file, for `Deno.saveResponse`.
+//!
+//! When the body of a `fetch` response hasn't been read yet, it is drained
+//! straight from the hyper body into the file, so its chunks never reach JS.
+//! Bodies of other responses are passed in as a resource created from their
+//! stream, and are read through that resource instead.
+//!
+//! Either way the body is already decoded: the client removes the
+//! `Content-Encoding` before the response reaches the resource, so the file
+//! receives the same bytes as `response.body` would have produced.
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+use deno_core::futures::StreamExt;
+use deno_core::op2;
+use deno_core::CancelFuture;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use http_body_util::BodyExt;
+use serde::Deserialize;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+
+use crate::FetchPermissions;
+use crate::FetchResponseReader;
+use crate::FetchResponseResource;
+
+/// Size of the reads from bodies that aren't backed by a `fetch` response.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BodyToFileError {
+  #[error(transparent)]
+  Resource(deno_core::error::AnyError),
+  #[error(transparent)]
+  Permission(deno_core::error::AnyError),
+  #[error(
+    "Reading the response body failed after {written} bytes were written to the file: {error}"
+  )]
+  Interrupted {
+    written: u64,
+    error: deno_core::error::AnyError,
+  },
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+  #[error(
+    "Response body was canceled after {0} bytes were written to the file"
+  )]
+  Canceled(u64),
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BodyToFileOptions {
+  #[serde(default)]
+  append: bool,
+}
+
+/// Returns whether `rid` is the body of a `fetch` response that nothing has
+/// read from yet, and can thus be passed to `op_fetch_body_to_file` without
+/// its chunks going through JS.
+#[op2(fast)]
+pub fn op_fetch_body_unread(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> bool {
+  let Ok(resource) = state.resource_table.get::<FetchResponseResource>(rid)
+  else {
+    return false;
+  };
+  let reader = RcRef::map(&resource, |r| &r.response_reader).try_borrow_mut();
+  matches!(reader.as_deref(), Some(FetchResponseReader::Start(_)))
+}
+
+/// Writes the body `rid` to the file at `path` and returns the number of
+/// bytes written. The resource is closed, also when writing fails.
+///
+/// The resource stays in the table while it is drained, so that closing it,
+/// as aborting the request does, cancels the transfer.
+#[op2(async)]
+#[number]
+pub async fn op_fetch_body_to_file<FP>(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[string] path: String,
+  #[serde] options: BodyToFileOptions,
+) -> Result<u64, BodyToFileError>
+where
+  FP: FetchPermissions + 'static,
+{
+  let result = body_to_file::<FP>(&state, rid, &path, options).await;
+  let _ = state.borrow_mut().resource_table.close(rid);
+  result
+}
+
+async fn body_to_file<FP: FetchPermissions + 'static>(
+  state: &RefCell<OpState>,
+  rid: ResourceId,
+  path: &str,
+  options: BodyToFileOptions,
+) -> Result<u64, BodyToFileError> {
+  let (path, body) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(deno_net::UNSTABLE_FEATURE_NAME, "Deno.saveResponse");
+    let path = state
+      .borrow_mut::<FP>()
+      .check_write(Path::new(path), "Deno.saveResponse()")
+      .map_err(BodyToFileError::Permission)?
+      .into_owned();
+    let body = match state.resource_table.get::<FetchResponseResource>(rid) {
+      Ok(response) => Body::Fetch(response),
+      Err(_) => Body::Other(
+        state
+          .resource_table
+          .get_any(rid)
+          .map_err(BodyToFileError::Resource)?,
+      ),
+    };
+    (path, body)
+  };
+
+  let mut file = tokio::fs::OpenOptions::new()
+    .write(true)
+    .create(true)
+    .append(options.append)
+    .truncate(!options.append)
+    .open(&path)
+    .await?;
+  let mut written = 0;
+  let result = match body {
+    Body::Fetch(response) => {
+      let cancel = RcRef::map(&response, |r| &r.cancel);
+      let fut = write_fetch_body(&response, &mut file, &mut written);
+      match fut.or_cancel(cancel).await {
+        Ok(result) => result,
+        Err(_) => Err(BodyToFileError::Canceled(written)),
+      }
+    }
+    Body::Other(resource) => {
+      write_resource(resource, &mut file, &mut written).await
+    }
+  };
+  // keep what was received also if the body failed midway
+  file.flush().await?;
+  result.map(|_| written)
+}
+
+enum Body {
+  Fetch(Rc<FetchResponseResource>),
+  Other(Rc<dyn Resource>),
+}
+
+async fn write_fetch_body(
+  response: &Rc<FetchResponseResource>,
+  file: &mut (impl AsyncWrite + Unpin),
+  written: &mut u64,
+) -> Result<(), BodyToFileError> {
+  let mut reader = RcRef::map(response, |r| &r.response_reader)
+    .borrow_mut()
+    .await;
+  match std::mem::take(&mut *reader) {
+    FetchResponseReader::Start(response) => {
+      let mut body = response.into_body();
+      while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|error| BodyToFileError::Interrupted {
+          written: *written,
+          error,
+        })?;
+        let Ok(chunk) = frame.into_data() else {
+          continue; // trailers
+        };
+        file.write_all(&chunk).await?;
+        *written += chunk.len() as u64;
+      }
+    }
+    // part of the body was already read through the resource
+    FetchResponseReader::BodyReader(mut stream) => {
+      while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|err| BodyToFileError::Interrupted {
+          written: *written,
+          error: err.into(),
+        })?;
+        file.write_all(&chunk).await?;
+        *written += chunk.len() as u64;
+      }
+    }
+  }
+  Ok(())
+}
+
+async fn write_resource(
+  resource: Rc<dyn Resource>,
+  file: &mut (impl AsyncWrite + Unpin),
+  written: &mut u64,
+) -> Result<(), BodyToFileError> {
+  loop {
+    let chunk =
+      resource
+        .clone()
+        .read(READ_CHUNK_SIZE)
+        .await
+        .map_err(|error| BodyToFileError::Interrupted {
+          written: *written,
+          error,
+        })?;
+    if chunk.is_empty() {
+      return Ok(());
+    }
+    file.write_all(&chunk).await?;
+    *written += chunk.len() as u64;
+  }
+}
@@ -0,0 +1,53 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use deno_net::resolver::DnsResolver;
+use hyper_util::client::legacy::connect::dns::Name;
+use tower_service::Service;
+
+/// Resolves the hosts that the HTTP connector connects to with the runtime's
+/// [`DnsResolver`], so that `fetch()` uses the same name servers and cache
+/// as `Deno.connect()`.
+#[derive(Clone, Debug)]
+pub struct Resolver(DnsResolver);
+
+impl Resolver {
+  pub fn new(resolver: DnsResolver) -> Self {
+    Self(resolver)
+  }
+}
+
+type ResolveFuture = Pin<
+  Box<
+    dyn Future<Output = Result<std::vec::IntoIter<SocketAddr>, io::Error>>
+      + Send,
+  >,
+>;
+
+impl Service<Name> for Resolver {
+  type Response = std::vec::IntoIter<SocketAddr>;
+  type Error = io::Error;
+  type Future = ResolveFuture;
+
+  fn poll_ready(
+    &mut self,
+    _cx: &mut Context<'_>,
+  ) -> Poll<Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, name: Name) -> Self::Future {
+    let resolver = self.0.clone();
+    Box::pin(async move {
+      // The connector sets the port of the request on the addresses.
+      let addrs = resolver.lookup(name.as_str(), 0).await?;
+      Ok(addrs.into_iter())
+    })
+  }
+}
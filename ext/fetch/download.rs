@@ -0,0 +1,833 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Downloads of a URL straight to a file, for `Deno.download`.
+//!
+//! The response body is written to the destination as it arrives, without
+//! passing through JS. Progress is reported through a
+//! [`DownloadProgressQueue`] resource that JS polls while the download runs.
+//!
+//! Every attempt after the first continues from the bytes already written,
+//! using a `Range` request. Servers that don't support ranges respond with
+//! the whole resource, in which case the file is truncated and written from
+//! the start. When the first response carried an `ETag` or `Last-Modified`
+//! header, later attempts send it as `If-Range`, so a resource that changed
+//! in between is also downloaded from the start.
+//!
+//! The partial file is kept when a download fails, so it can be resumed
+//! later with `resume: true`. It is only deleted when the checksum doesn't
+//! match.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::rc::Rc;
+use std::task::Poll;
+use std::task::Waker;
+
+use deno_core::op2;
+use deno_core::url::Url;
+use deno_core::ByteString;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_net::retry::RetryOn;
+use deno_net::retry::RetryPolicy;
+use http::header::HeaderName;
+use http::header::HeaderValue;
+use http::header::ACCEPT_ENCODING;
+use http::header::AUTHORIZATION;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_RANGE;
+use http::header::COOKIE;
+use http::header::ETAG;
+use http::header::HOST;
+use http::header::IF_RANGE;
+use http::header::LAST_MODIFIED;
+use http::header::LOCATION;
+use http::header::RANGE;
+use http::HeaderMap;
+use http::StatusCode;
+use http::Uri;
+use http_body_util::BodyExt;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::extract_authority;
+use crate::get_or_create_client_from_state;
+use crate::proxy;
+use crate::Client;
+use crate::ClientSendError;
+use crate::FetchPermissions;
+use crate::HttpClientCreateError;
+use crate::Options;
+use crate::ResBody;
+
+/// Same limit as `fetch`.
+const DEFAULT_MAX_REDIRECTS: u32 = 20;
+
+/// Progress events kept until JS receives them. When JS falls behind, the
+/// oldest event is dropped. Events carry cumulative counts, so only
+/// intermediate updates are lost.
+const PROGRESS_QUEUE_CAPACITY: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DownloadError {
+  #[error(transparent)]
+  Resource(deno_core::error::AnyError),
+  #[error(transparent)]
+  Permission(deno_core::error::AnyError),
+  #[error(transparent)]
+  Url(#[from] deno_core::url::ParseError),
+  #[error("Invalid URL {0}")]
+  InvalidUrl(Url),
+  #[error("Url scheme '{0}' not supported")]
+  SchemeNotSupported(String),
+  #[error("Invalid sha256 checksum '{0}': expected 64 hexadecimal digits")]
+  InvalidChecksum(String),
+  #[error("The '{0}' header is set by the download and can't be specified")]
+  ReservedHeader(HeaderName),
+  #[error(transparent)]
+  InvalidHeaderName(#[from] http::header::InvalidHeaderName),
+  #[error(transparent)]
+  InvalidHeaderValue(#[from] http::header::InvalidHeaderValue),
+  #[error(transparent)]
+  ClientCreate(#[from] HttpClientCreateError),
+  #[error(transparent)]
+  ClientSend(#[from] ClientSendError),
+  #[error(transparent)]
+  RequestBuilderHook(deno_core::error::AnyError),
+  #[error("Server responded with {0}")]
+  Status(StatusCode),
+  #[error("Too many redirects, the limit is {0}")]
+  TooManyRedirects(u32),
+  #[error("Redirect response {0} without a valid Location header")]
+  InvalidRedirect(StatusCode),
+  #[error("Invalid partial response: {0}")]
+  InvalidContentRange(String),
+  #[error(
+    "Content-Length of {content_length} bytes disagrees with Content-Range, which announces {range_length} bytes"
+  )]
+  ContentLengthMismatch {
+    content_length: u64,
+    range_length: u64,
+  },
+  #[error("Server sent more than the announced {0} bytes")]
+  ExceededLength(u64),
+  #[error(
+    "The resource changed during the download: its size went from {previous} to {current} bytes"
+  )]
+  SizeChanged { previous: u64, current: u64 },
+  #[error("Connection closed before the download completed, after {0} bytes")]
+  Interrupted(u64),
+  #[error("Checksum mismatch: expected sha256 {expected}, got {actual}")]
+  ChecksumMismatch { expected: String, actual: String },
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+  #[error(transparent)]
+  Canceled(#[from] deno_core::Canceled),
+  #[error(
+    "{source} (after {attempts} attempts, {total_delay_ms}ms of backoff)"
+  )]
+  RetryFailed {
+    source: Box<DownloadError>,
+    attempts: u32,
+    total_delay_ms: u64,
+  },
+}
+
+impl DownloadError {
+  /// The kind of failure, for deciding whether a retry policy applies.
+  fn retry_kind(&self) -> Option<RetryOn> {
+    match self {
+      DownloadError::Interrupted(_) => Some(RetryOn::Interrupted),
+      DownloadError::ClientSend(err) if !err.is_connect_error() => {
+        // the connection was established, but closed before a response
+        Some(RetryOn::Interrupted)
+      }
+      DownloadError::ClientSend(err) => {
+        let mut source: Option<&(dyn std::error::Error + 'static)> =
+          Some(&err.source);
+        while let Some(err) = source {
+          if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            match io_err.kind() {
+              std::io::ErrorKind::ConnectionRefused => {
+                return Some(RetryOn::Refused)
+              }
+              std::io::ErrorKind::TimedOut => return Some(RetryOn::TimedOut),
+              _ => {}
+            }
+          }
+          // hyper-util doesn't expose resolution failures as a type
+          if err.to_string().starts_with("dns error") {
+            return Some(RetryOn::Dns);
+          }
+          source = err.source();
+        }
+        None
+      }
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+  /// Bytes of the destination file written so far, including resumed bytes.
+  pub received: u64,
+  /// Size of the whole resource, if the server announced it.
+  pub total: Option<u64>,
+}
+
+#[derive(Default)]
+struct ProgressQueueInner {
+  events: VecDeque<DownloadProgress>,
+  finished: bool,
+  waker: Option<Waker>,
+}
+
+/// Progress events of one download, which JS receives with
+/// `op_download_progress_next`.
+#[derive(Default)]
+pub struct DownloadProgressQueue(RefCell<ProgressQueueInner>);
+
+impl DownloadProgressQueue {
+  fn push(&self, progress: DownloadProgress) {
+    let mut inner = self.0.borrow_mut();
+    if inner.events.len() >= PROGRESS_QUEUE_CAPACITY {
+      inner.events.pop_front();
+    }
+    inner.events.push_back(progress);
+    if let Some(waker) = inner.waker.take() {
+      waker.wake();
+    }
+  }
+
+  fn finish(&self) {
+    let mut inner = self.0.borrow_mut();
+    inner.finished = true;
+    if let Some(waker) = inner.waker.take() {
+      waker.wake();
+    }
+  }
+
+  /// The next event, or `None` once the download finished and every event
+  /// was received.
+  async fn next(&self) -> Option<DownloadProgress> {
+    poll_fn(|cx| {
+      let mut inner = self.0.borrow_mut();
+      if let Some(progress) = inner.events.pop_front() {
+        return Poll::Ready(Some(progress));
+      }
+      if inner.finished {
+        return Poll::Ready(None);
+      }
+      inner.waker = Some(cx.waker().clone());
+      Poll::Pending
+    })
+    .await
+  }
+}
+
+impl Resource for DownloadProgressQueue {
+  fn name(&self) -> Cow<str> {
+    "downloadProgress".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.finish();
+  }
+}
+
+#[op2(fast)]
+#[smi]
+pub fn op_download_progress_new(state: &mut OpState) -> ResourceId {
+  state.resource_table.add(DownloadProgressQueue::default())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_download_progress_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<DownloadProgress>, DownloadError> {
+  let queue = state
+    .borrow()
+    .resource_table
+    .get::<DownloadProgressQueue>(rid)
+    .map_err(DownloadError::Resource)?;
+  Ok(queue.next().await)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadOptions {
+  /// Continue an existing file at the destination instead of replacing it.
+  #[serde(default)]
+  resume: bool,
+  /// Expected digest of the whole file, in hex.
+  sha256: Option<String>,
+  progress_rid: Option<ResourceId>,
+  retry_policy_rid: Option<ResourceId>,
+  cancel_rid: Option<ResourceId>,
+  #[serde(default)]
+  headers: Vec<(ByteString, ByteString)>,
+  max_redirects: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadResult {
+  /// The URL the file was downloaded from, after redirects.
+  url: String,
+  size: u64,
+  /// Bytes of an existing file at the destination that were kept, because
+  /// the server honored the range request of `resume: true`.
+  resumed_from: u64,
+  sha256: String,
+  attempts: u32,
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_download<FP>(
+  state: Rc<RefCell<OpState>>,
+  #[string] url: String,
+  #[string] path: String,
+  #[serde] options: DownloadOptions,
+) -> Result<DownloadResult, DownloadError>
+where
+  FP: FetchPermissions + 'static,
+{
+  let progress = options
+    .progress_rid
+    .map(|rid| {
+      state
+        .borrow()
+        .resource_table
+        .get::<DownloadProgressQueue>(rid)
+    })
+    .transpose()
+    .map_err(DownloadError::Resource)?;
+  let result =
+    download::<FP>(&state, &url, &path, options, progress.clone()).await;
+  // also when the download failed early, so that JS stops waiting for events
+  if let Some(progress) = progress {
+    progress.finish();
+  }
+  result
+}
+
+async fn download<FP: FetchPermissions + 'static>(
+  state: &RefCell<OpState>,
+  url: &str,
+  path: &str,
+  options: DownloadOptions,
+  progress: Option<Rc<DownloadProgressQueue>>,
+) -> Result<DownloadResult, DownloadError> {
+  let url = Url::parse(url)?;
+  let expected_sha256 =
+    options.sha256.as_deref().map(parse_sha256).transpose()?;
+  let headers = request_headers(options.headers)?;
+  let max_redirects = options.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS);
+
+  let (client, path, policy, cancel, clock) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(deno_net::UNSTABLE_FEATURE_NAME, "Deno.download");
+    check_url::<FP>(&mut state, &url)?;
+    let path = state
+      .borrow_mut::<FP>()
+      .check_write(Path::new(path), "Deno.download()")
+      .map_err(DownloadError::Permission)?
+      .into_owned();
+    let policy = options
+      .retry_policy_rid
+      .map(|rid| state.resource_table.get::<RetryPolicy>(rid))
+      .transpose()
+      .map_err(DownloadError::Resource)?;
+    let cancel = options
+      .cancel_rid
+      .map(|rid| state.resource_table.get::<CancelHandle>(rid))
+      .transpose()
+      .map_err(DownloadError::Resource)?;
+    let client = get_or_create_client_from_state(&mut state)?;
+    (client, path, policy, cancel, deno_web::clock(&state))
+  };
+
+  let mut transfer = Transfer::open(&path, options.resume, progress).await?;
+  let mut attempts = 0;
+  let mut total_delay_ms = 0;
+  let attempts_with_retry = async {
+    loop {
+      attempts += 1;
+      let error = match transfer
+        .attempt::<FP>(state, &client, &url, &headers, max_redirects)
+        .await
+      {
+        Ok(url) => return Ok(url),
+        Err(error) => error,
+      };
+      let Some(policy) = policy.as_ref() else {
+        return Err(error);
+      };
+      if attempts >= policy.max_attempts()
+        || !error
+          .retry_kind()
+          .is_some_and(|kind| policy.retries_on(kind))
+      {
+        return Err(DownloadError::RetryFailed {
+          source: Box::new(error),
+          attempts,
+          total_delay_ms,
+        });
+      }
+      let delay = policy.delay(attempts);
+      clock.sleep(delay).await;
+      total_delay_ms += delay.as_millis() as u64;
+    }
+  };
+  let final_url = match cancel {
+    Some(cancel) => attempts_with_retry
+      .or_cancel(cancel)
+      .await
+      .unwrap_or_else(|canceled| Err(canceled.into())),
+    None => attempts_with_retry.await,
+  };
+  // write out what was received also if the download failed, so that it can
+  // be resumed
+  transfer.file.flush().await?;
+  let final_url = final_url?;
+
+  let actual = faster_hex::hex_string(&transfer.hasher.finalize());
+  if let Some(expected) = expected_sha256 {
+    if actual != expected {
+      drop(transfer.file);
+      tokio::fs::remove_file(&path).await?;
+      return Err(DownloadError::ChecksumMismatch { expected, actual });
+    }
+  }
+  Ok(DownloadResult {
+    url: final_url.into(),
+    size: transfer.received,
+    resumed_from: transfer.resumed_from,
+    sha256: actual,
+    attempts,
+  })
+}
+
+fn parse_sha256(value: &str) -> Result<String, DownloadError> {
+  if value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit()) {
+    Ok(value.to_ascii_lowercase())
+  } else {
+    Err(DownloadError::InvalidChecksum(value.to_string()))
+  }
+}
+
+fn request_headers(
+  headers: Vec<(ByteString, ByteString)>,
+) -> Result<HeaderMap, DownloadError> {
+  let mut map = HeaderMap::new();
+  for (key, value) in headers {
+    let name = HeaderName::from_bytes(&key)?;
+    // set by the download itself
+    if [ACCEPT_ENCODING, CONTENT_LENGTH, HOST, IF_RANGE, RANGE].contains(&name)
+    {
+      return Err(DownloadError::ReservedHeader(name));
+    }
+    map.append(name, HeaderValue::from_bytes(&value)?);
+  }
+  // compressed responses would be decompressed by the client, so byte
+  // offsets in the file wouldn't match the ranges of the resource
+  map.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+  Ok(map)
+}
+
+fn check_url<FP: FetchPermissions + 'static>(
+  state: &mut OpState,
+  url: &Url,
+) -> Result<(), DownloadError> {
+  if !matches!(url.scheme(), "http" | "https") {
+    return Err(DownloadError::SchemeNotSupported(url.scheme().to_string()));
+  }
+  state
+    .borrow_mut::<FP>()
+    .check_net_url(url, "Deno.download()")
+    .map_err(DownloadError::Permission)
+}
+
+/// Sends a GET request and follows up to `max_redirects` redirects, checking
+/// net permission for every redirect target. Credentials are not forwarded
+/// to other origins.
+async fn send_following_redirects<FP: FetchPermissions + 'static>(
+  state: &RefCell<OpState>,
+  client: &Client,
+  url: &Url,
+  mut headers: HeaderMap,
+  max_redirects: u32,
+) -> Result<(http::Response<ResBody>, Url), DownloadError> {
+  let mut url = url.clone();
+  let mut redirects = 0;
+  loop {
+    let mut request_url = url.clone();
+    let authority = extract_authority(&mut request_url);
+    let uri = request_url
+      .as_str()
+      .parse::<Uri>()
+      .map_err(|_| DownloadError::InvalidUrl(url.clone()))?;
+    let mut request = http::Request::new(
+      http_body_util::Empty::new()
+        .map_err(|never| match never {})
+        .boxed(),
+    );
+    *request.uri_mut() = uri;
+    *request.headers_mut() = headers.clone();
+    if let Some((username, password)) = authority {
+      request.headers_mut().insert(
+        AUTHORIZATION,
+        proxy::basic_auth(&username, password.as_deref()),
+      );
+    }
+    let request_builder_hook =
+      state.borrow().borrow::<Options>().request_builder_hook;
+    if let Some(request_builder_hook) = request_builder_hook {
+      request_builder_hook(&mut request)
+        .map_err(DownloadError::RequestBuilderHook)?;
+    }
+
+    let response = client.clone().send(request).await?;
+    let status = response.status();
+    if !matches!(status.as_u16(), 301 | 302 | 303 | 307 | 308) {
+      return Ok((response, url));
+    }
+    if redirects >= max_redirects {
+      return Err(DownloadError::TooManyRedirects(max_redirects));
+    }
+    let location = response
+      .headers()
+      .get(LOCATION)
+      .and_then(|location| location.to_str().ok())
+      .and_then(|location| url.join(location).ok())
+      .ok_or(DownloadError::InvalidRedirect(status))?;
+    check_url::<FP>(&mut state.borrow_mut(), &location)?;
+    if location.origin() != url.origin() {
+      headers.remove(AUTHORIZATION);
+      headers.remove(COOKIE);
+    }
+    url = location;
+    redirects += 1;
+  }
+}
+
+/// Parses `Content-Range: bytes <start>-<end>/<total>`, where the total may
+/// be `*`.
+fn parse_content_range(
+  headers: &HeaderMap,
+) -> Result<(u64, u64, Option<u64>), DownloadError> {
+  let invalid = || {
+    DownloadError::InvalidContentRange(format!(
+      "malformed Content-Range header {:?}",
+      headers.get(CONTENT_RANGE)
+    ))
+  };
+  let value = headers
+    .get(CONTENT_RANGE)
+    .and_then(|value| value.to_str().ok())
+    .ok_or_else(invalid)?;
+  let (range, total) = value
+    .strip_prefix("bytes ")
+    .and_then(|value| value.split_once('/'))
+    .ok_or_else(invalid)?;
+  let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+  let start = start.trim().parse::<u64>().map_err(|_| invalid())?;
+  let end = end.trim().parse::<u64>().map_err(|_| invalid())?;
+  let total = match total.trim() {
+    "*" => None,
+    total => Some(total.parse::<u64>().map_err(|_| invalid())?),
+  };
+  if end < start || total.is_some_and(|total| end >= total) {
+    return Err(invalid());
+  }
+  Ok((start, end, total))
+}
+
+/// Parses the `Content-Range: bytes */<total>` of a 416 response.
+fn parse_unsatisfied_range(headers: &HeaderMap) -> Option<u64> {
+  headers
+    .get(CONTENT_RANGE)?
+    .to_str()
+    .ok()?
+    .strip_prefix("bytes */")?
+    .trim()
+    .parse()
+    .ok()
+}
+
+/// State of a download that is kept across attempts.
+struct Transfer {
+  file: tokio::fs::File,
+  /// Digest of the bytes written to the file so far.
+  hasher: Sha256,
+  /// Bytes written to the file so far.
+  received: u64,
+  /// Size of the whole resource, once a response announced it.
+  total: Option<u64>,
+  /// `ETag` or `Last-Modified` of the first response, sent as `If-Range`.
+  validator: Option<HeaderValue>,
+  resumed_from: u64,
+  progress: Option<Rc<DownloadProgressQueue>>,
+}
+
+impl Transfer {
+  async fn open(
+    path: &Path,
+    resume: bool,
+    progress: Option<Rc<DownloadProgressQueue>>,
+  ) -> Result<Self, DownloadError> {
+    let mut file = tokio::fs::OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(!resume)
+      .open(path)
+      .await?;
+    // hash what is already there, which also moves to the end of the file
+    let mut hasher = Sha256::new();
+    let mut received = 0;
+    if resume {
+      let mut buf = vec![0; 64 * 1024];
+      loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+          break;
+        }
+        hasher.update(&buf[..n]);
+        received += n as u64;
+      }
+    }
+    Ok(Self {
+      file,
+      hasher,
+      received,
+      total: None,
+      validator: None,
+      resumed_from: received,
+      progress,
+    })
+  }
+
+  fn report_progress(&self) {
+    if let Some(progress) = &self.progress {
+      progress.push(DownloadProgress {
+        received: self.received,
+        total: self.total,
+      });
+    }
+  }
+
+  async fn restart(&mut self) -> Result<(), DownloadError> {
+    self.file.set_len(0).await?;
+    self.file.seek(SeekFrom::Start(0)).await?;
+    self.hasher = Sha256::new();
+    self.received = 0;
+    self.total = None;
+    self.resumed_from = 0;
+    Ok(())
+  }
+
+  /// Requests the part of the resource that wasn't received yet and writes
+  /// it to the file. Returns the URL after redirects.
+  async fn attempt<FP: FetchPermissions + 'static>(
+    &mut self,
+    state: &RefCell<OpState>,
+    client: &Client,
+    url: &Url,
+    headers: &HeaderMap,
+    max_redirects: u32,
+  ) -> Result<Url, DownloadError> {
+    let mut headers = headers.clone();
+    if self.received > 0 {
+      headers.insert(
+        RANGE,
+        HeaderValue::from_str(&format!("bytes={}-", self.received)).unwrap(),
+      );
+      if let Some(validator) = &self.validator {
+        headers.insert(IF_RANGE, validator.clone());
+      }
+    }
+    let (response, url) = send_following_redirects::<FP>(
+      state,
+      client,
+      url,
+      headers,
+      max_redirects,
+    )
+    .await?;
+
+    let status = response.status();
+    let body_length = hyper::body::Body::size_hint(response.body()).exact();
+    let total = if status == StatusCode::PARTIAL_CONTENT {
+      let (start, end, total) = parse_content_range(response.headers())?;
+      if start != self.received {
+        return Err(DownloadError::InvalidContentRange(format!(
+          "requested bytes from {}, but received bytes from {start}",
+          self.received
+        )));
+      }
+      if total.is_some_and(|total| end + 1 != total) {
+        return Err(DownloadError::InvalidContentRange(format!(
+          "the range ends at byte {end}, before the end of the resource"
+        )));
+      }
+      if let Some(content_length) =
+        body_length.filter(|length| *length != end - start + 1)
+      {
+        return Err(DownloadError::ContentLengthMismatch {
+          content_length,
+          range_length: end - start + 1,
+        });
+      }
+      if let (Some(previous), Some(current)) = (self.total, total) {
+        if previous != current {
+          return Err(DownloadError::SizeChanged { previous, current });
+        }
+      }
+      total
+    } else if status == StatusCode::RANGE_NOT_SATISFIABLE && self.received > 0 {
+      // the file at the destination is already complete
+      if parse_unsatisfied_range(response.headers()) == Some(self.received) {
+        self.total = Some(self.received);
+        self.report_progress();
+        return Ok(url);
+      }
+      return Err(DownloadError::Status(status));
+    } else if status.is_success() {
+      // the server ignored the range, or the resource changed
+      if self.received > 0 {
+        self.restart().await?;
+      }
+      body_length
+    } else {
+      return Err(DownloadError::Status(status));
+    };
+
+    self.total = total.or(self.total);
+    if self.validator.is_none() {
+      self.validator = response
+        .headers()
+        .get(ETAG)
+        .or_else(|| response.headers().get(LAST_MODIFIED))
+        .cloned();
+    }
+    self.report_progress();
+
+    let mut body = response.into_body();
+    while let Some(frame) = body.frame().await {
+      let Ok(frame) = frame else {
+        return Err(DownloadError::Interrupted(self.received));
+      };
+      let Ok(chunk) = frame.into_data() else {
+        continue; // trailers
+      };
+      if let Some(total) = self.total {
+        if self.received + chunk.len() as u64 > total {
+          return Err(DownloadError::ExceededLength(total));
+        }
+      }
+      self.file.write_all(&chunk).await?;
+      self.hasher.update(&chunk);
+      self.received += chunk.len() as u64;
+      self.report_progress();
+    }
+    if self.total.is_some_and(|total| self.received < total) {
+      return Err(DownloadError::Interrupted(self.received));
+    }
+    Ok(url)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn content_range(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_RANGE, HeaderValue::from_str(value).unwrap());
+    headers
+  }
+
+  #[test]
+  fn parses_content_range() {
+    assert_eq!(
+      parse_content_range(&content_range("bytes 10-19/20")).unwrap(),
+      (10, 19, Some(20))
+    );
+    assert_eq!(
+      parse_content_range(&content_range("bytes 0-9/*")).unwrap(),
+      (0, 9, None)
+    );
+    for invalid in [
+      "bytes 10-9/20",
+      "bytes 10-20/20",
+      "items 0-1/2",
+      "bytes */20",
+    ] {
+      assert!(parse_content_range(&content_range(invalid)).is_err());
+    }
+    assert_eq!(
+      parse_unsatisfied_range(&content_range("bytes */20")),
+      Some(20)
+    );
+  }
+
+  #[test]
+  fn validates_checksum_and_headers() {
+    let digest = "AB".repeat(32);
+    assert_eq!(parse_sha256(&digest).unwrap(), "ab".repeat(32));
+    assert!(parse_sha256("abc").is_err());
+    assert!(parse_sha256(&"zz".repeat(32)).is_err());
+
+    let headers =
+      request_headers(vec![("x-token".into(), "1".into())]).unwrap();
+    assert_eq!(headers.get(ACCEPT_ENCODING).unwrap(), "identity");
+    let err =
+      request_headers(vec![("Range".into(), "bytes=0-".into())]).unwrap_err();
+    assert!(matches!(err, DownloadError::ReservedHeader(_)));
+  }
+
+  #[test]
+  fn progress_queue_drops_oldest() {
+    let queue = DownloadProgressQueue::default();
+    for received in 0..(PROGRESS_QUEUE_CAPACITY as u64 + 10) {
+      queue.push(DownloadProgress {
+        received,
+        total: None,
+      });
+    }
+    queue.finish();
+    let received = deno_core::futures::executor::block_on(async {
+      let mut received = Vec::new();
+      while let Some(progress) = queue.next().await {
+        received.push(progress.received);
+      }
+      received
+    });
+    assert_eq!(received.len(), PROGRESS_QUEUE_CAPACITY);
+    assert_eq!(received[0], 10);
+    assert_eq!(
+      *received.last().unwrap(),
+      PROGRESS_QUEUE_CAPACITY as u64 + 9
+    );
+  }
+}
@@ -1,5 +1,8 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+mod body_to_file;
+mod dns;
+mod download;
 mod fs_fetch_handler;
 mod proxy;
 #[cfg(test)]
@@ -39,6 +42,7 @@ use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
+use deno_net::resolver::DnsResolver;
 use deno_tls::rustls::RootCertStore;
 use deno_tls::Proxy;
 use deno_tls::RootCertStoreProvider;
@@ -76,6 +80,8 @@ use tower_http::decompression::Decompression;
 pub use data_url;
 pub use proxy::basic_auth;
 
+pub use body_to_file::BodyToFileError;
+pub use download::DownloadError;
 pub use fs_fetch_handler::FsFetchHandler;
 
 #[derive(Clone)]
@@ -90,6 +96,7 @@ pub struct Options {
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
   pub client_cert_chain_and_key: TlsKeys,
   pub file_fetch_handler: Rc<dyn FetchHandler>,
+  pub dns_resolver: DnsResolver,
 }
 
 impl Options {
@@ -113,6 +120,7 @@ impl Default for Options {
       unsafely_ignore_certificate_errors: None,
       client_cert_chain_and_key: TlsKeys::Null,
       file_fetch_handler: Rc::new(DefaultFileFetchHandler),
+      dns_resolver: DnsResolver::default(),
     }
   }
 }
@@ -125,6 +133,11 @@ deno_core::extension!(deno_fetch,
     op_fetch_send,
     op_utf8_to_byte_string,
     op_fetch_custom_client<FP>,
+    download::op_download<FP>,
+    download::op_download_progress_new,
+    download::op_download_progress_next,
+    body_to_file::op_fetch_body_unread,
+    body_to_file::op_fetch_body_to_file<FP>,
   ],
   esm = [
     "20_headers.js",
@@ -134,7 +147,8 @@ deno_core::extension!(deno_fetch,
     "23_request.js",
     "23_response.js",
     "26_fetch.js",
-    "27_eventsource.js"
+    "27_eventsource.js",
+    "28_download.js"
   ],
   options = {
     options: Options,
@@ -266,6 +280,7 @@ pub fn create_client_from_options(
       pool_idle_timeout: None,
       http1: true,
       http2: true,
+      dns_resolver: options.dns_resolver.clone(),
     },
   )
 }
@@ -353,6 +368,12 @@ pub trait FetchPermissions {
     p: &'a Path,
     api_name: &str,
   ) -> Result<Cow<'a, Path>, deno_core::error::AnyError>;
+  #[must_use = "the resolved return value to mitigate time-of-check to time-of-use issues"]
+  fn check_write<'a>(
+    &mut self,
+    p: &'a Path,
+    api_name: &str,
+  ) -> Result<Cow<'a, Path>, deno_core::error::AnyError>;
 }
 
 impl FetchPermissions for deno_permissions::PermissionsContainer {
@@ -377,6 +398,17 @@ impl FetchPermissions for deno_permissions::PermissionsContainer {
       Some(api_name),
     )
   }
+
+  #[inline(always)]
+  fn check_write<'a>(
+    &mut self,
+    path: &'a Path,
+    api_name: &str,
+  ) -> Result<Cow<'a, Path>, deno_core::error::AnyError> {
+    deno_permissions::PermissionsContainer::check_write_path(
+      self, path, api_name,
+    )
+  }
 }
 
 #[op2]
@@ -441,10 +473,16 @@ where
       (request_rid, maybe_cancel_handle_rid)
     }
     "http" | "https" => {
+      let doh_url = state.borrow::<Options>().dns_resolver.doh_url().cloned();
       let permissions = state.borrow_mut::<FP>();
       permissions
         .check_net_url(&url, "fetch()")
         .map_err(FetchError::Resource)?;
+      if let Some(doh_url) = doh_url {
+        permissions
+          .check_net_url(&doh_url, "fetch()")
+          .map_err(FetchError::Resource)?;
+      }
 
       let maybe_authority = extract_authority(&mut url);
       let uri = url
@@ -900,6 +938,7 @@ where
       ),
       http1: args.http1,
       http2: args.http2,
+      dns_resolver: options.dns_resolver.clone(),
     },
   )?;
 
@@ -920,6 +959,8 @@ pub struct CreateHttpClientOptions {
   pub pool_idle_timeout: Option<Option<u64>>,
   pub http1: bool,
   pub http2: bool,
+  /// Resolver for the hosts of requests and proxies.
+  pub dns_resolver: DnsResolver,
 }
 
 impl Default for CreateHttpClientOptions {
@@ -934,6 +975,7 @@ impl Default for CreateHttpClientOptions {
       pool_idle_timeout: None,
       http1: true,
       http2: true,
+      dns_resolver: DnsResolver::default(),
     }
   }
 }
@@ -981,7 +1023,8 @@ pub fn create_http_client(
   tls_config.alpn_protocols = alpn_protocols;
   let tls_config = Arc::from(tls_config);
 
-  let mut http_connector = HttpConnector::new();
+  let mut http_connector =
+    HttpConnector::new_with_resolver(dns::Resolver::new(options.dns_resolver));
   http_connector.enforce_http(false);
 
   let user_agent = user_agent.parse::<HeaderValue>().map_err(|_| {
@@ -1056,7 +1099,7 @@ pub struct Client {
   user_agent: HeaderValue,
 }
 
-type Connector = proxy::ProxyConnector<HttpConnector>;
+type Connector = proxy::ProxyConnector<HttpConnector<dns::Resolver>>;
 
 // clippy is wrong here
 #[allow(clippy::declare_interior_mutable_const)]
@@ -12,6 +12,7 @@ import {
   op_fs_chmod_sync,
   op_fs_chown_async,
   op_fs_chown_sync,
+  op_fs_copy_dir,
   op_fs_copy_file_async,
   op_fs_copy_file_sync,
   op_fs_cwd,
@@ -27,8 +28,12 @@ import {
   op_fs_ftruncate_sync,
   op_fs_funlock_async,
   op_fs_funlock_sync,
+  op_fs_hash_tree,
+  op_fs_integrity_manifest_create,
+  op_fs_integrity_manifest_verify,
   op_fs_futime_async,
   op_fs_futime_sync,
+  op_fs_limits_usage,
   op_fs_link_async,
   op_fs_link_sync,
   op_fs_lstat_async,
@@ -39,8 +44,12 @@ import {
   op_fs_make_temp_file_sync,
   op_fs_mkdir_async,
   op_fs_mkdir_sync,
+  op_fs_mmap_readonly,
+  op_fs_munmap,
   op_fs_open_async,
   op_fs_open_sync,
+  op_fs_progress_new,
+  op_fs_progress_next,
   op_fs_read_dir_async,
   op_fs_read_dir_sync,
   op_fs_read_file_async,
@@ -53,6 +62,7 @@ import {
   op_fs_realpath_sync,
   op_fs_remove_async,
   op_fs_remove_sync,
+  op_fs_remove_tree,
   op_fs_rename_async,
   op_fs_rename_sync,
   op_fs_seek_async,
@@ -66,12 +76,39 @@ import {
   op_fs_umask,
   op_fs_utime_async,
   op_fs_utime_sync,
+  op_fs_walk,
+  op_fs_walk_next,
   op_fs_write_file_async,
+  op_fs_write_file_atomic_async,
+  op_fs_write_file_atomic_sync,
   op_fs_write_file_sync,
+  op_glob_compile,
+  op_glob_filter,
+  op_glob_match,
+  op_logfile_flush,
+  op_logfile_open,
+  op_logfile_write,
+  op_ndjson_encode_batch,
+  op_ndjson_read_batch,
+  op_ndjson_reader_open,
+  op_path_is_unc,
+  op_path_is_verbatim,
+  op_path_normalize_windows,
+  op_path_realpath,
+  op_path_relative,
   op_set_raw,
+  op_tempdir_create,
+  op_tempdir_persist,
+  op_tempfile_open,
+  op_tempfile_persist,
+  op_text_search,
+  op_text_search_next,
 } from "ext:core/ops";
 const {
+  ArrayIsArray,
   ArrayPrototypeFilter,
+  ArrayPrototypeMap,
+  ArrayPrototypePush,
   Date,
   DatePrototypeGetTime,
   Error,
@@ -81,6 +118,8 @@ const {
   ObjectDefineProperty,
   ObjectPrototypeIsPrototypeOf,
   ObjectValues,
+  queueMicrotask,
+  SafeFinalizationRegistry,
   StringPrototypeSlice,
   StringPrototypeStartsWith,
   SymbolAsyncIterator,
@@ -95,9 +134,17 @@ import * as abortSignal from "ext:deno_web/03_abort_signal.js";
 import {
   readableStreamForRid,
   ReadableStreamPrototype,
+  resourceForReadableStream,
   writableStreamForRid,
+  WritableStream,
 } from "ext:deno_web/06_streams.js";
-import { pathFromURL, SymbolDispose } from "ext:deno_web/00_infra.js";
+import { CompressionStream } from "ext:deno_web/14_compression.js";
+import {
+  OpBatch,
+  pathFromURL,
+  SymbolAsyncDispose,
+  SymbolDispose,
+} from "ext:deno_web/00_infra.js";
 
 function chmodSync(path, mode) {
   op_fs_chmod_sync(pathFromURL(path), mode);
@@ -253,12 +300,76 @@ async function remove(
   path,
   options = { __proto__: null },
 ) {
+  if (options.recursive && (options.signal || options.onProgress)) {
+    const result = await runTreeOperation(
+      (controls) => op_fs_remove_tree(pathFromURL(path), controls),
+      options,
+    );
+    if (result.canceled) {
+      options.signal.throwIfAborted();
+    }
+    return;
+  }
   await op_fs_remove_async(
     pathFromURL(path),
     !!options.recursive,
   );
 }
 
+async function receiveProgress(rid, onProgress, state) {
+  try {
+    while (true) {
+      const progress = await op_fs_progress_next(rid);
+      if (progress === null) {
+        return;
+      }
+      onProgress(progress);
+    }
+  } catch (error) {
+    state.error = error;
+  }
+}
+
+// Calls `op` with the progress queue and cancel handle for the `onProgress`
+// and `signal` options of a recursive remove or a directory copy.
+async function runTreeOperation(op, options) {
+  const { signal, onProgress } = options;
+  signal?.throwIfAborted();
+
+  let cancelRid;
+  let abortHandler;
+  let progressRid;
+  let progressDone;
+  const progressState = { error: undefined };
+  if (signal) {
+    cancelRid = createCancelHandle();
+    abortHandler = () => core.tryClose(cancelRid);
+    signal[abortSignal.add](abortHandler);
+  }
+  if (onProgress) {
+    progressRid = op_fs_progress_new();
+    progressDone = receiveProgress(progressRid, onProgress, progressState);
+  }
+  let result;
+  try {
+    result = await op({ progressRid, cancelRid });
+  } finally {
+    if (progressDone) {
+      await progressDone;
+      core.tryClose(progressRid);
+    }
+    if (signal) {
+      signal[abortSignal.remove](abortHandler);
+      core.tryClose(cancelRid);
+    }
+  }
+  // errors thrown by `onProgress` are reported once the operation is done
+  if (progressState.error !== undefined) {
+    throw progressState.error;
+  }
+  return result;
+}
+
 function renameSync(oldpath, newpath) {
   op_fs_rename_sync(
     pathFromURL(oldpath),
@@ -704,6 +815,512 @@ class FsFile {
   }
 }
 
+class LogFile {
+  #rid = 0;
+  // Queues the writes of a task after the first one, unless every write must
+  // be synced before it returns. The first one is made right away, so that it
+  // throws the error of a failed background flush.
+  #batch = null;
+  #inTask = false;
+  // The error of a queued write, thrown by the next write or flush.
+  #error = null;
+
+  constructor(rid, symbol, batched = false) {
+    this.#rid = rid;
+    if (!symbol || symbol !== SymbolFor("Deno.internal.LogFile")) {
+      throw new TypeError(
+        "`Deno.LogFile` cannot be constructed, use `Deno.openLogFile()` instead.",
+      );
+    }
+    if (batched) {
+      this.#batch = new OpBatch("op_logfile_write", (error) => {
+        this.#error ??= error;
+      });
+    }
+  }
+
+  #throwError() {
+    const error = this.#error;
+    if (error !== null) {
+      this.#error = null;
+      throw error;
+    }
+  }
+
+  write(...lines) {
+    this.#throwError();
+    const buffers = [];
+    for (let i = 0; i < lines.length; ++i) {
+      const line = lines[i];
+      ArrayPrototypePush(
+        buffers,
+        typeof line === "string" ? core.encode(line) : line,
+      );
+    }
+    if (this.#batch === null) {
+      op_logfile_write(this.#rid, buffers);
+    } else if (!this.#inTask && this.#batch.size === 0) {
+      op_logfile_write(this.#rid, buffers);
+      this.#inTask = true;
+      queueMicrotask(() => {
+        this.#inTask = false;
+      });
+    } else {
+      this.#batch.push([this.#rid, buffers]);
+    }
+  }
+
+  async flush() {
+    this.#batch?.flush();
+    this.#throwError();
+    await op_logfile_flush(this.#rid);
+  }
+
+  close() {
+    this.#batch?.flush();
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    this.#batch?.flush();
+    core.tryClose(this.#rid);
+  }
+}
+
+async function openLogFile(path, options) {
+  const rid = await op_logfile_open(pathFromURL(path), options);
+  return new LogFile(
+    rid,
+    SymbolFor("Deno.internal.LogFile"),
+    options?.sync !== "always",
+  );
+}
+
+class TempFile extends FsFile {
+  #tempRid = 0;
+  #path;
+
+  constructor(rid, tempRid, path, symbol) {
+    super(rid, SymbolFor("Deno.internal.FsFile"));
+    if (!symbol || symbol !== SymbolFor("Deno.internal.TempFile")) {
+      throw new TypeError(
+        "`Deno.TempFile` cannot be constructed, use `Deno.createTempFile()` instead.",
+      );
+    }
+    this.#tempRid = tempRid;
+    this.#path = path;
+  }
+
+  get path() {
+    return this.#path;
+  }
+
+  persist(path) {
+    path = pathFromURL(path);
+    op_tempfile_persist(this.#tempRid, path);
+    this.#path = path;
+  }
+
+  close() {
+    // The file is closed first, as Windows can't remove open files.
+    super.close();
+    core.close(this.#tempRid);
+  }
+
+  [SymbolDispose]() {
+    super[SymbolDispose]();
+    core.tryClose(this.#tempRid);
+  }
+}
+
+async function createTempFile(options = { __proto__: null }) {
+  const { rid, tempRid, path } = await op_tempfile_open({
+    dir: options.dir ? pathFromURL(options.dir) : undefined,
+    prefix: options.prefix,
+    suffix: options.suffix,
+    mode: options.mode,
+  });
+  return new TempFile(rid, tempRid, path, SymbolFor("Deno.internal.TempFile"));
+}
+
+class TempDir {
+  #rid = 0;
+  #path;
+
+  constructor(rid, path, symbol) {
+    if (!symbol || symbol !== SymbolFor("Deno.internal.TempDir")) {
+      throw new TypeError(
+        "`Deno.TempDir` cannot be constructed, use `Deno.createTempDir()` instead.",
+      );
+    }
+    this.#rid = rid;
+    this.#path = path;
+  }
+
+  get path() {
+    return this.#path;
+  }
+
+  persist(path) {
+    path = pathFromURL(path);
+    op_tempdir_persist(this.#rid, path);
+    this.#path = path;
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+async function createTempDir(options = { __proto__: null }) {
+  const { rid, path } = await op_tempdir_create({
+    dir: options.dir ? pathFromURL(options.dir) : undefined,
+    prefix: options.prefix,
+  });
+  return new TempDir(rid, path, SymbolFor("Deno.internal.TempDir"));
+}
+
+class NdjsonReader {
+  #rid = 0;
+  #streamRid = null;
+
+  constructor(rid, streamRid, symbol) {
+    if (!symbol || symbol !== SymbolFor("Deno.internal.NdjsonReader")) {
+      throw new TypeError(
+        "`Deno.NdjsonReader` cannot be constructed, use `Deno.openNdjsonReader()` instead.",
+      );
+    }
+    this.#rid = rid;
+    this.#streamRid = streamRid;
+  }
+
+  readBatch() {
+    return op_ndjson_read_batch(this.#rid);
+  }
+
+  async *[SymbolAsyncIterator]() {
+    try {
+      while (true) {
+        const batch = await this.readBatch();
+        if (batch === null) return;
+        yield batch;
+      }
+    } finally {
+      this[SymbolDispose]();
+    }
+  }
+
+  close() {
+    core.close(this.#rid);
+    if (this.#streamRid !== null) core.tryClose(this.#streamRid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+    if (this.#streamRid !== null) core.tryClose(this.#streamRid);
+  }
+}
+
+async function openNdjsonReader(source, options) {
+  let streamRid = null;
+  let arg;
+  if (ObjectPrototypeIsPrototypeOf(ReadableStreamPrototype, source)) {
+    streamRid = resourceForReadableStream(source);
+    arg = streamRid;
+  } else if (typeof source?.[internalRidSymbol] === "number") {
+    arg = source[internalRidSymbol];
+  } else {
+    arg = pathFromURL(source);
+  }
+  let rid;
+  try {
+    rid = await op_ndjson_reader_open(arg, options);
+  } catch (err) {
+    if (streamRid !== null) core.tryClose(streamRid);
+    throw err;
+  }
+  return new NdjsonReader(
+    rid,
+    streamRid,
+    SymbolFor("Deno.internal.NdjsonReader"),
+  );
+}
+
+function copyDir(from, to, options = { __proto__: null }) {
+  return runTreeOperation(
+    ({ progressRid, cancelRid }) =>
+      op_fs_copy_dir(pathFromURL(from), pathFromURL(to), {
+        symlinks: options.symlinks ?? "preserve",
+        progressRid,
+        cancelRid,
+      }),
+    options,
+  );
+}
+
+function hashTree(path, options) {
+  return op_fs_hash_tree(pathFromURL(path), options);
+}
+
+function createIntegrityManifest(path, options) {
+  return op_fs_integrity_manifest_create(pathFromURL(path), options);
+}
+
+function verifyIntegrityManifest(path, manifest, options) {
+  return op_fs_integrity_manifest_verify(pathFromURL(path), manifest, options);
+}
+
+let getGlobMatcherRid;
+
+class GlobMatcher {
+  #rid = 0;
+
+  static {
+    getGlobMatcherRid = (value) =>
+      value != null && typeof value === "object" && #rid in value
+        ? value.#rid
+        : undefined;
+  }
+
+  constructor(rid, symbol) {
+    this.#rid = rid;
+    if (!symbol || symbol !== SymbolFor("Deno.internal.GlobMatcher")) {
+      throw new TypeError(
+        "`Deno.GlobMatcher` cannot be constructed, use `Deno.globMatcher()` instead.",
+      );
+    }
+  }
+
+  match(path) {
+    return op_glob_match(this.#rid, path);
+  }
+
+  filter(paths) {
+    const indices = op_glob_filter(this.#rid, paths);
+    return ArrayPrototypeMap(indices, (index) => paths[index]);
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+// Removes the resources of mapped files that were garbage collected. Their
+// mappings are released by the backing stores of their buffers.
+const mappedFileRegistry = new SafeFinalizationRegistry((rid) => {
+  core.tryClose(rid);
+});
+
+class MappedFile {
+  #rid = 0;
+  #buffer;
+
+  constructor(rid, buffer, symbol) {
+    if (!symbol || symbol !== SymbolFor("Deno.internal.MappedFile")) {
+      throw new TypeError(
+        "`Deno.MappedFile` cannot be constructed, use `Deno.mapFile()` instead.",
+      );
+    }
+    this.#rid = rid;
+    this.#buffer = buffer;
+    mappedFileRegistry.register(this, rid, this);
+  }
+
+  get buffer() {
+    return this.#buffer;
+  }
+
+  close() {
+    op_fs_munmap(this.#rid);
+    mappedFileRegistry.unregister(this);
+  }
+
+  [SymbolDispose]() {
+    try {
+      this.close();
+    } catch {
+      // already closed
+    }
+  }
+}
+
+function mapFile(source, options = { __proto__: null }) {
+  const arg = typeof source?.[internalRidSymbol] === "number"
+    ? source[internalRidSymbol]
+    : pathFromURL(source);
+  const { 0: rid, 1: buffer } = op_fs_mmap_readonly(arg, !!options?.lock);
+  return new MappedFile(rid, buffer, SymbolFor("Deno.internal.MappedFile"));
+}
+
+function fsLimitUsage() {
+  return op_fs_limits_usage();
+}
+
+function globMatcher(patterns, options) {
+  const rid = op_glob_compile(patterns, options);
+  return new GlobMatcher(rid, SymbolFor("Deno.internal.GlobMatcher"));
+}
+
+/** Passes `include` and `exclude` given as a `Deno.GlobMatcher` by rid. */
+function globPatternOptions(options) {
+  const includeMatcher = getGlobMatcherRid(options?.include);
+  const excludeMatcher = getGlobMatcherRid(options?.exclude);
+  return {
+    include: includeMatcher === undefined ? options?.include : undefined,
+    exclude: excludeMatcher === undefined ? options?.exclude : undefined,
+    includeMatcher,
+    excludeMatcher,
+  };
+}
+
+function walkDir(path, options) {
+  path = pathFromURL(path);
+  options = { __proto__: null, ...options, ...globPatternOptions(options) };
+  return {
+    async *[SymbolAsyncIterator]() {
+      const rid = op_fs_walk(path, options);
+      try {
+        while (true) {
+          const batch = await op_fs_walk_next(rid);
+          if (batch === null) return;
+          for (let i = 0; i < batch.length; ++i) {
+            const entry = batch[i];
+            entry.mtime = entry.mtime !== null ? new Date(entry.mtime) : null;
+            yield entry;
+          }
+        }
+      } finally {
+        core.tryClose(rid);
+      }
+    },
+  };
+}
+
+function searchText(source, pattern, options = { __proto__: null }) {
+  const args = {
+    pattern,
+    flags: {
+      caseInsensitive: options.caseInsensitive ?? false,
+      multiline: options.multiline ?? false,
+      fixedString: options.fixedString ?? false,
+    },
+    maxMatches: options.maxMatches,
+    context: options.context,
+    ...globPatternOptions(options),
+    binary: options.binary ?? false,
+  };
+  return {
+    async *[SymbolAsyncIterator]() {
+      let streamRid = null;
+      if (ObjectPrototypeIsPrototypeOf(ReadableStreamPrototype, source)) {
+        streamRid = resourceForReadableStream(source);
+        args.readableRid = streamRid;
+      } else if (typeof source?.[internalRidSymbol] === "number") {
+        args.readableRid = source[internalRidSymbol];
+      } else if (ArrayIsArray(source)) {
+        args.paths = ArrayPrototypeMap(source, pathFromURL);
+      } else {
+        args.paths = [pathFromURL(source)];
+      }
+      let rid;
+      try {
+        rid = op_text_search(args);
+      } catch (err) {
+        if (streamRid !== null) core.tryClose(streamRid);
+        throw err;
+      }
+      try {
+        while (true) {
+          const batch = await op_text_search_next(rid);
+          if (batch === null) return;
+          for (let i = 0; i < batch.length; ++i) {
+            yield batch[i];
+          }
+        }
+      } finally {
+        core.tryClose(rid);
+        if (streamRid !== null) core.tryClose(streamRid);
+      }
+    },
+  };
+}
+
+const path = {
+  realPath(path, options = { __proto__: null }) {
+    return op_path_realpath(pathFromURL(path), options.longPath ?? false);
+  },
+  relative(from, to) {
+    return op_path_relative(pathFromURL(from), pathFromURL(to));
+  },
+  normalizeWindows(path, options = { __proto__: null }) {
+    return op_path_normalize_windows(`${path}`, options.longPath ?? false);
+  },
+  isUNC(path) {
+    return op_path_is_unc(`${path}`);
+  },
+  isVerbatim(path) {
+    return op_path_is_verbatim(`${path}`);
+  },
+};
+
+class NdjsonWriter {
+  #writer;
+  #piped;
+
+  constructor(writable, gzip, symbol) {
+    if (!symbol || symbol !== SymbolFor("Deno.internal.NdjsonWriter")) {
+      throw new TypeError(
+        "`Deno.NdjsonWriter` cannot be constructed, use `Deno.openNdjsonWriter()` instead.",
+      );
+    }
+    if (gzip) {
+      const compression = new CompressionStream("gzip");
+      this.#piped = compression.readable.pipeTo(writable);
+      writable = compression.writable;
+    }
+    this.#writer = writable.getWriter();
+  }
+
+  write(values) {
+    return this.#writer.write(op_ndjson_encode_batch(values));
+  }
+
+  async close() {
+    await this.#writer.close();
+    await this.#piped;
+  }
+
+  [SymbolAsyncDispose]() {
+    return this.close();
+  }
+}
+
+async function openNdjsonWriter(destination, options = { __proto__: null }) {
+  let writable = destination;
+  if (!ObjectPrototypeIsPrototypeOf(WritableStream.prototype, destination)) {
+    const file = await open(destination, {
+      write: true,
+      create: true,
+      append: options.append ?? false,
+      truncate: !options.append,
+    });
+    writable = file.writable;
+  }
+  return new NdjsonWriter(
+    writable,
+    options.gzip ?? false,
+    SymbolFor("Deno.internal.NdjsonWriter"),
+  );
+}
+
 function checkOpenOptions(options) {
   if (
     ArrayPrototypeFilter(
@@ -852,6 +1469,38 @@ async function writeFile(
   }
 }
 
+// Data is passed as a list of chunks, so that e.g. a header and a body
+// don't have to be concatenated first.
+function atomicWriteChunks(data) {
+  if (typeof data === "string") {
+    return [new TextEncoder().encode(data)];
+  }
+  if (ArrayIsArray(data)) {
+    const encoder = new TextEncoder();
+    return ArrayPrototypeMap(
+      data,
+      (chunk) => typeof chunk === "string" ? encoder.encode(chunk) : chunk,
+    );
+  }
+  return [data];
+}
+
+function writeFileAtomicSync(path, data, options = { __proto__: null }) {
+  op_fs_write_file_atomic_sync(
+    pathFromURL(path),
+    atomicWriteChunks(data),
+    options,
+  );
+}
+
+async function writeFileAtomic(path, data, options = { __proto__: null }) {
+  await op_fs_write_file_atomic_async(
+    pathFromURL(path),
+    atomicWriteChunks(data),
+    options,
+  );
+}
+
 function writeTextFileSync(
   path,
   data,
@@ -884,24 +1533,41 @@ export {
   chmodSync,
   chown,
   chownSync,
+  copyDir,
   copyFile,
   copyFileSync,
   create,
+  createIntegrityManifest,
   createSync,
+  createTempDir,
+  createTempFile,
   cwd,
   FsFile,
+  fsLimitUsage,
+  GlobMatcher,
+  globMatcher,
+  hashTree,
   link,
   linkSync,
+  LogFile,
   lstat,
   lstatSync,
   makeTempDir,
   makeTempDirSync,
   makeTempFile,
   makeTempFileSync,
+  MappedFile,
+  mapFile,
   mkdir,
+  NdjsonReader,
+  NdjsonWriter,
   mkdirSync,
   open,
+  openLogFile,
+  openNdjsonReader,
+  openNdjsonWriter,
   openSync,
+  path,
   readDir,
   readDirSync,
   readFile,
@@ -916,16 +1582,23 @@ export {
   removeSync,
   rename,
   renameSync,
+  searchText,
   stat,
   statSync,
   symlink,
   symlinkSync,
+  TempDir,
+  TempFile,
   truncate,
   truncateSync,
   umask,
   utime,
   utimeSync,
+  verifyIntegrityManifest,
+  walkDir,
   writeFile,
+  writeFileAtomic,
+  writeFileAtomicSync,
   writeFileSync,
   writeTextFile,
   writeTextFileSync,
@@ -0,0 +1,503 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Atomic file writes.
+//!
+//! The data is written to a temporary file in the target's directory, which
+//! is then renamed over the target. Readers, and the target after a crash,
+//! see either the old or the new contents, never a truncated file. With
+//! `fsync`, the temporary file is synced before the rename and the directory
+//! after it, so that the new contents survive a power loss once the write
+//! returns.
+//!
+//! Previous versions can be kept as `<path>.bak.1` (the most recent) up to
+//! `<path>.bak.<backup>`. On Unix the new file gets the permissions of the
+//! old one, unless a mode is given.
+//!
+//! If `path` is a symlink, the link itself is replaced.
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::BufView;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_io::fs::File;
+use deno_io::fs::FsResult;
+use deno_io::fs::FsStat;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::interface::FileSystem;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+use crate::OpenOptions;
+
+const MAX_TRIES: u32 = 10;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteFileAtomicOptions {
+  mode: Option<u32>,
+  /// Number of previous versions to keep.
+  #[serde(default)]
+  backup: u32,
+  #[serde(default = "default_fsync")]
+  fsync: bool,
+}
+
+fn default_fsync() -> bool {
+  true
+}
+
+impl Default for WriteFileAtomicOptions {
+  fn default() -> Self {
+    Self {
+      mode: None,
+      backup: 0,
+      fsync: default_fsync(),
+    }
+  }
+}
+
+/// The temporary file, which is removed when dropped unless it was renamed
+/// into place.
+struct TempFile<'a> {
+  fs: &'a dyn FileSystem,
+  path: PathBuf,
+  armed: bool,
+}
+
+impl<'a> TempFile<'a> {
+  fn open_options(mode: Option<u32>) -> OpenOptions {
+    OpenOptions {
+      write: true,
+      create_new: true,
+      mode: mode.map(|mode| mode & 0o777),
+      ..Default::default()
+    }
+  }
+
+  fn path(target: &Path) -> PathBuf {
+    let dir = target.parent().unwrap();
+    let name = target.file_name().unwrap().to_string_lossy();
+    dir.join(format!(
+      ".{name}.{:08x}.tmp",
+      rand::thread_rng().gen::<u32>()
+    ))
+  }
+
+  fn create_sync(
+    fs: &'a dyn FileSystem,
+    target: &Path,
+    mode: Option<u32>,
+  ) -> FsResult<(Self, Rc<dyn File>)> {
+    let mut tries = 0;
+    loop {
+      let path = Self::path(target);
+      match fs.open_sync(&path, Self::open_options(mode), None) {
+        Ok(file) => return Ok((Self::armed(fs, path), file)),
+        Err(err)
+          if err.kind() == io::ErrorKind::AlreadyExists
+            && tries < MAX_TRIES =>
+        {
+          tries += 1;
+        }
+        Err(err) => return Err(err),
+      }
+    }
+  }
+
+  async fn create_async(
+    fs: &'a dyn FileSystem,
+    target: &Path,
+    mode: Option<u32>,
+  ) -> FsResult<(Self, Rc<dyn File>)> {
+    let mut tries = 0;
+    loop {
+      let path = Self::path(target);
+      match fs
+        .open_async(path.clone(), Self::open_options(mode), None)
+        .await
+      {
+        Ok(file) => return Ok((Self::armed(fs, path), file)),
+        Err(err)
+          if err.kind() == io::ErrorKind::AlreadyExists
+            && tries < MAX_TRIES =>
+        {
+          tries += 1;
+        }
+        Err(err) => return Err(err),
+      }
+    }
+  }
+
+  fn armed(fs: &'a dyn FileSystem, path: PathBuf) -> Self {
+    Self {
+      fs,
+      path,
+      armed: true,
+    }
+  }
+}
+
+impl Drop for TempFile<'_> {
+  fn drop(&mut self) {
+    if self.armed {
+      let _ = self.fs.remove_sync(&self.path, false);
+    }
+  }
+}
+
+fn backup_path(target: &Path, n: u32) -> PathBuf {
+  let mut path = OsString::from(target.as_os_str());
+  path.push(format!(".bak.{n}"));
+  PathBuf::from(path)
+}
+
+fn ignore_not_found(result: FsResult<()>) -> FsResult<()> {
+  match result {
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+    result => result,
+  }
+}
+
+/// The mode of the file that is replaced, or `None` if there is none.
+fn existing_mode(stat: FsResult<FsStat>) -> FsResult<Option<u32>> {
+  match stat {
+    Ok(stat) => Ok(Some(stat.mode & 0o7777)),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+    Err(err) => Err(err),
+  }
+}
+
+/// Moves `.bak.N` to `.bak.N+1`, dropping the oldest version, and makes the
+/// current target `.bak.1`. The target stays in place until the rename, so
+/// the backup shares its data through a hard link, or is a copy where links
+/// aren't supported.
+fn backup_sync(fs: &dyn FileSystem, target: &Path, count: u32) -> FsResult<()> {
+  for n in (1..count).rev() {
+    ignore_not_found(
+      fs.rename_sync(&backup_path(target, n), &backup_path(target, n + 1)),
+    )?;
+  }
+  let backup = backup_path(target, 1);
+  ignore_not_found(fs.remove_sync(&backup, false))?;
+  if fs.link_sync(target, &backup).is_err() {
+    fs.copy_file_sync(target, &backup)?;
+  }
+  Ok(())
+}
+
+async fn backup_async(
+  fs: &dyn FileSystem,
+  target: &Path,
+  count: u32,
+) -> FsResult<()> {
+  for n in (1..count).rev() {
+    ignore_not_found(
+      fs.rename_async(backup_path(target, n), backup_path(target, n + 1))
+        .await,
+    )?;
+  }
+  let backup = backup_path(target, 1);
+  ignore_not_found(fs.remove_async(backup.clone(), false).await)?;
+  if fs
+    .link_async(target.to_path_buf(), backup.clone())
+    .await
+    .is_err()
+  {
+    fs.copy_file_async(target.to_path_buf(), backup).await?;
+  }
+  Ok(())
+}
+
+/// Writes `data` to `target` atomically. `target` must have a parent and a
+/// file name.
+pub fn write_file_atomic_sync<B: AsRef<[u8]>>(
+  fs: &dyn FileSystem,
+  target: &Path,
+  data: &[B],
+  options: &WriteFileAtomicOptions,
+) -> FsResult<()> {
+  let existing = existing_mode(fs.stat_sync(target))?;
+
+  let (mut temp, file) = TempFile::create_sync(fs, target, options.mode)?;
+  #[cfg(unix)]
+  if let (None, Some(mode)) = (options.mode, existing) {
+    file.clone().chmod_sync(mode)?;
+  }
+  for chunk in data {
+    file.clone().write_all_sync(chunk.as_ref())?;
+  }
+  if options.fsync {
+    file.clone().sync_sync()?;
+  }
+  // Windows can't rename open files.
+  drop(file);
+
+  if existing.is_some() && options.backup > 0 {
+    backup_sync(fs, target, options.backup)?;
+  }
+  fs.rename_sync(&temp.path, target)?;
+  temp.armed = false;
+
+  if options.fsync {
+    sync_dir_sync(fs, target.parent().unwrap())?;
+  }
+  Ok(())
+}
+
+/// Async version of [`write_file_atomic_sync`].
+pub async fn write_file_atomic_async<B: Into<BufView>>(
+  fs: &dyn FileSystem,
+  target: &Path,
+  data: Vec<B>,
+  options: &WriteFileAtomicOptions,
+) -> FsResult<()> {
+  let existing = existing_mode(fs.stat_async(target.to_path_buf()).await)?;
+
+  let (mut temp, file) =
+    TempFile::create_async(fs, target, options.mode).await?;
+  #[cfg(unix)]
+  if let (None, Some(mode)) = (options.mode, existing) {
+    file.clone().chmod_async(mode).await?;
+  }
+  for chunk in data {
+    file.clone().write_all(chunk.into()).await?;
+  }
+  if options.fsync {
+    file.clone().sync_async().await?;
+  }
+  // Windows can't rename open files.
+  drop(file);
+
+  if existing.is_some() && options.backup > 0 {
+    backup_async(fs, target, options.backup).await?;
+  }
+  fs.rename_async(temp.path.clone(), target.to_path_buf())
+    .await?;
+  temp.armed = false;
+
+  if options.fsync {
+    sync_dir_async(fs, target.parent().unwrap()).await?;
+  }
+  Ok(())
+}
+
+/// Makes the rename durable. Windows has no equivalent for directories; its
+/// file systems journal renames.
+#[cfg(unix)]
+fn sync_dir_sync(fs: &dyn FileSystem, dir: &Path) -> FsResult<()> {
+  fs.open_sync(dir, OpenOptions::read(), None)?.sync_sync()
+}
+
+#[cfg(not(unix))]
+fn sync_dir_sync(_fs: &dyn FileSystem, _dir: &Path) -> FsResult<()> {
+  Ok(())
+}
+
+#[cfg(unix)]
+async fn sync_dir_async(fs: &dyn FileSystem, dir: &Path) -> FsResult<()> {
+  fs.open_async(dir.to_path_buf(), OpenOptions::read(), None)
+    .await?
+    .sync_async()
+    .await
+}
+
+#[cfg(not(unix))]
+async fn sync_dir_async(_fs: &dyn FileSystem, _dir: &Path) -> FsResult<()> {
+  Ok(())
+}
+
+/// Checks write permission for the target's directory, where the temporary
+/// file and backups are created, and returns the resolved target.
+fn check_target<P>(
+  permissions: &mut P,
+  path: &str,
+  api_name: &str,
+) -> Result<PathBuf, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let path = Path::new(path);
+  let Some(name) = path.file_name() else {
+    return Err(FsOpsError::Io(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!("Not a file path: {}", path.display()),
+    )));
+  };
+  let dir = match path.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir,
+    _ => Path::new("."),
+  };
+  let dir = permissions
+    .check_write_path(dir, api_name)
+    .map_err(FsOpsError::Permission)?;
+  Ok(dir.join(name))
+}
+
+#[op2]
+pub fn op_fs_write_file_atomic_sync<P>(
+  state: &mut OpState,
+  #[string] path: String,
+  #[serde] data: Vec<JsBuffer>,
+  #[serde] options: Option<WriteFileAtomicOptions>,
+) -> Result<(), FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.writeFileAtomicSync");
+  let target =
+    check_target(state.borrow_mut::<P>(), &path, "Deno.writeFileAtomicSync()")?;
+  let fs = state.borrow::<FileSystemRc>();
+  write_file_atomic_sync(&**fs, &target, &data, &options.unwrap_or_default())
+    .context_path("writefile", &target)
+}
+
+#[op2(async)]
+pub async fn op_fs_write_file_atomic_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[serde] data: Vec<JsBuffer>,
+  #[serde] options: Option<WriteFileAtomicOptions>,
+) -> Result<(), FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let (target, fs) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.writeFileAtomic");
+    let target =
+      check_target(state.borrow_mut::<P>(), &path, "Deno.writeFileAtomic()")?;
+    (target, state.borrow::<FileSystemRc>().clone())
+  };
+  write_file_atomic_async(&*fs, &target, data, &options.unwrap_or_default())
+    .await
+    .context_path("writefile", &target)
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::RealFs;
+
+  fn options(backup: u32) -> WriteFileAtomicOptions {
+    WriteFileAtomicOptions {
+      backup,
+      ..Default::default()
+    }
+  }
+
+  fn entries(dir: &Path) -> Vec<String> {
+    let mut names = std::fs::read_dir(dir)
+      .unwrap()
+      .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+      .collect::<Vec<_>>();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn writes_chunks_and_replaces() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("config.json");
+    write_file_atomic_sync(
+      &RealFs,
+      &target,
+      &[b"{\"a\":".as_slice(), b"1}".as_slice()],
+      &options(0),
+    )
+    .unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), b"{\"a\":1}");
+    write_file_atomic_sync(&RealFs, &target, &[b"{}"], &options(0)).unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), b"{}");
+    assert_eq!(entries(dir.path()), vec!["config.json"]);
+  }
+
+  #[test]
+  fn rotates_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("data");
+    for i in 1..=4 {
+      write_file_atomic_sync(&RealFs, &target, &[i.to_string()], &options(2))
+        .unwrap();
+    }
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "4");
+    assert_eq!(
+      std::fs::read_to_string(backup_path(&target, 1)).unwrap(),
+      "3"
+    );
+    assert_eq!(
+      std::fs::read_to_string(backup_path(&target, 2)).unwrap(),
+      "2"
+    );
+    assert_eq!(
+      entries(dir.path()),
+      vec!["data", "data.bak.1", "data.bak.2"]
+    );
+  }
+
+  #[test]
+  fn writes_async() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("data");
+    std::fs::write(&target, "old").unwrap();
+    let write = write_file_atomic_async(
+      &RealFs,
+      &target,
+      vec![b"ne".to_vec(), b"w".to_vec()],
+      &options(1),
+    );
+    tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap()
+      .block_on(write)
+      .unwrap();
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+    assert_eq!(
+      std::fs::read_to_string(backup_path(&target, 1)).unwrap(),
+      "old"
+    );
+    assert_eq!(entries(dir.path()), vec!["data", "data.bak.1"]);
+  }
+
+  #[test]
+  fn removes_temp_file_on_error() {
+    let dir = tempfile::tempdir().unwrap();
+    // Renaming a file over a non-empty directory fails.
+    let target = dir.path().join("target");
+    std::fs::create_dir(&target).unwrap();
+    std::fs::write(target.join("file"), "").unwrap();
+    write_file_atomic_sync(&RealFs, &target, &[b"data"], &options(0))
+      .unwrap_err();
+    assert_eq!(entries(dir.path()), vec!["target"]);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("secret");
+    std::fs::write(&target, "old").unwrap();
+    std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o600))
+      .unwrap();
+    write_file_atomic_sync(&RealFs, &target, &[b"new"], &options(0)).unwrap();
+    let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+  }
+}
@@ -0,0 +1,409 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Glob patterns with the semantics of the config file's `include` and
+//! `exclude`, shared by the directory walkers and exposed to JS as
+//! `Deno.globMatcher()`.
+//!
+//! - Paths are matched relative to the directory the patterns are for, with
+//!   `/` separators. On Windows, `\` in a path is a separator too.
+//! - `*` and `?` never match a `/`. `**` as a whole path component matches
+//!   any number of directories, including none.
+//! - Wildcards match a leading dot, so `*` matches `.env`.
+//! - A leading `./` and a trailing `/` of a pattern are ignored.
+//! - A pattern that matches a directory matches everything below it.
+//! - Special characters are escaped with brackets: `[*]` matches a literal
+//!   `*`. `[!a]` matches any character but `a`.
+//! - Matching is case sensitive on every platform, unless `caseSensitive` is
+//!   `false`.
+//! - `{a,b}` alternatives, which may nest, are only expanded with
+//!   `extended`. Braces and commas inside brackets are literal.
+//!
+//! Compiled patterns are kept in a small cache in the op state, so walks that
+//! pass the same patterns again and again don't compile them every time.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::ResourceTable;
+use serde::Deserialize;
+
+use crate::ops::FsOpsError;
+
+/// Upper bound on the number of patterns a single pattern expands to.
+const MAX_BRACE_EXPANSIONS: usize = 1024;
+/// Compiled pattern sets kept in [`GlobSetCache`].
+const CACHED_GLOB_SETS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GlobOptions {
+  case_sensitive: bool,
+  /// Expand `{a,b}` alternatives.
+  extended: bool,
+}
+
+impl Default for GlobOptions {
+  fn default() -> Self {
+    Self {
+      case_sensitive: true,
+      extended: false,
+    }
+  }
+}
+
+/// Compiled patterns, matching a path if any of them does.
+#[derive(Debug)]
+pub(crate) struct GlobSet {
+  patterns: Vec<glob::Pattern>,
+  match_options: glob::MatchOptions,
+}
+
+impl GlobSet {
+  pub(crate) fn new(
+    patterns: &[String],
+    options: GlobOptions,
+  ) -> Result<Self, FsOpsError> {
+    let mut compiled = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+      let normalized = pattern.trim_start_matches("./").trim_end_matches('/');
+      let expanded = if options.extended {
+        expand_braces(normalized)
+          .map_err(|msg| FsOpsError::InvalidGlobPattern(pattern.clone(), msg))?
+      } else {
+        vec![normalized.to_string()]
+      };
+      for expanded in expanded {
+        compiled.push(glob::Pattern::new(&expanded).map_err(|err| {
+          FsOpsError::InvalidGlobPattern(pattern.clone(), err.msg)
+        })?);
+      }
+    }
+    Ok(Self {
+      patterns: compiled,
+      match_options: glob::MatchOptions {
+        case_sensitive: options.case_sensitive,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+      },
+    })
+  }
+
+  pub(crate) fn is_empty(&self) -> bool {
+    self.patterns.is_empty()
+  }
+
+  /// Whether `relative` itself matches, ignoring its parents. For walkers
+  /// that already know whether a parent matched.
+  pub(crate) fn matches_exact(&self, relative: &str) -> bool {
+    self
+      .patterns
+      .iter()
+      .any(|pattern| pattern.matches_with(relative, self.match_options))
+  }
+
+  /// Whether `relative` or one of its parent directories matches.
+  pub(crate) fn matches(&self, relative: &str) -> bool {
+    let relative = normalize_path(relative);
+    let mut end = relative.len();
+    loop {
+      if self.matches_exact(&relative[..end]) {
+        return true;
+      }
+      match relative[..end].rfind('/') {
+        Some(index) => end = index,
+        None => return false,
+      }
+    }
+  }
+}
+
+fn normalize_path(path: &str) -> Cow<str> {
+  if cfg!(windows) && path.contains('\\') {
+    let path = path.replace('\\', "/");
+    Cow::Owned(
+      path
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string(),
+    )
+  } else {
+    Cow::Borrowed(path.trim_start_matches("./").trim_end_matches('/'))
+  }
+}
+
+/// Returns the index of the `]` that closes the bracket expression starting
+/// at `start`, if any.
+fn bracket_end(bytes: &[u8], start: usize) -> Option<usize> {
+  let mut index = start + 1;
+  if bytes.get(index) == Some(&b'!') {
+    index += 1;
+  }
+  // a `]` right after the opening bracket is part of the set
+  if bytes.get(index) == Some(&b']') {
+    index += 1;
+  }
+  bytes[index.min(bytes.len())..]
+    .iter()
+    .position(|byte| *byte == b']')
+    .map(|offset| index + offset)
+}
+
+/// Expands the `{a,b}` alternatives of `pattern` into separate patterns.
+fn expand_braces(pattern: &str) -> Result<Vec<String>, &'static str> {
+  let bytes = pattern.as_bytes();
+  let mut open = None;
+  let mut depth = 0;
+  let mut commas = Vec::new();
+  let mut index = 0;
+  while index < bytes.len() {
+    match bytes[index] {
+      b'[' => {
+        if let Some(end) = bracket_end(bytes, index) {
+          index = end;
+        }
+      }
+      b'{' => {
+        if depth == 0 {
+          open = Some(index);
+        }
+        depth += 1;
+      }
+      b',' if depth == 1 => commas.push(index),
+      b'}' if depth > 0 => {
+        depth -= 1;
+        if depth == 0 {
+          let open = open.unwrap();
+          let prefix = &pattern[..open];
+          let suffix = &pattern[index + 1..];
+          let mut expanded = Vec::new();
+          let mut start = open + 1;
+          for end in commas.iter().copied().chain([index]) {
+            let alternative =
+              format!("{prefix}{}{suffix}", &pattern[start..end]);
+            // the suffix and the alternative may contain more braces
+            expanded.extend(expand_braces(&alternative)?);
+            if expanded.len() > MAX_BRACE_EXPANSIONS {
+              return Err("too many brace expansions");
+            }
+            start = end + 1;
+          }
+          return Ok(expanded);
+        }
+      }
+      b'}' => return Err("unmatched closing brace"),
+      _ => {}
+    }
+    index += 1;
+  }
+  if depth > 0 {
+    return Err("unmatched opening brace");
+  }
+  Ok(vec![pattern.to_string()])
+}
+
+/// Recently compiled pattern sets, keyed by their patterns and options.
+#[derive(Default)]
+struct GlobSetCache(HashMap<(Vec<String>, GlobOptions), Rc<GlobSet>>);
+
+/// Compiles `patterns`, or returns the set compiled for them before.
+pub(crate) fn compile_glob_set(
+  state: &mut OpState,
+  patterns: &[String],
+  options: GlobOptions,
+) -> Result<Rc<GlobSet>, FsOpsError> {
+  let key = (patterns.to_vec(), options);
+  let cached = state.try_borrow::<GlobSetCache>();
+  if let Some(set) = cached.and_then(|cache| cache.0.get(&key)) {
+    return Ok(set.clone());
+  }
+  let set = Rc::new(GlobSet::new(patterns, options)?);
+  if !state.has::<GlobSetCache>() {
+    state.put(GlobSetCache::default());
+  }
+  let cache = &mut state.borrow_mut::<GlobSetCache>().0;
+  if cache.len() >= CACHED_GLOB_SETS {
+    cache.clear();
+  }
+  cache.insert(key, set.clone());
+  Ok(set)
+}
+
+struct GlobMatcherResource {
+  set: Rc<GlobSet>,
+}
+
+impl Resource for GlobMatcherResource {
+  fn name(&self) -> Cow<str> {
+    "globMatcher".into()
+  }
+}
+
+/// Returns the patterns of the `Deno.GlobMatcher` `rid`.
+pub(crate) fn glob_matcher(
+  resource_table: &ResourceTable,
+  rid: ResourceId,
+) -> Result<Rc<GlobSet>, FsOpsError> {
+  let resource = resource_table
+    .get::<GlobMatcherResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  Ok(resource.set.clone())
+}
+
+#[op2]
+#[smi]
+pub fn op_glob_compile(
+  state: &mut OpState,
+  #[serde] patterns: Vec<String>,
+  #[serde] options: Option<GlobOptions>,
+) -> Result<ResourceId, FsOpsError> {
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.globMatcher");
+  let set = compile_glob_set(state, &patterns, options.unwrap_or_default())?;
+  Ok(state.resource_table.add(GlobMatcherResource { set }))
+}
+
+#[op2(fast)]
+pub fn op_glob_match(
+  state: &OpState,
+  #[smi] rid: ResourceId,
+  #[string] path: &str,
+) -> Result<bool, FsOpsError> {
+  Ok(glob_matcher(&state.resource_table, rid)?.matches(path))
+}
+
+/// Returns the indices of the matching paths.
+#[op2]
+#[serde]
+pub fn op_glob_filter(
+  state: &OpState,
+  #[smi] rid: ResourceId,
+  #[serde] paths: Vec<String>,
+) -> Result<Vec<u32>, FsOpsError> {
+  let set = glob_matcher(&state.resource_table, rid)?;
+  Ok(
+    paths
+      .iter()
+      .enumerate()
+      .filter(|(_, path)| set.matches(path))
+      .map(|(index, _)| index as u32)
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct SemanticsCase {
+    pattern: String,
+    path: String,
+    #[serde(default)]
+    options: GlobOptions,
+    matches: bool,
+    #[serde(default)]
+    windows_only: bool,
+  }
+
+  /// Shared with tests/unit/glob_matcher_test.ts, so that the matcher behind
+  /// the walkers and `Deno.globMatcher()` can't drift apart.
+  const SEMANTICS: &str = include_str!("testdata/glob_semantics.json");
+
+  #[test]
+  fn test_glob_semantics_table() {
+    let cases: Vec<SemanticsCase> =
+      deno_core::serde_json::from_str(SEMANTICS).unwrap();
+    assert!(cases.len() > 20);
+    for case in cases {
+      if case.windows_only && !cfg!(windows) {
+        continue;
+      }
+      let set = GlobSet::new(&[case.pattern.clone()], case.options).unwrap();
+      assert_eq!(
+        set.matches(&case.path),
+        case.matches,
+        "{:?} matching {:?} with {:?}",
+        case.pattern,
+        case.path,
+        case.options
+      );
+    }
+  }
+
+  #[test]
+  fn test_expand_braces() {
+    assert_eq!(expand_braces("*.ts").unwrap(), ["*.ts"]);
+    assert_eq!(expand_braces("*.{ts,js}").unwrap(), ["*.ts", "*.js"]);
+    assert_eq!(
+      expand_braces("{src,lib}/*.{ts,tsx}").unwrap(),
+      ["src/*.ts", "src/*.tsx", "lib/*.ts", "lib/*.tsx"]
+    );
+    assert_eq!(
+      expand_braces("a{b,c{d,e}}f").unwrap(),
+      ["abf", "acdf", "acef"]
+    );
+    assert_eq!(expand_braces("{,x}a").unwrap(), ["a", "xa"]);
+    assert_eq!(expand_braces("[{]a,b[}]").unwrap(), ["[{]a,b[}]"]);
+    assert_eq!(expand_braces("[],{]x").unwrap(), ["[],{]x"]);
+    assert_eq!(
+      expand_braces("{a,b").unwrap_err(),
+      "unmatched opening brace"
+    );
+    assert_eq!(
+      expand_braces("a,b}").unwrap_err(),
+      "unmatched closing brace"
+    );
+    let many = "{a,b,c,d}".repeat(6);
+    assert_eq!(
+      expand_braces(&many).unwrap_err(),
+      "too many brace expansions"
+    );
+  }
+
+  #[test]
+  fn test_glob_set_invalid_pattern() {
+    let err = GlobSet::new(&["src/**a".to_string()], Default::default())
+      .err()
+      .unwrap();
+    assert!(matches!(err, FsOpsError::InvalidGlobPattern(..)));
+    let extended = GlobOptions {
+      extended: true,
+      ..Default::default()
+    };
+    let err = GlobSet::new(&["{a".to_string()], extended).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Invalid glob pattern \"{a\": unmatched opening brace"
+    );
+  }
+
+  #[test]
+  fn test_glob_set_cache() {
+    let mut state = OpState::new(None);
+    let patterns = vec!["src".to_string(), "**/*.ts".to_string()];
+    let a =
+      compile_glob_set(&mut state, &patterns, Default::default()).unwrap();
+    let b =
+      compile_glob_set(&mut state, &patterns, Default::default()).unwrap();
+    assert!(Rc::ptr_eq(&a, &b));
+    let insensitive = GlobOptions {
+      case_sensitive: false,
+      ..Default::default()
+    };
+    let c = compile_glob_set(&mut state, &patterns, insensitive).unwrap();
+    assert!(!Rc::ptr_eq(&a, &c));
+
+    for i in 0..CACHED_GLOB_SETS {
+      compile_glob_set(&mut state, &[format!("{i}")], Default::default())
+        .unwrap();
+    }
+    assert!(state.borrow::<GlobSetCache>().0.len() <= CACHED_GLOB_SETS);
+  }
+}
@@ -0,0 +1,525 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Content hashing of directory trees, for cache keys and change detection.
+//!
+//! The tree is walked in the same deterministic order as the `deno compile`
+//! VFS builder. Every regular file contributes its relative path (always with
+//! `/` separators) and its contents to a single running digest, each prefixed
+//! by its length, so the digest doesn't depend on the platform and moving
+//! bytes between a path and a file's contents always changes it. Symlinks
+//! that aren't followed contribute their target instead of contents.
+//!
+//! The tree is read through the runtime's [`FileSystem`], a bounded number
+//! of files at a time, and the files are fed into the digest in walk order on
+//! the blocking pool. The runtime's interrupt handle is checked between
+//! entries, so a large tree can be abandoned, e.g. on Ctrl+C.
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::futures::stream;
+use deno_core::futures::StreamExt;
+use deno_core::op2;
+use deno_core::unsync::spawn_blocking;
+use deno_core::OpState;
+use deno_io::check_interrupt;
+use deno_io::InterruptHandle;
+use deno_io::InterruptToken;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+
+use crate::glob_matcher::GlobSet;
+use crate::ops::FsOpsError;
+use crate::walk::walk_dir_recursive_async;
+use crate::walk::WalkEntryKind;
+use crate::walk::WalkError;
+use crate::walk::WalkOptions;
+use crate::FileSystem;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+
+/// How many files are read and held in memory at once.
+pub(crate) const MAX_PARALLEL_READS: usize = 16;
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashTreeAlgorithm {
+  #[default]
+  Sha256,
+  Blake3,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HashTreeOptions {
+  algorithm: HashTreeAlgorithm,
+  include: Vec<String>,
+  exclude: Vec<String>,
+  follow_symlinks: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashTreeResult {
+  digest: String,
+  files: u64,
+  bytes: u64,
+}
+
+enum TreeHasher {
+  Sha256(sha2::Sha256),
+  Blake3(Box<blake3::Hasher>),
+}
+
+impl TreeHasher {
+  fn new(algorithm: HashTreeAlgorithm) -> Self {
+    match algorithm {
+      HashTreeAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+      HashTreeAlgorithm::Blake3 => {
+        Self::Blake3(Box::new(blake3::Hasher::new()))
+      }
+    }
+  }
+
+  fn update(&mut self, data: &[u8]) {
+    match self {
+      Self::Sha256(hasher) => hasher.update(data),
+      Self::Blake3(hasher) => {
+        hasher.update(data);
+      }
+    }
+  }
+
+  fn update_framed(&mut self, data: &[u8]) {
+    self.update(&(data.len() as u64).to_le_bytes());
+    self.update(data);
+  }
+
+  fn finalize_hex(self) -> String {
+    match self {
+      Self::Sha256(hasher) => faster_hex::hex_string(&hasher.finalize()),
+      Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+    }
+  }
+}
+
+pub(crate) struct Filter {
+  include: GlobSet,
+  exclude: GlobSet,
+}
+
+impl Filter {
+  pub(crate) fn new(
+    include: &[String],
+    exclude: &[String],
+  ) -> Result<Self, FsOpsError> {
+    Ok(Self {
+      include: GlobSet::new(include, Default::default())?,
+      exclude: GlobSet::new(exclude, Default::default())?,
+    })
+  }
+
+  fn is_excluded(&self, relative: &str) -> bool {
+    self.exclude.matches_exact(relative)
+  }
+
+  fn is_included(&self, relative: &str) -> bool {
+    (self.include.is_empty() || self.include.matches_exact(relative))
+      && !self.is_excluded(relative)
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum TreeEntryKind {
+  File,
+  Symlink,
+}
+
+#[derive(Debug)]
+pub(crate) struct TreeEntry {
+  /// Relative to the root, with `/` separators.
+  pub(crate) relative: String,
+  pub(crate) path: PathBuf,
+  pub(crate) kind: TreeEntryKind,
+}
+
+/// Builds the `/` separated path of `path` relative to `root`.
+fn relative_path(root: &Path, path: &Path) -> Result<String, FsOpsError> {
+  let mut relative = String::new();
+  for component in path.strip_prefix(root)?.components() {
+    let component = component.as_os_str();
+    let Some(component) = component.to_str() else {
+      return Err(FsOpsError::InvalidUtf8(component.to_os_string()));
+    };
+    if !relative.is_empty() {
+      relative.push('/');
+    }
+    relative.push_str(component);
+  }
+  Ok(relative)
+}
+
+/// Returns the entries to hash, and the canonical paths of those that were
+/// reached through a symlink pointing outside of `root`.
+pub(crate) async fn collect_entries(
+  fs: &dyn FileSystem,
+  root: &Path,
+  filter: &Filter,
+  follow_symlinks: bool,
+  interrupt: &InterruptToken,
+) -> Result<(Vec<TreeEntry>, Vec<PathBuf>), FsOpsError> {
+  let filter_dir = |path: &Path| {
+    // Paths that aren't valid UTF-8 are reported when visited.
+    relative_path(root, path)
+      .map(|relative| !filter.is_excluded(&relative))
+      .unwrap_or(true)
+  };
+  let options = WalkOptions {
+    follow_symlinks,
+    filter_dir: Some(&filter_dir),
+  };
+  let canonical_root = if follow_symlinks {
+    Some(fs.realpath_async(root.to_path_buf()).await?)
+  } else {
+    None
+  };
+  let mut entries = Vec::new();
+  walk_dir_recursive_async(fs, root, &options, &mut |path, kind| {
+    interrupt.check().map_err(FsOpsError::from)?;
+    let kind = match kind {
+      WalkEntryKind::Dir => return Ok(()),
+      WalkEntryKind::File => TreeEntryKind::File,
+      WalkEntryKind::Symlink => TreeEntryKind::Symlink,
+    };
+    let relative = relative_path(root, path)?;
+    if !filter.is_included(&relative) {
+      return Ok(());
+    }
+    entries.push(TreeEntry {
+      relative,
+      path: path.to_path_buf(),
+      kind,
+    });
+    Ok(())
+  })
+  .await
+  .map_err(|err| match err {
+    WalkError::Visit(err) => match err.downcast::<FsOpsError>() {
+      Ok(err) => err,
+      Err(err) => FsOpsError::Other(err),
+    },
+    err => FsOpsError::Walk(err),
+  })?;
+
+  // only entries reached through a symlink can resolve outside of the root
+  let mut outside_root = Vec::new();
+  if let Some(canonical_root) = &canonical_root {
+    for entry in &entries {
+      let canonical = fs.realpath_async(entry.path.clone()).await?;
+      if !canonical.starts_with(canonical_root) {
+        outside_root.push(canonical);
+      }
+    }
+  }
+  Ok((entries, outside_root))
+}
+
+/// Reads the contents of a file, or the target of a symlink with `/`
+/// separators.
+pub(crate) async fn read_entry(
+  fs: &dyn FileSystem,
+  entry: &TreeEntry,
+) -> Result<Vec<u8>, FsOpsError> {
+  match entry.kind {
+    TreeEntryKind::File => {
+      Ok(fs.read_file_async(entry.path.clone(), None).await?)
+    }
+    TreeEntryKind::Symlink => {
+      let target = fs.read_link_async(entry.path.clone()).await?;
+      Ok(target.to_string_lossy().replace('\\', "/").into_bytes())
+    }
+  }
+}
+
+async fn hash_entries(
+  fs: &dyn FileSystem,
+  entries: Vec<TreeEntry>,
+  algorithm: HashTreeAlgorithm,
+  interrupt: &InterruptToken,
+) -> Result<HashTreeResult, FsOpsError> {
+  let mut hasher = TreeHasher::new(algorithm);
+  let mut files = 0;
+  let mut bytes = 0;
+  let mut chunks = stream::iter(entries)
+    .map(|entry| async move {
+      let data = read_entry(fs, &entry).await?;
+      Ok::<_, FsOpsError>((entry, data))
+    })
+    .buffered(MAX_PARALLEL_READS)
+    .chunks(MAX_PARALLEL_READS);
+  while let Some(chunk) = chunks.next().await {
+    check_interrupt!(interrupt);
+    let chunk = chunk.into_iter().collect::<Result<Vec<_>, _>>()?;
+    for (entry, data) in &chunk {
+      if entry.kind == TreeEntryKind::File {
+        files += 1;
+        bytes += data.len() as u64;
+      }
+    }
+    hasher = spawn_blocking(move || {
+      for (entry, data) in chunk {
+        hasher.update(match entry.kind {
+          TreeEntryKind::File => b"f",
+          TreeEntryKind::Symlink => b"l",
+        });
+        hasher.update_framed(entry.relative.as_bytes());
+        hasher.update_framed(&data);
+      }
+      hasher
+    })
+    .await
+    .map_err(|err| FsOpsError::Other(err.into()))?;
+  }
+  Ok(HashTreeResult {
+    digest: hasher.finalize_hex(),
+    files,
+    bytes,
+  })
+}
+
+/// Checks read access to files that were reached through symlinks pointing
+/// outside of the (already checked) root.
+fn check_outside_root<P: FsPermissions + 'static>(
+  state: &Rc<RefCell<OpState>>,
+  outside_root: &[PathBuf],
+) -> Result<(), FsOpsError> {
+  let mut state = state.borrow_mut();
+  let permissions = state.borrow_mut::<P>();
+  for path in outside_root {
+    permissions
+      .check_read_path(path, "Deno.hashTree()")
+      .map_err(FsOpsError::Permission)?;
+  }
+  Ok(())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_hash_tree<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[serde] options: Option<HashTreeOptions>,
+) -> Result<HashTreeResult, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  let filter = Filter::new(&options.include, &options.exclude)?;
+
+  let (fs, root, interrupt) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.hashTree");
+    let root = state
+      .borrow_mut::<P>()
+      .check_read(&path, "Deno.hashTree()")
+      .map_err(FsOpsError::Permission)?;
+    let fs = state.borrow::<FileSystemRc>().clone();
+    (fs, root, InterruptHandle::token_from_state(&state))
+  };
+
+  let (entries, outside_root) =
+    collect_entries(&*fs, &root, &filter, options.follow_symlinks, &interrupt)
+      .await?;
+  // Followed symlinks were resolved while walking, but nothing was read yet.
+  check_outside_root::<P>(&state, &outside_root)?;
+
+  hash_entries(&*fs, entries, options.algorithm, &interrupt).await
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::RealFs;
+
+  fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap()
+      .block_on(future)
+  }
+
+  fn hash_tree(root: &Path, options: HashTreeOptions) -> HashTreeResult {
+    let filter = Filter::new(&options.include, &options.exclude).unwrap();
+    let interrupt = InterruptToken::default();
+    block_on(async {
+      let (entries, _) = collect_entries(
+        &RealFs,
+        root,
+        &filter,
+        options.follow_symlinks,
+        &interrupt,
+      )
+      .await
+      .unwrap();
+      hash_entries(&RealFs, entries, options.algorithm, &interrupt)
+        .await
+        .unwrap()
+    })
+  }
+
+  fn write_tree(root: &Path) {
+    std::fs::create_dir_all(root.join("src/nested")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+    std::fs::write(root.join("src/main.ts"), "console.log(1);\n").unwrap();
+    std::fs::write(root.join("src/nested/mod.ts"), "export {};\n").unwrap();
+    std::fs::write(root.join("src/out.tmp"), "scratch").unwrap();
+    std::fs::write(root.join("node_modules/pkg/index.js"), "x").unwrap();
+  }
+
+  #[test]
+  fn test_hash_tree_known_digest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "hello").unwrap();
+
+    let mut expected = sha2::Sha256::new();
+    expected.update(b"f");
+    expected.update(5u64.to_le_bytes());
+    expected.update(b"a.txt");
+    expected.update(5u64.to_le_bytes());
+    expected.update(b"hello");
+
+    assert_eq!(
+      hash_tree(dir.path(), Default::default()),
+      HashTreeResult {
+        digest: faster_hex::hex_string(&expected.finalize()),
+        files: 1,
+        bytes: 5,
+      }
+    );
+  }
+
+  #[test]
+  fn test_hash_tree_stable_and_sensitive() {
+    let a = tempfile::tempdir().unwrap();
+    let b = tempfile::tempdir().unwrap();
+    write_tree(a.path());
+    write_tree(b.path());
+
+    for algorithm in [HashTreeAlgorithm::Sha256, HashTreeAlgorithm::Blake3] {
+      let options = || HashTreeOptions {
+        algorithm,
+        ..Default::default()
+      };
+      let first = hash_tree(a.path(), options());
+      assert_eq!(first.files, 4);
+      assert_eq!(first, hash_tree(a.path(), options()));
+      // independent of where the tree lives
+      assert_eq!(first, hash_tree(b.path(), options()));
+
+      std::fs::write(b.path().join("src/main.ts"), "console.log(2);\n")
+        .unwrap();
+      assert_ne!(first.digest, hash_tree(b.path(), options()).digest);
+      write_tree(b.path());
+    }
+
+    // moving a file changes the digest
+    let first = hash_tree(a.path(), Default::default());
+    std::fs::rename(a.path().join("src/out.tmp"), a.path().join("out.tmp"))
+      .unwrap();
+    assert_ne!(first.digest, hash_tree(a.path(), Default::default()).digest);
+  }
+
+  #[test]
+  fn test_hash_tree_include_exclude() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let all = hash_tree(dir.path(), Default::default());
+
+    let excluded = hash_tree(
+      dir.path(),
+      HashTreeOptions {
+        exclude: vec!["node_modules".to_string(), "**/*.tmp".to_string()],
+        ..Default::default()
+      },
+    );
+    assert_eq!(excluded.files, 2);
+    assert_ne!(excluded.digest, all.digest);
+
+    // changes to excluded files don't matter
+    std::fs::write(dir.path().join("src/out.tmp"), "changed").unwrap();
+    std::fs::write(dir.path().join("node_modules/pkg/index.js"), "y").unwrap();
+    let excluded_again = hash_tree(
+      dir.path(),
+      HashTreeOptions {
+        exclude: vec!["node_modules".to_string(), "**/*.tmp".to_string()],
+        ..Default::default()
+      },
+    );
+    assert_eq!(excluded, excluded_again);
+
+    let included = hash_tree(
+      dir.path(),
+      HashTreeOptions {
+        include: vec!["src/**/*.ts".to_string()],
+        ..Default::default()
+      },
+    );
+    assert_eq!(included, excluded);
+  }
+
+  #[test]
+  fn test_hash_tree_invalid_glob() {
+    let err = Filter::new(&[], &["[".to_string()]).err().unwrap();
+    assert!(matches!(err, FsOpsError::InvalidGlobPattern(..)));
+  }
+
+  #[test]
+  fn test_hash_tree_interrupted() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let filter = Filter::new(&[], &[]).unwrap();
+    let handle = InterruptHandle::default();
+
+    let collect = |interrupt| {
+      block_on(collect_entries(
+        &RealFs,
+        dir.path(),
+        &filter,
+        false,
+        interrupt,
+      ))
+    };
+    let hash = |entries, interrupt| {
+      block_on(hash_entries(
+        &RealFs,
+        entries,
+        HashTreeAlgorithm::Sha256,
+        interrupt,
+      ))
+    };
+
+    let interrupt = handle.token();
+    handle.interrupt();
+    let err = collect(&interrupt).err().unwrap();
+    assert!(matches!(err, FsOpsError::Interrupted(_)));
+
+    let interrupt = handle.token();
+    let (entries, _) = collect(&interrupt).unwrap();
+    handle.interrupt();
+    let err = hash(entries, &interrupt).err().unwrap();
+    assert!(matches!(err, FsOpsError::Interrupted(_)));
+
+    // later calls are not affected
+    let interrupt = handle.token();
+    let (entries, _) = collect(&interrupt).unwrap();
+    assert!(hash(entries, &interrupt).is_ok());
+  }
+}
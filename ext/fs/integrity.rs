@@ -0,0 +1,602 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Integrity manifests of directory trees, to detect tampering with files
+//! shipped next to a program, e.g. the assets of a compiled binary.
+//!
+//! A manifest maps the `/` separated path of every file below a root to its
+//! subresource integrity string (`sha256-<base64>`), and carries a digest over
+//! all of those entries. It's plain JSON with a `version` field:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "algorithm": "sha256",
+//!   "include": [],
+//!   "exclude": ["**/*.tmp"],
+//!   "files": { "img/logo.png": "sha256-..." },
+//!   "digest": "sha256-..."
+//! }
+//! ```
+//!
+//! `files` is sorted by the UTF-8 bytes of the paths. `digest` hashes, in
+//! that order, every path followed by its integrity string, each prefixed by
+//! its length as a little endian u64. Other tools can produce or check a
+//! manifest from that alone.
+//!
+//! The tree is walked like `Deno.hashTree()` walks it, with the same include
+//! and exclude globs, but symlinks are never followed: a symlink is recorded
+//! with the hash of its target path, so retargeting it is detected. Files
+//! are read through the runtime's [`FileSystem`], a bounded number at a time,
+//! and hashed on the blocking pool. The runtime's interrupt handle is checked
+//! between batches.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::rc::Rc;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use deno_core::futures::stream;
+use deno_core::futures::StreamExt;
+use deno_core::op2;
+use deno_core::unsync::spawn_blocking;
+use deno_core::OpState;
+use deno_io::check_interrupt;
+use deno_io::InterruptHandle;
+use deno_io::InterruptToken;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+
+use crate::hash_tree::collect_entries;
+use crate::hash_tree::read_entry;
+use crate::hash_tree::Filter;
+use crate::hash_tree::TreeEntry;
+use crate::hash_tree::MAX_PARALLEL_READS;
+use crate::ops::FsOpsError;
+use crate::FileSystem;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+
+pub const INTEGRITY_MANIFEST_VERSION: u32 = 1;
+
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrityAlgorithm {
+  #[default]
+  Sha256,
+  Sha384,
+  Sha512,
+}
+
+impl IntegrityAlgorithm {
+  fn name(self) -> &'static str {
+    match self {
+      Self::Sha256 => "sha256",
+      Self::Sha384 => "sha384",
+      Self::Sha512 => "sha512",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityManifest {
+  pub version: u32,
+  pub algorithm: IntegrityAlgorithm,
+  #[serde(default)]
+  pub include: Vec<String>,
+  #[serde(default)]
+  pub exclude: Vec<String>,
+  pub files: BTreeMap<String, String>,
+  pub digest: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IntegrityManifestOptions {
+  pub algorithm: IntegrityAlgorithm,
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VerifyIntegrityManifestOptions {
+  /// Stop at the first file that doesn't match, and only report that one.
+  pub fail_fast: bool,
+}
+
+/// The files that differ from a manifest, each list sorted by path.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+  pub ok: bool,
+  /// In the manifest, but not in the tree.
+  pub missing: Vec<String>,
+  /// In the tree, but not in the manifest.
+  pub extra: Vec<String>,
+  /// In both, with different contents.
+  pub modified: Vec<String>,
+}
+
+enum IntegrityHasher {
+  Sha256(sha2::Sha256),
+  Sha384(sha2::Sha384),
+  Sha512(sha2::Sha512),
+}
+
+impl IntegrityHasher {
+  fn new(algorithm: IntegrityAlgorithm) -> Self {
+    match algorithm {
+      IntegrityAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+      IntegrityAlgorithm::Sha384 => Self::Sha384(sha2::Sha384::new()),
+      IntegrityAlgorithm::Sha512 => Self::Sha512(sha2::Sha512::new()),
+    }
+  }
+
+  fn update(&mut self, data: &[u8]) {
+    match self {
+      Self::Sha256(hasher) => hasher.update(data),
+      Self::Sha384(hasher) => hasher.update(data),
+      Self::Sha512(hasher) => hasher.update(data),
+    }
+  }
+
+  fn update_framed(&mut self, data: &[u8]) {
+    self.update(&(data.len() as u64).to_le_bytes());
+    self.update(data);
+  }
+
+  /// Returns the subresource integrity string of the hashed data.
+  fn finalize_sri(self) -> String {
+    let (name, hash) = match self {
+      Self::Sha256(hasher) => ("sha256", hasher.finalize().to_vec()),
+      Self::Sha384(hasher) => ("sha384", hasher.finalize().to_vec()),
+      Self::Sha512(hasher) => ("sha512", hasher.finalize().to_vec()),
+    };
+    format!("{name}-{}", BASE64_STANDARD.encode(hash))
+  }
+}
+
+/// Hashes `entries` a batch at a time and passes the results to `visit` in
+/// order, until it returns `false`.
+async fn hash_entries(
+  fs: &dyn FileSystem,
+  entries: &[&TreeEntry],
+  algorithm: IntegrityAlgorithm,
+  interrupt: &InterruptToken,
+  visit: &mut dyn FnMut(&TreeEntry, String) -> bool,
+) -> Result<(), FsOpsError> {
+  let mut chunks = stream::iter(entries)
+    .map(|entry| read_entry(fs, entry))
+    .buffered(MAX_PARALLEL_READS)
+    .chunks(MAX_PARALLEL_READS);
+  let mut entries = entries.iter();
+  while let Some(chunk) = chunks.next().await {
+    check_interrupt!(interrupt);
+    let chunk = chunk.into_iter().collect::<Result<Vec<_>, _>>()?;
+    let hashes = spawn_blocking(move || {
+      chunk
+        .iter()
+        .map(|data| {
+          let mut hasher = IntegrityHasher::new(algorithm);
+          hasher.update(data);
+          hasher.finalize_sri()
+        })
+        .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|err| FsOpsError::Other(err.into()))?;
+    for (hash, entry) in hashes.into_iter().zip(&mut entries) {
+      if !visit(entry, hash) {
+        return Ok(());
+      }
+    }
+  }
+  Ok(())
+}
+
+fn manifest_digest(
+  algorithm: IntegrityAlgorithm,
+  files: &BTreeMap<String, String>,
+) -> String {
+  let mut hasher = IntegrityHasher::new(algorithm);
+  for (path, integrity) in files {
+    hasher.update_framed(path.as_bytes());
+    hasher.update_framed(integrity.as_bytes());
+  }
+  hasher.finalize_sri()
+}
+
+/// Creates the manifest of the tree below `root`.
+pub async fn create_integrity_manifest(
+  fs: &dyn FileSystem,
+  root: &Path,
+  options: &IntegrityManifestOptions,
+  interrupt: &InterruptToken,
+) -> Result<IntegrityManifest, FsOpsError> {
+  let filter = Filter::new(&options.include, &options.exclude)?;
+  let (entries, _) =
+    collect_entries(fs, root, &filter, false, interrupt).await?;
+  let entries = entries.iter().collect::<Vec<_>>();
+  let mut files = BTreeMap::new();
+  hash_entries(
+    fs,
+    &entries,
+    options.algorithm,
+    interrupt,
+    &mut |entry, hash| {
+      files.insert(entry.relative.clone(), hash);
+      true
+    },
+  )
+  .await?;
+  Ok(IntegrityManifest {
+    version: INTEGRITY_MANIFEST_VERSION,
+    algorithm: options.algorithm,
+    include: options.include.clone(),
+    exclude: options.exclude.clone(),
+    digest: manifest_digest(options.algorithm, &files),
+    files,
+  })
+}
+
+/// Checks the tree below `root` against `manifest`.
+pub async fn verify_integrity_manifest(
+  fs: &dyn FileSystem,
+  root: &Path,
+  manifest: &IntegrityManifest,
+  options: &VerifyIntegrityManifestOptions,
+  interrupt: &InterruptToken,
+) -> Result<IntegrityReport, FsOpsError> {
+  if manifest.version != INTEGRITY_MANIFEST_VERSION {
+    return Err(FsOpsError::InvalidIntegrityManifest(format!(
+      "unsupported version {}, expected {INTEGRITY_MANIFEST_VERSION}",
+      manifest.version
+    )));
+  }
+  if manifest.digest != manifest_digest(manifest.algorithm, &manifest.files) {
+    return Err(FsOpsError::InvalidIntegrityManifest(format!(
+      "\"digest\" is not the {} digest of \"files\"",
+      manifest.algorithm.name()
+    )));
+  }
+
+  let filter = Filter::new(&manifest.include, &manifest.exclude)?;
+  let (entries, _) =
+    collect_entries(fs, root, &filter, false, interrupt).await?;
+  let present = entries
+    .iter()
+    .map(|entry| entry.relative.as_str())
+    .collect::<BTreeSet<_>>();
+
+  let mut report = IntegrityReport {
+    missing: manifest
+      .files
+      .keys()
+      .filter(|path| !present.contains(path.as_str()))
+      .cloned()
+      .collect(),
+    extra: present
+      .iter()
+      .filter(|path| !manifest.files.contains_key(*path))
+      .map(|path| path.to_string())
+      .collect(),
+    ..Default::default()
+  };
+
+  if options.fail_fast
+    && !(report.missing.is_empty() && report.extra.is_empty())
+  {
+    // Listing the tree is cheap, so report the first of those without
+    // reading any file.
+    if !report.missing.is_empty() {
+      report.missing.truncate(1);
+      report.extra.clear();
+    } else {
+      report.extra.truncate(1);
+    }
+    return Ok(report);
+  }
+
+  let mut to_hash = entries
+    .iter()
+    .filter(|entry| manifest.files.contains_key(&entry.relative))
+    .collect::<Vec<_>>();
+  to_hash.sort_by(|a, b| a.relative.cmp(&b.relative));
+  hash_entries(
+    fs,
+    &to_hash,
+    manifest.algorithm,
+    interrupt,
+    &mut |entry, hash| {
+      if manifest.files[&entry.relative] != hash {
+        report.modified.push(entry.relative.clone());
+        return !options.fail_fast;
+      }
+      true
+    },
+  )
+  .await?;
+
+  report.ok = report.missing.is_empty()
+    && report.extra.is_empty()
+    && report.modified.is_empty();
+  Ok(report)
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_integrity_manifest_create<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[serde] options: Option<IntegrityManifestOptions>,
+) -> Result<IntegrityManifest, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  let (fs, root, interrupt) = {
+    let mut state = state.borrow_mut();
+    state.feature_checker.check_or_exit(
+      crate::UNSTABLE_FEATURE_NAME,
+      "Deno.createIntegrityManifest",
+    );
+    let root = state
+      .borrow_mut::<P>()
+      .check_read(&path, "Deno.createIntegrityManifest()")
+      .map_err(FsOpsError::Permission)?;
+    let fs = state.borrow::<FileSystemRc>().clone();
+    (fs, root, InterruptHandle::token_from_state(&state))
+  };
+  create_integrity_manifest(&*fs, &root, &options, &interrupt).await
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_integrity_manifest_verify<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[serde] manifest: IntegrityManifest,
+  #[serde] options: Option<VerifyIntegrityManifestOptions>,
+) -> Result<IntegrityReport, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  let (fs, root, interrupt) = {
+    let mut state = state.borrow_mut();
+    state.feature_checker.check_or_exit(
+      crate::UNSTABLE_FEATURE_NAME,
+      "Deno.verifyIntegrityManifest",
+    );
+    let root = state
+      .borrow_mut::<P>()
+      .check_read(&path, "Deno.verifyIntegrityManifest()")
+      .map_err(FsOpsError::Permission)?;
+    let fs = state.borrow::<FileSystemRc>().clone();
+    (fs, root, InterruptHandle::token_from_state(&state))
+  };
+  verify_integrity_manifest(&*fs, &root, &manifest, &options, &interrupt).await
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::RealFs;
+
+  fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap()
+      .block_on(future)
+  }
+
+  fn write_tree(root: &Path) {
+    std::fs::create_dir_all(root.join("img")).unwrap();
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    std::fs::write(root.join("img/logo.svg"), "<svg/>").unwrap();
+    std::fs::write(root.join("img/out.tmp"), "scratch").unwrap();
+  }
+
+  fn create(root: &Path) -> IntegrityManifest {
+    block_on(create_integrity_manifest(
+      &RealFs,
+      root,
+      &IntegrityManifestOptions {
+        exclude: vec!["**/*.tmp".to_string()],
+        ..Default::default()
+      },
+      &InterruptToken::default(),
+    ))
+    .unwrap()
+  }
+
+  fn verify(
+    root: &Path,
+    manifest: &IntegrityManifest,
+    fail_fast: bool,
+  ) -> Result<IntegrityReport, FsOpsError> {
+    block_on(verify_integrity_manifest(
+      &RealFs,
+      root,
+      manifest,
+      &VerifyIntegrityManifestOptions { fail_fast },
+      &InterruptToken::default(),
+    ))
+  }
+
+  #[test]
+  fn test_manifest_format() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let manifest = create(dir.path());
+
+    let sri = |data: &[u8]| {
+      format!(
+        "sha256-{}",
+        BASE64_STANDARD.encode(sha2::Sha256::digest(data))
+      )
+    };
+    assert_eq!(
+      manifest.files,
+      BTreeMap::from([
+        ("a.txt".to_string(), sri(b"a")),
+        ("img/logo.svg".to_string(), sri(b"<svg/>")),
+      ])
+    );
+    let mut framed = Vec::new();
+    for (path, integrity) in &manifest.files {
+      for data in [path.as_bytes(), integrity.as_bytes()] {
+        framed.extend((data.len() as u64).to_le_bytes());
+        framed.extend(data);
+      }
+    }
+    assert_eq!(manifest.digest, sri(&framed));
+
+    let json = deno_core::serde_json::to_value(&manifest).unwrap();
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["algorithm"], "sha256");
+    assert_eq!(json["exclude"][0], "**/*.tmp");
+    let parsed: IntegrityManifest =
+      deno_core::serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, manifest);
+  }
+
+  #[test]
+  fn test_verify_reports() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let manifest = create(dir.path());
+
+    let report = verify(dir.path(), &manifest, false).unwrap();
+    assert!(report.ok);
+    assert_eq!(
+      report,
+      IntegrityReport {
+        ok: true,
+        ..Default::default()
+      }
+    );
+
+    // excluded files may change
+    std::fs::write(dir.path().join("img/out.tmp"), "changed").unwrap();
+    assert!(verify(dir.path(), &manifest, false).unwrap().ok);
+
+    std::fs::write(dir.path().join("a.txt"), "b").unwrap();
+    std::fs::remove_file(dir.path().join("img/logo.svg")).unwrap();
+    std::fs::write(dir.path().join("img/new.svg"), "<svg/>").unwrap();
+    std::fs::write(dir.path().join("z.txt"), "z").unwrap();
+    assert_eq!(
+      verify(dir.path(), &manifest, false).unwrap(),
+      IntegrityReport {
+        ok: false,
+        missing: vec!["img/logo.svg".to_string()],
+        extra: vec!["img/new.svg".to_string(), "z.txt".to_string()],
+        modified: vec!["a.txt".to_string()],
+      }
+    );
+
+    // fail fast reports the first missing or extra file without reading
+    // anything
+    assert_eq!(
+      verify(dir.path(), &manifest, true).unwrap(),
+      IntegrityReport {
+        ok: false,
+        missing: vec!["img/logo.svg".to_string()],
+        ..Default::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_verify_fail_fast_modified() {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..40 {
+      std::fs::write(dir.path().join(format!("{i:02}.txt")), "x").unwrap();
+    }
+    let manifest = create(dir.path());
+    for i in [3, 7, 35] {
+      std::fs::write(dir.path().join(format!("{i:02}.txt")), "y").unwrap();
+    }
+    assert_eq!(
+      verify(dir.path(), &manifest, false).unwrap().modified,
+      ["03.txt", "07.txt", "35.txt"]
+    );
+    assert_eq!(
+      verify(dir.path(), &manifest, true).unwrap(),
+      IntegrityReport {
+        ok: false,
+        modified: vec!["03.txt".to_string()],
+        ..Default::default()
+      }
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_verify_symlink_retargeted() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    std::os::unix::fs::symlink("a.txt", dir.path().join("link")).unwrap();
+    let manifest = create(dir.path());
+    assert!(manifest.files.contains_key("link"));
+
+    std::fs::remove_file(dir.path().join("link")).unwrap();
+    std::os::unix::fs::symlink("img/logo.svg", dir.path().join("link"))
+      .unwrap();
+    assert_eq!(
+      verify(dir.path(), &manifest, false).unwrap().modified,
+      ["link"]
+    );
+  }
+
+  #[test]
+  fn test_verify_invalid_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let manifest = create(dir.path());
+
+    let mut tampered = manifest.clone();
+    tampered
+      .files
+      .insert("a.txt".to_string(), "sha256-AAAA".to_string());
+    let err = verify(dir.path(), &tampered, false).unwrap_err();
+    assert!(matches!(err, FsOpsError::InvalidIntegrityManifest(_)));
+
+    let mut future = manifest;
+    future.version = 2;
+    let err = verify(dir.path(), &future, false).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Invalid integrity manifest: unsupported version 2, expected 1"
+    );
+  }
+
+  #[test]
+  fn test_integrity_interrupted() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let manifest = create(dir.path());
+    let handle = InterruptHandle::default();
+    let interrupt = handle.token();
+    handle.interrupt();
+    let err = block_on(verify_integrity_manifest(
+      &RealFs,
+      dir.path(),
+      &manifest,
+      &Default::default(),
+      &interrupt,
+    ))
+    .unwrap_err();
+    assert!(matches!(err, FsOpsError::Interrupted(_)));
+  }
+}
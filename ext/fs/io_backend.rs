@@ -0,0 +1,864 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! IO backends used by `RealFs` for whole-file reads (`Deno.readFile`,
+//! `Deno.readTextFile`) and async `stat`/`lstat`.
+//!
+//! Reads through file resources (`Deno.FsFile#read`, `readable` streams) are
+//! served by `deno_io`, which doesn't know about these backends, and always
+//! run on the blocking thread pool.
+//!
+//! The default backend runs every request on the blocking thread pool. The
+//! experimental backends submit requests to the kernel from a single driver
+//! thread instead: io_uring on Linux (`io_uring` cargo feature) and overlapped
+//! IO with a completion port on Windows (`overlapped` cargo feature).
+//!
+//! The backend is selected once per process with the `DENO_FS_IO_BACKEND`
+//! environment variable. If the value isn't a known backend, the requested
+//! backend wasn't compiled in or the kernel doesn't support it, a warning is
+//! printed and the blocking backend is used instead.
+//!
+//! Backends only ever see files that were already opened (and therefore
+//! permission checked) by the caller, and report errors as `FsError::Io` with
+//! the same OS error codes as the blocking backend.
+
+#![allow(clippy::disallowed_methods)]
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use deno_core::unsync::spawn_blocking;
+use deno_io::fs::FsError;
+use deno_io::fs::FsResult;
+use deno_io::fs::FsStat;
+
+pub const IO_BACKEND_ENV_VAR_NAME: &str = "DENO_FS_IO_BACKEND";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IoBackendKind {
+  #[default]
+  Blocking,
+  IoUring,
+  Overlapped,
+}
+
+impl FromStr for IoBackendKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "blocking" => Ok(Self::Blocking),
+      "io_uring" | "uring" => Ok(Self::IoUring),
+      "overlapped" | "iocp" => Ok(Self::Overlapped),
+      _ => Err(format!(
+        "Unknown IO backend '{s}', expected one of: blocking, io_uring, overlapped"
+      )),
+    }
+  }
+}
+
+#[async_trait::async_trait(?Send)]
+pub trait IoBackend: std::fmt::Debug + Send + Sync {
+  /// The backend that actually serves requests, after any fallback.
+  fn kind(&self) -> IoBackendKind;
+
+  /// Read the remaining contents of an already opened file.
+  async fn read_file(&self, file: std::fs::File) -> FsResult<Vec<u8>>;
+
+  /// Query metadata for `path`, following symlinks if `follow_symlinks` is
+  /// set (`stat`) or describing the link itself otherwise (`lstat`).
+  async fn metadata(
+    &self,
+    path: PathBuf,
+    follow_symlinks: bool,
+  ) -> FsResult<FsStat>;
+}
+
+/// Backend that runs every request on the blocking thread pool.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockingIoBackend;
+
+#[async_trait::async_trait(?Send)]
+impl IoBackend for BlockingIoBackend {
+  fn kind(&self) -> IoBackendKind {
+    IoBackendKind::Blocking
+  }
+
+  async fn read_file(&self, mut file: std::fs::File) -> FsResult<Vec<u8>> {
+    spawn_blocking(move || {
+      let mut buf = Vec::new();
+      file.read_to_end(&mut buf)?;
+      Ok::<_, FsError>(buf)
+    })
+    .await?
+  }
+
+  async fn metadata(
+    &self,
+    path: PathBuf,
+    follow_symlinks: bool,
+  ) -> FsResult<FsStat> {
+    spawn_blocking(move || {
+      if follow_symlinks {
+        crate::std_fs::stat(&path)
+      } else {
+        crate::std_fs::lstat(&path)
+      }
+    })
+    .await?
+  }
+}
+
+/// Returns the IO backend for this process, selecting it on first use.
+pub fn io_backend() -> &'static dyn IoBackend {
+  static BACKEND: OnceLock<Box<dyn IoBackend>> = OnceLock::new();
+  BACKEND
+    .get_or_init(|| {
+      let kind = match std::env::var(IO_BACKEND_ENV_VAR_NAME) {
+        Ok(value) => value.parse().unwrap_or_else(|err| {
+          log::warn!(
+            "Ignoring {IO_BACKEND_ENV_VAR_NAME}: {err}. Using the blocking backend."
+          );
+          IoBackendKind::default()
+        }),
+        Err(_) => IoBackendKind::default(),
+      };
+      select_io_backend(kind)
+    })
+    .as_ref()
+}
+
+/// Create the requested backend, falling back to the blocking backend with a
+/// warning if it isn't available in this build or on this kernel.
+pub fn select_io_backend(kind: IoBackendKind) -> Box<dyn IoBackend> {
+  let (name, reason) = match kind {
+    IoBackendKind::Blocking => return Box::new(BlockingIoBackend),
+    IoBackendKind::IoUring => {
+      #[cfg(all(target_os = "linux", feature = "io_uring"))]
+      let reason = match uring::UringIoBackend::new(uring::RING_ENTRIES) {
+        Ok(backend) => return Box::new(backend),
+        Err(err) => err.to_string(),
+      };
+      #[cfg(not(all(target_os = "linux", feature = "io_uring")))]
+      let reason = "not included in this build".to_string();
+      ("io_uring", reason)
+    }
+    IoBackendKind::Overlapped => {
+      #[cfg(all(windows, feature = "overlapped"))]
+      let reason = match overlapped::OverlappedIoBackend::new() {
+        Ok(backend) => return Box::new(backend),
+        Err(err) => err.to_string(),
+      };
+      #[cfg(not(all(windows, feature = "overlapped")))]
+      let reason = "not included in this build".to_string();
+      ("overlapped", reason)
+    }
+  };
+  log::warn!(
+    "The {name} IO backend is unavailable ({reason}). Using the blocking backend."
+  );
+  Box::new(BlockingIoBackend)
+}
+
+/// Size of the reads issued when the size of a file isn't known upfront.
+#[cfg(any(
+  all(target_os = "linux", feature = "io_uring"),
+  all(windows, feature = "overlapped")
+))]
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[cfg(any(
+  all(target_os = "linux", feature = "io_uring"),
+  all(windows, feature = "overlapped")
+))]
+fn initial_read_capacity(file: &std::fs::File) -> usize {
+  // Reserve one extra byte so that the final, empty read that detects EOF
+  // doesn't force a reallocation.
+  file
+    .metadata()
+    .map(|m| m.len() as usize + 1)
+    .unwrap_or(READ_CHUNK_SIZE)
+    .max(1)
+}
+
+#[cfg(all(target_os = "linux", feature = "io_uring"))]
+mod uring {
+  use std::collections::HashMap;
+  use std::ffi::CString;
+  use std::io;
+  use std::os::unix::ffi::OsStrExt;
+  use std::os::unix::io::AsRawFd;
+  use std::path::PathBuf;
+  use std::sync::mpsc;
+
+  use deno_core::futures::channel::oneshot;
+  use deno_io::fs::FsResult;
+  use deno_io::fs::FsStat;
+  use io_uring::opcode;
+  use io_uring::types;
+  use io_uring::IoUring;
+  use io_uring::Probe;
+
+  use super::initial_read_capacity;
+  use super::IoBackend;
+  use super::IoBackendKind;
+  use super::READ_CHUNK_SIZE;
+
+  pub const RING_ENTRIES: u32 = 256;
+
+  enum Request {
+    ReadFile {
+      file: std::fs::File,
+      reply: oneshot::Sender<io::Result<Vec<u8>>>,
+    },
+    Metadata {
+      path: CString,
+      follow_symlinks: bool,
+      reply: oneshot::Sender<io::Result<FsStat>>,
+    },
+  }
+
+  enum InFlight {
+    Read {
+      file: std::fs::File,
+      buf: Vec<u8>,
+      reply: oneshot::Sender<io::Result<Vec<u8>>>,
+    },
+    Statx {
+      // Kept alive until the kernel is done with the pointer.
+      path: CString,
+      follow_symlinks: bool,
+      buf: Box<libc::statx>,
+      reply: oneshot::Sender<io::Result<FsStat>>,
+    },
+  }
+
+  /// Backend that submits reads and `statx` calls to an io_uring instance
+  /// owned by a dedicated driver thread.
+  #[derive(Debug)]
+  pub struct UringIoBackend {
+    sender: mpsc::Sender<Request>,
+  }
+
+  impl UringIoBackend {
+    pub fn new(entries: u32) -> io::Result<Self> {
+      let ring = IoUring::new(entries)?;
+      let mut probe = Probe::new();
+      ring.submitter().register_probe(&mut probe)?;
+      if !probe.is_supported(opcode::Read::CODE)
+        || !probe.is_supported(opcode::Statx::CODE)
+      {
+        return Err(io::ErrorKind::Unsupported.into());
+      }
+      let (sender, receiver) = mpsc::channel();
+      std::thread::Builder::new()
+        .name("deno-fs-io-uring".to_string())
+        .spawn(move || drive(ring, receiver))?;
+      Ok(Self { sender })
+    }
+  }
+
+  #[async_trait::async_trait(?Send)]
+  impl IoBackend for UringIoBackend {
+    fn kind(&self) -> IoBackendKind {
+      IoBackendKind::IoUring
+    }
+
+    async fn read_file(&self, file: std::fs::File) -> FsResult<Vec<u8>> {
+      let (reply, rx) = oneshot::channel();
+      self
+        .sender
+        .send(Request::ReadFile { file, reply })
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+      let buf = rx
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))??;
+      Ok(buf)
+    }
+
+    async fn metadata(
+      &self,
+      path: PathBuf,
+      follow_symlinks: bool,
+    ) -> FsResult<FsStat> {
+      let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+      let (reply, rx) = oneshot::channel();
+      self
+        .sender
+        .send(Request::Metadata {
+          path,
+          follow_symlinks,
+          reply,
+        })
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+      let stat = rx
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))??;
+      Ok(stat)
+    }
+  }
+
+  /// Driver loop. Blocks on the request channel while idle; otherwise picks
+  /// up every queued request, submits them and waits for a completion. New
+  /// requests are picked up after the next completion is reaped.
+  fn drive(mut ring: IoUring, receiver: mpsc::Receiver<Request>) {
+    let mut in_flight: HashMap<u64, InFlight> = HashMap::new();
+    let mut next_id: u64 = 0;
+
+    loop {
+      if in_flight.is_empty() {
+        match receiver.recv() {
+          Ok(request) => {
+            start(&mut ring, &mut in_flight, &mut next_id, request)
+          }
+          // All backend handles are gone.
+          Err(_) => return,
+        }
+      }
+      while let Ok(request) = receiver.try_recv() {
+        start(&mut ring, &mut in_flight, &mut next_id, request);
+      }
+      if in_flight.is_empty() {
+        continue;
+      }
+
+      if let Err(err) = ring.submit_and_wait(1) {
+        if err.kind() == io::ErrorKind::Interrupted {
+          continue;
+        }
+        // The ring is unusable, fail everything that is still pending.
+        let code = err.raw_os_error().unwrap_or(libc::EIO);
+        for (_, op) in in_flight.drain() {
+          fail(op, io::Error::from_raw_os_error(code));
+        }
+        continue;
+      }
+
+      let completions = ring
+        .completion()
+        .map(|cqe| (cqe.user_data(), cqe.result()))
+        .collect::<Vec<_>>();
+      for (id, result) in completions {
+        let Some(op) = in_flight.remove(&id) else {
+          continue;
+        };
+        complete(&mut ring, &mut in_flight, id, op, result);
+      }
+    }
+  }
+
+  fn start(
+    ring: &mut IoUring,
+    in_flight: &mut HashMap<u64, InFlight>,
+    next_id: &mut u64,
+    request: Request,
+  ) {
+    let id = *next_id;
+    *next_id = next_id.wrapping_add(1);
+    let op = match request {
+      Request::ReadFile { file, reply } => {
+        let buf = Vec::with_capacity(initial_read_capacity(&file));
+        InFlight::Read { file, buf, reply }
+      }
+      Request::Metadata {
+        path,
+        follow_symlinks,
+        reply,
+      } => InFlight::Statx {
+        path,
+        follow_symlinks,
+        // SAFETY: `statx` is a plain C struct, all zeroes is a valid value.
+        buf: Box::new(unsafe { std::mem::zeroed() }),
+        reply,
+      },
+    };
+    submit(ring, in_flight, id, op);
+  }
+
+  fn submit(
+    ring: &mut IoUring,
+    in_flight: &mut HashMap<u64, InFlight>,
+    id: u64,
+    mut op: InFlight,
+  ) {
+    let entry = match &mut op {
+      InFlight::Read { file, buf, .. } => {
+        if buf.capacity() == buf.len() {
+          buf.reserve(READ_CHUNK_SIZE);
+        }
+        let spare = (buf.capacity() - buf.len()).min(u32::MAX as usize);
+        // SAFETY: the pointer stays valid until the completion is reaped,
+        // the Vec is owned by the in flight entry and isn't touched before.
+        let ptr = unsafe { buf.as_mut_ptr().add(buf.len()) };
+        opcode::Read::new(types::Fd(file.as_raw_fd()), ptr, spare as u32)
+          .offset(buf.len() as u64)
+          .build()
+          .user_data(id)
+      }
+      InFlight::Statx {
+        path,
+        follow_symlinks,
+        buf,
+        ..
+      } => {
+        let mut flags = libc::AT_STATX_SYNC_AS_STAT;
+        if !*follow_symlinks {
+          flags |= libc::AT_SYMLINK_NOFOLLOW;
+        }
+        opcode::Statx::new(
+          types::Fd(libc::AT_FDCWD),
+          path.as_ptr(),
+          buf.as_mut() as *mut libc::statx as *mut types::statx,
+        )
+        .flags(flags)
+        .mask(libc::STATX_ALL)
+        .build()
+        .user_data(id)
+      }
+    };
+
+    loop {
+      // SAFETY: the buffers referenced by `entry` are owned by `op`, which is
+      // stored in `in_flight` until the completion is reaped.
+      let pushed = unsafe { ring.submission().push(&entry).is_ok() };
+      if pushed {
+        break;
+      }
+      // Submission queue is full, hand the queued entries to the kernel.
+      if let Err(err) = ring.submit() {
+        fail(op, err);
+        return;
+      }
+    }
+    in_flight.insert(id, op);
+  }
+
+  fn complete(
+    ring: &mut IoUring,
+    in_flight: &mut HashMap<u64, InFlight>,
+    id: u64,
+    op: InFlight,
+    result: i32,
+  ) {
+    if result < 0 {
+      fail(op, io::Error::from_raw_os_error(-result));
+      return;
+    }
+    match op {
+      InFlight::Read {
+        file,
+        mut buf,
+        reply,
+      } => {
+        if result == 0 {
+          let _ = reply.send(Ok(buf));
+          return;
+        }
+        // SAFETY: the kernel initialized `result` bytes past the old length.
+        unsafe { buf.set_len(buf.len() + result as usize) };
+        submit(ring, in_flight, id, InFlight::Read { file, buf, reply });
+      }
+      InFlight::Statx { buf, reply, .. } => {
+        let _ = reply.send(Ok(fs_stat_from_statx(&buf)));
+      }
+    }
+  }
+
+  fn fail(op: InFlight, err: io::Error) {
+    match op {
+      InFlight::Read { reply, .. } => {
+        let _ = reply.send(Err(err));
+      }
+      InFlight::Statx { reply, .. } => {
+        let _ = reply.send(Err(err));
+      }
+    }
+  }
+
+  /// Mirrors `FsStat::from_std`, which is backed by `statx` as well.
+  fn fs_stat_from_statx(stx: &libc::statx) -> FsStat {
+    fn to_msec(ts: &libc::statx_timestamp) -> u64 {
+      (ts.tv_sec as u64)
+        .wrapping_mul(1000)
+        .wrapping_add(ts.tv_nsec as u64 / 1_000_000)
+    }
+    let mode = stx.stx_mode as u32;
+    let file_type = mode & libc::S_IFMT;
+    FsStat {
+      is_file: file_type == libc::S_IFREG,
+      is_directory: file_type == libc::S_IFDIR,
+      is_symlink: file_type == libc::S_IFLNK,
+      size: stx.stx_size,
+      mtime: Some(to_msec(&stx.stx_mtime)),
+      atime: Some(to_msec(&stx.stx_atime)),
+      birthtime: (stx.stx_mask & libc::STATX_BTIME != 0)
+        .then(|| to_msec(&stx.stx_btime)),
+      dev: libc::makedev(stx.stx_dev_major, stx.stx_dev_minor),
+      ino: stx.stx_ino,
+      mode,
+      nlink: stx.stx_nlink as u64,
+      uid: stx.stx_uid,
+      gid: stx.stx_gid,
+      rdev: libc::makedev(stx.stx_rdev_major, stx.stx_rdev_minor),
+      blksize: stx.stx_blksize as u64,
+      blocks: stx.stx_blocks,
+      is_block_device: file_type == libc::S_IFBLK,
+      is_char_device: file_type == libc::S_IFCHR,
+      is_fifo: file_type == libc::S_IFIFO,
+      is_socket: file_type == libc::S_IFSOCK,
+    }
+  }
+}
+
+#[cfg(all(windows, feature = "overlapped"))]
+mod overlapped {
+  use std::collections::HashMap;
+  use std::io;
+  use std::os::windows::io::AsRawHandle;
+  use std::path::PathBuf;
+  use std::sync::mpsc;
+
+  use deno_core::futures::channel::oneshot;
+  use deno_io::fs::FsResult;
+  use deno_io::fs::FsStat;
+  use windows_sys::Win32::Foundation::CloseHandle;
+  use windows_sys::Win32::Foundation::GetLastError;
+  use windows_sys::Win32::Foundation::ERROR_HANDLE_EOF;
+  use windows_sys::Win32::Foundation::ERROR_IO_PENDING;
+  use windows_sys::Win32::Foundation::HANDLE;
+  use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+  use windows_sys::Win32::Storage::FileSystem::ReOpenFile;
+  use windows_sys::Win32::Storage::FileSystem::ReadFile;
+  use windows_sys::Win32::Storage::FileSystem::FILE_FLAG_OVERLAPPED;
+  use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_READ;
+  use windows_sys::Win32::Storage::FileSystem::FILE_SHARE_DELETE;
+  use windows_sys::Win32::Storage::FileSystem::FILE_SHARE_READ;
+  use windows_sys::Win32::Storage::FileSystem::FILE_SHARE_WRITE;
+  use windows_sys::Win32::System::IO::CreateIoCompletionPort;
+  use windows_sys::Win32::System::IO::GetQueuedCompletionStatus;
+  use windows_sys::Win32::System::IO::PostQueuedCompletionStatus;
+  use windows_sys::Win32::System::IO::OVERLAPPED;
+
+  use super::initial_read_capacity;
+  use super::BlockingIoBackend;
+  use super::IoBackend;
+  use super::IoBackendKind;
+  use super::READ_CHUNK_SIZE;
+
+  /// Completion key used to wake up the driver for new requests.
+  const SUBMIT_KEY: usize = 0;
+  /// Completion key of overlapped reads.
+  const READ_KEY: usize = 1;
+  const INFINITE: u32 = u32::MAX;
+
+  struct ReadRequest {
+    file: std::fs::File,
+    reply: oneshot::Sender<io::Result<Vec<u8>>>,
+  }
+
+  /// State of an overlapped read. `overlapped` must stay the first field so
+  /// that the `OVERLAPPED` pointer returned by the completion port can be
+  /// mapped back to the read.
+  #[repr(C)]
+  struct InFlightRead {
+    overlapped: OVERLAPPED,
+    handle: HANDLE,
+    // Keeps the original handle (and its permission checks) alive.
+    _file: std::fs::File,
+    buf: Vec<u8>,
+    reply: oneshot::Sender<io::Result<Vec<u8>>>,
+  }
+
+  impl Drop for InFlightRead {
+    fn drop(&mut self) {
+      // SAFETY: `handle` was returned by `ReOpenFile` and is owned by us.
+      unsafe { CloseHandle(self.handle) };
+    }
+  }
+
+  /// Backend that issues overlapped reads from a driver thread blocked on an
+  /// IO completion port. Metadata queries use the blocking backend, as
+  /// `stat` on Windows needs several synchronous calls anyway.
+  #[derive(Debug)]
+  pub struct OverlappedIoBackend {
+    port: usize,
+    sender: mpsc::Sender<ReadRequest>,
+  }
+
+  impl OverlappedIoBackend {
+    pub fn new() -> io::Result<Self> {
+      // SAFETY: creating a new completion port not associated with a file.
+      let port =
+        unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, 0, 0, 1) };
+      if port == 0 {
+        return Err(io::Error::last_os_error());
+      }
+      let (sender, receiver) = mpsc::channel();
+      let driver_port = port as usize;
+      std::thread::Builder::new()
+        .name("deno-fs-overlapped".to_string())
+        .spawn(move || drive(driver_port as HANDLE, receiver))?;
+      Ok(Self {
+        port: port as usize,
+        sender,
+      })
+    }
+  }
+
+  #[async_trait::async_trait(?Send)]
+  impl IoBackend for OverlappedIoBackend {
+    fn kind(&self) -> IoBackendKind {
+      IoBackendKind::Overlapped
+    }
+
+    async fn read_file(&self, file: std::fs::File) -> FsResult<Vec<u8>> {
+      let (reply, rx) = oneshot::channel();
+      self
+        .sender
+        .send(ReadRequest { file, reply })
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+      // SAFETY: `port` is a valid completion port for the process lifetime.
+      let posted = unsafe {
+        PostQueuedCompletionStatus(
+          self.port as HANDLE,
+          0,
+          SUBMIT_KEY,
+          std::ptr::null_mut(),
+        )
+      };
+      if posted == 0 {
+        return Err(io::Error::last_os_error().into());
+      }
+      let buf = rx
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))??;
+      Ok(buf)
+    }
+
+    async fn metadata(
+      &self,
+      path: PathBuf,
+      follow_symlinks: bool,
+    ) -> FsResult<FsStat> {
+      BlockingIoBackend.metadata(path, follow_symlinks).await
+    }
+  }
+
+  fn drive(port: HANDLE, receiver: mpsc::Receiver<ReadRequest>) {
+    let mut in_flight: HashMap<usize, Box<InFlightRead>> = HashMap::new();
+    loop {
+      let mut transferred = 0u32;
+      let mut key = 0usize;
+      let mut overlapped: *mut OVERLAPPED = std::ptr::null_mut();
+      // SAFETY: all out pointers are valid for the duration of the call.
+      let ok = unsafe {
+        GetQueuedCompletionStatus(
+          port,
+          &mut transferred,
+          &mut key,
+          &mut overlapped,
+          INFINITE,
+        )
+      };
+      if overlapped.is_null() {
+        if ok == 0 {
+          // The port itself failed, nothing we can do.
+          return;
+        }
+        if key == SUBMIT_KEY {
+          while let Ok(request) = receiver.try_recv() {
+            start(port, &mut in_flight, request);
+          }
+        }
+        continue;
+      }
+      debug_assert_eq!(key, READ_KEY);
+      let Some(mut read) = in_flight.remove(&(overlapped as usize)) else {
+        continue;
+      };
+      if ok == 0 {
+        // SAFETY: trivially safe.
+        let err = unsafe { GetLastError() };
+        if err == ERROR_HANDLE_EOF {
+          let _ = read.reply.send(Ok(std::mem::take(&mut read.buf)));
+        } else {
+          let _ = read
+            .reply
+            .send(Err(io::Error::from_raw_os_error(err as i32)));
+        }
+        continue;
+      }
+      if transferred == 0 {
+        let _ = read.reply.send(Ok(std::mem::take(&mut read.buf)));
+        continue;
+      }
+      // SAFETY: the kernel initialized `transferred` bytes past the length.
+      unsafe { read.buf.set_len(read.buf.len() + transferred as usize) };
+      issue_read(&mut in_flight, read);
+    }
+  }
+
+  fn start(
+    port: HANDLE,
+    in_flight: &mut HashMap<usize, Box<InFlightRead>>,
+    request: ReadRequest,
+  ) {
+    let ReadRequest { file, reply } = request;
+    // SAFETY: reopening a valid handle owned by `file`.
+    let handle = unsafe {
+      ReOpenFile(
+        file.as_raw_handle() as HANDLE,
+        FILE_GENERIC_READ,
+        FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+        FILE_FLAG_OVERLAPPED,
+      )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+      let _ = reply.send(Err(io::Error::last_os_error()));
+      return;
+    }
+    // SAFETY: associating a valid overlapped handle with our port.
+    if unsafe { CreateIoCompletionPort(handle, port, READ_KEY, 0) } == 0 {
+      let err = io::Error::last_os_error();
+      // SAFETY: `handle` is owned by us and not used afterwards.
+      unsafe { CloseHandle(handle) };
+      let _ = reply.send(Err(err));
+      return;
+    }
+    let buf = Vec::with_capacity(initial_read_capacity(&file));
+    let read = Box::new(InFlightRead {
+      // SAFETY: `OVERLAPPED` is a plain C struct, zeroed is its initial state.
+      overlapped: unsafe { std::mem::zeroed() },
+      handle,
+      _file: file,
+      buf,
+      reply,
+    });
+    issue_read(in_flight, read);
+  }
+
+  fn issue_read(
+    in_flight: &mut HashMap<usize, Box<InFlightRead>>,
+    mut read: Box<InFlightRead>,
+  ) {
+    if read.buf.capacity() == read.buf.len() {
+      read.buf.reserve(READ_CHUNK_SIZE);
+    }
+    let offset = read.buf.len() as u64;
+    // SAFETY: `OVERLAPPED_0_0` is plain old data.
+    read.overlapped = unsafe { std::mem::zeroed() };
+    read.overlapped.Anonymous.Anonymous.Offset = offset as u32;
+    read.overlapped.Anonymous.Anonymous.OffsetHigh = (offset >> 32) as u32;
+    let spare = (read.buf.capacity() - read.buf.len()).min(u32::MAX as usize);
+    let overlapped_ptr = &mut read.overlapped as *mut OVERLAPPED;
+    // SAFETY: the buffer and `OVERLAPPED` live in the boxed in flight entry,
+    // which is kept alive until the completion is dequeued.
+    let ok = unsafe {
+      ReadFile(
+        read.handle,
+        read.buf.as_mut_ptr().add(read.buf.len()),
+        spare as u32,
+        std::ptr::null_mut(),
+        overlapped_ptr,
+      )
+    };
+    if ok == 0 {
+      // SAFETY: trivially safe.
+      let err = unsafe { GetLastError() };
+      if err == ERROR_HANDLE_EOF {
+        let _ = read.reply.send(Ok(std::mem::take(&mut read.buf)));
+        return;
+      }
+      if err != ERROR_IO_PENDING {
+        let _ = read
+          .reply
+          .send(Err(io::Error::from_raw_os_error(err as i32)));
+        return;
+      }
+    }
+    // A completion packet is queued even if the read finished synchronously.
+    in_flight.insert(overlapped_ptr as usize, read);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_backend_kind() {
+    assert_eq!(
+      "blocking".parse::<IoBackendKind>().unwrap(),
+      IoBackendKind::Blocking
+    );
+    assert_eq!(
+      "io_uring".parse::<IoBackendKind>().unwrap(),
+      IoBackendKind::IoUring
+    );
+    assert_eq!(
+      "overlapped".parse::<IoBackendKind>().unwrap(),
+      IoBackendKind::Overlapped
+    );
+    assert!("epoll".parse::<IoBackendKind>().is_err());
+  }
+
+  #[test]
+  fn unavailable_backends_fall_back_to_blocking() {
+    let io_uring = select_io_backend(IoBackendKind::IoUring);
+    if cfg!(not(all(target_os = "linux", feature = "io_uring"))) {
+      assert_eq!(io_uring.kind(), IoBackendKind::Blocking);
+    }
+    let overlapped = select_io_backend(IoBackendKind::Overlapped);
+    if cfg!(not(all(windows, feature = "overlapped"))) {
+      assert_eq!(overlapped.kind(), IoBackendKind::Blocking);
+    }
+  }
+
+  #[cfg(all(target_os = "linux", feature = "io_uring"))]
+  #[test]
+  fn io_uring_backend_matches_blocking_backend() {
+    use deno_core::futures::executor::block_on;
+    use std::io::Write;
+
+    // A ring that can't be created exercises the fallback path on kernels
+    // without io_uring support.
+    assert!(uring::UringIoBackend::new(0).is_err());
+
+    let Ok(backend) = uring::UringIoBackend::new(8) else {
+      // Kernel without io_uring, the fallback is covered above.
+      return;
+    };
+    let dir = std::env::temp_dir()
+      .join(format!("deno_fs_io_uring_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("data.bin");
+    let data = (0..(READ_CHUNK_SIZE * 3 + 17))
+      .map(|i| (i % 251) as u8)
+      .collect::<Vec<_>>();
+    std::fs::File::create(&path)
+      .unwrap()
+      .write_all(&data)
+      .unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+    assert_eq!(block_on(backend.read_file(file)).unwrap(), data);
+
+    let stat = block_on(backend.metadata(path.clone(), true)).unwrap();
+    let expected = crate::std_fs::stat(&path).unwrap();
+    assert_eq!(stat.size, expected.size);
+    assert_eq!(stat.ino, expected.ino);
+    assert_eq!(stat.mode, expected.mode);
+    assert_eq!(stat.mtime, expected.mtime);
+    assert!(stat.is_file);
+
+    let missing = dir.join("missing");
+    let err = block_on(backend.metadata(missing, true)).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+    match err {
+      FsError::Io(err) => assert_eq!(err.kind(), std::io::ErrorKind::NotFound),
+      err => panic!("unexpected error: {err}"),
+    }
+  }
+}
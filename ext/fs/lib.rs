@@ -1,12 +1,35 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+mod atomic_write;
+mod glob_matcher;
+mod hash_tree;
 mod in_memory_fs;
+mod integrity;
 mod interface;
+mod io_backend;
+mod limits;
+mod log_file;
+mod mmap;
+mod ndjson;
 mod ops;
+mod path;
 mod std_fs;
 pub mod sync;
+mod temp;
+mod text_search;
+mod tree_ops;
+mod walk;
+mod walk_stream;
 
 pub use crate::in_memory_fs::InMemoryFs;
+pub use crate::integrity::create_integrity_manifest;
+pub use crate::integrity::verify_integrity_manifest;
+pub use crate::integrity::IntegrityAlgorithm;
+pub use crate::integrity::IntegrityManifest;
+pub use crate::integrity::IntegrityManifestOptions;
+pub use crate::integrity::IntegrityReport;
+pub use crate::integrity::VerifyIntegrityManifestOptions;
+pub use crate::integrity::INTEGRITY_MANIFEST_VERSION;
 pub use crate::interface::AccessCheckCb;
 pub use crate::interface::AccessCheckFn;
 pub use crate::interface::FileSystem;
@@ -14,16 +37,59 @@ pub use crate::interface::FileSystemRc;
 pub use crate::interface::FsDirEntry;
 pub use crate::interface::FsFileType;
 pub use crate::interface::OpenOptions;
+pub use crate::io_backend::io_backend;
+pub use crate::io_backend::select_io_backend;
+pub use crate::io_backend::BlockingIoBackend;
+pub use crate::io_backend::IoBackend;
+pub use crate::io_backend::IoBackendKind;
+pub use crate::io_backend::IO_BACKEND_ENV_VAR_NAME;
+pub use crate::limits::FsLimitExceeded;
+pub use crate::limits::FsLimitGuard;
+pub use crate::limits::FsLimitScopeOptions;
+pub use crate::limits::FsLimitUsage;
+pub use crate::limits::FsLimits;
+pub use crate::limits::SharedFsLimits;
+pub use crate::limits::FS_LIMITS_ENV_VAR_NAME;
+pub use crate::log_file::LogFileResource;
+pub use crate::log_file::LogFileSync;
+pub use crate::ndjson::NdjsonReaderResource;
 pub use crate::ops::FsOpsError;
 pub use crate::ops::OperationError;
+pub use crate::path::is_unc;
+pub use crate::path::is_verbatim;
+pub use crate::path::long_path;
+pub use crate::path::normalize_windows;
+pub use crate::path::relative_path;
+pub use crate::path::WindowsPathError;
+pub use crate::path::MAX_PATH;
 pub use crate::std_fs::RealFs;
 pub use crate::sync::MaybeSend;
 pub use crate::sync::MaybeSync;
+pub use crate::temp::TempDirResource;
+pub use crate::temp::TempFileResource;
+pub use crate::walk::walk_dir_recursive;
+pub use crate::walk::WalkEntryKind;
+pub use crate::walk::WalkError;
+pub use crate::walk::WalkOptions;
 
+use crate::atomic_write::*;
+use crate::glob_matcher::*;
+use crate::hash_tree::*;
+use crate::integrity::*;
+use crate::limits::*;
+use crate::log_file::*;
+use crate::mmap::*;
+use crate::ndjson::*;
 use crate::ops::*;
+use crate::path::*;
+use crate::temp::*;
+use crate::text_search::*;
+use crate::tree_ops::*;
+use crate::walk_stream::*;
 
 use deno_core::error::AnyError;
 use deno_io::fs::FsError;
+use deno_permissions::FrozenReadError;
 use std::borrow::Cow;
 use std::path::Path;
 use std::path::PathBuf;
@@ -120,7 +186,10 @@ impl FsPermissions for deno_permissions::PermissionsContainer {
     let mut path: Cow<'a, Path> = Cow::Borrowed(path);
     if read {
       let resolved_path = FsPermissions::check_read_path(self, &path, api_name)
-        .map_err(|_| FsError::NotCapable("read"))?;
+        .map_err(|err| match err.downcast::<FrozenReadError>() {
+          Ok(err) => FsError::Io(err.into()),
+          Err(_) => FsError::NotCapable("read"),
+        })?;
       if let Cow::Owned(resolved_path) = resolved_path {
         path = Cow::Owned(resolved_path);
       }
@@ -262,6 +331,8 @@ deno_core::extension!(deno_fs,
     op_fs_make_temp_file_async<P>,
     op_fs_write_file_sync<P>,
     op_fs_write_file_async<P>,
+    op_fs_write_file_atomic_sync<P>,
+    op_fs_write_file_atomic_async<P>,
     op_fs_read_file_sync<P>,
     op_fs_read_file_async<P>,
     op_fs_read_file_text_sync<P>,
@@ -284,12 +355,49 @@ deno_core::extension!(deno_fs,
     op_fs_futime_sync,
     op_fs_futime_async,
 
+    op_logfile_open<P>,
+    op_logfile_write,
+    op_logfile_flush,
+    op_fs_mmap_readonly<P>,
+    op_fs_munmap,
+    op_fs_limits_usage,
+    op_tempfile_open<P>,
+    op_tempfile_persist<P>,
+    op_tempdir_create<P>,
+    op_tempdir_persist<P>,
+    op_ndjson_reader_open<P>,
+    op_ndjson_read_batch,
+    op_ndjson_encode_batch,
+    op_fs_hash_tree<P>,
+    op_fs_integrity_manifest_create<P>,
+    op_fs_integrity_manifest_verify<P>,
+    op_fs_progress_new,
+    op_fs_progress_next,
+    op_fs_remove_tree<P>,
+    op_fs_copy_dir<P>,
+    op_fs_walk<P>,
+    op_fs_walk_next,
+    op_glob_compile,
+    op_glob_match,
+    op_glob_filter,
+    op_text_search<P>,
+    op_text_search_next,
+    op_path_realpath<P>,
+    op_path_relative<P>,
+    op_path_normalize_windows,
+    op_path_is_unc,
+    op_path_is_verbatim,
   ],
   esm = [ "30_fs.js" ],
   options = {
     fs: FileSystemRc,
+    limits: Option<SharedFsLimits>,
   },
   state = |state, options| {
+    if let Some(limits) = options.limits {
+      state.put(limits);
+    }
     state.put(options.fs);
+    deno_web::register_batchable_op::<BatchedLogFileWrite>(state);
   },
 );
@@ -0,0 +1,766 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Caps on the files a program holds open, and the bytes it reads, below
+//! configured paths.
+//!
+//! Embedders pass [`FsLimits`] to the `deno_fs` extension; the CLI parses
+//! them from the `DENO_FS_LIMITS` environment variable, a JSON array of
+//! `{ "path", "maxOpenFiles", "maxBytesRead", "hard" }` objects. Relative
+//! paths are resolved against the working directory at startup. A file counts
+//! towards every scope it is below, after resolving symlinks, using the same
+//! prefix matching as `--allow-read`. The counters are shared by all workers
+//! given the same limits.
+//!
+//! Paths are resolved before the file is opened, and `RealFs` reports the
+//! path it resolved to the access check right before opening it, so the file
+//! counted is the one opened even if a symlink changes in between.
+//!
+//! Opening a file below a scope that already has `maxOpenFiles` files open
+//! fails with `LimitExceeded`. Bytes read are only counted, unless the scope is
+//! `hard`: then reads stop at `maxBytesRead`, and a whole-file read that would
+//! go past it fails.
+//!
+//! Files opened with `Deno.open()` and whole-file reads are counted. Other APIs
+//! reading files, like `Deno.mapFile()`, are not.
+
+// The counters are shared between threads.
+#![allow(clippy::disallowed_types)]
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use deno_core::op2;
+use deno_core::parking_lot::Mutex;
+use deno_core::BufMutView;
+use deno_core::BufView;
+use deno_core::OpState;
+use deno_core::ResourceHandleFd;
+use deno_io::fs::File;
+use deno_io::fs::FsResult;
+use deno_io::fs::FsStat;
+use deno_path_util::normalize_path;
+use deno_permissions::PathQueryDescriptor;
+use deno_permissions::QueryDescriptor;
+use deno_permissions::ReadDescriptor;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::interface::AccessCheckFn;
+use crate::interface::FileSystem;
+use crate::interface::FileSystemRc;
+
+pub const FS_LIMITS_ENV_VAR_NAME: &str = "DENO_FS_LIMITS";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FsLimitScopeOptions {
+  pub path: PathBuf,
+  #[serde(default)]
+  pub max_open_files: Option<u64>,
+  #[serde(default)]
+  pub max_bytes_read: Option<u64>,
+  /// Enforce `max_bytes_read` instead of only counting.
+  #[serde(default)]
+  pub hard: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FsLimitExceeded {
+  #[error("Limit exceeded for {}: at most {max} files may be open", .scope.display())]
+  OpenFiles { scope: PathBuf, max: u64 },
+  #[error("Limit exceeded for {}: at most {max} bytes may be read", .scope.display())]
+  BytesRead { scope: PathBuf, max: u64 },
+}
+
+impl From<FsLimitExceeded> for io::Error {
+  fn from(err: FsLimitExceeded) -> Self {
+    io::Error::new(io::ErrorKind::Other, err)
+  }
+}
+
+#[derive(Debug)]
+struct Scope {
+  descriptor: ReadDescriptor,
+  max_open_files: Option<u64>,
+  max_bytes_read: Option<u64>,
+  hard: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counters {
+  open_files: u64,
+  bytes_read: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsLimitUsage {
+  path: String,
+  open_files: u64,
+  max_open_files: Option<u64>,
+  bytes_read: u64,
+  max_bytes_read: Option<u64>,
+  hard: bool,
+}
+
+/// Limits shared by the workers they are passed to.
+pub type SharedFsLimits = Arc<FsLimits>;
+
+/// Put into the `OpState` by the `deno_fs` extension if it is given limits.
+#[derive(Debug)]
+pub struct FsLimits {
+  scopes: Vec<Scope>,
+  /// Indexed like `scopes`, behind one lock so that a file is counted towards
+  /// all of its scopes or none.
+  counters: Mutex<Vec<Counters>>,
+}
+
+impl FsLimits {
+  /// Scope paths are resolved against the working directory of `fs`, and
+  /// canonicalized if they exist.
+  pub fn new(options: Vec<FsLimitScopeOptions>, fs: &dyn FileSystem) -> Self {
+    let cwd = fs.cwd().unwrap_or_default();
+    let scopes = options
+      .into_iter()
+      .map(|options| {
+        let path = normalize_path(cwd.join(&options.path));
+        let path = fs.realpath_sync(&path).unwrap_or(path);
+        Scope {
+          descriptor: ReadDescriptor(path),
+          max_open_files: options.max_open_files,
+          max_bytes_read: options.max_bytes_read,
+          hard: options.hard,
+        }
+      })
+      .collect::<Vec<_>>();
+    let counters = Mutex::new(vec![Counters::default(); scopes.len()]);
+    Self { scopes, counters }
+  }
+
+  pub fn usage(&self) -> Vec<FsLimitUsage> {
+    let counters = self.counters.lock();
+    self
+      .scopes
+      .iter()
+      .zip(counters.iter())
+      .map(|(scope, counters)| FsLimitUsage {
+        path: scope.descriptor.0.to_string_lossy().into_owned(),
+        open_files: counters.open_files,
+        max_open_files: scope.max_open_files,
+        bytes_read: counters.bytes_read,
+        max_bytes_read: scope.max_bytes_read,
+        hard: scope.hard,
+      })
+      .collect()
+  }
+
+  /// Indices of the scopes that `path`, which must be canonical, is below.
+  fn matching_scopes(&self, path: &Path) -> Vec<usize> {
+    let query = PathQueryDescriptor {
+      requested: path.to_string_lossy().into_owned(),
+      resolved: path.to_path_buf(),
+    }
+    .into_read();
+    (0..self.scopes.len())
+      .filter(|&i| query.matches_allow(&self.scopes[i].descriptor))
+      .collect()
+  }
+
+  /// Counts a file opened at the canonical `path`. `None` if it isn't below
+  /// any scope.
+  pub fn open(
+    self: &Arc<Self>,
+    path: &Path,
+  ) -> Result<Option<FsLimitGuard>, FsLimitExceeded> {
+    let scopes = self.matching_scopes(path);
+    if scopes.is_empty() {
+      return Ok(None);
+    }
+    let mut counters = self.counters.lock();
+    for &i in &scopes {
+      let scope = &self.scopes[i];
+      if let Some(max) = scope.max_open_files {
+        if counters[i].open_files >= max {
+          return Err(FsLimitExceeded::OpenFiles {
+            scope: scope.descriptor.0.clone(),
+            max,
+          });
+        }
+      }
+    }
+    for &i in &scopes {
+      counters[i].open_files += 1;
+    }
+    Ok(Some(FsLimitGuard {
+      limits: self.clone(),
+      scopes,
+    }))
+  }
+
+  /// Counts `len` bytes read from the canonical `path` in one go, failing
+  /// instead if a hard scope doesn't have that many left.
+  pub fn count_read(
+    &self,
+    path: &Path,
+    len: usize,
+  ) -> Result<(), FsLimitExceeded> {
+    self.add_bytes_read(&self.matching_scopes(path), len)
+  }
+
+  fn add_bytes_read(
+    &self,
+    scopes: &[usize],
+    len: usize,
+  ) -> Result<(), FsLimitExceeded> {
+    let len = len as u64;
+    let mut counters = self.counters.lock();
+    for &i in scopes {
+      let scope = &self.scopes[i];
+      if let (true, Some(max)) = (scope.hard, scope.max_bytes_read) {
+        if counters[i].bytes_read.saturating_add(len) > max {
+          return Err(FsLimitExceeded::BytesRead {
+            scope: scope.descriptor.0.clone(),
+            max,
+          });
+        }
+      }
+    }
+    for &i in scopes {
+      counters[i].bytes_read = counters[i].bytes_read.saturating_add(len);
+    }
+    Ok(())
+  }
+}
+
+#[op2]
+#[serde]
+pub fn op_fs_limits_usage(state: &mut OpState) -> Vec<FsLimitUsage> {
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.fsLimitUsage");
+  state_limits(state)
+    .map(|limits| limits.usage())
+    .unwrap_or_default()
+}
+
+/// Counts a file towards its scopes while it is open.
+#[derive(Debug)]
+pub struct FsLimitGuard {
+  limits: Arc<FsLimits>,
+  scopes: Vec<usize>,
+}
+
+impl FsLimitGuard {
+  /// Reserves up to `len` bytes to be read, as many as every hard scope has
+  /// left. Fails if that is none of them.
+  fn reserve(&self, len: usize) -> Result<usize, FsLimitExceeded> {
+    let mut counters = self.limits.counters.lock();
+    let mut take = len as u64;
+    for &i in &self.scopes {
+      let scope = &self.limits.scopes[i];
+      if let (true, Some(max)) = (scope.hard, scope.max_bytes_read) {
+        let left = max.saturating_sub(counters[i].bytes_read);
+        if left == 0 && len > 0 {
+          return Err(FsLimitExceeded::BytesRead {
+            scope: scope.descriptor.0.clone(),
+            max,
+          });
+        }
+        take = take.min(left);
+      }
+    }
+    for &i in &self.scopes {
+      counters[i].bytes_read += take;
+    }
+    Ok(take as usize)
+  }
+
+  /// Returns the part of a reservation that wasn't read.
+  fn release(&self, unread: usize) {
+    let mut counters = self.limits.counters.lock();
+    for &i in &self.scopes {
+      counters[i].bytes_read -= unread as u64;
+    }
+  }
+}
+
+impl Drop for FsLimitGuard {
+  fn drop(&mut self) {
+    let mut counters = self.limits.counters.lock();
+    for &i in &self.scopes {
+      counters[i].open_files -= 1;
+    }
+  }
+}
+
+/// A file counted towards the scopes it is below from before it is opened.
+pub(crate) struct OpenReservation {
+  limits: Arc<FsLimits>,
+  path: PathBuf,
+  guard: Option<FsLimitGuard>,
+}
+
+impl OpenReservation {
+  /// Reserves the file at the canonical `path`.
+  pub fn new(limits: Arc<FsLimits>, path: PathBuf) -> FsResult<Self> {
+    let guard = limits.open(&path).map_err(io::Error::from)?;
+    Ok(Self {
+      limits,
+      path,
+      guard,
+    })
+  }
+
+  /// Moves the reservation to the canonical `path` the file system is about
+  /// to open, if a symlink changed since the path was resolved.
+  pub fn move_to(&mut self, path: &Path) -> FsResult<()> {
+    if path != self.path {
+      self.guard = None;
+      self.guard = self.limits.open(path).map_err(io::Error::from)?;
+      self.path = path.to_path_buf();
+    }
+    Ok(())
+  }
+
+  /// Counts `file`, opened at the reserved path, while it is open.
+  pub fn limit(self, file: Rc<dyn File>) -> Rc<dyn File> {
+    match self.guard {
+      Some(guard) => Rc::new(LimitedFile {
+        inner: file,
+        guard: Rc::new(guard),
+      }),
+      None => file,
+    }
+  }
+}
+
+/// Counts `len` bytes read from the whole file at the canonical `path`.
+pub(crate) fn count_file_read(
+  limits: &FsLimits,
+  path: &Path,
+  len: usize,
+) -> FsResult<()> {
+  limits
+    .count_read(path, len)
+    .map_err(|err| io::Error::from(err).into())
+}
+
+/// Resolves `path` like `RealFs` does before opening it: canonicalized, or
+/// below its canonicalized parent if it doesn't exist yet.
+pub(crate) fn resolve_path_sync(fs: &dyn FileSystem, path: &Path) -> PathBuf {
+  let path = absolute_path(fs, path);
+  if let Ok(resolved) = fs.realpath_sync(&path) {
+    return resolved;
+  }
+  match (path.parent(), path.file_name()) {
+    (Some(parent), Some(name)) => match fs.realpath_sync(parent) {
+      Ok(parent) => parent.join(name),
+      Err(_) => path,
+    },
+    _ => path,
+  }
+}
+
+/// Async version of [`resolve_path_sync`].
+pub(crate) async fn resolve_path_async(
+  fs: &FileSystemRc,
+  path: &Path,
+) -> PathBuf {
+  let path = absolute_path(&**fs, path);
+  if let Ok(resolved) = fs.realpath_async(path.clone()).await {
+    return resolved;
+  }
+  match (path.parent(), path.file_name()) {
+    (Some(parent), Some(name)) => {
+      match fs.realpath_async(parent.to_path_buf()).await {
+        Ok(parent) => parent.join(name),
+        Err(_) => path,
+      }
+    }
+    _ => path,
+  }
+}
+
+fn absolute_path(fs: &dyn FileSystem, path: &Path) -> PathBuf {
+  if path.is_absolute() {
+    normalize_path(path)
+  } else {
+    normalize_path(fs.cwd().unwrap_or_default().join(path))
+  }
+}
+
+/// Wraps the access check of an open, calling `on_resolved` with the path
+/// the file system resolved, right before it opens it.
+pub(crate) fn track_resolved_path<'a, F: AccessCheckFn>(
+  access_check: &'a mut F,
+  mut on_resolved: impl FnMut(&Path) -> FsResult<()> + 'a,
+) -> impl AccessCheckFn + 'a {
+  move |resolved, path, options| {
+    let path = access_check(resolved, path, options)?;
+    if resolved {
+      on_resolved(&path)?;
+    }
+    Ok(path)
+  }
+}
+
+pub(crate) fn state_limits(state: &OpState) -> Option<Arc<FsLimits>> {
+  state.try_borrow::<Arc<FsLimits>>().cloned()
+}
+
+/// A file below at least one scope. Clones share the guard, and count as one
+/// open file.
+struct LimitedFile {
+  inner: Rc<dyn File>,
+  guard: Rc<FsLimitGuard>,
+}
+
+impl LimitedFile {
+  fn count_read(&self, len: usize) -> FsResult<()> {
+    self
+      .guard
+      .limits
+      .add_bytes_read(&self.guard.scopes, len)
+      .map_err(|err| io::Error::from(err).into())
+  }
+}
+
+#[async_trait::async_trait(?Send)]
+impl File for LimitedFile {
+  fn read_sync(self: Rc<Self>, buf: &mut [u8]) -> FsResult<usize> {
+    let take = self.guard.reserve(buf.len()).map_err(io::Error::from)?;
+    let result = self.inner.clone().read_sync(&mut buf[..take]);
+    self.guard.release(take - *result.as_ref().unwrap_or(&0));
+    result
+  }
+  async fn read(self: Rc<Self>, limit: usize) -> FsResult<BufView> {
+    let take = self.guard.reserve(limit).map_err(io::Error::from)?;
+    let result = self.inner.clone().read(take).await;
+    self
+      .guard
+      .release(take - result.as_ref().map(|buf| buf.len()).unwrap_or(0));
+    result
+  }
+  async fn read_byob(
+    self: Rc<Self>,
+    mut buf: BufMutView,
+  ) -> FsResult<(usize, BufMutView)> {
+    let take = self.guard.reserve(buf.len()).map_err(io::Error::from)?;
+    let result = if take < buf.len() {
+      match self.inner.clone().read_byob(BufMutView::new(take)).await {
+        Ok((nread, partial)) => {
+          buf[..nread].copy_from_slice(&partial[..nread]);
+          Ok((nread, buf))
+        }
+        Err(err) => Err(err),
+      }
+    } else {
+      self.inner.clone().read_byob(buf).await
+    };
+    self
+      .guard
+      .release(take - result.as_ref().map(|(nread, _)| *nread).unwrap_or(0));
+    result
+  }
+
+  fn write_sync(self: Rc<Self>, buf: &[u8]) -> FsResult<usize> {
+    self.inner.clone().write_sync(buf)
+  }
+  async fn write(
+    self: Rc<Self>,
+    buf: BufView,
+  ) -> FsResult<deno_core::WriteOutcome> {
+    self.inner.clone().write(buf).await
+  }
+
+  fn write_all_sync(self: Rc<Self>, buf: &[u8]) -> FsResult<()> {
+    self.inner.clone().write_all_sync(buf)
+  }
+  async fn write_all(self: Rc<Self>, buf: BufView) -> FsResult<()> {
+    self.inner.clone().write_all(buf).await
+  }
+
+  fn read_all_sync(self: Rc<Self>) -> FsResult<Vec<u8>> {
+    let buf = self.inner.clone().read_all_sync()?;
+    self.count_read(buf.len())?;
+    Ok(buf)
+  }
+  async fn read_all_async(self: Rc<Self>) -> FsResult<Vec<u8>> {
+    let buf = self.inner.clone().read_all_async().await?;
+    self.count_read(buf.len())?;
+    Ok(buf)
+  }
+
+  fn chmod_sync(self: Rc<Self>, pathmode: u32) -> FsResult<()> {
+    self.inner.clone().chmod_sync(pathmode)
+  }
+  async fn chmod_async(self: Rc<Self>, mode: u32) -> FsResult<()> {
+    self.inner.clone().chmod_async(mode).await
+  }
+
+  fn seek_sync(self: Rc<Self>, pos: io::SeekFrom) -> FsResult<u64> {
+    self.inner.clone().seek_sync(pos)
+  }
+  async fn seek_async(self: Rc<Self>, pos: io::SeekFrom) -> FsResult<u64> {
+    self.inner.clone().seek_async(pos).await
+  }
+
+  fn datasync_sync(self: Rc<Self>) -> FsResult<()> {
+    self.inner.clone().datasync_sync()
+  }
+  async fn datasync_async(self: Rc<Self>) -> FsResult<()> {
+    self.inner.clone().datasync_async().await
+  }
+
+  fn sync_sync(self: Rc<Self>) -> FsResult<()> {
+    self.inner.clone().sync_sync()
+  }
+  async fn sync_async(self: Rc<Self>) -> FsResult<()> {
+    self.inner.clone().sync_async().await
+  }
+
+  fn stat_sync(self: Rc<Self>) -> FsResult<FsStat> {
+    self.inner.clone().stat_sync()
+  }
+  async fn stat_async(self: Rc<Self>) -> FsResult<FsStat> {
+    self.inner.clone().stat_async().await
+  }
+
+  fn lock_sync(self: Rc<Self>, exclusive: bool) -> FsResult<()> {
+    self.inner.clone().lock_sync(exclusive)
+  }
+  async fn lock_async(self: Rc<Self>, exclusive: bool) -> FsResult<()> {
+    self.inner.clone().lock_async(exclusive).await
+  }
+
+  fn unlock_sync(self: Rc<Self>) -> FsResult<()> {
+    self.inner.clone().unlock_sync()
+  }
+  async fn unlock_async(self: Rc<Self>) -> FsResult<()> {
+    self.inner.clone().unlock_async().await
+  }
+
+  fn truncate_sync(self: Rc<Self>, len: u64) -> FsResult<()> {
+    self.inner.clone().truncate_sync(len)
+  }
+  async fn truncate_async(self: Rc<Self>, len: u64) -> FsResult<()> {
+    self.inner.clone().truncate_async(len).await
+  }
+
+  fn utime_sync(
+    self: Rc<Self>,
+    atime_secs: i64,
+    atime_nanos: u32,
+    mtime_secs: i64,
+    mtime_nanos: u32,
+  ) -> FsResult<()> {
+    self.inner.clone().utime_sync(
+      atime_secs,
+      atime_nanos,
+      mtime_secs,
+      mtime_nanos,
+    )
+  }
+  async fn utime_async(
+    self: Rc<Self>,
+    atime_secs: i64,
+    atime_nanos: u32,
+    mtime_secs: i64,
+    mtime_nanos: u32,
+  ) -> FsResult<()> {
+    self
+      .inner
+      .clone()
+      .utime_async(atime_secs, atime_nanos, mtime_secs, mtime_nanos)
+      .await
+  }
+
+  fn as_stdio(self: Rc<Self>) -> FsResult<std::process::Stdio> {
+    self.inner.clone().as_stdio()
+  }
+  fn backing_fd(self: Rc<Self>) -> Option<ResourceHandleFd> {
+    self.inner.clone().backing_fd()
+  }
+  fn try_clone_inner(self: Rc<Self>) -> FsResult<Rc<dyn File>> {
+    Ok(Rc::new(LimitedFile {
+      inner: self.inner.clone().try_clone_inner()?,
+      guard: self.guard.clone(),
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use std::borrow::Cow;
+
+  use super::*;
+  use crate::OpenOptions;
+  use crate::RealFs;
+
+  struct Fixture {
+    _dir: tempfile::TempDir,
+    /// Canonical, the temp dir may be below a symlink.
+    root: PathBuf,
+    limits: Arc<FsLimits>,
+  }
+
+  impl Fixture {
+    fn new(scopes: Vec<FsLimitScopeOptions>) -> Self {
+      let dir = tempfile::tempdir().unwrap();
+      let root = RealFs.realpath_sync(dir.path()).unwrap();
+      std::fs::create_dir(root.join("data")).unwrap();
+      for name in ["data/a.txt", "data/b.txt", "data/c.txt", "other.txt"] {
+        std::fs::write(root.join(name), "hello").unwrap();
+      }
+      let scopes = scopes
+        .into_iter()
+        .map(|scope| FsLimitScopeOptions {
+          path: root.join(scope.path),
+          ..scope
+        })
+        .collect();
+      let limits = Arc::new(FsLimits::new(scopes, &RealFs));
+      Self {
+        _dir: dir,
+        root,
+        limits,
+      }
+    }
+
+    fn open(&self, name: &str) -> FsResult<Rc<dyn File>> {
+      let path = self.root.join(name);
+      let reservation =
+        OpenReservation::new(self.limits.clone(), path.clone())?;
+      let file = RealFs.open_sync(&path, OpenOptions::read(), None)?;
+      Ok(reservation.limit(file))
+    }
+  }
+
+  #[test]
+  fn caps_open_files() {
+    let fixture = Fixture::new(vec![FsLimitScopeOptions {
+      path: "data".into(),
+      max_open_files: Some(2),
+      ..Default::default()
+    }]);
+    let a = fixture.open("data/a.txt").unwrap();
+    let b = fixture.open("data/b.txt").unwrap();
+    let err = fixture.open("data/c.txt").err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "Limit exceeded for {}: at most 2 files may be open",
+        fixture.root.join("data").display()
+      )
+    );
+    // files outside the scope aren't counted
+    let _other = fixture.open("other.txt").unwrap();
+    // clones count as the same file
+    let _clone = b.clone().try_clone_inner().unwrap();
+    drop(a);
+    let _c = fixture.open("data/c.txt").unwrap();
+    assert_eq!(fixture.limits.usage()[0].open_files, 2);
+  }
+
+  #[test]
+  fn counts_bytes_read() {
+    let fixture = Fixture::new(vec![
+      FsLimitScopeOptions {
+        path: "data".into(),
+        max_bytes_read: Some(4),
+        ..Default::default()
+      },
+      FsLimitScopeOptions {
+        path: "".into(),
+        ..Default::default()
+      },
+    ]);
+    let file = fixture.open("data/a.txt").unwrap();
+    let mut buf = [0; 3];
+    assert_eq!(file.clone().read_sync(&mut buf).unwrap(), 3);
+    assert_eq!(file.clone().read_sync(&mut buf).unwrap(), 2);
+    assert_eq!(file.clone().read_sync(&mut buf).unwrap(), 0);
+    fixture
+      .limits
+      .count_read(&fixture.root.join("other.txt"), 5)
+      .unwrap();
+
+    let usage = fixture.limits.usage();
+    // soft limits are only counted
+    assert_eq!(usage[0].bytes_read, 5);
+    assert_eq!(usage[1].bytes_read, 10);
+    assert_eq!(usage[1].open_files, 1);
+    drop(file);
+    assert_eq!(fixture.limits.usage()[1].open_files, 0);
+  }
+
+  #[test]
+  fn hard_byte_limit() {
+    let fixture = Fixture::new(vec![FsLimitScopeOptions {
+      path: "data".into(),
+      max_bytes_read: Some(7),
+      hard: true,
+      ..Default::default()
+    }]);
+    let mut buf = [0; 16];
+    let file = fixture.open("data/a.txt").unwrap();
+    assert_eq!(file.clone().read_sync(&mut buf).unwrap(), 5);
+    let file = fixture.open("data/b.txt").unwrap();
+    // stops at the limit
+    assert_eq!(file.clone().read_sync(&mut buf).unwrap(), 2);
+    let err = file.clone().read_sync(&mut buf).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "Limit exceeded for {}: at most 7 bytes may be read",
+        fixture.root.join("data").display()
+      )
+    );
+    let c = fixture.root.join("data/c.txt");
+    assert!(fixture.limits.count_read(&c, 5).is_err());
+    assert_eq!(fixture.limits.usage()[0].bytes_read, 7);
+  }
+
+  #[test]
+  fn reservation_follows_the_opened_path() {
+    let fixture = Fixture::new(vec![FsLimitScopeOptions {
+      path: "data".into(),
+      max_open_files: Some(1),
+      ..Default::default()
+    }]);
+    // resolved while `data/a.txt` was elsewhere
+    let mut reservation = OpenReservation::new(
+      fixture.limits.clone(),
+      fixture.root.join("other.txt"),
+    )
+    .unwrap();
+    fn allow<'a>(
+      _resolved: bool,
+      path: &'a Path,
+      _options: &'a OpenOptions,
+    ) -> FsResult<Cow<'a, Path>> {
+      Ok(Cow::Borrowed(path))
+    }
+    let mut allow = allow;
+    let mut access_check =
+      track_resolved_path(&mut allow, |path| reservation.move_to(path));
+    let file = RealFs
+      .open_sync(
+        &fixture.root.join("data/a.txt"),
+        OpenOptions::read(),
+        Some(&mut access_check),
+      )
+      .unwrap();
+    drop(access_check);
+    let file = reservation.limit(file);
+    assert_eq!(fixture.limits.usage()[0].open_files, 1);
+    assert!(fixture.open("data/b.txt").is_err());
+    drop(file);
+    assert_eq!(fixture.limits.usage()[0].open_files, 0);
+  }
+}
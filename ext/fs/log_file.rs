@@ -0,0 +1,335 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Append-only log files that coalesce small writes.
+//!
+//! Writes are appended to an in-memory buffer that a background task hands to
+//! the file every `flushIntervalMs`, or as soon as the buffer holds
+//! `maxBufferBytes`. What survives a crash depends on `sync`:
+//!
+//! - `"none"`: flushed data reaches the OS but is never fsynced. Up to
+//!   `flushIntervalMs` of data is lost if the process crashes, and anything
+//!   the OS hasn't written back yet is lost if the machine does.
+//! - `"interval"`: every flush is followed by `fdatasync`, so at most
+//!   `flushIntervalMs` of data may be lost.
+//! - `"always"`: every write is written and `fdatasync`ed before the op
+//!   returns.
+//!
+//! Errors of a background flush (a partial write, a full disk) are kept and
+//! returned by the next write or flush call; the data of the failed flush is
+//! dropped. Closing the resource, or dropping it on runtime shutdown, flushes
+//! what is still buffered, after any flush that is in flight, but errors of
+//! that last flush can't be reported.
+//! Call `flush()` before closing to observe them.
+//!
+//! Unless `sync` is `"always"`, JS queues `write()` calls and hands them to
+//! `op_logfile_write` in one batched op call per task. An error of a queued
+//! write is thrown by the next write or flush call, like that of a
+//! background flush.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::BufView;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_io::fs::File;
+use deno_io::fs::FsError;
+use deno_io::fs::FsResult;
+use deno_web::BatchableOp;
+use serde::Deserialize;
+use tokio::sync::Notify;
+
+use crate::interface::FileSystemRc;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::FsPermissions;
+use crate::OpenOptions;
+
+const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1000;
+const DEFAULT_MAX_BUFFER_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogFileSync {
+  #[default]
+  None,
+  Interval,
+  Always,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFileOptions {
+  #[serde(default = "default_flush_interval_ms")]
+  flush_interval_ms: u64,
+  #[serde(default = "default_max_buffer_bytes")]
+  max_buffer_bytes: usize,
+  #[serde(default)]
+  sync: LogFileSync,
+}
+
+fn default_flush_interval_ms() -> u64 {
+  DEFAULT_FLUSH_INTERVAL_MS
+}
+
+fn default_max_buffer_bytes() -> usize {
+  DEFAULT_MAX_BUFFER_BYTES
+}
+
+impl Default for LogFileOptions {
+  fn default() -> Self {
+    Self {
+      flush_interval_ms: DEFAULT_FLUSH_INTERVAL_MS,
+      max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+      sync: LogFileSync::None,
+    }
+  }
+}
+
+pub struct LogFileResource {
+  file: Rc<dyn File>,
+  options: LogFileOptions,
+  buffer: RefCell<Vec<u8>>,
+  /// Error of a background flush, returned by the next write or flush.
+  pending_error: RefCell<Option<FsError>>,
+  /// Serializes flushes so batches reach the file in the order they were
+  /// written.
+  flush_lock: AsyncRefCell<()>,
+  flush_requested: Rc<Notify>,
+  cancel: Rc<CancelHandle>,
+}
+
+impl LogFileResource {
+  fn new(file: Rc<dyn File>, options: LogFileOptions) -> Rc<Self> {
+    let resource = Rc::new(Self {
+      file,
+      buffer: RefCell::new(Vec::with_capacity(options.max_buffer_bytes)),
+      options,
+      pending_error: RefCell::new(None),
+      flush_lock: AsyncRefCell::new(()),
+      flush_requested: Rc::new(Notify::new()),
+      cancel: CancelHandle::new_rc(),
+    });
+    if resource.options.sync != LogFileSync::Always {
+      spawn_flusher(&resource);
+    }
+    resource
+  }
+
+  fn take_pending_error(&self) -> FsResult<()> {
+    match self.pending_error.borrow_mut().take() {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+
+  fn write(&self, lines: &[JsBuffer]) -> FsResult<()> {
+    self.take_pending_error()?;
+    let len = {
+      let mut buffer = self.buffer.borrow_mut();
+      for line in lines {
+        buffer.extend_from_slice(line);
+      }
+      buffer.len()
+    };
+    if self.options.sync == LogFileSync::Always {
+      self.flush_sync()
+    } else {
+      if len >= self.options.max_buffer_bytes {
+        self.flush_requested.notify_one();
+      }
+      Ok(())
+    }
+  }
+
+  async fn flush(self: &Rc<Self>) -> FsResult<()> {
+    self.take_pending_error()?;
+    self.flush_buffer().await
+  }
+
+  /// Writes batches until the buffer is empty, including lines written while
+  /// a batch was in flight. `close` relies on this to leave the last lines
+  /// to a flush that is already running.
+  async fn flush_buffer(self: &Rc<Self>) -> FsResult<()> {
+    let _lock = RcRef::map(self, |r| &r.flush_lock).borrow_mut().await;
+    loop {
+      let data = std::mem::take(&mut *self.buffer.borrow_mut());
+      if data.is_empty() {
+        return Ok(());
+      }
+      self.file.clone().write_all(BufView::from(data)).await?;
+      if self.options.sync == LogFileSync::Interval {
+        self.file.clone().datasync_async().await?;
+      }
+    }
+  }
+
+  fn flush_sync(&self) -> FsResult<()> {
+    let data = std::mem::take(&mut *self.buffer.borrow_mut());
+    if data.is_empty() {
+      return Ok(());
+    }
+    self.file.clone().write_all_sync(&data)?;
+    if self.options.sync != LogFileSync::None {
+      self.file.clone().datasync_sync()?;
+    }
+    Ok(())
+  }
+}
+
+/// Flush on every interval tick, or earlier when a write fills the buffer.
+/// The task only holds a weak reference between flushes, so it doesn't keep
+/// the resource alive once it was removed from the resource table.
+fn spawn_flusher(resource: &Rc<LogFileResource>) {
+  let weak = Rc::downgrade(resource);
+  let interval = Duration::from_millis(resource.options.flush_interval_ms);
+  let flush_requested = resource.flush_requested.clone();
+  let cancel = resource.cancel.clone();
+  deno_core::unsync::spawn(async move {
+    loop {
+      // Only the wait is canceled: a flush that is in flight when the
+      // resource is closed runs to completion.
+      let wait = tokio::time::timeout(interval, flush_requested.notified());
+      if wait.or_cancel(cancel.clone()).await.is_err() {
+        break;
+      }
+      let Some(resource) = weak.upgrade() else {
+        break;
+      };
+      if let Err(err) = resource.flush_buffer().await {
+        resource.pending_error.borrow_mut().get_or_insert(err);
+      }
+    }
+  });
+}
+
+impl Resource for LogFileResource {
+  fn name(&self) -> Cow<str> {
+    "logFile".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+    // Writing here while a flush is in flight could put the last lines
+    // before its batch. That flush picks them up once its batch is written.
+    let Some(_lock) = RcRef::map(&self, |r| &r.flush_lock).try_borrow_mut()
+    else {
+      return;
+    };
+    let _ = self.flush_sync();
+  }
+}
+
+impl Drop for LogFileResource {
+  fn drop(&mut self) {
+    // The resource table is dropped with the runtime, so this also covers
+    // shutdown without an explicit close. No flush can be in flight here:
+    // flushes hold a strong reference to the resource until they're done.
+    let _ = self.flush_sync();
+  }
+}
+
+#[op2(async)]
+#[smi]
+pub async fn op_logfile_open<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[serde] options: Option<LogFileOptions>,
+) -> Result<ResourceId, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  if options.max_buffer_bytes == 0 {
+    return Err(FsOpsError::InvalidLogFileOption("maxBufferBytes"));
+  }
+  if options.flush_interval_ms == 0 {
+    return Err(FsOpsError::InvalidLogFileOption("flushIntervalMs"));
+  }
+
+  let (fs, path) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.openLogFile");
+    let path = state
+      .borrow_mut::<P>()
+      .check_write(&path, "Deno.openLogFile()")
+      .map_err(FsOpsError::Permission)?;
+    (state.borrow::<FileSystemRc>().clone(), path)
+  };
+  let file = fs
+    .open_async(
+      path.clone(),
+      OpenOptions::write(true, true, false, None),
+      None,
+    )
+    .await
+    .context_path("open", &path)?;
+
+  let resource = LogFileResource::new(file, options);
+  let rid = state.borrow_mut().resource_table.add_rc(resource);
+  Ok(rid)
+}
+
+fn write_lines(
+  state: &mut OpState,
+  rid: ResourceId,
+  lines: &[JsBuffer],
+) -> Result<(), FsOpsError> {
+  let resource = state
+    .resource_table
+    .get::<LogFileResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  resource.write(lines)?;
+  Ok(())
+}
+
+#[op2]
+pub fn op_logfile_write(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[serde] lines: Vec<JsBuffer>,
+) -> Result<(), FsOpsError> {
+  write_lines(state, rid, &lines)
+}
+
+/// `op_logfile_write` as a [`BatchableOp`].
+pub(crate) struct BatchedLogFileWrite;
+
+impl BatchableOp for BatchedLogFileWrite {
+  const NAME: &'static str = "op_logfile_write";
+  type Args = (ResourceId, Vec<JsBuffer>);
+  type Output = ();
+  type Error = FsOpsError;
+
+  fn call(
+    state: &mut OpState,
+    (rid, lines): Self::Args,
+  ) -> Result<(), FsOpsError> {
+    write_lines(state, rid, &lines)
+  }
+}
+
+#[op2(async)]
+pub async fn op_logfile_flush(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(), FsOpsError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<LogFileResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  resource.flush().await?;
+  Ok(())
+}
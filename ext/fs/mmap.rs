@@ -0,0 +1,336 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Read-only memory mapped files, exposed to JS as `ArrayBuffer`s.
+//!
+//! The mapping is owned by the backing store of the `ArrayBuffer` and unmapped
+//! by its deleter, so it stays alive as long as anything references the
+//! buffer: JS objects, or ops that are still using it. `op_fs_munmap` detaches
+//! the buffer so that the mapping is released right away instead of on the
+//! next garbage collection.
+//!
+//! Pages are mapped copy-on-write, writes to the buffer stay private to the
+//! process and never reach the file. Reading a page of a file that was
+//! truncated below it after it was mapped raises `SIGBUS` and kills the
+//! process on Unix. With `lock`, a shared advisory lock is held while the file
+//! is mapped, which protects against writers that take an exclusive lock
+//! first. Files on network filesystems, which can be truncated by other hosts
+//! without any locking, are refused on Linux. Windows refuses to truncate a
+//! file while it is mapped.
+
+use std::borrow::Cow;
+use std::ffi::c_void;
+use std::fs::File as StdFile;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::v8;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_io::fs::File;
+use deno_io::fs::FileResource;
+use deno_io::fs::FsError;
+use deno_io::fs::FsResult;
+use fs3::FileExt;
+use memmap2::MmapMut;
+use memmap2::MmapOptions;
+use serde::Deserialize;
+
+use crate::interface::FileSystemRc;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::FsPermissions;
+use crate::OpenOptions;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum MmapSource {
+  Rid(ResourceId),
+  Path(String),
+}
+
+/// Owned by the backing store of the buffer, dropped by its deleter.
+struct Mapping {
+  map: MmapMut,
+  /// The file holding the shared lock of a locked mapping.
+  locked: Option<StdFile>,
+}
+
+impl Drop for Mapping {
+  fn drop(&mut self) {
+    if let Some(file) = &self.locked {
+      let _ = FileExt::unlock(file);
+    }
+  }
+}
+
+/// May be called on any thread, V8 frees backing stores in the background.
+unsafe extern "C" fn mapping_deleter(
+  _data: *mut c_void,
+  _byte_length: usize,
+  deleter_data: *mut c_void,
+) {
+  // SAFETY: `deleter_data` is the mapping leaked in `op_fs_mmap_readonly`,
+  // and V8 calls the deleter exactly once.
+  drop(unsafe { Box::from_raw(deleter_data as *mut Mapping) });
+}
+
+struct MappedFileResource {
+  /// Doesn't keep the buffer alive, so that it can still be collected.
+  buffer: v8::Weak<v8::ArrayBuffer>,
+}
+
+impl Resource for MappedFileResource {
+  fn name(&self) -> Cow<str> {
+    "mappedFile".into()
+  }
+}
+
+/// A `std::fs::File` sharing the descriptor of `file`, which memmap2 and fs3
+/// need.
+fn std_file(file: Rc<dyn File>) -> Result<StdFile, FsOpsError> {
+  let Some(handle) = file.backing_fd() else {
+    return Err(FsOpsError::MmapUnsupported("it has no file descriptor"));
+  };
+  #[cfg(unix)]
+  let owned = {
+    use std::os::fd::BorrowedFd;
+    // SAFETY: the descriptor stays open while `file` is alive.
+    unsafe { BorrowedFd::borrow_raw(handle) }.try_clone_to_owned()
+  };
+  #[cfg(windows)]
+  let owned = {
+    use std::os::windows::io::BorrowedHandle;
+    // SAFETY: the handle stays open while `file` is alive.
+    unsafe { BorrowedHandle::borrow_raw(handle) }.try_clone_to_owned()
+  };
+  Ok(StdFile::from(owned.map_err(FsOpsError::Io)?))
+}
+
+/// Why `file` can't be mapped safely if it is on a network filesystem, where
+/// other hosts can truncate it under the mapping.
+#[cfg(target_os = "linux")]
+fn network_filesystem(file: &StdFile) -> Option<&'static str> {
+  use std::os::fd::AsRawFd;
+
+  const NFS_SUPER_MAGIC: u32 = 0x6969;
+  const SMB_SUPER_MAGIC: u32 = 0x517b;
+  const SMB2_SUPER_MAGIC: u32 = 0xfe534d42;
+  const CIFS_SUPER_MAGIC: u32 = 0xff534d42;
+  const V9FS_MAGIC: u32 = 0x01021997;
+
+  let mut stat = std::mem::MaybeUninit::<libc::statfs>::uninit();
+  // SAFETY: the descriptor is open, and `stat` is written on success.
+  if unsafe { libc::fstatfs(file.as_raw_fd(), stat.as_mut_ptr()) } != 0 {
+    return None;
+  }
+  // SAFETY: `fstatfs` succeeded.
+  let stat = unsafe { stat.assume_init() };
+  // The width and signedness of `f_type` differ between targets, the magic
+  // numbers all fit in 32 bits.
+  match stat.f_type as u32 {
+    NFS_SUPER_MAGIC => Some("it is on an NFS filesystem"),
+    SMB_SUPER_MAGIC | SMB2_SUPER_MAGIC | CIFS_SUPER_MAGIC => {
+      Some("it is on an SMB filesystem")
+    }
+    V9FS_MAGIC => Some("it is on a 9P filesystem"),
+    _ => None,
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn network_filesystem(_file: &StdFile) -> Option<&'static str> {
+  None
+}
+
+/// Maps `file`, taking a shared lock first if `lock` is set. `None` for an
+/// empty file, which can't be mapped.
+fn map_file(file: StdFile, lock: bool) -> FsResult<Option<Mapping>> {
+  let metadata = file.metadata()?;
+  if !metadata.is_file() {
+    return Err(FsError::NotSupported);
+  }
+  if lock {
+    FileExt::try_lock_shared(&file).map_err(|_| FsError::FileBusy)?;
+  }
+  // Checked after locking, the file might have been truncated before.
+  let len = file.metadata()?.len();
+  if len == 0 {
+    if lock {
+      // The descriptor may be shared with a file that stays open.
+      let _ = FileExt::unlock(&file);
+    }
+    return Ok(None);
+  }
+  let len = usize::try_from(len).map_err(|_| FsError::NotSupported)?;
+  // SAFETY: the mapping is private and only exposed as a buffer of `len`
+  // bytes. Truncating the file underneath is the risk documented above.
+  let map = unsafe { MmapOptions::new().len(len).map_copy(&file) }?;
+  Ok(Some(Mapping {
+    map,
+    locked: lock.then_some(file),
+  }))
+}
+
+/// Returns `[rid, buffer]`.
+#[op2]
+pub fn op_fs_mmap_readonly<P, 's>(
+  scope: &mut v8::HandleScope<'s>,
+  state: &mut OpState,
+  #[serde] source: MmapSource,
+  lock: bool,
+) -> Result<v8::Local<'s, v8::Array>, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.mapFile");
+
+  let (file, path) = match source {
+    MmapSource::Rid(rid) => {
+      let file =
+        FileResource::get_file(state, rid).map_err(FsOpsError::Resource)?;
+      (file, None)
+    }
+    MmapSource::Path(path) => {
+      let path = state
+        .borrow_mut::<P>()
+        .check_read(&path, "Deno.mapFile()")
+        .map_err(FsOpsError::Permission)?;
+      let fs = state.borrow::<FileSystemRc>();
+      let file = fs
+        .open_sync(&path, OpenOptions::read(), None)
+        .context_path("open", &path)?;
+      (file, Some(path))
+    }
+  };
+  let file = std_file(file)?;
+  if let Some(reason) = network_filesystem(&file) {
+    return Err(FsOpsError::MmapUnsupported(reason));
+  }
+
+  let mapping = match &path {
+    Some(path) => map_file(file, lock).context_path("mmap", path)?,
+    None => map_file(file, lock).context("mmap")?,
+  };
+  let buffer = match mapping {
+    Some(mut mapping) => {
+      let data = mapping.map.as_mut_ptr() as *mut c_void;
+      let len = mapping.map.len();
+      // SAFETY: `data` is valid for `len` bytes until the deleter drops the
+      // mapping.
+      let backing_store = unsafe {
+        v8::ArrayBuffer::new_backing_store_from_ptr(
+          data,
+          len,
+          mapping_deleter,
+          Box::into_raw(Box::new(mapping)) as *mut c_void,
+        )
+      }
+      .make_shared();
+      v8::ArrayBuffer::with_backing_store(scope, &backing_store)
+    }
+    None => v8::ArrayBuffer::new(scope, 0),
+  };
+
+  let rid = state.resource_table.add(MappedFileResource {
+    buffer: v8::Weak::new(scope, buffer),
+  });
+  let rid = v8::Integer::new_from_unsigned(scope, rid);
+  Ok(v8::Array::new_with_elements(
+    scope,
+    &[rid.into(), buffer.into()],
+  ))
+}
+
+/// Detaches the buffer and releases the mapping, unless an op is still using
+/// the buffer, in which case the mapping is released when it's done.
+#[op2]
+pub fn op_fs_munmap(
+  scope: &mut v8::HandleScope,
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<(), FsOpsError> {
+  let resource = state
+    .resource_table
+    .take::<MappedFileResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  if let Some(buffer) = resource.buffer.to_local(scope) {
+    buffer.detach(None);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Seek;
+  use std::io::SeekFrom;
+  use std::io::Write;
+
+  use super::*;
+
+  #[test]
+  fn maps_file_contents() {
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(b"hello").unwrap();
+    file.seek(SeekFrom::Start(1 << 20)).unwrap();
+    file.write_all(b"world").unwrap();
+
+    let mut mapping =
+      map_file(file.try_clone().unwrap(), false).unwrap().unwrap();
+    assert_eq!(mapping.map.len(), (1 << 20) + 5);
+    assert_eq!(&mapping.map[..5], b"hello");
+    assert_eq!(&mapping.map[1 << 20..], b"world");
+
+    // copy-on-write, the file doesn't change
+    mapping.map[0] = b'j';
+    let again = map_file(file.try_clone().unwrap(), false).unwrap().unwrap();
+    assert_eq!(&again.map[..5], b"hello");
+  }
+
+  #[test]
+  fn empty_file() {
+    let file = tempfile::tempfile().unwrap();
+    assert!(map_file(file, true).unwrap().is_none());
+  }
+
+  #[test]
+  fn locked_mapping_holds_shared_lock() {
+    let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+    std::fs::write(&path, b"data").unwrap();
+
+    let mapping = map_file(StdFile::open(&path).unwrap(), true)
+      .unwrap()
+      .unwrap();
+    let other = StdFile::open(&path).unwrap();
+    assert!(FileExt::try_lock_exclusive(&other).is_err());
+    // other readers can map it too
+    let shared = map_file(StdFile::open(&path).unwrap(), true)
+      .unwrap()
+      .unwrap();
+    drop(mapping);
+    drop(shared);
+    FileExt::try_lock_exclusive(&other).unwrap();
+
+    // and a writer holding the lock keeps locked mappings out
+    assert!(matches!(
+      map_file(StdFile::open(&path).unwrap(), true),
+      Err(FsError::FileBusy)
+    ));
+    // but not unlocked ones
+    map_file(StdFile::open(&path).unwrap(), false)
+      .unwrap()
+      .unwrap();
+  }
+
+  #[test]
+  fn refuses_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = StdFile::open(dir.path());
+    // opening a directory fails on Windows
+    if let Ok(file) = file {
+      assert!(matches!(map_file(file, false), Err(FsError::NotSupported)));
+    }
+  }
+}
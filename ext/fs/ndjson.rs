@@ -0,0 +1,420 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Newline-delimited JSON readers and encoding.
+//!
+//! Lines are split and parsed in Rust and a whole batch of values is handed
+//! to JS in one op call, so a large file doesn't cross the op boundary once
+//! per line. Values are materialized directly into V8 objects by serde_v8.
+//!
+//! Both `\n` and `\r\n` line endings are accepted, blank lines are skipped,
+//! and a final line without a trailing newline is still parsed. Lines that
+//! aren't valid JSON, or that are longer than `maxLineLength`, are reported
+//! as errors next to the values of their batch instead of failing the read.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::serde_json;
+use deno_core::AsyncRefCell;
+use deno_core::BufView;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::ToJsBuffer;
+use deno_io::fs::File;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::interface::FileSystemRc;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::FsPermissions;
+use crate::OpenOptions;
+
+const DEFAULT_BATCH_SIZE: usize = 1024;
+const DEFAULT_MAX_LINE_LENGTH: usize = 16 * 1024 * 1024;
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum NdjsonSource {
+  Rid(ResourceId),
+  Path(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NdjsonReaderOptions {
+  #[serde(default = "default_batch_size")]
+  batch_size: usize,
+  #[serde(default = "default_max_line_length")]
+  max_line_length: usize,
+}
+
+fn default_batch_size() -> usize {
+  DEFAULT_BATCH_SIZE
+}
+
+fn default_max_line_length() -> usize {
+  DEFAULT_MAX_LINE_LENGTH
+}
+
+impl Default for NdjsonReaderOptions {
+  fn default() -> Self {
+    Self {
+      batch_size: DEFAULT_BATCH_SIZE,
+      max_line_length: DEFAULT_MAX_LINE_LENGTH,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NdjsonLineError {
+  /// 1-based line number in the input.
+  line: u64,
+  message: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NdjsonBatch {
+  values: Vec<serde_json::Value>,
+  errors: Vec<NdjsonLineError>,
+}
+
+impl NdjsonBatch {
+  fn len(&self) -> usize {
+    self.values.len() + self.errors.len()
+  }
+}
+
+/// Splits a byte stream into lines and parses them. Kept separate from the
+/// resource so it can be tested without I/O.
+#[derive(Default)]
+struct LineDecoder {
+  buffer: Vec<u8>,
+  /// Start of the not yet consumed part of `buffer`.
+  offset: usize,
+  line: u64,
+  /// Set while skipping the rest of an oversized line.
+  discarding: bool,
+  max_line_length: usize,
+}
+
+impl LineDecoder {
+  fn new(max_line_length: usize) -> Self {
+    Self {
+      max_line_length,
+      ..Default::default()
+    }
+  }
+
+  fn push(&mut self, chunk: &[u8]) {
+    if self.offset > 0 {
+      self.buffer.drain(..self.offset);
+      self.offset = 0;
+    }
+    self.buffer.extend_from_slice(chunk);
+  }
+
+  /// Decode complete lines into `batch` until it holds `batch_size` items.
+  /// With `eof`, whatever is left is treated as the last line.
+  fn decode(&mut self, batch: &mut NdjsonBatch, batch_size: usize, eof: bool) {
+    while batch.len() < batch_size {
+      let rest = &self.buffer[self.offset..];
+      let (line_end, next) = match memchr(b'\n', rest) {
+        Some(index) => (index, index + 1),
+        None if eof && !rest.is_empty() => (rest.len(), rest.len()),
+        None => {
+          if !self.discarding && rest.len() > self.max_line_length {
+            self.line += 1;
+            self.report_oversized(batch);
+            self.discarding = true;
+          }
+          if self.discarding {
+            // Nothing of this line is needed anymore.
+            self.offset = self.buffer.len();
+          }
+          return;
+        }
+      };
+      let start = self.offset;
+      self.offset += next;
+      if std::mem::take(&mut self.discarding) {
+        continue;
+      }
+      self.line += 1;
+
+      let mut line = &self.buffer[start..start + line_end];
+      if let Some(stripped) = line.strip_suffix(b"\r") {
+        line = stripped;
+      }
+      if line.len() > self.max_line_length {
+        self.report_oversized(batch);
+        continue;
+      }
+      if line.iter().all(u8::is_ascii_whitespace) {
+        continue;
+      }
+      match serde_json::from_slice(line) {
+        Ok(value) => batch.values.push(value),
+        Err(err) => batch.errors.push(NdjsonLineError {
+          line: self.line,
+          message: err.to_string(),
+        }),
+      }
+    }
+  }
+
+  fn report_oversized(&self, batch: &mut NdjsonBatch) {
+    batch.errors.push(NdjsonLineError {
+      line: self.line,
+      message: format!(
+        "Line exceeds maxLineLength of {} bytes",
+        self.max_line_length
+      ),
+    });
+  }
+
+  fn is_empty(&self) -> bool {
+    self.offset == self.buffer.len()
+  }
+}
+
+fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+  haystack.iter().position(|b| *b == needle)
+}
+
+enum NdjsonInput {
+  File(Rc<dyn File>),
+  Resource(Rc<dyn Resource>),
+}
+
+impl NdjsonInput {
+  async fn read(&self, limit: usize) -> Result<BufView, FsOpsError> {
+    match self {
+      NdjsonInput::File(file) => Ok(file.clone().read(limit).await?),
+      NdjsonInput::Resource(resource) => resource
+        .clone()
+        .read(limit)
+        .await
+        .map_err(FsOpsError::Other),
+    }
+  }
+}
+
+struct NdjsonReaderState {
+  decoder: LineDecoder,
+  eof: bool,
+}
+
+pub struct NdjsonReaderResource {
+  input: NdjsonInput,
+  batch_size: usize,
+  state: AsyncRefCell<NdjsonReaderState>,
+}
+
+impl NdjsonReaderResource {
+  async fn read_batch(
+    self: Rc<Self>,
+  ) -> Result<Option<NdjsonBatch>, FsOpsError> {
+    let mut state = RcRef::map(&self, |r| &r.state).borrow_mut().await;
+    let mut batch = NdjsonBatch::default();
+    loop {
+      let eof = state.eof;
+      state.decoder.decode(&mut batch, self.batch_size, eof);
+      if batch.len() >= self.batch_size || eof {
+        break;
+      }
+      let chunk = self.input.read(READ_CHUNK_SIZE).await?;
+      if chunk.is_empty() {
+        state.eof = true;
+      } else {
+        state.decoder.push(&chunk);
+      }
+    }
+    if batch.len() == 0 && state.eof && state.decoder.is_empty() {
+      return Ok(None);
+    }
+    Ok(Some(batch))
+  }
+}
+
+impl Resource for NdjsonReaderResource {
+  fn name(&self) -> Cow<str> {
+    "ndjsonReader".into()
+  }
+}
+
+#[op2(async)]
+#[smi]
+pub async fn op_ndjson_reader_open<P>(
+  state: Rc<RefCell<OpState>>,
+  #[serde] source: NdjsonSource,
+  #[serde] options: Option<NdjsonReaderOptions>,
+) -> Result<ResourceId, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  if options.batch_size == 0 {
+    return Err(FsOpsError::InvalidNdjsonOption("batchSize"));
+  }
+  if options.max_line_length == 0 {
+    return Err(FsOpsError::InvalidNdjsonOption("maxLineLength"));
+  }
+
+  state
+    .borrow()
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.openNdjsonReader");
+
+  let input = match source {
+    NdjsonSource::Rid(rid) => {
+      let resource = state
+        .borrow()
+        .resource_table
+        .get_any(rid)
+        .map_err(FsOpsError::Resource)?;
+      NdjsonInput::Resource(resource)
+    }
+    NdjsonSource::Path(path) => {
+      let (fs, path) = {
+        let mut state = state.borrow_mut();
+        let path = state
+          .borrow_mut::<P>()
+          .check_read(&path, "Deno.openNdjsonReader()")
+          .map_err(FsOpsError::Permission)?;
+        (state.borrow::<FileSystemRc>().clone(), path)
+      };
+      let file = fs
+        .open_async(path.clone(), OpenOptions::read(), None)
+        .await
+        .context_path("open", &path)?;
+      NdjsonInput::File(file)
+    }
+  };
+
+  let resource = NdjsonReaderResource {
+    input,
+    batch_size: options.batch_size,
+    state: AsyncRefCell::new(NdjsonReaderState {
+      decoder: LineDecoder::new(options.max_line_length),
+      eof: false,
+    }),
+  };
+  let rid = state.borrow_mut().resource_table.add(resource);
+  Ok(rid)
+}
+
+/// Resolves to `null` once the input is exhausted.
+#[op2(async)]
+#[serde]
+pub async fn op_ndjson_read_batch(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<NdjsonBatch>, FsOpsError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<NdjsonReaderResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  resource.read_batch().await
+}
+
+/// Serialize `values` as NDJSON, one line per value, each terminated by `\n`.
+#[op2]
+#[serde]
+pub fn op_ndjson_encode_batch(
+  #[serde] values: Vec<serde_json::Value>,
+) -> Result<ToJsBuffer, FsOpsError> {
+  let mut buf = Vec::with_capacity(values.len() * 64);
+  for value in &values {
+    serde_json::to_writer(&mut buf, value)
+      .map_err(|err| FsOpsError::Other(err.into()))?;
+    buf.push(b'\n');
+  }
+  Ok(buf.into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::serde_json::json;
+
+  fn decode_all(
+    chunks: &[&[u8]],
+    batch_size: usize,
+    max_line_length: usize,
+  ) -> Vec<NdjsonBatch> {
+    let mut decoder = LineDecoder::new(max_line_length);
+    let mut batches = vec![];
+    let mut batch = NdjsonBatch::default();
+    let mut chunks = chunks.iter();
+    loop {
+      let chunk = chunks.next();
+      if let Some(chunk) = chunk {
+        decoder.push(chunk);
+      }
+      loop {
+        decoder.decode(&mut batch, batch_size, chunk.is_none());
+        if batch.len() < batch_size {
+          break;
+        }
+        batches.push(std::mem::take(&mut batch));
+      }
+      if chunk.is_none() {
+        break;
+      }
+    }
+    if batch.len() > 0 {
+      batches.push(batch);
+    }
+    batches
+  }
+
+  #[test]
+  fn splits_lines_across_chunks() {
+    let batches =
+      decode_all(&[b"{\"a\":1}\r\n[1,", b"2]\n\n  \n\"last\""], 10, 1024);
+    assert_eq!(
+      batches,
+      vec![NdjsonBatch {
+        values: vec![json!({ "a": 1 }), json!([1, 2]), json!("last")],
+        errors: vec![],
+      }]
+    );
+  }
+
+  #[test]
+  fn reports_malformed_lines() {
+    let batches = decode_all(&[b"1\n{oops\n3\n"], 2, 1024);
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].values, vec![json!(1)]);
+    assert_eq!(batches[0].errors.len(), 1);
+    assert_eq!(batches[0].errors[0].line, 2);
+    assert_eq!(batches[1].values, vec![json!(3)]);
+  }
+
+  #[test]
+  fn skips_oversized_lines() {
+    let long = format!("\"{}\"", "x".repeat(20));
+    let input = format!("1\n{long}\n2\n");
+    // The oversized line arrives in pieces, so it's detected before its end.
+    let (a, b) = input.as_bytes().split_at(14);
+    let batches = decode_all(&[a, b], 10, 8);
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].values, vec![json!(1), json!(2)]);
+    assert_eq!(
+      batches[0].errors,
+      vec![NdjsonLineError {
+        line: 2,
+        message: "Line exceeds maxLineLength of 8 bytes".to_string(),
+      }]
+    );
+  }
+}
@@ -17,6 +17,7 @@ use deno_core::JsBuffer;
 use deno_core::OpState;
 use deno_core::ResourceId;
 use deno_core::ToJsBuffer;
+use deno_io::fd_exhaustion::FdExhaustionRetry;
 use deno_io::fs::FileResource;
 use deno_io::fs::FsError;
 use deno_io::fs::FsStat;
@@ -29,6 +30,12 @@ use crate::interface::AccessCheckFn;
 use crate::interface::FileSystemRc;
 use crate::interface::FsDirEntry;
 use crate::interface::FsFileType;
+use crate::limits::count_file_read;
+use crate::limits::resolve_path_async;
+use crate::limits::resolve_path_sync;
+use crate::limits::state_limits;
+use crate::limits::track_resolved_path;
+use crate::limits::OpenReservation;
 use crate::FsPermissions;
 use crate::OpenOptions;
 
@@ -66,6 +73,28 @@ pub enum FsOpsError {
   },
   #[error("permission denied: {0}")]
   NotCapable(&'static str), // NotCapable
+  #[error("Invalid log file option: {0} must be greater than 0")]
+  InvalidLogFileOption(&'static str), // TypeError
+  #[error("Invalid NDJSON reader option: {0} must be greater than 0")]
+  InvalidNdjsonOption(&'static str), // TypeError
+  #[error("Temporary file or directory was already persisted")]
+  TempAlreadyPersisted, // BadResource
+  #[error("Invalid glob pattern {0:?}: {1}")]
+  InvalidGlobPattern(String, &'static str), // TypeError
+  #[error("Invalid search pattern: {0}")]
+  InvalidSearchPattern(String), // TypeError
+  #[error("Exactly one of paths and readable must be given")]
+  InvalidSearchInput, // TypeError
+  #[error("Invalid integrity manifest: {0}")]
+  InvalidIntegrityManifest(String), // TypeError
+  #[error("Cannot map the file, {0}")]
+  MmapUnsupported(&'static str), // NotSupported
+  #[error(transparent)]
+  Walk(crate::walk::WalkError),
+  #[error(transparent)]
+  WindowsPath(#[from] crate::path::WindowsPathError), // TypeError
+  #[error(transparent)]
+  Interrupted(#[from] deno_io::Interrupted),
   #[error(transparent)]
   Other(deno_core::error::AnyError),
 }
@@ -199,12 +228,31 @@ where
   let options = options.unwrap_or_else(OpenOptions::read);
 
   let fs = state.borrow::<FileSystemRc>().clone();
+  let mut reservation = match state_limits(state) {
+    Some(limits) => Some(
+      OpenReservation::new(limits, resolve_path_sync(&*fs, &path))
+        .map_err(|error| map_permission_error("open", error, &path))?,
+    ),
+    None => None,
+  };
   let mut access_check =
     sync_permission_check::<P>(state.borrow_mut(), "Deno.openSync()");
-  let file = fs
-    .open_sync(&path, options, Some(&mut access_check))
-    .map_err(|error| map_permission_error("open", error, &path))?;
+  let file = match &mut reservation {
+    Some(reservation) => fs.open_sync(
+      &path,
+      options,
+      Some(&mut track_resolved_path(&mut access_check, |path| {
+        reservation.move_to(path)
+      })),
+    ),
+    None => fs.open_sync(&path, options, Some(&mut access_check)),
+  }
+  .map_err(|error| map_permission_error("open", error, &path))?;
   drop(access_check);
+  let file = match reservation {
+    Some(reservation) => reservation.limit(file),
+    None => file,
+  };
   let rid = state
     .resource_table
     .add(FileResource::new(file, "fsFile".to_string()));
@@ -227,10 +275,45 @@ where
   let mut access_check =
     async_permission_check::<P>(state.clone(), "Deno.open()");
   let fs = state.borrow().borrow::<FileSystemRc>().clone();
-  let file = fs
-    .open_async(path.clone(), options, Some(&mut access_check))
-    .await
-    .map_err(|error| map_permission_error("open", error, &path))?;
+  let limits = state_limits(&state.borrow());
+  let mut reservation = match limits {
+    Some(limits) => {
+      let resolved = resolve_path_async(&fs, &path).await;
+      Some(
+        OpenReservation::new(limits, resolved)
+          .map_err(|error| map_permission_error("open", error, &path))?,
+      )
+    }
+    None => None,
+  };
+  let mut retry = FdExhaustionRetry::default();
+  let file = loop {
+    let result = match &mut reservation {
+      Some(reservation) => {
+        fs.open_async(
+          path.clone(),
+          options,
+          Some(&mut track_resolved_path(&mut access_check, |path| {
+            reservation.move_to(path)
+          })),
+        )
+        .await
+      }
+      None => {
+        fs.open_async(path.clone(), options, Some(&mut access_check))
+          .await
+      }
+    };
+    match result {
+      Err(error) if retry.should_retry(&error) => retry.backoff().await,
+      result => break result,
+    }
+  }
+  .map_err(|error| map_permission_error("open", error, &path))?;
+  let file = match reservation {
+    Some(reservation) => reservation.limit(file),
+    None => file,
+  };
 
   let rid = state
     .borrow_mut()
@@ -1192,7 +1275,7 @@ where
   .context("tmpfile")
 }
 
-fn strip_dir_prefix(
+pub(crate) fn strip_dir_prefix(
   resolved_dir: &Path,
   dir_arg: Option<&str>,
   result_path: PathBuf,
@@ -1235,7 +1318,7 @@ where
   Ok((dir, fs))
 }
 
-fn make_temp_check_async<P>(
+pub(crate) fn make_temp_check_async<P>(
   state: Rc<RefCell<OpState>>,
   dir: Option<&str>,
   api_name: &str,
@@ -1299,7 +1382,7 @@ fn validate_temporary_filename_component(
   Ok(())
 }
 
-fn tmp_name(
+pub(crate) fn tmp_name(
   rng: &mut ThreadRng,
   dir: &Path,
   prefix: Option<&str>,
@@ -1414,11 +1497,26 @@ where
   let path = PathBuf::from(path);
 
   let fs = state.borrow::<FileSystemRc>().clone();
+  let mut limits =
+    state_limits(state).map(|limits| (limits, resolve_path_sync(&*fs, &path)));
   let mut access_check =
     sync_permission_check::<P>(state.borrow_mut(), "Deno.readFileSync()");
-  let buf = fs
-    .read_file_sync(&path, Some(&mut access_check))
-    .map_err(|error| map_permission_error("readfile", error, &path))?;
+  let buf = match &mut limits {
+    Some((_, resolved)) => fs.read_file_sync(
+      &path,
+      Some(&mut track_resolved_path(&mut access_check, |path| {
+        *resolved = path.to_path_buf();
+        Ok(())
+      })),
+    ),
+    None => fs.read_file_sync(&path, Some(&mut access_check)),
+  }
+  .map_err(|error| map_permission_error("readfile", error, &path))?;
+  drop(access_check);
+  if let Some((limits, resolved)) = limits {
+    count_file_read(&limits, &resolved, buf.len())
+      .map_err(|error| map_permission_error("readfile", error, &path))?;
+  }
 
   Ok(buf.into())
 }
@@ -1444,6 +1542,17 @@ where
     (state.borrow::<FileSystemRc>().clone(), cancel_handle)
   };
 
+  let limits = state_limits(&state.borrow());
+  let mut resolved = match &limits {
+    Some(_) => Some(resolve_path_async(&fs, &path).await),
+    None => None,
+  };
+  let mut access_check = track_resolved_path(&mut access_check, |path| {
+    if let Some(resolved) = &mut resolved {
+      *resolved = path.to_path_buf();
+    }
+    Ok(())
+  });
   let fut = fs.read_file_async(path.clone(), Some(&mut access_check));
 
   let buf = if let Some(cancel_handle) = cancel_handle {
@@ -1461,6 +1570,11 @@ where
       .await
       .map_err(|error| map_permission_error("readfile", error, &path))?
   };
+  drop(access_check);
+  if let (Some(limits), Some(resolved)) = (limits, resolved) {
+    count_file_read(&limits, &resolved, buf.len())
+      .map_err(|error| map_permission_error("readfile", error, &path))?;
+  }
 
   Ok(buf.into())
 }
@@ -1477,11 +1591,26 @@ where
   let path = PathBuf::from(path);
 
   let fs = state.borrow::<FileSystemRc>().clone();
+  let mut limits =
+    state_limits(state).map(|limits| (limits, resolve_path_sync(&*fs, &path)));
   let mut access_check =
     sync_permission_check::<P>(state.borrow_mut(), "Deno.readFileSync()");
-  let str = fs
-    .read_text_file_lossy_sync(&path, Some(&mut access_check))
-    .map_err(|error| map_permission_error("readfile", error, &path))?;
+  let str = match &mut limits {
+    Some((_, resolved)) => fs.read_text_file_lossy_sync(
+      &path,
+      Some(&mut track_resolved_path(&mut access_check, |path| {
+        *resolved = path.to_path_buf();
+        Ok(())
+      })),
+    ),
+    None => fs.read_text_file_lossy_sync(&path, Some(&mut access_check)),
+  }
+  .map_err(|error| map_permission_error("readfile", error, &path))?;
+  drop(access_check);
+  if let Some((limits, resolved)) = limits {
+    count_file_read(&limits, &resolved, str.len())
+      .map_err(|error| map_permission_error("readfile", error, &path))?;
+  }
 
   Ok(str)
 }
@@ -1507,6 +1636,17 @@ where
     (state.borrow::<FileSystemRc>().clone(), cancel_handle)
   };
 
+  let limits = state_limits(&state.borrow());
+  let mut resolved = match &limits {
+    Some(_) => Some(resolve_path_async(&fs, &path).await),
+    None => None,
+  };
+  let mut access_check = track_resolved_path(&mut access_check, |path| {
+    if let Some(resolved) = &mut resolved {
+      *resolved = path.to_path_buf();
+    }
+    Ok(())
+  });
   let fut =
     fs.read_text_file_lossy_async(path.clone(), Some(&mut access_check));
 
@@ -1525,6 +1665,11 @@ where
       .await
       .map_err(|error| map_permission_error("readfile", error, &path))?
   };
+  drop(access_check);
+  if let (Some(limits), Some(resolved)) = (limits, resolved) {
+    count_file_read(&limits, &resolved, str.len())
+      .map_err(|error| map_permission_error("readfile", error, &path))?;
+  }
 
   Ok(str)
 }
@@ -1786,7 +1931,7 @@ pub enum OperationErrorKind {
   WithTwoPaths(PathBuf, PathBuf),
 }
 
-trait MapErrContext {
+pub(crate) trait MapErrContext {
   type R;
 
   fn context_fn<F>(self, f: F) -> Self::R
@@ -1848,7 +1993,9 @@ impl<T> MapErrContext for Result<T, FsError> {
   }
 }
 
-fn path_into_string(s: std::ffi::OsString) -> Result<String, FsOpsError> {
+pub(crate) fn path_into_string(
+  s: std::ffi::OsString,
+) -> Result<String, FsOpsError> {
   s.into_string().map_err(FsOpsError::InvalidUtf8)
 }
 
@@ -0,0 +1,474 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions between path representations, backing `Deno.path`.
+//!
+//! The Windows helpers work on strings and behave the same on every
+//! platform, so that tooling can reason about Windows paths anywhere. Paths
+//! with a verbatim (`\\?\`) prefix are passed to the OS as is: they aren't
+//! normalized and are the only way to use paths of [`MAX_PATH`] characters
+//! or more with some Win32 APIs. [`long_path`] converts such paths before
+//! they reach the OS in [`crate::RealFs`].
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::OpState;
+use deno_path_util::normalize_path;
+use deno_path_util::strip_unc_prefix;
+
+use crate::interface::FileSystemRc;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::FsPermissions;
+
+/// The length of paths from which Win32 APIs require a verbatim prefix,
+/// including the terminating NUL.
+pub const MAX_PATH: usize = 260;
+
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+const DEVICE_PREFIX: &str = r"\\.\";
+
+#[derive(Debug, thiserror::Error)]
+pub enum WindowsPathError {
+  #[error("Path {0:?} is not absolute, so it has no long path form")]
+  NotAbsolute(String),
+  #[error("UNC path {0:?} is missing a server or share name")]
+  MissingShare(String),
+}
+
+/// The root of a Windows path.
+#[derive(Debug, PartialEq, Eq)]
+enum Root<'a> {
+  /// `C:\`
+  Disk(&'a str),
+  /// `C:`, relative to the current directory of the drive.
+  DiskRelative(&'a str),
+  /// `\\server\share\`
+  Unc {
+    server: &'a str,
+    share: &'a str,
+  },
+  /// `\`, relative to the current drive.
+  Rooted,
+  Relative,
+}
+
+/// Returns whether `path` has a verbatim prefix, e.g. `\\?\C:\foo` or
+/// `\\?\UNC\server\share`.
+pub fn is_verbatim(path: &str) -> bool {
+  path.starts_with(VERBATIM_PREFIX)
+}
+
+/// Returns whether `path` is a UNC path, e.g. `\\server\share\foo`,
+/// `//server/share/foo` or `\\?\UNC\server\share\foo`. Device paths like
+/// `\\.\pipe\foo` are not.
+pub fn is_unc(path: &str) -> bool {
+  if is_verbatim(path) {
+    return starts_with_ignore_case(path, VERBATIM_UNC_PREFIX);
+  }
+  let mut chars = path.chars();
+  let (Some(a), Some(b)) = (chars.next(), chars.next()) else {
+    return false;
+  };
+  if !is_separator(a) || !is_separator(b) {
+    return false;
+  }
+  let server = path[2..].split(is_separator).next().unwrap_or("");
+  !server.is_empty() && server != "?" && server != "."
+}
+
+/// Normalizes a Windows path: `/` separators become `\`, `.` and `..`
+/// segments are resolved and trailing dots and spaces are removed from
+/// segments, like Win32 APIs do.
+///
+/// With `long_path`, absolute paths are returned in verbatim form
+/// (`\\?\C:\foo` or `\\?\UNC\server\share\foo`). Otherwise the verbatim
+/// prefix is removed from drive and UNC paths. Verbatim input is not
+/// normalized, because the OS doesn't normalize it either.
+pub fn normalize_windows(
+  path: &str,
+  long_path: bool,
+) -> Result<String, WindowsPathError> {
+  if is_verbatim(path) {
+    return Ok(convert_verbatim(path, long_path));
+  }
+  let path = path.replace('/', "\\");
+  if path.starts_with(DEVICE_PREFIX) {
+    return Ok(path);
+  }
+
+  let (root, rest) = parse_root(&path)?;
+  let segments = normalize_segments(rest, root != Root::Relative);
+  let tail = segments.join("\\");
+  let normalized = match root {
+    Root::Disk(drive) if long_path => {
+      format!(r"{VERBATIM_PREFIX}{drive}\{tail}")
+    }
+    Root::Unc { server, share } if long_path => {
+      format!(r"{VERBATIM_UNC_PREFIX}{server}\{share}\{tail}")
+    }
+    Root::DiskRelative(_) | Root::Rooted | Root::Relative if long_path => {
+      return Err(WindowsPathError::NotAbsolute(path));
+    }
+    Root::Disk(drive) => format!(r"{drive}\{tail}"),
+    Root::Unc { server, share } => format!(r"\\{server}\{share}\{tail}"),
+    Root::DiskRelative(drive) => format!("{drive}{tail}"),
+    Root::Rooted => format!(r"\{tail}"),
+    Root::Relative if tail.is_empty() => ".".to_string(),
+    Root::Relative => tail,
+  };
+  Ok(normalized)
+}
+
+fn convert_verbatim(path: &str, long_path: bool) -> String {
+  if long_path {
+    return path.to_string();
+  }
+  let rest = &path[VERBATIM_PREFIX.len()..];
+  if starts_with_ignore_case(path, VERBATIM_UNC_PREFIX) {
+    format!(r"\\{}", &path[VERBATIM_UNC_PREFIX.len()..])
+  } else if disk_prefix(rest).is_some() {
+    rest.to_string()
+  } else {
+    // e.g. volume GUID paths, which only exist in verbatim form
+    path.to_string()
+  }
+}
+
+fn parse_root(path: &str) -> Result<(Root<'_>, &str), WindowsPathError> {
+  if let Some(rest) = path.strip_prefix(r"\\") {
+    let mut parts = rest.splitn(3, '\\');
+    let server = parts.next().unwrap_or("");
+    let share = parts.next().unwrap_or("");
+    if server.is_empty() || share.is_empty() {
+      return Err(WindowsPathError::MissingShare(path.to_string()));
+    }
+    let rest = parts.next().unwrap_or("");
+    return Ok((Root::Unc { server, share }, rest));
+  }
+  if let Some(drive) = disk_prefix(path) {
+    let rest = &path[drive.len()..];
+    return Ok(match rest.strip_prefix('\\') {
+      Some(rest) => (Root::Disk(drive), rest),
+      None => (Root::DiskRelative(drive), rest),
+    });
+  }
+  Ok(match path.strip_prefix('\\') {
+    Some(rest) => (Root::Rooted, rest),
+    None => (Root::Relative, path),
+  })
+}
+
+/// Returns the `C:` prefix of `path`, if any.
+fn disk_prefix(path: &str) -> Option<&str> {
+  let bytes = path.as_bytes();
+  (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+    .then(|| &path[..2])
+}
+
+fn normalize_segments(path: &str, has_root: bool) -> Vec<&str> {
+  let mut segments: Vec<&str> = Vec::new();
+  for segment in path.split('\\') {
+    match segment {
+      "" | "." => {}
+      ".." => match segments.last() {
+        Some(&last) if last != ".." => {
+          segments.pop();
+        }
+        // `..` can't go above the root
+        _ if has_root => {}
+        _ => segments.push(".."),
+      },
+      segment => {
+        let trimmed = segment.trim_end_matches(['.', ' ']);
+        segments.push(if trimmed.is_empty() { segment } else { trimmed });
+      }
+    }
+  }
+  segments
+}
+
+fn is_separator(c: char) -> bool {
+  c == '\\' || c == '/'
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+  s.len() >= prefix.len()
+    && s.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
+
+/// Converts absolute paths of [`MAX_PATH`] characters or more to their
+/// verbatim form on Windows, so that they can be passed to any Win32 API.
+/// Does nothing on other platforms.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+  let Some(s) = path.to_str() else {
+    return Cow::Borrowed(path);
+  };
+  if s.len() < MAX_PATH || is_verbatim(s) || !path.is_absolute() {
+    return Cow::Borrowed(path);
+  }
+  match normalize_windows(s, true) {
+    Ok(normalized) => Cow::Owned(PathBuf::from(normalized)),
+    Err(_) => Cow::Borrowed(path),
+  }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+  Cow::Borrowed(path)
+}
+
+/// Like [`long_path`], for owned paths.
+pub fn long_path_buf(path: PathBuf) -> PathBuf {
+  if let Cow::Owned(long) = long_path(&path) {
+    return long;
+  }
+  path
+}
+
+/// Computes the relative path from the absolute path `from` to the absolute
+/// path `to`. Returns `to` if the paths have different roots, e.g. are on
+/// different drives, and an empty path if they are the same.
+pub fn relative_path(
+  from: &Path,
+  to: &Path,
+  case_insensitive: bool,
+) -> PathBuf {
+  let from = from.components().collect::<Vec<_>>();
+  let to_components = to.components().collect::<Vec<_>>();
+  let eq = |a: &Component, b: &Component| {
+    if case_insensitive {
+      a.as_os_str().eq_ignore_ascii_case(b.as_os_str())
+    } else {
+      a == b
+    }
+  };
+  let common = from
+    .iter()
+    .zip(&to_components)
+    .take_while(|(a, b)| eq(*a, *b))
+    .count();
+  if common == 0 {
+    return to.to_path_buf();
+  }
+  let mut relative = PathBuf::new();
+  for _ in common..from.len() {
+    relative.push("..");
+  }
+  for component in &to_components[common..] {
+    relative.push(component);
+  }
+  relative
+}
+
+fn path_to_string(path: PathBuf) -> Result<String, FsOpsError> {
+  path
+    .into_os_string()
+    .into_string()
+    .map_err(FsOpsError::InvalidUtf8)
+}
+
+#[op2(async)]
+#[string]
+pub async fn op_path_realpath<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  long_path: bool,
+) -> Result<String, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let (fs, path) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.path.realPath");
+    let fs = state.borrow::<FileSystemRc>().clone();
+    let permissions = state.borrow_mut::<P>();
+    let path = permissions
+      .check_read(&path, "Deno.path.realPath()")
+      .map_err(FsOpsError::Permission)?;
+    if path.is_relative() {
+      permissions
+        .check_read_blind(&fs.cwd()?, "CWD", "Deno.path.realPath()")
+        .map_err(FsOpsError::Permission)?;
+    }
+    (fs, path)
+  };
+  // goes through the VFS for paths inside a `deno compile` binary
+  let resolved = fs
+    .realpath_async(path.clone())
+    .await
+    .context_path("realpath", &path)?;
+  let resolved = path_to_string(resolved)?;
+  if cfg!(windows) && long_path {
+    Ok(normalize_windows(&resolved, true)?)
+  } else {
+    Ok(resolved)
+  }
+}
+
+#[op2]
+#[string]
+pub fn op_path_relative<P>(
+  state: &mut OpState,
+  #[string] from: String,
+  #[string] to: String,
+) -> Result<String, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.path.relative");
+  let (from, to) = (PathBuf::from(from), PathBuf::from(to));
+  let (from, to) = if from.is_absolute() && to.is_absolute() {
+    (from, to)
+  } else {
+    let cwd = state.borrow::<FileSystemRc>().cwd()?;
+    state
+      .borrow_mut::<P>()
+      .check_read_blind(&cwd, "CWD", "Deno.path.relative()")
+      .map_err(FsOpsError::Permission)?;
+    (cwd.join(from), cwd.join(to))
+  };
+  let from = normalize_path(strip_unc_prefix(from));
+  let to = normalize_path(strip_unc_prefix(to));
+  path_to_string(relative_path(&from, &to, cfg!(windows)))
+}
+
+#[op2]
+#[string]
+pub fn op_path_normalize_windows(
+  state: &mut OpState,
+  #[string] path: String,
+  long_path: bool,
+) -> Result<String, FsOpsError> {
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.path.normalizeWindows");
+  Ok(normalize_windows(&path, long_path)?)
+}
+
+#[op2(fast)]
+pub fn op_path_is_unc(#[string] path: &str) -> bool {
+  is_unc(path)
+}
+
+#[op2(fast)]
+pub fn op_path_is_verbatim(#[string] path: &str) -> bool {
+  is_verbatim(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn normalize(path: &str) -> String {
+    normalize_windows(path, false).unwrap()
+  }
+
+  fn long(path: &str) -> String {
+    normalize_windows(path, true).unwrap()
+  }
+
+  #[test]
+  fn detects_unc_and_verbatim() {
+    assert!(is_unc(r"\\server\share\foo"));
+    assert!(is_unc("//server/share"));
+    assert!(is_unc(r"\\?\UNC\server\share"));
+    assert!(is_unc(r"\\?\unc\server\share"));
+    assert!(!is_unc(r"\\?\C:\foo"));
+    assert!(!is_unc(r"\\.\pipe\foo"));
+    assert!(!is_unc(r"\\"));
+    assert!(!is_unc(r"C:\foo"));
+    assert!(!is_unc("/foo"));
+
+    assert!(is_verbatim(r"\\?\C:\foo"));
+    assert!(is_verbatim(r"\\?\UNC\server\share"));
+    assert!(!is_verbatim(r"\\server\share"));
+    assert!(!is_verbatim("//?/C:/foo"));
+  }
+
+  #[test]
+  fn normalizes_windows_paths() {
+    assert_eq!(normalize("C:/foo/./bar/../baz"), r"C:\foo\baz");
+    assert_eq!(normalize(r"C:\foo\..\..\bar"), r"C:\bar");
+    assert_eq!(normalize("C:/"), r"C:\");
+    assert_eq!(normalize(r"C:foo\..\bar"), r"C:bar");
+    assert_eq!(normalize(r"\foo\bar\"), r"\foo\bar");
+    assert_eq!(normalize(r"foo\..\..\bar"), r"..\bar");
+    assert_eq!(normalize(r"foo\.."), ".");
+    assert_eq!(normalize(r"C:\foo. \bar..."), r"C:\foo\bar");
+    assert_eq!(normalize("//server/share/a/../b"), r"\\server\share\b");
+    assert_eq!(normalize(r"\\server\share"), r"\\server\share\");
+    assert_eq!(normalize(r"\\.\pipe\foo"), r"\\.\pipe\foo");
+    assert!(matches!(
+      normalize_windows(r"\\server", false),
+      Err(WindowsPathError::MissingShare(_))
+    ));
+  }
+
+  #[test]
+  fn converts_verbatim_paths() {
+    assert_eq!(long(r"C:\foo\..\bar"), r"\\?\C:\bar");
+    assert_eq!(long(r"\\server\share\foo"), r"\\?\UNC\server\share\foo");
+    // verbatim paths are not normalized
+    assert_eq!(long(r"\\?\C:\foo\..\bar"), r"\\?\C:\foo\..\bar");
+    assert_eq!(normalize(r"\\?\C:\foo"), r"C:\foo");
+    assert_eq!(
+      normalize(r"\\?\UNC\server\share\foo"),
+      r"\\server\share\foo"
+    );
+    assert_eq!(
+      normalize(r"\\?\Volume{b75e2c83-0000-0000-0000-602f00000000}\foo"),
+      r"\\?\Volume{b75e2c83-0000-0000-0000-602f00000000}\foo"
+    );
+    for path in [r"foo\bar", r"\foo", "C:foo"] {
+      assert!(matches!(
+        normalize_windows(path, true),
+        Err(WindowsPathError::NotAbsolute(_))
+      ));
+    }
+
+    let segment = "a".repeat(100);
+    let path = format!(r"C:\{segment}\{segment}\{segment}");
+    assert_eq!(long(&path), format!(r"\\?\{path}"));
+  }
+
+  #[cfg(not(windows))]
+  #[test]
+  fn relative_paths() {
+    let relative = |from: &str, to: &str, case_insensitive: bool| {
+      relative_path(Path::new(from), Path::new(to), case_insensitive)
+        .to_string_lossy()
+        .into_owned()
+    };
+    assert_eq!(relative("/a/b/c", "/a/d", false), "../../d");
+    assert_eq!(relative("/a", "/a/b/c", false), "b/c");
+    assert_eq!(relative("/a/b", "/a/b", false), "");
+    assert_eq!(relative("/", "/a", false), "a");
+    assert_eq!(relative("/A/b", "/a/c", false), "../../a/c");
+    assert_eq!(relative("/A/b", "/a/c", true), "../c");
+  }
+
+  #[cfg(windows)]
+  #[test]
+  fn relative_paths() {
+    let relative = |from: &str, to: &str| {
+      relative_path(Path::new(from), Path::new(to), true)
+        .to_string_lossy()
+        .into_owned()
+    };
+    assert_eq!(relative(r"C:\Foo\bar", r"c:\foo\baz"), r"..\baz");
+    assert_eq!(relative(r"C:\foo", r"D:\foo"), r"D:\foo");
+    assert_eq!(relative(r"\\server\share\a", r"\\server\share\b"), r"..\b");
+  }
+}
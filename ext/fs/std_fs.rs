@@ -22,9 +22,15 @@ use deno_path_util::normalize_path;
 use crate::interface::AccessCheckCb;
 use crate::interface::FsDirEntry;
 use crate::interface::FsFileType;
+use crate::io_backend::io_backend;
+use crate::path::long_path;
+use crate::path::long_path_buf;
 use crate::FileSystem;
 use crate::OpenOptions;
 
+/// The file system of the OS. On Windows, absolute paths of `MAX_PATH`
+/// characters or more are converted to their verbatim form before they are
+/// passed to the OS, after permission checks.
 #[derive(Debug, Clone)]
 pub struct RealFs;
 
@@ -103,6 +109,7 @@ impl FileSystem for RealFs {
     recursive: bool,
     mode: Option<u32>,
   ) -> FsResult<()> {
+    let path = &long_path(path);
     mkdir(path, recursive, mode)
   }
   async fn mkdir_async(
@@ -111,13 +118,16 @@ impl FileSystem for RealFs {
     recursive: bool,
     mode: Option<u32>,
   ) -> FsResult<()> {
+    let path = long_path_buf(path);
     spawn_blocking(move || mkdir(&path, recursive, mode)).await?
   }
 
   fn chmod_sync(&self, path: &Path, mode: u32) -> FsResult<()> {
+    let path = &long_path(path);
     chmod(path, mode)
   }
   async fn chmod_async(&self, path: PathBuf, mode: u32) -> FsResult<()> {
+    let path = long_path_buf(path);
     spawn_blocking(move || chmod(&path, mode)).await?
   }
 
@@ -127,6 +137,7 @@ impl FileSystem for RealFs {
     uid: Option<u32>,
     gid: Option<u32>,
   ) -> FsResult<()> {
+    let path = &long_path(path);
     chown(path, uid, gid)
   }
   async fn chown_async(
@@ -135,68 +146,91 @@ impl FileSystem for RealFs {
     uid: Option<u32>,
     gid: Option<u32>,
   ) -> FsResult<()> {
+    let path = long_path_buf(path);
     spawn_blocking(move || chown(&path, uid, gid)).await?
   }
 
   fn remove_sync(&self, path: &Path, recursive: bool) -> FsResult<()> {
+    let path = &long_path(path);
     remove(path, recursive)
   }
   async fn remove_async(&self, path: PathBuf, recursive: bool) -> FsResult<()> {
+    let path = long_path_buf(path);
     spawn_blocking(move || remove(&path, recursive)).await?
   }
 
   fn copy_file_sync(&self, from: &Path, to: &Path) -> FsResult<()> {
+    let from = &long_path(from);
+    let to = &long_path(to);
     copy_file(from, to)
   }
   async fn copy_file_async(&self, from: PathBuf, to: PathBuf) -> FsResult<()> {
+    let from = long_path_buf(from);
+    let to = long_path_buf(to);
     spawn_blocking(move || copy_file(&from, &to)).await?
   }
 
   fn cp_sync(&self, fro: &Path, to: &Path) -> FsResult<()> {
+    let fro = &long_path(fro);
+    let to = &long_path(to);
     cp(fro, to)
   }
   async fn cp_async(&self, fro: PathBuf, to: PathBuf) -> FsResult<()> {
+    let fro = long_path_buf(fro);
+    let to = long_path_buf(to);
     spawn_blocking(move || cp(&fro, &to)).await?
   }
 
   fn stat_sync(&self, path: &Path) -> FsResult<FsStat> {
+    let path = &long_path(path);
     stat(path).map(Into::into)
   }
   async fn stat_async(&self, path: PathBuf) -> FsResult<FsStat> {
-    spawn_blocking(move || stat(&path)).await?.map(Into::into)
+    let path = long_path_buf(path);
+    io_backend().metadata(path, true).await
   }
 
   fn lstat_sync(&self, path: &Path) -> FsResult<FsStat> {
+    let path = &long_path(path);
     lstat(path).map(Into::into)
   }
   async fn lstat_async(&self, path: PathBuf) -> FsResult<FsStat> {
-    spawn_blocking(move || lstat(&path)).await?.map(Into::into)
+    let path = long_path_buf(path);
+    io_backend().metadata(path, false).await
   }
 
   fn exists_sync(&self, path: &Path) -> bool {
+    let path = &long_path(path);
     exists(path)
   }
   async fn exists_async(&self, path: PathBuf) -> FsResult<bool> {
+    let path = long_path_buf(path);
     spawn_blocking(move || exists(&path))
       .await
       .map_err(Into::into)
   }
 
   fn realpath_sync(&self, path: &Path) -> FsResult<PathBuf> {
+    let path = &long_path(path);
     realpath(path)
   }
   async fn realpath_async(&self, path: PathBuf) -> FsResult<PathBuf> {
+    let path = long_path_buf(path);
     spawn_blocking(move || realpath(&path)).await?
   }
 
   fn read_dir_sync(&self, path: &Path) -> FsResult<Vec<FsDirEntry>> {
+    let path = &long_path(path);
     read_dir(path)
   }
   async fn read_dir_async(&self, path: PathBuf) -> FsResult<Vec<FsDirEntry>> {
+    let path = long_path_buf(path);
     spawn_blocking(move || read_dir(&path)).await?
   }
 
   fn rename_sync(&self, oldpath: &Path, newpath: &Path) -> FsResult<()> {
+    let oldpath = &long_path(oldpath);
+    let newpath = &long_path(newpath);
     fs::rename(oldpath, newpath).map_err(Into::into)
   }
   async fn rename_async(
@@ -204,12 +238,16 @@ impl FileSystem for RealFs {
     oldpath: PathBuf,
     newpath: PathBuf,
   ) -> FsResult<()> {
+    let oldpath = long_path_buf(oldpath);
+    let newpath = long_path_buf(newpath);
     spawn_blocking(move || fs::rename(oldpath, newpath))
       .await?
       .map_err(Into::into)
   }
 
   fn link_sync(&self, oldpath: &Path, newpath: &Path) -> FsResult<()> {
+    let oldpath = &long_path(oldpath);
+    let newpath = &long_path(newpath);
     fs::hard_link(oldpath, newpath).map_err(Into::into)
   }
   async fn link_async(
@@ -217,6 +255,8 @@ impl FileSystem for RealFs {
     oldpath: PathBuf,
     newpath: PathBuf,
   ) -> FsResult<()> {
+    let oldpath = long_path_buf(oldpath);
+    let newpath = long_path_buf(newpath);
     spawn_blocking(move || fs::hard_link(oldpath, newpath))
       .await?
       .map_err(Into::into)
@@ -228,6 +268,7 @@ impl FileSystem for RealFs {
     newpath: &Path,
     file_type: Option<FsFileType>,
   ) -> FsResult<()> {
+    let newpath = &long_path(newpath);
     symlink(oldpath, newpath, file_type)
   }
   async fn symlink_async(
@@ -236,22 +277,27 @@ impl FileSystem for RealFs {
     newpath: PathBuf,
     file_type: Option<FsFileType>,
   ) -> FsResult<()> {
+    let newpath = long_path_buf(newpath);
     spawn_blocking(move || symlink(&oldpath, &newpath, file_type)).await?
   }
 
   fn read_link_sync(&self, path: &Path) -> FsResult<PathBuf> {
+    let path = &long_path(path);
     fs::read_link(path).map_err(Into::into)
   }
   async fn read_link_async(&self, path: PathBuf) -> FsResult<PathBuf> {
+    let path = long_path_buf(path);
     spawn_blocking(move || fs::read_link(path))
       .await?
       .map_err(Into::into)
   }
 
   fn truncate_sync(&self, path: &Path, len: u64) -> FsResult<()> {
+    let path = &long_path(path);
     truncate(path, len)
   }
   async fn truncate_async(&self, path: PathBuf, len: u64) -> FsResult<()> {
+    let path = long_path_buf(path);
     spawn_blocking(move || truncate(&path, len)).await?
   }
 
@@ -263,6 +309,7 @@ impl FileSystem for RealFs {
     mtime_secs: i64,
     mtime_nanos: u32,
   ) -> FsResult<()> {
+    let path = &long_path(path);
     let atime = filetime::FileTime::from_unix_time(atime_secs, atime_nanos);
     let mtime = filetime::FileTime::from_unix_time(mtime_secs, mtime_nanos);
     filetime::set_file_times(path, atime, mtime).map_err(Into::into)
@@ -275,6 +322,7 @@ impl FileSystem for RealFs {
     mtime_secs: i64,
     mtime_nanos: u32,
   ) -> FsResult<()> {
+    let path = long_path_buf(path);
     let atime = filetime::FileTime::from_unix_time(atime_secs, atime_nanos);
     let mtime = filetime::FileTime::from_unix_time(mtime_secs, mtime_nanos);
     spawn_blocking(move || {
@@ -291,6 +339,7 @@ impl FileSystem for RealFs {
     mtime_secs: i64,
     mtime_nanos: u32,
   ) -> FsResult<()> {
+    let path = &long_path(path);
     let atime = filetime::FileTime::from_unix_time(atime_secs, atime_nanos);
     let mtime = filetime::FileTime::from_unix_time(mtime_secs, mtime_nanos);
     filetime::set_symlink_file_times(path, atime, mtime).map_err(Into::into)
@@ -304,6 +353,7 @@ impl FileSystem for RealFs {
     mtime_secs: i64,
     mtime_nanos: u32,
   ) -> FsResult<()> {
+    let path = long_path_buf(path);
     let atime = filetime::FileTime::from_unix_time(atime_secs, atime_nanos);
     let mtime = filetime::FileTime::from_unix_time(mtime_secs, mtime_nanos);
     spawn_blocking(move || {
@@ -318,6 +368,7 @@ impl FileSystem for RealFs {
     uid: Option<u32>,
     gid: Option<u32>,
   ) -> FsResult<()> {
+    let path = &long_path(path);
     lchown(path, uid, gid)
   }
 
@@ -327,6 +378,7 @@ impl FileSystem for RealFs {
     uid: Option<u32>,
     gid: Option<u32>,
   ) -> FsResult<()> {
+    let path = long_path_buf(path);
     spawn_blocking(move || lchown(&path, uid, gid)).await?
   }
 
@@ -389,7 +441,9 @@ impl FileSystem for RealFs {
     path: PathBuf,
     access_check: Option<AccessCheckCb<'a>>,
   ) -> FsResult<Vec<u8>> {
-    let mut file = open_with_access_check(
+    // Permissions are checked while opening, before anything is submitted
+    // to the IO backend.
+    let file = open_with_access_check(
       OpenOptions {
         read: true,
         ..Default::default()
@@ -397,13 +451,7 @@ impl FileSystem for RealFs {
       &path,
       access_check,
     )?;
-    spawn_blocking(move || {
-      let mut buf = Vec::new();
-      file.read_to_end(&mut buf)?;
-      Ok::<_, FsError>(buf)
-    })
-    .await?
-    .map_err(Into::into)
+    io_backend().read_file(file).await
   }
 }
 
@@ -749,13 +797,13 @@ fn cp(from: &Path, to: &Path) -> FsResult<()> {
 }
 
 #[cfg(not(windows))]
-fn stat(path: &Path) -> FsResult<FsStat> {
+pub(crate) fn stat(path: &Path) -> FsResult<FsStat> {
   let metadata = fs::metadata(path)?;
   Ok(FsStat::from_std(metadata))
 }
 
 #[cfg(windows)]
-fn stat(path: &Path) -> FsResult<FsStat> {
+pub(crate) fn stat(path: &Path) -> FsResult<FsStat> {
   let metadata = fs::metadata(path)?;
   let mut fsstat = FsStat::from_std(metadata);
   use winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS;
@@ -765,13 +813,13 @@ fn stat(path: &Path) -> FsResult<FsStat> {
 }
 
 #[cfg(not(windows))]
-fn lstat(path: &Path) -> FsResult<FsStat> {
+pub(crate) fn lstat(path: &Path) -> FsResult<FsStat> {
   let metadata = fs::symlink_metadata(path)?;
   Ok(FsStat::from_std(metadata))
 }
 
 #[cfg(windows)]
-fn lstat(path: &Path) -> FsResult<FsStat> {
+pub(crate) fn lstat(path: &Path) -> FsResult<FsStat> {
   use winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS;
   use winapi::um::winbase::FILE_FLAG_OPEN_REPARSE_POINT;
 
@@ -1110,7 +1158,7 @@ fn open_with_access_check(
       }
     }
 
-    Ok(opts.open(&path)?)
+    Ok(opts.open(long_path(&path))?)
   } else {
     // for unix
     #[allow(unused_mut)]
@@ -1121,6 +1169,6 @@ fn open_with_access_check(
       use std::os::windows::fs::OpenOptionsExt;
       opts.custom_flags(winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS);
     }
-    Ok(opts.open(path)?)
+    Ok(opts.open(long_path(path))?)
   }
 }
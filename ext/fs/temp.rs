@@ -0,0 +1,381 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Temporary files and directories that are removed with their resource.
+//!
+//! A temp file is handed out as two resources: a regular file resource, so
+//! that everything `Deno.FsFile` does works on it, and a [`TempFileResource`]
+//! that owns the path. Closing or dropping the latter, which also happens
+//! when the runtime shuts down without closing it, removes the file; a temp
+//! dir is removed recursively. `persist()` disarms the cleanup and renames
+//! the file or dir to its final path, replacing a file that is already there.
+//!
+//! Cleanup is best effort. Failures, e.g. a file that is still open
+//! elsewhere on Windows, are logged and otherwise ignored.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_io::fs::File;
+use deno_io::fs::FileResource;
+use deno_io::fs::FsError;
+use rand::thread_rng;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::interface::FileSystem;
+use crate::ops::make_temp_check_async;
+use crate::ops::path_into_string;
+use crate::ops::strip_dir_prefix;
+use crate::ops::tmp_name;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+use crate::OpenOptions;
+
+const MAX_TRIES: u32 = 10;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempFileOptions {
+  dir: Option<String>,
+  prefix: Option<String>,
+  suffix: Option<String>,
+  mode: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempDirOptions {
+  dir: Option<String>,
+  prefix: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempFileInfo {
+  /// The file resource.
+  rid: ResourceId,
+  /// The [`TempFileResource`].
+  temp_rid: ResourceId,
+  path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempDirInfo {
+  rid: ResourceId,
+  path: String,
+}
+
+/// Owns the path of a temp file or dir until it is persisted. `None` once
+/// persisted.
+struct TempPath {
+  fs: FileSystemRc,
+  path: RefCell<Option<PathBuf>>,
+  is_dir: bool,
+}
+
+impl TempPath {
+  fn new(fs: FileSystemRc, path: PathBuf, is_dir: bool) -> Self {
+    Self {
+      fs,
+      path: RefCell::new(Some(path)),
+      is_dir,
+    }
+  }
+
+  fn persist(&self, to: &Path) -> Result<(), FsOpsError> {
+    let mut path = self.path.borrow_mut();
+    let Some(from) = path.as_ref() else {
+      return Err(FsOpsError::TempAlreadyPersisted);
+    };
+    self
+      .fs
+      .rename_sync(from, to)
+      .context_two_path("rename", from, to)?;
+    *path = None;
+    Ok(())
+  }
+}
+
+impl Drop for TempPath {
+  fn drop(&mut self) {
+    // The resource table is dropped with the runtime, so this also covers
+    // shutdown without an explicit close.
+    let Some(path) = self.path.get_mut().take() else {
+      return;
+    };
+    if let Err(err) = self.fs.remove_sync(&path, self.is_dir) {
+      if err.kind() != io::ErrorKind::NotFound {
+        let what = if self.is_dir { "directory" } else { "file" };
+        log::warn!(
+          "Failed to remove temporary {what} {}: {err}",
+          path.display()
+        );
+      }
+    }
+  }
+}
+
+pub struct TempFileResource(TempPath);
+
+impl Resource for TempFileResource {
+  fn name(&self) -> Cow<str> {
+    "tempFile".into()
+  }
+}
+
+pub struct TempDirResource(TempPath);
+
+impl Resource for TempDirResource {
+  fn name(&self) -> Cow<str> {
+    "tempDir".into()
+  }
+}
+
+async fn create_temp_file(
+  fs: &dyn FileSystem,
+  dir: &Path,
+  options: &TempFileOptions,
+) -> Result<(Rc<dyn File>, PathBuf), FsOpsError> {
+  let open_options = OpenOptions {
+    read: true,
+    write: true,
+    create_new: true,
+    mode: Some(options.mode.unwrap_or(0o600)),
+    ..Default::default()
+  };
+  let mut rng = thread_rng();
+  for _ in 0..MAX_TRIES {
+    let path = tmp_name(
+      &mut rng,
+      dir,
+      options.prefix.as_deref(),
+      options.suffix.as_deref(),
+    )?;
+    match fs.open_async(path.clone(), open_options, None).await {
+      Ok(file) => return Ok((file, path)),
+      Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+      Err(e) => return Err(e).context("tmpfile"),
+    }
+  }
+  Err(FsError::Io(io::Error::new(
+    io::ErrorKind::AlreadyExists,
+    "too many temp files exist",
+  )))
+  .context("tmpfile")
+}
+
+async fn create_temp_dir(
+  fs: &dyn FileSystem,
+  dir: &Path,
+  prefix: Option<&str>,
+) -> Result<PathBuf, FsOpsError> {
+  let mut rng = thread_rng();
+  for _ in 0..MAX_TRIES {
+    let path = tmp_name(&mut rng, dir, prefix, None)?;
+    match fs.mkdir_async(path.clone(), false, Some(0o700)).await {
+      Ok(()) => return Ok(path),
+      Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+      Err(e) => return Err(e).context("tmpdir"),
+    }
+  }
+  Err(FsError::Io(io::Error::new(
+    io::ErrorKind::AlreadyExists,
+    "too many temp dirs exist",
+  )))
+  .context("tmpdir")
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_tempfile_open<P>(
+  state: Rc<RefCell<OpState>>,
+  #[serde] options: Option<TempFileOptions>,
+) -> Result<TempFileInfo, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  state
+    .borrow()
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.createTempFile");
+  let (dir, fs) = make_temp_check_async::<P>(
+    state.clone(),
+    options.dir.as_deref(),
+    "Deno.createTempFile()",
+  )?;
+
+  let (file, path) = create_temp_file(&*fs, &dir, &options).await?;
+  // The resources own the file from here on, so it is removed even if the
+  // path can't be returned.
+  let (rid, temp_rid) = {
+    let mut state = state.borrow_mut();
+    let rid = state
+      .resource_table
+      .add(FileResource::new(file, "fsFile".to_string()));
+    let temp_rid = state.resource_table.add(TempFileResource(TempPath::new(
+      fs,
+      path.clone(),
+      false,
+    )));
+    (rid, temp_rid)
+  };
+  // PERMISSIONS: ensure the absolute path is not leaked
+  let path = strip_dir_prefix(&dir, options.dir.as_deref(), path)?;
+  Ok(TempFileInfo {
+    rid,
+    temp_rid,
+    path: path_into_string(path.into_os_string())?,
+  })
+}
+
+#[op2]
+pub fn op_tempfile_persist<P>(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] path: String,
+) -> Result<(), FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let to = state
+    .borrow_mut::<P>()
+    .check_write(&path, "Deno.TempFile.persist()")
+    .map_err(FsOpsError::Permission)?;
+  let resource = state
+    .resource_table
+    .get::<TempFileResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  resource.0.persist(&to)
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_tempdir_create<P>(
+  state: Rc<RefCell<OpState>>,
+  #[serde] options: Option<TempDirOptions>,
+) -> Result<TempDirInfo, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let options = options.unwrap_or_default();
+  state
+    .borrow()
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.createTempDir");
+  let (dir, fs) = make_temp_check_async::<P>(
+    state.clone(),
+    options.dir.as_deref(),
+    "Deno.createTempDir()",
+  )?;
+
+  let path = create_temp_dir(&*fs, &dir, options.prefix.as_deref()).await?;
+  // The resource owns the directory from here on, so it is removed even if
+  // the path can't be returned.
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add(TempDirResource(TempPath::new(fs, path.clone(), true)));
+  // PERMISSIONS: ensure the absolute path is not leaked
+  let path = strip_dir_prefix(&dir, options.dir.as_deref(), path)?;
+  Ok(TempDirInfo {
+    rid,
+    path: path_into_string(path.into_os_string())?,
+  })
+}
+
+#[op2]
+pub fn op_tempdir_persist<P>(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] path: String,
+) -> Result<(), FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let to = state
+    .borrow_mut::<P>()
+    .check_write(&path, "Deno.TempDir.persist()")
+    .map_err(FsOpsError::Permission)?;
+  let resource = state
+    .resource_table
+    .get::<TempDirResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  resource.0.persist(&to)
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::sync::MaybeArc;
+  use crate::RealFs;
+
+  fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap()
+      .block_on(future)
+  }
+
+  fn named(dir: &Path) -> (Rc<dyn File>, TempPath, PathBuf) {
+    let fs: FileSystemRc = MaybeArc::new(RealFs);
+    let (file, path) =
+      block_on(create_temp_file(&*fs, dir, &TempFileOptions::default()))
+        .unwrap();
+    let temp = TempPath::new(fs, path.clone(), false);
+    (file, temp, path)
+  }
+
+  #[test]
+  fn temp_file_removed_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let (file, temp, path) = named(dir.path());
+    assert!(path.exists());
+    drop(file);
+    drop(temp);
+    assert!(!path.exists());
+  }
+
+  #[test]
+  fn temp_file_persist_replaces_target() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("final.txt");
+    std::fs::write(&target, "old").unwrap();
+    let (file, temp, path) = named(dir.path());
+    file.write_all_sync(b"new").unwrap();
+    drop(file);
+    temp.persist(&target).unwrap();
+    assert!(matches!(
+      temp.persist(&target),
+      Err(FsOpsError::TempAlreadyPersisted)
+    ));
+    drop(temp);
+    assert!(!path.exists());
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+  }
+
+  #[test]
+  fn temp_dir_removed_recursively_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let fs: FileSystemRc = MaybeArc::new(RealFs);
+    let path =
+      block_on(create_temp_dir(&*fs, dir.path(), Some("scratch"))).unwrap();
+    std::fs::create_dir_all(path.join("a/b")).unwrap();
+    std::fs::write(path.join("a/b/c.txt"), "x").unwrap();
+    drop(TempPath::new(fs, path.clone(), true));
+    assert!(!path.exists());
+  }
+}
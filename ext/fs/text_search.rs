@@ -0,0 +1,930 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Regex and fixed string search over files and streams, for tools like
+//! linters and log scanners that would otherwise search line by line in JS.
+//!
+//! Directories are walked with the bounded walker of [`crate::walk_stream`]
+//! on the event loop, so `include` and `exclude` follow the same config file
+//! semantics. Files are read through the runtime's file system, a few at a
+//! time, and searched on the blocking pool.
+//! A stream is read from its resource in chunks and searched as complete
+//! lines arrive. Matches go to JS in batches through a resource, like the
+//! entries of `Deno.walkDir()`.
+//!
+//! By default every line is searched on its own, and a line is reported once
+//! even if the pattern matches it several times. With `multiline`, matches
+//! may span lines and each one is reported; the whole file or stream is then
+//! read into memory before it is searched, like ripgrep does.
+//!
+//! Files with a NUL byte in their first 8 KiB are considered binary and
+//! skipped, unless `binary` is set.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::futures::stream;
+use deno_core::futures::StreamExt;
+use deno_core::futures::TryStreamExt;
+use deno_core::op2;
+use deno_core::unsync::spawn;
+use deno_core::unsync::spawn_blocking;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_io::InterruptHandle;
+use deno_io::InterruptToken;
+use memchr::memchr;
+use memchr::memchr_iter;
+use memchr::memmem;
+use memchr::memrchr;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::hash_tree::MAX_PARALLEL_READS;
+use crate::interface::FileSystem;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::walk_stream::start_walk;
+use crate::walk_stream::FsWalkOptions;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+
+/// How much of a file is checked for NUL bytes.
+const BINARY_DETECTION_LEN: usize = 8 * 1024;
+/// How much is read from a stream at a time.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// Batches that may be waiting for JS before the search pauses.
+const QUEUED_BATCHES: usize = 4;
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TextSearchFlags {
+  case_insensitive: bool,
+  multiline: bool,
+  fixed_string: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct TextSearchContext {
+  before: usize,
+  after: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextSearchArgs {
+  paths: Option<Vec<String>>,
+  readable_rid: Option<ResourceId>,
+  pattern: String,
+  #[serde(default)]
+  flags: TextSearchFlags,
+  max_matches: Option<usize>,
+  #[serde(default)]
+  context: TextSearchContext,
+  #[serde(default)]
+  include: Vec<String>,
+  #[serde(default)]
+  exclude: Vec<String>,
+  include_matcher: Option<ResourceId>,
+  exclude_matcher: Option<ResourceId>,
+  /// Search files that look binary too.
+  #[serde(default)]
+  binary: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct TextSearchContextLines {
+  before: Vec<String>,
+  after: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextSearchMatch {
+  /// `None` when searching a stream.
+  path: Option<String>,
+  /// 1-based.
+  line_number: u64,
+  /// Where the match starts, from the start of the file or stream.
+  byte_offset: u64,
+  /// The line of the match, or all lines it spans, without the final line
+  /// break.
+  line_text: String,
+  context_lines: TextSearchContextLines,
+}
+
+type SearchBatch = Result<Vec<TextSearchMatch>, FsOpsError>;
+
+#[derive(Clone)]
+enum Matcher {
+  Fixed(memmem::Finder<'static>),
+  Regex(regex::bytes::Regex),
+}
+
+impl Matcher {
+  fn new(pattern: &str, flags: TextSearchFlags) -> Result<Self, FsOpsError> {
+    if flags.fixed_string && !flags.case_insensitive {
+      return Ok(Self::Fixed(
+        memmem::Finder::new(pattern.as_bytes()).into_owned(),
+      ));
+    }
+    let pattern = if flags.fixed_string {
+      Cow::Owned(regex::escape(pattern))
+    } else {
+      Cow::Borrowed(pattern)
+    };
+    regex::bytes::RegexBuilder::new(&pattern)
+      .case_insensitive(flags.case_insensitive)
+      .multi_line(true)
+      .build()
+      .map(Self::Regex)
+      .map_err(|err| FsOpsError::InvalidSearchPattern(err.to_string()))
+  }
+
+  fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+    match self {
+      Self::Fixed(finder) => finder
+        .find(&haystack[start..])
+        .map(|i| (start + i, start + i + finder.needle().len())),
+      Self::Regex(regex) => {
+        regex.find_at(haystack, start).map(|m| (m.start(), m.end()))
+      }
+    }
+  }
+
+  /// Returns up to `limit` matches in `region`. With `multiline`, these are
+  /// all non-overlapping matches. Otherwise matches don't span lines, and
+  /// only the first match of each line is returned.
+  fn find(
+    &self,
+    region: &[u8],
+    multiline: bool,
+    limit: usize,
+  ) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut pos = 0;
+    while matches.len() < limit && pos <= region.len() {
+      let Some((start, end)) = self.find_at(region, pos) else {
+        break;
+      };
+      // There is no line after the final line break.
+      if start == region.len() && region.last().map_or(true, |b| *b == b'\n') {
+        break;
+      }
+      if multiline {
+        matches.push((start, end));
+        // step over empty matches
+        pos = if end == start { end + 1 } else { end };
+        continue;
+      }
+      let line_start = memrchr(b'\n', &region[..start]).map_or(0, |i| i + 1);
+      let line_end =
+        memchr(b'\n', &region[start..]).map_or(region.len(), |i| start + i);
+      if end <= line_end {
+        matches.push((start, end));
+      } else if let Some((s, e)) = self.find_at(&region[..line_end], line_start)
+      {
+        // The leftmost match spans lines, but the line may still have a
+        // match of its own.
+        if e <= line_end {
+          matches.push((s, e));
+        }
+      }
+      pos = line_end + 1;
+    }
+    matches
+  }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+  memchr(0, &bytes[..bytes.len().min(BINARY_DETECTION_LEN)]).is_some()
+}
+
+fn line_text(line: &[u8]) -> String {
+  let line = line.strip_suffix(b"\r").unwrap_or(line);
+  String::from_utf8_lossy(line).into_owned()
+}
+
+/// Up to `n` lines before the line starting at `pos`, in order.
+fn lines_before(region: &[u8], pos: usize, n: usize) -> Vec<&[u8]> {
+  let mut lines = Vec::new();
+  let mut end = pos;
+  while lines.len() < n && end > 0 {
+    // `end - 1` is the line break of the previous line
+    let start = memrchr(b'\n', &region[..end - 1]).map_or(0, |i| i + 1);
+    lines.push(&region[start..end - 1]);
+    end = start;
+  }
+  lines.reverse();
+  lines
+}
+
+/// Up to `n` lines from the line starting at `pos` on.
+fn lines_after(region: &[u8], mut pos: usize, n: usize) -> Vec<&[u8]> {
+  let mut lines = Vec::new();
+  while lines.len() < n && pos < region.len() {
+    let end = memchr(b'\n', &region[pos..]).map_or(region.len(), |i| pos + i);
+    lines.push(&region[pos..end]);
+    pos = end + 1;
+  }
+  lines
+}
+
+struct PendingMatch {
+  search_match: TextSearchMatch,
+  /// Lines of context after the match that weren't searched yet.
+  after: usize,
+}
+
+/// Searches a file or stream that is fed to it in regions of complete lines,
+/// and collects the context of the matches across regions.
+struct LineSearcher<'a> {
+  matcher: &'a Matcher,
+  multiline: bool,
+  context: TextSearchContext,
+  path: Option<String>,
+  /// Matches that may still be reported.
+  remaining: usize,
+  /// The line number and offset of the start of the next region.
+  line_number: u64,
+  offset: u64,
+  /// The last lines of the previous regions, for the context before the
+  /// first matches of the next one.
+  before: VecDeque<String>,
+  /// Matches whose context after continues in the next region.
+  pending: Vec<PendingMatch>,
+}
+
+impl<'a> LineSearcher<'a> {
+  fn new(
+    matcher: &'a Matcher,
+    flags: TextSearchFlags,
+    context: TextSearchContext,
+    path: Option<String>,
+    max_matches: usize,
+  ) -> Self {
+    Self {
+      matcher,
+      multiline: flags.multiline,
+      context,
+      path,
+      remaining: max_matches,
+      line_number: 1,
+      offset: 0,
+      before: VecDeque::new(),
+      pending: Vec::new(),
+    }
+  }
+
+  /// Whether no more matches will be reported after the pending ones.
+  fn is_done(&self) -> bool {
+    self.remaining == 0
+  }
+
+  /// Searches `region`, which ends with a line break unless it is the last
+  /// one. Matches whose context is complete are added to `out`.
+  fn feed(&mut self, region: &[u8], out: &mut Vec<TextSearchMatch>) {
+    if !self.pending.is_empty() {
+      let head = lines_after(region, 0, self.context.after);
+      for pending in &mut self.pending {
+        let lines = &head[..pending.after.min(head.len())];
+        pending.after -= lines.len();
+        let after = &mut pending.search_match.context_lines.after;
+        after.extend(lines.iter().map(|line| line_text(line)));
+      }
+      self.flush(out, false);
+    }
+
+    let spans = if self.remaining > 0 {
+      self.matcher.find(region, self.multiline, self.remaining)
+    } else {
+      Vec::new()
+    };
+    let mut line_number = self.line_number;
+    let mut counted = 0;
+    for (start, end) in spans {
+      let line_start = memrchr(b'\n', &region[..start]).map_or(0, |i| i + 1);
+      line_number +=
+        memchr_iter(b'\n', &region[counted..line_start]).count() as u64;
+      counted = line_start;
+
+      // the line of the last byte of the match
+      let last = end.saturating_sub(1).max(start).min(region.len());
+      let line_end =
+        memchr(b'\n', &region[last..]).map_or(region.len(), |i| last + i);
+
+      let mut before = lines_before(region, line_start, self.context.before)
+        .into_iter()
+        .map(line_text)
+        .collect::<Vec<_>>();
+      if before.len() < self.context.before {
+        let missing = self.context.before - before.len();
+        let carried = self
+          .before
+          .iter()
+          .skip(self.before.len().saturating_sub(missing));
+        before.splice(0..0, carried.cloned());
+      }
+      let after = lines_after(region, line_end + 1, self.context.after)
+        .into_iter()
+        .map(line_text)
+        .collect::<Vec<_>>();
+      let missing_after = self.context.after - after.len();
+
+      self.pending.push(PendingMatch {
+        search_match: TextSearchMatch {
+          path: self.path.clone(),
+          line_number,
+          byte_offset: self.offset + start as u64,
+          line_text: line_text(&region[line_start..line_end]),
+          context_lines: TextSearchContextLines { before, after },
+        },
+        after: missing_after,
+      });
+      self.remaining -= 1;
+    }
+    self.flush(out, false);
+
+    self.line_number =
+      line_number + memchr_iter(b'\n', &region[counted..]).count() as u64;
+    self.offset += region.len() as u64;
+    if self.context.before > 0 && region.last() == Some(&b'\n') {
+      let last_lines = lines_before(region, region.len(), self.context.before);
+      let keep = self.context.before - last_lines.len();
+      while self.before.len() > keep {
+        self.before.pop_front();
+      }
+      self.before.extend(last_lines.into_iter().map(line_text));
+    }
+  }
+
+  /// Reports the pending matches, with the context after them cut short at
+  /// the end of the file or stream.
+  fn finish(mut self, out: &mut Vec<TextSearchMatch>) {
+    self.flush(out, true);
+  }
+
+  fn flush(&mut self, out: &mut Vec<TextSearchMatch>, all: bool) {
+    // Keep the matches in order: a match with incomplete context holds back
+    // the ones after it.
+    let complete = if all {
+      self.pending.len()
+    } else {
+      self
+        .pending
+        .iter()
+        .position(|pending| pending.after > 0)
+        .unwrap_or(self.pending.len())
+    };
+    out.extend(
+      self
+        .pending
+        .drain(..complete)
+        .map(|pending| pending.search_match),
+    );
+  }
+}
+
+/// Feeds the chunks of a stream to a [`LineSearcher`]. Without `multiline`,
+/// complete lines are searched as they arrive; otherwise the whole stream is
+/// buffered.
+struct StreamSearcher<'a> {
+  searcher: LineSearcher<'a>,
+  buf: Vec<u8>,
+}
+
+impl<'a> StreamSearcher<'a> {
+  fn push(&mut self, chunk: &[u8], out: &mut Vec<TextSearchMatch>) {
+    self.buf.extend_from_slice(chunk);
+    if self.searcher.multiline {
+      return;
+    }
+    // Only the new chunk can hold the last line break.
+    let searched = self.buf.len() - chunk.len();
+    if let Some(i) = memrchr(b'\n', &self.buf[searched..]) {
+      let end = searched + i + 1;
+      self.searcher.feed(&self.buf[..end], out);
+      self.buf.drain(..end);
+    }
+  }
+
+  fn finish(mut self, out: &mut Vec<TextSearchMatch>) {
+    if !self.buf.is_empty() {
+      self.searcher.feed(&self.buf, out);
+    }
+    self.searcher.finish(out);
+  }
+}
+
+#[derive(Clone)]
+struct FileSearch {
+  matcher: Matcher,
+  flags: TextSearchFlags,
+  context: TextSearchContext,
+  binary: bool,
+}
+
+impl FileSearch {
+  /// Returns `None` if the file was skipped because it is binary.
+  fn search_bytes(
+    &self,
+    path: &Path,
+    bytes: &[u8],
+    max_matches: usize,
+  ) -> Option<Vec<TextSearchMatch>> {
+    if !self.binary && is_binary(bytes) {
+      return None;
+    }
+    let mut searcher = LineSearcher::new(
+      &self.matcher,
+      self.flags,
+      self.context,
+      Some(path.to_string_lossy().into_owned()),
+      max_matches,
+    );
+    let mut matches = Vec::new();
+    searcher.feed(bytes, &mut matches);
+    searcher.finish(&mut matches);
+    Some(matches)
+  }
+
+  /// Reads `path` and searches it on the blocking pool. Returns `None` if
+  /// the file was skipped because it is binary.
+  async fn search_file(
+    &self,
+    fs: &dyn FileSystem,
+    path: PathBuf,
+    max_matches: usize,
+  ) -> Result<Option<Vec<TextSearchMatch>>, FsOpsError> {
+    let bytes = fs
+      .read_file_async(path.clone(), None)
+      .await
+      .context_path("readfile", &path)?;
+    let search = self.clone();
+    spawn_blocking(move || search.search_bytes(&path, &bytes, max_matches))
+      .await
+      .map_err(|err| FsOpsError::Other(err.into()))
+  }
+
+  /// Searches the files of `roots`, walking the directories among them.
+  async fn run(
+    &self,
+    fs: FileSystemRc,
+    roots: Vec<PathBuf>,
+    walk_options: &FsWalkOptions,
+    mut remaining: usize,
+    sender: &mpsc::Sender<SearchBatch>,
+    interrupt: &InterruptToken,
+  ) -> Result<(), FsOpsError> {
+    for root in roots {
+      if remaining == 0 {
+        break;
+      }
+      interrupt.check()?;
+      if !fs.stat_async(root.clone()).await?.is_directory {
+        let matches = self
+          .search_file(&*fs, root, remaining)
+          .await?
+          .unwrap_or_default();
+        remaining -= matches.len();
+        if !send(sender, matches).await {
+          return Ok(());
+        }
+      } else {
+        let mut walk = start_walk(fs.clone(), root, walk_options)?;
+        while let Some(entries) = walk.recv().await {
+          let fs = &*fs;
+          let results = stream::iter(entries?)
+            .map(|entry| async move {
+              interrupt.check()?;
+              let path = PathBuf::from(entry.path);
+              // Files can go away while the tree is walked.
+              let matches = self.search_file(fs, path, remaining).await;
+              Ok::<_, FsOpsError>(matches.ok().flatten())
+            })
+            .buffered(MAX_PARALLEL_READS)
+            .try_collect::<Vec<_>>()
+            .await?;
+          let mut matches =
+            results.into_iter().flatten().flatten().collect::<Vec<_>>();
+          matches.truncate(remaining);
+          remaining -= matches.len();
+          if !send(sender, matches).await || remaining == 0 {
+            return Ok(());
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Returns `false` once nobody is listening anymore.
+async fn send(
+  sender: &mpsc::Sender<SearchBatch>,
+  matches: Vec<TextSearchMatch>,
+) -> bool {
+  matches.is_empty() || sender.send(Ok(matches)).await.is_ok()
+}
+
+async fn search_stream(
+  resource: Rc<dyn Resource>,
+  search: FileSearch,
+  max_matches: usize,
+  sender: mpsc::Sender<SearchBatch>,
+  cancel: Rc<CancelHandle>,
+  interrupt: InterruptToken,
+) -> Result<(), FsOpsError> {
+  let mut stream = StreamSearcher {
+    searcher: LineSearcher::new(
+      &search.matcher,
+      search.flags,
+      search.context,
+      None,
+      max_matches,
+    ),
+    buf: Vec::new(),
+  };
+  let mut first = true;
+  loop {
+    interrupt.check()?;
+    let chunk = resource
+      .clone()
+      .read(STREAM_CHUNK_SIZE)
+      .or_cancel(cancel.clone())
+      .await?
+      .map_err(FsOpsError::Other)?;
+    if chunk.is_empty() {
+      break;
+    }
+    if first && !search.binary && is_binary(&chunk) {
+      return Ok(());
+    }
+    first = false;
+    let mut matches = Vec::new();
+    stream.push(&chunk, &mut matches);
+    let done = stream.searcher.is_done() && stream.searcher.pending.is_empty();
+    if !matches.is_empty() && sender.send(Ok(matches)).await.is_err() {
+      return Ok(());
+    }
+    if done {
+      return Ok(());
+    }
+  }
+  let mut matches = Vec::new();
+  stream.finish(&mut matches);
+  if !matches.is_empty() {
+    let _ = sender.send(Ok(matches)).await;
+  }
+  Ok(())
+}
+
+struct TextSearchResource {
+  receiver: AsyncRefCell<mpsc::Receiver<SearchBatch>>,
+  cancel: Rc<CancelHandle>,
+}
+
+impl Resource for TextSearchResource {
+  fn name(&self) -> Cow<str> {
+    "textSearch".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+/// Starts searching the files of `paths`, or the stream `readableRid`, and
+/// returns the resource to read the matches from.
+#[op2]
+#[smi]
+pub fn op_text_search<P>(
+  state: &mut OpState,
+  #[serde] args: TextSearchArgs,
+) -> Result<ResourceId, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.searchText");
+  let search = FileSearch {
+    matcher: Matcher::new(&args.pattern, args.flags)?,
+    flags: args.flags,
+    context: args.context,
+    binary: args.binary,
+  };
+  let max_matches = args.max_matches.unwrap_or(usize::MAX);
+  let interrupt = InterruptHandle::token_from_state(state);
+  let (sender, receiver) = mpsc::channel(QUEUED_BATCHES);
+  let cancel = CancelHandle::new_rc();
+
+  match (args.paths, args.readable_rid) {
+    (Some(paths), None) => {
+      let roots = paths
+        .iter()
+        .map(|path| {
+          state
+            .borrow_mut::<P>()
+            .check_read(path, "Deno.searchText()")
+            .map_err(FsOpsError::Permission)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+      let mut walk_options = FsWalkOptions::files(
+        args.include,
+        args.exclude,
+        args.include_matcher,
+        args.exclude_matcher,
+      );
+      walk_options.compile_patterns(state)?;
+      let fs = state.borrow::<FileSystemRc>().clone();
+      spawn(async move {
+        let result = search
+          .run(fs, roots, &walk_options, max_matches, &sender, &interrupt)
+          .await;
+        if let Err(err) = result {
+          let _ = sender.send(Err(err)).await;
+        }
+      });
+    }
+    (None, Some(rid)) => {
+      let resource = state
+        .resource_table
+        .get_any(rid)
+        .map_err(FsOpsError::Resource)?;
+      let cancel = cancel.clone();
+      spawn(async move {
+        let result = search_stream(
+          resource,
+          search,
+          max_matches,
+          sender.clone(),
+          cancel,
+          interrupt,
+        )
+        .await;
+        if let Err(err) = result {
+          let _ = sender.send(Err(err)).await;
+        }
+      });
+    }
+    _ => return Err(FsOpsError::InvalidSearchInput),
+  }
+
+  Ok(state.resource_table.add(TextSearchResource {
+    receiver: AsyncRefCell::new(receiver),
+    cancel,
+  }))
+}
+
+/// Resolves to `null` once the search is done.
+#[op2(async)]
+#[serde]
+pub async fn op_text_search_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<Vec<TextSearchMatch>>, FsOpsError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<TextSearchResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  let mut receiver = RcRef::map(&resource, |r| &r.receiver).borrow_mut().await;
+  let cancel = resource.cancel.clone();
+  receiver.recv().or_cancel(cancel).await?.transpose()
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::sync::MaybeArc;
+  use crate::RealFs;
+
+  fn flags(multiline: bool) -> TextSearchFlags {
+    TextSearchFlags {
+      multiline,
+      ..Default::default()
+    }
+  }
+
+  fn context(before: usize, after: usize) -> TextSearchContext {
+    TextSearchContext { before, after }
+  }
+
+  fn search(
+    text: &str,
+    pattern: &str,
+    flags: TextSearchFlags,
+    context: TextSearchContext,
+  ) -> Vec<TextSearchMatch> {
+    let matcher = Matcher::new(pattern, flags).unwrap();
+    let mut searcher =
+      LineSearcher::new(&matcher, flags, context, None, usize::MAX);
+    let mut matches = Vec::new();
+    searcher.feed(text.as_bytes(), &mut matches);
+    searcher.finish(&mut matches);
+    matches
+  }
+
+  fn search_chunked(
+    text: &str,
+    pattern: &str,
+    flags: TextSearchFlags,
+    context: TextSearchContext,
+    chunk_size: usize,
+  ) -> Vec<TextSearchMatch> {
+    let matcher = Matcher::new(pattern, flags).unwrap();
+    let mut stream = StreamSearcher {
+      searcher: LineSearcher::new(&matcher, flags, context, None, usize::MAX),
+      buf: Vec::new(),
+    };
+    let mut matches = Vec::new();
+    for chunk in text.as_bytes().chunks(chunk_size) {
+      stream.push(chunk, &mut matches);
+    }
+    stream.finish(&mut matches);
+    matches
+  }
+
+  fn summary(matches: &[TextSearchMatch]) -> Vec<(u64, u64, &str)> {
+    matches
+      .iter()
+      .map(|m| (m.line_number, m.byte_offset, m.line_text.as_str()))
+      .collect()
+  }
+
+  const TEXT: &str =
+    "alpha\nbegin one\nmiddle\nend\nbeta begin\nend two\r\nomega";
+
+  #[test]
+  fn test_multiline_matches_across_chunks() {
+    let pattern = r"begin.*\n(?:.*\n)?end";
+    let expected = search(TEXT, pattern, flags(true), context(1, 1));
+    assert_eq!(
+      summary(&expected),
+      [
+        (2, 6, "begin one\nmiddle\nend"),
+        (5, 34, "beta begin\nend two"),
+      ]
+    );
+    assert_eq!(expected[0].context_lines.before, ["alpha"]);
+    assert_eq!(expected[0].context_lines.after, ["beta begin"]);
+    assert_eq!(expected[1].context_lines.after, ["omega"]);
+    for chunk_size in 1..=TEXT.len() {
+      assert_eq!(
+        search_chunked(TEXT, pattern, flags(true), context(1, 1), chunk_size),
+        expected,
+        "chunk size {chunk_size}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_line_matches_across_chunks() {
+    let expected = search(TEXT, "end", flags(false), context(2, 2));
+    assert_eq!(summary(&expected), [(4, 30, "end"), (6, 45, "end two"),]);
+    for chunk_size in 1..=TEXT.len() {
+      assert_eq!(
+        search_chunked(TEXT, "end", flags(false), context(2, 2), chunk_size),
+        expected,
+        "chunk size {chunk_size}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_context_at_file_edges() {
+    let text = "match first\na\nb\nmatch last";
+    let matches = search(text, "^match", flags(false), context(3, 3));
+    assert_eq!(matches.len(), 2);
+    assert_eq!(
+      matches[0].context_lines,
+      TextSearchContextLines {
+        before: vec![],
+        after: vec!["a".into(), "b".into(), "match last".into()],
+      }
+    );
+    assert_eq!(
+      matches[1].context_lines,
+      TextSearchContextLines {
+        before: vec!["match first".into(), "a".into(), "b".into()],
+        after: vec![],
+      }
+    );
+
+    // a trailing line break doesn't add an empty line
+    let matches = search("x\ny\n", "y", flags(false), context(0, 2));
+    assert!(matches[0].context_lines.after.is_empty());
+    assert!(search("x\n", "^", flags(false), context(0, 0)).len() == 1);
+    assert!(search("", "^", flags(true), context(0, 0)).is_empty());
+  }
+
+  #[test]
+  fn test_line_mode_reports_lines_once() {
+    // The leftmost match spans two lines, so the second line is searched
+    // on its own.
+    let matches =
+      search("a\nb a  b b\n", r"a\s+b", flags(false), context(0, 0));
+    assert_eq!(summary(&matches), [(2, 4, "b a  b b")]);
+
+    let matches = search("xx\nx\n", "x", flags(false), context(0, 0));
+    assert_eq!(summary(&matches), [(1, 0, "xx"), (2, 3, "x")]);
+  }
+
+  #[test]
+  fn test_fixed_strings() {
+    let fixed = TextSearchFlags {
+      fixed_string: true,
+      ..Default::default()
+    };
+    let text = "a.b\naxb\nA.B\n";
+    assert_eq!(
+      summary(&search(text, "a.b", fixed, context(0, 0))),
+      [(1, 0, "a.b")]
+    );
+    let fixed_insensitive = TextSearchFlags {
+      case_insensitive: true,
+      ..fixed
+    };
+    assert_eq!(
+      summary(&search(text, "a.b", fixed_insensitive, context(0, 0))),
+      [(1, 0, "a.b"), (3, 8, "A.B")]
+    );
+    assert!(matches!(
+      Matcher::new("(", flags(false)),
+      Err(FsOpsError::InvalidSearchPattern(_))
+    ));
+  }
+
+  #[test]
+  fn test_search_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src/nested")).unwrap();
+    std::fs::write(root.join("src/a.ts"), "// TODO one\nx\n").unwrap();
+    std::fs::write(root.join("src/nested/b.ts"), "y\n// TODO two\n").unwrap();
+    std::fs::write(root.join("src/c.js"), "// TODO js\n").unwrap();
+    std::fs::write(root.join("src/d.ts"), b"TODO\0binary").unwrap();
+
+    let run = |binary: bool, max_matches: usize| {
+      let search = FileSearch {
+        matcher: Matcher::new("TODO", flags(false)).unwrap(),
+        flags: flags(false),
+        context: context(0, 1),
+        binary,
+      };
+      let options =
+        FsWalkOptions::files(vec!["**/*.ts".into()], vec![], None, None);
+      let root = root.to_path_buf();
+      let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+      runtime.block_on(async move {
+        let (sender, mut receiver) = mpsc::channel(QUEUED_BATCHES);
+        let task = spawn(async move {
+          let fs: FileSystemRc = MaybeArc::new(RealFs);
+          let interrupt = InterruptToken::default();
+          search
+            .run(fs, vec![root], &options, max_matches, &sender, &interrupt)
+            .await
+        });
+        let mut lines = Vec::new();
+        while let Some(batch) = receiver.recv().await {
+          for m in batch.unwrap() {
+            lines.push(format!("{} {:?}", m.line_text, m.context_lines.after));
+          }
+        }
+        task.await.unwrap().unwrap();
+        lines.sort();
+        lines
+      })
+    };
+
+    assert_eq!(
+      run(false, usize::MAX),
+      ["// TODO one [\"x\"]", "// TODO two []"]
+    );
+    assert_eq!(run(true, usize::MAX).len(), 3);
+    assert_eq!(run(false, 1).len(), 1);
+
+    let mut options =
+      FsWalkOptions::files(vec!["[".into()], vec![], None, None);
+    assert!(matches!(
+      options.compile_patterns(&mut OpState::new(None)),
+      Err(FsOpsError::InvalidGlobPattern(..))
+    ));
+  }
+}
@@ -0,0 +1,803 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Recursive removal and copying of directory trees, with progress events
+//! and cancellation.
+//!
+//! Both operations walk the whole tree first, so that progress events can
+//! carry the total number of entries, and then process the entries through
+//! the runtime's [`FileSystem`] in chunks of [`CHUNK_SIZE`]. After every
+//! chunk they push a progress event into an [`FsProgressQueue`] if the caller
+//! passed one. Before every chunk they check the cancel handle, and stop
+//! cleanly if it was canceled, and the runtime's interrupt handle, which fails
+//! the operation with `Interrupted` like `Deno.hashTree()`.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::future::Future;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Poll;
+use std::task::Waker;
+
+use deno_core::futures::future::select;
+use deno_core::futures::future::Either;
+use deno_core::op2;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_io::fs::FsResult;
+use deno_io::InterruptHandle;
+use deno_io::InterruptToken;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::interface::FileSystem;
+use crate::interface::FsFileType;
+use crate::ops::FsOpsError;
+use crate::ops::MapErrContext;
+use crate::walk::walk_dir_recursive_async;
+use crate::walk::WalkEntryKind;
+use crate::walk::WalkError;
+use crate::walk::WalkOptions;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+
+/// How many entries are processed between two progress events and
+/// cancellation checks.
+const CHUNK_SIZE: usize = 64;
+
+/// Progress events kept until JS receives them. When JS falls behind, the
+/// oldest event is dropped. Events carry cumulative counts, so only
+/// intermediate updates are lost.
+const PROGRESS_QUEUE_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsProgress {
+  /// Entries removed or copied so far.
+  processed: u64,
+  /// All entries of the tree, including the root.
+  total: u64,
+  /// The last entry that was processed.
+  current_path: String,
+}
+
+#[derive(Default)]
+struct ProgressQueueInner {
+  events: VecDeque<FsProgress>,
+  finished: bool,
+  waker: Option<Waker>,
+}
+
+/// Progress events of one tree operation, which JS receives with
+/// `op_fs_progress_next`.
+#[derive(Default)]
+pub struct FsProgressQueue(Rc<RefCell<ProgressQueueInner>>);
+
+impl FsProgressQueue {
+  fn sender(&self) -> ProgressSender {
+    ProgressSender(self.0.clone())
+  }
+
+  /// The next event, or `None` once the operation finished and every event
+  /// was received.
+  async fn next(&self) -> Option<FsProgress> {
+    poll_fn(|cx| {
+      let mut inner = self.0.borrow_mut();
+      if let Some(progress) = inner.events.pop_front() {
+        return Poll::Ready(Some(progress));
+      }
+      if inner.finished {
+        return Poll::Ready(None);
+      }
+      inner.waker = Some(cx.waker().clone());
+      Poll::Pending
+    })
+    .await
+  }
+}
+
+impl Resource for FsProgressQueue {
+  fn name(&self) -> Cow<str> {
+    "fsProgress".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.sender().finish();
+  }
+}
+
+#[derive(Clone)]
+struct ProgressSender(Rc<RefCell<ProgressQueueInner>>);
+
+impl ProgressSender {
+  fn push(&self, progress: FsProgress) {
+    let mut inner = self.0.borrow_mut();
+    if inner.events.len() >= PROGRESS_QUEUE_CAPACITY {
+      inner.events.pop_front();
+    }
+    inner.events.push_back(progress);
+    if let Some(waker) = inner.waker.take() {
+      waker.wake();
+    }
+  }
+
+  fn finish(&self) {
+    let mut inner = self.0.borrow_mut();
+    inner.finished = true;
+    if let Some(waker) = inner.waker.take() {
+      waker.wake();
+    }
+  }
+}
+
+type ProgressFn = dyn Fn(FsProgress);
+
+/// Reports progress and decides whether a tree operation continues.
+struct TreeOpControl {
+  total: u64,
+  on_progress: Option<Box<ProgressFn>>,
+  canceled: Rc<Cell<bool>>,
+  interrupt: InterruptToken,
+}
+
+impl TreeOpControl {
+  /// Returns `true` if the operation should stop because it was canceled.
+  fn is_canceled(&self) -> Result<bool, FsOpsError> {
+    self.interrupt.check()?;
+    Ok(self.canceled.get())
+  }
+
+  fn report(&self, processed: usize, current_path: &Path) {
+    if let Some(on_progress) = &self.on_progress {
+      on_progress(FsProgress {
+        processed: processed as u64,
+        total: self.total,
+        current_path: current_path.to_string_lossy().into_owned(),
+      });
+    }
+  }
+}
+
+fn map_walk_error(err: WalkError) -> FsOpsError {
+  match err {
+    WalkError::Visit(err) => match err.downcast::<FsOpsError>() {
+      Ok(err) => err,
+      Err(err) => FsOpsError::Other(err),
+    },
+    err => FsOpsError::Walk(err),
+  }
+}
+
+struct TreeEntry {
+  path: PathBuf,
+  kind: WalkEntryKind,
+}
+
+/// Collects `root` and everything below it, parents before their entries. If
+/// `root` isn't a directory, it is the only entry.
+async fn collect_tree(
+  fs: &dyn FileSystem,
+  root: &Path,
+  follow_symlinks: bool,
+  interrupt: &InterruptToken,
+) -> Result<Vec<TreeEntry>, FsOpsError> {
+  let stat = fs
+    .lstat_async(root.to_path_buf())
+    .await
+    .context_path("lstat", root)?;
+  let is_dir = stat.is_directory
+    || (follow_symlinks
+      && stat.is_symlink
+      && fs
+        .stat_async(root.to_path_buf())
+        .await
+        .is_ok_and(|stat| stat.is_directory));
+  if !is_dir {
+    let kind = if stat.is_symlink {
+      WalkEntryKind::Symlink
+    } else {
+      WalkEntryKind::File
+    };
+    return Ok(vec![TreeEntry {
+      path: root.to_path_buf(),
+      kind,
+    }]);
+  }
+
+  let options = WalkOptions {
+    follow_symlinks,
+    filter_dir: None,
+  };
+  let mut entries = Vec::new();
+  walk_dir_recursive_async(fs, root, &options, &mut |path, kind| {
+    interrupt.check().map_err(FsOpsError::from)?;
+    entries.push(TreeEntry {
+      path: path.to_path_buf(),
+      kind,
+    });
+    Ok(())
+  })
+  .await
+  .map_err(map_walk_error)?;
+  Ok(entries)
+}
+
+/// Runs `task`. When `cancel_handle` is canceled, `canceled` is set, so that
+/// the task stops after its current chunk, and the task's result is still
+/// returned.
+async fn run_cancelable<T>(
+  task: impl Future<Output = Result<T, FsOpsError>>,
+  cancel_handle: Option<Rc<CancelHandle>>,
+  canceled: Rc<Cell<bool>>,
+) -> Result<T, FsOpsError> {
+  let task = pin!(task);
+  match cancel_handle {
+    Some(cancel_handle) => {
+      let on_cancel = std::future::pending::<()>().or_cancel(cancel_handle);
+      match select(task, pin!(on_cancel)).await {
+        Either::Left((result, _)) => result,
+        Either::Right((_, task)) => {
+          canceled.set(true);
+          task.await
+        }
+      }
+    }
+    None => task.await,
+  }
+}
+
+/// Looks up the optional progress queue and cancel handle of a tree
+/// operation.
+fn get_control_resources(
+  state: &OpState,
+  progress_rid: Option<ResourceId>,
+  cancel_rid: Option<ResourceId>,
+) -> Result<(Option<Rc<FsProgressQueue>>, Option<Rc<CancelHandle>>), FsOpsError>
+{
+  let progress = progress_rid
+    .map(|rid| state.resource_table.get::<FsProgressQueue>(rid))
+    .transpose()
+    .map_err(FsOpsError::Resource)?;
+  let cancel_handle = cancel_rid
+    .and_then(|rid| state.resource_table.get::<CancelHandle>(rid).ok());
+  Ok((progress, cancel_handle))
+}
+
+fn progress_fn(
+  progress: &Option<Rc<FsProgressQueue>>,
+) -> Option<Box<ProgressFn>> {
+  let sender = progress.as_ref()?.sender();
+  Some(Box::new(move |event| sender.push(event)))
+}
+
+#[op2(fast)]
+#[smi]
+pub fn op_fs_progress_new(state: &mut OpState) -> ResourceId {
+  state.resource_table.add(FsProgressQueue::default())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_progress_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<FsProgress>, FsOpsError> {
+  let queue = state
+    .borrow()
+    .resource_table
+    .get::<FsProgressQueue>(rid)
+    .map_err(FsOpsError::Resource)?;
+  Ok(queue.next().await)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoveTreeOptions {
+  progress_rid: Option<ResourceId>,
+  cancel_rid: Option<ResourceId>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveTreeResult {
+  /// Entries that were removed, including the root if it was.
+  removed: u64,
+  canceled: bool,
+}
+
+/// Removes the entries deepest first, so that directories are empty when
+/// they are removed.
+async fn remove_entries(
+  fs: &dyn FileSystem,
+  entries: &[TreeEntry],
+  control: &TreeOpControl,
+) -> Result<RemoveTreeResult, FsOpsError> {
+  let mut result = RemoveTreeResult::default();
+  let mut processed = 0;
+  for chunk in entries.rchunks(CHUNK_SIZE) {
+    if control.is_canceled()? {
+      result.canceled = true;
+      break;
+    }
+    for entry in chunk.iter().rev() {
+      // Symlinks to directories on Windows are removed like directories.
+      fs.remove_async(entry.path.clone(), false)
+        .await
+        .context_path("remove", &entry.path)?;
+      result.removed += 1;
+    }
+    processed += chunk.len();
+    control.report(processed, &chunk[0].path);
+  }
+  Ok(result)
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_remove_tree<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[serde] options: RemoveTreeOptions,
+) -> Result<RemoveTreeResult, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let (fs, root, interrupt, progress, cancel_handle) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.remove");
+    let root = state
+      .borrow_mut::<P>()
+      .check_write(&path, "Deno.remove()")
+      .map_err(FsOpsError::Permission)?;
+    let (progress, cancel_handle) =
+      get_control_resources(&state, options.progress_rid, options.cancel_rid)?;
+    (
+      state.borrow::<FileSystemRc>().clone(),
+      root,
+      InterruptHandle::token_from_state(&state),
+      progress,
+      cancel_handle,
+    )
+  };
+
+  let canceled = Rc::new(Cell::new(false));
+  let control_canceled = canceled.clone();
+  let on_progress = progress_fn(&progress);
+  let result = run_cancelable(
+    async {
+      let entries = collect_tree(&*fs, &root, false, &interrupt).await?;
+      let control = TreeOpControl {
+        total: entries.len() as u64,
+        on_progress,
+        canceled: control_canceled,
+        interrupt,
+      };
+      remove_entries(&*fs, &entries, &control).await
+    },
+    cancel_handle,
+    canceled,
+  )
+  .await;
+  // also when the operation failed, so that JS stops waiting for events
+  if let Some(progress) = progress {
+    progress.sender().finish();
+  }
+  result
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkMode {
+  /// Copy symlinks as symlinks with the same target.
+  #[default]
+  Preserve,
+  /// Copy the files and directories symlinks point to.
+  Follow,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CopyDirOptions {
+  symlinks: SymlinkMode,
+  progress_rid: Option<ResourceId>,
+  cancel_rid: Option<ResourceId>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyDirResult {
+  /// Directories that were created, including the destination.
+  dirs: u64,
+  files: u64,
+  symlinks: u64,
+  /// The total size of the files that were copied.
+  bytes: u64,
+  canceled: bool,
+}
+
+/// Copies a symlink with the same target. Windows needs to know whether the
+/// link points to a directory.
+async fn copy_symlink(
+  fs: &dyn FileSystem,
+  link: &Path,
+  target: &Path,
+) -> FsResult<()> {
+  let link_target = fs.read_link_async(link.to_path_buf()).await?;
+  let is_dir = fs
+    .stat_async(link.to_path_buf())
+    .await
+    .is_ok_and(|stat| stat.is_directory);
+  let file_type = if is_dir {
+    FsFileType::Directory
+  } else {
+    FsFileType::File
+  };
+  fs.symlink_async(link_target, target.to_path_buf(), Some(file_type))
+    .await
+}
+
+async fn copy_entry(
+  fs: &dyn FileSystem,
+  entry: &TreeEntry,
+  target: &Path,
+  result: &mut CopyDirResult,
+) -> FsResult<()> {
+  match entry.kind {
+    WalkEntryKind::Dir => {
+      fs.mkdir_async(target.to_path_buf(), true, None).await?;
+      result.dirs += 1;
+    }
+    WalkEntryKind::File => {
+      fs.copy_file_async(entry.path.clone(), target.to_path_buf())
+        .await?;
+      result.files += 1;
+      result.bytes += fs.stat_async(target.to_path_buf()).await?.size;
+    }
+    WalkEntryKind::Symlink => {
+      copy_symlink(fs, &entry.path, target).await?;
+      result.symlinks += 1;
+    }
+  }
+  Ok(())
+}
+
+/// Copies the entries below `from` to the same place below `to`, parents
+/// first. Existing files are overwritten.
+async fn copy_entries(
+  fs: &dyn FileSystem,
+  from: &Path,
+  to: &Path,
+  entries: &[TreeEntry],
+  control: &TreeOpControl,
+) -> Result<CopyDirResult, FsOpsError> {
+  let mut result = CopyDirResult::default();
+  let mut processed = 0;
+  for chunk in entries.chunks(CHUNK_SIZE) {
+    if control.is_canceled()? {
+      result.canceled = true;
+      break;
+    }
+    for entry in chunk {
+      let target = to.join(entry.path.strip_prefix(from)?);
+      copy_entry(fs, entry, &target, &mut result)
+        .await
+        .context_two_path("copy", &entry.path, &target)?;
+    }
+    processed += chunk.len();
+    control.report(processed, &chunk[chunk.len() - 1].path);
+  }
+  Ok(result)
+}
+
+/// Checks read access to entries that were reached through symlinks
+/// pointing outside of the (already checked) source directory.
+async fn check_outside_root<P: FsPermissions + 'static>(
+  state: &Rc<RefCell<OpState>>,
+  fs: &dyn FileSystem,
+  root: &Path,
+  entries: &[TreeEntry],
+) -> Result<(), FsOpsError> {
+  let canonical_root = fs
+    .realpath_async(root.to_path_buf())
+    .await
+    .context_path("realpath", root)?;
+  for entry in entries {
+    let canonical = fs
+      .realpath_async(entry.path.clone())
+      .await
+      .context_path("realpath", &entry.path)?;
+    if !canonical.starts_with(&canonical_root) {
+      state
+        .borrow_mut()
+        .borrow_mut::<P>()
+        .check_read_path(&canonical, "Deno.copyDir()")
+        .map_err(FsOpsError::Permission)?;
+    }
+  }
+  Ok(())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_copy_dir<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] from: String,
+  #[string] to: String,
+  #[serde] options: CopyDirOptions,
+) -> Result<CopyDirResult, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  let (fs, from, to, interrupt, progress, cancel_handle) = {
+    let mut state = state.borrow_mut();
+    state
+      .feature_checker
+      .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.copyDir");
+    let permissions = state.borrow_mut::<P>();
+    let from = permissions
+      .check_read(&from, "Deno.copyDir()")
+      .map_err(FsOpsError::Permission)?;
+    let to = permissions
+      .check_write(&to, "Deno.copyDir()")
+      .map_err(FsOpsError::Permission)?;
+    let (progress, cancel_handle) =
+      get_control_resources(&state, options.progress_rid, options.cancel_rid)?;
+    (
+      state.borrow::<FileSystemRc>().clone(),
+      from,
+      to,
+      InterruptHandle::token_from_state(&state),
+      progress,
+      cancel_handle,
+    )
+  };
+
+  let follow_symlinks = options.symlinks == SymlinkMode::Follow;
+  let result = async {
+    let entries =
+      collect_tree(&*fs, &from, follow_symlinks, &interrupt).await?;
+    if follow_symlinks {
+      // Followed symlinks were resolved while walking, but nothing was read
+      // yet.
+      check_outside_root::<P>(&state, &*fs, &from, &entries).await?;
+    }
+
+    let canceled = Rc::new(Cell::new(false));
+    let control = TreeOpControl {
+      total: entries.len() as u64,
+      on_progress: progress_fn(&progress),
+      canceled: canceled.clone(),
+      interrupt,
+    };
+    run_cancelable(
+      copy_entries(&*fs, &from, &to, &entries, &control),
+      cancel_handle,
+      canceled,
+    )
+    .await
+  }
+  .await;
+  // also when the operation failed, so that JS stops waiting for events
+  if let Some(progress) = progress {
+    progress.sender().finish();
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::RealFs;
+
+  fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap()
+      .block_on(future)
+  }
+
+  fn collect(root: &Path, follow_symlinks: bool) -> Vec<TreeEntry> {
+    let interrupt = InterruptToken::default();
+    block_on(collect_tree(&RealFs, root, follow_symlinks, &interrupt)).unwrap()
+  }
+
+  fn write_tree(root: &Path, files: usize) {
+    std::fs::create_dir_all(root.join("a/b")).unwrap();
+    for i in 0..files {
+      std::fs::write(root.join(format!("a/b/{i}.txt")), i.to_string()).unwrap();
+    }
+    std::fs::write(root.join("top.txt"), "top").unwrap();
+  }
+
+  fn test_control(
+    total: usize,
+    on_progress: Option<Box<ProgressFn>>,
+    canceled: Rc<Cell<bool>>,
+  ) -> TreeOpControl {
+    TreeOpControl {
+      total: total as u64,
+      on_progress,
+      canceled,
+      interrupt: InterruptToken::default(),
+    }
+  }
+
+  /// Reports the events, and cancels the operation after the first chunk.
+  fn cancel_after_first_chunk(
+    canceled: &Rc<Cell<bool>>,
+    events: &Rc<RefCell<Vec<FsProgress>>>,
+  ) -> Option<Box<ProgressFn>> {
+    let canceled = canceled.clone();
+    let events = events.clone();
+    Some(Box::new(move |event| {
+      events.borrow_mut().push(event);
+      canceled.set(true);
+    }))
+  }
+
+  #[test]
+  fn test_remove_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("tree");
+    write_tree(&root, 100);
+    let entries = collect(&root, false);
+    // the root, a, a/b, the files in a/b and top.txt
+    assert_eq!(entries.len(), 104);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = events.clone();
+    let on_progress: Box<ProgressFn> =
+      Box::new(move |event| sink.borrow_mut().push(event));
+    let control =
+      test_control(entries.len(), Some(on_progress), Default::default());
+    let result = block_on(remove_entries(&RealFs, &entries, &control)).unwrap();
+    assert_eq!(
+      result,
+      RemoveTreeResult {
+        removed: 104,
+        canceled: false
+      }
+    );
+    assert!(!root.exists());
+    let events = events.borrow();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].processed, 64);
+    assert_eq!(events[1].processed, 104);
+    assert!(events.iter().all(|event| event.total == 104));
+    // the root is removed last
+    assert_eq!(events[1].current_path, root.to_string_lossy());
+  }
+
+  #[test]
+  fn test_remove_tree_canceled() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("tree");
+    write_tree(&root, 100);
+    let entries = collect(&root, false);
+
+    let canceled = Rc::new(Cell::new(false));
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let control = test_control(
+      entries.len(),
+      cancel_after_first_chunk(&canceled, &events),
+      canceled.clone(),
+    );
+    let result = block_on(remove_entries(&RealFs, &entries, &control)).unwrap();
+    assert_eq!(
+      result,
+      RemoveTreeResult {
+        removed: 64,
+        canceled: true
+      }
+    );
+    assert_eq!(events.borrow().len(), 1);
+    // the deepest entries are removed first, so the first chunk took
+    // top.txt and most of the files in a/b
+    assert!(!root.join("top.txt").exists());
+    assert_eq!(std::fs::read_dir(root.join("a/b")).unwrap().count(), 37);
+  }
+
+  #[test]
+  fn test_remove_tree_single_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("file.txt");
+    std::fs::write(&file, "x").unwrap();
+    let entries = collect(&file, false);
+    let result = block_on(remove_entries(
+      &RealFs,
+      &entries,
+      &test_control(1, None, Default::default()),
+    ))
+    .unwrap();
+    assert_eq!(result.removed, 1);
+    assert!(!file.exists());
+  }
+
+  #[test]
+  fn test_copy_dir_canceled() {
+    let dir = tempfile::tempdir().unwrap();
+    let from = dir.path().join("from");
+    let to = dir.path().join("to");
+    write_tree(&from, 100);
+    let entries = collect(&from, false);
+
+    let canceled = Rc::new(Cell::new(false));
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let control = test_control(
+      entries.len(),
+      cancel_after_first_chunk(&canceled, &events),
+      canceled.clone(),
+    );
+    let result =
+      block_on(copy_entries(&RealFs, &from, &to, &entries, &control)).unwrap();
+    assert!(result.canceled);
+    assert_eq!(result.dirs + result.files, 64);
+    assert_eq!(events.borrow()[0].processed, 64);
+    assert!(to.join("a/b/0.txt").exists());
+    assert!(!to.join("top.txt").exists());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_copy_dir_symlinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let from = dir.path().join("from");
+    write_tree(&from, 2);
+    std::os::unix::fs::symlink("a/b/0.txt", from.join("file_link")).unwrap();
+    std::os::unix::fs::symlink("a", from.join("dir_link")).unwrap();
+
+    let preserved = dir.path().join("preserved");
+    let entries = collect(&from, false);
+    let control = test_control(entries.len(), None, Default::default());
+    let result =
+      block_on(copy_entries(&RealFs, &from, &preserved, &entries, &control))
+        .unwrap();
+    assert_eq!(
+      result,
+      CopyDirResult {
+        dirs: 3,
+        files: 3,
+        symlinks: 2,
+        bytes: 5,
+        canceled: false,
+      }
+    );
+    assert_eq!(
+      std::fs::read_link(preserved.join("file_link")).unwrap(),
+      Path::new("a/b/0.txt")
+    );
+    assert_eq!(
+      std::fs::read_link(preserved.join("dir_link")).unwrap(),
+      Path::new("a")
+    );
+
+    let followed = dir.path().join("followed");
+    let entries = collect(&from, true);
+    let control = test_control(entries.len(), None, Default::default());
+    let result =
+      block_on(copy_entries(&RealFs, &from, &followed, &entries, &control))
+        .unwrap();
+    assert_eq!(result.symlinks, 0);
+    assert!(!followed.join("file_link").is_symlink());
+    assert_eq!(
+      std::fs::read_to_string(followed.join("file_link")).unwrap(),
+      "0"
+    );
+    assert_eq!(
+      std::fs::read_to_string(followed.join("dir_link/b/1.txt")).unwrap(),
+      "1"
+    );
+  }
+}
@@ -0,0 +1,692 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::error::AnyError;
+use deno_core::futures::future::LocalBoxFuture;
+use deno_core::futures::stream;
+use deno_core::futures::stream::FuturesUnordered;
+use deno_core::futures::FutureExt;
+use deno_core::futures::StreamExt;
+use deno_core::futures::TryStreamExt;
+use deno_io::fs::FsError;
+use deno_io::fs::FsStat;
+use thiserror::Error;
+
+use crate::interface::FsDirEntry;
+use crate::FileSystem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkEntryKind {
+  Dir,
+  File,
+  Symlink,
+}
+
+#[derive(Debug, Error)]
+pub enum WalkError {
+  #[error("Reading {}: {source}", path.display())]
+  Io {
+    path: PathBuf,
+    #[source]
+    source: FsError,
+  },
+  #[error(
+    "Symlink cycle detected: {} points to {}, which contains it",
+    link.display(),
+    target.display()
+  )]
+  SymlinkCycle { link: PathBuf, target: PathBuf },
+  #[error(transparent)]
+  Visit(AnyError),
+}
+
+impl WalkError {
+  pub(crate) fn io(path: &Path) -> impl FnOnce(FsError) -> Self + '_ {
+    move |source| Self::Io {
+      path: path.to_path_buf(),
+      source,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct WalkOptions<'a> {
+  /// Report the targets of symlinks as files and directories instead of
+  /// reporting the symlinks themselves.
+  pub follow_symlinks: bool,
+  /// Directories for which this returns `false` are skipped together with
+  /// everything below them. The root is always visited.
+  pub filter_dir: Option<&'a dyn Fn(&Path) -> bool>,
+}
+
+/// Visits `path` and everything below it in a deterministic, depth-first
+/// order, without reading any file contents. Directories are visited before
+/// their entries, and entries are visited sorted by file name.
+///
+/// Visited paths are always below `path`, also when they are reached through
+/// a followed symlink.
+pub fn walk_dir_recursive(
+  fs: &dyn FileSystem,
+  path: &Path,
+  options: &WalkOptions,
+  visit: &mut dyn FnMut(&Path, WalkEntryKind) -> Result<(), AnyError>,
+) -> Result<(), WalkError> {
+  let mut walk = Walk::new(options);
+  let canonical = if options.follow_symlinks {
+    Some(fs.realpath_sync(path).map_err(WalkError::io(path))?)
+  } else {
+    None
+  };
+  let mut next = Some(Step::Enter {
+    path: path.to_path_buf(),
+    canonical,
+  });
+  while let Some(step) = next.take() {
+    match step {
+      Step::Enter { path, canonical } => {
+        visit(&path, WalkEntryKind::Dir).map_err(WalkError::Visit)?;
+        let entries = fs.read_dir_sync(&path).map_err(WalkError::io(&path))?;
+        walk.push(path, canonical, entries);
+      }
+      Step::Visit(path, kind) => {
+        visit(&path, kind).map_err(WalkError::Visit)?
+      }
+      Step::Skip => {}
+    }
+    if let Some(child) = walk.next_child() {
+      let target = match child.is_followed_link(options) {
+        true => Some(resolve_link_sync(fs, &child.path)?),
+        false => None,
+      };
+      next = Some(walk.step(child, target)?);
+    }
+  }
+  Ok(())
+}
+
+/// Like [`walk_dir_recursive`], but reads the tree through the async
+/// methods of `fs`, so ops can walk it without blocking the event loop.
+pub async fn walk_dir_recursive_async(
+  fs: &dyn FileSystem,
+  path: &Path,
+  options: &WalkOptions<'_>,
+  visit: &mut dyn FnMut(&Path, WalkEntryKind) -> Result<(), AnyError>,
+) -> Result<(), WalkError> {
+  let mut walk = Walk::new(options);
+  let canonical = if options.follow_symlinks {
+    let canonical = fs.realpath_async(path.to_path_buf()).await;
+    Some(canonical.map_err(WalkError::io(path))?)
+  } else {
+    None
+  };
+  let mut next = Some(Step::Enter {
+    path: path.to_path_buf(),
+    canonical,
+  });
+  while let Some(step) = next.take() {
+    match step {
+      Step::Enter { path, canonical } => {
+        visit(&path, WalkEntryKind::Dir).map_err(WalkError::Visit)?;
+        let entries = fs.read_dir_async(path.clone()).await;
+        walk.push(
+          path.clone(),
+          canonical,
+          entries.map_err(WalkError::io(&path))?,
+        );
+      }
+      Step::Visit(path, kind) => {
+        visit(&path, kind).map_err(WalkError::Visit)?
+      }
+      Step::Skip => {}
+    }
+    if let Some(child) = walk.next_child() {
+      let target = match child.is_followed_link(options) {
+        true => Some(resolve_link_async(fs, &child.path).await?),
+        false => None,
+      };
+      next = Some(walk.step(child, target)?);
+    }
+  }
+  Ok(())
+}
+
+/// Resolves the symlink at `path` to its canonical target and the target's
+/// metadata.
+fn resolve_link_sync(
+  fs: &dyn FileSystem,
+  path: &Path,
+) -> Result<(PathBuf, FsStat), WalkError> {
+  let canonical = fs.realpath_sync(path).map_err(WalkError::io(path))?;
+  let stat = fs.stat_sync(&canonical).map_err(WalkError::io(path))?;
+  Ok((canonical, stat))
+}
+
+async fn resolve_link_async(
+  fs: &dyn FileSystem,
+  path: &Path,
+) -> Result<(PathBuf, FsStat), WalkError> {
+  let canonical = fs
+    .realpath_async(path.to_path_buf())
+    .await
+    .map_err(WalkError::io(path))?;
+  let stat = fs
+    .stat_async(canonical.clone())
+    .await
+    .map_err(WalkError::io(path))?;
+  Ok((canonical, stat))
+}
+
+/// What to do with the next entry of the walk.
+enum Step {
+  /// Visit the directory, then walk its entries.
+  Enter {
+    path: PathBuf,
+    canonical: Option<PathBuf>,
+  },
+  Visit(PathBuf, WalkEntryKind),
+  Skip,
+}
+
+struct Child {
+  path: PathBuf,
+  entry: FsDirEntry,
+  /// The canonical path of the directory containing the entry, only tracked
+  /// when following symlinks.
+  parent_canonical: Option<PathBuf>,
+}
+
+impl Child {
+  fn is_followed_link(&self, options: &WalkOptions) -> bool {
+    self.entry.is_symlink && options.follow_symlinks
+  }
+}
+
+struct Frame {
+  path: PathBuf,
+  canonical: Option<PathBuf>,
+  entries: std::vec::IntoIter<FsDirEntry>,
+}
+
+/// The directories being walked, innermost last. Doesn't do any IO, so the
+/// sync and the async walk share it.
+struct Walk<'a> {
+  options: &'a WalkOptions<'a>,
+  frames: Vec<Frame>,
+}
+
+impl<'a> Walk<'a> {
+  fn new(options: &'a WalkOptions<'a>) -> Self {
+    Self {
+      options,
+      frames: Vec::new(),
+    }
+  }
+
+  fn push(
+    &mut self,
+    path: PathBuf,
+    canonical: Option<PathBuf>,
+    mut entries: Vec<FsDirEntry>,
+  ) {
+    entries.sort_by(|a, b| a.name.cmp(&b.name)); // determinism
+    self.frames.push(Frame {
+      path,
+      canonical,
+      entries: entries.into_iter(),
+    });
+  }
+
+  /// The next entry in walk order, leaving the directories that are done.
+  fn next_child(&mut self) -> Option<Child> {
+    loop {
+      let frame = self.frames.last_mut()?;
+      if let Some(entry) = frame.entries.next() {
+        return Some(Child {
+          path: frame.path.join(&entry.name),
+          entry,
+          parent_canonical: frame.canonical.clone(),
+        });
+      }
+      self.frames.pop();
+    }
+  }
+
+  /// Decides how to walk `child`. `target` is the resolved symlink, when
+  /// it's followed.
+  fn step(
+    &self,
+    child: Child,
+    target: Option<(PathBuf, FsStat)>,
+  ) -> Result<Step, WalkError> {
+    let target = target.as_ref().map(|(path, stat)| (path.as_path(), stat));
+    classify(
+      child,
+      target,
+      |path| self.options.filter_dir.is_some_and(|filter| !filter(path)),
+      |target| {
+        self
+          .frames
+          .iter()
+          .any(|frame| frame.canonical.as_deref() == Some(target))
+      },
+    )
+  }
+}
+
+/// Decides how to walk `child`, given the resolved symlink when it's
+/// followed. Directories for which `skip_dir` returns `true` are skipped,
+/// and entering one for which `is_ancestor` returns `true` for its canonical
+/// path is a symlink cycle.
+fn classify(
+  child: Child,
+  target: Option<(&Path, &FsStat)>,
+  skip_dir: impl FnOnce(&Path) -> bool,
+  is_ancestor: impl FnOnce(&Path) -> bool,
+) -> Result<Step, WalkError> {
+  let Child {
+    path,
+    entry,
+    parent_canonical,
+  } = child;
+  let (is_dir, is_file) = match target {
+    Some((_, stat)) => (stat.is_directory, stat.is_file),
+    None => (entry.is_directory, entry.is_file),
+  };
+
+  if is_dir {
+    if skip_dir(&path) {
+      return Ok(Step::Skip);
+    }
+    let canonical = match target {
+      Some((target, _)) => {
+        if is_ancestor(target) {
+          return Err(WalkError::SymlinkCycle {
+            link: path,
+            target: target.to_path_buf(),
+          });
+        }
+        Some(target.to_path_buf())
+      }
+      // not a symlink, so below the canonical parent
+      None => parent_canonical.map(|parent| parent.join(&entry.name)),
+    };
+    Ok(Step::Enter { path, canonical })
+  } else if is_file {
+    Ok(Step::Visit(path, WalkEntryKind::File))
+  } else if entry.is_symlink {
+    Ok(Step::Visit(path, WalkEntryKind::Symlink))
+  } else {
+    Ok(Step::Skip)
+  }
+}
+
+#[derive(Default)]
+pub struct ConcurrentWalkOptions<'a> {
+  /// Follow symlinks whose target is below the root. Other symlinks, and
+  /// symlinks to a directory being walked, are reported but not followed.
+  pub follow_symlinks: bool,
+  /// Directories for which this returns `false` for their path relative to
+  /// the root, with `/` separators, are skipped together with everything
+  /// below them, and not reported.
+  pub filter_dir: Option<&'a dyn Fn(&str) -> bool>,
+  /// The entries of the root have a depth of 1. Directories at this depth
+  /// are reported, but not read.
+  pub max_depth: Option<usize>,
+  /// Read the metadata of every entry into [`WalkedEntry::stat`].
+  pub stat: bool,
+}
+
+/// An entry found by [`ConcurrentWalk`].
+pub struct WalkedEntry {
+  pub path: PathBuf,
+  /// Relative to the root, with `/` separators.
+  pub relative: String,
+  /// The kind of the target for a followed symlink.
+  pub kind: WalkEntryKind,
+  pub is_symlink: bool,
+  /// The metadata of the target for a followed symlink, or of the entry
+  /// itself when [`ConcurrentWalkOptions::stat`] is set.
+  pub stat: Option<FsStat>,
+}
+
+/// Canonical paths of a directory and its parents.
+struct Ancestor {
+  path: PathBuf,
+  parent: Option<Rc<Ancestor>>,
+}
+
+impl Ancestor {
+  fn contains(ancestor: Option<&Rc<Ancestor>>, path: &Path) -> bool {
+    let mut ancestor = ancestor;
+    while let Some(current) = ancestor {
+      if current.path == path {
+        return true;
+      }
+      ancestor = current.parent.as_ref();
+    }
+    false
+  }
+}
+
+struct DirJob {
+  path: PathBuf,
+  relative: String,
+  depth: usize,
+  /// Only tracked when following symlinks.
+  ancestors: Option<Rc<Ancestor>>,
+}
+
+/// What was learned about a directory entry while reading its directory.
+struct EntryInfo {
+  entry: FsDirEntry,
+  /// The canonical path and metadata of a followed symlink's target.
+  target: Option<(PathBuf, FsStat)>,
+  stat: Option<FsStat>,
+}
+
+type DirRead<'a> =
+  LocalBoxFuture<'a, (DirJob, Result<Vec<EntryInfo>, WalkError>)>;
+
+/// Walks a tree reading up to [`MAX_CONCURRENT_READS`] directories at the
+/// same time, through the async methods of a [`FileSystem`]. Entries are
+/// returned a directory at a time, in no particular order.
+///
+/// Directories are only queued for reading while the walk is polled, so a
+/// slow consumer holds it back instead of buffering the tree.
+pub struct ConcurrentWalk<'a> {
+  fs: &'a dyn FileSystem,
+  options: &'a ConcurrentWalkOptions<'a>,
+  canonical_root: Option<PathBuf>,
+  queue: Vec<DirJob>,
+  reads: FuturesUnordered<DirRead<'a>>,
+}
+
+/// Upper bound on the number of directories a [`ConcurrentWalk`] reads at
+/// the same time, and on the symlinks or metadata it reads at the same time
+/// for each of them.
+pub const MAX_CONCURRENT_READS: usize = 8;
+
+impl<'a> ConcurrentWalk<'a> {
+  pub async fn new(
+    fs: &'a dyn FileSystem,
+    root: &Path,
+    options: &'a ConcurrentWalkOptions<'a>,
+  ) -> Result<Self, WalkError> {
+    let canonical_root = if options.follow_symlinks {
+      let canonical = fs.realpath_async(root.to_path_buf()).await;
+      Some(canonical.map_err(WalkError::io(root))?)
+    } else {
+      None
+    };
+    let mut queue = Vec::new();
+    if options.max_depth != Some(0) {
+      queue.push(DirJob {
+        path: root.to_path_buf(),
+        relative: String::new(),
+        depth: 0,
+        ancestors: canonical_root
+          .clone()
+          .map(|path| Rc::new(Ancestor { path, parent: None })),
+      });
+    }
+    Ok(Self {
+      fs,
+      options,
+      canonical_root,
+      queue,
+      reads: FuturesUnordered::new(),
+    })
+  }
+
+  /// The entries of the next directory that was read, or `None` once the
+  /// walk is done.
+  pub async fn next_dir(
+    &mut self,
+  ) -> Option<Result<Vec<WalkedEntry>, WalkError>> {
+    while self.reads.len() < MAX_CONCURRENT_READS {
+      let Some(job) = self.queue.pop() else {
+        break;
+      };
+      let read = self.read_dir(job);
+      self.reads.push(read);
+    }
+    let (job, entries) = self.reads.next().await?;
+    Some(entries.map(|entries| self.walk_entries(&job, entries)))
+  }
+
+  fn read_dir(&self, job: DirJob) -> DirRead<'a> {
+    let fs = self.fs;
+    let stat = self.options.stat;
+    let follow_root = self.canonical_root.clone();
+    async move {
+      let entries = match fs.read_dir_async(job.path.clone()).await {
+        Ok(entries) => entries,
+        Err(err) => return (job, Err(WalkError::io(&job.path)(err))),
+      };
+      let infos: Result<Vec<_>, _> = stream::iter(entries)
+        .map(|entry| {
+          let path = job.path.join(&entry.name);
+          let follow_root = follow_root.as_deref();
+          async move {
+            let mut target = None;
+            if let (true, Some(root)) = (entry.is_symlink, follow_root) {
+              target = resolve_link_inside(fs, &path, root).await?;
+            }
+            let stat = match target {
+              None if stat => {
+                let stat = fs.lstat_async(path.clone()).await;
+                Some(stat.map_err(WalkError::io(&path))?)
+              }
+              _ => None,
+            };
+            Ok::<_, WalkError>(EntryInfo {
+              entry,
+              target,
+              stat,
+            })
+          }
+        })
+        .buffered(MAX_CONCURRENT_READS)
+        .try_collect()
+        .await;
+      (job, infos)
+    }
+    .boxed_local()
+  }
+
+  fn walk_entries(
+    &mut self,
+    job: &DirJob,
+    infos: Vec<EntryInfo>,
+  ) -> Vec<WalkedEntry> {
+    let depth = job.depth + 1;
+    let mut walked = Vec::with_capacity(infos.len());
+    for EntryInfo {
+      entry,
+      target,
+      stat,
+    } in infos
+    {
+      let relative = if job.relative.is_empty() {
+        entry.name.clone()
+      } else {
+        format!("{}/{}", job.relative, entry.name)
+      };
+      let is_symlink = entry.is_symlink;
+      let child = Child {
+        path: job.path.join(&entry.name),
+        entry,
+        parent_canonical: job.ancestors.as_ref().map(|a| a.path.clone()),
+      };
+      let filter_dir = self.options.filter_dir;
+      let step = classify(
+        child,
+        target.as_ref().map(|(path, stat)| (path.as_path(), stat)),
+        |_| filter_dir.is_some_and(|filter| !filter(&relative)),
+        |target| Ancestor::contains(job.ancestors.as_ref(), target),
+      );
+      let stat = target.map(|(_, stat)| stat).or(stat);
+      let (path, kind, enter) = match step {
+        Ok(Step::Enter { path, canonical }) => {
+          (path.clone(), WalkEntryKind::Dir, Some((path, canonical)))
+        }
+        Ok(Step::Visit(path, kind)) => (path, kind, None),
+        Ok(Step::Skip) => continue,
+        // reported, but not walked again
+        Err(WalkError::SymlinkCycle { link, .. }) => {
+          (link, WalkEntryKind::Dir, None)
+        }
+        Err(_) => unreachable!("classifying doesn't do IO"),
+      };
+      let at_max_depth = self.options.max_depth.is_some_and(|max| depth >= max);
+      if let (Some((path, canonical)), false) = (enter, at_max_depth) {
+        self.queue.push(DirJob {
+          path,
+          relative: relative.clone(),
+          depth,
+          ancestors: canonical.map(|path| {
+            Rc::new(Ancestor {
+              path,
+              parent: job.ancestors.clone(),
+            })
+          }),
+        });
+      }
+      walked.push(WalkedEntry {
+        path,
+        relative,
+        kind,
+        is_symlink,
+        stat,
+      });
+    }
+    walked
+  }
+}
+
+/// Resolves the symlink at `path` if its target exists and is below `root`.
+async fn resolve_link_inside(
+  fs: &dyn FileSystem,
+  path: &Path,
+  root: &Path,
+) -> Result<Option<(PathBuf, FsStat)>, WalkError> {
+  let canonical = match fs.realpath_async(path.to_path_buf()).await {
+    Ok(canonical) => canonical,
+    // dangling links are reported as symlinks
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(err) => return Err(WalkError::io(path)(err)),
+  };
+  if !canonical.starts_with(root) {
+    return Ok(None);
+  }
+  let stat = fs
+    .stat_async(canonical.clone())
+    .await
+    .map_err(WalkError::io(path))?;
+  Ok(Some((canonical, stat)))
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::RealFs;
+
+  fn walk(
+    root: &Path,
+    options: &WalkOptions,
+  ) -> Result<Vec<String>, WalkError> {
+    let mut visited = Vec::new();
+    let mut visit = |path: &Path, kind| {
+      let relative = path.strip_prefix(root).unwrap();
+      visited
+        .push(format!("{kind:?} {}", relative.display()).replace('\\', "/"));
+      Ok(())
+    };
+    walk_dir_recursive(&RealFs, root, options, &mut visit)?;
+    let sync_visited = std::mem::take(&mut visited);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let mut visit = |path: &Path, kind| {
+      let relative = path.strip_prefix(root).unwrap();
+      visited
+        .push(format!("{kind:?} {}", relative.display()).replace('\\', "/"));
+      Ok(())
+    };
+    runtime
+      .block_on(walk_dir_recursive_async(&RealFs, root, options, &mut visit))?;
+    assert_eq!(sync_visited, visited);
+    Ok(visited)
+  }
+
+  #[test]
+  fn test_walk_dir_recursive_sorted() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("b/d")).unwrap();
+    std::fs::create_dir_all(root.join("skip")).unwrap();
+    std::fs::write(root.join("c.txt"), "c").unwrap();
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    std::fs::write(root.join("b/d/e.txt"), "e").unwrap();
+    std::fs::write(root.join("skip/f.txt"), "f").unwrap();
+
+    let filter = |path: &Path| !path.ends_with("skip");
+    let options = WalkOptions {
+      follow_symlinks: false,
+      filter_dir: Some(&filter),
+    };
+    assert_eq!(
+      walk(root, &options).unwrap(),
+      vec![
+        "Dir ",
+        "File a.txt",
+        "Dir b",
+        "Dir b/d",
+        "File b/d/e.txt",
+        "File c.txt"
+      ]
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_walk_dir_recursive_symlinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("a")).unwrap();
+    std::fs::write(root.join("a/file.txt"), "a").unwrap();
+    std::os::unix::fs::symlink(root.join("a"), root.join("b")).unwrap();
+
+    assert_eq!(
+      walk(root, &WalkOptions::default()).unwrap(),
+      vec!["Dir ", "Dir a", "File a/file.txt", "Symlink b"]
+    );
+    let options = WalkOptions {
+      follow_symlinks: true,
+      filter_dir: None,
+    };
+    assert_eq!(
+      walk(root, &options).unwrap(),
+      vec![
+        "Dir ",
+        "Dir a",
+        "File a/file.txt",
+        "Dir b",
+        "File b/file.txt"
+      ]
+    );
+
+    std::os::unix::fs::symlink(root, root.join("a/loop")).unwrap();
+    let err = walk(root, &options).unwrap_err();
+    assert!(matches!(err, WalkError::SymlinkCycle { .. }));
+    assert!(err.to_string().starts_with("Symlink cycle detected: "));
+    assert!(err.to_string().contains("loop points to"));
+  }
+}
@@ -0,0 +1,526 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Concurrent directory walks whose entries are streamed to JS in batches.
+//!
+//! The traversal is a [`ConcurrentWalk`] over the runtime's [`FileSystem`],
+//! which reads a bounded number of directories at the same time. A task on
+//! the event loop applies the include and exclude patterns and sends the
+//! matching entries through a bounded channel, so a scan of a large tree
+//! costs one op call per batch instead of one per directory, and a slow
+//! consumer holds the walk back instead of buffering the whole tree. Entries
+//! are reported in no particular order.
+//!
+//! Patterns are matched by [`crate::glob_matcher`], with the semantics of the
+//! config file's `include` and `exclude`: they are matched against paths
+//! relative to the root with `/` separators, and a pattern that matches a
+//! directory matches everything below it. Excluded directories are not read
+//! at all. Instead of patterns, a `Deno.GlobMatcher` compiled before can be
+//! passed.
+//!
+//! When following symlinks, links whose target is outside of the root are
+//! reported but not followed, and so are links to a directory that contains
+//! them.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::unsync::spawn;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::glob_matcher::compile_glob_set;
+use crate::glob_matcher::glob_matcher;
+use crate::glob_matcher::GlobSet;
+use crate::ops::FsOpsError;
+use crate::walk::ConcurrentWalk;
+use crate::walk::ConcurrentWalkOptions;
+use crate::walk::WalkEntryKind;
+use crate::walk::WalkedEntry;
+use crate::FileSystem;
+use crate::FileSystemRc;
+use crate::FsPermissions;
+
+const BATCH_SIZE: usize = 256;
+/// Batches that may be waiting for JS before the walk pauses.
+const QUEUED_BATCHES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsWalkEntryType {
+  File,
+  Dir,
+  Symlink,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FsWalkOptions {
+  include: Vec<String>,
+  exclude: Vec<String>,
+  /// The rid of a `Deno.GlobMatcher` to use instead of `include`.
+  include_matcher: Option<ResourceId>,
+  /// The rid of a `Deno.GlobMatcher` to use instead of `exclude`.
+  exclude_matcher: Option<ResourceId>,
+  max_depth: Option<usize>,
+  follow_symlinks: bool,
+  types: Option<Vec<FsWalkEntryType>>,
+  #[serde(skip)]
+  filter: Option<PathFilter>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsWalkEntry {
+  pub(crate) path: String,
+  is_file: bool,
+  is_dir: bool,
+  is_symlink: bool,
+  size: u64,
+  /// Milliseconds since the Unix epoch.
+  mtime: Option<u64>,
+}
+
+impl FsWalkEntry {
+  fn new(entry: WalkedEntry) -> Self {
+    Self {
+      path: entry.path.to_string_lossy().into_owned(),
+      is_file: entry.kind == WalkEntryKind::File,
+      is_dir: entry.kind == WalkEntryKind::Dir,
+      is_symlink: entry.is_symlink,
+      size: entry.stat.as_ref().map_or(0, |stat| stat.size),
+      mtime: entry.stat.and_then(|stat| stat.mtime),
+    }
+  }
+}
+
+impl FsWalkOptions {
+  /// Options for walking the files that match `include` and `exclude`, or
+  /// the given `Deno.GlobMatcher`s instead.
+  pub(crate) fn files(
+    include: Vec<String>,
+    exclude: Vec<String>,
+    include_matcher: Option<ResourceId>,
+    exclude_matcher: Option<ResourceId>,
+  ) -> Self {
+    Self {
+      include,
+      exclude,
+      include_matcher,
+      exclude_matcher,
+      types: Some(vec![FsWalkEntryType::File]),
+      ..Default::default()
+    }
+  }
+
+  /// Compiles the patterns through the op state's cache, or looks up the
+  /// matchers given instead of them. Fails if a pattern is invalid.
+  pub(crate) fn compile_patterns(
+    &mut self,
+    state: &mut OpState,
+  ) -> Result<(), FsOpsError> {
+    let mut compile =
+      |patterns: &[String], matcher: Option<ResourceId>| match matcher {
+        Some(rid) => glob_matcher(&state.resource_table, rid),
+        None => compile_glob_set(state, patterns, Default::default()),
+      };
+    self.filter = Some(PathFilter {
+      include: compile(&self.include, self.include_matcher)?,
+      exclude: compile(&self.exclude, self.exclude_matcher)?,
+    });
+    Ok(())
+  }
+}
+
+type WalkBatch = Result<Vec<FsWalkEntry>, FsOpsError>;
+
+#[derive(Debug, Clone)]
+struct PathFilter {
+  include: Rc<GlobSet>,
+  exclude: Rc<GlobSet>,
+}
+
+impl PathFilter {
+  /// Uses the patterns compiled by [`FsWalkOptions::compile_patterns`], or
+  /// compiles them.
+  fn new(options: &FsWalkOptions) -> Result<Self, FsOpsError> {
+    if let Some(filter) = &options.filter {
+      return Ok(filter.clone());
+    }
+    Ok(Self {
+      include: Rc::new(GlobSet::new(&options.include, Default::default())?),
+      exclude: Rc::new(GlobSet::new(&options.exclude, Default::default())?),
+    })
+  }
+
+  /// Excluded directories are never read, so only `relative` itself has to be
+  /// checked.
+  fn is_excluded(&self, relative: &str) -> bool {
+    self.exclude.matches_exact(relative)
+  }
+
+  /// Whether `relative` matches an include pattern, ignoring its parents.
+  fn is_included(&self, relative: &str) -> bool {
+    self.include.is_empty() || self.include.matches_exact(relative)
+  }
+}
+
+struct TypeFilter {
+  file: bool,
+  dir: bool,
+  symlink: bool,
+}
+
+impl TypeFilter {
+  fn new(types: Option<&[FsWalkEntryType]>) -> Self {
+    match types {
+      Some(types) => Self {
+        file: types.contains(&FsWalkEntryType::File),
+        dir: types.contains(&FsWalkEntryType::Dir),
+        symlink: types.contains(&FsWalkEntryType::Symlink),
+      },
+      None => Self {
+        file: true,
+        dir: true,
+        symlink: true,
+      },
+    }
+  }
+
+  fn matches(&self, entry: &FsWalkEntry) -> bool {
+    (self.file && entry.is_file)
+      || (self.dir && entry.is_dir)
+      || (self.symlink && entry.is_symlink)
+  }
+}
+
+/// Walks `root` and sends the entries that pass the filters in batches,
+/// until the receiver is dropped.
+async fn walk(
+  fs: &dyn FileSystem,
+  root: &Path,
+  options: &ConcurrentWalkOptions<'_>,
+  filter: &PathFilter,
+  types: &TypeFilter,
+  sender: &mpsc::Sender<WalkBatch>,
+) -> Result<(), FsOpsError> {
+  let mut walk = ConcurrentWalk::new(fs, root, options)
+    .await
+    .map_err(FsOpsError::Walk)?;
+  // Everything below a directory that matched an include pattern is
+  // included. A directory is reported before its entries are read.
+  let mut included_dirs = HashSet::new();
+  let mut batch = Vec::with_capacity(BATCH_SIZE);
+  while let Some(entries) = walk.next_dir().await {
+    if sender.is_closed() {
+      return Ok(());
+    }
+    for entry in entries.map_err(FsOpsError::Walk)? {
+      if filter.is_excluded(&entry.relative) {
+        continue;
+      }
+      let included = filter.is_included(&entry.relative)
+        || entry
+          .relative
+          .rsplit_once('/')
+          .is_some_and(|(parent, _)| included_dirs.contains(parent));
+      if !included {
+        continue;
+      }
+      if entry.kind == WalkEntryKind::Dir && !filter.include.is_empty() {
+        included_dirs.insert(entry.relative.clone());
+      }
+      let entry = FsWalkEntry::new(entry);
+      if !types.matches(&entry) {
+        continue;
+      }
+      batch.push(entry);
+      if batch.len() >= BATCH_SIZE {
+        let full =
+          std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE));
+        if sender.send(Ok(full)).await.is_err() {
+          return Ok(());
+        }
+      }
+    }
+  }
+  if !batch.is_empty() {
+    let _ = sender.send(Ok(batch)).await;
+  }
+  Ok(())
+}
+
+/// Starts walking `root` in a task on the event loop and returns the
+/// receiving end of the batches. The walk stops when the receiver is dropped.
+pub(crate) fn start_walk(
+  fs: FileSystemRc,
+  root: PathBuf,
+  options: &FsWalkOptions,
+) -> Result<mpsc::Receiver<WalkBatch>, FsOpsError> {
+  let filter = PathFilter::new(options)?;
+  let types = TypeFilter::new(options.types.as_deref());
+  let max_depth = options.max_depth;
+  let follow_symlinks = options.follow_symlinks;
+  let (sender, receiver) = mpsc::channel(QUEUED_BATCHES);
+  spawn(async move {
+    let filter_dir = |relative: &str| !filter.is_excluded(relative);
+    let options = ConcurrentWalkOptions {
+      follow_symlinks,
+      filter_dir: Some(&filter_dir),
+      max_depth,
+      stat: true,
+    };
+    let result = walk(&*fs, &root, &options, &filter, &types, &sender).await;
+    if let Err(err) = result {
+      let _ = sender.send(Err(err)).await;
+    }
+  });
+  Ok(receiver)
+}
+
+struct FsWalkResource {
+  receiver: AsyncRefCell<mpsc::Receiver<WalkBatch>>,
+  cancel: CancelHandle,
+}
+
+impl Resource for FsWalkResource {
+  fn name(&self) -> Cow<str> {
+    "fsWalk".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+#[op2]
+#[smi]
+pub fn op_fs_walk<P>(
+  state: &mut OpState,
+  #[string] path: String,
+  #[serde] options: Option<FsWalkOptions>,
+) -> Result<ResourceId, FsOpsError>
+where
+  P: FsPermissions + 'static,
+{
+  state
+    .feature_checker
+    .check_or_exit(crate::UNSTABLE_FEATURE_NAME, "Deno.walkDir");
+  let root = state
+    .borrow_mut::<P>()
+    .check_read(&path, "Deno.walkDir()")
+    .map_err(FsOpsError::Permission)?;
+  let mut options = options.unwrap_or_default();
+  options.compile_patterns(state)?;
+  let fs = state.borrow::<FileSystemRc>().clone();
+  let receiver = start_walk(fs, root, &options)?;
+  Ok(state.resource_table.add(FsWalkResource {
+    receiver: AsyncRefCell::new(receiver),
+    cancel: Default::default(),
+  }))
+}
+
+/// Resolves to `null` once the walk is done.
+#[op2(async)]
+#[serde]
+pub async fn op_fs_walk_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<Vec<FsWalkEntry>>, FsOpsError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<FsWalkResource>(rid)
+    .map_err(FsOpsError::Resource)?;
+  let mut receiver = RcRef::map(&resource, |r| &r.receiver).borrow_mut().await;
+  let cancel = RcRef::map(&resource, |r| &r.cancel);
+  receiver.recv().or_cancel(cancel).await?.transpose()
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::disallowed_methods)]
+
+  use super::*;
+  use crate::sync::MaybeArc;
+  use crate::RealFs;
+
+  fn real_fs() -> FileSystemRc {
+    MaybeArc::new(RealFs)
+  }
+
+  fn collect(root: &Path, options: FsWalkOptions) -> Vec<String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let mut paths = runtime.block_on(async {
+      let mut receiver =
+        start_walk(real_fs(), root.to_path_buf(), &options).unwrap();
+      let mut paths = Vec::new();
+      while let Some(batch) = receiver.recv().await {
+        for entry in batch.unwrap() {
+          let relative = Path::new(&entry.path).strip_prefix(root).unwrap();
+          let kind = if entry.is_symlink {
+            "l"
+          } else if entry.is_dir {
+            "d"
+          } else {
+            "f"
+          };
+          paths.push(format!(
+            "{kind} {}",
+            relative.to_string_lossy().replace('\\', "/")
+          ));
+        }
+      }
+      paths
+    });
+    paths.sort();
+    paths
+  }
+
+  fn write_tree(root: &Path) {
+    std::fs::create_dir_all(root.join("src/nested/deep")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+    std::fs::write(root.join("deno.json"), "{}").unwrap();
+    std::fs::write(root.join("src/main.ts"), "").unwrap();
+    std::fs::write(root.join("src/main_test.ts"), "").unwrap();
+    std::fs::write(root.join("src/nested/mod.ts"), "").unwrap();
+    std::fs::write(root.join("src/nested/deep/x.js"), "").unwrap();
+    std::fs::write(root.join("node_modules/pkg/index.js"), "").unwrap();
+  }
+
+  #[test]
+  fn walks_whole_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    assert_eq!(
+      collect(dir.path(), Default::default()),
+      vec![
+        "d node_modules",
+        "d node_modules/pkg",
+        "d src",
+        "d src/nested",
+        "d src/nested/deep",
+        "f deno.json",
+        "f node_modules/pkg/index.js",
+        "f src/main.ts",
+        "f src/main_test.ts",
+        "f src/nested/deep/x.js",
+        "f src/nested/mod.ts",
+      ]
+    );
+  }
+
+  #[test]
+  fn applies_config_style_patterns() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let options = FsWalkOptions {
+      include: vec!["./src/".to_string()],
+      exclude: vec!["src/nested/deep".to_string(), "**/*_test.ts".to_string()],
+      types: Some(vec![FsWalkEntryType::File]),
+      ..Default::default()
+    };
+    // a directory pattern matches everything below it
+    assert_eq!(
+      collect(dir.path(), options),
+      vec!["f src/main.ts", "f src/nested/mod.ts"]
+    );
+
+    let options = FsWalkOptions {
+      include: vec!["**/*.js".to_string()],
+      exclude: vec!["node_modules".to_string()],
+      ..Default::default()
+    };
+    assert_eq!(collect(dir.path(), options), vec!["f src/nested/deep/x.js"]);
+
+    let options = FsWalkOptions {
+      include: vec!["[".to_string()],
+      ..Default::default()
+    };
+    assert!(matches!(
+      start_walk(real_fs(), dir.path().to_path_buf(), &options),
+      Err(FsOpsError::InvalidGlobPattern(..))
+    ));
+  }
+
+  #[test]
+  fn limits_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    let depth = |max_depth| FsWalkOptions {
+      max_depth: Some(max_depth),
+      ..Default::default()
+    };
+    assert!(collect(dir.path(), depth(0)).is_empty());
+    assert_eq!(
+      collect(dir.path(), depth(1)),
+      vec!["d node_modules", "d src", "f deno.json"]
+    );
+    assert_eq!(collect(dir.path(), depth(3)).len(), 10);
+  }
+
+  #[test]
+  fn many_entries_span_batches() {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..20 {
+      let sub = dir.path().join(format!("dir{i}"));
+      std::fs::create_dir(&sub).unwrap();
+      for j in 0..50 {
+        std::fs::write(sub.join(format!("{j}.txt")), "").unwrap();
+      }
+    }
+    let options = FsWalkOptions {
+      types: Some(vec![FsWalkEntryType::File]),
+      ..Default::default()
+    };
+    assert_eq!(collect(dir.path(), options).len(), 1000);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn follows_symlinks_inside_root_and_stops_at_cycles() {
+    let dir = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("a")).unwrap();
+    std::fs::write(root.join("a/file.txt"), "").unwrap();
+    std::fs::write(outside.path().join("secret.txt"), "").unwrap();
+    std::os::unix::fs::symlink(root.join("a"), root.join("b")).unwrap();
+    std::os::unix::fs::symlink(root, root.join("a/loop")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), root.join("escape")).unwrap();
+
+    assert_eq!(
+      collect(root, Default::default()),
+      vec!["d a", "f a/file.txt", "l a/loop", "l b", "l escape"]
+    );
+
+    let options = FsWalkOptions {
+      follow_symlinks: true,
+      ..Default::default()
+    };
+    let paths = collect(root, options);
+    // the link outside of the root isn't followed, and the walk ends at the
+    // links that point back to a directory being walked
+    assert!(!paths.iter().any(|path| path.contains("secret.txt")));
+    assert!(paths.contains(&"l escape".to_string()));
+    assert!(paths.contains(&"f b/file.txt".to_string()));
+    assert!(paths.contains(&"l a/loop".to_string()));
+    assert!(paths.contains(&"l b/loop".to_string()));
+    assert!(!paths.iter().any(|path| path.starts_with("f a/loop/")));
+  }
+}
@@ -0,0 +1,286 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Graceful degradation when the process runs out of file descriptors.
+//!
+//! Under load a server can hit its descriptor limit, after which every
+//! `accept` and `open` fails with `EMFILE` (or `ENFILE` for the system-wide
+//! table) until some sockets close. Ops that create descriptors retry such
+//! failures a bounded number of times with [`FdExhaustionRetry`], waiting on
+//! an exponential backoff with jitter in between. The jitter window widens
+//! with the number of ops that are backing off at the same time, so a burst of
+//! failures doesn't turn into a burst of retries.
+//!
+//! Listeners additionally use the reserved descriptor trick when it is enabled
+//! with [`reserve_fd`]: a descriptor is opened at startup, and when accepting
+//! fails because of exhaustion, it is closed so that one pending connection
+//! can be accepted and immediately closed. Otherwise the connection stays in
+//! the kernel backlog and keeps the listener readable, and clients keep
+//! retrying against a full queue.
+
+use std::fs::File;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use deno_core::futures::FutureExt;
+use rand::Rng;
+
+use crate::fs::FsError;
+
+/// Environment variable that enables the reserved descriptor when set to `1`.
+pub const RESERVE_FD_ENV_VAR_NAME: &str = "DENO_RESERVE_FD";
+
+/// Retries after the first failure. Without contention the delays add up to
+/// between 0.6 and 1.2 seconds.
+const MAX_RETRIES: u32 = 8;
+const BASE_DELAY: Duration = Duration::from_millis(5);
+const MAX_DELAY: Duration = Duration::from_millis(500);
+
+/// Ops that are currently waiting in [`FdExhaustionRetry::backoff`].
+static BACKING_OFF: AtomicUsize = AtomicUsize::new(0);
+
+static RESERVED_FD: Mutex<Option<File>> = Mutex::new(None);
+static RESERVE_ENABLED: AtomicBool = AtomicBool::new(false);
+/// Set when the reserved descriptor was released but couldn't be reopened.
+static RESERVE_MISSING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+const NULL_DEVICE: &str = "/dev/null";
+#[cfg(windows)]
+const NULL_DEVICE: &str = "NUL";
+
+/// Whether an error means that the process or the system ran out of file
+/// descriptors.
+pub trait IsFdExhaustion {
+  fn is_fd_exhaustion(&self) -> bool;
+}
+
+impl IsFdExhaustion for io::Error {
+  fn is_fd_exhaustion(&self) -> bool {
+    #[cfg(unix)]
+    {
+      matches!(self.raw_os_error(), Some(libc::EMFILE | libc::ENFILE))
+    }
+    #[cfg(windows)]
+    {
+      const ERROR_TOO_MANY_OPEN_FILES: i32 = 4;
+      const WSAEMFILE: i32 = 10024;
+      matches!(
+        self.raw_os_error(),
+        Some(ERROR_TOO_MANY_OPEN_FILES | WSAEMFILE)
+      )
+    }
+  }
+}
+
+impl IsFdExhaustion for FsError {
+  fn is_fd_exhaustion(&self) -> bool {
+    match self {
+      FsError::Io(err) => err.is_fd_exhaustion(),
+      _ => false,
+    }
+  }
+}
+
+/// Bounded retries of one op call that failed because of descriptor
+/// exhaustion.
+///
+/// ```ignore
+/// let mut retry = FdExhaustionRetry::default();
+/// let file = loop {
+///   match open().await {
+///     Err(err) if retry.should_retry(&err) => retry.backoff().await,
+///     result => break result,
+///   }
+/// };
+/// ```
+#[derive(Debug, Default)]
+pub struct FdExhaustionRetry {
+  attempt: u32,
+}
+
+impl FdExhaustionRetry {
+  /// Whether `err` is worth retrying, counting the attempt if it is.
+  pub fn should_retry(&mut self, err: &impl IsFdExhaustion) -> bool {
+    if self.attempt >= MAX_RETRIES || !err.is_fd_exhaustion() {
+      return false;
+    }
+    self.attempt += 1;
+    true
+  }
+
+  pub fn attempts(&self) -> u32 {
+    self.attempt
+  }
+
+  /// Waits before the next attempt.
+  pub async fn backoff(&self) {
+    struct Waiting;
+    impl Drop for Waiting {
+      fn drop(&mut self) {
+        BACKING_OFF.fetch_sub(1, Ordering::AcqRel);
+      }
+    }
+
+    let waiting = BACKING_OFF.fetch_add(1, Ordering::AcqRel);
+    // Decrements the count even if the op is canceled while waiting.
+    let _waiting = Waiting;
+    tokio::time::sleep(backoff_delay(self.attempt, waiting)).await;
+  }
+}
+
+/// The delay before attempt `attempt`, with `waiting` other ops backing off.
+fn backoff_delay(attempt: u32, waiting: usize) -> Duration {
+  let exponential =
+    BASE_DELAY.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+  let delay = exponential.min(MAX_DELAY);
+  let window = delay
+    .saturating_mul(waiting.min(8) as u32 + 1)
+    .min(MAX_DELAY);
+  // Full jitter over the upper half, so that a retry never comes sooner than
+  // half the exponential delay.
+  rand::thread_rng().gen_range(delay / 2..=window.max(delay))
+}
+
+/// Accepts a connection, retrying descriptor exhaustion.
+///
+/// `accept` is called again for every attempt. When the reserved descriptor
+/// is enabled, each failed attempt sheds one pending connection.
+pub async fn accept_with_retry<T, F, Fut>(mut accept: F) -> io::Result<T>
+where
+  F: FnMut() -> Fut,
+  Fut: Future<Output = io::Result<T>>,
+{
+  let mut retry = FdExhaustionRetry::default();
+  loop {
+    match accept().await {
+      Err(err) if retry.should_retry(&err) => {
+        if let Some(_released) = ReleasedFd::release() {
+          // Only take a connection that is already pending, never wait for
+          // a new one while the reserve is released.
+          drop(accept().now_or_never());
+        }
+        retry.backoff().await;
+      }
+      result => {
+        if result.is_ok() && RESERVE_MISSING.load(Ordering::Relaxed) {
+          restore_reserved_fd();
+        }
+        return result;
+      }
+    }
+  }
+}
+
+/// Opens the reserved descriptor used by [`accept_with_retry`]. Should be
+/// called once at startup, before any descriptors are exhausted.
+pub fn reserve_fd() -> io::Result<()> {
+  let file = File::open(NULL_DEVICE)?;
+  *RESERVED_FD.lock().unwrap() = Some(file);
+  RESERVE_ENABLED.store(true, Ordering::Release);
+  Ok(())
+}
+
+/// Calls [`reserve_fd`] if `DENO_RESERVE_FD=1` is set.
+pub fn reserve_fd_from_env() -> io::Result<()> {
+  if std::env::var(RESERVE_FD_ENV_VAR_NAME).is_ok_and(|value| value == "1") {
+    reserve_fd()?;
+  }
+  Ok(())
+}
+
+fn restore_reserved_fd() {
+  let mut reserved = RESERVED_FD.lock().unwrap();
+  if reserved.is_none() && RESERVE_ENABLED.load(Ordering::Acquire) {
+    if let Ok(file) = File::open(NULL_DEVICE) {
+      *reserved = Some(file);
+      RESERVE_MISSING.store(false, Ordering::Relaxed);
+    }
+  }
+}
+
+/// The reserved descriptor is closed while this is alive, and reopened when
+/// it is dropped.
+struct ReleasedFd;
+
+impl ReleasedFd {
+  fn release() -> Option<Self> {
+    let file = RESERVED_FD.lock().unwrap().take()?;
+    drop(file);
+    RESERVE_MISSING.store(true, Ordering::Relaxed);
+    Some(Self)
+  }
+}
+
+impl Drop for ReleasedFd {
+  fn drop(&mut self) {
+    restore_reserved_fd();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(unix)]
+  fn emfile() -> io::Error {
+    io::Error::from_raw_os_error(libc::EMFILE)
+  }
+
+  #[cfg(windows)]
+  fn emfile() -> io::Error {
+    io::Error::from_raw_os_error(4)
+  }
+
+  #[test]
+  fn should_retry_is_bounded() {
+    let mut retry = FdExhaustionRetry::default();
+    assert!(!retry.should_retry(&io::Error::from(io::ErrorKind::NotFound)));
+    for _ in 0..MAX_RETRIES {
+      assert!(retry.should_retry(&emfile()));
+    }
+    assert!(!retry.should_retry(&emfile()));
+    assert_eq!(retry.attempts(), MAX_RETRIES);
+    assert!(FsError::Io(emfile()).is_fd_exhaustion());
+    assert!(!FsError::FileBusy.is_fd_exhaustion());
+  }
+
+  #[test]
+  fn backoff_delay_bounds() {
+    for attempt in 1..=MAX_RETRIES {
+      let delay = BASE_DELAY.saturating_mul(1 << (attempt - 1)).min(MAX_DELAY);
+      for waiting in [0, 1, 100] {
+        let actual = backoff_delay(attempt, waiting);
+        assert!(actual >= delay / 2, "{actual:?} for attempt {attempt}");
+        assert!(actual <= MAX_DELAY, "{actual:?} for attempt {attempt}");
+      }
+    }
+    assert!(backoff_delay(1, 0) <= BASE_DELAY);
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn accept_with_retry_recovers() {
+    let mut calls = 0;
+    let result = accept_with_retry(|| {
+      calls += 1;
+      let result = if calls < 3 { Err(emfile()) } else { Ok(calls) };
+      std::future::ready(result)
+    })
+    .await;
+    assert_eq!(result.unwrap(), 3);
+
+    let mut calls = 0;
+    let err = accept_with_retry(|| {
+      calls += 1;
+      std::future::ready(Err::<(), _>(emfile()))
+    })
+    .await
+    .unwrap_err();
+    assert!(err.is_fd_exhaustion());
+    assert_eq!(calls, MAX_RETRIES + 1);
+  }
+}
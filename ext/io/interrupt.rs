@@ -0,0 +1,143 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Cooperative cancellation of long-running ops.
+//!
+//! Every runtime has an [`InterruptHandle`] in its `OpState`. It can be
+//! cloned and sent to other threads, e.g. a watchdog or a signal handler,
+//! which call [`InterruptHandle::interrupt`]. Ops that can run for a long time
+//! take an [`InterruptToken`] when they start and poll it between units of
+//! work with [`check_interrupt!`](crate::check_interrupt), returning
+//! [`Interrupted`] once the runtime was interrupted.
+//!
+//! The handle counts interrupts instead of holding a flag, so an interrupt
+//! only affects ops that were running when it happened. Nothing has to reset
+//! it, and an interrupt that arrives while no op is polling is simply not
+//! observed by anyone.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use deno_core::OpState;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Operation was interrupted")]
+pub struct Interrupted;
+
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicU64>);
+
+impl InterruptHandle {
+  /// Interrupt all ops that are currently polling a token of this handle.
+  ///
+  /// Only performs an atomic increment, so it is safe to call from a signal
+  /// handler.
+  pub fn interrupt(&self) {
+    self.0.fetch_add(1, Ordering::AcqRel);
+  }
+
+  /// A token for one op call, which is interrupted by any later call to
+  /// [`InterruptHandle::interrupt`].
+  pub fn token(&self) -> InterruptToken {
+    InterruptToken {
+      start: self.0.load(Ordering::Acquire),
+      handle: Some(self.clone()),
+    }
+  }
+
+  /// The runtime's handle. Ops get a token that is never interrupted if
+  /// the embedder didn't provide one.
+  pub fn token_from_state(state: &OpState) -> InterruptToken {
+    state
+      .try_borrow::<InterruptHandle>()
+      .map(InterruptHandle::token)
+      .unwrap_or_default()
+  }
+
+  /// The counter shared by all clones, for registering with
+  /// `signal_hook::low_level`.
+  pub fn counter(&self) -> &Arc<AtomicU64> {
+    &self.0
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InterruptToken {
+  start: u64,
+  handle: Option<InterruptHandle>,
+}
+
+impl InterruptToken {
+  pub fn is_interrupted(&self) -> bool {
+    self
+      .handle
+      .as_ref()
+      .is_some_and(|handle| handle.0.load(Ordering::Acquire) != self.start)
+  }
+
+  pub fn check(&self) -> Result<(), Interrupted> {
+    if self.is_interrupted() {
+      Err(Interrupted)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Returns early with [`Interrupted`], converted into the function's error
+/// type, if `token` was interrupted.
+#[macro_export]
+macro_rules! check_interrupt {
+  ($token:expr) => {
+    if let Err(err) = $token.check() {
+      return Err(::std::convert::From::from(err));
+    }
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use std::time::Instant;
+
+  use super::*;
+
+  /// A sync op that only ends when interrupted.
+  fn busy_work(token: &InterruptToken) -> Result<(), Interrupted> {
+    loop {
+      check_interrupt!(token);
+      std::hint::black_box((0..1000u64).sum::<u64>());
+    }
+  }
+
+  #[test]
+  fn interrupt_from_other_thread() {
+    let handle = InterruptHandle::default();
+    let token = handle.token();
+    assert!(!token.is_interrupted());
+
+    let start = Instant::now();
+    let thread = std::thread::spawn({
+      let handle = handle.clone();
+      move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+      }
+    });
+    assert!(matches!(busy_work(&token), Err(Interrupted)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    thread.join().unwrap();
+
+    // the interrupt doesn't stick to later op calls
+    let token = handle.token();
+    assert!(token.check().is_ok());
+    handle.interrupt();
+    assert!(token.check().is_err());
+  }
+
+  #[test]
+  fn default_token_is_never_interrupted() {
+    let token = InterruptToken::default();
+    assert!(token.check().is_ok());
+  }
+}
@@ -55,13 +55,18 @@ use parking_lot::Mutex;
 #[cfg(windows)]
 use std::sync::Arc;
 
+pub mod fd_exhaustion;
 pub mod fs;
+mod interrupt;
 mod pipe;
 #[cfg(windows)]
 mod winpipe;
 
 mod bi_pipe;
 
+pub use interrupt::InterruptHandle;
+pub use interrupt::InterruptToken;
+pub use interrupt::Interrupted;
 pub use pipe::pipe;
 pub use pipe::AsyncPipeRead;
 pub use pipe::AsyncPipeWrite;
@@ -228,6 +233,7 @@ deno_core::extension!(deno_io,
     _ => op,
   },
   state = |state, options| {
+    state.put(InterruptHandle::default());
     if let Some(stdio) = options.stdio {
       #[cfg(windows)]
       let stdin_state = {
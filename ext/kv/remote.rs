@@ -206,6 +206,7 @@ impl<P: RemoteDbHandlerPermissions + 'static> DatabaseHandler
         pool_idle_timeout: None,
         http1: false,
         http2: true,
+        dns_resolver: Default::default(),
       },
     )?;
     let fetch_client = FetchClient(client);
@@ -3,8 +3,6 @@
 #![allow(non_upper_case_globals)]
 #![deny(unsafe_op_in_unsafe_fn)]
 
-const NAPI_VERSION: u32 = 9;
-
 use crate::*;
 use libc::INT_MAX;
 
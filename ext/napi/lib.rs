@@ -23,16 +23,24 @@ pub mod uv;
 
 use core::ptr::NonNull;
 use deno_core::op2;
+use deno_core::parking_lot::Condvar;
+use deno_core::parking_lot::Mutex;
 use deno_core::parking_lot::RwLock;
 use deno_core::url::Url;
 use deno_core::ExternalOpsTracker;
 use deno_core::OpState;
 use deno_core::V8CrossThreadTaskSpawner;
+use serde::Serialize;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::thread_local;
+use std::time::Duration;
+use std::time::Instant;
 
 #[derive(Debug, thiserror::Error)]
 pub enum NApiError {
@@ -42,6 +50,13 @@ pub enum NApiError {
   LibLoading(#[from] libloading::Error),
   #[error("Unable to find register Node-API module at {}", .0.display())]
   ModuleNotFound(PathBuf),
+  #[error(
+    "Node-API module at {} requires Node-API version {}, but this version of Deno only supports up to version {}",
+    .path.display(),
+    .required,
+    NAPI_VERSION
+  )]
+  UnsupportedVersion { path: PathBuf, required: u32 },
   #[error(transparent)]
   Permission(deno_core::error::AnyError),
 }
@@ -131,6 +146,13 @@ pub static ERROR_MESSAGES: &[&CStr] = &[
 
 pub const NAPI_AUTO_LENGTH: usize = usize::MAX;
 
+/// The highest Node-API version implemented, as returned by `napi_get_version`.
+pub const NAPI_VERSION: u32 = 9;
+/// Version reported by modules built against the experimental Node-API.
+pub const NAPI_VERSION_EXPERIMENTAL: u32 = i32::MAX as u32;
+/// Assumed for modules that don't export `node_api_module_get_api_version_v1`.
+pub const NAPI_DEFAULT_MODULE_API_VERSION: u32 = 8;
+
 thread_local! {
   pub static MODULE_TO_REGISTER: RefCell<Option<*const NapiModule>> = const { RefCell::new(None) };
 }
@@ -139,6 +161,7 @@ type napi_addon_register_func =
   unsafe extern "C" fn(env: napi_env, exports: napi_value) -> napi_value;
 type napi_register_module_v1 =
   unsafe extern "C" fn(env: napi_env, exports: napi_value) -> napi_value;
+type node_api_module_get_api_version_v1 = unsafe extern "C" fn() -> i32;
 
 #[repr(C)]
 #[derive(Clone)]
@@ -295,6 +318,105 @@ impl<T> PendingNapiAsyncWork for T where T: FnOnce() + Send + 'static {}
 pub struct NapiState {
   // Thread safe functions.
   pub env_cleanup_hooks: Rc<RefCell<Vec<(napi_cleanup_hook, *mut c_void)>>>,
+  /// Metrics of every loaded module, by file URL.
+  pub metrics: Vec<(String, Arc<NapiMetrics>)>,
+  /// The exports of every module loaded in this isolate, by canonicalized
+  /// path. Opening a module again returns the same exports instead of
+  /// running its initializer with a new `Env`.
+  pub exports: HashMap<PathBuf, v8::Global<v8::Value>>,
+  /// Every `Env` created in this isolate, in creation order. They are freed
+  /// when the state is dropped, after their instance data is finalized.
+  pub envs: Vec<*mut Env>,
+  pub async_cleanup: Arc<AsyncCleanupTracker>,
+}
+
+/// How long shutdown waits for async cleanup hooks to complete.
+const ASYNC_CLEANUP_HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Counts the async cleanup hooks that were called at shutdown and didn't
+/// signal completion yet. Updated from any thread.
+#[derive(Debug, Default)]
+pub struct AsyncCleanupTracker {
+  pending: Mutex<usize>,
+  done: Condvar,
+}
+
+impl AsyncCleanupTracker {
+  pub(crate) fn start(&self) {
+    *self.pending.lock() += 1;
+  }
+
+  pub(crate) fn finish(&self) {
+    let mut pending = self.pending.lock();
+    *pending -= 1;
+    if *pending == 0 {
+      self.done.notify_all();
+    }
+  }
+
+  /// Waits until every started hook completed. Returns false on timeout.
+  fn wait(&self, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut pending = self.pending.lock();
+    while *pending > 0 {
+      if self.done.wait_until(&mut pending, deadline).timed_out() {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+/// Counters of the work a module scheduled on the event loop, to diagnose
+/// which module keeps it alive. Updated from any thread.
+#[derive(Debug, Default)]
+pub struct NapiMetrics {
+  /// Async work items that were queued and haven't completed yet.
+  pub pending_async_work: AtomicUsize,
+  /// Threadsafe function calls that weren't dispatched to JS yet.
+  pub pending_threadsafe_calls: AtomicUsize,
+  /// Threadsafe functions that weren't finalized yet.
+  pub active_threadsafe_functions: AtomicUsize,
+  /// Threadsafe functions that keep the event loop alive.
+  pub referenced_threadsafe_functions: AtomicUsize,
+  /// Total number of async work items whose `execute` callback ran.
+  pub executed_async_work: AtomicUsize,
+  /// Total number of async work items completed with `napi_cancelled`.
+  pub cancelled_async_work: AtomicUsize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NapiModuleMetrics {
+  filename: String,
+  pending_async_work: usize,
+  pending_threadsafe_calls: usize,
+  active_threadsafe_functions: usize,
+  referenced_threadsafe_functions: usize,
+  executed_async_work: usize,
+  cancelled_async_work: usize,
+}
+
+impl NapiModuleMetrics {
+  fn new(filename: &str, metrics: &NapiMetrics) -> Self {
+    Self {
+      filename: filename.to_string(),
+      pending_async_work: metrics.pending_async_work.load(Ordering::Relaxed),
+      pending_threadsafe_calls: metrics
+        .pending_threadsafe_calls
+        .load(Ordering::Relaxed),
+      active_threadsafe_functions: metrics
+        .active_threadsafe_functions
+        .load(Ordering::Relaxed),
+      referenced_threadsafe_functions: metrics
+        .referenced_threadsafe_functions
+        .load(Ordering::Relaxed),
+      executed_async_work: metrics.executed_async_work.load(Ordering::Relaxed),
+      cancelled_async_work: metrics
+        .cancelled_async_work
+        .load(Ordering::Relaxed),
+    }
+  }
 }
 
 impl Drop for NapiState {
@@ -329,6 +451,37 @@ impl Drop for NapiState {
           .retain(|pair| !(pair.0 == hook.0 && pair.1 == hook.1));
       }
     }
+
+    // Async hooks signal completion by removing their handle, possibly
+    // from another thread, which must happen before the `Env` is freed.
+    if !self.async_cleanup.wait(ASYNC_CLEANUP_HOOK_TIMEOUT) {
+      log::warn!(
+        "Timed out waiting for Node-API async cleanup hooks to complete"
+      );
+      // The remaining hooks may still use their `Env`.
+      self.envs.clear();
+      return;
+    }
+
+    // Like in Node, instance data is finalized after the cleanup hooks ran,
+    // and the most recently created `Env` goes first.
+    for env_ptr in self.envs.drain(..).rev() {
+      // SAFETY: `op_napi_open` created the `Env` and its `EnvShared` with
+      // `Box::into_raw`, and they are only freed here.
+      unsafe {
+        let instance_data = (*(*env_ptr).shared).instance_data.take();
+        if let Some(InstanceData {
+          data,
+          finalize_cb: Some(finalize_cb),
+          finalize_hint,
+        }) = instance_data
+        {
+          finalize_cb(env_ptr as _, data, finalize_hint);
+        }
+        let env = Box::from_raw(env_ptr);
+        drop(Box::from_raw(env.shared));
+      }
+    }
   }
 }
 
@@ -377,12 +530,14 @@ pub struct Env {
   pub shared: *mut EnvShared,
   pub async_work_sender: V8CrossThreadTaskSpawner,
   cleanup_hooks: Rc<RefCell<Vec<(napi_cleanup_hook, *mut c_void)>>>,
+  pub async_cleanup: Arc<AsyncCleanupTracker>,
   external_ops_tracker: ExternalOpsTracker,
   pub last_error: napi_extended_error_info,
   pub last_exception: Option<v8::Global<v8::Value>>,
   pub global: v8::Global<v8::Object>,
   pub buffer_constructor: v8::Global<v8::Function>,
   pub report_error: v8::Global<v8::Function>,
+  pub metrics: Arc<NapiMetrics>,
 }
 
 unsafe impl Send for Env {}
@@ -398,7 +553,9 @@ impl Env {
     report_error: v8::Global<v8::Function>,
     sender: V8CrossThreadTaskSpawner,
     cleanup_hooks: Rc<RefCell<Vec<(napi_cleanup_hook, *mut c_void)>>>,
+    async_cleanup: Arc<AsyncCleanupTracker>,
     external_ops_tracker: ExternalOpsTracker,
+    metrics: Arc<NapiMetrics>,
   ) -> Self {
     Self {
       isolate_ptr,
@@ -410,6 +567,7 @@ impl Env {
       open_handle_scopes: 0,
       async_work_sender: sender,
       cleanup_hooks,
+      async_cleanup,
       external_ops_tracker,
       last_error: napi_extended_error_info {
         error_message: std::ptr::null(),
@@ -418,6 +576,7 @@ impl Env {
         error_code: napi_ok,
       },
       last_exception: None,
+      metrics,
     }
   }
 
@@ -458,10 +617,18 @@ impl Env {
 
   pub fn threadsafe_function_ref(&mut self) {
     self.external_ops_tracker.ref_op();
+    self
+      .metrics
+      .referenced_threadsafe_functions
+      .fetch_add(1, Ordering::Relaxed);
   }
 
   pub fn threadsafe_function_unref(&mut self) {
     self.external_ops_tracker.unref_op();
+    self
+      .metrics
+      .referenced_threadsafe_functions
+      .fetch_sub(1, Ordering::Relaxed);
   }
 
   pub fn add_cleanup_hook(
@@ -497,11 +664,16 @@ impl Env {
 deno_core::extension!(deno_napi,
   parameters = [P: NapiPermissions],
   ops = [
-    op_napi_open<P>
+    op_napi_open<P>,
+    op_napi_metrics,
   ],
   state = |state| {
     state.put(NapiState {
       env_cleanup_hooks: Rc::new(RefCell::new(vec![])),
+      metrics: vec![],
+      exports: HashMap::new(),
+      envs: vec![],
+      async_cleanup: Default::default(),
     });
   },
 );
@@ -550,14 +722,27 @@ where
 {
   // We must limit the OpState borrow because this function can trigger a
   // re-borrow through the NAPI module.
-  let (async_work_sender, cleanup_hooks, external_ops_tracker, path) = {
+  let (
+    async_work_sender,
+    cleanup_hooks,
+    async_cleanup,
+    external_ops_tracker,
+    path,
+  ) = {
     let mut op_state = op_state.borrow_mut();
     let permissions = op_state.borrow_mut::<NP>();
     let path = permissions.check(&path).map_err(NApiError::Permission)?;
+    // The same file may be opened through different paths, e.g. through a
+    // symlink. If it doesn't exist, loading it fails below.
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
     let napi_state = op_state.borrow::<NapiState>();
+    if let Some(exports) = napi_state.exports.get(&path) {
+      return Ok(v8::Local::new(scope, exports));
+    }
     (
       op_state.borrow::<V8CrossThreadTaskSpawner>().clone(),
       napi_state.env_cleanup_hooks.clone(),
+      napi_state.async_cleanup.clone(),
       op_state.external_ops_tracker.clone(),
       path,
     )
@@ -576,6 +761,13 @@ where
   let env_shared =
     EnvShared::new(napi_wrap, type_tag, format!("{url_filename}\0"));
 
+  let metrics = Arc::new(NapiMetrics::default());
+  op_state
+    .borrow_mut()
+    .borrow_mut::<NapiState>()
+    .metrics
+    .push((url_filename.to_string(), metrics.clone()));
+
   let ctx = scope.get_current_context();
   let mut env = Env::new(
     isolate,
@@ -585,10 +777,17 @@ where
     v8::Global::new(scope, report_error),
     async_work_sender,
     cleanup_hooks,
+    async_cleanup,
     external_ops_tracker,
+    metrics,
   );
   env.shared = Box::into_raw(Box::new(env_shared));
-  let env_ptr = Box::into_raw(Box::new(env)) as _;
+  let env_ptr = Box::into_raw(Box::new(env));
+  op_state
+    .borrow_mut()
+    .borrow_mut::<NapiState>()
+    .envs
+    .push(env_ptr);
 
   #[cfg(unix)]
   let flags = RTLD_LAZY;
@@ -611,43 +810,107 @@ where
   // The `module.exports` object.
   let exports = v8::Object::new(scope);
 
-  let maybe_exports = if let Some(module_to_register) = maybe_module {
-    NAPI_LOADED_MODULES
-      .write()
-      .insert(path, NapiModuleHandle(module_to_register));
-    // SAFETY: napi_register_module guarantees that `module_to_register` is valid.
-    let nm = unsafe { &*module_to_register };
-    assert_eq!(nm.nm_version, 1);
-    // SAFETY: we are going blind, calling the register function on the other side.
-    unsafe { (nm.nm_register_func)(env_ptr, exports.into()) }
-  } else if let Some(module_to_register) =
-    { NAPI_LOADED_MODULES.read().get(&path).copied() }
-  {
-    // SAFETY: this originated from `napi_register_module`, so the
-    // pointer should still be valid.
-    let nm = unsafe { &*module_to_register.0 };
-    assert_eq!(nm.nm_version, 1);
-    // SAFETY: we are going blind, calling the register function on the other side.
-    unsafe { (nm.nm_register_func)(env_ptr, exports.into()) }
-  } else if let Ok(init) = unsafe {
-    library.get::<napi_register_module_v1>(b"napi_register_module_v1")
-  } {
-    // Initializer callback.
+  let register: napi_addon_register_func =
+    if let Some(module_to_register) = maybe_module {
+      NAPI_LOADED_MODULES
+        .write()
+        .insert(path.clone(), NapiModuleHandle(module_to_register));
+      // SAFETY: napi_register_module guarantees that `module_to_register` is valid.
+      let nm = unsafe { &*module_to_register };
+      assert_eq!(nm.nm_version, 1);
+      nm.nm_register_func
+    } else if let Some(module_to_register) =
+      { NAPI_LOADED_MODULES.read().get(&path).copied() }
+    {
+      // SAFETY: this originated from `napi_register_module`, so the
+      // pointer should still be valid.
+      let nm = unsafe { &*module_to_register.0 };
+      assert_eq!(nm.nm_version, 1);
+      nm.nm_register_func
+    } else if let Ok(init) = unsafe {
+      library.get::<napi_register_module_v1>(b"napi_register_module_v1")
+    } {
+      // Refuse modules built for a newer Node-API before running any of their
+      // code besides the version function.
+      let required = module_api_version(&library);
+      if required > NAPI_VERSION && required != NAPI_VERSION_EXPERIMENTAL {
+        return Err(NApiError::UnsupportedVersion { path, required });
+      }
+      *init
+    } else {
+      return Err(NApiError::ModuleNotFound(path));
+    };
+
+  // NAPI addons can't be unloaded, so we're going to "forget" the library
+  // object so it lives till the program exit.
+  std::mem::forget(library);
+
+  let (maybe_exports, exception) = {
+    let tc_scope = &mut v8::TryCatch::new(scope);
     // SAFETY: we are going blind, calling the register function on the other side.
-    unsafe { init(env_ptr, exports.into()) }
-  } else {
-    return Err(NApiError::ModuleNotFound(path));
+    let maybe_exports = unsafe { register(env_ptr as _, exports.into()) };
+    // SAFETY: `env_ptr` was created from a `Box<Env>` above, and the
+    // register function returned.
+    let env = unsafe { &mut *env_ptr };
+    // Exceptions thrown with `napi_throw*` are recorded on the `Env`, others
+    // are only caught here.
+    let exception = env.last_exception.take().or_else(|| {
+      let exception = tc_scope.exception()?;
+      Some(v8::Global::new(tc_scope, exception))
+    });
+    (maybe_exports, exception)
   };
+  if let Some(exception) = exception {
+    // The exports aren't cached, so loading the module again runs the
+    // register function again, like in Node.
+    let exception = v8::Local::new(scope, exception);
+    scope.throw_exception(exception);
+    return Ok(v8::undefined(scope).into());
+  }
 
+  // A register function may return NULL instead of the exports object.
   let exports = maybe_exports.unwrap_or(exports.into());
 
-  // NAPI addons can't be unloaded, so we're going to "forget" the library
-  // object so it lives till the program exit.
-  std::mem::forget(library);
+  let global_exports = v8::Global::new(scope, exports);
+  op_state
+    .borrow_mut()
+    .borrow_mut::<NapiState>()
+    .exports
+    .insert(path, global_exports);
 
   Ok(exports)
 }
 
+/// Returns the async work and threadsafe function counters of every module
+/// loaded in this isolate.
+#[op2]
+#[serde]
+fn op_napi_metrics(state: &OpState) -> Vec<NapiModuleMetrics> {
+  state
+    .borrow::<NapiState>()
+    .metrics
+    .iter()
+    .map(|(filename, metrics)| NapiModuleMetrics::new(filename, metrics))
+    .collect()
+}
+
+/// The Node-API version the module was built against, as reported by its
+/// `node_api_module_get_api_version_v1` export.
+fn module_api_version(library: &Library) -> u32 {
+  // SAFETY: the symbol, when present, has this signature per the Node-API
+  // module registration contract.
+  let Ok(get_version) = (unsafe {
+    library.get::<node_api_module_get_api_version_v1>(
+      b"node_api_module_get_api_version_v1",
+    )
+  }) else {
+    return NAPI_DEFAULT_MODULE_API_VERSION;
+  };
+  // SAFETY: calling the version function takes no arguments and has no
+  // preconditions.
+  unsafe { get_version() as u32 }
+}
+
 #[allow(clippy::print_stdout)]
 pub fn print_linker_flags(name: &str) {
   let symbols_path =
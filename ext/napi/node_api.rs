@@ -66,11 +66,19 @@ struct AsyncCleanupHandle {
   env: *mut Env,
   hook: napi_async_cleanup_hook,
   data: *mut c_void,
+  tracker: Arc<AsyncCleanupTracker>,
+  /// Set when the hook is called at shutdown. From then on, removing the
+  /// handle signals that the hook completed, possibly from another thread.
+  started: AtomicBool,
 }
 
 unsafe extern "C" fn async_cleanup_handler(arg: *mut c_void) {
   unsafe {
-    let handle = Box::<AsyncCleanupHandle>::from_raw(arg as _);
+    // The handle is freed by `napi_remove_async_cleanup_hook()`, which the
+    // hook calls once it is done.
+    let handle = &*(arg as *const AsyncCleanupHandle);
+    handle.started.store(true, Ordering::SeqCst);
+    handle.tracker.start();
     (handle.hook)(arg, handle.data);
   }
 }
@@ -91,6 +99,8 @@ fn napi_add_async_cleanup_hook(
     env,
     hook,
     data: arg,
+    tracker: env.async_cleanup.clone(),
+    started: AtomicBool::new(false),
   })) as *mut c_void;
 
   env.add_cleanup_hook(async_cleanup_handler, handle);
@@ -115,9 +125,12 @@ fn napi_remove_async_cleanup_hook(
   let handle =
     unsafe { Box::<AsyncCleanupHandle>::from_raw(remove_handle as _) };
 
-  let env = unsafe { &mut *handle.env };
-
-  env.remove_cleanup_hook(async_cleanup_handler, remove_handle);
+  if handle.started.load(Ordering::SeqCst) {
+    handle.tracker.finish();
+  } else {
+    let env = unsafe { &mut *handle.env };
+    env.remove_cleanup_hook(async_cleanup_handler, remove_handle);
+  }
 
   napi_ok
 }
@@ -588,6 +601,9 @@ pub(crate) fn napi_queue_async_work(
     return napi_clear_last_error(env);
   }
 
+  let metrics = env.metrics.clone();
+  metrics.pending_async_work.fetch_add(1, Ordering::Relaxed);
+
   let work = SendPtr(work);
 
   env.add_async_work(move || {
@@ -605,6 +621,7 @@ pub(crate) fn napi_queue_async_work(
       unsafe {
         (work.execute)(work.env as _, work.data);
       }
+      metrics.executed_async_work.fetch_add(1, Ordering::Relaxed);
 
       // reset back to idle if its still marked as running
       let _ = work.state.compare_exchange(
@@ -615,6 +632,11 @@ pub(crate) fn napi_queue_async_work(
       );
     }
 
+    if state == Err(AsyncWork::IDLE) {
+      metrics.cancelled_async_work.fetch_add(1, Ordering::Relaxed);
+    }
+    metrics.pending_async_work.fetch_sub(1, Ordering::Relaxed);
+
     if let Some(complete) = work.complete {
       let status = if state.is_ok() {
         napi_ok
@@ -642,13 +664,19 @@ fn napi_cancel_async_work(env: *mut Env, work: napi_async_work) -> napi_status {
 
   let work = unsafe { &*(work as *mut AsyncWork) };
 
-  let _ = work.state.compare_exchange(
+  // Only work that hasn't started can be cancelled. The queued task still
+  // runs, and calls `complete` with `napi_cancelled` instead of `execute`.
+  let cancelled = work.state.compare_exchange(
     AsyncWork::QUEUED,
     AsyncWork::IDLE,
     Ordering::SeqCst,
     Ordering::Relaxed,
   );
 
+  if cancelled.is_err() {
+    return napi_set_last_error(env, napi_generic_failure);
+  }
+
   napi_clear_last_error(env)
 }
 
@@ -685,6 +713,9 @@ struct TsFn {
   is_closed: Arc<AtomicBool>,
   sender: V8CrossThreadTaskSpawner,
   is_ref: AtomicBool,
+  metrics: Arc<NapiMetrics>,
+  /// The thread of the isolate, which drains the queue.
+  js_thread: std::thread::ThreadId,
 }
 
 impl Drop for TsFn {
@@ -695,6 +726,10 @@ impl Drop for TsFn {
       .is_ok());
 
     self.unref();
+    self
+      .metrics
+      .active_threadsafe_functions
+      .fetch_sub(1, Ordering::Relaxed);
 
     if let Some(finalizer) = self.thread_finalize_cb {
       unsafe {
@@ -792,6 +827,11 @@ impl TsFn {
       let mut queue_size = self.queue_size.lock();
       while *queue_size >= self.max_queue_size {
         if mode == napi_tsfn_blocking {
+          // Calls are only dequeued on the JS thread, so blocking it on a
+          // full queue would never return.
+          if std::thread::current().id() == self.js_thread {
+            return napi_would_deadlock;
+          }
           self.queue_cond.wait(&mut queue_size);
 
           if self.is_closing.load(Ordering::SeqCst) {
@@ -804,6 +844,11 @@ impl TsFn {
       *queue_size += 1;
     }
 
+    let metrics = self.metrics.clone();
+    metrics
+      .pending_threadsafe_calls
+      .fetch_add(1, Ordering::Relaxed);
+
     let is_closed = self.is_closed.clone();
     let tsfn = SendPtr(self);
     let data = SendPtr(data);
@@ -812,6 +857,9 @@ impl TsFn {
 
     self.sender.spawn(move |scope: &mut v8::HandleScope| {
       let data = data.take();
+      metrics
+        .pending_threadsafe_calls
+        .fetch_sub(1, Ordering::Relaxed);
 
       // if is_closed then tsfn is freed, don't read from it.
       if is_closed.load(Ordering::Relaxed) {
@@ -920,8 +968,14 @@ fn napi_create_threadsafe_function(
     is_closed: Arc::new(AtomicBool::new(false)),
     is_ref: AtomicBool::new(false),
     sender: env.async_work_sender.clone(),
+    metrics: env.metrics.clone(),
+    js_thread: std::thread::current().id(),
   });
 
+  tsfn
+    .metrics
+    .active_threadsafe_functions
+    .fetch_add(1, Ordering::Relaxed);
   tsfn.ref_();
 
   unsafe {
@@ -9,28 +9,36 @@ const {
 } = core;
 import {
   op_dns_resolve,
+  op_net_accept_any,
+  op_net_accept_named_pipe,
   op_net_accept_tcp,
   op_net_accept_unix,
+  op_net_connect_named_pipe,
   op_net_connect_tcp,
   op_net_connect_unix,
   op_net_join_multi_v4_udp,
   op_net_join_multi_v6_udp,
   op_net_leave_multi_v4_udp,
   op_net_leave_multi_v6_udp,
+  op_net_listen_named_pipe,
   op_net_listen_tcp,
   op_net_listen_unix,
+  op_net_proxy,
   op_net_recv_udp,
   op_net_recv_unixpacket,
   op_net_send_udp,
   op_net_send_unixpacket,
+  op_net_sendfile,
   op_net_set_multi_loopback_udp,
   op_net_set_multi_ttl_udp,
+  op_retry_policy_new,
   op_set_keepalive,
   op_set_nodelay,
 } from "ext:core/ops";
 const UDP_DGRAM_MAXSIZE = 65507;
 
 const {
+  ArrayPrototypePush,
   Error,
   Number,
   NumberIsNaN,
@@ -273,13 +281,21 @@ class Listener {
       case "unix":
         promise = op_net_accept_unix(this.#rid);
         break;
+      case "namedPipe":
+        promise = op_net_accept_named_pipe(this.#rid);
+        break;
       default:
         throw new Error(`Unsupported transport: ${this.addr.transport}`);
     }
     this.#promise = promise;
     if (this.#unref) core.unrefOpPromise(promise);
-    const { 0: rid, 1: localAddr, 2: remoteAddr } = await promise;
+    const result = await promise;
     this.#promise = null;
+    if (this.addr.transport == "namedPipe") {
+      const addr = { transport: "namedPipe", path: result[1] };
+      return new Conn(result[0], addr, addr);
+    }
+    const { 0: rid, 1: localAddr, 2: remoteAddr } = result;
     if (this.addr.transport == "tcp") {
       localAddr.transport = "tcp";
       remoteAddr.transport = "tcp";
@@ -523,6 +539,13 @@ function listen(args) {
       };
       return new Listener(rid, addr);
     }
+    case "namedPipe": {
+      const { 0: rid, 1: path } = op_net_listen_named_pipe(args.path, {
+        maxInstances: args.maxInstances,
+        securityDescriptor: args.securityDescriptor,
+      });
+      return new Listener(rid, { transport: "namedPipe", path });
+    }
     default:
       throw new TypeError(`Unsupported transport: '${transport}'`);
   }
@@ -580,15 +603,46 @@ async function connect(args) {
   switch (args.transport ?? "tcp") {
     case "tcp": {
       const port = validatePort(args.port);
-      const { 0: rid, 1: localAddr, 2: remoteAddr } = await op_net_connect_tcp(
-        {
-          hostname: args.hostname ?? "127.0.0.1",
-          port,
-        },
-      );
-      localAddr.transport = "tcp";
-      remoteAddr.transport = "tcp";
-      return new TcpConn(rid, remoteAddr, localAddr);
+      let retryPolicyRid;
+      let cancelRid;
+      let abortHandler;
+      if (args.signal) {
+        args.signal.throwIfAborted();
+        cancelRid = createCancelHandle();
+        abortHandler = () => core.tryClose(cancelRid);
+        args.signal[abortSignal.add](abortHandler);
+      }
+      try {
+        if (args.retry) {
+          retryPolicyRid = op_retry_policy_new(args.retry);
+        }
+        const { 0: rid, 1: localAddr, 2: remoteAddr, 3: retryStats } =
+          await op_net_connect_tcp(
+            {
+              hostname: args.hostname ?? "127.0.0.1",
+              port,
+            },
+            { retryPolicyRid, cancelRid },
+          );
+        localAddr.transport = "tcp";
+        remoteAddr.transport = "tcp";
+        const conn = new TcpConn(rid, remoteAddr, localAddr);
+        if (retryPolicyRid !== undefined) {
+          conn.retryStats = retryStats;
+        }
+        return conn;
+      } finally {
+        if (retryPolicyRid !== undefined) {
+          core.tryClose(retryPolicyRid);
+        }
+        if (args.signal) {
+          args.signal[abortSignal.remove](abortHandler);
+          core.tryClose(cancelRid);
+
+          // always throw the abort error when aborted
+          args.signal.throwIfAborted();
+        }
+      }
     }
     case "unix": {
       const { 0: rid, 1: localAddr, 2: remoteAddr } = await op_net_connect_unix(
@@ -600,19 +654,103 @@ async function connect(args) {
         { transport: "unix", path: localAddr },
       );
     }
+    case "namedPipe": {
+      const { 0: rid, 1: path } = await op_net_connect_named_pipe(args.path);
+      const addr = { transport: "namedPipe", path };
+      return new Conn(rid, addr, addr);
+    }
     default:
       throw new TypeError(`Unsupported transport: '${transport}'`);
   }
 }
 
+async function acceptAny(listeners, options = { __proto__: null }) {
+  const { signal } = options;
+  signal?.throwIfAborted();
+  const rids = [];
+  for (let i = 0; i < listeners.length; ++i) {
+    const listener = listeners[i];
+    if (listener.addr.transport !== "tcp") {
+      throw new TypeError(
+        `Failed to execute 'acceptAny': listener at index ${i} is not a TCP listener`,
+      );
+    }
+    ArrayPrototypePush(rids, listener[internalRidSymbol]);
+  }
+  let cancelRid;
+  let abortHandler;
+  if (signal) {
+    cancelRid = createCancelHandle();
+    abortHandler = () => core.tryClose(cancelRid);
+    signal[abortSignal.add](abortHandler);
+  }
+  try {
+    const { rid, localAddr, remoteAddr, listenerIndex } =
+      await op_net_accept_any(rids, cancelRid);
+    localAddr.transport = "tcp";
+    remoteAddr.transport = "tcp";
+    return {
+      conn: new TcpConn(rid, remoteAddr, localAddr),
+      listener: listeners[listenerIndex],
+      index: listenerIndex,
+    };
+  } finally {
+    if (signal) {
+      signal[abortSignal.remove](abortHandler);
+      core.tryClose(cancelRid);
+
+      // always throw the abort error when aborted
+      signal.throwIfAborted();
+    }
+  }
+}
+
+async function proxy(a, b, options = { __proto__: null }) {
+  const { idleTimeoutMs, signal } = options;
+  signal?.throwIfAborted();
+  let cancelRid;
+  let abortHandler;
+  if (signal) {
+    cancelRid = createCancelHandle();
+    abortHandler = () => core.tryClose(cancelRid);
+    signal[abortSignal.add](abortHandler);
+  }
+  try {
+    return await op_net_proxy(a[internalRidSymbol], b[internalRidSymbol], {
+      idleTimeoutMs,
+      cancelRid,
+    });
+  } finally {
+    if (signal) {
+      signal[abortSignal.remove](abortHandler);
+      core.tryClose(cancelRid);
+
+      // always throw the abort error when aborted
+      signal.throwIfAborted();
+    }
+  }
+}
+
+async function sendFile(conn, file, options = { __proto__: null }) {
+  const { offset, length } = options;
+  return await op_net_sendfile(
+    conn[internalRidSymbol],
+    file[internalRidSymbol],
+    { offset, length },
+  );
+}
+
 export {
+  acceptAny,
   Conn,
   connect,
   createListenDatagram,
   listen,
   Listener,
   listenOptionApiName,
+  proxy,
   resolveDns,
+  sendFile,
   TcpConn,
   UnixConn,
   UpgradedConn,
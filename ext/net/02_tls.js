@@ -10,6 +10,7 @@ import {
   op_tls_cert_resolver_poll,
   op_tls_cert_resolver_resolve,
   op_tls_cert_resolver_resolve_error,
+  op_tls_cert_spki_hash,
   op_tls_handshake,
   op_tls_key_null,
   op_tls_key_static,
@@ -50,6 +51,8 @@ async function connectTls({
   keyFormat = undefined,
   cert = undefined,
   key = undefined,
+  pins = undefined,
+  pinsReportOnly = false,
 }) {
   if (transport !== "tcp") {
     throw new TypeError(`Unsupported transport: '${transport}'`);
@@ -65,7 +68,7 @@ async function connectTls({
   const serverName = arguments[0][serverNameSymbol] ?? null;
   const { 0: rid, 1: localAddr, 2: remoteAddr } = await op_net_connect_tls(
     { hostname, port },
-    { caCerts, alpnProtocols, serverName },
+    { caCerts, alpnProtocols, serverName, pins, pinsReportOnly },
     keyPair,
   );
   localAddr.transport = "tcp";
@@ -180,6 +183,8 @@ async function startTls(
     hostname = "127.0.0.1",
     caCerts = [],
     alpnProtocols = undefined,
+    pins = undefined,
+    pinsReportOnly = false,
   } = { __proto__: null },
 ) {
   const { 0: rid, 1: localAddr, 2: remoteAddr } = op_tls_start({
@@ -187,10 +192,16 @@ async function startTls(
     hostname,
     caCerts,
     alpnProtocols,
+    pins,
+    pinsReportOnly,
   });
   return new TlsConn(rid, remoteAddr, localAddr);
 }
 
+function certificateSpkiHash(cert) {
+  return op_tls_cert_spki_hash(cert);
+}
+
 const resolverSymbol = SymbolFor("unstableSniResolver");
 const serverNameSymbol = SymbolFor("unstableServerName");
 
@@ -223,6 +234,7 @@ internals.serverNameSymbol = serverNameSymbol;
 internals.createTlsKeyResolver = createTlsKeyResolver;
 
 export {
+  certificateSpkiHash,
   connectTls,
   hasTlsKeyPairOptions,
   listenTls,
@@ -0,0 +1,147 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Accepting connections from several TCP listeners with a single op call,
+//! for programs that serve multiple pre-bound sockets from one accept loop.
+
+use std::cell::RefCell;
+use std::future::poll_fn;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::Poll;
+
+use deno_core::op2;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::CancelTryFuture;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::ResourceId;
+use serde::Serialize;
+use tokio::net::TcpStream;
+
+use crate::io::TcpStreamResource;
+use crate::ops::accept_err;
+use crate::ops::IpAddr;
+use crate::ops::NetError;
+use crate::raw::NetworkListenerResource;
+use crate::tcp::TcpListener;
+
+type AcceptFuture =
+  Pin<Box<dyn Future<Output = Result<(TcpStream, SocketAddr), NetError>>>>;
+
+/// The listener that the next `op_net_accept_any` call polls first. It moves
+/// by one on every call, so that a listener that always has a connection
+/// waiting can't starve the listeners after it.
+#[derive(Default)]
+struct AcceptAnyRotation(usize);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptAnyResult {
+  rid: ResourceId,
+  local_addr: IpAddr,
+  remote_addr: IpAddr,
+  /// Index of the listener in the `rids` passed to the op.
+  listener_index: usize,
+  listener_rid: ResourceId,
+}
+
+struct PendingAccept {
+  index: usize,
+  rid: ResourceId,
+  accept: AcceptFuture,
+}
+
+/// Waits for a connection on any of the TCP listeners in `rids`.
+///
+/// A listener that is closed before or during the call is dropped from the
+/// set; the op only rejects with "Listener has been closed" once no listener
+/// is left. Closing the `cancel_rid` handle cancels the whole call.
+#[op2(async)]
+#[serde]
+pub async fn op_net_accept_any(
+  state: Rc<RefCell<OpState>>,
+  #[serde] rids: Vec<ResourceId>,
+  #[serde] cancel_rid: Option<ResourceId>,
+) -> Result<AcceptAnyResult, NetError> {
+  let (mut pending, cancel, start) = {
+    let mut state = state.borrow_mut();
+    let mut pending = Vec::with_capacity(rids.len());
+    for (index, rid) in rids.into_iter().enumerate() {
+      let Ok(resource) = state
+        .resource_table
+        .get::<NetworkListenerResource<TcpListener>>(rid)
+      else {
+        continue;
+      };
+      let listener = RcRef::map(&resource, |r| &r.listener)
+        .try_borrow_mut()
+        .ok_or(NetError::AcceptTaskOngoing)?;
+      let listener_cancel = RcRef::map(resource, |r| &r.cancel);
+      let accept: AcceptFuture = Box::pin(async move {
+        listener
+          .accept()
+          .try_or_cancel(listener_cancel)
+          .await
+          .map_err(accept_err)
+      });
+      pending.push(Some(PendingAccept { index, rid, accept }));
+    }
+    let cancel = cancel_rid
+      .map(|rid| state.resource_table.get::<CancelHandle>(rid))
+      .transpose()
+      .map_err(NetError::Resource)?;
+    if !state.has::<AcceptAnyRotation>() {
+      state.put(AcceptAnyRotation::default());
+    }
+    let rotation = state.borrow_mut::<AcceptAnyRotation>();
+    let start = rotation.0;
+    rotation.0 = rotation.0.wrapping_add(1);
+    (pending, cancel, start)
+  };
+
+  let accept = poll_fn(|cx| {
+    let len = pending.len();
+    for i in 0..len {
+      let slot = &mut pending[(start + i) % len];
+      let Some(entry) = slot else {
+        continue;
+      };
+      match entry.accept.as_mut().poll(cx) {
+        Poll::Pending => {}
+        Poll::Ready(Ok(accepted)) => {
+          return Poll::Ready(Ok((entry.index, entry.rid, accepted)));
+        }
+        Poll::Ready(Err(NetError::ListenerClosed)) => {
+          *slot = None;
+        }
+        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+      }
+    }
+    if pending.iter().all(Option::is_none) {
+      return Poll::Ready(Err(NetError::ListenerClosed));
+    }
+    Poll::Pending
+  });
+  let (listener_index, listener_rid, (tcp_stream, _socket_addr)) = match cancel
+  {
+    Some(cancel) => accept.or_cancel(cancel).await??,
+    None => accept.await?,
+  };
+  let local_addr = tcp_stream.local_addr()?;
+  let remote_addr = tcp_stream.peer_addr()?;
+
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add(TcpStreamResource::new(tcp_stream.into_split()));
+  Ok(AcceptAnyResult {
+    rid,
+    local_addr: IpAddr::from(local_addr),
+    remote_addr: IpAddr::from(remote_addr),
+    listener_index,
+    listener_rid,
+  })
+}
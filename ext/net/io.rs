@@ -19,6 +19,8 @@ use tokio::net::tcp;
 
 #[cfg(unix)]
 use tokio::net::unix;
+#[cfg(windows)]
+use tokio::net::windows::named_pipe;
 
 /// A full duplex resource has a read and write ends that are completely
 /// independent, like TCP/Unix sockets and TLS streams.
@@ -131,10 +133,10 @@ impl TcpStreamResource {
   }
 
   #[allow(clippy::type_complexity)]
-  fn map_socket(
+  pub fn map_socket<T>(
     self: Rc<Self>,
-    map: Box<dyn FnOnce(SockRef) -> Result<(), std::io::Error>>,
-  ) -> Result<(), MapError> {
+    map: Box<dyn FnOnce(SockRef) -> Result<T, std::io::Error>>,
+  ) -> Result<T, MapError> {
     if let Some(wr) = RcRef::map(self, |r| &r.wr).try_borrow() {
       let stream = wr.as_ref().as_ref();
       let socket = socket2::SockRef::from(stream);
@@ -188,3 +190,51 @@ impl Resource for UnixStreamResource {
     self.cancel_read_ops();
   }
 }
+
+#[cfg(windows)]
+pub type NamedPipeServerStreamResource = FullDuplexResource<
+  tokio::io::ReadHalf<named_pipe::NamedPipeServer>,
+  tokio::io::WriteHalf<named_pipe::NamedPipeServer>,
+>;
+
+#[cfg(windows)]
+impl Resource for NamedPipeServerStreamResource {
+  deno_core::impl_readable_byob!();
+  deno_core::impl_writable!();
+
+  fn name(&self) -> Cow<str> {
+    "namedPipeStream".into()
+  }
+
+  fn shutdown(self: Rc<Self>) -> AsyncResult<()> {
+    Box::pin(self.shutdown().map_err(Into::into))
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel_read_ops();
+  }
+}
+
+#[cfg(windows)]
+pub type NamedPipeClientStreamResource = FullDuplexResource<
+  tokio::io::ReadHalf<named_pipe::NamedPipeClient>,
+  tokio::io::WriteHalf<named_pipe::NamedPipeClient>,
+>;
+
+#[cfg(windows)]
+impl Resource for NamedPipeClientStreamResource {
+  deno_core::impl_readable_byob!();
+  deno_core::impl_writable!();
+
+  fn name(&self) -> Cow<str> {
+    "namedPipeStream".into()
+  }
+
+  fn shutdown(self: Rc<Self>) -> AsyncResult<()> {
+    Box::pin(self.shutdown().map_err(Into::into))
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel_read_ops();
+  }
+}
@@ -18,8 +18,20 @@ declare namespace Deno {
     path: string;
   }
 
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The address of a Windows named pipe, e.g. `\\.\pipe\my-pipe`.
+   *
+   * @category Network
+   * @experimental
+   */
+  export interface NamedPipeAddr {
+    transport: "namedPipe";
+    path: string;
+  }
+
   /** @category Network */
-  export type Addr = NetAddr | UnixAddr;
+  export type Addr = NetAddr | UnixAddr | NamedPipeAddr;
 
   /** A generic network listener for stream-oriented protocols.
    *
@@ -290,6 +302,33 @@ declare namespace Deno {
      * @default {"127.0.0.1"} */
     hostname?: string;
     transport?: "tcp";
+    /** Retry the connection with exponential backoff when it fails with one
+     * of the listed errors. Without it the connection is attempted once. */
+    retry?: RetryOptions;
+    /** Aborts the connection attempt, including any pending backoff. */
+    signal?: AbortSignal;
+  }
+
+  /** @category Network */
+  export interface RetryOptions {
+    /** Total number of attempts, including the first one. */
+    maxAttempts: number;
+    /** Delay before the first retry. It doubles for every further retry. */
+    baseDelayMs: number;
+    /** Upper bound of a single delay. */
+    maxDelayMs: number;
+    /** Fraction of each delay, between 0 and 1, that is randomized.
+     *
+     * @default {0} */
+    jitter?: number;
+    /** Seed of the jitter, so that a policy always produces the same delays.
+     *
+     * @default {0} */
+    seed?: number;
+    /** Errors that cause a retry. Other errors are thrown right away.
+     * `"interrupted"` only applies to transfers, such as `Deno.download`,
+     * whose connection is closed before they complete. */
+    retryOn: ("refused" | "timedOut" | "dns" | "interrupted")[];
   }
 
   /**
@@ -320,6 +359,21 @@ declare namespace Deno {
     setNoDelay(noDelay?: boolean): void;
     /** Enable/disable keep-alive functionality. */
     setKeepAlive(keepAlive?: boolean): void;
+    /** How many attempts and how much backoff the connection took. Only set
+     * when it was opened with the `retry` option. */
+    retryStats?: RetryStats;
+  }
+
+  /** Attempts and cumulative backoff of a retried operation. Errors of a
+   * retried connection that failed after at least one retry carry the same
+   * fields.
+   *
+   * @category Network */
+  export interface RetryStats {
+    /** Number of attempts, including the first one. */
+    attempts: number;
+    /** Sum of the delays slept between attempts. */
+    totalDelayMs: number;
   }
 
   /** @category Network */
@@ -1,18 +1,27 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+pub mod accept_any;
 pub mod io;
 pub mod ops;
+#[cfg(windows)]
+pub mod ops_named_pipe;
 pub mod ops_tls;
 #[cfg(unix)]
 pub mod ops_unix;
+pub mod proxy;
 pub mod raw;
 pub mod resolve_addr;
+pub mod resolver;
+pub mod retry;
+pub mod sendfile;
+pub mod socket_buffers;
 mod tcp;
 
 use deno_core::error::AnyError;
 use deno_core::OpState;
 use deno_tls::rustls::RootCertStore;
 use deno_tls::RootCertStoreProvider;
+use deno_tls::TlsClientDefaults;
 use std::borrow::Cow;
 use std::path::Path;
 use std::path::PathBuf;
@@ -109,6 +118,14 @@ impl DefaultTlsOptions {
       None => None,
     })
   }
+
+  pub fn client_defaults(&self) -> TlsClientDefaults {
+    self
+      .root_cert_store_provider
+      .as_ref()
+      .and_then(|provider| provider.client_defaults().cloned())
+      .unwrap_or_default()
+  }
 }
 
 /// `UnsafelyIgnoreCertificateErrors` is a wrapper struct so it can be placed inside `GothamState`;
@@ -121,8 +138,10 @@ deno_core::extension!(deno_net,
   deps = [ deno_web ],
   parameters = [ P: NetPermissions ],
   ops = [
+    accept_any::op_net_accept_any,
     ops::op_net_accept_tcp,
     ops::op_net_connect_tcp<P>,
+    ops::op_retry_policy_new,
     ops::op_net_listen_tcp<P>,
     ops::op_net_listen_udp<P>,
     ops::op_node_unstable_net_listen_udp<P>,
@@ -137,9 +156,14 @@ deno_core::extension!(deno_net,
     ops::op_dns_resolve<P>,
     ops::op_set_nodelay,
     ops::op_set_keepalive,
+    ops::op_net_buffered,
+    ops::op_net_set_buffer_sizes,
+    proxy::op_net_proxy,
+    sendfile::op_net_sendfile,
 
     ops_tls::op_tls_key_null,
     ops_tls::op_tls_key_static,
+    ops_tls::op_tls_cert_spki_hash,
     ops_tls::op_tls_cert_resolver_create,
     ops_tls::op_tls_cert_resolver_poll,
     ops_tls::op_tls_cert_resolver_resolve,
@@ -157,11 +181,16 @@ deno_core::extension!(deno_net,
     ops_unix::op_node_unstable_net_listen_unixpacket<P>,
     ops_unix::op_net_recv_unixpacket,
     ops_unix::op_net_send_unixpacket<P>,
+
+    ops_named_pipe::op_net_accept_named_pipe,
+    ops_named_pipe::op_net_connect_named_pipe<P>,
+    ops_named_pipe::op_net_listen_named_pipe<P>,
   ],
   esm = [ "01_net.js", "02_tls.js" ],
   options = {
     root_cert_store_provider: Option<Arc<dyn RootCertStoreProvider>>,
     unsafely_ignore_certificate_errors: Option<Vec<String>>,
+    dns_resolver: resolver::DnsResolver,
   },
   state = |state, options| {
     state.put(DefaultTlsOptions {
@@ -170,6 +199,8 @@ deno_core::extension!(deno_net,
     state.put(UnsafelyIgnoreCertificateErrors(
       options.unsafely_ignore_certificate_errors,
     ));
+    state.put(options.dns_resolver);
+    deno_web::register_async_close::<ops_tls::TlsStreamResource>(state);
   },
 );
 
@@ -216,3 +247,27 @@ mod ops_unix {
   stub_op!(op_net_recv_unixpacket);
   stub_op!(op_net_send_unixpacket<P>);
 }
+
+/// Stub ops for non-Windows platforms.
+#[cfg(not(windows))]
+mod ops_named_pipe {
+  use crate::ops::NetError;
+  use crate::NetPermissions;
+  use deno_core::op2;
+
+  #[op2(fast)]
+  pub fn op_net_accept_named_pipe() -> Result<(), NetError> {
+    Err(NetError::NamedPipeUnsupported)
+  }
+
+  #[op2(fast)]
+  pub fn op_net_connect_named_pipe<P: NetPermissions>() -> Result<(), NetError>
+  {
+    Err(NetError::NamedPipeUnsupported)
+  }
+
+  #[op2(fast)]
+  pub fn op_net_listen_named_pipe<P: NetPermissions>() -> Result<(), NetError> {
+    Err(NetError::NamedPipeUnsupported)
+  }
+}
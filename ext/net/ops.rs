@@ -2,8 +2,16 @@
 
 use crate::io::TcpStreamResource;
 use crate::raw::NetworkListenerResource;
-use crate::resolve_addr::resolve_addr;
 use crate::resolve_addr::resolve_addr_sync;
+use crate::resolver::DnsResolver;
+use crate::retry::retry;
+use crate::retry::RetryPolicy;
+use crate::retry::RetryPolicyOptions;
+use crate::retry::RetryStats;
+use crate::socket_buffers;
+use crate::socket_buffers::BufferSizes;
+use crate::socket_buffers::SetBufferSizes;
+use crate::socket_buffers::SocketBuffered;
 use crate::tcp::TcpListener;
 use crate::NetPermissions;
 use deno_core::op2;
@@ -18,10 +26,12 @@ use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
+use deno_io::fd_exhaustion::accept_with_retry;
 use serde::Deserialize;
 use serde::Serialize;
 use socket2::Domain;
 use socket2::Protocol;
+use socket2::SockRef;
 use socket2::Socket;
 use socket2::Type;
 use std::borrow::Cow;
@@ -43,6 +53,7 @@ use trust_dns_resolver::error::ResolveError;
 use trust_dns_resolver::error::ResolveErrorKind;
 use trust_dns_resolver::system_conf;
 use trust_dns_resolver::AsyncResolver;
+use trust_dns_resolver::TokioAsyncResolver;
 
 #[derive(Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -50,7 +61,7 @@ pub struct TlsHandshakeInfo {
   pub alpn_protocol: Option<ByteString>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct IpAddr {
   pub hostname: String,
   pub port: u16,
@@ -121,6 +132,34 @@ pub enum NetError {
   RootCertStore(deno_core::anyhow::Error),
   #[error("{0}")]
   Reunite(tokio::net::tcp::ReuniteError),
+  #[error("{0}")]
+  Resolve(std::io::Error),
+  #[error("Invalid retry policy: {0}")]
+  InvalidRetryPolicy(&'static str), // TypeError
+  #[error(
+    "{source} (after {attempts} attempts, {total_delay_ms}ms of backoff)"
+  )]
+  RetryFailed {
+    source: Box<NetError>,
+    attempts: u32,
+    total_delay_ms: u64,
+  },
+  #[error("Cannot proxy a connection to itself")]
+  ProxySameResource, // TypeError
+  #[error("Proxy was idle for {0}ms")]
+  ProxyIdleTimeout(u64), // TimedOut
+  #[error("Resource is not a file")]
+  SendFileNotAFile, // BadResource
+  #[error("{0}")]
+  Stream(deno_core::error::AnyError),
+  #[error("Named pipes are only supported on Windows")]
+  NamedPipeUnsupported, // NotSupported
+  #[error(r"Invalid named pipe path '{0}', expected '\\.\pipe\<name>'")]
+  InvalidPipeName(String), // TypeError
+  #[error("Invalid maxInstances {0}, expected a value between 1 and 254")]
+  InvalidPipeInstances(u32), // RangeError
+  #[error("Invalid security descriptor: {0}")]
+  InvalidSecurityDescriptor(std::io::Error), // TypeError
 }
 
 pub(crate) fn accept_err(e: std::io::Error) -> NetError {
@@ -146,8 +185,7 @@ pub async fn op_net_accept_tcp(
     .try_borrow_mut()
     .ok_or_else(|| NetError::AcceptTaskOngoing)?;
   let cancel = RcRef::map(resource, |r| &r.cancel);
-  let (tcp_stream, _socket_addr) = listener
-    .accept()
+  let (tcp_stream, _socket_addr) = accept_with_retry(|| listener.accept())
     .try_or_cancel(cancel)
     .await
     .map_err(accept_err)?;
@@ -202,10 +240,13 @@ where
       )
       .map_err(NetError::Permission)?;
   }
-  let addr = resolve_addr(&addr.hostname, addr.port)
-    .await?
-    .next()
-    .ok_or(NetError::NoResolvedAddress)?;
+  let addr = resolve_connect_addr::<NP>(
+    &state,
+    &addr.hostname,
+    addr.port,
+    "Deno.DatagramConn.send()",
+  )
+  .await?;
 
   let resource = state
     .borrow_mut()
@@ -345,16 +386,54 @@ pub async fn op_net_set_multi_ttl_udp(
   Ok(())
 }
 
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectRetryArgs {
+  retry_policy_rid: Option<ResourceId>,
+  cancel_rid: Option<ResourceId>,
+}
+
 #[op2(async)]
 #[serde]
 pub async fn op_net_connect_tcp<NP>(
   state: Rc<RefCell<OpState>>,
   #[serde] addr: IpAddr,
-) -> Result<(ResourceId, IpAddr, IpAddr), NetError>
+  #[serde] retry_args: Option<ConnectRetryArgs>,
+) -> Result<(ResourceId, IpAddr, IpAddr, RetryStats), NetError>
 where
   NP: NetPermissions + 'static,
 {
-  op_net_connect_tcp_inner::<NP>(state, addr).await
+  let retry_args = retry_args.unwrap_or_default();
+  let (policy, cancel, clock) = {
+    let state = state.borrow();
+    let policy = retry_args
+      .retry_policy_rid
+      .map(|rid| state.resource_table.get::<RetryPolicy>(rid))
+      .transpose()
+      .map_err(NetError::Resource)?;
+    let cancel = retry_args
+      .cancel_rid
+      .map(|rid| state.resource_table.get::<CancelHandle>(rid))
+      .transpose()
+      .map_err(NetError::Resource)?;
+    (policy, cancel, deno_web::clock(&state))
+  };
+  let ((rid, local_addr, remote_addr), stats) =
+    retry(policy, clock, cancel, || {
+      op_net_connect_tcp_inner::<NP>(state.clone(), addr.clone())
+    })
+    .await?;
+  Ok((rid, local_addr, remote_addr, stats))
+}
+
+#[op2]
+#[smi]
+pub fn op_retry_policy_new(
+  state: &mut OpState,
+  #[serde] options: RetryPolicyOptions,
+) -> Result<ResourceId, NetError> {
+  let policy = RetryPolicy::new(options)?;
+  Ok(state.resource_table.add(policy))
 }
 
 #[inline]
@@ -373,10 +452,13 @@ where
       .map_err(NetError::Permission)?;
   }
 
-  let addr = resolve_addr(&addr.hostname, addr.port)
-    .await?
-    .next()
-    .ok_or_else(|| NetError::NoResolvedAddress)?;
+  let addr = resolve_connect_addr::<NP>(
+    &state,
+    &addr.hostname,
+    addr.port,
+    "Deno.connect()",
+  )
+  .await?;
   let tcp_stream = TcpStream::connect(&addr).await?;
   let local_addr = tcp_stream.local_addr()?;
   let remote_addr = tcp_stream.peer_addr()?;
@@ -389,6 +471,35 @@ where
   Ok((rid, IpAddr::from(local_addr), IpAddr::from(remote_addr)))
 }
 
+/// Resolves the address to connect or send to with the runtime's
+/// [`DnsResolver`], checking that its DNS-over-HTTPS endpoint, if any, may be
+/// contacted.
+pub(crate) async fn resolve_connect_addr<NP>(
+  state: &Rc<RefCell<OpState>>,
+  hostname: &str,
+  port: u16,
+  api_name: &str,
+) -> Result<SocketAddr, NetError>
+where
+  NP: NetPermissions + 'static,
+{
+  let resolver = {
+    let mut state = state.borrow_mut();
+    let resolver = state.borrow::<DnsResolver>().clone();
+    resolver
+      .check_permissions(state.borrow_mut::<NP>(), api_name)
+      .map_err(NetError::Permission)?;
+    resolver
+  };
+  resolver
+    .lookup(hostname, port)
+    .await
+    .map_err(NetError::Resolve)?
+    .into_iter()
+    .next()
+    .ok_or(NetError::NoResolvedAddress)
+}
+
 struct UdpSocketResource {
   socket: AsyncRefCell<UdpSocket>,
   cancel: CancelHandle,
@@ -622,38 +733,23 @@ where
     cancel_rid,
   } = args;
 
-  let (config, opts) = if let Some(name_server) =
-    options.as_ref().and_then(|o| o.name_server.as_ref())
-  {
-    let group = NameServerConfigGroup::from_ips_clear(
-      &[name_server.ip_addr.parse()?],
-      name_server.port,
-      true,
-    );
-    (
-      ResolverConfig::from_parts(None, vec![], group),
-      ResolverOpts::default(),
-    )
+  let name_server = options.as_ref().and_then(|o| o.name_server.as_ref());
+  let configured = if name_server.is_none() {
+    let mut s = state.borrow_mut();
+    let resolver = s.borrow::<DnsResolver>().clone();
+    resolver
+      .check_permissions(s.borrow_mut::<NP>(), "Deno.resolveDns()")
+      .map_err(NetError::Permission)?;
+    resolver.async_resolver()?
   } else {
-    system_conf::read_system_conf()?
+    None
   };
 
-  {
-    let mut s = state.borrow_mut();
-    let perm = s.borrow_mut::<NP>();
-
-    // Checks permission against the name servers which will be actually queried.
-    for ns in config.name_servers() {
-      let socker_addr = &ns.socket_addr;
-      let ip = socker_addr.ip().to_string();
-      let port = socker_addr.port();
-      perm
-        .check_net(&(ip, Some(port)), "Deno.resolveDns()")
-        .map_err(NetError::Permission)?;
-    }
-  }
-
-  let resolver = AsyncResolver::tokio(config, opts);
+  let resolver = if let Some(resolver) = configured {
+    resolver
+  } else {
+    system_or_override_resolver::<NP>(&state, name_server)?
+  };
 
   let lookup_fut = resolver.lookup(query, record_type);
 
@@ -693,6 +789,47 @@ where
     .collect::<Result<Vec<DnsReturnRecord>, NetError>>()
 }
 
+/// Creates a resolver for the per-call name server, or one that reads the
+/// system configuration, and checks permission for the servers it will ask.
+fn system_or_override_resolver<NP>(
+  state: &Rc<RefCell<OpState>>,
+  name_server: Option<&NameServer>,
+) -> Result<TokioAsyncResolver, NetError>
+where
+  NP: NetPermissions + 'static,
+{
+  let (config, opts) = if let Some(name_server) = name_server {
+    let group = NameServerConfigGroup::from_ips_clear(
+      &[name_server.ip_addr.parse()?],
+      name_server.port,
+      true,
+    );
+    (
+      ResolverConfig::from_parts(None, vec![], group),
+      ResolverOpts::default(),
+    )
+  } else {
+    system_conf::read_system_conf()?
+  };
+
+  {
+    let mut s = state.borrow_mut();
+    let perm = s.borrow_mut::<NP>();
+
+    // Checks permission against the name servers which will be actually queried.
+    for ns in config.name_servers() {
+      let socker_addr = &ns.socket_addr;
+      let ip = socker_addr.ip().to_string();
+      let port = socker_addr.port();
+      perm
+        .check_net(&(ip, Some(port)), "Deno.resolveDns()")
+        .map_err(NetError::Permission)?;
+    }
+  }
+
+  Ok(AsyncResolver::tokio(config, opts))
+}
+
 #[op2(fast)]
 pub fn op_set_nodelay(
   state: &mut OpState,
@@ -737,6 +874,58 @@ pub fn op_set_keepalive_inner(
   resource.set_keepalive(keepalive).map_err(NetError::Map)
 }
 
+#[op2]
+#[serde]
+pub fn op_net_buffered(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<SocketBuffered, NetError> {
+  map_net_socket(state, rid, Box::new(socket_buffers::buffered))
+}
+
+#[op2]
+#[serde]
+pub fn op_net_set_buffer_sizes(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[serde] sizes: SetBufferSizes,
+) -> Result<BufferSizes, NetError> {
+  op_net_set_buffer_sizes_inner(state, rid, sizes)
+}
+
+#[inline]
+pub fn op_net_set_buffer_sizes_inner(
+  state: &mut OpState,
+  rid: ResourceId,
+  sizes: SetBufferSizes,
+) -> Result<BufferSizes, NetError> {
+  map_net_socket(
+    state,
+    rid,
+    Box::new(move |socket| socket_buffers::set_buffer_sizes(socket, sizes)),
+  )
+}
+
+/// Run `map` on the socket behind a TCP stream or UDP socket resource.
+#[allow(clippy::type_complexity)]
+fn map_net_socket<T>(
+  state: &mut OpState,
+  rid: ResourceId,
+  map: Box<dyn FnOnce(SockRef) -> Result<T, std::io::Error>>,
+) -> Result<T, NetError> {
+  if let Ok(resource) = state.resource_table.get::<TcpStreamResource>(rid) {
+    return resource.map_socket(map).map_err(NetError::Map);
+  }
+  let resource = state
+    .resource_table
+    .get::<UdpSocketResource>(rid)
+    .map_err(NetError::Resource)?;
+  let socket = RcRef::map(&resource, |r| &r.socket)
+    .try_borrow()
+    .ok_or(NetError::SocketBusy)?;
+  Ok(map(SockRef::from(&*socket))?)
+}
+
 fn rdata_to_return_record(
   ty: RecordType,
 ) -> impl Fn(&RData) -> Result<Option<DnsReturnRecord>, NetError> {
@@ -1094,6 +1283,29 @@ mod tests {
     check_sockopt(String::from("127.0.0.1:4146"), set_keepalive, test_fn).await;
   }
 
+  #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+  async fn tcp_set_buffer_sizes() {
+    let set_buffer_sizes = Box::new(|state: &mut OpState, rid| {
+      let sizes = op_net_set_buffer_sizes_inner(
+        state,
+        rid,
+        SetBufferSizes {
+          recv: Some(128 * 1024),
+          send: Some(96 * 1024),
+        },
+      )
+      .unwrap();
+      assert!(sizes.recv >= 128 * 1024);
+      assert!(sizes.send >= 96 * 1024);
+    });
+    let test_fn = Box::new(|socket: SockRef| {
+      assert!(socket.recv_buffer_size().unwrap() >= 128 * 1024);
+      assert!(socket.send_buffer_size().unwrap() >= 96 * 1024);
+    });
+    check_sockopt(String::from("127.0.0.1:4147"), set_buffer_sizes, test_fn)
+      .await;
+  }
+
   #[allow(clippy::type_complexity)]
   async fn check_sockopt(
     addr: String,
@@ -0,0 +1,314 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Windows named pipes (`\\.\pipe\<name>`) as a stream transport.
+
+use crate::io::NamedPipeClientStreamResource;
+use crate::io::NamedPipeServerStreamResource;
+use crate::ops::NetError;
+use crate::NetPermissions;
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::CancelHandle;
+use deno_core::CancelTryFuture;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::net::windows::named_pipe::ClientOptions;
+use tokio::net::windows::named_pipe::NamedPipeServer;
+use tokio::net::windows::named_pipe::ServerOptions;
+use windows_sys::Win32::Foundation::LocalFree;
+use windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED;
+use windows_sys::Win32::Foundation::ERROR_PIPE_BUSY;
+use windows_sys::Win32::Security::Authorization::ConvertStringSecurityDescriptorToSecurityDescriptorW;
+use windows_sys::Win32::Security::Authorization::SDDL_REVISION_1;
+use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+
+/// Only the owner of the pipe, i.e. the current user, has access.
+const DEFAULT_SECURITY_DESCRIPTOR: &str = "D:P(A;;GA;;;OW)";
+
+/// The limit of `CreateNamedPipe`, 255 means unlimited.
+const MAX_PIPE_INSTANCES: u32 = 254;
+
+/// How long to wait between attempts to open or create a pipe instance
+/// while all instances are busy.
+const PIPE_BUSY_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a client keeps retrying while all instances are busy.
+const PIPE_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedPipeListenOptions {
+  max_instances: Option<u32>,
+  security_descriptor: Option<String>,
+}
+
+/// A security descriptor allocated by
+/// `ConvertStringSecurityDescriptorToSecurityDescriptorW`.
+struct SecurityDescriptor(*mut c_void);
+
+impl SecurityDescriptor {
+  fn from_sddl(sddl: &str) -> Result<Self, NetError> {
+    let sddl = sddl
+      .encode_utf16()
+      .chain(std::iter::once(0))
+      .collect::<Vec<_>>();
+    let mut descriptor = std::ptr::null_mut();
+    // SAFETY: `sddl` is NUL terminated and `descriptor` is a valid out
+    // pointer; the size is optional.
+    let ok = unsafe {
+      ConvertStringSecurityDescriptorToSecurityDescriptorW(
+        sddl.as_ptr(),
+        SDDL_REVISION_1,
+        &mut descriptor,
+        std::ptr::null_mut(),
+      )
+    };
+    if ok == 0 {
+      return Err(NetError::InvalidSecurityDescriptor(
+        std::io::Error::last_os_error(),
+      ));
+    }
+    Ok(Self(descriptor as _))
+  }
+}
+
+impl Drop for SecurityDescriptor {
+  fn drop(&mut self) {
+    // SAFETY: the descriptor was allocated with `LocalAlloc`.
+    unsafe { LocalFree(self.0 as _) };
+  }
+}
+
+pub struct NamedPipeListenerResource {
+  path: String,
+  max_instances: Option<u32>,
+  security_descriptor: SecurityDescriptor,
+  /// The instance the next client connects to. It is `None` while no
+  /// instance could be created because all of them are connected.
+  server: AsyncRefCell<Option<NamedPipeServer>>,
+  cancel: CancelHandle,
+}
+
+impl Resource for NamedPipeListenerResource {
+  fn name(&self) -> Cow<str> {
+    "namedPipeListener".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl NamedPipeListenerResource {
+  fn create_instance(
+    &self,
+    first: bool,
+  ) -> Result<NamedPipeServer, std::io::Error> {
+    let mut options = ServerOptions::new();
+    options.first_pipe_instance(first);
+    if let Some(max_instances) = self.max_instances {
+      options.max_instances(max_instances as usize);
+    }
+    let mut attributes = SECURITY_ATTRIBUTES {
+      nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+      lpSecurityDescriptor: self.security_descriptor.0 as _,
+      bInheritHandle: 0,
+    };
+    // SAFETY: the attributes and the descriptor they point to outlive the
+    // call.
+    unsafe {
+      options.create_with_security_attributes_raw(
+        &self.path,
+        &mut attributes as *mut SECURITY_ATTRIBUTES as *mut c_void,
+      )
+    }
+  }
+
+  /// Creates an instance, waiting for a connected one to close if the
+  /// maximum number of instances is reached.
+  async fn next_instance(&self) -> Result<NamedPipeServer, std::io::Error> {
+    loop {
+      match self.create_instance(false) {
+        Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+          tokio::time::sleep(PIPE_BUSY_RETRY_INTERVAL).await;
+        }
+        result => return result,
+      }
+    }
+  }
+}
+
+fn check_pipe_name(path: &str) -> Result<(), NetError> {
+  const PREFIX: &str = r"\\.\pipe\";
+  let valid = path.len() > PREFIX.len()
+    && path
+      .get(..PREFIX.len())
+      .is_some_and(|prefix| prefix.eq_ignore_ascii_case(PREFIX));
+  if !valid {
+    return Err(NetError::InvalidPipeName(path.to_string()));
+  }
+  Ok(())
+}
+
+#[op2]
+#[serde]
+pub fn op_net_listen_named_pipe<NP>(
+  state: &mut OpState,
+  #[string] path: String,
+  #[serde] options: NamedPipeListenOptions,
+) -> Result<(ResourceId, String), NetError>
+where
+  NP: NetPermissions + 'static,
+{
+  super::check_unstable(state, "Deno.listen");
+  check_pipe_name(&path)?;
+  let permissions = state.borrow_mut::<NP>();
+  let checked_path = permissions
+    .check_read(&path, "Deno.listen()")
+    .map_err(NetError::Permission)?;
+  _ = permissions
+    .check_write_path(&checked_path, "Deno.listen()")
+    .map_err(NetError::Permission)?;
+
+  if let Some(max_instances) = options.max_instances {
+    if !(1..=MAX_PIPE_INSTANCES).contains(&max_instances) {
+      return Err(NetError::InvalidPipeInstances(max_instances));
+    }
+  }
+  let security_descriptor = SecurityDescriptor::from_sddl(
+    options
+      .security_descriptor
+      .as_deref()
+      .unwrap_or(DEFAULT_SECURITY_DESCRIPTOR),
+  )?;
+
+  let mut listener = NamedPipeListenerResource {
+    path,
+    max_instances: options.max_instances,
+    security_descriptor,
+    server: AsyncRefCell::new(None),
+    cancel: Default::default(),
+  };
+  // Refuse to listen on a pipe that another server already created.
+  let server = listener.create_instance(true).map_err(|err| {
+    if err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) {
+      std::io::Error::new(std::io::ErrorKind::AddrInUse, err)
+    } else {
+      err
+    }
+  })?;
+  listener.server = AsyncRefCell::new(Some(server));
+
+  let path = listener.path.clone();
+  let rid = state.resource_table.add(listener);
+  Ok((rid, path))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_net_accept_named_pipe(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(ResourceId, String), NetError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<NamedPipeListenerResource>(rid)
+    .map_err(|_| NetError::ListenerClosed)?;
+  let mut server = RcRef::map(&resource, |r| &r.server)
+    .try_borrow_mut()
+    .ok_or(NetError::ListenerBusy)?;
+  let cancel = RcRef::map(&resource, |r| &r.cancel);
+
+  let connected = async {
+    let instance = match server.take() {
+      Some(instance) => instance,
+      None => resource.next_instance().await?,
+    };
+    instance.connect().await?;
+    Ok::<_, std::io::Error>(instance)
+  }
+  .try_or_cancel(cancel)
+  .await
+  .map_err(crate::ops::accept_err)?;
+
+  // Create the instance for the next client right away, so clients don't
+  // see the pipe missing in between. If all instances are connected, the
+  // next accept waits for one to close instead.
+  *server = resource.create_instance(false).ok();
+
+  let stream = NamedPipeServerStreamResource::new(tokio::io::split(connected));
+  let rid = state.borrow_mut().resource_table.add(stream);
+  Ok((rid, resource.path.clone()))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_net_connect_named_pipe<NP>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+) -> Result<(ResourceId, String), NetError>
+where
+  NP: NetPermissions + 'static,
+{
+  {
+    let mut state_ = state.borrow_mut();
+    super::check_unstable(&state_, "Deno.connect");
+    check_pipe_name(&path)?;
+    let checked_path = state_
+      .borrow_mut::<NP>()
+      .check_read(&path, "Deno.connect()")
+      .map_err(NetError::Permission)?;
+    _ = state_
+      .borrow_mut::<NP>()
+      .check_write_path(&checked_path, "Deno.connect()")
+      .map_err(NetError::Permission)?;
+  }
+
+  // All instances being connected to other clients is transient, so keep
+  // trying for a while like `WaitNamedPipe` would.
+  let deadline = Instant::now() + PIPE_BUSY_TIMEOUT;
+  let client = loop {
+    match ClientOptions::new().open(&path) {
+      Ok(client) => break client,
+      Err(err)
+        if err.raw_os_error() == Some(ERROR_PIPE_BUSY as i32)
+          && Instant::now() < deadline => {}
+      Err(err) => return Err(err.into()),
+    }
+    tokio::time::sleep(PIPE_BUSY_RETRY_INTERVAL).await;
+  };
+
+  let stream = NamedPipeClientStreamResource::new(tokio::io::split(client));
+  let rid = state.borrow_mut().resource_table.add(stream);
+  Ok((rid, path))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pipe_names() {
+    assert!(check_pipe_name(r"\\.\pipe\deno").is_ok());
+    assert!(check_pipe_name(r"\\.\PIPE\deno").is_ok());
+    assert!(check_pipe_name(r"\\.\pipe\").is_err());
+    assert!(check_pipe_name(r"\\server\pipe\deno").is_err());
+    assert!(check_pipe_name(r"C:\pipe\deno").is_err());
+  }
+
+  #[test]
+  fn security_descriptor() {
+    assert!(SecurityDescriptor::from_sddl(DEFAULT_SECURITY_DESCRIPTOR).is_ok());
+    assert!(SecurityDescriptor::from_sddl("not sddl").is_err());
+  }
+}
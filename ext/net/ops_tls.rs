@@ -1,11 +1,11 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 use crate::io::TcpStreamResource;
+use crate::ops::resolve_connect_addr;
 use crate::ops::IpAddr;
 use crate::ops::NetError;
 use crate::ops::TlsHandshakeInfo;
 use crate::raw::NetworkListenerResource;
-use crate::resolve_addr::resolve_addr;
 use crate::resolve_addr::resolve_addr_sync;
 use crate::tcp::TcpListener;
 use crate::DefaultTlsOptions;
@@ -22,19 +22,26 @@ use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
-use deno_tls::create_client_config;
+use deno_core::StringOrBuffer;
+use deno_io::fd_exhaustion::accept_with_retry;
+use deno_tls::create_client_config_with_pins;
+use deno_tls::create_client_config_with_versions;
 use deno_tls::load_certs;
 use deno_tls::load_private_keys;
 use deno_tls::new_resolver;
+use deno_tls::rustls::pki_types::CertificateDer;
 use deno_tls::rustls::pki_types::ServerName;
 use deno_tls::rustls::ClientConnection;
 use deno_tls::rustls::ServerConfig;
 use deno_tls::ServerConfigProvider;
 use deno_tls::SocketUse;
+use deno_tls::SpkiPins;
 use deno_tls::TlsKey;
 use deno_tls::TlsKeyLookup;
 use deno_tls::TlsKeys;
 use deno_tls::TlsKeysHolder;
+use deno_web::AsyncClose;
+use deno_web::AsyncCloseFuture;
 use rustls_tokio_stream::TlsStreamRead;
 use rustls_tokio_stream::TlsStreamWrite;
 use serde::Deserialize;
@@ -170,6 +177,16 @@ impl Resource for TlsStreamResource {
   }
 }
 
+impl AsyncClose for TlsStreamResource {
+  /// Sends a close_notify alert, so that the peer can tell the connection
+  /// was closed on purpose rather than truncated.
+  fn close_async(self: Rc<Self>) -> Option<AsyncCloseFuture> {
+    Some(Box::pin(async move {
+      let _ = self.shutdown().await;
+    }))
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConnectTlsArgs {
@@ -177,6 +194,9 @@ pub struct ConnectTlsArgs {
   ca_certs: Vec<String>,
   alpn_protocols: Option<Vec<String>>,
   server_name: Option<String>,
+  pins: Option<Vec<String>>,
+  #[serde(default)]
+  pins_report_only: bool,
 }
 
 #[derive(Deserialize)]
@@ -186,6 +206,72 @@ pub struct StartTlsArgs {
   ca_certs: Vec<String>,
   hostname: String,
   alpn_protocols: Option<Vec<String>>,
+  pins: Option<Vec<String>>,
+  #[serde(default)]
+  pins_report_only: bool,
+}
+
+/// Parses the `pins` option of `Deno.connectTls()` and `Deno.startTls()`.
+fn parse_pins(
+  state: &OpState,
+  pins: Option<Vec<String>>,
+  report_only: bool,
+  api_name: &str,
+) -> Result<Option<SpkiPins>, NetError> {
+  let Some(pins) = pins else {
+    return Ok(None);
+  };
+  super::check_unstable(state, api_name);
+  Ok(Some(SpkiPins::new(&pins, report_only)?))
+}
+
+fn create_client_config(
+  root_cert_store: Option<deno_tls::rustls::RootCertStore>,
+  ca_certs: Vec<Vec<u8>>,
+  unsafely_ignore_certificate_errors: Option<Vec<String>>,
+  maybe_cert_chain_and_key: TlsKeys,
+  versions: &[&'static deno_tls::rustls::SupportedProtocolVersion],
+  pins: Option<SpkiPins>,
+) -> Result<deno_tls::rustls::ClientConfig, deno_tls::TlsError> {
+  match pins {
+    Some(pins) => create_client_config_with_pins(
+      root_cert_store,
+      ca_certs,
+      unsafely_ignore_certificate_errors,
+      maybe_cert_chain_and_key,
+      SocketUse::GeneralSsl,
+      versions,
+      pins,
+    ),
+    None => create_client_config_with_versions(
+      root_cert_store,
+      ca_certs,
+      unsafely_ignore_certificate_errors,
+      maybe_cert_chain_and_key,
+      SocketUse::GeneralSsl,
+      versions,
+    ),
+  }
+}
+
+/// Returns the base64 encoded SHA-256 hash of the SubjectPublicKeyInfo of a
+/// certificate, the value `Deno.connectTls({ pins })` expects. A PEM encoded
+/// certificate is read from a string, a DER encoded one from a buffer. Only
+/// the first certificate of a PEM bundle is hashed.
+#[op2]
+#[string]
+pub fn op_tls_cert_spki_hash(
+  state: &OpState,
+  #[serde] cert: StringOrBuffer,
+) -> Result<String, NetError> {
+  super::check_unstable(state, "Deno.certificateSpkiHash");
+  let cert = match cert {
+    StringOrBuffer::String(pem) => {
+      load_certs(&mut BufReader::new(pem.as_bytes()))?.swap_remove(0)
+    }
+    StringOrBuffer::Buffer(der) => CertificateDer::from(der.to_vec()),
+  };
+  Ok(deno_tls::spki_hash(&cert)?)
 }
 
 #[op2]
@@ -274,6 +360,13 @@ where
       .map_err(NetError::Permission)?;
   }
 
+  let pins = parse_pins(
+    &state.borrow(),
+    args.pins,
+    args.pins_report_only,
+    "Deno.startTls({ pins })",
+  )?;
+
   let ca_certs = args
     .ca_certs
     .into_iter()
@@ -288,11 +381,16 @@ where
     .try_borrow::<UnsafelyIgnoreCertificateErrors>()
     .and_then(|it| it.0.clone());
 
-  let root_cert_store = state
-    .borrow()
-    .borrow::<DefaultTlsOptions>()
-    .root_cert_store()
-    .map_err(NetError::RootCertStore)?;
+  let (root_cert_store, client_defaults) = {
+    let state = state.borrow();
+    let default_tls_options = state.borrow::<DefaultTlsOptions>();
+    (
+      default_tls_options
+        .root_cert_store()
+        .map_err(NetError::RootCertStore)?,
+      default_tls_options.client_defaults(),
+    )
+  };
 
   let resource_rc = state
     .borrow_mut()
@@ -315,10 +413,16 @@ where
     ca_certs,
     unsafely_ignore_certificate_errors,
     TlsKeys::Null,
-    SocketUse::GeneralSsl,
+    client_defaults
+      .min_version
+      .map(|version| version.protocol_versions())
+      .unwrap_or(deno_tls::rustls::DEFAULT_VERSIONS),
+    pins,
   )?;
 
-  if let Some(alpn_protocols) = args.alpn_protocols {
+  if let Some(alpn_protocols) =
+    args.alpn_protocols.or(client_defaults.alpn_protocols)
+  {
     tls_config.alpn_protocols =
       alpn_protocols.into_iter().map(|s| s.into_bytes()).collect();
   }
@@ -374,6 +478,13 @@ where
     }
   };
 
+  let pins = parse_pins(
+    &state.borrow(),
+    args.pins,
+    args.pins_report_only,
+    "Deno.connectTls({ pins })",
+  )?;
+
   let mut ca_certs = args
     .ca_certs
     .into_iter()
@@ -386,21 +497,29 @@ where
     ca_certs.push(buf);
   };
 
-  let root_cert_store = state
-    .borrow()
-    .borrow::<DefaultTlsOptions>()
-    .root_cert_store()
-    .map_err(NetError::RootCertStore)?;
+  let (root_cert_store, client_defaults) = {
+    let state = state.borrow();
+    let default_tls_options = state.borrow::<DefaultTlsOptions>();
+    (
+      default_tls_options
+        .root_cert_store()
+        .map_err(NetError::RootCertStore)?,
+      default_tls_options.client_defaults(),
+    )
+  };
   let hostname_dns = if let Some(server_name) = args.server_name {
     ServerName::try_from(server_name)
   } else {
     ServerName::try_from(addr.hostname.clone())
   }
   .map_err(|_| NetError::InvalidHostname(addr.hostname.clone()))?;
-  let connect_addr = resolve_addr(&addr.hostname, addr.port)
-    .await?
-    .next()
-    .ok_or_else(|| NetError::NoResolvedAddress)?;
+  let connect_addr = resolve_connect_addr::<NP>(
+    &state,
+    &addr.hostname,
+    addr.port,
+    "Deno.connectTls()",
+  )
+  .await?;
   let tcp_stream = TcpStream::connect(connect_addr).await?;
   let local_addr = tcp_stream.local_addr()?;
   let remote_addr = tcp_stream.peer_addr()?;
@@ -410,10 +529,16 @@ where
     ca_certs,
     unsafely_ignore_certificate_errors,
     key_pair.take(),
-    SocketUse::GeneralSsl,
+    client_defaults
+      .min_version
+      .map(|version| version.protocol_versions())
+      .unwrap_or(deno_tls::rustls::DEFAULT_VERSIONS),
+    pins,
   )?;
 
-  if let Some(alpn_protocols) = args.alpn_protocols {
+  if let Some(alpn_protocols) =
+    args.alpn_protocols.or(client_defaults.alpn_protocols)
+  {
     tls_config.alpn_protocols =
       alpn_protocols.into_iter().map(|s| s.into_bytes()).collect();
   }
@@ -527,8 +652,9 @@ pub async fn op_net_accept_tls(
     .try_borrow_mut()
     .ok_or_else(|| NetError::AcceptTaskOngoing)?;
 
+  let accept = accept_with_retry(|| listener.accept());
   let (tls_stream, remote_addr) =
-    match listener.accept().try_or_cancel(&cancel_handle).await {
+    match accept.try_or_cancel(&cancel_handle).await {
       Ok(tuple) => tuple,
       Err(err) if err.kind() == ErrorKind::Interrupted => {
         return Err(NetError::ListenerClosed);
@@ -13,6 +13,7 @@ use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
+use deno_io::fd_exhaustion::accept_with_retry;
 use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
@@ -68,8 +69,7 @@ pub async fn op_net_accept_unix(
     .try_borrow_mut()
     .ok_or(NetError::ListenerBusy)?;
   let cancel = RcRef::map(resource, |r| &r.cancel);
-  let (unix_stream, _socket_addr) = listener
-    .accept()
+  let (unix_stream, _socket_addr) = accept_with_retry(|| listener.accept())
     .try_or_cancel(cancel)
     .await
     .map_err(crate::ops::accept_err)?;
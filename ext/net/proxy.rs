@@ -0,0 +1,227 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Copying between two connections in both directions, for proxies.
+//!
+//! Each direction is copied by its own future. When one side reaches EOF,
+//! the write half of the other side is shut down and the opposite direction
+//! keeps running, so half-closed connections behave as they would without
+//! the proxy in between. The op resolves once both directions are done.
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use deno_core::futures::future::try_join;
+use deno_core::op2;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::ops::NetError;
+
+/// Size of the reads from either side.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyOptions {
+  idle_timeout_ms: Option<u64>,
+  cancel_rid: Option<ResourceId>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyResult {
+  a_to_b: u64,
+  b_to_a: u64,
+}
+
+/// Copies between the streams `rid_a` and `rid_b` in both directions until
+/// both reach EOF, and returns the number of bytes copied each way.
+///
+/// Both resources are removed from the table, so that nothing else reads
+/// from or writes to them while they are proxied, and are closed when the op
+/// completes. When `idle_timeout_ms` passes without data in either
+/// direction, the op rejects with `TimedOut`. Closing the `cancel_rid`
+/// handle tears down both directions.
+#[op2(async)]
+#[serde]
+pub async fn op_net_proxy(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid_a: ResourceId,
+  #[smi] rid_b: ResourceId,
+  #[serde] options: ProxyOptions,
+) -> Result<ProxyResult, NetError> {
+  let (a, b, cancel) = {
+    let mut state = state.borrow_mut();
+    if rid_a == rid_b {
+      return Err(NetError::ProxySameResource);
+    }
+    // look both up before taking either, so a bad rid leaves both in place
+    state
+      .resource_table
+      .get_any(rid_a)
+      .map_err(NetError::Resource)?;
+    state
+      .resource_table
+      .get_any(rid_b)
+      .map_err(NetError::Resource)?;
+    let cancel = options
+      .cancel_rid
+      .map(|rid| state.resource_table.get::<CancelHandle>(rid))
+      .transpose()
+      .map_err(NetError::Resource)?;
+    let a = state.resource_table.take_any(rid_a).unwrap();
+    let b = state.resource_table.take_any(rid_b).unwrap();
+    (a, b, cancel)
+  };
+
+  let idle_timeout = options.idle_timeout_ms.map(Duration::from_millis);
+  let result = match cancel {
+    Some(cancel) => proxy(a.clone(), b.clone(), idle_timeout)
+      .or_cancel(cancel)
+      .await
+      .unwrap_or_else(|err| Err(err.into())),
+    None => proxy(a.clone(), b.clone(), idle_timeout).await,
+  };
+  a.close();
+  b.close();
+  result
+}
+
+async fn proxy(
+  a: Rc<dyn Resource>,
+  b: Rc<dyn Resource>,
+  idle_timeout: Option<Duration>,
+) -> Result<ProxyResult, NetError> {
+  let last_activity = Cell::new(Instant::now());
+  let copy = try_join(
+    copy(a.clone(), b.clone(), &last_activity),
+    copy(b, a, &last_activity),
+  );
+  let Some(timeout) = idle_timeout else {
+    let (a_to_b, b_to_a) = copy.await?;
+    return Ok(ProxyResult { a_to_b, b_to_a });
+  };
+  tokio::select! {
+    result = copy => {
+      let (a_to_b, b_to_a) = result?;
+      Ok(ProxyResult { a_to_b, b_to_a })
+    }
+    _ = idle(&last_activity, timeout) => {
+      Err(NetError::ProxyIdleTimeout(timeout.as_millis() as u64))
+    }
+  }
+}
+
+/// Copies `from` to `to` until EOF, then shuts down the write half of `to`.
+async fn copy(
+  from: Rc<dyn Resource>,
+  to: Rc<dyn Resource>,
+  last_activity: &Cell<Instant>,
+) -> Result<u64, NetError> {
+  let mut copied = 0;
+  loop {
+    let chunk = from
+      .clone()
+      .read(READ_CHUNK_SIZE)
+      .await
+      .map_err(NetError::Stream)?;
+    if chunk.is_empty() {
+      to.shutdown().await.map_err(NetError::Stream)?;
+      return Ok(copied);
+    }
+    last_activity.set(Instant::now());
+    copied += chunk.len() as u64;
+    to.clone()
+      .write_all(chunk)
+      .await
+      .map_err(NetError::Stream)?;
+    last_activity.set(Instant::now());
+  }
+}
+
+/// Resolves once `timeout` has passed since `last_activity`.
+async fn idle(last_activity: &Cell<Instant>, timeout: Duration) {
+  loop {
+    let deadline = last_activity.get() + timeout;
+    if Instant::now() >= deadline {
+      return;
+    }
+    tokio::time::sleep_until(deadline).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::io::TcpStreamResource;
+  use tokio::io::AsyncReadExt;
+  use tokio::io::AsyncWriteExt;
+  use tokio::net::TcpListener;
+  use tokio::net::TcpStream;
+
+  /// Returns a connected pair of sockets, one of them as a resource.
+  async fn pair() -> (Rc<dyn Resource>, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (client, accepted) =
+      tokio::join!(TcpStream::connect(addr), listener.accept());
+    let resource = TcpStreamResource::new(client.unwrap().into_split());
+    (Rc::new(resource), accepted.unwrap().0)
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn half_close_keeps_other_direction_open() {
+    let (a, mut client) = pair().await;
+    let (b, mut server) = pair().await;
+    let proxy = deno_core::unsync::spawn(proxy(a, b, None));
+
+    client.write_all(b"request").await.unwrap();
+    client.shutdown().await.unwrap();
+
+    // the server sees the request and then EOF, but can still respond
+    let mut request = vec![];
+    server.read_to_end(&mut request).await.unwrap();
+    assert_eq!(request, b"request");
+    server.write_all(b"response").await.unwrap();
+    server.shutdown().await.unwrap();
+
+    let mut response = vec![];
+    client.read_to_end(&mut response).await.unwrap();
+    assert_eq!(response, b"response");
+
+    let result = proxy.await.unwrap().unwrap();
+    assert_eq!(
+      result,
+      ProxyResult {
+        a_to_b: 7,
+        b_to_a: 8
+      }
+    );
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn idle_timeout_tears_down_both_directions() {
+    let (a, mut client) = pair().await;
+    let (b, mut server) = pair().await;
+    let proxy =
+      deno_core::unsync::spawn(proxy(a, b, Some(Duration::from_millis(50))));
+
+    client.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    server.read_exact(&mut buf).await.unwrap();
+
+    let err = proxy.await.unwrap().unwrap_err();
+    assert!(matches!(err, NetError::ProxyIdleTimeout(50)), "{err:?}");
+    // the proxy dropped its ends of both connections
+    assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+    assert_eq!(server.read(&mut buf).await.unwrap(), 0);
+  }
+}
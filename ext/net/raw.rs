@@ -9,6 +9,7 @@ use deno_core::CancelHandle;
 use deno_core::Resource;
 use deno_core::ResourceId;
 use deno_core::ResourceTable;
+use deno_io::fd_exhaustion::accept_with_retry;
 use std::borrow::Cow;
 use std::rc::Rc;
 
@@ -215,12 +216,12 @@ macro_rules! network_stream {
     }
 
     impl NetworkStreamListener {
-      /// Accepts a connection on this listener.
+      /// Accepts a connection on this listener, retrying descriptor exhaustion.
       pub async fn accept(&self) -> Result<(NetworkStream, NetworkStreamAddress), std::io::Error> {
         Ok(match self {
           $(
             Self::$i(s) => {
-              let (stm, addr) = s.accept().await?;
+              let (stm, addr) = accept_with_retry(|| s.accept()).await?;
               (NetworkStream::$i(stm), addr.into())
             }
           )*
@@ -0,0 +1,422 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Name resolution used by `Deno.connect()`, `Deno.resolveDns()` and
+//! `fetch()`.
+//!
+//! By default names are resolved by the system (`getaddrinfo`). Embedders can
+//! install a [`DnsResolverConfig`] instead, which sends queries to a list of
+//! name servers, or to a DNS-over-HTTPS (RFC 8484) endpoint. The queries are
+//! made by trust-dns, whose cache keeps answers for as long as their TTLs
+//! allow. All clones of a [`DnsResolver`] share one resolver, and with it the
+//! cache.
+
+use std::fmt;
+use std::io;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use deno_core::error::AnyError;
+use deno_core::url::Host;
+use deno_core::url::Url;
+use deno_tls::RootCertStoreProvider;
+use trust_dns_resolver::config::NameServerConfig;
+use trust_dns_resolver::config::NameServerConfigGroup;
+use trust_dns_resolver::config::Protocol;
+use trust_dns_resolver::config::ResolverConfig;
+use trust_dns_resolver::config::ResolverOpts;
+use trust_dns_resolver::config::ServerOrderingStrategy;
+use trust_dns_resolver::config::TlsClientConfig;
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::resolve_addr::resolve_addr;
+use crate::NetPermissions;
+
+/// Path of DNS-over-HTTPS endpoints; trust-dns doesn't support others.
+const DOH_PATH: &str = "/dns-query";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DnsConfigError {
+  #[error("No DNS servers given")]
+  NoServers,
+  #[error("Invalid DNS-over-HTTPS endpoint '{0}': must be an https: URL")]
+  NotHttps(Url),
+  #[error(
+    "Invalid DNS-over-HTTPS endpoint '{0}': path must be \"{DOH_PATH}\""
+  )]
+  UnsupportedPath(Url),
+  #[error("DNS-over-HTTPS endpoint '{0}' needs bootstrap addresses, as its host is not an IP address")]
+  MissingBootstrap(Url),
+}
+
+/// How the runtime resolves names.
+#[derive(Clone, Debug, Default)]
+pub enum DnsResolverConfig {
+  /// Use the system resolver.
+  #[default]
+  System,
+  /// Send queries to the given name servers.
+  NameServers(NameServersConfig),
+  /// Send queries to a DNS-over-HTTPS endpoint.
+  Https(DohConfig),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameServerProtocol {
+  Udp,
+  Tcp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NameServer {
+  pub addr: SocketAddr,
+  pub protocol: NameServerProtocol,
+}
+
+#[derive(Clone, Debug)]
+pub struct NameServersConfig {
+  servers: Vec<NameServer>,
+  rotate: bool,
+  timeout: Duration,
+}
+
+impl NameServersConfig {
+  /// Servers are asked in order; the next one is tried when a server fails
+  /// or times out.
+  pub fn new(servers: Vec<NameServer>) -> Result<Self, DnsConfigError> {
+    if servers.is_empty() {
+      return Err(DnsConfigError::NoServers);
+    }
+    Ok(Self {
+      servers,
+      rotate: false,
+      timeout: ResolverOpts::default().timeout,
+    })
+  }
+
+  /// Spread queries over the servers, preferring the ones that answered
+  /// fastest, instead of always asking the first healthy one.
+  pub fn rotate(mut self, rotate: bool) -> Self {
+    self.rotate = rotate;
+    self
+  }
+
+  /// Time to wait for a server before trying the next one.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct DohConfig {
+  url: Url,
+  bootstrap: Vec<IpAddr>,
+  timeout: Duration,
+}
+
+impl DohConfig {
+  /// `url` is the endpoint, e.g. `https://dns.example/dns-query`. Its host
+  /// can't be resolved through the endpoint itself, so unless it is an IP
+  /// address, its addresses have to be given in `bootstrap`.
+  pub fn new(url: Url, bootstrap: Vec<IpAddr>) -> Result<Self, DnsConfigError> {
+    if url.scheme() != "https" {
+      return Err(DnsConfigError::NotHttps(url));
+    }
+    if url.path() != DOH_PATH || url.query().is_some() {
+      return Err(DnsConfigError::UnsupportedPath(url));
+    }
+    let is_ip = matches!(url.host(), Some(Host::Ipv4(_) | Host::Ipv6(_)));
+    if bootstrap.is_empty() && !is_ip {
+      return Err(DnsConfigError::MissingBootstrap(url));
+    }
+    Ok(Self {
+      url,
+      bootstrap,
+      timeout: ResolverOpts::default().timeout,
+    })
+  }
+
+  /// Time to wait for the endpoint before trying the next bootstrap
+  /// address.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn url(&self) -> &Url {
+    &self.url
+  }
+
+  fn host(&self) -> String {
+    match self.url.host() {
+      Some(Host::Ipv6(ip)) => ip.to_string(),
+      Some(host) => host.to_string(),
+      None => unreachable!("https: URLs have a host"),
+    }
+  }
+
+  fn port(&self) -> u16 {
+    self.url.port_or_known_default().unwrap_or(443)
+  }
+
+  fn addresses(&self) -> Vec<SocketAddr> {
+    let port = self.port();
+    if self.bootstrap.is_empty() {
+      // Checked in `new()` that the host is an IP address.
+      let ip = self.host().parse::<IpAddr>().unwrap();
+      return vec![SocketAddr::new(ip, port)];
+    }
+    self
+      .bootstrap
+      .iter()
+      .map(|ip| SocketAddr::new(*ip, port))
+      .collect()
+  }
+}
+
+/// Resolves names according to a [`DnsResolverConfig`]. The trust-dns
+/// resolver is created on first use, so that the root certificates for
+/// DNS-over-HTTPS are only loaded when needed.
+#[derive(Clone, Default)]
+pub struct DnsResolver {
+  config: Arc<DnsResolverConfig>,
+  root_cert_store_provider: Option<Arc<dyn RootCertStoreProvider>>,
+  resolver: Arc<OnceLock<Result<TokioAsyncResolver, String>>>,
+}
+
+impl fmt::Debug for DnsResolver {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("DnsResolver")
+      .field("config", &self.config)
+      .finish()
+  }
+}
+
+impl DnsResolver {
+  pub fn new(
+    config: DnsResolverConfig,
+    root_cert_store_provider: Option<Arc<dyn RootCertStoreProvider>>,
+  ) -> Self {
+    Self {
+      config: Arc::new(config),
+      root_cert_store_provider,
+      resolver: Default::default(),
+    }
+  }
+
+  pub fn config(&self) -> &DnsResolverConfig {
+    &self.config
+  }
+
+  /// The DNS-over-HTTPS endpoint that lookups contact, if any.
+  pub fn doh_url(&self) -> Option<&Url> {
+    match &*self.config {
+      DnsResolverConfig::Https(doh) => Some(doh.url()),
+      _ => None,
+    }
+  }
+
+  /// Checks that the DNS-over-HTTPS endpoint, if any, may be contacted.
+  /// Configured name servers are chosen by the embedder and need no
+  /// permission.
+  pub fn check_permissions<NP: NetPermissions>(
+    &self,
+    permissions: &mut NP,
+    api_name: &str,
+  ) -> Result<(), AnyError> {
+    if let DnsResolverConfig::Https(doh) = &*self.config {
+      permissions.check_net(&(doh.host(), Some(doh.port())), api_name)?;
+    }
+    Ok(())
+  }
+
+  /// The configured resolver, or `None` when the system resolver is used.
+  pub fn async_resolver(
+    &self,
+  ) -> Result<Option<TokioAsyncResolver>, io::Error> {
+    if matches!(&*self.config, DnsResolverConfig::System) {
+      return Ok(None);
+    }
+    match self.resolver.get_or_init(|| self.create_resolver()) {
+      Ok(resolver) => Ok(Some(resolver.clone())),
+      Err(err) => Err(io::Error::other(err.clone())),
+    }
+  }
+
+  /// Resolve `hostname` to socket addresses with `port`. IP addresses are
+  /// returned as they are, and an empty hostname means "0.0.0.0".
+  pub async fn lookup(
+    &self,
+    hostname: &str,
+    port: u16,
+  ) -> Result<Vec<SocketAddr>, io::Error> {
+    let Some(resolver) = self.async_resolver()? else {
+      return Ok(resolve_addr(hostname, port).await?.collect());
+    };
+    let host = hostname.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+      return Ok(vec![SocketAddr::from(([0, 0, 0, 0], port))]);
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+      return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let lookup = resolver.lookup_ip(host).await.map_err(|err| {
+      let kind = match err.kind() {
+        ResolveErrorKind::NoRecordsFound { .. } => io::ErrorKind::NotFound,
+        ResolveErrorKind::Timeout => io::ErrorKind::TimedOut,
+        _ => io::ErrorKind::Other,
+      };
+      io::Error::new(kind, err)
+    })?;
+    Ok(lookup.iter().map(|ip| SocketAddr::new(ip, port)).collect())
+  }
+
+  fn create_resolver(&self) -> Result<TokioAsyncResolver, String> {
+    let mut opts = ResolverOpts::default();
+    let servers = match &*self.config {
+      DnsResolverConfig::System => unreachable!(),
+      DnsResolverConfig::NameServers(config) => {
+        opts.timeout = config.timeout;
+        opts.server_ordering_strategy = if config.rotate {
+          ServerOrderingStrategy::QueryStatistics
+        } else {
+          ServerOrderingStrategy::UserProvidedOrder
+        };
+        config
+          .servers
+          .iter()
+          .map(|server| {
+            let protocol = match server.protocol {
+              NameServerProtocol::Udp => Protocol::Udp,
+              NameServerProtocol::Tcp => Protocol::Tcp,
+            };
+            NameServerConfig::new(server.addr, protocol)
+          })
+          .collect::<Vec<_>>()
+      }
+      DnsResolverConfig::Https(config) => {
+        opts.timeout = config.timeout;
+        opts.server_ordering_strategy =
+          ServerOrderingStrategy::UserProvidedOrder;
+        let tls_config = self.doh_tls_config()?;
+        config
+          .addresses()
+          .into_iter()
+          .map(|addr| NameServerConfig {
+            socket_addr: addr,
+            protocol: Protocol::Https,
+            tls_dns_name: Some(config.host()),
+            trust_negative_responses: true,
+            tls_config: Some(tls_config.clone()),
+            bind_addr: None,
+          })
+          .collect::<Vec<_>>()
+      }
+    };
+    let config = ResolverConfig::from_parts(
+      None,
+      vec![],
+      NameServerConfigGroup::from(servers),
+    );
+    Ok(TokioAsyncResolver::tokio(config, opts))
+  }
+
+  /// trust-dns uses an older rustls than the rest of the runtime, so the
+  /// runtime's root certificates are copied into a config of that version.
+  fn doh_tls_config(&self) -> Result<TlsClientConfig, String> {
+    let root_cert_store = match &self.root_cert_store_provider {
+      Some(provider) => provider
+        .get_or_try_init()
+        .map_err(|err| err.to_string())?
+        .clone(),
+      None => deno_tls::create_default_root_cert_store(),
+    };
+    let mut roots = rustls_doh::RootCertStore::empty();
+    roots.add_trust_anchors(root_cert_store.roots.iter().map(|anchor| {
+      rustls_doh::OwnedTrustAnchor::from_subject_spki_name_constraints(
+        anchor.subject.as_ref(),
+        anchor.subject_public_key_info.as_ref(),
+        anchor.name_constraints.as_ref().map(|nc| nc.as_ref()),
+      )
+    }));
+    let mut config = rustls_doh::ClientConfig::builder()
+      .with_safe_defaults()
+      .with_root_certificates(roots)
+      .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec()];
+    Ok(TlsClientConfig(Arc::new(config)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn doh_config_validation() {
+    let url = |s: &str| Url::parse(s).unwrap();
+    assert!(matches!(
+      DohConfig::new(url("http://1.1.1.1/dns-query"), vec![]),
+      Err(DnsConfigError::NotHttps(_))
+    ));
+    assert!(matches!(
+      DohConfig::new(url("https://1.1.1.1/resolve"), vec![]),
+      Err(DnsConfigError::UnsupportedPath(_))
+    ));
+    assert!(matches!(
+      DohConfig::new(url("https://dns.example/dns-query"), vec![]),
+      Err(DnsConfigError::MissingBootstrap(_))
+    ));
+
+    let config =
+      DohConfig::new(url("https://[::1]:8443/dns-query"), vec![]).unwrap();
+    assert_eq!(config.host(), "::1");
+    assert_eq!(config.addresses(), vec!["[::1]:8443".parse().unwrap()]);
+
+    let config = DohConfig::new(
+      url("https://dns.example/dns-query"),
+      vec!["192.0.2.1".parse().unwrap(), "192.0.2.2".parse().unwrap()],
+    )
+    .unwrap();
+    assert_eq!(config.host(), "dns.example");
+    assert_eq!(
+      config.addresses(),
+      vec![
+        "192.0.2.1:443".parse().unwrap(),
+        "192.0.2.2:443".parse().unwrap()
+      ]
+    );
+  }
+
+  #[test]
+  fn name_servers_config_requires_servers() {
+    assert!(matches!(
+      NameServersConfig::new(vec![]),
+      Err(DnsConfigError::NoServers)
+    ));
+  }
+
+  #[tokio::test]
+  async fn lookup_ip_literals_without_queries() {
+    // Nothing listens on this address, so any query would fail.
+    let config = NameServersConfig::new(vec![NameServer {
+      addr: "127.0.0.1:9".parse().unwrap(),
+      protocol: NameServerProtocol::Udp,
+    }])
+    .unwrap();
+    let resolver =
+      DnsResolver::new(DnsResolverConfig::NameServers(config), None);
+    assert_eq!(
+      resolver.lookup("[::1]", 80).await.unwrap(),
+      vec!["[::1]:80".parse().unwrap()]
+    );
+    assert_eq!(
+      resolver.lookup("", 80).await.unwrap(),
+      vec!["0.0.0.0:80".parse().unwrap()]
+    );
+  }
+}
@@ -0,0 +1,364 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Retry with exponential backoff for network operations.
+//!
+//! Delays are computed from the policy alone (jitter uses a PRNG seeded by
+//! the policy), so a given policy always produces the same schedule. Delays
+//! are slept through the `Clock` in the `OpState`.
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::Resource;
+use deno_web::SharedClock;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ops::NetError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RetryOn {
+  /// The connection was refused.
+  Refused,
+  /// The connection attempt timed out.
+  TimedOut,
+  /// The hostname couldn't be resolved.
+  Dns,
+  /// The connection was closed before a transfer completed. Only reported
+  /// by transfers, such as `Deno.download`, never by connection attempts.
+  Interrupted,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicyOptions {
+  pub max_attempts: u32,
+  pub base_delay_ms: u64,
+  pub max_delay_ms: u64,
+  /// Fraction of each delay, between 0 and 1, that is randomized.
+  #[serde(default)]
+  pub jitter: f64,
+  #[serde(default)]
+  pub seed: u64,
+  pub retry_on: Vec<RetryOn>,
+}
+
+#[derive(Debug)]
+pub struct RetryPolicy {
+  max_attempts: u32,
+  base_delay: Duration,
+  max_delay: Duration,
+  jitter: f64,
+  seed: u64,
+  retry_on: Vec<RetryOn>,
+}
+
+impl Resource for RetryPolicy {
+  fn name(&self) -> Cow<str> {
+    "retryPolicy".into()
+  }
+}
+
+/// Attempt count and cumulative backoff of a retried operation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryStats {
+  pub attempts: u32,
+  pub total_delay_ms: u64,
+}
+
+impl RetryPolicy {
+  pub fn new(options: RetryPolicyOptions) -> Result<Self, NetError> {
+    if options.max_attempts == 0 {
+      return Err(NetError::InvalidRetryPolicy(
+        "maxAttempts must be at least 1",
+      ));
+    }
+    if !(0.0..=1.0).contains(&options.jitter) {
+      return Err(NetError::InvalidRetryPolicy(
+        "jitter must be between 0 and 1",
+      ));
+    }
+    if options.base_delay_ms > options.max_delay_ms {
+      return Err(NetError::InvalidRetryPolicy(
+        "baseDelayMs must not be greater than maxDelayMs",
+      ));
+    }
+    Ok(Self {
+      max_attempts: options.max_attempts,
+      base_delay: Duration::from_millis(options.base_delay_ms),
+      max_delay: Duration::from_millis(options.max_delay_ms),
+      jitter: options.jitter,
+      seed: options.seed,
+      retry_on: options.retry_on,
+    })
+  }
+
+  /// Delay before retry number `retry` (starting at 1): the base delay
+  /// doubled for every retry, capped at the max delay, with the jittered
+  /// fraction scaled down by a seeded random factor.
+  pub fn delay(&self, retry: u32) -> Duration {
+    let exponential = self.base_delay.saturating_mul(
+      1u32
+        .checked_shl(retry.saturating_sub(1))
+        .unwrap_or(u32::MAX),
+    );
+    let delay = exponential.min(self.max_delay);
+    if self.jitter == 0.0 {
+      return delay;
+    }
+    let random = splitmix64(self.seed.wrapping_add(retry as u64));
+    let unit = (random >> 11) as f64 / (1u64 << 53) as f64;
+    delay.mul_f64(1.0 - self.jitter * unit)
+  }
+
+  pub fn max_attempts(&self) -> u32 {
+    self.max_attempts
+  }
+
+  /// Whether failures of `kind` are retried, as long as attempts are left.
+  pub fn retries_on(&self, kind: RetryOn) -> bool {
+    self.retry_on.contains(&kind)
+  }
+
+  fn should_retry(&self, error: &NetError) -> bool {
+    classify(error).is_some_and(|kind| self.retries_on(kind))
+  }
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+  z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
+
+fn classify(error: &NetError) -> Option<RetryOn> {
+  match error {
+    NetError::Io(e) => match e.kind() {
+      std::io::ErrorKind::ConnectionRefused => Some(RetryOn::Refused),
+      std::io::ErrorKind::TimedOut => Some(RetryOn::TimedOut),
+      _ => None,
+    },
+    NetError::Resolve(_)
+    | NetError::NoResolvedAddress
+    | NetError::DnsNotFound(_)
+    | NetError::DnsNotConnected(_)
+    | NetError::Dns(_) => Some(RetryOn::Dns),
+    NetError::DnsTimedOut(_) => Some(RetryOn::TimedOut),
+    _ => None,
+  }
+}
+
+/// Run `attempt` until it succeeds, fails with an error the policy doesn't
+/// retry, or the policy runs out of attempts. Without a policy `attempt` runs
+/// once. The whole sequence, including backoff, is aborted when `cancel` is
+/// canceled.
+///
+/// The last error is wrapped in `NetError::RetryFailed` if it was preceded by
+/// at least one retry, and returned unchanged otherwise.
+pub async fn retry<T, F, Fut>(
+  policy: Option<Rc<RetryPolicy>>,
+  clock: SharedClock,
+  cancel: Option<Rc<CancelHandle>>,
+  mut attempt: F,
+) -> Result<(T, RetryStats), NetError>
+where
+  F: FnMut() -> Fut,
+  Fut: Future<Output = Result<T, NetError>>,
+{
+  let sequence = async {
+    let mut stats = RetryStats::default();
+    loop {
+      stats.attempts += 1;
+      let error = match attempt().await {
+        Ok(value) => return Ok((value, stats)),
+        Err(error) => error,
+      };
+      let Some(policy) = policy.as_ref() else {
+        return Err(error);
+      };
+      if stats.attempts >= policy.max_attempts || !policy.should_retry(&error) {
+        if stats.attempts == 1 {
+          // Nothing was retried, the error is reported as is.
+          return Err(error);
+        }
+        return Err(NetError::RetryFailed {
+          source: Box::new(error),
+          attempts: stats.attempts,
+          total_delay_ms: stats.total_delay_ms,
+        });
+      }
+      let delay = policy.delay(stats.attempts);
+      clock.sleep(delay).await;
+      stats.total_delay_ms += delay.as_millis() as u64;
+    }
+  };
+  match cancel {
+    Some(cancel) => sequence.or_cancel(cancel).await?,
+    None => sequence.await,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_web::TokioClock;
+  use std::cell::Cell;
+
+  fn policy(max_attempts: u32, retry_on: Vec<RetryOn>) -> Rc<RetryPolicy> {
+    Rc::new(
+      RetryPolicy::new(RetryPolicyOptions {
+        max_attempts,
+        base_delay_ms: 100,
+        max_delay_ms: 1000,
+        jitter: 0.0,
+        seed: 0,
+        retry_on,
+      })
+      .unwrap(),
+    )
+  }
+
+  fn refused() -> NetError {
+    NetError::Io(std::io::ErrorKind::ConnectionRefused.into())
+  }
+
+  #[test]
+  fn delays_grow_exponentially_and_are_capped() {
+    let policy = policy(10, vec![]);
+    let delays = (1..=6)
+      .map(|retry| policy.delay(retry).as_millis())
+      .collect::<Vec<_>>();
+    assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+  }
+
+  #[test]
+  fn jitter_is_deterministic_and_bounded() {
+    let options = || RetryPolicyOptions {
+      max_attempts: 5,
+      base_delay_ms: 1000,
+      max_delay_ms: 1000,
+      jitter: 0.5,
+      seed: 42,
+      retry_on: vec![],
+    };
+    let a = RetryPolicy::new(options()).unwrap();
+    let b = RetryPolicy::new(options()).unwrap();
+    for retry in 1..5 {
+      assert_eq!(a.delay(retry), b.delay(retry));
+      let delay = a.delay(retry).as_millis();
+      assert!((500..=1000).contains(&delay), "{delay}");
+    }
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn succeeds_on_third_attempt() {
+    let calls = Rc::new(Cell::new(0));
+    let start = tokio::time::Instant::now();
+    let (value, stats) = retry(
+      Some(policy(5, vec![RetryOn::Refused])),
+      Rc::new(TokioClock),
+      None,
+      || {
+        let calls = calls.clone();
+        async move {
+          calls.set(calls.get() + 1);
+          if calls.get() < 3 {
+            Err(refused())
+          } else {
+            Ok("connected")
+          }
+        }
+      },
+    )
+    .await
+    .unwrap();
+    assert_eq!(value, "connected");
+    assert_eq!(
+      stats,
+      RetryStats {
+        attempts: 3,
+        total_delay_ms: 300,
+      }
+    );
+    // The paused clock was fast-forwarded through the backoff.
+    assert_eq!(start.elapsed(), Duration::from_millis(300));
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn gives_up_with_stats() {
+    let err = retry(
+      Some(policy(2, vec![RetryOn::Refused])),
+      Rc::new(TokioClock),
+      None,
+      || async { Err::<(), _>(refused()) },
+    )
+    .await
+    .unwrap_err();
+    let NetError::RetryFailed {
+      source,
+      attempts,
+      total_delay_ms,
+    } = err
+    else {
+      panic!("unexpected error: {err}");
+    };
+    assert!(matches!(*source, NetError::Io(_)));
+    assert_eq!(attempts, 2);
+    assert_eq!(total_delay_ms, 100);
+  }
+
+  #[tokio::test(flavor = "current_thread", start_paused = true)]
+  async fn does_not_retry_unlisted_errors() {
+    let calls = Cell::new(0);
+    let err = retry(
+      Some(policy(5, vec![RetryOn::Dns])),
+      Rc::new(TokioClock),
+      None,
+      || {
+        calls.set(calls.get() + 1);
+        async { Err::<(), _>(refused()) }
+      },
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, NetError::Io(_)), "{err}");
+    assert_eq!(calls.get(), 1);
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn cancel_during_backoff_rejects_promptly() {
+    let policy = Rc::new(
+      RetryPolicy::new(RetryPolicyOptions {
+        max_attempts: 3,
+        base_delay_ms: 60_000,
+        max_delay_ms: 60_000,
+        jitter: 0.0,
+        seed: 0,
+        retry_on: vec![RetryOn::Refused],
+      })
+      .unwrap(),
+    );
+    let cancel = Rc::new(CancelHandle::new());
+    let canceler = cancel.clone();
+    deno_core::unsync::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(10)).await;
+      canceler.cancel();
+    });
+    let start = std::time::Instant::now();
+    let err =
+      retry(Some(policy), Rc::new(TokioClock), Some(cancel), || async {
+        Err::<(), _>(refused())
+      })
+      .await
+      .unwrap_err();
+    assert!(matches!(err, NetError::Canceled(_)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+  }
+}
@@ -0,0 +1,297 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Sending a window of a file over a connection without the data passing
+//! through JavaScript.
+//!
+//! For plain TCP streams on Linux and macOS the bytes are moved by the kernel
+//! with `sendfile(2)`. Everywhere else, including TLS streams, and when the
+//! kernel refuses the file (e.g. it is a pipe), the file is read in chunks
+//! and written through the stream resource. Reads are positional, so the
+//! file's seek position is left where it was.
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::BufView;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceHandleFd;
+use deno_core::ResourceId;
+use serde::Deserialize;
+
+use crate::ops::NetError;
+
+/// Size of the reads when the file is copied through the stream.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SendFileOptions {
+  offset: Option<u64>,
+  length: Option<u64>,
+}
+
+/// Writes `length` bytes of the file `file_rid`, starting at `offset`, to the
+/// stream `socket_rid`, and returns the number of bytes sent. Stops early at
+/// the end of the file. Without a `length`, sends everything up to the end
+/// of the file.
+#[op2(async)]
+#[number]
+pub async fn op_net_sendfile(
+  state: Rc<RefCell<OpState>>,
+  #[smi] socket_rid: ResourceId,
+  #[smi] file_rid: ResourceId,
+  #[serde] options: SendFileOptions,
+) -> Result<u64, NetError> {
+  let (socket, file) = {
+    let state = state.borrow();
+    let socket = state
+      .resource_table
+      .get_any(socket_rid)
+      .map_err(NetError::Resource)?;
+    let file = state
+      .resource_table
+      .get_any(file_rid)
+      .map_err(NetError::Resource)?;
+    (socket, file)
+  };
+  // `file` is held until the op completes, which keeps the descriptor open.
+  let fd = file
+    .clone()
+    .backing_fd()
+    .ok_or(NetError::SendFileNotAFile)?;
+  let offset = options.offset.unwrap_or(0);
+  let length = options.length.unwrap_or(u64::MAX);
+
+  let sent = send_with_kernel(&socket, fd, offset, length).await?;
+  if sent == length {
+    return Ok(sent);
+  }
+  let copied =
+    copy_through_stream(socket, fd, offset + sent, length - sent).await?;
+  Ok(sent + copied)
+}
+
+/// Sends as much as possible with `sendfile(2)`. Returns the number of bytes
+/// sent; when that is less than `length`, either the end of the file was
+/// reached or the rest has to be copied through the stream.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+async fn send_with_kernel(
+  socket: &Rc<dyn Resource>,
+  fd: ResourceHandleFd,
+  offset: u64,
+  length: u64,
+) -> Result<u64, NetError> {
+  use std::os::fd::AsRawFd;
+  use tokio::io::Interest;
+
+  // Linux refuses to send more than this in one call.
+  const MAX_SEND: u64 = 0x7fff_f000;
+
+  let Some(tcp) = socket.downcast_rc::<crate::io::TcpStreamResource>() else {
+    return Ok(0);
+  };
+  // Hold the write half, so that other writes don't interleave with ours.
+  let wr = tcp.wr_borrow_mut().await;
+  let stream: &tokio::net::TcpStream = (*wr).as_ref();
+  let mut sent = 0;
+  while sent < length {
+    let count = (length - sent).min(MAX_SEND) as usize;
+    stream.writable().await?;
+    let result = stream.try_io(Interest::WRITABLE, || {
+      sys::sendfile(stream.as_raw_fd(), fd, offset + sent, count)
+    });
+    match result {
+      // End of file.
+      Ok(0) => return Ok(sent),
+      Ok(n) => sent += n as u64,
+      Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+      Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+      Err(err) if sys::is_unsupported(&err) => break,
+      Err(err) => return Err(err.into()),
+    }
+  }
+  Ok(sent)
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "android",
+  target_os = "macos"
+)))]
+async fn send_with_kernel(
+  _socket: &Rc<dyn Resource>,
+  _fd: ResourceHandleFd,
+  _offset: u64,
+  _length: u64,
+) -> Result<u64, NetError> {
+  Ok(0)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+  use std::io;
+  use std::os::fd::RawFd;
+
+  pub fn sendfile(
+    socket: RawFd,
+    file: RawFd,
+    offset: u64,
+    count: usize,
+  ) -> io::Result<usize> {
+    let mut offset = offset as libc::off_t;
+    // SAFETY: both descriptors stay open for the duration of the call and
+    // `offset` is valid for writes. Passing an offset leaves the file
+    // position unchanged.
+    let n = unsafe { libc::sendfile(socket, file, &mut offset, count) };
+    if n < 0 {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+  }
+
+  /// Errors for files that `sendfile` can't read from, like pipes.
+  pub fn is_unsupported(err: &io::Error) -> bool {
+    matches!(
+      err.raw_os_error(),
+      Some(libc::EINVAL | libc::ENOSYS | libc::EOPNOTSUPP)
+    )
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+  use std::io;
+  use std::os::fd::RawFd;
+
+  pub fn sendfile(
+    socket: RawFd,
+    file: RawFd,
+    offset: u64,
+    count: usize,
+  ) -> io::Result<usize> {
+    let mut len = count as libc::off_t;
+    // SAFETY: both descriptors stay open for the duration of the call and
+    // `len` is valid for reads and writes. The file position is not used.
+    let ret = unsafe {
+      libc::sendfile(
+        file,
+        socket,
+        offset as libc::off_t,
+        &mut len,
+        std::ptr::null_mut(),
+        0,
+      )
+    };
+    if ret == -1 {
+      let err = io::Error::last_os_error();
+      // A non-blocking socket may take part of the data before failing
+      // with EAGAIN; `len` then holds what was sent.
+      if len > 0 && err.kind() == io::ErrorKind::WouldBlock {
+        return Ok(len as usize);
+      }
+      return Err(err);
+    }
+    Ok(len as usize)
+  }
+
+  /// Errors for files that `sendfile` can't read from, like pipes.
+  pub fn is_unsupported(err: &io::Error) -> bool {
+    matches!(
+      err.raw_os_error(),
+      Some(libc::EINVAL | libc::ENOTSOCK | libc::ENOTSUP | libc::EOPNOTSUPP)
+    )
+  }
+}
+
+/// Reads the window of the file in chunks and writes them to the stream,
+/// which works for any writable resource, e.g. TLS streams.
+async fn copy_through_stream(
+  socket: Rc<dyn Resource>,
+  fd: ResourceHandleFd,
+  offset: u64,
+  length: u64,
+) -> Result<u64, NetError> {
+  let mut file = PositionalReader::new(fd)?;
+  let mut copied = 0;
+  while copied < length {
+    let size = (length - copied).min(READ_CHUNK_SIZE as u64) as usize;
+    let pos = offset + copied;
+    let (returned, chunk) = deno_core::unsync::spawn_blocking(move || {
+      let mut chunk = vec![0; size];
+      let result = file.read_at(&mut chunk, pos).map(|n| {
+        chunk.truncate(n);
+        chunk
+      });
+      (file, result)
+    })
+    .await
+    .map_err(|err| NetError::Io(io::Error::other(err)))?;
+    file = returned;
+    let chunk = chunk?;
+    if chunk.is_empty() {
+      break;
+    }
+    copied += chunk.len() as u64;
+    socket
+      .clone()
+      .write_all(BufView::from(chunk))
+      .await
+      .map_err(NetError::Stream)?;
+  }
+  Ok(copied)
+}
+
+/// A duplicate of a file descriptor that reads at explicit offsets without
+/// changing the position of the original.
+struct PositionalReader {
+  file: std::fs::File,
+  #[cfg(windows)]
+  position: u64,
+}
+
+impl PositionalReader {
+  #[cfg(unix)]
+  fn new(fd: ResourceHandleFd) -> io::Result<Self> {
+    use std::os::fd::BorrowedFd;
+    // SAFETY: the caller keeps the resource that owns `fd` alive.
+    let fd = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+    Ok(Self { file: fd.into() })
+  }
+
+  #[cfg(unix)]
+  fn read_at(&mut self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::unix::fs::FileExt;
+    loop {
+      match self.file.read_at(buf, offset) {
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+        result => return result,
+      }
+    }
+  }
+
+  // Duplicated handles share the file pointer, which `seek_read` moves, so
+  // it is put back after every read.
+  #[cfg(windows)]
+  fn new(handle: ResourceHandleFd) -> io::Result<Self> {
+    use std::io::Seek;
+    use std::os::windows::io::BorrowedHandle;
+    // SAFETY: the caller keeps the resource that owns `handle` alive.
+    let handle =
+      unsafe { BorrowedHandle::borrow_raw(handle) }.try_clone_to_owned()?;
+    let mut file = std::fs::File::from(handle);
+    let position = file.stream_position()?;
+    Ok(Self { file, position })
+  }
+
+  #[cfg(windows)]
+  fn read_at(&mut self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::io::Seek;
+    use std::os::windows::fs::FileExt;
+    let result = self.file.seek_read(buf, offset);
+    self.file.seek(io::SeekFrom::Start(self.position))?;
+    result
+  }
+}
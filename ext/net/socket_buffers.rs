@@ -0,0 +1,207 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Inspection and tuning of the kernel buffers of TCP and UDP sockets.
+
+use serde::Deserialize;
+use serde::Serialize;
+use socket2::SockRef;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketBuffered {
+  /// Bytes received by the kernel that haven't been read yet (`FIONREAD`).
+  /// For UDP sockets on Linux this is the size of the next datagram only.
+  pub kernel_readable: Option<usize>,
+  /// Bytes written but not yet acknowledged by the peer (`SIOCOUTQ` on
+  /// Linux, `SO_NWRITE` on macOS). `None` where the platform has no
+  /// equivalent.
+  pub kernel_send_queue: Option<usize>,
+  /// Bytes held by deno's own resource layer. TCP and UDP resources read and
+  /// write the socket directly, so this is always 0 for them.
+  pub internal_buffered: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SetBufferSizes {
+  pub recv: Option<usize>,
+  pub send: Option<usize>,
+}
+
+/// Effective `SO_RCVBUF`/`SO_SNDBUF` values, as reported by the kernel after
+/// a resize. Linux doubles the requested value to leave room for bookkeeping
+/// and reports the doubled value, other platforms round to their own limits.
+#[derive(Debug, Serialize)]
+pub struct BufferSizes {
+  pub recv: usize,
+  pub send: usize,
+}
+
+pub fn buffered(socket: SockRef) -> std::io::Result<SocketBuffered> {
+  Ok(SocketBuffered {
+    kernel_readable: kernel_readable(&socket)?,
+    kernel_send_queue: kernel_send_queue(&socket)?,
+    internal_buffered: 0,
+  })
+}
+
+/// Resize the socket buffers. Sizes that are `None` are left untouched, so
+/// passing neither acts as a getter.
+pub fn set_buffer_sizes(
+  socket: SockRef,
+  sizes: SetBufferSizes,
+) -> std::io::Result<BufferSizes> {
+  if let Some(recv) = sizes.recv {
+    socket.set_recv_buffer_size(recv)?;
+  }
+  if let Some(send) = sizes.send {
+    socket.set_send_buffer_size(send)?;
+  }
+  Ok(BufferSizes {
+    recv: socket.recv_buffer_size()?,
+    send: socket.send_buffer_size()?,
+  })
+}
+
+#[cfg(unix)]
+fn kernel_readable(socket: &SockRef) -> std::io::Result<Option<usize>> {
+  use std::os::fd::AsRawFd;
+
+  let mut readable: libc::c_int = 0;
+  // SAFETY: FIONREAD writes a single c_int to the provided pointer.
+  let ret =
+    unsafe { libc::ioctl(socket.as_raw_fd(), libc::FIONREAD, &mut readable) };
+  if ret == -1 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(Some(readable as usize))
+}
+
+#[cfg(windows)]
+fn kernel_readable(socket: &SockRef) -> std::io::Result<Option<usize>> {
+  use std::os::windows::io::AsRawSocket;
+  use windows_sys::Win32::Networking::WinSock::ioctlsocket;
+  use windows_sys::Win32::Networking::WinSock::FIONREAD;
+
+  let mut readable: u32 = 0;
+  // SAFETY: FIONREAD writes a single u32 to the provided pointer.
+  let ret = unsafe {
+    ioctlsocket(socket.as_raw_socket() as usize, FIONREAD, &mut readable)
+  };
+  if ret != 0 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(Some(readable as usize))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kernel_readable(_socket: &SockRef) -> std::io::Result<Option<usize>> {
+  Ok(None)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn kernel_send_queue(socket: &SockRef) -> std::io::Result<Option<usize>> {
+  use std::os::fd::AsRawFd;
+
+  let mut queued: libc::c_int = 0;
+  // SAFETY: SIOCOUTQ (an alias of TIOCOUTQ) writes a single c_int to the
+  // provided pointer.
+  let ret =
+    unsafe { libc::ioctl(socket.as_raw_fd(), libc::TIOCOUTQ, &mut queued) };
+  if ret == -1 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(Some(queued as usize))
+}
+
+#[cfg(target_vendor = "apple")]
+fn kernel_send_queue(socket: &SockRef) -> std::io::Result<Option<usize>> {
+  use std::os::fd::AsRawFd;
+
+  let mut queued: libc::c_int = 0;
+  let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+  // SAFETY: SO_NWRITE writes a single c_int to the provided pointer.
+  let ret = unsafe {
+    libc::getsockopt(
+      socket.as_raw_fd(),
+      libc::SOL_SOCKET,
+      libc::SO_NWRITE,
+      &mut queued as *mut libc::c_int as *mut libc::c_void,
+      &mut len,
+    )
+  };
+  if ret == -1 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(Some(queued as usize))
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "android",
+  target_vendor = "apple"
+)))]
+fn kernel_send_queue(_socket: &SockRef) -> std::io::Result<Option<usize>> {
+  Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+  use tokio::io::AsyncWriteExt;
+  use tokio::net::TcpListener;
+  use tokio::net::TcpStream;
+
+  #[tokio::test]
+  async fn kernel_readable_grows_when_peer_writes() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = TcpStream::connect(addr).await.unwrap();
+    let (mut server, _) = listener.accept().await.unwrap();
+
+    let before = buffered(SockRef::from(&client)).unwrap();
+    assert_eq!(before.kernel_readable, Some(0));
+    assert_eq!(before.internal_buffered, 0);
+
+    server.write_all(&[1; 4096]).await.unwrap();
+    server.flush().await.unwrap();
+
+    // Delivery over loopback is fast but not synchronous.
+    let mut readable = 0;
+    for _ in 0..100 {
+      readable = buffered(SockRef::from(&client))
+        .unwrap()
+        .kernel_readable
+        .unwrap();
+      if readable == 4096 {
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(readable, 4096);
+  }
+
+  #[tokio::test]
+  async fn set_buffer_sizes_is_reflected() {
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let initial =
+      set_buffer_sizes(SockRef::from(&socket), Default::default()).unwrap();
+    assert!(initial.recv > 0 && initial.send > 0);
+
+    let sizes = set_buffer_sizes(
+      SockRef::from(&socket),
+      SetBufferSizes {
+        recv: Some(64 * 1024),
+        send: Some(32 * 1024),
+      },
+    )
+    .unwrap();
+    // Kernels round (Linux doubles), but never go below the request.
+    assert!(sizes.recv >= 64 * 1024);
+    assert!(sizes.send >= 32 * 1024);
+    let again =
+      set_buffer_sizes(SockRef::from(&socket), Default::default()).unwrap();
+    assert_eq!(again.recv, sizes.recv);
+    assert_eq!(again.send, sizes.send);
+  }
+}
@@ -282,7 +282,9 @@ deno_core::extension!(deno_node,
     ops::fs::op_node_statfs<P>,
     ops::winerror::op_node_sys_to_uv_error,
     ops::v8::op_v8_cached_data_version_tag,
+    ops::v8::op_v8_get_heap_space_statistics,
     ops::v8::op_v8_get_heap_statistics,
+    ops::v8::op_v8_low_memory_notification,
     ops::v8::op_v8_get_wire_format_version,
     ops::v8::op_v8_new_deserializer,
     ops::v8::op_v8_new_serializer,
@@ -298,6 +300,7 @@ deno_core::extension!(deno_node,
     ops::v8::op_v8_transfer_array_buffer_de,
     ops::v8::op_v8_write_double,
     ops::v8::op_v8_write_header,
+    ops::v8::op_v8_write_heap_snapshot<P>,
     ops::v8::op_v8_write_raw_bytes,
     ops::v8::op_v8_write_uint32,
     ops::v8::op_v8_write_uint64,
@@ -4,7 +4,11 @@ use deno_core::op2;
 use deno_core::v8;
 use deno_core::FastString;
 use deno_core::GarbageCollected;
+use deno_core::OpState;
 use deno_core::ToJsBuffer;
+use deno_fs::FileSystemRc;
+use deno_fs::OpenOptions;
+use serde::Serialize;
 use std::ptr::NonNull;
 use v8::ValueDeserializerHelper;
 use v8::ValueSerializerHelper;
@@ -38,6 +42,97 @@ pub fn op_v8_get_heap_statistics(
   buffer[13] = stats.external_memory() as f64;
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum V8Error {
+  #[error(transparent)]
+  Permission(deno_core::error::AnyError),
+  #[error("{0}")]
+  Io(#[from] std::io::Error),
+}
+
+#[derive(Serialize)]
+pub struct HeapSpaceStatistics {
+  space_name: String,
+  space_size: usize,
+  space_used_size: usize,
+  space_available_size: usize,
+  physical_space_size: usize,
+}
+
+#[op2]
+#[serde]
+pub fn op_v8_get_heap_space_statistics(
+  scope: &mut v8::HandleScope,
+) -> Vec<HeapSpaceStatistics> {
+  let mut spaces = vec![];
+  for index in 0..scope.number_of_heap_spaces() {
+    let mut stats = v8::HeapSpaceStatistics::default();
+    if !scope.get_heap_space_statistics(&mut stats, index) {
+      continue;
+    }
+    spaces.push(HeapSpaceStatistics {
+      space_name: stats.space_name().to_string_lossy().into_owned(),
+      space_size: stats.space_size(),
+      space_used_size: stats.space_used_size(),
+      space_available_size: stats.space_available_size(),
+      physical_space_size: stats.physical_space_size(),
+    });
+  }
+  spaces
+}
+
+/// Chunks of the snapshot are collected up to this size before being written.
+const HEAP_SNAPSHOT_WRITE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Writes a `.heapsnapshot` file of the current isolate.
+///
+/// V8 can't run JavaScript while it walks the heap, so this runs
+/// synchronously and the whole isolate (timers, pending promises, incoming
+/// requests) is paused until the snapshot is written. For large heaps that
+/// can take seconds.
+#[op2(fast)]
+pub fn op_v8_write_heap_snapshot<P>(
+  scope: &mut v8::HandleScope,
+  state: &mut OpState,
+  #[string] path: &str,
+) -> Result<(), V8Error>
+where
+  P: crate::NodePermissions + 'static,
+{
+  let path = state
+    .borrow_mut::<P>()
+    .check_write_with_api_name(path, Some("node:v8.writeHeapSnapshot"))
+    .map_err(V8Error::Permission)?;
+  let fs = state.borrow::<FileSystemRc>().clone();
+  let file = fs
+    .open_sync(&path, OpenOptions::write(true, false, false, None), None)
+    .map_err(|err| err.into_io_error())?;
+
+  let mut buffer = Vec::with_capacity(HEAP_SNAPSHOT_WRITE_BUFFER_SIZE);
+  let mut result = Ok(());
+  scope.take_heap_snapshot(|chunk| {
+    buffer.extend_from_slice(chunk);
+    if buffer.len() >= HEAP_SNAPSHOT_WRITE_BUFFER_SIZE {
+      result = file.clone().write_all_sync(&buffer);
+      buffer.clear();
+    }
+    // Returning false aborts the snapshot.
+    result.is_ok()
+  });
+  result.map_err(|err| err.into_io_error())?;
+  file
+    .write_all_sync(&buffer)
+    .map_err(|err| err.into_io_error())?;
+  Ok(())
+}
+
+/// Tells V8 the process is under memory pressure, which triggers a full,
+/// compacting garbage collection.
+#[op2(fast)]
+pub fn op_v8_low_memory_notification(scope: &mut v8::HandleScope) {
+  scope.low_memory_notification();
+}
+
 pub struct Serializer<'a> {
   inner: v8::ValueSerializer<'a>,
 }
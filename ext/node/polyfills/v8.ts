@@ -10,6 +10,7 @@ import { primordials } from "ext:core/mod.js";
 const { ObjectPrototypeToString } = primordials;
 import {
   op_v8_cached_data_version_tag,
+  op_v8_get_heap_space_statistics,
   op_v8_get_heap_statistics,
   op_v8_get_wire_format_version,
   op_v8_new_deserializer,
@@ -26,6 +27,7 @@ import {
   op_v8_transfer_array_buffer_de,
   op_v8_write_double,
   op_v8_write_header,
+  op_v8_write_heap_snapshot,
   op_v8_write_raw_bytes,
   op_v8_write_uint32,
   op_v8_write_uint64,
@@ -33,6 +35,8 @@ import {
 } from "ext:core/ops";
 
 import { Buffer } from "node:buffer";
+import process from "node:process";
+import { threadId } from "node:worker_threads";
 
 import { notImplemented } from "ext:deno_node/_utils.ts";
 import { isArrayBufferView } from "ext:deno_node/internal/util/types.ts";
@@ -47,7 +51,7 @@ export function getHeapSnapshot() {
   notImplemented("v8.getHeapSnapshot");
 }
 export function getHeapSpaceStatistics() {
-  notImplemented("v8.getHeapSpaceStatistics");
+  return op_v8_get_heap_space_statistics();
 }
 
 const buffer = new Float64Array(14);
@@ -89,8 +93,30 @@ export function stopCoverage() {
 export function takeCoverage() {
   notImplemented("v8.takeCoverage");
 }
-export function writeHeapSnapshot() {
-  notImplemented("v8.writeHeapSnapshot");
+let heapSnapshotSeq = 0;
+
+function defaultHeapSnapshotFilename() {
+  const now = new Date();
+  const pad = (n: number, width = 2) => String(n).padStart(width, "0");
+  const date = `${now.getFullYear()}${pad(now.getMonth() + 1)}${
+    pad(now.getDate())
+  }`;
+  const time = `${pad(now.getHours())}${pad(now.getMinutes())}${
+    pad(now.getSeconds())
+  }`;
+  return `Heap.${date}.${time}.${process.pid}.${threadId}.${
+    pad(++heapSnapshotSeq, 3)
+  }.heapsnapshot`;
+}
+
+// The snapshot is taken synchronously; the isolate is paused until the file
+// is written.
+export function writeHeapSnapshot(filename?: string) {
+  if (filename === undefined) {
+    filename = defaultHeapSnapshotFilename();
+  }
+  op_v8_write_heap_snapshot(filename);
+  return filename;
 }
 // deno-lint-ignore no-explicit-any
 export function serialize(value: any) {
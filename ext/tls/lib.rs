@@ -21,13 +21,18 @@ use rustls_pemfile::ec_private_keys;
 use rustls_pemfile::pkcs8_private_keys;
 use rustls_pemfile::rsa_private_keys;
 use serde::Deserialize;
+use serde::Serialize;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Cursor;
 use std::net::IpAddr;
 use std::sync::Arc;
 
+mod pinning;
 mod tls_key;
+pub use pinning::spki_hash;
+pub use pinning::PinningError;
+pub use pinning::SpkiPins;
 pub use tls_key::*;
 
 #[derive(Debug, thiserror::Error)]
@@ -44,6 +49,10 @@ pub enum TlsError {
   KeysNotFound,
   #[error("Unable to decode key")]
   KeyDecode,
+  #[error("Invalid pin '{0}', expected a base64 encoded SHA-256 hash")]
+  InvalidPin(String),
+  #[error(transparent)]
+  Verifier(#[from] rustls::client::VerifierBuilderError),
 }
 
 /// Lazily resolves the root cert store.
@@ -54,6 +63,52 @@ pub trait RootCertStoreProvider: Send + Sync {
   fn get_or_try_init(
     &self,
   ) -> Result<&RootCertStore, deno_core::error::AnyError>;
+
+  /// Defaults for TLS client connections that don't configure these options
+  /// themselves, e.g. the ones baked into a `deno compile`d binary.
+  fn client_defaults(&self) -> Option<&TlsClientDefaults> {
+    None
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TlsVersion {
+  #[serde(rename = "1.2")]
+  Tls12,
+  #[serde(rename = "1.3")]
+  Tls13,
+}
+
+impl TlsVersion {
+  /// The protocol versions that are at least this version.
+  pub fn protocol_versions(
+    &self,
+  ) -> &'static [&'static rustls::SupportedProtocolVersion] {
+    match self {
+      TlsVersion::Tls12 => rustls::ALL_VERSIONS,
+      TlsVersion::Tls13 => &[&rustls::version::TLS13],
+    }
+  }
+}
+
+impl std::str::FromStr for TlsVersion {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "1.2" => Ok(TlsVersion::Tls12),
+      "1.3" => Ok(TlsVersion::Tls13),
+      _ => Err(format!(
+        "Unsupported TLS version '{s}', expected 1.2 or 1.3"
+      )),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TlsClientDefaults {
+  pub min_version: Option<TlsVersion>,
+  pub alpn_protocols: Option<Vec<String>>,
 }
 
 // This extension has no runtime apis, it only exports some shared native functions.
@@ -193,9 +248,29 @@ pub fn create_client_config(
   unsafely_ignore_certificate_errors: Option<Vec<String>>,
   maybe_cert_chain_and_key: TlsKeys,
   socket_use: SocketUse,
+) -> Result<ClientConfig, TlsError> {
+  create_client_config_with_versions(
+    root_cert_store,
+    ca_certs,
+    unsafely_ignore_certificate_errors,
+    maybe_cert_chain_and_key,
+    socket_use,
+    rustls::DEFAULT_VERSIONS,
+  )
+}
+
+/// Like `create_client_config`, but only negotiates the given protocol
+/// versions.
+pub fn create_client_config_with_versions(
+  root_cert_store: Option<RootCertStore>,
+  ca_certs: Vec<Vec<u8>>,
+  unsafely_ignore_certificate_errors: Option<Vec<String>>,
+  maybe_cert_chain_and_key: TlsKeys,
+  socket_use: SocketUse,
+  versions: &[&'static rustls::SupportedProtocolVersion],
 ) -> Result<ClientConfig, TlsError> {
   if let Some(ic_allowlist) = unsafely_ignore_certificate_errors {
-    let client_config = ClientConfig::builder()
+    let client_config = ClientConfig::builder_with_protocol_versions(versions)
       .dangerous()
       .with_custom_certificate_verifier(Arc::new(
         NoCertificateVerification::new(ic_allowlist),
@@ -219,7 +294,57 @@ pub fn create_client_config(
 
   let mut root_cert_store =
     root_cert_store.unwrap_or_else(create_default_root_cert_store);
-  // If custom certs are specified, add them to the store
+  add_ca_certs(&mut root_cert_store, ca_certs)?;
+
+  let client_config = ClientConfig::builder_with_protocol_versions(versions)
+    .with_root_certificates(root_cert_store);
+
+  let mut client = match maybe_cert_chain_and_key {
+    TlsKeys::Static(TlsKey(cert_chain, private_key)) => client_config
+      .with_client_auth_cert(cert_chain, private_key.clone_key())
+      .expect("invalid client key or certificate"),
+    TlsKeys::Null => client_config.with_no_client_auth(),
+    TlsKeys::Resolver(_) => unimplemented!(),
+  };
+
+  add_alpn(&mut client, socket_use);
+  Ok(client)
+}
+
+/// Like `create_client_config_with_versions`, but additionally requires the
+/// server to present a certificate with one of the pinned public keys.
+pub fn create_client_config_with_pins(
+  root_cert_store: Option<RootCertStore>,
+  ca_certs: Vec<Vec<u8>>,
+  unsafely_ignore_certificate_errors: Option<Vec<String>>,
+  maybe_cert_chain_and_key: TlsKeys,
+  socket_use: SocketUse,
+  versions: &[&'static rustls::SupportedProtocolVersion],
+  pins: SpkiPins,
+) -> Result<ClientConfig, TlsError> {
+  let verifier: Arc<dyn ServerCertVerifier> =
+    if let Some(ic_allowlist) = unsafely_ignore_certificate_errors {
+      Arc::new(NoCertificateVerification::new(ic_allowlist))
+    } else {
+      let mut root_cert_store =
+        root_cert_store.unwrap_or_else(create_default_root_cert_store);
+      add_ca_certs(&mut root_cert_store, ca_certs)?;
+      WebPkiServerVerifier::builder(Arc::new(root_cert_store)).build()?
+    };
+  Ok(pinning::pinned_client_config(
+    versions,
+    verifier,
+    pins,
+    maybe_cert_chain_and_key,
+    socket_use,
+  ))
+}
+
+/// Adds the PEM encoded certificates in `ca_certs` to `root_cert_store`.
+fn add_ca_certs(
+  root_cert_store: &mut RootCertStore,
+  ca_certs: Vec<Vec<u8>>,
+) -> Result<(), TlsError> {
   for cert in ca_certs {
     let reader = &mut BufReader::new(Cursor::new(cert));
     // This function does not return specific errors, if it fails give a generic message.
@@ -234,20 +359,7 @@ pub fn create_client_config(
       }
     }
   }
-
-  let client_config =
-    ClientConfig::builder().with_root_certificates(root_cert_store);
-
-  let mut client = match maybe_cert_chain_and_key {
-    TlsKeys::Static(TlsKey(cert_chain, private_key)) => client_config
-      .with_client_auth_cert(cert_chain, private_key.clone_key())
-      .expect("invalid client key or certificate"),
-    TlsKeys::Null => client_config.with_no_client_auth(),
-    TlsKeys::Resolver(_) => unimplemented!(),
-  };
-
-  add_alpn(&mut client, socket_use);
-  Ok(client)
+  Ok(())
 }
 
 fn add_alpn(client: &mut ClientConfig, socket_use: SocketUse) {
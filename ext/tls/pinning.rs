@@ -0,0 +1,387 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Certificate pinning by the SHA-256 hash of the SubjectPublicKeyInfo.
+//!
+//! A pinned connection first verifies the server's certificate chain as
+//! usual, then requires at least one certificate presented by the server to
+//! carry one of the pinned public keys. Pinning the key rather than the
+//! certificate keeps a pin valid when a certificate is renewed with the same
+//! key.
+//!
+//! Pins are the base64 encoded SHA-256 hashes of the DER encoded
+//! SubjectPublicKeyInfo, the format of HPKP's `pin-sha256`.
+//!
+//! Pinned connections never resume a TLS session: a resumed handshake does
+//! not present the server's certificate, so it could not be checked.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use rustls::client::danger::HandshakeSignatureValid;
+use rustls::client::danger::ServerCertVerified;
+use rustls::client::danger::ServerCertVerifier;
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::ServerName;
+use rustls::pki_types::UnixTime;
+use rustls::ClientConfig;
+use rustls::DigitallySignedStruct;
+use rustls::OtherError;
+use x509_parser::prelude::FromDer;
+use x509_parser::prelude::X509Certificate;
+
+use crate::add_alpn;
+use crate::SocketUse;
+use crate::TlsError;
+use crate::TlsKey;
+use crate::TlsKeys;
+
+/// The pins of a connection.
+#[derive(Debug, Clone)]
+pub struct SpkiPins {
+  hashes: Vec<[u8; 32]>,
+  /// Only log a mismatch instead of failing the handshake.
+  report_only: bool,
+}
+
+impl SpkiPins {
+  pub fn new(pins: &[String], report_only: bool) -> Result<Self, TlsError> {
+    let hashes = pins
+      .iter()
+      .map(|pin| {
+        BASE64_STANDARD
+          .decode(pin)
+          .ok()
+          .and_then(|hash| hash.try_into().ok())
+          .ok_or_else(|| TlsError::InvalidPin(pin.clone()))
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(Self {
+      hashes,
+      report_only,
+    })
+  }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+  "No certificate presented by '{server_name}' matches a pinned public key, presented SPKI hashes: {}",
+  presented.join(", ")
+)]
+pub struct PinningError {
+  pub server_name: String,
+  /// The base64 encoded SPKI hashes of the presented certificates, leaf
+  /// first.
+  pub presented: Vec<String>,
+}
+
+fn spki_sha256(cert: &CertificateDer) -> Result<[u8; 32], TlsError> {
+  let (_, cert) =
+    X509Certificate::from_der(cert).map_err(|_| TlsError::CertInvalid)?;
+  let digest =
+    ring::digest::digest(&ring::digest::SHA256, cert.public_key().raw);
+  Ok(digest.as_ref().try_into().unwrap())
+}
+
+/// The base64 encoded SHA-256 hash of the SubjectPublicKeyInfo of a DER
+/// encoded certificate, as used for a pin.
+pub fn spki_hash(cert: &CertificateDer) -> Result<String, TlsError> {
+  Ok(BASE64_STANDARD.encode(spki_sha256(cert)?))
+}
+
+fn server_name_to_string(server_name: &ServerName) -> String {
+  match server_name {
+    ServerName::DnsName(dns_name) => dns_name.as_ref().to_owned(),
+    ServerName::IpAddress(ip_address) => {
+      Into::<IpAddr>::into(*ip_address).to_string()
+    }
+    _ => "unknown".to_string(),
+  }
+}
+
+/// Verifies the certificate chain with `inner`, then checks it against the
+/// pins.
+#[derive(Debug)]
+pub struct PinningVerifier {
+  inner: Arc<dyn ServerCertVerifier>,
+  pins: SpkiPins,
+}
+
+impl PinningVerifier {
+  pub fn new(inner: Arc<dyn ServerCertVerifier>, pins: SpkiPins) -> Self {
+    Self { inner, pins }
+  }
+
+  fn check_pins(
+    &self,
+    chain: &[&CertificateDer],
+    server_name: &ServerName,
+  ) -> Result<(), PinningError> {
+    let hashes = chain
+      .iter()
+      .filter_map(|cert| spki_sha256(cert).ok())
+      .collect::<Vec<_>>();
+    if hashes.iter().any(|hash| self.pins.hashes.contains(hash)) {
+      return Ok(());
+    }
+    Err(PinningError {
+      server_name: server_name_to_string(server_name),
+      presented: hashes
+        .iter()
+        .map(|hash| BASE64_STANDARD.encode(hash))
+        .collect(),
+    })
+  }
+}
+
+impl ServerCertVerifier for PinningVerifier {
+  fn verify_server_cert(
+    &self,
+    end_entity: &CertificateDer<'_>,
+    intermediates: &[CertificateDer<'_>],
+    server_name: &ServerName<'_>,
+    ocsp_response: &[u8],
+    now: UnixTime,
+  ) -> Result<ServerCertVerified, rustls::Error> {
+    let verified = self.inner.verify_server_cert(
+      end_entity,
+      intermediates,
+      server_name,
+      ocsp_response,
+      now,
+    )?;
+    let chain = std::iter::once(end_entity)
+      .chain(intermediates)
+      .collect::<Vec<_>>();
+    match self.check_pins(&chain, server_name) {
+      Ok(()) => Ok(verified),
+      Err(err) if self.pins.report_only => {
+        log::warn!("{err}");
+        Ok(verified)
+      }
+      Err(err) => Err(rustls::Error::Other(OtherError(Arc::new(err)))),
+    }
+  }
+
+  fn verify_tls12_signature(
+    &self,
+    message: &[u8],
+    cert: &CertificateDer<'_>,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, rustls::Error> {
+    self.inner.verify_tls12_signature(message, cert, dss)
+  }
+
+  fn verify_tls13_signature(
+    &self,
+    message: &[u8],
+    cert: &CertificateDer<'_>,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, rustls::Error> {
+    self.inner.verify_tls13_signature(message, cert, dss)
+  }
+
+  fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+    self.inner.supported_verify_schemes()
+  }
+}
+
+/// Creates a client config that verifies certificates with `inner` and then
+/// checks them against `pins`.
+pub(crate) fn pinned_client_config(
+  versions: &[&'static rustls::SupportedProtocolVersion],
+  inner: Arc<dyn ServerCertVerifier>,
+  pins: SpkiPins,
+  maybe_cert_chain_and_key: TlsKeys,
+  socket_use: SocketUse,
+) -> ClientConfig {
+  let client_config = ClientConfig::builder_with_protocol_versions(versions)
+    .dangerous()
+    .with_custom_certificate_verifier(Arc::new(PinningVerifier::new(
+      inner, pins,
+    )));
+  let mut client = match maybe_cert_chain_and_key {
+    TlsKeys::Static(TlsKey(cert_chain, private_key)) => client_config
+      .with_client_auth_cert(cert_chain, private_key.clone_key())
+      .expect("invalid client key or certificate"),
+    TlsKeys::Null => client_config.with_no_client_auth(),
+    TlsKeys::Resolver(_) => unimplemented!(),
+  };
+  client.resumption = rustls::client::Resumption::disabled();
+  add_alpn(&mut client, socket_use);
+  client
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rustls::pki_types::PrivateKeyDer;
+  use rustls::ClientConnection;
+  use rustls::ServerConfig;
+  use rustls::ServerConnection;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+
+  const EXAMPLE1_PIN: &str = "k9nHE4T5YCE1CQ9ge4Yztfonw6e4EE63lojBOiGUx6w=";
+  const EXAMPLE2_PIN: &str = "FjRhD53WknpS6W2K6c6KtzKCEy51op9GMixhcgcIMdY=";
+
+  fn testdata(name: &str) -> Vec<u8> {
+    let manifest_dir =
+      std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    std::fs::read(manifest_dir.join("testdata").join(name)).unwrap()
+  }
+
+  /// Accepts any certificate, and counts how often it was asked to.
+  #[derive(Debug, Default)]
+  struct AcceptAll(AtomicUsize);
+
+  impl ServerCertVerifier for AcceptAll {
+    fn verify_server_cert(
+      &self,
+      _end_entity: &CertificateDer<'_>,
+      _intermediates: &[CertificateDer<'_>],
+      _server_name: &ServerName<'_>,
+      _ocsp_response: &[u8],
+      _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+      self.0.fetch_add(1, Ordering::SeqCst);
+      Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+      &self,
+      _message: &[u8],
+      _cert: &CertificateDer<'_>,
+      _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+      Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+      &self,
+      _message: &[u8],
+      _cert: &CertificateDer<'_>,
+      _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+      Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+      rustls::crypto::ring::default_provider()
+        .signature_verification_algorithms
+        .supported_schemes()
+    }
+  }
+
+  fn server_config() -> Arc<ServerConfig> {
+    let cert = CertificateDer::from(testdata("example1_cert.der"));
+    let key = PrivateKeyDer::Pkcs1(testdata("example1_prikey.der").into());
+    Arc::new(
+      ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .unwrap(),
+    )
+  }
+
+  fn client_config(
+    pins: &[&str],
+    report_only: bool,
+  ) -> (Arc<ClientConfig>, Arc<AcceptAll>) {
+    let pins = pins.iter().map(|pin| pin.to_string()).collect::<Vec<_>>();
+    let inner = Arc::new(AcceptAll::default());
+    let config = pinned_client_config(
+      rustls::DEFAULT_VERSIONS,
+      inner.clone(),
+      SpkiPins::new(&pins, report_only).unwrap(),
+      TlsKeys::Null,
+      SocketUse::GeneralSsl,
+    );
+    (Arc::new(config), inner)
+  }
+
+  /// Runs a handshake in memory, including the session tickets sent after
+  /// it.
+  fn handshake(
+    client_config: &Arc<ClientConfig>,
+    server_config: &Arc<ServerConfig>,
+  ) -> Result<(), rustls::Error> {
+    let server_name = ServerName::try_from("example1.com").unwrap();
+    let mut client =
+      ClientConnection::new(client_config.clone(), server_name).unwrap();
+    let mut server = ServerConnection::new(server_config.clone()).unwrap();
+    while client.wants_write() || server.wants_write() {
+      let mut buf = Vec::new();
+      while client.wants_write() {
+        client.write_tls(&mut buf).unwrap();
+      }
+      if !buf.is_empty() {
+        server.read_tls(&mut buf.as_slice()).unwrap();
+        server.process_new_packets()?;
+      }
+      let mut buf = Vec::new();
+      while server.wants_write() {
+        server.write_tls(&mut buf).unwrap();
+      }
+      if !buf.is_empty() {
+        client.read_tls(&mut buf.as_slice()).unwrap();
+        client.process_new_packets()?;
+      }
+    }
+    assert!(!client.is_handshaking());
+    Ok(())
+  }
+
+  #[test]
+  fn spki_hash_of_certificate() {
+    let cert = CertificateDer::from(testdata("example1_cert.der"));
+    assert_eq!(spki_hash(&cert).unwrap(), EXAMPLE1_PIN);
+    let cert = CertificateDer::from(testdata("example2_cert.der"));
+    assert_eq!(spki_hash(&cert).unwrap(), EXAMPLE2_PIN);
+    assert!(spki_hash(&CertificateDer::from(vec![0x30, 0x00])).is_err());
+  }
+
+  #[test]
+  fn invalid_pins() {
+    for pin in ["", "not base64!", "AAAA"] {
+      let err = SpkiPins::new(&[pin.to_string()], false).unwrap_err();
+      assert!(matches!(err, TlsError::InvalidPin(p) if p == pin));
+    }
+  }
+
+  #[test]
+  fn matching_pin() {
+    let (client, _) = client_config(&[EXAMPLE2_PIN, EXAMPLE1_PIN], false);
+    handshake(&client, &server_config()).unwrap();
+  }
+
+  #[test]
+  fn mismatching_pin() {
+    let (client, _) = client_config(&[EXAMPLE2_PIN], false);
+    let err = handshake(&client, &server_config()).unwrap_err();
+    let rustls::Error::Other(OtherError(err)) = err else {
+      panic!("unexpected error: {err:?}");
+    };
+    let err = err.downcast_ref::<PinningError>().unwrap();
+    assert_eq!(err.server_name, "example1.com");
+    assert_eq!(err.presented, [EXAMPLE1_PIN]);
+    assert!(err.to_string().contains(EXAMPLE1_PIN));
+  }
+
+  #[test]
+  fn report_only_pin() {
+    let (client, _) = client_config(&[EXAMPLE2_PIN], true);
+    handshake(&client, &server_config()).unwrap();
+  }
+
+  #[test]
+  fn sessions_are_not_resumed() {
+    let server = server_config();
+    let (client, inner) = client_config(&[EXAMPLE1_PIN], false);
+    handshake(&client, &server).unwrap();
+    handshake(&client, &server).unwrap();
+    // A resumed session would have skipped the verifier, and the pin check.
+    assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+  }
+}
@@ -7,15 +7,24 @@
 /// <reference path="../web/lib.deno_web.d.ts" />
 
 import { core, internals, primordials } from "ext:core/mod.js";
-import { op_base64_decode, op_base64_encode } from "ext:core/ops";
+import {
+  op_base64_decode,
+  op_base64_encode,
+  op_dispatch_batch,
+  op_stream_next,
+  op_stream_take,
+} from "ext:core/ops";
 const {
   ArrayPrototypeJoin,
+  ArrayPrototypePush,
   ArrayPrototypeMap,
   decodeURIComponent,
   Error,
   JSONStringify,
   NumberPrototypeToString,
   ObjectPrototypeIsPrototypeOf,
+  PromiseResolve,
+  queueMicrotask,
   RegExpPrototypeTest,
   SafeArrayIterator,
   SafeRegExp,
@@ -31,8 +40,13 @@ const {
   StringPrototypeToLowerCase,
   StringPrototypeToUpperCase,
   Symbol,
+  SymbolAsyncIterator,
   TypeError,
 } = primordials;
+const {
+  BadResourcePrototype,
+  InterruptedPrototype,
+} = core;
 
 import { URLPrototype } from "ext:deno_url/00_url.js";
 
@@ -467,6 +481,139 @@ export const SymbolAsyncDispose = Symbol.asyncDispose ??
 export const SymbolMetadata = Symbol.metadata ??
   Symbol("Symbol.metadata");
 
+/**
+ * Async iterator over the items of a stream that an op registered with
+ * `add_op_stream`. The stream is only polled while a `next()` call is
+ * pending, and `return()` closes it.
+ */
+class OpStream {
+  #rid = 0;
+  #promise;
+
+  constructor(rid) {
+    this.#rid = rid;
+  }
+
+  get rid() {
+    return this.#rid;
+  }
+
+  async next() {
+    let ready;
+    try {
+      this.#promise = op_stream_next(this.#rid);
+      ready = await this.#promise;
+    } catch (error) {
+      if (
+        ObjectPrototypeIsPrototypeOf(BadResourcePrototype, error) ||
+        ObjectPrototypeIsPrototypeOf(InterruptedPrototype, error)
+      ) {
+        return { value: undefined, done: true };
+      }
+      core.tryClose(this.#rid);
+      throw error;
+    }
+    if (!ready) {
+      core.tryClose(this.#rid);
+      return { value: undefined, done: true };
+    }
+    return { value: op_stream_take(this.#rid), done: false };
+  }
+
+  return(value) {
+    core.tryClose(this.#rid);
+    return PromiseResolve({ value, done: true });
+  }
+
+  ref() {
+    core.refOpPromise(this.#promise);
+  }
+
+  unref() {
+    core.unrefOpPromise(this.#promise);
+  }
+
+  [SymbolAsyncIterator]() {
+    return this;
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+function opStream(rid) {
+  return new OpStream(rid);
+}
+
+/**
+ * Queues calls of a sync op that was registered with
+ * `register_batchable_op` and makes them with one `op_dispatch_batch` call,
+ * in a microtask scheduled by the first queued call or when `flush()` is
+ * called. A failed call doesn't stop the following ones, its error is passed
+ * to `onError`.
+ */
+class OpBatch {
+  #opName;
+  #onError;
+  #payloads = [];
+  #scheduled = false;
+
+  /**
+   * @param {string} opName
+   * @param {(error: Error, args: unknown[]) => void} onError
+   */
+  constructor(opName, onError) {
+    this.#opName = opName;
+    this.#onError = onError;
+  }
+
+  /** The number of queued calls. */
+  get size() {
+    return this.#payloads.length;
+  }
+
+  /** @param {unknown[]} args */
+  push(args) {
+    ArrayPrototypePush(this.#payloads, args);
+    if (!this.#scheduled) {
+      this.#scheduled = true;
+      queueMicrotask(() => {
+        this.#scheduled = false;
+        this.flush();
+      });
+    }
+  }
+
+  /**
+   * Makes the queued calls. Returns their results in order, with an error
+   * object in place of the result of a failed call.
+   * @returns {unknown[]}
+   */
+  flush() {
+    const payloads = this.#payloads;
+    if (payloads.length === 0) {
+      return [];
+    }
+    this.#payloads = [];
+    const { 0: results, 1: errors } = op_dispatch_batch(
+      this.#opName,
+      payloads,
+    );
+    for (let i = 0; i < errors.length; ++i) {
+      const { index, className, message, code } = errors[i];
+      const error = core.buildCustomError(className, message) ??
+        new Error(message);
+      if (code !== null) {
+        error.code = code;
+      }
+      results[index] = error;
+      this.#onError(error, payloads[index]);
+    }
+    return results;
+  }
+}
+
 export {
   ASCII_ALPHA,
   ASCII_ALPHANUMERIC,
@@ -494,6 +641,8 @@ export {
   HTTP_WHITESPACE_PREFIX_RE,
   HTTP_WHITESPACE_SUFFIX_RE,
   httpTrim,
+  OpBatch,
+  opStream,
   pathFromURL,
   regexMatcher,
   serializeJSValueToJSONString,
@@ -0,0 +1,122 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// @ts-check
+/// <reference path="../../core/lib.deno_core.d.ts" />
+/// <reference path="./internal.d.ts" />
+
+import { core, primordials } from "ext:core/mod.js";
+import {
+  op_channel_close,
+  op_channel_create,
+  op_channel_recv,
+  op_channel_send,
+} from "ext:core/ops";
+const {
+  NumberIsSafeInteger,
+  ObjectPrototypeIsPrototypeOf,
+  RangeError,
+  Symbol,
+  SymbolFor,
+} = primordials;
+
+import { DOMException } from "./01_dom_exception.js";
+import { createFilteredInspectProxy } from "ext:deno_console/01_console.js";
+
+const _token = Symbol("[[token]]");
+const _brand = Symbol("[[brand]]");
+
+/**
+ * @param {{ timeoutMs?: number } | undefined} options
+ * @returns {number | null}
+ */
+function getTimeout(options) {
+  const timeoutMs = options?.timeoutMs;
+  if (timeoutMs === undefined) {
+    return null;
+  }
+  if (!NumberIsSafeInteger(timeoutMs) || timeoutMs < 0) {
+    throw new RangeError(
+      `'timeoutMs' must be a non-negative integer, received ${timeoutMs}`,
+    );
+  }
+  return timeoutMs;
+}
+
+class Channel {
+  /** @type {string} */
+  [_token];
+
+  /**
+   * @param {number} capacity
+   */
+  constructor(capacity, brand = undefined) {
+    if (brand === _brand) {
+      this[_token] = capacity;
+      return;
+    }
+    if (
+      !NumberIsSafeInteger(capacity) || capacity < 1 || capacity > 0x7fffffff
+    ) {
+      throw new RangeError(
+        `'capacity' must be a positive integer, received ${capacity}`,
+      );
+    }
+    this[_token] = op_channel_create(capacity);
+  }
+
+  /**
+   * Connect to a channel created by this or another worker.
+   * @param {string} token
+   * @returns {Channel}
+   */
+  static fromToken(token) {
+    return new Channel(token, _brand);
+  }
+
+  /** @returns {string} */
+  get token() {
+    return this[_token];
+  }
+
+  /**
+   * @param {any} value
+   * @param {{ timeoutMs?: number }} [options]
+   * @returns {Promise<void>}
+   */
+  async send(value, options = undefined) {
+    const timeoutMs = getTimeout(options);
+    const data = core.serialize(value, undefined, (err) => {
+      throw new DOMException(err, "DataCloneError");
+    });
+    await op_channel_send(this[_token], data, timeoutMs);
+  }
+
+  /**
+   * @param {{ timeoutMs?: number }} [options]
+   * @returns {Promise<any>}
+   */
+  async recv(options = undefined) {
+    const timeoutMs = getTimeout(options);
+    const data = await op_channel_recv(this[_token], timeoutMs);
+    return core.deserialize(data);
+  }
+
+  close() {
+    op_channel_close(this[_token]);
+  }
+
+  [SymbolFor("Deno.privateCustomInspect")](inspect, inspectOptions) {
+    return inspect(
+      createFilteredInspectProxy({
+        object: this,
+        evaluate: ObjectPrototypeIsPrototypeOf(ChannelPrototype, this),
+        keys: ["token"],
+      }),
+      inspectOptions,
+    );
+  }
+}
+
+const ChannelPrototype = Channel.prototype;
+
+export { Channel };
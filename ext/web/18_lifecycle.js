@@ -0,0 +1,171 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// @ts-check
+/// <reference path="../../core/lib.deno_core.d.ts" />
+/// <reference path="./internal.d.ts" />
+
+import { core, primordials } from "ext:core/mod.js";
+import { op_lifecycle_hooks } from "ext:core/ops";
+const {
+  ArrayPrototypeIncludes,
+  ArrayPrototypeIndexOf,
+  ArrayPrototypePush,
+  ArrayPrototypeSlice,
+  ArrayPrototypeSplice,
+  FunctionPrototypeCall,
+  SafeMap,
+  TypeError,
+} = primordials;
+
+import { reportException } from "./02_event.js";
+
+const LIFECYCLE_EVENTS = [
+  "postInit",
+  "preMainModule",
+  "unhandledRejection",
+  "beforeShutdown",
+  "beforeTerminate",
+];
+
+// By the time user code runs, "postInit" and "preMainModule" have already
+// fired.
+const USER_LIFECYCLE_EVENTS = [
+  "unhandledRejection",
+  "beforeShutdown",
+  "beforeTerminate",
+];
+
+/** @type {Map<string, { source: string, fn: unknown }>} */
+const compiledHooks = new SafeMap();
+/** @type {Map<string, Function[]>} */
+const userHooks = new SafeMap();
+
+function compileHook(event, name, source) {
+  const key = `${event}:${name}`;
+  const compiled = compiledHooks.get(key);
+  if (compiled !== undefined && compiled.source === source) {
+    return compiled.fn;
+  }
+  const { 0: fn, 1: errorInfo } = core.evalContext(
+    `(${source})`,
+    `ext:lifecycle/${name}`,
+  );
+  if (errorInfo) {
+    throw errorInfo.thrown;
+  }
+  compiledHooks.set(key, { source, fn });
+  return fn;
+}
+
+function resolveHook(event, hook) {
+  const { name, callback } = hook;
+  const fn = callback.kind === "source"
+    ? compileHook(event, name, callback.value)
+    : globalThis[callback.value];
+  if (typeof fn !== "function") {
+    throw new TypeError(
+      `Lifecycle hook "${name}" for "${event}" is not a function`,
+    );
+  }
+  return fn;
+}
+
+/**
+ * Calls the hooks registered by extensions for `event`, then the hooks
+ * registered by user code. An exception thrown by a hook is reported like an
+ * exception thrown by an event listener and doesn't stop the other hooks.
+ * @param {string} event
+ * @param {unknown} args
+ * @param {() => boolean} [isDone] checked after every hook; remaining hooks
+ * are skipped once it returns true
+ */
+function dispatchLifecycleEvent(event, args, isDone) {
+  const hooks = op_lifecycle_hooks(event);
+  for (let i = 0; i < hooks.length; i++) {
+    try {
+      const fn = resolveHook(event, hooks[i]);
+      FunctionPrototypeCall(fn, undefined, args);
+    } catch (error) {
+      reportException(error);
+    }
+    if (isDone?.()) {
+      return;
+    }
+  }
+  const fns = userHooks.get(event);
+  if (fns === undefined) {
+    return;
+  }
+  // Hooks may add or remove hooks while running.
+  const snapshot = ArrayPrototypeSlice(fns);
+  for (let i = 0; i < snapshot.length; i++) {
+    try {
+      FunctionPrototypeCall(snapshot[i], undefined, args);
+    } catch (error) {
+      reportException(error);
+    }
+    if (isDone?.()) {
+      return;
+    }
+  }
+}
+
+/**
+ * Calls the "unhandledRejection" hooks until one of them marks the rejection
+ * as handled.
+ * @returns {boolean} whether the rejection was handled
+ */
+function dispatchUnhandledRejection(promise, reason) {
+  const args = {
+    promise,
+    reason,
+    handled: false,
+    markHandled() {
+      args.handled = true;
+    },
+  };
+  dispatchLifecycleEvent("unhandledRejection", args, () => args.handled);
+  return args.handled;
+}
+
+function checkUserEvent(event, prefix) {
+  if (!ArrayPrototypeIncludes(USER_LIFECYCLE_EVENTS, event)) {
+    const reason = ArrayPrototypeIncludes(LIFECYCLE_EVENTS, event)
+      ? `"${event}" has already fired`
+      : `"${event}" is not a lifecycle event`;
+    throw new TypeError(`${prefix}: ${reason}`);
+  }
+}
+
+function addLifecycleHook(event, hook) {
+  const prefix = "Failed to execute 'addLifecycleHook'";
+  checkUserEvent(event, prefix);
+  if (typeof hook !== "function") {
+    throw new TypeError(`${prefix}: hook is not a function`);
+  }
+  let fns = userHooks.get(event);
+  if (fns === undefined) {
+    fns = [];
+    userHooks.set(event, fns);
+  }
+  ArrayPrototypePush(fns, hook);
+}
+
+function removeLifecycleHook(event, hook) {
+  checkUserEvent(event, "Failed to execute 'removeLifecycleHook'");
+  const fns = userHooks.get(event);
+  if (fns === undefined) {
+    return;
+  }
+  const index = ArrayPrototypeIndexOf(fns, hook);
+  if (index !== -1) {
+    ArrayPrototypeSplice(fns, index, 1);
+  }
+}
+
+export {
+  addLifecycleHook,
+  dispatchLifecycleEvent,
+  dispatchUnhandledRejection,
+  removeLifecycleHook,
+};
@@ -0,0 +1,200 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// @ts-check
+/// <reference path="../../core/lib.deno_core.d.ts" />
+/// <reference path="./internal.d.ts" />
+
+import { primordials } from "ext:core/mod.js";
+import {
+  op_bytesink_create,
+  op_bytesink_length,
+  op_bytesink_take,
+  op_bytesink_write_bytes,
+  op_bytesink_write_u64,
+  op_bytesink_write_uint,
+  op_bytesink_write_varint,
+  op_bytesource_create,
+  op_bytesource_position,
+  op_bytesource_read_bytes,
+  op_bytesource_read_u64,
+  op_bytesource_read_uint,
+  op_bytesource_read_varint,
+  op_bytesource_remaining,
+} from "ext:core/ops";
+const {
+  NumberIsSafeInteger,
+  RangeError,
+  Symbol,
+} = primordials;
+
+const _sink = Symbol("[[sink]]");
+const _source = Symbol("[[source]]");
+
+// Values are big endian unless `littleEndian` is true, like with `DataView`.
+
+/**
+ * @param {number} value
+ * @param {string} name
+ */
+function checkLength(value, name) {
+  if (!NumberIsSafeInteger(value) || value < 0) {
+    throw new RangeError(
+      `'${name}' must be a non-negative integer, received ${value}`,
+    );
+  }
+}
+
+/**
+ * Appends binary values to a buffer that grows as needed.
+ */
+class ByteSink {
+  [_sink];
+
+  /**
+   * @param {{ initialCapacity?: number }} options
+   */
+  constructor(options = {}) {
+    if (options.initialCapacity !== undefined) {
+      checkLength(options.initialCapacity, "initialCapacity");
+    }
+    this[_sink] = op_bytesink_create(options);
+  }
+
+  /** @param {number} value */
+  writeU8(value) {
+    op_bytesink_write_uint(this[_sink], 1, value, false);
+  }
+
+  /**
+   * @param {number} value
+   * @param {boolean} littleEndian
+   */
+  writeU16(value, littleEndian = false) {
+    op_bytesink_write_uint(this[_sink], 2, value, littleEndian);
+  }
+
+  /**
+   * @param {number} value
+   * @param {boolean} littleEndian
+   */
+  writeU32(value, littleEndian = false) {
+    op_bytesink_write_uint(this[_sink], 4, value, littleEndian);
+  }
+
+  /**
+   * @param {bigint} value
+   * @param {boolean} littleEndian
+   */
+  writeU64(value, littleEndian = false) {
+    op_bytesink_write_u64(this[_sink], value, littleEndian);
+  }
+
+  /**
+   * Writes an unsigned LEB128 varint.
+   * @param {number} value
+   */
+  writeVarint(value) {
+    checkLength(value, "value");
+    op_bytesink_write_varint(this[_sink], value);
+  }
+
+  /** @param {ArrayBufferView | ArrayBuffer} bytes */
+  writeBytes(bytes) {
+    op_bytesink_write_bytes(this[_sink], bytes, false);
+  }
+
+  /**
+   * Writes the length of `bytes` as a varint, followed by `bytes`.
+   * @param {ArrayBufferView | ArrayBuffer} bytes
+   */
+  writeLengthPrefixed(bytes) {
+    op_bytesink_write_bytes(this[_sink], bytes, true);
+  }
+
+  /** @returns {number} */
+  get length() {
+    return op_bytesink_length(this[_sink]);
+  }
+
+  /**
+   * Returns the written bytes. The sink can't be written to afterwards.
+   * @returns {Uint8Array}
+   */
+  take() {
+    return op_bytesink_take(this[_sink]);
+  }
+}
+
+/**
+ * Reads binary values from a copy of a buffer. Reads past the end throw a
+ * `RangeError` with the `offset` and `width` of the read, and don't advance
+ * the position.
+ */
+class ByteSource {
+  [_source];
+
+  /** @param {ArrayBufferView} bytes */
+  constructor(bytes) {
+    this[_source] = op_bytesource_create(bytes);
+  }
+
+  /** @returns {number} */
+  readU8() {
+    return op_bytesource_read_uint(this[_source], 1, false);
+  }
+
+  /**
+   * @param {boolean} littleEndian
+   * @returns {number}
+   */
+  readU16(littleEndian = false) {
+    return op_bytesource_read_uint(this[_source], 2, littleEndian);
+  }
+
+  /**
+   * @param {boolean} littleEndian
+   * @returns {number}
+   */
+  readU32(littleEndian = false) {
+    return op_bytesource_read_uint(this[_source], 4, littleEndian);
+  }
+
+  /**
+   * @param {boolean} littleEndian
+   * @returns {bigint}
+   */
+  readU64(littleEndian = false) {
+    return op_bytesource_read_u64(this[_source], littleEndian);
+  }
+
+  /** @returns {number} */
+  readVarint() {
+    return op_bytesource_read_varint(this[_source]);
+  }
+
+  /**
+   * @param {number} length
+   * @returns {Uint8Array}
+   */
+  readBytes(length) {
+    checkLength(length, "length");
+    return op_bytesource_read_bytes(this[_source], length);
+  }
+
+  /** @returns {Uint8Array} */
+  readLengthPrefixed() {
+    return op_bytesource_read_bytes(this[_source], null);
+  }
+
+  /** @returns {number} */
+  get position() {
+    return op_bytesource_position(this[_source]);
+  }
+
+  /** @returns {number} */
+  get remaining() {
+    return op_bytesource_remaining(this[_source]);
+  }
+}
+
+export { ByteSink, ByteSource };
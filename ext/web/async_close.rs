@@ -0,0 +1,222 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Asynchronous teardown of resources when the runtime shuts down.
+//!
+//! `Resource::close` is synchronous and the resource table is dropped in no
+//! particular order, so a resource can neither do I/O on its way out (send a
+//! TLS close_notify, checkpoint a database) nor rely on the resources it is
+//! layered over still being open. Resource types that need either implement
+//! [`AsyncClose`] and are registered with [`register_async_close`], usually
+//! in their extension's `state` initializer. Once the event loop is done, the
+//! runtime calls [`close_resources`], which closes them before the resource
+//! table is dropped.
+//!
+//! Resources are closed one at a time in reverse creation order, so a stream
+//! is closed before the socket it wraps. Resource ids are handed out in
+//! increasing order, so they double as the creation sequence.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use deno_core::futures::future::LocalBoxFuture;
+use deno_core::OpState;
+use deno_core::Resource;
+
+pub type AsyncCloseFuture = LocalBoxFuture<'static, ()>;
+
+/// A resource that needs to do asynchronous work when the runtime shuts
+/// down.
+pub trait AsyncClose: Resource {
+  /// Starts closing the resource. Returns `None` when there is nothing to
+  /// wait for.
+  fn close_async(self: Rc<Self>) -> Option<AsyncCloseFuture>;
+}
+
+type ResourceCloser = fn(&Rc<dyn Resource>) -> Option<Option<AsyncCloseFuture>>;
+type StateCloser = Box<dyn FnOnce(&mut OpState) -> Option<AsyncCloseFuture>>;
+
+#[derive(Default)]
+pub struct AsyncCloseRegistry {
+  resources: Vec<ResourceCloser>,
+  state: Vec<StateCloser>,
+}
+
+fn close_as<T: AsyncClose>(
+  resource: &Rc<dyn Resource>,
+) -> Option<Option<AsyncCloseFuture>> {
+  resource
+    .downcast_rc::<T>()
+    .map(|resource| resource.clone().close_async())
+}
+
+fn registry(state: &mut OpState) -> &mut AsyncCloseRegistry {
+  if !state.has::<AsyncCloseRegistry>() {
+    state.put(AsyncCloseRegistry::default());
+  }
+  state.borrow_mut::<AsyncCloseRegistry>()
+}
+
+/// Makes [`close_resources`] close resources of type `T` with
+/// [`AsyncClose::close_async`].
+pub fn register_async_close<T: AsyncClose>(state: &mut OpState) {
+  registry(state).resources.push(close_as::<T>);
+}
+
+/// Registers `close` to tear down something that is kept in the `OpState`
+/// rather than in the resource table. These run after all resources are
+/// closed, in reverse registration order.
+pub fn register_state_close(
+  state: &mut OpState,
+  close: impl FnOnce(&mut OpState) -> Option<AsyncCloseFuture> + 'static,
+) {
+  registry(state).state.push(Box::new(close));
+}
+
+/// Takes all resources out of the table in reverse creation order, closing
+/// those with a registered [`AsyncClose`] implementation and awaiting each
+/// close before moving on to the next resource. Then runs the closers
+/// registered with [`register_state_close`].
+///
+/// Returns `false` when `deadline` passed first; whatever wasn't closed by
+/// then is left to be dropped with the runtime.
+pub async fn close_resources(
+  state: &Rc<RefCell<OpState>>,
+  deadline: Duration,
+) -> bool {
+  let Some(registry) = state.borrow_mut().try_take::<AsyncCloseRegistry>()
+  else {
+    return true;
+  };
+  let close_all = async move {
+    let rids = state
+      .borrow()
+      .resource_table
+      .names()
+      .map(|(rid, _)| rid)
+      .collect::<Vec<_>>();
+    for rid in rids.into_iter().rev() {
+      let Ok(resource) = state.borrow_mut().resource_table.take_any(rid) else {
+        continue;
+      };
+      let close = registry
+        .resources
+        .iter()
+        .find_map(|close| close(&resource))
+        .flatten();
+      drop(resource);
+      if let Some(close) = close {
+        close.await;
+      }
+    }
+    for close in registry.state.into_iter().rev() {
+      let close = close(&mut state.borrow_mut());
+      if let Some(close) = close {
+        close.await;
+      }
+    }
+  };
+  tokio::time::timeout(deadline, close_all).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::borrow::Cow;
+
+  type Log = Rc<RefCell<Vec<String>>>;
+
+  /// A resource layered over `inner`, which must still be open while it is
+  /// being closed.
+  struct Layer {
+    name: &'static str,
+    inner: Option<Rc<Layer>>,
+    open: RefCell<bool>,
+    log: Log,
+  }
+
+  impl Resource for Layer {
+    fn name(&self) -> Cow<str> {
+      self.name.into()
+    }
+  }
+
+  impl AsyncClose for Layer {
+    fn close_async(self: Rc<Self>) -> Option<AsyncCloseFuture> {
+      Some(Box::pin(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        if let Some(inner) = &self.inner {
+          assert!(*inner.open.borrow(), "{} closed first", inner.name);
+        }
+        *self.open.borrow_mut() = false;
+        self.log.borrow_mut().push(self.name.to_string());
+      }))
+    }
+  }
+
+  struct Hung;
+
+  impl Resource for Hung {}
+
+  impl AsyncClose for Hung {
+    fn close_async(self: Rc<Self>) -> Option<AsyncCloseFuture> {
+      Some(Box::pin(std::future::pending()))
+    }
+  }
+
+  fn layer(
+    name: &'static str,
+    inner: Option<Rc<Layer>>,
+    log: &Log,
+  ) -> Rc<Layer> {
+    Rc::new(Layer {
+      name,
+      inner,
+      open: RefCell::new(true),
+      log: log.clone(),
+    })
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn closes_in_reverse_creation_order() {
+    let log = Log::default();
+    let mut state = OpState::new(None);
+    register_async_close::<Layer>(&mut state);
+    register_state_close(&mut state, {
+      let log = log.clone();
+      move |_| {
+        log.borrow_mut().push("state".to_string());
+        None
+      }
+    });
+    let socket = layer("socket", None, &log);
+    let tls = layer("tls", Some(socket.clone()), &log);
+    let http = layer("http", Some(tls.clone()), &log);
+    state.resource_table.add_rc(socket);
+    state.resource_table.add_rc(tls);
+    state.resource_table.add_rc(http);
+
+    let state = Rc::new(RefCell::new(state));
+    assert!(close_resources(&state, Duration::from_secs(1)).await);
+    assert_eq!(*log.borrow(), ["http", "tls", "socket", "state"]);
+    assert_eq!(state.borrow().resource_table.names().count(), 0);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn deadline_bounds_a_hung_close() {
+    let log = Log::default();
+    let mut state = OpState::new(None);
+    register_async_close::<Layer>(&mut state);
+    register_async_close::<Hung>(&mut state);
+    state.resource_table.add_rc(layer("socket", None, &log));
+    state.resource_table.add(Hung);
+
+    let state = Rc::new(RefCell::new(state));
+    let start = tokio::time::Instant::now();
+    assert!(!close_resources(&state, Duration::from_secs(2)).await);
+    assert_eq!(start.elapsed(), Duration::from_secs(2));
+    // resources after the hung one are left for the table to drop
+    assert!(log.borrow().is_empty());
+    assert_eq!(state.borrow().resource_table.names().count(), 1);
+  }
+}
@@ -0,0 +1,132 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use deno_bench_util::bench_js_sync;
+use deno_bench_util::bench_or_profile;
+use deno_bench_util::bencher::benchmark_group;
+use deno_bench_util::bencher::Bencher;
+use deno_core::Extension;
+
+#[derive(Clone)]
+struct Permissions;
+
+impl deno_web::TimersPermission for Permissions {
+  fn allow_hrtime(&mut self) -> bool {
+    false
+  }
+}
+
+fn setup() -> Vec<Extension> {
+  // A message of a made up protocol: a header with an id, flags and a
+  // timestamp, followed by a length prefixed name and a payload.
+  deno_core::extension!(
+    bench_setup,
+    esm_entry_point = "ext:bench_setup/setup",
+    esm = ["ext:bench_setup/setup" = {
+      source = r#"
+        import { ByteSink, ByteSource } from "ext:deno_web/19_byte_codec.js";
+        const name = Deno.core.encode("temperature-sensor-17");
+        const payload = new Uint8Array(64).fill(7);
+
+        globalThis.encodeWithByteSink = () => {
+          const sink = new ByteSink({ initialCapacity: 128 });
+          sink.writeU32(42);
+          sink.writeU8(3);
+          sink.writeU64(1700000000000n);
+          sink.writeLengthPrefixed(name);
+          sink.writeBytes(payload);
+          return sink.take();
+        };
+
+        globalThis.encodeWithDataView = () => {
+          let buf = new Uint8Array(16);
+          let view = new DataView(buf.buffer);
+          let offset = 0;
+          const reserve = (n) => {
+            if (offset + n <= buf.length) return;
+            const grown = new Uint8Array(Math.max(buf.length * 2, offset + n));
+            grown.set(buf);
+            buf = grown;
+            view = new DataView(buf.buffer);
+          };
+          reserve(13);
+          view.setUint32(offset, 42);
+          view.setUint8(offset + 4, 3);
+          view.setBigUint64(offset + 5, 1700000000000n);
+          offset += 13;
+          reserve(1 + name.length);
+          view.setUint8(offset, name.length);
+          buf.set(name, offset + 1);
+          offset += 1 + name.length;
+          reserve(payload.length);
+          buf.set(payload, offset);
+          offset += payload.length;
+          return buf.slice(0, offset);
+        };
+
+        globalThis.decodeWithByteSource = (bytes) => {
+          const source = new ByteSource(bytes);
+          return [
+            source.readU32(),
+            source.readU8(),
+            source.readU64(),
+            source.readLengthPrefixed(),
+            source.readBytes(64),
+          ];
+        };
+
+        globalThis.decodeWithDataView = (bytes) => {
+          const view = new DataView(bytes.buffer, bytes.byteOffset);
+          const nameLength = view.getUint8(13);
+          return [
+            view.getUint32(0),
+            view.getUint8(4),
+            view.getBigUint64(5),
+            bytes.slice(14, 14 + nameLength),
+            bytes.slice(14 + nameLength, 14 + nameLength + 64),
+          ];
+        };
+
+        globalThis.message = encodeWithByteSink();
+      "#
+    }],
+    state = |state| {
+      state.put(Permissions {});
+    },
+  );
+
+  vec![
+    deno_webidl::deno_webidl::init_ops_and_esm(),
+    deno_url::deno_url::init_ops_and_esm(),
+    deno_console::deno_console::init_ops_and_esm(),
+    deno_web::deno_web::init_ops_and_esm::<Permissions>(
+      Default::default(),
+      None,
+    ),
+    bench_setup::init_ops_and_esm(),
+  ]
+}
+
+fn bench_encode_byte_sink(b: &mut Bencher) {
+  bench_js_sync(b, r#"encodeWithByteSink();"#, setup);
+}
+
+fn bench_encode_data_view(b: &mut Bencher) {
+  bench_js_sync(b, r#"encodeWithDataView();"#, setup);
+}
+
+fn bench_decode_byte_source(b: &mut Bencher) {
+  bench_js_sync(b, r#"decodeWithByteSource(message);"#, setup);
+}
+
+fn bench_decode_data_view(b: &mut Bencher) {
+  bench_js_sync(b, r#"decodeWithDataView(message);"#, setup);
+}
+
+benchmark_group!(
+  benches,
+  bench_encode_byte_sink,
+  bench_encode_data_view,
+  bench_decode_byte_source,
+  bench_decode_data_view,
+);
+bench_or_profile!(benches);
@@ -0,0 +1,566 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Growable, bounds-checked buffers for binary protocols.
+//!
+//! A `ByteSink` appends fixed-width integers, LEB128 varints and byte slices
+//! to a buffer that grows geometrically, and hands out an exact-size copy of
+//! it once. A `ByteSource` reads the same values from a buffer while
+//! advancing a cursor. Reads past the end fail with
+//! [`ByteCodecError::OutOfBounds`] and leave the cursor where it was.
+//! Endianness is chosen per call.
+
+use std::cell::Cell;
+use std::cell::RefCell;
+
+use deno_core::op2;
+use serde::Deserialize;
+
+/// The largest value a varint may hold, so that it survives the round trip
+/// through a JS number.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+const DEFAULT_CAPACITY: usize = 64;
+
+/// The largest initial capacity, to catch mistakes before allocating.
+const MAX_INITIAL_CAPACITY: usize = 1 << 30;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ByteCodecError {
+  #[error(
+    "Out of bounds read of {width} bytes at offset {offset}, {available} bytes left"
+  )]
+  OutOfBounds {
+    offset: usize,
+    width: usize,
+    available: usize,
+  },
+  #[error("Unsupported integer width {0}, expected 1, 2 or 4")]
+  InvalidWidth(u32),
+  #[error("Value {value} does not fit in {width} bytes")]
+  ValueOutOfRange { value: u64, width: u32 },
+  #[error("Varint at offset {0} exceeds 2^53 - 1")]
+  VarintOverflow(usize),
+  #[error("Initial capacity {0} is too large")]
+  CapacityTooLarge(usize),
+  #[error("ByteSink was already taken")]
+  Taken,
+}
+
+#[derive(Debug)]
+pub struct ByteSink(RefCell<Option<Vec<u8>>>);
+
+impl deno_core::GarbageCollected for ByteSink {}
+
+impl ByteSink {
+  fn with_capacity(capacity: usize) -> Self {
+    Self(RefCell::new(Some(Vec::with_capacity(capacity))))
+  }
+
+  fn write(&self, bytes: &[u8]) -> Result<(), ByteCodecError> {
+    let mut buf = self.0.borrow_mut();
+    let buf = buf.as_mut().ok_or(ByteCodecError::Taken)?;
+    // `Vec` at least doubles its capacity when it grows.
+    buf.extend_from_slice(bytes);
+    Ok(())
+  }
+
+  fn write_uint(
+    &self,
+    width: u32,
+    value: u32,
+    little_endian: bool,
+  ) -> Result<(), ByteCodecError> {
+    let max = match width {
+      1 => u8::MAX as u32,
+      2 => u16::MAX as u32,
+      4 => u32::MAX,
+      _ => return Err(ByteCodecError::InvalidWidth(width)),
+    };
+    if value > max {
+      return Err(ByteCodecError::ValueOutOfRange {
+        value: value as u64,
+        width,
+      });
+    }
+    let width = width as usize;
+    if little_endian {
+      self.write(&value.to_le_bytes()[..width])
+    } else {
+      self.write(&value.to_be_bytes()[4 - width..])
+    }
+  }
+
+  fn write_u64(
+    &self,
+    value: u64,
+    little_endian: bool,
+  ) -> Result<(), ByteCodecError> {
+    if little_endian {
+      self.write(&value.to_le_bytes())
+    } else {
+      self.write(&value.to_be_bytes())
+    }
+  }
+
+  fn write_varint(&self, mut value: u64) -> Result<(), ByteCodecError> {
+    if value > MAX_SAFE_INTEGER {
+      return Err(ByteCodecError::ValueOutOfRange { value, width: 8 });
+    }
+    let mut bytes = [0; 8];
+    let mut len = 0;
+    loop {
+      let byte = (value & 0x7f) as u8;
+      value >>= 7;
+      if value == 0 {
+        bytes[len] = byte;
+        len += 1;
+        break;
+      }
+      bytes[len] = byte | 0x80;
+      len += 1;
+    }
+    self.write(&bytes[..len])
+  }
+
+  fn len(&self) -> Result<usize, ByteCodecError> {
+    self
+      .0
+      .borrow()
+      .as_ref()
+      .map(Vec::len)
+      .ok_or(ByteCodecError::Taken)
+  }
+
+  fn take(&self) -> Result<Vec<u8>, ByteCodecError> {
+    let mut buf = self.0.borrow_mut().take().ok_or(ByteCodecError::Taken)?;
+    buf.shrink_to_fit();
+    Ok(buf)
+  }
+}
+
+#[derive(Debug)]
+pub struct ByteSource {
+  buf: Vec<u8>,
+  position: Cell<usize>,
+}
+
+impl deno_core::GarbageCollected for ByteSource {}
+
+impl ByteSource {
+  fn new(buf: Vec<u8>) -> Self {
+    Self {
+      buf,
+      position: Cell::new(0),
+    }
+  }
+
+  fn remaining(&self) -> usize {
+    self.buf.len() - self.position.get()
+  }
+
+  fn read(&self, width: usize) -> Result<&[u8], ByteCodecError> {
+    let offset = self.position.get();
+    let available = self.remaining();
+    if width > available {
+      return Err(ByteCodecError::OutOfBounds {
+        offset,
+        width,
+        available,
+      });
+    }
+    self.position.set(offset + width);
+    Ok(&self.buf[offset..offset + width])
+  }
+
+  fn read_uint(
+    &self,
+    width: u32,
+    little_endian: bool,
+  ) -> Result<u32, ByteCodecError> {
+    if !matches!(width, 1 | 2 | 4) {
+      return Err(ByteCodecError::InvalidWidth(width));
+    }
+    let width = width as usize;
+    let bytes = self.read(width)?;
+    let mut value = [0; 4];
+    if little_endian {
+      value[..width].copy_from_slice(bytes);
+      Ok(u32::from_le_bytes(value))
+    } else {
+      value[4 - width..].copy_from_slice(bytes);
+      Ok(u32::from_be_bytes(value))
+    }
+  }
+
+  fn read_u64(&self, little_endian: bool) -> Result<u64, ByteCodecError> {
+    let bytes: [u8; 8] = self.read(8)?.try_into().unwrap();
+    if little_endian {
+      Ok(u64::from_le_bytes(bytes))
+    } else {
+      Ok(u64::from_be_bytes(bytes))
+    }
+  }
+
+  fn read_varint(&self) -> Result<u64, ByteCodecError> {
+    let offset = self.position.get();
+    let mut value = 0u64;
+    for (i, byte) in self.buf[offset..].iter().enumerate() {
+      let shift = 7 * i as u32;
+      let bits = (byte & 0x7f) as u64;
+      // 2^53 - 1 takes 8 bytes, the last one holding 4 bits.
+      if shift > 49 || (shift == 49 && bits > 0xf) {
+        return Err(ByteCodecError::VarintOverflow(offset));
+      }
+      value |= bits << shift;
+      if byte & 0x80 == 0 {
+        self.position.set(offset + i + 1);
+        return Ok(value);
+      }
+    }
+    // The varint continues past the end.
+    let available = self.remaining();
+    Err(ByteCodecError::OutOfBounds {
+      offset,
+      width: available + 1,
+      available,
+    })
+  }
+
+  fn read_prefixed(&self) -> Result<&[u8], ByteCodecError> {
+    let start = self.position.get();
+    let len = self.read_varint()? as usize;
+    self.read(len).inspect_err(|_| self.position.set(start))
+  }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ByteSinkOptions {
+  initial_capacity: Option<usize>,
+}
+
+#[op2]
+#[cppgc]
+pub fn op_bytesink_create(
+  #[serde] options: Option<ByteSinkOptions>,
+) -> Result<ByteSink, ByteCodecError> {
+  let capacity = options
+    .unwrap_or_default()
+    .initial_capacity
+    .unwrap_or(DEFAULT_CAPACITY);
+  if capacity > MAX_INITIAL_CAPACITY {
+    return Err(ByteCodecError::CapacityTooLarge(capacity));
+  }
+  Ok(ByteSink::with_capacity(capacity))
+}
+
+#[op2]
+pub fn op_bytesink_write_uint(
+  #[cppgc] sink: &ByteSink,
+  #[smi] width: u32,
+  value: u32,
+  little_endian: bool,
+) -> Result<(), ByteCodecError> {
+  sink.write_uint(width, value, little_endian)
+}
+
+#[op2]
+pub fn op_bytesink_write_u64(
+  #[cppgc] sink: &ByteSink,
+  #[bigint] value: u64,
+  little_endian: bool,
+) -> Result<(), ByteCodecError> {
+  sink.write_u64(value, little_endian)
+}
+
+#[op2]
+pub fn op_bytesink_write_varint(
+  #[cppgc] sink: &ByteSink,
+  #[number] value: u64,
+) -> Result<(), ByteCodecError> {
+  sink.write_varint(value)
+}
+
+/// Appends `bytes`, preceded by their length as a varint if `prefixed`.
+#[op2]
+pub fn op_bytesink_write_bytes(
+  #[cppgc] sink: &ByteSink,
+  #[anybuffer] bytes: &[u8],
+  prefixed: bool,
+) -> Result<(), ByteCodecError> {
+  if prefixed {
+    sink.write_varint(bytes.len() as u64)?;
+  }
+  sink.write(bytes)
+}
+
+#[op2]
+#[number]
+pub fn op_bytesink_length(
+  #[cppgc] sink: &ByteSink,
+) -> Result<usize, ByteCodecError> {
+  sink.len()
+}
+
+/// Returns the written bytes. The sink can't be used afterwards.
+#[op2]
+#[buffer]
+pub fn op_bytesink_take(
+  #[cppgc] sink: &ByteSink,
+) -> Result<Vec<u8>, ByteCodecError> {
+  sink.take()
+}
+
+#[op2]
+#[cppgc]
+pub fn op_bytesource_create(#[buffer(copy)] buf: Vec<u8>) -> ByteSource {
+  ByteSource::new(buf)
+}
+
+#[op2]
+pub fn op_bytesource_read_uint(
+  #[cppgc] source: &ByteSource,
+  #[smi] width: u32,
+  little_endian: bool,
+) -> Result<u32, ByteCodecError> {
+  source.read_uint(width, little_endian)
+}
+
+#[op2]
+#[bigint]
+pub fn op_bytesource_read_u64(
+  #[cppgc] source: &ByteSource,
+  little_endian: bool,
+) -> Result<u64, ByteCodecError> {
+  source.read_u64(little_endian)
+}
+
+#[op2]
+#[number]
+pub fn op_bytesource_read_varint(
+  #[cppgc] source: &ByteSource,
+) -> Result<u64, ByteCodecError> {
+  source.read_varint()
+}
+
+/// Reads `len` bytes, or as many as the preceding varint says if `len` is
+/// `None`.
+#[op2]
+#[buffer]
+pub fn op_bytesource_read_bytes(
+  #[cppgc] source: &ByteSource,
+  #[serde] len: Option<usize>,
+) -> Result<Vec<u8>, ByteCodecError> {
+  let bytes = match len {
+    Some(len) => source.read(len)?,
+    None => source.read_prefixed()?,
+  };
+  Ok(bytes.to_vec())
+}
+
+#[op2]
+#[number]
+pub fn op_bytesource_position(#[cppgc] source: &ByteSource) -> usize {
+  source.position.get()
+}
+
+#[op2]
+#[number]
+pub fn op_bytesource_remaining(#[cppgc] source: &ByteSource) -> usize {
+  source.remaining()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Debug, Clone, Copy, PartialEq)]
+  enum Value {
+    Uint(u32, u32, bool),
+    U64(u64, bool),
+    Varint(u64),
+    Bytes(usize, bool),
+  }
+
+  /// xorshift64*, so that failures can be reproduced from the seed.
+  struct Rng(u64);
+
+  impl Rng {
+    fn next(&mut self) -> u64 {
+      self.0 ^= self.0 >> 12;
+      self.0 ^= self.0 << 25;
+      self.0 ^= self.0 >> 27;
+      self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn value(&mut self) -> Value {
+      let little_endian = self.next() & 1 == 0;
+      match self.next() % 4 {
+        0 => {
+          let width = [1, 2, 4][(self.next() % 3) as usize];
+          let max = if width == 4 {
+            u32::MAX as u64
+          } else {
+            (1 << (8 * width)) - 1
+          };
+          Value::Uint(width, (self.next() % (max + 1)) as u32, little_endian)
+        }
+        1 => Value::U64(self.next(), little_endian),
+        // Small and large varints.
+        2 => Value::Varint(self.next() >> (11 + self.next() % 53)),
+        _ => Value::Bytes((self.next() % 300) as usize, self.next() & 1 == 0),
+      }
+    }
+  }
+
+  fn bytes(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+  }
+
+  #[test]
+  fn round_trips_random_values() {
+    for seed in 1..=20 {
+      let mut rng = Rng(seed);
+      let values = (0..500).map(|_| rng.value()).collect::<Vec<_>>();
+
+      let sink = ByteSink::with_capacity(0);
+      for value in &values {
+        match *value {
+          Value::Uint(width, value, le) => {
+            sink.write_uint(width, value, le).unwrap()
+          }
+          Value::U64(value, le) => sink.write_u64(value, le).unwrap(),
+          Value::Varint(value) => sink.write_varint(value).unwrap(),
+          Value::Bytes(len, true) => {
+            sink.write_varint(len as u64).unwrap();
+            sink.write(&bytes(len)).unwrap();
+          }
+          Value::Bytes(len, false) => sink.write(&bytes(len)).unwrap(),
+        }
+      }
+      let buf = sink.take().unwrap();
+      assert_eq!(buf.len(), buf.capacity());
+
+      let source = ByteSource::new(buf);
+      for value in &values {
+        let read = match *value {
+          Value::Uint(width, _, le) => {
+            Value::Uint(width, source.read_uint(width, le).unwrap(), le)
+          }
+          Value::U64(_, le) => Value::U64(source.read_u64(le).unwrap(), le),
+          Value::Varint(_) => Value::Varint(source.read_varint().unwrap()),
+          Value::Bytes(len, true) => {
+            let read = source.read_prefixed().unwrap();
+            assert_eq!(read, bytes(len));
+            Value::Bytes(read.len(), true)
+          }
+          Value::Bytes(len, false) => {
+            let read = source.read(len).unwrap();
+            assert_eq!(read, bytes(len));
+            Value::Bytes(read.len(), false)
+          }
+        };
+        assert_eq!(&read, value, "seed {seed}");
+      }
+      assert_eq!(source.remaining(), 0);
+    }
+  }
+
+  #[test]
+  fn encodes_with_requested_endianness() {
+    let sink = ByteSink::with_capacity(0);
+    sink.write_uint(2, 0x0102, true).unwrap();
+    sink.write_uint(2, 0x0102, false).unwrap();
+    sink.write_uint(4, 0x01020304, false).unwrap();
+    sink.write_varint(300).unwrap();
+    assert_eq!(
+      sink.take().unwrap(),
+      [0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04, 0xac, 0x02]
+    );
+    assert!(matches!(sink.take(), Err(ByteCodecError::Taken)));
+  }
+
+  #[test]
+  fn rejects_values_that_do_not_fit() {
+    let sink = ByteSink::with_capacity(0);
+    assert!(matches!(
+      sink.write_uint(1, 256, true),
+      Err(ByteCodecError::ValueOutOfRange {
+        value: 256,
+        width: 1
+      })
+    ));
+    assert!(matches!(
+      sink.write_uint(3, 0, true),
+      Err(ByteCodecError::InvalidWidth(3))
+    ));
+    assert!(sink.write_varint(MAX_SAFE_INTEGER).is_ok());
+    assert!(sink.write_varint(MAX_SAFE_INTEGER + 1).is_err());
+    assert_eq!(sink.len().unwrap(), 8);
+  }
+
+  #[test]
+  fn reads_fail_at_exact_boundaries() {
+    let source = ByteSource::new(vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(source.read_uint(4, true).unwrap(), 0x04030201);
+    assert!(matches!(
+      source.read_uint(4, true),
+      Err(ByteCodecError::OutOfBounds {
+        offset: 4,
+        width: 4,
+        available: 2
+      })
+    ));
+    assert!(matches!(
+      source.read_u64(true),
+      Err(ByteCodecError::OutOfBounds {
+        offset: 4,
+        width: 8,
+        available: 2
+      })
+    ));
+    // Failed reads don't move the cursor.
+    assert_eq!(source.read_uint(2, false).unwrap(), 0x0506);
+    assert!(matches!(
+      source.read_uint(1, true),
+      Err(ByteCodecError::OutOfBounds {
+        offset: 6,
+        width: 1,
+        available: 0
+      })
+    ));
+    assert!(source.read(0).unwrap().is_empty());
+  }
+
+  #[test]
+  fn truncated_varints_and_prefixed_bytes() {
+    let source = ByteSource::new(vec![0x80, 0x80]);
+    assert!(matches!(
+      source.read_varint(),
+      Err(ByteCodecError::OutOfBounds {
+        offset: 0,
+        width: 3,
+        available: 2
+      })
+    ));
+    assert_eq!(source.position.get(), 0);
+
+    // Length 3, but only 2 bytes follow.
+    let source = ByteSource::new(vec![3, 1, 2]);
+    assert!(matches!(
+      source.read_prefixed(),
+      Err(ByteCodecError::OutOfBounds {
+        offset: 1,
+        width: 3,
+        available: 2
+      })
+    ));
+    assert_eq!(source.position.get(), 0);
+
+    let source = ByteSource::new(vec![0xff; 9]);
+    assert!(matches!(
+      source.read_varint(),
+      Err(ByteCodecError::VarintOverflow(0))
+    ));
+  }
+}
@@ -0,0 +1,295 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Bounded multi-producer, multi-consumer queues shared between workers.
+//!
+//! Channels live in a process-global registry and are addressed by a random
+//! 128-bit token, so any isolate in the process that was handed the token
+//! (e.g. via `postMessage`) can send to and receive from the same queue, and
+//! no other isolate can guess it. Items are structured-clone serialized by
+//! the caller.
+//!
+//! Senders waiting for space and receivers waiting for an item are each woken
+//! in FIFO order. A channel stays registered until it is closed, or until the
+//! worker that created it is dropped; closing it drops the queued items and
+//! rejects every pending and future operation.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use deno_core::op2;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_core::ToJsBuffer;
+use tokio::sync::Semaphore;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChannelError {
+  #[error("Channel capacity must be at least 1")]
+  InvalidCapacity,
+  #[error("Channel is closed")]
+  Closed,
+  #[error("Channel operation timed out")]
+  TimedOut,
+}
+
+struct Channel {
+  items: Mutex<VecDeque<Vec<u8>>>,
+  /// One permit per free slot. Senders queue here.
+  slots: Semaphore,
+  /// One permit per queued item. Receivers queue here.
+  ready: Semaphore,
+}
+
+impl Channel {
+  fn new(capacity: usize) -> Self {
+    Self {
+      items: Mutex::new(VecDeque::with_capacity(capacity)),
+      slots: Semaphore::new(capacity),
+      ready: Semaphore::new(0),
+    }
+  }
+
+  // Both operations are cancel safe: nothing changes until the permit was
+  // acquired, and everything after that is synchronous.
+
+  async fn send(&self, item: Vec<u8>) -> Result<(), ChannelError> {
+    let permit = self
+      .slots
+      .acquire()
+      .await
+      .map_err(|_| ChannelError::Closed)?;
+    permit.forget();
+    self.items.lock().unwrap().push_back(item);
+    self.ready.add_permits(1);
+    Ok(())
+  }
+
+  async fn recv(&self) -> Result<Vec<u8>, ChannelError> {
+    let permit = self
+      .ready
+      .acquire()
+      .await
+      .map_err(|_| ChannelError::Closed)?;
+    permit.forget();
+    let item = self.items.lock().unwrap().pop_front();
+    self.slots.add_permits(1);
+    // The queue is only emptied without taking permits by `close`, which
+    // also closed `ready`.
+    item.ok_or(ChannelError::Closed)
+  }
+
+  fn close(&self) {
+    self.slots.close();
+    self.ready.close();
+    self.items.lock().unwrap().clear();
+  }
+}
+
+static CHANNELS: LazyLock<Mutex<HashMap<u128, Arc<Channel>>>> =
+  LazyLock::new(Default::default);
+
+/// The channels created by a worker, closed when its `OpState` is dropped so
+/// that they don't outlive it in the registry.
+#[derive(Default)]
+struct OwnedChannels(HashSet<u128>);
+
+impl Drop for OwnedChannels {
+  fn drop(&mut self) {
+    let mut channels = CHANNELS.lock().unwrap();
+    for token in self.0.drain() {
+      if let Some(channel) = channels.remove(&token) {
+        channel.close();
+      }
+    }
+  }
+}
+
+fn create_channel(
+  owned: &mut OwnedChannels,
+  capacity: usize,
+) -> Result<u128, ChannelError> {
+  if capacity == 0 {
+    return Err(ChannelError::InvalidCapacity);
+  }
+  let channel = Arc::new(Channel::new(capacity));
+  let mut channels = CHANNELS.lock().unwrap();
+  let token = loop {
+    let token = rand::random::<u128>();
+    if !channels.contains_key(&token) {
+      break token;
+    }
+  };
+  channels.insert(token, channel);
+  owned.0.insert(token);
+  Ok(token)
+}
+
+fn close_channel(token: u128) {
+  let channel = CHANNELS.lock().unwrap().remove(&token);
+  if let Some(channel) = channel {
+    channel.close();
+  }
+}
+
+/// Tokens are passed to JS as 32 hex digits.
+fn parse_token(token: &str) -> Option<u128> {
+  if token.len() != 32 || !token.bytes().all(|b| b.is_ascii_hexdigit()) {
+    return None;
+  }
+  u128::from_str_radix(token, 16).ok()
+}
+
+fn get_channel(token: &str) -> Result<Arc<Channel>, ChannelError> {
+  parse_token(token)
+    .and_then(|token| CHANNELS.lock().unwrap().get(&token).cloned())
+    .ok_or(ChannelError::Closed)
+}
+
+async fn with_timeout<T>(
+  timeout_ms: Option<u64>,
+  fut: impl std::future::Future<Output = Result<T, ChannelError>>,
+) -> Result<T, ChannelError> {
+  match timeout_ms {
+    Some(ms) => tokio::time::timeout(Duration::from_millis(ms), fut)
+      .await
+      .map_err(|_| ChannelError::TimedOut)?,
+    None => fut.await,
+  }
+}
+
+#[op2]
+#[string]
+pub fn op_channel_create(
+  state: &mut OpState,
+  #[smi] capacity: u32,
+) -> Result<String, ChannelError> {
+  if !state.has::<OwnedChannels>() {
+    state.put(OwnedChannels::default());
+  }
+  let owned = state.borrow_mut::<OwnedChannels>();
+  let token = create_channel(owned, capacity as usize)?;
+  Ok(format!("{token:032x}"))
+}
+
+#[op2(async)]
+pub async fn op_channel_send(
+  #[string] token: String,
+  #[buffer] data: JsBuffer,
+  #[serde] timeout_ms: Option<u64>,
+) -> Result<(), ChannelError> {
+  let channel = get_channel(&token)?;
+  with_timeout(timeout_ms, channel.send(data.to_vec())).await
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_channel_recv(
+  #[string] token: String,
+  #[serde] timeout_ms: Option<u64>,
+) -> Result<ToJsBuffer, ChannelError> {
+  let channel = get_channel(&token)?;
+  let item = with_timeout(timeout_ms, channel.recv()).await?;
+  Ok(item.into())
+}
+
+#[op2(fast)]
+pub fn op_channel_close(state: &mut OpState, #[string] token: &str) {
+  let Some(token) = parse_token(token) else {
+    return;
+  };
+  if let Some(owned) = state.try_borrow_mut::<OwnedChannels>() {
+    owned.0.remove(&token);
+  }
+  close_channel(token);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn send_waits_for_space() {
+    let channel = Channel::new(1);
+    channel.send(vec![1]).await.unwrap();
+    let blocked =
+      tokio::time::timeout(Duration::from_millis(20), channel.send(vec![2]))
+        .await;
+    assert!(blocked.is_err());
+    assert_eq!(channel.recv().await.unwrap(), vec![1]);
+    channel.send(vec![3]).await.unwrap();
+    assert_eq!(channel.recv().await.unwrap(), vec![3]);
+    assert!(channel.items.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn receivers_are_woken_in_fifo_order() {
+    let channel = Arc::new(Channel::new(4));
+    let mut receivers = vec![];
+    for _ in 0..3 {
+      let channel = channel.clone();
+      receivers.push(tokio::spawn(async move { channel.recv().await }));
+      // Make sure each receiver is queued before the next one.
+      tokio::task::yield_now().await;
+    }
+    for i in 0..3u8 {
+      channel.send(vec![i]).await.unwrap();
+    }
+    for (i, receiver) in receivers.into_iter().enumerate() {
+      assert_eq!(receiver.await.unwrap().unwrap(), vec![i as u8]);
+    }
+  }
+
+  #[tokio::test]
+  async fn close_wakes_waiters() {
+    let mut owned = OwnedChannels::default();
+    let token = create_channel(&mut owned, 1).unwrap();
+    let channel = get_channel(&format!("{token:032x}")).unwrap();
+    let receiver = tokio::spawn({
+      let channel = channel.clone();
+      async move { channel.recv().await }
+    });
+    tokio::task::yield_now().await;
+    close_channel(token);
+    assert!(matches!(receiver.await.unwrap(), Err(ChannelError::Closed)));
+    assert!(matches!(
+      channel.send(vec![1]).await,
+      Err(ChannelError::Closed)
+    ));
+    assert!(matches!(
+      get_channel(&format!("{token:032x}")),
+      Err(ChannelError::Closed)
+    ));
+  }
+
+  #[tokio::test]
+  async fn dropping_the_owner_closes_its_channels() {
+    let mut owned = OwnedChannels::default();
+    let tokens = [
+      create_channel(&mut owned, 1).unwrap(),
+      create_channel(&mut owned, 1).unwrap(),
+    ];
+    assert_ne!(tokens[0], tokens[1]);
+    let channel = CHANNELS.lock().unwrap()[&tokens[0]].clone();
+    drop(owned);
+    for token in tokens {
+      assert!(!CHANNELS.lock().unwrap().contains_key(&token));
+    }
+    assert!(matches!(
+      channel.send(vec![1]).await,
+      Err(ChannelError::Closed)
+    ));
+  }
+
+  #[test]
+  fn tokens_must_be_32_hex_digits() {
+    assert_eq!(parse_token(&format!("{:032x}", 42)), Some(42));
+    assert_eq!(parse_token("2a"), None);
+    assert_eq!(parse_token(&format!("+{:031x}", 42)), None);
+    assert!(matches!(get_channel("1"), Err(ChannelError::Closed)));
+  }
+}
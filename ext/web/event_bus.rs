@@ -0,0 +1,286 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A single-threaded, typed publish/subscribe bus stored in the `OpState`.
+//!
+//! Extensions use it to react to each other's events without depending on
+//! each other's internals: the publisher only needs the event type, and any
+//! number of subscribers (including none) can receive it.
+//!
+//! Ordering: events of one type (topic) are delivered to every subscriber in
+//! the order they were published. There are no ordering guarantees between
+//! different topics.
+//!
+//! Each subscriber has a bounded queue. When a subscriber falls behind, the
+//! oldest queued event is dropped to make room for the new one and the
+//! subscriber's lost counter is incremented.
+
+use std::any::Any;
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::rc::Rc;
+use std::rc::Weak;
+use std::task::Poll;
+use std::task::Waker;
+
+/// Queue capacity used by `EventBus::subscribe`.
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 64;
+
+struct SubscriberQueue<T> {
+  events: VecDeque<T>,
+  capacity: usize,
+  lost: u64,
+  closed: bool,
+  waker: Option<Waker>,
+}
+
+struct Topic<T> {
+  subscribers: Vec<Weak<RefCell<SubscriberQueue<T>>>>,
+}
+
+impl<T> Topic<T> {
+  fn prune(&mut self) {
+    self.subscribers.retain(|s| s.strong_count() > 0);
+  }
+}
+
+impl<T> Drop for Topic<T> {
+  fn drop(&mut self) {
+    for subscriber in self.subscribers.drain(..) {
+      if let Some(queue) = subscriber.upgrade() {
+        let mut queue = queue.borrow_mut();
+        queue.closed = true;
+        if let Some(waker) = queue.waker.take() {
+          waker.wake();
+        }
+      }
+    }
+  }
+}
+
+/// Handle to the bus. Clones share the same topics, so a task can keep
+/// publishing after the op that spawned it returned.
+#[derive(Default, Clone)]
+pub struct EventBus {
+  topics: Rc<RefCell<HashMap<TypeId, Box<dyn Any>>>>,
+}
+
+impl std::fmt::Debug for EventBus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("EventBus")
+      .field("topics", &self.topics.borrow().len())
+      .finish()
+  }
+}
+
+impl EventBus {
+  /// Publish an event to every current subscriber of `T`.
+  pub fn publish<T: Clone + 'static>(&self, event: T) {
+    let mut topics = self.topics.borrow_mut();
+    let Some(topic) = topics
+      .get_mut(&TypeId::of::<T>())
+      .and_then(|topic| topic.downcast_mut::<Topic<T>>())
+    else {
+      return;
+    };
+    topic.prune();
+    for subscriber in &topic.subscribers {
+      let Some(queue) = subscriber.upgrade() else {
+        continue;
+      };
+      let mut queue = queue.borrow_mut();
+      if queue.events.len() >= queue.capacity {
+        queue.events.pop_front();
+        queue.lost += 1;
+      }
+      queue.events.push_back(event.clone());
+      if let Some(waker) = queue.waker.take() {
+        waker.wake();
+      }
+    }
+  }
+
+  /// Subscribe to events of type `T` with the default queue capacity.
+  pub fn subscribe<T: Clone + 'static>(&self) -> EventReceiver<T> {
+    self.subscribe_with_capacity(DEFAULT_SUBSCRIBER_CAPACITY)
+  }
+
+  /// Subscribe to events of type `T`, keeping at most `capacity` unreceived
+  /// events.
+  pub fn subscribe_with_capacity<T: Clone + 'static>(
+    &self,
+    capacity: usize,
+  ) -> EventReceiver<T> {
+    let queue = Rc::new(RefCell::new(SubscriberQueue {
+      events: VecDeque::new(),
+      capacity: capacity.max(1),
+      lost: 0,
+      closed: false,
+      waker: None,
+    }));
+    let mut topics = self.topics.borrow_mut();
+    let topic = topics
+      .entry(TypeId::of::<T>())
+      .or_insert_with(|| {
+        Box::new(Topic::<T> {
+          subscribers: Vec::new(),
+        })
+      })
+      .downcast_mut::<Topic<T>>()
+      .unwrap();
+    topic.prune();
+    topic.subscribers.push(Rc::downgrade(&queue));
+    EventReceiver { queue }
+  }
+
+  /// Number of live subscribers of `T`. Publishers can use it to skip
+  /// building events nobody listens to.
+  pub fn subscriber_count<T: 'static>(&self) -> usize {
+    let mut topics = self.topics.borrow_mut();
+    match topics
+      .get_mut(&TypeId::of::<T>())
+      .and_then(|topic| topic.downcast_mut::<Topic<T>>())
+    {
+      Some(topic) => {
+        topic.prune();
+        topic.subscribers.len()
+      }
+      None => 0,
+    }
+  }
+}
+
+/// Receiving half of a subscription. Dropping it unsubscribes.
+pub struct EventReceiver<T> {
+  queue: Rc<RefCell<SubscriberQueue<T>>>,
+}
+
+impl<T> EventReceiver<T> {
+  /// Take the next queued event without waiting.
+  pub fn try_recv(&self) -> Option<T> {
+    self.queue.borrow_mut().events.pop_front()
+  }
+
+  /// Wait for the next event. Returns `None` once every handle to the bus
+  /// was dropped and every queued event was received.
+  pub async fn recv(&self) -> Option<T> {
+    poll_fn(|cx| {
+      let mut queue = self.queue.borrow_mut();
+      if let Some(event) = queue.events.pop_front() {
+        return Poll::Ready(Some(event));
+      }
+      if queue.closed {
+        return Poll::Ready(None);
+      }
+      queue.waker = Some(cx.waker().clone());
+      Poll::Pending
+    })
+    .await
+  }
+
+  /// Number of events dropped for this subscriber because its queue was full.
+  pub fn lost(&self) -> u64 {
+    self.queue.borrow().lost
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Clone, Debug, PartialEq)]
+  struct Ping(u32);
+
+  #[derive(Clone, Debug, PartialEq)]
+  struct Pong(&'static str);
+
+  #[test]
+  fn multiple_subscribers_receive_in_publish_order() {
+    let bus = EventBus::default();
+    let a = bus.subscribe::<Ping>();
+    let b = bus.subscribe::<Ping>();
+    let pong = bus.subscribe::<Pong>();
+
+    bus.publish(Ping(1));
+    bus.publish(Pong("x"));
+    bus.publish(Ping(2));
+
+    for rx in [&a, &b] {
+      assert_eq!(rx.try_recv(), Some(Ping(1)));
+      assert_eq!(rx.try_recv(), Some(Ping(2)));
+      assert_eq!(rx.try_recv(), None);
+    }
+    assert_eq!(pong.try_recv(), Some(Pong("x")));
+    assert_eq!(pong.try_recv(), None);
+  }
+
+  #[test]
+  fn overflow_drops_oldest_and_counts_lost() {
+    let bus = EventBus::default();
+    let slow = bus.subscribe_with_capacity::<Ping>(2);
+    let fast = bus.subscribe_with_capacity::<Ping>(8);
+
+    for i in 0..5 {
+      bus.publish(Ping(i));
+    }
+
+    assert_eq!(slow.lost(), 3);
+    assert_eq!(slow.try_recv(), Some(Ping(3)));
+    assert_eq!(slow.try_recv(), Some(Ping(4)));
+    assert_eq!(slow.try_recv(), None);
+
+    assert_eq!(fast.lost(), 0);
+    for i in 0..5 {
+      assert_eq!(fast.try_recv(), Some(Ping(i)));
+    }
+  }
+
+  #[test]
+  fn dropping_receiver_unsubscribes() {
+    let bus = EventBus::default();
+    let a = bus.subscribe::<Ping>();
+    let b = bus.subscribe::<Ping>();
+    assert_eq!(bus.subscriber_count::<Ping>(), 2);
+    drop(a);
+    assert_eq!(bus.subscriber_count::<Ping>(), 1);
+    bus.publish(Ping(7));
+    assert_eq!(b.try_recv(), Some(Ping(7)));
+    drop(b);
+    assert_eq!(bus.subscriber_count::<Ping>(), 0);
+    // Publishing without subscribers is a no-op.
+    bus.publish(Ping(8));
+    assert_eq!(bus.subscriber_count::<Pong>(), 0);
+  }
+
+  #[test]
+  fn clones_share_topics() {
+    let bus = EventBus::default();
+    let publisher = bus.clone();
+    let rx = bus.subscribe::<Ping>();
+    assert_eq!(publisher.subscriber_count::<Ping>(), 1);
+    publisher.publish(Ping(1));
+    assert_eq!(rx.try_recv(), Some(Ping(1)));
+  }
+
+  #[tokio::test(flavor = "current_thread")]
+  async fn recv_waits_for_publish_and_ends_with_bus() {
+    let bus = Rc::new(EventBus::default());
+    let rx = bus.subscribe::<Ping>();
+
+    let publisher = bus.clone();
+    let task = deno_core::unsync::spawn(async move {
+      tokio::task::yield_now().await;
+      publisher.publish(Ping(1));
+    });
+
+    assert_eq!(rx.recv().await, Some(Ping(1)));
+    task.await.unwrap();
+
+    bus.publish(Ping(2));
+    drop(bus);
+    assert_eq!(rx.recv().await, Some(Ping(2)));
+    assert_eq!(rx.recv().await, None);
+  }
+}
@@ -44,6 +44,21 @@ declare module "ext:deno_web/00_infra.js" {
   function forgivingBase64UrlEncode(data: Uint8Array | string): string;
   function forgivingBase64UrlDecode(data: string): Uint8Array;
   function serializeJSValueToJSONString(value: unknown): string;
+  interface OpStream<T> extends AsyncIterableIterator<T>, Disposable {
+    readonly rid: number;
+    ref(): void;
+    unref(): void;
+  }
+  function opStream<T = unknown>(rid: number): OpStream<T>;
+  class OpBatch {
+    constructor(
+      opName: string,
+      onError: (error: Error, args: unknown[]) => void,
+    );
+    readonly size: number;
+    push(args: unknown[]): void;
+    flush(): unknown[];
+  }
 }
 
 declare module "ext:deno_web/01_dom_exception.js" {
@@ -1,8 +1,16 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+mod async_close;
 mod blob;
+mod byte_codec;
+mod channel;
 mod compression;
+mod event_bus;
+mod lifecycle;
 mod message_port;
+mod op_batch;
+mod op_error;
+mod op_stream;
 mod stream_resource;
 mod timers;
 
@@ -20,13 +28,22 @@ use encoding_rs::Encoding;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::Arc;
 
 pub use blob::BlobError;
+pub use byte_codec::ByteCodecError;
+pub use channel::ChannelError;
 pub use compression::CompressionError;
 pub use message_port::MessagePortError;
 pub use stream_resource::StreamResourceError;
 
+pub use crate::async_close::close_resources;
+pub use crate::async_close::register_async_close;
+pub use crate::async_close::register_state_close;
+pub use crate::async_close::AsyncClose;
+pub use crate::async_close::AsyncCloseFuture;
+pub use crate::async_close::AsyncCloseRegistry;
 use crate::blob::op_blob_create_object_url;
 use crate::blob::op_blob_create_part;
 use crate::blob::op_blob_from_object_url;
@@ -34,11 +51,22 @@ use crate::blob::op_blob_read_part;
 use crate::blob::op_blob_remove_part;
 use crate::blob::op_blob_revoke_object_url;
 use crate::blob::op_blob_slice_part;
+
 pub use crate::blob::Blob;
 pub use crate::blob::BlobPart;
 pub use crate::blob::BlobStore;
 pub use crate::blob::InMemoryBlobPart;
 
+pub use crate::event_bus::EventBus;
+pub use crate::event_bus::EventReceiver;
+pub use crate::event_bus::DEFAULT_SUBSCRIBER_CAPACITY;
+
+pub use crate::lifecycle::register_lifecycle_hook;
+pub use crate::lifecycle::LifecycleCallback;
+pub use crate::lifecycle::LifecycleEvent;
+pub use crate::lifecycle::LifecycleHook;
+pub use crate::lifecycle::LifecycleHooks;
+
 pub use crate::message_port::create_entangled_message_port;
 pub use crate::message_port::deserialize_js_transferables;
 use crate::message_port::op_message_port_create_entangled;
@@ -50,10 +78,30 @@ pub use crate::message_port::JsMessageData;
 pub use crate::message_port::MessagePort;
 pub use crate::message_port::Transferable;
 
+pub use crate::op_batch::register_batchable_op;
+pub use crate::op_batch::set_batch_error_class_fn;
+pub use crate::op_batch::BatchableOp;
+pub use crate::op_batch::BatchableOps;
+
+pub use crate::op_error::io_error_class;
+pub use crate::op_error::io_error_code;
+pub use crate::op_error::is_retryable_io_error;
+pub use crate::op_error::stage_op_error_details;
+pub use crate::op_error::OpError;
+pub use crate::op_error::OpErrorDetails;
+pub use crate::op_error::STRUCTURED_ERROR_CLASS;
+
+pub use crate::op_stream::add_op_stream;
+pub use crate::op_stream::OpStreamResource;
+
+pub use crate::timers::clock;
 use crate::timers::op_defer;
 use crate::timers::op_now;
+pub use crate::timers::Clock;
+pub use crate::timers::SharedClock;
 use crate::timers::StartTime;
 pub use crate::timers::TimersPermission;
+pub use crate::timers::TokioClock;
 
 deno_core::extension!(deno_web,
   deps = [ deno_webidl, deno_console, deno_url ],
@@ -80,9 +128,32 @@ deno_core::extension!(deno_web,
     op_message_port_post_message,
     op_message_port_recv_message,
     op_message_port_recv_message_sync,
+    byte_codec::op_bytesink_create,
+    byte_codec::op_bytesink_write_uint,
+    byte_codec::op_bytesink_write_u64,
+    byte_codec::op_bytesink_write_varint,
+    byte_codec::op_bytesink_write_bytes,
+    byte_codec::op_bytesink_length,
+    byte_codec::op_bytesink_take,
+    byte_codec::op_bytesource_create,
+    byte_codec::op_bytesource_read_uint,
+    byte_codec::op_bytesource_read_u64,
+    byte_codec::op_bytesource_read_varint,
+    byte_codec::op_bytesource_read_bytes,
+    byte_codec::op_bytesource_position,
+    byte_codec::op_bytesource_remaining,
+    channel::op_channel_create,
+    channel::op_channel_send,
+    channel::op_channel_recv,
+    channel::op_channel_close,
     compression::op_compression_new,
     compression::op_compression_write,
     compression::op_compression_finish,
+    lifecycle::op_lifecycle_hooks,
+    op_batch::op_dispatch_batch,
+    op_error::op_take_op_error_details,
+    op_stream::op_stream_next,
+    op_stream::op_stream_take,
     op_now<P>,
     op_defer,
     stream_resource::op_readable_stream_resource_allocate,
@@ -113,6 +184,9 @@ deno_core::extension!(deno_web,
     "14_compression.js",
     "15_performance.js",
     "16_image_data.js",
+    "17_channel.js",
+    "18_lifecycle.js",
+    "19_byte_codec.js",
   ],
   options = {
     blob_store: Arc<BlobStore>,
@@ -124,6 +198,8 @@ deno_core::extension!(deno_web,
       state.put(Location(location));
     }
     state.put(StartTime::now());
+    state.put::<SharedClock>(Rc::new(TokioClock));
+    state.put(EventBus::default());
   }
 );
 
@@ -0,0 +1,231 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A registry of JS callbacks that extensions want invoked at well-defined
+//! points of the runtime's lifecycle.
+//!
+//! Extensions register hooks from Rust, usually in their `state` initializer,
+//! with `register_lifecycle_hook`. The registry lives in the `OpState`; the
+//! runtime's JS side fetches the hooks for an event when that event fires and
+//! calls them in registration order, so hooks of an extension that is
+//! initialized earlier run before hooks of extensions initialized later.
+//!
+//! Hooks registered by user code through `Deno.addLifecycleHook()` are kept on
+//! the JS side and run after all extension hooks.
+
+use std::borrow::Cow;
+
+use deno_core::op2;
+use deno_core::OpState;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LifecycleEvent {
+  /// The runtime finished bootstrapping, after the snapshot was restored.
+  /// Called without arguments.
+  PostInit,
+  /// The main module is loaded and about to be evaluated. Called with
+  /// `{ mainModule }`.
+  PreMainModule,
+  /// A promise rejection was not handled by any "unhandledrejection" event
+  /// listener. Called with `{ promise, reason, handled, markHandled() }`;
+  /// a hook that calls `markHandled()` stops the runtime from exiting.
+  UnhandledRejection,
+  /// The event loop is done and the runtime is about to shut down, right
+  /// before the "unload" event. Called with `{ exitCode }`.
+  BeforeShutdown,
+  /// The worker was asked to terminate by its parent and should release its
+  /// resources, right before the "beforeterminate" event. Called with
+  /// `{ deadline }`, the milliseconds left before the worker is killed.
+  BeforeTerminate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum LifecycleCallback {
+  /// Source of a JS expression that evaluates to the callback function. It is
+  /// evaluated once, the first time the hook is called.
+  Source(Cow<'static, str>),
+  /// Name of a function on `globalThis`, looked up every time the hook is
+  /// called.
+  Global(Cow<'static, str>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LifecycleHook {
+  pub name: String,
+  pub callback: LifecycleCallback,
+}
+
+#[derive(Debug, Default)]
+pub struct LifecycleHooks {
+  hooks: Vec<(LifecycleEvent, LifecycleHook)>,
+}
+
+impl LifecycleHooks {
+  /// Adds a hook for `event`. Hook names are unique per event: registering a
+  /// name again replaces the callback but keeps the original position.
+  pub fn register(
+    &mut self,
+    event: LifecycleEvent,
+    name: impl Into<String>,
+    callback: LifecycleCallback,
+  ) {
+    let name = name.into();
+    let existing = self
+      .hooks
+      .iter_mut()
+      .find(|(e, hook)| *e == event && hook.name == name);
+    match existing {
+      Some((_, hook)) => hook.callback = callback,
+      None => self.hooks.push((event, LifecycleHook { name, callback })),
+    }
+  }
+
+  /// The hooks for `event`, in the order they will be called.
+  pub fn hooks(
+    &self,
+    event: LifecycleEvent,
+  ) -> impl Iterator<Item = &LifecycleHook> {
+    self
+      .hooks
+      .iter()
+      .filter(move |(e, _)| *e == event)
+      .map(|(_, hook)| hook)
+  }
+}
+
+/// Registers a JS callback to be called when `event` fires.
+///
+/// Can be called from any extension's `state` initializer; the registry is
+/// created on first use.
+pub fn register_lifecycle_hook(
+  state: &mut OpState,
+  event: LifecycleEvent,
+  name: impl Into<String>,
+  callback: LifecycleCallback,
+) {
+  if !state.has::<LifecycleHooks>() {
+    state.put(LifecycleHooks::default());
+  }
+  state
+    .borrow_mut::<LifecycleHooks>()
+    .register(event, name, callback);
+}
+
+#[op2]
+#[serde]
+pub fn op_lifecycle_hooks(
+  state: &mut OpState,
+  #[serde] event: LifecycleEvent,
+) -> Vec<LifecycleHook> {
+  match state.try_borrow::<LifecycleHooks>() {
+    Some(hooks) => hooks.hooks(event).cloned().collect(),
+    None => vec![],
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::JsRuntime;
+  use deno_core::RuntimeOptions;
+
+  fn names(state: &OpState, event: LifecycleEvent) -> Vec<String> {
+    state
+      .borrow::<LifecycleHooks>()
+      .hooks(event)
+      .map(|hook| hook.name.clone())
+      .collect()
+  }
+
+  #[test]
+  fn hooks_are_ordered_across_extensions() {
+    deno_core::extension!(
+      first_ext,
+      state = |state| {
+        register_lifecycle_hook(
+          state,
+          LifecycleEvent::PostInit,
+          "first:init",
+          LifecycleCallback::Global("firstInit".into()),
+        );
+        register_lifecycle_hook(
+          state,
+          LifecycleEvent::BeforeShutdown,
+          "first:shutdown",
+          LifecycleCallback::Source("() => {}".into()),
+        );
+      }
+    );
+    deno_core::extension!(
+      second_ext,
+      state = |state| {
+        register_lifecycle_hook(
+          state,
+          LifecycleEvent::BeforeShutdown,
+          "second:shutdown",
+          LifecycleCallback::Source("() => {}".into()),
+        );
+        register_lifecycle_hook(
+          state,
+          LifecycleEvent::PostInit,
+          "second:init",
+          LifecycleCallback::Global("secondInit".into()),
+        );
+      }
+    );
+
+    let runtime = JsRuntime::new(RuntimeOptions {
+      extensions: vec![first_ext::init_ops(), second_ext::init_ops()],
+      ..Default::default()
+    });
+    let state = runtime.op_state();
+    let state = state.borrow();
+    assert_eq!(
+      names(&state, LifecycleEvent::PostInit),
+      ["first:init", "second:init"]
+    );
+    assert_eq!(
+      names(&state, LifecycleEvent::BeforeShutdown),
+      ["first:shutdown", "second:shutdown"]
+    );
+    assert!(names(&state, LifecycleEvent::PreMainModule).is_empty());
+  }
+
+  #[test]
+  fn registering_a_name_again_keeps_its_position() {
+    let mut hooks = LifecycleHooks::default();
+    for name in ["a", "b"] {
+      hooks.register(
+        LifecycleEvent::UnhandledRejection,
+        name,
+        LifecycleCallback::Global(name.to_string().into()),
+      );
+    }
+    hooks.register(
+      LifecycleEvent::UnhandledRejection,
+      "a",
+      LifecycleCallback::Source("() => {}".into()),
+    );
+    // same name, different event
+    hooks.register(
+      LifecycleEvent::PostInit,
+      "a",
+      LifecycleCallback::Global("a".into()),
+    );
+
+    let rejection = hooks
+      .hooks(LifecycleEvent::UnhandledRejection)
+      .collect::<Vec<_>>();
+    assert_eq!(rejection.len(), 2);
+    assert_eq!(rejection[0].name, "a");
+    assert_eq!(
+      rejection[0].callback,
+      LifecycleCallback::Source("() => {}".into())
+    );
+    assert_eq!(rejection[1].name, "b");
+    assert_eq!(hooks.hooks(LifecycleEvent::PostInit).count(), 1);
+  }
+}
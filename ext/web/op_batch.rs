@@ -0,0 +1,398 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Batched dispatch of small sync ops.
+//!
+//! A tight JS loop making many small sync op calls spends most of its time
+//! crossing into Rust. Ops that opt in by implementing [`BatchableOp`] and
+//! being registered with [`register_batchable_op`] can instead be queued in
+//! JS with `OpBatch` from `00_infra.js`, which makes all queued calls with a
+//! single `op_dispatch_batch(opName, payloads)` call once the current task is
+//! done, or earlier when JS needs their effects.
+//!
+//! Each payload is the array of arguments of one call. The calls are made in
+//! order, and a failing call doesn't stop the following ones: the op returns
+//! the results and the errors of the calls positionally, and `OpBatch` puts
+//! the error objects at the positions of the failed calls. When ending the
+//! batch fails, e.g. because its work couldn't be committed, every call that
+//! succeeded fails with that error instead.
+
+use std::collections::HashMap;
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::serde_v8;
+use deno_core::v8;
+use deno_core::GetErrorClassFn;
+use deno_core::OpState;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::op_error::take_op_error_details;
+use crate::op_error::STRUCTURED_ERROR_CLASS;
+
+/// A sync op whose calls can be batched.
+///
+/// Implementing this trait asserts that the op has no reentrancy hazards:
+/// it doesn't call into JS, and its effects don't need to be visible to JS
+/// before the batch is dispatched. The arguments are deserialized while the
+/// `OpState` is borrowed, so they must be plain values; `OpBatch` callers
+/// only pass primitives and buffers.
+pub trait BatchableOp: 'static {
+  /// The name JS passes to `op_dispatch_batch`.
+  const NAME: &'static str;
+  /// The arguments of one call, deserialized from an array.
+  type Args: DeserializeOwned;
+  type Output: Serialize;
+  type Error: Into<AnyError>;
+
+  fn call(
+    state: &mut OpState,
+    args: Self::Args,
+  ) -> Result<Self::Output, Self::Error>;
+
+  /// Called before the first call of a batch. An error fails the whole
+  /// batch without making any call.
+  fn begin_batch(_state: &mut OpState) -> Result<(), Self::Error> {
+    Ok(())
+  }
+
+  /// Called after the last call of a batch, e.g. to commit the work of all
+  /// calls at once. An error fails every call of the batch that succeeded,
+  /// so it must leave no work of the batch behind.
+  fn end_batch(_state: &mut OpState) -> Result<(), Self::Error> {
+    Ok(())
+  }
+}
+
+struct BatchHandler {
+  begin: fn(&mut OpState) -> Result<(), AnyError>,
+  call: for<'s> fn(
+    &mut v8::HandleScope<'s>,
+    &mut OpState,
+    v8::Local<'s, v8::Value>,
+  ) -> Result<v8::Local<'s, v8::Value>, AnyError>,
+  end: fn(&mut OpState) -> Result<(), AnyError>,
+}
+
+fn begin_batch<T: BatchableOp>(state: &mut OpState) -> Result<(), AnyError> {
+  T::begin_batch(state).map_err(Into::into)
+}
+
+fn end_batch<T: BatchableOp>(state: &mut OpState) -> Result<(), AnyError> {
+  T::end_batch(state).map_err(Into::into)
+}
+
+fn call_batched<'s, T: BatchableOp>(
+  scope: &mut v8::HandleScope<'s>,
+  state: &mut OpState,
+  payload: v8::Local<'s, v8::Value>,
+) -> Result<v8::Local<'s, v8::Value>, AnyError> {
+  let args = serde_v8::from_v8::<T::Args>(scope, payload)
+    .map_err(|err| type_error(format!("Invalid arguments: {err}")))?;
+  let output = T::call(state, args).map_err(Into::into)?;
+  Ok(serde_v8::to_v8(scope, output)?)
+}
+
+/// The ops that can be dispatched with `op_dispatch_batch`.
+pub struct BatchableOps {
+  ops: HashMap<&'static str, BatchHandler>,
+  get_error_class_fn: GetErrorClassFn,
+}
+
+fn default_error_class(err: &AnyError) -> &'static str {
+  deno_core::error::get_custom_error_class(err).unwrap_or("Error")
+}
+
+impl Default for BatchableOps {
+  fn default() -> Self {
+    Self {
+      ops: HashMap::new(),
+      get_error_class_fn: &default_error_class,
+    }
+  }
+}
+
+impl BatchableOps {
+  /// The JS class and code of a failed call. Structured details staged by
+  /// the runtime's `get_error_class_fn` are taken right away, as the next
+  /// error of the batch would replace them.
+  fn error_class(&self, err: &AnyError) -> (&'static str, Option<String>) {
+    let class = (self.get_error_class_fn)(err);
+    if class != STRUCTURED_ERROR_CLASS {
+      return (class, None);
+    }
+    match take_op_error_details() {
+      Some(details) => (details.class, details.code),
+      None => ("Error", None),
+    }
+  }
+}
+
+fn registry(state: &mut OpState) -> &mut BatchableOps {
+  if !state.has::<BatchableOps>() {
+    state.put(BatchableOps::default());
+  }
+  state.borrow_mut::<BatchableOps>()
+}
+
+/// Allows calls of `T` to be batched, usually called in the `state`
+/// initializer of the extension declaring the op.
+pub fn register_batchable_op<T: BatchableOp>(state: &mut OpState) {
+  registry(state).ops.insert(
+    T::NAME,
+    BatchHandler {
+      begin: begin_batch::<T>,
+      call: call_batched::<T>,
+      end: end_batch::<T>,
+    },
+  );
+}
+
+/// Sets how errors of batched calls are classified. Runtimes pass the same
+/// function they give deno_core, so that a batched call fails with the
+/// same error as a direct one.
+pub fn set_batch_error_class_fn(
+  state: &mut OpState,
+  get_error_class_fn: GetErrorClassFn,
+) {
+  registry(state).get_error_class_fn = get_error_class_fn;
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchError {
+  index: u32,
+  class_name: &'static str,
+  message: String,
+  code: Option<String>,
+}
+
+fn batch_error(state: &OpState, index: u32, err: &AnyError) -> BatchError {
+  let (class_name, code) = match state.try_borrow::<BatchableOps>() {
+    Some(ops) => ops.error_class(err),
+    None => ("Error", None),
+  };
+  BatchError {
+    index,
+    class_name,
+    message: format!("{err:#}"),
+    code,
+  }
+}
+
+/// Calls the op registered as `op_name` once for every payload. Returns an
+/// array with the results of the calls, `undefined` where a call failed,
+/// and an array with the errors of the failed calls and their indexes.
+#[op2]
+pub fn op_dispatch_batch<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  state: &mut OpState,
+  #[string] op_name: &str,
+  payloads: v8::Local<'s, v8::Array>,
+) -> Result<v8::Local<'s, v8::Array>, AnyError> {
+  let Some((begin, call, end)) = state
+    .try_borrow::<BatchableOps>()
+    .and_then(|ops| ops.ops.get(op_name))
+    .map(|handler| (handler.begin, handler.call, handler.end))
+  else {
+    return Err(type_error(format!("Op \"{op_name}\" can't be batched")));
+  };
+
+  begin(state)?;
+  let len = payloads.length();
+  let mut results = Vec::with_capacity(len as usize);
+  let mut errors = vec![];
+  for index in 0..len {
+    let payload = payloads
+      .get_index(scope, index)
+      .unwrap_or_else(|| v8::undefined(scope).into());
+    match call(scope, state, payload) {
+      Ok(result) => results.push(result),
+      Err(err) => {
+        errors.push(batch_error(state, index, &err));
+        results.push(v8::undefined(scope).into());
+      }
+    }
+  }
+  if let Err(err) = end(state) {
+    let error = batch_error(state, 0, &err);
+    let mut failed = std::mem::take(&mut errors).into_iter().peekable();
+    for index in 0..len {
+      match failed.next_if(|failed| failed.index == index) {
+        Some(failed) => errors.push(failed),
+        None => {
+          errors.push(BatchError {
+            index,
+            class_name: error.class_name,
+            message: error.message.clone(),
+            code: error.code.clone(),
+          });
+          results[index as usize] = v8::undefined(scope).into();
+        }
+      }
+    }
+  }
+
+  let results = v8::Array::new_with_elements(scope, &results);
+  let errors = serde_v8::to_v8(scope, errors)?;
+  Ok(v8::Array::new_with_elements(
+    scope,
+    &[results.into(), errors],
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use deno_core::error::custom_error;
+  use deno_core::JsRuntime;
+  use deno_core::RuntimeOptions;
+
+  use super::*;
+
+  /// Appends a number to the `Vec<i32>` in the `OpState`, failing for
+  /// negative numbers.
+  struct PushOp;
+
+  impl BatchableOp for PushOp {
+    const NAME: &'static str = "push";
+    type Args = (i32,);
+    type Output = usize;
+    type Error = AnyError;
+
+    fn call(
+      state: &mut OpState,
+      (value,): Self::Args,
+    ) -> Result<Self::Output, Self::Error> {
+      if value < 0 {
+        return Err(custom_error("RangeError", format!("{value} < 0")));
+      }
+      let values = state.borrow_mut::<Vec<i32>>();
+      values.push(value);
+      Ok(values.len())
+    }
+
+    fn end_batch(state: &mut OpState) -> Result<(), Self::Error> {
+      state.borrow_mut::<Vec<i32>>().push(0);
+      Ok(())
+    }
+  }
+
+  /// Like [`PushOp`], but fails to end the batch after pushing 13.
+  struct CommitOp;
+
+  impl BatchableOp for CommitOp {
+    const NAME: &'static str = "commit";
+    type Args = (i32,);
+    type Output = usize;
+    type Error = AnyError;
+
+    fn call(
+      state: &mut OpState,
+      args: Self::Args,
+    ) -> Result<Self::Output, Self::Error> {
+      PushOp::call(state, args)
+    }
+
+    fn end_batch(state: &mut OpState) -> Result<(), Self::Error> {
+      let values = state.borrow_mut::<Vec<i32>>();
+      if values.contains(&13) {
+        values.clear();
+        return Err(custom_error("TypeError", "commit failed"));
+      }
+      Ok(())
+    }
+  }
+
+  fn runtime() -> JsRuntime {
+    deno_core::extension!(
+      batch_test,
+      ops = [op_dispatch_batch],
+      state = |state| {
+        state.put(Vec::<i32>::new());
+        register_batchable_op::<PushOp>(state);
+        register_batchable_op::<CommitOp>(state);
+      }
+    );
+    JsRuntime::new(RuntimeOptions {
+      extensions: vec![batch_test::init_ops()],
+      ..Default::default()
+    })
+  }
+
+  fn eval(runtime: &mut JsRuntime, source: &'static str) -> String {
+    let value = runtime.execute_script("batch_test.js", source).unwrap();
+    let scope = &mut runtime.handle_scope();
+    let value = v8::Local::new(scope, value);
+    value.to_rust_string_lossy(scope)
+  }
+
+  #[test]
+  fn errors_are_positional_and_dont_abort_the_batch() {
+    let mut runtime = runtime();
+    let result = eval(
+      &mut runtime,
+      r#"
+        const [results, errors] = Deno.core.ops.op_dispatch_batch(
+          "push",
+          [[1], [-2], [3], null, [5]],
+        );
+        JSON.stringify([
+          results,
+          errors.map((err) => [err.index, err.className]),
+          errors[0].message,
+        ]);
+      "#,
+    );
+    assert_eq!(
+      result,
+      r#"[[1,null,2,null,3],[[1,"RangeError"],[3,"TypeError"]],"-2 < 0"]"#
+    );
+    // the calls were made in order, then the batch was ended once
+    let state = runtime.op_state();
+    assert_eq!(*state.borrow().borrow::<Vec<i32>>(), [1, 3, 5, 0]);
+  }
+
+  #[test]
+  fn failing_to_end_the_batch_fails_the_successful_calls() {
+    let mut runtime = runtime();
+    let result = eval(
+      &mut runtime,
+      r#"
+        const [results, errors] = Deno.core.ops.op_dispatch_batch(
+          "commit",
+          [[1], [-2], [13]],
+        );
+        JSON.stringify([
+          results,
+          errors.map((err) => [err.index, err.className, err.message]),
+        ]);
+      "#,
+    );
+    assert_eq!(
+      result,
+      r#"[[null,null,null],[[0,"TypeError","commit failed"],[1,"RangeError","-2 < 0"],[2,"TypeError","commit failed"]]]"#
+    );
+    let state = runtime.op_state();
+    assert!(state.borrow().borrow::<Vec<i32>>().is_empty());
+  }
+
+  #[test]
+  fn unregistered_ops_cant_be_batched() {
+    let mut runtime = runtime();
+    let result = eval(
+      &mut runtime,
+      r#"
+        try {
+          Deno.core.ops.op_dispatch_batch("op_dispatch_batch", [[]]);
+          "no error";
+        } catch (err) {
+          err.message;
+        }
+      "#,
+    );
+    assert_eq!(result, r#"Op "op_dispatch_batch" can't be batched"#);
+    let state = runtime.op_state();
+    assert!(state.borrow().borrow::<Vec<i32>>().is_empty());
+  }
+}
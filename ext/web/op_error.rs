@@ -0,0 +1,313 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Structured details for errors thrown by ops.
+//!
+//! deno_core turns an op error into a JS exception using only a class name
+//! and a message. Errors that carry more than that, such as a stable error
+//! code, a retryable flag or the messages of the errors they wrap, are routed
+//! through the [`STRUCTURED_ERROR_CLASS`] builder instead: the runtime's
+//! `get_error_class_fn` stages the details with [`stage_op_error_details`]
+//! right before deno_core calls the builder, and the builder fetches them
+//! with `op_take_op_error_details` and attaches them to the error object.
+//! The message is the one deno_core formats, so it is the same as for any
+//! other error.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use deno_core::op2;
+use deno_core::serde_json;
+use serde::Serialize;
+
+/// Class name under which errors with structured details are thrown. The JS
+/// builder for it constructs an error of the actual class.
+pub const STRUCTURED_ERROR_CLASS: &str = "DenoStructuredError";
+
+type BoxedError = Box<dyn Error + Send + Sync + 'static>;
+
+/// An op error with an explicit JS class and optional structured details.
+///
+/// ```ignore
+/// OpError::new("ConnectionRefused", "Failed to connect")
+///   .code("ECONNREFUSED")
+///   .retryable(true)
+///   .cause(err)
+/// ```
+///
+/// An `io::Error` converts into an `OpError` with its class, code and
+/// retryable flag derived from the error kind.
+#[derive(Debug)]
+pub struct OpError {
+  class: &'static str,
+  message: Cow<'static, str>,
+  code: Option<Cow<'static, str>>,
+  retryable: bool,
+  cause: Option<BoxedError>,
+}
+
+impl OpError {
+  pub fn new(
+    class: &'static str,
+    message: impl Into<Cow<'static, str>>,
+  ) -> Self {
+    Self {
+      class,
+      message: message.into(),
+      code: None,
+      retryable: false,
+      cause: None,
+    }
+  }
+
+  /// Sets a stable, machine readable code, exposed as `error.code`.
+  pub fn code(mut self, code: impl Into<Cow<'static, str>>) -> Self {
+    self.code = Some(code.into());
+    self
+  }
+
+  /// Marks whether retrying the operation may succeed, exposed as
+  /// `error.retryable`.
+  pub fn retryable(mut self, retryable: bool) -> Self {
+    self.retryable = retryable;
+    self
+  }
+
+  /// Sets the error this one wraps. The messages of the whole source chain
+  /// are exposed as `error.cause`.
+  pub fn cause(mut self, cause: impl Into<BoxedError>) -> Self {
+    self.cause = Some(cause.into());
+    self
+  }
+
+  /// Wraps this error in a new one with `message`, keeping its class, code
+  /// and retryable flag.
+  pub fn context(self, message: impl Into<Cow<'static, str>>) -> Self {
+    Self {
+      class: self.class,
+      message: message.into(),
+      code: self.code.clone(),
+      retryable: self.retryable,
+      cause: Some(Box::new(self)),
+    }
+  }
+
+  pub fn class(&self) -> &'static str {
+    self.class
+  }
+
+  pub fn details(&self, class: &'static str) -> OpErrorDetails {
+    let mut causes = vec![];
+    let mut source = self.source();
+    while let Some(err) = source {
+      causes.push(err.to_string());
+      source = err.source();
+    }
+    OpErrorDetails {
+      class,
+      code: self.code.as_deref().map(str::to_string),
+      retryable: self.retryable,
+      causes,
+      properties: Default::default(),
+    }
+  }
+}
+
+impl fmt::Display for OpError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl Error for OpError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    self.cause.as_deref().map(|e| e as &(dyn Error + 'static))
+  }
+}
+
+impl From<io::Error> for OpError {
+  fn from(err: io::Error) -> Self {
+    let kind = err.kind();
+    Self {
+      class: io_error_class(kind),
+      message: err.to_string().into(),
+      code: io_error_code(kind).map(Cow::Borrowed),
+      retryable: is_retryable_io_error(kind),
+      cause: None,
+    }
+  }
+}
+
+/// The JS class of errors of `kind`.
+pub fn io_error_class(kind: io::ErrorKind) -> &'static str {
+  use io::ErrorKind::*;
+  match kind {
+    NotFound => "NotFound",
+    PermissionDenied => "PermissionDenied",
+    ConnectionRefused => "ConnectionRefused",
+    ConnectionReset => "ConnectionReset",
+    ConnectionAborted => "ConnectionAborted",
+    NotConnected => "NotConnected",
+    AddrInUse => "AddrInUse",
+    AddrNotAvailable => "AddrNotAvailable",
+    BrokenPipe => "BrokenPipe",
+    AlreadyExists => "AlreadyExists",
+    InvalidInput => "TypeError",
+    InvalidData => "InvalidData",
+    TimedOut => "TimedOut",
+    Interrupted => "Interrupted",
+    WriteZero => "WriteZero",
+    UnexpectedEof => "UnexpectedEof",
+    Other => "Error",
+    WouldBlock => "WouldBlock",
+    // Non-exhaustive enum - might add new variants
+    // in the future
+    kind => {
+      let kind_str = kind.to_string();
+      match kind_str.as_str() {
+        "FilesystemLoop" => "FilesystemLoop",
+        "IsADirectory" => "IsADirectory",
+        "NetworkUnreachable" => "NetworkUnreachable",
+        "NotADirectory" => "NotADirectory",
+        _ => "Error",
+      }
+    }
+  }
+}
+
+/// The POSIX style code of errors of `kind`, the same on every platform.
+pub fn io_error_code(kind: io::ErrorKind) -> Option<&'static str> {
+  use io::ErrorKind::*;
+  let code = match kind {
+    NotFound => "ENOENT",
+    PermissionDenied => "EACCES",
+    ConnectionRefused => "ECONNREFUSED",
+    ConnectionReset => "ECONNRESET",
+    ConnectionAborted => "ECONNABORTED",
+    NotConnected => "ENOTCONN",
+    AddrInUse => "EADDRINUSE",
+    AddrNotAvailable => "EADDRNOTAVAIL",
+    BrokenPipe => "EPIPE",
+    AlreadyExists => "EEXIST",
+    InvalidInput => "EINVAL",
+    TimedOut => "ETIMEDOUT",
+    Interrupted => "EINTR",
+    WouldBlock => "EAGAIN",
+    kind => match kind.to_string().as_str() {
+      "FilesystemLoop" => "ELOOP",
+      "IsADirectory" => "EISDIR",
+      "NetworkUnreachable" => "ENETUNREACH",
+      "NotADirectory" => "ENOTDIR",
+      _ => return None,
+    },
+  };
+  Some(code)
+}
+
+/// Whether an operation that failed with `kind` may succeed when retried.
+pub fn is_retryable_io_error(kind: io::ErrorKind) -> bool {
+  use io::ErrorKind::*;
+  matches!(
+    kind,
+    ConnectionRefused
+      | ConnectionReset
+      | ConnectionAborted
+      | TimedOut
+      | Interrupted
+      | WouldBlock
+  )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OpErrorDetails {
+  pub class: &'static str,
+  pub code: Option<String>,
+  pub retryable: bool,
+  /// Messages of the wrapped errors, outermost first.
+  pub causes: Vec<String>,
+  /// Additional properties set on the error object.
+  pub properties: serde_json::Map<String, serde_json::Value>,
+}
+
+thread_local! {
+  // Each isolate runs on its own thread, and deno_core calls the error
+  // builder right after asking for the class, so a single slot is enough.
+  static STAGED_DETAILS: RefCell<Option<OpErrorDetails>> =
+    const { RefCell::new(None) };
+}
+
+/// Stages `details` for the next error thrown with the
+/// [`STRUCTURED_ERROR_CLASS`]. Only the most recently staged details are
+/// kept.
+pub fn stage_op_error_details(details: OpErrorDetails) {
+  STAGED_DETAILS.with(|staged| {
+    *staged.borrow_mut() = Some(details);
+  });
+}
+
+pub(crate) fn take_op_error_details() -> Option<OpErrorDetails> {
+  STAGED_DETAILS.with(|staged| staged.borrow_mut().take())
+}
+
+#[op2]
+#[serde]
+pub fn op_take_op_error_details() -> Option<OpErrorDetails> {
+  take_op_error_details()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn io_errors_are_mapped() {
+    let err = OpError::from(io::Error::from(io::ErrorKind::ConnectionRefused));
+    assert_eq!(err.class(), "ConnectionRefused");
+    let details = err.details(err.class());
+    assert_eq!(details.code.as_deref(), Some("ECONNREFUSED"));
+    assert!(details.retryable);
+    assert!(details.causes.is_empty());
+
+    let err = OpError::from(io::Error::from(io::ErrorKind::NotFound));
+    let details = err.details(err.class());
+    assert_eq!(details.code.as_deref(), Some("ENOENT"));
+    assert!(!details.retryable);
+  }
+
+  #[test]
+  fn context_keeps_details_and_records_causes() {
+    let inner = OpError::new("Busy", "device busy")
+      .code("EBUSY")
+      .retryable(true)
+      .cause(io::Error::other("locked by pid 42"));
+    let err = inner.context("Failed to open '/dev/x'");
+    assert_eq!(err.to_string(), "Failed to open '/dev/x'");
+    assert_eq!(
+      err.details("Busy"),
+      OpErrorDetails {
+        class: "Busy",
+        code: Some("EBUSY".to_string()),
+        retryable: true,
+        causes: vec!["device busy".to_string(), "locked by pid 42".to_string()],
+        properties: Default::default(),
+      }
+    );
+  }
+
+  #[test]
+  fn staged_details_are_taken_once() {
+    let details = |class| OpErrorDetails {
+      class,
+      code: None,
+      retryable: false,
+      causes: vec![],
+      properties: Default::default(),
+    };
+    stage_op_error_details(details("TypeError"));
+    stage_op_error_details(details("NotFound"));
+    assert_eq!(take_op_error_details(), Some(details("NotFound")));
+    assert_eq!(take_op_error_details(), None);
+  }
+}
@@ -0,0 +1,252 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Ops whose results are delivered to JS as an async iterator.
+//!
+//! A deno_core op resolves a single promise. An op that produces a sequence
+//! of items instead builds a [`Stream`] and hands it to [`add_op_stream`],
+//! which stores it as a resource and returns the rid to JS. The JS side wraps
+//! the rid with `opStream()` from `00_infra.js`, which implements the async
+//! iterator protocol on top of two shared ops:
+//!
+//! - `op_stream_next` polls the stream for one item and resolves to whether
+//!   one is ready. The stream is only polled while such a call is pending,
+//!   so a slow consumer holds the producer back.
+//! - `op_stream_take` serializes the ready item.
+//!
+//! The end of the stream and an error both end the iteration, the error by
+//! rejecting the pending `next()`. Calling `return()` on the iterator, e.g.
+//! by breaking out of a `for await` loop, closes the resource, which cancels
+//! a pending poll and drops the stream.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use deno_core::error::AnyError;
+use deno_core::futures::stream;
+use deno_core::futures::stream::LocalBoxStream;
+use deno_core::futures::Stream;
+use deno_core::futures::StreamExt;
+use deno_core::futures::TryStreamExt;
+use deno_core::op2;
+use deno_core::serde_v8;
+use deno_core::v8;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use serde::Serialize;
+
+/// An item of an op stream, serialized when JS takes it.
+trait OpStreamItem {
+  fn to_v8<'s>(
+    self: Box<Self>,
+    scope: &mut v8::HandleScope<'s>,
+  ) -> Result<v8::Local<'s, v8::Value>, serde_v8::Error>;
+}
+
+impl<T: Serialize> OpStreamItem for T {
+  fn to_v8<'s>(
+    self: Box<Self>,
+    scope: &mut v8::HandleScope<'s>,
+  ) -> Result<v8::Local<'s, v8::Value>, serde_v8::Error> {
+    serde_v8::to_v8(scope, *self)
+  }
+}
+
+type ItemStream =
+  LocalBoxStream<'static, Result<Box<dyn OpStreamItem>, AnyError>>;
+
+pub struct OpStreamResource {
+  stream: AsyncRefCell<ItemStream>,
+  /// The item returned by the last poll, until JS takes it.
+  ready: RefCell<Option<Box<dyn OpStreamItem>>>,
+  cancel: CancelHandle,
+}
+
+impl Resource for OpStreamResource {
+  fn name(&self) -> Cow<str> {
+    "opStream".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl OpStreamResource {
+  fn new<S, T, E>(stream: S) -> Self
+  where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<AnyError> + 'static,
+  {
+    let stream = stream
+      .map_ok(|item| Box::new(item) as Box<dyn OpStreamItem>)
+      .map_err(Into::into)
+      .boxed_local();
+    Self {
+      stream: AsyncRefCell::new(stream),
+      ready: RefCell::new(None),
+      cancel: CancelHandle::new(),
+    }
+  }
+
+  /// Polls the stream for the next item. Returns `false` once the stream
+  /// ended or the resource was closed.
+  async fn next(self: Rc<Self>) -> Result<bool, AnyError> {
+    let mut items = RcRef::map(&self, |r| &r.stream).borrow_mut().await;
+    let cancel = RcRef::map(&self, |r| &r.cancel);
+    let Ok(item) = items.next().or_cancel(cancel).await else {
+      return Ok(false);
+    };
+    match item {
+      Some(Ok(item)) => {
+        *self.ready.borrow_mut() = Some(item);
+        Ok(true)
+      }
+      Some(Err(error)) => {
+        // An error ends the stream.
+        *items = stream::empty().boxed_local();
+        Err(error)
+      }
+      None => Ok(false),
+    }
+  }
+
+  fn take(&self) -> Option<Box<dyn OpStreamItem>> {
+    self.ready.borrow_mut().take()
+  }
+}
+
+/// Stores `stream` as a resource whose items JS can iterate with
+/// `opStream(rid)`.
+pub fn add_op_stream<S, T, E>(state: &mut OpState, stream: S) -> ResourceId
+where
+  S: Stream<Item = Result<T, E>> + 'static,
+  T: Serialize + 'static,
+  E: Into<AnyError> + 'static,
+{
+  state.resource_table.add(OpStreamResource::new(stream))
+}
+
+#[op2(async)]
+pub async fn op_stream_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<bool, AnyError> {
+  let resource = state.borrow().resource_table.get::<OpStreamResource>(rid)?;
+  resource.next().await
+}
+
+#[op2]
+pub fn op_stream_take<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  state: &OpState,
+  #[smi] rid: ResourceId,
+) -> Result<v8::Local<'s, v8::Value>, AnyError> {
+  let resource = state.resource_table.get::<OpStreamResource>(rid)?;
+  match resource.take() {
+    Some(item) => Ok(item.to_v8(scope)?),
+    None => Ok(v8::undefined(scope).into()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::Cell;
+  use std::time::Duration;
+
+  #[derive(Debug, thiserror::Error)]
+  #[error("broken")]
+  struct Broken;
+
+  /// A stream of 0, 1, 2, ... that counts the items it produced.
+  fn counting(
+    produced: Rc<Cell<u32>>,
+  ) -> impl Stream<Item = Result<u32, Broken>> {
+    stream::unfold(0, move |n| {
+      let produced = produced.clone();
+      async move {
+        produced.set(produced.get() + 1);
+        Some((Ok(n), n + 1))
+      }
+    })
+  }
+
+  #[tokio::test]
+  async fn slow_consumer_delays_production() {
+    let produced = Rc::new(Cell::new(0));
+    let resource = Rc::new(OpStreamResource::new(counting(produced.clone())));
+
+    assert!(resource.clone().next().await.unwrap());
+    assert!(resource.take().is_some());
+    assert_eq!(produced.get(), 1);
+
+    // Nothing is produced while nobody asks for the next item.
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(produced.get(), 1);
+    assert!(resource.take().is_none());
+
+    assert!(resource.clone().next().await.unwrap());
+    assert!(resource.clone().next().await.unwrap());
+    assert_eq!(produced.get(), 3);
+  }
+
+  #[tokio::test]
+  async fn close_cancels_and_drops_the_stream() {
+    struct DropFlag(Rc<Cell<bool>>);
+
+    impl Drop for DropFlag {
+      fn drop(&mut self) {
+        self.0.set(true);
+      }
+    }
+
+    let dropped = Rc::new(Cell::new(false));
+    let flag = DropFlag(dropped.clone());
+    let items = stream::once(async { Ok::<_, Broken>(1) })
+      .chain(stream::pending())
+      .map(move |item| {
+        let _flag = &flag;
+        item
+      });
+
+    let mut state = OpState::new(None);
+    let rid = add_op_stream(&mut state, items);
+    let resource = state.resource_table.get::<OpStreamResource>(rid).unwrap();
+    assert!(resource.clone().next().await.unwrap());
+
+    // The stream never produces another item, so the poll only ends when the
+    // resource is closed.
+    let pending = tokio::task::LocalSet::new();
+    let next = pending.spawn_local(resource.clone().next());
+    pending
+      .run_until(tokio::time::sleep(Duration::from_millis(20)))
+      .await;
+    assert!(!next.is_finished());
+
+    state.resource_table.close(rid).unwrap();
+    assert!(!pending.run_until(next).await.unwrap().unwrap());
+    assert!(!dropped.get());
+    drop(resource);
+    assert!(dropped.get());
+  }
+
+  #[tokio::test]
+  async fn error_ends_the_stream() {
+    let items = stream::iter([Ok(1), Err(Broken), Ok(2)]);
+    let resource = Rc::new(OpStreamResource::new(items));
+
+    assert!(resource.clone().next().await.unwrap());
+    assert!(resource.take().is_some());
+    let error = resource.clone().next().await.unwrap_err();
+    assert!(error.downcast_ref::<Broken>().is_some());
+    assert!(resource.take().is_none());
+    assert!(!resource.clone().next().await.unwrap());
+  }
+}
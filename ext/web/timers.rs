@@ -2,9 +2,14 @@
 
 //! This module helps deno implement timers and performance APIs.
 
+use deno_core::futures::future::LocalBoxFuture;
+use deno_core::futures::FutureExt;
 use deno_core::op2;
 use deno_core::OpState;
+use std::rc::Rc;
+use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
 
 pub trait TimersPermission {
   fn allow_hrtime(&mut self) -> bool;
@@ -19,6 +24,40 @@ impl TimersPermission for deno_permissions::PermissionsContainer {
 
 pub type StartTime = Instant;
 
+/// Source of delays for Rust-side timers, such as retry backoff. Extensions
+/// sleep through the clock in the `OpState` instead of calling tokio
+/// directly, so tests can substitute a virtual clock.
+pub trait Clock {
+  fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()>;
+
+  /// Wall-clock time, for timers that fire at a date rather than after a
+  /// delay.
+  fn now(&self) -> SystemTime {
+    SystemTime::now()
+  }
+}
+
+/// The default clock. It sleeps on the tokio timer, so it also follows
+/// tokio's paused test time.
+#[derive(Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+  fn sleep(&self, duration: Duration) -> LocalBoxFuture<'static, ()> {
+    tokio::time::sleep(duration).boxed_local()
+  }
+}
+
+pub type SharedClock = Rc<dyn Clock>;
+
+/// The clock stored in the `OpState`, or the default one.
+pub fn clock(state: &OpState) -> SharedClock {
+  state
+    .try_borrow::<SharedClock>()
+    .cloned()
+    .unwrap_or_else(|| Rc::new(TokioClock))
+}
+
 // Returns a milliseconds and nanoseconds subsec
 // since the start time of the deno runtime.
 // If the High precision flag is not set, the
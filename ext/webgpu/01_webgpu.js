@@ -28,9 +28,11 @@ import {
   op_webgpu_command_encoder_push_debug_group,
   op_webgpu_command_encoder_resolve_query_set,
   op_webgpu_command_encoder_write_timestamp,
+  op_webgpu_compute_pass_begin_pipeline_statistics_query,
   op_webgpu_compute_pass_dispatch_workgroups,
   op_webgpu_compute_pass_dispatch_workgroups_indirect,
   op_webgpu_compute_pass_end,
+  op_webgpu_compute_pass_end_pipeline_statistics_query,
   op_webgpu_compute_pass_insert_debug_marker,
   op_webgpu_compute_pass_pop_debug_group,
   op_webgpu_compute_pass_push_debug_group,
@@ -50,6 +52,7 @@ import {
   op_webgpu_create_shader_module,
   op_webgpu_create_texture,
   op_webgpu_create_texture_view,
+  op_webgpu_queue_get_timestamp_period,
   op_webgpu_queue_submit,
   op_webgpu_render_bundle_encoder_draw,
   op_webgpu_render_bundle_encoder_draw_indexed,
@@ -63,12 +66,14 @@ import {
   op_webgpu_render_bundle_encoder_set_pipeline,
   op_webgpu_render_bundle_encoder_set_vertex_buffer,
   op_webgpu_render_pass_begin_occlusion_query,
+  op_webgpu_render_pass_begin_pipeline_statistics_query,
   op_webgpu_render_pass_draw,
   op_webgpu_render_pass_draw_indexed,
   op_webgpu_render_pass_draw_indexed_indirect,
   op_webgpu_render_pass_draw_indirect,
   op_webgpu_render_pass_end,
   op_webgpu_render_pass_end_occlusion_query,
+  op_webgpu_render_pass_end_pipeline_statistics_query,
   op_webgpu_render_pass_execute_bundles,
   op_webgpu_render_pass_insert_debug_marker,
   op_webgpu_render_pass_pop_debug_group,
@@ -161,8 +166,6 @@ const _mipLevelCount = Symbol("[[mipLevelCount]]");
 const _sampleCount = Symbol("[[sampleCount]]");
 const _dimension = Symbol("[[dimension]]");
 const _format = Symbol("[[format]]");
-const _type = Symbol("[[type]]");
-const _count = Symbol("[[count]]");
 
 /**
  * @param {any} self
@@ -1951,6 +1954,19 @@ class GPUQueue {
     return PromiseResolve();
   }
 
+  /**
+   * Non-standard: the number of nanoseconds per tick of the values that
+   * timestamp queries resolve to.
+   * @returns {number}
+   */
+  getTimestampPeriod() {
+    webidl.assertBranded(this, GPUQueuePrototype);
+    const prefix = "Failed to execute 'getTimestampPeriod' on 'GPUQueue'";
+    assertDevice(this, prefix, "this");
+    const queueRid = assertResource(this, prefix, "this");
+    return op_webgpu_queue_get_timestamp_period(queueRid);
+  }
+
   /**
    * @param {GPUBuffer} buffer
    * @param {number} bufferOffset
@@ -3983,6 +3999,47 @@ class GPURenderPassEncoder {
     op_webgpu_render_pass_end_occlusion_query(renderPassRid);
   }
 
+  /**
+   * @param {GPUQuerySet} querySet
+   * @param {number} queryIndex
+   */
+  beginPipelineStatisticsQuery(querySet, queryIndex) {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'beginPipelineStatisticsQuery' on 'GPURenderPassEncoder'";
+    webidl.requiredArguments(arguments.length, 2, prefix);
+    querySet = webidl.converters.GPUQuerySet(querySet, prefix, "Argument 1");
+    queryIndex = webidl.converters.GPUSize32(queryIndex, prefix, "Argument 2");
+    const device = assertDevice(
+      this[_encoder],
+      prefix,
+      "encoder referenced by this",
+    );
+    assertResource(this[_encoder], prefix, "encoder referenced by this");
+    const renderPassRid = assertResource(this, prefix, "this");
+    const querySetRid = assertResource(querySet, prefix, "Argument 1");
+    assertDeviceMatch(device, querySet, {
+      prefix,
+      resourceContext: "Argument 1",
+      selfContext: "this",
+    });
+    op_webgpu_render_pass_begin_pipeline_statistics_query(
+      renderPassRid,
+      querySetRid,
+      queryIndex,
+    );
+  }
+
+  endPipelineStatisticsQuery() {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'endPipelineStatisticsQuery' on 'GPURenderPassEncoder'";
+    assertDevice(this[_encoder], prefix, "encoder referenced by this");
+    assertResource(this[_encoder], prefix, "encoder referenced by this");
+    const renderPassRid = assertResource(this, prefix, "this");
+    op_webgpu_render_pass_end_pipeline_statistics_query(renderPassRid);
+  }
+
   /**
    * @param {GPURenderBundle[]} bundles
    */
@@ -4572,6 +4629,47 @@ class GPUComputePassEncoder {
     );
   }
 
+  /**
+   * @param {GPUQuerySet} querySet
+   * @param {number} queryIndex
+   */
+  beginPipelineStatisticsQuery(querySet, queryIndex) {
+    webidl.assertBranded(this, GPUComputePassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'beginPipelineStatisticsQuery' on 'GPUComputePassEncoder'";
+    webidl.requiredArguments(arguments.length, 2, prefix);
+    querySet = webidl.converters.GPUQuerySet(querySet, prefix, "Argument 1");
+    queryIndex = webidl.converters.GPUSize32(queryIndex, prefix, "Argument 2");
+    const device = assertDevice(
+      this[_encoder],
+      prefix,
+      "encoder referenced by this",
+    );
+    assertResource(this[_encoder], prefix, "encoder referenced by this");
+    const computePassRid = assertResource(this, prefix, "this");
+    const querySetRid = assertResource(querySet, prefix, "Argument 1");
+    assertDeviceMatch(device, querySet, {
+      prefix,
+      resourceContext: "Argument 1",
+      selfContext: "this",
+    });
+    op_webgpu_compute_pass_begin_pipeline_statistics_query(
+      computePassRid,
+      querySetRid,
+      queryIndex,
+    );
+  }
+
+  endPipelineStatisticsQuery() {
+    webidl.assertBranded(this, GPUComputePassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'endPipelineStatisticsQuery' on 'GPUComputePassEncoder'";
+    assertDevice(this[_encoder], prefix, "encoder referenced by this");
+    assertResource(this[_encoder], prefix, "encoder referenced by this");
+    const computePassRid = assertResource(this, prefix, "this");
+    op_webgpu_compute_pass_end_pipeline_statistics_query(computePassRid);
+  }
+
   end() {
     webidl.assertBranded(this, GPUComputePassEncoderPrototype);
     const prefix = "Failed to execute 'end' on 'GPUComputePassEncoder'";
@@ -5215,10 +5313,6 @@ class GPUQuerySet {
   [_rid];
   /** @type {GPUQuerySetDescriptor} */
   [_descriptor];
-  /** @type {GPUQueryType} */
-  [_type];
-  /** @type {number} */
-  [_count];
 
   [_cleanup]() {
     const rid = this[_rid];
@@ -5240,12 +5334,12 @@ class GPUQuerySet {
 
   get type() {
     webidl.assertBranded(this, GPUQuerySetPrototype);
-    return this[_type]();
+    return this[_descriptor].type;
   }
 
   get count() {
     webidl.assertBranded(this, GPUQuerySetPrototype);
-    return this[_count]();
+    return this[_descriptor].count;
   }
 
   [SymbolFor("Deno.privateCustomInspect")](inspect, inspectOptions) {
@@ -5354,7 +5448,7 @@ webidl.converters["GPUFeatureName"] = webidl.createEnumConverter(
     "texture-compression-astc-hdr",
     "texture-adapter-specific-format-features",
     // api
-    //"pipeline-statistics-query",
+    "pipeline-statistics-query",
     "timestamp-query-inside-passes",
     "mappable-primary-buffers",
     "texture-binding-array",
@@ -7294,6 +7388,19 @@ webidl.converters["GPUQueryType"] = webidl.createEnumConverter(
   [
     "occlusion",
     "timestamp",
+    "pipeline-statistics",
+  ],
+);
+
+// ENUM: GPUPipelineStatisticName
+webidl.converters["GPUPipelineStatisticName"] = webidl.createEnumConverter(
+  "GPUPipelineStatisticName",
+  [
+    "vertex-shader-invocations",
+    "clipper-invocations",
+    "clipper-primitives-out",
+    "fragment-shader-invocations",
+    "compute-shader-invocations",
   ],
 );
 
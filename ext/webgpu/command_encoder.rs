@@ -11,6 +11,7 @@ use std::borrow::Cow;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use super::error::WebGpuError;
 use super::error::WebGpuResult;
 
 pub(crate) struct WebGpuCommandEncoder(
@@ -329,6 +330,23 @@ pub struct GpuImageCopyBuffer {
   rows_per_image: Option<u32>,
 }
 
+impl GpuImageCopyBuffer {
+  /// A misaligned `bytesPerRow` is by far the most common mistake when
+  /// reading back a texture, so it is reported with the field name and the
+  /// value that would have been valid.
+  fn bytes_per_row_error(&self, field: &str) -> Option<WebGpuError> {
+    let bytes_per_row = self.bytes_per_row?;
+    let align = wgpu_types::COPY_BYTES_PER_ROW_ALIGNMENT;
+    if bytes_per_row % align == 0 {
+      return None;
+    }
+    Some(WebGpuError::Validation(format!(
+      "{field}.bytesPerRow ({bytes_per_row}) must be a multiple of {align}, use {} instead",
+      super::texture::align_bytes_per_row(bytes_per_row),
+    )))
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GpuImageCopyTexture {
@@ -360,6 +378,7 @@ pub fn op_webgpu_command_encoder_copy_buffer_to_texture(
     state
       .resource_table
       .get::<super::texture::WebGpuTexture>(destination.texture)?;
+  let layout_err = source.bytes_per_row_error("source");
 
   let source = wgpu_core::command::ImageCopyBuffer {
     buffer: source_buffer_resource.1,
@@ -375,12 +394,17 @@ pub fn op_webgpu_command_encoder_copy_buffer_to_texture(
     origin: destination.origin,
     aspect: destination.aspect,
   };
-  gfx_ok!(command_encoder => instance.command_encoder_copy_buffer_to_texture(
+  let maybe_err = gfx_select!(command_encoder => instance.command_encoder_copy_buffer_to_texture(
     command_encoder,
     &source,
     &destination,
     &copy_size
-  ))
+  )).err();
+
+  Ok(WebGpuResult {
+    rid: None,
+    err: layout_err.or(maybe_err.map(Into::into)),
+  })
 }
 
 #[op2]
@@ -405,6 +429,7 @@ pub fn op_webgpu_command_encoder_copy_texture_to_buffer(
     state
       .resource_table
       .get::<super::buffer::WebGpuBuffer>(destination.buffer)?;
+  let layout_err = destination.bytes_per_row_error("destination");
 
   let source = wgpu_core::command::ImageCopyTexture {
     texture: source_texture_resource.id,
@@ -420,12 +445,17 @@ pub fn op_webgpu_command_encoder_copy_texture_to_buffer(
       rows_per_image: destination.rows_per_image,
     },
   };
-  gfx_ok!(command_encoder => instance.command_encoder_copy_texture_to_buffer(
+  let maybe_err = gfx_select!(command_encoder => instance.command_encoder_copy_texture_to_buffer(
     command_encoder,
     &source,
     &destination,
     &copy_size
-  ))
+  )).err();
+
+  Ok(WebGpuResult {
+    rid: None,
+    err: layout_err.or(maybe_err.map(Into::into)),
+  })
 }
 
 #[op2]
@@ -208,3 +208,44 @@ pub fn op_webgpu_compute_pass_insert_debug_marker(
 
   Ok(WebGpuResult::empty())
 }
+
+#[op2]
+#[serde]
+pub fn op_webgpu_compute_pass_begin_pipeline_statistics_query(
+  state: &mut OpState,
+  #[smi] compute_pass_rid: ResourceId,
+  #[smi] query_set: ResourceId,
+  query_index: u32,
+) -> Result<WebGpuResult, AnyError> {
+  let compute_pass_resource = state
+    .resource_table
+    .get::<WebGpuComputePass>(compute_pass_rid)?;
+  let query_set_resource = state
+    .resource_table
+    .get::<super::WebGpuQuerySet>(query_set)?;
+
+  wgpu_core::command::compute_commands::wgpu_compute_pass_begin_pipeline_statistics_query(
+    &mut compute_pass_resource.0.borrow_mut(),
+    query_set_resource.1,
+    query_index,
+  );
+
+  Ok(WebGpuResult::empty())
+}
+
+#[op2]
+#[serde]
+pub fn op_webgpu_compute_pass_end_pipeline_statistics_query(
+  state: &mut OpState,
+  #[smi] compute_pass_rid: ResourceId,
+) -> Result<WebGpuResult, AnyError> {
+  let compute_pass_resource = state
+    .resource_table
+    .get::<WebGpuComputePass>(compute_pass_rid)?;
+
+  wgpu_core::command::compute_commands::wgpu_compute_pass_end_pipeline_statistics_query(
+    &mut compute_pass_resource.0.borrow_mut(),
+  );
+
+  Ok(WebGpuResult::empty())
+}
@@ -93,6 +93,13 @@ pub enum InitError {
   RequestDevice(wgpu_core::instance::RequestDeviceError),
   #[error(transparent)]
   InvalidDevice(wgpu_core::device::InvalidDevice),
+  #[error(
+    "Query sets of type '{query_type}' require the '{feature}' feature to be enabled on the device"
+  )]
+  MissingFeature {
+    query_type: &'static str,
+    feature: &'static str,
+  },
 }
 
 pub type Instance = std::sync::Arc<wgpu_core::global::Global>;
@@ -183,6 +190,8 @@ deno_core::extension!(
     render_pass::op_webgpu_render_pass_set_stencil_reference,
     render_pass::op_webgpu_render_pass_begin_occlusion_query,
     render_pass::op_webgpu_render_pass_end_occlusion_query,
+    render_pass::op_webgpu_render_pass_begin_pipeline_statistics_query,
+    render_pass::op_webgpu_render_pass_end_pipeline_statistics_query,
     render_pass::op_webgpu_render_pass_execute_bundles,
     render_pass::op_webgpu_render_pass_end,
     render_pass::op_webgpu_render_pass_set_bind_group,
@@ -204,6 +213,8 @@ deno_core::extension!(
     compute_pass::op_webgpu_compute_pass_push_debug_group,
     compute_pass::op_webgpu_compute_pass_pop_debug_group,
     compute_pass::op_webgpu_compute_pass_insert_debug_marker,
+    compute_pass::op_webgpu_compute_pass_begin_pipeline_statistics_query,
+    compute_pass::op_webgpu_compute_pass_end_pipeline_statistics_query,
     // bundle
     bundle::op_webgpu_create_render_bundle_encoder,
     bundle::op_webgpu_render_bundle_encoder_finish,
@@ -221,6 +232,7 @@ deno_core::extension!(
     queue::op_webgpu_queue_submit,
     queue::op_webgpu_write_buffer,
     queue::op_webgpu_write_texture,
+    queue::op_webgpu_queue_get_timestamp_period,
     // shader
     shader::op_webgpu_create_shader_module,
     // surface
@@ -764,6 +776,8 @@ pub struct CreateQuerySetArgs {
   #[serde(flatten)]
   r#type: GpuQueryType,
   count: u32,
+  #[serde(default)]
+  pipeline_statistics: Vec<GpuPipelineStatisticName>,
 }
 
 #[derive(Deserialize)]
@@ -771,13 +785,62 @@ pub struct CreateQuerySetArgs {
 enum GpuQueryType {
   Occlusion,
   Timestamp,
+  PipelineStatistics,
 }
 
-impl From<GpuQueryType> for wgpu_types::QueryType {
-  fn from(query_type: GpuQueryType) -> Self {
-    match query_type {
-      GpuQueryType::Occlusion => wgpu_types::QueryType::Occlusion,
-      GpuQueryType::Timestamp => wgpu_types::QueryType::Timestamp,
+impl GpuQueryType {
+  fn name(&self) -> &'static str {
+    match self {
+      GpuQueryType::Occlusion => "occlusion",
+      GpuQueryType::Timestamp => "timestamp",
+      GpuQueryType::PipelineStatistics => "pipeline-statistics",
+    }
+  }
+
+  /// The device feature, and its WebGPU name, that query sets of this type
+  /// require.
+  fn required_feature(&self) -> Option<(wgpu_types::Features, &'static str)> {
+    match self {
+      GpuQueryType::Occlusion => None,
+      GpuQueryType::Timestamp => {
+        Some((wgpu_types::Features::TIMESTAMP_QUERY, "timestamp-query"))
+      }
+      GpuQueryType::PipelineStatistics => Some((
+        wgpu_types::Features::PIPELINE_STATISTICS_QUERY,
+        "pipeline-statistics-query",
+      )),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum GpuPipelineStatisticName {
+  VertexShaderInvocations,
+  ClipperInvocations,
+  ClipperPrimitivesOut,
+  FragmentShaderInvocations,
+  ComputeShaderInvocations,
+}
+
+impl From<GpuPipelineStatisticName> for wgpu_types::PipelineStatisticsTypes {
+  fn from(name: GpuPipelineStatisticName) -> Self {
+    match name {
+      GpuPipelineStatisticName::VertexShaderInvocations => {
+        wgpu_types::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS
+      }
+      GpuPipelineStatisticName::ClipperInvocations => {
+        wgpu_types::PipelineStatisticsTypes::CLIPPER_INVOCATIONS
+      }
+      GpuPipelineStatisticName::ClipperPrimitivesOut => {
+        wgpu_types::PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT
+      }
+      GpuPipelineStatisticName::FragmentShaderInvocations => {
+        wgpu_types::PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS
+      }
+      GpuPipelineStatisticName::ComputeShaderInvocations => {
+        wgpu_types::PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS
+      }
     }
   }
 }
@@ -795,9 +858,34 @@ pub fn op_webgpu_create_query_set(
   let device = device_resource.1;
   let instance = state.borrow::<Instance>();
 
+  if let Some((feature, feature_name)) = args.r#type.required_feature() {
+    let features = gfx_select!(device => instance.device_features(device))
+      .map_err(InitError::InvalidDevice)?;
+    if !features.contains(feature) {
+      return Err(InitError::MissingFeature {
+        query_type: args.r#type.name(),
+        feature: feature_name,
+      });
+    }
+  }
+
+  let ty = match args.r#type {
+    GpuQueryType::Occlusion => wgpu_types::QueryType::Occlusion,
+    GpuQueryType::Timestamp => wgpu_types::QueryType::Timestamp,
+    GpuQueryType::PipelineStatistics => {
+      // results are written in the order of the flags, not of the names
+      wgpu_types::QueryType::PipelineStatistics(
+        args
+          .pipeline_statistics
+          .into_iter()
+          .map(wgpu_types::PipelineStatisticsTypes::from)
+          .collect(),
+      )
+    }
+  };
   let descriptor = wgpu_types::QuerySetDescriptor {
     label: Some(Cow::Owned(args.label)),
-    ty: args.r#type.into(),
+    ty,
     count: args.count,
   };
 
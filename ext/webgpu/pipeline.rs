@@ -15,6 +15,7 @@ use super::error::WebGpuError;
 use super::error::WebGpuResult;
 
 const MAX_BIND_GROUPS: usize = 8;
+const MAX_COLOR_ATTACHMENTS: usize = 8;
 
 pub(crate) struct WebGpuPipelineLayout(
   pub(crate) crate::Instance,
@@ -329,6 +330,102 @@ pub struct CreateRenderPipelineArgs {
   fragment: Option<GpuFragmentState>,
 }
 
+fn validate_blend_component(
+  component: &wgpu_types::BlendComponent,
+  field: &str,
+) -> Result<(), String> {
+  use wgpu_types::BlendFactor;
+  use wgpu_types::BlendOperation;
+
+  if matches!(
+    component.operation,
+    BlendOperation::Min | BlendOperation::Max
+  ) {
+    if component.src_factor != BlendFactor::One {
+      return Err(format!(
+        "{field}.srcFactor must be \"one\" when operation is \"min\" or \"max\""
+      ));
+    }
+    if component.dst_factor != BlendFactor::One {
+      return Err(format!(
+        "{field}.dstFactor must be \"one\" when operation is \"min\" or \"max\""
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Checks the parts of a render pipeline descriptor that can be validated
+/// without the device, so that the error names the offending field instead of
+/// only describing the failure in terms of the underlying pipeline state.
+fn validate_render_pipeline_descriptor(
+  args: &CreateRenderPipelineArgs,
+) -> Result<(), String> {
+  for (i, layout) in args.vertex.buffers.iter().enumerate() {
+    let Some(layout) = layout else {
+      continue;
+    };
+    let field = format!("vertex.buffers[{i}]");
+    if layout.array_stride % 4 != 0 {
+      return Err(format!(
+        "{field}.arrayStride ({}) must be a multiple of 4",
+        layout.array_stride
+      ));
+    }
+    for (j, attribute) in layout.attributes.iter().enumerate() {
+      let size = attribute.format.size();
+      if attribute.offset % size.min(4) != 0 {
+        return Err(format!(
+          "{field}.attributes[{j}].offset ({}) must be a multiple of {}",
+          attribute.offset,
+          size.min(4)
+        ));
+      }
+      if layout.array_stride != 0
+        && attribute.offset + size > layout.array_stride
+      {
+        return Err(format!(
+          "{field}.attributes[{j}] ({:?} at offset {}) does not fit in arrayStride ({})",
+          attribute.format, attribute.offset, layout.array_stride
+        ));
+      }
+    }
+  }
+
+  if let Some(fragment) = &args.fragment {
+    if fragment.targets.len() > MAX_COLOR_ATTACHMENTS {
+      return Err(format!(
+        "fragment.targets has {} entries, but at most {MAX_COLOR_ATTACHMENTS} are supported",
+        fragment.targets.len()
+      ));
+    }
+    for (i, target) in fragment.targets.iter().enumerate() {
+      let Some(target) = target else {
+        continue;
+      };
+      let field = format!("fragment.targets[{i}]");
+      if target.format.is_depth_stencil_format() {
+        return Err(format!(
+          "{field}.format ({:?}) is not a color format",
+          target.format
+        ));
+      }
+      if let Some(blend) = &target.blend {
+        validate_blend_component(
+          &blend.color,
+          &format!("{field}.blend.color"),
+        )?;
+        validate_blend_component(
+          &blend.alpha,
+          &format!("{field}.blend.alpha"),
+        )?;
+      }
+    }
+  }
+
+  Ok(())
+}
+
 #[op2]
 #[serde]
 pub fn op_webgpu_create_render_pipeline(
@@ -340,6 +437,7 @@ pub fn op_webgpu_create_render_pipeline(
     .resource_table
     .get::<super::WebGpuDevice>(args.device_rid)?;
   let device = device_resource.1;
+  let descriptor_err = validate_render_pipeline_descriptor(&args).err();
 
   let layout = match args.layout {
     GPUPipelineLayoutOrGPUAutoLayoutMode::Layout(rid) => {
@@ -424,7 +522,12 @@ pub fn op_webgpu_create_render_pipeline(
     .resource_table
     .add(WebGpuRenderPipeline(instance.clone(), render_pipeline));
 
-  Ok(WebGpuResult::rid_err(rid, maybe_err))
+  Ok(WebGpuResult {
+    rid: Some(rid),
+    err: descriptor_err
+      .map(WebGpuError::Validation)
+      .or(maybe_err.map(Into::into)),
+  })
 }
 
 #[op2]
@@ -457,3 +560,127 @@ pub fn op_webgpu_render_pipeline_get_bind_group_layout(
     err: maybe_err.map(WebGpuError::from),
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::serde_json;
+  use deno_core::serde_json::json;
+
+  fn render_pipeline_args(
+    buffers: serde_json::Value,
+    targets: serde_json::Value,
+  ) -> CreateRenderPipelineArgs {
+    serde_json::from_value(json!({
+      "deviceRid": 0,
+      "label": "",
+      "layout": "auto",
+      "vertex": {
+        "module": 0,
+        "entryPoint": "vs_main",
+        "buffers": buffers,
+      },
+      "primitive": {
+        "topology": "triangle-list",
+        "stripIndexFormat": null,
+        "frontFace": "ccw",
+        "cullMode": "none",
+        "unclippedDepth": false,
+      },
+      "depthStencil": null,
+      "multisample": {
+        "count": 1,
+        "mask": 0xFFFFFFFFu32,
+        "alphaToCoverageEnabled": false,
+      },
+      "fragment": {
+        "module": 0,
+        "entryPoint": "fs_main",
+        "targets": targets,
+      },
+    }))
+    .unwrap()
+  }
+
+  fn color_target(
+    format: &str,
+    operation: &str,
+    src_factor: &str,
+  ) -> serde_json::Value {
+    let component = json!({
+      "srcFactor": src_factor,
+      "dstFactor": "one",
+      "operation": operation,
+    });
+    json!({
+      "format": format,
+      "blend": { "color": component, "alpha": component },
+      "writeMask": 0xF,
+    })
+  }
+
+  fn validate(
+    buffers: serde_json::Value,
+    targets: serde_json::Value,
+  ) -> Result<(), String> {
+    validate_render_pipeline_descriptor(&render_pipeline_args(buffers, targets))
+  }
+
+  #[test]
+  fn test_validate_render_pipeline_descriptor() {
+    let vertex_buffer = |stride: u64, offset: u64| {
+      json!([{
+        "arrayStride": stride,
+        "stepMode": "vertex",
+        "attributes": [{
+          "format": "float32x2",
+          "offset": offset,
+          "shaderLocation": 0,
+        }],
+      }])
+    };
+
+    assert_eq!(
+      validate(
+        vertex_buffer(8, 0),
+        json!([color_target("rgba8unorm", "add", "src-alpha")])
+      ),
+      Ok(())
+    );
+    assert_eq!(validate(json!([null]), json!([null])), Ok(()));
+
+    assert_eq!(
+      validate(vertex_buffer(10, 0), json!([])).unwrap_err(),
+      "vertex.buffers[0].arrayStride (10) must be a multiple of 4"
+    );
+    assert_eq!(
+      validate(vertex_buffer(8, 2), json!([])).unwrap_err(),
+      "vertex.buffers[0].attributes[0].offset (2) must be a multiple of 4"
+    );
+    assert_eq!(
+      validate(vertex_buffer(8, 4), json!([])).unwrap_err(),
+      "vertex.buffers[0].attributes[0] (Float32x2 at offset 4) does not fit in arrayStride (8)"
+    );
+
+    assert_eq!(
+      validate(
+        json!([]),
+        json!([null, color_target("rgba8unorm", "max", "src-alpha")])
+      )
+      .unwrap_err(),
+      "fragment.targets[1].blend.color.srcFactor must be \"one\" when operation is \"min\" or \"max\""
+    );
+    assert_eq!(
+      validate(
+        json!([]),
+        json!([color_target("depth24plus", "add", "one")])
+      )
+      .unwrap_err(),
+      "fragment.targets[0].format (Depth24Plus) is not a color format"
+    );
+    assert_eq!(
+      validate(json!([]), json!(vec![serde_json::Value::Null; 9])).unwrap_err(),
+      "fragment.targets has 9 entries, but at most 8 are supported"
+    );
+  }
+}
@@ -141,3 +141,17 @@ pub fn op_webgpu_write_texture(
     &size
   ))
 }
+
+/// Returns the number of nanoseconds per tick of the timestamps written to
+/// query sets by this queue's device.
+#[op2(fast)]
+pub fn op_webgpu_queue_get_timestamp_period(
+  state: &mut OpState,
+  #[smi] queue_rid: ResourceId,
+) -> Result<f32, AnyError> {
+  let instance = state.borrow::<Instance>();
+  let queue_resource = state.resource_table.get::<WebGpuQueue>(queue_rid)?;
+  let queue = queue_resource.1;
+
+  Ok(gfx_select!(queue => instance.queue_get_timestamp_period(queue))?)
+}
@@ -161,6 +161,47 @@ pub fn op_webgpu_render_pass_end_occlusion_query(
   Ok(WebGpuResult::empty())
 }
 
+#[op2]
+#[serde]
+pub fn op_webgpu_render_pass_begin_pipeline_statistics_query(
+  state: &mut OpState,
+  #[smi] render_pass_rid: ResourceId,
+  #[smi] query_set: ResourceId,
+  query_index: u32,
+) -> Result<WebGpuResult, deno_core::error::AnyError> {
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+  let query_set_resource = state
+    .resource_table
+    .get::<super::WebGpuQuerySet>(query_set)?;
+
+  wgpu_core::command::render_commands::wgpu_render_pass_begin_pipeline_statistics_query(
+    &mut render_pass_resource.0.borrow_mut(),
+    query_set_resource.1,
+    query_index,
+  );
+
+  Ok(WebGpuResult::empty())
+}
+
+#[op2]
+#[serde]
+pub fn op_webgpu_render_pass_end_pipeline_statistics_query(
+  state: &mut OpState,
+  #[smi] render_pass_rid: ResourceId,
+) -> Result<WebGpuResult, deno_core::error::AnyError> {
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+
+  wgpu_core::command::render_commands::wgpu_render_pass_end_pipeline_statistics_query(
+    &mut render_pass_resource.0.borrow_mut(),
+  );
+
+  Ok(WebGpuResult::empty())
+}
+
 #[op2]
 #[serde]
 pub fn op_webgpu_render_pass_execute_bundles(
@@ -130,3 +130,41 @@ pub fn op_webgpu_create_texture_view(
     None
   ) => state, WebGpuTextureView)
 }
+
+/// Rounds `bytes_per_row` up to the alignment required for the `bytesPerRow`
+/// of a texture-to-buffer or buffer-to-texture copy.
+pub fn align_bytes_per_row(bytes_per_row: u32) -> u32 {
+  let align = wgpu_types::COPY_BYTES_PER_ROW_ALIGNMENT;
+  bytes_per_row.div_ceil(align) * align
+}
+
+/// The `bytesPerRow` to use when copying a row of `width` texels of a format
+/// with `bytes_per_texel` bytes per texel into a buffer, e.g. for reading back
+/// a render target. The buffer needs `padded_bytes_per_row * height` bytes,
+/// and only the first `width * bytes_per_texel` bytes of each row are pixels.
+pub fn padded_bytes_per_row(width: u32, bytes_per_texel: u32) -> u32 {
+  align_bytes_per_row(width * bytes_per_texel)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_padded_bytes_per_row() {
+    assert_eq!(padded_bytes_per_row(0, 4), 0);
+    assert_eq!(padded_bytes_per_row(1, 4), 256);
+    assert_eq!(padded_bytes_per_row(64, 4), 256);
+    assert_eq!(padded_bytes_per_row(65, 4), 512);
+    assert_eq!(padded_bytes_per_row(200, 4), 1024);
+    assert_eq!(padded_bytes_per_row(256, 1), 256);
+    assert_eq!(padded_bytes_per_row(100, 16), 1792);
+  }
+
+  #[test]
+  fn test_align_bytes_per_row() {
+    assert_eq!(align_bytes_per_row(256), 256);
+    assert_eq!(align_bytes_per_row(257), 512);
+    assert_eq!(align_bytes_per_row(800), 1024);
+  }
+}
@@ -581,6 +581,33 @@ impl ServerWebSocket {
     ws.write_frame(frame).await?;
     Ok(())
   }
+
+  /// Writes a text or binary message, split into continuation frames of at
+  /// most `MAX_FRAME_PAYLOAD` bytes. The write lock is held until the last
+  /// fragment is written, so other messages can't be interleaved with it.
+  async fn write_message(
+    self: &Rc<Self>,
+    lock: AsyncMutFuture<WebSocketWrite<WriteHalf<WebSocketStream>>>,
+    opcode: OpCode,
+    payload: Vec<u8>,
+  ) -> Result<(), WebsocketError> {
+    if payload.len() <= MAX_FRAME_PAYLOAD {
+      return self
+        .write_frame(lock, Frame::new(true, opcode, None, payload.into()))
+        .await;
+    }
+    let mut ws = lock.await;
+    let count = payload.len().div_ceil(MAX_FRAME_PAYLOAD);
+    for (i, chunk) in payload.chunks(MAX_FRAME_PAYLOAD).enumerate() {
+      if ws.is_closed() {
+        return Ok(());
+      }
+      let opcode = if i == 0 { opcode } else { OpCode::Continuation };
+      ws.write_frame(Frame::new(i + 1 == count, opcode, None, chunk.into()))
+        .await?;
+    }
+    Ok(())
+  }
 }
 
 impl Resource for ServerWebSocket {
@@ -615,10 +642,7 @@ fn send_binary(state: &mut OpState, rid: ResourceId, data: &[u8]) {
   resource.buffered.set(resource.buffered.get() + len);
   let lock = resource.reserve_lock();
   deno_core::unsync::spawn(async move {
-    if let Err(err) = resource
-      .write_frame(lock, Frame::new(true, OpCode::Binary, None, data.into()))
-      .await
-    {
+    if let Err(err) = resource.write_message(lock, OpCode::Binary, data).await {
       resource.set_error(Some(err.to_string()));
     } else {
       resource.buffered.set(resource.buffered.get() - len);
@@ -656,10 +680,7 @@ pub fn op_ws_send_text(
   let lock = resource.reserve_lock();
   deno_core::unsync::spawn(async move {
     if let Err(err) = resource
-      .write_frame(
-        lock,
-        Frame::new(true, OpCode::Text, None, data.into_bytes().into()),
-      )
+      .write_message(lock, OpCode::Text, data.into_bytes())
       .await
     {
       resource.set_error(Some(err.to_string()));
@@ -683,9 +704,7 @@ pub async fn op_ws_send_binary_async(
     .map_err(WebsocketError::Resource)?;
   let data = data.to_vec();
   let lock = resource.reserve_lock();
-  resource
-    .write_frame(lock, Frame::new(true, OpCode::Binary, None, data.into()))
-    .await
+  resource.write_message(lock, OpCode::Binary, data).await
 }
 
 /// Async version of send. Does not update buffered amount as we rely on the socket itself for backpressure.
@@ -702,15 +721,16 @@ pub async fn op_ws_send_text_async(
     .map_err(WebsocketError::Resource)?;
   let lock = resource.reserve_lock();
   resource
-    .write_frame(
-      lock,
-      Frame::new(true, OpCode::Text, None, data.into_bytes().into()),
-    )
+    .write_message(lock, OpCode::Text, data.into_bytes())
     .await
 }
 
 const EMPTY_PAYLOAD: &[u8] = &[];
 
+/// Messages larger than this are sent as fragments, since many servers limit
+/// the size of a single frame well below the size of a message.
+const MAX_FRAME_PAYLOAD: usize = 1024 * 1024;
+
 #[op2(fast)]
 #[smi]
 pub fn op_ws_get_buffered_amount(
@@ -2,15 +2,16 @@
 
 /// <reference path="../../core/internal.d.ts" />
 
-import { primordials } from "ext:core/mod.js";
+import { core, primordials } from "ext:core/mod.js";
 import {
   op_webstorage_clear,
   op_webstorage_get,
   op_webstorage_iterate_keys,
   op_webstorage_key,
   op_webstorage_length,
-  op_webstorage_remove,
+  op_webstorage_remaining,
   op_webstorage_set,
+  op_webstorage_subscribe,
 } from "ext:core/ops";
 const {
   Symbol,
@@ -25,8 +26,33 @@ const {
 } = primordials;
 
 import * as webidl from "ext:deno_webidl/00_webidl.js";
+import { OpBatch } from "ext:deno_web/00_infra.js";
+import {
+  Event,
+  listenerCount,
+  reportError,
+  setIsTrusted,
+} from "ext:deno_web/02_event.js";
 
 const _persistent = Symbol("[[persistent]]");
+const _setBatch = Symbol("[[setBatch]]");
+const _removeBatch = Symbol("[[removeBatch]]");
+const _budget = Symbol("[[budget]]");
+
+// setItem() and removeItem() are batched, so that a burst of writes in one
+// task crosses into Rust once. At most one of the two batches holds writes,
+// which keeps them in order. Everything else makes the queued writes first,
+// so that it sees them.
+//
+// setItem() must throw when the quota is exceeded, so only writes that can't
+// fail on it are batched: sessionStorage writes that fit in the bytes left
+// at the last flush, as no other context writes to it. Other contexts can
+// fill localStorage at any time, so its writes are made right away.
+function flushWrites(storage) {
+  storage[_setBatch].flush();
+  storage[_removeBatch].flush();
+  storage[_budget] = null;
+}
 
 class Storage {
   [_persistent];
@@ -37,6 +63,7 @@ class Storage {
 
   get length() {
     webidl.assertBranded(this, StoragePrototype);
+    flushWrites(this);
     return op_webstorage_length(this[_persistent]);
   }
 
@@ -46,6 +73,7 @@ class Storage {
     webidl.requiredArguments(arguments.length, 1, prefix);
     index = webidl.converters["unsigned long"](index, prefix, "Argument 1");
 
+    flushWrites(this);
     return op_webstorage_key(index, this[_persistent]);
   }
 
@@ -56,7 +84,18 @@ class Storage {
     key = webidl.converters.DOMString(key, prefix, "Argument 1");
     value = webidl.converters.DOMString(value, prefix, "Argument 2");
 
-    op_webstorage_set(key, value, this[_persistent]);
+    const persistent = this[_persistent];
+    this[_removeBatch].flush();
+    // a UTF-16 code unit takes at most 3 bytes in UTF-8
+    const size = (key.length + value.length) * 3;
+    this[_budget] ??= persistent ? 0 : op_webstorage_remaining(persistent);
+    if (size > this[_budget]) {
+      flushWrites(this);
+      op_webstorage_set(key, value, persistent);
+      return;
+    }
+    this[_budget] -= size;
+    this[_setBatch].push([key, value, persistent]);
   }
 
   getItem(key) {
@@ -65,6 +104,7 @@ class Storage {
     webidl.requiredArguments(arguments.length, 1, prefix);
     key = webidl.converters.DOMString(key, prefix, "Argument 1");
 
+    flushWrites(this);
     return op_webstorage_get(key, this[_persistent]);
   }
 
@@ -74,11 +114,13 @@ class Storage {
     webidl.requiredArguments(arguments.length, 1, prefix);
     key = webidl.converters.DOMString(key, prefix, "Argument 1");
 
-    op_webstorage_remove(key, this[_persistent]);
+    this[_setBatch].flush();
+    this[_removeBatch].push([key, this[_persistent]]);
   }
 
   clear() {
     webidl.assertBranded(this, StoragePrototype);
+    flushWrites(this);
     op_webstorage_clear(this[_persistent]);
   }
 }
@@ -88,6 +130,9 @@ const StoragePrototype = Storage.prototype;
 function createStorage(persistent) {
   const storage = webidl.createBranded(Storage);
   storage[_persistent] = persistent;
+  storage[_setBatch] = new OpBatch("op_webstorage_set", reportError);
+  storage[_removeBatch] = new OpBatch("op_webstorage_remove", reportError);
+  storage[_budget] = null;
 
   const proxy = new Proxy(storage, {
     deleteProperty(target, key) {
@@ -136,6 +181,7 @@ function createStorage(persistent) {
     },
 
     ownKeys() {
+      flushWrites(storage);
       return op_webstorage_iterate_keys(persistent);
     },
 
@@ -190,4 +236,99 @@ function sessionStorage() {
   return sessionStorageStorage;
 }
 
-export { localStorage, sessionStorage, Storage };
+// Makes the queued writes, as the process exits before microtasks run.
+function flushPendingWrites() {
+  if (localStorageStorage) {
+    flushWrites(localStorageStorage);
+  }
+  if (sessionStorageStorage) {
+    flushWrites(sessionStorageStorage);
+  }
+}
+
+class StorageEvent extends Event {
+  #key = null;
+  #oldValue = null;
+  #newValue = null;
+  #url = "";
+  #storageArea = null;
+
+  get key() {
+    return this.#key;
+  }
+  get oldValue() {
+    return this.#oldValue;
+  }
+  get newValue() {
+    return this.#newValue;
+  }
+  get url() {
+    return this.#url;
+  }
+  get storageArea() {
+    return this.#storageArea;
+  }
+
+  constructor(type, {
+    bubbles,
+    cancelable,
+    composed,
+    key = null,
+    oldValue = null,
+    newValue = null,
+    url = "",
+    storageArea = null,
+  } = { __proto__: null }) {
+    super(type, {
+      bubbles: bubbles,
+      cancelable: cancelable,
+      composed: composed,
+    });
+
+    this.#key = key;
+    this.#oldValue = oldValue;
+    this.#newValue = newValue;
+    this.#url = url;
+    this.#storageArea = storageArea;
+  }
+}
+
+// Fires a `storage` event for every change another context of the same
+// origin makes to its `localStorage`. Doesn't keep the event loop alive.
+async function pollStorageEvents() {
+  while (true) {
+    let change;
+    try {
+      const promise = op_webstorage_subscribe();
+      core.unrefOpPromise(promise);
+      change = await promise;
+    } catch {
+      // the extension is disabled by the runtime profile
+      break;
+    }
+    if (change === null) {
+      break;
+    }
+    if (listenerCount(globalThis, "storage") === 0) {
+      continue;
+    }
+    const event = new StorageEvent("storage", {
+      key: change.key,
+      oldValue: change.oldValue,
+      newValue: change.newValue,
+      url: change.url,
+      storageArea: localStorage(),
+    });
+    setIsTrusted(event, true);
+    globalThis.dispatchEvent(event);
+  }
+}
+
+export {
+  flushPendingWrites,
+  localStorage,
+  pollStorageEvents,
+  sessionStorage,
+  Storage,
+  StorageEvent,
+};
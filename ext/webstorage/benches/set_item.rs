@@ -0,0 +1,76 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use deno_bench_util::bench_js_async_with;
+use deno_bench_util::bench_or_profile;
+use deno_bench_util::bencher::benchmark_group;
+use deno_bench_util::bencher::Bencher;
+use deno_bench_util::BenchOptions;
+use deno_core::Extension;
+
+#[derive(Clone)]
+struct Permissions;
+
+impl deno_web::TimersPermission for Permissions {
+  fn allow_hrtime(&mut self) -> bool {
+    false
+  }
+}
+
+fn setup() -> Vec<Extension> {
+  deno_core::extension!(
+    bench_setup,
+    esm_entry_point = "ext:bench_setup/setup",
+    esm = ["ext:bench_setup/setup" = {
+      source = r#"
+        import { sessionStorage } from "ext:deno_webstorage/01_webstorage.js";
+        globalThis.sessionStorage = sessionStorage();
+      "#
+    }],
+    state = |state| {
+      state.put(Permissions {});
+    },
+  );
+
+  vec![
+    deno_webidl::deno_webidl::init_ops_and_esm(),
+    deno_url::deno_url::init_ops_and_esm(),
+    deno_console::deno_console::init_ops_and_esm(),
+    deno_web::deno_web::init_ops_and_esm::<Permissions>(
+      Default::default(),
+      None,
+    ),
+    deno_webstorage::deno_webstorage::init_ops_and_esm(None, None, None),
+    bench_setup::init_ops_and_esm(),
+  ]
+}
+
+const OPTIONS: BenchOptions = BenchOptions {
+  benching_inner: 100_000,
+  profiling_inner: 100_000,
+  profiling_outer: 10,
+};
+
+/// 100k `sessionStorage.setItem()` calls in one task, made with one batched
+/// op call. `localStorage` writes aren't batched, as another context could
+/// make them exceed the quota.
+fn bench_set_item_100k(b: &mut Bencher) {
+  bench_js_async_with(
+    b,
+    r#"sessionStorage.setItem(`key${i}`, "value");"#,
+    setup,
+    OPTIONS,
+  );
+}
+
+/// The same writes made with one op call each, for comparison.
+fn bench_set_item_100k_unbatched(b: &mut Bencher) {
+  bench_js_async_with(
+    b,
+    r#"Deno.core.ops.op_webstorage_set(`key${i}`, "value", false);"#,
+    setup,
+    OPTIONS,
+  );
+}
+
+benchmark_group!(benches, bench_set_item_100k, bench_set_item_100k_unbatched);
+bench_or_profile!(benches);
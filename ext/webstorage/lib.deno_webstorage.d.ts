@@ -46,3 +46,36 @@ declare var Storage: {
   readonly prototype: Storage;
   new (): never;
 };
+
+/** @category Storage */
+interface StorageEventInit extends EventInit {
+  key?: string | null;
+  oldValue?: string | null;
+  newValue?: string | null;
+  url?: string;
+  storageArea?: Storage | null;
+}
+
+/** Fired on the other contexts of an origin when one of them changes its
+ * `localStorage`.
+ *
+ * @category Storage
+ */
+interface StorageEvent extends Event {
+  /** The changed key, or null if the storage area was cleared. */
+  readonly key: string | null;
+  /** The previous value of the key, or null if it was just added. */
+  readonly oldValue: string | null;
+  /** The new value of the key, or null if it was removed. */
+  readonly newValue: string | null;
+  /** The URL of the context that changed the storage area. */
+  readonly url: string;
+  /** The storage area that was changed. */
+  readonly storageArea: Storage | null;
+}
+
+/** @category Storage */
+declare var StorageEvent: {
+  readonly prototype: StorageEvent;
+  new (type: string, eventInitDict?: StorageEventInit): StorageEvent;
+};
@@ -2,13 +2,26 @@
 
 // NOTE to all: use **cached** prepared statements when interfacing with SQLite.
 
+use std::cell::RefCell;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 use deno_core::op2;
+use deno_core::unsync::spawn_blocking;
+use deno_core::AsyncRefCell;
 use deno_core::OpState;
+use deno_core::RcRef;
+use deno_web::BatchableOp;
+use deno_web::EventBus;
 use rusqlite::params;
 use rusqlite::Connection;
 use rusqlite::OptionalExtension;
+use rusqlite::TransactionBehavior;
+use serde::Serialize;
+use tokio::sync::broadcast;
 
 pub use rusqlite;
 
@@ -18,19 +31,118 @@ pub enum WebStorageError {
   ContextNotSupported,
   #[error(transparent)]
   Sqlite(#[from] rusqlite::Error),
-  #[error(transparent)]
+  #[error("Failed to create the storage directory: {0}")]
   Io(std::io::Error),
   #[error("Exceeded maximum storage size")]
   StorageExceeded,
 }
 
+/// Published on the `EventBus` whenever a storage area is modified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageChange {
+  /// `true` for `localStorage`, `false` for `sessionStorage`.
+  pub persistent: bool,
+  /// The changed key, or `None` when the storage area was cleared.
+  pub key: Option<String>,
+  pub old_value: Option<String>,
+  pub new_value: Option<String>,
+}
+
+/// The payload of a `storage` event, fired on the other contexts of an
+/// origin after one of them modified its `localStorage`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEventData {
+  /// The changed key, or `None` when the storage area was cleared.
+  pub key: Option<String>,
+  pub old_value: Option<String>,
+  pub new_value: Option<String>,
+  /// The URL of the context that modified the storage area.
+  pub url: String,
+}
+
+#[derive(Debug, Clone)]
+struct StorageEventMessage {
+  origin_storage_dir: Arc<PathBuf>,
+  /// The context that sent the message, which doesn't receive it.
+  context: u64,
+  data: StorageEventData,
+}
+
+/// Carries `storage` events between the contexts of an embedder. Every
+/// worker that should see the `localStorage` changes of the others must be
+/// initialized with a clone of the same channel. Only contexts using the
+/// same origin storage directory receive each other's events.
+#[derive(Clone)]
+pub struct StorageEventChannel(broadcast::Sender<StorageEventMessage>);
+
+impl Default for StorageEventChannel {
+  fn default() -> Self {
+    Self(broadcast::channel(256).0)
+  }
+}
+
+static NEXT_CONTEXT: AtomicU64 = AtomicU64::new(0);
+
+struct StorageEventReceiver {
+  receiver: AsyncRefCell<broadcast::Receiver<StorageEventMessage>>,
+}
+
+struct StorageEvents {
+  channel: StorageEventChannel,
+  context: u64,
+  url: String,
+  origin_storage_dir: Arc<PathBuf>,
+  receiver: Option<Rc<StorageEventReceiver>>,
+}
+
+impl StorageEvents {
+  /// Whether another context is subscribed to the channel. Some of them
+  /// might use another origin, but that is cheaper to find out on their
+  /// side.
+  fn has_other_subscribers(&self) -> bool {
+    let own = self.receiver.is_some() as usize;
+    self.channel.0.receiver_count() > own
+  }
+
+  fn send(&self, data: StorageEventData) {
+    // fails only when nobody is subscribed
+    let _ = self.channel.0.send(StorageEventMessage {
+      origin_storage_dir: self.origin_storage_dir.clone(),
+      context: self.context,
+      data,
+    });
+  }
+
+  fn subscribe(&mut self) -> Rc<StorageEventReceiver> {
+    self
+      .receiver
+      .get_or_insert_with(|| {
+        Rc::new(StorageEventReceiver {
+          receiver: AsyncRefCell::new(self.channel.0.subscribe()),
+        })
+      })
+      .clone()
+  }
+}
+
 #[derive(Clone)]
 struct OriginStorageDir(PathBuf);
 
-const MAX_STORAGE_BYTES: usize = 10 * 1024 * 1024;
+/// The most bytes a storage area can hold, counting the UTF-8 length of
+/// every key and value. Defaults to 5 MiB, like browsers allow per origin.
+/// Embedders can put a different quota into the `OpState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebStorageQuota(pub u64);
+
+impl Default for WebStorageQuota {
+  fn default() -> Self {
+    Self(5 * 1024 * 1024)
+  }
+}
 
 deno_core::extension!(deno_webstorage,
-  deps = [ deno_webidl ],
+  deps = [ deno_webidl, deno_web ],
   ops = [
     op_webstorage_length,
     op_webstorage_key,
@@ -39,15 +151,31 @@ deno_core::extension!(deno_webstorage,
     op_webstorage_remove,
     op_webstorage_clear,
     op_webstorage_iterate_keys,
+    op_webstorage_remaining,
+    op_webstorage_subscribe,
   ],
   esm = [ "01_webstorage.js" ],
   options = {
-    origin_storage_dir: Option<PathBuf>
+    origin_storage_dir: Option<PathBuf>,
+    event_channel: Option<StorageEventChannel>,
+    url: Option<String>,
   },
   state = |state, options| {
     if let Some(origin_storage_dir) = options.origin_storage_dir {
+      if let Some(channel) = options.event_channel {
+        state.put(StorageEvents {
+          channel,
+          context: NEXT_CONTEXT.fetch_add(1, Ordering::Relaxed),
+          url: options.url.unwrap_or_default(),
+          origin_storage_dir: Arc::new(origin_storage_dir.clone()),
+          receiver: None,
+        });
+      }
       state.put(OriginStorageDir(origin_storage_dir));
     }
+    deno_web::register_state_close(state, close_local_storage);
+    deno_web::register_batchable_op::<BatchedSet>(state);
+    deno_web::register_batchable_op::<BatchedRemove>(state);
   },
 );
 
@@ -55,14 +183,309 @@ pub fn get_declaration() -> PathBuf {
   PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("lib.deno_webstorage.d.ts")
 }
 
-struct LocalStorage(Connection);
-struct SessionStorage(Connection);
+/// Folds the write-ahead log back into the database at shutdown, so that
+/// the next run doesn't start with a large `-wal` file to replay.
+fn close_local_storage(
+  state: &mut OpState,
+) -> Option<deno_web::AsyncCloseFuture> {
+  let storage = state.try_take::<LocalStorage>()?;
+  Some(Box::pin(async move {
+    let _ = spawn_blocking(move || storage.0.checkpoint()).await;
+  }))
+}
+
+/// Whether modifications of the storage area need to be published, which
+/// costs reading the old value first.
+fn has_change_subscribers(state: &OpState, persistent: bool) -> bool {
+  state
+    .try_borrow::<EventBus>()
+    .is_some_and(|bus| bus.subscriber_count::<StorageChange>() > 0)
+    || (persistent
+      && state
+        .try_borrow::<StorageEvents>()
+        .is_some_and(StorageEvents::has_other_subscribers))
+}
+
+/// Changes of `localStorage` made by a batch whose transaction isn't
+/// committed yet. They are published once it is, and dropped when it is
+/// rolled back, so that no context hears of a change that didn't happen.
+#[derive(Default)]
+struct PendingStorageChanges(Vec<StorageChange>);
+
+fn publish_change(state: &mut OpState, change: StorageChange) {
+  if change.persistent {
+    if let Some(pending) = state.try_borrow_mut::<PendingStorageChanges>() {
+      pending.0.push(change);
+      return;
+    }
+  }
+  if let Some(bus) = state.try_borrow::<EventBus>() {
+    bus.publish(change.clone());
+  }
+  if !change.persistent {
+    return;
+  }
+  if let Some(events) = state.try_borrow::<StorageEvents>() {
+    if events.has_other_subscribers() {
+      events.send(StorageEventData {
+        key: change.key,
+        old_value: change.old_value,
+        new_value: change.new_value,
+        url: events.url.clone(),
+      });
+    }
+  }
+}
+
+/// A storage area. Keys are ordered by insertion, so that `key()` returns
+/// the same key for an index as long as the storage area isn't modified.
+struct Storage {
+  conn: Connection,
+  /// Bumped on every modification, which invalidates `cursor`.
+  generation: u64,
+  /// The last key returned by `key()`, so that iterating over the keys
+  /// doesn't need an `OFFSET` scan for every index.
+  cursor: Option<KeyCursor>,
+  /// The bytes stored, so that checking the quota doesn't need to sum up
+  /// every row on each write.
+  usage: Option<Usage>,
+}
+
+struct Usage {
+  /// Changes when another connection modified a persistent database.
+  data_version: i64,
+  bytes: u64,
+}
+
+struct KeyCursor {
+  generation: u64,
+  /// Changes when another connection modified a persistent database.
+  data_version: i64,
+  index: u32,
+  seq: i64,
+  key: String,
+}
+
+impl Storage {
+  fn new(mut conn: Connection) -> Result<Self, WebStorageError> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    tx.execute_batch(
+      "CREATE TABLE IF NOT EXISTS data (key VARCHAR UNIQUE, value VARCHAR, seq INTEGER)",
+    )?;
+    // Databases created before keys were ordered by insertion have no `seq`
+    // column. Their keys, and keys set by such older versions, are ordered
+    // after all other keys, like they were before.
+    let has_seq = tx
+      .prepare("SELECT 1 FROM pragma_table_info('data') WHERE name = 'seq'")?
+      .exists(params![])?;
+    if !has_seq {
+      tx.execute_batch("ALTER TABLE data ADD COLUMN seq INTEGER")?;
+    }
+    tx.execute_batch(
+      "
+      UPDATE data SET seq = (SELECT IFNULL(MAX(seq), 0) FROM data) + rowid
+        WHERE seq IS NULL;
+      CREATE INDEX IF NOT EXISTS data_seq ON data (seq);
+      ",
+    )?;
+    tx.commit()?;
+
+    Ok(Self {
+      conn,
+      generation: 0,
+      cursor: None,
+      usage: None,
+    })
+  }
+
+  fn checkpoint(&self) -> Result<(), WebStorageError> {
+    self.conn.query_row(
+      "PRAGMA wal_checkpoint(TRUNCATE)",
+      params![],
+      |_| Ok(()),
+    )?;
+    Ok(())
+  }
+
+  /// Rolls back the open transaction, if any. The cached usage and key
+  /// cursor may describe rolled back writes, so they are dropped.
+  fn rollback(&mut self) -> Result<(), WebStorageError> {
+    self.generation += 1;
+    self.usage = None;
+    if !self.conn.is_autocommit() {
+      self.conn.execute_batch("ROLLBACK")?;
+    }
+    Ok(())
+  }
+
+  fn data_version(&self) -> Result<i64, WebStorageError> {
+    let mut stmt = self.conn.prepare_cached("PRAGMA data_version")?;
+    Ok(stmt.query_row(params![], |row| row.get(0))?)
+  }
+
+  /// The bytes taken by all keys and values.
+  fn usage(&mut self) -> Result<u64, WebStorageError> {
+    let data_version = self.data_version()?;
+    if let Some(usage) = &self.usage {
+      if usage.data_version == data_version {
+        return Ok(usage.bytes);
+      }
+    }
+    let mut stmt = self.conn.prepare_cached(
+      "SELECT IFNULL(SUM(LENGTH(CAST(key AS BLOB)) + LENGTH(CAST(value AS BLOB))), 0) FROM data",
+    )?;
+    let bytes = stmt.query_row(params![], |row| row.get(0))?;
+    self.usage = Some(Usage {
+      data_version,
+      bytes,
+    });
+    Ok(bytes)
+  }
+
+  /// The bytes taken by `key` and its value, if it is set.
+  fn entry_size(&self, key: &str) -> Result<u64, WebStorageError> {
+    let mut stmt = self.conn.prepare_cached(
+      "SELECT LENGTH(CAST(key AS BLOB)) + LENGTH(CAST(value AS BLOB)) FROM data WHERE key = ?",
+    )?;
+    let size = stmt.query_row(params![key], |row| row.get(0)).optional()?;
+    Ok(size.unwrap_or(0))
+  }
+
+  /// Replaces `old` bytes with `new` bytes in the cached usage.
+  fn update_usage(&mut self, old: u64, new: u64) {
+    if let Some(usage) = &mut self.usage {
+      usage.bytes = usage.bytes.saturating_sub(old) + new;
+    }
+  }
+
+  fn len(&self) -> Result<u32, WebStorageError> {
+    let mut stmt = self.conn.prepare_cached("SELECT COUNT(*) FROM data")?;
+    Ok(stmt.query_row(params![], |row| row.get(0))?)
+  }
+
+  fn key(&mut self, index: u32) -> Result<Option<String>, WebStorageError> {
+    let data_version = self.data_version()?;
+    let cursor = self.cursor.take().filter(|cursor| {
+      cursor.generation == self.generation
+        && cursor.data_version == data_version
+    });
+
+    let found = match cursor {
+      Some(cursor) if cursor.index == index => Some((cursor.seq, cursor.key)),
+      Some(cursor) if cursor.index.checked_add(1) == Some(index) => {
+        let mut stmt = self.conn.prepare_cached(
+          "SELECT seq, key FROM data WHERE seq > ? ORDER BY seq LIMIT 1",
+        )?;
+        stmt
+          .query_row(params![cursor.seq], |row| Ok((row.get(0)?, row.get(1)?)))
+          .optional()?
+      }
+      Some(cursor) if cursor.index.checked_sub(1) == Some(index) => {
+        let mut stmt = self.conn.prepare_cached(
+          "SELECT seq, key FROM data WHERE seq < ? ORDER BY seq DESC LIMIT 1",
+        )?;
+        stmt
+          .query_row(params![cursor.seq], |row| Ok((row.get(0)?, row.get(1)?)))
+          .optional()?
+      }
+      _ => {
+        let mut stmt = self.conn.prepare_cached(
+          "SELECT seq, key FROM data ORDER BY seq LIMIT 1 OFFSET ?",
+        )?;
+        stmt
+          .query_row(params![index], |row| Ok((row.get(0)?, row.get(1)?)))
+          .optional()?
+      }
+    };
+
+    let Some((seq, key)) = found else {
+      return Ok(None);
+    };
+    self.cursor = Some(KeyCursor {
+      generation: self.generation,
+      data_version,
+      index,
+      seq,
+      key: key.clone(),
+    });
+    Ok(Some(key))
+  }
+
+  fn keys(&self) -> Result<Vec<String>, WebStorageError> {
+    let mut stmt = self
+      .conn
+      .prepare_cached("SELECT key FROM data ORDER BY seq")?;
+    let keys = stmt
+      .query_map(params![], |row| row.get::<_, String>(0))?
+      .collect::<Result<_, _>>()?;
+    Ok(keys)
+  }
+
+  fn get(&self, key: &str) -> Result<Option<String>, WebStorageError> {
+    let mut stmt = self
+      .conn
+      .prepare_cached("SELECT value FROM data WHERE key = ?")?;
+    Ok(stmt.query_row(params![key], |row| row.get(0)).optional()?)
+  }
+
+  /// Sets the value of `key`. New keys are ordered after all existing keys,
+  /// the order of existing keys doesn't change.
+  ///
+  /// Fails without changing anything if the storage area would hold more
+  /// than `quota` bytes afterwards.
+  fn set(
+    &mut self,
+    key: &str,
+    value: &str,
+    quota: WebStorageQuota,
+  ) -> Result<(), WebStorageError> {
+    let old_size = self.entry_size(key)?;
+    let new_size = (key.len() + value.len()) as u64;
+    if self.usage()?.saturating_sub(old_size) + new_size > quota.0 {
+      return Err(WebStorageError::StorageExceeded);
+    }
+
+    let mut stmt = self.conn.prepare_cached(
+      "INSERT INTO data (key, value, seq)
+        VALUES (?1, ?2, (SELECT IFNULL(MAX(seq), 0) + 1 FROM data))
+        ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+    )?;
+    stmt.execute(params![key, value])?;
+    self.generation += 1;
+    self.update_usage(old_size, new_size);
+    Ok(())
+  }
+
+  fn remove(&mut self, key: &str) -> Result<(), WebStorageError> {
+    let old_size = self.entry_size(key)?;
+    let mut stmt =
+      self.conn.prepare_cached("DELETE FROM data WHERE key = ?")?;
+    stmt.execute(params![key])?;
+    self.generation += 1;
+    self.update_usage(old_size, 0);
+    Ok(())
+  }
+
+  /// Removes all keys, returns the number of removed keys.
+  fn clear(&mut self) -> Result<usize, WebStorageError> {
+    let mut stmt = self.conn.prepare_cached("DELETE FROM data")?;
+    let deleted = stmt.execute(params![])?;
+    self.generation += 1;
+    if let Some(usage) = &mut self.usage {
+      usage.bytes = 0;
+    }
+    Ok(deleted)
+  }
+}
+
+struct LocalStorage(Storage);
+struct SessionStorage(Storage);
 
 fn get_webstorage(
   state: &mut OpState,
   persistent: bool,
-) -> Result<&Connection, WebStorageError> {
-  let conn = if persistent {
+) -> Result<&mut Storage, WebStorageError> {
+  let storage = if persistent {
     if state.try_borrow::<LocalStorage>().is_none() {
       let path = state
         .try_borrow::<OriginStorageDir>()
@@ -82,32 +505,20 @@ fn get_webstorage(
 
       conn.execute_batch(initial_pragmas)?;
       conn.set_prepared_statement_cache_capacity(128);
-      {
-        let mut stmt = conn.prepare_cached(
-          "CREATE TABLE IF NOT EXISTS data (key VARCHAR UNIQUE, value VARCHAR)",
-        )?;
-        stmt.execute(params![])?;
-      }
-      state.put(LocalStorage(conn));
+      state.put(LocalStorage(Storage::new(conn)?));
     }
 
-    &state.borrow::<LocalStorage>().0
+    &mut state.borrow_mut::<LocalStorage>().0
   } else {
     if state.try_borrow::<SessionStorage>().is_none() {
       let conn = Connection::open_in_memory()?;
-      {
-        let mut stmt = conn.prepare_cached(
-          "CREATE TABLE data (key VARCHAR UNIQUE, value VARCHAR)",
-        )?;
-        stmt.execute(params![])?;
-      }
-      state.put(SessionStorage(conn));
+      state.put(SessionStorage(Storage::new(conn)?));
     }
 
-    &state.borrow::<SessionStorage>().0
+    &mut state.borrow_mut::<SessionStorage>().0
   };
 
-  Ok(conn)
+  Ok(storage)
 }
 
 #[op2(fast)]
@@ -115,12 +526,7 @@ pub fn op_webstorage_length(
   state: &mut OpState,
   persistent: bool,
 ) -> Result<u32, WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
-
-  let mut stmt = conn.prepare_cached("SELECT COUNT(*) FROM data")?;
-  let length: u32 = stmt.query_row(params![], |row| row.get(0))?;
-
-  Ok(length)
+  get_webstorage(state, persistent)?.len()
 }
 
 #[op2]
@@ -130,22 +536,69 @@ pub fn op_webstorage_key(
   #[smi] index: u32,
   persistent: bool,
 ) -> Result<Option<String>, WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
+  get_webstorage(state, persistent)?.key(index)
+}
 
-  let mut stmt =
-    conn.prepare_cached("SELECT key FROM data LIMIT 1 OFFSET ?")?;
+fn quota(state: &OpState) -> WebStorageQuota {
+  state
+    .try_borrow::<WebStorageQuota>()
+    .copied()
+    .unwrap_or_default()
+}
 
-  let key: Option<String> = stmt
-    .query_row(params![index], |row| row.get(0))
-    .optional()?;
+fn set_item(
+  state: &mut OpState,
+  key: &str,
+  value: &str,
+  persistent: bool,
+) -> Result<(), WebStorageError> {
+  let notify = has_change_subscribers(state, persistent);
+  let quota = quota(state);
+  let old_value = {
+    let storage = get_webstorage(state, persistent)?;
+    let old_value = if notify { storage.get(key)? } else { None };
+    storage.set(key, value, quota)?;
+    old_value
+  };
+
+  if notify {
+    publish_change(
+      state,
+      StorageChange {
+        persistent,
+        key: Some(key.to_string()),
+        old_value,
+        new_value: Some(value.to_string()),
+      },
+    );
+  }
 
-  Ok(key)
+  Ok(())
 }
 
-#[inline]
-fn size_check(input: usize) -> Result<(), WebStorageError> {
-  if input >= MAX_STORAGE_BYTES {
-    return Err(WebStorageError::StorageExceeded);
+fn remove_item(
+  state: &mut OpState,
+  key: &str,
+  persistent: bool,
+) -> Result<(), WebStorageError> {
+  let notify = has_change_subscribers(state, persistent);
+  let old_value = {
+    let storage = get_webstorage(state, persistent)?;
+    let old_value = if notify { storage.get(key)? } else { None };
+    storage.remove(key)?;
+    old_value
+  };
+
+  if old_value.is_some() {
+    publish_change(
+      state,
+      StorageChange {
+        persistent,
+        key: Some(key.to_string()),
+        old_value,
+        new_value: None,
+      },
+    );
   }
 
   Ok(())
@@ -158,21 +611,7 @@ pub fn op_webstorage_set(
   #[string] value: &str,
   persistent: bool,
 ) -> Result<(), WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
-
-  size_check(key.len() + value.len())?;
-
-  let mut stmt = conn
-    .prepare_cached("SELECT SUM(pgsize) FROM dbstat WHERE name = 'data'")?;
-  let size: u32 = stmt.query_row(params![], |row| row.get(0))?;
-
-  size_check(size as usize)?;
-
-  let mut stmt = conn
-    .prepare_cached("INSERT OR REPLACE INTO data (key, value) VALUES (?, ?)")?;
-  stmt.execute(params![key, value])?;
-
-  Ok(())
+  set_item(state, key, value, persistent)
 }
 
 #[op2]
@@ -182,14 +621,7 @@ pub fn op_webstorage_get(
   #[string] key_name: String,
   persistent: bool,
 ) -> Result<Option<String>, WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
-
-  let mut stmt = conn.prepare_cached("SELECT value FROM data WHERE key = ?")?;
-  let val = stmt
-    .query_row(params![key_name], |row| row.get(0))
-    .optional()?;
-
-  Ok(val)
+  get_webstorage(state, persistent)?.get(&key_name)
 }
 
 #[op2(fast)]
@@ -198,12 +630,7 @@ pub fn op_webstorage_remove(
   #[string] key_name: &str,
   persistent: bool,
 ) -> Result<(), WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
-
-  let mut stmt = conn.prepare_cached("DELETE FROM data WHERE key = ?")?;
-  stmt.execute(params![key_name])?;
-
-  Ok(())
+  remove_item(state, key_name, persistent)
 }
 
 #[op2(fast)]
@@ -211,10 +638,20 @@ pub fn op_webstorage_clear(
   state: &mut OpState,
   persistent: bool,
 ) -> Result<(), WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
-
-  let mut stmt = conn.prepare_cached("DELETE FROM data")?;
-  stmt.execute(params![])?;
+  let notify = has_change_subscribers(state, persistent);
+  let deleted = get_webstorage(state, persistent)?.clear()?;
+
+  if notify && deleted > 0 {
+    publish_change(
+      state,
+      StorageChange {
+        persistent,
+        key: None,
+        old_value: None,
+        new_value: None,
+      },
+    );
+  }
 
   Ok(())
 }
@@ -225,13 +662,350 @@ pub fn op_webstorage_iterate_keys(
   state: &mut OpState,
   persistent: bool,
 ) -> Result<Vec<String>, WebStorageError> {
-  let conn = get_webstorage(state, persistent)?;
+  get_webstorage(state, persistent)?.keys()
+}
+
+/// The bytes that can still be stored. JS batches the writes that are sure
+/// to fit, and makes the others directly so that they throw right away.
+#[op2]
+#[number]
+pub fn op_webstorage_remaining(
+  state: &mut OpState,
+  persistent: bool,
+) -> Result<u64, WebStorageError> {
+  let quota = quota(state);
+  let usage = get_webstorage(state, persistent)?.usage()?;
+  Ok(quota.0.saturating_sub(usage))
+}
 
-  let mut stmt = conn.prepare_cached("SELECT key FROM data")?;
-  let keys = stmt
-    .query_map(params![], |row| row.get::<_, String>(0))?
-    .map(|r| r.unwrap())
-    .collect();
+/// Runs a batch of `localStorage` writes in one transaction, which is much
+/// cheaper than committing each of them. The transaction is deferred, so
+/// it takes no lock before the first write, and it is committed before the
+/// batch returns to JS. Storage events of the batch are held back until
+/// then.
+fn begin_local_storage_batch(
+  state: &mut OpState,
+) -> Result<(), WebStorageError> {
+  if let Some(storage) = state.try_borrow::<LocalStorage>() {
+    if storage.0.conn.is_autocommit() {
+      storage.0.conn.execute_batch("BEGIN")?;
+      state.put(PendingStorageChanges::default());
+    }
+  }
+  Ok(())
+}
 
-  Ok(keys)
+/// Commits the transaction of a batch and publishes its changes. A failed
+/// `COMMIT` can leave the transaction open, so it is rolled back, and the
+/// changes are dropped with it.
+fn end_local_storage_batch(state: &mut OpState) -> Result<(), WebStorageError> {
+  let pending = state.try_take::<PendingStorageChanges>();
+  if let Some(storage) = state.try_borrow_mut::<LocalStorage>() {
+    if !storage.0.conn.is_autocommit() {
+      if let Err(err) = storage.0.conn.execute_batch("COMMIT") {
+        let _ = storage.0.rollback();
+        return Err(err.into());
+      }
+    }
+  }
+  for change in pending.into_iter().flat_map(|pending| pending.0) {
+    publish_change(state, change);
+  }
+  Ok(())
+}
+
+/// `op_webstorage_set` as a [`BatchableOp`].
+struct BatchedSet;
+
+impl BatchableOp for BatchedSet {
+  const NAME: &'static str = "op_webstorage_set";
+  type Args = (String, String, bool);
+  type Output = ();
+  type Error = WebStorageError;
+
+  fn call(
+    state: &mut OpState,
+    (key, value, persistent): Self::Args,
+  ) -> Result<(), WebStorageError> {
+    set_item(state, &key, &value, persistent)
+  }
+
+  fn begin_batch(state: &mut OpState) -> Result<(), WebStorageError> {
+    begin_local_storage_batch(state)
+  }
+
+  fn end_batch(state: &mut OpState) -> Result<(), WebStorageError> {
+    end_local_storage_batch(state)
+  }
+}
+
+/// `op_webstorage_remove` as a [`BatchableOp`].
+struct BatchedRemove;
+
+impl BatchableOp for BatchedRemove {
+  const NAME: &'static str = "op_webstorage_remove";
+  type Args = (String, bool);
+  type Output = ();
+  type Error = WebStorageError;
+
+  fn call(
+    state: &mut OpState,
+    (key, persistent): Self::Args,
+  ) -> Result<(), WebStorageError> {
+    remove_item(state, &key, persistent)
+  }
+
+  fn begin_batch(state: &mut OpState) -> Result<(), WebStorageError> {
+    begin_local_storage_batch(state)
+  }
+
+  fn end_batch(state: &mut OpState) -> Result<(), WebStorageError> {
+    end_local_storage_batch(state)
+  }
+}
+
+/// Resolves with the next `localStorage` change made by another context of
+/// the same origin, or `null` when this context can't receive any.
+#[op2(async)]
+#[serde]
+pub async fn op_webstorage_subscribe(
+  state: Rc<RefCell<OpState>>,
+) -> Option<StorageEventData> {
+  let (receiver, context, origin_storage_dir) = {
+    let mut state = state.borrow_mut();
+    let events = state.try_borrow_mut::<StorageEvents>()?;
+    (
+      events.subscribe(),
+      events.context,
+      events.origin_storage_dir.clone(),
+    )
+  };
+  let mut receiver = RcRef::map(&receiver, |r| &r.receiver).borrow_mut().await;
+  loop {
+    match receiver.recv().await {
+      Ok(message)
+        if message.context != context
+          && message.origin_storage_dir == origin_storage_dir =>
+      {
+        return Some(message.data);
+      }
+      Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+      Err(broadcast::error::RecvError::Closed) => return None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rusqlite::StatementStatus;
+
+  use super::*;
+
+  const QUOTA: WebStorageQuota = WebStorageQuota(5 * 1024 * 1024);
+
+  fn storage_with_keys(keys: &[&str]) -> Storage {
+    let mut storage =
+      Storage::new(Connection::open_in_memory().unwrap()).unwrap();
+    for key in keys {
+      storage.set(key, "value", QUOTA).unwrap();
+    }
+    storage
+  }
+
+  fn all_keys(storage: &mut Storage) -> Vec<String> {
+    let len = storage.len().unwrap();
+    (0..len).map(|i| storage.key(i).unwrap().unwrap()).collect()
+  }
+
+  #[test]
+  fn keys_are_ordered_by_insertion() {
+    let mut storage = storage_with_keys(&["c", "a", "b"]);
+    assert_eq!(all_keys(&mut storage), ["c", "a", "b"]);
+    // stable across repeated and random access
+    assert_eq!(storage.key(1).unwrap().as_deref(), Some("a"));
+    assert_eq!(storage.key(1).unwrap().as_deref(), Some("a"));
+    assert_eq!(storage.key(2).unwrap().as_deref(), Some("b"));
+    assert_eq!(storage.key(0).unwrap().as_deref(), Some("c"));
+    assert_eq!(storage.key(3).unwrap(), None);
+    assert_eq!(storage.keys().unwrap(), ["c", "a", "b"]);
+
+    // updating a value keeps the position of the key
+    storage.set("c", "new", QUOTA).unwrap();
+    assert_eq!(all_keys(&mut storage), ["c", "a", "b"]);
+    assert_eq!(storage.get("c").unwrap().as_deref(), Some("new"));
+  }
+
+  #[test]
+  fn key_after_mutations() {
+    let mut storage = storage_with_keys(&["a", "b", "c", "d"]);
+    assert_eq!(storage.key(1).unwrap().as_deref(), Some("b"));
+    storage.remove("b").unwrap();
+    assert_eq!(storage.key(1).unwrap().as_deref(), Some("c"));
+    assert_eq!(storage.key(2).unwrap().as_deref(), Some("d"));
+    storage.set("b", "value", QUOTA).unwrap();
+    assert_eq!(storage.key(3).unwrap().as_deref(), Some("b"));
+    assert_eq!(all_keys(&mut storage), ["a", "c", "d", "b"]);
+    storage.clear().unwrap();
+    assert_eq!(storage.key(0).unwrap(), None);
+  }
+
+  #[test]
+  fn migrates_unordered_database() {
+    let dir = std::env::temp_dir()
+      .join(format!("deno_webstorage_migrate_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("local_storage");
+    let _ = std::fs::remove_file(&path);
+    {
+      let conn = Connection::open(&path).unwrap();
+      conn
+        .execute_batch(
+          "
+          CREATE TABLE data (key VARCHAR UNIQUE, value VARCHAR);
+          INSERT INTO data VALUES ('x', '1'), ('y', '2');
+          ",
+        )
+        .unwrap();
+    }
+
+    let mut storage = Storage::new(Connection::open(&path).unwrap()).unwrap();
+    storage.set("z", "3", QUOTA).unwrap();
+    assert_eq!(all_keys(&mut storage), ["x", "y", "z"]);
+    drop(storage);
+    // opening a migrated database again doesn't change it
+    let mut storage = Storage::new(Connection::open(&path).unwrap()).unwrap();
+    assert_eq!(all_keys(&mut storage), ["x", "y", "z"]);
+    drop(storage);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn quota_counts_keys_and_values() {
+    let quota = WebStorageQuota(10);
+    let mut storage =
+      Storage::new(Connection::open_in_memory().unwrap()).unwrap();
+    storage.set("a", "1234", quota).unwrap();
+    storage.set("b", "1234", quota).unwrap();
+    assert!(matches!(
+      storage.set("c", "", quota),
+      Err(WebStorageError::StorageExceeded)
+    ));
+    assert_eq!(storage.keys().unwrap(), ["a", "b"]);
+    // replacing a value only counts the difference
+    storage.set("a", "", quota).unwrap();
+    storage.set("b", "12345678", quota).unwrap();
+    assert_eq!(storage.usage().unwrap(), 10);
+    assert!(matches!(
+      storage.set("b", "123456789", quota),
+      Err(WebStorageError::StorageExceeded)
+    ));
+    assert_eq!(storage.get("b").unwrap().as_deref(), Some("12345678"));
+    // UTF-8 bytes
+    storage.remove("b").unwrap();
+    assert!(matches!(
+      storage.set("b", "ééééé", quota),
+      Err(WebStorageError::StorageExceeded)
+    ));
+    storage.set("b", "éééé", quota).unwrap();
+    storage.clear().unwrap();
+    assert_eq!(storage.usage().unwrap(), 0);
+    storage.set("c", "123456789", quota).unwrap();
+  }
+
+  #[test]
+  fn quota_sees_other_connections() {
+    let dir = std::env::temp_dir()
+      .join(format!("deno_webstorage_quota_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("local_storage");
+    let _ = std::fs::remove_file(&path);
+
+    let quota = WebStorageQuota(10);
+    let mut storage = Storage::new(Connection::open(&path).unwrap()).unwrap();
+    let mut other = Storage::new(Connection::open(&path).unwrap()).unwrap();
+    storage.set("a", "1234", quota).unwrap();
+    assert_eq!(storage.usage().unwrap(), 5);
+    other.set("b", "1234", quota).unwrap();
+    assert_eq!(storage.usage().unwrap(), 10);
+    assert!(storage.set("c", "", quota).is_err());
+    drop(storage);
+    drop(other);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn iterating_keys_is_linear() {
+    const KEYS: u32 = 10_000;
+    let mut storage =
+      Storage::new(Connection::open_in_memory().unwrap()).unwrap();
+    for i in 0..KEYS {
+      storage.set(&format!("key{i}"), "value", QUOTA).unwrap();
+    }
+    for i in 0..KEYS {
+      assert_eq!(storage.key(i).unwrap(), Some(format!("key{i}")));
+    }
+
+    let vm_steps = [
+      "SELECT seq, key FROM data WHERE seq > ? ORDER BY seq LIMIT 1",
+      "SELECT seq, key FROM data ORDER BY seq LIMIT 1 OFFSET ?",
+    ]
+    .into_iter()
+    .map(|sql| {
+      let stmt = storage.conn.prepare_cached(sql).unwrap();
+      stmt.get_status(StatementStatus::VmStep) as u64
+    })
+    .sum::<u64>();
+    // an `OFFSET` scan per key would take more than KEYS * KEYS / 2 steps
+    assert!(
+      vm_steps < 100 * KEYS as u64,
+      "iterating {KEYS} keys took {vm_steps} VM steps"
+    );
+  }
+
+  #[test]
+  fn rollback_drops_rolled_back_usage() {
+    let mut storage =
+      Storage::new(Connection::open_in_memory().unwrap()).unwrap();
+    storage.set("a", "1234", QUOTA).unwrap();
+    storage.conn.execute_batch("BEGIN").unwrap();
+    storage.set("b", "1234", QUOTA).unwrap();
+    assert_eq!(storage.usage().unwrap(), 10);
+
+    storage.rollback().unwrap();
+    assert!(storage.conn.is_autocommit());
+    assert_eq!(storage.get("b").unwrap(), None);
+    assert_eq!(storage.usage().unwrap(), 5);
+  }
+
+  #[test]
+  fn storage_events_are_sent_to_other_contexts() {
+    let channel = StorageEventChannel::default();
+    let context = |context| StorageEvents {
+      channel: channel.clone(),
+      context,
+      url: "https://example.com/".to_string(),
+      origin_storage_dir: Arc::new(PathBuf::from("origin")),
+      receiver: None,
+    };
+    let a = context(0);
+    let mut b = context(1);
+    // nobody to tell about changes yet
+    assert!(!a.has_other_subscribers());
+    b.subscribe();
+    assert!(a.has_other_subscribers());
+    assert!(!b.has_other_subscribers());
+
+    let mut receiver = channel.0.subscribe();
+    let data = StorageEventData {
+      key: Some("key".to_string()),
+      old_value: None,
+      new_value: Some("value".to_string()),
+      url: a.url.clone(),
+    };
+    a.send(data.clone());
+    let message = receiver.try_recv().unwrap();
+    assert_eq!(message.context, 0);
+    assert_eq!(message.data, data);
+    assert!(receiver.try_recv().is_err());
+  }
 }
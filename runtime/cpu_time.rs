@@ -0,0 +1,306 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! CPU time accounting for workers.
+//!
+//! Every worker accumulates the CPU time consumed by the thread its isolate
+//! runs on. The thread's CPU clock is only sampled at event loop tick
+//! boundaries and, when a quota is configured, when an op is dispatched, so
+//! running JavaScript has no accounting cost. The counters are only updated
+//! by samples, so CPU time spent since the last boundary shows up at the next
+//! one.
+//!
+//! The counters of a worker are shared through a [`CpuTimeCounter`], which can
+//! be read from any thread without entering the worker's isolate.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deno_core::OpMetricsEvent;
+use deno_core::OpMetricsFactoryFn;
+
+/// CPU time consumed by a thread, split between user and kernel mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTime {
+  pub user: Duration,
+  pub system: Duration,
+}
+
+impl CpuTime {
+  pub fn total(&self) -> Duration {
+    self.user + self.system
+  }
+
+  fn saturating_sub(self, other: Self) -> Self {
+    Self {
+      user: self.user.saturating_sub(other.user),
+      system: self.system.saturating_sub(other.system),
+    }
+  }
+}
+
+/// CPU time used by a worker so far.
+#[derive(Debug, Default)]
+pub struct CpuTimeCounter {
+  user_us: AtomicU64,
+  system_us: AtomicU64,
+}
+
+impl CpuTimeCounter {
+  /// Returns the CPU time accumulated up to the worker's last sample. The
+  /// user and system times are read separately, so they may come from two
+  /// consecutive samples when read while the worker is sampling.
+  pub fn used(&self) -> CpuTime {
+    CpuTime {
+      user: Duration::from_micros(self.user_us.load(Ordering::Relaxed)),
+      system: Duration::from_micros(self.system_us.load(Ordering::Relaxed)),
+    }
+  }
+
+  fn store(&self, used: CpuTime) {
+    self
+      .user_us
+      .store(used.user.as_micros() as u64, Ordering::Relaxed);
+    self
+      .system_us
+      .store(used.system.as_micros() as u64, Ordering::Relaxed);
+  }
+}
+
+pub type CpuTimeQuotaCallback = dyn Fn(CpuTime) + Send + Sync;
+
+/// A soft limit on the CPU time a worker may use.
+///
+/// Once the worker's total CPU time exceeds `budget`, `on_exceeded` is called
+/// once, on the worker's thread, with the CPU time used so far. The worker
+/// keeps running; the callback can stop it, e.g. with
+/// `v8::IsolateHandle::terminate_execution()`.
+#[derive(Clone)]
+pub struct CpuTimeQuota {
+  pub budget: Duration,
+  pub on_exceeded: Arc<CpuTimeQuotaCallback>,
+}
+
+/// Samples the CPU clock of the current thread into a [`CpuTimeCounter`].
+/// Must be created and used on the thread of the worker it accounts for.
+pub(crate) struct CpuTimeSampler {
+  counter: Arc<CpuTimeCounter>,
+  start: CpuTime,
+  quota: Option<CpuTimeQuota>,
+  quota_exceeded: Cell<bool>,
+}
+
+impl CpuTimeSampler {
+  pub fn new(quota: Option<CpuTimeQuota>) -> Rc<Self> {
+    Rc::new(Self {
+      counter: Default::default(),
+      start: thread_cpu_time(),
+      quota,
+      quota_exceeded: Cell::new(false),
+    })
+  }
+
+  pub fn counter(&self) -> Arc<CpuTimeCounter> {
+    self.counter.clone()
+  }
+
+  pub fn has_quota(&self) -> bool {
+    self.quota.is_some()
+  }
+
+  /// Stores the CPU time used since the sampler was created in the counter
+  /// and returns it.
+  pub fn sample(&self) -> CpuTime {
+    self
+      .counter
+      .store(thread_cpu_time().saturating_sub(self.start));
+    let used = self.counter.used();
+    if let Some(quota) = &self.quota {
+      if used.total() > quota.budget && !self.quota_exceeded.replace(true) {
+        (quota.on_exceeded)(used);
+      }
+    }
+    used
+  }
+
+  /// Returns an op metrics factory that samples whenever an op is
+  /// dispatched, for workers that need to notice a quota being exceeded
+  /// without waiting for the end of the event loop tick.
+  pub fn op_metrics_factory_fn(self: &Rc<Self>) -> OpMetricsFactoryFn {
+    let sampler = self.clone();
+    Box::new(move |_, _, _| {
+      let sampler = sampler.clone();
+      Some(Rc::new(move |_: &deno_core::_ops::OpCtx, event, _| {
+        if matches!(event, OpMetricsEvent::Dispatched) {
+          sampler.sample();
+        }
+      }))
+    })
+  }
+}
+
+#[cfg(target_os = "linux")]
+fn thread_cpu_time() -> CpuTime {
+  fn to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64)
+      + Duration::from_micros(time.tv_usec as u64)
+  }
+
+  let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+  // SAFETY: `usage` is valid for writes of a `rusage`.
+  let ret = unsafe { libc::getrusage(libc::RUSAGE_THREAD, usage.as_mut_ptr()) };
+  if ret != 0 {
+    return CpuTime::default();
+  }
+  // SAFETY: getrusage initialized `usage`.
+  let usage = unsafe { usage.assume_init() };
+  CpuTime {
+    user: to_duration(usage.ru_utime),
+    system: to_duration(usage.ru_stime),
+  }
+}
+
+// The per-thread clock doesn't tell user and kernel time apart here, so all
+// of it is reported as user time.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn thread_cpu_time() -> CpuTime {
+  let mut time = libc::timespec {
+    tv_sec: 0,
+    tv_nsec: 0,
+  };
+  // SAFETY: `time` is valid for writes of a `timespec`.
+  let ret =
+    unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+  if ret != 0 {
+    return CpuTime::default();
+  }
+  CpuTime {
+    user: Duration::new(time.tv_sec as u64, time.tv_nsec as u32),
+    system: Duration::ZERO,
+  }
+}
+
+#[cfg(windows)]
+fn thread_cpu_time() -> CpuTime {
+  use winapi::shared::minwindef::FILETIME;
+  use winapi::um::processthreadsapi::GetCurrentThread;
+  use winapi::um::processthreadsapi::GetThreadTimes;
+
+  // FILETIME durations are in 100ns units.
+  fn to_duration(time: FILETIME) -> Duration {
+    let ticks =
+      ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64;
+    Duration::from_nanos(ticks * 100)
+  }
+
+  let empty = || FILETIME {
+    dwLowDateTime: 0,
+    dwHighDateTime: 0,
+  };
+  let (mut creation, mut exit, mut kernel, mut user) =
+    (empty(), empty(), empty(), empty());
+  // SAFETY: winapi call with valid out pointers; the pseudo handle of the
+  // current thread doesn't need to be closed.
+  let ok = unsafe {
+    GetThreadTimes(
+      GetCurrentThread(),
+      &mut creation,
+      &mut exit,
+      &mut kernel,
+      &mut user,
+    )
+  };
+  if ok == 0 {
+    return CpuTime::default();
+  }
+  CpuTime {
+    user: to_duration(user),
+    system: to_duration(kernel),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+  use std::time::Instant;
+
+  fn spin(duration: Duration) -> u64 {
+    let start = Instant::now();
+    let mut n = 0u64;
+    while start.elapsed() < duration {
+      n = std::hint::black_box(n.wrapping_add(1));
+    }
+    n
+  }
+
+  #[test]
+  fn busy_and_idle_threads_diverge() {
+    let run = |busy: bool| {
+      std::thread::spawn(move || {
+        let sampler = CpuTimeSampler::new(None);
+        for _ in 0..10 {
+          if busy {
+            spin(Duration::from_millis(20));
+          } else {
+            std::thread::sleep(Duration::from_millis(20));
+          }
+          sampler.sample();
+        }
+        sampler.counter().used()
+      })
+    };
+    let busy = run(true);
+    let idle = run(false);
+    let busy = busy.join().unwrap();
+    let idle = idle.join().unwrap();
+
+    assert!(busy.total() >= Duration::from_millis(50), "{busy:?}");
+    assert!(busy.total() > idle.total() * 2, "{busy:?} {idle:?}");
+    assert!(idle.total() < Duration::from_millis(50), "{idle:?}");
+  }
+
+  #[test]
+  fn counter_is_readable_from_another_thread() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let worker = std::thread::spawn(move || {
+      let sampler = CpuTimeSampler::new(None);
+      tx.send(sampler.counter()).unwrap();
+      spin(Duration::from_millis(50));
+      sampler.sample()
+    });
+    let counter = rx.recv().unwrap();
+    let last_sample = worker.join().unwrap();
+    assert_eq!(counter.used(), last_sample);
+    assert!(last_sample.total() > Duration::ZERO);
+  }
+
+  #[test]
+  fn quota_callback_fires_once() {
+    let fired = Arc::new(Mutex::new(vec![]));
+    let quota = CpuTimeQuota {
+      budget: Duration::from_millis(30),
+      on_exceeded: Arc::new({
+        let fired = fired.clone();
+        move |used| fired.lock().unwrap().push(used)
+      }),
+    };
+    std::thread::spawn(move || {
+      let sampler = CpuTimeSampler::new(Some(quota));
+      sampler.sample();
+      assert!(fired.lock().unwrap().is_empty());
+      for _ in 0..5 {
+        spin(Duration::from_millis(20));
+        sampler.sample();
+      }
+      let fired = fired.lock().unwrap();
+      assert_eq!(fired.len(), 1);
+      assert!(fired[0].total() > Duration::from_millis(30));
+    })
+    .join()
+    .unwrap();
+  }
+}
@@ -0,0 +1,223 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Deprecation of ops that are slated for removal.
+//!
+//! An op is deprecated by adding it to [`DEPRECATED_OPS`]. When a worker is
+//! created with [`DeprecatedOpsOptions`], every call to a deprecated op is
+//! counted, the first calls in the process are reported to a sink, and in
+//! strict mode calling the op throws instead of running it, so that CI can
+//! catch remaining uses before the op is removed.
+//!
+//! Calls are observed through the op metrics hooks. Those are only installed
+//! for deprecated ops, so other ops don't get slower.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::LazyLock;
+
+use deno_core::op2;
+use deno_core::parking_lot::Mutex;
+use deno_core::Extension;
+use deno_core::OpMetricsEvent;
+use deno_core::OpMetricsFactoryFn;
+
+/// Ops that are slated for removal.
+pub const DEPRECATED_OPS: &[DeprecatedOp] = &[
+  DeprecatedOp::new("op_run", Some("Deno.Command"), "3.0.0"),
+  DeprecatedOp::new("op_run_status", Some("Deno.Command"), "3.0.0"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeprecatedOp {
+  pub name: &'static str,
+  /// The op or API to use instead, if any.
+  pub replacement: Option<&'static str>,
+  /// The version that removes the op.
+  pub removal_version: &'static str,
+}
+
+impl DeprecatedOp {
+  pub const fn new(
+    name: &'static str,
+    replacement: Option<&'static str>,
+    removal_version: &'static str,
+  ) -> Self {
+    Self {
+      name,
+      replacement,
+      removal_version,
+    }
+  }
+}
+
+/// A call to a deprecated op, as reported to the sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeprecatedOpCall {
+  pub op: DeprecatedOp,
+  /// How many times the op was called in this process, including this call.
+  pub count: u64,
+}
+
+pub type DeprecatedOpSinkFn = dyn Fn(&DeprecatedOpCall) + Send + Sync;
+
+#[derive(Clone)]
+pub struct DeprecatedOpsOptions {
+  pub ops: Vec<DeprecatedOp>,
+  /// How many calls of each op are reported to the sink per process.
+  pub report_limit: u64,
+  /// Receives the reported calls. If `None`, they are logged as warnings.
+  pub sink: Option<Arc<DeprecatedOpSinkFn>>,
+  /// If true, calling a deprecated op throws a `NotSupported` error.
+  pub strict: bool,
+}
+
+impl Default for DeprecatedOpsOptions {
+  fn default() -> Self {
+    Self {
+      ops: DEPRECATED_OPS.to_vec(),
+      report_limit: 1,
+      sink: None,
+      strict: false,
+    }
+  }
+}
+
+impl DeprecatedOpsOptions {
+  /// Reads the mode from the `DENO_DEPRECATED_OPS` environment variable,
+  /// which is either `warn` or `strict`. Returns `None` if it isn't set.
+  pub fn from_env() -> Option<Self> {
+    let strict = match std::env::var("DENO_DEPRECATED_OPS").ok()?.as_str() {
+      "warn" => false,
+      "strict" => true,
+      _ => return None,
+    };
+    Some(Self {
+      strict,
+      ..Default::default()
+    })
+  }
+
+  /// In strict mode, replaces the deprecated ops of `extensions` with ops
+  /// that throw.
+  pub(crate) fn apply(&self, extensions: &mut [Extension]) {
+    if !self.strict {
+      return;
+    }
+    for extension in extensions {
+      if !extension.ops.iter().any(|op| self.find(op.name).is_some()) {
+        continue;
+      }
+      for op in extension.ops.to_mut() {
+        if self.find(op.name).is_none() {
+          continue;
+        }
+        *op = if op.is_async {
+          op.with_implementation_from(&op_deprecated_op_disabled_async())
+        } else {
+          op.with_implementation_from(&op_deprecated_op_disabled())
+        };
+      }
+    }
+  }
+
+  /// Returns an op metrics factory that records the calls of deprecated
+  /// ops.
+  pub(crate) fn op_metrics_factory_fn(&self) -> OpMetricsFactoryFn {
+    let options = Rc::new(self.clone());
+    Box::new(move |_, _, decl| {
+      let op = options.find(decl.name)?;
+      let options = options.clone();
+      Some(Rc::new(move |_: &deno_core::_ops::OpCtx, event, _| {
+        if matches!(event, OpMetricsEvent::Dispatched) {
+          options.record_call(op);
+        }
+      }))
+    })
+  }
+
+  fn find(&self, name: &str) -> Option<DeprecatedOp> {
+    self.ops.iter().find(|op| op.name == name).copied()
+  }
+
+  fn record_call(&self, op: DeprecatedOp) {
+    let count = {
+      let mut counts = CALL_COUNTS.lock();
+      let count = counts.entry(op.name).or_default();
+      *count += 1;
+      *count
+    };
+    if count <= self.report_limit {
+      let call = DeprecatedOpCall { op, count };
+      match &self.sink {
+        Some(sink) => sink(&call),
+        None => log::warn!("{}", DeprecatedOpError(op)),
+      }
+    }
+    if self.strict {
+      DISABLED_OP_CALL.set(Some(op));
+    }
+  }
+}
+
+static CALL_COUNTS: LazyLock<Mutex<HashMap<&'static str, u64>>> =
+  LazyLock::new(Default::default);
+
+/// Returns how many times each deprecated op was called in this process, by
+/// workers that track deprecated ops.
+pub fn deprecated_op_call_counts() -> Vec<(&'static str, u64)> {
+  let mut counts = CALL_COUNTS
+    .lock()
+    .iter()
+    .map(|(name, count)| (*name, *count))
+    .collect::<Vec<_>>();
+  counts.sort();
+  counts
+}
+
+thread_local! {
+  /// The deprecated op whose call is being dispatched in strict mode. The
+  /// metrics hook runs right before the op, and tells the op that replaced
+  /// it which one was called.
+  static DISABLED_OP_CALL: Cell<Option<DeprecatedOp>> = const { Cell::new(None) };
+}
+
+#[derive(Debug, thiserror::Error)]
+pub struct DeprecatedOpError(pub DeprecatedOp);
+
+impl std::fmt::Display for DeprecatedOpError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let op = self.0;
+    write!(
+      f,
+      "Op \"{}\" is deprecated and will be removed in Deno {}",
+      op.name, op.removal_version
+    )?;
+    if let Some(replacement) = op.replacement {
+      write!(f, ", use {replacement} instead")?;
+    }
+    Ok(())
+  }
+}
+
+fn disabled_op_error() -> DeprecatedOpError {
+  DeprecatedOpError(DISABLED_OP_CALL.take().unwrap_or(DeprecatedOp::new(
+    "<unknown>",
+    None,
+    "a future version",
+  )))
+}
+
+#[op2(fast)]
+fn op_deprecated_op_disabled() -> Result<(), DeprecatedOpError> {
+  Err(disabled_op_error())
+}
+
+#[op2(async)]
+fn op_deprecated_op_disabled_async(
+) -> impl Future<Output = Result<(), DeprecatedOpError>> {
+  let error = disabled_op_error();
+  async move { Err(error) }
+}
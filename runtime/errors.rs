@@ -9,11 +9,15 @@
 //!   Diagnostics are compile-time type errors, whereas JsErrors are runtime
 //!   exceptions.
 
+use crate::deprecated_ops::DeprecatedOpError;
+use crate::ops::blob_store::BlobStoreError;
 use crate::ops::fs_events::FsEventsError;
 use crate::ops::http::HttpStartError;
 use crate::ops::os::OsError;
 use crate::ops::process::ProcessError;
+use crate::ops::realm::RealmError;
 use crate::ops::signal::SignalError;
+use crate::ops::storage_archive::StorageArchiveError;
 use crate::ops::tty::TtyError;
 use crate::ops::web_worker::SyncFetchError;
 use crate::ops::worker_host::CreateWorkerError;
@@ -26,10 +30,13 @@ use deno_core::url;
 use deno_core::ModuleResolutionError;
 use deno_cron::CronError;
 use deno_crypto::DecryptError;
+use deno_crypto::DigestError;
 use deno_crypto::EncryptError;
 use deno_crypto::ExportKeyError;
 use deno_crypto::GenerateKeyError;
 use deno_crypto::ImportKeyError;
+use deno_fetch::BodyToFileError;
+use deno_fetch::DownloadError;
 use deno_fetch::FetchError;
 use deno_fetch::HttpClientCreateError;
 use deno_ffi::CallError;
@@ -38,6 +45,7 @@ use deno_ffi::DlfcnError;
 use deno_ffi::IRError;
 use deno_ffi::ReprError;
 use deno_ffi::StaticError;
+use deno_fs::FsLimitExceeded;
 use deno_fs::FsOpsError;
 use deno_http::HttpError;
 use deno_http::HttpNextError;
@@ -48,14 +56,20 @@ use deno_kv::KvError;
 use deno_kv::KvMutationError;
 use deno_napi::NApiError;
 use deno_net::ops::NetError;
+use deno_permissions::FrozenReadError;
 use deno_tls::TlsError;
 use deno_web::BlobError;
+use deno_web::ByteCodecError;
+use deno_web::ChannelError;
 use deno_web::CompressionError;
 use deno_web::MessagePortError;
+use deno_web::OpError;
+use deno_web::OpErrorDetails;
 use deno_web::StreamResourceError;
 use deno_web::WebError;
 use deno_websocket::HandshakeError;
 use deno_websocket::WebsocketError;
+use deno_webstorage::rusqlite;
 use deno_webstorage::WebStorageError;
 use rustyline::error::ReadlineError;
 use std::env;
@@ -83,38 +97,22 @@ fn get_env_var_error_class(error: &env::VarError) -> &'static str {
 }
 
 fn get_io_error_class(error: &io::Error) -> &'static str {
-  use io::ErrorKind::*;
-  match error.kind() {
-    NotFound => "NotFound",
-    PermissionDenied => "PermissionDenied",
-    ConnectionRefused => "ConnectionRefused",
-    ConnectionReset => "ConnectionReset",
-    ConnectionAborted => "ConnectionAborted",
-    NotConnected => "NotConnected",
-    AddrInUse => "AddrInUse",
-    AddrNotAvailable => "AddrNotAvailable",
-    BrokenPipe => "BrokenPipe",
-    AlreadyExists => "AlreadyExists",
-    InvalidInput => "TypeError",
-    InvalidData => "InvalidData",
-    TimedOut => "TimedOut",
-    Interrupted => "Interrupted",
-    WriteZero => "WriteZero",
-    UnexpectedEof => "UnexpectedEof",
-    Other => "Error",
-    WouldBlock => "WouldBlock",
-    // Non-exhaustive enum - might add new variants
-    // in the future
-    kind => {
-      let kind_str = kind.to_string();
-      match kind_str.as_str() {
-        "FilesystemLoop" => "FilesystemLoop",
-        "IsADirectory" => "IsADirectory",
-        "NetworkUnreachable" => "NetworkUnreachable",
-        "NotADirectory" => "NotADirectory",
-        _ => "Error",
-      }
-    }
+  if let Some(e) = error
+    .get_ref()
+    .and_then(|e| e.downcast_ref::<FrozenReadError>())
+  {
+    return get_frozen_read_error_class(e);
+  }
+  if error.get_ref().is_some_and(|e| e.is::<FsLimitExceeded>()) {
+    return "LimitExceeded";
+  }
+  deno_web::io_error_class(error.kind())
+}
+
+fn get_frozen_read_error_class(error: &FrozenReadError) -> &'static str {
+  match error {
+    FrozenReadError::NotFound(_) => "NotFound",
+    FrozenReadError::StaleRead(_) => "StaleRead",
   }
 }
 
@@ -206,6 +204,7 @@ fn get_webgpu_error_class(e: &deno_webgpu::InitError) -> &'static str {
     deno_webgpu::InitError::InvalidAdapter(_) => "Error",
     deno_webgpu::InitError::RequestDevice(_) => "DOMExceptionOperationError",
     deno_webgpu::InitError::InvalidDevice(_) => "Error",
+    deno_webgpu::InitError::MissingFeature { .. } => "TypeError",
   }
 }
 
@@ -292,7 +291,6 @@ fn get_crypto_decrypt_error_class(e: &DecryptError) -> &'static str {
     DecryptError::InvalidKeyOrIv => "DOMExceptionOperationError",
     DecryptError::TooMuchData => "DOMExceptionOperationError",
     DecryptError::InvalidIvLength => "TypeError",
-    DecryptError::Rsa(_) => "DOMExceptionOperationError",
   }
 }
 
@@ -343,7 +341,6 @@ fn get_crypto_export_key_error_class(e: &ExportKeyError) -> &'static str {
   match e {
     ExportKeyError::General(e) => get_crypto_shared_error_class(e),
     ExportKeyError::Der(_) => "Error",
-    ExportKeyError::UnsupportedNamedCurve => "DOMExceptionNotSupportedError",
   }
 }
 
@@ -374,6 +371,10 @@ fn get_crypto_import_key_error_class(e: &ImportKeyError) -> &'static str {
     ImportKeyError::InvalidB64Coordinate => "DOMExceptionDataError",
     ImportKeyError::InvalidRSAPublicKey => "DOMExceptionDataError",
     ImportKeyError::InvalidRSAPrivateKey => "DOMExceptionDataError",
+    ImportKeyError::InsecureRSAModulusLength => "DOMExceptionDataError",
+    ImportKeyError::UnsupportedRSAModulusLength => "DOMExceptionDataError",
+    ImportKeyError::InvalidRSAPrimeFactors => "DOMExceptionDataError",
+    ImportKeyError::InconsistentRSAPrivateKey => "DOMExceptionDataError",
     ImportKeyError::UnsupportedAlgorithm => "DOMExceptionDataError",
     ImportKeyError::PublicKeyTooLong => "DOMExceptionDataError",
     ImportKeyError::PrivateKeyTooLong => "DOMExceptionDataError",
@@ -384,6 +385,7 @@ fn get_crypto_import_key_error_class(e: &ImportKeyError) -> &'static str {
     ImportKeyError::CurveMismatch => "DOMExceptionDataError",
     ImportKeyError::InvalidKeyData => "DOMExceptionDataError",
     ImportKeyError::InvalidJWKPrivateKey => "DOMExceptionDataError",
+    ImportKeyError::PublicKeyMismatch => "DOMExceptionDataError",
     ImportKeyError::EllipticCurve(_) => "DOMExceptionDataError",
     ImportKeyError::ExpectedValidPkcs8Data => "DOMExceptionDataError",
     ImportKeyError::MalformedParameters => "DOMExceptionDataError",
@@ -409,6 +411,51 @@ fn get_crypto_x25519_error_class(e: &deno_crypto::X25519Error) -> &'static str {
   }
 }
 
+fn get_crypto_password_wrap_error_class(
+  e: &deno_crypto::PasswordWrapError,
+) -> &'static str {
+  match e {
+    deno_crypto::PasswordWrapError::InvalidIterations
+    | deno_crypto::PasswordWrapError::InvalidSaltLength
+    | deno_crypto::PasswordWrapError::EmptyData => "TypeError",
+    deno_crypto::PasswordWrapError::WrapFailed
+    | deno_crypto::PasswordWrapError::UnwrapFailed => {
+      "DOMExceptionOperationError"
+    }
+    deno_crypto::PasswordWrapError::JoinError(_) => "Error",
+  }
+}
+
+fn get_crypto_ssh_error_class(e: &deno_crypto::SshError) -> &'static str {
+  match e {
+    deno_crypto::SshError::Resource(e) => {
+      get_error_class_name(e).unwrap_or("Error")
+    }
+    deno_crypto::SshError::InvalidPublicKey(_)
+    | deno_crypto::SshError::InvalidPrivateKey(_)
+    | deno_crypto::SshError::InvalidSignature(_)
+    | deno_crypto::SshError::PassphraseRequired
+    | deno_crypto::SshError::UnsupportedKdf
+    | deno_crypto::SshError::EmptyNamespace => "TypeError",
+    deno_crypto::SshError::UnsupportedAlgorithm(_) => "NotSupported",
+    deno_crypto::SshError::WrongPassphrase => "DOMExceptionOperationError",
+    deno_crypto::SshError::SignFailed(_)
+    | deno_crypto::SshError::JoinError(_) => "Error",
+  }
+}
+
+fn get_crypto_digest_error_class(e: &DigestError) -> &'static str {
+  match e {
+    DigestError::General(e) => get_crypto_shared_error_class(e),
+    DigestError::Resource(e) | DigestError::Permission(e) => {
+      get_error_class_name(e).unwrap_or("Error")
+    }
+    DigestError::Io(e) => get_io_error_class(e),
+    DigestError::JoinError(_) => "Error",
+    DigestError::Finalized => "BadResource",
+  }
+}
+
 fn get_crypto_error_class(e: &deno_crypto::Error) -> &'static str {
   match e {
     deno_crypto::Error::Der(_) => "Error",
@@ -444,7 +491,8 @@ fn get_napi_error_class(e: &NApiError) -> &'static str {
   match e {
     NApiError::InvalidPath
     | NApiError::LibLoading(_)
-    | NApiError::ModuleNotFound(_) => "TypeError",
+    | NApiError::ModuleNotFound(_)
+    | NApiError::UnsupportedVersion { .. } => "TypeError",
     NApiError::Permission(e) => get_error_class_name(e).unwrap_or("Error"),
   }
 }
@@ -495,6 +543,25 @@ fn get_web_stream_resource_error_class(
   }
 }
 
+fn get_web_byte_codec_error_class(e: &ByteCodecError) -> &'static str {
+  match e {
+    ByteCodecError::OutOfBounds { .. } => "RangeError",
+    ByteCodecError::InvalidWidth(_) => "TypeError",
+    ByteCodecError::ValueOutOfRange { .. } => "RangeError",
+    ByteCodecError::VarintOverflow(_) => "RangeError",
+    ByteCodecError::CapacityTooLarge(_) => "RangeError",
+    ByteCodecError::Taken => "BadResource",
+  }
+}
+
+fn get_web_channel_error_class(e: &ChannelError) -> &'static str {
+  match e {
+    ChannelError::InvalidCapacity => "RangeError",
+    ChannelError::Closed => "BadResource",
+    ChannelError::TimedOut => "TimedOut",
+  }
+}
+
 fn get_web_blob_error_class(e: &BlobError) -> &'static str {
   match e {
     BlobError::BlobPartNotFound => "TypeError",
@@ -566,20 +633,27 @@ fn get_ffi_call_error_class(e: &CallError) -> &'static str {
 fn get_webstorage_class_name(e: &WebStorageError) -> &'static str {
   match e {
     WebStorageError::ContextNotSupported => "DOMExceptionNotSupportedError",
-    WebStorageError::Sqlite(_) => todo!(),
-    WebStorageError::Io(e) => get_io_error_class(e),
+    WebStorageError::Sqlite(rusqlite::Error::SqliteFailure(e, _))
+      if e.code == rusqlite::ErrorCode::DiskFull =>
+    {
+      "DOMExceptionQuotaExceededError"
+    }
+    // A locked, unreadable or corrupt database.
+    WebStorageError::Sqlite(_) => "DOMExceptionOperationError",
+    WebStorageError::Io(_) => "DOMExceptionOperationError",
     WebStorageError::StorageExceeded => "DOMExceptionQuotaExceededError",
   }
 }
 
 fn get_tls_error_class(e: &TlsError) -> &'static str {
   match e {
-    TlsError::Rustls(_) => "Error",
+    TlsError::Rustls(_) | TlsError::Verifier(_) => "Error",
     TlsError::UnableAddPemFileToCert(e) => get_io_error_class(e),
     TlsError::CertInvalid
     | TlsError::CertsNotFound
     | TlsError::KeysNotFound
-    | TlsError::KeyDecode => "InvalidData",
+    | TlsError::KeyDecode
+    | TlsError::InvalidPin(_) => "InvalidData",
   }
 }
 
@@ -594,6 +668,8 @@ pub fn get_cron_error_class(e: &CronError) -> &'static str {
     CronError::TooManyCrons => "TypeError",
     CronError::InvalidCron => "TypeError",
     CronError::InvalidBackoff => "TypeError",
+    CronError::InvalidInterval => "TypeError",
+    CronError::StateFile(e) => get_io_error_class(e),
     CronError::AcquireError(_) => "Error",
     CronError::Other(e) => get_error_class_name(e).unwrap_or("Error"),
   }
@@ -657,6 +733,50 @@ fn get_fetch_error(error: &FetchError) -> &'static str {
   }
 }
 
+fn get_download_error(error: &DownloadError) -> &'static str {
+  match error {
+    DownloadError::Resource(e) | DownloadError::Permission(e) => {
+      get_error_class_name(e).unwrap_or("Error")
+    }
+    DownloadError::Url(e) => get_url_parse_error_class(e),
+    DownloadError::InvalidUrl(_) => "TypeError",
+    DownloadError::SchemeNotSupported(_) => "TypeError",
+    DownloadError::InvalidChecksum(_) => "TypeError",
+    DownloadError::ReservedHeader(_) => "TypeError",
+    DownloadError::InvalidHeaderName(_) => "TypeError",
+    DownloadError::InvalidHeaderValue(_) => "TypeError",
+    DownloadError::ClientCreate(e) => get_http_client_create_error(e),
+    DownloadError::ClientSend(_) => "TypeError",
+    DownloadError::RequestBuilderHook(_) => "TypeError",
+    DownloadError::Status(_) => "Http",
+    DownloadError::TooManyRedirects(_) => "Http",
+    DownloadError::InvalidRedirect(_) => "Http",
+    DownloadError::InvalidContentRange(_) => "Http",
+    DownloadError::ContentLengthMismatch { .. } => "Http",
+    DownloadError::ExceededLength(_) => "Http",
+    DownloadError::SizeChanged { .. } => "Http",
+    DownloadError::Interrupted(_) => "UnexpectedEof",
+    DownloadError::ChecksumMismatch { .. } => "InvalidData",
+    DownloadError::Io(e) => get_io_error_class(e),
+    DownloadError::Canceled(e) => {
+      let io_err: io::Error = e.to_owned().into();
+      get_io_error_class(&io_err)
+    }
+    DownloadError::RetryFailed { source, .. } => get_download_error(source),
+  }
+}
+
+fn get_body_to_file_error(error: &BodyToFileError) -> &'static str {
+  match error {
+    BodyToFileError::Resource(e) | BodyToFileError::Permission(e) => {
+      get_error_class_name(e).unwrap_or("Error")
+    }
+    BodyToFileError::Interrupted { .. } => "TypeError",
+    BodyToFileError::Io(e) => get_io_error_class(e),
+    BodyToFileError::Canceled(_) => "Interrupted",
+  }
+}
+
 fn get_http_client_create_error(error: &HttpClientCreateError) -> &'static str {
   match error {
     HttpClientCreateError::Tls(_) => "TypeError",
@@ -724,6 +844,25 @@ fn get_fs_ops_error(error: &FsOpsError) -> &'static str {
     FsOpsError::InvalidTrailingCharacter => "Error",
     FsOpsError::NotCapableAccess { .. } => "NotCapable",
     FsOpsError::NotCapable(_) => "NotCapable",
+    FsOpsError::InvalidLogFileOption(_) => "TypeError",
+    FsOpsError::InvalidNdjsonOption(_) => "TypeError",
+    FsOpsError::InvalidGlobPattern(..) => "TypeError",
+    FsOpsError::InvalidSearchPattern(_) => "TypeError",
+    FsOpsError::InvalidSearchInput => "TypeError",
+    FsOpsError::InvalidIntegrityManifest(_) => "TypeError",
+    FsOpsError::MmapUnsupported(_) => "NotSupported",
+    FsOpsError::TempAlreadyPersisted => "BadResource",
+    FsOpsError::Walk(e) => get_walk_error_class(e),
+    FsOpsError::WindowsPath(_) => "TypeError",
+    FsOpsError::Interrupted(_) => "Interrupted",
+  }
+}
+
+fn get_walk_error_class(error: &deno_fs::WalkError) -> &'static str {
+  match error {
+    deno_fs::WalkError::Io { source, .. } => get_fs_error(source),
+    deno_fs::WalkError::SymlinkCycle { .. } => "FilesystemLoop",
+    deno_fs::WalkError::Visit(e) => get_error_class_name(e).unwrap_or("Error"),
   }
 }
 
@@ -800,6 +939,17 @@ fn get_net_error(error: &NetError) -> &'static str {
     NetError::Tls(e) => get_tls_error_class(e),
     NetError::ListenTlsRequiresKey => "InvalidData",
     NetError::Reunite(_) => "Error",
+    NetError::Resolve(e) => get_io_error_class(e),
+    NetError::InvalidRetryPolicy(_) => "TypeError",
+    NetError::RetryFailed { source, .. } => get_net_error(source),
+    NetError::ProxySameResource => "TypeError",
+    NetError::ProxyIdleTimeout(_) => "TimedOut",
+    NetError::SendFileNotAFile => "BadResource",
+    NetError::Stream(e) => get_error_class_name(e).unwrap_or("Error"),
+    NetError::NamedPipeUnsupported => "NotSupported",
+    NetError::InvalidPipeName(_) => "TypeError",
+    NetError::InvalidPipeInstances(_) => "RangeError",
+    NetError::InvalidSecurityDescriptor(_) => "TypeError",
   }
 }
 
@@ -830,6 +980,11 @@ fn get_tty_error(error: &TtyError) -> &'static str {
       get_error_class_name(e).unwrap_or("Error")
     }
     TtyError::Io(e) => get_io_error_class(e),
+    TtyError::NotATerminal => "BadResource",
+    TtyError::Canceled(e) => {
+      let io_err: io::Error = e.to_owned().into();
+      get_io_error_class(&io_err)
+    }
     #[cfg(unix)]
     TtyError::Nix(e) => get_nix_error_class(e),
   }
@@ -866,10 +1021,6 @@ fn get_fs_events_error(error: &FsEventsError) -> &'static str {
       get_error_class_name(e).unwrap_or("Error")
     }
     FsEventsError::Notify(e) => get_notify_error_class(e),
-    FsEventsError::Canceled(e) => {
-      let io_err: io::Error = e.to_owned().into();
-      get_io_error_class(&io_err)
-    }
   }
 }
 
@@ -889,6 +1040,7 @@ fn get_http_start_error(error: &HttpStartError) -> &'static str {
 fn get_process_error(error: &ProcessError) -> &'static str {
   match error {
     ProcessError::SpawnFailed { error, .. } => get_process_error(error),
+    ProcessError::Op(e) => e.class(),
     ProcessError::FailedResolvingCwd(e) | ProcessError::Io(e) => {
       get_io_error_class(e)
     }
@@ -901,11 +1053,50 @@ fn get_process_error(error: &ProcessError) -> &'static str {
     ProcessError::Signal(e) => get_signal_error(e),
     ProcessError::MissingCmd => "Error",
     ProcessError::InvalidPid => "TypeError",
+    ProcessError::IpcMessageTooLarge => "RangeError",
     #[cfg(unix)]
     ProcessError::Nix(e) => get_nix_error_class(e),
   }
 }
 
+fn get_blob_store_error(error: &BlobStoreError) -> &'static str {
+  match error {
+    BlobStoreError::NotAvailable => "NotSupported",
+    BlobStoreError::InvalidHash(_) => "TypeError",
+    BlobStoreError::HashMismatch(_) => "InvalidData",
+    BlobStoreError::NotFound(_) => "NotFound",
+    BlobStoreError::CorruptRefCount(_) => "InvalidData",
+    BlobStoreError::Resource(e) => get_error_class_name(e).unwrap_or("Error"),
+    BlobStoreError::Io(e) => get_io_error_class(e),
+  }
+}
+
+fn get_storage_archive_error(error: &StorageArchiveError) -> &'static str {
+  match error {
+    StorageArchiveError::NotAvailable => "NotSupported",
+    StorageArchiveError::UnknownOrigin(_) => "NotFound",
+    StorageArchiveError::InvalidArchive(_)
+    | StorageArchiveError::UnsupportedVersion { .. }
+    | StorageArchiveError::NewerSchema(_)
+    | StorageArchiveError::ChecksumMismatch(_) => "InvalidData",
+    StorageArchiveError::Canceled => "Interrupted",
+    StorageArchiveError::Sqlite(_) => "Error",
+    StorageArchiveError::BlobStore(e) => get_blob_store_error(e),
+    StorageArchiveError::Resource(e) | StorageArchiveError::Permission(e) => {
+      get_error_class_name(e).unwrap_or("Error")
+    }
+    StorageArchiveError::Io(e) => get_io_error_class(e),
+  }
+}
+
+fn get_realm_error(error: &RealmError) -> &'static str {
+  match error {
+    RealmError::Resource(e) => get_error_class_name(e).unwrap_or("Error"),
+    RealmError::NotAFunction(_) => "TypeError",
+    RealmError::TimedOut(_) => "TimedOut",
+  }
+}
+
 fn get_http_error(error: &HttpError) -> &'static str {
   match error {
     HttpError::Canceled(e) => {
@@ -982,6 +1173,7 @@ mod node {
   use deno_node::ops::os::priority::PriorityError;
   pub use deno_node::ops::os::OsError;
   pub use deno_node::ops::require::RequireError;
+  pub use deno_node::ops::v8::V8Error;
   pub use deno_node::ops::worker_threads::WorkerThreadsFilenameError;
   pub use deno_node::ops::zlib::brotli::BrotliError;
   pub use deno_node::ops::zlib::mode::ModeError;
@@ -1089,6 +1281,13 @@ mod node {
     }
   }
 
+  pub fn get_v8_error(error: &V8Error) -> &'static str {
+    match error {
+      V8Error::Permission(e) => get_error_class_name(e).unwrap_or("Error"),
+      V8Error::Io(e) => get_io_error_class(e),
+    }
+  }
+
   pub fn get_brotli_error(error: &BrotliError) -> &'static str {
     match error {
       BrotliError::InvalidEncoderMode => "TypeError",
@@ -1144,6 +1343,10 @@ fn get_sync_fetch_error(error: &SyncFetchError) -> &'static str {
 pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
   deno_core::error::get_custom_error_class(e)
     .or_else(|| e.downcast_ref::<FsError>().map(get_fs_error))
+    .or_else(|| {
+      e.downcast_ref::<FrozenReadError>()
+        .map(get_frozen_read_error_class)
+    })
     .or_else(|| {
       e.downcast_ref::<node::BlocklistError>()
         .map(node::get_blocklist_error)
@@ -1171,6 +1374,7 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
         .map(node::get_http2_error)
     })
     .or_else(|| e.downcast_ref::<node::OsError>().map(node::get_os_error))
+    .or_else(|| e.downcast_ref::<node::V8Error>().map(node::get_v8_error))
     .or_else(|| {
       e.downcast_ref::<node::BrotliError>()
         .map(node::get_brotli_error)
@@ -1194,7 +1398,18 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
     .or_else(|| e.downcast_ref::<SignalError>().map(get_signal_error))
     .or_else(|| e.downcast_ref::<FsEventsError>().map(get_fs_events_error))
     .or_else(|| e.downcast_ref::<HttpStartError>().map(get_http_start_error))
+    .or_else(|| e.downcast_ref::<OpError>().map(OpError::class))
     .or_else(|| e.downcast_ref::<ProcessError>().map(get_process_error))
+    .or_else(|| e.downcast_ref::<BlobStoreError>().map(get_blob_store_error))
+    .or_else(|| e.downcast_ref::<RealmError>().map(get_realm_error))
+    .or_else(|| {
+      e.downcast_ref::<DeprecatedOpError>()
+        .map(|_| "NotSupported")
+    })
+    .or_else(|| {
+      e.downcast_ref::<StorageArchiveError>()
+        .map(get_storage_archive_error)
+    })
     .or_else(|| e.downcast_ref::<OsError>().map(get_os_error))
     .or_else(|| e.downcast_ref::<SyncFetchError>().map(get_sync_fetch_error))
     .or_else(|| {
@@ -1210,6 +1425,14 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
         .map(get_web_stream_resource_error_class)
     })
     .or_else(|| e.downcast_ref::<BlobError>().map(get_web_blob_error_class))
+    .or_else(|| {
+      e.downcast_ref::<ChannelError>()
+        .map(get_web_channel_error_class)
+    })
+    .or_else(|| {
+      e.downcast_ref::<ByteCodecError>()
+        .map(get_web_byte_codec_error_class)
+    })
     .or_else(|| e.downcast_ref::<IRError>().map(|_| "TypeError"))
     .or_else(|| e.downcast_ref::<ReprError>().map(get_ffi_repr_error_class))
     .or_else(|| e.downcast_ref::<HttpError>().map(get_http_error))
@@ -1243,6 +1466,11 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
     })
     .or_else(|| e.downcast_ref::<KvError>().map(get_kv_error))
     .or_else(|| e.downcast_ref::<FetchError>().map(get_fetch_error))
+    .or_else(|| e.downcast_ref::<DownloadError>().map(get_download_error))
+    .or_else(|| {
+      e.downcast_ref::<BodyToFileError>()
+        .map(get_body_to_file_error)
+    })
     .or_else(|| {
       e.downcast_ref::<HttpClientCreateError>()
         .map(get_http_client_create_error)
@@ -1316,10 +1544,22 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
       e.downcast_ref::<deno_crypto::X25519Error>()
         .map(get_crypto_x25519_error_class)
     })
+    .or_else(|| {
+      e.downcast_ref::<deno_crypto::PasswordWrapError>()
+        .map(get_crypto_password_wrap_error_class)
+    })
+    .or_else(|| {
+      e.downcast_ref::<deno_crypto::SshError>()
+        .map(get_crypto_ssh_error_class)
+    })
     .or_else(|| {
       e.downcast_ref::<deno_crypto::Error>()
         .map(get_crypto_error_class)
     })
+    .or_else(|| {
+      e.downcast_ref::<DigestError>()
+        .map(get_crypto_digest_error_class)
+    })
     .or_else(|| {
       e.downcast_ref::<WebStorageError>()
         .map(get_webstorage_class_name)
@@ -1386,3 +1626,115 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
       (maybe_get_nix_error_class)()
     })
 }
+
+/// Returns the class an op error with the given `class` is thrown with.
+///
+/// Errors that carry an [`OpError`], as well as net and process errors caused
+/// by an `io::Error`, are thrown with the [`deno_web::STRUCTURED_ERROR_CLASS`]
+/// so that `code`, `retryable` and `cause` are attached to the JS error. Their
+/// details are staged here, right before deno_core builds the error.
+pub fn get_op_error_class_name(
+  class: &'static str,
+  e: &AnyError,
+) -> &'static str {
+  let details = e
+    .downcast_ref::<OpError>()
+    .map(|e| e.details(class))
+    .or_else(|| {
+      e.downcast_ref::<NetError>()
+        .and_then(|e| get_net_op_error_details(class, e))
+    })
+    .or_else(|| {
+      e.downcast_ref::<ProcessError>()
+        .and_then(|e| get_process_op_error_details(class, e))
+    })
+    .or_else(|| {
+      e.downcast_ref::<ByteCodecError>()
+        .and_then(|e| get_byte_codec_op_error_details(class, e))
+    });
+  match details {
+    Some(details) => {
+      deno_web::stage_op_error_details(details);
+      deno_web::STRUCTURED_ERROR_CLASS
+    }
+    None => class,
+  }
+}
+
+fn get_io_op_error_details(
+  class: &'static str,
+  error: &io::Error,
+) -> OpErrorDetails {
+  OpErrorDetails {
+    class,
+    code: deno_web::io_error_code(error.kind()).map(str::to_string),
+    retryable: deno_web::is_retryable_io_error(error.kind()),
+    causes: vec![],
+    properties: Default::default(),
+  }
+}
+
+/// Out of bounds reads carry where they happened, as `error.offset` and
+/// `error.width`.
+fn get_byte_codec_op_error_details(
+  class: &'static str,
+  error: &ByteCodecError,
+) -> Option<OpErrorDetails> {
+  let ByteCodecError::OutOfBounds { offset, width, .. } = error else {
+    return None;
+  };
+  let mut properties = serde_json::Map::new();
+  properties.insert("offset".to_string(), (*offset).into());
+  properties.insert("width".to_string(), (*width).into());
+  Some(OpErrorDetails {
+    class,
+    code: Some("ERR_OUT_OF_BOUNDS".to_string()),
+    retryable: false,
+    causes: vec![],
+    properties,
+  })
+}
+
+/// Connections that failed after being retried carry the attempt count and
+/// the cumulative backoff, as `error.attempts` and `error.totalDelayMs`.
+fn get_net_op_error_details(
+  class: &'static str,
+  error: &NetError,
+) -> Option<OpErrorDetails> {
+  match error {
+    NetError::Io(e) => Some(get_io_op_error_details(class, e)),
+    NetError::RetryFailed {
+      source,
+      attempts,
+      total_delay_ms,
+    } => {
+      let mut details =
+        get_net_op_error_details(class, source).unwrap_or(OpErrorDetails {
+          class,
+          code: None,
+          retryable: false,
+          causes: vec![],
+          properties: Default::default(),
+        });
+      details
+        .properties
+        .insert("attempts".to_string(), (*attempts).into());
+      details
+        .properties
+        .insert("totalDelayMs".to_string(), (*total_delay_ms).into());
+      Some(details)
+    }
+    _ => None,
+  }
+}
+
+fn get_process_op_error_details(
+  class: &'static str,
+  error: &ProcessError,
+) -> Option<OpErrorDetails> {
+  match error {
+    ProcessError::Op(e) => Some(e.details(class)),
+    ProcessError::Io(e) => Some(get_io_op_error_details(class, e)),
+    _ => None,
+  }
+}
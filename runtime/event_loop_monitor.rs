@@ -0,0 +1,402 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Event loop lag measurement and a guard against ticks that starve it.
+//!
+//! The lag of a tick is the time between the event loop being woken up, e.g.
+//! by a timer firing or an op completing, and the tick starting to run. It
+//! grows when earlier ticks run for long, typically because JavaScript keeps
+//! the microtask queue busy, which holds back timers and op completions.
+//!
+//! With a [`TickBudget`], a watchdog thread notices ticks that run for longer
+//! than the budget. The worker then logs a warning with the JavaScript stack
+//! of the running code and, in [`TickBudgetMode::Yield`], terminates the
+//! running JavaScript so that the event loop gets to run timers and I/O
+//! again. V8 discards the microtasks that were still queued at that point, so
+//! the yield mode is meant for containing misbehaving code.
+
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_core::futures::task::waker_ref;
+use deno_core::futures::task::ArcWake;
+use deno_core::futures::task::AtomicWaker;
+use deno_core::v8;
+use deno_terminal::colors;
+
+/// Number of JavaScript frames included in the warning.
+const STACK_FRAME_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickBudgetMode {
+  /// Log a warning with the JavaScript stack of the running code.
+  Warn,
+  /// Log the warning, then terminate the running JavaScript and return to
+  /// the event loop.
+  Yield,
+}
+
+/// The time a single event loop tick, including the microtasks it queues,
+/// may run for before the worker intervenes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickBudget {
+  pub budget: Duration,
+  pub mode: TickBudgetMode,
+}
+
+/// Lag statistics of a worker's event loop.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EventLoopLag {
+  /// Lag of the most recent tick.
+  pub last: Duration,
+  /// Largest lag seen so far.
+  pub max: Duration,
+  /// Number of ticks measured.
+  pub ticks: u64,
+  /// Number of ticks that ran for longer than the tick budget.
+  pub overruns: u64,
+}
+
+/// Records when the event loop was first woken up since its last tick, and
+/// forwards the wake-up to the task running the event loop.
+#[derive(Default)]
+struct WakeRecorder {
+  woken_at: Mutex<Option<Instant>>,
+  waker: AtomicWaker,
+}
+
+impl ArcWake for WakeRecorder {
+  fn wake_by_ref(arc_self: &Arc<Self>) {
+    arc_self
+      .woken_at
+      .lock()
+      .unwrap()
+      .get_or_insert_with(Instant::now);
+    arc_self.waker.wake();
+  }
+}
+
+#[derive(Default)]
+struct TickState {
+  /// Incremented when a tick starts.
+  tick: u64,
+  /// Set while a tick is running.
+  started: Option<Instant>,
+  closed: bool,
+}
+
+/// The running tick, shared with the watchdog thread.
+#[derive(Default)]
+struct TickClock {
+  state: Mutex<TickState>,
+  changed: Condvar,
+  overruns: AtomicU64,
+  /// Set when the running JavaScript was terminated to force a yield.
+  yielded: AtomicBool,
+}
+
+impl TickClock {
+  fn is_running(&self, tick: u64) -> bool {
+    let state = self.state.lock().unwrap();
+    state.tick == tick && state.started.is_some()
+  }
+}
+
+/// A tick that ran for longer than the budget.
+struct Overrun {
+  clock: Arc<TickClock>,
+  budget: TickBudget,
+  tick: u64,
+  elapsed: Duration,
+}
+
+/// Measures the lag of a worker's event loop and, with a [`TickBudget`],
+/// watches for ticks that run for too long. Must be used on the thread of
+/// the worker it monitors.
+pub(crate) struct EventLoopMonitor {
+  wake: Arc<WakeRecorder>,
+  lag: Cell<EventLoopLag>,
+  budget: Option<TickBudget>,
+  clock: Arc<TickClock>,
+}
+
+impl EventLoopMonitor {
+  pub fn new(budget: Option<TickBudget>) -> Self {
+    Self {
+      wake: Default::default(),
+      lag: Default::default(),
+      budget,
+      clock: Default::default(),
+    }
+  }
+
+  pub fn lag(&self) -> EventLoopLag {
+    EventLoopLag {
+      overruns: self.clock.overruns.load(Ordering::Relaxed),
+      ..self.lag.get()
+    }
+  }
+
+  /// Starts the watchdog thread, if a tick budget is configured. Ticks that
+  /// overrun the budget are reported by interrupting `isolate`.
+  pub fn start_watchdog(&self, isolate: v8::IsolateHandle) {
+    self.start_watchdog_with(move |overrun| {
+      let data = Box::into_raw(Box::new(overrun));
+      if !isolate.request_interrupt(report_overrun, data as *mut c_void) {
+        // The isolate is gone, so the callback will never run.
+        // SAFETY: `data` was created by `Box::into_raw` above and not passed
+        // on.
+        drop(unsafe { Box::from_raw(data) });
+      }
+    });
+  }
+
+  fn start_watchdog_with(&self, on_overrun: impl Fn(Overrun) + Send + 'static) {
+    let Some(budget) = self.budget else {
+      return;
+    };
+    let clock = self.clock.clone();
+    std::thread::Builder::new()
+      .name("event-loop-watchdog".to_string())
+      .spawn(move || watch(clock, budget, on_overrun))
+      .expect("failed to spawn the event loop watchdog thread");
+  }
+
+  /// Runs one tick of the event loop through `poll`, measuring its lag and
+  /// letting the watchdog time it.
+  pub fn poll<T>(
+    &self,
+    cx: &mut Context,
+    poll: impl FnOnce(&mut Context) -> Poll<T>,
+  ) -> Poll<T> {
+    self.wake.waker.register(cx.waker());
+    let now = Instant::now();
+    let woken_at = self.wake.woken_at.lock().unwrap().take();
+    let lag = woken_at.map(|at| now.saturating_duration_since(at));
+    let mut stats = self.lag.get();
+    stats.last = lag.unwrap_or_default();
+    stats.max = stats.max.max(stats.last);
+    stats.ticks += 1;
+    self.lag.set(stats);
+
+    if self.budget.is_some() {
+      let mut state = self.clock.state.lock().unwrap();
+      state.tick += 1;
+      state.started = Some(now);
+      self.clock.changed.notify_all();
+    }
+    let waker = waker_ref(&self.wake);
+    let result = poll(&mut Context::from_waker(&waker));
+    if self.budget.is_some() {
+      self.clock.state.lock().unwrap().started = None;
+      self.clock.changed.notify_all();
+    }
+    result
+  }
+
+  /// Returns whether the running JavaScript was terminated to force a yield
+  /// since the last call. The caller must then cancel the termination.
+  pub fn take_yield(&self) -> bool {
+    self.clock.yielded.swap(false, Ordering::Relaxed)
+  }
+}
+
+impl Drop for EventLoopMonitor {
+  fn drop(&mut self) {
+    self.clock.state.lock().unwrap().closed = true;
+    self.clock.changed.notify_all();
+  }
+}
+
+fn watch(
+  clock: Arc<TickClock>,
+  budget: TickBudget,
+  on_overrun: impl Fn(Overrun),
+) {
+  let mut reported = None;
+  let mut state = clock.state.lock().unwrap();
+  while !state.closed {
+    let tick = state.tick;
+    match state.started {
+      Some(started) if reported != Some(tick) => {
+        let elapsed = started.elapsed();
+        if elapsed < budget.budget {
+          state = clock
+            .changed
+            .wait_timeout(state, budget.budget - elapsed)
+            .unwrap()
+            .0;
+          continue;
+        }
+        reported = Some(tick);
+        clock.overruns.fetch_add(1, Ordering::Relaxed);
+        drop(state);
+        on_overrun(Overrun {
+          clock: clock.clone(),
+          budget,
+          tick,
+          elapsed,
+        });
+        state = clock.state.lock().unwrap();
+      }
+      _ => state = clock.changed.wait(state).unwrap(),
+    }
+  }
+}
+
+extern "C" fn report_overrun(isolate: &mut v8::Isolate, data: *mut c_void) {
+  // SAFETY: `data` was created by `Box::into_raw` in `start_watchdog`, and
+  // V8 calls the callback once.
+  let overrun = unsafe { Box::from_raw(data as *mut Overrun) };
+  // The tick may have ended before V8 got to the interrupt.
+  if !overrun.clock.is_running(overrun.tick) {
+    return;
+  }
+
+  let stack = {
+    let scope = &mut v8::HandleScope::new(isolate);
+    let context = scope.get_current_context();
+    let scope = &mut v8::ContextScope::new(scope, context);
+    format_stack(scope)
+  };
+  let action = match overrun.budget.mode {
+    TickBudgetMode::Warn => "Timers and I/O are delayed until it returns.",
+    TickBudgetMode::Yield => {
+      "Terminating the running code to yield to the event loop."
+    }
+  };
+  log::warn!(
+    "{} An event loop tick has been running for {}ms, exceeding the tick budget of {}ms. {}\n{}",
+    colors::yellow("Warning"),
+    overrun.elapsed.as_millis(),
+    overrun.budget.budget.as_millis(),
+    action,
+    stack,
+  );
+
+  if overrun.budget.mode == TickBudgetMode::Yield {
+    overrun.clock.yielded.store(true, Ordering::Relaxed);
+    isolate.terminate_execution();
+  }
+}
+
+fn format_stack(scope: &mut v8::HandleScope) -> String {
+  let Some(stack) =
+    v8::StackTrace::current_stack_trace(scope, STACK_FRAME_LIMIT)
+  else {
+    return String::new();
+  };
+  let mut lines = vec![];
+  for i in 0..stack.get_frame_count() {
+    let Some(frame) = stack.get_frame(scope, i) else {
+      continue;
+    };
+    let function = frame
+      .get_function_name(scope)
+      .map(|name| name.to_rust_string_lossy(scope))
+      .filter(|name| !name.is_empty())
+      .unwrap_or_else(|| "<anonymous>".to_string());
+    let script = frame
+      .get_script_name_or_source_url(scope)
+      .map(|name| name.to_rust_string_lossy(scope))
+      .unwrap_or_else(|| "<unknown>".to_string());
+    lines.push(format!(
+      "    at {function} ({script}:{}:{})",
+      frame.get_line_number(),
+      frame.get_column()
+    ));
+  }
+  lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::futures::task::noop_waker;
+  use std::sync::mpsc;
+
+  fn monitor(budget: Duration) -> EventLoopMonitor {
+    EventLoopMonitor::new(Some(TickBudget {
+      budget,
+      mode: TickBudgetMode::Warn,
+    }))
+  }
+
+  #[test]
+  fn lag_is_measured_from_the_wake_up() {
+    let monitor = EventLoopMonitor::new(None);
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let inner = monitor.poll(&mut cx, |cx| Poll::Ready(cx.waker().clone()));
+    let Poll::Ready(inner) = inner else {
+      unreachable!()
+    };
+    assert_eq!(monitor.lag().last, Duration::ZERO);
+
+    inner.wake_by_ref();
+    std::thread::sleep(Duration::from_millis(50));
+    // Later wake-ups don't move the start of the lag.
+    inner.wake_by_ref();
+    let _ = monitor.poll(&mut cx, |_| Poll::Ready(()));
+    let lag = monitor.lag();
+    assert!(lag.last >= Duration::from_millis(50), "{lag:?}");
+    assert_eq!(lag.max, lag.last);
+    assert_eq!(lag.ticks, 2);
+
+    // Polled without being woken up.
+    let _ = monitor.poll(&mut cx, |_| Poll::Ready(()));
+    let lag = monitor.lag();
+    assert_eq!(lag.last, Duration::ZERO);
+    assert!(lag.max >= Duration::from_millis(50), "{lag:?}");
+  }
+
+  #[test]
+  fn watchdog_reports_long_ticks_once() {
+    let monitor = monitor(Duration::from_millis(30));
+    let (tx, rx) = mpsc::channel();
+    monitor.start_watchdog_with(move |overrun| {
+      let running = overrun.clock.is_running(overrun.tick);
+      tx.send((overrun.elapsed, running)).unwrap();
+    });
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let _ = monitor.poll(&mut cx, |_| {
+      std::thread::sleep(Duration::from_millis(5));
+      Poll::Ready(())
+    });
+    let _ = monitor.poll(&mut cx, |_| {
+      std::thread::sleep(Duration::from_millis(150));
+      Poll::Ready(())
+    });
+    let (elapsed, running) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(elapsed >= Duration::from_millis(30), "{elapsed:?}");
+    assert!(running);
+    // The long tick was reported once, the short one not at all.
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    assert_eq!(monitor.lag().overruns, 1);
+  }
+
+  #[test]
+  fn watchdog_stops_with_the_monitor() {
+    let monitor = monitor(Duration::from_millis(10));
+    let (tx, rx) = mpsc::channel::<()>();
+    monitor.start_watchdog_with(move |_| tx.send(()).unwrap());
+    drop(monitor);
+    // The sender is dropped along with the watchdog's closure.
+    assert_eq!(
+      rx.recv_timeout(Duration::from_secs(5)),
+      Err(mpsc::RecvTimeoutError::Disconnected)
+    );
+  }
+}
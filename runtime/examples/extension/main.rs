@@ -46,14 +46,17 @@ async fn main() -> Result<(), AnyError> {
       permissions: PermissionsContainer::allow_all(permission_desc_parser),
       blob_store: Default::default(),
       broadcast_channel: Default::default(),
+      content_store: Default::default(),
       feature_checker: Default::default(),
       node_services: Default::default(),
       npm_process_state_provider: Default::default(),
       root_cert_store_provider: Default::default(),
       shared_array_buffer_store: Default::default(),
       compiled_wasm_module_store: Default::default(),
+      storage_event_channel: Default::default(),
       v8_code_cache: Default::default(),
       fs,
+      fs_limits: None,
     },
     WorkerOptions {
       extensions: vec![hello_runtime::init_ops_and_esm()],
@@ -0,0 +1,117 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Errors that would terminate a worker: uncaught exceptions and unhandled
+//! promise rejections.
+//!
+//! By default such an error is returned from `MainWorker::evaluate_module()`
+//! or `MainWorker::run_event_loop()`, and the embedder usually prints it and
+//! exits. An embedder can instead handle it in an [`OnFatalErrorFn`], which
+//! gets a [`FatalError`] and decides what happens next.
+//!
+//! Independently of that callback, the [`UnhandledRejectionPolicy`] decides
+//! whether a promise rejection that no handler took care of is fatal at all.
+
+use deno_core::error::JsError;
+
+/// What kind of error reached the top of the worker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatalErrorKind {
+  UncaughtException,
+  UnhandledRejection,
+}
+
+/// A stack frame of a [`FatalError`]. Positions are source mapped if the
+/// module loader provides source maps, and 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FatalErrorFrame {
+  pub function_name: Option<String>,
+  pub file_name: Option<String>,
+  pub line_number: Option<i64>,
+  pub column_number: Option<i64>,
+}
+
+/// A structured representation of an uncaught exception or unhandled
+/// rejection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FatalError {
+  pub kind: FatalErrorKind,
+  /// The class of the error, e.g. `TypeError`. `None` if the thrown value
+  /// isn't an error object.
+  pub class: Option<String>,
+  /// The error message, or the formatted value if it isn't an error object.
+  pub message: String,
+  /// The `code` property of the thrown value, if it is a string.
+  pub code: Option<String>,
+  pub frames: Vec<FatalErrorFrame>,
+}
+
+impl FatalError {
+  pub fn from_js_error(js_error: &JsError, code: Option<String>) -> Self {
+    let kind = if js_error
+      .exception_message
+      .starts_with("Uncaught (in promise)")
+    {
+      FatalErrorKind::UnhandledRejection
+    } else {
+      FatalErrorKind::UncaughtException
+    };
+    let message = match &js_error.message {
+      Some(message) if js_error.name.is_some() => message.clone(),
+      _ => js_error.exception_message.clone(),
+    };
+    let frames = js_error
+      .frames
+      .iter()
+      .map(|frame| FatalErrorFrame {
+        function_name: frame.function_name.clone(),
+        file_name: frame.file_name.clone(),
+        line_number: frame.line_number,
+        column_number: frame.column_number,
+      })
+      .collect();
+    Self {
+      kind,
+      class: js_error.name.clone(),
+      message,
+      code,
+      frames,
+    }
+  }
+}
+
+/// What the worker does with a [`FatalError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatalErrorDecision {
+  /// Exit the process with the given code, without dispatching the "unload"
+  /// event.
+  Exit(i32),
+  /// Drop the error and keep running the event loop.
+  Continue,
+  /// Return the error to the embedder, like without a callback.
+  Rethrow,
+}
+
+/// Called on the worker's thread for every uncaught exception and fatal
+/// unhandled rejection, after source maps were applied to the stack frames.
+pub type OnFatalErrorFn =
+  dyn Fn(&FatalError) -> FatalErrorDecision + Send + Sync;
+
+/// What happens to a promise rejection that wasn't handled by the program,
+/// i.e. no "unhandledrejection" listener called `preventDefault()` and no
+/// "unhandledRejection" lifecycle hook claimed it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum UnhandledRejectionPolicy {
+  /// The rejection is a fatal error.
+  #[default]
+  Exit = 0,
+  /// Print the rejection to stderr and keep running.
+  Warn = 1,
+  /// Ignore the rejection.
+  Silence = 2,
+}
+
+/// The `code` property of the last exception that was formatted for a
+/// `JsError`, kept in the `OpState` until the error is handled.
+#[derive(Debug, Default)]
+pub(crate) struct FatalErrorCode(pub Option<String>);
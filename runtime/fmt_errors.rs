@@ -328,6 +328,49 @@ fn get_suggestions_for_terminal_errors(e: &JsError) -> Vec<FixSuggestion> {
           "Run again with `--unstable-kv` flag to enable this API.",
         ),
       ];
+    } else if msg.contains("openLogFile is not a function") {
+      return vec![
+        FixSuggestion::info("Deno.openLogFile() is an unstable API."),
+        FixSuggestion::hint(
+          "Run again with `--unstable-fs` flag to enable this API.",
+        ),
+      ];
+    } else if msg.contains("Deno.Channel is not a constructor") {
+      return vec![
+        FixSuggestion::info("Deno.Channel is an unstable API."),
+        FixSuggestion::hint(
+          "Run again with `--unstable-worker-options` flag to enable this API.",
+        ),
+      ];
+    } else if msg.contains("openNdjsonReader is not a function")
+      || msg.contains("openNdjsonWriter is not a function")
+    {
+      return vec![
+        FixSuggestion::info(
+          "Deno.openNdjsonReader() and Deno.openNdjsonWriter() are unstable APIs.",
+        ),
+        FixSuggestion::hint(
+          "Run again with `--unstable-fs` flag to enable this API.",
+        ),
+      ];
+    } else if msg.contains("hashTree is not a function") {
+      return vec![
+        FixSuggestion::info("Deno.hashTree() is an unstable API."),
+        FixSuggestion::hint(
+          "Run again with `--unstable-fs` flag to enable this API.",
+        ),
+      ];
+    } else if msg.contains("spawnSelf is not a function")
+      || msg.contains("parentIpc is not a function")
+    {
+      return vec![
+        FixSuggestion::info(
+          "Deno.spawnSelf() and Deno.parentIpc() are unstable APIs.",
+        ),
+        FixSuggestion::hint(
+          "Run again with `--unstable-process` flag to enable this API.",
+        ),
+      ];
     } else if msg.contains("cron is not a function") {
       return vec![
         FixSuggestion::info("Deno.cron() is an unstable API."),
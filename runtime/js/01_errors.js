@@ -158,6 +158,20 @@ class NotADirectory extends Error {
   }
 }
 
+class StaleRead extends Error {
+  constructor(msg) {
+    super(msg);
+    this.name = "StaleRead";
+  }
+}
+
+class LimitExceeded extends Error {
+  constructor(msg) {
+    super(msg);
+    this.name = "LimitExceeded";
+  }
+}
+
 const errors = {
   NotFound,
   PermissionDenied,
@@ -184,6 +198,8 @@ const errors = {
   NetworkUnreachable,
   NotADirectory,
   NotCapable,
+  StaleRead,
+  LimitExceeded,
 };
 
 export { errors };
@@ -6,12 +6,19 @@ import {
   op_host_post_message,
   op_host_recv_ctrl,
   op_host_recv_message,
-  op_host_terminate_worker,
+  op_host_terminate_worker_gracefully,
 } from "ext:core/ops";
 const {
   ArrayPrototypeFilter,
+  ArrayPrototypeMap,
   Error,
   ObjectPrototypeIsPrototypeOf,
+  PromisePrototypeThen,
+  SafePromiseAll,
+  SafeSet,
+  SafeSetIterator,
+  SetPrototypeAdd,
+  SetPrototypeDelete,
   String,
   StringPrototypeStartsWith,
   Symbol,
@@ -25,6 +32,7 @@ import { createFilteredInspectProxy } from "ext:deno_console/01_console.js";
 import { URL } from "ext:deno_url/00_url.js";
 import { getLocationHref } from "ext:deno_web/12_location.js";
 import { serializePermissions } from "ext:runtime/10_permissions.js";
+import { serializeEnvPolicy } from "ext:runtime/40_process.js";
 import { log } from "ext:runtime/06_util.js";
 import {
   defineEventHandler,
@@ -44,6 +52,7 @@ function createWorker(
   hasSourceCode,
   sourceCode,
   permissions,
+  env,
   name,
   workerType,
   closeOnIdle,
@@ -52,6 +61,7 @@ function createWorker(
     hasSourceCode,
     name,
     permissions: serializePermissions(permissions),
+    env: serializeEnvPolicy(env),
     sourceCode,
     specifier,
     workerType,
@@ -59,8 +69,11 @@ function createWorker(
   });
 }
 
-function hostTerminateWorker(id) {
-  op_host_terminate_worker(id);
+/** How long a worker gets to shut down after "beforeterminate" by default. */
+const DEFAULT_TERMINATE_DEADLINE = 2000;
+
+function hostTerminateWorker(id, deadline) {
+  return op_host_terminate_worker_gracefully(id, deadline);
 }
 
 function hostPostMessage(id, data) {
@@ -77,12 +90,30 @@ function hostRecvMessage(id) {
 
 const privateWorkerRef = Symbol();
 
+/** The workers created by this global scope that weren't terminated. */
+const liveWorkers = new SafeSet();
+
+/**
+ * Terminates the workers created by this global scope, so that worker trees
+ * shut down depth-first.
+ * @param {number} deadline
+ */
+function terminateAllWorkers(deadline) {
+  return SafePromiseAll(
+    ArrayPrototypeMap(
+      [...new SafeSetIterator(liveWorkers)],
+      (worker) => worker.terminate({ deadline }),
+    ),
+  );
+}
+
 class Worker extends EventTarget {
   #id = 0;
   #name = "";
   #refCount = 1;
   #messagePromise = undefined;
   #controlPromise = undefined;
+  #terminatePromise = undefined;
 
   // "RUNNING" | "CLOSED" | "TERMINATED"
   // "TERMINATED" means that any controls or messages received will be
@@ -128,11 +159,13 @@ class Worker extends EventTarget {
       hasSourceCode,
       sourceCode,
       deno?.permissions,
+      deno?.env,
       this.#name,
       workerType,
       false,
     );
     this.#id = id;
+    SetPrototypeAdd(liveWorkers, this);
     this.#pollControl();
     this.#pollMessages();
   }
@@ -198,6 +231,7 @@ class Worker extends EventTarget {
       switch (type) {
         case 1: { // TerminalError
           this.#status = "CLOSED";
+          SetPrototypeDelete(liveWorkers, this);
         } /* falls through */
         case 2: { // Error
           if (!this.#handleError(data)) {
@@ -208,6 +242,7 @@ class Worker extends EventTarget {
         case 3: { // Close
           log(`Host got "close" message from worker: ${this.#name}`);
           this.#status = "CLOSED";
+          SetPrototypeDelete(liveWorkers, this);
           return;
         }
         default: {
@@ -284,11 +319,28 @@ class Worker extends EventTarget {
     }
   }
 
-  terminate() {
-    if (this.#status !== "TERMINATED") {
-      this.#status = "TERMINATED";
-      hostTerminateWorker(this.#id);
+  terminate(options = { __proto__: null }) {
+    if (this.#terminatePromise) {
+      return this.#terminatePromise;
     }
+    const prefix = "Failed to execute 'terminate' on 'Worker'";
+    const deadline = options?.deadline === undefined
+      ? DEFAULT_TERMINATE_DEADLINE
+      : webidl.converters["unsigned long"](
+        options.deadline,
+        prefix,
+        "deadline",
+        { enforceRange: true },
+      );
+    this.#status = "TERMINATED";
+    this.#terminatePromise = PromisePrototypeThen(
+      hostTerminateWorker(this.#id, deadline),
+      (graceful) => {
+        SetPrototypeDelete(liveWorkers, this);
+        return { graceful };
+      },
+    );
+    return this.#terminatePromise;
   }
 
   [SymbolFor("Deno.privateCustomInspect")](inspect, inspectOptions) {
@@ -320,4 +372,4 @@ webidl.converters["WorkerType"] = webidl.createEnumConverter("WorkerType", [
   "module",
 ]);
 
-export { Worker };
+export { terminateAllWorkers, Worker };
@@ -6,6 +6,7 @@ import {
   op_env,
   op_exec_path,
   op_exit,
+  op_fd_limits,
   op_get_env,
   op_get_exit_code,
   op_gid,
@@ -16,6 +17,7 @@ import {
   op_os_uptime,
   op_set_env,
   op_set_exit_code,
+  op_system_cpu_info,
   op_system_memory_info,
   op_uid,
 } from "ext:core/ops";
@@ -29,6 +31,7 @@ const {
 } = primordials;
 
 import { Event, EventTarget } from "ext:deno_web/02_event.js";
+import { flushPendingWrites } from "ext:deno_webstorage/01_webstorage.js";
 
 const windowDispatchEvent = FunctionPrototypeBind(
   EventTarget.prototype.dispatchEvent,
@@ -55,6 +58,14 @@ function systemMemoryInfo() {
   return op_system_memory_info();
 }
 
+function systemCpuInfo() {
+  return op_system_cpu_info();
+}
+
+function fdLimits() {
+  return op_fd_limits();
+}
+
 function networkInterfaces() {
   return op_network_interfaces();
 }
@@ -94,6 +105,8 @@ function exit(code) {
     return;
   }
 
+  // The process exits before pending microtasks run.
+  flushPendingWrites();
   op_exit();
   throw new Error("Code not reachable");
 }
@@ -148,6 +161,7 @@ export {
   env,
   execPath,
   exit,
+  fdLimits,
   getExitCode,
   gid,
   hostname,
@@ -157,6 +171,7 @@ export {
   osUptime,
   setExitCode,
   setExitHandler,
+  systemCpuInfo,
   systemMemoryInfo,
   uid,
 };
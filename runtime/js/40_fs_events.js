@@ -1,27 +1,82 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 import { core, primordials } from "ext:core/mod.js";
-import { op_fs_events_open, op_fs_events_poll } from "ext:core/ops";
+import {
+  op_config_watch_next,
+  op_config_watch_open,
+  op_fs_events_open,
+} from "ext:core/ops";
 const {
   BadResourcePrototype,
   InterruptedPrototype,
 } = core;
 const {
   ArrayIsArray,
+  Error,
   ObjectPrototypeIsPrototypeOf,
   PromiseResolve,
   SymbolAsyncIterator,
 } = primordials;
 
-import { SymbolDispose } from "ext:deno_web/00_infra.js";
+import {
+  opStream,
+  pathFromURL,
+  SymbolDispose,
+} from "ext:deno_web/00_infra.js";
 
 class FsWatcher {
   #rid = 0;
-  #promise;
+  #events;
 
   constructor(paths, options) {
     const { recursive } = options;
     this.#rid = op_fs_events_open(recursive, paths);
+    this.#events = opStream(this.#rid);
+  }
+
+  unref() {
+    this.#events.unref();
+  }
+
+  ref() {
+    this.#events.ref();
+  }
+
+  next() {
+    return this.#events.next();
+  }
+
+  return(value) {
+    core.close(this.#rid);
+    return PromiseResolve({ value, done: true });
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolAsyncIterator]() {
+    return this;
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+function watchFs(
+  paths,
+  options = { __proto__: null, recursive: true },
+) {
+  return new FsWatcher(ArrayIsArray(paths) ? paths : [paths], options);
+}
+
+class ConfigWatcher {
+  #rid = 0;
+  #promise;
+
+  constructor(path, options) {
+    this.#rid = op_config_watch_open(path, options);
   }
 
   unref() {
@@ -33,20 +88,30 @@ class FsWatcher {
   }
 
   async next() {
+    let event;
     try {
-      this.#promise = op_fs_events_poll(this.#rid);
-      const value = await this.#promise;
-      return value ? { value, done: false } : { value: undefined, done: true };
+      this.#promise = op_config_watch_next(this.#rid);
+      event = await this.#promise;
     } catch (error) {
-      if (ObjectPrototypeIsPrototypeOf(BadResourcePrototype, error)) {
-        return { value: undefined, done: true };
-      } else if (
+      if (
+        ObjectPrototypeIsPrototypeOf(BadResourcePrototype, error) ||
         ObjectPrototypeIsPrototypeOf(InterruptedPrototype, error)
       ) {
         return { value: undefined, done: true };
       }
       throw error;
     }
+    if (event === null) {
+      return { value: undefined, done: true };
+    }
+    if (event.kind === "error") {
+      event = {
+        kind: "error",
+        error: new Error(event.message),
+        previous: event.previous,
+      };
+    }
+    return { value: event, done: false };
   }
 
   return(value) {
@@ -67,11 +132,11 @@ class FsWatcher {
   }
 }
 
-function watchFs(
-  paths,
-  options = { __proto__: null, recursive: true },
-) {
-  return new FsWatcher(ArrayIsArray(paths) ? paths : [paths], options);
+function watchConfig(path, options = { __proto__: null }) {
+  return new ConfigWatcher(pathFromURL(path), {
+    debounceMs: options.debounceMs,
+    parser: options.parser ?? "json",
+  });
 }
 
-export { watchFs };
+export { watchConfig, watchFs };
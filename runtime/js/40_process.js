@@ -2,19 +2,27 @@
 
 import { core, internals, primordials } from "ext:core/mod.js";
 import {
+  op_ipc_channel_read,
+  op_ipc_channel_write,
   op_kill,
   op_run,
   op_run_status,
   op_spawn_child,
   op_spawn_kill,
+  op_spawn_self,
+  op_spawn_self_parent_ipc,
   op_spawn_sync,
   op_spawn_wait,
 } from "ext:core/ops";
 const {
+  ArrayIsArray,
   ArrayPrototypeMap,
   ArrayPrototypeSlice,
   TypeError,
+  ObjectDefineProperty,
   ObjectEntries,
+  ObjectKeys,
+  PromisePrototypeCatch,
   SafeArrayIterator,
   String,
   ObjectPrototypeIsPrototypeOf,
@@ -31,6 +39,14 @@ import {
   pathFromURL,
   SymbolAsyncDispose,
 } from "ext:deno_web/00_infra.js";
+import { DOMException } from "ext:deno_web/01_dom_exception.js";
+import {
+  defineEventHandler,
+  Event,
+  EventTarget,
+  MessageEvent,
+  setIsTrusted,
+} from "ext:deno_web/02_event.js";
 import * as abortSignal from "ext:deno_web/03_abort_signal.js";
 import {
   readableStreamCollectIntoUint8Array,
@@ -164,11 +180,49 @@ export const kNeedsNpmProcessState = Symbol("needsNpmProcessState");
 
 const illegalConstructorKey = Symbol("illegalConstructorKey");
 
+/**
+ * @param {Deno.EnvPolicy | undefined} policy
+ */
+function serializeEnvPolicy(policy) {
+  if (policy === undefined) {
+    return undefined;
+  }
+  const {
+    inherit = "all",
+    set = { __proto__: null },
+    remove = [],
+  } = policy;
+  if (inherit !== "all" && inherit !== "none" && !ArrayIsArray(inherit)) {
+    throw new TypeError(
+      `Invalid env policy: 'inherit' must be "all", "none" or an array of variable names, received ${
+        typeof inherit === "string" ? `"${inherit}"` : typeof inherit
+      }`,
+    );
+  }
+  return {
+    inherit: ArrayIsArray(inherit)
+      ? ArrayPrototypeMap(inherit, String)
+      : inherit,
+    set: ObjectEntries(set),
+    remove: ArrayPrototypeMap(remove, String),
+  };
+}
+
+function serializeCommandEnvPolicy(envPolicy, clearEnv, env) {
+  if (envPolicy !== undefined && (clearEnv || ObjectKeys(env).length > 0)) {
+    throw new TypeError(
+      "Cannot use 'envPolicy' together with 'env' or 'clearEnv', use 'envPolicy.set' and 'envPolicy.inherit' instead",
+    );
+  }
+  return serializeEnvPolicy(envPolicy);
+}
+
 function spawnChildInner(command, apiName, {
   args = [],
   cwd = undefined,
   clearEnv = false,
   env = { __proto__: null },
+  envPolicy = undefined,
   uid = undefined,
   gid = undefined,
   signal = undefined,
@@ -187,6 +241,7 @@ function spawnChildInner(command, apiName, {
     cwd: pathFromURL(cwd),
     clearEnv,
     env: ObjectEntries(env),
+    envPolicy: serializeCommandEnvPolicy(envPolicy, clearEnv, env),
     uid,
     gid,
     stdin,
@@ -241,6 +296,11 @@ class ChildProcess {
     return this.#pid;
   }
 
+  #envDiagnostics;
+  get envDiagnostics() {
+    return this.#envDiagnostics;
+  }
+
   #stdin = null;
   get stdin() {
     if (this.#stdin == null) {
@@ -274,6 +334,7 @@ class ChildProcess {
     stderrRid,
     ipcPipeRid, // internal
     extraPipeRids,
+    envDiagnostics,
   } = null) {
     if (key !== illegalConstructorKey) {
       throw new TypeError("Illegal constructor");
@@ -281,6 +342,7 @@ class ChildProcess {
 
     this.#rid = rid;
     this.#pid = pid;
+    this.#envDiagnostics = envDiagnostics ?? undefined;
     this[_ipcPipeRid] = ipcPipeRid;
     this[_extraPipeRids] = extraPipeRids;
 
@@ -379,6 +441,145 @@ class ChildProcess {
   }
 }
 
+class IpcChannel extends EventTarget {
+  #rid;
+  #started = false;
+  #refed = true;
+  #readPromise = undefined;
+
+  constructor(key = null, rid) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor");
+    }
+    super();
+    this.#rid = rid;
+  }
+
+  /**
+   * @param {any} message
+   */
+  postMessage(message) {
+    if (this.#rid === null) return;
+    const data = core.serialize(message, undefined, (err) => {
+      throw new DOMException(err, "DataCloneError");
+    });
+    // A failed write means the other end is gone.
+    PromisePrototypeCatch(
+      op_ipc_channel_write(this.#rid, data),
+      () => this.close(),
+    );
+  }
+
+  start() {
+    if (this.#started || this.#rid === null) return;
+    this.#started = true;
+    (async () => {
+      while (this.#rid !== null) {
+        let data;
+        try {
+          this.#readPromise = op_ipc_channel_read(this.#rid);
+          if (!this.#refed) {
+            core.unrefOpPromise(this.#readPromise);
+          }
+          data = await this.#readPromise;
+        } catch {
+          // Closed locally, or the other end went away mid-message.
+          break;
+        } finally {
+          this.#readPromise = undefined;
+        }
+        if (data === null) break;
+        let event;
+        try {
+          event = new MessageEvent("message", {
+            data: core.deserialize(data),
+          });
+        } catch (err) {
+          event = new MessageEvent("messageerror", { data: err });
+        }
+        setIsTrusted(event, true);
+        this.dispatchEvent(event);
+      }
+      this.close();
+    })();
+  }
+
+  close() {
+    if (this.#rid === null) return;
+    core.tryClose(this.#rid);
+    this.#rid = null;
+    const event = new Event("close");
+    setIsTrusted(event, true);
+    this.dispatchEvent(event);
+  }
+
+  addEventListener(...args) {
+    super.addEventListener(...new SafeArrayIterator(args));
+    if (args[0] === "message") this.start();
+  }
+
+  ref() {
+    this.#refed = true;
+    if (this.#readPromise) core.refOpPromise(this.#readPromise);
+  }
+
+  unref() {
+    this.#refed = false;
+    if (this.#readPromise) core.unrefOpPromise(this.#readPromise);
+  }
+}
+
+defineEventHandler(IpcChannel.prototype, "message", (self) => self.start());
+defineEventHandler(IpcChannel.prototype, "messageerror");
+defineEventHandler(IpcChannel.prototype, "close");
+
+function spawnSelf(args = [], {
+  cwd = undefined,
+  clearEnv = false,
+  env = { __proto__: null },
+  signal = undefined,
+  stdin = "inherit",
+  stdout = "inherit",
+  stderr = "inherit",
+  ipc = false,
+} = { __proto__: null }) {
+  const { ipcPipeRid, ...child } = op_spawn_self({
+    args: ArrayPrototypeMap(args, String),
+    cwd: pathFromURL(cwd),
+    clearEnv,
+    env: ObjectEntries(env),
+    stdin,
+    stdout,
+    stderr,
+    ipc,
+  });
+  const childProcess = new ChildProcess(illegalConstructorKey, {
+    ...child,
+    ipcPipeRid: null,
+    signal,
+  });
+  ObjectDefineProperty(childProcess, "ipc", {
+    __proto__: null,
+    value: ipcPipeRid === null
+      ? null
+      : new IpcChannel(illegalConstructorKey, ipcPipeRid),
+    enumerable: true,
+  });
+  return childProcess;
+}
+
+let parentIpcChannel;
+
+function parentIpc() {
+  if (parentIpcChannel === undefined) {
+    const rid = op_spawn_self_parent_ipc();
+    parentIpcChannel = rid === null
+      ? null
+      : new IpcChannel(illegalConstructorKey, rid);
+  }
+  return parentIpcChannel;
+}
+
 function spawn(command, options) {
   if (options?.stdin === "piped") {
     throw new TypeError(
@@ -398,6 +599,7 @@ function spawnSync(command, {
   cwd = undefined,
   clearEnv = false,
   env = { __proto__: null },
+  envPolicy = undefined,
   uid = undefined,
   gid = undefined,
   stdin = "null",
@@ -416,6 +618,7 @@ function spawnSync(command, {
     cwd: pathFromURL(cwd),
     clearEnv,
     env: ObjectEntries(env),
+    envPolicy: serializeCommandEnvPolicy(envPolicy, clearEnv, env),
     uid,
     gid,
     stdin,
@@ -484,4 +687,14 @@ class Command {
   }
 }
 
-export { ChildProcess, Command, kill, Process, run };
+export {
+  ChildProcess,
+  Command,
+  IpcChannel,
+  kill,
+  parentIpc,
+  Process,
+  run,
+  serializeEnvPolicy,
+  spawnSelf,
+};
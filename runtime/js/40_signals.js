@@ -1,7 +1,13 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 import { core, primordials } from "ext:core/mod.js";
-import { op_signal_bind, op_signal_poll, op_signal_unbind } from "ext:core/ops";
+import {
+  op_sigint_handled,
+  op_sigint_poll,
+  op_signal_bind,
+  op_signal_poll,
+  op_signal_unbind,
+} from "ext:core/ops";
 const {
   SafeSet,
   SafeSetIterator,
@@ -79,4 +85,30 @@ async function loop(sigData) {
   }
 }
 
-export { addSignalListener, removeSignalListener };
+function hasSignalListener(signo) {
+  return (signalData[signo]?.listeners.size ?? 0) > 0;
+}
+
+/**
+ * Resolves to true on every SIGINT that starts a grace period, when the
+ * runtime manages SIGINT, and to false right away otherwise.
+ * @returns {Promise<boolean>}
+ */
+function pollSigint() {
+  const promise = op_sigint_poll();
+  core.unrefOpPromise(promise);
+  return promise;
+}
+
+/** Tells the runtime that the program handled the last SIGINT itself. */
+function sigintHandled() {
+  op_sigint_handled();
+}
+
+export {
+  addSignalListener,
+  hasSignalListener,
+  pollSigint,
+  removeSignalListener,
+  sigintHandled,
+};
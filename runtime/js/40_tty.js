@@ -1,10 +1,26 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 import { core, primordials } from "ext:core/mod.js";
-import { op_console_size } from "ext:core/ops";
+import {
+  op_console_size,
+  op_readline_abort,
+  op_readline_create,
+  op_readline_read,
+  op_tty_events_open,
+  op_tty_read_events,
+  op_tty_set_bracketed_paste,
+  op_tty_set_mouse_reporting,
+} from "ext:core/ops";
+import { pathFromURL } from "ext:deno_web/00_infra.js";
+import * as abortSignal from "ext:deno_web/03_abort_signal.js";
 const {
+  ArrayPrototypePush,
+  SafeArrayIterator,
+  SymbolAsyncIterator,
+  SymbolDispose,
   Uint32Array,
 } = primordials;
 const {
+  createCancelHandle,
   isTerminal,
 } = core;
 
@@ -21,4 +37,136 @@ function isatty(rid) {
   return isTerminal(rid);
 }
 
-export { consoleSize, isatty };
+class TtyEvents {
+  #rid = 0;
+
+  constructor(rid) {
+    this.#rid = rid;
+  }
+
+  setMouseReporting(enabled) {
+    op_tty_set_mouse_reporting(this.#rid, !!enabled);
+  }
+
+  setBracketedPaste(enabled) {
+    op_tty_set_bracketed_paste(this.#rid, !!enabled);
+  }
+
+  async *[SymbolAsyncIterator]() {
+    while (true) {
+      const events = await op_tty_read_events(this.#rid);
+      if (events === null) {
+        return;
+      }
+      for (let i = 0; i < events.length; ++i) {
+        yield events[i];
+      }
+    }
+  }
+
+  close() {
+    core.tryClose(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+function openTtyEvents(options = { __proto__: null }) {
+  const events = new TtyEvents(op_tty_events_open(0));
+  try {
+    if (options.mouse) {
+      events.setMouseReporting(true);
+    }
+    if (options.bracketedPaste) {
+      events.setBracketedPaste(true);
+    }
+  } catch (error) {
+    events.close();
+    throw error;
+  }
+  return events;
+}
+
+class Readline {
+  #rid = 0;
+  #complete;
+
+  constructor(rid, complete) {
+    this.#rid = rid;
+    this.#complete = complete;
+  }
+
+  async read(options) {
+    const signal = options?.signal;
+    signal?.throwIfAborted();
+    let completions = null;
+    try {
+      while (true) {
+        let cancelRid;
+        let abortHandler;
+        if (signal) {
+          cancelRid = createCancelHandle();
+          abortHandler = () => core.tryClose(cancelRid);
+          signal[abortSignal.add](abortHandler);
+        }
+        let result;
+        try {
+          result = await op_readline_read(this.#rid, completions, cancelRid);
+        } finally {
+          if (signal) {
+            signal[abortSignal.remove](abortHandler);
+            // always throw the abort error when aborted
+            signal.throwIfAborted();
+          }
+        }
+        if (result.kind !== "complete") {
+          return result;
+        }
+        completions = [];
+        const items = await this.#complete?.(result.line, result.cursor);
+        for (const item of new SafeArrayIterator(items ?? [])) {
+          ArrayPrototypePush(completions, String(item));
+        }
+        signal?.throwIfAborted();
+      }
+    } catch (error) {
+      // leave raw mode when the completer throws, the line is abandoned
+      try {
+        op_readline_abort(this.#rid);
+      } catch {
+        // closed
+      }
+      throw error;
+    }
+  }
+
+  close() {
+    core.tryClose(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    this.close();
+  }
+}
+
+function createReadline(options = { __proto__: null }) {
+  const rid = op_readline_create(0, {
+    prompt: options.prompt ?? "",
+    historyPath: options.historyPath != null
+      ? pathFromURL(options.historyPath)
+      : undefined,
+    maxHistory: options.maxHistory,
+  });
+  return new Readline(rid, options.complete);
+}
+
+export {
+  consoleSize,
+  createReadline,
+  isatty,
+  openTtyEvents,
+  Readline,
+  TtyEvents,
+};
@@ -8,7 +8,10 @@ import {
 } from "ext:core/ops";
 
 import * as timers from "ext:deno_web/02_timers.js";
+import * as channel from "ext:deno_web/17_channel.js";
+import * as lifecycle from "ext:deno_web/18_lifecycle.js";
 import * as httpClient from "ext:deno_fetch/22_http_client.js";
+import * as download from "ext:deno_fetch/28_download.js";
 import * as console from "ext:deno_console/01_console.js";
 import * as ffi from "ext:deno_ffi/00_ffi.js";
 import * as net from "ext:deno_net/01_net.js";
@@ -28,6 +31,7 @@ import * as signals from "ext:runtime/40_signals.js";
 import * as tty from "ext:runtime/40_tty.js";
 import * as kv from "ext:deno_kv/01_db.ts";
 import * as cron from "ext:deno_cron/01_cron.ts";
+import * as crypto from "ext:deno_crypto/00_crypto.js";
 import * as webgpuSurface from "ext:deno_webgpu/02_surface.js";
 
 const denoNs = {
@@ -118,6 +122,8 @@ const denoNs = {
   osUptime: os.osUptime,
   hostname: os.hostname,
   systemMemoryInfo: os.systemMemoryInfo,
+  systemCpuInfo: os.systemCpuInfo,
+  fdLimits: os.fdLimits,
   networkInterfaces: os.networkInterfaces,
   consoleSize: tty.consoleSize,
   gid: os.gid,
@@ -138,6 +144,7 @@ const denoNs = {
 const unstableIds = {
   broadcastChannel: 1,
   cron: 2,
+  crypto: 13,
   ffi: 3,
   fs: 4,
   http: 5,
@@ -145,6 +152,7 @@ const unstableIds = {
   net: 7,
   process: 8,
   temporal: 9,
+  tty: 14,
   unsafeProto: 10,
   webgpu: 11,
   workerOptions: 12,
@@ -156,6 +164,44 @@ const denoNsUnstableById = { __proto__: null };
 
 denoNsUnstableById[unstableIds.cron] = {
   cron: cron.cron,
+  schedule: cron.schedule,
+};
+
+denoNsUnstableById[unstableIds.crypto] = {
+  wrapWithPassword: crypto.wrapWithPassword,
+  unwrapWithPassword: crypto.unwrapWithPassword,
+  parseSshPublicKey: crypto.parseSshPublicKey,
+  parseSshPrivateKey: crypto.parseSshPrivateKey,
+  SshPublicKey: crypto.SshPublicKey,
+  SshPrivateKey: crypto.SshPrivateKey,
+};
+
+denoNsUnstableById[unstableIds.fs] = {
+  createTempFile: fs.createTempFile,
+  TempFile: fs.TempFile,
+  createTempDir: fs.createTempDir,
+  TempDir: fs.TempDir,
+  openLogFile: fs.openLogFile,
+  writeFileAtomic: fs.writeFileAtomic,
+  writeFileAtomicSync: fs.writeFileAtomicSync,
+  LogFile: fs.LogFile,
+  openNdjsonReader: fs.openNdjsonReader,
+  NdjsonReader: fs.NdjsonReader,
+  openNdjsonWriter: fs.openNdjsonWriter,
+  NdjsonWriter: fs.NdjsonWriter,
+  hashTree: fs.hashTree,
+  createIntegrityManifest: fs.createIntegrityManifest,
+  verifyIntegrityManifest: fs.verifyIntegrityManifest,
+  copyDir: fs.copyDir,
+  walkDir: fs.walkDir,
+  globMatcher: fs.globMatcher,
+  GlobMatcher: fs.GlobMatcher,
+  mapFile: fs.mapFile,
+  MappedFile: fs.MappedFile,
+  fsLimitUsage: fs.fsLimitUsage,
+  searchText: fs.searchText,
+  path: fs.path,
+  watchConfig: fsEvents.watchConfig,
 };
 
 denoNsUnstableById[unstableIds.kv] = {
@@ -167,10 +213,31 @@ denoNsUnstableById[unstableIds.kv] = {
 };
 
 denoNsUnstableById[unstableIds.net] = {
+  acceptAny: net.acceptAny,
+  certificateSpkiHash: tls.certificateSpkiHash,
+  proxy: net.proxy,
+  sendFile: net.sendFile,
+  download: download.download,
   listenDatagram: net.createListenDatagram(
     op_net_listen_udp,
     op_net_listen_unixpacket,
   ),
+  saveResponse: download.saveResponse,
+};
+
+denoNsUnstableById[unstableIds.process] = {
+  spawnSelf: process.spawnSelf,
+  parentIpc: process.parentIpc,
+  IpcChannel: process.IpcChannel,
+  addLifecycleHook: lifecycle.addLifecycleHook,
+  removeLifecycleHook: lifecycle.removeLifecycleHook,
+};
+
+denoNsUnstableById[unstableIds.tty] = {
+  openTtyEvents: tty.openTtyEvents,
+  TtyEvents: tty.TtyEvents,
+  createReadline: tty.createReadline,
+  Readline: tty.Readline,
 };
 
 // denoNsUnstableById[unstableIds.unsafeProto] = { __proto__: null }
@@ -179,6 +246,8 @@ denoNsUnstableById[unstableIds.webgpu] = {
   UnsafeWindowSurface: webgpuSurface.UnsafeWindowSurface,
 };
 
-// denoNsUnstableById[unstableIds.workerOptions] = { __proto__: null }
+denoNsUnstableById[unstableIds.workerOptions] = {
+  Channel: channel.Channel,
+};
 
 export { denoNs, denoNsUnstableById, unstableIds };
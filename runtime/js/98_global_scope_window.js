@@ -121,6 +121,7 @@ const mainRuntimeGlobalProperties = {
   localStorage: core.propGetterOnly(webStorage.localStorage),
   sessionStorage: core.propGetterOnly(webStorage.sessionStorage),
   Storage: core.propNonEnumerable(webStorage.Storage),
+  StorageEvent: core.propNonEnumerable(webStorage.StorageEvent),
 };
 
 export { mainRuntimeGlobalProperties, memoizeLazy };
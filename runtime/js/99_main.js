@@ -14,8 +14,11 @@ import {
   op_bootstrap_pid,
   op_main_module,
   op_ppid,
+  op_set_fatal_error_code,
   op_set_format_exception_callback,
   op_snapshot_options,
+  op_take_op_error_details,
+  op_unhandled_rejection_policy,
   op_worker_close,
   op_worker_get_type,
   op_worker_post_message,
@@ -42,10 +45,14 @@ const {
   ObjectSetPrototypeOf,
   PromisePrototypeThen,
   PromiseResolve,
+  RangeError,
+  ReferenceError,
   StringPrototypePadEnd,
   Symbol,
   SymbolIterator,
+  SyntaxError,
   TypeError,
+  URIError,
 } = primordials;
 const {
   isNativeError,
@@ -67,6 +74,11 @@ import * as performance from "ext:deno_web/15_performance.js";
 import * as url from "ext:deno_url/00_url.js";
 import * as fetch from "ext:deno_fetch/26_fetch.js";
 import * as messagePort from "ext:deno_web/13_message_port.js";
+import * as lifecycle from "ext:deno_web/18_lifecycle.js";
+import { ByteSink, ByteSource } from "ext:deno_web/19_byte_codec.js";
+import * as signals from "ext:runtime/40_signals.js";
+import { pollStorageEvents } from "ext:deno_webstorage/01_webstorage.js";
+import { terminateAllWorkers } from "ext:runtime/11_workers.js";
 import {
   denoNs,
   denoNsUnstableById,
@@ -166,6 +178,10 @@ function postMessage(message, transferOrOptions = { __proto__: null }) {
 }
 
 let isClosing = false;
+// Set once the parent asked the worker to terminate. Unlike `isClosing`, the
+// worker can still call `close()`.
+let isTerminating = false;
+let pendingRecvMessage;
 let globalDispatchEvent;
 
 function hasMessageEventListener() {
@@ -183,12 +199,14 @@ async function pollForMessages() {
       globalThis,
     );
   }
-  while (!isClosing) {
+  while (!isClosing && !isTerminating) {
     const recvMessage = op_worker_recv_message();
     if (globalThis[messagePort.unrefPollForMessages] === true) {
       core.unrefOpPromise(recvMessage);
     }
+    pendingRecvMessage = recvMessage;
     const data = await recvMessage;
+    pendingRecvMessage = undefined;
     // const data = await op_worker_recv_message();
     if (data === null) break;
     const v = messagePort.deserializeJsMessageData(data);
@@ -227,6 +245,23 @@ async function pollForMessages() {
   }
 }
 
+/**
+ * Called by the runtime when the parent asked the worker to terminate. Child
+ * workers are terminated first, then "beforeterminate" is dispatched. The
+ * worker shuts down once its event loop is done, so waiting for messages
+ * doesn't keep it alive anymore.
+ * @param {number} deadline milliseconds left before the worker is killed
+ */
+async function dispatchBeforeTerminate(deadline) {
+  isTerminating = true;
+  if (pendingRecvMessage) {
+    core.unrefOpPromise(pendingRecvMessage);
+  }
+  await terminateAllWorkers(deadline);
+  lifecycle.dispatchLifecycleEvent("beforeTerminate", { deadline });
+  globalThis_.dispatchEvent(new Event("beforeterminate"));
+}
+
 let loadedMainWorkerScript = false;
 
 function importScripts(...urls) {
@@ -273,6 +308,7 @@ setNoColorFns(
 );
 
 function formatException(error) {
+  recordErrorCode(error);
   if (
     isNativeError(error) ||
     ObjectPrototypeIsPrototypeOf(ErrorPrototype, error)
@@ -289,65 +325,145 @@ function formatException(error) {
   }
 }
 
-core.registerErrorClass("NotFound", errors.NotFound);
-core.registerErrorClass("ConnectionRefused", errors.ConnectionRefused);
-core.registerErrorClass("ConnectionReset", errors.ConnectionReset);
-core.registerErrorClass("ConnectionAborted", errors.ConnectionAborted);
-core.registerErrorClass("NotConnected", errors.NotConnected);
-core.registerErrorClass("AddrInUse", errors.AddrInUse);
-core.registerErrorClass("AddrNotAvailable", errors.AddrNotAvailable);
-core.registerErrorClass("BrokenPipe", errors.BrokenPipe);
-core.registerErrorClass("PermissionDenied", errors.PermissionDenied);
-core.registerErrorClass("AlreadyExists", errors.AlreadyExists);
-core.registerErrorClass("InvalidData", errors.InvalidData);
-core.registerErrorClass("TimedOut", errors.TimedOut);
-core.registerErrorClass("WouldBlock", errors.WouldBlock);
-core.registerErrorClass("WriteZero", errors.WriteZero);
-core.registerErrorClass("UnexpectedEof", errors.UnexpectedEof);
-core.registerErrorClass("Http", errors.Http);
-core.registerErrorClass("Busy", errors.Busy);
-core.registerErrorClass("NotSupported", errors.NotSupported);
-core.registerErrorClass("FilesystemLoop", errors.FilesystemLoop);
-core.registerErrorClass("IsADirectory", errors.IsADirectory);
-core.registerErrorClass("NetworkUnreachable", errors.NetworkUnreachable);
-core.registerErrorClass("NotADirectory", errors.NotADirectory);
+// Lets the fatal error handler of the worker report the `code` property of
+// the error it was given.
+function recordErrorCode(error) {
+  let code = "";
+  try {
+    if (typeof error?.code === "string") {
+      code = error.code;
+    }
+  } catch {
+    // A throwing getter or proxy trap; report no code.
+  }
+  op_set_fatal_error_code(code);
+}
+
+// Builders for every error class ops can throw, so that errors with
+// structured details can be built with the right class.
+const errorBuilders = {
+  __proto__: null,
+  Error: (msg) => new Error(msg),
+  RangeError: (msg) => new RangeError(msg),
+  ReferenceError: (msg) => new ReferenceError(msg),
+  SyntaxError: (msg) => new SyntaxError(msg),
+  TypeError: (msg) => new TypeError(msg),
+  URIError: (msg) => new URIError(msg),
+  // Registered by deno_core.
+  BadResource: (msg) => new errors.BadResource(msg),
+  Interrupted: (msg) => new errors.Interrupted(msg),
+  NotCapable: (msg) => new errors.NotCapable(msg),
+};
+
+function registerErrorClass(className, errorClass) {
+  errorBuilders[className] = (msg) => new errorClass(msg);
+  core.registerErrorClass(className, errorClass);
+}
+
+function registerErrorBuilder(className, errorBuilder) {
+  errorBuilders[className] = errorBuilder;
+  core.registerErrorBuilder(className, errorBuilder);
+}
+
+// Op errors that carry a code, a retryable flag or wrapped errors are thrown
+// with this class; their details are staged by the op layer.
 core.registerErrorBuilder(
+  "DenoStructuredError",
+  function DenoStructuredError(msg) {
+    const details = op_take_op_error_details();
+    if (details === null) {
+      return new Error(msg);
+    }
+    const build = errorBuilders[details.class] ?? errorBuilders.Error;
+    const error = build(msg);
+    if (details.code !== null) {
+      error.code = details.code;
+    }
+    error.retryable = details.retryable;
+    ObjectAssign(error, details.properties);
+    let cause;
+    for (let i = details.causes.length - 1; i >= 0; --i) {
+      const causeMsg = details.causes[i];
+      cause = cause === undefined
+        ? new Error(causeMsg)
+        : new Error(causeMsg, { cause });
+      // The wrapped errors come from Rust, there are no JS frames to show.
+      cause.stack = `Error: ${causeMsg}`;
+    }
+    if (cause !== undefined) {
+      ObjectDefineProperty(error, "cause", {
+        __proto__: null,
+        value: cause,
+        writable: true,
+        enumerable: false,
+        configurable: true,
+      });
+    }
+    return error;
+  },
+);
+
+registerErrorClass("NotFound", errors.NotFound);
+registerErrorClass("ConnectionRefused", errors.ConnectionRefused);
+registerErrorClass("ConnectionReset", errors.ConnectionReset);
+registerErrorClass("ConnectionAborted", errors.ConnectionAborted);
+registerErrorClass("NotConnected", errors.NotConnected);
+registerErrorClass("AddrInUse", errors.AddrInUse);
+registerErrorClass("AddrNotAvailable", errors.AddrNotAvailable);
+registerErrorClass("BrokenPipe", errors.BrokenPipe);
+registerErrorClass("PermissionDenied", errors.PermissionDenied);
+registerErrorClass("AlreadyExists", errors.AlreadyExists);
+registerErrorClass("InvalidData", errors.InvalidData);
+registerErrorClass("TimedOut", errors.TimedOut);
+registerErrorClass("WouldBlock", errors.WouldBlock);
+registerErrorClass("WriteZero", errors.WriteZero);
+registerErrorClass("UnexpectedEof", errors.UnexpectedEof);
+registerErrorClass("Http", errors.Http);
+registerErrorClass("Busy", errors.Busy);
+registerErrorClass("NotSupported", errors.NotSupported);
+registerErrorClass("FilesystemLoop", errors.FilesystemLoop);
+registerErrorClass("IsADirectory", errors.IsADirectory);
+registerErrorClass("NetworkUnreachable", errors.NetworkUnreachable);
+registerErrorClass("NotADirectory", errors.NotADirectory);
+registerErrorClass("StaleRead", errors.StaleRead);
+registerErrorClass("LimitExceeded", errors.LimitExceeded);
+registerErrorBuilder(
   "DOMExceptionOperationError",
   function DOMExceptionOperationError(msg) {
     return new DOMException(msg, "OperationError");
   },
 );
-core.registerErrorBuilder(
+registerErrorBuilder(
   "DOMExceptionQuotaExceededError",
   function DOMExceptionQuotaExceededError(msg) {
     return new DOMException(msg, "QuotaExceededError");
   },
 );
-core.registerErrorBuilder(
+registerErrorBuilder(
   "DOMExceptionNotSupportedError",
   function DOMExceptionNotSupportedError(msg) {
     return new DOMException(msg, "NotSupported");
   },
 );
-core.registerErrorBuilder(
+registerErrorBuilder(
   "DOMExceptionNetworkError",
   function DOMExceptionNetworkError(msg) {
     return new DOMException(msg, "NetworkError");
   },
 );
-core.registerErrorBuilder(
+registerErrorBuilder(
   "DOMExceptionAbortError",
   function DOMExceptionAbortError(msg) {
     return new DOMException(msg, "AbortError");
   },
 );
-core.registerErrorBuilder(
+registerErrorBuilder(
   "DOMExceptionInvalidCharacterError",
   function DOMExceptionInvalidCharacterError(msg) {
     return new DOMException(msg, "InvalidCharacterError");
   },
 );
-core.registerErrorBuilder(
+registerErrorBuilder(
   "DOMExceptionDataError",
   function DOMExceptionDataError(msg) {
     return new DOMException(msg, "DataError");
@@ -374,6 +490,10 @@ function runtimeStart(
 core.setUnhandledPromiseRejectionHandler(processUnhandledPromiseRejection);
 core.setHandledPromiseRejectionHandler(processRejectionHandled);
 
+// Values of `UnhandledRejectionPolicy`, besides the default `Exit`.
+const UNHANDLED_REJECTION_WARN = 1;
+const UNHANDLED_REJECTION_SILENCE = 2;
+
 // Notification that the core received an unhandled promise rejection that is about to
 // terminate the runtime. If we can handle it, attempt to do so.
 function processUnhandledPromiseRejection(promise, reason) {
@@ -404,7 +524,27 @@ function processUnhandledPromiseRejection(promise, reason) {
     return true;
   }
 
-  return false;
+  // Last chance: an "unhandledRejection" lifecycle hook can mark the
+  // rejection as handled.
+  if (lifecycle.dispatchUnhandledRejection(promise, reason)) {
+    return true;
+  }
+
+  // Otherwise the embedder decides, see `UnhandledRejectionPolicy`.
+  switch (op_unhandled_rejection_policy()) {
+    case UNHANDLED_REJECTION_WARN:
+      core.print(
+        `warning: Unhandled promise rejection: ${
+          inspectArgs([reason], { colors: !getStderrNoColor() })
+        }\n`,
+        true,
+      );
+      return true;
+    case UNHANDLED_REJECTION_SILENCE:
+      return true;
+    default:
+      return false;
+  }
 }
 
 function processRejectionHandled(promise, reason) {
@@ -432,9 +572,36 @@ function dispatchBeforeUnloadEvent() {
 }
 
 function dispatchUnloadEvent() {
+  lifecycle.dispatchLifecycleEvent("beforeShutdown", {
+    exitCode: os.getExitCode(),
+  });
   globalThis_.dispatchEvent(new Event("unload"));
 }
 
+// The runtime forwards SIGINT here when it manages it, see
+// `SigintDisposition`. It exits with code 130 if the program doesn't exit
+// within the grace period, or on a second SIGINT.
+async function handleInterrupts() {
+  while (await signals.pollSigint()) {
+    const interruptEvent = new Event("interrupt", { cancelable: true });
+    globalThis_.dispatchEvent(interruptEvent);
+    if (interruptEvent.defaultPrevented) {
+      signals.sigintHandled();
+      continue;
+    }
+    if (os.getExitCode() === 0) {
+      os.setExitCode(130);
+    }
+    // Nothing is going to clean up, so there is no reason to wait.
+    if (
+      event.listenerCount(globalThis_, "interrupt") === 0 &&
+      !signals.hasSignalListener("SIGINT")
+    ) {
+      os.exit();
+    }
+  }
+}
+
 let hasBootstrapped = false;
 // Set up global properties shared by main and worker runtime.
 ObjectDefineProperties(globalThis, windowOrWorkerGlobalScope);
@@ -470,6 +637,7 @@ const NOT_IMPORTED_OPS = [
   // Related to `Deno.jupyter` API
   "op_jupyter_broadcast",
   "op_jupyter_input",
+  "op_jupyter_input_request",
 
   // Related to `Deno.test()` API
   "op_test_event_step_result_failed",
@@ -506,6 +674,8 @@ function removeImportedOps() {
 // `Deno[Deno.internal]` namespace. It should be removed and only necessary
 // methods should be left there.
 ObjectAssign(internals, { core });
+// Exposed for tests.
+ObjectAssign(internals, { ByteSink, ByteSource });
 const internalSymbol = Symbol("Deno.internal");
 const finalDenoNs = {
   internal: internalSymbol,
@@ -683,6 +853,11 @@ function bootstrapMainRuntime(runtimeOptions, warmup = false) {
     event.defineEventHandler(globalThis, "load");
     event.defineEventHandler(globalThis, "beforeunload");
     event.defineEventHandler(globalThis, "unload");
+    event.defineEventHandler(globalThis, "interrupt");
+    event.defineEventHandler(globalThis, "storage");
+
+    handleInterrupts();
+    pollStorageEvents();
 
     runtimeStart(
       denoVersion,
@@ -829,6 +1004,8 @@ function bootstrapMainRuntime(runtimeOptions, warmup = false) {
         nodeDebug,
       });
     }
+
+    lifecycle.dispatchLifecycleEvent("postInit");
   } else {
     // Warmup
   }
@@ -1036,6 +1213,8 @@ function bootstrapWorkerRuntime(
         nodeDebug,
       });
     }
+
+    lifecycle.dispatchLifecycleEvent("postInit");
   } else {
     // Warmup
     return;
@@ -1057,6 +1236,8 @@ globalThis.bootstrap = {
   dispatchBeforeUnloadEvent,
   dispatchProcessExitEvent,
   dispatchProcessBeforeExitEvent,
+  dispatchLifecycleEvent: lifecycle.dispatchLifecycleEvent,
+  dispatchBeforeTerminate,
 };
 
 event.setEventTargetData(globalThis);
@@ -27,14 +27,21 @@ pub use deno_websocket;
 pub use deno_webstorage;
 
 pub mod code_cache;
+pub mod cpu_time;
+pub mod deprecated_ops;
 pub mod errors;
+pub mod event_loop_monitor;
+pub mod fatal_error;
 pub mod fmt_errors;
 pub mod fs_util;
 pub mod inspector_server;
 pub mod js;
+pub mod module_graph;
 pub mod ops;
+pub mod otel;
 pub mod permissions;
 pub mod snapshot;
+pub mod source_map;
 pub mod tokio_util;
 pub mod web_worker;
 pub mod worker;
@@ -69,6 +76,12 @@ pub static UNSTABLE_GRANULAR_FLAGS: &[UnstableGranularFlag] = &[
     show_in_help: true,
     id: 2,
   },
+  UnstableGranularFlag {
+    name: deno_crypto::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable password based key wrapping APIs",
+    show_in_help: true,
+    id: 13,
+  },
   UnstableGranularFlag {
     name: deno_ffi::UNSTABLE_FEATURE_NAME,
     help_text: "Enable unstable FFI APIs",
@@ -112,6 +125,12 @@ pub static UNSTABLE_GRANULAR_FLAGS: &[UnstableGranularFlag] = &[
     show_in_help: true,
     id: 9,
   },
+  UnstableGranularFlag {
+    name: ops::tty::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable terminal input APIs",
+    show_in_help: true,
+    id: 14,
+  },
   UnstableGranularFlag {
     name: "unsafe-proto",
     help_text: "Enable unsafe __proto__ support. This is a security risk.",
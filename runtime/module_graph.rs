@@ -0,0 +1,374 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Virtual modules and a record of the modules a worker loaded.
+//!
+//! The module loader of a `MainWorker` is wrapped in a loader that serves
+//! the virtual modules added with `MainWorker::add_virtual_module()` and
+//! records every module it loads, with its static imports, size and load
+//! time. `MainWorker::module_graph_snapshot()` returns that record, and the
+//! `op_module_graph_snapshot` op returns it to JS.
+//!
+//! Virtual modules never reach the wrapped loader, so they aren't written to
+//! its caches, and V8 code cache isn't produced for them.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_ast::MediaType;
+use deno_ast::ParseParams;
+use deno_ast::SourceMapOption;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::futures::FutureExt;
+use deno_core::v8;
+use deno_core::ModuleLoadResponse;
+use deno_core::ModuleLoader;
+use deno_core::ModuleSource;
+use deno_core::ModuleSourceCode;
+use deno_core::ModuleSpecifier;
+use deno_core::ModuleType;
+use deno_core::RequestedModuleType;
+use deno_core::ResolutionKind;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+/// A module loaded by a worker.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedModule {
+  pub specifier: ModuleSpecifier,
+  /// The resolved specifiers of the module's static imports, in the order
+  /// they were resolved.
+  pub dependencies: Vec<ModuleSpecifier>,
+  /// The size of the source in bytes, as returned by the loader.
+  pub size: usize,
+  /// How long the loader took to return the source, in milliseconds.
+  pub load_time_ms: f64,
+  /// Whether the module was added with `MainWorker::add_virtual_module()`.
+  pub is_virtual: bool,
+}
+
+/// The modules loaded by a worker, in the order they finished loading.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ModuleGraphSnapshot {
+  pub modules: Vec<LoadedModule>,
+}
+
+impl ModuleGraphSnapshot {
+  pub fn get(&self, specifier: &ModuleSpecifier) -> Option<&LoadedModule> {
+    self
+      .modules
+      .iter()
+      .find(|module| &module.specifier == specifier)
+  }
+}
+
+struct VirtualModule {
+  source: String,
+  module_type: ModuleType,
+}
+
+#[derive(Default)]
+struct ModuleGraphState {
+  virtual_modules: HashMap<ModuleSpecifier, VirtualModule>,
+  modules: IndexMap<ModuleSpecifier, LoadedModule>,
+  /// Imports resolved for modules that didn't finish loading yet.
+  dependencies: HashMap<ModuleSpecifier, Vec<ModuleSpecifier>>,
+}
+
+/// The virtual modules and loaded modules of a worker. Cloning it returns a
+/// handle to the same graph.
+#[derive(Clone, Default)]
+pub struct ModuleGraph(Rc<RefCell<ModuleGraphState>>);
+
+impl ModuleGraph {
+  /// Registers `source` as the module `specifier`. JSX and TypeScript are
+  /// transpiled right away. Replaces an earlier virtual module with the same
+  /// specifier, but modules that were already evaluated keep their exports.
+  pub fn add_virtual_module(
+    &self,
+    specifier: ModuleSpecifier,
+    source: String,
+    media_type: MediaType,
+  ) -> Result<(), AnyError> {
+    let (source, module_type) = match media_type {
+      MediaType::JavaScript | MediaType::Mjs | MediaType::Cjs => {
+        (source, ModuleType::JavaScript)
+      }
+      MediaType::Json => (source, ModuleType::Json),
+      MediaType::Jsx
+      | MediaType::TypeScript
+      | MediaType::Mts
+      | MediaType::Cts
+      | MediaType::Tsx => (
+        transpile(&specifier, source, media_type)?,
+        ModuleType::JavaScript,
+      ),
+      _ => {
+        return Err(generic_error(format!(
+          "Unsupported media type {media_type} for virtual module \"{specifier}\""
+        )))
+      }
+    };
+    self.0.borrow_mut().virtual_modules.insert(
+      specifier,
+      VirtualModule {
+        source,
+        module_type,
+      },
+    );
+    Ok(())
+  }
+
+  pub fn snapshot(&self) -> ModuleGraphSnapshot {
+    ModuleGraphSnapshot {
+      modules: self.0.borrow().modules.values().cloned().collect(),
+    }
+  }
+
+  fn resolve_virtual(&self, specifier: &str) -> Option<ModuleSpecifier> {
+    let specifier = ModuleSpecifier::parse(specifier).ok()?;
+    self
+      .0
+      .borrow()
+      .virtual_modules
+      .contains_key(&specifier)
+      .then_some(specifier)
+  }
+
+  fn is_virtual(&self, specifier: &ModuleSpecifier) -> bool {
+    self.0.borrow().virtual_modules.contains_key(specifier)
+  }
+
+  fn add_dependency(
+    &self,
+    referrer: ModuleSpecifier,
+    specifier: ModuleSpecifier,
+  ) {
+    let mut state = self.0.borrow_mut();
+    let state = &mut *state;
+    let dependencies = match state.modules.get_mut(&referrer) {
+      Some(module) => &mut module.dependencies,
+      None => state.dependencies.entry(referrer).or_default(),
+    };
+    if !dependencies.contains(&specifier) {
+      dependencies.push(specifier);
+    }
+  }
+
+  fn add_module(
+    &self,
+    specifier: &ModuleSpecifier,
+    size: usize,
+    load_time: Duration,
+    is_virtual: bool,
+  ) {
+    let mut state = self.0.borrow_mut();
+    let dependencies = state.dependencies.remove(specifier).unwrap_or_default();
+    state.modules.insert(
+      specifier.clone(),
+      LoadedModule {
+        specifier: specifier.clone(),
+        dependencies,
+        size,
+        load_time_ms: load_time.as_secs_f64() * 1000.0,
+        is_virtual,
+      },
+    );
+  }
+
+  fn add_loaded(
+    &self,
+    specifier: &ModuleSpecifier,
+    result: &Result<ModuleSource, AnyError>,
+    start: Instant,
+  ) {
+    if let Ok(source) = result {
+      self.add_module(specifier, source_size(source), start.elapsed(), false);
+    }
+  }
+}
+
+fn source_size(source: &ModuleSource) -> usize {
+  match &source.code {
+    ModuleSourceCode::String(code) => code.as_bytes().len(),
+    ModuleSourceCode::Bytes(code) => code.as_bytes().len(),
+  }
+}
+
+fn transpile(
+  specifier: &ModuleSpecifier,
+  source: String,
+  media_type: MediaType,
+) -> Result<String, AnyError> {
+  let parsed = deno_ast::parse_module(ParseParams {
+    specifier: specifier.clone(),
+    text: source.into(),
+    media_type,
+    capture_tokens: false,
+    scope_analysis: false,
+    maybe_syntax: None,
+  })?;
+  let transpiled = parsed
+    .transpile(
+      &deno_ast::TranspileOptions {
+        imports_not_used_as_values: deno_ast::ImportsNotUsedAsValues::Remove,
+        ..Default::default()
+      },
+      &deno_ast::EmitOptions {
+        source_map: SourceMapOption::Inline,
+        ..Default::default()
+      },
+    )?
+    .into_source();
+  Ok(String::from_utf8(transpiled.source)?)
+}
+
+/// Serves the virtual modules of a [`ModuleGraph`] and records the modules
+/// loaded through the wrapped loader.
+pub(crate) struct ModuleGraphLoader {
+  inner: Rc<dyn ModuleLoader>,
+  graph: ModuleGraph,
+}
+
+impl ModuleGraphLoader {
+  pub fn new(inner: Rc<dyn ModuleLoader>, graph: ModuleGraph) -> Self {
+    Self { inner, graph }
+  }
+}
+
+impl ModuleLoader for ModuleGraphLoader {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer: &str,
+    kind: ResolutionKind,
+  ) -> Result<ModuleSpecifier, AnyError> {
+    let resolved = match self.graph.resolve_virtual(specifier) {
+      Some(resolved) => resolved,
+      None => self.inner.resolve(specifier, referrer, kind)?,
+    };
+    // Dynamic imports and `import.meta.resolve()` aren't dependencies of
+    // the module.
+    if kind == ResolutionKind::Import {
+      if let Ok(referrer) = ModuleSpecifier::parse(referrer) {
+        self.graph.add_dependency(referrer, resolved.clone());
+      }
+    }
+    Ok(resolved)
+  }
+
+  fn get_host_defined_options<'s>(
+    &self,
+    scope: &mut v8::HandleScope<'s>,
+    name: &str,
+  ) -> Option<v8::Local<'s, v8::Data>> {
+    self.inner.get_host_defined_options(scope, name)
+  }
+
+  fn load(
+    &self,
+    specifier: &ModuleSpecifier,
+    maybe_referrer: Option<&ModuleSpecifier>,
+    is_dynamic: bool,
+    requested_module_type: RequestedModuleType,
+  ) -> ModuleLoadResponse {
+    let start = Instant::now();
+    let virtual_module = self
+      .graph
+      .0
+      .borrow()
+      .virtual_modules
+      .get(specifier)
+      .map(|module| {
+        ModuleSource::new(
+          module.module_type.clone(),
+          ModuleSourceCode::String(module.source.clone().into()),
+          specifier,
+          None,
+        )
+      });
+    if let Some(source) = virtual_module {
+      let size = source_size(&source);
+      self
+        .graph
+        .add_module(specifier, size, start.elapsed(), true);
+      return ModuleLoadResponse::Sync(Ok(source));
+    }
+
+    match self.inner.load(
+      specifier,
+      maybe_referrer,
+      is_dynamic,
+      requested_module_type,
+    ) {
+      ModuleLoadResponse::Sync(result) => {
+        self.graph.add_loaded(specifier, &result, start);
+        ModuleLoadResponse::Sync(result)
+      }
+      ModuleLoadResponse::Async(future) => {
+        let graph = self.graph.clone();
+        let specifier = specifier.clone();
+        ModuleLoadResponse::Async(
+          async move {
+            let result = future.await;
+            graph.add_loaded(&specifier, &result, start);
+            result
+          }
+          .boxed_local(),
+        )
+      }
+    }
+  }
+
+  fn prepare_load(
+    &self,
+    specifier: &ModuleSpecifier,
+    maybe_referrer: Option<String>,
+    is_dynamic: bool,
+  ) -> Pin<Box<dyn Future<Output = Result<(), AnyError>>>> {
+    if self.graph.is_virtual(specifier) {
+      return Box::pin(deno_core::futures::future::ready(Ok(())));
+    }
+    self
+      .inner
+      .prepare_load(specifier, maybe_referrer, is_dynamic)
+  }
+
+  fn finish_load(&self) {
+    self.inner.finish_load()
+  }
+
+  fn code_cache_ready(
+    &self,
+    specifier: ModuleSpecifier,
+    source_hash: u64,
+    code_cache: &[u8],
+  ) -> Pin<Box<dyn Future<Output = ()>>> {
+    if self.graph.is_virtual(&specifier) {
+      return Box::pin(deno_core::futures::future::ready(()));
+    }
+    self
+      .inner
+      .code_cache_ready(specifier, source_hash, code_cache)
+  }
+
+  fn get_source_map(&self, file_name: &str) -> Option<Vec<u8>> {
+    self.inner.get_source_map(file_name)
+  }
+
+  fn get_source_mapped_source_line(
+    &self,
+    file_name: &str,
+    line_number: usize,
+  ) -> Option<String> {
+    self
+      .inner
+      .get_source_mapped_source_line(file_name, line_number)
+  }
+}
@@ -0,0 +1,844 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A content-addressed blob store on disk.
+//!
+//! Bytes are stored once under their SHA-256 hash and streamed back from disk
+//! when they're opened, so features that hold on to large bodies (Cache API
+//! responses, downloads, spilled `postMessage` payloads) can share one copy.
+//! Every put of a blob adds a reference and every delete drops one; the file
+//! is only removed when the last reference is dropped.
+//!
+//! Layout of the store directory:
+//!
+//! - `blobs/<first two hex digits>/<hash>`: the content.
+//! - `refs/<first two hex digits>/<hash>`: the reference count, in decimal.
+//! - `tmp/`: puts in progress.
+//!
+//! A put writes and fsyncs a temp file, renames it into `blobs/` and then
+//! writes the reference count (through a temp file as well). A delete removes
+//! the reference count before the content. So a crash can leave temp files
+//! and blobs without a reference count behind, but never a reference count
+//! that points to missing or partial content. Both kinds of leftovers are
+//! removed by the sweep that runs when the store is first used.
+//!
+//! Puts and deletes are serialized within a process; the store is not meant
+//! to be written by several processes at once.
+
+#![allow(clippy::disallowed_methods)]
+
+use std::cell::RefCell;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use deno_core::op2;
+use deno_core::unsync::spawn_blocking;
+use deno_core::OpState;
+use deno_core::ResourceId;
+use deno_io::fs::FileResource;
+use deno_io::StdFileResourceInner;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tempfile::NamedTempFile;
+
+deno_core::extension!(
+  deno_blob_store,
+  ops = [
+    op_blob_store_put,
+    op_blob_store_put_stream,
+    op_blob_store_open,
+    op_blob_store_delete,
+    op_blob_store_stats,
+  ],
+  options = { store: Option<Arc<ContentStore>> },
+  state = |state, options| {
+    if let Some(store) = options.store {
+      state.put(store);
+    }
+  },
+);
+
+const BLOBS_DIR: &str = "blobs";
+const REFS_DIR: &str = "refs";
+const TMP_DIR: &str = "tmp";
+
+/// Temp files and unreferenced blobs older than this are considered left
+/// over by a crashed process. Younger ones may belong to a put that is still
+/// in progress.
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlobStoreError {
+  #[error("Blob store is not available")]
+  NotAvailable,
+  #[error("Invalid blob hash '{0}': expected 64 lowercase hex digits")]
+  InvalidHash(String),
+  #[error("Content of blob '{0}' doesn't match its hash")]
+  HashMismatch(String),
+  #[error("Blob '{0}' not found")]
+  NotFound(String),
+  #[error("Invalid reference count for blob '{0}'")]
+  CorruptRefCount(String),
+  #[error(transparent)]
+  Resource(deno_core::error::AnyError),
+  #[error(transparent)]
+  Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobInfo {
+  pub hash: String,
+  pub size: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobStoreStats {
+  /// Number of stored blobs.
+  pub blobs: u64,
+  /// Total size of the stored blobs, in bytes.
+  pub bytes: u64,
+  /// Sum of the reference counts of all blobs.
+  pub references: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobEntry {
+  pub hash: String,
+  pub size: u64,
+  pub refs: u64,
+}
+
+/// What the startup sweep removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SweepReport {
+  pub temp_files: u64,
+  pub unreferenced_blobs: u64,
+  pub dangling_refs: u64,
+}
+
+/// A put in progress: the bytes written so far, in a temp file in the store.
+/// Dropping it without calling `ContentStore::commit` deletes the temp file.
+pub struct PendingBlob {
+  file: NamedTempFile,
+  hasher: Sha256,
+  size: u64,
+}
+
+impl PendingBlob {
+  pub fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+    self.hasher.update(buf);
+    self.file.write_all(buf)?;
+    self.size += buf.len() as u64;
+    Ok(())
+  }
+}
+
+struct PendingWriter<'a>(&'a mut PendingBlob);
+
+impl Write for PendingWriter<'_> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.write(buf)?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+pub struct ContentStore {
+  root: PathBuf,
+  /// Serializes the steps that read or change the reference counts. `true`
+  /// once the directories exist and the sweep ran.
+  lock: Mutex<bool>,
+}
+
+impl ContentStore {
+  /// Creates a store in `root`. The directory is created and swept the first
+  /// time the store is used.
+  pub fn new(root: PathBuf) -> Self {
+    Self {
+      root,
+      lock: Mutex::new(false),
+    }
+  }
+
+  fn lock(&self) -> Result<MutexGuard<'_, bool>, BlobStoreError> {
+    let mut opened = self.lock.lock().unwrap();
+    if !*opened {
+      for dir in [BLOBS_DIR, REFS_DIR, TMP_DIR] {
+        fs::create_dir_all(self.root.join(dir))?;
+      }
+      self.sweep(ORPHAN_GRACE_PERIOD)?;
+      *opened = true;
+    }
+    Ok(opened)
+  }
+
+  fn blob_path(&self, hash: &str) -> PathBuf {
+    self.root.join(BLOBS_DIR).join(&hash[..2]).join(hash)
+  }
+
+  fn refs_path(&self, hash: &str) -> PathBuf {
+    self.root.join(REFS_DIR).join(&hash[..2]).join(hash)
+  }
+
+  fn temp_file(&self) -> io::Result<NamedTempFile> {
+    tempfile::Builder::new()
+      .suffix(".tmp")
+      .tempfile_in(self.root.join(TMP_DIR))
+  }
+
+  /// Starts a put. Write the content to the returned `PendingBlob` and pass
+  /// it to `commit`.
+  pub fn begin(&self) -> Result<PendingBlob, BlobStoreError> {
+    drop(self.lock()?);
+    Ok(PendingBlob {
+      file: self.temp_file()?,
+      hasher: Sha256::new(),
+      size: 0,
+    })
+  }
+
+  /// Stores the content of `pending` and adds a reference to it. If a blob
+  /// with the same content is already stored, the new copy is discarded.
+  pub fn commit(
+    &self,
+    pending: PendingBlob,
+  ) -> Result<BlobInfo, BlobStoreError> {
+    self.commit_with_refs(pending, |refs| refs + 1)
+  }
+
+  /// Like `commit`, but `refs` maps the current reference count of the blob
+  /// (0 if it isn't stored yet) to the new one.
+  fn commit_with_refs(
+    &self,
+    pending: PendingBlob,
+    refs: impl FnOnce(u64) -> u64,
+  ) -> Result<BlobInfo, BlobStoreError> {
+    let PendingBlob { file, hasher, size } = pending;
+    file.as_file().sync_all()?;
+    let hash = faster_hex::hex_string(&hasher.finalize());
+
+    let _guard = self.lock()?;
+    let path = self.blob_path(&hash);
+    let refs = if path.exists() {
+      drop(file);
+      refs(self.read_refs(&hash)?)
+    } else {
+      let dir = path.parent().unwrap();
+      fs::create_dir_all(dir)?;
+      file.persist(&path).map_err(|err| err.error)?;
+      sync_dir(dir)?;
+      // a reference count left without content by a crash is reset here
+      refs(0)
+    };
+    self.write_refs(&hash, refs)?;
+    Ok(BlobInfo { hash, size })
+  }
+
+  /// Stores everything read from `reader`.
+  pub fn put(&self, mut reader: impl Read) -> Result<BlobInfo, BlobStoreError> {
+    let mut pending = self.begin()?;
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+      let n = match reader.read(&mut buf) {
+        Ok(0) => break,
+        Ok(n) => n,
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+        Err(err) => return Err(err.into()),
+      };
+      pending.write(&buf[..n])?;
+    }
+    self.commit(pending)
+  }
+
+  /// Opens a stored blob for reading.
+  pub fn open(&self, hash: &str) -> Result<File, BlobStoreError> {
+    validate_hash(hash)?;
+    drop(self.lock()?);
+    File::open(self.blob_path(hash)).map_err(|err| {
+      if err.kind() == io::ErrorKind::NotFound {
+        BlobStoreError::NotFound(hash.to_string())
+      } else {
+        err.into()
+      }
+    })
+  }
+
+  /// Drops a reference to a blob and returns the number of references left.
+  /// The blob is removed when none are left.
+  pub fn delete(&self, hash: &str) -> Result<u64, BlobStoreError> {
+    validate_hash(hash)?;
+    let _guard = self.lock()?;
+    let path = self.blob_path(hash);
+    let refs = self.read_refs(hash)?;
+    if !path.exists() {
+      return Err(BlobStoreError::NotFound(hash.to_string()));
+    }
+    if refs > 1 {
+      self.write_refs(hash, refs - 1)?;
+      return Ok(refs - 1);
+    }
+    remove_if_exists(&self.refs_path(hash))?;
+    fs::remove_file(&path)?;
+    sync_dir(path.parent().unwrap())?;
+    Ok(0)
+  }
+
+  /// Lists the stored blobs with their reference counts. The list is a
+  /// snapshot; blobs may be deleted before they are opened.
+  pub fn entries(&self) -> Result<Vec<BlobEntry>, BlobStoreError> {
+    let _guard = self.lock()?;
+    let mut entries = vec![];
+    for_each_file(&self.root.join(BLOBS_DIR), |path, metadata| {
+      let Some(hash) = file_hash(path) else {
+        return Ok(());
+      };
+      let refs = self.read_refs(hash)?;
+      // blobs without references are leftovers or puts in progress
+      if refs > 0 {
+        entries.push(BlobEntry {
+          hash: hash.to_string(),
+          size: metadata.len(),
+          refs,
+        });
+      }
+      Ok(())
+    })?;
+    entries.sort_by(|a, b| a.hash.cmp(&b.hash));
+    Ok(entries)
+  }
+
+  /// Stores everything read from `reader` with exactly `refs` references,
+  /// as when restoring a backup. If the blob is already stored, its
+  /// reference count is only changed if `overwrite` is set. Returns whether
+  /// the blob was written.
+  pub fn restore(
+    &self,
+    reader: impl Read,
+    hash: &str,
+    refs: u64,
+    overwrite: bool,
+  ) -> Result<bool, BlobStoreError> {
+    validate_hash(hash)?;
+    {
+      let _guard = self.lock()?;
+      if self.blob_path(hash).exists() {
+        if overwrite {
+          self.write_refs(hash, refs)?;
+        }
+        return Ok(overwrite);
+      }
+    }
+    let mut pending = self.begin()?;
+    io::copy(&mut { reader }, &mut PendingWriter(&mut pending))?;
+    if faster_hex::hex_string(&pending.hasher.clone().finalize()) != hash {
+      return Err(BlobStoreError::HashMismatch(hash.to_string()));
+    }
+    self.commit_with_refs(pending, |existing| {
+      // `existing` is only set if the blob was stored concurrently
+      if existing == 0 || overwrite {
+        refs
+      } else {
+        existing
+      }
+    })?;
+    Ok(true)
+  }
+
+  pub fn stats(&self) -> Result<BlobStoreStats, BlobStoreError> {
+    let _guard = self.lock()?;
+    let mut stats = BlobStoreStats::default();
+    for_each_file(&self.root.join(BLOBS_DIR), |path, metadata| {
+      let Some(hash) = file_hash(path) else {
+        return Ok(());
+      };
+      stats.blobs += 1;
+      stats.bytes += metadata.len();
+      stats.references += self.read_refs(hash)?;
+      Ok(())
+    })?;
+    Ok(stats)
+  }
+
+  /// Removes what a crashed process left behind: temp files and blobs without
+  /// a reference count that are older than `grace_period`, and reference
+  /// counts without a blob.
+  pub fn sweep(
+    &self,
+    grace_period: Duration,
+  ) -> Result<SweepReport, BlobStoreError> {
+    let mut report = SweepReport::default();
+    let cutoff = SystemTime::now()
+      .checked_sub(grace_period)
+      .unwrap_or(SystemTime::UNIX_EPOCH);
+    let is_old = |metadata: &fs::Metadata| {
+      metadata.modified().map(|m| m < cutoff).unwrap_or(false)
+    };
+
+    for_each_file(&self.root.join(TMP_DIR), |path, metadata| {
+      if is_old(metadata) {
+        remove_if_exists(path)?;
+        report.temp_files += 1;
+      }
+      Ok(())
+    })?;
+    for_each_file(&self.root.join(REFS_DIR), |path, _| {
+      let has_blob =
+        file_hash(path).is_some_and(|hash| self.blob_path(hash).exists());
+      if !has_blob {
+        remove_if_exists(path)?;
+        report.dangling_refs += 1;
+      }
+      Ok(())
+    })?;
+    for_each_file(&self.root.join(BLOBS_DIR), |path, metadata| {
+      let has_refs =
+        file_hash(path).is_some_and(|hash| self.refs_path(hash).exists());
+      if !has_refs && is_old(metadata) {
+        remove_if_exists(path)?;
+        report.unreferenced_blobs += 1;
+      }
+      Ok(())
+    })?;
+    Ok(report)
+  }
+
+  fn read_refs(&self, hash: &str) -> Result<u64, BlobStoreError> {
+    match fs::read_to_string(self.refs_path(hash)) {
+      Ok(text) => text
+        .trim()
+        .parse()
+        .map_err(|_| BlobStoreError::CorruptRefCount(hash.to_string())),
+      Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  fn write_refs(&self, hash: &str, refs: u64) -> Result<(), BlobStoreError> {
+    let path = self.refs_path(hash);
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir)?;
+    let mut file = self.temp_file()?;
+    write!(file, "{refs}")?;
+    file.as_file().sync_all()?;
+    file.persist(&path).map_err(|err| err.error)?;
+    sync_dir(dir)?;
+    Ok(())
+  }
+}
+
+fn validate_hash(hash: &str) -> Result<(), BlobStoreError> {
+  let valid = hash.len() == 64
+    && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+  if valid {
+    Ok(())
+  } else {
+    Err(BlobStoreError::InvalidHash(hash.to_string()))
+  }
+}
+
+/// The hash a file in `blobs/` or `refs/` is named after.
+fn file_hash(path: &Path) -> Option<&str> {
+  let name = path.file_name()?.to_str()?;
+  validate_hash(name).ok()?;
+  Some(name)
+}
+
+/// Calls `f` for every file in `dir` and in its direct subdirectories.
+fn for_each_file(
+  dir: &Path,
+  mut f: impl FnMut(&Path, &fs::Metadata) -> Result<(), BlobStoreError>,
+) -> Result<(), BlobStoreError> {
+  let mut dirs = vec![dir.to_path_buf()];
+  let mut depth = 0;
+  while depth < 2 && !dirs.is_empty() {
+    for dir in std::mem::take(&mut dirs) {
+      let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+        Err(err) => return Err(err.into()),
+      };
+      for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+          dirs.push(entry.path());
+        } else {
+          f(&entry.path(), &metadata)?;
+        }
+      }
+    }
+    depth += 1;
+  }
+  Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+  match fs::remove_file(path) {
+    Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+    _ => Ok(()),
+  }
+}
+
+/// Makes a rename or removal in `dir` durable.
+fn sync_dir(dir: &Path) -> io::Result<()> {
+  #[cfg(unix)]
+  {
+    File::open(dir)?.sync_all()
+  }
+  #[cfg(not(unix))]
+  {
+    // Windows doesn't support opening directories for syncing; renames are
+    // durable once the rename call returns.
+    let _ = dir;
+    Ok(())
+  }
+}
+
+fn store(state: &OpState) -> Result<Arc<ContentStore>, BlobStoreError> {
+  state
+    .try_borrow::<Arc<ContentStore>>()
+    .cloned()
+    .ok_or(BlobStoreError::NotAvailable)
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_blob_store_put(
+  state: Rc<RefCell<OpState>>,
+  #[buffer(copy)] data: Vec<u8>,
+) -> Result<BlobInfo, BlobStoreError> {
+  let store = store(&state.borrow())?;
+  spawn_blocking(move || store.put(data.as_slice()))
+    .await
+    .unwrap()
+}
+
+/// Stores everything read from the resource `rid`, which is closed when the
+/// put is done.
+#[op2(async)]
+#[serde]
+pub async fn op_blob_store_put_stream(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<BlobInfo, BlobStoreError> {
+  let store = store(&state.borrow())?;
+  let resource = state
+    .borrow_mut()
+    .resource_table
+    .take_any(rid)
+    .map_err(BlobStoreError::Resource)?;
+  let store_ = store.clone();
+  let result: Result<PendingBlob, BlobStoreError> = async {
+    let mut pending = spawn_blocking(move || store_.begin()).await.unwrap()?;
+    loop {
+      let buf = resource
+        .clone()
+        .read(CHUNK_SIZE)
+        .await
+        .map_err(BlobStoreError::Resource)?;
+      if buf.is_empty() {
+        return Ok(pending);
+      }
+      pending = spawn_blocking(move || {
+        pending.write(&buf)?;
+        Ok::<_, BlobStoreError>(pending)
+      })
+      .await
+      .unwrap()?;
+    }
+  }
+  .await;
+  resource.close();
+  let pending = result?;
+  spawn_blocking(move || store.commit(pending)).await.unwrap()
+}
+
+#[op2(async)]
+#[smi]
+pub async fn op_blob_store_open(
+  state: Rc<RefCell<OpState>>,
+  #[string] hash: String,
+) -> Result<ResourceId, BlobStoreError> {
+  let store = store(&state.borrow())?;
+  let file = spawn_blocking(move || store.open(&hash)).await.unwrap()?;
+  let file = Rc::new(StdFileResourceInner::file(file));
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add(FileResource::new(file, "blobStoreBlob".to_string()));
+  Ok(rid)
+}
+
+/// Drops a reference to a blob. Returns the number of references left.
+#[op2(async)]
+#[number]
+pub async fn op_blob_store_delete(
+  state: Rc<RefCell<OpState>>,
+  #[string] hash: String,
+) -> Result<u64, BlobStoreError> {
+  let store = store(&state.borrow())?;
+  spawn_blocking(move || store.delete(&hash)).await.unwrap()
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_blob_store_stats(
+  state: Rc<RefCell<OpState>>,
+) -> Result<BlobStoreStats, BlobStoreError> {
+  let store = store(&state.borrow())?;
+  spawn_blocking(move || store.stats()).await.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread;
+
+  fn sha256(data: &[u8]) -> String {
+    faster_hex::hex_string(&Sha256::digest(data))
+  }
+
+  fn blob_files(root: &Path) -> usize {
+    let mut count = 0;
+    for_each_file(&root.join(BLOBS_DIR), |_, _| {
+      count += 1;
+      Ok(())
+    })
+    .unwrap();
+    count
+  }
+
+  fn read_all(store: &ContentStore, hash: &str) -> Vec<u8> {
+    let mut data = vec![];
+    store.open(hash).unwrap().read_to_end(&mut data).unwrap();
+    data
+  }
+
+  #[test]
+  fn identical_puts_are_stored_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().to_path_buf());
+    let a = store.put(&b"hello world"[..]).unwrap();
+    let b = store.put(&b"hello world"[..]).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.hash, sha256(b"hello world"));
+    assert_eq!(a.size, 11);
+    assert_eq!(blob_files(dir.path()), 1);
+    assert_eq!(read_all(&store, &a.hash), b"hello world");
+    assert_eq!(
+      store.stats().unwrap(),
+      BlobStoreStats {
+        blobs: 1,
+        bytes: 11,
+        references: 2,
+      }
+    );
+  }
+
+  #[test]
+  fn concurrent_identical_puts_coalesce() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(ContentStore::new(dir.path().to_path_buf()));
+    let data = vec![7u8; 1024 * 1024];
+    let handles = (0..8)
+      .map(|_| {
+        let store = store.clone();
+        let data = data.clone();
+        thread::spawn(move || store.put(data.as_slice()).unwrap())
+      })
+      .collect::<Vec<_>>();
+    for handle in handles {
+      assert_eq!(handle.join().unwrap().hash, sha256(&data));
+    }
+    assert_eq!(blob_files(dir.path()), 1);
+    assert_eq!(store.stats().unwrap().references, 8);
+    // no temp file is left behind
+    assert_eq!(fs::read_dir(dir.path().join(TMP_DIR)).unwrap().count(), 0);
+  }
+
+  #[test]
+  fn delete_drops_one_reference() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().to_path_buf());
+    let shared = store.put(&b"shared"[..]).unwrap();
+    store.put(&b"shared"[..]).unwrap();
+    let other = store.put(&b"other"[..]).unwrap();
+
+    assert_eq!(store.delete(&shared.hash).unwrap(), 1);
+    assert_eq!(read_all(&store, &shared.hash), b"shared");
+    assert_eq!(store.delete(&shared.hash).unwrap(), 0);
+    assert!(matches!(
+      store.open(&shared.hash),
+      Err(BlobStoreError::NotFound(_))
+    ));
+    assert!(matches!(
+      store.delete(&shared.hash),
+      Err(BlobStoreError::NotFound(_))
+    ));
+    assert_eq!(read_all(&store, &other.hash), b"other");
+    assert_eq!(
+      store.stats().unwrap(),
+      BlobStoreStats {
+        blobs: 1,
+        bytes: 5,
+        references: 1,
+      }
+    );
+
+    // the blob can be stored again
+    store.put(&b"shared"[..]).unwrap();
+    assert_eq!(read_all(&store, &shared.hash), b"shared");
+  }
+
+  #[test]
+  fn restore_sets_reference_counts() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().to_path_buf());
+    let a = store.put(&b"a"[..]).unwrap();
+    let b_hash = sha256(b"b");
+
+    assert!(store.restore(&b"b"[..], &b_hash, 3, false).unwrap());
+    // an existing blob keeps its count, unless overwritten
+    assert!(!store.restore(&b"a"[..], &a.hash, 5, false).unwrap());
+    assert_eq!(
+      store.entries().unwrap().iter().map(|e| e.refs).sum::<u64>(),
+      4
+    );
+    assert!(store.restore(&b"a"[..], &a.hash, 5, true).unwrap());
+    let mut expected = vec![
+      BlobEntry {
+        hash: a.hash.clone(),
+        size: 1,
+        refs: 5,
+      },
+      BlobEntry {
+        hash: b_hash.clone(),
+        size: 1,
+        refs: 3,
+      },
+    ];
+    expected.sort_by(|a, b| a.hash.cmp(&b.hash));
+    assert_eq!(store.entries().unwrap(), expected);
+    assert_eq!(read_all(&store, &b_hash), b"b");
+
+    let err = store.restore(&b"not c"[..], &sha256(b"c"), 1, false);
+    assert!(matches!(err, Err(BlobStoreError::HashMismatch(_))));
+    assert_eq!(store.entries().unwrap().len(), 2);
+  }
+
+  #[test]
+  fn invalid_hashes_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().to_path_buf());
+    let upper = "A".repeat(64);
+    let short = "0".repeat(63);
+    for hash in ["", "../../etc/passwd", &upper, &short] {
+      assert!(matches!(
+        store.open(hash),
+        Err(BlobStoreError::InvalidHash(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn sweep_removes_crash_leftovers() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_path_buf();
+    let kept = {
+      let store = ContentStore::new(root.clone());
+      store.put(&b"kept"[..]).unwrap()
+    };
+
+    // a put that crashed while writing its temp file
+    let old = SystemTime::now() - ORPHAN_GRACE_PERIOD * 2;
+    let stale_tmp = root.join(TMP_DIR).join("crashed.tmp");
+    File::create(&stale_tmp).unwrap().set_modified(old).unwrap();
+    // a put that is still writing in another store instance
+    let fresh_tmp = root.join(TMP_DIR).join("in_progress.tmp");
+    File::create(&fresh_tmp).unwrap();
+    // a put that crashed after renaming the blob, before counting the
+    // reference
+    let unreferenced = sha256(b"unreferenced");
+    let unreferenced_path = root.join(BLOBS_DIR).join(&unreferenced[..2]);
+    fs::create_dir_all(&unreferenced_path).unwrap();
+    let unreferenced_path = unreferenced_path.join(&unreferenced);
+    fs::write(&unreferenced_path, b"unreferenced").unwrap();
+    File::options()
+      .write(true)
+      .open(&unreferenced_path)
+      .unwrap()
+      .set_modified(old)
+      .unwrap();
+    // a delete that crashed after removing the blob
+    let dangling = sha256(b"dangling");
+    let dangling_path = root.join(REFS_DIR).join(&dangling[..2]);
+    fs::create_dir_all(&dangling_path).unwrap();
+    fs::write(dangling_path.join(&dangling), "1").unwrap();
+
+    let store = ContentStore::new(root.clone());
+    assert_eq!(
+      store.stats().unwrap(),
+      BlobStoreStats {
+        blobs: 1,
+        bytes: 4,
+        references: 1,
+      }
+    );
+    assert!(!stale_tmp.exists());
+    assert!(fresh_tmp.exists());
+    assert!(!unreferenced_path.exists());
+    assert!(!dangling_path.join(&dangling).exists());
+    assert_eq!(read_all(&store, &kept.hash), b"kept");
+  }
+
+  #[test]
+  fn large_blobs_are_streamed() {
+    const SIZE: u64 = 300 * 1024 * 1024;
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().to_path_buf());
+
+    // `io::repeat` produces the content on the fly, so neither the put nor
+    // the read below hold more than one chunk in memory.
+    let info = store.put(io::repeat(0xab).take(SIZE)).unwrap();
+    assert_eq!(info.size, SIZE);
+    let mut expected = Sha256::new();
+    io::copy(&mut io::repeat(0xab).take(SIZE), &mut expected).unwrap();
+    assert_eq!(info.hash, faster_hex::hex_string(&expected.finalize()));
+
+    let mut file = store.open(&info.hash).unwrap();
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut read = 0;
+    loop {
+      let n = file.read(&mut buf).unwrap();
+      if n == 0 {
+        break;
+      }
+      assert!(buf[..n].iter().all(|b| *b == 0xab));
+      hasher.update(&buf[..n]);
+      read += n as u64;
+    }
+    assert_eq!(read, SIZE);
+    assert_eq!(faster_hex::hex_string(&hasher.finalize()), info.hash);
+  }
+}
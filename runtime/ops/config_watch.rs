@@ -0,0 +1,440 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Watching a configuration file for `Deno.watchConfig`.
+//!
+//! The file is read and parsed in Rust whenever it changes, and the result is
+//! delivered as an event. Changes are debounced, so an editor saving a file in
+//! several writes produces a single event. The first event carries the
+//! initial contents, so consumers handle the startup and the reloads the same
+//! way.
+//!
+//! The last contents that parsed successfully are retained, and an event for
+//! contents that don't parse carries them as the previous value, so the
+//! application can keep running with them.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use deno_core::op2;
+use deno_core::serde_json;
+use deno_core::unsync::spawn_blocking;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::ToJsBuffer;
+use deno_permissions::PermissionsContainer;
+use deno_web::EventReceiver;
+use notify::RecursiveMode;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::fs_events::canonicalize_watched;
+use super::fs_events::start_watcher;
+use super::fs_events::watch_path;
+use super::fs_events::FsEventMessage;
+use super::fs_events::FsEventsError;
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ConfigParser {
+  #[default]
+  Json,
+  Toml,
+  Raw,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigWatchOptions {
+  debounce_ms: Option<u64>,
+  #[serde(default)]
+  parser: ConfigParser,
+}
+
+/// Contents of the watched file, parsed according to the `ConfigParser`.
+#[derive(Debug, Clone, PartialEq)]
+enum ConfigValue {
+  Parsed(serde_json::Value),
+  Raw(Vec<u8>),
+}
+
+impl Serialize for ConfigValue {
+  fn serialize<S: serde::Serializer>(
+    &self,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    match self {
+      ConfigValue::Parsed(value) => value.serialize(serializer),
+      ConfigValue::Raw(bytes) => {
+        ToJsBuffer::from(bytes.clone()).serialize(serializer)
+      }
+    }
+  }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+enum ConfigWatchEvent {
+  Initial {
+    value: ConfigValue,
+  },
+  Update {
+    value: ConfigValue,
+  },
+  /// The file couldn't be read or parsed. `previous` is the last value that
+  /// was delivered, if any.
+  Error {
+    message: String,
+    previous: Option<ConfigValue>,
+  },
+}
+
+#[derive(Debug, thiserror::Error)]
+enum LoadError {
+  #[error("Failed to read '{path}': {source}")]
+  Read {
+    path: String,
+    source: std::io::Error,
+  },
+  #[error("Failed to parse '{path}': {message}")]
+  Parse { path: String, message: String },
+}
+
+fn parse(
+  parser: ConfigParser,
+  path: &Path,
+  bytes: Vec<u8>,
+) -> Result<ConfigValue, LoadError> {
+  let parse_error = |message: String| LoadError::Parse {
+    path: path.display().to_string(),
+    message,
+  };
+  match parser {
+    ConfigParser::Raw => Ok(ConfigValue::Raw(bytes)),
+    ConfigParser::Json => serde_json::from_slice(&bytes)
+      .map(ConfigValue::Parsed)
+      .map_err(|err| parse_error(err.to_string())),
+    ConfigParser::Toml => {
+      let text =
+        String::from_utf8(bytes).map_err(|err| parse_error(err.to_string()))?;
+      text
+        .parse::<toml::Table>()
+        .map(|table| {
+          ConfigValue::Parsed(toml_to_json(toml::Value::Table(table)))
+        })
+        .map_err(|err| parse_error(err.to_string()))
+    }
+  }
+}
+
+/// Converts TOML to the JSON data model, with dates and times as their RFC
+/// 3339 strings.
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+  match value {
+    toml::Value::String(s) => serde_json::Value::String(s),
+    toml::Value::Integer(i) => i.into(),
+    toml::Value::Float(f) => serde_json::Number::from_f64(f)
+      .map(serde_json::Value::Number)
+      .unwrap_or(serde_json::Value::Null),
+    toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+    toml::Value::Datetime(datetime) => {
+      serde_json::Value::String(datetime.to_string())
+    }
+    toml::Value::Array(array) => {
+      serde_json::Value::Array(array.into_iter().map(toml_to_json).collect())
+    }
+    toml::Value::Table(table) => serde_json::Value::Object(
+      table
+        .into_iter()
+        .map(|(key, value)| (key, toml_to_json(value)))
+        .collect(),
+    ),
+  }
+}
+
+/// Tracks the contents of the watched file across events.
+struct ConfigState {
+  parser: ConfigParser,
+  /// The bytes read last, so that changes that leave the contents as they
+  /// were, like touching the file, don't produce events.
+  last_read: Option<Result<Vec<u8>, std::io::ErrorKind>>,
+  last_good: Option<ConfigValue>,
+}
+
+impl ConfigState {
+  fn new(parser: ConfigParser) -> Self {
+    Self {
+      parser,
+      last_read: None,
+      last_good: None,
+    }
+  }
+
+  /// Turns the result of reading the file into an event, or `None` when it
+  /// is the same as the last time.
+  fn on_read(
+    &mut self,
+    path: &Path,
+    read: std::io::Result<Vec<u8>>,
+  ) -> Option<ConfigWatchEvent> {
+    let initial = self.last_read.is_none();
+    let last_read = match &read {
+      Ok(bytes) => Ok(bytes.clone()),
+      Err(err) => Err(err.kind()),
+    };
+    if self.last_read.as_ref() == Some(&last_read) {
+      return None;
+    }
+    self.last_read = Some(last_read);
+
+    let read = read.map_err(|source| LoadError::Read {
+      path: path.display().to_string(),
+      source,
+    });
+    match read.and_then(|bytes| parse(self.parser, path, bytes)) {
+      Ok(value) => {
+        self.last_good = Some(value.clone());
+        Some(if initial {
+          ConfigWatchEvent::Initial { value }
+        } else {
+          ConfigWatchEvent::Update { value }
+        })
+      }
+      Err(err) => Some(ConfigWatchEvent::Error {
+        message: err.to_string(),
+        previous: self.last_good.clone(),
+      }),
+    }
+  }
+}
+
+struct ConfigWatchResource {
+  path: PathBuf,
+  debounce: Duration,
+  /// Canonicalized `path`, to match watcher events against.
+  watched: Vec<PathBuf>,
+  changes: AsyncRefCell<EventReceiver<FsEventMessage>>,
+  state: RefCell<ConfigState>,
+  started: Cell<bool>,
+  cancel: CancelHandle,
+}
+
+impl Resource for ConfigWatchResource {
+  fn name(&self) -> Cow<str> {
+    "configWatch".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl ConfigWatchResource {
+  /// Waits for the next event about the file. Returns `false` when the
+  /// watcher is gone.
+  async fn next_change(&self, changes: &EventReceiver<FsEventMessage>) -> bool {
+    loop {
+      match changes.recv().await {
+        Some(message) if message.matches(&self.watched) => return true,
+        Some(_) => continue,
+        None => return false,
+      }
+    }
+  }
+
+  /// Waits for the file to change, and then for `debounce` to pass without
+  /// further changes. Returns `false` when the watcher was closed.
+  async fn changed(self: &Rc<Self>) -> bool {
+    let changes = RcRef::map(self, |r| &r.changes).borrow_mut().await;
+    if !self.next_change(&changes).await {
+      return false;
+    }
+    loop {
+      match tokio::time::timeout(self.debounce, self.next_change(&changes))
+        .await
+      {
+        Ok(true) => continue,
+        Ok(false) | Err(_) => return true,
+      }
+    }
+  }
+
+  async fn next(
+    self: &Rc<Self>,
+  ) -> Result<Option<ConfigWatchEvent>, FsEventsError> {
+    if self.started.replace(true) && !self.changed().await {
+      return Ok(None);
+    }
+    loop {
+      let path = self.path.clone();
+      #[allow(clippy::disallowed_methods)]
+      let read = spawn_blocking(move || std::fs::read(path))
+        .await
+        .unwrap_or_else(|err| Err(std::io::Error::other(err)));
+      if let Some(event) = self.state.borrow_mut().on_read(&self.path, read) {
+        return Ok(Some(event));
+      }
+      if !self.changed().await {
+        return Ok(None);
+      }
+    }
+  }
+}
+
+#[op2]
+#[smi]
+pub fn op_config_watch_open(
+  state: &mut OpState,
+  #[string] path: String,
+  #[serde] options: ConfigWatchOptions,
+) -> Result<ResourceId, FsEventsError> {
+  super::check_unstable(
+    state,
+    deno_fs::UNSTABLE_FEATURE_NAME,
+    "Deno.watchConfig",
+  );
+  let path = state
+    .borrow_mut::<PermissionsContainer>()
+    .check_read(&path, "Deno.watchConfig()")
+    .map_err(FsEventsError::Permission)?;
+
+  let changes = start_watcher(state)?;
+  // Watch the directory rather than the file, so that the file keeps being
+  // watched when it is replaced, as editors do when saving.
+  let dir = path.parent().unwrap_or(&path).to_path_buf();
+  watch_path(state, &dir, RecursiveMode::NonRecursive)?;
+
+  let watched = canonicalize_watched(&[path.clone()]);
+  let resource = ConfigWatchResource {
+    path,
+    debounce: options
+      .debounce_ms
+      .map(Duration::from_millis)
+      .unwrap_or(DEFAULT_DEBOUNCE),
+    watched,
+    changes: AsyncRefCell::new(changes),
+    state: RefCell::new(ConfigState::new(options.parser)),
+    started: Cell::new(false),
+    cancel: Default::default(),
+  };
+  Ok(state.resource_table.add(resource))
+}
+
+/// Returns the next event of the config watcher `rid`, or `null` once it is
+/// closed.
+#[op2(async)]
+#[serde]
+pub async fn op_config_watch_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ConfigWatchEvent>, FsEventsError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<ConfigWatchResource>(rid)
+    .map_err(FsEventsError::Resource)?;
+  let cancel = RcRef::map(&resource, |r| &r.cancel);
+  resource.next().or_cancel(cancel).await?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::serde_json::json;
+
+  fn path() -> &'static Path {
+    Path::new("config.json")
+  }
+
+  #[test]
+  fn events_keep_the_last_good_value() {
+    let mut state = ConfigState::new(ConfigParser::Json);
+    let v1 = ConfigValue::Parsed(json!({ "port": 1 }));
+    let v2 = ConfigValue::Parsed(json!({ "port": 2 }));
+
+    assert_eq!(
+      state.on_read(path(), Ok(br#"{"port":1}"#.to_vec())),
+      Some(ConfigWatchEvent::Initial { value: v1.clone() })
+    );
+    // touching the file doesn't produce an event
+    assert_eq!(state.on_read(path(), Ok(br#"{"port":1}"#.to_vec())), None);
+    let Some(ConfigWatchEvent::Error { message, previous }) =
+      state.on_read(path(), Ok(br#"{"port":"#.to_vec()))
+    else {
+      panic!("expected an error event");
+    };
+    assert!(
+      message.starts_with("Failed to parse 'config.json'"),
+      "{message}"
+    );
+    assert_eq!(previous, Some(v1.clone()));
+    assert_eq!(
+      state.on_read(path(), Ok(br#"{"port":2}"#.to_vec())),
+      Some(ConfigWatchEvent::Update { value: v2.clone() })
+    );
+    let Some(ConfigWatchEvent::Error { message, previous }) = state.on_read(
+      path(),
+      Err(std::io::Error::from(std::io::ErrorKind::NotFound)),
+    ) else {
+      panic!("expected an error event");
+    };
+    assert!(
+      message.starts_with("Failed to read 'config.json'"),
+      "{message}"
+    );
+    assert_eq!(previous, Some(v2));
+  }
+
+  #[test]
+  fn initial_error_has_no_previous_value() {
+    let mut state = ConfigState::new(ConfigParser::Json);
+    assert!(matches!(
+      state.on_read(path(), Ok(b"{".to_vec())),
+      Some(ConfigWatchEvent::Error { previous: None, .. })
+    ));
+    assert_eq!(
+      state.on_read(path(), Ok(b"[]".to_vec())),
+      Some(ConfigWatchEvent::Update {
+        value: ConfigValue::Parsed(json!([]))
+      })
+    );
+  }
+
+  #[test]
+  fn parses_toml_and_raw() {
+    let toml = br#"
+      name = "app"
+      started = 1979-05-27T07:32:00Z
+      [server]
+      ports = [80, 443]
+      ratio = 0.5
+    "#;
+    assert_eq!(
+      parse(ConfigParser::Toml, path(), toml.to_vec()).unwrap(),
+      ConfigValue::Parsed(json!({
+        "name": "app",
+        "started": "1979-05-27T07:32:00Z",
+        "server": { "ports": [80, 443], "ratio": 0.5 },
+      }))
+    );
+    assert!(matches!(
+      parse(ConfigParser::Toml, path(), b"name = ".to_vec()),
+      Err(LoadError::Parse { .. })
+    ));
+    assert_eq!(
+      parse(ConfigParser::Raw, path(), b"not json".to_vec()).unwrap(),
+      ConfigValue::Raw(b"not json".to_vec())
+    );
+  }
+}
@@ -0,0 +1,296 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Scoped environments for subprocesses and workers.
+//!
+//! An [`EnvPolicy`] describes an environment in terms of the environment of
+//! the spawning runtime: which variables to inherit, which to remove and
+//! which to set. Inherited variables are only forwarded if the spawning
+//! runtime has env permission for them, so `--allow-env=FOO,BAR` also limits
+//! what a subprocess or worker can inherit.
+//!
+//! Workers spawned with a policy get a [`ScopedEnv`] in their `OpState`,
+//! which `Deno.env` and subprocesses spawned by the worker use instead of the
+//! process environment.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+
+use deno_core::serde::Deserialize;
+use deno_core::serde::Serialize;
+use deno_core::OpState;
+use deno_permissions::PermissionState;
+use deno_permissions::PermissionsContainer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvInheritMode {
+  All,
+  None,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum EnvInherit {
+  Mode(EnvInheritMode),
+  Names(Vec<String>),
+}
+
+impl Default for EnvInherit {
+  fn default() -> Self {
+    EnvInherit::Mode(EnvInheritMode::All)
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvPolicy {
+  #[serde(default)]
+  pub inherit: EnvInherit,
+  #[serde(default)]
+  pub set: Vec<(String, String)>,
+  #[serde(default)]
+  pub remove: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvPolicyDiagnostics {
+  /// Inherited variables that were dropped because env permission wasn't
+  /// granted for them, sorted.
+  pub denied: Vec<String>,
+}
+
+pub struct ResolvedEnv {
+  pub vars: HashMap<OsString, OsString>,
+  pub diagnostics: EnvPolicyDiagnostics,
+}
+
+impl EnvPolicy {
+  /// Builds an environment from `parent`. The fields apply in order:
+  ///
+  /// 1. `inherit` selects the variables of `parent` to start from. Of those,
+  ///    only the ones `permissions` grants env access to are kept. This never
+  ///    prompts; denied variables are reported in the diagnostics instead.
+  /// 2. `remove` drops variables. Removed variables are not reported as
+  ///    denied.
+  /// 3. `set` sets variables, also ones listed in `remove`.
+  pub fn resolve(
+    &self,
+    parent: HashMap<OsString, OsString>,
+    permissions: &PermissionsContainer,
+  ) -> ResolvedEnv {
+    let inherit_names = match &self.inherit {
+      EnvInherit::Mode(EnvInheritMode::All) => None,
+      EnvInherit::Mode(EnvInheritMode::None) => Some(HashSet::new()),
+      EnvInherit::Names(names) => {
+        Some(names.iter().map(|name| env_key(name)).collect())
+      }
+    };
+    let remove = self
+      .remove
+      .iter()
+      .map(|name| env_key(name))
+      .collect::<HashSet<_>>();
+
+    let mut denied = Vec::new();
+    let mut vars = HashMap::new();
+    for (key, value) in parent {
+      let key = normalize_env_key(key);
+      if inherit_names
+        .as_ref()
+        .is_some_and(|names| !names.contains(&key))
+      {
+        continue;
+      }
+      if remove.contains(&key) {
+        continue;
+      }
+      if can_inherit(permissions, &key) {
+        vars.insert(key, value);
+      } else {
+        denied.push(key.to_string_lossy().into_owned());
+      }
+    }
+    for (key, value) in &self.set {
+      vars.insert(env_key(key), OsString::from(value));
+    }
+
+    denied.sort();
+    ResolvedEnv {
+      vars,
+      diagnostics: EnvPolicyDiagnostics { denied },
+    }
+  }
+}
+
+fn can_inherit(permissions: &PermissionsContainer, key: &OsStr) -> bool {
+  // permissions can't be granted for names that aren't valid UTF-8, only
+  // for all variables
+  if permissions.query_env(None) == PermissionState::Granted {
+    return true;
+  }
+  key.to_str().is_some_and(|name| {
+    permissions.query_env(Some(name)) == PermissionState::Granted
+  })
+}
+
+/// Environment variable names are case insensitive on Windows, where they
+/// are compared uppercased.
+pub fn normalize_env_key(key: OsString) -> OsString {
+  if cfg!(windows) {
+    key.to_ascii_uppercase()
+  } else {
+    key
+  }
+}
+
+fn env_key(key: &str) -> OsString {
+  normalize_env_key(OsString::from(key))
+}
+
+/// The environment of a worker that was spawned with an [`EnvPolicy`],
+/// which it sees instead of the process environment.
+#[derive(Debug, Clone, Default)]
+pub struct ScopedEnv(HashMap<OsString, OsString>);
+
+impl ScopedEnv {
+  pub fn new(vars: HashMap<OsString, OsString>) -> Self {
+    Self(vars)
+  }
+
+  pub fn get(&self, key: &str) -> Option<&OsStr> {
+    self.0.get(&env_key(key)).map(OsString::as_os_str)
+  }
+
+  pub fn set(&mut self, key: &str, value: &str) {
+    self.0.insert(env_key(key), OsString::from(value));
+  }
+
+  pub fn remove(&mut self, key: &str) {
+    self.0.remove(&env_key(key));
+  }
+
+  pub fn vars(&self) -> impl Iterator<Item = (&OsStr, &OsStr)> {
+    self.0.iter().map(|(k, v)| (k.as_os_str(), v.as_os_str()))
+  }
+}
+
+/// The environment as seen by the runtime of `state`, with normalized keys.
+pub fn env_snapshot(state: &OpState) -> HashMap<OsString, OsString> {
+  match state.try_borrow::<ScopedEnv>() {
+    Some(scoped) => scoped.0.clone(),
+    None => std::env::vars_os()
+      .map(|(key, value)| (normalize_env_key(key), value))
+      .collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use deno_fs::RealFs;
+  use deno_permissions::Permissions;
+  use deno_permissions::PermissionsOptions;
+
+  use super::*;
+  use crate::permissions::RuntimePermissionDescriptorParser;
+
+  fn permissions(allow_env: Option<Vec<&str>>) -> PermissionsContainer {
+    let parser = RuntimePermissionDescriptorParser::new(Arc::new(RealFs));
+    let perms = Permissions::from_options(
+      &parser,
+      &PermissionsOptions {
+        allow_env: allow_env
+          .map(|names| names.into_iter().map(String::from).collect()),
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    PermissionsContainer::new(Arc::new(parser), perms)
+  }
+
+  fn parent() -> HashMap<OsString, OsString> {
+    [("FOO", "foo"), ("BAR", "bar"), ("SECRET", "secret")]
+      .into_iter()
+      .map(|(k, v)| (OsString::from(k), OsString::from(v)))
+      .collect()
+  }
+
+  fn sorted(vars: &HashMap<OsString, OsString>) -> Vec<(&str, &str)> {
+    let mut vars = vars
+      .iter()
+      .map(|(k, v)| (k.to_str().unwrap(), v.to_str().unwrap()))
+      .collect::<Vec<_>>();
+    vars.sort();
+    vars
+  }
+
+  #[test]
+  fn inherit_filtered_by_permissions() {
+    let policy = EnvPolicy::default();
+    let resolved =
+      policy.resolve(parent(), &permissions(Some(vec!["FOO", "BAR"])));
+    assert_eq!(sorted(&resolved.vars), vec![("BAR", "bar"), ("FOO", "foo")]);
+    assert_eq!(resolved.diagnostics.denied, vec!["SECRET"]);
+
+    let resolved = policy.resolve(parent(), &permissions(Some(vec![])));
+    assert_eq!(resolved.vars.len(), 3);
+    assert!(resolved.diagnostics.denied.is_empty());
+
+    let resolved = policy.resolve(parent(), &permissions(None));
+    assert!(resolved.vars.is_empty());
+    assert_eq!(resolved.diagnostics.denied, vec!["BAR", "FOO", "SECRET"]);
+  }
+
+  #[test]
+  fn inherit_names_and_none() {
+    let policy = EnvPolicy {
+      inherit: EnvInherit::Names(vec!["FOO".into(), "SECRET".into()]),
+      ..Default::default()
+    };
+    let resolved = policy.resolve(parent(), &permissions(Some(vec!["FOO"])));
+    assert_eq!(sorted(&resolved.vars), vec![("FOO", "foo")]);
+    // only variables selected by `inherit` are reported
+    assert_eq!(resolved.diagnostics.denied, vec!["SECRET"]);
+
+    let policy = EnvPolicy {
+      inherit: EnvInherit::Mode(EnvInheritMode::None),
+      set: vec![("A".into(), "a".into())],
+      ..Default::default()
+    };
+    let resolved = policy.resolve(parent(), &permissions(None));
+    assert_eq!(sorted(&resolved.vars), vec![("A", "a")]);
+    assert!(resolved.diagnostics.denied.is_empty());
+  }
+
+  #[test]
+  fn precedence() {
+    let policy = EnvPolicy {
+      inherit: EnvInherit::Mode(EnvInheritMode::All),
+      set: vec![("FOO".into(), "set".into()), ("NEW".into(), "new".into())],
+      remove: vec!["FOO".into(), "BAR".into()],
+    };
+    let resolved = policy.resolve(parent(), &permissions(Some(vec![])));
+    assert_eq!(
+      sorted(&resolved.vars),
+      vec![("FOO", "set"), ("NEW", "new"), ("SECRET", "secret")]
+    );
+
+    let resolved = policy.resolve(parent(), &permissions(Some(vec!["FOO"])));
+    assert_eq!(sorted(&resolved.vars), vec![("FOO", "set"), ("NEW", "new")]);
+    assert_eq!(resolved.diagnostics.denied, vec!["SECRET"]);
+  }
+
+  #[test]
+  fn scoped_env() {
+    let mut env = ScopedEnv::new(parent());
+    env.set("NEW", "new");
+    env.remove("SECRET");
+    assert_eq!(env.get("NEW"), Some(OsStr::new("new")));
+    assert_eq!(env.get("SECRET"), None);
+    assert_eq!(env.vars().count(), 3);
+  }
+}
@@ -1,17 +1,14 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
-use deno_core::parking_lot::Mutex;
-use deno_core::AsyncRefCell;
-use deno_core::CancelFuture;
-use deno_core::CancelHandle;
+use deno_core::futures::stream;
 use deno_core::OpState;
-use deno_core::RcRef;
-use deno_core::Resource;
 use deno_core::ResourceId;
 
 use deno_core::op2;
 
 use deno_permissions::PermissionsContainer;
+use deno_web::EventBus;
+use deno_web::EventReceiver;
 use notify::event::Event as NotifyEvent;
 use notify::event::ModifyKind;
 use notify::Error as NotifyError;
@@ -20,34 +17,22 @@ use notify::RecommendedWatcher;
 use notify::RecursiveMode;
 use notify::Watcher;
 use serde::Serialize;
-use std::borrow::Cow;
-use std::cell::RefCell;
 use std::convert::From;
 use std::path::Path;
 use std::path::PathBuf;
-use std::rc::Rc;
-use std::sync::Arc;
 use tokio::sync::mpsc;
 
 deno_core::extension!(
   deno_fs_events,
-  ops = [op_fs_events_open, op_fs_events_poll],
+  ops = [
+    op_fs_events_open,
+    super::config_watch::op_config_watch_open,
+    super::config_watch::op_config_watch_next,
+  ],
 );
 
-struct FsEventsResource {
-  receiver: AsyncRefCell<mpsc::Receiver<Result<FsEvent, NotifyError>>>,
-  cancel: CancelHandle,
-}
-
-impl Resource for FsEventsResource {
-  fn name(&self) -> Cow<str> {
-    "fsEvents".into()
-  }
-
-  fn close(self: Rc<Self>) {
-    self.cancel.cancel();
-  }
-}
+/// Events a subscriber can fall behind by before the oldest ones are dropped.
+const WATCHER_CAPACITY: usize = 16;
 
 /// Represents a file system event.
 ///
@@ -58,7 +43,7 @@ impl Resource for FsEventsResource {
 /// Feel free to expand this struct as long as you can add tests to demonstrate
 /// the complexity.
 #[derive(Serialize, Debug, Clone)]
-struct FsEvent {
+pub(super) struct FsEvent {
   kind: &'static str,
   paths: Vec<PathBuf>,
   flag: Option<&'static str>,
@@ -91,22 +76,60 @@ impl From<NotifyEvent> for FsEvent {
   }
 }
 
-type WatchSender = (Vec<String>, mpsc::Sender<Result<FsEvent, NotifyError>>);
+/// Published on the `EventBus` for every event reported by the watcher.
+///
+/// There is a single watcher per runtime. `Deno.watchFs()` and
+/// `Deno.watchConfig()` subscribe to these events and keep the ones for the
+/// paths they watch, see `FsEventMessage::matches`.
+#[derive(Debug, Clone)]
+pub(super) struct FsEventMessage {
+  event: FsEvent,
+  /// The paths of `event` that still exist, canonicalized on the watcher
+  /// thread.
+  canonical_paths: Vec<PathBuf>,
+}
+
+impl FsEventMessage {
+  fn new(event: FsEvent) -> Self {
+    let canonical_paths = event
+      .paths
+      .iter()
+      .filter_map(|path| canonicalize(path))
+      .collect();
+    Self {
+      event,
+      canonical_paths,
+    }
+  }
+
+  /// Whether the event is about one of `watched` or a path inside of it.
+  /// `watched` must be canonicalized with `canonicalize_watched`.
+  pub fn matches(&self, watched: &[PathBuf]) -> bool {
+    watched.iter().any(|prefix| {
+      self
+        .canonical_paths
+        .iter()
+        .any(|path| path.starts_with(prefix))
+    })
+  }
+}
 
 struct WatcherState {
-  senders: Arc<Mutex<Vec<WatchSender>>>,
   watcher: RecommendedWatcher,
 }
 
-fn starts_with_canonicalized(path: &Path, prefix: &str) -> bool {
+fn canonicalize(path: &Path) -> Option<PathBuf> {
   #[allow(clippy::disallowed_methods)]
-  let path = path.canonicalize().ok();
-  #[allow(clippy::disallowed_methods)]
-  let prefix = std::fs::canonicalize(prefix).ok();
-  match (path, prefix) {
-    (Some(path), Some(prefix)) => path.starts_with(prefix),
-    _ => false,
-  }
+  path.canonicalize().ok()
+}
+
+/// Canonicalizes the paths a subscriber watches, for
+/// `FsEventMessage::matches`.
+pub(super) fn canonicalize_watched(paths: &[PathBuf]) -> Vec<PathBuf> {
+  paths
+    .iter()
+    .map(|path| canonicalize(path).unwrap_or_else(|| path.clone()))
+    .collect()
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -117,48 +140,53 @@ pub enum FsEventsError {
   Permission(deno_core::error::AnyError),
   #[error(transparent)]
   Notify(#[from] NotifyError),
-  #[error(transparent)]
-  Canceled(#[from] deno_core::Canceled),
 }
 
-fn start_watcher(
+/// Starts the runtime's watcher if it isn't running yet, and subscribes to
+/// its events. Paths are added to the watcher with `watch_path`.
+pub(super) fn start_watcher(
   state: &mut OpState,
-  paths: Vec<String>,
-  sender: mpsc::Sender<Result<FsEvent, NotifyError>>,
-) -> Result<(), FsEventsError> {
-  if let Some(watcher) = state.try_borrow_mut::<WatcherState>() {
-    watcher.senders.lock().push((paths, sender));
-    return Ok(());
+) -> Result<EventReceiver<FsEventMessage>, FsEventsError> {
+  let bus = state.borrow::<EventBus>().clone();
+  let receiver = bus.subscribe_with_capacity(WATCHER_CAPACITY);
+  if state.has::<WatcherState>() {
+    return Ok(receiver);
   }
 
-  let senders = Arc::new(Mutex::new(vec![(paths, sender)]));
-
-  let sender_clone = senders.clone();
+  // notify calls back on its own thread, hand the events over to the
+  // runtime's thread to publish them.
+  let (sender, mut events) = mpsc::channel(WATCHER_CAPACITY);
   let watcher: RecommendedWatcher = Watcher::new(
     move |res: Result<NotifyEvent, NotifyError>| {
-      let res2 = res.map(FsEvent::from).map_err(FsEventsError::Notify);
-      for (paths, sender) in sender_clone.lock().iter() {
-        // Ignore result, if send failed it means that watcher was already closed,
-        // but not all messages have been flushed.
-
-        // Only send the event if the path matches one of the paths that the user is watching
-        if let Ok(event) = &res2 {
-          if paths.iter().any(|path| {
-            event.paths.iter().any(|event_path| {
-              same_file::is_same_file(event_path, path).unwrap_or(false)
-                || starts_with_canonicalized(event_path, path)
-            })
-          }) {
-            let _ = sender.try_send(Ok(event.clone()));
-          }
-        }
+      if let Ok(event) = res {
+        // Ignore result, if send failed it means that the runtime is gone
+        // or isn't keeping up, in which case the event is dropped.
+        let _ = sender.try_send(FsEventMessage::new(FsEvent::from(event)));
       }
     },
     Default::default(),
   )?;
+  deno_core::unsync::spawn(async move {
+    // Ends once the watcher, which owns the sender, is dropped.
+    while let Some(message) = events.recv().await {
+      bus.publish(message);
+    }
+  });
 
-  state.put::<WatcherState>(WatcherState { watcher, senders });
+  state.put::<WatcherState>(WatcherState { watcher });
 
+  Ok(receiver)
+}
+
+/// Adds `path` to the paths watched by the watcher started with
+/// `start_watcher`.
+pub(super) fn watch_path(
+  state: &mut OpState,
+  path: &Path,
+  recursive_mode: RecursiveMode,
+) -> Result<(), FsEventsError> {
+  let watcher = state.borrow_mut::<WatcherState>();
+  watcher.watcher.watch(path, recursive_mode)?;
   Ok(())
 }
 
@@ -169,49 +197,34 @@ fn op_fs_events_open(
   recursive: bool,
   #[serde] paths: Vec<String>,
 ) -> Result<ResourceId, FsEventsError> {
-  let (sender, receiver) = mpsc::channel::<Result<FsEvent, NotifyError>>(16);
-
-  start_watcher(state, paths.clone(), sender)?;
+  let receiver = start_watcher(state)?;
 
   let recursive_mode = if recursive {
     RecursiveMode::Recursive
   } else {
     RecursiveMode::NonRecursive
   };
+  let mut watched = Vec::with_capacity(paths.len());
   for path in &paths {
     let path = state
       .borrow_mut::<PermissionsContainer>()
       .check_read(path, "Deno.watchFs()")
       .map_err(FsEventsError::Permission)?;
 
-    let watcher = state.borrow_mut::<WatcherState>();
-    watcher.watcher.watch(&path, recursive_mode)?;
-  }
-  let resource = FsEventsResource {
-    receiver: AsyncRefCell::new(receiver),
-    cancel: Default::default(),
-  };
-  let rid = state.resource_table.add(resource);
-  Ok(rid)
-}
-
-#[op2(async)]
-#[serde]
-async fn op_fs_events_poll(
-  state: Rc<RefCell<OpState>>,
-  #[smi] rid: ResourceId,
-) -> Result<Option<FsEvent>, FsEventsError> {
-  let resource = state
-    .borrow()
-    .resource_table
-    .get::<FsEventsResource>(rid)
-    .map_err(FsEventsError::Resource)?;
-  let mut receiver = RcRef::map(&resource, |r| &r.receiver).borrow_mut().await;
-  let cancel = RcRef::map(resource, |r| &r.cancel);
-  let maybe_result = receiver.recv().or_cancel(cancel).await?;
-  match maybe_result {
-    Some(Ok(value)) => Ok(Some(value)),
-    Some(Err(err)) => Err(FsEventsError::Notify(err)),
-    None => Ok(None),
+    watch_path(state, &path, recursive_mode)?;
+    watched.push(path);
   }
+  let watched = canonicalize_watched(&watched);
+  let events = stream::unfold(receiver, move |receiver| {
+    let watched = watched.clone();
+    async move {
+      loop {
+        let message = receiver.recv().await?;
+        if message.matches(&watched) {
+          return Some((Ok::<_, FsEventsError>(message.event), receiver));
+        }
+      }
+    }
+  });
+  Ok(deno_web::add_op_stream(state, events))
 }
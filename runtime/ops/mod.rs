@@ -1,14 +1,21 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+pub mod blob_store;
 pub mod bootstrap;
+pub mod config_watch;
+pub mod env_policy;
 pub mod fs_events;
 pub mod http;
 pub mod os;
 pub mod permissions;
 pub mod process;
+pub mod realm;
 pub mod runtime;
 pub mod signal;
+pub mod storage_archive;
 pub mod tty;
+pub mod tty_input;
+pub mod tty_readline;
 pub mod web_worker;
 pub mod worker_host;
 
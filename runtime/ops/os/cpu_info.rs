@@ -0,0 +1,400 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! CPU topology, and how much of it this process can actually use.
+//!
+//! Inside a container the host's CPU count says little about how many
+//! threads are worth running: a cgroup CPU quota of half a CPU still sees all
+//! of the host's cores. The effective parallelism is the logical CPU count
+//! lowered by the affinity mask and, on Linux, by the cgroup v1/v2 CPU quota
+//! and cpuset.
+
+use std::num::NonZeroUsize;
+
+/// Overrides the value of `navigator.hardwareConcurrency`.
+pub const HARDWARE_CONCURRENCY_ENV: &str = "DENO_HARDWARE_CONCURRENCY";
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuInfo {
+  /// Logical CPUs online in the system.
+  pub logical: usize,
+  /// Physical cores, or `logical` where they can't be told apart.
+  pub physical: usize,
+  /// Current frequency of each logical CPU in MHz, where reported.
+  pub frequencies_mhz: Vec<u64>,
+  /// CPUs this process can keep busy.
+  pub effective_parallelism: usize,
+}
+
+pub fn cpu_info() -> CpuInfo {
+  let logical = logical_cpus();
+  let (physical, frequencies_mhz) = topology();
+  CpuInfo {
+    logical,
+    physical: physical.unwrap_or(logical),
+    frequencies_mhz,
+    effective_parallelism: effective_parallelism(logical),
+  }
+}
+
+/// The value of `navigator.hardwareConcurrency`: the effective parallelism,
+/// unless overridden with `DENO_HARDWARE_CONCURRENCY`.
+pub fn hardware_concurrency() -> usize {
+  std::env::var(HARDWARE_CONCURRENCY_ENV)
+    .ok()
+    .and_then(|value| value.parse::<NonZeroUsize>().ok())
+    .map(NonZeroUsize::get)
+    .unwrap_or_else(|| effective_parallelism(logical_cpus()))
+}
+
+fn logical_cpus() -> usize {
+  #[cfg(unix)]
+  {
+    // SAFETY: sysconf has no preconditions.
+    let n = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    if n > 0 {
+      return n as usize;
+    }
+  }
+  std::thread::available_parallelism()
+    .map(NonZeroUsize::get)
+    .unwrap_or(1)
+}
+
+fn effective_parallelism(logical: usize) -> usize {
+  #[cfg(target_os = "linux")]
+  {
+    #[allow(clippy::disallowed_methods)]
+    let read = |path: &std::path::Path| std::fs::read_to_string(path).ok();
+    let limits = [affinity_cpus(), cgroup::cpu_limit(&read)];
+    limits
+      .into_iter()
+      .flatten()
+      .fold(logical, usize::min)
+      .max(1)
+  }
+  #[cfg(not(target_os = "linux"))]
+  {
+    logical
+  }
+}
+
+#[cfg(target_os = "linux")]
+fn affinity_cpus() -> Option<usize> {
+  // SAFETY: an all-zero cpu_set_t is a valid empty set.
+  let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+  // SAFETY: `set` is a valid cpu_set_t of the size passed.
+  let res = unsafe {
+    libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set)
+  };
+  if res != 0 {
+    return None;
+  }
+  // SAFETY: `set` was initialized by sched_getaffinity.
+  let count = unsafe { libc::CPU_COUNT(&set) };
+  (count > 0).then_some(count as usize)
+}
+
+/// Physical core count and per-CPU frequencies.
+#[cfg(target_os = "linux")]
+fn topology() -> (Option<usize>, Vec<u64>) {
+  #[allow(clippy::disallowed_methods)]
+  let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") else {
+    return (None, vec![]);
+  };
+  parse_cpuinfo(&cpuinfo)
+}
+
+#[cfg(target_vendor = "apple")]
+fn topology() -> (Option<usize>, Vec<u64>) {
+  let mut physical: libc::c_int = 0;
+  let mut size = std::mem::size_of_val(&physical);
+  // SAFETY: `physical` is a valid c_int buffer of `size` bytes.
+  let res = unsafe {
+    libc::sysctlbyname(
+      "hw.physicalcpu\0".as_ptr() as *const libc::c_char,
+      &mut physical as *mut _ as *mut libc::c_void,
+      &mut size,
+      std::ptr::null_mut(),
+      0,
+    )
+  };
+  let physical = (res == 0 && physical > 0).then_some(physical as usize);
+  (physical, vec![])
+}
+
+#[cfg(not(any(target_os = "linux", target_vendor = "apple")))]
+fn topology() -> (Option<usize>, Vec<u64>) {
+  (None, vec![])
+}
+
+/// Counts distinct (physical id, core id) pairs and collects the `cpu MHz`
+/// of every processor in `/proc/cpuinfo`.
+#[cfg(any(target_os = "linux", test))]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpuinfo(cpuinfo: &str) -> (Option<usize>, Vec<u64>) {
+  let mut cores = std::collections::HashSet::new();
+  let mut frequencies = vec![];
+  for processor in cpuinfo.split("\n\n") {
+    let mut package = None;
+    let mut core = None;
+    for line in processor.lines() {
+      let Some((key, value)) = line.split_once(':') else {
+        continue;
+      };
+      let value = value.trim();
+      match key.trim() {
+        "physical id" => package = Some(value),
+        "core id" => core = Some(value),
+        "cpu MHz" => {
+          if let Ok(mhz) = value.parse::<f64>() {
+            frequencies.push(mhz.round() as u64);
+          }
+        }
+        _ => {}
+      }
+    }
+    if let (Some(package), Some(core)) = (package, core) {
+      cores.insert((package, core));
+    }
+  }
+  ((!cores.is_empty()).then_some(cores.len()), frequencies)
+}
+
+#[cfg(any(target_os = "linux", test))]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+mod cgroup {
+  use std::path::Path;
+  use std::path::PathBuf;
+
+  const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+  /// The number of CPUs the cgroups of this process allow it to use, with
+  /// the CPU quota rounded up. `None` when there is no limit.
+  ///
+  /// Limits set on the ancestors of the process's cgroup apply as well. In a
+  /// container with its own cgroup namespace the cgroup path is `/` and the
+  /// container's limits are found at the root of the mount.
+  pub fn cpu_limit(read: &dyn Fn(&Path) -> Option<String>) -> Option<usize> {
+    let cgroups = read(Path::new("/proc/self/cgroup"))?;
+    let mut limit = None;
+    for line in cgroups.lines() {
+      let mut fields = line.splitn(3, ':');
+      let (Some(_), Some(controllers), Some(path)) =
+        (fields.next(), fields.next(), fields.next())
+      else {
+        continue;
+      };
+      if controllers.is_empty() {
+        // cgroup v2: a single hierarchy with all controllers
+        for dir in ancestors(Path::new(CGROUP_ROOT), path) {
+          lower(&mut limit, read(&dir.join("cpu.max")).and_then(cpu_max));
+          lower(
+            &mut limit,
+            read(&dir.join("cpuset.cpus.effective")).and_then(cpu_list),
+          );
+        }
+        continue;
+      }
+      // cgroup v1: a hierarchy per set of controllers
+      let root = Path::new(CGROUP_ROOT).join(controllers);
+      let controllers = controllers.split(',').collect::<Vec<_>>();
+      if controllers.contains(&"cpu") {
+        for dir in ancestors(&root, path) {
+          let quota = read(&dir.join("cpu.cfs_quota_us"));
+          let period = read(&dir.join("cpu.cfs_period_us"));
+          lower(&mut limit, quota.zip(period).and_then(cfs_quota));
+        }
+      }
+      if controllers.contains(&"cpuset") {
+        for dir in ancestors(&root, path) {
+          lower(
+            &mut limit,
+            read(&dir.join("cpuset.effective_cpus")).and_then(cpu_list),
+          );
+        }
+      }
+    }
+    limit
+  }
+
+  fn lower(limit: &mut Option<usize>, value: Option<usize>) {
+    if let Some(value) = value {
+      *limit = Some(limit.map_or(value, |limit| limit.min(value)));
+    }
+  }
+
+  /// The cgroup directory at `path` under `root` and its ancestors up to
+  /// `root`.
+  fn ancestors(root: &Path, path: &str) -> Vec<PathBuf> {
+    root
+      .join(path.trim_start_matches('/'))
+      .ancestors()
+      .take_while(|dir| dir.starts_with(root))
+      .map(Path::to_path_buf)
+      .collect()
+  }
+
+  fn quota_cpus(quota: u64, period: u64) -> Option<usize> {
+    if quota == 0 || period == 0 {
+      return None;
+    }
+    Some(quota.div_ceil(period) as usize)
+  }
+
+  /// Parses `cpu.max`: `$MAX $PERIOD`, where `$MAX` may be `max`.
+  fn cpu_max(contents: String) -> Option<usize> {
+    let (quota, period) = contents.trim().split_once(' ')?;
+    quota_cpus(quota.parse().ok()?, period.parse().ok()?)
+  }
+
+  /// Parses `cpu.cfs_quota_us` and `cpu.cfs_period_us`. A quota of -1 means
+  /// no limit.
+  fn cfs_quota((quota, period): (String, String)) -> Option<usize> {
+    quota_cpus(quota.trim().parse().ok()?, period.trim().parse().ok()?)
+  }
+
+  /// Counts the CPUs in a cpuset list such as `0-3,8,10-11`.
+  fn cpu_list(contents: String) -> Option<usize> {
+    let mut count = 0;
+    for range in contents.trim().split(',').filter(|r| !r.is_empty()) {
+      count += match range.split_once('-') {
+        Some((start, end)) => {
+          end
+            .parse::<usize>()
+            .ok()?
+            .checked_sub(start.parse().ok()?)?
+            + 1
+        }
+        None => {
+          range.parse::<usize>().ok()?;
+          1
+        }
+      };
+    }
+    (count > 0).then_some(count)
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn limit(files: &[(&str, &str)]) -> Option<usize> {
+      let files = files
+        .iter()
+        .map(|(path, contents)| (PathBuf::from(path), contents.to_string()))
+        .collect::<HashMap<_, _>>();
+      cpu_limit(&|path| files.get(path).cloned())
+    }
+
+    #[test]
+    fn v2_quota() {
+      let cgroup = ("/proc/self/cgroup", "0::/app.slice/deno.service\n");
+      let quota = "/sys/fs/cgroup/app.slice/deno.service/cpu.max";
+      assert_eq!(limit(&[cgroup, (quota, "50000 100000\n")]), Some(1));
+      assert_eq!(limit(&[cgroup, (quota, "250000 100000\n")]), Some(3));
+      assert_eq!(limit(&[cgroup, (quota, "max 100000\n")]), None);
+      assert_eq!(limit(&[cgroup]), None);
+    }
+
+    #[test]
+    fn v2_ancestors_and_cpuset() {
+      assert_eq!(
+        limit(&[
+          ("/proc/self/cgroup", "0::/app.slice/deno.service\n"),
+          (
+            "/sys/fs/cgroup/app.slice/deno.service/cpu.max",
+            "max 100000"
+          ),
+          ("/sys/fs/cgroup/app.slice/cpu.max", "400000 100000"),
+          (
+            "/sys/fs/cgroup/app.slice/deno.service/cpuset.cpus.effective",
+            "0-1\n"
+          ),
+        ]),
+        Some(2)
+      );
+      // cgroup namespace: the container's limits are at the root
+      assert_eq!(
+        limit(&[
+          ("/proc/self/cgroup", "0::/\n"),
+          ("/sys/fs/cgroup/cpu.max", "150000 100000\n"),
+        ]),
+        Some(2)
+      );
+    }
+
+    #[test]
+    fn v1_quota_and_cpuset() {
+      let cgroup = (
+        "/proc/self/cgroup",
+        "12:cpuset:/docker/abc\n4:cpu,cpuacct:/docker/abc\n1:name=systemd:/\n",
+      );
+      let quota = "/sys/fs/cgroup/cpu,cpuacct/docker/abc/cpu.cfs_quota_us";
+      let period = "/sys/fs/cgroup/cpu,cpuacct/docker/abc/cpu.cfs_period_us";
+      let cpuset = "/sys/fs/cgroup/cpuset/docker/abc/cpuset.effective_cpus";
+      assert_eq!(
+        limit(&[cgroup, (quota, "50000\n"), (period, "100000\n")]),
+        Some(1)
+      );
+      assert_eq!(
+        limit(&[cgroup, (quota, "-1\n"), (period, "100000\n")]),
+        None
+      );
+      assert_eq!(
+        limit(&[
+          cgroup,
+          (quota, "800000\n"),
+          (period, "100000\n"),
+          (cpuset, "0-1,4-5\n")
+        ]),
+        Some(4)
+      );
+    }
+
+    #[test]
+    fn cpu_lists() {
+      assert_eq!(cpu_list("0-3,8,10-11\n".to_string()), Some(7));
+      assert_eq!(cpu_list("5".to_string()), Some(1));
+      assert_eq!(cpu_list("\n".to_string()), None);
+      assert_eq!(cpu_list("3-1".to_string()), None);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cpuinfo_physical_cores_and_frequencies() {
+    let cpuinfo = "\
+processor\t: 0
+physical id\t: 0
+core id\t\t: 0
+cpu MHz\t\t: 2400.120
+
+processor\t: 1
+physical id\t: 0
+core id\t\t: 0
+cpu MHz\t\t: 2399.870
+
+processor\t: 2
+physical id\t: 0
+core id\t\t: 1
+cpu MHz\t\t: 3100.000
+";
+    assert_eq!(parse_cpuinfo(cpuinfo), (Some(2), vec![2400, 2400, 3100]));
+    // no topology, as on some ARM systems
+    assert_eq!(parse_cpuinfo("processor\t: 0\n"), (None, vec![]));
+  }
+
+  #[test]
+  fn effective_parallelism_is_at_most_logical() {
+    let info = cpu_info();
+    assert!(info.effective_parallelism >= 1);
+    assert!(info.effective_parallelism <= info.logical);
+    assert!(info.physical <= info.logical);
+  }
+}
@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use crate::ops::env_policy::ScopedEnv;
 use crate::worker::ExitCode;
 use deno_core::op2;
 use deno_core::v8;
@@ -11,6 +12,7 @@ use serde::Serialize;
 use std::collections::HashMap;
 use std::env;
 
+pub mod cpu_info;
 mod sys_info;
 
 deno_core::extension!(
@@ -20,6 +22,7 @@ deno_core::extension!(
     op_exec_path,
     op_exit,
     op_delete_env,
+    op_fd_limits,
     op_get_env,
     op_gid,
     op_hostname,
@@ -30,6 +33,7 @@ deno_core::extension!(
     op_set_env,
     op_set_exit_code,
     op_get_exit_code,
+    op_system_cpu_info,
     op_system_memory_info,
     op_uid,
     op_runtime_memory_usage,
@@ -49,6 +53,7 @@ deno_core::extension!(
     op_exec_path,
     op_exit,
     op_delete_env,
+    op_fd_limits,
     op_get_env,
     op_gid,
     op_hostname,
@@ -59,6 +64,7 @@ deno_core::extension!(
     op_set_env,
     op_set_exit_code,
     op_get_exit_code,
+    op_system_cpu_info,
     op_system_memory_info,
     op_uid,
     op_runtime_memory_usage,
@@ -124,7 +130,10 @@ fn op_set_env(
   if value.contains('\0') {
     return Err(OsError::EnvInvalidValue(value.to_string()));
   }
-  env::set_var(key, value);
+  match state.try_borrow_mut::<ScopedEnv>() {
+    Some(scoped) => scoped.set(key, value),
+    None => env::set_var(key, value),
+  }
   Ok(())
 }
 
@@ -134,7 +143,17 @@ fn op_env(
   state: &mut OpState,
 ) -> Result<HashMap<String, String>, deno_core::error::AnyError> {
   state.borrow_mut::<PermissionsContainer>().check_env_all()?;
-  Ok(env::vars().collect())
+  match state.try_borrow::<ScopedEnv>() {
+    Some(scoped) => Ok(
+      scoped
+        .vars()
+        .filter_map(|(k, v)| {
+          Some((k.to_str()?.to_string(), v.to_str()?.to_string()))
+        })
+        .collect(),
+    ),
+    None => Ok(env::vars().collect()),
+  }
 }
 
 #[op2]
@@ -160,6 +179,19 @@ fn op_get_env(
     return Err(OsError::EnvInvalidKey(key.to_string()));
   }
 
+  if let Some(scoped) = state.try_borrow::<ScopedEnv>() {
+    let value = scoped
+      .get(&key)
+      .map(|value| {
+        value
+          .to_os_string()
+          .into_string()
+          .map_err(env::VarError::NotUnicode)
+      })
+      .transpose()?;
+    return Ok(value);
+  }
+
   let r = match env::var(key) {
     Err(env::VarError::NotPresent) => None,
     v => Some(v?),
@@ -179,7 +211,10 @@ fn op_delete_env(
   if key.is_empty() || key.contains(&['=', '\0'] as &[char]) {
     return Err(OsError::EnvInvalidKey(key.to_string()));
   }
-  env::remove_var(key);
+  match state.try_borrow_mut::<ScopedEnv>() {
+    Some(scoped) => scoped.remove(&key),
+    None => env::remove_var(key),
+  }
   Ok(())
 }
 
@@ -297,6 +332,47 @@ fn op_system_memory_info(
   Ok(sys_info::mem_info())
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FdLimits {
+  soft: Option<u64>,
+  hard: Option<u64>,
+  in_use: u64,
+}
+
+#[op2]
+#[serde]
+fn op_fd_limits(
+  state: &mut OpState,
+) -> Result<FdLimits, deno_core::error::AnyError> {
+  state
+    .borrow_mut::<PermissionsContainer>()
+    .check_sys("fdLimits", "Deno.fdLimits()")?;
+  let (soft, hard) = sys_info::fd_limits();
+  let in_use = sys_info::open_fd_count().unwrap_or_else(|| {
+    // Without a listing from the system, count the resources that are
+    // backed by a descriptor, which include stdio.
+    state
+      .resource_table
+      .names()
+      .filter_map(|(rid, _)| state.resource_table.get_any(rid).ok())
+      .filter(|resource| resource.clone().backing_handle().is_some())
+      .count() as u64
+  });
+  Ok(FdLimits { soft, hard, in_use })
+}
+
+#[op2]
+#[serde]
+fn op_system_cpu_info(
+  state: &mut OpState,
+) -> Result<cpu_info::CpuInfo, deno_core::error::AnyError> {
+  state
+    .borrow_mut::<PermissionsContainer>()
+    .check_sys("systemCpuInfo", "Deno.systemCpuInfo()")?;
+  Ok(cpu_info::cpu_info())
+}
+
 #[cfg(not(windows))]
 #[op2]
 #[smi]
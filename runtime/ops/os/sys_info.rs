@@ -423,3 +423,54 @@ pub fn os_uptime() -> u64 {
 
   uptime
 }
+
+/// The soft and hard limits on open file descriptors, `None` where there is
+/// no limit.
+pub fn fd_limits() -> (Option<u64>, Option<u64>) {
+  #[cfg(unix)]
+  {
+    let mut limits = libc::rlimit {
+      rlim_cur: 0,
+      rlim_max: 0,
+    };
+    // SAFETY: `limits` is a valid pointer to a `libc::rlimit` struct.
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limits) } != 0 {
+      return (None, None);
+    }
+    let limit = |value: libc::rlim_t| {
+      (value != libc::RLIM_INFINITY).then_some(value as u64)
+    };
+    (limit(limits.rlim_cur), limit(limits.rlim_max))
+  }
+  #[cfg(target_family = "windows")]
+  {
+    (None, None)
+  }
+}
+
+/// The number of file descriptors open in the process, where the system
+/// lists them.
+pub fn open_fd_count() -> Option<u64> {
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  let dir = "/proc/self/fd";
+  #[cfg(target_vendor = "apple")]
+  let dir = "/dev/fd";
+  #[cfg(any(
+    target_os = "android",
+    target_os = "linux",
+    target_vendor = "apple"
+  ))]
+  {
+    let entries = std::fs::read_dir(dir).ok()?;
+    // Reading the directory takes a descriptor of its own.
+    Some((entries.count() as u64).saturating_sub(1))
+  }
+  #[cfg(not(any(
+    target_os = "android",
+    target_os = "linux",
+    target_vendor = "apple"
+  )))]
+  {
+    None
+  }
+}
@@ -4,6 +4,9 @@ use deno_core::op2;
 use deno_core::serde_json;
 use deno_core::AsyncMutFuture;
 use deno_core::AsyncRefCell;
+use deno_core::CancelHandle;
+use deno_core::CancelTryFuture;
+use deno_core::JsBuffer;
 use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
@@ -16,6 +19,7 @@ use deno_io::ChildStdoutResource;
 use deno_io::IntoRawIoHandle;
 use deno_permissions::PermissionsContainer;
 use deno_permissions::RunQueryDescriptor;
+use deno_web::OpError;
 use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
@@ -27,11 +31,17 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
 use std::rc::Rc;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
+use crate::ops::env_policy::env_snapshot;
+use crate::ops::env_policy::normalize_env_key;
+use crate::ops::env_policy::EnvPolicy;
+use crate::ops::env_policy::EnvPolicyDiagnostics;
 use crate::ops::signal::SignalError;
 #[cfg(unix)]
 use std::os::unix::prelude::ExitStatusExt;
@@ -146,6 +156,10 @@ deno_core::extension!(
     op_spawn_wait,
     op_spawn_sync,
     op_spawn_kill,
+    op_spawn_self,
+    op_spawn_self_parent_ipc,
+    op_ipc_channel_read,
+    op_ipc_channel_write,
     deprecated::op_run,
     deprecated::op_run_status,
     deprecated::op_kill,
@@ -174,6 +188,7 @@ pub struct SpawnArgs {
   cwd: Option<String>,
   clear_env: bool,
   env: Vec<(String, String)>,
+  env_policy: Option<EnvPolicy>,
   #[cfg(unix)]
   gid: Option<u32>,
   #[cfg(unix)]
@@ -181,6 +196,9 @@ pub struct SpawnArgs {
   #[cfg(windows)]
   windows_raw_arguments: bool,
   ipc: Option<i32>,
+  /// Use an `IpcChannelResource` instead of node's JSON stream for `ipc`.
+  #[serde(skip)]
+  structured_ipc: bool,
 
   #[serde(flatten)]
   stdio: ChildStdio,
@@ -200,6 +218,8 @@ pub enum ProcessError {
   },
   #[error("{0}")]
   Io(#[from] std::io::Error),
+  #[error(transparent)]
+  Op(#[from] OpError),
   #[cfg(unix)]
   #[error(transparent)]
   Nix(nix::Error),
@@ -221,6 +241,25 @@ pub enum ProcessError {
   Signal(#[from] SignalError),
   #[error("Missing cmd")]
   MissingCmd, // only for Deno.run
+  #[error(
+    "IPC message exceeds the maximum size of {} bytes",
+    MAX_IPC_MESSAGE_SIZE
+  )]
+  IpcMessageTooLarge,
+}
+
+/// Wraps an error that prevented `command` from being spawned. IO errors keep
+/// their class and code and are exposed to JS as the cause.
+fn spawn_failed(command: String, error: ProcessError) -> ProcessError {
+  match error {
+    ProcessError::Io(e) => ProcessError::Op(
+      OpError::from(e).context(format!("Failed to spawn '{command}'")),
+    ),
+    error => ProcessError::SpawnFailed {
+      command,
+      error: Box::new(error),
+    },
+  }
 }
 
 #[derive(Deserialize)]
@@ -287,6 +326,7 @@ type CreateCommand = (
   Option<ResourceId>,
   Vec<Option<ResourceId>>,
   Vec<deno_io::RawBiPipeHandle>,
+  Option<EnvPolicyDiagnostics>,
 );
 
 pub fn npm_process_state_tempfile(
@@ -347,11 +387,19 @@ fn create_command(
     None
   };
 
+  if args.env_policy.is_some() {
+    super::check_unstable(
+      state,
+      UNSTABLE_FEATURE_NAME,
+      "Deno.Command.envPolicy",
+    );
+  }
   let (cmd, run_env) = compute_run_cmd_and_check_permissions(
     &args.cmd,
     args.cwd.as_deref(),
     &args.env,
     args.clear_env,
+    args.env_policy.as_ref(),
     state,
     api_name,
   )?;
@@ -381,6 +429,7 @@ fn create_command(
   command.current_dir(run_env.cwd);
   command.env_clear();
   command.envs(run_env.envs);
+  let env_diagnostics = run_env.diagnostics;
 
   #[cfg(unix)]
   if let Some(gid) = args.gid {
@@ -423,15 +472,22 @@ fn create_command(
         fds_to_dup.push((ipc_fd2, ipc));
         fds_to_close.push(ipc_fd2);
         /* One end returned to parent process (this) */
-        let pipe_rid =
+        let pipe_rid = if args.structured_ipc {
+          /* The other end passed to child process via DENO_SPAWN_SELF_IPC_FD */
+          command.env(SPAWN_SELF_IPC_FD_ENV_VAR_NAME, format!("{}", ipc));
+          state
+            .resource_table
+            .add(IpcChannelResource::from_raw_handle(ipc_fd1)?)
+        } else {
+          /* The other end passed to child process via NODE_CHANNEL_FD */
+          command.env("NODE_CHANNEL_FD", format!("{}", ipc));
           state
             .resource_table
             .add(deno_node::IpcJsonStreamResource::new(
               ipc_fd1 as _,
               deno_node::IpcRefTracker::new(state.external_ops_tracker.clone()),
-            )?);
-        /* The other end passed to child process via NODE_CHANNEL_FD */
-        command.env("NODE_CHANNEL_FD", format!("{}", ipc));
+            )?)
+        };
         ipc_rid = Some(pipe_rid);
       }
     }
@@ -478,7 +534,13 @@ fn create_command(
       Ok(())
     });
 
-    Ok((command, ipc_rid, extra_pipe_rids, fds_to_close))
+    Ok((
+      command,
+      ipc_rid,
+      extra_pipe_rids,
+      fds_to_close,
+      env_diagnostics,
+    ))
   }
 
   #[cfg(windows)]
@@ -493,15 +555,29 @@ fn create_command(
         let (hd1, hd2) = deno_io::bi_pipe_pair_raw()?;
 
         /* One end returned to parent process (this) */
-        let pipe_rid = Some(state.resource_table.add(
-          deno_node::IpcJsonStreamResource::new(
-            hd1 as i64,
-            deno_node::IpcRefTracker::new(state.external_ops_tracker.clone()),
-          )?,
-        ));
-
-        /* The other end passed to child process via NODE_CHANNEL_FD */
-        command.env("NODE_CHANNEL_FD", format!("{}", hd2 as i64));
+        let pipe_rid = if args.structured_ipc {
+          /* The other end passed to child process via DENO_SPAWN_SELF_IPC_FD */
+          command
+            .env(SPAWN_SELF_IPC_FD_ENV_VAR_NAME, format!("{}", hd2 as i64));
+          Some(
+            state
+              .resource_table
+              .add(IpcChannelResource::from_raw_handle(hd1)?),
+          )
+        } else {
+          /* The other end passed to child process via NODE_CHANNEL_FD */
+          command.env("NODE_CHANNEL_FD", format!("{}", hd2 as i64));
+          Some(
+            state
+              .resource_table
+              .add(deno_node::IpcJsonStreamResource::new(
+                hd1 as i64,
+                deno_node::IpcRefTracker::new(
+                  state.external_ops_tracker.clone(),
+                ),
+              )?),
+          )
+        };
 
         handles_to_close.push(hd2);
 
@@ -515,7 +591,7 @@ fn create_command(
       );
     }
 
-    Ok((command, ipc_rid, vec![], handles_to_close))
+    Ok((command, ipc_rid, vec![], handles_to_close, env_diagnostics))
   }
 }
 
@@ -529,6 +605,7 @@ struct Child {
   stderr_rid: Option<ResourceId>,
   ipc_pipe_rid: Option<ResourceId>,
   extra_pipe_rids: Vec<Option<ResourceId>>,
+  env_diagnostics: Option<EnvPolicyDiagnostics>,
 }
 
 fn spawn_child(
@@ -586,10 +663,10 @@ fn spawn_child(
         }
       }
 
-      return Err(ProcessError::SpawnFailed {
-        command: command.get_program().to_string_lossy().to_string(),
-        error: Box::new(err.into()),
-      });
+      return Err(spawn_failed(
+        command.get_program().to_string_lossy().to_string(),
+        err.into(),
+      ));
     }
   };
 
@@ -622,6 +699,7 @@ fn spawn_child(
     stderr_rid,
     ipc_pipe_rid,
     extra_pipe_rids,
+    env_diagnostics: None,
   })
 }
 
@@ -630,21 +708,15 @@ fn compute_run_cmd_and_check_permissions(
   arg_cwd: Option<&str>,
   arg_envs: &[(String, String)],
   arg_clear_env: bool,
+  arg_env_policy: Option<&EnvPolicy>,
   state: &mut OpState,
   api_name: &str,
 ) -> Result<(PathBuf, RunEnv), ProcessError> {
   let run_env =
-    compute_run_env(arg_cwd, arg_envs, arg_clear_env).map_err(|e| {
-      ProcessError::SpawnFailed {
-        command: arg_cmd.to_string(),
-        error: Box::new(e),
-      }
-    })?;
-  let cmd =
-    resolve_cmd(arg_cmd, &run_env).map_err(|e| ProcessError::SpawnFailed {
-      command: arg_cmd.to_string(),
-      error: Box::new(e),
-    })?;
+    compute_run_env(state, arg_cwd, arg_envs, arg_clear_env, arg_env_policy)
+      .map_err(|e| spawn_failed(arg_cmd.to_string(), e))?;
+  let cmd = resolve_cmd(arg_cmd, &run_env)
+    .map_err(|e| spawn_failed(arg_cmd.to_string(), e))?;
   check_run_permission(
     state,
     &RunQueryDescriptor::Path {
@@ -661,6 +733,8 @@ fn compute_run_cmd_and_check_permissions(
 struct RunEnv {
   envs: HashMap<OsString, OsString>,
   cwd: PathBuf,
+  /// Only set when the environment was built from an `EnvPolicy`.
+  diagnostics: Option<EnvPolicyDiagnostics>,
 }
 
 /// Computes the current environment, which will then be used to inform
@@ -668,10 +742,15 @@ struct RunEnv {
 /// ahead of time so that the environment used to verify permissions is
 /// the same environment used to spawn the sub command. This protects against
 /// someone doing timing attacks by changing the environment on a worker.
+///
+/// `arg_envs` is applied on top of the environment built from
+/// `arg_env_policy`.
 fn compute_run_env(
+  state: &OpState,
   arg_cwd: Option<&str>,
   arg_envs: &[(String, String)],
   arg_clear_env: bool,
+  arg_env_policy: Option<&EnvPolicy>,
 ) -> Result<RunEnv, ProcessError> {
   #[allow(clippy::disallowed_methods)]
   let cwd =
@@ -679,37 +758,34 @@ fn compute_run_env(
   let cwd = arg_cwd
     .map(|cwd_arg| resolve_path(cwd_arg, &cwd))
     .unwrap_or(cwd);
+  let mut diagnostics = None;
   let envs = if arg_clear_env {
     arg_envs
       .iter()
       .map(|(k, v)| (OsString::from(k), OsString::from(v)))
       .collect()
   } else {
-    let mut envs = std::env::vars_os()
-      .map(|(k, v)| {
-        (
-          if cfg!(windows) {
-            k.to_ascii_uppercase()
-          } else {
-            k
-          },
-          v,
-        )
-      })
-      .collect::<HashMap<_, _>>();
+    let mut envs = if let Some(policy) = arg_env_policy {
+      let resolved = policy
+        .resolve(env_snapshot(state), state.borrow::<PermissionsContainer>());
+      diagnostics = Some(resolved.diagnostics);
+      resolved.vars
+    } else {
+      env_snapshot(state)
+    };
     for (key, value) in arg_envs {
       envs.insert(
-        OsString::from(if cfg!(windows) {
-          key.to_ascii_uppercase()
-        } else {
-          key.clone()
-        }),
+        normalize_env_key(OsString::from(key)),
         OsString::from(value.clone()),
       );
     }
     envs
   };
-  Ok(RunEnv { envs, cwd })
+  Ok(RunEnv {
+    envs,
+    cwd,
+    diagnostics,
+  })
 }
 
 fn resolve_cmd(cmd: &str, env: &RunEnv) -> Result<PathBuf, ProcessError> {
@@ -798,13 +874,15 @@ fn op_spawn_child(
   #[string] api_name: String,
 ) -> Result<Child, ProcessError> {
   let detached = args.detached;
-  let (command, pipe_rid, extra_pipe_rids, handles_to_close) =
+  let (command, pipe_rid, extra_pipe_rids, handles_to_close, env_diagnostics) =
     create_command(state, args, &api_name)?;
   let child = spawn_child(state, command, pipe_rid, extra_pipe_rids, detached);
   for handle in handles_to_close {
     deno_io::close_raw_handle(handle);
   }
-  child
+  let mut child = child?;
+  child.env_diagnostics = env_diagnostics;
+  Ok(child)
 }
 
 #[op2(async)]
@@ -840,11 +918,13 @@ fn op_spawn_sync(
 ) -> Result<SpawnOutput, ProcessError> {
   let stdout = matches!(args.stdio.stdout, StdioOrRid::Stdio(Stdio::Piped));
   let stderr = matches!(args.stdio.stderr, StdioOrRid::Stdio(Stdio::Piped));
-  let (mut command, _, _, _) =
+  let (mut command, _, _, _, _) =
     create_command(state, args, "Deno.Command().outputSync()")?;
-  let output = command.output().map_err(|e| ProcessError::SpawnFailed {
-    command: command.get_program().to_string_lossy().to_string(),
-    error: Box::new(e.into()),
+  let output = command.output().map_err(|e| {
+    spawn_failed(
+      command.get_program().to_string_lossy().to_string(),
+      e.into(),
+    )
   })?;
 
   Ok(SpawnOutput {
@@ -875,6 +955,215 @@ fn op_spawn_kill(
   Err(ProcessError::ChildProcessAlreadyTerminated)
 }
 
+/// Environment variable used to hand the IPC channel created by
+/// `Deno.spawnSelf()` to the child process.
+pub const SPAWN_SELF_IPC_FD_ENV_VAR_NAME: &str = "DENO_SPAWN_SELF_IPC_FD";
+/// Overrides the executable spawned by `Deno.spawnSelf()`.
+pub const EXEC_PATH_ENV_VAR_NAME: &str = "DENO_EXEC_PATH";
+/// The fd the IPC channel is mapped to in the child on unix.
+const SPAWN_SELF_IPC_FD: i32 = 3;
+const MAX_IPC_MESSAGE_SIZE: usize = 256 * 1024 * 1024;
+
+/// The IPC channel to the parent process, set when this process was spawned
+/// with `Deno.spawnSelf({ ipc: true })`.
+pub struct SpawnSelfIpcFd(pub i64);
+
+/// Reads the IPC channel handed down by `Deno.spawnSelf()`, if any.
+pub fn spawn_self_ipc_fd_from_env() -> Option<i64> {
+  let fd = std::env::var(SPAWN_SELF_IPC_FD_ENV_VAR_NAME).ok()?;
+  // Remove so that child processes don't inherit this environment variable.
+  std::env::remove_var(SPAWN_SELF_IPC_FD_ENV_VAR_NAME);
+  fd.parse::<i64>().ok()
+}
+
+/// One end of the message channel between a process and a child spawned
+/// with `Deno.spawnSelf({ ipc: true })`. Messages are serialized by the
+/// caller and framed with a little-endian `u32` length prefix.
+pub struct IpcChannelResource {
+  read_half: AsyncRefCell<deno_io::BiPipeRead>,
+  write_half: AsyncRefCell<deno_io::BiPipeWrite>,
+  cancel: CancelHandle,
+}
+
+impl Resource for IpcChannelResource {
+  fn name(&self) -> Cow<str> {
+    "ipcChannel".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl IpcChannelResource {
+  pub fn from_raw_handle(
+    raw: deno_io::RawBiPipeHandle,
+  ) -> Result<Self, std::io::Error> {
+    let (read_half, write_half) = deno_io::BiPipe::from_raw(raw)?.split();
+    Ok(Self {
+      read_half: AsyncRefCell::new(read_half),
+      write_half: AsyncRefCell::new(write_half),
+      cancel: Default::default(),
+    })
+  }
+
+  /// Returns `None` once the other end was closed.
+  async fn read_message(
+    self: Rc<Self>,
+  ) -> Result<Option<Vec<u8>>, std::io::Error> {
+    let mut rd = RcRef::map(&self, |r| &r.read_half).borrow_mut().await;
+    let cancel_handle = RcRef::map(&self, |r| &r.cancel);
+    async move {
+      let mut len = [0; 4];
+      match rd.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+          return Ok(None)
+        }
+        Err(err) => return Err(err),
+      }
+      let len = u32::from_le_bytes(len) as usize;
+      if len > MAX_IPC_MESSAGE_SIZE {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidData,
+          ProcessError::IpcMessageTooLarge.to_string(),
+        ));
+      }
+      let mut data = vec![0; len];
+      rd.read_exact(&mut data).await?;
+      Ok(Some(data))
+    }
+    .try_or_cancel(cancel_handle)
+    .await
+  }
+
+  async fn write_message(
+    self: Rc<Self>,
+    data: &[u8],
+  ) -> Result<(), std::io::Error> {
+    let mut wr = RcRef::map(self, |r| &r.write_half).borrow_mut().await;
+    wr.write_all(&(data.len() as u32).to_le_bytes()).await?;
+    wr.write_all(data).await?;
+    wr.flush().await
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpawnSelfArgs {
+  args: Vec<String>,
+  cwd: Option<String>,
+  clear_env: bool,
+  env: Vec<(String, String)>,
+  ipc: bool,
+
+  #[serde(flatten)]
+  stdio: ChildStdio,
+}
+
+/// Resolves the executable spawned by `Deno.spawnSelf()`: `DENO_EXEC_PATH`
+/// if set, otherwise the running executable. For `deno compile`d programs
+/// that is the compiled program itself.
+fn resolve_self_exec_path() -> Result<PathBuf, ProcessError> {
+  if let Some(path) =
+    std::env::var_os(EXEC_PATH_ENV_VAR_NAME).filter(|path| !path.is_empty())
+  {
+    return Ok(PathBuf::from(path));
+  }
+  let current_exe = std::env::current_exe()?;
+  // If the executable was replaced or removed since it was started (e.g. by
+  // `deno upgrade`) the path ends in " (deleted)", but /proc/self/exe still
+  // refers to the running binary.
+  #[cfg(target_os = "linux")]
+  #[allow(clippy::disallowed_methods)]
+  if !current_exe.exists() {
+    return Ok(PathBuf::from("/proc/self/exe"));
+  }
+  Ok(current_exe)
+}
+
+#[op2]
+#[serde]
+fn op_spawn_self(
+  state: &mut OpState,
+  #[serde] args: SpawnSelfArgs,
+) -> Result<Child, ProcessError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.spawnSelf");
+  let exec_path = resolve_self_exec_path()?;
+  let args = SpawnArgs {
+    cmd: exec_path.to_string_lossy().into_owned(),
+    args: args.args,
+    cwd: args.cwd,
+    clear_env: args.clear_env,
+    env: args.env,
+    env_policy: None,
+    #[cfg(unix)]
+    gid: None,
+    #[cfg(unix)]
+    uid: None,
+    #[cfg(windows)]
+    windows_raw_arguments: false,
+    ipc: args.ipc.then_some(SPAWN_SELF_IPC_FD),
+    structured_ipc: true,
+    stdio: args.stdio,
+    extra_stdio: vec![],
+    detached: false,
+    needs_npm_process_state: false,
+  };
+  let (command, pipe_rid, extra_pipe_rids, handles_to_close, _) =
+    create_command(state, args, "Deno.spawnSelf()")?;
+  let child = spawn_child(state, command, pipe_rid, extra_pipe_rids, false);
+  for handle in handles_to_close {
+    deno_io::close_raw_handle(handle);
+  }
+  child
+}
+
+// Open the IPC channel to the parent process from bootstrap options.
+#[op2]
+#[smi]
+fn op_spawn_self_parent_ipc(
+  state: &mut OpState,
+) -> Result<Option<ResourceId>, ProcessError> {
+  let Some(SpawnSelfIpcFd(fd)) = state.try_take::<SpawnSelfIpcFd>() else {
+    return Ok(None);
+  };
+  let resource = IpcChannelResource::from_raw_handle(fd as _)?;
+  Ok(Some(state.resource_table.add(resource)))
+}
+
+#[op2(async)]
+#[serde]
+async fn op_ipc_channel_read(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ToJsBuffer>, ProcessError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<IpcChannelResource>(rid)
+    .map_err(ProcessError::Resource)?;
+  Ok(resource.read_message().await?.map(Into::into))
+}
+
+#[op2(async)]
+async fn op_ipc_channel_write(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[buffer] data: JsBuffer,
+) -> Result<(), ProcessError> {
+  if data.len() > MAX_IPC_MESSAGE_SIZE {
+    return Err(ProcessError::IpcMessageTooLarge);
+  }
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<IpcChannelResource>(rid)
+    .map_err(ProcessError::Resource)?;
+  resource.write_message(&data).await?;
+  Ok(())
+}
+
 mod deprecated {
   use super::*;
 
@@ -929,6 +1218,7 @@ mod deprecated {
       run_args.cwd.as_deref(),
       &run_args.env,
       /* clear env */ false,
+      /* env policy */ None,
       state,
       "Deno.run()",
     )?;
@@ -0,0 +1,493 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Realms: separate V8 contexts in the same isolate, for evaluating code
+//! that shouldn't see the globals of the main program, such as config files
+//! written in JS or plugin hook scripts.
+//!
+//! A realm starts out with only the JavaScript builtins. In particular it
+//! has no `Deno` namespace and no ops; the ops passed to `op_realm_create`
+//! are the only ones it can call, as `globalThis.ops.<name>`. Values never
+//! cross the boundary as objects: arguments, results and completion values
+//! are structured-cloned, and errors are re-created on the other side with
+//! their name, message and stack. Handing out an object of one context to
+//! the other would give access to its `Function` constructor, and so to its
+//! global scope.
+//!
+//! `op_realm_eval` runs in the isolate's thread and blocks the event loop
+//! until the code returns or its timeout terminates it. Promises created in
+//! a realm are settled by the shared microtask queue.
+//!
+//! There is no public JS API; the ops are meant for internal consumers.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use deno_core::op2;
+use deno_core::v8;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use serde::Deserialize;
+
+deno_core::extension!(
+  deno_realm,
+  ops = [op_realm_create, op_realm_eval, op_realm_close],
+);
+
+#[derive(Debug, thiserror::Error)]
+pub enum RealmError {
+  #[error(transparent)]
+  Resource(deno_core::error::AnyError),
+  #[error("Exposed op \"{0}\" is not a function")]
+  NotAFunction(String),
+  #[error("Realm evaluation timed out after {0}ms")]
+  TimedOut(u64),
+}
+
+struct RealmResource {
+  name: String,
+  context: v8::Global<v8::Context>,
+}
+
+impl Resource for RealmResource {
+  fn name(&self) -> std::borrow::Cow<str> {
+    "realm".into()
+  }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RealmEvalOptions {
+  timeout_ms: Option<u64>,
+  filename: Option<String>,
+}
+
+/// Creates a realm. `exposed_ops` maps names to op functions that code in
+/// the realm may call; anything else is unreachable from it.
+#[op2]
+#[smi]
+pub fn op_realm_create(
+  scope: &mut v8::HandleScope,
+  state: &mut OpState,
+  #[string] name: String,
+  exposed_ops: v8::Local<v8::Value>,
+) -> Result<ResourceId, RealmError> {
+  let mut ops = Vec::new();
+  if let Ok(exposed_ops) = v8::Local::<v8::Object>::try_from(exposed_ops) {
+    let names = exposed_ops
+      .get_own_property_names(scope, Default::default())
+      .unwrap_or_else(|| v8::Array::new(scope, 0));
+    for i in 0..names.length() {
+      let key = names.get_index(scope, i).unwrap();
+      let op = exposed_ops.get(scope, key).unwrap();
+      let Ok(op) = v8::Local::<v8::Function>::try_from(op) else {
+        return Err(RealmError::NotAFunction(key.to_rust_string_lossy(scope)));
+      };
+      ops.push((key, op));
+    }
+  }
+
+  let context = v8::Context::new(scope, Default::default());
+  {
+    let scope = &mut v8::ContextScope::new(scope, context);
+    let ops_object = v8::Object::new(scope);
+    for (key, op) in ops {
+      // The wrapper belongs to the realm, so its prototype chain doesn't
+      // lead back to the main context.
+      let wrapper = v8::Function::builder(call_exposed_op)
+        .data(op.into())
+        .build(scope)
+        .unwrap();
+      ops_object.set(scope, key, wrapper.into());
+    }
+    ops_object.set_integrity_level(scope, v8::IntegrityLevel::Frozen);
+    let key = v8::String::new_external_onebyte_static(scope, b"ops").unwrap();
+    context
+      .global(scope)
+      .set(scope, key.into(), ops_object.into());
+  }
+
+  Ok(state.resource_table.add(RealmResource {
+    name,
+    context: v8::Global::new(scope, context),
+  }))
+}
+
+enum Outcome<'s> {
+  Value(v8::Local<'s, v8::Value>),
+  Thrown(ErrorInfo),
+  Terminated,
+}
+
+/// Evaluates `source` as a classic script in the realm and returns a clone
+/// of its completion value. Exceptions are re-thrown in the caller's
+/// context with the realm-side stack.
+#[op2]
+pub fn op_realm_eval<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[string] source: String,
+  #[serde] options: Option<RealmEvalOptions>,
+) -> Result<v8::Local<'s, v8::Value>, RealmError> {
+  // The state isn't borrowed while the code runs: it may call exposed ops,
+  // including this one.
+  let realm = state
+    .borrow()
+    .resource_table
+    .get::<RealmResource>(rid)
+    .map_err(RealmError::Resource)?;
+  let options = options.unwrap_or_default();
+  let filename = options
+    .filename
+    .unwrap_or_else(|| format!("realm:{}", realm.name));
+  let host = scope.get_current_context();
+  let context = v8::Local::new(scope, &realm.context);
+
+  let watchdog = options.timeout_ms.map(|timeout_ms| {
+    Watchdog::start(
+      scope.thread_safe_handle(),
+      Duration::from_millis(timeout_ms),
+    )
+  });
+  let outcome = {
+    let scope = &mut v8::TryCatch::new(scope);
+    let completion = {
+      let scope = &mut v8::ContextScope::new(scope, context);
+      compile_and_run(scope, &source, &filename)
+    };
+    // Cloning reads properties, which may run getters, so it is still
+    // covered by the timeout.
+    match completion {
+      Some(value) => match clone_into(scope, context, host, value) {
+        Ok(value) => Outcome::Value(value),
+        Err(message) => Outcome::Thrown(ErrorInfo::data_clone(message)),
+      },
+      None if scope.has_terminated() => Outcome::Terminated,
+      None => {
+        let exception = scope.exception().unwrap();
+        let scope = &mut v8::ContextScope::new(scope, context);
+        Outcome::Thrown(ErrorInfo::from_exception(scope, exception))
+      }
+    }
+  };
+  let timed_out = watchdog.is_some_and(Watchdog::stop);
+  if timed_out {
+    // Also clears a termination that was requested just after the code
+    // returned.
+    scope.cancel_terminate_execution();
+  }
+
+  match outcome {
+    Outcome::Value(value) => Ok(value),
+    Outcome::Terminated if timed_out => {
+      Err(RealmError::TimedOut(options.timeout_ms.unwrap()))
+    }
+    // Terminated by the host, e.g. `worker.terminate()`; keep unwinding.
+    Outcome::Terminated => Ok(v8::undefined(scope).into()),
+    Outcome::Thrown(info) => {
+      let error = info.to_error(scope);
+      scope.throw_exception(error);
+      Ok(v8::undefined(scope).into())
+    }
+  }
+}
+
+#[op2(fast)]
+pub fn op_realm_close(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<(), RealmError> {
+  state
+    .resource_table
+    .take::<RealmResource>(rid)
+    .map_err(RealmError::Resource)?;
+  Ok(())
+}
+
+fn compile_and_run<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  source: &str,
+  filename: &str,
+) -> Option<v8::Local<'s, v8::Value>> {
+  let source = v8::String::new(scope, source)?;
+  let filename = v8::String::new(scope, filename)?;
+  let origin = v8::ScriptOrigin::new(
+    scope,
+    filename.into(),
+    0,
+    0,
+    false,
+    -1,
+    None,
+    false,
+    false,
+    false,
+    None,
+  );
+  let script = v8::Script::compile(scope, source, Some(&origin))?;
+  script.run(scope)
+}
+
+/// Calls an exposed op from a realm. The op runs in the context it was
+/// created in; its arguments and result are cloned across.
+fn call_exposed_op(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let op = v8::Local::<v8::Function>::try_from(args.data()).unwrap();
+  let realm = scope.get_current_context();
+  let host = op.get_creation_context(scope).unwrap();
+
+  let mut op_args = Vec::with_capacity(args.length() as usize);
+  for i in 0..args.length() {
+    match clone_into(scope, realm, host, args.get(i)) {
+      Ok(arg) => op_args.push(arg),
+      Err(message) => {
+        let error = ErrorInfo::data_clone(message).to_error(scope);
+        scope.throw_exception(error);
+        return;
+      }
+    }
+  }
+
+  let result = {
+    let scope = &mut v8::TryCatch::new(scope);
+    let result = {
+      let scope = &mut v8::ContextScope::new(scope, host);
+      let recv = v8::undefined(scope).into();
+      op.call(scope, recv, &op_args)
+    };
+    match result {
+      Some(result) => Ok(result),
+      None if scope.has_terminated() => return,
+      None => {
+        let exception = scope.exception().unwrap();
+        Err(ErrorInfo::from_exception(scope, exception))
+      }
+    }
+  };
+
+  let value = match result {
+    Ok(result) => match v8::Local::<v8::Promise>::try_from(result) {
+      Ok(promise) => Ok(bridge_promise(scope, host, promise)),
+      Err(_) => {
+        clone_into(scope, host, realm, result).map_err(ErrorInfo::data_clone)
+      }
+    },
+    Err(info) => Err(info),
+  };
+  match value {
+    Ok(value) => rv.set(value),
+    Err(info) => {
+      let error = info.to_error(scope);
+      scope.throw_exception(error);
+    }
+  }
+}
+
+/// Returns a promise of the current (realm) context that settles with a
+/// clone of the outcome of `promise`, which belongs to `host`.
+fn bridge_promise<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  host: v8::Local<'s, v8::Context>,
+  promise: v8::Local<'s, v8::Promise>,
+) -> v8::Local<'s, v8::Value> {
+  let resolver = v8::PromiseResolver::new(scope).unwrap();
+  {
+    let scope = &mut v8::ContextScope::new(scope, host);
+    let on_fulfilled = v8::Function::builder(settle_bridged::<true>)
+      .data(resolver.into())
+      .build(scope)
+      .unwrap();
+    let on_rejected = v8::Function::builder(settle_bridged::<false>)
+      .data(resolver.into())
+      .build(scope)
+      .unwrap();
+    promise.then2(scope, on_fulfilled, on_rejected);
+  }
+  resolver.get_promise(scope).into()
+}
+
+fn settle_bridged<const FULFILLED: bool>(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  _rv: v8::ReturnValue,
+) {
+  let resolver =
+    v8::Local::<v8::PromiseResolver>::try_from(args.data()).unwrap();
+  let host = scope.get_current_context();
+  let realm = resolver.get_creation_context(scope).unwrap();
+  let outcome = if FULFILLED {
+    clone_into(scope, host, realm, args.get(0)).map_err(ErrorInfo::data_clone)
+  } else {
+    Err(ErrorInfo::from_exception(scope, args.get(0)))
+  };
+  let scope = &mut v8::ContextScope::new(scope, realm);
+  match outcome {
+    Ok(value) => resolver.resolve(scope, value),
+    Err(info) => {
+      let error = info.to_error(scope);
+      resolver.reject(scope, error)
+    }
+  };
+}
+
+/// An error, detached from the context it was thrown in.
+struct ErrorInfo {
+  name: String,
+  message: String,
+  stack: Option<String>,
+}
+
+impl ErrorInfo {
+  fn data_clone(message: String) -> Self {
+    Self {
+      name: "DataCloneError".to_string(),
+      message,
+      stack: None,
+    }
+  }
+
+  fn from_exception(
+    scope: &mut v8::HandleScope,
+    exception: v8::Local<v8::Value>,
+  ) -> Self {
+    let scope = &mut v8::TryCatch::new(scope);
+    let Ok(object) = v8::Local::<v8::Object>::try_from(exception) else {
+      return Self {
+        name: "Error".to_string(),
+        message: exception.to_rust_string_lossy(scope),
+        stack: None,
+      };
+    };
+    let mut get = |name: &str| {
+      let key = v8::String::new(scope, name)?;
+      let value = object.get(scope, key.into())?;
+      (!value.is_null_or_undefined()).then(|| value.to_rust_string_lossy(scope))
+    };
+    Self {
+      name: get("name").unwrap_or_else(|| "Error".to_string()),
+      message: get("message").unwrap_or_default(),
+      stack: get("stack"),
+    }
+  }
+
+  /// Creates the error in the current context.
+  fn to_error<'s>(
+    &self,
+    scope: &mut v8::HandleScope<'s>,
+  ) -> v8::Local<'s, v8::Value> {
+    let message = v8::String::new(scope, &self.message).unwrap();
+    let error = v8::Exception::error(scope, message);
+    let object = v8::Local::<v8::Object>::try_from(error).unwrap();
+    let mut set = |name: &str, value: &str| {
+      let key = v8::String::new(scope, name).unwrap();
+      let value = v8::String::new(scope, value).unwrap();
+      object.set(scope, key.into(), value.into());
+    };
+    set("name", &self.name);
+    if let Some(stack) = &self.stack {
+      set("stack", stack);
+    }
+    error
+  }
+}
+
+/// Structured-clones `value` from one context into another.
+fn clone_into<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  from: v8::Local<'s, v8::Context>,
+  to: v8::Local<'s, v8::Context>,
+  value: v8::Local<'s, v8::Value>,
+) -> Result<v8::Local<'s, v8::Value>, String> {
+  let error = Rc::new(RefCell::new(None));
+  let scope = &mut v8::TryCatch::new(scope);
+  let data = {
+    let scope = &mut v8::ContextScope::new(scope, from);
+    let serializer =
+      v8::ValueSerializer::new(scope, Box::new(CloneDelegate(error.clone())));
+    serializer.write_header();
+    serializer
+      .write_value(from, value)
+      .map(|_| serializer.release())
+  };
+  let cloned = data.and_then(|data| {
+    let scope = &mut v8::ContextScope::new(scope, to);
+    let deserializer = v8::ValueDeserializer::new(
+      scope,
+      Box::new(CloneDelegate(error.clone())),
+      &data,
+    );
+    deserializer.read_header(to)?;
+    deserializer.read_value(to)
+  });
+  cloned.ok_or_else(|| {
+    error
+      .borrow_mut()
+      .take()
+      .unwrap_or_else(|| "Value could not be cloned".to_string())
+  })
+}
+
+struct CloneDelegate(Rc<RefCell<Option<String>>>);
+
+impl v8::ValueSerializerImpl for CloneDelegate {
+  fn throw_data_clone_error<'s>(
+    &self,
+    scope: &mut v8::HandleScope<'s>,
+    message: v8::Local<'s, v8::String>,
+  ) {
+    self.0.replace(Some(message.to_rust_string_lossy(scope)));
+    let error = v8::Exception::type_error(scope, message);
+    scope.throw_exception(error);
+  }
+}
+
+impl v8::ValueDeserializerImpl for CloneDelegate {}
+
+/// Terminates execution in the isolate if it isn't stopped in time.
+struct Watchdog {
+  state: Arc<Mutex<WatchdogState>>,
+  stop_tx: mpsc::Sender<()>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+  stopped: bool,
+  fired: bool,
+}
+
+impl Watchdog {
+  fn start(isolate: v8::IsolateHandle, timeout: Duration) -> Self {
+    let state = Arc::new(Mutex::new(WatchdogState::default()));
+    let (stop_tx, stop_rx) = mpsc::channel();
+    deno_core::unsync::spawn_blocking({
+      let state = state.clone();
+      move || {
+        if stop_rx.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout)
+        {
+          let mut state = state.lock().unwrap();
+          if !state.stopped {
+            state.fired = true;
+            isolate.terminate_execution();
+          }
+        }
+      }
+    });
+    Self { state, stop_tx }
+  }
+
+  /// Stops the watchdog and returns whether it terminated execution.
+  fn stop(self) -> bool {
+    let mut state = self.state.lock().unwrap();
+    state.stopped = true;
+    let _ = self.stop_tx.send(());
+    state.fired
+  }
+}
@@ -1,12 +1,34 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::rc::Rc;
+
 use deno_core::op2;
 use deno_core::ModuleSpecifier;
 use deno_core::OpState;
+use serde::Serialize;
+
+use crate::cpu_time::CpuTimeSampler;
+use crate::event_loop_monitor::EventLoopMonitor;
+use crate::fatal_error::FatalErrorCode;
+use crate::fatal_error::UnhandledRejectionPolicy;
+use crate::module_graph::ModuleGraph;
+use crate::module_graph::ModuleGraphSnapshot;
+use crate::source_map::MappedSourceLocation;
+use crate::source_map::SourceLocation;
+use crate::source_map::SourceMapCache;
 
 deno_core::extension!(
   deno_runtime,
-  ops = [op_main_module, op_ppid],
+  ops = [
+    op_main_module,
+    op_ppid,
+    op_cpu_time_used,
+    op_event_loop_lag,
+    op_unhandled_rejection_policy,
+    op_set_fatal_error_code,
+    op_module_graph_snapshot,
+    op_apply_source_maps,
+  ],
   options = { main_module: ModuleSpecifier },
   state = |state, options| {
     state.put::<ModuleSpecifier>(options.main_module);
@@ -20,6 +42,60 @@ fn op_main_module(state: &mut OpState) -> String {
   main_url.to_string()
 }
 
+#[op2(fast)]
+#[smi]
+fn op_unhandled_rejection_policy(state: &OpState) -> u8 {
+  state
+    .try_borrow::<UnhandledRejectionPolicy>()
+    .copied()
+    .unwrap_or_default() as u8
+}
+
+/// Called with the `code` property of every exception formatted for a
+/// `JsError`, or an empty string if there is none, so that the fatal error
+/// handler of the worker can report it.
+#[op2(fast)]
+fn op_set_fatal_error_code(state: &mut OpState, #[string] code: &str) {
+  let code = (!code.is_empty()).then(|| code.to_string());
+  state.put(FatalErrorCode(code));
+}
+
+/// Returns the modules loaded by the current worker, for tooling. Workers
+/// that don't record their module graph return no modules.
+#[op2]
+#[serde]
+fn op_module_graph_snapshot(state: &OpState) -> ModuleGraphSnapshot {
+  state
+    .try_borrow::<ModuleGraph>()
+    .map(|graph| graph.snapshot())
+    .unwrap_or_default()
+}
+
+/// Maps stack frame locations collected by JS, e.g. by error reporting
+/// libraries, to the original sources.
+#[op2]
+#[serde]
+fn op_apply_source_maps(
+  state: &OpState,
+  #[serde] locations: Vec<SourceLocation>,
+) -> Vec<MappedSourceLocation> {
+  match state.try_borrow::<Rc<SourceMapCache>>() {
+    Some(source_maps) => locations
+      .into_iter()
+      .map(|location| source_maps.apply(location))
+      .collect(),
+    None => locations
+      .into_iter()
+      .map(|location| MappedSourceLocation {
+        file_name: location.file_name,
+        line_number: location.line_number,
+        column_number: location.column_number,
+        source_line: None,
+      })
+      .collect(),
+  }
+}
+
 /// This is an op instead of being done at initialization time because
 /// it's expensive to retrieve the ppid on Windows.
 #[op2(fast)]
@@ -85,3 +161,52 @@ pub fn op_ppid() -> i64 {
     parent_id().into()
   }
 }
+
+#[derive(Serialize)]
+pub struct CpuTimeUsed {
+  user: u64,
+  system: u64,
+  total: u64,
+}
+
+/// Returns the CPU time, in microseconds, used by the thread of the current
+/// worker since it was created.
+#[op2]
+#[serde]
+pub fn op_cpu_time_used(state: &mut OpState) -> CpuTimeUsed {
+  let used = state
+    .try_borrow::<Rc<CpuTimeSampler>>()
+    .map(|sampler| sampler.sample())
+    .unwrap_or_default();
+  CpuTimeUsed {
+    user: used.user.as_micros() as u64,
+    system: used.system.as_micros() as u64,
+    total: used.total().as_micros() as u64,
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventLoopLagStats {
+  last_ms: f64,
+  max_ms: f64,
+  ticks: u64,
+  overruns: u64,
+}
+
+/// Returns the lag of the current worker's event loop: how long its ticks
+/// waited to run after the event loop was woken up.
+#[op2]
+#[serde]
+pub fn op_event_loop_lag(state: &mut OpState) -> EventLoopLagStats {
+  let lag = state
+    .try_borrow::<Rc<EventLoopMonitor>>()
+    .map(|monitor| monitor.lag())
+    .unwrap_or_default();
+  EventLoopLagStats {
+    last_ms: lag.last.as_secs_f64() * 1000.0,
+    max_ms: lag.max.as_secs_f64() * 1000.0,
+    ticks: lag.ticks,
+    overruns: lag.overruns,
+  }
+}
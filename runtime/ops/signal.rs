@@ -15,8 +15,12 @@ use std::collections::BTreeMap;
 use std::rc::Rc;
 #[cfg(unix)]
 use std::sync::atomic::AtomicBool;
-#[cfg(unix)]
 use std::sync::Arc;
+use std::time::Duration;
+
+use deno_io::InterruptHandle;
+use tokio::sync::mpsc;
+use tokio::sync::Notify;
 
 #[cfg(unix)]
 use tokio::signal::unix::signal;
@@ -35,7 +39,13 @@ use tokio::signal::windows::CtrlC;
 
 deno_core::extension!(
   deno_signal,
-  ops = [op_signal_bind, op_signal_unbind, op_signal_poll],
+  ops = [
+    op_signal_bind,
+    op_signal_unbind,
+    op_signal_poll,
+    op_sigint_poll,
+    op_sigint_handled,
+  ],
   state = |state| {
     #[cfg(unix)]
     {
@@ -84,6 +94,18 @@ pub enum SignalError {
 #[derive(Default)]
 struct SignalState {
   enable_default_handlers: BTreeMap<libc::c_int, Arc<AtomicBool>>,
+  /// Interrupts long-running ops on SIGINT once a listener is bound, because
+  /// the listener itself can't run before the op returns.
+  interrupt_on_sigint: Option<signal_hook::SigId>,
+}
+
+#[cfg(unix)]
+impl Drop for SignalState {
+  fn drop(&mut self) {
+    if let Some(id) = self.interrupt_on_sigint.take() {
+      signal_hook::low_level::unregister(id);
+    }
+  }
 }
 
 #[cfg(unix)]
@@ -181,6 +203,104 @@ impl Resource for SignalStreamResource {
   }
 }
 
+/// Environment variable that sets the grace period, in milliseconds, of the
+/// SIGINT handling managed by the runtime.
+pub const SIGINT_GRACE_PERIOD_ENV_VAR_NAME: &str = "DENO_SIGINT_GRACE_PERIOD";
+
+pub const DEFAULT_SIGINT_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// The exit code of a process that was stopped by SIGINT.
+const SIGINT_EXIT_CODE: i32 = 130;
+
+/// Reads the SIGINT grace period from the `DENO_SIGINT_GRACE_PERIOD`
+/// environment variable, falling back to [`DEFAULT_SIGINT_GRACE_PERIOD`] when
+/// it is unset or can't be parsed.
+pub fn sigint_grace_period_from_env() -> Duration {
+  std::env::var(SIGINT_GRACE_PERIOD_ENV_VAR_NAME)
+    .ok()
+    .and_then(|value| value.trim().parse().ok())
+    .map(Duration::from_millis)
+    .unwrap_or(DEFAULT_SIGINT_GRACE_PERIOD)
+}
+
+#[cfg(unix)]
+type SigintStream = Signal;
+#[cfg(windows)]
+type SigintStream = CtrlC;
+
+/// SIGINT handling managed by the runtime, Ctrl+C on Windows.
+///
+/// A monitor thread waits for the signal. The first one is forwarded to
+/// JavaScript, which dispatches a cancelable "interrupt" event, and starts a
+/// grace period. The process exits with code 130 when the grace period ends
+/// or on a second signal, unless JavaScript canceled the event, in which case
+/// the next signal is handled like a first one again. The monitor has its own
+/// thread so that it can exit the process even if JavaScript is stuck.
+pub struct SigintDisposition {
+  received: AsyncRefCell<mpsc::UnboundedReceiver<()>>,
+  handled: Arc<Notify>,
+}
+
+impl SigintDisposition {
+  /// Starts the monitor thread. `interrupt` is interrupted on the first
+  /// signal, so that long-running ops don't hold up the "interrupt" event.
+  pub fn install(
+    grace_period: Duration,
+    interrupt: Option<InterruptHandle>,
+  ) -> Result<Self, std::io::Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()?;
+    // Subscribe before returning, so that a signal received while the
+    // monitor thread starts isn't lost.
+    let mut signal = {
+      let _guard = runtime.enter();
+      sigint_stream()?
+    };
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let handled = Arc::new(Notify::new());
+    let monitor_handled = handled.clone();
+    std::thread::Builder::new()
+      .name("sigint-monitor".to_string())
+      .spawn(move || {
+        runtime.block_on(async move {
+          loop {
+            if signal.recv().await.is_none() {
+              return;
+            }
+            if let Some(interrupt) = &interrupt {
+              interrupt.interrupt();
+            }
+            if sender.send(()).is_err() {
+              // The runtime is gone, nothing is left to clean up.
+              break;
+            }
+            tokio::select! {
+              _ = signal.recv() => break,
+              _ = tokio::time::sleep(grace_period) => break,
+              _ = monitor_handled.notified() => {}
+            }
+          }
+          std::process::exit(SIGINT_EXIT_CODE);
+        });
+      })?;
+    Ok(Self {
+      received: AsyncRefCell::new(receiver),
+      handled,
+    })
+  }
+}
+
+#[cfg(unix)]
+fn sigint_stream() -> Result<SigintStream, std::io::Error> {
+  signal(SignalKind::interrupt())
+}
+
+#[cfg(windows)]
+fn sigint_stream() -> Result<SigintStream, std::io::Error> {
+  ctrl_c()
+}
+
 macro_rules! first_literal {
   ($head:literal $(, $tail:literal)*) => {
     $head
@@ -407,6 +527,21 @@ fn op_signal_bind(
 
   let signal = AsyncRefCell::new(signal(SignalKind::from_raw(signo))?);
 
+  if signo == libc::SIGINT {
+    let interrupt = state.try_borrow::<InterruptHandle>().cloned();
+    let signal_state = state.borrow_mut::<SignalState>();
+    if let (None, Some(interrupt)) =
+      (&signal_state.interrupt_on_sigint, interrupt)
+    {
+      // SAFETY: the action only increments an atomic counter, which is
+      // async-signal-safe.
+      let id = unsafe {
+        signal_hook::low_level::register(signo, move || interrupt.interrupt())
+      }?;
+      signal_state.interrupt_on_sigint = Some(id);
+    }
+  }
+
   let (enable_default_handler, has_default_handler) = state
     .borrow_mut::<SignalState>()
     .disable_default_handler(signo);
@@ -418,7 +553,11 @@ fn op_signal_bind(
   };
   let rid = state.resource_table.add(resource);
 
-  if !has_default_handler {
+  // When the runtime manages SIGINT, unbinding the last listener must not
+  // bring back the default handler, which would exit right away.
+  let managed_by_runtime =
+    signo == libc::SIGINT && state.has::<Rc<SigintDisposition>>();
+  if !has_default_handler && !managed_by_runtime {
     // restore default signal handler when the signal is unbound
     // this can error if the signal is not supported, if so let's just leave it as is
     let _ = signal_hook::flag::register_conditional_default(
@@ -492,3 +631,28 @@ pub fn op_signal_unbind(
   resource.close();
   Ok(())
 }
+
+/// Waits for a SIGINT that starts a grace period. Resolves to false right away
+/// when the runtime doesn't manage SIGINT.
+#[op2(async)]
+async fn op_sigint_poll(state: Rc<RefCell<OpState>>) -> bool {
+  let Some(disposition) = state
+    .borrow()
+    .try_borrow::<Rc<SigintDisposition>>()
+    .cloned()
+  else {
+    return false;
+  };
+  let mut received =
+    RcRef::map(disposition, |d| &d.received).borrow_mut().await;
+  received.recv().await.is_some()
+}
+
+/// Ends the current grace period, because JavaScript canceled the
+/// "interrupt" event.
+#[op2(fast)]
+fn op_sigint_handled(state: &mut OpState) {
+  if let Some(disposition) = state.try_borrow::<Rc<SigintDisposition>>() {
+    disposition.handled.notify_one();
+  }
+}
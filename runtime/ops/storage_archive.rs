@@ -0,0 +1,1371 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Export and import of the persistent web data of an installation: the
+//! `localStorage` and Cache API databases of every origin, and the
+//! content-addressed blob store.
+//!
+//! An archive is a gzipped tar with this layout:
+//!
+//! - `origins/<id>/local_storage`: the `localStorage` database of an origin.
+//! - `caches/<id>/cache_metadata.db`: the Cache API database of an origin,
+//!   with its response bodies in `caches/<id>/<cache id>/responses/`.
+//! - `blobs/<hash>`: the content of a blob.
+//! - `manifest.json`: the format version, the origins with the schema
+//!   versions of their databases, the blobs with their reference counts and
+//!   the size and SHA-256 of every other file. Written last.
+//!
+//! `<id>` is the name of the origin's data directory, the hash of its storage
+//! key.
+//!
+//! Databases are copied with SQLite's backup API in a single step, so every
+//! database in the archive is a consistent snapshot even if its owner keeps
+//! writing to it. Different databases are snapshotted at different times.
+//!
+//! An import unpacks the archive into a staging directory and checks it
+//! against the manifest first. Nothing is changed if a checksum doesn't
+//! match, or if the archive or one of its databases is newer than this
+//! version understands. The databases are then swapped in with the backup
+//! API, which replaces the content of the destination in one transaction.
+//! `merge` only restores databases and blobs that are missing; `replace`
+//! overwrites them, unless that would downgrade a database's schema.
+
+#![allow(clippy::disallowed_methods)]
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use deno_core::op2;
+use deno_core::serde_json;
+use deno_core::unsync::spawn_blocking;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_permissions::PermissionsContainer;
+use deno_webstorage::rusqlite;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::backup::Backup;
+use rusqlite::backup::StepResult;
+use rusqlite::params;
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+
+use super::blob_store::BlobEntry;
+use super::blob_store::BlobStoreError;
+use super::blob_store::ContentStore;
+
+deno_core::extension!(
+  deno_storage_archive,
+  ops = [op_storage_export, op_storage_import, op_storage_task_next],
+  options = { dirs: Option<StorageDirs> },
+  state = |state, options| {
+    if let Some(dirs) = options.dirs {
+      state.put(dirs);
+    }
+  },
+);
+
+const FORMAT_VERSION: u32 = 1;
+/// 2 added the `seq` column that orders keys by insertion.
+const LOCAL_STORAGE_SCHEMA_VERSION: u32 = 2;
+/// 2 added the `response_body_etag` column.
+const CACHE_SCHEMA_VERSION: u32 = 2;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const LOCAL_STORAGE_FILE: &str = "local_storage";
+const CACHE_DB_FILE: &str = "cache_metadata.db";
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const BUSY_RETRIES: u32 = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageArchiveError {
+  #[error("Storage export is not available in this context")]
+  NotAvailable,
+  #[error("No data stored for origin '{0}'")]
+  UnknownOrigin(String),
+  #[error("Invalid storage archive: {0}")]
+  InvalidArchive(String),
+  #[error(
+    "Storage archive {what} version {version} is newer than the supported version {supported}"
+  )]
+  UnsupportedVersion {
+    what: &'static str,
+    version: u32,
+    supported: u32,
+  },
+  #[error("Refusing to replace '{0}', which has a newer schema version")]
+  NewerSchema(String),
+  #[error("Checksum mismatch for '{0}' in storage archive")]
+  ChecksumMismatch(String),
+  #[error("Storage task was canceled")]
+  Canceled,
+  #[error(transparent)]
+  Sqlite(#[from] rusqlite::Error),
+  #[error(transparent)]
+  BlobStore(#[from] BlobStoreError),
+  #[error(transparent)]
+  Resource(deno_core::error::AnyError),
+  #[error(transparent)]
+  Permission(deno_core::error::AnyError),
+  #[error(transparent)]
+  Io(#[from] io::Error),
+}
+
+/// Where the persistent web data of an installation is kept. Each directory
+/// holds a directory per origin.
+#[derive(Debug, Clone)]
+pub struct StorageDirs {
+  /// Holds the `localStorage` databases.
+  pub origin_data_dir: PathBuf,
+  /// Holds the Cache API databases and response bodies.
+  pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+  format_version: u32,
+  /// Milliseconds since the epoch.
+  created_at: u64,
+  origins: Vec<OriginManifest>,
+  blobs: Vec<BlobEntry>,
+  files: Vec<FileManifest>,
+}
+
+/// The schema versions of the databases of an origin that are in the
+/// archive.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OriginManifest {
+  id: String,
+  local_storage_version: Option<u32>,
+  cache_version: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileManifest {
+  path: String,
+  size: u64,
+  sha256: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+  #[default]
+  Merge,
+  Replace,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageExportOptions {
+  /// Ids of the origins to export. All origins and the blob store are
+  /// exported if not set.
+  origins: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageImportOptions {
+  #[serde(default)]
+  mode: ImportMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageProgress {
+  /// Number of databases and blobs that were copied, out of `total`.
+  pub done: u64,
+  pub total: u64,
+  /// Archive path of the last one.
+  pub item: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageArchiveSummary {
+  pub origins: u64,
+  pub databases: u64,
+  pub blobs: u64,
+  /// Databases and blobs that an import in `merge` mode kept.
+  pub skipped: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum StorageTaskEvent {
+  Progress(StorageProgress),
+  Finished(StorageArchiveSummary),
+}
+
+type ProgressFn<'a> =
+  &'a mut dyn FnMut(StorageProgress) -> Result<(), StorageArchiveError>;
+
+/// Writes an archive of the data in `dirs` and `store` to `archive_path`.
+/// If `origins` is set, only those origins are exported, without the blob
+/// store, whose blobs don't belong to an origin.
+pub fn export(
+  dirs: &StorageDirs,
+  store: Option<&ContentStore>,
+  archive_path: &Path,
+  origins: Option<&[String]>,
+  progress: ProgressFn,
+) -> Result<StorageArchiveSummary, StorageArchiveError> {
+  let mut found = list_origins(dirs)?;
+  let selected = match origins {
+    Some(ids) => ids
+      .iter()
+      .map(|id| {
+        let paths = found
+          .remove(id)
+          .ok_or_else(|| StorageArchiveError::UnknownOrigin(id.clone()))?;
+        Ok((id.clone(), paths))
+      })
+      .collect::<Result<Vec<_>, StorageArchiveError>>()?,
+    None => found.into_iter().collect(),
+  };
+  let blobs = match (store, origins) {
+    (Some(store), None) => store.entries()?,
+    _ => vec![],
+  };
+  let total = selected
+    .iter()
+    .map(|(_, paths)| {
+      paths.local_storage.is_some() as u64 + paths.cache.is_some() as u64
+    })
+    .sum::<u64>()
+    + blobs.len() as u64;
+
+  let dir = match archive_path.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir,
+    _ => Path::new("."),
+  };
+  let staging = tempfile::Builder::new()
+    .prefix(".deno_storage_export")
+    .tempdir_in(dir)?;
+  let (file, temp_path) = tempfile::Builder::new()
+    .prefix(".deno_storage_export")
+    .tempfile_in(dir)?
+    .into_parts();
+  let mut writer = ArchiveWriter::new(file);
+  let mut summary = StorageArchiveSummary::default();
+  let mut manifest = Manifest {
+    format_version: FORMAT_VERSION,
+    created_at: SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .map(|d| d.as_millis() as u64)
+      .unwrap_or(0),
+    origins: vec![],
+    blobs: vec![],
+    files: vec![],
+  };
+  let mut done = 0;
+  let mut report = |item: String| {
+    done += 1;
+    progress(StorageProgress { done, total, item })
+  };
+
+  for (i, (id, paths)) in selected.into_iter().enumerate() {
+    let mut origin = OriginManifest {
+      id: id.clone(),
+      local_storage_version: None,
+      cache_version: None,
+    };
+
+    if let Some(src) = &paths.local_storage {
+      let staged = staging.path().join(i.to_string());
+      copy_database_file(src, &staged)?;
+      origin.local_storage_version =
+        Some(local_storage_version(&Connection::open(&staged)?)?);
+      let item = local_storage_path(&id);
+      writer.add_file(&item, &staged)?;
+      summary.databases += 1;
+      report(item)?;
+    }
+
+    if let Some(src_dir) = &paths.cache {
+      let staged = staging.path().join(format!("{i}.cache"));
+      copy_database_file(&src_dir.join(CACHE_DB_FILE), &staged)?;
+      let conn = Connection::open(&staged)?;
+      origin.cache_version = Some(cache_version(&conn)?);
+      // Bodies are written before their row and never modified, so a body
+      // is only missing if its entry was deleted after the snapshot.
+      let bodies = conn
+        .prepare(
+          "SELECT cache_id, response_body_key FROM request_response_list
+            WHERE response_body_key IS NOT NULL",
+        )?
+        .query_map(params![], |row| {
+          Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+      let mut staged_bodies = vec![];
+      for (n, (cache_id, key)) in bodies.into_iter().enumerate() {
+        let rel = format!("{cache_id}/responses/{key}");
+        let staged_body = staging.path().join(format!("{i}.body{n}"));
+        match fs::copy(src_dir.join(&rel), &staged_body) {
+          Ok(_) => staged_bodies.push((rel, staged_body)),
+          Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            conn.execute(
+              "DELETE FROM request_response_list
+                WHERE cache_id = ?1 AND response_body_key = ?2",
+              params![cache_id, key],
+            )?;
+          }
+          Err(err) => return Err(err.into()),
+        }
+      }
+      drop(conn);
+      let item = cache_db_path(&id);
+      writer.add_file(&item, &staged)?;
+      for (rel, staged_body) in staged_bodies {
+        writer.add_file(&format!("caches/{id}/{rel}"), &staged_body)?;
+      }
+      summary.databases += 1;
+      report(item)?;
+    }
+
+    manifest.origins.push(origin);
+    summary.origins += 1;
+  }
+
+  if let Some(store) = store {
+    for blob in blobs {
+      let file = match store.open(&blob.hash) {
+        Ok(file) => file,
+        // deleted since it was listed
+        Err(BlobStoreError::NotFound(_)) => continue,
+        Err(err) => return Err(err.into()),
+      };
+      let item = format!("blobs/{}", blob.hash);
+      writer.append(&item, blob.size, file)?;
+      manifest.blobs.push(blob);
+      summary.blobs += 1;
+      report(item)?;
+    }
+  }
+
+  manifest.files = std::mem::take(&mut writer.files);
+  let manifest =
+    serde_json::to_vec_pretty(&manifest).map_err(io::Error::from)?;
+  writer.append(MANIFEST_PATH, manifest.len() as u64, manifest.as_slice())?;
+  let file = writer.finish()?;
+  file.sync_all()?;
+  temp_path.persist(archive_path).map_err(|err| err.error)?;
+  Ok(summary)
+}
+
+/// Restores the data in the archive at `archive_path` into `dirs` and
+/// `store`.
+pub fn import(
+  dirs: &StorageDirs,
+  store: Option<&ContentStore>,
+  archive_path: &Path,
+  mode: ImportMode,
+  progress: ProgressFn,
+) -> Result<StorageArchiveSummary, StorageArchiveError> {
+  fs::create_dir_all(&dirs.origin_data_dir)?;
+  let staging = tempfile::Builder::new()
+    .prefix(".deno_storage_import")
+    .tempdir_in(&dirs.origin_data_dir)?;
+  let manifest = unpack(archive_path, staging.path())?;
+  let staged = |path: &str| staging.path().join(path);
+
+  for origin in &manifest.origins {
+    let databases = [
+      (
+        local_storage_path(&origin.id),
+        origin.local_storage_version,
+        "localStorage",
+        LOCAL_STORAGE_SCHEMA_VERSION,
+      ),
+      (
+        cache_db_path(&origin.id),
+        origin.cache_version,
+        "cache",
+        CACHE_SCHEMA_VERSION,
+      ),
+    ];
+    for (path, version, what, supported) in databases {
+      let Some(version) = version else {
+        continue;
+      };
+      if version > supported {
+        return Err(StorageArchiveError::UnsupportedVersion {
+          what,
+          version,
+          supported,
+        });
+      }
+      if !staged(&path).is_file() {
+        return Err(StorageArchiveError::InvalidArchive(format!(
+          "missing '{path}'"
+        )));
+      }
+    }
+  }
+  if !manifest.blobs.is_empty() && store.is_none() {
+    return Err(StorageArchiveError::NotAvailable);
+  }
+
+  // Only databases that would be replaced can block the import, so it is
+  // checked before anything is changed.
+  let targets = manifest
+    .origins
+    .iter()
+    .map(|origin| {
+      let local_storage = dirs.origin_data_dir.join(&origin.id);
+      let cache = dirs.cache_dir.as_ref().map(|dir| dir.join(&origin.id));
+      (origin, local_storage, cache)
+    })
+    .collect::<Vec<_>>();
+  if mode == ImportMode::Replace {
+    for (origin, local_storage_dir, cache_dir) in &targets {
+      let dst = local_storage_dir.join(LOCAL_STORAGE_FILE);
+      if let Some(version) = origin.local_storage_version {
+        if dst.is_file() && local_storage_version(&open(&dst)?)? > version {
+          return Err(StorageArchiveError::NewerSchema(
+            dst.display().to_string(),
+          ));
+        }
+      }
+      let Some(cache_dir) = cache_dir else {
+        continue;
+      };
+      let dst = cache_dir.join(CACHE_DB_FILE);
+      if let Some(version) = origin.cache_version {
+        if dst.is_file() && cache_version(&open(&dst)?)? > version {
+          return Err(StorageArchiveError::NewerSchema(
+            dst.display().to_string(),
+          ));
+        }
+      }
+    }
+  }
+
+  let total = manifest
+    .origins
+    .iter()
+    .map(|origin| {
+      origin.local_storage_version.is_some() as u64
+        + origin.cache_version.is_some() as u64
+    })
+    .sum::<u64>()
+    + manifest.blobs.len() as u64;
+  let mut done = 0;
+  let mut report = |item: String| {
+    done += 1;
+    progress(StorageProgress { done, total, item })
+  };
+  let mut summary = StorageArchiveSummary::default();
+
+  for (origin, local_storage_dir, cache_dir) in targets {
+    if origin.local_storage_version.is_some() {
+      let item = local_storage_path(&origin.id);
+      let dst = local_storage_dir.join(LOCAL_STORAGE_FILE);
+      if mode == ImportMode::Merge && dst.is_file() {
+        summary.skipped += 1;
+      } else {
+        fs::create_dir_all(&local_storage_dir)?;
+        copy_database_file(&staged(&item), &dst)?;
+        summary.databases += 1;
+      }
+      report(item)?;
+    }
+
+    if origin.cache_version.is_some() {
+      let item = cache_db_path(&origin.id);
+      match cache_dir {
+        Some(cache_dir)
+          if mode == ImportMode::Replace
+            || !cache_dir.join(CACHE_DB_FILE).is_file() =>
+        {
+          // The bodies go first, so that the restored entries never point
+          // to a missing body.
+          let prefix = format!("caches/{}/", origin.id);
+          for file in &manifest.files {
+            let Some(rel) = file.path.strip_prefix(&prefix) else {
+              continue;
+            };
+            if rel == CACHE_DB_FILE {
+              continue;
+            }
+            let dst = cache_dir.join(rel);
+            fs::create_dir_all(dst.parent().unwrap())?;
+            fs::copy(staged(&file.path), dst)?;
+          }
+          copy_database_file(&staged(&item), &cache_dir.join(CACHE_DB_FILE))?;
+          summary.databases += 1;
+        }
+        _ => summary.skipped += 1,
+      }
+      report(item)?;
+    }
+
+    summary.origins += 1;
+  }
+
+  if let Some(store) = store {
+    for blob in &manifest.blobs {
+      let item = format!("blobs/{}", blob.hash);
+      let file = File::open(staged(&item))?;
+      if store.restore(
+        file,
+        &blob.hash,
+        blob.refs,
+        mode == ImportMode::Replace,
+      )? {
+        summary.blobs += 1;
+      } else {
+        summary.skipped += 1;
+      }
+      report(item)?;
+    }
+  }
+
+  Ok(summary)
+}
+
+#[derive(Debug, Default)]
+struct OriginPaths {
+  /// The `localStorage` database.
+  local_storage: Option<PathBuf>,
+  /// The directory with the Cache API database.
+  cache: Option<PathBuf>,
+}
+
+fn list_origins(
+  dirs: &StorageDirs,
+) -> Result<BTreeMap<String, OriginPaths>, StorageArchiveError> {
+  let mut origins = BTreeMap::<String, OriginPaths>::new();
+  for (root, is_cache) in [
+    (Some(&dirs.origin_data_dir), false),
+    (dirs.cache_dir.as_ref(), true),
+  ] {
+    let Some(root) = root else {
+      continue;
+    };
+    let entries = match fs::read_dir(root) {
+      Ok(entries) => entries,
+      Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+      Err(err) => return Err(err.into()),
+    };
+    for entry in entries {
+      let entry = entry?;
+      let Some(id) = entry.file_name().to_str().map(str::to_string) else {
+        continue;
+      };
+      if !is_valid_id(&id) {
+        continue;
+      }
+      if is_cache {
+        if entry.path().join(CACHE_DB_FILE).is_file() {
+          origins.entry(id).or_default().cache = Some(entry.path());
+        }
+      } else {
+        let path = entry.path().join(LOCAL_STORAGE_FILE);
+        if path.is_file() {
+          origins.entry(id).or_default().local_storage = Some(path);
+        }
+      }
+    }
+  }
+  Ok(origins)
+}
+
+/// Origin directories are named after a hash; anything else in the data
+/// directories, such as staging directories, isn't an origin.
+fn is_valid_id(id: &str) -> bool {
+  !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+fn local_storage_path(id: &str) -> String {
+  format!("origins/{id}/{LOCAL_STORAGE_FILE}")
+}
+
+fn cache_db_path(id: &str) -> String {
+  format!("caches/{id}/{CACHE_DB_FILE}")
+}
+
+fn open(path: &Path) -> Result<Connection, rusqlite::Error> {
+  let conn = Connection::open(path)?;
+  conn.busy_timeout(BUSY_TIMEOUT)?;
+  Ok(conn)
+}
+
+/// Copies the database at `src` to `dst` in one step of the backup API: the
+/// copy is a consistent snapshot of `src`, and replaces the content of `dst`
+/// in one transaction.
+fn copy_database_file(src: &Path, dst: &Path) -> Result<(), rusqlite::Error> {
+  copy_database(&open(src)?, &mut open(dst)?)
+}
+
+fn copy_database(
+  src: &Connection,
+  dst: &mut Connection,
+) -> Result<(), rusqlite::Error> {
+  let backup = Backup::new(src, dst)?;
+  let mut retries = 0;
+  loop {
+    match backup.step(-1)? {
+      StepResult::Done => return Ok(()),
+      StepResult::More => {}
+      // the busy timeout ran out
+      _ if retries < BUSY_RETRIES => retries += 1,
+      _ => {
+        return Err(rusqlite::Error::SqliteFailure(
+          rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+          None,
+        ))
+      }
+    }
+  }
+}
+
+fn has_column(
+  conn: &Connection,
+  table: &str,
+  column: &str,
+) -> Result<bool, rusqlite::Error> {
+  conn
+    .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+    .exists(params![table, column])
+}
+
+fn local_storage_version(conn: &Connection) -> Result<u32, rusqlite::Error> {
+  Ok(if has_column(conn, "data", "seq")? {
+    2
+  } else {
+    1
+  })
+}
+
+fn cache_version(conn: &Connection) -> Result<u32, rusqlite::Error> {
+  let has_etag =
+    has_column(conn, "request_response_list", "response_body_etag")?;
+  Ok(if has_etag { 2 } else { 1 })
+}
+
+struct ArchiveWriter {
+  tar: tar::Builder<GzEncoder<File>>,
+  files: Vec<FileManifest>,
+}
+
+impl ArchiveWriter {
+  fn new(file: File) -> Self {
+    Self {
+      tar: tar::Builder::new(GzEncoder::new(file, Compression::default())),
+      files: vec![],
+    }
+  }
+
+  /// Adds a file that isn't modified while it is read.
+  fn add_file(&mut self, path: &str, src: &Path) -> io::Result<()> {
+    let file = File::open(src)?;
+    let size = file.metadata()?.len();
+    self.append(path, size, file)
+  }
+
+  fn append(
+    &mut self,
+    path: &str,
+    size: u64,
+    reader: impl Read,
+  ) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_entry_type(tar::EntryType::Regular);
+    let mut reader = HashingReader {
+      inner: reader.take(size),
+      hasher: Sha256::new(),
+      read: 0,
+    };
+    self.tar.append_data(&mut header, path, &mut reader)?;
+    if reader.read != size {
+      return Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("'{path}' changed while it was archived"),
+      ));
+    }
+    if path != MANIFEST_PATH {
+      self.files.push(FileManifest {
+        path: path.to_string(),
+        size,
+        sha256: faster_hex::hex_string(&reader.hasher.finalize()),
+      });
+    }
+    Ok(())
+  }
+
+  fn finish(self) -> io::Result<File> {
+    self.tar.into_inner()?.finish()
+  }
+}
+
+struct HashingReader<R> {
+  inner: R,
+  hasher: Sha256,
+  read: u64,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let n = self.inner.read(buf)?;
+    self.hasher.update(&buf[..n]);
+    self.read += n as u64;
+    Ok(n)
+  }
+}
+
+/// Unpacks the archive into `dir` and checks its content against the
+/// manifest, which is returned.
+fn unpack(
+  archive_path: &Path,
+  dir: &Path,
+) -> Result<Manifest, StorageArchiveError> {
+  let invalid = StorageArchiveError::InvalidArchive;
+  let mut archive =
+    tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    if entry.header().entry_type() != tar::EntryType::Regular {
+      return Err(invalid("unexpected entry type".to_string()));
+    }
+    let path = entry.path()?.into_owned();
+    if !is_safe_path(&path) {
+      return Err(invalid(format!("invalid path '{}'", path.display())));
+    }
+    entry.unpack_in(dir)?;
+  }
+
+  let manifest = match fs::read(dir.join(MANIFEST_PATH)) {
+    Ok(manifest) => manifest,
+    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+      return Err(invalid("missing manifest".to_string()))
+    }
+    Err(err) => return Err(err.into()),
+  };
+  let manifest: Manifest = serde_json::from_slice(&manifest)
+    .map_err(|err| invalid(format!("invalid manifest: {err}")))?;
+  if manifest.format_version > FORMAT_VERSION {
+    return Err(StorageArchiveError::UnsupportedVersion {
+      what: "format",
+      version: manifest.format_version,
+      supported: FORMAT_VERSION,
+    });
+  }
+  for origin in &manifest.origins {
+    if !is_valid_id(&origin.id) {
+      return Err(invalid(format!("invalid origin id '{}'", origin.id)));
+    }
+  }
+
+  // Every unpacked file must be listed and match its checksum, so that the
+  // import can't be made to copy anything else.
+  let mut listed = HashSet::new();
+  for file in &manifest.files {
+    if !is_safe_path(Path::new(&file.path)) {
+      return Err(invalid(format!("invalid path '{}'", file.path)));
+    }
+    let (size, sha256) = hash_file(&dir.join(&file.path)).map_err(|err| {
+      if err.kind() == io::ErrorKind::NotFound {
+        invalid(format!("missing '{}'", file.path))
+      } else {
+        err.into()
+      }
+    })?;
+    if size != file.size || sha256 != file.sha256 {
+      return Err(StorageArchiveError::ChecksumMismatch(file.path.clone()));
+    }
+    listed.insert(file.path.as_str());
+  }
+  for blob in &manifest.blobs {
+    let path = format!("blobs/{}", blob.hash);
+    let (size, sha256) = hash_file(&dir.join(&path))
+      .map_err(|_| invalid(format!("missing '{path}'")))?;
+    if size != blob.size || sha256 != blob.hash {
+      return Err(StorageArchiveError::ChecksumMismatch(path));
+    }
+  }
+  let blobs = manifest
+    .blobs
+    .iter()
+    .map(|blob| format!("blobs/{}", blob.hash))
+    .collect::<HashSet<_>>();
+  let mut unlisted = None;
+  walk_files(dir, dir, &mut |rel| {
+    if rel != MANIFEST_PATH
+      && !listed.contains(rel)
+      && !blobs.contains(rel)
+      && unlisted.is_none()
+    {
+      unlisted = Some(rel.to_string());
+    }
+  })?;
+  if let Some(path) = unlisted {
+    return Err(invalid(format!("'{path}' is not in the manifest")));
+  }
+  Ok(manifest)
+}
+
+fn is_safe_path(path: &Path) -> bool {
+  path.components().all(|c| matches!(c, Component::Normal(_)))
+    && path.components().next().is_some()
+}
+
+fn hash_file(path: &Path) -> io::Result<(u64, String)> {
+  let mut reader = HashingReader {
+    inner: File::open(path)?,
+    hasher: Sha256::new(),
+    read: 0,
+  };
+  io::copy(&mut reader, &mut io::sink())?;
+  Ok((
+    reader.read,
+    faster_hex::hex_string(&reader.hasher.finalize()),
+  ))
+}
+
+/// Calls `f` with the path of every file below `dir`, relative to `root`
+/// and with `/` as separator.
+fn walk_files(
+  root: &Path,
+  dir: &Path,
+  f: &mut dyn FnMut(&str),
+) -> io::Result<()> {
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let path = entry.path();
+    if entry.file_type()?.is_dir() {
+      walk_files(root, &path, f)?;
+    } else {
+      let rel = path.strip_prefix(root).unwrap();
+      let rel = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+      f(&rel);
+    }
+  }
+  Ok(())
+}
+
+type StorageTaskResult = Result<StorageTaskEvent, StorageArchiveError>;
+
+struct StorageTaskResource {
+  events: AsyncRefCell<mpsc::UnboundedReceiver<StorageTaskResult>>,
+  cancel: CancelHandle,
+}
+
+impl Resource for StorageTaskResource {
+  fn name(&self) -> Cow<str> {
+    "storageTask".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+/// Runs `task` on the blocking pool. Its progress and outcome are read with
+/// `op_storage_task_next`; closing the resource cancels it at the next
+/// progress report.
+fn spawn_task(
+  state: &mut OpState,
+  task: impl FnOnce(ProgressFn) -> Result<StorageArchiveSummary, StorageArchiveError>
+    + Send
+    + 'static,
+) -> ResourceId {
+  let (tx, rx) = mpsc::unbounded_channel();
+  spawn_blocking(move || {
+    let mut progress = |progress: StorageProgress| {
+      tx.send(Ok(StorageTaskEvent::Progress(progress)))
+        .map_err(|_| StorageArchiveError::Canceled)
+    };
+    let result = task(&mut progress).map(StorageTaskEvent::Finished);
+    let _ = tx.send(result);
+  });
+  state.resource_table.add(StorageTaskResource {
+    events: AsyncRefCell::new(rx),
+    cancel: Default::default(),
+  })
+}
+
+fn storage_dirs(state: &OpState) -> Result<StorageDirs, StorageArchiveError> {
+  state
+    .try_borrow::<StorageDirs>()
+    .cloned()
+    .ok_or(StorageArchiveError::NotAvailable)
+}
+
+/// Starts exporting the persistent web data to an archive at `path`.
+/// Returns a task resource for `op_storage_task_next`.
+#[op2]
+#[smi]
+pub fn op_storage_export(
+  state: &mut OpState,
+  #[string] path: String,
+  #[serde] options: Option<StorageExportOptions>,
+) -> Result<ResourceId, StorageArchiveError> {
+  let path = {
+    let permissions = state.borrow_mut::<PermissionsContainer>();
+    permissions
+      .check_read_all("op_storage_export")
+      .map_err(StorageArchiveError::Permission)?;
+    permissions
+      .check_write(&path, "op_storage_export")
+      .map_err(StorageArchiveError::Permission)?
+  };
+  let dirs = storage_dirs(state)?;
+  let store = state.try_borrow::<Arc<ContentStore>>().cloned();
+  let origins = options.unwrap_or_default().origins;
+  Ok(spawn_task(state, move |progress| {
+    export(&dirs, store.as_deref(), &path, origins.as_deref(), progress)
+  }))
+}
+
+/// Starts importing the archive at `path`. Returns a task resource for
+/// `op_storage_task_next`.
+#[op2]
+#[smi]
+pub fn op_storage_import(
+  state: &mut OpState,
+  #[string] path: String,
+  #[serde] options: Option<StorageImportOptions>,
+) -> Result<ResourceId, StorageArchiveError> {
+  let path = {
+    let permissions = state.borrow_mut::<PermissionsContainer>();
+    permissions
+      .check_write_all("op_storage_import")
+      .map_err(StorageArchiveError::Permission)?;
+    permissions
+      .check_read(&path, "op_storage_import")
+      .map_err(StorageArchiveError::Permission)?
+  };
+  let dirs = storage_dirs(state)?;
+  let store = state.try_borrow::<Arc<ContentStore>>().cloned();
+  let mode = options.unwrap_or_default().mode;
+  Ok(spawn_task(state, move |progress| {
+    import(&dirs, store.as_deref(), &path, mode, progress)
+  }))
+}
+
+/// Returns the next event of the storage task `rid`: progress reports, then
+/// the summary. Returns `null` after the summary, or if the task was closed;
+/// throws if the task failed.
+#[op2(async)]
+#[serde]
+pub async fn op_storage_task_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<StorageTaskEvent>, StorageArchiveError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<StorageTaskResource>(rid)
+    .map_err(StorageArchiveError::Resource)?;
+  let cancel = RcRef::map(&resource, |r| &r.cancel);
+  let mut events = RcRef::map(&resource, |r| &r.events).borrow_mut().await;
+  match events.recv().or_cancel(cancel).await {
+    Ok(Some(event)) => event.map(Some),
+    Ok(None) | Err(_) => Ok(None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::Ordering;
+  use std::thread;
+
+  struct Install {
+    root: tempfile::TempDir,
+    dirs: StorageDirs,
+    store: ContentStore,
+  }
+
+  fn install() -> Install {
+    let dir = tempfile::tempdir().unwrap();
+    let dirs = StorageDirs {
+      origin_data_dir: dir.path().join("location_data"),
+      cache_dir: Some(dir.path().join("deno_cache")),
+    };
+    let store = ContentStore::new(dir.path().join("blob_store"));
+    Install {
+      root: dir,
+      dirs,
+      store,
+    }
+  }
+
+  /// Creates the `localStorage` database of `id` like `deno_webstorage`.
+  fn local_storage(install: &Install, id: &str) -> Connection {
+    let dir = install.dirs.origin_data_dir.join(id);
+    fs::create_dir_all(&dir).unwrap();
+    let conn = Connection::open(dir.join(LOCAL_STORAGE_FILE)).unwrap();
+    conn
+      .execute_batch(
+        "
+        PRAGMA journal_mode=WAL;
+        CREATE TABLE IF NOT EXISTS data
+          (key VARCHAR UNIQUE, value VARCHAR, seq INTEGER);
+        ",
+      )
+      .unwrap();
+    conn
+  }
+
+  fn set(conn: &Connection, key: &str, value: &str) {
+    conn
+      .execute(
+        "INSERT INTO data (key, value, seq)
+          VALUES (?1, ?2, (SELECT IFNULL(MAX(seq), 0) + 1 FROM data))
+          ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+      )
+      .unwrap();
+  }
+
+  fn get(install: &Install, id: &str, key: &str) -> Option<String> {
+    let path = install
+      .dirs
+      .origin_data_dir
+      .join(id)
+      .join(LOCAL_STORAGE_FILE);
+    if !path.is_file() {
+      return None;
+    }
+    Connection::open(path)
+      .unwrap()
+      .query_row(
+        "SELECT value FROM data WHERE key = ?",
+        params![key],
+        |row| row.get(0),
+      )
+      .ok()
+  }
+
+  fn no_progress(_: StorageProgress) -> Result<(), StorageArchiveError> {
+    Ok(())
+  }
+
+  fn export_all(install: &Install, path: &Path) -> StorageArchiveSummary {
+    export(
+      &install.dirs,
+      Some(&install.store),
+      path,
+      None,
+      &mut no_progress,
+    )
+    .unwrap()
+  }
+
+  fn import_all(
+    install: &Install,
+    path: &Path,
+    mode: ImportMode,
+  ) -> Result<StorageArchiveSummary, StorageArchiveError> {
+    import(
+      &install.dirs,
+      Some(&install.store),
+      path,
+      mode,
+      &mut no_progress,
+    )
+  }
+
+  #[test]
+  fn export_while_writing_is_consistent() {
+    let src = install();
+    local_storage(&src, "a1");
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = thread::spawn({
+      let path = src.dirs.origin_data_dir.join("a1").join(LOCAL_STORAGE_FILE);
+      let stop = stop.clone();
+      move || {
+        let mut conn = Connection::open(path).unwrap();
+        conn.busy_timeout(BUSY_TIMEOUT).unwrap();
+        let mut i = 0u64;
+        while !stop.load(Ordering::Relaxed) || i < 100 {
+          // `count` always matches the number of other keys
+          let tx = conn.transaction().unwrap();
+          set(&tx, &format!("key{i}"), &"x".repeat(512));
+          set(&tx, "count", &(i + 1).to_string());
+          tx.commit().unwrap();
+          i += 1;
+        }
+      }
+    });
+
+    let archive = src.root.path().join("export.tar.gz");
+    let mut events = vec![];
+    for _ in 0..5 {
+      events.clear();
+      export(
+        &src.dirs,
+        Some(&src.store),
+        &archive,
+        None,
+        &mut |progress| {
+          events.push(progress);
+          Ok(())
+        },
+      )
+      .unwrap();
+    }
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+    assert_eq!(
+      events,
+      [StorageProgress {
+        done: 1,
+        total: 1,
+        item: "origins/a1/local_storage".to_string(),
+      }]
+    );
+
+    let dst = install();
+    let summary = import_all(&dst, &archive, ImportMode::Merge).unwrap();
+    assert_eq!(summary.databases, 1);
+    let path = dst.dirs.origin_data_dir.join("a1").join(LOCAL_STORAGE_FILE);
+    let conn = Connection::open(path).unwrap();
+    let (count, keys): (String, u64) = conn
+      .query_row(
+        "SELECT (SELECT value FROM data WHERE key = 'count'),
+          (SELECT COUNT(*) FROM data WHERE key != 'count')",
+        params![],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )
+      .unwrap();
+    assert_eq!(count.parse::<u64>().unwrap(), keys);
+  }
+
+  #[test]
+  fn import_restores_contents_and_refcounts() {
+    let src = install();
+    set(&local_storage(&src, "a1"), "hello", "world");
+    set(&local_storage(&src, "b2"), "other", "origin");
+    let shared = src.store.put(&b"shared"[..]).unwrap();
+    src.store.put(&b"shared"[..]).unwrap();
+    let single = src.store.put(&b"single"[..]).unwrap();
+
+    let cache = deno_cache::SqliteBackedCache::new(
+      src.dirs.cache_dir.as_ref().unwrap().join("a1"),
+    );
+    {
+      let conn = cache.connection.lock();
+      conn
+        .execute_batch(
+          "
+          INSERT INTO cache_storage (id, cache_name) VALUES (1, 'v1');
+          INSERT INTO request_response_list (cache_id, request_url,
+            request_headers, response_headers, response_status,
+            response_body_key, last_inserted_at)
+            VALUES (1, 'https://deno.land/', x'', x'', 200, 'body1', 0),
+              (1, 'https://deno.land/gone', x'', x'', 200, 'body2', 0);
+          ",
+        )
+        .unwrap();
+    }
+    let responses = cache.cache_storage_dir.join("1").join("responses");
+    fs::create_dir_all(&responses).unwrap();
+    fs::write(responses.join("body1"), "cached body").unwrap();
+    // body2 was deleted after the row was read by an export
+
+    let archive = src.root.path().join("export.tar.gz");
+    let summary = export_all(&src, &archive);
+    assert_eq!(
+      summary,
+      StorageArchiveSummary {
+        origins: 2,
+        databases: 3,
+        blobs: 2,
+        skipped: 0,
+      }
+    );
+
+    let dst = install();
+    let summary = import_all(&dst, &archive, ImportMode::Merge).unwrap();
+    assert_eq!(summary.databases, 3);
+    assert_eq!(summary.blobs, 2);
+    assert_eq!(get(&dst, "a1", "hello").as_deref(), Some("world"));
+    assert_eq!(get(&dst, "b2", "other").as_deref(), Some("origin"));
+    assert_eq!(
+      dst.store.entries().unwrap(),
+      src.store.entries().unwrap(),
+      "reference counts are kept"
+    );
+    assert!(dst
+      .store
+      .entries()
+      .unwrap()
+      .iter()
+      .any(|e| e.hash == shared.hash && e.refs == 2));
+    let mut content = String::new();
+    dst
+      .store
+      .open(&single.hash)
+      .unwrap()
+      .read_to_string(&mut content)
+      .unwrap();
+    assert_eq!(content, "single");
+
+    let cache_dir = dst.dirs.cache_dir.as_ref().unwrap().join("a1");
+    assert_eq!(
+      fs::read_to_string(cache_dir.join("1/responses/body1")).unwrap(),
+      "cached body"
+    );
+    let urls = Connection::open(cache_dir.join(CACHE_DB_FILE))
+      .unwrap()
+      .prepare("SELECT request_url FROM request_response_list")
+      .unwrap()
+      .query_map(params![], |row| row.get::<_, String>(0))
+      .unwrap()
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    // the entry whose body was gone isn't exported
+    assert_eq!(urls, ["https://deno.land/"]);
+
+    // merging again keeps what is there
+    set(&local_storage(&dst, "a1"), "hello", "changed");
+    let summary = import_all(&dst, &archive, ImportMode::Merge).unwrap();
+    assert_eq!(summary.skipped, 5);
+    assert_eq!(get(&dst, "a1", "hello").as_deref(), Some("changed"));
+    // replacing doesn't
+    import_all(&dst, &archive, ImportMode::Replace).unwrap();
+    assert_eq!(get(&dst, "a1", "hello").as_deref(), Some("world"));
+    assert_eq!(dst.store.entries().unwrap(), src.store.entries().unwrap());
+  }
+
+  #[test]
+  fn export_selected_origins() {
+    let src = install();
+    set(&local_storage(&src, "a1"), "k", "a");
+    set(&local_storage(&src, "b2"), "k", "b");
+    src.store.put(&b"blob"[..]).unwrap();
+
+    let archive = src.root.path().join("export.tar.gz");
+    let origins = ["b2".to_string()];
+    let summary = export(
+      &src.dirs,
+      Some(&src.store),
+      &archive,
+      Some(&origins),
+      &mut no_progress,
+    )
+    .unwrap();
+    assert_eq!((summary.origins, summary.blobs), (1, 0));
+
+    let dst = install();
+    import_all(&dst, &archive, ImportMode::Merge).unwrap();
+    assert_eq!(get(&dst, "a1", "k"), None);
+    assert_eq!(get(&dst, "b2", "k").as_deref(), Some("b"));
+
+    let origins = ["c3".to_string()];
+    let err = export(
+      &src.dirs,
+      Some(&src.store),
+      &archive,
+      Some(&origins),
+      &mut no_progress,
+    );
+    assert!(
+      matches!(err, Err(StorageArchiveError::UnknownOrigin(id)) if id == "c3")
+    );
+  }
+
+  #[test]
+  fn import_refuses_newer_schema() {
+    let src = install();
+    let dir = src.dirs.origin_data_dir.join("a1");
+    fs::create_dir_all(&dir).unwrap();
+    // a database from before keys were ordered
+    Connection::open(dir.join(LOCAL_STORAGE_FILE))
+      .unwrap()
+      .execute_batch(
+        "CREATE TABLE data (key VARCHAR UNIQUE, value VARCHAR);
+        INSERT INTO data VALUES ('k', 'old');",
+      )
+      .unwrap();
+    let archive = src.root.path().join("export.tar.gz");
+    export_all(&src, &archive);
+
+    let dst = install();
+    set(&local_storage(&dst, "a1"), "k", "new");
+    let err = import_all(&dst, &archive, ImportMode::Replace);
+    assert!(matches!(err, Err(StorageArchiveError::NewerSchema(_))));
+    assert_eq!(get(&dst, "a1", "k").as_deref(), Some("new"));
+    // merging doesn't replace the database, so it isn't refused
+    import_all(&dst, &archive, ImportMode::Merge).unwrap();
+  }
+
+  #[test]
+  fn import_rejects_tampered_archives() {
+    let src = install();
+    set(&local_storage(&src, "a1"), "k", "v");
+    let archive = src.root.path().join("export.tar.gz");
+    export_all(&src, &archive);
+
+    // rewrite the archive with a different database but the same manifest
+    let unpacked = src.root.path().join("unpacked");
+    tar::Archive::new(GzDecoder::new(File::open(&archive).unwrap()))
+      .unpack(&unpacked)
+      .unwrap();
+    let db = unpacked.join("origins/a1/local_storage");
+    set(&Connection::open(&db).unwrap(), "k", "tampered");
+    let write = |archive: &Path, manifest: &[u8]| {
+      let file = File::create(archive).unwrap();
+      let mut writer = ArchiveWriter::new(file);
+      writer.add_file("origins/a1/local_storage", &db).unwrap();
+      writer
+        .append(MANIFEST_PATH, manifest.len() as u64, manifest)
+        .unwrap();
+      writer.finish().unwrap();
+    };
+    let manifest = fs::read(unpacked.join(MANIFEST_PATH)).unwrap();
+    write(&archive, &manifest);
+
+    let dst = install();
+    let err = import_all(&dst, &archive, ImportMode::Merge);
+    assert!(matches!(err, Err(StorageArchiveError::ChecksumMismatch(_))));
+    assert_eq!(get(&dst, "a1", "k"), None);
+
+    let mut manifest: serde_json::Value =
+      serde_json::from_slice(&manifest).unwrap();
+    manifest["formatVersion"] = (FORMAT_VERSION + 1).into();
+    write(&archive, &serde_json::to_vec(&manifest).unwrap());
+    let err = import_all(&dst, &archive, ImportMode::Merge);
+    assert!(matches!(
+      err,
+      Err(StorageArchiveError::UnsupportedVersion { what: "format", .. })
+    ));
+  }
+}
@@ -12,6 +12,16 @@ use rustyline::KeyCode;
 use rustyline::KeyEvent;
 use rustyline::Modifiers;
 
+use super::tty_input::op_tty_events_open;
+use super::tty_input::op_tty_read_events;
+use super::tty_input::op_tty_set_bracketed_paste;
+use super::tty_input::op_tty_set_mouse_reporting;
+use super::tty_readline::op_readline_abort;
+use super::tty_readline::op_readline_create;
+use super::tty_readline::op_readline_read;
+
+pub const UNSTABLE_FEATURE_NAME: &str = "tty";
+
 #[cfg(windows)]
 use deno_core::parking_lot::Mutex;
 #[cfg(windows)]
@@ -56,7 +66,18 @@ use winapi::um::wincon;
 
 deno_core::extension!(
   deno_tty,
-  ops = [op_set_raw, op_console_size, op_read_line_prompt],
+  ops = [
+    op_set_raw,
+    op_console_size,
+    op_read_line_prompt,
+    op_tty_events_open,
+    op_tty_read_events,
+    op_tty_set_mouse_reporting,
+    op_tty_set_bracketed_paste,
+    op_readline_create,
+    op_readline_read,
+    op_readline_abort,
+  ],
   state = |state| {
     #[cfg(unix)]
     state.put(TtyModeStore::default());
@@ -72,6 +93,10 @@ pub enum TtyError {
   #[cfg(unix)]
   #[error(transparent)]
   Nix(nix::Error),
+  #[error("The resource is not a terminal")]
+  NotATerminal,
+  #[error(transparent)]
+  Canceled(#[from] deno_core::Canceled),
   #[error(transparent)]
   Other(deno_core::error::AnyError),
 }
@@ -96,6 +121,65 @@ fn mode_raw_input_off(original_mode: DWORD) -> DWORD {
   original_mode & !wincon::ENABLE_VIRTUAL_TERMINAL_INPUT | COOKED_MODE
 }
 
+/// Saves the mode of stdin the first time it's called, and restores it when
+/// the process exits.
+#[cfg(unix)]
+pub(super) fn prepare_stdio() {
+  // SAFETY: Save current state of stdio and restore it when we exit.
+  unsafe {
+    use libc::atexit;
+    use libc::tcgetattr;
+    use libc::tcsetattr;
+    use libc::termios;
+    use once_cell::sync::OnceCell;
+
+    // Only save original state once.
+    static ORIG_TERMIOS: OnceCell<Option<termios>> = OnceCell::new();
+    ORIG_TERMIOS.get_or_init(|| {
+      let mut termios = std::mem::zeroed::<termios>();
+      if tcgetattr(libc::STDIN_FILENO, &mut termios) == 0 {
+        extern "C" fn reset_stdio() {
+          // SAFETY: Reset the stdio state.
+          unsafe {
+            tcsetattr(
+              libc::STDIN_FILENO,
+              0,
+              &ORIG_TERMIOS.get().unwrap().unwrap(),
+            )
+          };
+        }
+
+        atexit(reset_stdio);
+        return Some(termios);
+      }
+
+      None
+    });
+  }
+}
+
+/// Turns `mode` into raw mode. With `cbreak`, Ctrl+C and the other signal
+/// keys still raise signals.
+#[cfg(unix)]
+pub(super) fn make_raw(mode: &mut termios::Termios, cbreak: bool) {
+  mode.input_flags &= !(termios::InputFlags::BRKINT
+    | termios::InputFlags::ICRNL
+    | termios::InputFlags::INPCK
+    | termios::InputFlags::ISTRIP
+    | termios::InputFlags::IXON);
+
+  mode.control_flags |= termios::ControlFlags::CS8;
+
+  mode.local_flags &= !(termios::LocalFlags::ECHO
+    | termios::LocalFlags::ICANON
+    | termios::LocalFlags::IEXTEN);
+  if !cbreak {
+    mode.local_flags &= !(termios::LocalFlags::ISIG);
+  }
+  mode.control_chars[termios::SpecialCharacterIndices::VMIN as usize] = 1;
+  mode.control_chars[termios::SpecialCharacterIndices::VTIME as usize] = 0;
+}
+
 #[op2(fast)]
 fn op_set_raw(
   state: &mut OpState,
@@ -221,40 +305,6 @@ fn op_set_raw(
   }
   #[cfg(unix)]
   {
-    fn prepare_stdio() {
-      // SAFETY: Save current state of stdio and restore it when we exit.
-      unsafe {
-        use libc::atexit;
-        use libc::tcgetattr;
-        use libc::tcsetattr;
-        use libc::termios;
-        use once_cell::sync::OnceCell;
-
-        // Only save original state once.
-        static ORIG_TERMIOS: OnceCell<Option<termios>> = OnceCell::new();
-        ORIG_TERMIOS.get_or_init(|| {
-          let mut termios = std::mem::zeroed::<termios>();
-          if tcgetattr(libc::STDIN_FILENO, &mut termios) == 0 {
-            extern "C" fn reset_stdio() {
-              // SAFETY: Reset the stdio state.
-              unsafe {
-                tcsetattr(
-                  libc::STDIN_FILENO,
-                  0,
-                  &ORIG_TERMIOS.get().unwrap().unwrap(),
-                )
-              };
-            }
-
-            atexit(reset_stdio);
-            return Some(termios);
-          }
-
-          None
-        });
-      }
-    }
-
     prepare_stdio();
     let tty_mode_store = state.borrow::<TtyModeStore>().clone();
     let previous_mode = tty_mode_store.get(rid);
@@ -274,22 +324,7 @@ fn op_set_raw(
         }
       };
 
-      raw.input_flags &= !(termios::InputFlags::BRKINT
-        | termios::InputFlags::ICRNL
-        | termios::InputFlags::INPCK
-        | termios::InputFlags::ISTRIP
-        | termios::InputFlags::IXON);
-
-      raw.control_flags |= termios::ControlFlags::CS8;
-
-      raw.local_flags &= !(termios::LocalFlags::ECHO
-        | termios::LocalFlags::ICANON
-        | termios::LocalFlags::IEXTEN);
-      if !cbreak {
-        raw.local_flags &= !(termios::LocalFlags::ISIG);
-      }
-      raw.control_chars[termios::SpecialCharacterIndices::VMIN as usize] = 1;
-      raw.control_chars[termios::SpecialCharacterIndices::VTIME as usize] = 0;
+      make_raw(&mut raw, cbreak);
       termios::tcsetattr(raw_fd, termios::SetArg::TCSADRAIN, &raw)
         .map_err(TtyError::Nix)?;
     } else {
@@ -361,7 +396,7 @@ pub fn console_size(
 }
 
 #[cfg(windows)]
-fn console_size_from_fd(
+pub(crate) fn console_size_from_fd(
   handle: std::os::windows::io::RawHandle,
 ) -> Result<ConsoleSize, std::io::Error> {
   // SAFETY: winapi calls
@@ -391,7 +426,7 @@ fn console_size_from_fd(
 }
 
 #[cfg(not(windows))]
-fn console_size_from_fd(
+pub(crate) fn console_size_from_fd(
   fd: std::os::unix::prelude::RawFd,
 ) -> Result<ConsoleSize, std::io::Error> {
   // SAFETY: libc calls
@@ -0,0 +1,1439 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Key, mouse, paste and resize events read from the terminal, for
+//! `Deno.openTtyEvents()`.
+//!
+//! On Unix the bytes read from stdin are run through [`Decoder`], which
+//! knows the sequences of xterm-compatible terminals and the key sequences
+//! of the terminfo entry of `$TERM`. Sequences may be split across reads. A
+//! lone ESC is ambiguous, since every sequence starts with one: it is taken
+//! as the Escape key when nothing follows it within [`ESC_TIMEOUT`]. Resizes
+//! are reported on SIGWINCH.
+//!
+//! On Windows the console input records are read instead, so there is
+//! nothing to decode.
+//!
+//! Mouse reporting and bracketed paste are terminal state that outlives the
+//! process. They are turned off again when the resource is closed, and at
+//! exit if they are still on.
+
+// The decoder is only used on Unix, and by the tests.
+#![cfg_attr(windows, allow(dead_code))]
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use std::sync::Once;
+use std::time::Duration;
+
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceHandleFd;
+use deno_core::ResourceId;
+use serde::Serialize;
+
+use super::tty::console_size_from_fd;
+use super::tty::TtyError;
+use super::tty::UNSTABLE_FEATURE_NAME;
+
+/// How long to wait for the rest of a sequence after an ESC.
+pub const ESC_TIMEOUT: Duration = Duration::from_millis(50);
+#[cfg(unix)]
+const READ_SIZE: usize = 4 * 1024;
+
+const ESC: u8 = 0x1b;
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+const MODE_MOUSE: u8 = 1;
+const MODE_BRACKETED_PASTE: u8 = 2;
+
+/// Normal, button-event and any-event tracking, reported as SGR sequences.
+#[cfg(unix)]
+const MOUSE_ON: &[u8] = b"\x1b[?1000h\x1b[?1002h\x1b[?1003h\x1b[?1006h";
+#[cfg(unix)]
+const MOUSE_OFF: &[u8] = b"\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l";
+#[cfg(unix)]
+const BRACKETED_PASTE_ON: &[u8] = b"\x1b[?2004h";
+#[cfg(unix)]
+const BRACKETED_PASTE_OFF: &[u8] = b"\x1b[?2004l";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TtyEventKind {
+  Key,
+  Mouse,
+  Paste,
+  Resize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MouseAction {
+  Press,
+  Release,
+  Drag,
+  Move,
+  Scroll,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TtyModifiers {
+  pub(super) shift: bool,
+  pub(super) alt: bool,
+  pub(super) ctrl: bool,
+  pub(super) meta: bool,
+}
+
+impl TtyModifiers {
+  pub(super) const NONE: Self = Self {
+    shift: false,
+    alt: false,
+    ctrl: false,
+    meta: false,
+  };
+  const ALT: Self = Self {
+    alt: true,
+    ..Self::NONE
+  };
+  pub(super) const CTRL: Self = Self {
+    ctrl: true,
+    ..Self::NONE
+  };
+  const SHIFT: Self = Self {
+    shift: true,
+    ..Self::NONE
+  };
+
+  /// From the modifier parameter of a CSI sequence, which is 1 plus a
+  /// bitmask.
+  fn from_param(param: u32) -> Self {
+    let bits = param.saturating_sub(1);
+    Self {
+      shift: bits & 1 != 0,
+      alt: bits & 2 != 0,
+      ctrl: bits & 4 != 0,
+      meta: bits & 8 != 0,
+    }
+  }
+
+  fn with_ctrl(self) -> Self {
+    Self { ctrl: true, ..self }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TtyEvent {
+  pub(super) kind: TtyEventKind,
+  /// The key, named like `KeyboardEvent.key`, or the mouse button.
+  pub(super) key: Option<String>,
+  pub(super) modifiers: TtyModifiers,
+  /// The 0-based column and row of a mouse event, or the columns and rows
+  /// of the terminal after a resize.
+  x: Option<u32>,
+  y: Option<u32>,
+  /// The text typed or pasted.
+  pub(super) text: Option<String>,
+  action: Option<MouseAction>,
+}
+
+impl TtyEvent {
+  pub(super) fn key(key: impl Into<String>, modifiers: TtyModifiers) -> Self {
+    Self {
+      kind: TtyEventKind::Key,
+      key: Some(key.into()),
+      modifiers,
+      x: None,
+      y: None,
+      text: None,
+      action: None,
+    }
+  }
+
+  pub(super) fn char(c: char, mut modifiers: TtyModifiers) -> Self {
+    modifiers.shift |= c.is_uppercase();
+    let text = (!modifiers.ctrl && !modifiers.alt).then(|| c.to_string());
+    Self {
+      text,
+      ..Self::key(c, modifiers)
+    }
+  }
+
+  fn mouse(
+    button: Option<&str>,
+    action: MouseAction,
+    x: u32,
+    y: u32,
+    modifiers: TtyModifiers,
+  ) -> Self {
+    Self {
+      kind: TtyEventKind::Mouse,
+      key: button.map(str::to_string),
+      modifiers,
+      x: Some(x),
+      y: Some(y),
+      text: None,
+      action: Some(action),
+    }
+  }
+
+  pub(super) fn paste(text: &[u8]) -> Self {
+    Self {
+      kind: TtyEventKind::Paste,
+      key: None,
+      modifiers: TtyModifiers::NONE,
+      x: None,
+      y: None,
+      text: Some(String::from_utf8_lossy(text).into_owned()),
+      action: None,
+    }
+  }
+
+  fn resize(columns: u32, rows: u32) -> Self {
+    Self {
+      kind: TtyEventKind::Resize,
+      key: None,
+      modifiers: TtyModifiers::NONE,
+      x: Some(columns),
+      y: Some(rows),
+      text: None,
+      action: None,
+    }
+  }
+}
+
+/// A key sequence of a terminfo entry, and the key and modifiers it stands
+/// for.
+pub type KeySequence = (Vec<u8>, &'static str, TtyModifiers);
+
+enum Parsed {
+  Event(TtyEvent, usize),
+  PasteStart(usize),
+  /// An unknown or malformed sequence of this length.
+  Skip(usize),
+  Incomplete,
+}
+
+/// Decodes the raw input of a terminal into events.
+#[derive(Default)]
+pub struct Decoder {
+  /// Input that doesn't form a complete event yet.
+  buf: Vec<u8>,
+  /// The text pasted so far, while in a bracketed paste.
+  paste: Option<Vec<u8>>,
+  /// Key sequences from terminfo, longest first.
+  keys: Vec<KeySequence>,
+}
+
+impl Decoder {
+  pub fn new(mut keys: Vec<KeySequence>) -> Self {
+    keys.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    Self {
+      keys,
+      ..Default::default()
+    }
+  }
+
+  /// Decodes `bytes`, and adds the events completed by them to `out`.
+  pub fn feed(&mut self, bytes: &[u8], out: &mut Vec<TtyEvent>) {
+    let Self { buf, paste, keys } = self;
+    buf.extend_from_slice(bytes);
+    let mut pos = 0;
+    loop {
+      if let Some(text) = paste {
+        let rest = &buf[pos..];
+        match find(rest, PASTE_END) {
+          Some(i) => {
+            text.extend_from_slice(&rest[..i]);
+            out.push(TtyEvent::paste(text));
+            *paste = None;
+            pos += i + PASTE_END.len();
+            continue;
+          }
+          None => {
+            // The end marker may be split across reads.
+            let keep = (1..PASTE_END.len())
+              .rev()
+              .find(|&n| rest.ends_with(&PASTE_END[..n]))
+              .unwrap_or(0);
+            text.extend_from_slice(&rest[..rest.len() - keep]);
+            pos = buf.len() - keep;
+            break;
+          }
+        }
+      }
+      if pos == buf.len() {
+        break;
+      }
+      match parse(&buf[pos..], keys) {
+        Parsed::Event(event, len) => {
+          out.push(event);
+          pos += len;
+        }
+        Parsed::PasteStart(len) => {
+          *paste = Some(Vec::new());
+          pos += len;
+        }
+        Parsed::Skip(len) => pos += len,
+        Parsed::Incomplete => break,
+      }
+    }
+    buf.drain(..pos);
+  }
+
+  /// Whether an ESC waits for the rest of its sequence.
+  pub fn has_pending_escape(&self) -> bool {
+    self.paste.is_none() && self.buf.first() == Some(&ESC)
+  }
+
+  /// Takes a pending ESC as the Escape key, or as Alt with the byte after
+  /// it, since the rest of the sequence didn't arrive in time.
+  pub fn flush_escape(&mut self, out: &mut Vec<TtyEvent>) {
+    if !self.has_pending_escape() {
+      return;
+    }
+    let rest = self.buf.split_off(1);
+    self.buf.clear();
+    match rest.first() {
+      Some(&c @ (b'[' | b'O')) => {
+        out.push(TtyEvent::char(c as char, TtyModifiers::ALT));
+        self.feed(&rest[1..], out);
+      }
+      _ => {
+        out.push(TtyEvent::key("Escape", TtyModifiers::NONE));
+        self.feed(&rest, out);
+      }
+    }
+  }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack
+    .windows(needle.len())
+    .position(|window| window == needle)
+}
+
+fn parse(bytes: &[u8], keys: &[KeySequence]) -> Parsed {
+  if bytes[0] != ESC {
+    return parse_char(bytes, TtyModifiers::NONE);
+  }
+  // terminfo takes precedence over the built-in sequences
+  if let Some((seq, key, modifiers)) =
+    keys.iter().find(|(seq, ..)| bytes.starts_with(seq))
+  {
+    return Parsed::Event(TtyEvent::key(*key, *modifiers), seq.len());
+  }
+  if keys
+    .iter()
+    .any(|(seq, ..)| seq.len() > bytes.len() && seq.starts_with(bytes))
+  {
+    return Parsed::Incomplete;
+  }
+  match bytes.get(1) {
+    None => Parsed::Incomplete,
+    Some(b'[') => parse_csi(bytes),
+    Some(b'O') => match bytes.get(2) {
+      None => Parsed::Incomplete,
+      Some(&c) => match ss3_key(c) {
+        Some(key) => Parsed::Event(TtyEvent::key(key, TtyModifiers::NONE), 3),
+        None => Parsed::Skip(3),
+      },
+    },
+    Some(&ESC) => Parsed::Event(TtyEvent::key("Escape", TtyModifiers::NONE), 1),
+    Some(_) => match parse_char(&bytes[1..], TtyModifiers::ALT) {
+      Parsed::Event(event, len) => Parsed::Event(event, len + 1),
+      Parsed::Skip(len) => Parsed::Skip(len + 1),
+      parsed => parsed,
+    },
+  }
+}
+
+/// A character or control key, which isn't ESC.
+fn parse_char(bytes: &[u8], modifiers: TtyModifiers) -> Parsed {
+  let b = bytes[0];
+  let event = match b {
+    b'\r' | b'\n' => TtyEvent::key("Enter", modifiers),
+    b'\t' => TtyEvent::key("Tab", modifiers),
+    0x08 | 0x7f => TtyEvent::key("Backspace", modifiers),
+    0x00 => TtyEvent::char(' ', modifiers.with_ctrl()),
+    0x01..=0x1a => {
+      TtyEvent::char((b - 1 + b'a') as char, modifiers.with_ctrl())
+    }
+    // Ctrl+\ ] ^ _
+    0x1c..=0x1f => TtyEvent::char((b + 0x40) as char, modifiers.with_ctrl()),
+    _ => {
+      let len = match b {
+        0x20..=0x7e => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Parsed::Skip(1),
+      };
+      if bytes.len() < len {
+        if bytes[1..].iter().all(|b| b & 0xc0 == 0x80) {
+          return Parsed::Incomplete;
+        }
+        return Parsed::Skip(1);
+      }
+      match std::str::from_utf8(&bytes[..len]) {
+        Ok(s) => {
+          let c = s.chars().next().unwrap();
+          return Parsed::Event(TtyEvent::char(c, modifiers), len);
+        }
+        Err(_) => return Parsed::Skip(1),
+      }
+    }
+  };
+  Parsed::Event(event, 1)
+}
+
+fn ss3_key(c: u8) -> Option<&'static str> {
+  Some(match c {
+    b'A' => "ArrowUp",
+    b'B' => "ArrowDown",
+    b'C' => "ArrowRight",
+    b'D' => "ArrowLeft",
+    b'H' => "Home",
+    b'F' => "End",
+    b'P' => "F1",
+    b'Q' => "F2",
+    b'R' => "F3",
+    b'S' => "F4",
+    b'M' => "Enter",
+    _ => return None,
+  })
+}
+
+/// A sequence starting with `ESC [`.
+fn parse_csi(bytes: &[u8]) -> Parsed {
+  // X10 mouse reports carry three raw bytes after `ESC [ M`.
+  if bytes.get(2) == Some(&b'M') {
+    if bytes.len() < 6 {
+      return Parsed::Incomplete;
+    }
+    let cb = u32::from(bytes[3]).saturating_sub(32);
+    let x = u32::from(bytes[4]).saturating_sub(33);
+    let y = u32::from(bytes[5]).saturating_sub(33);
+    // X10 doesn't say which button was released.
+    let release = cb & 0b110_0011 == 3;
+    return Parsed::Event(mouse_event(cb, x, y, release), 6);
+  }
+  let mut end = 2;
+  loop {
+    match bytes.get(end) {
+      None => return Parsed::Incomplete,
+      Some(0x20..=0x3f) => end += 1,
+      Some(0x40..=0x7e) => break,
+      Some(_) => return Parsed::Skip(end),
+    }
+  }
+  let params = &bytes[2..end];
+  let len = end + 1;
+  if bytes[..len] == *PASTE_START {
+    return Parsed::PasteStart(len);
+  }
+  match csi_event(params, bytes[end]) {
+    Some(event) => Parsed::Event(event, len),
+    None => Parsed::Skip(len),
+  }
+}
+
+fn csi_event(params: &[u8], final_byte: u8) -> Option<TtyEvent> {
+  if let Some(params) = params.strip_prefix(b"<") {
+    let params = parse_params(params)?;
+    let [cb, x, y] = params[..] else {
+      return None;
+    };
+    let release = match final_byte {
+      b'M' => false,
+      b'm' => true,
+      _ => return None,
+    };
+    let (x, y) = (x.saturating_sub(1), y.saturating_sub(1));
+    return Some(mouse_event(cb, x, y, release));
+  }
+  let params = parse_params(params)?;
+  let first = params.first().copied().unwrap_or(1);
+  let modifiers = TtyModifiers::from_param(params.get(1).copied().unwrap_or(1));
+  let key = match final_byte {
+    b'A' => "ArrowUp",
+    b'B' => "ArrowDown",
+    b'C' => "ArrowRight",
+    b'D' => "ArrowLeft",
+    b'H' => "Home",
+    b'F' => "End",
+    // Cursor position reports end in `R` too, but never start with row 1
+    // and a modifier parameter.
+    b'P' | b'Q' | b'R' | b'S' if first == 1 => {
+      return Some(TtyEvent::key(
+        format!("F{}", final_byte - b'P' + 1),
+        modifiers,
+      ));
+    }
+    b'Z' => {
+      let modifiers = TtyModifiers {
+        shift: true,
+        ..modifiers
+      };
+      return Some(TtyEvent::key("Tab", modifiers));
+    }
+    b'~' => return tilde_key(first).map(|key| TtyEvent::key(key, modifiers)),
+    b'u' => {
+      return Some(match first {
+        9 => TtyEvent::key("Tab", modifiers),
+        13 => TtyEvent::key("Enter", modifiers),
+        27 => TtyEvent::key("Escape", modifiers),
+        127 => TtyEvent::key("Backspace", modifiers),
+        code => TtyEvent::char(char::from_u32(code)?, modifiers),
+      })
+    }
+    _ => return None,
+  };
+  Some(TtyEvent::key(key, modifiers))
+}
+
+fn tilde_key(code: u32) -> Option<Cow<'static, str>> {
+  Some(match code {
+    1 | 7 => "Home".into(),
+    2 => "Insert".into(),
+    3 => "Delete".into(),
+    4 | 8 => "End".into(),
+    5 => "PageUp".into(),
+    6 => "PageDown".into(),
+    11..=15 => format!("F{}", code - 10).into(),
+    17..=21 => format!("F{}", code - 11).into(),
+    23 | 24 => format!("F{}", code - 12).into(),
+    _ => return None,
+  })
+}
+
+/// Numeric parameters separated by `;`, where an empty one is 1.
+fn parse_params(params: &[u8]) -> Option<Vec<u32>> {
+  if params.is_empty() {
+    return Some(Vec::new());
+  }
+  params
+    .split(|b| *b == b';')
+    .map(|param| {
+      if param.is_empty() {
+        return Some(1);
+      }
+      std::str::from_utf8(param).ok()?.parse().ok()
+    })
+    .collect()
+}
+
+/// A mouse event from the button byte of an X10 or SGR report.
+fn mouse_event(cb: u32, x: u32, y: u32, release: bool) -> TtyEvent {
+  let modifiers = TtyModifiers {
+    shift: cb & 4 != 0,
+    alt: cb & 8 != 0,
+    ctrl: cb & 16 != 0,
+    meta: false,
+  };
+  let index = (cb & 3) as usize;
+  if cb & 64 != 0 {
+    let button = ["wheelUp", "wheelDown", "wheelLeft", "wheelRight"][index];
+    return TtyEvent::mouse(Some(button), MouseAction::Scroll, x, y, modifiers);
+  }
+  let button = ["left", "middle", "right"].get(index).copied();
+  let action = if release {
+    MouseAction::Release
+  } else if cb & 32 != 0 {
+    if button.is_some() {
+      MouseAction::Drag
+    } else {
+      MouseAction::Move
+    }
+  } else {
+    MouseAction::Press
+  };
+  TtyEvent::mouse(button, action, x, y, modifiers)
+}
+
+/// The terminfo string capabilities of keys, by index. Back tab is Tab with
+/// Shift.
+#[cfg(unix)]
+const TERMINFO_BACK_TAB: usize = 148;
+#[cfg(unix)]
+const TERMINFO_KEYS: &[(usize, &str)] = &[
+  (55, "Backspace"),
+  (59, "Delete"),
+  (61, "ArrowDown"),
+  (66, "F1"),
+  (67, "F10"),
+  (68, "F2"),
+  (69, "F3"),
+  (70, "F4"),
+  (71, "F5"),
+  (72, "F6"),
+  (73, "F7"),
+  (74, "F8"),
+  (75, "F9"),
+  (76, "Home"),
+  (77, "Insert"),
+  (79, "ArrowLeft"),
+  (81, "PageDown"),
+  (82, "PageUp"),
+  (83, "ArrowRight"),
+  (87, "ArrowUp"),
+  (TERMINFO_BACK_TAB, "Tab"),
+  (164, "End"),
+  (216, "F11"),
+  (217, "F12"),
+];
+
+/// The key sequences of the terminfo entry of `$TERM`, or none if it can't
+/// be found.
+#[cfg(unix)]
+fn terminfo_keys() -> Vec<KeySequence> {
+  let Some(term) = std::env::var_os("TERM") else {
+    return Vec::new();
+  };
+  let Some(first) = term.to_str().and_then(|term| term.chars().next()) else {
+    return Vec::new();
+  };
+  let mut dirs = Vec::new();
+  if let Some(dir) = std::env::var_os("TERMINFO") {
+    dirs.push(std::path::PathBuf::from(dir));
+  }
+  if let Some(home) = std::env::var_os("HOME") {
+    dirs.push(std::path::Path::new(&home).join(".terminfo"));
+  }
+  if let Some(list) = std::env::var_os("TERMINFO_DIRS") {
+    for dir in std::env::split_paths(&list) {
+      if dir.as_os_str().is_empty() {
+        dirs.push("/usr/share/terminfo".into());
+      } else {
+        dirs.push(dir);
+      }
+    }
+  }
+  for dir in ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"] {
+    dirs.push(dir.into());
+  }
+  for dir in dirs {
+    // macOS uses the hex code of the first letter as the directory name
+    for sub in [first.to_string(), format!("{:x}", first as u32)] {
+      // Not user data: the entry only tells how the terminal encodes keys.
+      #[allow(clippy::disallowed_methods)]
+      if let Ok(bytes) = std::fs::read(dir.join(sub).join(&term)) {
+        return parse_terminfo(&bytes).unwrap_or_default();
+      }
+    }
+  }
+  Vec::new()
+}
+
+/// Reads the key sequences that start with ESC from a compiled terminfo
+/// entry, as described in term(5).
+#[cfg(unix)]
+fn parse_terminfo(bytes: &[u8]) -> Option<Vec<KeySequence>> {
+  let read_i16 = |pos: usize| -> Option<i16> {
+    Some(i16::from_le_bytes(
+      bytes.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+  };
+  let number_size = match read_i16(0)? {
+    0o432 => 2,
+    0o1036 => 4,
+    _ => return None,
+  };
+  let header = (1..6)
+    .map(|i| usize::try_from(read_i16(i * 2)?).ok())
+    .collect::<Option<Vec<_>>>()?;
+  let [names_size, bools_count, numbers_count, strings_count, table_size] =
+    header[..]
+  else {
+    return None;
+  };
+  let mut pos = 12 + names_size + bools_count;
+  // numbers start on an even byte
+  pos += pos % 2;
+  pos += numbers_count * number_size;
+  let offsets = pos;
+  let table = bytes.get(offsets + strings_count * 2..)?;
+  let table = table.get(..table_size)?;
+
+  let mut keys = Vec::new();
+  for &(index, key) in TERMINFO_KEYS {
+    if index >= strings_count {
+      continue;
+    }
+    let Ok(offset) = usize::try_from(read_i16(offsets + index * 2)?) else {
+      // absent or cancelled
+      continue;
+    };
+    let Some(value) = table.get(offset..) else {
+      continue;
+    };
+    let end = value.iter().position(|b| *b == 0)?;
+    let seq = &value[..end];
+    if seq.len() > 1 && seq[0] == ESC {
+      let modifiers = if index == TERMINFO_BACK_TAB {
+        TtyModifiers::SHIFT
+      } else {
+        TtyModifiers::NONE
+      };
+      keys.push((seq.to_vec(), key, modifiers));
+    }
+  }
+  Some(keys)
+}
+
+/// The modes turned on by any resource, to turn off at exit.
+static ENABLED_MODES: AtomicU8 = AtomicU8::new(0);
+static RESET_AT_EXIT: Once = Once::new();
+
+extern "C" fn reset_modes_at_exit() {
+  let modes = ENABLED_MODES.swap(0, Ordering::SeqCst);
+  // Nothing to do about errors this late.
+  let _ = set_modes(modes, false);
+  #[cfg(windows)]
+  windows::restore_console_mode();
+}
+
+#[cfg(unix)]
+fn set_modes(modes: u8, enabled: bool) -> std::io::Result<()> {
+  let mut seq = Vec::new();
+  if modes & MODE_MOUSE != 0 {
+    seq.extend_from_slice(if enabled { MOUSE_ON } else { MOUSE_OFF });
+  }
+  if modes & MODE_BRACKETED_PASTE != 0 {
+    seq.extend_from_slice(if enabled {
+      BRACKETED_PASTE_ON
+    } else {
+      BRACKETED_PASTE_OFF
+    });
+  }
+  // Written to the file descriptor directly, since this may run in an
+  // `atexit` handler.
+  let mut written = 0;
+  while written < seq.len() {
+    // SAFETY: the pointer and length are those of the unwritten part of
+    // `seq`.
+    let n = unsafe {
+      libc::write(
+        libc::STDOUT_FILENO,
+        seq[written..].as_ptr() as *const libc::c_void,
+        seq.len() - written,
+      )
+    };
+    if n < 0 {
+      let err = std::io::Error::last_os_error();
+      if err.kind() == std::io::ErrorKind::Interrupted {
+        continue;
+      }
+      return Err(err);
+    }
+    written += n as usize;
+  }
+  Ok(())
+}
+
+#[cfg(windows)]
+fn set_modes(modes: u8, enabled: bool) -> std::io::Result<()> {
+  if modes & MODE_MOUSE != 0 {
+    windows::set_mouse_input(enabled)?;
+  }
+  Ok(())
+}
+
+struct ReadState {
+  #[cfg(unix)]
+  decoder: Decoder,
+  /// A read that was interrupted by the ESC timeout or a resize, and is
+  /// resumed by the next read so that no input is lost.
+  #[cfg(unix)]
+  pending_read: Option<deno_core::AsyncResult<deno_core::BufView>>,
+  #[cfg(unix)]
+  resize: tokio::signal::unix::Signal,
+}
+
+pub struct TtyEventsResource {
+  input: Rc<dyn Resource>,
+  fd: ResourceHandleFd,
+  state: AsyncRefCell<ReadState>,
+  /// The modes turned on through this resource.
+  modes: Cell<u8>,
+  cancel: CancelHandle,
+}
+
+impl Resource for TtyEventsResource {
+  fn name(&self) -> Cow<str> {
+    "ttyEvents".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl Drop for TtyEventsResource {
+  fn drop(&mut self) {
+    let modes = self.modes.get();
+    ENABLED_MODES.fetch_and(!modes, Ordering::SeqCst);
+    let _ = set_modes(modes, false);
+    #[cfg(windows)]
+    windows::restore_console_mode();
+  }
+}
+
+impl TtyEventsResource {
+  fn set_mode(&self, mode: u8, enabled: bool) -> Result<(), TtyError> {
+    #[cfg(windows)]
+    if mode == MODE_BRACKETED_PASTE {
+      return Err(TtyError::Other(deno_core::error::not_supported()));
+    }
+    RESET_AT_EXIT.call_once(|| {
+      // SAFETY: registers a function without arguments, which doesn't
+      // unwind.
+      unsafe { libc::atexit(reset_modes_at_exit) };
+    });
+    set_modes(mode, enabled)?;
+    if enabled {
+      self.modes.set(self.modes.get() | mode);
+      ENABLED_MODES.fetch_or(mode, Ordering::SeqCst);
+    } else {
+      self.modes.set(self.modes.get() & !mode);
+      ENABLED_MODES.fetch_and(!mode, Ordering::SeqCst);
+    }
+    Ok(())
+  }
+
+  /// Starts reading events from `input`, which should be a terminal in raw
+  /// mode.
+  pub(super) fn open(input: Rc<dyn Resource>) -> Result<Self, TtyError> {
+    let fd = input
+      .clone()
+      .backing_fd()
+      .filter(|fd| is_terminal(*fd))
+      .ok_or(TtyError::NotATerminal)?;
+
+    #[cfg(unix)]
+    let read_state = ReadState {
+      decoder: Decoder::new(terminfo_keys()),
+      pending_read: None,
+      resize: tokio::signal::unix::signal(
+        tokio::signal::unix::SignalKind::window_change(),
+      )?,
+    };
+    #[cfg(windows)]
+    let read_state = {
+      windows::prepare_console_mode(fd)?;
+      ReadState {}
+    };
+
+    Ok(Self {
+      input,
+      fd,
+      state: AsyncRefCell::new(read_state),
+      modes: Cell::new(0),
+      cancel: CancelHandle::default(),
+    })
+  }
+
+  pub(super) fn fd(&self) -> ResourceHandleFd {
+    self.fd
+  }
+
+  #[cfg(unix)]
+  pub(super) async fn read_events(
+    self: Rc<Self>,
+  ) -> Result<Vec<TtyEvent>, TtyError> {
+    let mut state = RcRef::map(&self, |r| &r.state).borrow_mut().await;
+    let ReadState {
+      decoder,
+      pending_read,
+      resize,
+    } = &mut *state;
+    let mut events = Vec::new();
+    while events.is_empty() {
+      let mut read = pending_read
+        .take()
+        .unwrap_or_else(|| self.input.clone().read(READ_SIZE));
+      let has_pending_escape = decoder.has_pending_escape();
+      let timeout = async {
+        if has_pending_escape {
+          tokio::time::sleep(ESC_TIMEOUT).await
+        } else {
+          std::future::pending().await
+        }
+      };
+      tokio::select! {
+        chunk = &mut read => {
+          let chunk = chunk.map_err(TtyError::Other)?;
+          if chunk.is_empty() {
+            decoder.flush_escape(&mut events);
+            break;
+          }
+          decoder.feed(&chunk, &mut events);
+        }
+        Some(()) = resize.recv() => {
+          *pending_read = Some(read);
+          let size = console_size_from_fd(self.fd)?;
+          events.push(TtyEvent::resize(size.cols, size.rows));
+        }
+        _ = timeout => {
+          *pending_read = Some(read);
+          decoder.flush_escape(&mut events);
+        }
+      }
+    }
+    Ok(events)
+  }
+
+  #[cfg(windows)]
+  pub(super) async fn read_events(
+    self: Rc<Self>,
+  ) -> Result<Vec<TtyEvent>, TtyError> {
+    let _state = RcRef::map(&self, |r| &r.state).borrow_mut().await;
+    let handle = self.fd as usize;
+    loop {
+      let events =
+        deno_core::unsync::spawn_blocking(move || windows::read_events(handle))
+          .await
+          .unwrap()?;
+      if !events.is_empty() {
+        return Ok(events);
+      }
+    }
+  }
+}
+
+fn is_terminal(fd: ResourceHandleFd) -> bool {
+  use std::io::IsTerminal;
+  #[cfg(unix)]
+  let fd = {
+    // SAFETY: the descriptor belongs to a resource that is alive.
+    unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) }
+  };
+  #[cfg(windows)]
+  let fd = {
+    // SAFETY: the handle belongs to a resource that is alive.
+    unsafe { std::os::windows::io::BorrowedHandle::borrow_raw(fd) }
+  };
+  fd.is_terminal()
+}
+
+/// Starts reading events from the terminal `rid`, which should be in raw
+/// mode.
+#[op2(fast)]
+#[smi]
+pub fn op_tty_events_open(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<ResourceId, TtyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.openTtyEvents");
+  let input = state
+    .resource_table
+    .get_any(rid)
+    .map_err(TtyError::Resource)?;
+  let events = TtyEventsResource::open(input)?;
+  Ok(state.resource_table.add(events))
+}
+
+/// Resolves to the next events, or to `null` when the input ended or the
+/// resource was closed.
+#[op2(async)]
+#[serde]
+pub async fn op_tty_read_events(
+  state: Rc<std::cell::RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<Vec<TtyEvent>>, TtyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<TtyEventsResource>(rid)
+    .map_err(TtyError::Resource)?;
+  let cancel = RcRef::map(&resource, |r| &r.cancel);
+  match resource.clone().read_events().or_cancel(cancel).await {
+    Ok(Ok(events)) if !events.is_empty() => Ok(Some(events)),
+    Ok(Ok(_)) | Err(_) => Ok(None),
+    Ok(Err(err)) => Err(err),
+  }
+}
+
+#[op2(fast)]
+pub fn op_tty_set_mouse_reporting(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  enabled: bool,
+) -> Result<(), TtyError> {
+  let resource = state
+    .resource_table
+    .get::<TtyEventsResource>(rid)
+    .map_err(TtyError::Resource)?;
+  resource.set_mode(MODE_MOUSE, enabled)
+}
+
+#[op2(fast)]
+pub fn op_tty_set_bracketed_paste(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  enabled: bool,
+) -> Result<(), TtyError> {
+  let resource = state
+    .resource_table
+    .get::<TtyEventsResource>(rid)
+    .map_err(TtyError::Resource)?;
+  resource.set_mode(MODE_BRACKETED_PASTE, enabled)
+}
+
+/// Console input records, which describe keys and mouse events directly.
+#[cfg(windows)]
+pub(super) mod windows {
+  use std::io::Error;
+
+  use deno_core::parking_lot::Mutex;
+  use winapi::shared::minwindef::DWORD;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::um::consoleapi;
+  use winapi::um::processenv::GetStdHandle;
+  use winapi::um::winbase::STD_INPUT_HANDLE;
+  use winapi::um::wincon;
+  use winapi::um::winnt::HANDLE;
+  use winapi::um::winuser;
+
+  use super::MouseAction;
+  use super::TtyEvent;
+  use super::TtyModifiers;
+
+  /// The console mode before the first resource changed it.
+  static ORIGINAL_MODE: Mutex<Option<DWORD>> = Mutex::new(None);
+
+  fn get_mode(handle: HANDLE) -> Result<DWORD, Error> {
+    let mut mode: DWORD = 0;
+    // SAFETY: winapi call
+    if unsafe { consoleapi::GetConsoleMode(handle, &mut mode) } == FALSE {
+      return Err(Error::last_os_error());
+    }
+    Ok(mode)
+  }
+
+  fn set_mode(handle: HANDLE, mode: DWORD) -> Result<(), Error> {
+    // SAFETY: winapi call
+    if unsafe { consoleapi::SetConsoleMode(handle, mode) } == FALSE {
+      return Err(Error::last_os_error());
+    }
+    Ok(())
+  }
+
+  /// Reports resizes, and keys as key codes instead of VT sequences.
+  pub fn prepare_console_mode(handle: HANDLE) -> Result<(), Error> {
+    let mode = get_mode(handle)?;
+    ORIGINAL_MODE.lock().get_or_insert(mode);
+    set_mode(
+      handle,
+      mode & !wincon::ENABLE_VIRTUAL_TERMINAL_INPUT
+        | wincon::ENABLE_WINDOW_INPUT,
+    )
+  }
+
+  pub fn set_mouse_input(enabled: bool) -> Result<(), Error> {
+    // SAFETY: winapi call
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    let mode = get_mode(handle)?;
+    // Quick edit mode would take the mouse for selecting text.
+    let mode = if enabled {
+      mode & !wincon::ENABLE_QUICK_EDIT_MODE
+        | wincon::ENABLE_MOUSE_INPUT
+        | wincon::ENABLE_EXTENDED_FLAGS
+    } else {
+      mode & !wincon::ENABLE_MOUSE_INPUT
+    };
+    set_mode(handle, mode)
+  }
+
+  /// Whether Ctrl+C raises a signal, instead of being read as a key.
+  pub fn set_processed_input(enabled: bool) -> Result<(), Error> {
+    // SAFETY: winapi call
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    let mode = get_mode(handle)?;
+    let mode = if enabled {
+      mode | wincon::ENABLE_PROCESSED_INPUT
+    } else {
+      mode & !wincon::ENABLE_PROCESSED_INPUT
+    };
+    set_mode(handle, mode)
+  }
+
+  pub fn restore_console_mode() {
+    if let Some(mode) = ORIGINAL_MODE.lock().take() {
+      // SAFETY: winapi call
+      let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+      let _ = set_mode(handle, mode | wincon::ENABLE_EXTENDED_FLAGS);
+    }
+  }
+
+  /// Blocks until input records are available and decodes them.
+  pub fn read_events(handle: usize) -> Result<Vec<TtyEvent>, Error> {
+    let handle = handle as HANDLE;
+    // SAFETY: INPUT_RECORD is plain data.
+    let mut records: [wincon::INPUT_RECORD; 32] = unsafe { std::mem::zeroed() };
+    let mut count: DWORD = 0;
+    // SAFETY: winapi call with a buffer of the given length.
+    if unsafe {
+      wincon::ReadConsoleInputW(
+        handle,
+        records.as_mut_ptr(),
+        records.len() as DWORD,
+        &mut count,
+      )
+    } == FALSE
+    {
+      return Err(Error::last_os_error());
+    }
+    Ok(
+      records[..count as usize]
+        .iter()
+        .filter_map(record_event)
+        .collect(),
+    )
+  }
+
+  fn modifiers(state: DWORD) -> TtyModifiers {
+    TtyModifiers {
+      shift: state & wincon::SHIFT_PRESSED != 0,
+      alt: state & (wincon::LEFT_ALT_PRESSED | wincon::RIGHT_ALT_PRESSED) != 0,
+      ctrl: state & (wincon::LEFT_CTRL_PRESSED | wincon::RIGHT_CTRL_PRESSED)
+        != 0,
+      meta: false,
+    }
+  }
+
+  fn record_event(record: &wincon::INPUT_RECORD) -> Option<TtyEvent> {
+    match record.EventType {
+      wincon::KEY_EVENT => {
+        // SAFETY: the event type says which union field is set.
+        let event = unsafe { record.Event.KeyEvent() };
+        if event.bKeyDown == FALSE {
+          return None;
+        }
+        let modifiers = modifiers(event.dwControlKeyState);
+        let key = match event.wVirtualKeyCode as i32 {
+          winuser::VK_UP => "ArrowUp",
+          winuser::VK_DOWN => "ArrowDown",
+          winuser::VK_LEFT => "ArrowLeft",
+          winuser::VK_RIGHT => "ArrowRight",
+          winuser::VK_HOME => "Home",
+          winuser::VK_END => "End",
+          winuser::VK_PRIOR => "PageUp",
+          winuser::VK_NEXT => "PageDown",
+          winuser::VK_INSERT => "Insert",
+          winuser::VK_DELETE => "Delete",
+          winuser::VK_RETURN => "Enter",
+          winuser::VK_TAB => "Tab",
+          winuser::VK_BACK => "Backspace",
+          winuser::VK_ESCAPE => "Escape",
+          vk @ winuser::VK_F1..=winuser::VK_F12 => {
+            let key = format!("F{}", vk - winuser::VK_F1 + 1);
+            return Some(TtyEvent::key(key, modifiers));
+          }
+          _ => {
+            // SAFETY: the key event was read with ReadConsoleInputW.
+            let unit = unsafe { *event.uChar.UnicodeChar() };
+            // Surrogate pairs arrive as two events and aren't decoded.
+            let c = char::from_u32(u32::from(unit)).filter(|c| *c != '\0')?;
+            // Ctrl+letter arrives as a control character
+            let c = match c {
+              '\x01'..='\x1a' => (c as u8 - 1 + b'a') as char,
+              c => c,
+            };
+            return Some(TtyEvent::char(c, modifiers));
+          }
+        };
+        Some(TtyEvent::key(key, modifiers))
+      }
+      wincon::MOUSE_EVENT => {
+        // SAFETY: the event type says which union field is set.
+        let event = unsafe { record.Event.MouseEvent() };
+        let modifiers = modifiers(event.dwControlKeyState);
+        let x = event.dwMousePosition.X.max(0) as u32;
+        let y = event.dwMousePosition.Y.max(0) as u32;
+        let buttons = event.dwButtonState;
+        if event.dwEventFlags & (wincon::MOUSE_WHEELED | wincon::MOUSE_HWHEELED)
+          != 0
+        {
+          // The high word is the signed distance.
+          let forward = (buttons as i32) > 0;
+          let button =
+            match (event.dwEventFlags & wincon::MOUSE_WHEELED != 0, forward) {
+              (true, true) => "wheelUp",
+              (true, false) => "wheelDown",
+              (false, true) => "wheelRight",
+              (false, false) => "wheelLeft",
+            };
+          return Some(TtyEvent::mouse(
+            Some(button),
+            MouseAction::Scroll,
+            x,
+            y,
+            modifiers,
+          ));
+        }
+        let button = if buttons & wincon::FROM_LEFT_1ST_BUTTON_PRESSED != 0 {
+          Some("left")
+        } else if buttons & wincon::RIGHTMOST_BUTTON_PRESSED != 0 {
+          Some("right")
+        } else if buttons & wincon::FROM_LEFT_2ND_BUTTON_PRESSED != 0 {
+          Some("middle")
+        } else {
+          None
+        };
+        let action =
+          match (event.dwEventFlags & wincon::MOUSE_MOVED != 0, button) {
+            (true, Some(_)) => MouseAction::Drag,
+            (true, None) => MouseAction::Move,
+            (false, Some(_)) => MouseAction::Press,
+            (false, None) => MouseAction::Release,
+          };
+        Some(TtyEvent::mouse(button, action, x, y, modifiers))
+      }
+      wincon::WINDOW_BUFFER_SIZE_EVENT => {
+        // SAFETY: the event type says which union field is set.
+        let event = unsafe { record.Event.WindowBufferSizeEvent() };
+        Some(TtyEvent::resize(
+          event.dwSize.X.max(0) as u32,
+          event.dwSize.Y.max(0) as u32,
+        ))
+      }
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode(chunks: &[&[u8]]) -> Vec<TtyEvent> {
+    let mut decoder = Decoder::default();
+    let mut events = Vec::new();
+    for chunk in chunks {
+      decoder.feed(chunk, &mut events);
+    }
+    decoder.flush_escape(&mut events);
+    events
+  }
+
+  fn key(key: &str, modifiers: TtyModifiers) -> TtyEvent {
+    TtyEvent::key(key, modifiers)
+  }
+
+  fn ctrl_shift() -> TtyModifiers {
+    TtyModifiers {
+      shift: true,
+      ctrl: true,
+      ..TtyModifiers::NONE
+    }
+  }
+
+  #[test]
+  fn test_decode_keys() {
+    let events = decode(&[
+      b"a\xc3\xa9Z\r\t\x7f\x03\x1b[A\x1bOP\x1b[1;6C\x1b[3~\x1b[24;5~\x1b[Z",
+    ]);
+    assert_eq!(
+      events,
+      [
+        TtyEvent::char('a', TtyModifiers::NONE),
+        TtyEvent::char('é', TtyModifiers::NONE),
+        TtyEvent::char('Z', TtyModifiers::NONE),
+        key("Enter", TtyModifiers::NONE),
+        key("Tab", TtyModifiers::NONE),
+        key("Backspace", TtyModifiers::NONE),
+        TtyEvent::char('c', TtyModifiers::CTRL),
+        key("ArrowUp", TtyModifiers::NONE),
+        key("F1", TtyModifiers::NONE),
+        key("ArrowRight", ctrl_shift()),
+        key("Delete", TtyModifiers::NONE),
+        key("F12", TtyModifiers::CTRL),
+        key(
+          "Tab",
+          TtyModifiers {
+            shift: true,
+            ..TtyModifiers::NONE
+          }
+        ),
+      ]
+    );
+    assert!(events[2].modifiers.shift);
+    assert_eq!(events[0].text.as_deref(), Some("a"));
+    assert_eq!(events[6].text, None);
+  }
+
+  #[test]
+  fn test_decode_split_sequences() {
+    let input: &[u8] =
+      b"\x1b[1;5D\xe2\x82\xac\x1b[<0;10;5M\x1b[200~a\x1b[201x\x1b[201~";
+    let expected = decode(&[input]);
+    assert_eq!(expected.len(), 4);
+    assert_eq!(expected[0], key("ArrowLeft", TtyModifiers::CTRL));
+    assert_eq!(expected[1], TtyEvent::char('€', TtyModifiers::NONE));
+    assert_eq!(expected[3], TtyEvent::paste(b"a\x1b[201x"));
+    for i in 1..input.len() {
+      assert_eq!(
+        decode(&[&input[..i], &input[i..]]),
+        expected,
+        "split at {i}"
+      );
+    }
+    let bytes = input.iter().map(std::slice::from_ref).collect::<Vec<_>>();
+    assert_eq!(decode(&bytes), expected);
+  }
+
+  #[test]
+  fn test_decode_ambiguous_escape() {
+    let mut decoder = Decoder::default();
+    let mut events = Vec::new();
+
+    // A lone ESC waits for the timeout.
+    decoder.feed(b"\x1b", &mut events);
+    assert!(events.is_empty());
+    assert!(decoder.has_pending_escape());
+    decoder.flush_escape(&mut events);
+    assert_eq!(events, [key("Escape", TtyModifiers::NONE)]);
+    assert!(!decoder.has_pending_escape());
+
+    // ESC followed by a key in the same read is Alt.
+    events.clear();
+    decoder.feed(b"\x1bx\x1b\x1b[B", &mut events);
+    assert_eq!(
+      events,
+      [
+        TtyEvent::char('x', TtyModifiers::ALT),
+        key("Escape", TtyModifiers::NONE),
+        key("ArrowDown", TtyModifiers::NONE),
+      ]
+    );
+
+    // A sequence cut short by the timeout is Alt with its second byte,
+    // and the rest is taken as typed.
+    events.clear();
+    decoder.feed(b"\x1b[", &mut events);
+    decoder.flush_escape(&mut events);
+    decoder.feed(b"\x1b[1;", &mut events);
+    decoder.flush_escape(&mut events);
+    assert_eq!(
+      events,
+      [
+        TtyEvent::char('[', TtyModifiers::ALT),
+        TtyEvent::char('[', TtyModifiers::ALT),
+        TtyEvent::char('1', TtyModifiers::NONE),
+        TtyEvent::char(';', TtyModifiers::NONE),
+      ]
+    );
+
+    // The timeout doesn't end a paste.
+    events.clear();
+    decoder.feed(b"\x1b[200~abc\x1b", &mut events);
+    assert!(!decoder.has_pending_escape());
+    decoder.feed(b"[201~", &mut events);
+    assert_eq!(events, [TtyEvent::paste(b"abc")]);
+  }
+
+  #[test]
+  fn test_decode_mouse() {
+    let shift = TtyModifiers {
+      shift: true,
+      ..TtyModifiers::NONE
+    };
+    let events = decode(&[
+      b"\x1b[<0;10;5M\x1b[<0;10;5m\x1b[<34;1;1M\x1b[<35;2;3M\x1b[<65;4;4M\x1b[<4;1;1M",
+      b"\x1b[M !\"\x1b[M#!\"",
+    ]);
+    assert_eq!(
+      events,
+      [
+        TtyEvent::mouse(
+          Some("left"),
+          MouseAction::Press,
+          9,
+          4,
+          TtyModifiers::NONE
+        ),
+        TtyEvent::mouse(
+          Some("left"),
+          MouseAction::Release,
+          9,
+          4,
+          TtyModifiers::NONE
+        ),
+        TtyEvent::mouse(
+          Some("right"),
+          MouseAction::Drag,
+          0,
+          0,
+          TtyModifiers::NONE
+        ),
+        TtyEvent::mouse(None, MouseAction::Move, 1, 2, TtyModifiers::NONE),
+        TtyEvent::mouse(
+          Some("wheelDown"),
+          MouseAction::Scroll,
+          3,
+          3,
+          TtyModifiers::NONE
+        ),
+        TtyEvent::mouse(Some("left"), MouseAction::Press, 0, 0, shift),
+        TtyEvent::mouse(
+          Some("left"),
+          MouseAction::Press,
+          0,
+          1,
+          TtyModifiers::NONE
+        ),
+        TtyEvent::mouse(None, MouseAction::Release, 0, 1, TtyModifiers::NONE),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_decode_skips_unknown_sequences() {
+    let events = decode(&[b"\x1b[?1;2c\x1b[5;10R\xffa\x1b[I"]);
+    assert_eq!(events, [TtyEvent::char('a', TtyModifiers::NONE)]);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_terminfo_keys() {
+    // A legacy terminfo entry with no booleans or numbers, whose strings
+    // define kcuu1 (87) as `ESC O A` and kbs (55) as DEL.
+    let mut strings = vec![-1i16; 88];
+    strings[55] = 0;
+    strings[87] = 2;
+    let table = b"\x7f\0\x1bOA\0";
+    let names = b"test\0";
+    let mut entry = Vec::new();
+    for value in [0o432, names.len() as i16, 0, 0, 88, table.len() as i16] {
+      entry.extend_from_slice(&value.to_le_bytes());
+    }
+    entry.extend_from_slice(names);
+    // padding to an even offset
+    entry.push(0);
+    for offset in strings {
+      entry.extend_from_slice(&offset.to_le_bytes());
+    }
+    entry.extend_from_slice(table);
+
+    let keys = parse_terminfo(&entry).unwrap();
+    assert_eq!(keys, [(b"\x1bOA".to_vec(), "ArrowUp", TtyModifiers::NONE)]);
+    assert!(parse_terminfo(b"\x1a\x01").is_none());
+
+    // Sequences from terminfo win over the built-in ones.
+    let mut decoder =
+      Decoder::new(vec![(b"\x1b[11^".to_vec(), "F1", TtyModifiers::NONE)]);
+    let mut events = Vec::new();
+    decoder.feed(b"\x1b[11", &mut events);
+    assert!(events.is_empty());
+    decoder.feed(b"^", &mut events);
+    assert_eq!(events, [key("F1", TtyModifiers::NONE)]);
+  }
+}
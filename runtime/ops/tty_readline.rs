@@ -0,0 +1,898 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Line editing for `Deno.createReadline()`.
+//!
+//! Keys are read with [`TtyEventsResource`], so they are decoded the same way
+//! as for `Deno.openTtyEvents()`. The terminal is put in raw mode when a read
+//! starts, and restored when the line is entered, the read is cancelled or
+//! the readline is closed. The mode saved by `Deno.stdin.setRaw()` is also
+//! restored at exit, in case the process exits in the middle of a line.
+//!
+//! Tab asks JS for completions: the read resolves with the line and the
+//! cursor, and the next read continues the line with the candidates for the
+//! word before the cursor.
+//!
+//! The history is loaded when the readline is created, and written when it
+//! is closed. An entered line replaces an equal older entry, and the oldest
+//! entries are dropped beyond the limit.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceHandleFd;
+use deno_core::ResourceId;
+use deno_permissions::PermissionsContainer;
+use serde::Deserialize;
+use serde::Serialize;
+use unicode_width::UnicodeWidthChar;
+
+use super::tty::console_size_from_fd;
+use super::tty::TtyError;
+use super::tty::UNSTABLE_FEATURE_NAME;
+use super::tty_input::TtyEvent;
+use super::tty_input::TtyEventKind;
+use super::tty_input::TtyEventsResource;
+
+const DEFAULT_MAX_HISTORY: usize = 1000;
+/// Used when the size of the terminal can't be read.
+const DEFAULT_COLUMNS: usize = 80;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadlineOptions {
+  #[serde(default)]
+  prompt: String,
+  history_path: Option<String>,
+  max_history: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ReadlineResult {
+  Line {
+    line: String,
+  },
+  /// Ctrl+C was pressed.
+  Interrupt,
+  /// Ctrl+D was pressed on an empty line, the input ended or the readline
+  /// was closed.
+  Eof,
+  /// Tab was pressed. `cursor` is a UTF-16 offset, like JS string indices.
+  Complete {
+    line: String,
+    cursor: usize,
+  },
+}
+
+#[derive(Debug, Default)]
+struct History {
+  entries: VecDeque<String>,
+  max: usize,
+  /// Whether the entries differ from those that were loaded.
+  changed: bool,
+}
+
+impl History {
+  fn new(max: usize) -> Self {
+    Self {
+      max,
+      ..Default::default()
+    }
+  }
+
+  /// Entries from a history file, one per line, oldest first.
+  fn parse(text: &str, max: usize) -> Self {
+    let mut history = Self::new(max);
+    for line in text.lines() {
+      history.add(line);
+    }
+    history.changed = false;
+    history
+  }
+
+  fn add(&mut self, line: &str) {
+    if self.max == 0 || line.trim().is_empty() {
+      return;
+    }
+    if let Some(index) = self.entries.iter().position(|entry| entry == line) {
+      self.entries.remove(index);
+    }
+    self.entries.push_back(line.to_string());
+    while self.entries.len() > self.max {
+      self.entries.pop_front();
+    }
+    self.changed = true;
+  }
+
+  fn to_text(&self) -> String {
+    let mut text = String::new();
+    for entry in &self.entries {
+      text.push_str(entry);
+      text.push('\n');
+    }
+    text
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+  /// The key isn't bound, or didn't change anything.
+  None,
+  Redraw,
+  ClearScreen,
+  Submit(String),
+  Interrupt,
+  Eof,
+  Complete,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Completion {
+  Done,
+  /// The candidates have to be listed, since there is no longer prefix to
+  /// insert.
+  List,
+}
+
+/// The line being edited.
+#[derive(Debug, Default)]
+struct LineEditor {
+  line: Vec<char>,
+  /// The index of the character after the cursor.
+  cursor: usize,
+  /// The history entry shown, and the line that was edited before browsing
+  /// the history.
+  browsing: Option<(usize, Vec<char>)>,
+}
+
+impl LineEditor {
+  fn text(&self) -> String {
+    self.line.iter().collect()
+  }
+
+  /// The offset of the cursor in UTF-16 code units.
+  fn utf16_cursor(&self) -> usize {
+    self.line[..self.cursor].iter().map(|c| c.len_utf16()).sum()
+  }
+
+  fn set_line(&mut self, line: Vec<char>) {
+    self.cursor = line.len();
+    self.line = line;
+  }
+
+  fn insert(&mut self, text: &str) {
+    // Pasted line breaks don't end the line.
+    let chars = text
+      .chars()
+      .map(|c| if c.is_whitespace() { ' ' } else { c })
+      .filter(|c| !c.is_control())
+      .collect::<Vec<_>>();
+    let len = chars.len();
+    self.line.splice(self.cursor..self.cursor, chars);
+    self.cursor += len;
+  }
+
+  /// The start of the word before the cursor.
+  fn word_start(&self) -> usize {
+    let mut start = self.cursor;
+    while start > 0 && self.line[start - 1].is_whitespace() {
+      start -= 1;
+    }
+    while start > 0 && !self.line[start - 1].is_whitespace() {
+      start -= 1;
+    }
+    start
+  }
+
+  fn delete(&mut self, range: std::ops::Range<usize>) -> Action {
+    if range.is_empty() {
+      return Action::None;
+    }
+    self.cursor = range.start;
+    self.line.drain(range);
+    Action::Redraw
+  }
+
+  fn move_to(&mut self, cursor: usize) -> Action {
+    if cursor == self.cursor {
+      return Action::None;
+    }
+    self.cursor = cursor;
+    Action::Redraw
+  }
+
+  fn history_prev(&mut self, history: &History) -> Action {
+    let index = match &self.browsing {
+      Some((index, _)) => index.checked_sub(1),
+      None => history.entries.len().checked_sub(1),
+    };
+    let Some(index) = index else {
+      return Action::None;
+    };
+    let saved = match self.browsing.take() {
+      Some((_, saved)) => saved,
+      None => std::mem::take(&mut self.line),
+    };
+    self.browsing = Some((index, saved));
+    self.set_line(history.entries[index].chars().collect());
+    Action::Redraw
+  }
+
+  fn history_next(&mut self, history: &History) -> Action {
+    let Some((index, saved)) = self.browsing.take() else {
+      return Action::None;
+    };
+    let index = index + 1;
+    if index < history.entries.len() {
+      self.browsing = Some((index, saved));
+      self.set_line(history.entries[index].chars().collect());
+    } else {
+      self.set_line(saved);
+    }
+    Action::Redraw
+  }
+
+  fn handle(&mut self, event: &TtyEvent, history: &History) -> Action {
+    let text = event.text.as_deref();
+    match event.kind {
+      TtyEventKind::Key => {}
+      TtyEventKind::Paste => {
+        self.insert(text.unwrap_or_default());
+        return Action::Redraw;
+      }
+      TtyEventKind::Mouse | TtyEventKind::Resize => return Action::None,
+    }
+    let key = event.key.as_deref().unwrap_or_default();
+    let modifiers = event.modifiers;
+    if modifiers.ctrl && !modifiers.alt && key.chars().count() == 1 {
+      return match key {
+        "c" => Action::Interrupt,
+        "d" if self.line.is_empty() => Action::Eof,
+        "d" => self.delete(self.cursor..(self.cursor + 1).min(self.line.len())),
+        "a" => self.move_to(0),
+        "e" => self.move_to(self.line.len()),
+        "b" => self.move_to(self.cursor.saturating_sub(1)),
+        "f" => self.move_to((self.cursor + 1).min(self.line.len())),
+        "h" => self.delete(self.cursor.saturating_sub(1)..self.cursor),
+        "u" => self.delete(0..self.cursor),
+        "k" => self.delete(self.cursor..self.line.len()),
+        "w" => self.delete(self.word_start()..self.cursor),
+        "p" => self.history_prev(history),
+        "n" => self.history_next(history),
+        "l" => Action::ClearScreen,
+        _ => Action::None,
+      };
+    }
+    match key {
+      "Enter" => Action::Submit(self.text()),
+      "Tab" if !modifiers.shift => Action::Complete,
+      "Backspace" => self.delete(self.cursor.saturating_sub(1)..self.cursor),
+      "Delete" => {
+        self.delete(self.cursor..(self.cursor + 1).min(self.line.len()))
+      }
+      "ArrowLeft" => self.move_to(self.cursor.saturating_sub(1)),
+      "ArrowRight" => self.move_to((self.cursor + 1).min(self.line.len())),
+      "Home" => self.move_to(0),
+      "End" => self.move_to(self.line.len()),
+      "ArrowUp" => self.history_prev(history),
+      "ArrowDown" => self.history_next(history),
+      _ => match text {
+        Some(text) if !modifiers.ctrl && !modifiers.alt => {
+          self.insert(text);
+          Action::Redraw
+        }
+        _ => Action::None,
+      },
+    }
+  }
+
+  /// Completes the word before the cursor with `candidates`: a single one
+  /// replaces it, several are inserted as far as they agree.
+  fn complete(&mut self, candidates: &[String]) -> Completion {
+    let start = self.word_start();
+    let word = &self.line[start..self.cursor];
+    let replacement: Vec<char> = match candidates {
+      [] => return Completion::Done,
+      [candidate] => candidate.chars().collect(),
+      [first, rest @ ..] => {
+        let mut prefix: Vec<char> = first.chars().collect();
+        for candidate in rest {
+          let common = prefix
+            .iter()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+          prefix.truncate(common);
+        }
+        if prefix.len() <= word.len() || !prefix.starts_with(word) {
+          return Completion::List;
+        }
+        prefix
+      }
+    };
+    let len = replacement.len();
+    self.line.splice(start..self.cursor, replacement);
+    self.cursor = start + len;
+    Completion::Done
+  }
+}
+
+/// The columns taken by `chars` on the terminal. Escape sequences, such as
+/// colors in the prompt, take none.
+fn display_width(chars: impl IntoIterator<Item = char>) -> usize {
+  let mut width = 0;
+  let mut chars = chars.into_iter().peekable();
+  while let Some(c) = chars.next() {
+    if c == '\x1b' && chars.peek() == Some(&'[') {
+      chars.next();
+      for c in chars.by_ref() {
+        if ('\x40'..='\x7e').contains(&c) {
+          break;
+        }
+      }
+      continue;
+    }
+    width += c.width().unwrap_or(0);
+  }
+  width
+}
+
+/// Writes the prompt and the line over their last rendering, which left the
+/// cursor on `cursor_row` relative to the first row of the prompt. Returns
+/// that row for the new cursor position.
+fn render(
+  out: &mut String,
+  prompt: &str,
+  editor: &LineEditor,
+  columns: usize,
+  cursor_row: usize,
+) -> usize {
+  let columns = columns.max(1);
+  if cursor_row > 0 {
+    write!(out, "\x1b[{cursor_row}A").unwrap();
+  }
+  out.push_str("\r\x1b[J");
+  out.push_str(prompt);
+  out.extend(&editor.line);
+
+  let prompt_width = display_width(prompt.chars());
+  let end = prompt_width + display_width(editor.line.iter().copied());
+  // Terminals only wrap when the next character is written, so the cursor
+  // would still be on the full row.
+  if end > 0 && end % columns == 0 {
+    out.push_str("\r\n");
+  }
+  let position =
+    prompt_width + display_width(editor.line[..editor.cursor].iter().copied());
+  let (row, column) = (position / columns, position % columns);
+  let end_row = end / columns;
+  if end_row > row {
+    write!(out, "\x1b[{}A", end_row - row).unwrap();
+  }
+  out.push('\r');
+  if column > 0 {
+    write!(out, "\x1b[{column}C").unwrap();
+  }
+  row
+}
+
+/// Raw mode, for as long as a line is read.
+struct RawMode {
+  #[cfg(unix)]
+  fd: ResourceHandleFd,
+  #[cfg(unix)]
+  original: nix::sys::termios::Termios,
+}
+
+impl RawMode {
+  fn enable(fd: ResourceHandleFd) -> Result<Self, TtyError> {
+    #[cfg(unix)]
+    {
+      use nix::sys::termios;
+
+      super::tty::prepare_stdio();
+      // SAFETY: the descriptor belongs to a resource that is alive.
+      let borrowed = unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) };
+      let original = termios::tcgetattr(borrowed).map_err(TtyError::Nix)?;
+      let mut raw = original.clone();
+      // Ctrl+C is read as a key, instead of killing the process.
+      super::tty::make_raw(&mut raw, false);
+      termios::tcsetattr(borrowed, termios::SetArg::TCSADRAIN, &raw)
+        .map_err(TtyError::Nix)?;
+      Ok(Self { fd, original })
+    }
+    #[cfg(windows)]
+    {
+      let _ = fd;
+      super::tty_input::windows::set_processed_input(false)?;
+      Ok(Self {})
+    }
+  }
+}
+
+impl Drop for RawMode {
+  fn drop(&mut self) {
+    #[cfg(unix)]
+    {
+      use nix::sys::termios;
+
+      // SAFETY: the descriptor belongs to a resource that is alive.
+      let borrowed = unsafe { std::os::fd::BorrowedFd::borrow_raw(self.fd) };
+      let _ = termios::tcsetattr(
+        borrowed,
+        termios::SetArg::TCSADRAIN,
+        &self.original,
+      );
+    }
+    #[cfg(windows)]
+    {
+      let _ = super::tty_input::windows::set_processed_input(true);
+    }
+  }
+}
+
+#[derive(Default)]
+struct ReadlineState {
+  editor: LineEditor,
+  /// Set while a line is read.
+  raw_mode: Option<RawMode>,
+  /// The row of the cursor, relative to the first row of the prompt.
+  cursor_row: usize,
+  /// Whether the last read resolved with a completion request.
+  completing: bool,
+  /// Events read after the end of the last line.
+  queued: VecDeque<TtyEvent>,
+}
+
+pub struct ReadlineResource {
+  // Declared before `events`, so that raw mode is turned off before the
+  // console mode is restored.
+  state: AsyncRefCell<ReadlineState>,
+  events: Rc<TtyEventsResource>,
+  prompt: String,
+  history: RefCell<History>,
+  history_path: Option<PathBuf>,
+  cancel: CancelHandle,
+}
+
+impl Resource for ReadlineResource {
+  fn name(&self) -> Cow<str> {
+    "readline".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl Drop for ReadlineResource {
+  fn drop(&mut self) {
+    let history = self.history.get_mut();
+    if let (Some(path), true) = (&self.history_path, history.changed) {
+      // There is nobody to report an error to when the resource is dropped.
+      #[allow(clippy::disallowed_methods)]
+      let _ = std::fs::write(path, history.to_text());
+    }
+  }
+}
+
+fn write_out(out: &str) -> Result<(), TtyError> {
+  let mut stdout = std::io::stdout().lock();
+  stdout.write_all(out.as_bytes())?;
+  stdout.flush()?;
+  Ok(())
+}
+
+impl ReadlineResource {
+  fn columns(&self) -> usize {
+    console_size_from_fd(self.events.fd())
+      .ok()
+      .map(|size| size.cols as usize)
+      .filter(|columns| *columns > 0)
+      .unwrap_or(DEFAULT_COLUMNS)
+  }
+
+  fn redraw(&self, state: &mut ReadlineState, out: &mut String) {
+    state.cursor_row = render(
+      out,
+      &self.prompt,
+      &state.editor,
+      self.columns(),
+      state.cursor_row,
+    );
+  }
+
+  /// Moves past the end of the line, and writes `suffix` there.
+  fn move_past_line(
+    &self,
+    state: &mut ReadlineState,
+    out: &mut String,
+    suffix: &str,
+  ) {
+    state.editor.cursor = state.editor.line.len();
+    self.redraw(state, out);
+    out.push_str(suffix);
+    out.push_str("\r\n");
+    state.cursor_row = 0;
+  }
+
+  /// Ends the line, and turns off raw mode.
+  fn finish_line(&self, state: &mut ReadlineState, suffix: &str) {
+    let mut out = String::new();
+    self.move_past_line(state, &mut out, suffix);
+    let _ = write_out(&out);
+    state.raw_mode = None;
+    state.completing = false;
+  }
+
+  /// Abandons the line being read, if any.
+  fn abort_line(self: &Rc<Self>) {
+    let Some(mut state) = RcRef::map(self, |r| &r.state).try_borrow_mut()
+    else {
+      return;
+    };
+    if state.raw_mode.is_some() {
+      self.finish_line(&mut state, "");
+    }
+  }
+
+  async fn read(
+    self: Rc<Self>,
+    completions: Option<Vec<String>>,
+  ) -> Result<ReadlineResult, TtyError> {
+    let mut state = RcRef::map(&self, |r| &r.state).borrow_mut().await;
+    let state = &mut *state;
+    let mut out = String::new();
+    if state.raw_mode.is_none() {
+      state.raw_mode = Some(RawMode::enable(self.events.fd())?);
+      state.editor = LineEditor::default();
+      state.cursor_row = 0;
+      self.redraw(state, &mut out);
+    } else if std::mem::take(&mut state.completing) {
+      let candidates = completions.unwrap_or_default();
+      if state.editor.complete(&candidates) == Completion::List {
+        let cursor = state.editor.cursor;
+        self.move_past_line(state, &mut out, "");
+        out.push_str(&candidates.join("  "));
+        out.push_str("\r\n");
+        state.editor.cursor = cursor;
+      }
+      self.redraw(state, &mut out);
+    }
+    write_out(&std::mem::take(&mut out))?;
+
+    loop {
+      let event = match state.queued.pop_front() {
+        Some(event) => event,
+        None => {
+          let events = self.events.clone().read_events().await?;
+          if events.is_empty() {
+            self.finish_line(state, "");
+            return Ok(ReadlineResult::Eof);
+          }
+          state.queued.extend(events);
+          continue;
+        }
+      };
+      if event.kind == TtyEventKind::Resize {
+        self.redraw(state, &mut out);
+        write_out(&std::mem::take(&mut out))?;
+        continue;
+      }
+      let action = state.editor.handle(&event, &self.history.borrow());
+      match action {
+        Action::None => continue,
+        Action::Redraw => self.redraw(state, &mut out),
+        Action::ClearScreen => {
+          out.push_str("\x1b[H\x1b[2J");
+          state.cursor_row = 0;
+          self.redraw(state, &mut out);
+        }
+        Action::Submit(line) => {
+          self.history.borrow_mut().add(&line);
+          self.finish_line(state, "");
+          return Ok(ReadlineResult::Line { line });
+        }
+        Action::Interrupt => {
+          self.finish_line(state, "^C");
+          return Ok(ReadlineResult::Interrupt);
+        }
+        Action::Eof => {
+          self.finish_line(state, "");
+          return Ok(ReadlineResult::Eof);
+        }
+        Action::Complete => {
+          state.completing = true;
+          return Ok(ReadlineResult::Complete {
+            line: state.editor.text(),
+            cursor: state.editor.utf16_cursor(),
+          });
+        }
+      }
+      write_out(&std::mem::take(&mut out))?;
+    }
+  }
+}
+
+/// Creates a readline reading from the terminal `rid`.
+#[op2]
+#[smi]
+pub fn op_readline_create(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[serde] options: ReadlineOptions,
+) -> Result<ResourceId, TtyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.createReadline");
+  let history_path = match &options.history_path {
+    Some(path) => {
+      let permissions = state.borrow_mut::<PermissionsContainer>();
+      permissions
+        .check_read(path, "Deno.createReadline()")
+        .map_err(TtyError::Other)?;
+      let path = permissions
+        .check_write(path, "Deno.createReadline()")
+        .map_err(TtyError::Other)?;
+      Some(path)
+    }
+    None => None,
+  };
+  let max_history = options.max_history.unwrap_or(DEFAULT_MAX_HISTORY);
+  let history = match &history_path {
+    // The path was checked above.
+    #[allow(clippy::disallowed_methods)]
+    Some(path) => match std::fs::read_to_string(path) {
+      Ok(text) => History::parse(&text, max_history),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+        History::new(max_history)
+      }
+      Err(err) => return Err(err.into()),
+    },
+    None => History::new(max_history),
+  };
+
+  let input = state
+    .resource_table
+    .get_any(rid)
+    .map_err(TtyError::Resource)?;
+  let events = TtyEventsResource::open(input)?;
+  Ok(state.resource_table.add(ReadlineResource {
+    state: AsyncRefCell::new(ReadlineState::default()),
+    events: Rc::new(events),
+    prompt: options.prompt,
+    history: RefCell::new(history),
+    history_path,
+    cancel: CancelHandle::default(),
+  }))
+}
+
+/// Reads a line, or continues the line with `completions` after a
+/// completion request.
+#[op2(async)]
+#[serde]
+pub async fn op_readline_read(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[serde] completions: Option<Vec<String>>,
+  #[smi] cancel_rid: Option<ResourceId>,
+) -> Result<ReadlineResult, TtyError> {
+  let (resource, cancel_handle) = {
+    let state = state.borrow();
+    let resource = state
+      .resource_table
+      .get::<ReadlineResource>(rid)
+      .map_err(TtyError::Resource)?;
+    let cancel_handle = cancel_rid
+      .and_then(|rid| state.resource_table.get::<CancelHandle>(rid).ok());
+    (resource, cancel_handle)
+  };
+
+  let read = resource
+    .clone()
+    .read(completions)
+    .or_cancel(RcRef::map(&resource, |r| &r.cancel));
+  let result = match cancel_handle {
+    Some(cancel_handle) => {
+      let result = read.or_cancel(cancel_handle).await;
+      if let Some(cancel_rid) = cancel_rid {
+        if let Ok(res) = state.borrow_mut().resource_table.take_any(cancel_rid)
+        {
+          res.close();
+        }
+      }
+      result
+    }
+    None => Ok(read.await),
+  };
+  match result {
+    Ok(Ok(result)) => result,
+    // closed
+    Ok(Err(_)) => {
+      resource.abort_line();
+      Ok(ReadlineResult::Eof)
+    }
+    Err(canceled) => {
+      resource.abort_line();
+      Err(canceled.into())
+    }
+  }
+}
+
+/// Abandons the line being read, after the completion callback failed.
+#[op2(fast)]
+pub fn op_readline_abort(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<(), TtyError> {
+  let resource = state
+    .resource_table
+    .get::<ReadlineResource>(rid)
+    .map_err(TtyError::Resource)?;
+  resource.abort_line();
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::tty_input::TtyModifiers;
+  use super::*;
+
+  fn key(key: &str) -> TtyEvent {
+    TtyEvent::key(key, TtyModifiers::NONE)
+  }
+
+  fn ctrl(key: &str) -> TtyEvent {
+    TtyEvent::key(key, TtyModifiers::CTRL)
+  }
+
+  fn type_text(editor: &mut LineEditor, text: &str) {
+    for c in text.chars() {
+      let event = TtyEvent::char(c, TtyModifiers::NONE);
+      editor.handle(&event, &History::default());
+    }
+  }
+
+  fn line_editor(text: &str) -> LineEditor {
+    let mut editor = LineEditor::default();
+    type_text(&mut editor, text);
+    editor
+  }
+
+  #[test]
+  fn test_edit_line() {
+    let history = History::default();
+    let mut editor = line_editor("hello world");
+    assert_eq!(editor.cursor, 11);
+    editor.handle(&key("ArrowLeft"), &history);
+    editor.handle(&key("ArrowLeft"), &history);
+    type_text(&mut editor, "XY");
+    assert_eq!(editor.text(), "hello worXYld");
+    editor.handle(&key("Backspace"), &history);
+    editor.handle(&key("Delete"), &history);
+    assert_eq!(editor.text(), "hello worXd");
+    editor.handle(&key("Home"), &history);
+    type_text(&mut editor, "> ");
+    editor.handle(&ctrl("e"), &history);
+    editor.handle(&ctrl("w"), &history);
+    assert_eq!(editor.text(), "> hello ");
+    editor.handle(&ctrl("a"), &history);
+    editor.handle(&ctrl("k"), &history);
+    assert_eq!(editor.text(), "");
+    assert_eq!(editor.handle(&ctrl("d"), &history), Action::Eof);
+    assert_eq!(editor.handle(&ctrl("c"), &history), Action::Interrupt);
+
+    let mut editor = line_editor("héllo");
+    editor.handle(&key("ArrowLeft"), &history);
+    assert_eq!(editor.utf16_cursor(), 4);
+    assert_eq!(
+      editor.handle(&key("Enter"), &history),
+      Action::Submit("héllo".to_string())
+    );
+    // Ctrl+D deletes the character after the cursor unless the line is empty
+    assert_eq!(editor.handle(&ctrl("d"), &history), Action::Redraw);
+    assert_eq!(editor.text(), "héll");
+  }
+
+  #[test]
+  fn test_paste_is_one_line() {
+    let mut editor = LineEditor::default();
+    let event = TtyEvent::paste(b"a\nb\tc\x07");
+    editor.handle(&event, &History::default());
+    assert_eq!(editor.text(), "a b c");
+  }
+
+  #[test]
+  fn test_history() {
+    let mut history = History::new(3);
+    for line in ["one", "two", "one", " ", "three", "four"] {
+      history.add(line);
+    }
+    assert_eq!(history.entries, ["one", "three", "four"]);
+    assert_eq!(history.to_text(), "one\nthree\nfour\n");
+    let loaded = History::parse("a\nb\na\nc\n", 2);
+    assert_eq!(loaded.entries, ["a", "c"]);
+    assert!(!loaded.changed);
+    let mut disabled = History::new(0);
+    disabled.add("line");
+    assert!(disabled.entries.is_empty());
+
+    let mut editor = line_editor("draft");
+    editor.handle(&key("ArrowUp"), &history);
+    assert_eq!(editor.text(), "four");
+    editor.handle(&key("ArrowUp"), &history);
+    editor.handle(&key("ArrowUp"), &history);
+    assert_eq!(editor.text(), "one");
+    // stays at the oldest entry
+    assert_eq!(editor.handle(&key("ArrowUp"), &history), Action::None);
+    editor.handle(&key("ArrowDown"), &history);
+    assert_eq!(editor.text(), "three");
+    editor.handle(&ctrl("n"), &history);
+    editor.handle(&ctrl("n"), &history);
+    assert_eq!(editor.text(), "draft");
+    assert_eq!(editor.cursor, 5);
+  }
+
+  #[test]
+  fn test_complete() {
+    let candidates = |items: &[&str]| -> Vec<String> {
+      items.iter().map(|item| item.to_string()).collect()
+    };
+    let mut editor = line_editor("Deno.re");
+    assert_eq!(
+      editor.complete(&candidates(&["Deno.readFile", "Deno.readDir"])),
+      Completion::Done
+    );
+    assert_eq!(editor.text(), "Deno.read");
+    assert_eq!(
+      editor.complete(&candidates(&["Deno.readFile", "Deno.readDir"])),
+      Completion::List
+    );
+    assert_eq!(editor.text(), "Deno.read");
+
+    // only the word before the cursor is replaced
+    let mut editor = line_editor("let x = fo bar");
+    for _ in 0..4 {
+      editor.handle(&key("ArrowLeft"), &History::default());
+    }
+    editor.complete(&candidates(&["foo"]));
+    assert_eq!(editor.text(), "let x = foo bar");
+    assert_eq!(editor.cursor, 11);
+    assert_eq!(editor.complete(&[]), Completion::Done);
+  }
+
+  #[test]
+  fn test_render() {
+    let mut out = String::new();
+    let editor = line_editor("abc");
+    assert_eq!(render(&mut out, "> ", &editor, 80, 0), 0);
+    assert_eq!(out, "\r\x1b[J> abc\r\x1b[5C");
+
+    // wrapped, with the cursor on the first row
+    let mut editor = line_editor("abcdefgh");
+    editor.cursor = 1;
+    out.clear();
+    assert_eq!(render(&mut out, "\x1b[32m>\x1b[0m ", &editor, 4, 1), 0);
+    assert_eq!(
+      out,
+      "\x1b[1A\r\x1b[J\x1b[32m>\x1b[0m abcdefgh\x1b[2A\r\x1b[3C"
+    );
+
+    // the line ends at the edge of the terminal
+    let editor = line_editor("ab");
+    out.clear();
+    assert_eq!(render(&mut out, "> ", &editor, 4, 0), 1);
+    assert_eq!(out, "\r\x1b[J> ab\r\n\r");
+    assert_eq!(display_width("\x1b[1;32m日本\x1b[0m".chars()), 4);
+  }
+}
@@ -1,5 +1,8 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use crate::ops::env_policy::env_snapshot;
+use crate::ops::env_policy::EnvPolicy;
+use crate::ops::env_policy::ScopedEnv;
 use crate::ops::TestingFeaturesEnabled;
 use crate::web_worker::run_web_worker;
 use crate::web_worker::SendableWebWorkerHandle;
@@ -26,6 +29,7 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub const UNSTABLE_FEATURE_NAME: &str = "worker-options";
 
@@ -86,6 +90,7 @@ deno_core::extension!(
   ops = [
     op_create_worker,
     op_host_terminate_worker,
+    op_host_terminate_worker_gracefully,
     op_host_post_message,
     op_host_recv_ctrl,
     op_host_recv_message,
@@ -112,6 +117,7 @@ pub struct CreateWorkerArgs {
   has_source_code: bool,
   name: Option<String>,
   permissions: Option<ChildPermissionsArg>,
+  env: Option<EnvPolicy>,
   source_code: String,
   specifier: String,
   worker_type: WebWorkerType,
@@ -161,6 +167,19 @@ fn op_create_worker(
       "Worker.deno.permissions",
     );
   }
+  if args.env.is_some() {
+    super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Worker.deno.env");
+  }
+  // A worker without a policy of its own sees the same environment as its
+  // parent, which is a copy if the parent was spawned with a policy.
+  let worker_env = match &args.env {
+    Some(policy) => {
+      let resolved = policy
+        .resolve(env_snapshot(state), state.borrow::<PermissionsContainer>());
+      Some(ScopedEnv::new(resolved.vars))
+    }
+    None => state.try_borrow::<ScopedEnv>().cloned(),
+  };
   let parent_permissions = state.borrow_mut::<PermissionsContainer>();
   let worker_permissions = if let Some(child_permissions_arg) = args.permissions
   {
@@ -200,7 +219,7 @@ fn op_create_worker(
     //  all action done upon it should be noops
     // - newly spawned thread exits
 
-    let (worker, external_handle) =
+    let (mut worker, external_handle) =
       (create_web_worker_cb.0)(CreateWebWorkerArgs {
         name: worker_name,
         worker_id,
@@ -211,6 +230,9 @@ fn op_create_worker(
         close_on_idle: args.close_on_idle,
         maybe_worker_metadata,
       });
+    if let Some(worker_env) = worker_env {
+      worker.js_runtime.op_state().borrow_mut().put(worker_env);
+    }
 
     // Send thread safe handle from newly created worker to host thread
     handle_sender.send(external_handle).unwrap();
@@ -256,6 +278,32 @@ fn op_host_terminate_worker(state: &mut OpState, #[serde] id: WorkerId) {
   }
 }
 
+/// Dispatches "beforeterminate" in the worker and waits until it shut down, or
+/// until it was terminated after `deadline_ms`. Returns whether it shut down
+/// before the deadline.
+#[op2(async)]
+async fn op_host_terminate_worker_gracefully(
+  state: Rc<RefCell<OpState>>,
+  #[serde] id: WorkerId,
+  deadline_ms: u32,
+) -> bool {
+  let Some(worker_thread) =
+    state.borrow_mut().borrow_mut::<WorkersTable>().remove(&id)
+  else {
+    // The worker already closed itself.
+    debug!("tried to terminate non-existent worker {}", id);
+    return true;
+  };
+  worker_thread.cancel_handle.cancel();
+  let graceful = worker_thread
+    .worker_handle
+    .clone()
+    .terminate_gracefully(Duration::from_millis(deadline_ms as u64))
+    .await;
+  drop(worker_thread);
+  graceful
+}
+
 enum WorkerChannel {
   Ctrl,
   Messages,
@@ -0,0 +1,1063 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Export of op and event loop telemetry to an OpenTelemetry collector over
+//! OTLP/HTTP with protobuf payloads.
+//!
+//! An [`OtelRecorder`] lives on the worker's thread. It is fed by the op
+//! metrics hooks and by a ticker task on the worker's event loop, and keeps:
+//!
+//! - per op call and error counts and a duration histogram,
+//! - an event loop lag gauge, measured as how late the ticker wakes up,
+//! - a gauge of the number of open resources,
+//! - when op tracing is enabled, one span per async op call.
+//!
+//! Every export interval the ticker encodes the metrics, and the spans
+//! recorded since the last export, into OTLP requests and hands them to the
+//! exporter thread through a bounded queue. The worker never waits on the
+//! collector: when the queue is full, or a request fails, the data is dropped
+//! and counted, and the counts are exported as metrics too. Pending data is
+//! flushed when the worker's resources are closed at shutdown.
+
+pub mod proto;
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::rc::Weak;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use deno_core::OpMetricsEvent;
+use deno_core::OpMetricsFactoryFn;
+use deno_core::OpMetricsSource;
+use deno_core::OpState;
+use http_body_util::BodyExt;
+use prost::Message;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+const INSTRUMENTATION_SCOPE: &str = "deno";
+/// Upper bounds of the op duration histogram buckets, in milliseconds.
+const DURATION_BOUNDS_MS: &[f64] =
+  &[0.1, 0.5, 1.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1000.0, 5000.0];
+/// Requests waiting for the exporter thread.
+const EXPORT_QUEUE_CAPACITY: usize = 16;
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where and how often to export telemetry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OtelConfig {
+  /// Base URL of the collector's OTLP/HTTP receiver, e.g.
+  /// `http://localhost:4318`. Requests go to `/v1/metrics` and `/v1/traces`.
+  pub endpoint: String,
+  /// Extra request headers, e.g. for authentication.
+  pub headers: Vec<(String, String)>,
+  pub export_interval: Duration,
+  /// Attributes of the `Resource` the telemetry is reported for.
+  pub resource_attributes: Vec<(String, String)>,
+  /// Record a span for every async op call.
+  pub trace_ops: bool,
+  /// Maximum number of spans kept between two exports. Further spans are
+  /// dropped.
+  pub max_queued_spans: usize,
+}
+
+impl Default for OtelConfig {
+  fn default() -> Self {
+    Self {
+      endpoint: "http://localhost:4318".to_string(),
+      headers: vec![],
+      export_interval: Duration::from_secs(60),
+      resource_attributes: vec![(
+        "service.name".to_string(),
+        "unknown_service:deno".to_string(),
+      )],
+      trace_ops: false,
+      max_queued_spans: 2048,
+    }
+  }
+}
+
+impl OtelConfig {
+  /// Reads the configuration from the standard `OTEL_*` environment
+  /// variables. Returns `None` unless `OTEL_EXPORTER_OTLP_ENDPOINT` is set,
+  /// or when `OTEL_SDK_DISABLED` is `true`.
+  ///
+  /// Op spans are only recorded with `OTEL_TRACES_SAMPLER=always_on`, as
+  /// there is one for every async op call.
+  pub fn from_env() -> Option<Self> {
+    Self::from_env_vars(|name| std::env::var(name).ok())
+  }
+
+  fn from_env_vars(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+    if var("OTEL_SDK_DISABLED").is_some_and(|v| v.eq_ignore_ascii_case("true"))
+    {
+      return None;
+    }
+    let mut config = OtelConfig {
+      endpoint: var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .filter(|endpoint| !endpoint.is_empty())?,
+      ..Default::default()
+    };
+    if let Some(headers) = var("OTEL_EXPORTER_OTLP_HEADERS") {
+      config.headers = parse_key_value_list(&headers);
+    }
+    if let Some(interval) =
+      var("OTEL_METRIC_EXPORT_INTERVAL").and_then(|v| v.parse::<u64>().ok())
+    {
+      config.export_interval = Duration::from_millis(interval.max(1));
+    }
+    if let Some(attributes) = var("OTEL_RESOURCE_ATTRIBUTES") {
+      for (key, value) in parse_key_value_list(&attributes) {
+        config.set_resource_attribute(key, value);
+      }
+    }
+    if let Some(name) = var("OTEL_SERVICE_NAME").filter(|v| !v.is_empty()) {
+      config.set_resource_attribute("service.name".to_string(), name);
+    }
+    config.trace_ops =
+      var("OTEL_TRACES_SAMPLER").as_deref() == Some("always_on");
+    if let Some(size) =
+      var("OTEL_BSP_MAX_QUEUE_SIZE").and_then(|v| v.parse().ok())
+    {
+      config.max_queued_spans = size;
+    }
+    Some(config)
+  }
+
+  fn set_resource_attribute(&mut self, key: String, value: String) {
+    self.resource_attributes.retain(|(k, _)| *k != key);
+    self.resource_attributes.push((key, value));
+  }
+
+  fn resource(&self) -> proto::Resource {
+    proto::Resource {
+      attributes: self
+        .resource_attributes
+        .iter()
+        .map(|(key, value)| proto::KeyValue::string(key, value))
+        .collect(),
+      dropped_attributes_count: 0,
+    }
+  }
+}
+
+/// Parses a `key1=value1,key2=value2` list, as used by the `OTEL_*`
+/// environment variables. Values are percent-decoded; malformed entries are
+/// skipped.
+fn parse_key_value_list(list: &str) -> Vec<(String, String)> {
+  list
+    .split(',')
+    .filter_map(|entry| {
+      let (key, value) = entry.split_once('=')?;
+      let key = key.trim();
+      if key.is_empty() {
+        return None;
+      }
+      let value = percent_encoding::percent_decode_str(value.trim())
+        .decode_utf8()
+        .ok()?;
+      Some((key.to_string(), value.into_owned()))
+    })
+    .collect()
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+  time
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|d| d.as_nanos() as u64)
+    .unwrap_or(0)
+}
+
+/// Counts of telemetry that didn't make it to the collector. Shared with the
+/// exporter thread.
+#[derive(Debug, Default)]
+pub struct DroppedCounters {
+  /// Export requests that were dropped because the queue was full, or that
+  /// failed to be delivered.
+  pub requests: AtomicU64,
+  /// Spans that were dropped, either on their own because too many were
+  /// queued, or as part of a dropped request.
+  pub spans: AtomicU64,
+}
+
+enum ExportMessage {
+  Export {
+    path: &'static str,
+    body: Vec<u8>,
+    spans: u64,
+  },
+  Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the thread that sends export requests to the collector. The
+/// thread exits once all handles are dropped.
+#[derive(Clone)]
+pub struct OtelExporter {
+  tx: mpsc::Sender<ExportMessage>,
+  dropped: Arc<DroppedCounters>,
+}
+
+impl OtelExporter {
+  pub fn start(config: &OtelConfig) -> Self {
+    let (tx, rx) = mpsc::channel(EXPORT_QUEUE_CAPACITY);
+    let dropped = Arc::new(DroppedCounters::default());
+    let endpoint = config.endpoint.trim_end_matches('/').to_string();
+    let headers = config.headers.clone();
+    let thread_dropped = dropped.clone();
+    let spawn_result = std::thread::Builder::new()
+      .name("deno-otel-exporter".to_string())
+      .spawn(move || {
+        let rt = crate::tokio_util::create_basic_runtime();
+        rt.block_on(run_exporter(rx, endpoint, headers, thread_dropped));
+      });
+    if let Err(err) = spawn_result {
+      log::debug!("Failed to start the OpenTelemetry exporter: {err}");
+    }
+    Self { tx, dropped }
+  }
+
+  pub fn dropped(&self) -> &DroppedCounters {
+    &self.dropped
+  }
+
+  /// Queues a request without waiting. Drops it when the queue is full.
+  fn try_export(&self, path: &'static str, body: Vec<u8>, spans: u64) {
+    let message = ExportMessage::Export { path, body, spans };
+    if self.tx.try_send(message).is_err() {
+      self.drop_request(spans);
+    }
+  }
+
+  /// Queues a request, waiting for room in the queue if needed.
+  async fn export(&self, path: &'static str, body: Vec<u8>, spans: u64) {
+    let message = ExportMessage::Export { path, body, spans };
+    if self.tx.send(message).await.is_err() {
+      self.drop_request(spans);
+    }
+  }
+
+  fn drop_request(&self, spans: u64) {
+    self.dropped.requests.fetch_add(1, Ordering::Relaxed);
+    self.dropped.spans.fetch_add(spans, Ordering::Relaxed);
+  }
+
+  /// Resolves once all requests queued so far were sent or dropped.
+  async fn flush(&self) {
+    let (tx, rx) = oneshot::channel();
+    if self.tx.send(ExportMessage::Flush(tx)).await.is_ok() {
+      let _ = rx.await;
+    }
+  }
+}
+
+async fn run_exporter(
+  mut rx: mpsc::Receiver<ExportMessage>,
+  endpoint: String,
+  headers: Vec<(String, String)>,
+  dropped: Arc<DroppedCounters>,
+) {
+  let client = deno_fetch::create_http_client(
+    "Deno",
+    deno_fetch::CreateHttpClientOptions {
+      root_cert_store: Some(deno_tls::create_default_root_cert_store()),
+      ..Default::default()
+    },
+  );
+  let client = match client {
+    Ok(client) => Some(client),
+    Err(err) => {
+      log::debug!("Failed to create the OpenTelemetry HTTP client: {err}");
+      None
+    }
+  };
+
+  while let Some(message) = rx.recv().await {
+    match message {
+      ExportMessage::Export { path, body, spans } => {
+        let sent = match &client {
+          Some(client) => {
+            let url = format!("{endpoint}{path}");
+            let send = send_request(client.clone(), &url, &headers, body);
+            match tokio::time::timeout(EXPORT_TIMEOUT, send).await {
+              Ok(Ok(())) => true,
+              Ok(Err(err)) => {
+                log::debug!("Failed to export telemetry to {url}: {err}");
+                false
+              }
+              Err(_) => {
+                log::debug!("Timed out exporting telemetry to {url}");
+                false
+              }
+            }
+          }
+          None => false,
+        };
+        if !sent {
+          dropped.requests.fetch_add(1, Ordering::Relaxed);
+          dropped.spans.fetch_add(spans, Ordering::Relaxed);
+        }
+      }
+      ExportMessage::Flush(done) => {
+        let _ = done.send(());
+      }
+    }
+  }
+}
+
+async fn send_request(
+  client: deno_fetch::Client,
+  url: &str,
+  headers: &[(String, String)],
+  body: Vec<u8>,
+) -> Result<(), deno_core::error::AnyError> {
+  let body = http_body_util::Full::new(body.into())
+    .map_err(|never| match never {})
+    .boxed();
+  let mut request = http::Request::post(url)
+    .header(http::header::CONTENT_TYPE, "application/x-protobuf");
+  for (name, value) in headers {
+    request = request.header(name.as_str(), value.as_str());
+  }
+  let response = client.send(request.body(body)?).await?;
+  let status = response.status();
+  // Drain the body so the connection can be reused.
+  let _ = response.into_body().collect().await;
+  if !status.is_success() {
+    deno_core::anyhow::bail!("collector responded with {status}");
+  }
+  Ok(())
+}
+
+#[derive(Default)]
+struct OpStats {
+  calls: u64,
+  errors: u64,
+  /// Start times of the calls in flight. Concurrent calls of the same op are
+  /// paired with their completions in dispatch order, so when they complete
+  /// out of order the individual durations are swapped between calls.
+  in_flight: VecDeque<(Instant, SystemTime)>,
+  duration_buckets: Vec<u64>,
+  duration_count: u64,
+  duration_sum_ms: f64,
+  duration_min_ms: f64,
+  duration_max_ms: f64,
+}
+
+impl OpStats {
+  fn record_duration(&mut self, ms: f64) {
+    if self.duration_buckets.is_empty() {
+      self.duration_buckets = vec![0; DURATION_BOUNDS_MS.len() + 1];
+      self.duration_min_ms = ms;
+      self.duration_max_ms = ms;
+    }
+    let bucket = DURATION_BOUNDS_MS.partition_point(|bound| *bound < ms);
+    self.duration_buckets[bucket] += 1;
+    self.duration_count += 1;
+    self.duration_sum_ms += ms;
+    self.duration_min_ms = self.duration_min_ms.min(ms);
+    self.duration_max_ms = self.duration_max_ms.max(ms);
+  }
+}
+
+/// Collects the telemetry of one worker. Must be used on the worker's thread.
+pub struct OtelRecorder {
+  config: OtelConfig,
+  exporter: OtelExporter,
+  start_time: SystemTime,
+  ops: RefCell<HashMap<&'static str, OpStats>>,
+  spans: RefCell<Vec<proto::Span>>,
+  event_loop_lag: Cell<Option<Duration>>,
+  resource_count: Cell<Option<usize>>,
+}
+
+impl OtelRecorder {
+  pub fn new(config: OtelConfig) -> Rc<Self> {
+    let exporter = OtelExporter::start(&config);
+    Rc::new(Self {
+      config,
+      exporter,
+      start_time: SystemTime::now(),
+      ops: Default::default(),
+      spans: Default::default(),
+      event_loop_lag: Default::default(),
+      resource_count: Default::default(),
+    })
+  }
+
+  pub fn dropped(&self) -> &DroppedCounters {
+    self.exporter.dropped()
+  }
+
+  /// Returns an op metrics factory that feeds every op call to this
+  /// recorder.
+  pub fn op_metrics_factory_fn(self: &Rc<Self>) -> OpMetricsFactoryFn {
+    let recorder = self.clone();
+    Box::new(move |_, _, _| {
+      let recorder = recorder.clone();
+      Some(Rc::new(
+        move |op: &deno_core::_ops::OpCtx, event, source| {
+          recorder.record_op(op.decl().name, event, source);
+        },
+      ))
+    })
+  }
+
+  fn record_op(
+    &self,
+    name: &'static str,
+    event: OpMetricsEvent,
+    source: OpMetricsSource,
+  ) {
+    let mut ops = self.ops.borrow_mut();
+    let stats = ops.entry(name).or_default();
+    let ok = match event {
+      OpMetricsEvent::Dispatched => {
+        stats.calls += 1;
+        stats
+          .in_flight
+          .push_back((Instant::now(), SystemTime::now()));
+        return;
+      }
+      OpMetricsEvent::Completed | OpMetricsEvent::CompletedAsync => true,
+      OpMetricsEvent::Error | OpMetricsEvent::ErrorAsync => {
+        stats.errors += 1;
+        false
+      }
+    };
+    let Some((started, start_time)) = stats.in_flight.pop_front() else {
+      return;
+    };
+    stats.record_duration(started.elapsed().as_secs_f64() * 1000.0);
+    drop(ops);
+
+    if self.config.trace_ops && matches!(source, OpMetricsSource::Async) {
+      self.record_span(name, start_time, ok);
+    }
+  }
+
+  fn record_span(&self, name: &str, start_time: SystemTime, ok: bool) {
+    let mut spans = self.spans.borrow_mut();
+    if spans.len() >= self.config.max_queued_spans {
+      self.dropped().spans.fetch_add(1, Ordering::Relaxed);
+      return;
+    }
+    let (code, message) = if ok {
+      (proto::STATUS_CODE_OK, String::new())
+    } else {
+      (proto::STATUS_CODE_ERROR, "op failed".to_string())
+    };
+    spans.push(proto::Span {
+      trace_id: deno_crypto::rand::random::<[u8; 16]>().to_vec(),
+      span_id: deno_crypto::rand::random::<[u8; 8]>().to_vec(),
+      name: name.to_string(),
+      kind: proto::SPAN_KIND_INTERNAL,
+      start_time_unix_nano: unix_nanos(start_time),
+      end_time_unix_nano: unix_nanos(SystemTime::now()),
+      attributes: vec![proto::KeyValue::string("deno.op.name", name)],
+      status: Some(proto::Status { message, code }),
+    });
+  }
+
+  fn set_gauges(&self, event_loop_lag: Duration, resource_count: usize) {
+    self.event_loop_lag.set(Some(event_loop_lag));
+    self.resource_count.set(Some(resource_count));
+  }
+
+  fn scope() -> Option<proto::InstrumentationScope> {
+    Some(proto::InstrumentationScope {
+      name: INSTRUMENTATION_SCOPE.to_string(),
+      version: String::new(),
+    })
+  }
+
+  fn metrics_request(&self) -> proto::ExportMetricsServiceRequest {
+    use proto::metric::Data;
+    use proto::number_data_point::Value;
+
+    let start_time_unix_nano = unix_nanos(self.start_time);
+    let time_unix_nano = unix_nanos(SystemTime::now());
+    let point =
+      |attributes: Vec<proto::KeyValue>, value| proto::NumberDataPoint {
+        attributes,
+        start_time_unix_nano,
+        time_unix_nano,
+        value: Some(value),
+      };
+    let counter = |name: &str, description: &str, data_points| proto::Metric {
+      name: name.to_string(),
+      description: description.to_string(),
+      unit: "1".to_string(),
+      data: Some(Data::Sum(proto::Sum {
+        data_points,
+        aggregation_temporality: proto::AGGREGATION_TEMPORALITY_CUMULATIVE,
+        is_monotonic: true,
+      })),
+    };
+
+    let ops = self.ops.borrow();
+    let mut names = ops.keys().copied().collect::<Vec<_>>();
+    names.sort_unstable();
+    let op_attributes =
+      |name: &str| vec![proto::KeyValue::string("deno.op.name", name)];
+
+    let mut metrics = vec![
+      counter(
+        "deno.op.calls",
+        "Number of op calls",
+        names
+          .iter()
+          .map(|&name| {
+            point(op_attributes(name), Value::AsInt(ops[name].calls as i64))
+          })
+          .collect(),
+      ),
+      counter(
+        "deno.op.errors",
+        "Number of op calls that failed",
+        names
+          .iter()
+          .filter(|&&name| ops[name].errors > 0)
+          .map(|&name| {
+            point(op_attributes(name), Value::AsInt(ops[name].errors as i64))
+          })
+          .collect(),
+      ),
+      proto::Metric {
+        name: "deno.op.duration".to_string(),
+        description: "Duration of op calls".to_string(),
+        unit: "ms".to_string(),
+        data: Some(Data::Histogram(proto::Histogram {
+          data_points: names
+            .iter()
+            .filter(|&&name| ops[name].duration_count > 0)
+            .map(|&name| {
+              let stats = &ops[name];
+              proto::HistogramDataPoint {
+                attributes: op_attributes(name),
+                start_time_unix_nano,
+                time_unix_nano,
+                count: stats.duration_count,
+                sum: Some(stats.duration_sum_ms),
+                bucket_counts: stats.duration_buckets.clone(),
+                explicit_bounds: DURATION_BOUNDS_MS.to_vec(),
+                min: Some(stats.duration_min_ms),
+                max: Some(stats.duration_max_ms),
+              }
+            })
+            .collect(),
+          aggregation_temporality: proto::AGGREGATION_TEMPORALITY_CUMULATIVE,
+        })),
+      },
+    ];
+    drop(ops);
+
+    let gauge =
+      |name: &str, description: &str, unit: &str, value| proto::Metric {
+        name: name.to_string(),
+        description: description.to_string(),
+        unit: unit.to_string(),
+        data: Some(Data::Gauge(proto::Gauge {
+          data_points: vec![point(vec![], value)],
+        })),
+      };
+    if let Some(lag) = self.event_loop_lag.get() {
+      metrics.push(gauge(
+        "deno.event_loop.lag",
+        "How late the event loop ran a timer at the last measurement",
+        "ms",
+        Value::AsDouble(lag.as_secs_f64() * 1000.0),
+      ));
+    }
+    if let Some(count) = self.resource_count.get() {
+      metrics.push(gauge(
+        "deno.resources",
+        "Number of open resources",
+        "1",
+        Value::AsInt(count as i64),
+      ));
+    }
+
+    let dropped = self.dropped();
+    metrics.push(counter(
+      "deno.otel.dropped_requests",
+      "Telemetry export requests that were dropped or failed",
+      vec![point(
+        vec![],
+        Value::AsInt(dropped.requests.load(Ordering::Relaxed) as i64),
+      )],
+    ));
+    metrics.push(counter(
+      "deno.otel.dropped_spans",
+      "Spans that were dropped before reaching the collector",
+      vec![point(
+        vec![],
+        Value::AsInt(dropped.spans.load(Ordering::Relaxed) as i64),
+      )],
+    ));
+
+    proto::ExportMetricsServiceRequest {
+      resource_metrics: vec![proto::ResourceMetrics {
+        resource: Some(self.config.resource()),
+        scope_metrics: vec![proto::ScopeMetrics {
+          scope: Self::scope(),
+          metrics,
+          schema_url: String::new(),
+        }],
+        schema_url: String::new(),
+      }],
+    }
+  }
+
+  /// Takes the spans recorded since the last export. Returns `None` when
+  /// there are none.
+  fn take_traces_request(
+    &self,
+  ) -> Option<(proto::ExportTraceServiceRequest, u64)> {
+    let spans = std::mem::take(&mut *self.spans.borrow_mut());
+    if spans.is_empty() {
+      return None;
+    }
+    let count = spans.len() as u64;
+    let request = proto::ExportTraceServiceRequest {
+      resource_spans: vec![proto::ResourceSpans {
+        resource: Some(self.config.resource()),
+        scope_spans: vec![proto::ScopeSpans {
+          scope: Self::scope(),
+          spans,
+          schema_url: String::new(),
+        }],
+        schema_url: String::new(),
+      }],
+    };
+    Some((request, count))
+  }
+
+  /// Queues the current metrics and the pending spans for export, without
+  /// waiting.
+  pub fn export(&self) {
+    if let Some((request, spans)) = self.take_traces_request() {
+      self
+        .exporter
+        .try_export("/v1/traces", request.encode_to_vec(), spans);
+    }
+    let request = self.metrics_request();
+    self
+      .exporter
+      .try_export("/v1/metrics", request.encode_to_vec(), 0);
+  }
+
+  /// Exports the current metrics and the pending spans, and waits until the
+  /// exporter is done with everything queued.
+  pub async fn flush(&self) {
+    if let Some((request, spans)) = self.take_traces_request() {
+      self
+        .exporter
+        .export("/v1/traces", request.encode_to_vec(), spans)
+        .await;
+    }
+    let request = self.metrics_request();
+    self
+      .exporter
+      .export("/v1/metrics", request.encode_to_vec(), 0)
+      .await;
+    self.exporter.flush().await;
+  }
+
+  /// Starts the task that measures the event loop lag, counts the open
+  /// resources and exports every interval, on the current thread's event
+  /// loop. Also makes the runtime flush pending telemetry when it closes its
+  /// resources at shutdown.
+  pub fn start(self: &Rc<Self>, state: &Rc<RefCell<OpState>>) {
+    deno_core::unsync::spawn(run_ticker(
+      Rc::downgrade(self),
+      Rc::downgrade(state),
+      self.config.export_interval,
+    ));
+    let recorder = self.clone();
+    deno_web::register_state_close(&mut state.borrow_mut(), move |_| {
+      Some(Box::pin(async move { recorder.flush().await }))
+    });
+  }
+}
+
+async fn run_ticker(
+  recorder: Weak<OtelRecorder>,
+  state: Weak<RefCell<OpState>>,
+  interval: Duration,
+) {
+  loop {
+    let expected = Instant::now() + interval;
+    tokio::time::sleep(interval).await;
+    let lag = Instant::now().saturating_duration_since(expected);
+    let (Some(recorder), Some(state)) = (recorder.upgrade(), state.upgrade())
+    else {
+      break;
+    };
+    let resource_count = state.borrow().resource_table.names().count();
+    recorder.set_gauges(lag, resource_count);
+    recorder.export();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::BufRead;
+  use std::io::BufReader;
+  use std::io::Read;
+  use std::io::Write;
+  use std::net::TcpListener;
+  use std::sync::Mutex;
+
+  struct ReceivedRequest {
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+  }
+
+  /// An OTLP/HTTP collector that accepts everything and records the
+  /// requests.
+  fn start_fake_collector() -> (String, Arc<Mutex<Vec<ReceivedRequest>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(vec![]));
+    let requests = received.clone();
+    std::thread::spawn(move || {
+      for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { break };
+        let requests = requests.clone();
+        std::thread::spawn(move || {
+          let mut reader = BufReader::new(stream.try_clone().unwrap());
+          loop {
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+              return;
+            }
+            let path = request_line.split(' ').nth(1).unwrap().to_string();
+            let mut headers = vec![];
+            loop {
+              let mut line = String::new();
+              reader.read_line(&mut line).unwrap();
+              let line = line.trim_end();
+              if line.is_empty() {
+                break;
+              }
+              let (name, value) = line.split_once(':').unwrap();
+              headers.push((name.to_lowercase(), value.trim().to_string()));
+            }
+            let length = headers
+              .iter()
+              .find(|(name, _)| name == "content-length")
+              .map(|(_, value)| value.parse().unwrap())
+              .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            requests.lock().unwrap().push(ReceivedRequest {
+              path,
+              headers,
+              body,
+            });
+            stream
+              .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+              .unwrap();
+          }
+        });
+      }
+    });
+    (endpoint, received)
+  }
+
+  fn test_config(endpoint: String) -> OtelConfig {
+    OtelConfig {
+      endpoint,
+      headers: vec![("x-api-key".to_string(), "secret".to_string())],
+      resource_attributes: vec![(
+        "service.name".to_string(),
+        "test-service".to_string(),
+      )],
+      trace_ops: true,
+      ..Default::default()
+    }
+  }
+
+  fn record_call(recorder: &OtelRecorder, name: &'static str, ok: bool) {
+    recorder.record_op(
+      name,
+      OpMetricsEvent::Dispatched,
+      OpMetricsSource::Async,
+    );
+    let event = if ok {
+      OpMetricsEvent::CompletedAsync
+    } else {
+      OpMetricsEvent::ErrorAsync
+    };
+    recorder.record_op(name, event, OpMetricsSource::Async);
+  }
+
+  fn run<F: std::future::Future>(future: F) -> F::Output {
+    crate::tokio_util::create_basic_runtime().block_on(future)
+  }
+
+  fn metric<'a>(
+    request: &'a proto::ExportMetricsServiceRequest,
+    name: &str,
+  ) -> &'a proto::Metric {
+    request.resource_metrics[0].scope_metrics[0]
+      .metrics
+      .iter()
+      .find(|metric| metric.name == name)
+      .unwrap_or_else(|| panic!("missing metric {name}"))
+  }
+
+  fn int_points(metric: &proto::Metric) -> Vec<(String, i64)> {
+    let points = match &metric.data {
+      Some(proto::metric::Data::Sum(sum)) => &sum.data_points,
+      Some(proto::metric::Data::Gauge(gauge)) => &gauge.data_points,
+      _ => panic!("not a number metric"),
+    };
+    points
+      .iter()
+      .map(|point| {
+        let op = point
+          .attributes
+          .first()
+          .and_then(|attr| match &attr.value.as_ref()?.value {
+            Some(proto::any_value::Value::StringValue(s)) => Some(s.clone()),
+            _ => None,
+          })
+          .unwrap_or_default();
+        let Some(proto::number_data_point::Value::AsInt(value)) = point.value
+        else {
+          panic!("not an int point");
+        };
+        (op, value)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn config_from_env() {
+    let vars = HashMap::from([
+      ("OTEL_EXPORTER_OTLP_ENDPOINT", "https://otel.example.com"),
+      ("OTEL_EXPORTER_OTLP_HEADERS", "api-key=a%20b, x=1,broken"),
+      ("OTEL_METRIC_EXPORT_INTERVAL", "5000"),
+      ("OTEL_RESOURCE_ATTRIBUTES", "service.name=a,deployment=prod"),
+      ("OTEL_SERVICE_NAME", "b"),
+      ("OTEL_TRACES_SAMPLER", "always_on"),
+    ]);
+    let config =
+      OtelConfig::from_env_vars(|name| vars.get(name).map(|v| v.to_string()))
+        .unwrap();
+    assert_eq!(config.endpoint, "https://otel.example.com");
+    assert_eq!(
+      config.headers,
+      vec![
+        ("api-key".to_string(), "a b".to_string()),
+        ("x".to_string(), "1".to_string())
+      ]
+    );
+    assert_eq!(config.export_interval, Duration::from_secs(5));
+    assert_eq!(
+      config.resource_attributes,
+      vec![
+        ("deployment".to_string(), "prod".to_string()),
+        ("service.name".to_string(), "b".to_string())
+      ]
+    );
+    assert!(config.trace_ops);
+
+    assert_eq!(OtelConfig::from_env_vars(|_| None), None);
+    let disabled = HashMap::from([
+      ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318"),
+      ("OTEL_SDK_DISABLED", "true"),
+    ]);
+    assert_eq!(
+      OtelConfig::from_env_vars(|name| disabled
+        .get(name)
+        .map(|v| v.to_string())),
+      None
+    );
+  }
+
+  #[test]
+  fn exports_metrics_and_batched_spans() {
+    let (endpoint, received) = start_fake_collector();
+    let recorder = OtelRecorder::new(test_config(endpoint));
+    for _ in 0..3 {
+      record_call(&recorder, "op_read", true);
+    }
+    record_call(&recorder, "op_connect", false);
+    recorder.record_op(
+      "op_sync",
+      OpMetricsEvent::Dispatched,
+      OpMetricsSource::Slow,
+    );
+    recorder.record_op(
+      "op_sync",
+      OpMetricsEvent::Completed,
+      OpMetricsSource::Slow,
+    );
+    recorder.set_gauges(Duration::from_millis(7), 4);
+    run(recorder.flush());
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].path, "/v1/traces");
+    assert_eq!(received[1].path, "/v1/metrics");
+    for request in received.iter() {
+      assert!(request.headers.contains(&(
+        "content-type".to_string(),
+        "application/x-protobuf".to_string()
+      )));
+      assert!(request
+        .headers
+        .contains(&("x-api-key".to_string(), "secret".to_string())));
+    }
+
+    // All spans are batched into one request. Sync ops get no spans.
+    let traces =
+      proto::ExportTraceServiceRequest::decode(&*received[0].body).unwrap();
+    let resource = traces.resource_spans[0].resource.as_ref().unwrap();
+    assert_eq!(
+      resource.attributes,
+      vec![proto::KeyValue::string("service.name", "test-service")]
+    );
+    let spans = &traces.resource_spans[0].scope_spans[0].spans;
+    assert_eq!(spans.len(), 4);
+    assert_eq!(
+      spans.iter().filter(|span| span.name == "op_read").count(),
+      3
+    );
+    let failed = spans.iter().find(|span| span.name == "op_connect").unwrap();
+    assert_eq!(
+      failed.status.as_ref().unwrap().code,
+      proto::STATUS_CODE_ERROR
+    );
+    assert_eq!(failed.trace_id.len(), 16);
+    assert_eq!(failed.span_id.len(), 8);
+    assert!(failed.end_time_unix_nano >= failed.start_time_unix_nano);
+
+    let metrics =
+      proto::ExportMetricsServiceRequest::decode(&*received[1].body).unwrap();
+    assert_eq!(
+      int_points(metric(&metrics, "deno.op.calls")),
+      vec![
+        ("op_connect".to_string(), 1),
+        ("op_read".to_string(), 3),
+        ("op_sync".to_string(), 1)
+      ]
+    );
+    assert_eq!(
+      int_points(metric(&metrics, "deno.op.errors")),
+      vec![("op_connect".to_string(), 1)]
+    );
+    let Some(proto::metric::Data::Histogram(histogram)) =
+      &metric(&metrics, "deno.op.duration").data
+    else {
+      panic!("deno.op.duration is not a histogram");
+    };
+    let read = histogram
+      .data_points
+      .iter()
+      .find(|point| {
+        point.attributes[0].value
+          == Some(proto::AnyValue {
+            value: Some(proto::any_value::Value::StringValue("op_read".into())),
+          })
+      })
+      .unwrap();
+    assert_eq!(read.count, 3);
+    assert_eq!(read.bucket_counts.iter().sum::<u64>(), 3);
+    assert_eq!(read.bucket_counts.len(), read.explicit_bounds.len() + 1);
+    assert_eq!(
+      int_points(metric(&metrics, "deno.resources")),
+      vec![(String::new(), 4)]
+    );
+    assert!(matches!(
+      metric(&metrics, "deno.event_loop.lag").data,
+      Some(proto::metric::Data::Gauge(_))
+    ));
+    assert_eq!(
+      int_points(metric(&metrics, "deno.otel.dropped_spans")),
+      vec![(String::new(), 0)]
+    );
+  }
+
+  #[test]
+  fn flush_sends_spans_recorded_since_last_export() {
+    let (endpoint, received) = start_fake_collector();
+    let recorder = OtelRecorder::new(test_config(endpoint));
+    record_call(&recorder, "op_read", true);
+    recorder.export();
+    record_call(&recorder, "op_write", true);
+    run(recorder.flush());
+
+    let received = received.lock().unwrap();
+    let span_names = received
+      .iter()
+      .filter(|request| request.path == "/v1/traces")
+      .map(|request| {
+        let traces =
+          proto::ExportTraceServiceRequest::decode(&*request.body).unwrap();
+        traces.resource_spans[0].scope_spans[0]
+          .spans
+          .iter()
+          .map(|span| span.name.clone())
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(span_names, vec![vec!["op_read"], vec!["op_write"]]);
+    assert_eq!(
+      received
+        .iter()
+        .filter(|request| request.path == "/v1/metrics")
+        .count(),
+      2
+    );
+  }
+
+  #[test]
+  fn span_queue_is_bounded() {
+    let (endpoint, received) = start_fake_collector();
+    let recorder = OtelRecorder::new(OtelConfig {
+      max_queued_spans: 2,
+      ..test_config(endpoint)
+    });
+    for _ in 0..5 {
+      record_call(&recorder, "op_read", true);
+    }
+    assert_eq!(recorder.dropped().spans.load(Ordering::Relaxed), 3);
+    run(recorder.flush());
+
+    let received = received.lock().unwrap();
+    let traces =
+      proto::ExportTraceServiceRequest::decode(&*received[0].body).unwrap();
+    assert_eq!(traces.resource_spans[0].scope_spans[0].spans.len(), 2);
+  }
+
+  #[test]
+  fn unreachable_collector_only_counts_drops() {
+    // Bind and drop a listener to get a port nothing listens on.
+    let port = TcpListener::bind("127.0.0.1:0")
+      .unwrap()
+      .local_addr()
+      .unwrap()
+      .port();
+    let recorder =
+      OtelRecorder::new(test_config(format!("http://127.0.0.1:{port}")));
+    record_call(&recorder, "op_read", true);
+    record_call(&recorder, "op_read", true);
+    run(recorder.flush());
+
+    let dropped = recorder.dropped();
+    assert_eq!(dropped.requests.load(Ordering::Relaxed), 2);
+    assert_eq!(dropped.spans.load(Ordering::Relaxed), 2);
+  }
+}
@@ -0,0 +1,247 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The subset of the OpenTelemetry protocol (OTLP) messages used by the
+//! exporter, as of opentelemetry-proto v1.3. Field tags match
+//! `opentelemetry/proto/{common,resource,metrics,trace}/v1/*.proto`, so the
+//! encoded requests can be decoded by any OTLP/HTTP collector. Enum fields
+//! are plain `int32`s, which encode the same as proto enums.
+
+// Most messages can't be `Eq` because of their float fields; keep them alike.
+#![allow(clippy::derive_partial_eq_without_eq)]
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AnyValue {
+  #[prost(oneof = "any_value::Value", tags = "1, 2, 3, 4")]
+  pub value: Option<any_value::Value>,
+}
+
+pub mod any_value {
+  #[derive(Clone, PartialEq, prost::Oneof)]
+  pub enum Value {
+    #[prost(string, tag = "1")]
+    StringValue(String),
+    #[prost(bool, tag = "2")]
+    BoolValue(bool),
+    #[prost(int64, tag = "3")]
+    IntValue(i64),
+    #[prost(double, tag = "4")]
+    DoubleValue(f64),
+  }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct KeyValue {
+  #[prost(string, tag = "1")]
+  pub key: String,
+  #[prost(message, optional, tag = "2")]
+  pub value: Option<AnyValue>,
+}
+
+impl KeyValue {
+  pub fn string(key: &str, value: &str) -> Self {
+    Self {
+      key: key.to_string(),
+      value: Some(AnyValue {
+        value: Some(any_value::Value::StringValue(value.to_string())),
+      }),
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct InstrumentationScope {
+  #[prost(string, tag = "1")]
+  pub name: String,
+  #[prost(string, tag = "2")]
+  pub version: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Resource {
+  #[prost(message, repeated, tag = "1")]
+  pub attributes: Vec<KeyValue>,
+  #[prost(uint32, tag = "2")]
+  pub dropped_attributes_count: u32,
+}
+
+// Metrics
+
+pub const AGGREGATION_TEMPORALITY_CUMULATIVE: i32 = 2;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportMetricsServiceRequest {
+  #[prost(message, repeated, tag = "1")]
+  pub resource_metrics: Vec<ResourceMetrics>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResourceMetrics {
+  #[prost(message, optional, tag = "1")]
+  pub resource: Option<Resource>,
+  #[prost(message, repeated, tag = "2")]
+  pub scope_metrics: Vec<ScopeMetrics>,
+  #[prost(string, tag = "3")]
+  pub schema_url: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ScopeMetrics {
+  #[prost(message, optional, tag = "1")]
+  pub scope: Option<InstrumentationScope>,
+  #[prost(message, repeated, tag = "2")]
+  pub metrics: Vec<Metric>,
+  #[prost(string, tag = "3")]
+  pub schema_url: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Metric {
+  #[prost(string, tag = "1")]
+  pub name: String,
+  #[prost(string, tag = "2")]
+  pub description: String,
+  #[prost(string, tag = "3")]
+  pub unit: String,
+  #[prost(oneof = "metric::Data", tags = "5, 7, 9")]
+  pub data: Option<metric::Data>,
+}
+
+pub mod metric {
+  #[derive(Clone, PartialEq, prost::Oneof)]
+  pub enum Data {
+    #[prost(message, tag = "5")]
+    Gauge(super::Gauge),
+    #[prost(message, tag = "7")]
+    Sum(super::Sum),
+    #[prost(message, tag = "9")]
+    Histogram(super::Histogram),
+  }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Gauge {
+  #[prost(message, repeated, tag = "1")]
+  pub data_points: Vec<NumberDataPoint>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Sum {
+  #[prost(message, repeated, tag = "1")]
+  pub data_points: Vec<NumberDataPoint>,
+  #[prost(int32, tag = "2")]
+  pub aggregation_temporality: i32,
+  #[prost(bool, tag = "3")]
+  pub is_monotonic: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Histogram {
+  #[prost(message, repeated, tag = "1")]
+  pub data_points: Vec<HistogramDataPoint>,
+  #[prost(int32, tag = "2")]
+  pub aggregation_temporality: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NumberDataPoint {
+  #[prost(message, repeated, tag = "7")]
+  pub attributes: Vec<KeyValue>,
+  #[prost(fixed64, tag = "2")]
+  pub start_time_unix_nano: u64,
+  #[prost(fixed64, tag = "3")]
+  pub time_unix_nano: u64,
+  #[prost(oneof = "number_data_point::Value", tags = "4, 6")]
+  pub value: Option<number_data_point::Value>,
+}
+
+pub mod number_data_point {
+  #[derive(Clone, PartialEq, prost::Oneof)]
+  pub enum Value {
+    #[prost(double, tag = "4")]
+    AsDouble(f64),
+    #[prost(sfixed64, tag = "6")]
+    AsInt(i64),
+  }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HistogramDataPoint {
+  #[prost(message, repeated, tag = "9")]
+  pub attributes: Vec<KeyValue>,
+  #[prost(fixed64, tag = "2")]
+  pub start_time_unix_nano: u64,
+  #[prost(fixed64, tag = "3")]
+  pub time_unix_nano: u64,
+  #[prost(fixed64, tag = "4")]
+  pub count: u64,
+  #[prost(double, optional, tag = "5")]
+  pub sum: Option<f64>,
+  #[prost(fixed64, repeated, tag = "6")]
+  pub bucket_counts: Vec<u64>,
+  #[prost(double, repeated, tag = "7")]
+  pub explicit_bounds: Vec<f64>,
+  #[prost(double, optional, tag = "11")]
+  pub min: Option<f64>,
+  #[prost(double, optional, tag = "12")]
+  pub max: Option<f64>,
+}
+
+// Traces
+
+pub const SPAN_KIND_INTERNAL: i32 = 1;
+pub const STATUS_CODE_OK: i32 = 1;
+pub const STATUS_CODE_ERROR: i32 = 2;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportTraceServiceRequest {
+  #[prost(message, repeated, tag = "1")]
+  pub resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResourceSpans {
+  #[prost(message, optional, tag = "1")]
+  pub resource: Option<Resource>,
+  #[prost(message, repeated, tag = "2")]
+  pub scope_spans: Vec<ScopeSpans>,
+  #[prost(string, tag = "3")]
+  pub schema_url: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ScopeSpans {
+  #[prost(message, optional, tag = "1")]
+  pub scope: Option<InstrumentationScope>,
+  #[prost(message, repeated, tag = "2")]
+  pub spans: Vec<Span>,
+  #[prost(string, tag = "3")]
+  pub schema_url: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Span {
+  #[prost(bytes = "vec", tag = "1")]
+  pub trace_id: Vec<u8>,
+  #[prost(bytes = "vec", tag = "2")]
+  pub span_id: Vec<u8>,
+  #[prost(string, tag = "5")]
+  pub name: String,
+  #[prost(int32, tag = "6")]
+  pub kind: i32,
+  #[prost(fixed64, tag = "7")]
+  pub start_time_unix_nano: u64,
+  #[prost(fixed64, tag = "8")]
+  pub end_time_unix_nano: u64,
+  #[prost(message, repeated, tag = "9")]
+  pub attributes: Vec<KeyValue>,
+  #[prost(message, optional, tag = "15")]
+  pub status: Option<Status>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Status {
+  #[prost(string, tag = "2")]
+  pub message: String,
+  #[prost(int32, tag = "3")]
+  pub code: i32,
+}
@@ -0,0 +1,259 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Snapshots backing `--allow-read-frozen`.
+//!
+//! The listing of every frozen directory tree is recorded once at startup.
+//! Read checks for paths inside a frozen tree then consult the snapshot:
+//! paths that didn't exist at startup are reported as not found, paths that
+//! existed but were removed (or replaced by an entry of another kind) are
+//! reported as stale, and everything else is read from disk as usual.
+//!
+//! Paths are compared with their parent directory canonicalized, so that a
+//! path below a symlinked directory is found under the directory it points
+//! to, while a symlink itself is checked as a symlink.
+
+use std::collections::HashMap;
+use std::fs::FileType;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use deno_path_util::canonicalize_path_maybe_not_exists;
+use deno_path_util::normalize_path;
+use deno_path_util::strip_unc_prefix;
+use deno_terminal::colors;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrozenReadError {
+  #[error("No such file or directory (created after startup in a frozen read path): {}", .0.display())]
+  NotFound(PathBuf),
+  #[error("File was removed after startup from a frozen read path: {}", .0.display())]
+  StaleRead(PathBuf),
+}
+
+impl From<FrozenReadError> for io::Error {
+  fn from(err: FrozenReadError) -> Self {
+    let kind = match err {
+      FrozenReadError::NotFound(_) => io::ErrorKind::NotFound,
+      FrozenReadError::StaleRead(_) => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, err)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrozenEntryKind {
+  File,
+  Dir,
+  Symlink,
+}
+
+impl From<FileType> for FrozenEntryKind {
+  fn from(file_type: FileType) -> Self {
+    if file_type.is_symlink() {
+      Self::Symlink
+    } else if file_type.is_dir() {
+      Self::Dir
+    } else {
+      Self::File
+    }
+  }
+}
+
+fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+  canonicalize_path_maybe_not_exists(path, &|path| {
+    std::fs::canonicalize(path).map(strip_unc_prefix)
+  })
+}
+
+/// The path that reading `root` freezes: the canonical path of what it
+/// points to, which is where snapshots of it need to be taken.
+pub fn resolve_frozen_root(root: &Path) -> PathBuf {
+  canonicalize(root).unwrap_or_else(|_| normalize_path(root))
+}
+
+/// A path resolved by [`FrozenReadSnapshot::resolve`].
+#[derive(Debug)]
+pub enum FrozenPath {
+  /// Outside of the frozen roots.
+  Unfrozen,
+  /// Inside of a frozen root, with the kind of the entry now on disk, or
+  /// `None` when there is none.
+  Frozen(PathBuf, Option<FrozenEntryKind>),
+}
+
+#[derive(Debug, Default)]
+pub struct FrozenReadSnapshot {
+  roots: Vec<PathBuf>,
+  entries: HashMap<PathBuf, FrozenEntryKind>,
+  /// Log violations instead of failing the read.
+  audit: bool,
+}
+
+impl FrozenReadSnapshot {
+  pub fn new(audit: bool) -> Self {
+    Self {
+      audit,
+      ..Default::default()
+    }
+  }
+
+  /// Freeze everything below `root`, which must have been resolved with
+  /// [`resolve_frozen_root`]. The root itself must be added with `insert`
+  /// like any other entry.
+  pub fn add_root(&mut self, root: PathBuf) {
+    self.roots.push(root);
+  }
+
+  pub fn insert(&mut self, path: PathBuf, kind: FrozenEntryKind) {
+    self.entries.insert(path, kind);
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Check a path against the snapshot. Relative paths are resolved against
+  /// the current working directory.
+  pub fn check(&self, path: &Path) -> Result<(), FrozenReadError> {
+    self.check_resolved(self.resolve(path))
+  }
+
+  /// Resolves a path for [`Self::check_resolved`]: canonicalizes its parent
+  /// and looks up the kind of the entry. This touches the file system, so
+  /// callers holding locks do it before taking them.
+  pub fn resolve(&self, path: &Path) -> FrozenPath {
+    let path = if path.is_absolute() {
+      normalize_path(path)
+    } else {
+      match std::env::current_dir() {
+        Ok(cwd) => normalize_path(cwd.join(path)),
+        Err(_) => return FrozenPath::Unfrozen,
+      }
+    };
+    let path = match (path.parent(), path.file_name()) {
+      (Some(parent), Some(name)) => match canonicalize(parent) {
+        Ok(parent) => parent.join(name),
+        Err(_) => path,
+      },
+      _ => path,
+    };
+    if !self.roots.iter().any(|root| path.starts_with(root)) {
+      return FrozenPath::Unfrozen;
+    }
+    let kind = std::fs::symlink_metadata(&path)
+      .ok()
+      .map(|metadata| metadata.file_type().into());
+    FrozenPath::Frozen(path, kind)
+  }
+
+  /// Checks a path resolved with [`Self::resolve`] against the snapshot.
+  pub fn check_resolved(
+    &self,
+    path: FrozenPath,
+  ) -> Result<(), FrozenReadError> {
+    let FrozenPath::Frozen(path, kind) = path else {
+      return Ok(());
+    };
+    let result = match self.entries.get(&path) {
+      None => Err(FrozenReadError::NotFound(path)),
+      Some(frozen) if kind == Some(*frozen) => Ok(()),
+      Some(_) => Err(FrozenReadError::StaleRead(path)),
+    };
+    match result {
+      Err(err) if self.audit => {
+        log::warn!("{} {}", colors::yellow("Frozen read violation:"), err);
+        Ok(())
+      }
+      result => result,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn snapshot_of(root: &Path, audit: bool) -> FrozenReadSnapshot {
+    let root = resolve_frozen_root(root);
+    let root = root.as_path();
+    let mut snapshot = FrozenReadSnapshot::new(audit);
+    snapshot.add_root(root.to_path_buf());
+    for entry in std::fs::read_dir(root).unwrap() {
+      let entry = entry.unwrap();
+      snapshot.insert(entry.path(), entry.file_type().unwrap().into());
+    }
+    snapshot.insert(root.to_path_buf(), FrozenEntryKind::Dir);
+    snapshot
+  }
+
+  #[test]
+  fn check_frozen_read() {
+    let dir = std::env::temp_dir()
+      .join(format!("deno_frozen_read_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("kept.txt"), "a").unwrap();
+    std::fs::write(dir.join("removed.txt"), "b").unwrap();
+    let snapshot = snapshot_of(&dir, false);
+    assert_eq!(snapshot.len(), 3);
+
+    std::fs::write(dir.join("added.txt"), "c").unwrap();
+    std::fs::remove_file(dir.join("removed.txt")).unwrap();
+
+    assert!(snapshot.check(&dir).is_ok());
+    assert!(snapshot.check(&dir.join("kept.txt")).is_ok());
+    assert!(matches!(
+      snapshot.check(&dir.join("added.txt")),
+      Err(FrozenReadError::NotFound(_))
+    ));
+    assert!(matches!(
+      snapshot.check(&dir.join("removed.txt")),
+      Err(FrozenReadError::StaleRead(_))
+    ));
+    // Paths outside of the frozen roots are not affected.
+    assert!(snapshot.check(&std::env::temp_dir()).is_ok());
+
+    let mut audit = snapshot_of(&dir, true);
+    audit.insert(
+      resolve_frozen_root(&dir).join("removed.txt"),
+      FrozenEntryKind::File,
+    );
+    assert!(audit.check(&dir.join("removed.txt")).is_ok());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn check_paths_below_symlinked_dirs() {
+    let dir = std::env::temp_dir()
+      .join(format!("deno_frozen_read_symlink_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("real")).unwrap();
+    std::fs::write(dir.join("real/a.txt"), "a").unwrap();
+    std::os::unix::fs::symlink(dir.join("real"), dir.join("link")).unwrap();
+    let mut snapshot = snapshot_of(&dir, false);
+    for entry in std::fs::read_dir(dir.join("real")).unwrap() {
+      let entry = entry.unwrap();
+      snapshot.insert(
+        resolve_frozen_root(&entry.path()),
+        entry.file_type().unwrap().into(),
+      );
+    }
+
+    // read through the link, found where it points to
+    assert!(snapshot.check(&dir.join("link/a.txt")).is_ok());
+    // the link itself is still a symlink
+    assert!(snapshot.check(&dir.join("link")).is_ok());
+    std::fs::write(dir.join("real/b.txt"), "b").unwrap();
+    assert!(matches!(
+      snapshot.check(&dir.join("link/b.txt")),
+      Err(FrozenReadError::NotFound(_))
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -33,10 +33,15 @@ use std::path::PathBuf;
 use std::string::ToString;
 use std::sync::Arc;
 
+pub mod frozen_read;
 pub mod prompter;
+use frozen_read::FrozenPath;
+use frozen_read::FrozenReadSnapshot;
 use prompter::permission_prompt;
 use prompter::PERMISSION_EMOJI;
 
+pub use frozen_read::FrozenEntryKind;
+pub use frozen_read::FrozenReadError;
 pub use prompter::set_prompt_callbacks;
 pub use prompter::set_prompter;
 pub use prompter::PermissionPrompter;
@@ -1367,9 +1372,9 @@ impl SysDescriptor {
   pub fn parse(kind: String) -> Result<Self, AnyError> {
     match kind.as_str() {
       "hostname" | "osRelease" | "osUptime" | "loadavg"
-      | "networkInterfaces" | "systemMemoryInfo" | "uid" | "gid" | "cpus"
-      | "homedir" | "getegid" | "username" | "statfs" | "getPriority"
-      | "setPriority" => Ok(Self(kind)),
+      | "networkInterfaces" | "systemMemoryInfo" | "systemCpuInfo"
+      | "fdLimits" | "uid" | "gid" | "cpus" | "homedir" | "getegid"
+      | "username" | "statfs" | "getPriority" | "setPriority" => Ok(Self(kind)),
       _ => Err(type_error(format!("unknown system info kind \"{kind}\""))),
     }
   }
@@ -2058,6 +2063,7 @@ pub enum CheckSpecifierKind {
 pub struct PermissionsContainer {
   descriptor_parser: Arc<dyn PermissionDescriptorParser>,
   inner: Arc<Mutex<Permissions>>,
+  frozen_read: Option<Arc<FrozenReadSnapshot>>,
 }
 
 impl PermissionsContainer {
@@ -2068,6 +2074,35 @@ impl PermissionsContainer {
     Self {
       descriptor_parser,
       inner: Arc::new(Mutex::new(perms)),
+      frozen_read: None,
+    }
+  }
+
+  /// Make read checks consult `snapshot` for paths below its frozen roots.
+  /// Containers derived from this one share the snapshot.
+  pub fn with_frozen_read(mut self, snapshot: Arc<FrozenReadSnapshot>) -> Self {
+    self.frozen_read = Some(snapshot);
+    self
+  }
+
+  /// Resolves `path` for the frozen read check. This touches the file
+  /// system, so it must be done before taking the `inner` lock.
+  #[inline(always)]
+  fn resolve_frozen_read(&self, path: &Path) -> Option<FrozenPath> {
+    self
+      .frozen_read
+      .as_ref()
+      .map(|snapshot| snapshot.resolve(path))
+  }
+
+  #[inline(always)]
+  fn check_frozen_read(
+    &self,
+    path: Option<FrozenPath>,
+  ) -> Result<(), FrozenReadError> {
+    match (&self.frozen_read, path) {
+      (Some(snapshot), Some(path)) => snapshot.check_resolved(path),
+      _ => Ok(()),
     }
   }
 
@@ -2077,6 +2112,20 @@ impl PermissionsContainer {
     Self::new(descriptor_parser, Permissions::allow_all())
   }
 
+  /// A copy of the current permission state.
+  pub fn snapshot(&self) -> Permissions {
+    self.inner.lock().clone()
+  }
+
+  /// A new, independent container holding `perms` that shares this
+  /// container's descriptor parser.
+  pub fn with_permissions(&self, perms: Permissions) -> PermissionsContainer {
+    PermissionsContainer {
+      frozen_read: self.frozen_read.clone(),
+      ..PermissionsContainer::new(self.descriptor_parser.clone(), perms)
+    }
+  }
+
   pub fn create_child_permissions(
     &self,
     child_permissions_arg: ChildPermissionsArg,
@@ -2161,10 +2210,10 @@ impl PermissionsContainer {
         Ok(Some(self.descriptor_parser.parse_ffi_descriptor(text)?))
       })?;
 
-    Ok(PermissionsContainer::new(
-      self.descriptor_parser.clone(),
-      worker_perms,
-    ))
+    Ok(PermissionsContainer {
+      frozen_read: self.frozen_read.clone(),
+      ..PermissionsContainer::new(self.descriptor_parser.clone(), worker_perms)
+    })
   }
 
   #[inline(always)]
@@ -2227,6 +2276,19 @@ impl PermissionsContainer {
     path: &str,
     api_name: Option<&str>,
   ) -> Result<PathBuf, AnyError> {
+    if self.frozen_read.is_some() {
+      let desc = self.descriptor_parser.parse_path_query(path)?.into_read();
+      let frozen = self.resolve_frozen_read(&desc.0.resolved);
+      {
+        let mut inner = self.inner.lock();
+        let inner = &mut inner.read;
+        if !inner.is_allow_all() {
+          inner.check(&desc, api_name)?;
+        }
+      }
+      self.check_frozen_read(frozen)?;
+      return Ok(desc.0.resolved);
+    }
     let mut inner = self.inner.lock();
     let inner = &mut inner.read;
     if inner.is_allow_all() {
@@ -2245,9 +2307,11 @@ impl PermissionsContainer {
     path: &'a Path,
     api_name: Option<&str>,
   ) -> Result<Cow<'a, Path>, AnyError> {
+    let frozen = self.resolve_frozen_read(path);
     let mut inner = self.inner.lock();
     let inner = &mut inner.read;
     if inner.is_allow_all() {
+      self.check_frozen_read(frozen)?;
       Ok(Cow::Borrowed(path))
     } else {
       let desc = PathQueryDescriptor {
@@ -2256,6 +2320,7 @@ impl PermissionsContainer {
       }
       .into_read();
       inner.check(&desc, api_name)?;
+      self.check_frozen_read(frozen)?;
       Ok(Cow::Owned(desc.0.resolved))
     }
   }
@@ -37,6 +37,16 @@ impl deno_web::TimersPermission for Permissions {
   }
 }
 
+impl deno_crypto::CryptoPermissions for Permissions {
+  fn check_read<'a>(
+    &mut self,
+    _p: &'a Path,
+    _api_name: &str,
+  ) -> Result<Cow<'a, Path>, AnyError> {
+    unreachable!("snapshotting!")
+  }
+}
+
 impl deno_fetch::FetchPermissions for Permissions {
   fn check_net_url(
     &mut self,
@@ -53,6 +63,14 @@ impl deno_fetch::FetchPermissions for Permissions {
   ) -> Result<Cow<'a, Path>, AnyError> {
     unreachable!("snapshotting!")
   }
+
+  fn check_write<'a>(
+    &mut self,
+    _p: &'a Path,
+    _api_name: &str,
+  ) -> Result<Cow<'a, Path>, AnyError> {
+    unreachable!("snapshotting!")
+  }
 }
 
 impl deno_ffi::FfiPermissions for Permissions {
@@ -273,13 +291,17 @@ pub fn create_runtime_snapshot(
       None,
       None,
     ),
-    deno_webstorage::deno_webstorage::init_ops_and_esm(None),
-    deno_crypto::deno_crypto::init_ops_and_esm(None),
+    deno_webstorage::deno_webstorage::init_ops_and_esm(None, None, None),
+    deno_crypto::deno_crypto::init_ops_and_esm::<Permissions>(None),
     deno_broadcast_channel::deno_broadcast_channel::init_ops_and_esm(
       deno_broadcast_channel::InMemoryBroadcastChannel::default(),
     ),
     deno_ffi::deno_ffi::init_ops_and_esm::<Permissions>(),
-    deno_net::deno_net::init_ops_and_esm::<Permissions>(None, None),
+    deno_net::deno_net::init_ops_and_esm::<Permissions>(
+      None,
+      None,
+      Default::default(),
+    ),
     deno_tls::deno_tls::init_ops_and_esm(),
     deno_kv::deno_kv::init_ops_and_esm(
       deno_kv::sqlite::SqliteDbHandler::<Permissions>::new(None, None),
@@ -287,11 +309,12 @@ pub fn create_runtime_snapshot(
     ),
     deno_cron::deno_cron::init_ops_and_esm(
       deno_cron::local::LocalCronHandler::new(),
+      None,
     ),
     deno_napi::deno_napi::init_ops_and_esm::<Permissions>(),
     deno_http::deno_http::init_ops_and_esm::<DefaultHttpPropertyExtractor>(),
     deno_io::deno_io::init_ops_and_esm(Default::default()),
-    deno_fs::deno_fs::init_ops_and_esm::<Permissions>(fs.clone()),
+    deno_fs::deno_fs::init_ops_and_esm::<Permissions>(fs.clone(), None),
     deno_node::deno_node::init_ops_and_esm::<Permissions>(None, fs.clone()),
     runtime::init_ops_and_esm(),
     ops::runtime::deno_runtime::init_ops("deno:runtime".parse().unwrap()),
@@ -306,6 +329,9 @@ pub fn create_runtime_snapshot(
     ops::signal::deno_signal::init_ops(),
     ops::tty::deno_tty::init_ops(),
     ops::http::deno_http_runtime::init_ops(),
+    ops::blob_store::deno_blob_store::init_ops(None),
+    ops::storage_archive::deno_storage_archive::init_ops(None),
+    ops::realm::deno_realm::init_ops(),
     ops::bootstrap::deno_bootstrap::init_ops(Some(snapshot_options)),
     ops::web_worker::deno_web_worker::init_ops(),
   ];
@@ -0,0 +1,220 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Source mapping of arbitrary stack frame locations, for error reporting
+//! libraries that collect frames themselves. The source maps come from the
+//! worker's module loader, like the ones applied to `error.stack`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use deno_core::sourcemap::SourceMap;
+use deno_core::ModuleLoader;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// A position in a generated file. Lines and columns are 1-based, like in
+/// V8 call sites.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceLocation {
+  pub file_name: String,
+  pub line_number: u32,
+  pub column_number: u32,
+}
+
+/// A [`SourceLocation`] mapped to the original source. It is the location
+/// itself if the file has no source map.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappedSourceLocation {
+  pub file_name: String,
+  pub line_number: u32,
+  pub column_number: u32,
+  /// The text of the original line, if the loader still has the source.
+  pub source_line: Option<String>,
+}
+
+/// The source maps of a worker, parsed on first use.
+pub struct SourceMapCache {
+  loader: Rc<dyn ModuleLoader>,
+  maps: RefCell<HashMap<String, Option<Rc<SourceMap>>>>,
+}
+
+impl SourceMapCache {
+  pub fn new(loader: Rc<dyn ModuleLoader>) -> Self {
+    Self {
+      loader,
+      maps: Default::default(),
+    }
+  }
+
+  pub fn apply(&self, location: SourceLocation) -> MappedSourceLocation {
+    let mapped = self.source_map(&location.file_name).and_then(|map| {
+      let token = map.lookup_token(
+        location.line_number.saturating_sub(1),
+        location.column_number.saturating_sub(1),
+      )?;
+      Some((
+        token
+          .get_source()
+          .map(ToString::to_string)
+          .unwrap_or_else(|| location.file_name.clone()),
+        token.get_src_line() + 1,
+        token.get_src_col() + 1,
+      ))
+    });
+    let (file_name, line_number, column_number) = mapped.unwrap_or((
+      location.file_name,
+      location.line_number,
+      location.column_number,
+    ));
+    let source_line = self
+      .loader
+      .get_source_mapped_source_line(
+        &file_name,
+        line_number.saturating_sub(1) as usize,
+      )
+      .filter(|line| !line.is_empty());
+    MappedSourceLocation {
+      file_name,
+      line_number,
+      column_number,
+      source_line,
+    }
+  }
+
+  fn source_map(&self, file_name: &str) -> Option<Rc<SourceMap>> {
+    if let Some(map) = self.maps.borrow().get(file_name) {
+      return map.clone();
+    }
+    let map = self
+      .loader
+      .get_source_map(file_name)
+      .and_then(|map| SourceMap::from_slice(&map).ok())
+      .map(Rc::new);
+    self
+      .maps
+      .borrow_mut()
+      .insert(file_name.to_string(), map.clone());
+    map
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+
+  use deno_core::error::AnyError;
+  use deno_core::ModuleLoadResponse;
+  use deno_core::ModuleSpecifier;
+  use deno_core::RequestedModuleType;
+  use deno_core::ResolutionKind;
+
+  use super::*;
+
+  /// Maps line 1 column 1 of `file:///generated.js` to line 5 column 3 of
+  /// `file:///original.ts`, and line 2 column 3 to line 7 column 5.
+  const SOURCE_MAP: &str = r#"{"version":3,"sources":["file:///original.ts"],"names":[],"mappings":"AAIE;EAEE"}"#;
+
+  #[derive(Default)]
+  struct TestLoader {
+    source_map_requests: Cell<usize>,
+  }
+
+  impl ModuleLoader for TestLoader {
+    fn resolve(
+      &self,
+      specifier: &str,
+      referrer: &str,
+      _kind: ResolutionKind,
+    ) -> Result<ModuleSpecifier, AnyError> {
+      Ok(deno_core::resolve_import(specifier, referrer)?)
+    }
+
+    fn load(
+      &self,
+      _specifier: &ModuleSpecifier,
+      _maybe_referrer: Option<&ModuleSpecifier>,
+      _is_dynamic: bool,
+      _requested_module_type: RequestedModuleType,
+    ) -> ModuleLoadResponse {
+      unreachable!()
+    }
+
+    fn get_source_map(&self, file_name: &str) -> Option<Vec<u8>> {
+      self
+        .source_map_requests
+        .set(self.source_map_requests.get() + 1);
+      (file_name == "file:///generated.js")
+        .then(|| SOURCE_MAP.as_bytes().to_vec())
+    }
+
+    fn get_source_mapped_source_line(
+      &self,
+      file_name: &str,
+      line_number: usize,
+    ) -> Option<String> {
+      (file_name == "file:///original.ts" && line_number == 6)
+        .then(|| "    throw new Error();".to_string())
+    }
+  }
+
+  fn location(
+    file_name: &str,
+    line_number: u32,
+    column_number: u32,
+  ) -> SourceLocation {
+    SourceLocation {
+      file_name: file_name.to_string(),
+      line_number,
+      column_number,
+    }
+  }
+
+  #[test]
+  fn maps_locations() {
+    let loader = Rc::new(TestLoader::default());
+    let cache = SourceMapCache::new(loader.clone());
+
+    assert_eq!(
+      cache.apply(location("file:///generated.js", 2, 6)),
+      MappedSourceLocation {
+        file_name: "file:///original.ts".to_string(),
+        line_number: 7,
+        column_number: 5,
+        source_line: Some("    throw new Error();".to_string()),
+      }
+    );
+    assert_eq!(
+      cache.apply(location("file:///generated.js", 1, 1)),
+      MappedSourceLocation {
+        file_name: "file:///original.ts".to_string(),
+        line_number: 5,
+        column_number: 3,
+        source_line: None,
+      }
+    );
+    // parsed once
+    assert_eq!(loader.source_map_requests.get(), 1);
+  }
+
+  #[test]
+  fn keeps_unmapped_locations() {
+    let loader = Rc::new(TestLoader::default());
+    let cache = SourceMapCache::new(loader.clone());
+
+    for _ in 0..2 {
+      assert_eq!(
+        cache.apply(location("file:///plain.js", 3, 4)),
+        MappedSourceLocation {
+          file_name: "file:///plain.js".to_string(),
+          line_number: 3,
+          column_number: 4,
+          source_line: None,
+        }
+      );
+    }
+    assert_eq!(loader.source_map_requests.get(), 1);
+  }
+}
@@ -10,6 +10,8 @@ use deno_core::futures::future::poll_fn;
 use deno_core::futures::stream::StreamExt;
 use deno_core::futures::task::AtomicWaker;
 use deno_core::located_script_name;
+use deno_core::merge_op_metrics;
+use deno_core::parking_lot::Mutex;
 use deno_core::serde::Deserialize;
 use deno_core::serde::Serialize;
 use deno_core::serde_json::json;
@@ -30,9 +32,12 @@ use deno_core::RuntimeOptions;
 use deno_core::SharedArrayBufferStore;
 use deno_cron::local::LocalCronHandler;
 use deno_fs::FileSystem;
+use deno_fs::SharedFsLimits;
 use deno_http::DefaultHttpPropertyExtractor;
 use deno_io::Stdio;
 use deno_kv::dynamic::MultiBackendDbHandler;
+use deno_net::resolver::DnsResolver;
+use deno_net::resolver::DnsResolverConfig;
 use deno_node::NodeExtInitServices;
 use deno_permissions::PermissionsContainer;
 use deno_terminal::colors;
@@ -42,6 +47,7 @@ use deno_web::create_entangled_message_port;
 use deno_web::serialize_transferables;
 use deno_web::BlobStore;
 use deno_web::JsMessageData;
+use deno_web::LifecycleEvent;
 use deno_web::MessagePort;
 use deno_web::Transferable;
 use log::debug;
@@ -54,18 +60,28 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
-
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::Notify;
+
+use crate::cpu_time::CpuTimeCounter;
+use crate::cpu_time::CpuTimeQuota;
+use crate::cpu_time::CpuTimeSampler;
+use crate::deprecated_ops::DeprecatedOpsOptions;
 use crate::inspector_server::InspectorServer;
 use crate::ops;
+use crate::ops::blob_store::ContentStore;
 use crate::ops::process::NpmProcessStateProviderRc;
 use crate::ops::worker_host::WorkersTable;
 use crate::shared::maybe_transpile_source;
 use crate::shared::runtime;
+use crate::source_map::SourceMapCache;
 use crate::tokio_util::create_and_run_current_thread;
 use crate::worker::create_op_metrics;
 use crate::worker::import_meta_resolve_callback;
 use crate::worker::validate_import_attributes_callback;
 use crate::worker::FormatJsErrorFn;
+use crate::worker::PreMainModuleArgs;
 use crate::BootstrapOptions;
 
 pub struct WorkerMetadata {
@@ -150,6 +166,38 @@ impl Serialize for WorkerControlEvent {
   }
 }
 
+/// The state of a graceful termination requested with
+/// [`WebWorkerHandle::terminate_gracefully()`], shared by the worker and its
+/// parent.
+#[derive(Default)]
+struct GracefulShutdown {
+  /// When the worker gets terminated forcibly. Set once the parent asked the
+  /// worker to shut down.
+  deadline: Mutex<Option<Instant>>,
+  /// Whether "beforeterminate" was dispatched in the worker.
+  dispatched: AtomicBool,
+  /// Notified by the worker's thread when the worker finished.
+  completed: Notify,
+}
+
+impl GracefulShutdown {
+  fn is_requested(&self) -> bool {
+    self.deadline.lock().is_some()
+  }
+
+  /// Returns the time left before the deadline, the first time it is called
+  /// after shutdown was requested.
+  fn take_request(&self) -> Option<Duration> {
+    let remaining = self.remaining()?;
+    (!self.dispatched.swap(true, Ordering::SeqCst)).then_some(remaining)
+  }
+
+  fn remaining(&self) -> Option<Duration> {
+    let deadline = (*self.deadline.lock())?;
+    Some(deadline.saturating_duration_since(Instant::now()))
+  }
+}
+
 // Channels used for communication with worker's parent
 #[derive(Clone)]
 pub struct WebWorkerInternalHandle {
@@ -159,6 +207,7 @@ pub struct WebWorkerInternalHandle {
   termination_signal: Arc<AtomicBool>,
   has_terminated: Arc<AtomicBool>,
   terminate_waker: Arc<AtomicWaker>,
+  graceful_shutdown: Arc<GracefulShutdown>,
   isolate_handle: v8::IsolateHandle,
   pub name: String,
   pub worker_type: WebWorkerType,
@@ -228,7 +277,9 @@ pub struct SendableWebWorkerHandle {
   termination_signal: Arc<AtomicBool>,
   has_terminated: Arc<AtomicBool>,
   terminate_waker: Arc<AtomicWaker>,
+  graceful_shutdown: Arc<GracefulShutdown>,
   isolate_handle: v8::IsolateHandle,
+  cpu_time: Arc<CpuTimeCounter>,
 }
 
 impl From<SendableWebWorkerHandle> for WebWorkerHandle {
@@ -239,7 +290,9 @@ impl From<SendableWebWorkerHandle> for WebWorkerHandle {
       termination_signal: handle.termination_signal,
       has_terminated: handle.has_terminated,
       terminate_waker: handle.terminate_waker,
+      graceful_shutdown: handle.graceful_shutdown,
       isolate_handle: handle.isolate_handle,
+      cpu_time: handle.cpu_time,
     }
   }
 }
@@ -258,7 +311,9 @@ pub struct WebWorkerHandle {
   termination_signal: Arc<AtomicBool>,
   has_terminated: Arc<AtomicBool>,
   terminate_waker: Arc<AtomicWaker>,
+  graceful_shutdown: Arc<GracefulShutdown>,
   isolate_handle: v8::IsolateHandle,
+  cpu_time: Arc<CpuTimeCounter>,
 }
 
 impl WebWorkerHandle {
@@ -270,6 +325,12 @@ impl WebWorkerHandle {
     receiver.next().await
   }
 
+  /// Returns the CPU time counter of the worker, which is updated by the
+  /// worker's thread.
+  pub fn cpu_time_counter(&self) -> Arc<CpuTimeCounter> {
+    self.cpu_time.clone()
+  }
+
   /// Terminate the worker
   /// This function will set the termination signal, close the message channel,
   /// and schedule to terminate the isolate after two seconds.
@@ -304,24 +365,56 @@ impl WebWorkerHandle {
       });
     }
   }
+
+  /// Asks the worker to shut down: "beforeterminate" is dispatched in the
+  /// worker, which keeps running until its event loop is done or it calls
+  /// `close()`, and then closes its resources. If that doesn't happen within
+  /// `deadline`, its isolate is terminated.
+  ///
+  /// Returns whether the worker shut down before the deadline.
+  pub async fn terminate_gracefully(self, deadline: Duration) -> bool {
+    *self.graceful_shutdown.deadline.lock() = Some(Instant::now() + deadline);
+    self.terminate_waker.wake();
+
+    let graceful = tokio::time::timeout(
+      deadline,
+      self.graceful_shutdown.completed.notified(),
+    )
+    .await
+    .is_ok();
+
+    if !graceful {
+      self.termination_signal.store(true, Ordering::SeqCst);
+      // A worker's isolate can only be terminated once.
+      if !self.has_terminated.swap(true, Ordering::SeqCst) {
+        self.isolate_handle.terminate_execution();
+      }
+      self.terminate_waker.wake();
+    }
+    self.port.disentangle();
+    graceful
+  }
 }
 
 fn create_handles(
   isolate_handle: v8::IsolateHandle,
   name: String,
   worker_type: WebWorkerType,
+  cpu_time: Arc<CpuTimeCounter>,
 ) -> (WebWorkerInternalHandle, SendableWebWorkerHandle) {
   let (parent_port, worker_port) = create_entangled_message_port();
   let (ctrl_tx, ctrl_rx) = mpsc::channel::<WorkerControlEvent>(1);
   let termination_signal = Arc::new(AtomicBool::new(false));
   let has_terminated = Arc::new(AtomicBool::new(false));
   let terminate_waker = Arc::new(AtomicWaker::new());
+  let graceful_shutdown = Arc::new(GracefulShutdown::default());
   let internal_handle = WebWorkerInternalHandle {
     name,
     port: Rc::new(parent_port),
     termination_signal: termination_signal.clone(),
     has_terminated: has_terminated.clone(),
     terminate_waker: terminate_waker.clone(),
+    graceful_shutdown: graceful_shutdown.clone(),
     isolate_handle: isolate_handle.clone(),
     cancel: CancelHandle::new_rc(),
     sender: ctrl_tx,
@@ -333,7 +426,9 @@ fn create_handles(
     termination_signal,
     has_terminated,
     terminate_waker,
+    graceful_shutdown,
     isolate_handle,
+    cpu_time,
   };
   (internal_handle, external_handle)
 }
@@ -341,9 +436,11 @@ fn create_handles(
 pub struct WebWorkerServiceOptions {
   pub blob_store: Arc<BlobStore>,
   pub broadcast_channel: InMemoryBroadcastChannel,
+  pub content_store: Option<Arc<ContentStore>>,
   pub compiled_wasm_module_store: Option<CompiledWasmModuleStore>,
   pub feature_checker: Arc<FeatureChecker>,
   pub fs: Arc<dyn FileSystem>,
+  pub fs_limits: Option<SharedFsLimits>,
   pub maybe_inspector_server: Option<Arc<InspectorServer>>,
   pub module_loader: Rc<dyn ModuleLoader>,
   pub node_services: Option<NodeExtInitServices>,
@@ -361,7 +458,12 @@ pub struct WebWorkerOptions {
   pub extensions: Vec<Extension>,
   pub startup_snapshot: Option<&'static [u8]>,
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
+  /// How `Deno.connect()`, `Deno.resolveDns()` and `fetch()` resolve names.
+  pub dns_resolver: DnsResolverConfig,
   pub seed: Option<u64>,
+  /// If Some, `on_exceeded` is called once the worker has used more CPU time
+  /// than the budget.
+  pub cpu_time_quota: Option<CpuTimeQuota>,
   pub create_web_worker_cb: Arc<ops::worker_host::CreateWebWorkerCb>,
   pub format_js_error_fn: Option<Arc<FormatJsErrorFn>>,
   pub worker_type: WebWorkerType,
@@ -369,6 +471,9 @@ pub struct WebWorkerOptions {
   pub cache_storage_dir: Option<std::path::PathBuf>,
   pub stdio: Stdio,
   pub strace_ops: Option<Vec<String>>,
+  /// If Some, calls of deprecated ops are counted and reported, or rejected
+  /// in strict mode.
+  pub deprecated_ops: Option<DeprecatedOpsOptions>,
   pub close_on_idle: bool,
   pub maybe_worker_metadata: Option<WorkerMetadata>,
 }
@@ -381,6 +486,7 @@ pub struct WebWorker {
   id: WorkerId,
   pub js_runtime: JsRuntime,
   pub name: String,
+  cpu_time: Rc<CpuTimeSampler>,
   close_on_idle: bool,
   has_executed_main_module: bool,
   internal_handle: WebWorkerInternalHandle,
@@ -389,6 +495,8 @@ pub struct WebWorker {
   poll_for_messages_fn: Option<v8::Global<v8::Value>>,
   has_message_event_listener_fn: Option<v8::Global<v8::Value>>,
   bootstrap_fn_global: Option<v8::Global<v8::Function>>,
+  dispatch_lifecycle_event_fn_global: v8::Global<v8::Function>,
+  dispatch_before_terminate_fn_global: v8::Global<v8::Function>,
   // Consumed when `bootstrap_fn` is called
   maybe_worker_metadata: Option<WorkerMetadata>,
 }
@@ -421,6 +529,10 @@ impl WebWorker {
 
     // Permissions: many ops depend on this
     let enable_testing_features = options.bootstrap.enable_testing_features;
+    let dns_resolver = DnsResolver::new(
+      options.dns_resolver.clone(),
+      services.root_cert_store_provider.clone(),
+    );
     let create_cache = options.cache_storage_dir.map(|storage_dir| {
       let create_cache_fn = move || SqliteBackedCache::new(storage_dir.clone());
       CreateCache(Arc::new(create_cache_fn))
@@ -448,6 +560,7 @@ impl WebWorker {
             .unsafely_ignore_certificate_errors
             .clone(),
           file_fetch_handler: Rc::new(deno_fetch::FsFetchHandler),
+          dns_resolver: dns_resolver.clone(),
           ..Default::default()
         },
       ),
@@ -459,8 +572,11 @@ impl WebWorker {
         services.root_cert_store_provider.clone(),
         options.unsafely_ignore_certificate_errors.clone(),
       ),
-      deno_webstorage::deno_webstorage::init_ops_and_esm(None).disable(),
-      deno_crypto::deno_crypto::init_ops_and_esm(options.seed),
+      deno_webstorage::deno_webstorage::init_ops_and_esm(None, None, None)
+        .disable(),
+      deno_crypto::deno_crypto::init_ops_and_esm::<PermissionsContainer>(
+        options.seed,
+      ),
       deno_broadcast_channel::deno_broadcast_channel::init_ops_and_esm(
         services.broadcast_channel,
       ),
@@ -468,6 +584,7 @@ impl WebWorker {
       deno_net::deno_net::init_ops_and_esm::<PermissionsContainer>(
         services.root_cert_store_provider.clone(),
         options.unsafely_ignore_certificate_errors.clone(),
+        dns_resolver,
       ),
       deno_tls::deno_tls::init_ops_and_esm(),
       deno_kv::deno_kv::init_ops_and_esm(
@@ -486,12 +603,14 @@ impl WebWorker {
         ),
         deno_kv::KvConfig::builder().build(),
       ),
-      deno_cron::deno_cron::init_ops_and_esm(LocalCronHandler::new()),
+      // Schedules in workers aren't persisted.
+      deno_cron::deno_cron::init_ops_and_esm(LocalCronHandler::new(), None),
       deno_napi::deno_napi::init_ops_and_esm::<PermissionsContainer>(),
       deno_http::deno_http::init_ops_and_esm::<DefaultHttpPropertyExtractor>(),
       deno_io::deno_io::init_ops_and_esm(Some(options.stdio)),
       deno_fs::deno_fs::init_ops_and_esm::<PermissionsContainer>(
         services.fs.clone(),
+        services.fs_limits,
       ),
       deno_node::deno_node::init_ops_and_esm::<PermissionsContainer>(
         services.node_services,
@@ -512,6 +631,11 @@ impl WebWorker {
       ops::signal::deno_signal::init_ops_and_esm(),
       ops::tty::deno_tty::init_ops_and_esm(),
       ops::http::deno_http_runtime::init_ops_and_esm(),
+      ops::blob_store::deno_blob_store::init_ops_and_esm(
+        services.content_store,
+      ),
+      ops::storage_archive::deno_storage_archive::init_ops_and_esm(None),
+      ops::realm::deno_realm::init_ops_and_esm(),
       ops::bootstrap::deno_bootstrap::init_ops_and_esm(
         if options.startup_snapshot.is_some() {
           None
@@ -542,16 +666,36 @@ impl WebWorker {
     }
 
     extensions.extend(std::mem::take(&mut options.extensions));
+    if let Some(deprecated_ops) = &options.deprecated_ops {
+      deprecated_ops.apply(&mut extensions);
+    }
 
     #[cfg(feature = "only_snapshotted_js_sources")]
     options.startup_snapshot.as_ref().expect("A user snapshot was not provided, even though 'only_snapshotted_js_sources' is used.");
 
     // Get our op metrics
-    let (op_summary_metrics, op_metrics_factory_fn) = create_op_metrics(
+    let (op_summary_metrics, mut op_metrics_factory_fn) = create_op_metrics(
       options.bootstrap.enable_op_summary_metrics,
       options.strace_ops,
     );
+    let cpu_time = CpuTimeSampler::new(options.cpu_time_quota);
+    if cpu_time.has_quota() {
+      let cpu_time_metrics = cpu_time.op_metrics_factory_fn();
+      op_metrics_factory_fn = Some(match op_metrics_factory_fn {
+        Some(f) => merge_op_metrics(f, cpu_time_metrics),
+        None => cpu_time_metrics,
+      });
+    }
+    if let Some(deprecated_ops) = &options.deprecated_ops {
+      let deprecated_ops_metrics = deprecated_ops.op_metrics_factory_fn();
+      op_metrics_factory_fn = Some(match op_metrics_factory_fn {
+        Some(f) => merge_op_metrics(f, deprecated_ops_metrics),
+        None => deprecated_ops_metrics,
+      });
+    }
 
+    let source_maps =
+      Rc::new(SourceMapCache::new(services.module_loader.clone()));
     let mut js_runtime = JsRuntime::new(RuntimeOptions {
       module_loader: Some(services.module_loader),
       startup_snapshot: options.startup_snapshot,
@@ -578,6 +722,14 @@ impl WebWorker {
     if let Some(op_summary_metrics) = op_summary_metrics {
       js_runtime.op_state().borrow_mut().put(op_summary_metrics);
     }
+    if let Some(get_error_class_fn) = options.get_error_class_fn {
+      deno_web::set_batch_error_class_fn(
+        &mut js_runtime.op_state().borrow_mut(),
+        get_error_class_fn,
+      );
+    }
+    js_runtime.op_state().borrow_mut().put(cpu_time.clone());
+    js_runtime.op_state().borrow_mut().put(source_maps);
 
     if let Some(server) = services.maybe_inspector_server {
       server.register_inspector(
@@ -595,15 +747,23 @@ impl WebWorker {
 
     let (internal_handle, external_handle) = {
       let handle = js_runtime.v8_isolate().thread_safe_handle();
-      let (internal_handle, external_handle) =
-        create_handles(handle, options.name.clone(), options.worker_type);
+      let (internal_handle, external_handle) = create_handles(
+        handle,
+        options.name.clone(),
+        options.worker_type,
+        cpu_time.counter(),
+      );
       let op_state = js_runtime.op_state();
       let mut op_state = op_state.borrow_mut();
       op_state.put(internal_handle.clone());
       (internal_handle, external_handle)
     };
 
-    let bootstrap_fn_global = {
+    let (
+      bootstrap_fn_global,
+      dispatch_lifecycle_event_fn_global,
+      dispatch_before_terminate_fn_global,
+    ) = {
       let context = js_runtime.main_context();
       let scope = &mut js_runtime.handle_scope();
       let context_local = v8::Local::new(scope, context);
@@ -622,7 +782,35 @@ impl WebWorker {
         bootstrap_ns.get(scope, main_runtime_str.into()).unwrap();
       let bootstrap_fn =
         v8::Local::<v8::Function>::try_from(bootstrap_fn).unwrap();
-      v8::Global::new(scope, bootstrap_fn)
+      let dispatch_lifecycle_event_str =
+        v8::String::new_external_onebyte_static(
+          scope,
+          b"dispatchLifecycleEvent",
+        )
+        .unwrap();
+      let dispatch_lifecycle_event_fn = bootstrap_ns
+        .get(scope, dispatch_lifecycle_event_str.into())
+        .unwrap();
+      let dispatch_lifecycle_event_fn =
+        v8::Local::<v8::Function>::try_from(dispatch_lifecycle_event_fn)
+          .unwrap();
+      let dispatch_before_terminate_str =
+        v8::String::new_external_onebyte_static(
+          scope,
+          b"dispatchBeforeTerminate",
+        )
+        .unwrap();
+      let dispatch_before_terminate_fn = bootstrap_ns
+        .get(scope, dispatch_before_terminate_str.into())
+        .unwrap();
+      let dispatch_before_terminate_fn =
+        v8::Local::<v8::Function>::try_from(dispatch_before_terminate_fn)
+          .unwrap();
+      (
+        v8::Global::new(scope, bootstrap_fn),
+        v8::Global::new(scope, dispatch_lifecycle_event_fn),
+        v8::Global::new(scope, dispatch_before_terminate_fn),
+      )
     };
 
     (
@@ -630,12 +818,15 @@ impl WebWorker {
         id: options.worker_id,
         js_runtime,
         name: options.name,
+        cpu_time,
         internal_handle,
         worker_type: options.worker_type,
         main_module: options.main_module,
         poll_for_messages_fn: None,
         has_message_event_listener_fn: None,
         bootstrap_fn_global: Some(bootstrap_fn_global),
+        dispatch_lifecycle_event_fn_global,
+        dispatch_before_terminate_fn_global,
         close_on_idle: options.close_on_idle,
         has_executed_main_module: false,
         maybe_worker_metadata: options.maybe_worker_metadata,
@@ -723,11 +914,60 @@ impl WebWorker {
   }
 
   /// Loads and instantiates specified JavaScript module as "main" module.
+  ///
+  /// Calls the "preMainModule" lifecycle hooks once the module is loaded.
   pub async fn preload_main_module(
     &mut self,
     module_specifier: &ModuleSpecifier,
   ) -> Result<ModuleId, AnyError> {
-    self.js_runtime.load_main_es_module(module_specifier).await
+    let id = self
+      .js_runtime
+      .load_main_es_module(module_specifier)
+      .await?;
+    self.dispatch_lifecycle_event(
+      LifecycleEvent::PreMainModule,
+      PreMainModuleArgs {
+        main_module: module_specifier.as_str(),
+      },
+    )?;
+    Ok(id)
+  }
+
+  /// Calls the JS hooks registered for a lifecycle event with `args`.
+  pub fn dispatch_lifecycle_event(
+    &mut self,
+    event: LifecycleEvent,
+    args: impl Serialize,
+  ) -> Result<(), AnyError> {
+    let scope = &mut self.js_runtime.handle_scope();
+    let tc_scope = &mut v8::TryCatch::new(scope);
+    let dispatch_lifecycle_event_fn =
+      v8::Local::new(tc_scope, &self.dispatch_lifecycle_event_fn_global);
+    let event = deno_core::serde_v8::to_v8(tc_scope, event)?;
+    let args = deno_core::serde_v8::to_v8(tc_scope, args)?;
+    let undefined = v8::undefined(tc_scope);
+    dispatch_lifecycle_event_fn.call(
+      tc_scope,
+      undefined.into(),
+      &[event, args],
+    );
+    if let Some(exception) = tc_scope.exception() {
+      let error = JsError::from_v8_exception(tc_scope, exception);
+      return Err(error.into());
+    }
+    Ok(())
+  }
+
+  /// Dispatches "beforeterminate" once the parent asked the worker to shut
+  /// down. Child workers are terminated first.
+  fn dispatch_before_terminate(&mut self, remaining: Duration) {
+    let scope = &mut self.js_runtime.handle_scope();
+    let dispatch_before_terminate_fn =
+      v8::Local::new(scope, &self.dispatch_before_terminate_fn_global);
+    let deadline = v8::Number::new(scope, remaining.as_millis() as f64).into();
+    let undefined = v8::undefined(scope);
+    // This call may return `None` if worker is terminated.
+    dispatch_before_terminate_fn.call(scope, undefined.into(), &[deadline]);
   }
 
   /// Loads and instantiates specified JavaScript module as "side" module.
@@ -783,7 +1023,9 @@ impl WebWorker {
       }
 
       event_loop_result = self.run_event_loop(poll_options) => {
-        if self.internal_handle.is_terminated() {
+        if self.internal_handle.is_terminated()
+          || self.internal_handle.graceful_shutdown.is_requested()
+        {
            return Ok(());
         }
         event_loop_result?;
@@ -804,7 +1046,15 @@ impl WebWorker {
 
     self.internal_handle.terminate_waker.register(cx.waker());
 
-    match self.js_runtime.poll_event_loop(cx, poll_options) {
+    if let Some(remaining) =
+      self.internal_handle.graceful_shutdown.take_request()
+    {
+      self.dispatch_before_terminate(remaining);
+    }
+
+    let poll_result = self.js_runtime.poll_event_loop(cx, poll_options);
+    self.cpu_time.sample();
+    match poll_result {
       Poll::Ready(r) => {
         // If js ended because we are terminating, just return Ok
         if self.internal_handle.terminate_if_needed() {
@@ -815,7 +1065,11 @@ impl WebWorker {
           return Poll::Ready(Err(e));
         }
 
-        if self.close_on_idle {
+        // After a graceful termination request, the event loop being done
+        // means the worker finished its cleanup.
+        if self.close_on_idle
+          || self.internal_handle.graceful_shutdown.is_requested()
+        {
           return Poll::Ready(Ok(()));
         }
 
@@ -857,6 +1111,12 @@ impl WebWorker {
     poll_fn(|cx| self.poll_event_loop(cx, poll_options)).await
   }
 
+  /// Returns the CPU time counter of this worker. It can be sent to and read
+  /// from another thread.
+  pub fn cpu_time_counter(&self) -> Arc<CpuTimeCounter> {
+    self.cpu_time.counter()
+  }
+
   // Starts polling for messages from worker host from JavaScript.
   fn start_polling_for_messages(&mut self) {
     let poll_for_messages_fn = self.poll_for_messages_fn.take().unwrap();
@@ -921,7 +1181,7 @@ fn print_worker_error(
 pub fn run_web_worker(
   mut worker: WebWorker,
   specifier: ModuleSpecifier,
-  mut maybe_source_code: Option<String>,
+  maybe_source_code: Option<String>,
   format_js_error_fn: Option<Arc<FormatJsErrorFn>>,
 ) -> Result<(), AnyError> {
   let name = worker.name.to_string();
@@ -930,54 +1190,84 @@ pub fn run_web_worker(
   // with terminate
 
   let fut = async move {
-    let internal_handle = worker.internal_handle.clone();
-
-    // Execute provided source code immediately
-    let result = if let Some(source_code) = maybe_source_code.take() {
-      let r = worker.execute_script(located_script_name!(), source_code.into());
-      worker.start_polling_for_messages();
-      r
-    } else {
-      // TODO(bartlomieju): add "type": "classic", ie. ability to load
-      // script instead of module
-      match worker.preload_main_module(&specifier).await {
-        Ok(id) => {
-          worker.start_polling_for_messages();
-          worker.execute_main_module(id).await
-        }
-        Err(e) => Err(e),
+    let graceful_shutdown = worker.internal_handle.graceful_shutdown.clone();
+
+    let result = run_web_worker_inner(
+      &mut worker,
+      &specifier,
+      maybe_source_code,
+      &name,
+      format_js_error_fn.as_deref(),
+    )
+    .await;
+
+    // Close the resources within the deadline when the parent asked the
+    // worker to terminate, before telling it that the worker is done.
+    if let Some(remaining) = graceful_shutdown.remaining() {
+      if !remaining.is_zero() {
+        let state = worker.js_runtime.op_state();
+        deno_web::close_resources(&state, remaining).await;
       }
-    };
-
-    // If sender is closed it means that worker has already been closed from
-    // within using "globalThis.close()"
-    if internal_handle.is_terminated() {
-      return Ok(());
     }
+    graceful_shutdown.completed.notify_one();
+    result
+  };
+  create_and_run_current_thread(fut)
+}
 
-    let result = if result.is_ok() {
-      worker
-        .run_event_loop(PollEventLoopOptions {
-          wait_for_inspector: true,
-          ..Default::default()
-        })
-        .await
-    } else {
-      result
-    };
-
-    if let Err(e) = result {
-      print_worker_error(&e, &name, format_js_error_fn.as_deref());
-      internal_handle
-        .post_event(WorkerControlEvent::TerminalError(e))
-        .expect("Failed to post message to host");
-
-      // Failure to execute script is a terminal error, bye, bye.
-      return Ok(());
+async fn run_web_worker_inner(
+  worker: &mut WebWorker,
+  specifier: &ModuleSpecifier,
+  maybe_source_code: Option<String>,
+  name: &str,
+  format_js_error_fn: Option<&FormatJsErrorFn>,
+) -> Result<(), AnyError> {
+  let internal_handle = worker.internal_handle.clone();
+
+  // Execute provided source code immediately
+  let result = if let Some(source_code) = maybe_source_code {
+    let r = worker.execute_script(located_script_name!(), source_code.into());
+    worker.start_polling_for_messages();
+    r
+  } else {
+    // TODO(bartlomieju): add "type": "classic", ie. ability to load
+    // script instead of module
+    match worker.preload_main_module(specifier).await {
+      Ok(id) => {
+        worker.start_polling_for_messages();
+        worker.execute_main_module(id).await
+      }
+      Err(e) => Err(e),
     }
+  };
+
+  // If sender is closed it means that worker has already been closed from
+  // within using "globalThis.close()"
+  if internal_handle.is_terminated() {
+    return Ok(());
+  }
 
-    debug!("Worker thread shuts down {}", &name);
+  let result = if result.is_ok() {
+    worker
+      .run_event_loop(PollEventLoopOptions {
+        wait_for_inspector: true,
+        ..Default::default()
+      })
+      .await
+  } else {
     result
   };
-  create_and_run_current_thread(fut)
+
+  if let Err(e) = result {
+    print_worker_error(&e, name, format_js_error_fn);
+    internal_handle
+      .post_event(WorkerControlEvent::TerminalError(e))
+      .expect("Failed to post message to host");
+
+    // Failure to execute script is a terminal error, bye, bye.
+    return Ok(());
+  }
+
+  debug!("Worker thread shuts down {}", name);
+  result
 }
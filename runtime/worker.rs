@@ -1,20 +1,25 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::future::poll_fn;
 use std::rc::Rc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicI32;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::Duration;
 use std::time::Instant;
 
+use deno_ast::MediaType;
 use deno_broadcast_channel::InMemoryBroadcastChannel;
 use deno_cache::CreateCache;
 use deno_cache::SqliteBackedCache;
 use deno_core::error::AnyError;
 use deno_core::error::JsError;
 use deno_core::merge_op_metrics;
+use deno_core::serde::Serialize;
+use deno_core::serde_v8;
 use deno_core::v8;
 use deno_core::CompiledWasmModuleStore;
 use deno_core::Extension;
@@ -34,23 +39,48 @@ use deno_core::SharedArrayBufferStore;
 use deno_core::SourceCodeCacheInfo;
 use deno_cron::local::LocalCronHandler;
 use deno_fs::FileSystem;
+use deno_fs::SharedFsLimits;
 use deno_http::DefaultHttpPropertyExtractor;
+use deno_io::InterruptHandle;
 use deno_io::Stdio;
 use deno_kv::dynamic::MultiBackendDbHandler;
+use deno_net::resolver::DnsResolver;
+use deno_net::resolver::DnsResolverConfig;
 use deno_node::NodeExtInitServices;
 use deno_permissions::PermissionsContainer;
 use deno_tls::RootCertStoreProvider;
 use deno_tls::TlsKeys;
 use deno_web::BlobStore;
+use deno_web::LifecycleEvent;
+use deno_webstorage::StorageEventChannel;
 use log::debug;
 
 use crate::code_cache::CodeCache;
 use crate::code_cache::CodeCacheType;
+use crate::cpu_time::CpuTimeCounter;
+use crate::cpu_time::CpuTimeQuota;
+use crate::cpu_time::CpuTimeSampler;
+use crate::deprecated_ops::DeprecatedOpsOptions;
+use crate::event_loop_monitor::EventLoopMonitor;
+use crate::event_loop_monitor::TickBudget;
+use crate::fatal_error::FatalError;
+use crate::fatal_error::FatalErrorCode;
+use crate::fatal_error::FatalErrorDecision;
+use crate::fatal_error::OnFatalErrorFn;
+use crate::fatal_error::UnhandledRejectionPolicy;
 use crate::inspector_server::InspectorServer;
+use crate::module_graph::ModuleGraph;
+use crate::module_graph::ModuleGraphLoader;
+use crate::module_graph::ModuleGraphSnapshot;
 use crate::ops;
+use crate::ops::blob_store::ContentStore;
 use crate::ops::process::NpmProcessStateProviderRc;
+use crate::ops::storage_archive::StorageDirs;
+use crate::otel::OtelConfig;
+use crate::otel::OtelRecorder;
 use crate::shared::maybe_transpile_source;
 use crate::shared::runtime;
+use crate::source_map::SourceMapCache;
 use crate::BootstrapOptions;
 
 pub type FormatJsErrorFn = dyn Fn(&JsError) -> String + Sync + Send;
@@ -93,6 +123,13 @@ pub fn validate_import_attributes_callback(
   }
 }
 
+/// Arguments of the "preMainModule" lifecycle hooks.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PreMainModuleArgs<'a> {
+  pub main_module: &'a str,
+}
+
 #[derive(Clone, Default)]
 pub struct ExitCode(Arc<AtomicI32>);
 
@@ -115,6 +152,8 @@ impl ExitCode {
 /// are descendants of this worker.
 pub struct MainWorker {
   pub js_runtime: JsRuntime,
+  cpu_time: Rc<CpuTimeSampler>,
+  event_loop_monitor: Rc<EventLoopMonitor>,
   should_break_on_first_statement: bool,
   should_wait_for_inspector_session: bool,
   exit_code: ExitCode,
@@ -124,13 +163,22 @@ pub struct MainWorker {
   dispatch_unload_event_fn_global: v8::Global<v8::Function>,
   dispatch_process_beforeexit_event_fn_global: v8::Global<v8::Function>,
   dispatch_process_exit_event_fn_global: v8::Global<v8::Function>,
+  dispatch_lifecycle_event_fn_global: v8::Global<v8::Function>,
+  on_fatal_error: Option<Arc<OnFatalErrorFn>>,
+  module_graph: ModuleGraph,
 }
 
 pub struct WorkerServiceOptions {
   pub blob_store: Arc<BlobStore>,
   pub broadcast_channel: InMemoryBroadcastChannel,
+  /// The content-addressed store used by the `op_blob_store_*` ops. If not
+  /// provided, those ops fail.
+  pub content_store: Option<Arc<ContentStore>>,
   pub feature_checker: Arc<FeatureChecker>,
   pub fs: Arc<dyn FileSystem>,
+  /// Caps on the files opened and bytes read below some paths. Workers given
+  /// the same limits share their counters.
+  pub fs_limits: Option<SharedFsLimits>,
   /// Implementation of `ModuleLoader` which will be
   /// called when V8 requests to load ES modules.
   ///
@@ -141,6 +189,8 @@ pub struct WorkerServiceOptions {
   pub npm_process_state_provider: Option<NpmProcessStateProviderRc>,
   pub permissions: PermissionsContainer,
   pub root_cert_store_provider: Option<Arc<dyn RootCertStoreProvider>>,
+  /// Carries `storage` events between the workers sharing it.
+  pub storage_event_channel: StorageEventChannel,
 
   /// The store to use for transferring SharedArrayBuffers between isolates.
   /// If multiple isolates should have the possibility of sharing
@@ -181,11 +231,31 @@ pub struct WorkerOptions {
   pub create_params: Option<v8::CreateParams>,
 
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
+  /// How `Deno.connect()`, `Deno.resolveDns()` and `fetch()` resolve names.
+  pub dns_resolver: DnsResolverConfig,
   pub seed: Option<u64>,
+  /// If Some, `on_exceeded` is called once the worker has used more CPU time
+  /// than the budget.
+  pub cpu_time_quota: Option<CpuTimeQuota>,
+  /// If Some, the runtime handles SIGINT (Ctrl+C on Windows): the first
+  /// signal dispatches a cancelable "interrupt" event, and the process exits
+  /// with code 130 after this grace period or on a second signal. Only one
+  /// worker per process should enable it.
+  pub sigint_grace_period: Option<Duration>,
+  /// If Some, event loop ticks that run for longer than the budget, e.g.
+  /// because of an endless chain of microtasks, are reported and optionally
+  /// interrupted.
+  pub tick_budget: Option<TickBudget>,
 
   // Callbacks invoked when creating new instance of WebWorker
   pub create_web_worker_cb: Arc<ops::worker_host::CreateWebWorkerCb>,
   pub format_js_error_fn: Option<Arc<FormatJsErrorFn>>,
+  /// If Some, called with uncaught exceptions and fatal unhandled rejections
+  /// before they are returned from `evaluate_module()` and
+  /// `run_event_loop()`. The returned decision can keep the worker running.
+  pub on_fatal_error: Option<Arc<OnFatalErrorFn>>,
+  /// What happens to promise rejections that the program doesn't handle.
+  pub unhandled_rejection_policy: UnhandledRejectionPolicy,
 
   pub maybe_inspector_server: Option<Arc<InspectorServer>>,
   // If true, the worker will wait for inspector session and break on first
@@ -197,12 +267,24 @@ pub struct WorkerOptions {
   pub should_wait_for_inspector_session: bool,
   /// If Some, print a low-level trace output for ops matching the given patterns.
   pub strace_ops: Option<Vec<String>>,
+  /// If Some, op and event loop telemetry is exported to an OpenTelemetry
+  /// collector.
+  pub otel: Option<OtelConfig>,
+  /// If Some, calls of deprecated ops are counted and reported, or rejected
+  /// in strict mode.
+  pub deprecated_ops: Option<DeprecatedOpsOptions>,
 
   /// Allows to map error type to a string "class" used to represent
   /// error in JavaScript.
   pub get_error_class_fn: Option<GetErrorClassFn>,
   pub cache_storage_dir: Option<std::path::PathBuf>,
   pub origin_storage_dir: Option<std::path::PathBuf>,
+  /// File in which `Deno.schedule()` keeps the last run of each schedule. If
+  /// `None`, nothing is kept across restarts.
+  pub schedule_state_path: Option<std::path::PathBuf>,
+  /// Data directories for `op_storage_export` and `op_storage_import`. If
+  /// `None`, the ops throw.
+  pub storage_dirs: Option<StorageDirs>,
   pub stdio: Stdio,
 }
 
@@ -214,14 +296,24 @@ impl Default for WorkerOptions {
       }),
       skip_op_registration: false,
       seed: None,
+      cpu_time_quota: None,
+      sigint_grace_period: None,
+      tick_budget: None,
       unsafely_ignore_certificate_errors: Default::default(),
+      dns_resolver: Default::default(),
       should_break_on_first_statement: Default::default(),
       should_wait_for_inspector_session: Default::default(),
       strace_ops: Default::default(),
+      otel: Default::default(),
+      deprecated_ops: Default::default(),
       maybe_inspector_server: Default::default(),
       format_js_error_fn: Default::default(),
+      on_fatal_error: Default::default(),
+      unhandled_rejection_policy: Default::default(),
       get_error_class_fn: Default::default(),
       origin_storage_dir: Default::default(),
+      schedule_state_path: Default::default(),
+      storage_dirs: Default::default(),
       cache_storage_dir: Default::default(),
       extensions: Default::default(),
       startup_snapshot: Default::default(),
@@ -327,14 +419,41 @@ impl MainWorker {
     );
 
     // Get our op metrics
-    let (op_summary_metrics, op_metrics_factory_fn) = create_op_metrics(
+    let (op_summary_metrics, mut op_metrics_factory_fn) = create_op_metrics(
       options.bootstrap.enable_op_summary_metrics,
       options.strace_ops,
     );
+    let cpu_time = CpuTimeSampler::new(options.cpu_time_quota);
+    if cpu_time.has_quota() {
+      let cpu_time_metrics = cpu_time.op_metrics_factory_fn();
+      op_metrics_factory_fn = Some(match op_metrics_factory_fn {
+        Some(f) => merge_op_metrics(f, cpu_time_metrics),
+        None => cpu_time_metrics,
+      });
+    }
+    let otel = options.otel.map(OtelRecorder::new);
+    if let Some(otel) = &otel {
+      let otel_metrics = otel.op_metrics_factory_fn();
+      op_metrics_factory_fn = Some(match op_metrics_factory_fn {
+        Some(f) => merge_op_metrics(f, otel_metrics),
+        None => otel_metrics,
+      });
+    }
+    if let Some(deprecated_ops) = &options.deprecated_ops {
+      let deprecated_ops_metrics = deprecated_ops.op_metrics_factory_fn();
+      op_metrics_factory_fn = Some(match op_metrics_factory_fn {
+        Some(f) => merge_op_metrics(f, deprecated_ops_metrics),
+        None => deprecated_ops_metrics,
+      });
+    }
 
     // Permissions: many ops depend on this
     let enable_testing_features = options.bootstrap.enable_testing_features;
     let exit_code = ExitCode(Arc::new(AtomicI32::new(0)));
+    let dns_resolver = DnsResolver::new(
+      options.dns_resolver.clone(),
+      services.root_cert_store_provider.clone(),
+    );
     let create_cache = options.cache_storage_dir.map(|storage_dir| {
       let create_cache_fn = move || SqliteBackedCache::new(storage_dir.clone());
       CreateCache(Arc::new(create_cache_fn))
@@ -361,6 +480,7 @@ impl MainWorker {
             .unsafely_ignore_certificate_errors
             .clone(),
           file_fetch_handler: Rc::new(deno_fetch::FsFetchHandler),
+          dns_resolver: dns_resolver.clone(),
           ..Default::default()
         },
       ),
@@ -374,8 +494,19 @@ impl MainWorker {
       ),
       deno_webstorage::deno_webstorage::init_ops_and_esm(
         options.origin_storage_dir.clone(),
+        Some(services.storage_event_channel.clone()),
+        Some(
+          options
+            .bootstrap
+            .location
+            .as_ref()
+            .unwrap_or(&main_module)
+            .to_string(),
+        ),
+      ),
+      deno_crypto::deno_crypto::init_ops_and_esm::<PermissionsContainer>(
+        options.seed,
       ),
-      deno_crypto::deno_crypto::init_ops_and_esm(options.seed),
       deno_broadcast_channel::deno_broadcast_channel::init_ops_and_esm(
         services.broadcast_channel.clone(),
       ),
@@ -383,6 +514,7 @@ impl MainWorker {
       deno_net::deno_net::init_ops_and_esm::<PermissionsContainer>(
         services.root_cert_store_provider.clone(),
         options.unsafely_ignore_certificate_errors.clone(),
+        dns_resolver,
       ),
       deno_tls::deno_tls::init_ops_and_esm(),
       deno_kv::deno_kv::init_ops_and_esm(
@@ -401,12 +533,16 @@ impl MainWorker {
         ),
         deno_kv::KvConfig::builder().build(),
       ),
-      deno_cron::deno_cron::init_ops_and_esm(LocalCronHandler::new()),
+      deno_cron::deno_cron::init_ops_and_esm(
+        LocalCronHandler::new(),
+        options.schedule_state_path.clone(),
+      ),
       deno_napi::deno_napi::init_ops_and_esm::<PermissionsContainer>(),
       deno_http::deno_http::init_ops_and_esm::<DefaultHttpPropertyExtractor>(),
       deno_io::deno_io::init_ops_and_esm(Some(options.stdio)),
       deno_fs::deno_fs::init_ops_and_esm::<PermissionsContainer>(
         services.fs.clone(),
+        services.fs_limits,
       ),
       deno_node::deno_node::init_ops_and_esm::<PermissionsContainer>(
         services.node_services,
@@ -427,6 +563,13 @@ impl MainWorker {
       ops::signal::deno_signal::init_ops_and_esm(),
       ops::tty::deno_tty::init_ops_and_esm(),
       ops::http::deno_http_runtime::init_ops_and_esm(),
+      ops::blob_store::deno_blob_store::init_ops_and_esm(
+        services.content_store,
+      ),
+      ops::storage_archive::deno_storage_archive::init_ops_and_esm(
+        options.storage_dirs.clone(),
+      ),
+      ops::realm::deno_realm::init_ops_and_esm(),
       ops::bootstrap::deno_bootstrap::init_ops_and_esm(
         if options.startup_snapshot.is_some() {
           None
@@ -461,6 +604,9 @@ impl MainWorker {
     }
 
     extensions.extend(std::mem::take(&mut options.extensions));
+    if let Some(deprecated_ops) = &options.deprecated_ops {
+      deprecated_ops.apply(&mut extensions);
+    }
 
     #[cfg(feature = "only_snapshotted_js_sources")]
     options.startup_snapshot.as_ref().expect("A user snapshot was not provided, even though 'only_snapshotted_js_sources' is used.");
@@ -474,8 +620,14 @@ impl MainWorker {
       }
     });
 
+    let module_graph = ModuleGraph::default();
+    let module_loader: Rc<dyn ModuleLoader> = Rc::new(ModuleGraphLoader::new(
+      services.module_loader.clone(),
+      module_graph.clone(),
+    ));
+    let source_maps = Rc::new(SourceMapCache::new(module_loader.clone()));
     let mut js_runtime = JsRuntime::new(RuntimeOptions {
-      module_loader: Some(services.module_loader.clone()),
+      module_loader: Some(module_loader),
       startup_snapshot: options.startup_snapshot,
       create_params: options.create_params,
       skip_op_registration: options.skip_op_registration,
@@ -543,6 +695,45 @@ impl MainWorker {
     if let Some(op_summary_metrics) = op_summary_metrics {
       js_runtime.op_state().borrow_mut().put(op_summary_metrics);
     }
+    if let Some(get_error_class_fn) = options.get_error_class_fn {
+      deno_web::set_batch_error_class_fn(
+        &mut js_runtime.op_state().borrow_mut(),
+        get_error_class_fn,
+      );
+    }
+    js_runtime.op_state().borrow_mut().put(cpu_time.clone());
+    js_runtime.op_state().borrow_mut().put(module_graph.clone());
+    js_runtime.op_state().borrow_mut().put(source_maps);
+    let event_loop_monitor =
+      Rc::new(EventLoopMonitor::new(options.tick_budget));
+    event_loop_monitor
+      .start_watchdog(js_runtime.v8_isolate().thread_safe_handle());
+    js_runtime
+      .op_state()
+      .borrow_mut()
+      .put(event_loop_monitor.clone());
+
+    if let Some(otel) = otel {
+      // The ticker runs on the worker's event loop, which needs a runtime.
+      if tokio::runtime::Handle::try_current().is_ok() {
+        otel.start(&js_runtime.op_state());
+      }
+    }
+
+    if let Some(grace_period) = options.sigint_grace_period {
+      let op_state = js_runtime.op_state();
+      let mut op_state = op_state.borrow_mut();
+      let interrupt = op_state.try_borrow::<InterruptHandle>().cloned();
+      match ops::signal::SigintDisposition::install(grace_period, interrupt) {
+        Ok(disposition) => op_state.put(Rc::new(disposition)),
+        Err(err) => log::warn!("Failed to install the SIGINT handler: {err}"),
+      }
+    }
+
+    js_runtime
+      .op_state()
+      .borrow_mut()
+      .put(options.unhandled_rejection_policy);
 
     if let Some(server) = options.maybe_inspector_server.clone() {
       server.register_inspector(
@@ -565,6 +756,7 @@ impl MainWorker {
       dispatch_unload_event_fn_global,
       dispatch_process_beforeexit_event_fn_global,
       dispatch_process_exit_event_fn_global,
+      dispatch_lifecycle_event_fn_global,
     ) = {
       let context = js_runtime.main_context();
       let scope = &mut js_runtime.handle_scope();
@@ -637,6 +829,17 @@ impl MainWorker {
       let dispatch_process_exit_event_fn =
         v8::Local::<v8::Function>::try_from(dispatch_process_exit_event_fn)
           .unwrap();
+      let dispatch_lifecycle_event = v8::String::new_external_onebyte_static(
+        scope,
+        b"dispatchLifecycleEvent",
+      )
+      .unwrap();
+      let dispatch_lifecycle_event_fn = bootstrap_ns
+        .get(scope, dispatch_lifecycle_event.into())
+        .unwrap();
+      let dispatch_lifecycle_event_fn =
+        v8::Local::<v8::Function>::try_from(dispatch_lifecycle_event_fn)
+          .unwrap();
       (
         v8::Global::new(scope, bootstrap_fn),
         v8::Global::new(scope, dispatch_load_event_fn),
@@ -644,11 +847,14 @@ impl MainWorker {
         v8::Global::new(scope, dispatch_unload_event_fn),
         v8::Global::new(scope, dispatch_process_beforeexit_event_fn),
         v8::Global::new(scope, dispatch_process_exit_event_fn),
+        v8::Global::new(scope, dispatch_lifecycle_event_fn),
       )
     };
 
     let worker = Self {
       js_runtime,
+      cpu_time,
+      event_loop_monitor,
       should_break_on_first_statement: options.should_break_on_first_statement,
       should_wait_for_inspector_session: options
         .should_wait_for_inspector_session,
@@ -659,6 +865,9 @@ impl MainWorker {
       dispatch_unload_event_fn_global,
       dispatch_process_beforeexit_event_fn_global,
       dispatch_process_exit_event_fn_global,
+      dispatch_lifecycle_event_fn_global,
+      on_fatal_error: options.on_fatal_error,
+      module_graph,
     };
     (worker, options.bootstrap)
   }
@@ -672,6 +881,9 @@ impl MainWorker {
       if let Some(node_ipc_fd) = options.node_ipc_fd {
         state.put(deno_node::ChildPipeFd(node_ipc_fd));
       }
+      if let Some(fd) = options.spawn_self_ipc_fd {
+        state.put(ops::process::SpawnSelfIpcFd(fd));
+      }
     }
 
     let scope = &mut self.js_runtime.handle_scope();
@@ -697,11 +909,23 @@ impl MainWorker {
   }
 
   /// Loads and instantiates specified JavaScript module as "main" module.
+  ///
+  /// Calls the "preMainModule" lifecycle hooks once the module is loaded.
   pub async fn preload_main_module(
     &mut self,
     module_specifier: &ModuleSpecifier,
   ) -> Result<ModuleId, AnyError> {
-    self.js_runtime.load_main_es_module(module_specifier).await
+    let id = self
+      .js_runtime
+      .load_main_es_module(module_specifier)
+      .await?;
+    self.dispatch_lifecycle_event(
+      LifecycleEvent::PreMainModule,
+      PreMainModuleArgs {
+        main_module: module_specifier.as_str(),
+      },
+    )?;
+    Ok(id)
   }
 
   /// Loads and instantiates specified JavaScript module as "side" module.
@@ -719,7 +943,7 @@ impl MainWorker {
   ) -> Result<(), AnyError> {
     self.wait_for_inspector_session();
     let mut receiver = self.js_runtime.mod_evaluate(id);
-    tokio::select! {
+    let result = tokio::select! {
       // Not using biased mode leads to non-determinism for relatively simple
       // programs.
       biased;
@@ -733,7 +957,8 @@ impl MainWorker {
         event_loop_result?;
         receiver.await
       }
-    }
+    };
+    result.or_else(|error| self.handle_fatal_error(error))
   }
 
   /// Run the event loop up to a given duration. If the runtime resolves early, returns
@@ -744,9 +969,7 @@ impl MainWorker {
   ) -> Result<(), AnyError> {
     match tokio::time::timeout(
       duration,
-      self
-        .js_runtime
-        .run_event_loop(PollEventLoopOptions::default()),
+      self.run_event_loop_with_options(PollEventLoopOptions::default()),
     )
     .await
     {
@@ -800,20 +1023,129 @@ impl MainWorker {
     wait_for_inspector: bool,
   ) -> Result<(), AnyError> {
     self
-      .js_runtime
-      .run_event_loop(deno_core::PollEventLoopOptions {
+      .run_event_loop_with_options(PollEventLoopOptions {
         wait_for_inspector,
         ..Default::default()
       })
       .await
   }
 
+  async fn run_event_loop_with_options(
+    &mut self,
+    poll_options: PollEventLoopOptions,
+  ) -> Result<(), AnyError> {
+    poll_fn(|cx| {
+      let result = self
+        .event_loop_monitor
+        .poll(cx, |cx| self.js_runtime.poll_event_loop(cx, poll_options));
+      self.cpu_time.sample();
+      if self.event_loop_monitor.take_yield() {
+        // The running JavaScript was terminated because the tick overran its
+        // budget; resume with the next tick instead of failing.
+        self.js_runtime.v8_isolate().cancel_terminate_execution();
+        if result.is_ready() {
+          cx.waker().wake_by_ref();
+          return Poll::Pending;
+        }
+      }
+      match result {
+        Poll::Ready(Err(error)) => match self.handle_fatal_error(error) {
+          Ok(()) => {
+            // The embedder chose to continue; keep polling.
+            cx.waker().wake_by_ref();
+            Poll::Pending
+          }
+          Err(error) => Poll::Ready(Err(error)),
+        },
+        result => result,
+      }
+    })
+    .await
+  }
+
+  /// Passes an uncaught exception or unhandled rejection to the
+  /// `on_fatal_error` callback. Returns `Ok` if the worker should keep
+  /// running, and the error if it should be returned to the embedder.
+  fn handle_fatal_error(&mut self, error: AnyError) -> Result<(), AnyError> {
+    let Some(on_fatal_error) = self.on_fatal_error.clone() else {
+      return Err(error);
+    };
+    let Some(js_error) = error.downcast_ref::<JsError>() else {
+      return Err(error);
+    };
+    let code = self
+      .js_runtime
+      .op_state()
+      .borrow_mut()
+      .try_take::<FatalErrorCode>()
+      .and_then(|code| code.0);
+    let fatal_error = FatalError::from_js_error(js_error, code);
+    match on_fatal_error(&fatal_error) {
+      FatalErrorDecision::Exit(code) => {
+        self.exit_code.set(code);
+        std::process::exit(code)
+      }
+      FatalErrorDecision::Continue => Ok(()),
+      FatalErrorDecision::Rethrow => Err(error),
+    }
+  }
+
+  /// Closes the resources that need asynchronous teardown, like TLS streams
+  /// that send a close_notify, in reverse creation order. Should be called
+  /// once the event loop is done, before the worker is dropped.
+  ///
+  /// Returns `false` when `deadline` passed before everything was closed;
+  /// the remaining resources are then dropped with the worker as usual.
+  pub async fn close_resources(&mut self, deadline: Duration) -> bool {
+    let state = self.js_runtime.op_state();
+    deno_web::close_resources(&state, deadline).await
+  }
+
+  /// Returns the CPU time counter of this worker. It can be sent to and read
+  /// from another thread.
+  pub fn cpu_time_counter(&self) -> Arc<CpuTimeCounter> {
+    self.cpu_time.counter()
+  }
+
+  /// Registers a module that imports of `specifier` resolve to, without
+  /// calling the module loader. It can be added before or between
+  /// evaluations, and isn't written to any cache.
+  pub fn add_virtual_module(
+    &self,
+    specifier: ModuleSpecifier,
+    source: String,
+    media_type: MediaType,
+  ) -> Result<(), AnyError> {
+    self
+      .module_graph
+      .add_virtual_module(specifier, source, media_type)
+  }
+
+  /// Returns the modules loaded so far, with their static imports, sizes
+  /// and load times.
+  pub fn module_graph_snapshot(&self) -> ModuleGraphSnapshot {
+    self.module_graph.snapshot()
+  }
+
   /// Return exit code set by the executed code (either in main worker
   /// or one of child web workers).
   pub fn exit_code(&self) -> i32 {
     self.exit_code.get()
   }
 
+  /// Returns a handle that interrupts the long-running ops of this worker,
+  /// e.g. `Deno.hashTree()`, which then reject with `Deno.errors.Interrupted`.
+  /// The handle can be sent to another thread, so a watchdog can abort an op
+  /// that blocks the event loop.
+  pub fn interrupt_handle(&self) -> InterruptHandle {
+    self
+      .js_runtime
+      .op_state()
+      .borrow()
+      .borrow::<InterruptHandle>()
+      .clone()
+  }
+
   /// Dispatches "load" event to the JavaScript runtime.
   ///
   /// Does not poll event loop, and thus not await any of the "load" event handlers.
@@ -848,6 +1180,34 @@ impl MainWorker {
     Ok(())
   }
 
+  /// Calls the JS hooks registered for a lifecycle event with `args`.
+  ///
+  /// Does not poll event loop, and thus not await any promises returned by
+  /// the hooks.
+  pub fn dispatch_lifecycle_event(
+    &mut self,
+    event: LifecycleEvent,
+    args: impl Serialize,
+  ) -> Result<(), AnyError> {
+    let scope = &mut self.js_runtime.handle_scope();
+    let tc_scope = &mut v8::TryCatch::new(scope);
+    let dispatch_lifecycle_event_fn =
+      v8::Local::new(tc_scope, &self.dispatch_lifecycle_event_fn_global);
+    let event = serde_v8::to_v8(tc_scope, event)?;
+    let args = serde_v8::to_v8(tc_scope, args)?;
+    let undefined = v8::undefined(tc_scope);
+    dispatch_lifecycle_event_fn.call(
+      tc_scope,
+      undefined.into(),
+      &[event, args],
+    );
+    if let Some(exception) = tc_scope.exception() {
+      let error = JsError::from_v8_exception(tc_scope, exception);
+      return Err(error.into());
+    }
+    Ok(())
+  }
+
   /// Dispatches process.emit("exit") event for node compat.
   pub fn dispatch_process_exit_event(&mut self) -> Result<(), AnyError> {
     let scope = &mut self.js_runtime.handle_scope();
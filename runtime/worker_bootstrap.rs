@@ -4,7 +4,6 @@ use deno_core::v8;
 use deno_core::ModuleSpecifier;
 use serde::Serialize;
 use std::cell::RefCell;
-use std::thread;
 
 use deno_terminal::colors;
 
@@ -114,6 +113,8 @@ pub struct BootstrapOptions {
   pub argv0: Option<String>,
   pub node_debug: Option<String>,
   pub node_ipc_fd: Option<i64>,
+  /// IPC channel to the parent process, see `Deno.spawnSelf()`.
+  pub spawn_self_ipc_fd: Option<i64>,
   pub mode: WorkerExecutionMode,
   // Used by `deno serve`
   pub serve_port: Option<u16>,
@@ -122,9 +123,7 @@ pub struct BootstrapOptions {
 
 impl Default for BootstrapOptions {
   fn default() -> Self {
-    let cpu_count = thread::available_parallelism()
-      .map(|p| p.get())
-      .unwrap_or(1);
+    let cpu_count = crate::ops::os::cpu_info::hardware_concurrency();
 
     let runtime_version = env!("CARGO_PKG_VERSION");
     let user_agent = format!("Deno/{runtime_version}");
@@ -149,6 +148,7 @@ impl Default for BootstrapOptions {
       argv0: None,
       node_debug: None,
       node_ipc_fd: None,
+      spawn_self_ipc_fd: None,
       mode: WorkerExecutionMode::None,
       serve_port: Default::default(),
       serve_host: Default::default(),
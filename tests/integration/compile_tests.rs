@@ -125,6 +125,42 @@ fn standalone_error() {
   assert_contains!(stderr, "standalone_error.ts:7:1");
 }
 
+#[test]
+fn standalone_error_source_mapped() {
+  let context = TestContextBuilder::new().build();
+  let dir = context.temp_dir();
+  let exe = if cfg!(windows) {
+    dir.path().join("error.exe")
+  } else {
+    dir.path().join("error")
+  };
+  context
+    .new_command()
+    .args_vec([
+      "compile",
+      "--output",
+      &exe.to_string_lossy(),
+      "./compile/standalone_error_source_mapped.ts",
+    ])
+    .run()
+    .skip_output_check()
+    .assert_exit_code(0);
+
+  let output = context
+    .new_command()
+    .name(&exe)
+    .env("NO_COLOR", "1")
+    .split_output()
+    .run();
+  output.assert_exit_code(1);
+  let stderr = output.stderr();
+  // The types are stripped from the embedded module, so these positions
+  // are only right if the stack is source mapped.
+  assert_contains!(stderr, "error: Uncaught (in promise) Error: boom!");
+  assert_contains!(stderr, "standalone_error_source_mapped.ts:8:9");
+  assert_contains!(stderr, "standalone_error_source_mapped.ts:11:1");
+}
+
 #[test]
 fn standalone_error_module_with_imports() {
   let context = TestContextBuilder::new().build();
@@ -221,6 +257,132 @@ fn standalone_follow_redirects() {
     .assert_exit_code(0);
 }
 
+#[test]
+fn standalone_embedded_ca_precedence() {
+  let context = TestContextBuilder::new().use_http_server().build();
+  let dir = context.temp_dir();
+  let exe = if cfg!(windows) {
+    dir.path().join("embedded_ca.exe")
+  } else {
+    dir.path().join("embedded_ca")
+  };
+  // The test TLS server uses a certificate signed by RootCA, which isn't in
+  // the default (mozilla) store.
+  dir.write(
+    "main.ts",
+    r#"const caCerts = Deno.env.get("CA_CERTS_FILE");
+const conn = await Deno.connectTls({
+  hostname: "localhost",
+  port: 4557,
+  caCerts: caCerts ? [Deno.readTextFileSync(caCerts)] : undefined,
+});
+const buf = new Uint8Array(4);
+await conn.read(buf);
+console.log(new TextDecoder().decode(buf));
+conn.close();
+"#,
+  );
+  context
+    .new_command()
+    .args_vec([
+      "compile",
+      "--allow-net",
+      "--allow-env",
+      "--allow-read",
+      "--embed-ca-file",
+      "./tls/RootCA.pem",
+      "--output",
+      &exe.to_string_lossy(),
+      &dir.path().join("main.ts").to_string_lossy(),
+    ])
+    .run()
+    .skip_output_check()
+    .assert_exit_code(0);
+
+  // The embedded CA is used.
+  context
+    .new_command()
+    .name(&exe)
+    .env_remove("DENO_CERT")
+    .run()
+    .assert_matches_text("PASS\n")
+    .assert_exit_code(0);
+
+  // DENO_CERT replaces the embedded CA.
+  let unrelated_ca = testdata_path().join("tls/localhost_ecc.crt");
+  let output = context
+    .new_command()
+    .name(&exe)
+    .env("DENO_CERT", &unrelated_ca)
+    .run();
+  output.assert_exit_code(1);
+  assert_contains!(output.combined_output(), "UnknownIssuer");
+
+  // Per-connection caCerts take precedence over DENO_CERT.
+  context
+    .new_command()
+    .name(&exe)
+    .env("DENO_CERT", &unrelated_ca)
+    .env("CA_CERTS_FILE", testdata_path().join("tls/RootCA.pem"))
+    .run()
+    .assert_matches_text("PASS\n")
+    .assert_exit_code(0);
+}
+
+#[test]
+fn standalone_verify_assets() {
+  let context = TestContextBuilder::new().build();
+  let dir = context.temp_dir();
+  let exe = if cfg!(windows) {
+    dir.path().join("verify_assets.exe")
+  } else {
+    dir.path().join("verify_assets")
+  };
+  dir.write("main.ts", "console.log('started');");
+  dir.create_dir_all("assets/plugins");
+  dir.write("assets/a.txt", "hello");
+  dir.write("assets/plugins/b.js", "export {};");
+  context
+    .new_command()
+    .args_vec([
+      "compile",
+      "--verify-assets",
+      &dir.path().join("assets").to_string_lossy(),
+      "--output",
+      &exe.to_string_lossy(),
+      &dir.path().join("main.ts").to_string_lossy(),
+    ])
+    .run()
+    .skip_output_check()
+    .assert_exit_code(0);
+
+  // The assets next to the executable are the ones it was built with.
+  context
+    .new_command()
+    .name(&exe)
+    .run()
+    .assert_matches_text("started\n")
+    .assert_exit_code(0);
+
+  dir.write("assets/a.txt", "tampered");
+  dir.remove_file("assets/plugins/b.js");
+  dir.write("assets/plugins/c.js", "export {};");
+  let output = context.new_command().name(&exe).run();
+  output.assert_exit_code(1);
+  let output = output.combined_output();
+  assert_contains!(output, "don't match the ones this executable was built");
+  assert_contains!(output, "missing: plugins/b.js");
+  assert_contains!(output, "unexpected: plugins/c.js");
+  assert_contains!(output, "modified: a.txt");
+  assert_not_contains!(output, "started");
+
+  // A missing asset directory also fails.
+  dir.remove_dir_all("assets");
+  let output = context.new_command().name(&exe).run();
+  output.assert_exit_code(1);
+  assert_contains!(output.combined_output(), "Failed verifying assets");
+}
+
 #[test]
 fn compile_with_file_exists_error() {
   let context = TestContextBuilder::new().build();
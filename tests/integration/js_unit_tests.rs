@@ -16,6 +16,7 @@ util::unit_test_factory!(
     body_test,
     broadcast_channel_test,
     build_test,
+    byte_codec_test,
     cache_api_test,
     chmod_test,
     chown_test,
@@ -26,8 +27,10 @@ util::unit_test_factory!(
     cron_test,
     dir_test,
     dom_exception_test,
+    download_test,
     error_stack_test,
     error_test,
+    env_policy_test,
     esnext_test,
     event_source_test,
     event_target_test,
@@ -38,12 +41,16 @@ util::unit_test_factory!(
     filereader_test,
     files_test,
     fs_events_test,
+    fs_progress_test,
     get_random_values_test,
+    glob_matcher_test,
     globals_test,
+    hash_tree_test,
     headers_test,
     http_test,
     image_bitmap_test,
     image_data_test,
+    integrity_manifest_test,
     internals_test,
     intl_test,
     jupyter_test,
@@ -52,14 +59,19 @@ util::unit_test_factory!(
     kv_queue_test,
     kv_queue_undelivered_test,
     link_test,
+    log_file_test,
     make_temp_test,
     message_channel_test,
+    mmap_test,
     mkdir_test,
     navigator_test,
+    ndjson_test,
     net_test,
     network_interfaces_test,
     os_test,
     ops_test,
+    password_wrap_test,
+    path_api_test,
     path_from_url_test,
     performance_test,
     permissions_test,
@@ -71,22 +83,28 @@ util::unit_test_factory!(
     read_link_test,
     read_text_file_test,
     real_path_test,
+    realm_test,
     ref_unref_test,
     remove_test,
     rename_test,
     request_test,
     response_test,
+    schedule_test,
     serve_test,
     signal_test,
+    spawn_self_test,
+    ssh_test,
     stat_test,
     stdio_test,
     streams_test,
     structured_clone_test,
     symbol_test,
     symlink_test,
+    temp_resource_test,
     test_util,
     testing_test,
     text_encoding_test,
+    text_search_test,
     timers_test,
     tls_test,
     tls_sni_test,
@@ -99,13 +117,16 @@ util::unit_test_factory!(
     urlpattern_test,
     utime_test,
     version_test,
+    walk_dir_test,
     wasm_test,
+    watch_config_test,
     webcrypto_test,
     webgpu_test,
     websocket_test,
     webstorage_test,
     worker_permissions_test,
     worker_test,
+    write_file_atomic_test,
     write_file_test,
     write_text_file_test,
   ]
@@ -131,15 +152,43 @@ fn js_unit_test(test: String) {
     deno = deno.arg("--unstable-broadcast-channel");
   }
 
-  if test == "cron_test" {
+  if test == "cron_test" || test == "schedule_test" {
     deno = deno.arg("--unstable-cron");
   }
 
+  if test == "password_wrap_test" || test == "ssh_test" {
+    deno = deno.arg("--unstable-crypto");
+  }
+
+  if test == "fs_progress_test"
+    || test == "glob_matcher_test"
+    || test == "hash_tree_test"
+    || test == "integrity_manifest_test"
+    || test == "log_file_test"
+    || test == "mmap_test"
+    || test == "ndjson_test"
+    || test == "path_api_test"
+    || test == "temp_resource_test"
+    || test == "text_search_test"
+    || test == "walk_dir_test"
+    || test == "watch_config_test"
+    || test == "write_file_atomic_test"
+  {
+    deno = deno.arg("--unstable-fs");
+  }
+
+  if test == "spawn_self_test" || test == "env_policy_test" {
+    deno = deno.arg("--unstable-process");
+  }
+
   if test.contains("kv_") {
     deno = deno.arg("--unstable-kv");
   }
 
-  if test == "worker_permissions_test" || test == "worker_test" {
+  if test == "worker_permissions_test"
+    || test == "worker_test"
+    || test == "env_policy_test"
+  {
     deno = deno.arg("--unstable-worker-options");
   }
 
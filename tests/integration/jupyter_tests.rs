@@ -21,6 +21,8 @@ use serde::Serialize;
 use tokio::sync::Mutex;
 use tokio::time::timeout;
 use uuid::Uuid;
+use zeromq::util::PeerIdentity;
+use zeromq::SocketOptions;
 use zeromq::SocketRecv;
 use zeromq::SocketSend;
 use zeromq::ZmqMessage;
@@ -206,9 +208,17 @@ impl JupyterMsg {
 async fn connect_socket<S: zeromq::Socket>(
   spec: &ConnectionSpec,
   port: u16,
+) -> S {
+  connect_socket_with_options(spec, port, SocketOptions::default()).await
+}
+
+async fn connect_socket_with_options<S: zeromq::Socket>(
+  spec: &ConnectionSpec,
+  port: u16,
+  options: SocketOptions,
 ) -> S {
   let addr = spec.endpoint(port);
-  let mut socket = S::new();
+  let mut socket = S::with_options(options);
   match timeout(Duration::from_millis(5000), socket.connect(&addr)).await {
     Ok(Ok(_)) => socket,
     Ok(Err(e)) => {
@@ -228,14 +238,13 @@ struct JupyterClient {
   control: Arc<Mutex<zeromq::DealerSocket>>,
   shell: Arc<Mutex<zeromq::DealerSocket>>,
   io_pub: Arc<Mutex<zeromq::SubSocket>>,
-  stdin: Arc<Mutex<zeromq::RouterSocket>>,
+  stdin: Arc<Mutex<zeromq::DealerSocket>>,
 }
 
 #[derive(Debug, Clone, Copy)]
 enum JupyterChannel {
   Control,
   Shell,
-  #[allow(dead_code)]
   Stdin,
   IoPub,
 }
@@ -248,16 +257,34 @@ impl JupyterClient {
   }
 
   async fn new_with_timeout(spec: &ConnectionSpec, timeout: Duration) -> Self {
+    // The kernel routes input requests to the stdin socket with the identity
+    // of the shell socket that sent the execute request, like frontends do.
+    let session = Uuid::new_v4();
+    let identity_options = || {
+      let identity =
+        PeerIdentity::try_from(session.as_bytes().to_vec()).unwrap();
+      let mut options = SocketOptions::default();
+      options.peer_identity(identity);
+      options
+    };
     let (heartbeat, control, shell, io_pub, stdin) = tokio::join!(
       connect_socket::<zeromq::ReqSocket>(spec, spec.hb_port),
       connect_socket::<zeromq::DealerSocket>(spec, spec.control_port),
-      connect_socket::<zeromq::DealerSocket>(spec, spec.shell_port),
+      connect_socket_with_options::<zeromq::DealerSocket>(
+        spec,
+        spec.shell_port,
+        identity_options()
+      ),
       connect_socket::<zeromq::SubSocket>(spec, spec.iopub_port),
-      connect_socket::<zeromq::RouterSocket>(spec, spec.stdin_port),
+      connect_socket_with_options::<zeromq::DealerSocket>(
+        spec,
+        spec.stdin_port,
+        identity_options()
+      ),
     );
 
     Self {
-      session: Uuid::new_v4(),
+      session,
       heartbeat: Arc::new(Mutex::new(heartbeat)),
       control: Arc::new(Mutex::new(control)),
       shell: Arc::new(Mutex::new(shell)),
@@ -662,3 +689,135 @@ async fn jupyter_http_server() -> Result<()> {
 
   Ok(())
 }
+
+async fn execute_with_stdin(
+  client: &JupyterClient,
+  code: &str,
+  allow_stdin: bool,
+) -> Result<JupyterMsg> {
+  client
+    .send(
+      Shell,
+      "execute_request",
+      json!({
+        "silent": false,
+        "store_history": false,
+        "allow_stdin": allow_stdin,
+        "code": code,
+      }),
+    )
+    .await
+}
+
+/// Returns the text of the next stdout stream message of `request`.
+async fn recv_stdout(
+  client: &JupyterClient,
+  request: &JupyterMsg,
+) -> Result<String> {
+  loop {
+    let msg = client.recv(IoPub).await?;
+    if msg.header.msg_type == "stream"
+      && msg.parent_header == request.header.to_json()
+      && msg.content["name"] == "stdout"
+    {
+      return Ok(msg.content["text"].as_str().unwrap().to_string());
+    }
+  }
+}
+
+#[tokio::test]
+async fn jupyter_prompt_input_request() -> Result<()> {
+  let (_ctx, client, _process) = setup().await;
+  let request = execute_with_stdin(
+    &client,
+    r#"const name = await Deno.jupyter.prompt("Name?", { password: true });
+console.log(`hello ${name}`);"#,
+    true,
+  )
+  .await?;
+
+  let input_request = client.recv(Stdin).await?;
+  assert_eq!(input_request.header.msg_type, "input_request");
+  assert_eq!(input_request.parent_header, request.header.to_json());
+  assert_json_subset(
+    input_request.content.clone(),
+    json!({ "prompt": "Name?", "password": true }),
+  );
+
+  let reply = JupyterMsg {
+    parent_header: input_request.header.to_json(),
+    ..JupyterMsg::new(
+      client.session,
+      "input_reply",
+      json!({ "status": "ok", "value": "deno" }),
+    )
+  };
+  client.send_msg(Stdin, reply).await?;
+
+  let reply = client.recv(Shell).await?;
+  assert_eq!(reply.header.msg_type, "execute_reply");
+  assert_json_subset(reply.content, json!({ "status": "ok" }));
+  assert_eq!(recv_stdout(&client, &request).await?, "hello deno\n");
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn jupyter_prompt_without_allow_stdin() -> Result<()> {
+  let (_ctx, client, _process) = setup().await;
+  let request = execute_with_stdin(
+    &client,
+    r#"try {
+  await Deno.jupyter.prompt("Name?");
+} catch (error) {
+  console.log(`${error.name}: ${error.message}`);
+}"#,
+    false,
+  )
+  .await?;
+
+  let reply = client.recv(Shell).await?;
+  assert_json_subset(reply.content, json!({ "status": "ok" }));
+  assert_eq!(
+    recv_stdout(&client, &request).await?,
+    "NotSupported: The frontend doesn't support input requests for this execution (allow_stdin is false)\n"
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn jupyter_prompt_one_request_at_a_time() -> Result<()> {
+  let (_ctx, client, _process) = setup().await;
+  let request = execute_with_stdin(
+    &client,
+    r#"const first = Deno.jupyter.prompt("first");
+try {
+  await Deno.jupyter.prompt("second");
+} catch (error) {
+  console.log(error.name);
+}
+console.log(await first);"#,
+    true,
+  )
+  .await?;
+
+  assert_eq!(recv_stdout(&client, &request).await?, "Busy\n");
+  let input_request = client.recv(Stdin).await?;
+  assert_json_subset(
+    input_request.content.clone(),
+    json!({ "prompt": "first" }),
+  );
+  let reply = JupyterMsg {
+    parent_header: input_request.header.to_json(),
+    ..JupyterMsg::new(
+      client.session,
+      "input_reply",
+      json!({ "status": "ok", "value": "1" }),
+    )
+  };
+  client.send_msg(Stdin, reply).await?;
+  assert_eq!(recv_stdout(&client, &request).await?, "1\n");
+
+  Ok(())
+}
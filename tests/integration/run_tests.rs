@@ -325,6 +325,122 @@ fn webstorage_location_shares_origin() {
   assert_eq!(output.stdout, b"Storage { hello: \"deno\", length: 1 }\n");
 }
 
+// tests that scripts run with locations of different origins don't share
+// their localStorage.
+#[test]
+fn webstorage_location_separates_origins() {
+  let context = TestContext::default();
+
+  context
+    .new_command()
+    .args("run --location https://example.com/a.ts run/webstorage/fixture.ts")
+    .run()
+    .assert_matches_text("Storage { length: 0 }\n");
+
+  for location in [
+    "https://example.org/a.ts",
+    "http://example.com/a.ts",
+    "https://example.com:8443/a.ts",
+  ] {
+    context
+      .new_command()
+      .args_vec(["run", "--location", location, "run/webstorage/logger.ts"])
+      .run()
+      .assert_matches_text("Storage { length: 0 }\n");
+  }
+
+  context
+    .new_command()
+    .args("run --location https://example.com/b.ts run/webstorage/logger.ts")
+    .run()
+    .assert_matches_text("Storage { hello: \"deno\", length: 1 }\n");
+}
+
+// tests that writes are committed when the process exits right after them
+#[test]
+fn webstorage_writes_persist_on_exit() {
+  let deno_dir = util::new_deno_dir();
+
+  let output = util::deno_cmd_with_deno_dir(&deno_dir)
+    .current_dir(util::testdata_path())
+    .arg("run")
+    .arg("--location")
+    .arg("https://example.com/a.ts")
+    .arg("run/webstorage/exit.ts")
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+
+  let output = util::deno_cmd_with_deno_dir(&deno_dir)
+    .current_dir(util::testdata_path())
+    .arg("run")
+    .arg("--location")
+    .arg("https://example.com/a.ts")
+    .arg("run/webstorage/logger.ts")
+    .stdout(Stdio::piped())
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(output.stdout, b"Storage { hello: \"deno\", length: 1 }\n");
+}
+
+// tests that failing to open the localStorage database throws a DOMException
+// instead of panicking
+#[cfg(unix)]
+#[test]
+fn webstorage_readonly_data_dir() {
+  let context = TestContext::default();
+  context
+    .deno_dir()
+    .path()
+    .join("location_data")
+    .make_dir_readonly();
+
+  context
+    .new_command()
+    .args(
+      "run --location https://example.com/a.ts run/webstorage/open_error.ts",
+    )
+    .run()
+    .assert_matches_text("true OperationError\ntrue OperationError\ndeno\n")
+    .assert_exit_code(0);
+}
+
+#[test]
+fn webstorage_corrupt_database() {
+  let context = TestContext::default();
+  context
+    .new_command()
+    .args("run --location https://example.com/a.ts run/webstorage/setter.ts")
+    .run()
+    .skip_output_check()
+    .assert_exit_code(0);
+
+  let location_data = context.deno_dir().path().join("location_data");
+  let origin_dir = location_data.read_dir().next().unwrap().unwrap().path();
+  let database = PathRef::new(origin_dir.join("local_storage"));
+  database.write(
+    "this is not a sqlite database, it is much longer than the header of one",
+  );
+  for suffix in ["-wal", "-shm"] {
+    let _ =
+      std::fs::remove_file(origin_dir.join(format!("local_storage{suffix}")));
+  }
+
+  context
+    .new_command()
+    .args(
+      "run --location https://example.com/a.ts run/webstorage/open_error.ts",
+    )
+    .run()
+    .assert_matches_text("true OperationError\ntrue OperationError\ndeno\n")
+    .assert_exit_code(0);
+}
+
 // test to ensure that when a --config file is set, but no --location, that
 // storage persists against unique configuration files.
 #[test]
@@ -1535,6 +1651,13 @@ itest!(finalization_registry {
   output: "run/finalization_registry.js.out",
 });
 
+// Reads /proc/self/maps to see whether the file is still mapped.
+#[cfg(target_os = "linux")]
+itest!(mmap_gc {
+  args: "run --quiet --unstable-fs --v8-flags=--expose-gc -A run/mmap_gc.ts",
+  output: "run/mmap_gc.ts.out",
+});
+
 itest!(https_import {
   args: "run --allow-import --quiet --reload --cert tls/RootCA.pem run/https_import.ts",
   output: "run/https_import.ts.out",
@@ -1597,6 +1720,27 @@ itest!(seed_random {
   output: "run/seed_random.js.out",
 });
 
+#[test]
+fn seed_crypto_keys() {
+  let run = |seed: &str| {
+    let output = util::deno_cmd()
+      .current_dir(util::testdata_path())
+      .arg("run")
+      .arg(format!("--seed={seed}"))
+      .arg("run/seed_crypto_keys.js")
+      .stdout_piped()
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+  };
+
+  let keys = run("42");
+  assert_eq!(keys.lines().count(), 8);
+  assert_eq!(keys, run("42"));
+  assert_ne!(keys, run("43"));
+}
+
 itest!(type_definitions {
   args: "run --reload run/type_definitions.ts",
   output: "run/type_definitions.ts.out",
@@ -4047,6 +4191,162 @@ async fn test_resolve_dns() {
   handle.abort();
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dns_resolver_flags() {
+  use std::net::SocketAddr;
+  use std::str::FromStr;
+  use std::sync::Arc;
+  use std::time::Duration;
+  use tokio::net::TcpListener;
+  use tokio::net::UdpSocket;
+  use tokio::sync::oneshot;
+  use trust_dns_server::authority::Catalog;
+  use trust_dns_server::authority::ZoneType;
+  use trust_dns_server::proto::rr::Name;
+  use trust_dns_server::store::in_memory::InMemoryAuthority;
+  use trust_dns_server::ServerFuture;
+
+  const UDP_PORT: u16 = 4554;
+  // Nothing listens here, so queries sent to it fail or time out.
+  const UNUSED_PORT: u16 = 4555;
+  const DOH_PORT: u16 = 4556;
+
+  async fn run_dns_server(tx: oneshot::Sender<()>) {
+    let zone_file = std::fs::read_to_string(
+      util::testdata_path().join("run/dns_resolver/example.zone.in"),
+    )
+    .unwrap();
+    let (origin, records) = Parser::new()
+      .parse(
+        Lexer::new(&zone_file),
+        Some(Name::from_str("example.com").unwrap()),
+      )
+      .unwrap();
+    let authority = Box::new(Arc::new(
+      InMemoryAuthority::new(origin, records, ZoneType::Primary, false)
+        .unwrap(),
+    ));
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), authority);
+
+    let mut server_fut = ServerFuture::new(catalog);
+    let udp_socket =
+      UdpSocket::bind(SocketAddr::from(([127, 0, 0, 1], UDP_PORT)))
+        .await
+        .unwrap();
+    server_fut.register_socket(udp_socket);
+
+    // trust-dns-server takes certificates of the rustls version it uses.
+    let tls_dir = util::testdata_path().join("tls");
+    let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(
+      std::fs::File::open(tls_dir.join("localhost.crt")).unwrap(),
+    ))
+    .map(|cert| rustls_doh::Certificate(cert.unwrap().to_vec()))
+    .collect();
+    let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(
+      std::fs::File::open(tls_dir.join("localhost.key")).unwrap(),
+    ))
+    .unwrap()
+    .unwrap();
+    let key = rustls_doh::PrivateKey(key.secret_der().to_vec());
+    let doh_listener =
+      TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], DOH_PORT)))
+        .await
+        .unwrap();
+    server_fut
+      .register_https_listener(
+        doh_listener,
+        Duration::from_secs(5),
+        (certs, key),
+        Some("localhost".to_string()),
+      )
+      .unwrap();
+
+    tx.send(()).unwrap();
+
+    server_fut.block_until_done().await.unwrap();
+  }
+
+  let (ready_tx, ready_rx) = oneshot::channel();
+  let handle = tokio::spawn(run_dns_server(ready_tx));
+  ready_rx.await.unwrap();
+
+  let run = |args: &[&str]| {
+    util::deno_cmd()
+      .current_dir(util::testdata_path())
+      .env("NO_COLOR", "1")
+      .arg("run")
+      .args(args)
+      .arg("run/dns_resolver/main.ts")
+      .piped_output()
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap()
+  };
+  let expected = std::fs::read_to_string(
+    util::testdata_path().join("run/dns_resolver/main.ts.out"),
+  )
+  .unwrap();
+
+  // Fails over to the second server when the first one doesn't answer.
+  {
+    let output = run(&[
+      "--allow-net",
+      &format!("--dns-server=127.0.0.1:{UNUSED_PORT},127.0.0.1:{UDP_PORT}"),
+      "--dns-timeout=500",
+    ]);
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {err}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+  }
+
+  // The host of the DoH endpoint is only known by its bootstrap address.
+  {
+    let output = run(&[
+      "--allow-net",
+      "--cert=tls/RootCA.pem",
+      &format!("--dns-over-https=https://localhost:{DOH_PORT}/dns-query"),
+      "--dns-bootstrap=127.0.0.1",
+    ]);
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {err}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+  }
+
+  // Without a bootstrap address, the endpoint's host can't be resolved.
+  {
+    let output = run(&[
+      "--allow-net",
+      &format!("--dns-over-https=https://localhost:{DOH_PORT}/dns-query"),
+    ]);
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(err.contains("needs bootstrap addresses"), "stderr: {err}");
+  }
+
+  // Queries to the DoH endpoint need net permission for it.
+  {
+    let output = run(&[
+      "--allow-net=127.0.0.1,local.example.com",
+      "--cert=tls/RootCA.pem",
+      &format!("--dns-over-https=https://localhost:{DOH_PORT}/dns-query"),
+      "--dns-bootstrap=127.0.0.1",
+    ]);
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+      err.contains(&format!(
+        r#"NotCapable: Requires net access to "localhost:{DOH_PORT}""#
+      )),
+      "stderr: {err}"
+    );
+    assert!(output.stdout.is_empty());
+  }
+
+  handle.abort();
+}
+
 #[tokio::test]
 async fn http2_request_url() {
   let mut child = util::deno_cmd()
@@ -4129,6 +4429,138 @@ fn fsfile_set_raw_should_not_panic_on_no_tty() {
   );
 }
 
+#[cfg(unix)]
+#[test]
+fn tty_events_decode_input() {
+  TestContext::default()
+    .new_command()
+    .args_vec(["run", "--quiet", "--unstable-tty", "run/tty_events.ts"])
+    .with_pty(|mut console| {
+      console.expect("ready");
+      console.write_raw("a");
+      console.expect("key a a");
+      console.write_raw("\x1b[1;5A");
+      console.expect("key ArrowUp ctrl");
+      console.write_raw("\x1b[<0;3;5M");
+      console.expect("mouse left press 2 4");
+      console.write_raw("\x1b[200~pasted text\x1b[201~");
+      console.expect("paste pasted text");
+      console.write_raw("\x1b");
+      console.expect("key Escape");
+      console.write_raw("q");
+      console.expect_raw_in_current_output("\x1b[?2004l");
+      console.expect_raw_in_current_output("\x1b[?1000l");
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn tty_events_not_a_terminal() {
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("--unstable-tty")
+    .arg("Deno.openTtyEvents()")
+    .stdin(std::process::Stdio::piped())
+    .stderr_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap().trim();
+  assert!(
+    stderr.contains("BadResource"),
+    "stderr did not contain BadResource: {stderr}"
+  );
+}
+
+#[cfg(unix)]
+#[test]
+fn readline_edit_history_and_complete() {
+  let context = TestContext::default();
+  let history = context.temp_dir().path().join("history");
+  history.write("from history\n");
+  context
+    .new_command()
+    .args_vec([
+      "run",
+      "--quiet",
+      "--unstable-tty",
+      "--allow-read",
+      "--allow-write",
+      "run/readline.ts",
+      history.to_string_lossy().as_ref(),
+    ])
+    .with_pty(|mut console| {
+      console.expect("ready");
+      // "hello wld", then the missing letters before "ld"
+      console.write_raw("hello wld\x1b[D\x1b[Dor\r");
+      console.expect("line: hello world");
+      console.write_raw("\x1b[A\x1b[A\r");
+      console.expect("line: from history");
+      // "hel" is inserted, then the candidates are listed
+      console.write_raw("he\t");
+      console.write_raw("\t");
+      console.expect("hello  help");
+      console.write_raw("p\r");
+      console.expect("line: help");
+      console.write_raw("abc\x03");
+      console.expect("interrupt");
+      console.write_raw("\x04");
+      console.expect("eof");
+      console.expect("done");
+    });
+  assert_eq!(
+    history.read_to_string(),
+    "hello world\nfrom history\nhelp\n"
+  );
+}
+
+/// Runs `run/fd_exhaustion.ts` with both descriptor limits lowered, so that
+/// the CLI can't raise the soft limit again at startup.
+#[cfg(unix)]
+fn run_fd_exhaustion(mode: &str, envs: &[(&str, &str)]) -> String {
+  let deno_dir = util::new_deno_dir();
+  let output = Command::new("sh")
+    .arg("-c")
+    .arg(r#"ulimit -n 256 && exec "$@""#)
+    .arg("sh")
+    .arg(util::deno_exe_path())
+    .arg("run")
+    .arg("--quiet")
+    .arg("--allow-read")
+    .arg("--allow-net")
+    .arg("--allow-sys=fdLimits")
+    .arg("run/fd_exhaustion.ts")
+    .arg(mode)
+    .envs(envs.iter().copied())
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::testdata_path())
+    .stderr(Stdio::inherit())
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  String::from_utf8(output.stdout).unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_exhaustion_retry() {
+  assert_eq!(
+    run_fd_exhaustion("retry", &[]),
+    "limits ok\nsaturated\nopen retried\naccept retried\nopen gave up\n"
+  );
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_exhaustion_reserve_fd() {
+  assert_eq!(
+    run_fd_exhaustion("reserve", &[("DENO_RESERVE_FD", "1")]),
+    "limits ok\nsaturated\nconnection shed\n"
+  );
+}
+
 #[test]
 fn timeout_clear() {
   // https://github.com/denoland/deno/issues/7599
@@ -0,0 +1,49 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+import { assertEquals, loadTestLibrary } from "./common.js";
+
+const lib = loadTestLibrary();
+
+if (import.meta.main) {
+  lib.installAsyncCleanupHook(Deno.args[0]);
+  lib.setInstanceData(3);
+  console.log("installed async cleanup hook");
+} else {
+  Deno.test("napi async cleanup hook completes before exit", async () => {
+    const tempDir = Deno.makeTempDirSync();
+    const path = `${tempDir}/flushed.txt`;
+    try {
+      const { stdout, stderr, code } = await new Deno.Command(
+        Deno.execPath(),
+        {
+          args: [
+            "run",
+            "--config",
+            Deno.realPathSync("../config/deno.json"),
+            "--no-lock",
+            "-A",
+            "--unstable-ffi",
+            import.meta.url,
+            path,
+          ],
+        },
+      ).output();
+
+      assertEquals(new TextDecoder().decode(stderr), "");
+      assertEquals(code, 0);
+
+      const stdoutLines = new TextDecoder().decode(stdout).split("\n");
+      assertEquals(stdoutLines, [
+        "installed async cleanup hook",
+        "async cleanup done",
+        "instance data finalized(3)",
+        "",
+      ]);
+      assertEquals(Deno.readTextFileSync(path), "flushed");
+    } finally {
+      Deno.removeSync(tempDir, { recursive: true });
+    }
+  });
+}
@@ -3,6 +3,13 @@
 import { assertEquals, loadTestLibrary } from "./common.js";
 
 const asyncTask = loadTestLibrary();
+const ops = Deno[Deno.internal].core.ops;
+
+function metrics() {
+  return ops.op_napi_metrics().findLast(({ filename }) =>
+    filename.includes("test_napi")
+  );
+}
 
 Deno.test("napi async task schedule", async () => {
   let called = false;
@@ -14,3 +21,37 @@ Deno.test("napi async task schedule", async () => {
   });
   assertEquals(called, true);
 });
+
+Deno.test("napi async task cancel", async () => {
+  const [status, executed, lateCancelStatus] = await new Promise((resolve) => {
+    asyncTask.test_cancel_async_work((...args) => resolve(args), true);
+  });
+  // napi_cancelled
+  assertEquals(status, 11);
+  assertEquals(executed, false);
+  // napi_generic_failure
+  assertEquals(lateCancelStatus, 9);
+});
+
+Deno.test("napi async task cancel after completion fails", async () => {
+  const [status, executed, lateCancelStatus] = await new Promise((resolve) => {
+    asyncTask.test_cancel_async_work((...args) => resolve(args), false);
+  });
+  assertEquals(status, 0);
+  assertEquals(executed, true);
+  assertEquals(lateCancelStatus, 9);
+});
+
+Deno.test("napi async task metrics", async () => {
+  const before = metrics();
+  const [status] = await new Promise((resolve) => {
+    asyncTask.test_cancel_async_work((...args) => resolve(args), true);
+  });
+  assertEquals(status, 11);
+  await new Promise((resolve) => asyncTask.test_async_work(resolve));
+
+  const after = metrics();
+  assertEquals(after.pendingAsyncWork, 0);
+  assertEquals(after.executedAsyncWork, before.executedAsyncWork + 1);
+  assertEquals(after.cancelledAsyncWork, before.cancelledAsyncWork + 1);
+});
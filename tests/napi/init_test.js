@@ -1,7 +1,14 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 import { Buffer } from "node:buffer";
-import { assert, libSuffix } from "./common.js";
+import process from "node:process";
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+  libSuffix,
+  loadTestLibrary,
+} from "./common.js";
 import { Worker } from "node:worker_threads";
 
 const ops = Deno[Deno.internal].core.ops;
@@ -48,3 +55,66 @@ Deno.test("ctr initialization by multiple threads (napi_module_register)", {
 
   await p.promise;
 });
+
+Deno.test("module api version is checked before initialization", {
+  ignore: Deno.build.os == "windows",
+}, function () {
+  const path = new URL(`./module_version.${libSuffix}`, import.meta.url)
+    .pathname;
+  const obj = ops.op_napi_open(path, {}, Buffer, reportError);
+  assert(obj != null);
+  assert(typeof obj === "object");
+
+  // initializing this one would abort the process
+  const unsupported = new URL(
+    `./module_version_unsupported.${libSuffix}`,
+    import.meta.url,
+  ).pathname;
+  assertThrows(
+    () => ops.op_napi_open(unsupported, {}, Buffer, reportError),
+    TypeError,
+    "requires Node-API version 1000",
+  );
+});
+
+Deno.test("modules are cached by canonicalized path", {
+  ignore: Deno.build.os == "windows",
+}, function () {
+  const path = new URL(`./module.${libSuffix}`, import.meta.url).pathname;
+  const obj = ops.op_napi_open(path, {}, Buffer, reportError);
+  assert(ops.op_napi_open(path, {}, Buffer, reportError) === obj);
+
+  const dir = Deno.makeTempDirSync();
+  const link = `${dir}/module.${libSuffix}`;
+  Deno.symlinkSync(path, link);
+  assert(ops.op_napi_open(link, {}, Buffer, reportError) === obj);
+
+  // modules registered through `napi_register_module_v1`
+  assert(loadTestLibrary() === loadTestLibrary());
+});
+
+Deno.test("exceptions thrown while registering a module are rethrown", {
+  ignore: Deno.build.os == "windows",
+}, function () {
+  const path = new URL(`./module_throw.${libSuffix}`, import.meta.url)
+    .pathname;
+  const error = assertThrows(
+    () => process.dlopen({}, path),
+    Error,
+    "init failed",
+  );
+  assertEquals(error.code, "ERR_INIT_FAILED");
+  // not cached, the register function runs again
+  assertThrows(() => process.dlopen({}, path), Error, "init failed");
+});
+
+Deno.test("a register function returning NULL keeps the exports object", {
+  ignore: Deno.build.os == "windows",
+}, function () {
+  const path = new URL(`./module_null_exports.${libSuffix}`, import.meta.url)
+    .pathname;
+  const module = { exports: {} };
+  process.dlopen(module, path);
+  assert(typeof module.exports === "object");
+  assert(module.exports != null);
+});
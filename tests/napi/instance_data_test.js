@@ -0,0 +1,39 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// deno-lint-ignore-file no-console
+
+import { assertEquals, loadTestLibrary } from "./common.js";
+
+const lib = loadTestLibrary();
+
+if (import.meta.main) {
+  lib.installCleanupHook();
+  lib.setInstanceData(7);
+  console.log(`instance data ${lib.getInstanceData()}`);
+} else {
+  Deno.test("napi instance data is finalized after cleanup hooks", async () => {
+    const { stdout, stderr, code } = await new Deno.Command(Deno.execPath(), {
+      args: [
+        "run",
+        "--config",
+        Deno.realPathSync("../config/deno.json"),
+        "--no-lock",
+        "-A",
+        "--unstable-ffi",
+        import.meta.url,
+      ],
+    }).output();
+
+    assertEquals(new TextDecoder().decode(stderr), "");
+    assertEquals(code, 0);
+
+    const stdoutLines = new TextDecoder().decode(stdout).split("\n");
+    assertEquals(stdoutLines, [
+      "instance data 7",
+      "cleanup(18)",
+      "cleanup(42)",
+      "instance data finalized(7)",
+      "",
+    ]);
+  });
+}
@@ -0,0 +1,25 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// A module registered through `napi_register_module_v1` whose register
+// function throws and returns NULL. Built with `NO_THROW` defined, it only
+// returns NULL, so the loader falls back to the exports object it passed in.
+
+#include <stddef.h>
+
+#ifdef _WIN32
+#define NAPI_EXTERN __declspec(dllexport)
+#else
+#define NAPI_EXTERN __attribute__((visibility("default")))
+#endif
+
+extern int napi_throw_error(void* env, const char* code, const char* msg);
+
+NAPI_EXTERN void* napi_register_module_v1(void* env, void* exports
+                                          __attribute__((unused))) {
+#ifndef NO_THROW
+  napi_throw_error(env, "ERR_INIT_FAILED", "init failed");
+#else
+  (void)env;
+#endif
+  return NULL;
+}
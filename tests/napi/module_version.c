@@ -0,0 +1,26 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// A module registered through `napi_register_module_v1` that reports
+// `MODULE_API_VERSION` from `node_api_module_get_api_version_v1`. Built with
+// `EXPECT_REJECTED` defined, initializing it aborts the process, so a test
+// loading it can tell whether the loader refused it before running its code.
+
+#include <stdlib.h>
+
+#ifdef _WIN32
+#define NAPI_EXTERN __declspec(dllexport)
+#else
+#define NAPI_EXTERN __attribute__((visibility("default")))
+#endif
+
+NAPI_EXTERN int node_api_module_get_api_version_v1(void) {
+  return MODULE_API_VERSION;
+}
+
+NAPI_EXTERN void* napi_register_module_v1(void* env __attribute__((unused)),
+                                          void* exports) {
+#ifdef EXPECT_REJECTED
+  abort();
+#endif
+  return exports;
+}
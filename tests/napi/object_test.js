@@ -36,3 +36,91 @@ Deno.test("napi object", function () {
     "Cannot assign to read only property 'method' of object '#<Object>'",
   );
 });
+
+// napi_key_collection_mode
+const KEY_INCLUDE_PROTOTYPES = 0;
+const KEY_OWN_ONLY = 1;
+// napi_key_filter
+const KEY_ALL_PROPERTIES = 0;
+const KEY_WRITABLE = 1;
+const KEY_ENUMERABLE = 1 << 1;
+const KEY_CONFIGURABLE = 1 << 2;
+const KEY_SKIP_STRINGS = 1 << 3;
+const KEY_SKIP_SYMBOLS = 1 << 4;
+// napi_key_conversion
+const KEY_KEEP_NUMBERS = 0;
+const KEY_NUMBERS_TO_STRINGS = 1;
+
+Deno.test("napi get all property names", function () {
+  const symbol = Symbol("sym");
+  const proto = { inherited: 1 };
+  const obj = Object.create(proto);
+  obj[0] = "index";
+  obj.plain = 1;
+  obj[symbol] = 2;
+  Object.defineProperty(obj, "readonly", {
+    value: 3,
+    enumerable: true,
+    configurable: true,
+    writable: false,
+  });
+  Object.defineProperty(obj, "hidden", {
+    value: 4,
+    enumerable: false,
+    configurable: true,
+    writable: true,
+  });
+  Object.defineProperty(obj, "fixed", {
+    value: 5,
+    enumerable: true,
+    configurable: false,
+    writable: true,
+  });
+
+  const names = (mode, filter, conversion = KEY_NUMBERS_TO_STRINGS) =>
+    object.test_object_get_all_property_names(obj, mode, filter, conversion);
+
+  assertEquals(names(KEY_OWN_ONLY, KEY_ALL_PROPERTIES), [
+    "0",
+    "plain",
+    "readonly",
+    "hidden",
+    "fixed",
+    symbol,
+  ]);
+  assertEquals(names(KEY_OWN_ONLY, KEY_ALL_PROPERTIES, KEY_KEEP_NUMBERS), [
+    0,
+    "plain",
+    "readonly",
+    "hidden",
+    "fixed",
+    symbol,
+  ]);
+  assertEquals(names(KEY_OWN_ONLY, KEY_WRITABLE | KEY_SKIP_SYMBOLS), [
+    "0",
+    "plain",
+    "hidden",
+    "fixed",
+  ]);
+  assertEquals(names(KEY_OWN_ONLY, KEY_ENUMERABLE | KEY_SKIP_SYMBOLS), [
+    "0",
+    "plain",
+    "readonly",
+    "fixed",
+  ]);
+  assertEquals(names(KEY_OWN_ONLY, KEY_CONFIGURABLE | KEY_SKIP_SYMBOLS), [
+    "0",
+    "plain",
+    "readonly",
+    "hidden",
+  ]);
+  assertEquals(names(KEY_OWN_ONLY, KEY_SKIP_STRINGS), [symbol]);
+  assertEquals(
+    names(KEY_OWN_ONLY, KEY_WRITABLE | KEY_ENUMERABLE | KEY_CONFIGURABLE),
+    ["0", "plain", symbol],
+  );
+  assertEquals(
+    names(KEY_INCLUDE_PROTOTYPES, KEY_ENUMERABLE | KEY_SKIP_SYMBOLS),
+    ["0", "plain", "readonly", "fixed", "inherited"],
+  );
+});
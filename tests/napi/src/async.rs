@@ -101,9 +101,115 @@ extern "C" fn test_async_work(
   ptr::null_mut()
 }
 
+struct CancelBaton {
+  func: napi_ref,
+  task: napi_async_work,
+  executed: bool,
+}
+
+unsafe extern "C" fn cancel_execute(_env: napi_env, data: *mut c_void) {
+  let baton = &mut *(data as *mut CancelBaton);
+  baton.executed = true;
+}
+
+/// Calls the JS callback with the status passed to `complete`, whether
+/// `execute` ran, and the status of cancelling the already finished work.
+unsafe extern "C" fn cancel_complete(
+  env: napi_env,
+  status: napi_status,
+  data: *mut c_void,
+) {
+  let baton = Box::from_raw(data as *mut CancelBaton);
+
+  let late_cancel_status = napi_cancel_async_work(env, baton.task);
+
+  let mut args = [ptr::null_mut(); 3];
+  assert_napi_ok!(napi_create_int32(env, status, &mut args[0]));
+  assert_napi_ok!(napi_get_boolean(env, baton.executed, &mut args[1]));
+  assert_napi_ok!(napi_create_int32(env, late_cancel_status, &mut args[2]));
+
+  let mut global: napi_value = ptr::null_mut();
+  assert_napi_ok!(napi_get_global(env, &mut global));
+  let mut callback: napi_value = ptr::null_mut();
+  assert_napi_ok!(napi_get_reference_value(env, baton.func, &mut callback));
+  let mut _result: napi_value = ptr::null_mut();
+  assert_napi_ok!(napi_call_function(
+    env,
+    global,
+    callback,
+    args.len(),
+    args.as_ptr(),
+    &mut _result
+  ));
+  assert_napi_ok!(napi_delete_reference(env, baton.func));
+  assert_napi_ok!(napi_delete_async_work(env, baton.task));
+}
+
+/// Queues a work item, and cancels it right away when the second argument is
+/// true.
+extern "C" fn test_cancel_async_work(
+  env: napi_env,
+  info: napi_callback_info,
+) -> napi_value {
+  let (args, argc, _) = napi_get_callback_info!(env, info, 2);
+  assert_eq!(argc, 2);
+
+  let mut cancel_before_start = false;
+  assert_napi_ok!(napi_get_value_bool(env, args[1], &mut cancel_before_start));
+
+  let mut resource_name: napi_value = ptr::null_mut();
+  assert_napi_ok!(napi_create_string_utf8(
+    env,
+    "test_cancel_async_resource".as_ptr() as *const c_char,
+    usize::MAX,
+    &mut resource_name,
+  ));
+
+  let mut func: napi_ref = ptr::null_mut();
+  assert_napi_ok!(napi_create_reference(env, args[0], 1, &mut func));
+  let baton = Box::into_raw(Box::new(CancelBaton {
+    func,
+    task: ptr::null_mut(),
+    executed: false,
+  }));
+
+  let mut async_work: napi_async_work = ptr::null_mut();
+  assert_napi_ok!(napi_create_async_work(
+    env,
+    ptr::null_mut(),
+    resource_name,
+    Some(cancel_execute),
+    Some(cancel_complete),
+    baton as *mut c_void,
+    &mut async_work,
+  ));
+  unsafe {
+    (*baton).task = async_work;
+  }
+
+  // work that was never queued can't be cancelled
+  assert_eq!(
+    unsafe { napi_cancel_async_work(env, async_work) },
+    Status::napi_generic_failure
+  );
+  assert_napi_ok!(napi_queue_async_work(env, async_work));
+  if cancel_before_start {
+    assert_napi_ok!(napi_cancel_async_work(env, async_work));
+    // it is no longer queued
+    assert_eq!(
+      unsafe { napi_cancel_async_work(env, async_work) },
+      Status::napi_generic_failure
+    );
+  }
+
+  ptr::null_mut()
+}
+
 pub fn init(env: napi_env, exports: napi_value) {
-  let properties =
-    &[napi_new_property!(env, "test_async_work", test_async_work)];
+  let properties = &[
+    napi_new_property!(env, "test_async_work", test_async_work),
+    napi_new_property!(env, "test_cancel_async_work", test_cancel_async_work),
+  ];
 
   assert_napi_ok!(napi_define_properties(
     env,
@@ -4,6 +4,7 @@ use crate::assert_napi_ok;
 use crate::napi_get_callback_info;
 use crate::napi_new_property;
 use napi_sys::*;
+use std::ffi::c_void;
 
 extern "C" fn get_node_global(
   env: napi_env,
@@ -18,9 +19,60 @@ extern "C" fn get_node_global(
   result
 }
 
+unsafe extern "C" fn finalize_instance_data(
+  _env: napi_env,
+  data: *mut c_void,
+  _hint: *mut c_void,
+) {
+  let data = unsafe { Box::from_raw(data as *mut u32) };
+  println!("instance data finalized({data})");
+}
+
+extern "C" fn set_instance_data(
+  env: napi_env,
+  info: napi_callback_info,
+) -> napi_value {
+  let (args, argc, _) = napi_get_callback_info!(env, info, 1);
+  assert_eq!(argc, 1);
+
+  let mut value: u32 = 0;
+  assert_napi_ok!(napi_get_value_uint32(env, args[0], &mut value));
+  let data = Box::into_raw(Box::new(value));
+  assert_napi_ok!(napi_set_instance_data(
+    env,
+    data as *mut c_void,
+    Some(finalize_instance_data),
+    std::ptr::null_mut(),
+  ));
+
+  std::ptr::null_mut()
+}
+
+extern "C" fn get_instance_data(
+  env: napi_env,
+  info: napi_callback_info,
+) -> napi_value {
+  let (_, argc, _) = napi_get_callback_info!(env, info, 0);
+  assert_eq!(argc, 0);
+
+  let mut data: *mut c_void = std::ptr::null_mut();
+  assert_napi_ok!(napi_get_instance_data(env, &mut data));
+  let mut result: napi_value = std::ptr::null_mut();
+  assert_napi_ok!(napi_create_uint32(
+    env,
+    unsafe { *(data as *mut u32) },
+    &mut result
+  ));
+
+  result
+}
+
 pub fn init(env: napi_env, exports: napi_value) {
-  let properties =
-    &[napi_new_property!(env, "testNodeGlobal", get_node_global)];
+  let properties = &[
+    napi_new_property!(env, "testNodeGlobal", get_node_global),
+    napi_new_property!(env, "setInstanceData", set_instance_data),
+    napi_new_property!(env, "getInstanceData", get_instance_data),
+  ];
 
   assert_napi_ok!(napi_define_properties(
     env,
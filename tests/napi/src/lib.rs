@@ -116,12 +116,71 @@ extern "C" fn install_cleanup_hook(
   std::ptr::null_mut()
 }
 
-pub fn init_cleanup_hook(env: napi_env, exports: napi_value) {
-  let properties = &[napi_new_property!(
+struct AsyncCleanupPath(String);
+
+unsafe extern "C" fn async_cleanup(
+  handle: napi_async_cleanup_hook_handle,
+  arg: *mut c_void,
+) {
+  let path = unsafe { Box::from_raw(arg as *mut AsyncCleanupPath) };
+  let handle = handle as usize;
+  // Complete on another thread, like an addon flushing pending I/O.
+  std::thread::spawn(move || {
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    std::fs::write(&path.0, "flushed").unwrap();
+    println!("async cleanup done");
+    assert_napi_ok!(napi_remove_async_cleanup_hook(handle as _));
+  });
+}
+
+extern "C" fn install_async_cleanup_hook(
+  env: napi_env,
+  info: napi_callback_info,
+) -> napi_value {
+  let (args, argc, _) = napi_get_callback_info!(env, info, 1);
+  assert_eq!(argc, 1);
+
+  let mut buf = [0u8; 1024];
+  let mut len = 0;
+  assert_napi_ok!(napi_get_value_string_utf8(
+    env,
+    args[0],
+    buf.as_mut_ptr() as _,
+    buf.len(),
+    &mut len
+  ));
+  let path = String::from_utf8(buf[..len].to_vec()).unwrap();
+
+  // A hook that is removed before shutdown never runs.
+  let mut removed = std::ptr::null_mut();
+  assert_napi_ok!(napi_add_async_cleanup_hook(
     env,
-    "installCleanupHook",
-    install_cleanup_hook
-  )];
+    Some(async_cleanup),
+    std::ptr::null_mut(),
+    &mut removed
+  ));
+  assert_napi_ok!(napi_remove_async_cleanup_hook(removed));
+
+  let path = Box::into_raw(Box::new(AsyncCleanupPath(path)));
+  assert_napi_ok!(napi_add_async_cleanup_hook(
+    env,
+    Some(async_cleanup),
+    path as *mut c_void,
+    std::ptr::null_mut()
+  ));
+
+  std::ptr::null_mut()
+}
+
+pub fn init_cleanup_hook(env: napi_env, exports: napi_value) {
+  let properties = &[
+    napi_new_property!(env, "installCleanupHook", install_cleanup_hook),
+    napi_new_property!(
+      env,
+      "installAsyncCleanupHook",
+      install_async_cleanup_hook
+    ),
+  ];
 
   assert_napi_ok!(napi_define_properties(
     env,
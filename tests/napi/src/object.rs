@@ -64,6 +64,34 @@ extern "C" fn test_object_attr_property(
   obj
 }
 
+/// Calls `napi_get_all_property_names` with the object, key collection mode,
+/// key filter and key conversion passed from JS.
+extern "C" fn test_object_get_all_property_names(
+  env: napi_env,
+  info: napi_callback_info,
+) -> napi_value {
+  let (args, argc, _) = napi_get_callback_info!(env, info, 4);
+  assert_eq!(argc, 4);
+
+  let mut key_mode = 0;
+  assert_napi_ok!(napi_get_value_int32(env, args[1], &mut key_mode));
+  let mut key_filter = 0;
+  assert_napi_ok!(napi_get_value_int32(env, args[2], &mut key_filter));
+  let mut key_conversion = 0;
+  assert_napi_ok!(napi_get_value_int32(env, args[3], &mut key_conversion));
+
+  let mut result: napi_value = ptr::null_mut();
+  assert_napi_ok!(napi_get_all_property_names(
+    env,
+    args[0],
+    key_mode,
+    key_filter,
+    key_conversion,
+    &mut result
+  ));
+  result
+}
+
 pub fn init(env: napi_env, exports: napi_value) {
   let properties = &[
     napi_new_property!(env, "test_object_new", test_object_new),
@@ -73,6 +101,11 @@ pub fn init(env: napi_env, exports: napi_value) {
       "test_object_attr_property",
       test_object_attr_property
     ),
+    napi_new_property!(
+      env,
+      "test_object_get_all_property_names",
+      test_object_get_all_property_names
+    ),
   ];
 
   assert_napi_ok!(napi_define_properties(
@@ -3,7 +3,11 @@
 // This test performs initialization similar to napi-rs.
 // https://github.com/napi-rs/napi-rs/commit/a5a04a4e545f268769cc78e2bd6c45af4336aac3
 
+use crate::assert_napi_ok;
+use crate::napi_get_callback_info;
+use crate::napi_new_property;
 use napi_sys as sys;
+use napi_sys::napi_property_descriptor;
 use std::ffi::c_char;
 use std::ffi::c_void;
 use std::ptr;
@@ -105,6 +109,78 @@ extern "C" fn custom_gc(
   );
 }
 
-pub fn init(env: sys::napi_env, _exports: sys::napi_value) {
+/// Fills the queue of a threadsafe function that holds one call, from the JS
+/// thread, and returns the statuses of a call that fits, a non-blocking call
+/// and a blocking call. The queued call invokes `callback` later.
+extern "C" fn test_tsfn_queue_full(
+  env: sys::napi_env,
+  info: sys::napi_callback_info,
+) -> sys::napi_value {
+  let (args, argc, _) = napi_get_callback_info!(env, info, 1);
+  assert_eq!(argc, 1);
+
+  let mut resource_name = ptr::null_mut();
+  assert_napi_ok!(sys::napi_create_string_utf8(
+    env,
+    "queue_full".as_ptr() as *const c_char,
+    10,
+    &mut resource_name,
+  ));
+  let mut tsfn = ptr::null_mut();
+  assert_napi_ok!(sys::napi_create_threadsafe_function(
+    env,
+    args[0],
+    ptr::null_mut(),
+    resource_name,
+    1,
+    1,
+    ptr::null_mut(),
+    None,
+    ptr::null_mut(),
+    None,
+    &mut tsfn,
+  ));
+
+  let statuses = [
+    sys::ThreadsafeFunctionCallMode::nonblocking,
+    sys::ThreadsafeFunctionCallMode::nonblocking,
+    sys::ThreadsafeFunctionCallMode::blocking,
+  ]
+  .map(|mode| unsafe {
+    sys::napi_call_threadsafe_function(tsfn, ptr::null_mut(), mode)
+  });
+
+  assert_napi_ok!(sys::napi_release_threadsafe_function(
+    tsfn,
+    sys::ThreadsafeFunctionReleaseMode::release,
+  ));
+
+  let mut result = ptr::null_mut();
+  assert_napi_ok!(sys::napi_create_array_with_length(
+    env,
+    statuses.len(),
+    &mut result
+  ));
+  for (i, status) in statuses.into_iter().enumerate() {
+    let mut value = ptr::null_mut();
+    assert_napi_ok!(sys::napi_create_int32(env, status, &mut value));
+    assert_napi_ok!(sys::napi_set_element(env, result, i as u32, value));
+  }
+  result
+}
+
+pub fn init(env: sys::napi_env, exports: sys::napi_value) {
   create_custom_gc(env);
+
+  let properties = &[napi_new_property!(
+    env,
+    "test_tsfn_queue_full",
+    test_tsfn_queue_full
+  )];
+  assert_napi_ok!(sys::napi_define_properties(
+    env,
+    exports,
+    properties.len(),
+    properties.as_ptr()
+  ));
 }
@@ -29,30 +29,45 @@ fn build() {
   // cc module.c -undefined dynamic_lookup -shared -Wl,-no_fixup_chains -dynamic -o module.dylib
   #[cfg(not(target_os = "windows"))]
   {
-    let out = if cfg!(target_os = "macos") {
-      "module.dylib"
+    let ext = if cfg!(target_os = "macos") {
+      "dylib"
     } else {
-      "module.so"
+      "so"
     };
+    let modules: [(&str, &str, &[&str]); 5] = [
+      ("module.c", "module", &[]),
+      (
+        "module_version.c",
+        "module_version",
+        &["-DMODULE_API_VERSION=8"],
+      ),
+      (
+        "module_version.c",
+        "module_version_unsupported",
+        &["-DMODULE_API_VERSION=1000", "-DEXPECT_REJECTED"],
+      ),
+      ("module_throw.c", "module_throw", &[]),
+      ("module_throw.c", "module_null_exports", &["-DNO_THROW"]),
+    ];
 
-    let mut cc = Command::new("cc");
+    for (source, name, defines) in modules {
+      let mut cc = Command::new("cc");
+      cc.arg(source).args(defines);
 
-    #[cfg(not(target_os = "macos"))]
-    let c_module = cc.arg("module.c").arg("-shared").arg("-o").arg(out);
+      #[cfg(not(target_os = "macos"))]
+      cc.arg("-shared");
 
-    #[cfg(target_os = "macos")]
-    let c_module = {
-      cc.arg("module.c")
-        .arg("-undefined")
+      #[cfg(target_os = "macos")]
+      cc.arg("-undefined")
         .arg("dynamic_lookup")
         .arg("-shared")
         .arg("-Wl,-no_fixup_chains")
-        .arg("-dynamic")
-        .arg("-o")
-        .arg(out)
-    };
-    let c_module_output = c_module.output().unwrap();
-    assert!(c_module_output.status.success());
+        .arg("-dynamic");
+
+      let c_module_output =
+        cc.arg("-o").arg(format!("{name}.{ext}")).output().unwrap();
+      assert!(c_module_output.status.success());
+    }
   }
 }
 
@@ -66,6 +81,7 @@ fn napi_tests() {
     .env("RUST_BACKTRACE", "1")
     .arg("test")
     .arg("--allow-read")
+    .arg("--allow-write")
     .arg("--allow-env")
     .arg("--allow-ffi")
     .arg("--allow-run")
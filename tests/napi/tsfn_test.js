@@ -0,0 +1,38 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+import { assertEquals, loadTestLibrary } from "./common.js";
+
+const tsfn = loadTestLibrary();
+const ops = Deno[Deno.internal].core.ops;
+
+function metrics() {
+  return ops.op_napi_metrics().findLast(({ filename }) =>
+    filename.includes("test_napi")
+  );
+}
+
+Deno.test("napi threadsafe function queue full", async () => {
+  let calls = 0;
+  let statuses;
+  await new Promise((resolve) => {
+    statuses = tsfn.test_tsfn_queue_full(() => {
+      calls++;
+      resolve();
+    });
+  });
+  // napi_ok, napi_queue_full, napi_would_deadlock
+  assertEquals(statuses, [0, 15, 21]);
+  assertEquals(calls, 1);
+
+  // the finalization is queued after the call
+  await new Promise((resolve) => setTimeout(resolve, 0));
+  const {
+    pendingThreadsafeCalls,
+    activeThreadsafeFunctions,
+    referencedThreadsafeFunctions,
+  } = metrics();
+  assertEquals(pendingThreadsafeCalls, 0);
+  // the unref'd custom GC threadsafe function from init is never released
+  assertEquals(activeThreadsafeFunctions, 1);
+  assertEquals(referencedThreadsafeFunctions, 0);
+});
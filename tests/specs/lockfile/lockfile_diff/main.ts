@@ -0,0 +1,7 @@
+const diffs = await Deno[Deno.internal].core.ops.op_lockfile_diff([
+  "http://localhost:4545/subdir/print_hello.ts",
+  "http://localhost:4545/subdir/mod1.ts",
+  "http://localhost:4545/subdir/mod2.ts",
+  import.meta.url,
+]);
+console.log(JSON.stringify(diffs, null, 2));
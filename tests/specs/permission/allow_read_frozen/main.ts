@@ -0,0 +1,24 @@
+// Changes made to the frozen directory after startup must not be visible.
+Deno.writeTextFileSync("./frozen/added.txt", "added\n");
+Deno.removeSync("./frozen/removed.txt");
+
+try {
+  console.log(Deno.readTextFileSync("./frozen/kept.txt").trim());
+  for (const name of ["added.txt", "removed.txt"]) {
+    try {
+      Deno.readTextFileSync(`./frozen/${name}`);
+      console.log(name, "readable");
+    } catch (err) {
+      console.log(name, (err as Error).name);
+    }
+  }
+  try {
+    Deno.statSync("./frozen/added.txt");
+    console.log("stat added.txt ok");
+  } catch (err) {
+    console.log("stat added.txt", (err as Error).name);
+  }
+} finally {
+  Deno.removeSync("./frozen/added.txt");
+  Deno.writeTextFileSync("./frozen/removed.txt", "removed\n");
+}
@@ -0,0 +1,41 @@
+function usage() {
+  return Deno.fsLimitUsage().map(({ openFiles, bytesRead }) => ({
+    openFiles,
+    bytesRead,
+  }));
+}
+
+function logError(fn: () => unknown) {
+  try {
+    fn();
+  } catch (err) {
+    console.log(err instanceof Deno.errors.LimitExceeded, String(err));
+  }
+}
+
+const a = Deno.openSync("data/a.txt");
+const b = await Deno.open("data/b.txt");
+logError(() => Deno.openSync("data/c.txt"));
+console.log(usage());
+
+// closing a file makes room for another one
+a.close();
+const c = Deno.openSync("data/c.txt");
+console.log(usage()[0].openFiles);
+
+// bytes add up across reads, going past a soft limit is fine
+const buf = new Uint8Array(4);
+while (c.readSync(buf) !== null);
+await b.read(buf);
+Deno.readTextFileSync("data/a.txt");
+console.log(usage()[0]);
+b.close();
+c.close();
+
+// reads stop at a hard limit
+const d = Deno.openSync("hard/d.txt");
+console.log(d.readSync(new Uint8Array(16)));
+logError(() => d.readSync(buf));
+logError(() => Deno.readFileSync("hard/e.txt"));
+d.close();
+console.log(usage());
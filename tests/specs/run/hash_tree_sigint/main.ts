@@ -0,0 +1,27 @@
+// A SIGINT that arrives while `Deno.hashTree()` is running interrupts it
+// instead of waiting for it to finish.
+const root = Deno.makeTempDirSync();
+for (let i = 0; i < 100; i++) {
+  Deno.mkdirSync(`${root}/${i}`);
+  for (let j = 0; j < 30; j++) {
+    Deno.writeTextFileSync(`${root}/${i}/${j}.txt`, `${i} ${j}`);
+  }
+}
+
+const { promise: received, resolve } = Promise.withResolvers<void>();
+Deno.addSignalListener("SIGINT", () => resolve());
+
+const promise = Deno.hashTree(root);
+Deno.kill(Deno.pid, "SIGINT");
+try {
+  await promise;
+  console.log("not interrupted");
+} catch (err) {
+  console.log(err instanceof Deno.errors.Interrupted, err.message);
+}
+await received;
+console.log("listener called");
+
+// the runtime is still usable
+console.log((await Deno.hashTree(root)).files);
+Deno.exit(0);
@@ -0,0 +1,9 @@
+function hook(rejection: Deno.UnhandledRejection) {
+  console.log("hook:", (rejection.reason as Error).message);
+}
+Deno.addLifecycleHook("unhandledRejection", hook);
+Deno.addLifecycleHook("unhandledRejection", () => console.log("removed"));
+Deno.removeLifecycleHook("unhandledRejection", hook);
+Deno.addLifecycleHook("unhandledRejection", hook);
+
+Promise.reject(new Error("boom"));
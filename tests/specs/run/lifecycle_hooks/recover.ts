@@ -0,0 +1,23 @@
+globalThis.addEventListener("unhandledrejection", (e) => {
+  console.log("event listener:", e.reason.message);
+});
+globalThis.addEventListener("unload", () => {
+  console.log("unload");
+});
+
+Deno.addLifecycleHook("unhandledRejection", (rejection) => {
+  console.log("first hook:", rejection.handled);
+});
+Deno.addLifecycleHook("unhandledRejection", (rejection) => {
+  console.log("second hook:", (rejection.reason as Error).message);
+  rejection.markHandled();
+});
+Deno.addLifecycleHook("unhandledRejection", () => {
+  console.log("not reached");
+});
+Deno.addLifecycleHook("beforeShutdown", ({ exitCode }) => {
+  console.log("before shutdown:", exitCode);
+});
+
+Promise.reject(new Error("boom"));
+setTimeout(() => console.log("still running"), 10);
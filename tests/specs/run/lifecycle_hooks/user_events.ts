@@ -0,0 +1,8 @@
+for (const event of ["postInit", "preMainModule", "shutdown"]) {
+  try {
+    // deno-lint-ignore no-explicit-any
+    Deno.addLifecycleHook(event as any, () => {});
+  } catch (e) {
+    console.log((e as Error).message);
+  }
+}
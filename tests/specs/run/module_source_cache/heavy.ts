@@ -0,0 +1,82 @@
+// A module with enough type annotations to make transpiling it worthwhile
+// to cache.
+
+export interface Vector {
+  readonly x: number;
+  readonly y: number;
+  readonly z: number;
+}
+
+export type Operation = "add" | "sub" | "scale" | "dot" | "cross";
+
+export enum Axis {
+  X = "x",
+  Y = "y",
+  Z = "z",
+}
+
+export function vector(x: number, y: number, z: number): Vector {
+  return { x, y, z };
+}
+
+export function add(a: Vector, b: Vector): Vector {
+  return vector(a.x + b.x, a.y + b.y, a.z + b.z);
+}
+
+export function sub(a: Vector, b: Vector): Vector {
+  return vector(a.x - b.x, a.y - b.y, a.z - b.z);
+}
+
+export function scale(a: Vector, factor: number): Vector {
+  return vector(a.x * factor, a.y * factor, a.z * factor);
+}
+
+export function dot(a: Vector, b: Vector): number {
+  return a.x * b.x + a.y * b.y + a.z * b.z;
+}
+
+export function cross(a: Vector, b: Vector): Vector {
+  return vector(
+    a.y * b.z - a.z * b.y,
+    a.z * b.x - a.x * b.z,
+    a.x * b.y - a.y * b.x,
+  );
+}
+
+export function component(a: Vector, axis: Axis): number {
+  switch (axis) {
+    case Axis.X:
+      return a.x;
+    case Axis.Y:
+      return a.y;
+    case Axis.Z:
+      return a.z;
+  }
+}
+
+export class Path<T extends Vector = Vector> {
+  #points: T[] = [];
+
+  push(point: T): this {
+    this.#points.push(point);
+    return this;
+  }
+
+  length(): number {
+    let length = 0;
+    for (let i = 1; i < this.#points.length; i++) {
+      const d = sub(this.#points[i], this.#points[i - 1]);
+      length += Math.sqrt(dot(d, d));
+    }
+    return length;
+  }
+}
+
+export function checksum(): number {
+  const up = cross(vector(1, 0, 0), vector(0, 1, 0));
+  const path = new Path()
+    .push(vector(0, 0, 0))
+    .push(vector(3, 4, 0))
+    .push(add(vector(3, 4, 0), scale(up, 12)));
+  return path.length() + component(vector(1, 2, 3), Axis.Z);
+}
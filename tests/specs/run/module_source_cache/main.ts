@@ -0,0 +1,37 @@
+import { checksum } from "./heavy.ts";
+
+interface Metrics {
+  hits: number;
+  misses: number;
+  evictions: number;
+  transpiles: number;
+}
+
+function metrics(): Metrics {
+  // @ts-ignore: internal API
+  return Deno[Deno.internal].core.ops.op_module_source_cache_metrics();
+}
+
+function runWorker(): Promise<number> {
+  const { promise, resolve } = Promise.withResolvers<number>();
+  const worker = new Worker(import.meta.resolve("./worker.ts"), {
+    type: "module",
+  });
+  worker.onmessage = (e) => resolve(e.data);
+  return promise;
+}
+
+const before = metrics();
+// One after the other, so that the first worker has cached `worker.ts` when
+// the others load it.
+for (let i = 0; i < 4; i++) {
+  console.log(await runWorker() === checksum());
+}
+const after = metrics();
+
+// `heavy.ts` was transpiled for the main module and is a hit in all workers,
+// `worker.ts` is only loaded from the emit cache or transpiled once.
+console.log("hits", after.hits - before.hits);
+console.log("misses", after.misses - before.misses);
+console.log("transpiles", after.transpiles - before.transpiles <= 1);
+console.log("evictions", after.evictions - before.evictions);
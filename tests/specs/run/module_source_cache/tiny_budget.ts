@@ -0,0 +1,21 @@
+// Every copy of `heavy.ts` fits into the budget of the cache, but not all of
+// them together.
+interface Metrics {
+  evictions: number;
+  entries: number;
+  bytes: number;
+}
+
+function metrics(): Metrics {
+  // @ts-ignore: internal API
+  return Deno[Deno.internal].core.ops.op_module_source_cache_metrics();
+}
+
+const copies = await Promise.all(
+  [1, 2, 3, 4, 5, 6, 7, 8].map((i) => import(`./heavy.ts?copy=${i}`)),
+);
+const { evictions, entries, bytes } = metrics();
+console.log(new Set(copies.map((copy) => copy.checksum())).size);
+console.log("evictions", evictions > 0);
+console.log("entries", entries < copies.length);
+console.log("within budget", bytes <= 16000);
@@ -0,0 +1,5 @@
+import { checksum } from "./heavy.ts";
+
+const value: number = checksum();
+self.postMessage(value);
+self.close();
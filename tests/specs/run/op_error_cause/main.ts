@@ -0,0 +1,5 @@
+try {
+  new Deno.Command("doesntexist").outputSync();
+} catch (error) {
+  console.log(error);
+}
@@ -0,0 +1,13 @@
+// Canceling the "interrupt" event keeps the program running past the grace
+// period.
+addEventListener("interrupt", (event) => {
+  console.log("interrupted");
+  event.preventDefault();
+  setTimeout(() => {
+    console.log("still running");
+    Deno.exit(0);
+  }, 1000);
+});
+
+setInterval(() => {}, 1000);
+Deno.kill(Deno.pid, "SIGINT");
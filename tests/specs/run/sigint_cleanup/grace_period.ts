@@ -0,0 +1,8 @@
+// The process exits once the grace period is over, even though the program
+// never exits on its own.
+addEventListener("interrupt", () => {
+  console.log("interrupted");
+});
+
+setInterval(() => {}, 1000);
+Deno.kill(Deno.pid, "SIGINT");
@@ -0,0 +1,8 @@
+// Without anything listening for it, SIGINT exits right away, after the
+// "unload" event.
+addEventListener("unload", () => {
+  console.log("unload");
+});
+
+setInterval(() => {}, 1000);
+Deno.kill(Deno.pid, "SIGINT");
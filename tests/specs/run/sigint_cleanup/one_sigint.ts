@@ -0,0 +1,10 @@
+// The first SIGINT lets the "interrupt" listener finish its cleanup.
+addEventListener("interrupt", async () => {
+  console.log("interrupted");
+  await new Promise((resolve) => setTimeout(resolve, 300));
+  console.log("cleanup done");
+  Deno.exit();
+});
+
+setInterval(() => {}, 1000);
+Deno.kill(Deno.pid, "SIGINT");
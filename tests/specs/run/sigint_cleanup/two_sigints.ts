@@ -0,0 +1,10 @@
+// A second SIGINT during the grace period exits without waiting for the
+// cleanup.
+addEventListener("interrupt", () => {
+  console.log("interrupted");
+  setTimeout(() => console.log("cleanup done"), 5000);
+  Deno.kill(Deno.pid, "SIGINT");
+});
+
+setInterval(() => {}, 1000);
+Deno.kill(Deno.pid, "SIGINT");
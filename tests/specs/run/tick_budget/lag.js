@@ -0,0 +1,28 @@
+const { op_event_loop_lag } = Deno[Deno.internal].core.ops;
+
+// Keeps the microtask queue busy for `ms` milliseconds.
+function starve(ms) {
+  const end = Date.now() + ms;
+  return new Promise((resolve) => {
+    function step() {
+      if (Date.now() < end) {
+        queueMicrotask(step);
+      } else {
+        resolve();
+      }
+    }
+    step();
+  });
+}
+
+setTimeout(async () => {
+  const before = op_event_loop_lag();
+  // Completes on another thread while the microtasks hold the event loop.
+  const stat = Deno.stat(".");
+  await starve(200);
+  await stat;
+  const after = op_event_loop_lag();
+  console.log("lag grew:", after.maxMs > before.maxMs);
+  console.log("lag covers the starvation:", after.maxMs >= 100);
+  console.log("no budget, no overruns:", after.overruns === 0);
+}, 0);
@@ -0,0 +1,12 @@
+setTimeout(() => {
+  const end = Date.now() + 300;
+  function step() {
+    if (Date.now() < end) {
+      queueMicrotask(step);
+    } else {
+      const { overruns } = Deno[Deno.internal].core.ops.op_event_loop_lag();
+      console.log("overruns:", overruns);
+    }
+  }
+  step();
+}, 0);
@@ -0,0 +1,13 @@
+let fired = false;
+
+setTimeout(() => {
+  setTimeout(() => {
+    fired = true;
+    console.log("timer fired");
+  }, 10);
+  // Without a forced yield this chain never lets the timer above run.
+  function step() {
+    if (!fired) queueMicrotask(step);
+  }
+  step();
+}, 0);
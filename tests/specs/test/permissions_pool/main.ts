@@ -0,0 +1,32 @@
+function assertReadGranted() {
+  const { state } = Deno.permissions.querySync({ name: "read" });
+  if (state !== "granted") {
+    throw new Error(`read permission is "${state}"`);
+  }
+}
+
+Deno.test({ permissions: { read: true } }, function leaksAndRevokes() {
+  Deno.openSync("main.ts");
+  Deno.permissions.revokeSync({ name: "read" });
+});
+
+Deno.test({ permissions: { read: true } }, function startsCleanAfterLeak() {
+  assertReadGranted();
+});
+
+Deno.test({ permissions: { read: true } }, function revokes() {
+  Deno.permissions.revokeSync({ name: "read" });
+});
+
+Deno.test({ permissions: { read: true } }, function reusedIsIsolated() {
+  assertReadGranted();
+});
+
+Deno.test({ permissions: { read: true } }, function addsGlobal() {
+  // deno-lint-ignore no-explicit-any
+  (globalThis as any).addedByTest = true;
+});
+
+Deno.test({ permissions: { read: true } }, function startsCleanAfterGlobal() {
+  assertReadGranted();
+});
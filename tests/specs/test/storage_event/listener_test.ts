@@ -0,0 +1,29 @@
+Deno.test("receives storage events of other test files", async () => {
+  const { promise, resolve, reject } = Promise.withResolvers<StorageEvent>();
+  const timeout = setTimeout(
+    () => reject(new Error("no storage event")),
+    10_000,
+  );
+  globalThis.onstorage = (event) => {
+    if (event.key === "hello") {
+      resolve(event);
+    }
+  };
+  localStorage.setItem("ready", "true");
+
+  const event = await promise;
+  clearTimeout(timeout);
+  globalThis.onstorage = null;
+  const { key, oldValue, newValue, url, storageArea } = event;
+  if (
+    key !== "hello" || oldValue !== null || newValue !== "world" ||
+    url !== "https://example.com/" || storageArea !== localStorage ||
+    !event.isTrusted
+  ) {
+    throw new Error(`unexpected event: ${key} ${oldValue} ${newValue} ${url}`);
+  }
+  // the change is visible once the event fires
+  if (localStorage.getItem("hello") !== "world") {
+    throw new Error("change not committed");
+  }
+});
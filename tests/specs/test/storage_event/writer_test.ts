@@ -0,0 +1,6 @@
+Deno.test("changes localStorage", async () => {
+  while (localStorage.getItem("ready") === null) {
+    await new Promise((resolve) => setTimeout(resolve, 10));
+  }
+  localStorage.setItem("hello", "world");
+});
@@ -0,0 +1,14 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// 500 tests that all ask for the same permissions, to measure the cost of
+// applying per-test permissions.
+const permissions = {
+  read: ["./tests/testdata"],
+  write: false,
+  net: ["127.0.0.1:4545", "localhost"],
+  env: ["HOME", "PATH"],
+};
+
+for (let i = 0; i < 500; i++) {
+  Deno.test({ name: `test ${i}`, permissions }, () => {});
+}
@@ -0,0 +1,11 @@
+interface Options {
+  message: string;
+}
+
+type Thrower = (options: Options) => never;
+
+const boom: Thrower = (options: Options) => {
+  throw new Error(options.message);
+};
+
+boom({ message: "boom!" });
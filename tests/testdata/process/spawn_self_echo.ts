@@ -0,0 +1,8 @@
+const parent = Deno.parentIpc()!;
+parent.onmessage = (e) => {
+  if (e.data === "exit") {
+    parent.close();
+    return;
+  }
+  parent.postMessage(e.data);
+};
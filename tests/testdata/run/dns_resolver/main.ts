@@ -0,0 +1,24 @@
+// Resolves names with the resolver configured by the --dns-* flags.
+console.log(JSON.stringify(await Deno.resolveDns("www.example.com", "A")));
+
+const listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+const conn = await Deno.connect({
+  hostname: "local.example.com",
+  port: listener.addr.port,
+});
+(await listener.accept()).close();
+conn.close();
+listener.close();
+console.log("connected");
+
+const ac = new AbortController();
+const server = Deno.serve({
+  hostname: "127.0.0.1",
+  port: 0,
+  signal: ac.signal,
+  onListen() {},
+}, () => new Response("hello"));
+const res = await fetch(`http://local.example.com:${server.addr.port}/`);
+console.log("fetched", await res.text());
+ac.abort();
+await server.finished;
@@ -0,0 +1,74 @@
+// Run with `ulimit -n` lowered, see `fd_exhaustion_*` in run_tests.rs.
+const mode = Deno.args[0];
+
+const limits = Deno.fdLimits();
+if (limits.soft === null || limits.soft > 1024) {
+  throw new Error(`limit wasn't lowered: ${JSON.stringify(limits)}`);
+}
+if (limits.inUse <= 3 || limits.inUse >= limits.soft) {
+  throw new Error(`unexpected descriptor count: ${JSON.stringify(limits)}`);
+}
+console.log("limits ok");
+
+const listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+const client = await Deno.connect(listener.addr as Deno.NetAddr);
+
+// Sync opens aren't retried, so they fill up the table right away.
+const files = [];
+while (true) {
+  try {
+    files.push(Deno.openSync("/dev/null"));
+  } catch (err) {
+    if (!(err instanceof Error) || !err.message.includes("os error 24")) {
+      throw err;
+    }
+    break;
+  }
+}
+console.log("saturated");
+
+if (mode === "retry") {
+  // Both wait for a descriptor to be closed instead of failing.
+  setTimeout(() => files.pop()!.close(), 50);
+  const file = await Deno.open("/dev/null");
+  file.close();
+  console.log("open retried");
+
+  setTimeout(() => files.pop()!.close(), 50);
+  const conn = await listener.accept();
+  conn.close();
+  console.log("accept retried");
+
+  const start = performance.now();
+  try {
+    await Deno.open("/dev/null");
+    throw new Error("open should have failed");
+  } catch (err) {
+    if (!(err instanceof Error) || !err.message.includes("os error 24")) {
+      throw err;
+    }
+  }
+  if (performance.now() - start < 500) {
+    throw new Error("open failed without backing off");
+  }
+  console.log("open gave up");
+} else if (mode === "reserve") {
+  // The pending connection is shed, so the client sees it closed while the
+  // listener is still out of descriptors.
+  const accepted = listener.accept();
+  const read = await client.read(new Uint8Array(1));
+  console.log(read === null ? "connection shed" : "connection kept");
+  listener.close();
+  await accepted.then(
+    () => console.log("accepted a shed connection"),
+    () => {},
+  );
+}
+
+for (const file of files) {
+  file.close();
+}
+client.close();
+if (mode === "retry") {
+  listener.close();
+}
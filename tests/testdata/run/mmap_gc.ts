@@ -0,0 +1,23 @@
+// Run with --v8-flags=--expose-gc, see `mmap_gc` in run_tests.rs.
+declare const gc: () => void;
+
+const path = Deno.realPathSync(Deno.makeTempFileSync());
+Deno.writeTextFileSync(path, "x".repeat(4096));
+const isMapped = () => Deno.readTextFileSync("/proc/self/maps").includes(path);
+
+const mapped = Deno.mapFile(path);
+console.log("mapped:", isMapped());
+mapped.close();
+console.log("after close:", isMapped());
+
+(() => {
+  Deno.mapFile(path);
+})();
+console.log("mapped:", isMapped());
+// The backing store may be freed in the background after the collection.
+for (let i = 0; i < 50 && isMapped(); i++) {
+  gc();
+  await new Promise((resolve) => setTimeout(resolve, 10));
+}
+console.log("after gc:", isMapped());
+Deno.removeSync(path);
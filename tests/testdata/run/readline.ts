@@ -0,0 +1,19 @@
+const rl = Deno.createReadline({
+  prompt: "> ",
+  historyPath: Deno.args[0],
+  complete: (line, cursor) => {
+    const word = line.slice(0, cursor).split(" ").pop()!;
+    return ["hello", "help", "history"].filter((name) => name.startsWith(word));
+  },
+});
+console.log("ready");
+while (true) {
+  const result = await rl.read();
+  console.log(result.kind === "line" ? `line: ${result.line}` : result.kind);
+  if (result.kind === "eof") {
+    break;
+  }
+}
+// writes the history
+rl.close();
+console.log("done");
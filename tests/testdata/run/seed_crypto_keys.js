@@ -0,0 +1,35 @@
+// Prints exported keys of every kind, which are the same for the same --seed.
+const subtle = crypto.subtle;
+
+async function exported(key) {
+  if (key.privateKey) {
+    return [
+      await subtle.exportKey("jwk", key.privateKey),
+      await subtle.exportKey("jwk", key.publicKey),
+    ];
+  }
+  return await subtle.exportKey("jwk", key);
+}
+
+const sign = ["sign", "verify"];
+const derive = ["deriveBits"];
+const cases = [
+  [{
+    name: "RSA-PSS",
+    modulusLength: 1024,
+    publicExponent: new Uint8Array([1, 0, 1]),
+    hash: "SHA-256",
+  }, sign],
+  [{ name: "ECDSA", namedCurve: "P-256" }, sign],
+  [{ name: "ECDSA", namedCurve: "P-384" }, sign],
+  [{ name: "ECDH", namedCurve: "P-521" }, derive],
+  [{ name: "AES-GCM", length: 256 }, ["encrypt", "decrypt"]],
+  [{ name: "HMAC", hash: "SHA-256" }, sign],
+  [{ name: "Ed25519" }, sign],
+  [{ name: "X25519" }, derive],
+];
+
+for (const [algorithm, usages] of cases) {
+  const key = await subtle.generateKey(algorithm, true, usages);
+  console.log(JSON.stringify(await exported(key)));
+}
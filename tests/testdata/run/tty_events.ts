@@ -0,0 +1,18 @@
+Deno.stdin.setRaw(true);
+const events = Deno.openTtyEvents({ mouse: true, bracketedPaste: true });
+console.log("ready");
+for await (const event of events) {
+  if (event.kind === "key" && event.key === "q") {
+    // Exit without closing, so the modes are reset by the exit handler.
+    Deno.exit(0);
+  }
+  const modifiers = Object.entries(event.modifiers)
+    .filter(([, held]) => held)
+    .map(([name]) => name);
+  console.log(
+    [event.kind, event.key, event.action, event.x, event.y, event.text]
+      .concat(modifiers)
+      .filter((part) => part !== null)
+      .join(" "),
+  );
+}
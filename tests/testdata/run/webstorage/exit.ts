@@ -0,0 +1,3 @@
+localStorage.setItem("hello", "deno");
+// exits before the microtask that commits the write runs
+Deno.exit(0);
@@ -0,0 +1,14 @@
+for (const fn of [
+  () => localStorage.setItem("hello", "deno"),
+  () => localStorage.length,
+]) {
+  try {
+    fn();
+  } catch (err) {
+    console.log(err instanceof DOMException, err.name);
+  }
+}
+
+// sessionStorage doesn't need the data directory
+sessionStorage.setItem("hello", "deno");
+console.log(sessionStorage.getItem("hello"));
@@ -0,0 +1,16 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// Drains a `Deno.Channel` until it receives `null`, then reports what it got.
+self.onmessage = async (e: MessageEvent<string>) => {
+  const channel = Deno.Channel.fromToken(e.data);
+  let count = 0;
+  let sum = 0;
+  while (true) {
+    const item = await channel.recv() as { value: number } | null;
+    if (item === null) break;
+    count++;
+    sum += item.value;
+  }
+  self.postMessage({ count, sum });
+  self.close();
+};
@@ -0,0 +1,13 @@
+self.onmessage = async ({ data: execPath }) => {
+  const env = Deno.env.toObject();
+  Deno.env.set("ENV_POLICY_WORKER", "worker");
+  Deno.env.delete("ENV_POLICY_A");
+  const { stdout } = await new Deno.Command(execPath, {
+    args: ["eval", "-p", "JSON.stringify(Deno.env.toObject())"],
+  }).output();
+  self.postMessage({
+    env,
+    childEnv: JSON.parse(new TextDecoder().decode(stdout)),
+  });
+  self.close();
+};
@@ -0,0 +1,15 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// Opens the file it receives the path of, and writes to it and closes it
+// when terminated.
+let file;
+
+self.onmessage = (e) => {
+  file = Deno.openSync(e.data, { write: true, create: true });
+  postMessage("ready");
+};
+
+self.addEventListener("beforeterminate", () => {
+  file.writeSync(new TextEncoder().encode("closed"));
+  file.close();
+});
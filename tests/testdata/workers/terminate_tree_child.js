@@ -0,0 +1,12 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+let path;
+
+self.onmessage = (e) => {
+  path = e.data;
+  postMessage("ready");
+};
+
+self.addEventListener("beforeterminate", () => {
+  Deno.writeTextFileSync(path, "child\n", { append: true });
+});
@@ -0,0 +1,20 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+// Appends "parent" to the file it receives the path of when terminated,
+// and starts a child worker that appends "child".
+let path;
+
+const child = new Worker(
+  import.meta.resolve("./terminate_tree_child.js"),
+  { type: "module" },
+);
+child.onmessage = () => postMessage("ready");
+
+self.onmessage = (e) => {
+  path = e.data;
+  child.postMessage(path);
+};
+
+self.addEventListener("beforeterminate", () => {
+  Deno.writeTextFileSync(path, "parent\n", { append: true });
+});
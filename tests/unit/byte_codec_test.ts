@@ -0,0 +1,137 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertThrows } from "./test_util.ts";
+
+const { ByteSink, ByteSource } = Deno[Deno.internal];
+
+Deno.test(function byteCodecRoundTrip() {
+  const sink = new ByteSink({ initialCapacity: 1 });
+  sink.writeU8(0xff);
+  sink.writeU16(0x0102);
+  sink.writeU16(0x0102, true);
+  sink.writeU32(0xdeadbeef, true);
+  sink.writeU64(0x0102030405060708n);
+  sink.writeVarint(300);
+  sink.writeVarint(Number.MAX_SAFE_INTEGER);
+  sink.writeLengthPrefixed(new Uint8Array([7, 8, 9]));
+  sink.writeBytes(new Uint8Array([10]));
+  assertEquals(sink.length, 32);
+
+  const bytes = sink.take();
+  assertEquals(bytes.byteLength, 32);
+  assertEquals(bytes.buffer.byteLength, 32);
+  assertEquals(bytes.subarray(0, 5), new Uint8Array([0xff, 1, 2, 2, 1]));
+  // Matches `DataView`.
+  const view = new DataView(bytes.buffer);
+  assertEquals(view.getUint32(5, true), 0xdeadbeef);
+  assertEquals(view.getBigUint64(9), 0x0102030405060708n);
+
+  const source = new ByteSource(bytes);
+  assertEquals(source.readU8(), 0xff);
+  assertEquals(source.readU16(), 0x0102);
+  assertEquals(source.readU16(true), 0x0102);
+  assertEquals(source.readU32(true), 0xdeadbeef);
+  assertEquals(source.readU64(), 0x0102030405060708n);
+  assertEquals(source.readVarint(), 300);
+  assertEquals(source.readVarint(), Number.MAX_SAFE_INTEGER);
+  assertEquals(source.readLengthPrefixed(), new Uint8Array([7, 8, 9]));
+  assertEquals(source.position, 31);
+  assertEquals(source.readBytes(1), new Uint8Array([10]));
+  assertEquals(source.remaining, 0);
+});
+
+Deno.test(function byteCodecFuzzedRoundTrip() {
+  // xorshift32, so that failures can be reproduced.
+  let seed = 42;
+  const random = (max: number) => {
+    seed ^= seed << 13;
+    seed ^= seed >>> 17;
+    seed ^= seed << 5;
+    return (seed >>> 0) % max;
+  };
+  const ops: [string, number | bigint | Uint8Array, boolean][] = [];
+  const sink = new ByteSink();
+  for (let i = 0; i < 2000; i++) {
+    const littleEndian = random(2) === 0;
+    switch (random(6)) {
+      case 0:
+        ops.push(["U8", random(0x100), false]);
+        break;
+      case 1:
+        ops.push(["U16", random(0x10000), littleEndian]);
+        break;
+      case 2:
+        ops.push(["U32", random(0x80000000) * 2 + random(2), littleEndian]);
+        break;
+      case 3:
+        ops.push(["U64", BigInt(random(0x80000000)) << 33n, littleEndian]);
+        break;
+      case 4:
+        ops.push(["Varint", random(0x80000000) * random(0x200000), false]);
+        break;
+      default:
+        ops.push([
+          "LengthPrefixed",
+          new Uint8Array(random(200)).fill(random(256)),
+          false,
+        ]);
+    }
+    const [kind, value, le] = ops[ops.length - 1];
+    sink[`write${kind}`](value, le);
+  }
+
+  const source = new ByteSource(sink.take());
+  for (const [kind, value, le] of ops) {
+    assertEquals(source[`read${kind}`](le), value);
+  }
+  assertEquals(source.remaining, 0);
+});
+
+Deno.test(function byteSourceOutOfBounds() {
+  const source = new ByteSource(new Uint8Array([1, 2, 3, 4, 5, 6]));
+  assertEquals(source.readU32(), 0x01020304);
+
+  const error = assertThrows(
+    () => source.readU32(),
+    RangeError,
+    "Out of bounds read of 4 bytes at offset 4, 2 bytes left",
+  );
+  // deno-lint-ignore no-explicit-any
+  const { code, offset, width } = error as any;
+  assertEquals({ code, offset, width }, {
+    code: "ERR_OUT_OF_BOUNDS",
+    offset: 4,
+    width: 4,
+  });
+  assertThrows(() => source.readU64(), RangeError);
+  assertThrows(() => source.readBytes(3), RangeError);
+  assertEquals(source.position, 4);
+
+  assertEquals(source.readU16(true), 0x0605);
+  const atEnd = assertThrows(() => source.readU8(), RangeError);
+  // deno-lint-ignore no-explicit-any
+  assertEquals((atEnd as any).offset, 6);
+  assertEquals(source.readBytes(0), new Uint8Array());
+
+  // A length prefix of 3 with 2 bytes after it.
+  const truncated = new ByteSource(new Uint8Array([3, 1, 2]));
+  const prefixed = assertThrows(
+    () => truncated.readLengthPrefixed(),
+    RangeError,
+  );
+  // deno-lint-ignore no-explicit-any
+  assertEquals((prefixed as any).offset, 1);
+  assertEquals(truncated.position, 0);
+});
+
+Deno.test(function byteSinkRejectsInvalidValues() {
+  const sink = new ByteSink();
+  assertThrows(() => sink.writeU8(256), RangeError);
+  assertThrows(() => sink.writeVarint(2 ** 53), RangeError);
+  assertThrows(() => new ByteSink({ initialCapacity: -1 }), RangeError);
+  assertEquals(sink.length, 0);
+
+  sink.take();
+  assertThrows(() => sink.writeU8(1), Deno.errors.BadResource);
+  assertThrows(() => sink.take(), Deno.errors.BadResource);
+  assertThrows(() => sink.length, Deno.errors.BadResource);
+});
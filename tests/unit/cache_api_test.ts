@@ -205,3 +205,30 @@ Deno.test(async function cachePutResource() {
   const res = await cache.match(request);
   assertEquals(await res?.text(), "Contents".repeat(1024));
 });
+
+Deno.test(async function cacheMatchIfNoneMatch() {
+  const cache = await caches.open("cache-v1");
+  const request = new Request("https://example.com/etag");
+  await cache.put(request, new Response("hello world"));
+
+  const res = await cache.match(request, { ifNoneMatch: '"stale"' });
+  assertEquals(res?.status, 200);
+  assertEquals(await res?.text(), "hello world");
+  const etag = res?.headers.get("etag");
+  assert(etag);
+
+  const notModified = await cache.match(request, { ifNoneMatch: etag });
+  assertEquals(notModified?.status, 304);
+  assertEquals(notModified?.body, null);
+  assertEquals(notModified?.headers.get("etag"), etag);
+
+  // If-None-Match uses the weak comparison.
+  const weak = await cache.match(request, { ifNoneMatch: `W/${etag}` });
+  assertEquals(weak?.status, 304);
+
+  // Without the option, responses are returned as stored.
+  const plain = await cache.match(request);
+  assertEquals(plain?.status, 200);
+  assertEquals(plain?.headers.get("etag"), null);
+  await plain?.body?.cancel();
+});
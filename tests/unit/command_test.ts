@@ -1012,6 +1012,27 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandSpawnFailureHasCodeAndCause() {
+    const error = assertThrows(
+      () => new Deno.Command("doesntexist").outputSync(),
+      Deno.errors.NotFound,
+    ) as Error & { code: string; retryable: boolean };
+    assertEquals(error.code, "ENOENT");
+    assertEquals(error.retryable, false);
+    assert(error.cause instanceof Error);
+    assertStringIncludes(error.message, error.cause.message);
+
+    const asyncError = await assertRejects(
+      () => new Deno.Command("doesntexist").output(),
+      Deno.errors.NotFound,
+    ) as Error & { code: string; retryable: boolean };
+    assertEquals(asyncError.code, "ENOENT");
+    assertEquals(asyncError.retryable, false);
+  },
+);
+
 Deno.test(
   { permissions: { write: true, run: true, read: true } },
   async function commandWithCwdOrPath() {
@@ -0,0 +1,379 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertRejects } from "./test_util.ts";
+
+const DATA = new Uint8Array(64 * 1024).map((_, i) => (i * 31) % 251);
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+async function sha256Hex(data: Uint8Array): Promise<string> {
+  const digest = await crypto.subtle.digest("SHA-256", data);
+  return Array.from(new Uint8Array(digest))
+    .map((b) => b.toString(16).padStart(2, "0"))
+    .join("");
+}
+
+interface RawRequest {
+  path: string;
+  range: string | null;
+}
+
+interface RawResponse {
+  status: number;
+  headers?: Record<string, string>;
+  body?: Uint8Array;
+  /** Close the connection after this many body bytes. */
+  cutAfter?: number;
+}
+
+/** An HTTP/1.1 server on a plain TCP listener, so that tests can close a
+ * connection in the middle of a response. */
+function serveRaw(
+  handler: (request: RawRequest, index: number) => RawResponse,
+) {
+  const listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+  const requests: RawRequest[] = [];
+  const connections: Promise<void>[] = [];
+  const accepting = (async () => {
+    for await (const conn of listener) {
+      connections.push((async () => {
+        const request = await readRequest(conn);
+        const response = handler(request, requests.length);
+        requests.push(request);
+        await writeResponse(conn, response);
+        conn.close();
+      })());
+    }
+  })();
+  return {
+    url: `http://127.0.0.1:${(listener.addr as Deno.NetAddr).port}`,
+    requests,
+    async close() {
+      listener.close();
+      await accepting;
+      await Promise.all(connections);
+    },
+  };
+}
+
+async function readRequest(conn: Deno.Conn): Promise<RawRequest> {
+  let head = "";
+  const buf = new Uint8Array(1024);
+  while (!head.includes("\r\n\r\n")) {
+    const n = await conn.read(buf);
+    if (n === null) break;
+    head += decoder.decode(buf.subarray(0, n));
+  }
+  const [requestLine, ...lines] = head.split("\r\n");
+  const range = lines.find((line) => line.toLowerCase().startsWith("range:"));
+  return {
+    path: requestLine.split(" ")[1],
+    range: range ? range.slice("range:".length).trim() : null,
+  };
+}
+
+async function writeResponse(conn: Deno.Conn, response: RawResponse) {
+  const body = response.body ?? new Uint8Array();
+  const headers = {
+    "content-length": `${body.length}`,
+    "connection": "close",
+    ...response.headers,
+  };
+  const head = `HTTP/1.1 ${response.status} X\r\n` +
+    Object.entries(headers).map(([k, v]) => `${k}: ${v}\r\n`).join("") +
+    "\r\n";
+  await conn.write(encoder.encode(head));
+  const sent = body.subarray(0, response.cutAfter ?? body.length);
+  let written = 0;
+  while (written < sent.length) {
+    written += await conn.write(sent.subarray(written));
+  }
+}
+
+/** Serves `DATA`, honoring ranges, but cuts the first response short. */
+function rangeResponse(
+  request: RawRequest,
+  index: number,
+  cutFirstAfter?: number,
+): RawResponse {
+  const start = request.range
+    ? Number(request.range.match(/^bytes=(\d+)-$/)![1])
+    : null;
+  if (start === null) {
+    return {
+      status: 200,
+      body: DATA,
+      cutAfter: index === 0 ? cutFirstAfter : undefined,
+    };
+  }
+  return {
+    status: 206,
+    headers: {
+      "content-range": `bytes ${start}-${DATA.length - 1}/${DATA.length}`,
+    },
+    body: DATA.subarray(start),
+  };
+}
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadWithProgressAndChecksum() {
+    const server = serveRaw(() => ({ status: 200, body: DATA }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    const progress: Deno.DownloadProgress[] = [];
+    const sha256 = await sha256Hex(DATA);
+    const result = await Deno.download(server.url + "/file.bin", path, {
+      sha256: sha256.toUpperCase(),
+      onProgress: (p) => progress.push(p),
+    });
+    await server.close();
+
+    assertEquals(result, {
+      url: server.url + "/file.bin",
+      size: DATA.length,
+      resumedFrom: 0,
+      sha256,
+      attempts: 1,
+    });
+    assertEquals(Deno.readFileSync(path), DATA);
+    assertEquals(progress[0], { received: 0, total: DATA.length });
+    assertEquals(progress.at(-1), { received: DATA.length, total: DATA.length });
+    for (let i = 1; i < progress.length; i++) {
+      assert(progress[i].received >= progress[i - 1].received);
+    }
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadChecksumMismatchDeletesFile() {
+    const server = serveRaw(() => ({ status: 200, body: DATA }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    await assertRejects(
+      () => Deno.download(server.url, path, { sha256: "00".repeat(32) }),
+      Deno.errors.InvalidData,
+      "Checksum mismatch",
+    );
+    await server.close();
+    assert(!(await Deno.stat(path).then(() => true, () => false)));
+
+    await assertRejects(
+      () => Deno.download(server.url, path, { sha256: "abc" }),
+      TypeError,
+      "Invalid sha256 checksum",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadResumesPartialFile() {
+    const server = serveRaw((request, index) =>
+      rangeResponse(request, index, 1000)
+    );
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    await assertRejects(
+      () => Deno.download(server.url, path),
+      Deno.errors.UnexpectedEof,
+    );
+    // the partial file is kept
+    assertEquals(Deno.readFileSync(path), DATA.subarray(0, 1000));
+
+    const progress: Deno.DownloadProgress[] = [];
+    const result = await Deno.download(server.url, path, {
+      resume: true,
+      sha256: await sha256Hex(DATA),
+      onProgress: (p) => progress.push(p),
+    });
+    await server.close();
+
+    assertEquals(server.requests.map((r) => r.range), [null, "bytes=1000-"]);
+    assertEquals(result.resumedFrom, 1000);
+    assertEquals(result.size, DATA.length);
+    assertEquals(progress[0], { received: 1000, total: DATA.length });
+    assertEquals(Deno.readFileSync(path), DATA);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadRetriesInterruptedTransfer() {
+    const server = serveRaw((request, index) =>
+      rangeResponse(request, index, 1000)
+    );
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    const result = await Deno.download(server.url, path, {
+      retry: {
+        maxAttempts: 3,
+        baseDelayMs: 1,
+        maxDelayMs: 1,
+        retryOn: ["interrupted"],
+      },
+    });
+    await server.close();
+
+    assertEquals(server.requests.map((r) => r.range), [null, "bytes=1000-"]);
+    assertEquals(result.attempts, 2);
+    assertEquals(result.resumedFrom, 0);
+    assertEquals(Deno.readFileSync(path), DATA);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadRestartsWhenRangeIsIgnored() {
+    const server = serveRaw(() => ({ status: 200, body: DATA }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    Deno.writeFileSync(path, new Uint8Array(500).fill(1));
+    const result = await Deno.download(server.url, path, { resume: true });
+    await server.close();
+
+    assertEquals(server.requests[0].range, "bytes=500-");
+    assertEquals(result.resumedFrom, 0);
+    assertEquals(Deno.readFileSync(path), DATA);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadRejectsMismatchedContentRange() {
+    const server = serveRaw(() => ({
+      status: 206,
+      headers: { "content-range": `bytes 0-9/${DATA.length}` },
+      body: DATA.subarray(0, 10),
+    }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    Deno.writeFileSync(path, DATA.subarray(0, 100));
+    await assertRejects(
+      () => Deno.download(server.url, path, { resume: true }),
+      Deno.errors.Http,
+      "requested bytes from 100, but received bytes from 0",
+    );
+    await server.close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function downloadLimitsRedirects() {
+    const server = serveRaw((request) => ({
+      status: 302,
+      headers: { "location": `${request.path}x` },
+    }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    await assertRejects(
+      () => Deno.download(server.url + "/", path, { maxRedirects: 2 }),
+      Deno.errors.Http,
+      "Too many redirects, the limit is 2",
+    );
+    await server.close();
+    assertEquals(server.requests.map((r) => r.path), ["/", "/x", "/xx"]);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, write: false } },
+  async function downloadRequiresWritePermission() {
+    await assertRejects(
+      () => Deno.download("http://127.0.0.1:4545/", "file.bin"),
+      Deno.errors.NotCapable,
+    );
+  },
+);
+
+const LARGE_DATA = new Uint8Array(4 * 1024 * 1024 + 7).map((_, i) =>
+  (i * 7) % 253
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function saveResponseWritesLargeBody() {
+    const server = serveRaw(() => ({ status: 200, body: LARGE_DATA }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    const response = await fetch(server.url);
+    const written = await Deno.saveResponse(response, path);
+    await server.close();
+
+    assertEquals(written, LARGE_DATA.length);
+    assertEquals(
+      await sha256Hex(Deno.readFileSync(path)),
+      await sha256Hex(LARGE_DATA),
+    );
+    assert(response.bodyUsed);
+    await assertRejects(
+      () => response.arrayBuffer(),
+      TypeError,
+      "Body already consumed",
+    );
+    await assertRejects(
+      () => Deno.saveResponse(response, path),
+      TypeError,
+      "Body already consumed",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function saveResponseDecodesContentEncoding() {
+    const compressed = new Uint8Array(
+      await new Response(
+        new Blob([LARGE_DATA]).stream().pipeThrough(
+          new CompressionStream("gzip"),
+        ),
+      ).arrayBuffer(),
+    );
+    const server = serveRaw(() => ({
+      status: 200,
+      headers: { "content-encoding": "gzip" },
+      body: compressed,
+    }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    const written = await Deno.saveResponse(await fetch(server.url), path);
+    await server.close();
+
+    assertEquals(written, LARGE_DATA.length);
+    assertEquals(
+      await sha256Hex(Deno.readFileSync(path)),
+      await sha256Hex(LARGE_DATA),
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function saveResponseKeepsPartialBody() {
+    const server = serveRaw(() => ({
+      status: 200,
+      body: LARGE_DATA,
+      cutAfter: 1024 * 1024,
+    }));
+    const path = Deno.makeTempDirSync() + "/file.bin";
+    const response = await fetch(server.url);
+    const error = await assertRejects(
+      () => Deno.saveResponse(response, path),
+      TypeError,
+      "bytes were written to the file",
+    );
+    await server.close();
+
+    const written = Number(error.message.match(/after (\d+) bytes/)![1]);
+    assert(written <= 1024 * 1024);
+    assertEquals(Deno.readFileSync(path), LARGE_DATA.subarray(0, written));
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function saveResponseWithoutFetchBody() {
+    const path = Deno.makeTempDirSync() + "/file.txt";
+    Deno.writeTextFileSync(path, "hello ");
+    const response = new Response("world");
+    const written = await Deno.saveResponse(response, path, { append: true });
+    assertEquals(written, 5);
+    assertEquals(Deno.readTextFileSync(path), "hello world");
+    assert(response.bodyUsed);
+
+    assertEquals(await Deno.saveResponse(new Response(null), path), 0);
+    assertEquals(Deno.readTextFileSync(path), "");
+  },
+);
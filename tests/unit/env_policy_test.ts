@@ -0,0 +1,171 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertThrows } from "./test_util.ts";
+
+const execPath = Deno.execPath();
+const printEnvArgs = ["eval", "-p", "JSON.stringify(Deno.env.toObject())"];
+
+// Set while the module has all permissions, most tests run with fewer.
+Deno.env.set("ENV_POLICY_A", "a");
+Deno.env.set("ENV_POLICY_B", "b");
+
+async function spawnPrintEnv(envPolicy: Deno.EnvPolicy) {
+  const child = new Deno.Command(execPath, {
+    args: printEnvArgs,
+    envPolicy,
+    stdout: "piped",
+  }).spawn();
+  const { stdout } = await child.output();
+  return {
+    env: JSON.parse(new TextDecoder().decode(stdout)),
+    diagnostics: child.envDiagnostics,
+  };
+}
+
+Deno.test(
+  { permissions: { run: true, env: ["ENV_POLICY_A"] } },
+  async function envPolicyInheritFilteredByPermissions() {
+    const { env, diagnostics } = await spawnPrintEnv({ inherit: "all" });
+    assertEquals(env.ENV_POLICY_A, "a");
+    assert(!("ENV_POLICY_B" in env));
+    assert(!("PATH" in env));
+
+    assert(diagnostics);
+    assert(diagnostics.denied.includes("ENV_POLICY_B"));
+    assert(!diagnostics.denied.includes("ENV_POLICY_A"));
+    assertEquals(diagnostics.denied, [...diagnostics.denied].sort());
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, env: ["ENV_POLICY_A"] } },
+  async function envPolicyInheritNames() {
+    const { env, diagnostics } = await spawnPrintEnv({
+      inherit: ["ENV_POLICY_A", "ENV_POLICY_B"],
+    });
+    assertEquals(env.ENV_POLICY_A, "a");
+    assert(!("ENV_POLICY_B" in env));
+    assert(!("PATH" in env));
+    // only variables selected by `inherit` are reported
+    assertEquals(diagnostics, { denied: ["ENV_POLICY_B"] });
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, env: true } },
+  async function envPolicyPrecedence() {
+    const { env, diagnostics } = await spawnPrintEnv({
+      remove: ["ENV_POLICY_A", "ENV_POLICY_B"],
+      set: { ENV_POLICY_B: "set", ENV_POLICY_C: "c" },
+    });
+    assert(!("ENV_POLICY_A" in env));
+    assertEquals(env.ENV_POLICY_B, "set");
+    assertEquals(env.ENV_POLICY_C, "c");
+    assert("PATH" in env);
+    assertEquals(diagnostics, { denied: [] });
+  },
+);
+
+Deno.test(
+  { permissions: { run: true } },
+  async function envPolicyInheritNone() {
+    const { env, diagnostics } = await spawnPrintEnv({
+      inherit: "none",
+      set: { ENV_POLICY_C: "c" },
+    });
+    assertEquals(env.ENV_POLICY_C, "c");
+    assert(!("ENV_POLICY_A" in env));
+    assert(!("PATH" in env));
+    assertEquals(diagnostics, { denied: [] });
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, env: ["ENV_POLICY_A"] } },
+  function envPolicyOutputSync() {
+    const { stdout } = new Deno.Command(execPath, {
+      args: printEnvArgs,
+      envPolicy: { inherit: "all" },
+    }).outputSync();
+    const env = JSON.parse(new TextDecoder().decode(stdout));
+    assertEquals(env.ENV_POLICY_A, "a");
+    assert(!("ENV_POLICY_B" in env));
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, env: true } },
+  async function envPolicyDiagnosticsUndefinedWithoutPolicy() {
+    const child = new Deno.Command(execPath, {
+      args: ["eval", ""],
+      stdout: "null",
+    }).spawn();
+    await child.status;
+    assertEquals(child.envDiagnostics, undefined);
+  },
+);
+
+Deno.test({ permissions: { run: true } }, function envPolicyInvalidOptions() {
+  assertThrows(
+    () =>
+      new Deno.Command(execPath, {
+        env: { FOO: "foo" },
+        envPolicy: { inherit: "none" },
+      }).spawn(),
+    TypeError,
+    "Cannot use 'envPolicy' together with 'env' or 'clearEnv'",
+  );
+  assertThrows(
+    () =>
+      new Deno.Command(execPath, {
+        clearEnv: true,
+        envPolicy: {},
+      }).outputSync(),
+    TypeError,
+    "Cannot use 'envPolicy' together with 'env' or 'clearEnv'",
+  );
+  assertThrows(
+    () =>
+      new Deno.Command(execPath, {
+        // @ts-expect-error invalid value
+        envPolicy: { inherit: "some" },
+      }).spawn(),
+    TypeError,
+    `'inherit' must be "all", "none" or an array of variable names, received "some"`,
+  );
+});
+
+Deno.test(
+  { permissions: { run: true, read: true, env: true } },
+  async function envPolicyWorker() {
+    const worker = new Worker(
+      import.meta.resolve("../testdata/workers/env_policy_worker.js"),
+      {
+        type: "module",
+        deno: {
+          env: {
+            inherit: ["ENV_POLICY_A", "ENV_POLICY_B"],
+            remove: ["ENV_POLICY_B"],
+            set: { ENV_POLICY_C: "c" },
+          },
+        },
+      },
+    );
+    const { promise, resolve } = Promise.withResolvers<
+      { env: Record<string, string>; childEnv: Record<string, string> }
+    >();
+    worker.onmessage = (e) => resolve(e.data);
+    worker.postMessage(execPath);
+    const { env, childEnv } = await promise;
+
+    assertEquals(env, { ENV_POLICY_A: "a", ENV_POLICY_C: "c" });
+    // subprocesses of the worker inherit the worker's environment
+    assertEquals(childEnv.ENV_POLICY_WORKER, "worker");
+    assertEquals(childEnv.ENV_POLICY_C, "c");
+    assert(!("ENV_POLICY_A" in childEnv));
+    assert(!("PATH" in childEnv));
+
+    // changes made by the worker don't affect the process environment
+    assertEquals(Deno.env.get("ENV_POLICY_A"), "a");
+    assertEquals(Deno.env.get("ENV_POLICY_WORKER"), undefined);
+  },
+);
@@ -0,0 +1,179 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertRejects,
+  assertThrows,
+} from "./test_util.ts";
+
+function writeTree(root: string, files: number) {
+  Deno.mkdirSync(`${root}/a/b`, { recursive: true });
+  for (let i = 0; i < files; i++) {
+    Deno.writeTextFileSync(`${root}/a/b/${i}.txt`, `${i}`);
+  }
+  Deno.writeTextFileSync(`${root}/top.txt`, "top");
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function copyDirReportsProgress() {
+    const root = Deno.makeTempDirSync();
+    writeTree(`${root}/from`, 100);
+
+    const events: Deno.FsProgress[] = [];
+    const result = await Deno.copyDir(`${root}/from`, `${root}/to`, {
+      onProgress: (progress) => events.push(progress),
+    });
+    assertEquals(result, {
+      dirs: 3,
+      files: 101,
+      symlinks: 0,
+      bytes: 193,
+      canceled: false,
+    });
+    assert(events.length > 0);
+    const last = events[events.length - 1];
+    assertEquals(last.processed, 104);
+    assertEquals(last.total, 104);
+    assert(last.currentPath.endsWith("top.txt"));
+    assertEquals(Deno.readTextFileSync(`${root}/to/a/b/42.txt`), "42");
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  async function copyDirSymlinks() {
+    const root = Deno.makeTempDirSync();
+    writeTree(`${root}/from`, 2);
+    Deno.symlinkSync("a/b/0.txt", `${root}/from/file_link`);
+    Deno.symlinkSync("a", `${root}/from/dir_link`);
+
+    const preserved = await Deno.copyDir(
+      `${root}/from`,
+      `${root}/preserved`,
+    );
+    assertEquals(preserved.symlinks, 2);
+    assertEquals(Deno.readLinkSync(`${root}/preserved/file_link`), "a/b/0.txt");
+    assertEquals(Deno.readLinkSync(`${root}/preserved/dir_link`), "a");
+    assertEquals(
+      Deno.readTextFileSync(`${root}/preserved/dir_link/b/1.txt`),
+      "1",
+    );
+
+    // copying the copy keeps the links
+    await Deno.copyDir(`${root}/preserved`, `${root}/round_trip`);
+    assertEquals(
+      Deno.readLinkSync(`${root}/round_trip/file_link`),
+      "a/b/0.txt",
+    );
+
+    const followed = await Deno.copyDir(`${root}/from`, `${root}/followed`, {
+      symlinks: "follow",
+    });
+    assertEquals(followed.symlinks, 0);
+    assert(Deno.lstatSync(`${root}/followed/file_link`).isFile);
+    assert(Deno.lstatSync(`${root}/followed/dir_link`).isDirectory);
+    assertEquals(
+      Deno.readTextFileSync(`${root}/followed/dir_link/b/1.txt`),
+      "1",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function copyDirAlreadyAborted() {
+    const root = Deno.makeTempDirSync();
+    writeTree(`${root}/from`, 1);
+    const controller = new AbortController();
+    controller.abort(new Error("stop"));
+    await assertRejects(
+      () =>
+        Deno.copyDir(`${root}/from`, `${root}/to`, {
+          signal: controller.signal,
+        }),
+      Error,
+      "stop",
+    );
+    assertThrows(() => Deno.statSync(`${root}/to`), Deno.errors.NotFound);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function copyDirCanceledFromProgress() {
+    const root = Deno.makeTempDirSync();
+    writeTree(`${root}/from`, 1000);
+    const controller = new AbortController();
+    const result = await Deno.copyDir(`${root}/from`, `${root}/to`, {
+      signal: controller.signal,
+      onProgress: () => controller.abort(),
+    });
+    // The copy may finish before the first event reaches JS.
+    if (result.canceled) {
+      assert(result.dirs + result.files < 1004);
+    } else {
+      assertEquals(result.files, 1001);
+    }
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function removeRecursiveReportsProgress() {
+    const root = Deno.makeTempDirSync();
+    writeTree(`${root}/tree`, 100);
+
+    const events: Deno.FsProgress[] = [];
+    await Deno.remove(`${root}/tree`, {
+      recursive: true,
+      onProgress: (progress) => events.push(progress),
+    });
+    assertThrows(() => Deno.statSync(`${root}/tree`), Deno.errors.NotFound);
+    const last = events[events.length - 1];
+    assertEquals(last.processed, 104);
+    assertEquals(last.total, 104);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function removeRecursiveCanceled() {
+    const root = Deno.makeTempDirSync();
+    writeTree(`${root}/tree`, 1000);
+    const controller = new AbortController();
+    const events: Deno.FsProgress[] = [];
+    try {
+      await Deno.remove(`${root}/tree`, {
+        recursive: true,
+        signal: controller.signal,
+        onProgress: (progress) => {
+          events.push(progress);
+          controller.abort();
+        },
+      });
+    } catch (error) {
+      assert(error instanceof DOMException);
+      assertEquals(error.name, "AbortError");
+      // stopped mid-tree, the root is removed last
+      assert(Deno.statSync(`${root}/tree`).isDirectory);
+      assert(events[events.length - 1].processed < 1004);
+      return;
+    }
+    // The removal may finish before the first event reaches JS.
+    assertThrows(() => Deno.statSync(`${root}/tree`), Deno.errors.NotFound);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: false } },
+  async function copyDirRequiresWritePermission() {
+    await assertRejects(
+      () => Deno.copyDir("from", "to"),
+      Deno.errors.NotCapable,
+    );
+  },
+);
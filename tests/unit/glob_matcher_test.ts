@@ -0,0 +1,118 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertThrows } from "./test_util.ts";
+
+interface SemanticsCase {
+  pattern: string;
+  path: string;
+  options?: Deno.GlobMatcherOptions;
+  matches: boolean;
+  windowsOnly?: boolean;
+}
+
+Deno.test(
+  { permissions: { read: true } },
+  function globMatcherSemanticsTable() {
+    // The same table is checked against the matcher behind the walkers in
+    // ext/fs/glob_matcher.rs.
+    const cases: SemanticsCase[] = JSON.parse(
+      Deno.readTextFileSync("ext/fs/testdata/glob_semantics.json"),
+    );
+    for (const { pattern, path, options, matches, windowsOnly } of cases) {
+      if (windowsOnly && Deno.build.os !== "windows") continue;
+      using matcher = Deno.globMatcher([pattern], options);
+      assertEquals(
+        matcher.match(path),
+        matches,
+        `${pattern} matching ${path} with ${JSON.stringify(options)}`,
+      );
+    }
+  },
+);
+
+Deno.test(function globMatcherFilter() {
+  using matcher = Deno.globMatcher(["src", "**/*.md"]);
+  assertEquals(
+    matcher.filter(["README.md", "deno.json", "src/main.ts", "docs/a.md"]),
+    ["README.md", "src/main.ts", "docs/a.md"],
+  );
+  assertEquals(matcher.filter([]), []);
+});
+
+Deno.test(function globMatcherInvalidPattern() {
+  assertThrows(
+    () => Deno.globMatcher(["src/**a"]),
+    TypeError,
+    "Invalid glob pattern",
+  );
+  assertThrows(
+    () => Deno.globMatcher(["{a,b"], { extended: true }),
+    TypeError,
+    "unmatched opening brace",
+  );
+  // braces are literal unless extended
+  using matcher = Deno.globMatcher(["{a,b"]);
+  assert(matcher.match("{a,b"));
+});
+
+Deno.test(function globMatcherClosed() {
+  const matcher = Deno.globMatcher(["*.ts"]);
+  matcher.close();
+  assertThrows(() => matcher.match("a.ts"), Deno.errors.BadResource);
+  assertThrows(
+    // @ts-ignore testing the error
+    () => new Deno.GlobMatcher(),
+    TypeError,
+    "cannot be constructed",
+  );
+});
+
+Deno.test(function globMatcherBatchPerformance() {
+  const paths = [];
+  for (let i = 0; i < 100_000; i++) {
+    paths.push(`src/module_${i % 100}/file_${i}.${i % 3 ? "ts" : "js"}`);
+  }
+  using matcher = Deno.globMatcher(["src/module_1*/**/*.ts"]);
+
+  const start = performance.now();
+  const matched = matcher.filter(paths);
+  const elapsed = performance.now() - start;
+  // module_1 and module_10 to module_19, two of every three files
+  assertEquals(
+    matched.length,
+    paths.filter((path) => /^src\/module_1\d?\/.*\.ts$/.test(path)).length,
+  );
+  // generous, only catches matching one path per op call or recompiling
+  assert(elapsed < 5_000, `took ${elapsed} ms`);
+});
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function globMatcherWithWalkDirAndSearchText() {
+    const root = Deno.makeTempDirSync();
+    Deno.mkdirSync(`${root}/src/nested`, { recursive: true });
+    Deno.writeTextFileSync(`${root}/src/main.ts`, "// TODO main");
+    Deno.writeTextFileSync(`${root}/src/upper.TS`, "// TODO upper");
+    Deno.writeTextFileSync(`${root}/src/nested/mod.js`, "// TODO js");
+
+    using include = Deno.globMatcher(["**/*.ts"], { caseSensitive: false });
+    using exclude = Deno.globMatcher(["src/{nested,other}"], {
+      extended: true,
+    });
+    const walked = [];
+    for await (
+      const entry of Deno.walkDir(root, { include, types: ["file"] })
+    ) {
+      walked.push(entry.path.slice(root.length + 1).replaceAll("\\", "/"));
+    }
+    assertEquals(walked.sort(), ["src/main.ts", "src/upper.TS"]);
+
+    const searched = [];
+    for await (const match of Deno.searchText(root, "TODO", { exclude })) {
+      searched.push(match.lineText);
+    }
+    assertEquals(searched.sort(), ["// TODO main", "// TODO upper"]);
+
+    // the matchers can be reused
+    assert(include.match("a/b.Ts"));
+  },
+);
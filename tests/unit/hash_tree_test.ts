@@ -0,0 +1,171 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertNotEquals,
+  assertRejects,
+} from "./test_util.ts";
+
+function writeTree(root: string) {
+  Deno.mkdirSync(`${root}/src/nested`, { recursive: true });
+  Deno.mkdirSync(`${root}/node_modules/pkg`, { recursive: true });
+  Deno.writeTextFileSync(`${root}/src/main.ts`, "console.log(1);\n");
+  Deno.writeTextFileSync(`${root}/src/nested/mod.ts`, "export {};\n");
+  Deno.writeTextFileSync(`${root}/src/out.tmp`, "scratch");
+  Deno.writeTextFileSync(`${root}/node_modules/pkg/index.js`, "x");
+}
+
+function framed(bytes: Uint8Array): Uint8Array {
+  const out = new Uint8Array(8 + bytes.length);
+  new DataView(out.buffer).setBigUint64(0, BigInt(bytes.length), true);
+  out.set(bytes, 8);
+  return out;
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function hashTreeMatchesReferenceDigest() {
+    const root = Deno.makeTempDirSync();
+    Deno.mkdirSync(`${root}/dir`);
+    Deno.writeTextFileSync(`${root}/dir/b.txt`, "world");
+    Deno.writeTextFileSync(`${root}/a.txt`, "hello");
+
+    // Paths always use `/`, so this is the digest on every platform.
+    const encoder = new TextEncoder();
+    const parts = [];
+    const files = [["a.txt", "hello"], ["dir/b.txt", "world"]];
+    for (const [path, content] of files) {
+      parts.push(
+        encoder.encode("f"),
+        framed(encoder.encode(path)),
+        framed(encoder.encode(content)),
+      );
+    }
+    const input = new Uint8Array(await new Blob(parts).arrayBuffer());
+    const expected = Array.from(
+      new Uint8Array(await crypto.subtle.digest("SHA-256", input)),
+      (byte) => byte.toString(16).padStart(2, "0"),
+    ).join("");
+
+    assertEquals(await Deno.hashTree(root), {
+      digest: expected,
+      files: 2,
+      bytes: 10,
+    });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function hashTreeStableAndSensitive() {
+    const a = Deno.makeTempDirSync();
+    const b = Deno.makeTempDirSync();
+    writeTree(a);
+    writeTree(b);
+
+    for (const algorithm of ["sha256", "blake3"] as const) {
+      const first = await Deno.hashTree(a, { algorithm });
+      assertEquals(first.files, 4);
+      assertEquals(first.digest.length, 64);
+      assertEquals(await Deno.hashTree(a, { algorithm }), first);
+      assertEquals(await Deno.hashTree(b, { algorithm }), first);
+
+      // a single changed byte
+      Deno.writeTextFileSync(`${b}/src/main.ts`, "console.log(2);\n");
+      const changed = await Deno.hashTree(b, { algorithm });
+      assertNotEquals(changed.digest, first.digest);
+      assertEquals(changed.bytes, first.bytes);
+      writeTree(b);
+    }
+
+    assertNotEquals(
+      (await Deno.hashTree(a, { algorithm: "sha256" })).digest,
+      (await Deno.hashTree(a, { algorithm: "blake3" })).digest,
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function hashTreeIncludeExclude() {
+    const root = Deno.makeTempDirSync();
+    writeTree(root);
+    const exclude = ["node_modules", "**/*.tmp"];
+
+    const excluded = await Deno.hashTree(root, { exclude });
+    assertEquals(excluded.files, 2);
+
+    Deno.writeTextFileSync(`${root}/src/out.tmp`, "changed");
+    Deno.writeTextFileSync(`${root}/node_modules/pkg/index.js`, "y");
+    assertEquals(await Deno.hashTree(root, { exclude }), excluded);
+
+    assertEquals(
+      await Deno.hashTree(root, { include: ["src/**/*.ts"] }),
+      excluded,
+    );
+
+    await assertRejects(
+      () => Deno.hashTree(root, { exclude: ["["] }),
+      TypeError,
+      'Invalid glob pattern "["',
+    );
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  async function hashTreeSymlinks() {
+    const root = Deno.makeTempDirSync();
+    Deno.mkdirSync(`${root}/a`);
+    Deno.writeTextFileSync(`${root}/a/file.txt`, "a");
+    Deno.symlinkSync(`${root}/a`, `${root}/b`);
+
+    assertEquals((await Deno.hashTree(root)).files, 1);
+    assertEquals(
+      (await Deno.hashTree(root, { followSymlinks: true })).files,
+      2,
+    );
+
+    Deno.symlinkSync(root, `${root}/a/loop`);
+    // not followed, so not a cycle
+    assertEquals((await Deno.hashTree(root)).files, 1);
+    await assertRejects(
+      () => Deno.hashTree(root, { followSymlinks: true }),
+      Deno.errors.FilesystemLoop,
+      "Symlink cycle detected",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true } },
+  async function hashTreeNotFound() {
+    await assertRejects(
+      () => Deno.hashTree("/this/path/does/not/exist"),
+      Deno.errors.NotFound,
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: false } },
+  async function hashTreePermissions() {
+    await assertRejects(
+      () => Deno.hashTree("."),
+      Deno.errors.NotCapable,
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function hashTreeEmptyDirectory() {
+    const root = Deno.makeTempDirSync();
+    const { digest, files, bytes } = await Deno.hashTree(root);
+    assert(digest.length > 0);
+    assertEquals([files, bytes], [0, 0]);
+  },
+);
@@ -0,0 +1,142 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertRejects } from "./test_util.ts";
+
+function writeTree(root: string) {
+  Deno.mkdirSync(`${root}/img`, { recursive: true });
+  Deno.writeTextFileSync(`${root}/a.txt`, "hello");
+  Deno.writeTextFileSync(`${root}/img/logo.svg`, "<svg/>");
+  Deno.writeTextFileSync(`${root}/img/out.tmp`, "scratch");
+}
+
+async function sri(data: string): Promise<string> {
+  const digest = await crypto.subtle.digest(
+    "SHA-256",
+    new TextEncoder().encode(data),
+  );
+  let binary = "";
+  for (const byte of new Uint8Array(digest)) {
+    binary += String.fromCharCode(byte);
+  }
+  return `sha256-${btoa(binary)}`;
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function integrityManifestFormat() {
+    const root = Deno.makeTempDirSync();
+    writeTree(root);
+    const manifest = await Deno.createIntegrityManifest(root, {
+      exclude: ["**/*.tmp"],
+    });
+    assertEquals(manifest.version, 1);
+    assertEquals(manifest.algorithm, "sha256");
+    assertEquals(manifest.include, []);
+    assertEquals(manifest.exclude, ["**/*.tmp"]);
+    assertEquals(manifest.files, {
+      "a.txt": await sri("hello"),
+      "img/logo.svg": await sri("<svg/>"),
+    });
+    assert(manifest.digest.startsWith("sha256-"));
+
+    const sha512 = await Deno.createIntegrityManifest(root, {
+      algorithm: "sha512",
+    });
+    assertEquals(Object.keys(sha512.files).length, 3);
+    assert(sha512.files["a.txt"].startsWith("sha512-"));
+    assert(sha512.digest.startsWith("sha512-"));
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function integrityManifestVerifyReport() {
+    const root = Deno.makeTempDirSync();
+    writeTree(root);
+    // survives a round trip through JSON
+    const manifest = JSON.parse(JSON.stringify(
+      await Deno.createIntegrityManifest(root, { exclude: ["**/*.tmp"] }),
+    ));
+    assertEquals(await Deno.verifyIntegrityManifest(root, manifest), {
+      ok: true,
+      missing: [],
+      extra: [],
+      modified: [],
+    });
+
+    Deno.writeTextFileSync(`${root}/img/out.tmp`, "excluded");
+    Deno.writeTextFileSync(`${root}/a.txt`, "tampered");
+    Deno.removeSync(`${root}/img/logo.svg`);
+    Deno.writeTextFileSync(`${root}/plugin.js`, "evil()");
+    assertEquals(await Deno.verifyIntegrityManifest(root, manifest), {
+      ok: false,
+      missing: ["img/logo.svg"],
+      extra: ["plugin.js"],
+      modified: ["a.txt"],
+    });
+    assertEquals(
+      await Deno.verifyIntegrityManifest(root, manifest, { failFast: true }),
+      { ok: false, missing: ["img/logo.svg"], extra: [], modified: [] },
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function integrityManifestInvalid() {
+    const root = Deno.makeTempDirSync();
+    writeTree(root);
+    const manifest = await Deno.createIntegrityManifest(root);
+    await assertRejects(
+      () =>
+        Deno.verifyIntegrityManifest(root, {
+          ...manifest,
+          files: { ...manifest.files, "a.txt": manifest.files["img/logo.svg"] },
+        }),
+      TypeError,
+      "Invalid integrity manifest",
+    );
+    await assertRejects(
+      () => Deno.verifyIntegrityManifest(root, { ...manifest, version: 2 }),
+      TypeError,
+      "unsupported version 2",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function integrityManifestLargeTree() {
+    const root = Deno.makeTempDirSync();
+    const count = 2000;
+    for (let i = 0; i < count; i++) {
+      const dir = `${root}/${i % 20}`;
+      Deno.mkdirSync(dir, { recursive: true });
+      Deno.writeTextFileSync(`${dir}/file_${i}.js`, `export default ${i};\n`);
+    }
+
+    const start = performance.now();
+    const manifest = await Deno.createIntegrityManifest(root);
+    const report = await Deno.verifyIntegrityManifest(root, manifest);
+    const elapsed = performance.now() - start;
+    assertEquals(Object.keys(manifest.files).length, count);
+    assert(report.ok);
+    // generous, only catches reading files one at a time on the main thread
+    assert(elapsed < 30_000, `took ${elapsed} ms`);
+
+    Deno.writeTextFileSync(`${root}/7/file_1987.js`, "changed");
+    assertEquals(
+      (await Deno.verifyIntegrityManifest(root, manifest)).modified,
+      ["7/file_1987.js"],
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: false } },
+  async function integrityManifestPermissions() {
+    await assertRejects(
+      () => Deno.createIntegrityManifest("."),
+      Deno.errors.NotCapable,
+    );
+  },
+);
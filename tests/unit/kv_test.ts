@@ -773,6 +773,53 @@ dbTest("list prefix reverse", async (db) => {
   ]);
 });
 
+dbTest("list prefix ending in 0xff bytes", async (db) => {
+  const res = await db.atomic()
+    .set([new Uint8Array([0xff])], 0)
+    .set([new Uint8Array([0xff]), new Uint8Array([0x00])], 1)
+    .set([new Uint8Array([0xff]), "a"], 2)
+    .set([new Uint8Array([0xff]), true], 3)
+    .set([new Uint8Array([0xff, 0xff])], 4)
+    .set([new Uint8Array([0xff, 0xff]), "a"], 5)
+    .commit();
+  assert(res.ok);
+  const { versionstamp } = res;
+
+  const prefix = [new Uint8Array([0xff])];
+  assertEquals(await collect(db.list({ prefix })), [
+    {
+      key: [new Uint8Array([0xff]), new Uint8Array([0x00])],
+      value: 1,
+      versionstamp,
+    },
+    { key: [new Uint8Array([0xff]), "a"], value: 2, versionstamp },
+    { key: [new Uint8Array([0xff]), true], value: 3, versionstamp },
+  ]);
+  assertEquals(
+    (await collect(db.list({ prefix }, { reverse: true, limit: 2 })))
+      .map((entry) => entry.value),
+    [3, 2],
+  );
+});
+
+dbTest("list prefix of empty and zero bytes", async (db) => {
+  const res = await db.atomic()
+    .set([new Uint8Array()], 0)
+    .set([new Uint8Array(), "a"], 1)
+    .set([new Uint8Array([0x00])], 2)
+    .set([new Uint8Array([0x00]), "a"], 3)
+    .commit();
+  assert(res.ok);
+
+  // The escaped 0x00 byte of `[0x00]` must not fall into the range of `[]`.
+  const entries = await collect(db.list({ prefix: [new Uint8Array()] }));
+  assertEquals(entries.map((entry) => entry.value), [1]);
+  const entries2 = await collect(
+    db.list({ prefix: [new Uint8Array([0x00])] }),
+  );
+  assertEquals(entries2.map((entry) => entry.value), [3]);
+});
+
 dbTest("list prefix reverse with start", async (db) => {
   const versionstamp = await setupData(db);
   const entries = await collect(
@@ -2319,3 +2366,88 @@ Deno.test({
     await completion;
   },
 });
+
+Deno.test({
+  name: "kv data survives reopen",
+  async fn() {
+    const filename = await Deno.makeTempFile({ prefix: "kv_reopen_db" });
+    try {
+      let db = await Deno.openKv(filename);
+      const res = await db.atomic()
+        .set(["a"], { nested: [1, 2, 3] })
+        .set(["b"], new Uint8Array([1, 2]))
+        .commit();
+      assert(res.ok);
+      await db.delete(["b"]);
+      db.close();
+
+      db = await Deno.openKv(filename);
+      try {
+        assertEquals(await db.get(["a"]), {
+          key: ["a"],
+          value: { nested: [1, 2, 3] },
+          versionstamp: res.versionstamp,
+        });
+        assertEquals((await db.get(["b"])).versionstamp, null);
+
+        // Versionstamps keep increasing after a reopen.
+        const res2 = await db.set(["c"], 1);
+        assert(res2.versionstamp > res.versionstamp);
+      } finally {
+        db.close();
+      }
+    } finally {
+      await Deno.remove(filename);
+    }
+  },
+});
+
+Deno.test({
+  name: "kv check detects write from another process",
+  async fn() {
+    const filename = await Deno.makeTempFile({ prefix: "kv_conflict_db" });
+    try {
+      const db = await Deno.openKv(filename);
+      try {
+        await db.set(["counter"], 1);
+        const entry = await db.get<number>(["counter"]);
+
+        // Another runtime increments the counter between our read and our
+        // write.
+        const { success } = await new Deno.Command(Deno.execPath(), {
+          args: [
+            "eval",
+            "--unstable-kv",
+            `const db = await Deno.openKv(${JSON.stringify(filename)});
+             const entry = await db.get(["counter"]);
+             await db.set(["counter"], entry.value + 1);
+             db.close();`,
+          ],
+        }).output();
+        assert(success);
+
+        const res = await db.atomic()
+          .check(entry)
+          .set(["counter"], entry.value! + 1)
+          .commit();
+        assert(!res.ok);
+
+        // Retrying with a fresh read commits on top of the other write.
+        const fresh = await db.get<number>(["counter"]);
+        assertEquals(fresh.value, 2);
+        assert(fresh.versionstamp! > entry.versionstamp!);
+        const res2 = await db.atomic()
+          .check(fresh)
+          .set(["counter"], fresh.value! + 1)
+          .commit();
+        assert(res2.ok);
+        assert(res2.versionstamp > fresh.versionstamp!);
+        assertEquals((await db.get(["counter"])).value, 3);
+      } finally {
+        db.close();
+      }
+    } finally {
+      await Deno.remove(filename);
+    }
+  },
+});
@@ -0,0 +1,146 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assertEquals,
+  assertRejects,
+  assertThrows,
+  delay,
+} from "./test_util.ts";
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileFlushesOnClose() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    const log = await Deno.openLogFile(filename, {
+      flushIntervalMs: 60_000,
+    });
+    log.write("a\n", "b\n");
+    log.write(new TextEncoder().encode("c\n"));
+    // Nothing reaches the file before the interval or a flush.
+    assertEquals(Deno.readTextFileSync(filename), "");
+    log.close();
+    assertEquals(Deno.readTextFileSync(filename), "a\nb\nc\n");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileCloseDuringFlushKeepsOrder() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    const log = await Deno.openLogFile(filename, {
+      flushIntervalMs: 60_000,
+    });
+    log.write("a\n");
+    const flushed = log.flush();
+    // Buffered while "a" is being written, and left to that flush.
+    log.write("b\n");
+    log.close();
+    await flushed;
+    assertEquals(Deno.readTextFileSync(filename), "a\nb\n");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileFlushAndAppend() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    Deno.writeTextFileSync(filename, "existing\n");
+    using log = await Deno.openLogFile(filename);
+    for (let i = 0; i < 3; i++) {
+      log.write(`${i}\n`);
+    }
+    await log.flush();
+    assertEquals(Deno.readTextFileSync(filename), "existing\n0\n1\n2\n");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileBatchedWritesKeepOrder() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    using log = await Deno.openLogFile(filename, {
+      flushIntervalMs: 60_000,
+    });
+    // The first write of a task is made right away, the others in one batch.
+    for (let i = 0; i < 1000; i++) {
+      log.write(`${i}\n`);
+    }
+    await Promise.resolve();
+    log.write("a\n", "b\n");
+    log.write("c\n");
+    await log.flush();
+    let expected = "";
+    for (let i = 0; i < 1000; i++) {
+      expected += `${i}\n`;
+    }
+    assertEquals(Deno.readTextFileSync(filename), expected + "a\nb\nc\n");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileFlushesOnThreshold() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    using log = await Deno.openLogFile(filename, {
+      flushIntervalMs: 60_000,
+      maxBufferBytes: 8,
+    });
+    log.write("0123456789\n");
+    for (let i = 0; i < 100; i++) {
+      if (Deno.readTextFileSync(filename) !== "") break;
+      await delay(10);
+    }
+    assertEquals(Deno.readTextFileSync(filename), "0123456789\n");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileSyncAlways() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    using log = await Deno.openLogFile(filename, { sync: "always" });
+    log.write("durable\n");
+    assertEquals(Deno.readTextFileSync(filename), "durable\n");
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "linux",
+    permissions: { read: true, write: true },
+  },
+  async function logFileReportsFlushErrors() {
+    // Every write to /dev/full fails with ENOSPC.
+    using log = await Deno.openLogFile("/dev/full", { flushIntervalMs: 10 });
+    log.write("lost\n");
+    await assertRejects(() => log.flush(), Error, "No space left on device");
+
+    // A failed background flush is reported by the next call.
+    log.write("lost\n");
+    await delay(100);
+    assertThrows(() => log.write("next\n"), Error, "No space left on device");
+    // The error is only reported once.
+    log.write("");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function logFileInvalidOptions() {
+    const filename = Deno.makeTempDirSync() + "/test.log";
+    await assertRejects(
+      () => Deno.openLogFile(filename, { maxBufferBytes: 0 }),
+      TypeError,
+      "maxBufferBytes must be greater than 0",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: false } },
+  async function logFilePerm() {
+    await assertRejects(
+      () => Deno.openLogFile("test.log"),
+      Deno.errors.NotCapable,
+    );
+  },
+);
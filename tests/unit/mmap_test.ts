@@ -0,0 +1,156 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertThrows } from "./test_util.ts";
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function mapFileLargeSparseFile() {
+    // sparse, so it doesn't take up the disk space
+    const size = Deno.build.os === "windows" ? 64 * 2 ** 20 : 3 * 2 ** 30;
+    const path = Deno.makeTempFileSync();
+    const offsets = [0, size - 8];
+    for (let i = 0; i < 64; i++) {
+      offsets.push(Math.floor(Math.random() * (size / 8 - 1)) * 8);
+    }
+    {
+      using file = Deno.openSync(path, { write: true });
+      file.truncateSync(size);
+      for (const offset of offsets) {
+        const marker = new Uint8Array(8);
+        new DataView(marker.buffer).setBigUint64(0, BigInt(offset) + 1n);
+        file.seekSync(offset, Deno.SeekMode.Start);
+        file.writeSync(marker);
+      }
+    }
+
+    using mapped = Deno.mapFile(path);
+    assertEquals(mapped.buffer.byteLength, size);
+    const view = new DataView(mapped.buffer);
+    for (const offset of offsets) {
+      assertEquals(view.getBigUint64(offset), BigInt(offset) + 1n);
+    }
+    // between the markers
+    assertEquals(view.getUint8(size / 2 + 3), 0);
+    mapped.close();
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function mapFileCloseDetachesBuffer() {
+    const path = Deno.makeTempFileSync();
+    Deno.writeTextFileSync(path, "hello world");
+
+    const mapped = Deno.mapFile(path);
+    const bytes = new Uint8Array(mapped.buffer);
+    assertEquals(new TextDecoder().decode(bytes), "hello world");
+    mapped.close();
+    assertEquals(mapped.buffer.byteLength, 0);
+    assertEquals(bytes.length, 0);
+    assertEquals(bytes[0], undefined);
+    assertThrows(() => mapped.close(), Deno.errors.BadResource);
+    // disposing a closed file is fine
+    mapped[Symbol.dispose]();
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function mapFileIsCopyOnWrite() {
+    const path = Deno.makeTempFileSync();
+    Deno.writeTextFileSync(path, "hello");
+
+    using mapped = Deno.mapFile(path);
+    const bytes = new Uint8Array(mapped.buffer);
+    bytes[0] = "j".charCodeAt(0);
+    assertEquals(new TextDecoder().decode(bytes), "jello");
+    assertEquals(Deno.readTextFileSync(path), "hello");
+    using again = Deno.mapFile(path);
+    assertEquals(new TextDecoder().decode(again.buffer), "hello");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function mapFileFromFsFile() {
+    const path = Deno.makeTempFileSync();
+    Deno.writeTextFileSync(path, "from an open file");
+
+    using file = Deno.openSync(path);
+    using mapped = Deno.mapFile(file);
+    assertEquals(
+      new TextDecoder().decode(mapped.buffer),
+      "from an open file",
+    );
+    // the file can be closed while it is mapped
+    file.close();
+    assertEquals(new Uint8Array(mapped.buffer)[0], "f".charCodeAt(0));
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function mapFileEmpty() {
+    const path = Deno.makeTempFileSync();
+    using mapped = Deno.mapFile(path, { lock: true });
+    assertEquals(mapped.buffer.byteLength, 0);
+    // the lock was released right away
+    using file = Deno.openSync(path, { write: true });
+    file.lockSync(true);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function mapFileLock() {
+    const path = Deno.makeTempFileSync();
+    Deno.writeTextFileSync(path, "locked");
+
+    using writer = Deno.openSync(path, { write: true });
+    writer.lockSync(true);
+    assertThrows(() => Deno.mapFile(path, { lock: true }), Deno.errors.Busy);
+    // an unlocked mapping ignores the lock
+    Deno.mapFile(path).close();
+    writer.unlockSync();
+
+    using mapped = Deno.mapFile(path, { lock: true });
+    using other = Deno.mapFile(path, { lock: true });
+    assertEquals(other.buffer.byteLength, 6);
+    mapped.close();
+    other.close();
+    // both shared locks were released
+    writer.lockSync(true);
+    writer.unlockSync();
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  function mapFileRefusesDirectories() {
+    const dir = Deno.makeTempDirSync();
+    assertThrows(() => Deno.mapFile(dir), Deno.errors.NotSupported);
+    Deno.removeSync(dir);
+  },
+);
+
+Deno.test({ permissions: { read: false } }, function mapFilePerm() {
+  assertThrows(() => Deno.mapFile("README.md"), Deno.errors.NotCapable);
+});
+
+Deno.test({ permissions: { read: true } }, function mapFileNotFound() {
+  assertThrows(
+    () => Deno.mapFile("does/not/exist"),
+    Deno.errors.NotFound,
+    "open",
+  );
+  assertThrows(
+    // @ts-ignore testing the error
+    () => new Deno.MappedFile(),
+    TypeError,
+    "cannot be constructed",
+  );
+});
@@ -0,0 +1,125 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertRejects } from "./test_util.ts";
+
+async function readAll(reader: Deno.NdjsonReader) {
+  const values = [];
+  const errors = [];
+  for await (const batch of reader) {
+    values.push(...batch.values);
+    errors.push(...batch.errors);
+  }
+  return { values, errors };
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function ndjsonReadFileInBatches() {
+    const filename = Deno.makeTempDirSync() + "/test.ndjson";
+    const lines = [];
+    for (let i = 0; i < 10; i++) {
+      lines.push(JSON.stringify({ i, s: "x".repeat(i) }));
+    }
+    Deno.writeTextFileSync(filename, lines.join("\n") + "\n");
+
+    using reader = await Deno.openNdjsonReader(filename, { batchSize: 4 });
+    const sizes = [];
+    let batch;
+    while ((batch = await reader.readBatch()) !== null) {
+      sizes.push(batch.values.length);
+      assertEquals(batch.errors, []);
+    }
+    assertEquals(sizes, [4, 4, 2]);
+  },
+);
+
+Deno.test(async function ndjsonReadCrlfAndBlankLines() {
+  const input = '{"a":1}\r\n\r\n  \n[1,2]\r\n"no trailing newline"';
+  const reader = await Deno.openNdjsonReader(
+    ReadableStream.from([new TextEncoder().encode(input)]),
+  );
+  assertEquals(await readAll(reader), {
+    values: [{ a: 1 }, [1, 2], "no trailing newline"],
+    errors: [],
+  });
+});
+
+Deno.test(async function ndjsonReadReportsMalformedLines() {
+  const encoder = new TextEncoder();
+  // Split a line across chunks to make sure it is reassembled.
+  const reader = await Deno.openNdjsonReader(
+    ReadableStream.from([
+      encoder.encode('1\n{"broken"\n'),
+      encoder.encode('{"ok":'),
+      encoder.encode("true}\n4\n"),
+    ]),
+  );
+  const { values, errors } = await readAll(reader);
+  assertEquals(values, [1, { ok: true }, 4]);
+  assertEquals(errors.map((e) => e.line), [2]);
+});
+
+Deno.test(async function ndjsonReadOversizedLines() {
+  const input = `1\n"${"x".repeat(100)}"\n3\n`;
+  const reader = await Deno.openNdjsonReader(
+    ReadableStream.from([new TextEncoder().encode(input)]),
+    { maxLineLength: 16 },
+  );
+  assertEquals(await readAll(reader), {
+    values: [1, 3],
+    errors: [{ line: 2, message: "Line exceeds maxLineLength of 16 bytes" }],
+  });
+});
+
+Deno.test(async function ndjsonReadInvalidOptions() {
+  await assertRejects(
+    () =>
+      Deno.openNdjsonReader(ReadableStream.from([]), { batchSize: 0 }),
+    TypeError,
+    "Invalid NDJSON reader option: batchSize must be greater than 0",
+  );
+});
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function ndjsonWriteRoundTrip() {
+    const filename = Deno.makeTempDirSync() + "/test.ndjson";
+    const values = [{ a: 1 }, "two", [3], null];
+    {
+      await using writer = await Deno.openNdjsonWriter(filename);
+      await writer.write(values.slice(0, 2));
+      await writer.write(values.slice(2));
+    }
+    assertEquals(
+      Deno.readTextFileSync(filename),
+      '{"a":1}\n"two"\n[3]\nnull\n',
+    );
+    const reader = await Deno.openNdjsonReader(filename);
+    assertEquals((await readAll(reader)).values, values);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function ndjsonWriteGzip() {
+    const filename = Deno.makeTempDirSync() + "/test.ndjson.gz";
+    const writer = await Deno.openNdjsonWriter(filename, { gzip: true });
+    await writer.write([{ a: 1 }, { b: 2 }]);
+    await writer.close();
+
+    using file = await Deno.open(filename);
+    const reader = await Deno.openNdjsonReader(
+      file.readable.pipeThrough(new DecompressionStream("gzip")),
+    );
+    assertEquals((await readAll(reader)).values, [{ a: 1 }, { b: 2 }]);
+  },
+);
+
+Deno.test(
+  { permissions: { read: false } },
+  async function ndjsonReadPerm() {
+    await assertRejects(
+      () => Deno.openNdjsonReader("test.ndjson"),
+      Deno.errors.NotCapable,
+    );
+  },
+);
@@ -1327,3 +1327,537 @@ Deno.test(
     // calling [Symbol.dispose] after manual close is a no-op
   },
 );
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpConnectRetryGivesUp() {
+    const error = await assertRejects(
+      () =>
+        Deno.connect({
+          port: listenPort,
+          retry: {
+            maxAttempts: 3,
+            baseDelayMs: 1,
+            maxDelayMs: 5,
+            retryOn: ["refused"],
+          },
+        }),
+      Deno.errors.ConnectionRefused,
+    ) as Error & { attempts: number; totalDelayMs: number };
+    assert(error.message.includes("after 3 attempts"), error.message);
+    assertEquals(error.attempts, 3);
+    assert(error.totalDelayMs >= 2, `${error.totalDelayMs}`);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpConnectRetrySucceedsOnceListening() {
+    const connPromise = Deno.connect({
+      port: listenPort,
+      retry: {
+        maxAttempts: 20,
+        baseDelayMs: 50,
+        maxDelayMs: 100,
+        retryOn: ["refused"],
+      },
+    });
+    await delay(20);
+    using listener = Deno.listen({ port: listenPort });
+    const conn = await connPromise;
+    assert(conn.retryStats!.attempts > 1);
+    assert(conn.retryStats!.totalDelayMs >= 50);
+    const serverConn = await listener.accept();
+    conn.close();
+    serverConn.close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpConnectRetryAbortDuringBackoff() {
+    const controller = new AbortController();
+    const start = Date.now();
+    setTimeout(() => controller.abort(), 20);
+    await assertRejects(
+      () =>
+        Deno.connect({
+          port: listenPort,
+          retry: {
+            maxAttempts: 3,
+            baseDelayMs: 60_000,
+            maxDelayMs: 60_000,
+            retryOn: ["refused"],
+          },
+          signal: controller.signal,
+        }),
+      DOMException,
+      "aborted",
+    );
+    assert(Date.now() - start < 10_000);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpConnectRetryInvalidPolicy() {
+    await assertRejects(
+      () =>
+        Deno.connect({
+          port: listenPort,
+          retry: {
+            maxAttempts: 0,
+            baseDelayMs: 1,
+            maxDelayMs: 1,
+            retryOn: ["refused"],
+          },
+        }),
+      TypeError,
+      "maxAttempts must be at least 1",
+    );
+  },
+);
+
+function listenOnRandomPorts(count: number): Deno.TcpListener[] {
+  return Array.from(
+    { length: count },
+    () => Deno.listen({ hostname: "127.0.0.1", port: 0 }),
+  );
+}
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpAcceptAnyReportsSourceListener() {
+    const listeners = listenOnRandomPorts(3);
+    const order = [2, 0, 1, 1, 2, 0];
+    for (const index of order) {
+      const { port } = listeners[index].addr;
+      const client = await Deno.connect({ hostname: "127.0.0.1", port });
+      const accepted = await Deno.acceptAny(listeners);
+      assertEquals(accepted.index, index);
+      assert(accepted.listener === listeners[index]);
+      assertEquals(accepted.conn.localAddr.port, port);
+      assertEquals(accepted.conn.remoteAddr, client.localAddr);
+      accepted.conn.close();
+      client.close();
+    }
+    listeners.forEach((listener) => listener.close());
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpAcceptAnyIsFair() {
+    const listeners = listenOnRandomPorts(3);
+    // Every listener has a connection waiting, so every call could be served
+    // by the first listener; the rotation must pick each one once.
+    const clients = await Promise.all(
+      listeners.map((listener) =>
+        Deno.connect({ hostname: "127.0.0.1", port: listener.addr.port })
+      ),
+    );
+    await delay(100);
+    const seen = new Set<number>();
+    for (let i = 0; i < listeners.length; i++) {
+      const { conn, index } = await Deno.acceptAny(listeners);
+      seen.add(index);
+      conn.close();
+    }
+    assertEquals(seen.size, listeners.length);
+    clients.forEach((client) => client.close());
+    listeners.forEach((listener) => listener.close());
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpAcceptAnyListenerClosedWhileWaiting() {
+    const listeners = listenOnRandomPorts(3);
+    const pending = Deno.acceptAny(listeners);
+    await delay(10);
+    listeners[1].close();
+    await delay(10);
+
+    const { port } = listeners[2].addr;
+    const client = await Deno.connect({ hostname: "127.0.0.1", port });
+    const { conn, index } = await pending;
+    assertEquals(index, 2);
+    conn.close();
+    client.close();
+
+    // the closed listener is skipped
+    const open = [listeners[0], listeners[2]];
+    const pending2 = Deno.acceptAny(listeners);
+    await delay(10);
+    const client2 = await Deno.connect({
+      hostname: "127.0.0.1",
+      port: listeners[0].addr.port,
+    });
+    const accepted = await pending2;
+    assertEquals(accepted.index, 0);
+    accepted.conn.close();
+    client2.close();
+
+    // only rejects once every listener is closed
+    const pending3 = Deno.acceptAny(listeners);
+    await delay(10);
+    open.forEach((listener) => listener.close());
+    await assertRejects(() => pending3, Deno.errors.BadResource);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpAcceptAnyAbort() {
+    const listeners = listenOnRandomPorts(2);
+    const controller = new AbortController();
+    const pending = Deno.acceptAny(listeners, { signal: controller.signal });
+    await delay(10);
+    controller.abort();
+    await assertRejects(() => pending, DOMException, "aborted");
+
+    // the listeners are still usable
+    const client = await Deno.connect({
+      hostname: "127.0.0.1",
+      port: listeners[1].addr.port,
+    });
+    const { conn, index } = await Deno.acceptAny(listeners);
+    assertEquals(index, 1);
+    conn.close();
+    client.close();
+    listeners.forEach((listener) => listener.close());
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netTcpConnectRefusedHasCode() {
+    const listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+    const { port } = listener.addr;
+    listener.close();
+
+    const error = await assertRejects(
+      () => Deno.connect({ hostname: "127.0.0.1", port }),
+      Deno.errors.ConnectionRefused,
+    ) as Error & { code: string; retryable: boolean };
+    assertEquals(error.code, "ECONNREFUSED");
+    assertEquals(error.retryable, true);
+  },
+);
+
+async function echoUntilEof(conn: Deno.Conn) {
+  const buf = new Uint8Array(1024);
+  let n;
+  while ((n = await conn.read(buf)) !== null) {
+    // answer slowly, so that the reply is still in flight when the client
+    // has already closed its write half
+    await delay(10);
+    await conn.write(buf.subarray(0, n));
+  }
+  await conn.closeWrite();
+}
+
+async function proxiedPair(options?: Deno.ProxyOptions) {
+  const echoListener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+  const proxyListener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+  const client = await Deno.connect({
+    hostname: "127.0.0.1",
+    port: proxyListener.addr.port,
+  });
+  const downstream = await proxyListener.accept();
+  const upstream = await Deno.connect({
+    hostname: "127.0.0.1",
+    port: echoListener.addr.port,
+  });
+  const server = await echoListener.accept();
+  echoListener.close();
+  proxyListener.close();
+  const proxy = Deno.proxy(downstream, upstream, options);
+  return { client, server, proxy };
+}
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netProxyHalfClose() {
+    const { client, server, proxy } = await proxiedPair();
+    const echo = echoUntilEof(server);
+
+    await client.write(new TextEncoder().encode("hello "));
+    await client.write(new TextEncoder().encode("world"));
+    await client.closeWrite();
+
+    // the echo still arrives after the client closed its write half
+    const response = await new Response(client.readable).text();
+    assertEquals(response, "hello world");
+
+    await echo;
+    server.close();
+    assertEquals(await proxy, { aToB: 11, bToA: 11 });
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netProxyIdleTimeout() {
+    const { client, server, proxy } = await proxiedPair({
+      idleTimeoutMs: 50,
+    });
+    await client.write(new Uint8Array([1, 2, 3]));
+    const buf = new Uint8Array(3);
+    assertEquals(await server.read(buf), 3);
+
+    await assertRejects(() => proxy, Deno.errors.TimedOut);
+    // both connections were torn down by the proxy
+    assertEquals(await client.read(buf), null);
+    assertEquals(await server.read(buf), null);
+    client.close();
+    server.close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netProxyAbort() {
+    const controller = new AbortController();
+    const { client, server, proxy } = await proxiedPair({
+      signal: controller.signal,
+    });
+    await delay(10);
+    controller.abort();
+    await assertRejects(() => proxy, DOMException, "aborted");
+    const buf = new Uint8Array(1);
+    assertEquals(await client.read(buf), null);
+    client.close();
+    server.close();
+  },
+);
+
+async function tcpPair() {
+  const listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+  const client = await Deno.connect({
+    hostname: "127.0.0.1",
+    port: listener.addr.port,
+  });
+  const server = await listener.accept();
+  listener.close();
+  return { client, server };
+}
+
+async function sha256(data: Uint8Array) {
+  return new Uint8Array(await crypto.subtle.digest("SHA-256", data));
+}
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function netSendFile() {
+    const content = new Uint8Array(3 * 1024 * 1024 + 7).map((_, i) =>
+      (i * 31) % 251
+    );
+    const path = Deno.makeTempFileSync();
+    Deno.writeFileSync(path, content);
+    using file = Deno.openSync(path);
+    const { client, server } = await tcpPair();
+
+    const received = new Response(client.readable).bytes();
+    assertEquals(await Deno.sendFile(server, file), content.length);
+    server.close();
+    assertEquals(await sha256(await received), await sha256(content));
+    // the file position is left alone
+    assertEquals(await file.seek(0, Deno.SeekMode.Current), 0);
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function netSendFileWindow() {
+    const content = new TextEncoder().encode("0123456789");
+    const path = Deno.makeTempFileSync();
+    Deno.writeFileSync(path, content);
+    using file = Deno.openSync(path);
+    file.seekSync(4, Deno.SeekMode.Start);
+    const { client, server } = await tcpPair();
+
+    const received = new Response(client.readable).text();
+    assertEquals(
+      await Deno.sendFile(server, file, { offset: 2, length: 3 }),
+      3,
+    );
+    // stops at the end of the file
+    assertEquals(
+      await Deno.sendFile(server, file, { offset: 8, length: 100 }),
+      2,
+    );
+    assertEquals(await Deno.sendFile(server, file, { offset: 20 }), 0);
+    server.close();
+    assertEquals(await received, "23489");
+    assertEquals(file.seekSync(0, Deno.SeekMode.Current), 4);
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function netSendFileNotAFile() {
+    const { client, server } = await tcpPair();
+    await assertRejects(
+      // deno-lint-ignore no-explicit-any
+      () => Deno.sendFile(server, client as any),
+      Deno.errors.BadResource,
+      "Resource is not a file",
+    );
+    client.close();
+    server.close();
+  },
+);
+
+function namedPipePath() {
+  return `\\\\.\\pipe\\deno-test-${crypto.randomUUID()}`;
+}
+
+async function echo(conn: Deno.Conn) {
+  const buf = new Uint8Array(64);
+  let n;
+  while ((n = await conn.read(buf)) !== null) {
+    await conn.write(buf.subarray(0, n));
+  }
+  conn.close();
+}
+
+async function roundTrip(conn: Deno.Conn, message: string) {
+  await conn.write(new TextEncoder().encode(message));
+  const buf = new Uint8Array(64);
+  const n = await conn.read(buf);
+  return new TextDecoder().decode(buf.subarray(0, n!));
+}
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "windows",
+    permissions: { read: true, write: true },
+  },
+  async function netNamedPipeEcho() {
+    const path = namedPipePath();
+    const listener = Deno.listen({ path, transport: "namedPipe" });
+    assertEquals(listener.addr, { transport: "namedPipe", path });
+    const server = (async () => {
+      const conns = [];
+      for (let i = 0; i < 2; i++) {
+        const conn = await listener.accept();
+        assertEquals(conn.localAddr, { transport: "namedPipe", path });
+        conns.push(echo(conn));
+      }
+      await Promise.all(conns);
+    })();
+
+    const [a, b] = await Promise.all([
+      Deno.connect({ path, transport: "namedPipe" }),
+      Deno.connect({ path, transport: "namedPipe" }),
+    ]);
+    assertEquals(a.remoteAddr, { transport: "namedPipe", path });
+    assertEquals(
+      await Promise.all([roundTrip(a, "hello"), roundTrip(b, "world")]),
+      ["hello", "world"],
+    );
+    a.close();
+    b.close();
+    await server;
+
+    // the name can't be taken while the listener is open
+    assertThrows(
+      () => Deno.listen({ path, transport: "namedPipe" }),
+      Deno.errors.AddrInUse,
+    );
+    listener.close();
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "windows",
+    permissions: { read: true, write: true },
+  },
+  async function netNamedPipeBusy() {
+    const path = namedPipePath();
+    const listener = Deno.listen({
+      path,
+      transport: "namedPipe",
+      maxInstances: 1,
+    });
+    const first = await Deno.connect({ path, transport: "namedPipe" });
+    const firstServer = await listener.accept();
+
+    // the only instance is connected, so the client retries until it closes
+    let secondConnected = false;
+    const second = Deno.connect({ path, transport: "namedPipe" }).then(
+      (conn) => {
+        secondConnected = true;
+        return conn;
+      },
+    );
+    const secondServer = listener.accept();
+    await delay(200);
+    assert(!secondConnected);
+
+    first.close();
+    firstServer.close();
+    const conn = await second;
+    const serverConn = await secondServer;
+    const served = echo(serverConn);
+    assertEquals(await roundTrip(conn, "again"), "again");
+    conn.close();
+    await served;
+    listener.close();
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "windows",
+    permissions: { read: true, write: true },
+  },
+  function netNamedPipeInvalidOptions() {
+    assertThrows(
+      () => Deno.listen({ path: "C:\\pipe", transport: "namedPipe" }),
+      TypeError,
+      "Invalid named pipe path",
+    );
+    assertThrows(
+      () =>
+        Deno.listen({
+          path: namedPipePath(),
+          transport: "namedPipe",
+          maxInstances: 255,
+        }),
+      RangeError,
+    );
+    assertThrows(
+      () =>
+        Deno.listen({
+          path: namedPipePath(),
+          transport: "namedPipe",
+          securityDescriptor: "not sddl",
+        }),
+      TypeError,
+      "Invalid security descriptor",
+    );
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  async function netNamedPipeNotSupported() {
+    assertThrows(
+      () => Deno.listen({ path: namedPipePath(), transport: "namedPipe" }),
+      Deno.errors.NotSupported,
+      "Named pipes are only supported on Windows",
+    );
+    await assertRejects(
+      () => Deno.connect({ path: namedPipePath(), transport: "namedPipe" }),
+      Deno.errors.NotSupported,
+    );
+  },
+);
@@ -297,6 +297,47 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { sys: ["systemCpuInfo"] } },
+  function systemCpuInfo() {
+    const info = Deno.systemCpuInfo();
+    assert(info.logical >= 1);
+    assert(info.physical >= 1 && info.physical <= info.logical);
+    assert(
+      info.effectiveParallelism >= 1 &&
+        info.effectiveParallelism <= info.logical,
+    );
+    assert(info.frequenciesMhz.every((mhz) => mhz >= 0));
+    assertEquals(navigator.hardwareConcurrency, info.effectiveParallelism);
+  },
+);
+
+Deno.test({ permissions: { sys: false } }, function systemCpuInfoPerm() {
+  assertThrows(() => Deno.systemCpuInfo(), Deno.errors.NotCapable);
+});
+
+Deno.test(
+  { permissions: { sys: ["fdLimits"], read: true } },
+  function fdLimits() {
+    const before = Deno.fdLimits();
+    if (Deno.build.os === "windows") {
+      assertEquals(before.soft, null);
+      assertEquals(before.hard, null);
+    } else if (before.soft !== null && before.hard !== null) {
+      assert(before.soft <= before.hard);
+    }
+    assert(before.inUse >= 3);
+
+    const file = Deno.openSync("README.md");
+    assert(Deno.fdLimits().inUse > before.inUse);
+    file.close();
+  },
+);
+
+Deno.test({ permissions: { sys: false } }, function fdLimitsPerm() {
+  assertThrows(() => Deno.fdLimits(), Deno.errors.NotCapable);
+});
+
 Deno.test({ permissions: { sys: ["uid"] } }, function getUid() {
   if (Deno.build.os === "windows") {
     assertEquals(Deno.uid(), null);
@@ -0,0 +1,70 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertNotEquals, assertRejects } from "./test_util.ts";
+
+const options = { iterations: 1000 };
+
+Deno.test(async function passwordWrapRoundTrip() {
+  for (const length of [1, 7, 8, 31, 64]) {
+    const secret = crypto.getRandomValues(new Uint8Array(length));
+    const envelope = await Deno.wrapWithPassword(secret, "hunter2", options);
+    assertEquals(envelope[0], 1);
+    assertEquals(
+      await Deno.unwrapWithPassword(envelope, "hunter2"),
+      secret,
+    );
+  }
+});
+
+Deno.test(async function passwordWrapBufferPassword() {
+  const secret = new TextEncoder().encode("secret");
+  const password = new Uint8Array([0, 1, 2, 3, 255]);
+  const envelope = await Deno.wrapWithPassword(secret, password, options);
+  assertEquals(
+    await Deno.unwrapWithPassword(envelope.buffer, password),
+    secret,
+  );
+});
+
+Deno.test(async function passwordWrapRandomSalt() {
+  const secret = new Uint8Array(16);
+  const a = await Deno.wrapWithPassword(secret, "pw", options);
+  const b = await Deno.wrapWithPassword(secret, "pw", options);
+  assertNotEquals(a, b);
+});
+
+Deno.test(async function passwordUnwrapFailures() {
+  const secret = new Uint8Array(16);
+  const envelope = await Deno.wrapWithPassword(secret, "pw", options);
+
+  await assertRejects(
+    () => Deno.unwrapWithPassword(envelope, "wrong"),
+    DOMException,
+    "wrong password or corrupted envelope",
+  );
+
+  const corrupted = envelope.slice();
+  corrupted[corrupted.length - 1] ^= 1;
+  await assertRejects(
+    () => Deno.unwrapWithPassword(corrupted, "pw"),
+    DOMException,
+    "wrong password or corrupted envelope",
+  );
+});
+
+Deno.test(async function passwordWrapInvalidOptions() {
+  await assertRejects(
+    () => Deno.wrapWithPassword(new Uint8Array(8), "pw", { iterations: 0 }),
+    TypeError,
+    "iterations must be between",
+  );
+  await assertRejects(
+    () => Deno.wrapWithPassword(new Uint8Array(8), "pw", { saltLength: 4 }),
+    TypeError,
+    "saltLength must be between",
+  );
+  await assertRejects(
+    () => Deno.wrapWithPassword(new Uint8Array(0), "pw", options),
+    TypeError,
+    "data must not be empty",
+  );
+});
@@ -0,0 +1,107 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertRejects,
+  assertThrows,
+} from "./test_util.ts";
+
+const isWindows = Deno.build.os === "windows";
+
+Deno.test(function pathNormalizeWindows() {
+  assertEquals(
+    Deno.path.normalizeWindows("C:/foo/./bar/../baz"),
+    "C:\\foo\\baz",
+  );
+  assertEquals(
+    Deno.path.normalizeWindows("//server/share/a", { longPath: true }),
+    "\\\\?\\UNC\\server\\share\\a",
+  );
+  assertEquals(
+    Deno.path.normalizeWindows("\\\\?\\C:\\foo", { longPath: false }),
+    "C:\\foo",
+  );
+  assertThrows(
+    () => Deno.path.normalizeWindows("foo\\bar", { longPath: true }),
+    TypeError,
+    "is not absolute",
+  );
+});
+
+Deno.test(function pathIsUncAndIsVerbatim() {
+  assert(Deno.path.isUNC("\\\\server\\share\\foo"));
+  assert(Deno.path.isUNC("\\\\?\\UNC\\server\\share"));
+  assert(!Deno.path.isUNC("\\\\.\\pipe\\foo"));
+  assert(!Deno.path.isUNC("C:\\foo"));
+  assert(Deno.path.isVerbatim("\\\\?\\C:\\foo"));
+  assert(!Deno.path.isVerbatim("C:\\foo"));
+});
+
+Deno.test({ permissions: { read: true } }, function pathRelative() {
+  const sep = isWindows ? "\\" : "/";
+  const root = isWindows ? "C:\\" : "/";
+  assertEquals(
+    Deno.path.relative(`${root}a${sep}b${sep}c`, `${root}a${sep}d`),
+    `..${sep}..${sep}d`,
+  );
+  assertEquals(Deno.path.relative(`${root}a`, `${root}a`), "");
+  assertEquals(Deno.path.relative(".", "tests"), "tests");
+  if (isWindows) {
+    assertEquals(
+      Deno.path.relative("C:\\Foo\\bar", "c:\\foo\\baz"),
+      "..\\baz",
+    );
+    assertEquals(Deno.path.relative("C:\\foo", "D:\\foo"), "D:\\foo");
+  }
+});
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function pathRealPathResolvesSymlinks() {
+    const dir = await Deno.realPath(Deno.makeTempDirSync());
+    Deno.mkdirSync(`${dir}/target`);
+    Deno.symlinkSync(`${dir}/target`, `${dir}/link`);
+    const expected = isWindows ? `${dir}\\target` : `${dir}/target`;
+    assertEquals(await Deno.path.realPath(`${dir}/link`), expected);
+
+    const long = await Deno.path.realPath(`${dir}/link`, { longPath: true });
+    assertEquals(long, isWindows ? `\\\\?\\${expected}` : expected);
+  },
+);
+
+Deno.test(
+  { permissions: { read: false } },
+  async function pathRealPathRequiresReadPermission() {
+    await assertRejects(
+      () => Deno.path.realPath("."),
+      Deno.errors.NotCapable,
+    );
+  },
+);
+
+Deno.test(
+  { ignore: !isWindows, permissions: { read: true, write: true } },
+  async function pathLongPathsOnWindows() {
+    const root = await Deno.realPath(Deno.makeTempDirSync());
+    let dir = root;
+    while (dir.length <= 300) {
+      dir += "\\" + "a".repeat(50);
+    }
+    Deno.mkdirSync(dir, { recursive: true });
+    const file = `${dir}\\file.txt`;
+    Deno.writeTextFileSync(file, "hello");
+    assert(file.length > 260);
+
+    assertEquals(Deno.readTextFileSync(file), "hello");
+    assertEquals(await Deno.readTextFile(file), "hello");
+    assert(Deno.statSync(file).isFile);
+    assertEquals(Deno.readDirSync(dir).next().value?.name, "file.txt");
+
+    const verbatim = await Deno.path.realPath(file, { longPath: true });
+    assert(Deno.path.isVerbatim(verbatim));
+    assertEquals(Deno.readTextFileSync(verbatim), "hello");
+    assertEquals(await Deno.path.realPath(file), file);
+
+    Deno.removeSync(root, { recursive: true });
+  },
+);
@@ -46,6 +46,8 @@ Deno.test(
     await Deno.permissions.query({ name: "sys", kind: "osUptime" });
     await Deno.permissions.query({ name: "sys", kind: "networkInterfaces" });
     await Deno.permissions.query({ name: "sys", kind: "systemMemoryInfo" });
+    await Deno.permissions.query({ name: "sys", kind: "systemCpuInfo" });
+    await Deno.permissions.query({ name: "sys", kind: "fdLimits" });
     await Deno.permissions.query({ name: "sys", kind: "hostname" });
     await Deno.permissions.query({ name: "sys", kind: "uid" });
     await Deno.permissions.query({ name: "sys", kind: "gid" });
@@ -60,6 +62,8 @@ Deno.test(
     Deno.permissions.querySync({ name: "sys", kind: "osRelease" });
     Deno.permissions.querySync({ name: "sys", kind: "networkInterfaces" });
     Deno.permissions.querySync({ name: "sys", kind: "systemMemoryInfo" });
+    Deno.permissions.querySync({ name: "sys", kind: "systemCpuInfo" });
+    Deno.permissions.querySync({ name: "sys", kind: "fdLimits" });
     Deno.permissions.querySync({ name: "sys", kind: "hostname" });
     Deno.permissions.querySync({ name: "sys", kind: "uid" });
     Deno.permissions.querySync({ name: "sys", kind: "gid" });
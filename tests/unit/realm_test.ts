@@ -0,0 +1,177 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertNotStrictEquals,
+  assertStringIncludes,
+  assertThrows,
+} from "./test_util.ts";
+
+// @ts-ignore This is not publicly typed namespace, but it's there for sure.
+const { op_realm_create, op_realm_eval, op_realm_close } = Deno[Deno.internal]
+  .core.ops;
+
+function withRealm(
+  fn: (rid: number) => void,
+  exposedOps: Record<string, unknown> = {},
+) {
+  const rid = op_realm_create("test", exposedOps);
+  try {
+    fn(rid);
+  } finally {
+    op_realm_close(rid);
+  }
+}
+
+Deno.test(function realmGlobalsAreIsolated() {
+  withRealm((rid) => {
+    op_realm_eval(rid, "var realmOnly = 1; globalThis.shared = 'realm';");
+    const host = globalThis as Record<string, unknown>;
+    assertEquals(typeof host.realmOnly, "undefined");
+    assertEquals(typeof host.shared, "undefined");
+    assertEquals(op_realm_eval(rid, "realmOnly + 1"), 2);
+    assertEquals(op_realm_eval(rid, "Array === globalThis.Array"), true);
+  });
+});
+
+Deno.test(function realmsDontShareGlobals() {
+  withRealm((a) => {
+    withRealm((b) => {
+      op_realm_eval(a, "globalThis.x = 'a'");
+      assertEquals(op_realm_eval(b, "typeof x"), "undefined");
+    });
+  });
+});
+
+Deno.test(function realmHasNoOpsByDefault() {
+  withRealm((rid) => {
+    assertEquals(op_realm_eval(rid, "Object.keys(ops)"), []);
+    assertEquals(op_realm_eval(rid, "typeof Deno"), "undefined");
+  });
+});
+
+Deno.test(function realmCallsExposedOps() {
+  const calls: unknown[] = [];
+  withRealm((rid) => {
+    assertEquals(
+      op_realm_eval(rid, "ops.record({ n: 1 }, [2])"),
+      { ok: true },
+    );
+    assertEquals(calls, [[{ n: 1 }, [2]]]);
+    // The realm gets a clone, not the host's object.
+    assertEquals(
+      op_realm_eval(
+        rid,
+        "Object.getPrototypeOf(ops.record()) === Object.prototype",
+      ),
+      true,
+    );
+    assertThrows(() => op_realm_eval(rid, "'use strict'; ops.record = null"));
+  }, {
+    record(...args: unknown[]) {
+      calls.push(args);
+      return { ok: true };
+    },
+  });
+});
+
+Deno.test(function realmRejectsNonFunctionOps() {
+  assertThrows(
+    () => op_realm_create("test", { notAnOp: 1 }),
+    TypeError,
+    'Exposed op "notAnOp" is not a function',
+  );
+});
+
+Deno.test(async function realmExposedAsyncOps() {
+  const rid = op_realm_create("test", {
+    delayed: (value: number) => Promise.resolve(value * 2),
+    failing: () => Promise.reject(new RangeError("nope")),
+  });
+  try {
+    op_realm_eval(
+      rid,
+      "globalThis.result = ops.delayed(21);" +
+        "globalThis.failure = ops.failing()" +
+        ".catch((e) => e.name + ': ' + e.message);",
+    );
+    await new Promise((resolve) => setTimeout(resolve, 0));
+    assertEquals(op_realm_eval(rid, "result instanceof Promise"), true);
+    op_realm_eval(
+      rid,
+      "result.then((v) => globalThis.resolved = v);" +
+        "failure.then((v) => globalThis.rejected = v);",
+    );
+    await new Promise((resolve) => setTimeout(resolve, 0));
+    assertEquals(op_realm_eval(rid, "resolved"), 42);
+    assertEquals(op_realm_eval(rid, "rejected"), "RangeError: nope");
+  } finally {
+    op_realm_close(rid);
+  }
+});
+
+Deno.test(function realmResultsAreCloned() {
+  withRealm((rid) => {
+    const value = op_realm_eval(
+      rid,
+      "globalThis.obj = { a: [1, 2], m: new Map([[1, 'x']]) }",
+    );
+    assertEquals(value, { a: [1, 2], m: new Map([[1, "x"]]) });
+    assertEquals(Object.getPrototypeOf(value), Object.prototype);
+    assertNotStrictEquals(value, op_realm_eval(rid, "obj"));
+    assertThrows(
+      () => op_realm_eval(rid, "() => {}"),
+      Error,
+      "could not be cloned",
+    );
+  });
+});
+
+Deno.test(function realmEvalTimesOut() {
+  withRealm((rid) => {
+    assertThrows(
+      () => op_realm_eval(rid, "while (true) {}", { timeoutMs: 50 }),
+      Deno.errors.TimedOut,
+    );
+    // The isolate keeps working after the termination is cancelled.
+    assertEquals(op_realm_eval(rid, "1 + 1", { timeoutMs: 1000 }), 2);
+  });
+});
+
+Deno.test(function realmErrorKeepsRealmStack() {
+  withRealm((rid) => {
+    const error = assertThrows(
+      () =>
+        op_realm_eval(
+          rid,
+          "function inner() { throw new SyntaxError('bad config'); }\n" +
+            "inner();",
+          { filename: "file:///config.js" },
+        ),
+      Error,
+      "bad config",
+    );
+    assertEquals(error.name, "SyntaxError");
+    assert(error.stack);
+    assertStringIncludes(error.stack, "at inner (file:///config.js:1:");
+    assertStringIncludes(error.stack, "file:///config.js:2:1");
+  });
+});
+
+Deno.test(function realmIsReentrant() {
+  const rid = op_realm_create("test", {
+    evalAgain: () => op_realm_eval(rid, "counter += 1"),
+  });
+  try {
+    op_realm_eval(rid, "globalThis.counter = 0");
+    assertEquals(op_realm_eval(rid, "ops.evalAgain() + ops.evalAgain()"), 3);
+  } finally {
+    op_realm_close(rid);
+  }
+});
+
+Deno.test(function realmCloseInvalidatesRid() {
+  const rid = op_realm_create("test", {});
+  op_realm_close(rid);
+  assertThrows(() => op_realm_eval(rid, "1"), Deno.errors.BadResource);
+});
@@ -0,0 +1,90 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertThrows } from "./test_util.ts";
+
+Deno.test(function scheduleRequiresHandler() {
+  assertThrows(
+    // @ts-ignore test
+    () => Deno.schedule("foo", { every: 1000 }),
+    TypeError,
+    "Cannot create schedule: a handler is required",
+  );
+});
+
+Deno.test(function scheduleInvalidSpec() {
+  assertThrows(
+    () => Deno.schedule("foo", "bogus", () => {}),
+    TypeError,
+    "Invalid cron schedule",
+  );
+  assertThrows(
+    () => Deno.schedule("foo", { every: 10 }, () => {}),
+    TypeError,
+    "Invalid schedule interval",
+  );
+  assertThrows(
+    () => Deno.schedule("a**bc", { every: 1000 }, () => {}),
+    TypeError,
+    "Invalid cron name",
+  );
+});
+
+Deno.test(async function scheduleDuplicateName() {
+  const ac = new AbortController();
+  const promise = Deno.schedule(
+    "dup",
+    { every: 60_000 },
+    { signal: ac.signal },
+    () => {},
+  );
+  assertThrows(
+    () => Deno.schedule("dup", { every: 60_000 }, () => {}),
+    TypeError,
+    "Cron with this name already exists",
+  );
+  ac.abort();
+  await promise;
+});
+
+Deno.test(async function scheduleRunsHandler() {
+  const ac = new AbortController();
+  const times: Date[] = [];
+  const { promise: ran, resolve } = Promise.withResolvers<void>();
+  const promise = Deno.schedule(
+    "interval",
+    { every: 1000 },
+    { signal: ac.signal },
+    ({ scheduledTime }) => {
+      times.push(scheduledTime);
+      if (times.length === 2) {
+        ac.abort();
+        resolve();
+      }
+    },
+  );
+  await ran;
+  await promise;
+  assertEquals(times.length, 2);
+  assertEquals(times[1].getTime() - times[0].getTime(), 1000);
+  assert(times[1].getTime() <= Date.now());
+});
+
+Deno.test(async function scheduleSkipsOverlappingRuns() {
+  const ac = new AbortController();
+  const times: number[] = [];
+  const promise = Deno.schedule(
+    "slow",
+    { every: 1000 },
+    { signal: ac.signal, overlap: "skip" },
+    async ({ scheduledTime }) => {
+      times.push(scheduledTime.getTime());
+      if (times.length === 2) {
+        ac.abort();
+        return;
+      }
+      // Runs over the next occurrence.
+      await new Promise((resolve) => setTimeout(resolve, 1500));
+    },
+  );
+  await promise;
+  assertEquals(times[1] - times[0], 2000);
+});
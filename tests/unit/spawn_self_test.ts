@@ -0,0 +1,90 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertThrows } from "./test_util.ts";
+
+const echoScript = new URL(
+  "../testdata/process/spawn_self_echo.ts",
+  import.meta.url,
+);
+
+function nextMessage(channel: Deno.IpcChannel): Promise<unknown> {
+  return new Promise((resolve) => {
+    channel.addEventListener("message", (e) => resolve(e.data), {
+      once: true,
+    });
+  });
+}
+
+Deno.test(
+  { permissions: { read: true, run: true } },
+  async function spawnSelfIpcRoundTrip() {
+    const child = Deno.spawnSelf(
+      ["run", "--quiet", "--unstable-process", echoScript.href],
+      { ipc: true },
+    );
+    const ipc = child.ipc!;
+    assert(ipc instanceof Deno.IpcChannel);
+
+    const values = [
+      new Map([["a", 1]]),
+      new Date(0),
+      new Uint8Array([1, 2, 3]),
+      { nested: [1n, null, "x"] },
+    ];
+    for (const value of values) {
+      const reply = nextMessage(ipc);
+      ipc.postMessage(value);
+      assertEquals(await reply, value);
+    }
+
+    const closed = new Promise((resolve) => ipc.onclose = resolve);
+    ipc.postMessage("exit");
+    await closed;
+    const status = await child.status;
+    assert(status.success);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, run: true } },
+  async function spawnSelfWithoutIpc() {
+    const child = Deno.spawnSelf(
+      ["eval", "--unstable-process", "Deno.exit(Deno.parentIpc() ? 1 : 0)"],
+      { stdout: "null" },
+    );
+    assertEquals(child.ipc, null);
+    const status = await child.status;
+    assert(status.success);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, run: true } },
+  async function spawnSelfPipedOutput() {
+    const child = Deno.spawnSelf(["eval", "console.log('hello')"], {
+      stdout: "piped",
+    });
+    const { success, stdout } = await child.output();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout), "hello\n");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, run: false } },
+  function spawnSelfPermissions() {
+    assertThrows(() => Deno.spawnSelf(["--version"]), Deno.errors.NotCapable);
+  },
+);
+
+Deno.test(function spawnSelfParentIpcNull() {
+  assertEquals(Deno.parentIpc(), null);
+});
+
+Deno.test(function spawnSelfIpcChannelIllegalConstructor() {
+  assertThrows(
+    // @ts-expect-error the constructor is private
+    () => new Deno.IpcChannel(),
+    TypeError,
+    "Illegal constructor",
+  );
+});
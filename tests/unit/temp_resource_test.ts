@@ -0,0 +1,101 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertRejects,
+  assertThrows,
+} from "./test_util.ts";
+
+function exists(path: string): boolean {
+  try {
+    Deno.lstatSync(path);
+    return true;
+  } catch (err) {
+    if (err instanceof Deno.errors.NotFound) return false;
+    throw err;
+  }
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function tempFileRemovedOnClose() {
+    const dir = Deno.makeTempDirSync();
+    const file = await Deno.createTempFile({
+      dir,
+      prefix: "a-",
+      suffix: ".txt",
+    });
+    const path = file.path;
+    assert(path.startsWith(dir));
+    assert(path.endsWith(".txt"));
+    await file.write(new TextEncoder().encode("hello"));
+    assert(exists(path));
+    file.close();
+    assert(!exists(path));
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function tempFilePersist() {
+    const dir = Deno.makeTempDirSync();
+    const target = `${dir}/out.txt`;
+    Deno.writeTextFileSync(target, "old");
+    {
+      using file = await Deno.createTempFile({ dir });
+      const original = file.path;
+      await file.write(new TextEncoder().encode("new"));
+      file.persist(target);
+      assertEquals(file.path, target);
+      assert(!exists(original));
+      assertThrows(
+        () => file.persist(`${dir}/other.txt`),
+        Deno.errors.BadResource,
+        "already persisted",
+      );
+    }
+    assertEquals(Deno.readTextFileSync(target), "new");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function tempDirRemovedOnClose() {
+    const parent = Deno.makeTempDirSync();
+    const dir = await Deno.createTempDir({ dir: parent, prefix: "build-" });
+    assert(dir.path.startsWith(`${parent}`));
+    Deno.mkdirSync(`${dir.path}/nested`);
+    Deno.writeTextFileSync(`${dir.path}/nested/file.txt`, "x");
+    dir.close();
+    assert(!exists(dir.path));
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function tempDirPersist() {
+    const parent = Deno.makeTempDirSync();
+    const target = `${parent}/kept`;
+    {
+      using dir = await Deno.createTempDir({ dir: parent });
+      Deno.writeTextFileSync(`${dir.path}/file.txt`, "x");
+      dir.persist(target);
+      assertEquals(dir.path, target);
+      assertThrows(
+        () => dir.persist(`${parent}/again`),
+        Deno.errors.BadResource,
+      );
+    }
+    assertEquals(Deno.readTextFileSync(`${target}/file.txt`), "x");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: false } },
+  async function tempFileRequiresWritePermission() {
+    await assertRejects(
+      () => Deno.createTempFile(),
+      Deno.errors.NotCapable,
+    );
+  },
+);
@@ -109,6 +109,87 @@ Deno.test(function textDecoderErrorEncoding() {
   assert(didThrow);
 });
 
+Deno.test(function textDecoderLabelsAreNormalized() {
+  assertEquals(new TextDecoder(" SJIS\n").encoding, "shift_jis");
+  assertEquals(new TextDecoder("latin1").encoding, "windows-1252");
+  assertEquals(new TextDecoder("GB2312").encoding, "gbk");
+  assertEquals(new TextDecoder("unicode-1-1-utf-8").encoding, "utf-8");
+  // the replacement encoding can't be used with TextDecoder
+  assertThrows(() => new TextDecoder("iso-2022-kr"), RangeError);
+  assertThrows(() => new TextDecoder("replacement"), RangeError);
+});
+
+Deno.test(function textDecoderLegacyEncodings() {
+  assertEquals(
+    new TextDecoder("windows-1252").decode(new Uint8Array([0x80, 0xe9])),
+    "€é",
+  );
+  assertEquals(
+    new TextDecoder("gbk").decode(new Uint8Array([0xd6, 0xd0, 0xce, 0xc4])),
+    "中文",
+  );
+  assertEquals(
+    new TextDecoder("shift_jis").decode(
+      new Uint8Array([0x93, 0xfa, 0x96, 0x7b]),
+    ),
+    "日本",
+  );
+});
+
+Deno.test(function textDecoderStreamsSplitSequences() {
+  const decoder = new TextDecoder("shift_jis");
+  // "日本" is 0x93 0xfa 0x96 0x7b, split in the middle of both characters
+  assertEquals(decoder.decode(new Uint8Array([0x93]), { stream: true }), "");
+  assertEquals(
+    decoder.decode(new Uint8Array([0xfa, 0x96]), { stream: true }),
+    "日",
+  );
+  assertEquals(decoder.decode(new Uint8Array([0x7b])), "本");
+
+  // a sequence left incomplete at the end is replaced
+  assertEquals(decoder.decode(new Uint8Array([0x93]), { stream: true }), "");
+  assertEquals(decoder.decode(), "\ufffd");
+  // and doesn't leak into the next decode
+  assertEquals(decoder.decode(new Uint8Array([0x41])), "A");
+
+  const gbk = new TextDecoder("gbk");
+  assertEquals(
+    gbk.decode(new Uint8Array([0xd6, 0xd0, 0xce]), { stream: true }),
+    "中",
+  );
+  assertEquals(gbk.decode(new Uint8Array([0xc4])), "文");
+
+  const utf8 = new TextDecoder();
+  assertEquals(utf8.decode(new Uint8Array([0xe2, 0x82]), { stream: true }), "");
+  assertEquals(utf8.decode(new Uint8Array([0xac])), "€");
+});
+
+Deno.test(function textDecoderFatal() {
+  const decoder = new TextDecoder("shift_jis", { fatal: true });
+  assertEquals(decoder.decode(new Uint8Array([0x93]), { stream: true }), "");
+  assertThrows(() => decoder.decode(), TypeError);
+  // the decoder is usable again afterwards
+  assertEquals(decoder.decode(new Uint8Array([0x93, 0xfa])), "日");
+
+  assertThrows(
+    () =>
+      new TextDecoder("utf-8", { fatal: true }).decode(
+        new Uint8Array([0x61, 0xff]),
+      ),
+    TypeError,
+  );
+  assertThrows(
+    () =>
+      new TextDecoder("gbk", { fatal: true }).decode(new Uint8Array([0xff])),
+    TypeError,
+  );
+  // the same input is replaced without `fatal`
+  assertEquals(
+    new TextDecoder().decode(new Uint8Array([0x61, 0xff])),
+    "a\ufffd",
+  );
+});
+
 Deno.test(function textEncoder() {
   const fixture = "𝓽𝓮𝔁𝓽";
   const encoder = new TextEncoder();
@@ -163,6 +244,35 @@ Deno.test(function textEncodeInto3() {
   ]);
 });
 
+Deno.test(function textEncodeIntoBufferBoundaries() {
+  const encoder = new TextEncoder();
+  // "€" takes 3 bytes, "𝄞" takes 4 bytes and 2 UTF-16 code units
+  const cases: [string, number, number, number][] = [
+    ["a€", 0, 0, 0],
+    ["a€", 1, 1, 1],
+    ["a€", 3, 1, 1],
+    ["a€", 4, 2, 4],
+    ["𝄞", 3, 0, 0],
+    ["𝄞", 4, 2, 4],
+    ["é𝄞", 5, 1, 2],
+    ["é𝄞", 6, 3, 6],
+  ];
+  for (const [input, size, read, written] of cases) {
+    const bytes = new Uint8Array(size);
+    assertEquals(
+      encoder.encodeInto(input, bytes),
+      { read, written },
+      `${input} into ${size} bytes`,
+    );
+  }
+
+  // writes go to the view, not to the start of its buffer
+  const buffer = new Uint8Array(6);
+  const result = encoder.encodeInto("€€", buffer.subarray(1, 5));
+  assertEquals(result, { read: 1, written: 3 });
+  assertEquals(Array.from(buffer), [0, 0xe2, 0x82, 0xac, 0, 0]);
+});
+
 Deno.test(function loneSurrogateEncodeInto() {
   const fixture = "lone𝄞\ud888surrogate";
   const encoder = new TextEncoder();
@@ -0,0 +1,110 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertRejects } from "./test_util.ts";
+
+async function search(
+  source: Parameters<typeof Deno.searchText>[0],
+  pattern: string,
+  options?: Deno.SearchTextOptions,
+): Promise<Deno.SearchTextMatch[]> {
+  const matches = [];
+  for await (const match of Deno.searchText(source, pattern, options)) {
+    matches.push(match);
+  }
+  return matches;
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function searchTextInTree() {
+    const root = Deno.realPathSync(Deno.makeTempDirSync());
+    Deno.mkdirSync(`${root}/src/nested`, { recursive: true });
+    Deno.writeTextFileSync(`${root}/src/a.ts`, "// TODO one\nx\n");
+    Deno.writeTextFileSync(`${root}/src/nested/b.ts`, "y\n// todo two\n");
+    Deno.writeTextFileSync(`${root}/src/c.js`, "// TODO js\n");
+    Deno.writeFileSync(
+      `${root}/src/d.ts`,
+      new TextEncoder().encode("TODO\0binary"),
+    );
+
+    const matches = await search(root, "todo", {
+      caseInsensitive: true,
+      include: ["**/*.ts"],
+      context: { before: 1, after: 1 },
+    });
+    const found = matches
+      .map((m) => ({
+        ...m,
+        path: m.path!.slice(root.length + 1).replaceAll("\\", "/"),
+      }))
+      .sort((a, b) => a.path.localeCompare(b.path));
+    assertEquals(found, [
+      {
+        path: "src/a.ts",
+        lineNumber: 1,
+        byteOffset: 3,
+        lineText: "// TODO one",
+        contextLines: { before: [], after: ["x"] },
+      },
+      {
+        path: "src/nested/b.ts",
+        lineNumber: 2,
+        byteOffset: 5,
+        lineText: "// todo two",
+        contextLines: { before: ["y"], after: [] },
+      },
+    ]);
+
+    assertEquals(
+      (await search(`${root}/src`, "TODO", { binary: true })).length,
+      3,
+    );
+    assertEquals((await search([`${root}/src/c.js`], "TODO")).length, 1);
+    assertEquals(
+      (await search(root, "TODO", { maxMatches: 1, binary: true })).length,
+      1,
+    );
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(async function searchTextInStream() {
+  const chunks = ["first line\nbegin", " one\nmid", "dle\nend\nlast\n"];
+  const stream = ReadableStream.from(chunks).pipeThrough(
+    new TextEncoderStream(),
+  );
+  const matches = await search(stream, String.raw`begin.*\n.*\nend`, {
+    multiline: true,
+    context: { before: 1, after: 1 },
+  });
+  assertEquals(matches, [
+    {
+      path: null,
+      lineNumber: 2,
+      byteOffset: 11,
+      lineText: "begin one\nmiddle\nend",
+      contextLines: { before: ["first line"], after: ["last"] },
+    },
+  ]);
+});
+
+Deno.test(async function searchTextFixedString() {
+  const stream = ReadableStream.from(["a.b\naxb\n"]).pipeThrough(
+    new TextEncoderStream(),
+  );
+  const matches = await search(stream, "a.b", { fixedString: true });
+  assertEquals(matches.map((m) => m.lineText), ["a.b"]);
+});
+
+Deno.test(async function searchTextInvalidPattern() {
+  await assertRejects(() => search("README.md", "("), TypeError);
+});
+
+Deno.test(
+  { permissions: { read: false } },
+  async function searchTextPerm() {
+    await assertRejects(
+      () => search(".", "x"),
+      Deno.errors.NotCapable,
+    );
+  },
+);
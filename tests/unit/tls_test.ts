@@ -1404,3 +1404,152 @@ Deno.test(
     listener.close();
   },
 );
+
+Deno.test(
+  { permissions: { net: true, read: true, write: true } },
+  async function tlsSendFile() {
+    const content = new Uint8Array(200_000).map((_, i) => i % 253);
+    const path = Deno.makeTempFileSync();
+    Deno.writeFileSync(path, content);
+    using file = Deno.openSync(path);
+
+    const { listener, port, hostname } = listenTls();
+    const accepted = listener.accept();
+    const client = await Deno.connectTls({ hostname, port, caCerts });
+    const server = await accepted;
+    listener.close();
+
+    const received = readAll(client);
+    assertEquals(
+      await Deno.sendFile(server, file, { offset: 100 }),
+      content.length - 100,
+    );
+    server.close();
+    assertEquals(await received, content.subarray(100));
+    client.close();
+    Deno.removeSync(path);
+  },
+);
+
+const localhostPin = "LUoAIoic1GZuPrXMkihRhgG8Jz8PmEo5I5EfXR+b/eo=";
+const rootCaPin = "rCZhu/dePzdoNw3S5Br47UF6PM6fPNxAicc5U6w9jrU=";
+
+function pemToDer(pem: string): Uint8Array {
+  const base64 = pem
+    .replace(/-----(BEGIN|END) CERTIFICATE-----/g, "")
+    .replace(/\s/g, "");
+  return Uint8Array.from(atob(base64), (c) => c.charCodeAt(0));
+}
+
+Deno.test(function tlsCertificateSpkiHash() {
+  assertEquals(Deno.certificateSpkiHash(cert), localhostPin);
+  assertEquals(Deno.certificateSpkiHash(pemToDer(cert)), localhostPin);
+  assertEquals(Deno.certificateSpkiHash(caCerts[0]), rootCaPin);
+  assertThrows(
+    () => Deno.certificateSpkiHash("not a certificate"),
+    Deno.errors.InvalidData,
+  );
+  assertThrows(
+    () => Deno.certificateSpkiHash(new Uint8Array([1, 2, 3])),
+    Deno.errors.InvalidData,
+  );
+});
+
+async function connectTlsPinned(
+  options: { pins: string[]; pinsReportOnly?: boolean },
+): Promise<void> {
+  const { listener, port, hostname } = listenTls();
+  const accepted = listener.accept().then(async (conn) => {
+    try {
+      await conn.handshake();
+    } catch {
+      // The client rejected the certificate.
+    } finally {
+      conn.close();
+    }
+  });
+  try {
+    const conn = await Deno.connectTls({ hostname, port, caCerts, ...options });
+    try {
+      await conn.handshake();
+    } finally {
+      conn.close();
+    }
+  } finally {
+    await accepted;
+    listener.close();
+  }
+}
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function connectTlsPinMatch() {
+    await connectTlsPinned({ pins: [rootCaPin, localhostPin] });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function connectTlsPinMismatch() {
+    const error = await assertRejects(
+      () => connectTlsPinned({ pins: [rootCaPin] }),
+      Deno.errors.InvalidData,
+    );
+    assert(error.message.includes(localhostPin), error.message);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function connectTlsPinMismatchReportOnly() {
+    await connectTlsPinned({ pins: [rootCaPin], pinsReportOnly: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function connectTlsPinsCheckedOnEveryConnection() {
+    await connectTlsPinned({ pins: [localhostPin] });
+    await assertRejects(
+      () => connectTlsPinned({ pins: [rootCaPin] }),
+      Deno.errors.InvalidData,
+    );
+    await connectTlsPinned({ pins: [localhostPin] });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function connectTlsInvalidPin() {
+    await assertRejects(
+      () => Deno.connectTls({ hostname: "localhost", port: 0, pins: ["abc"] }),
+      Deno.errors.InvalidData,
+      "Invalid pin 'abc'",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function startTlsPinMismatch() {
+    const { listener, port, hostname } = listenTls();
+    const accepted = listener.accept().then(async (conn) => {
+      await conn.handshake().catch(() => {});
+      conn.close();
+    });
+    const tcpConn = await Deno.connect({ hostname, port });
+    const conn = await Deno.startTls(tcpConn, {
+      hostname,
+      caCerts,
+      pins: [rootCaPin],
+    });
+    const error = await assertRejects(
+      () => conn.handshake(),
+      Deno.errors.InvalidData,
+    );
+    assert(error.message.includes(localhostPin), error.message);
+    conn.close();
+    await accepted;
+    listener.close();
+  },
+);
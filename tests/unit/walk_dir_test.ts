@@ -0,0 +1,162 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertRejects } from "./test_util.ts";
+
+function writeTree(root: string) {
+  Deno.mkdirSync(`${root}/src/nested/deep`, { recursive: true });
+  Deno.mkdirSync(`${root}/node_modules/pkg`, { recursive: true });
+  Deno.writeTextFileSync(`${root}/deno.json`, "{}");
+  Deno.writeTextFileSync(`${root}/src/main.ts`, "console.log(1);\n");
+  Deno.writeTextFileSync(`${root}/src/main_test.ts`, "");
+  Deno.writeTextFileSync(`${root}/src/nested/mod.ts`, "export {};\n");
+  Deno.writeTextFileSync(`${root}/src/nested/deep/x.js`, "");
+  Deno.writeTextFileSync(`${root}/node_modules/pkg/index.js`, "x");
+}
+
+async function walk(
+  root: string,
+  options?: Deno.WalkDirOptions,
+): Promise<string[]> {
+  const paths = [];
+  for await (const entry of Deno.walkDir(root, options)) {
+    const relative = entry.path.slice(root.length + 1).replaceAll("\\", "/");
+    const kind = entry.isSymlink ? "l" : entry.isDir ? "d" : "f";
+    paths.push(`${kind} ${relative}`);
+  }
+  return paths.sort();
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function walkDirYieldsAllEntries() {
+    const root = Deno.realPathSync(Deno.makeTempDirSync());
+    writeTree(root);
+    assertEquals(await walk(root), [
+      "d node_modules",
+      "d node_modules/pkg",
+      "d src",
+      "d src/nested",
+      "d src/nested/deep",
+      "f deno.json",
+      "f node_modules/pkg/index.js",
+      "f src/main.ts",
+      "f src/main_test.ts",
+      "f src/nested/deep/x.js",
+      "f src/nested/mod.ts",
+    ]);
+
+    const options = { include: ["src/main.ts"] };
+    for await (const entry of Deno.walkDir(root, options)) {
+      assertEquals(entry.size, 16);
+      assert(entry.mtime instanceof Date);
+      assert(entry.isFile);
+    }
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function walkDirGlobFilters() {
+    const root = Deno.realPathSync(Deno.makeTempDirSync());
+    writeTree(root);
+    assertEquals(
+      await walk(root, {
+        include: ["src"],
+        exclude: ["src/nested/deep", "**/*_test.ts"],
+        types: ["file"],
+      }),
+      ["f src/main.ts", "f src/nested/mod.ts"],
+    );
+    assertEquals(
+      await walk(root, { include: ["**/*.js"], exclude: ["node_modules"] }),
+      ["f src/nested/deep/x.js"],
+    );
+    await assertRejects(
+      () => walk(root, { include: ["["] }),
+      TypeError,
+      "Invalid glob pattern",
+    );
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function walkDirMaxDepth() {
+    const root = Deno.realPathSync(Deno.makeTempDirSync());
+    writeTree(root);
+    assertEquals(await walk(root, { maxDepth: 0 }), []);
+    assertEquals(await walk(root, { maxDepth: 1 }), [
+      "d node_modules",
+      "d src",
+      "f deno.json",
+    ]);
+    assertEquals((await walk(root, { maxDepth: 3 })).length, 10);
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function walkDirBreakStopsWalk() {
+    const root = Deno.realPathSync(Deno.makeTempDirSync());
+    for (let i = 0; i < 20; i++) {
+      Deno.mkdirSync(`${root}/${i}`);
+      for (let j = 0; j < 100; j++) {
+        Deno.writeTextFileSync(`${root}/${i}/${j}.txt`, "");
+      }
+    }
+    let count = 0;
+    for await (const _ of Deno.walkDir(root)) {
+      if (++count === 10) break;
+    }
+    assertEquals(count, 10);
+    assertEquals((await walk(root, { types: ["file"] })).length, 2000);
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  async function walkDirSymlinkCycles() {
+    const root = Deno.realPathSync(Deno.makeTempDirSync());
+    const outside = Deno.realPathSync(Deno.makeTempDirSync());
+    Deno.mkdirSync(`${root}/a`);
+    Deno.writeTextFileSync(`${root}/a/file.txt`, "");
+    Deno.writeTextFileSync(`${outside}/secret.txt`, "");
+    Deno.symlinkSync(`${root}/a`, `${root}/b`);
+    Deno.symlinkSync(root, `${root}/a/loop`);
+    Deno.symlinkSync(outside, `${root}/escape`);
+
+    assertEquals(await walk(root), [
+      "d a",
+      "f a/file.txt",
+      "l a/loop",
+      "l b",
+      "l escape",
+    ]);
+
+    const paths = await walk(root, { followSymlinks: true });
+    assert(paths.includes("f b/file.txt"), paths.join("\n"));
+    assert(paths.includes("l b/loop"), paths.join("\n"));
+    assert(paths.includes("l escape"), paths.join("\n"));
+    assert(!paths.some((path) => path.includes("secret.txt")));
+    assert(!paths.some((path) => path.startsWith("f a/loop/")));
+
+    Deno.removeSync(root, { recursive: true });
+    Deno.removeSync(outside, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: false } },
+  function walkDirRequiresReadPermission() {
+    return assertRejects(
+      () => walk("."),
+      Deno.errors.NotCapable,
+    );
+  },
+);
@@ -0,0 +1,72 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertThrows } from "./test_util.ts";
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function watchConfigReloadsAndKeepsLastGoodValue() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/config.json`;
+    Deno.writeTextFileSync(path, '{ "port": 1 }');
+
+    using watcher = Deno.watchConfig<{ port: number }>(path, {
+      debounceMs: 20,
+    });
+    const events = watcher[Symbol.asyncIterator]();
+
+    assertEquals((await events.next()).value, {
+      kind: "initial",
+      value: { port: 1 },
+    });
+
+    Deno.writeTextFileSync(path, '{ "port": 2 }');
+    assertEquals((await events.next()).value, {
+      kind: "update",
+      value: { port: 2 },
+    });
+
+    Deno.writeTextFileSync(path, '{ "port": ');
+    const error = (await events.next()).value!;
+    assert(error.kind === "error");
+    assert(error.error instanceof Error);
+    assertEquals(error.previous, { port: 2 });
+
+    // replaced by a rename, as editors do
+    Deno.writeTextFileSync(`${path}.tmp`, '{ "port": 3 }');
+    Deno.renameSync(`${path}.tmp`, path);
+    assertEquals((await events.next()).value, {
+      kind: "update",
+      value: { port: 3 },
+    });
+
+    watcher.close();
+    assert((await events.next()).done);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function watchConfigRaw() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/config.bin`;
+    Deno.writeFileSync(path, new Uint8Array([1, 2, 3]));
+
+    using watcher = Deno.watchConfig(path, { parser: "raw" });
+    for await (const event of watcher) {
+      assertEquals(event, {
+        kind: "initial",
+        value: new Uint8Array([1, 2, 3]),
+      });
+      break;
+    }
+  },
+);
+
+Deno.test(
+  { permissions: { read: false } },
+  function watchConfigPermissions() {
+    assertThrows(
+      () => Deno.watchConfig("config.json"),
+      Deno.errors.NotCapable,
+    );
+  },
+);
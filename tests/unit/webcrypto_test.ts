@@ -189,6 +189,39 @@ Deno.test(async function testGenerateHMACKey() {
   assert(key.usages.includes("sign"));
 });
 
+Deno.test(async function testGenerateHMACKeyWithLength() {
+  const key = await crypto.subtle.generateKey(
+    { name: "HMAC", hash: "SHA-256", length: 512 },
+    true,
+    ["sign", "verify"],
+  );
+  assertEquals((key.algorithm as HmacKeyAlgorithm).length, 512);
+  const raw = await crypto.subtle.exportKey("raw", key);
+  assertEquals(raw.byteLength, 64);
+
+  const data = new Uint8Array([1, 2, 3]);
+  const signature = await crypto.subtle.sign("HMAC", key, data);
+  const imported = await crypto.subtle.importKey(
+    "raw",
+    raw,
+    { name: "HMAC", hash: "SHA-256" },
+    false,
+    ["verify"],
+  );
+  assert(await crypto.subtle.verify("HMAC", imported, signature, data));
+
+  await assertRejects(
+    () =>
+      crypto.subtle.generateKey(
+        { name: "HMAC", hash: "SHA-256", length: 4096 },
+        true,
+        ["sign"],
+      ),
+    DOMException,
+    "Invalid HMAC key length",
+  );
+});
+
 Deno.test(async function testECDSASignVerify() {
   const key = await globalThis.crypto.subtle.generateKey(
     {
@@ -843,6 +876,119 @@ Deno.test(async function testECDH() {
   }
 });
 
+Deno.test(async function testEcdhP521() {
+  const alg = { name: "ECDH", namedCurve: "P-521" };
+  const alice = await crypto.subtle.generateKey(alg, true, [
+    "deriveBits",
+    "deriveKey",
+  ]);
+  const bob = await crypto.subtle.generateKey(alg, true, [
+    "deriveBits",
+    "deriveKey",
+  ]);
+
+  const aliceBits = await crypto.subtle.deriveBits(
+    { name: "ECDH", public: bob.publicKey },
+    alice.privateKey,
+    528,
+  );
+  const bobBits = await crypto.subtle.deriveBits(
+    { name: "ECDH", public: alice.publicKey },
+    bob.privateKey,
+    528,
+  );
+  assertEquals(aliceBits.byteLength, 66);
+  assertEquals(new Uint8Array(aliceBits), new Uint8Array(bobBits));
+
+  // Imported keys agree on the same secret.
+  const bobPublic = await crypto.subtle.importKey(
+    "raw",
+    await crypto.subtle.exportKey("raw", bob.publicKey),
+    alg,
+    true,
+    [],
+  );
+  const importedBits = await crypto.subtle.deriveBits(
+    { name: "ECDH", public: bobPublic },
+    alice.privateKey,
+    528,
+  );
+  assertEquals(new Uint8Array(importedBits), new Uint8Array(aliceBits));
+
+  // The derived secret can key AES-GCM on both sides.
+  const aliceKey = await crypto.subtle.deriveKey(
+    { name: "ECDH", public: bob.publicKey },
+    alice.privateKey,
+    { name: "AES-GCM", length: 256 },
+    false,
+    ["encrypt"],
+  );
+  const bobKey = await crypto.subtle.deriveKey(
+    { name: "ECDH", public: alice.publicKey },
+    bob.privateKey,
+    { name: "AES-GCM", length: 256 },
+    false,
+    ["decrypt"],
+  );
+  const iv = crypto.getRandomValues(new Uint8Array(12));
+  const ciphertext = await crypto.subtle.encrypt(
+    { name: "AES-GCM", iv },
+    aliceKey,
+    new Uint8Array([1, 2, 3]),
+  );
+  const plaintext = await crypto.subtle.decrypt(
+    { name: "AES-GCM", iv },
+    bobKey,
+    ciphertext,
+  );
+  assertEquals(new Uint8Array(plaintext), new Uint8Array([1, 2, 3]));
+});
+
+Deno.test(async function testPbkdf2DeriveKeyAes() {
+  // RFC 6070, test vector 2.
+  const key = await crypto.subtle.importKey(
+    "raw",
+    new TextEncoder().encode("password"),
+    "PBKDF2",
+    false,
+    ["deriveKey"],
+  );
+  const derivedKey = await crypto.subtle.deriveKey(
+    {
+      name: "PBKDF2",
+      salt: new TextEncoder().encode("salt"),
+      iterations: 2,
+      hash: "SHA-1",
+    },
+    key,
+    { name: "AES-CBC", length: 128 },
+    true,
+    ["encrypt"],
+  );
+  const raw = await crypto.subtle.exportKey("raw", derivedKey);
+  assertEquals(
+    new Uint8Array(raw),
+    new Uint8Array([
+      0xea,
+      0x6c,
+      0x01,
+      0x4d,
+      0xc7,
+      0x2d,
+      0x6f,
+      0x8c,
+      0xcd,
+      0x1e,
+      0xd9,
+      0x2a,
+      0xce,
+      0x1d,
+      0x41,
+      0xf0,
+    ]),
+  );
+});
+
 Deno.test(async function testWrapKey() {
   // Test wrapKey
   const key = await crypto.subtle.generateKey(
@@ -1238,6 +1384,65 @@ Deno.test(async function testImportRsaJwk() {
   }
 });
 
+Deno.test(async function testImportRsaJwkInsecureKeySize() {
+  const { publicJWK, privateJWK } = jwtRSAKeys["1024"];
+  for (const jwk of [publicJWK, privateJWK]) {
+    const usages: KeyUsage[] = jwk === publicJWK ? ["verify"] : ["sign"];
+    await assertRejects(
+      () =>
+        crypto.subtle.importKey(
+          "jwk",
+          jwk,
+          { name: "RSASSA-PKCS1-v1_5", hash: "SHA-256" },
+          true,
+          usages,
+        ),
+      DOMException,
+      "RSA modulus length must be at least 2048 bits",
+    );
+    const key = await crypto.subtle.importKey(
+      "jwk",
+      jwk,
+      {
+        name: "RSASSA-PKCS1-v1_5",
+        hash: "SHA-256",
+        allowInsecureKeySizes: true,
+      },
+      true,
+      usages,
+    );
+    assertEquals((key.algorithm as RsaHashedKeyAlgorithm).modulusLength, 1024);
+  }
+});
+
+Deno.test(async function testImportRsaJwkRecomputesCrtParams() {
+  const { dp, dq, qi, ...jwk } = jwtRSAKeys["2048"].privateJWK;
+  const alg = { name: "RSA-PSS", hash: "SHA-256" };
+  const key = await crypto.subtle.importKey("jwk", jwk, alg, true, ["sign"]);
+  const exported = await crypto.subtle.exportKey("jwk", key);
+  assertEquals(exported.dp, dp);
+  assertEquals(exported.dq, dq);
+  assertEquals(exported.qi, qi);
+});
+
+Deno.test(async function testImportRsaJwkInconsistentKey() {
+  const { privateJWK } = jwtRSAKeys["2048"];
+  const alg = { name: "RSA-PSS", hash: "SHA-256" };
+  const invalid = [
+    // p and q swapped, CRT parameters left as is.
+    { ...privateJWK, p: privateJWK.q, q: privateJWK.p },
+    // d from another key.
+    { ...privateJWK, d: jwtRSAKeys["4096"].privateJWK.d },
+    { ...privateJWK, qi: privateJWK.dp },
+  ];
+  for (const jwk of invalid) {
+    await assertRejects(
+      () => crypto.subtle.importKey("jwk", jwk, alg, true, ["sign"]),
+      DOMException,
+    );
+  }
+});
+
 const jwtECKeys = {
   "256": {
     size: 256,
@@ -2085,3 +2290,594 @@ Deno.test(async function x25519SharedSecret() {
   assertEquals(sharedSecret1.byteLength, 16);
   assertEquals(new Uint8Array(sharedSecret1), new Uint8Array(sharedSecret2));
 });
+
+// RFC 7517, Appendix A.1 and A.2.
+const rfc7517EcPublicJwk = {
+  kty: "EC",
+  crv: "P-256",
+  x: "MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4",
+  y: "4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM",
+};
+const rfc7517EcPrivateJwk = {
+  ...rfc7517EcPublicJwk,
+  d: "870MB6gfuTJ4HtUnUvYMyJpr5eUZNP4Bk43bVdj3eAE",
+};
+
+Deno.test(async function ecJwkRfc7517RoundTrip() {
+  const alg = { name: "ECDH", namedCurve: "P-256" };
+  const publicKey = await crypto.subtle.importKey(
+    "jwk",
+    rfc7517EcPublicJwk,
+    alg,
+    true,
+    [],
+  );
+  assertEquals(publicKey.type, "public");
+  const exportedPublic = await crypto.subtle.exportKey("jwk", publicKey);
+  assertEquals(exportedPublic.crv, "P-256");
+  assertEquals(exportedPublic.x, rfc7517EcPublicJwk.x);
+  assertEquals(exportedPublic.y, rfc7517EcPublicJwk.y);
+
+  const privateKey = await crypto.subtle.importKey(
+    "jwk",
+    rfc7517EcPrivateJwk,
+    alg,
+    true,
+    ["deriveBits"],
+  );
+  assertEquals(privateKey.type, "private");
+  const exportedPrivate = await crypto.subtle.exportKey("jwk", privateKey);
+  assertEquals(exportedPrivate.x, rfc7517EcPrivateJwk.x);
+  assertEquals(exportedPrivate.y, rfc7517EcPrivateJwk.y);
+  assertEquals(exportedPrivate.d, rfc7517EcPrivateJwk.d);
+});
+
+// RFC 7515, Appendix A.3.
+Deno.test(async function ecJwkRfc7515SignVerify() {
+  const jwk = {
+    kty: "EC",
+    crv: "P-256",
+    x: "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+    y: "x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0",
+    d: "jpsQnnGQmL-YBIffH1136cLNCrsYLbHLbOaTgT_6sTE",
+  };
+  const alg = { name: "ECDSA", namedCurve: "P-256" };
+  const privateKey = await crypto.subtle.importKey(
+    "jwk",
+    jwk,
+    alg,
+    false,
+    ["sign"],
+  );
+  const { d: _, ...publicJwk } = jwk;
+  const publicKey = await crypto.subtle.importKey(
+    "jwk",
+    publicJwk,
+    alg,
+    false,
+    ["verify"],
+  );
+  const data = new TextEncoder().encode("RFC 7515");
+  const signAlg = { name: "ECDSA", hash: "SHA-256" };
+  const signature = await crypto.subtle.sign(signAlg, privateKey, data);
+  assert(await crypto.subtle.verify(signAlg, publicKey, signature, data));
+});
+
+Deno.test(async function ecJwkRejectsInvalidKeys() {
+  const alg = { name: "ECDSA", namedCurve: "P-256" };
+
+  // Off-curve point.
+  await assertRejects(
+    () =>
+      crypto.subtle.importKey(
+        "jwk",
+        {
+          ...rfc7517EcPublicJwk,
+          y: "4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyQ",
+        },
+        alg,
+        true,
+        ["verify"],
+      ),
+    DOMException,
+  );
+
+  // Public coordinates that don't belong to `d`.
+  await assertRejects(
+    () =>
+      crypto.subtle.importKey(
+        "jwk",
+        {
+          ...rfc7517EcPrivateJwk,
+          d: "jpsQnnGQmL-YBIffH1136cLNCrsYLbHLbOaTgT_6sTE",
+        },
+        alg,
+        true,
+        ["sign"],
+      ),
+    DOMException,
+  );
+
+  // `d` out of range (zero).
+  await assertRejects(
+    () =>
+      crypto.subtle.importKey(
+        "jwk",
+        {
+          ...rfc7517EcPrivateJwk,
+          d: "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+        },
+        alg,
+        true,
+        ["sign"],
+      ),
+    DOMException,
+  );
+
+  // Mismatched curve.
+  await assertRejects(
+    () =>
+      crypto.subtle.importKey(
+        "jwk",
+        rfc7517EcPublicJwk,
+        { name: "ECDSA", namedCurve: "P-384" },
+        true,
+        ["verify"],
+      ),
+    DOMException,
+  );
+});
+
+// Produced by Node.js' WebCrypto with the public key of `jwtRSAKeys["1024"]`.
+const rsaOaepInteropVectors = [
+  {
+    hash: "SHA-256",
+    label: undefined,
+    plainText: "no label",
+    cipherText:
+      "yPuTwLuKXR31aw9lVntV2couvW3sep2CJZgl6CDBTq35VHG6q+AjWNtiA8R+dTS52Ms6bTafW8+Zs/ncoQVD8zwG+Op+Dwu/MFXAFsKDVq6fnUJLybRf/MuNk+0apfgZzWSiUm33JL1dojapXk6SAt6DOwM5B9G2PSDlALoq/FQ=",
+  },
+  {
+    hash: "SHA-256",
+    label: new Uint8Array([0xff, 0xfe, 0x00, 0x80, 0x01]),
+    plainText: "binary label",
+    cipherText:
+      "SqeIZ0NnaGoBIqpGh5MBtdy9zOmHfZN4m3NzTX9ey57zQA9XnOFE1KsImk+hhf6xOY0tvjpSaBPvSiYRSlvKPhUNRikiFHgBjQ1t2flhtZnn/rkgrvEAuJ5wM7O6ki3rQDFjn0ICAZ7JfHgJt7TxcF82nOz5m4DHE3RL6GZJ7xY=",
+  },
+  {
+    hash: "SHA-1",
+    label: new TextEncoder().encode("deno"),
+    plainText: "text label",
+    cipherText:
+      "smWOAUwdBzK8SfY2itKXH9TO1W9JWlXeiZDqVv33ozLwIcjP9OZOGPPQCSLRNynC15hqpS6GYyMKZ8JTDKoB5wTSQK2DZtO90ZD19FglYP/pSYIx4az1V+G7Sn6UDyw3/I3D3vC26Jp83jKJVE0lDA7TEO3u3fVVQpjSSMOP1t0=",
+  },
+];
+
+function importRsaOaepPrivateKey(hash: string) {
+  return crypto.subtle.importKey(
+    "jwk",
+    jwtRSAKeys["1024"].privateJWK,
+    { name: "RSA-OAEP", hash },
+    false,
+    ["decrypt"],
+  );
+}
+
+Deno.test(async function testRsaOaepDecryptInterop() {
+  for (const { hash, label, plainText, cipherText } of rsaOaepInteropVectors) {
+    const key = await importRsaOaepPrivateKey(hash);
+    const decrypted = await crypto.subtle.decrypt(
+      { name: "RSA-OAEP", label },
+      key,
+      Uint8Array.from(atob(cipherText), (c) => c.charCodeAt(0)),
+    );
+    assertEquals(new TextDecoder().decode(decrypted), plainText);
+  }
+});
+
+Deno.test(async function testRsaOaepDecryptFailuresAreIndistinguishable() {
+  const { label, cipherText } = rsaOaepInteropVectors[1];
+  const valid = Uint8Array.from(atob(cipherText), (c) => c.charCodeAt(0));
+  const flipped = valid.slice();
+  flipped[20] ^= 1;
+  const extended = new Uint8Array(valid.length + 1);
+  extended.set(valid);
+
+  const cases: [string, BufferSource | undefined, Uint8Array][] = [
+    ["SHA-256", label, flipped],
+    ["SHA-256", label, valid.subarray(1)],
+    ["SHA-256", label, extended],
+    ["SHA-256", new Uint8Array([0xff]), valid],
+    ["SHA-256", undefined, valid],
+    ["SHA-1", label, valid],
+  ];
+  for (const [hash, caseLabel, data] of cases) {
+    const key = await importRsaOaepPrivateKey(hash);
+    const error = await assertRejects(
+      () =>
+        crypto.subtle.decrypt(
+          { name: "RSA-OAEP", label: caseLabel },
+          key,
+          data,
+        ),
+      DOMException,
+    );
+    assertEquals(error.name, "OperationError");
+    assertEquals(error.message, "Decryption failed");
+  }
+});
+
+Deno.test(async function verifyReturnsFalseForInvalidSignatures() {
+  const data = new TextEncoder().encode("Hello, World!");
+  const rsaParams = {
+    modulusLength: 2048,
+    publicExponent: new Uint8Array([1, 0, 1]),
+    hash: "SHA-256",
+  };
+  const cases: [
+    AlgorithmIdentifier | RsaPssParams | EcdsaParams,
+    CryptoKey,
+    CryptoKey,
+  ][] = [];
+
+  const hmacKey = await crypto.subtle.generateKey(
+    { name: "HMAC", hash: "SHA-256" },
+    false,
+    ["sign", "verify"],
+  );
+  cases.push([{ name: "HMAC" }, hmacKey, hmacKey]);
+  for (const name of ["RSASSA-PKCS1-v1_5", "RSA-PSS"]) {
+    const { privateKey, publicKey } = await crypto.subtle.generateKey(
+      { name, ...rsaParams },
+      false,
+      ["sign", "verify"],
+    );
+    cases.push([{ name, saltLength: 32 }, privateKey, publicKey]);
+  }
+  const ecdsaKeyPair = await crypto.subtle.generateKey(
+    { name: "ECDSA", namedCurve: "P-256" },
+    false,
+    ["sign", "verify"],
+  );
+  cases.push([
+    { name: "ECDSA", hash: "SHA-256" },
+    ecdsaKeyPair.privateKey,
+    ecdsaKeyPair.publicKey,
+  ]);
+
+  for (const [algorithm, signingKey, verifyingKey] of cases) {
+    const signature = new Uint8Array(
+      await crypto.subtle.sign(algorithm, signingKey, data),
+    );
+    assert(
+      await crypto.subtle.verify(algorithm, verifyingKey, signature, data),
+    );
+
+    const tampered = signature.slice();
+    tampered[0] ^= 1;
+    const invalidSignatures = [
+      tampered,
+      signature.subarray(1),
+      new Uint8Array(signature.length + 1),
+      new Uint8Array(),
+    ];
+    for (const invalid of invalidSignatures) {
+      assertEquals(
+        await crypto.subtle.verify(algorithm, verifyingKey, invalid, data),
+        false,
+      );
+    }
+    assertEquals(
+      await crypto.subtle.verify(
+        algorithm,
+        verifyingKey,
+        signature,
+        new Uint8Array([1]),
+      ),
+      false,
+    );
+  }
+});
+
+Deno.test(async function subtleDigest() {
+  const data = new TextEncoder().encode("abc");
+  const expected: Record<string, string> = {
+    "SHA-1": "a9993e364706816aba3e25717850c26c9cd0d89d",
+    "SHA-256":
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    "SHA-384":
+      "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+    "SHA-512":
+      "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+  };
+  const hex = (buffer: ArrayBuffer) =>
+    Array.from(new Uint8Array(buffer))
+      .map((byte) => byte.toString(16).padStart(2, "0"))
+      .join("");
+
+  for (const [name, digest] of Object.entries(expected)) {
+    assertEquals(hex(await crypto.subtle.digest(name, data)), digest);
+    assertEquals(
+      hex(await crypto.subtle.digest({ name: name.toLowerCase() }, data)),
+      digest,
+    );
+  }
+
+  const error = await assertRejects(
+    () => crypto.subtle.digest("SHA-3", data),
+    DOMException,
+  );
+  assertEquals(error.name, "NotSupportedError");
+});
+
+Deno.test(async function subtleDigestLargeBufferIsHashedAsPassed() {
+  const data = new Uint8Array(16 * 1024 * 1024).fill(0x61);
+  const expected = await crypto.subtle.digest("SHA-256", data);
+
+  // The bytes are copied when `digest()` is called, so changing them while
+  // they are hashed doesn't change the result.
+  const promise = crypto.subtle.digest("SHA-256", data);
+  data.fill(0x62);
+  assertEquals(await promise, expected);
+  assertNotEquals(await crypto.subtle.digest("SHA-256", data), expected);
+});
+
+Deno.test(async function testAesGcmDecryptFailures() {
+  const key = await crypto.subtle.generateKey(
+    { name: "AES-GCM", length: 128 },
+    false,
+    ["encrypt", "decrypt"],
+  );
+  const iv = new Uint8Array(12);
+  const additionalData = new Uint8Array([1, 2, 3]);
+  const data = new TextEncoder().encode("Hello, World!");
+  const cipherText = new Uint8Array(
+    await crypto.subtle.encrypt(
+      { name: "AES-GCM", iv, additionalData },
+      key,
+      data,
+    ),
+  );
+  // The 16 byte tag is appended to the ciphertext.
+  assertEquals(cipherText.byteLength, data.byteLength + 16);
+  assertEquals(
+    new Uint8Array(
+      await crypto.subtle.decrypt(
+        { name: "AES-GCM", iv, additionalData },
+        key,
+        cipherText,
+      ),
+    ),
+    data,
+  );
+
+  const badTag = cipherText.slice();
+  badTag[badTag.length - 1] ^= 1;
+  const badData = cipherText.slice();
+  badData[0] ^= 1;
+  const cases: [AesGcmParams, Uint8Array][] = [
+    [{ name: "AES-GCM", iv, additionalData }, badTag],
+    [{ name: "AES-GCM", iv, additionalData }, badData],
+    [{ name: "AES-GCM", iv, additionalData }, cipherText.subarray(1)],
+    [{ name: "AES-GCM", iv, additionalData }, cipherText.subarray(0, 15)],
+    [{ name: "AES-GCM", iv }, cipherText],
+    [{ name: "AES-GCM", iv: new Uint8Array(16), additionalData }, cipherText],
+  ];
+  for (const [algorithm, data] of cases) {
+    const error = await assertRejects(
+      () => crypto.subtle.decrypt(algorithm, key, data),
+      DOMException,
+    );
+    assertEquals(error.name, "OperationError");
+  }
+
+  for (const ivLength of [8, 13]) {
+    const params = { name: "AES-GCM", iv: new Uint8Array(ivLength) };
+    let error = await assertRejects(
+      () => crypto.subtle.encrypt(params, key, data),
+      DOMException,
+    );
+    assertEquals(error.name, "NotSupportedError");
+    error = await assertRejects(
+      () => crypto.subtle.decrypt(params, key, cipherText),
+      DOMException,
+    );
+    assertEquals(error.name, "NotSupportedError");
+  }
+});
+
+Deno.test(async function p521ExportPrivateJwk() {
+  const { privateKey } = await crypto.subtle.generateKey(
+    { name: "ECDSA", namedCurve: "P-521" },
+    true,
+    ["sign", "verify"],
+  );
+  const jwk = await crypto.subtle.exportKey("jwk", privateKey);
+  assertEquals(jwk.kty, "EC");
+  assertEquals(jwk.crv, "P-521");
+  assertEquals(jwk.alg, "ES512");
+  assertEquals(jwk.key_ops, ["sign"]);
+  assertEquals(jwk.ext, true);
+
+  const imported = await crypto.subtle.importKey(
+    "jwk",
+    jwk,
+    { name: "ECDSA", namedCurve: "P-521" },
+    true,
+    ["sign"],
+  );
+  assertEquals(
+    new Uint8Array(await crypto.subtle.exportKey("pkcs8", imported)),
+    new Uint8Array(await crypto.subtle.exportKey("pkcs8", privateKey)),
+  );
+});
+
+Deno.test(async function exportKeyNotExtractable() {
+  const key = await crypto.subtle.generateKey(
+    { name: "HMAC", hash: "SHA-256" },
+    false,
+    ["sign"],
+  );
+  // The key is checked for being extractable before the format.
+  for (const format of ["raw", "jwk", "spki"] as const) {
+    const error = await assertRejects(
+      () => crypto.subtle.exportKey(format as "raw", key),
+      DOMException,
+    );
+    assertEquals(error.name, "InvalidAccessError");
+  }
+
+  const pbkdf2Key = await crypto.subtle.importKey(
+    "raw",
+    new Uint8Array(16),
+    "PBKDF2",
+    false,
+    ["deriveBits"],
+  );
+  const error = await assertRejects(
+    () => crypto.subtle.exportKey("raw", pbkdf2Key),
+    DOMException,
+  );
+  assertEquals(error.name, "NotSupportedError");
+});
+
+Deno.test(async function importKeyEcPkcs8Validation() {
+  const algorithm = { name: "ECDSA", namedCurve: "P-256" };
+  const { privateKey } = await crypto.subtle.generateKey(
+    algorithm,
+    true,
+    ["sign", "verify"],
+  );
+  const pkcs8 = new Uint8Array(
+    await crypto.subtle.exportKey("pkcs8", privateKey),
+  );
+
+  // An ECPrivateKey whose private key is zero.
+  const zeroKey = pkcs8.slice();
+  const header = [0x02, 0x01, 0x01, 0x04, 0x20];
+  const start = zeroKey.findIndex((_, i) =>
+    header.every((byte, j) => zeroKey[i + j] === byte)
+  ) + header.length;
+  zeroKey.fill(0, start, start + 32);
+
+  const { privateKey: rsaKey } = await crypto.subtle.generateKey(
+    {
+      name: "RSASSA-PKCS1-v1_5",
+      modulusLength: 2048,
+      publicExponent: new Uint8Array([1, 0, 1]),
+      hash: "SHA-256",
+    },
+    true,
+    ["sign", "verify"],
+  );
+  const rsaPkcs8 = await crypto.subtle.exportKey("pkcs8", rsaKey);
+
+  for (const data of [zeroKey, rsaPkcs8, pkcs8.subarray(0, 40)]) {
+    const error = await assertRejects(
+      () => crypto.subtle.importKey("pkcs8", data, algorithm, true, ["sign"]),
+      DOMException,
+    );
+    assertEquals(error.name, "DataError");
+  }
+
+  const imported = await crypto.subtle.importKey(
+    "pkcs8",
+    pkcs8,
+    algorithm,
+    true,
+    ["sign"],
+  );
+  assertEquals(imported.type, "private");
+
+  // Usages that don't fit the type of the key.
+  const spki = await crypto.subtle.exportKey(
+    "spki",
+    (await crypto.subtle.generateKey(algorithm, true, ["sign", "verify"]))
+      .publicKey,
+  );
+  for (
+    const [format, data, usage] of [
+      ["pkcs8", pkcs8, "verify"],
+      ["spki", spki, "sign"],
+    ] as const
+  ) {
+    const error = await assertRejects(
+      () => crypto.subtle.importKey(format, data, algorithm, true, [usage]),
+      DOMException,
+    );
+    assertEquals(error.name, "SyntaxError");
+  }
+});
+
+Deno.test(async function rsaPssSignRoundTrip() {
+  const data = new Uint8Array([1, 2, 3, 4]);
+  for (const hash of ["SHA-1", "SHA-256", "SHA-384", "SHA-512"]) {
+    const { privateKey, publicKey } = await crypto.subtle.generateKey(
+      {
+        name: "RSA-PSS",
+        modulusLength: 2048,
+        publicExponent: new Uint8Array([1, 0, 1]),
+        hash,
+      },
+      true,
+      ["sign", "verify"],
+    );
+    for (const saltLength of [0, 32]) {
+      const signature = await crypto.subtle.sign(
+        { name: "RSA-PSS", saltLength },
+        privateKey,
+        data,
+      );
+      assert(
+        await crypto.subtle.verify(
+          { name: "RSA-PSS", saltLength },
+          publicKey,
+          signature,
+          data,
+        ),
+        `${hash} with saltLength ${saltLength}`,
+      );
+    }
+
+    // The salt length is part of the signature.
+    const signature = await crypto.subtle.sign(
+      { name: "RSA-PSS", saltLength: 32 },
+      privateKey,
+      data,
+    );
+    assert(
+      !await crypto.subtle.verify(
+        { name: "RSA-PSS", saltLength: 20 },
+        publicKey,
+        signature,
+        data,
+      ),
+    );
+
+    await assertRejects(
+      // @ts-ignore testing a missing saltLength
+      () => crypto.subtle.sign({ name: "RSA-PSS" }, privateKey, data),
+      TypeError,
+      "saltLength",
+    );
+  }
+});
+
+Deno.test(async function rsaOaepSignRejects() {
+  const { privateKey } = await crypto.subtle.generateKey(
+    {
+      name: "RSA-OAEP",
+      modulusLength: 2048,
+      publicExponent: new Uint8Array([1, 0, 1]),
+      hash: "SHA-256",
+    },
+    true,
+    ["decrypt", "encrypt"],
+  );
+  const error = await assertRejects(
+    () =>
+      crypto.subtle.sign({ name: "RSA-OAEP" }, privateKey, new Uint8Array(4)),
+    DOMException,
+  );
+  assertEquals(error.name, "NotSupportedError");
+});
@@ -215,6 +215,255 @@ Deno.test({
   device.destroy();
 });
 
+const solidTriangleShader = `
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+  var positions = array<vec2<f32>, 3>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(0.0, 1.0),
+  );
+  return vec4<f32>(positions[index], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+  return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+}
+`;
+
+Deno.test({
+  ignore: isWsl || isCIWithoutGPU,
+}, async function webgpuOffscreenTrianglePixels() {
+  const adapter = await navigator.gpu.requestAdapter({
+    forceFallbackAdapter: true,
+  });
+  // Not every platform ships a software adapter.
+  if (!adapter) return;
+  const device = await adapter.requestDevice();
+
+  const module = device.createShaderModule({ code: solidTriangleShader });
+  const pipeline = device.createRenderPipeline({
+    layout: "auto",
+    vertex: { module, entryPoint: "vs_main" },
+    fragment: {
+      module,
+      entryPoint: "fs_main",
+      targets: [{ format: "rgba8unorm" }],
+    },
+  });
+
+  // Rows of 100 texels of 4 bytes each are padded to 512 bytes.
+  const width = 100;
+  const height = 100;
+  const bytesPerRow = 512;
+  const texture = device.createTexture({
+    size: { width, height },
+    format: "rgba8unorm",
+    usage: GPUTextureUsage.RENDER_ATTACHMENT | GPUTextureUsage.COPY_SRC,
+  });
+  const buffer = device.createBuffer({
+    size: bytesPerRow * height,
+    usage: GPUBufferUsage.MAP_READ | GPUBufferUsage.COPY_DST,
+  });
+
+  device.pushErrorScope("validation");
+  const encoder = device.createCommandEncoder();
+  const pass = encoder.beginRenderPass({
+    colorAttachments: [{
+      view: texture.createView(),
+      loadOp: "clear",
+      storeOp: "store",
+      clearValue: [0, 0, 1, 1],
+    }],
+  });
+  pass.setPipeline(pipeline);
+  pass.draw(3);
+  pass.end();
+  encoder.copyTextureToBuffer(
+    { texture },
+    { buffer, bytesPerRow },
+    { width, height },
+  );
+  device.queue.submit([encoder.finish()]);
+  assertEquals(await device.popErrorScope(), null);
+
+  await buffer.mapAsync(GPUMapMode.READ);
+  const data = new Uint8Array(buffer.getMappedRange());
+  const pixel = (x: number, y: number) => {
+    const offset = y * bytesPerRow + x * 4;
+    return Array.from(data.subarray(offset, offset + 4));
+  };
+
+  const red = [255, 0, 0, 255];
+  const blue = [0, 0, 255, 255];
+  assertEquals(pixel(50, 50), red);
+  assertEquals(pixel(50, 95), red);
+  assertEquals(pixel(5, 95), red);
+  assertEquals(pixel(0, 0), blue);
+  assertEquals(pixel(99, 0), blue);
+  assertEquals(pixel(10, 10), blue);
+
+  buffer.unmap();
+  device.destroy();
+});
+
+Deno.test({
+  ignore: isWsl || isCIWithoutGPU,
+}, async function webgpuCopyTextureToBufferUnpaddedBytesPerRow() {
+  const adapter = await navigator.gpu.requestAdapter();
+  assert(adapter);
+  const device = await adapter.requestDevice();
+
+  const texture = device.createTexture({
+    size: { width: 100, height: 1 },
+    format: "rgba8unorm",
+    usage: GPUTextureUsage.COPY_SRC,
+  });
+  const buffer = device.createBuffer({
+    size: 400,
+    usage: GPUBufferUsage.COPY_DST,
+  });
+
+  device.pushErrorScope("validation");
+  const encoder = device.createCommandEncoder();
+  encoder.copyTextureToBuffer(
+    { texture },
+    { buffer, bytesPerRow: 400 },
+    { width: 100, height: 1 },
+  );
+  const error = await device.popErrorScope();
+  assert(error instanceof GPUValidationError);
+  assertEquals(
+    error.message,
+    "destination.bytesPerRow (400) must be a multiple of 256, use 512 instead",
+  );
+
+  device.destroy();
+});
+
+Deno.test({
+  ignore: isWsl || isCIWithoutGPU,
+}, async function webgpuRenderPipelineDescriptorErrorNamesField() {
+  const adapter = await navigator.gpu.requestAdapter();
+  assert(adapter);
+  const device = await adapter.requestDevice();
+
+  const module = device.createShaderModule({ code: solidTriangleShader });
+  device.pushErrorScope("validation");
+  device.createRenderPipeline({
+    layout: "auto",
+    vertex: { module, entryPoint: "vs_main" },
+    fragment: {
+      module,
+      entryPoint: "fs_main",
+      targets: [{
+        format: "rgba8unorm",
+        blend: {
+          color: { operation: "max", srcFactor: "src-alpha" },
+          alpha: {},
+        },
+      }],
+    },
+  });
+  const error = await device.popErrorScope();
+  assert(error instanceof GPUValidationError);
+  assertEquals(
+    error.message,
+    'fragment.targets[0].blend.color.srcFactor must be "one" when operation is "min" or "max"',
+  );
+
+  device.destroy();
+});
+
+Deno.test({
+  ignore: isWsl || isCIWithoutGPU,
+}, async function webgpuTimestampQueriesAroundComputePass() {
+  const adapter = await navigator.gpu.requestAdapter({
+    forceFallbackAdapter: true,
+  }) ?? await navigator.gpu.requestAdapter();
+  assert(adapter);
+  if (!adapter.features.has("timestamp-query")) {
+    // the adapter can't write timestamps
+    return;
+  }
+  const device = await adapter.requestDevice({
+    requiredFeatures: ["timestamp-query"],
+  });
+
+  const querySet = device.createQuerySet({ type: "timestamp", count: 2 });
+  assertEquals(querySet.type, "timestamp");
+  const resolveBuffer = device.createBuffer({
+    size: 16,
+    usage: GPUBufferUsage.QUERY_RESOLVE | GPUBufferUsage.COPY_SRC,
+  });
+  const readBuffer = device.createBuffer({
+    size: 16,
+    usage: GPUBufferUsage.MAP_READ | GPUBufferUsage.COPY_DST,
+  });
+  const pipeline = device.createComputePipeline({
+    layout: "auto",
+    compute: {
+      module: device.createShaderModule({
+        code: "@compute @workgroup_size(1) fn main() {}",
+      }),
+      entryPoint: "main",
+    },
+  });
+
+  const encoder = device.createCommandEncoder();
+  const pass = encoder.beginComputePass({
+    timestampWrites: {
+      querySet,
+      beginningOfPassWriteIndex: 0,
+      endOfPassWriteIndex: 1,
+    },
+  });
+  pass.setPipeline(pipeline);
+  pass.dispatchWorkgroups(64);
+  pass.end();
+  encoder.resolveQuerySet(querySet, 0, 2, resolveBuffer, 0);
+  encoder.copyBufferToBuffer(resolveBuffer, 0, readBuffer, 0, 16);
+  device.queue.submit([encoder.finish()]);
+
+  await readBuffer.mapAsync(GPUMapMode.READ);
+  const [start, end] = new BigUint64Array(readBuffer.getMappedRange());
+  readBuffer.unmap();
+  assert(start > 0n, `${start}`);
+  assert(end >= start, `${start} ${end}`);
+  assert(device.queue.getTimestampPeriod() > 0);
+
+  device.destroy();
+});
+
+Deno.test({
+  ignore: isWsl || isCIWithoutGPU,
+}, async function webgpuQuerySetRequiresFeature() {
+  const adapter = await navigator.gpu.requestAdapter();
+  assert(adapter);
+  const device = await adapter.requestDevice();
+
+  assertThrows(
+    () => device.createQuerySet({ type: "timestamp", count: 2 }),
+    TypeError,
+    "Query sets of type 'timestamp' require the 'timestamp-query' feature to be enabled on the device",
+  );
+  assertThrows(
+    () =>
+      device.createQuerySet({
+        type: "pipeline-statistics",
+        count: 1,
+        pipelineStatistics: ["compute-shader-invocations"],
+      }),
+    TypeError,
+    "require the 'pipeline-statistics-query' feature",
+  );
+  const occlusion = device.createQuerySet({ type: "occlusion", count: 1 });
+  assertEquals(occlusion.type, "occlusion");
+
+  device.destroy();
+});
+
 Deno.test({
   ignore: isWsl || isCIWithoutGPU,
 }, async function webgpuAdapterHasFeatures() {
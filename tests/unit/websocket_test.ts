@@ -122,6 +122,27 @@ Deno.test(async function websocketSendLargeBlobPacket() {
   await promise;
 });
 
+Deno.test(async function websocketSendFragmentedMessage() {
+  const { promise, resolve, reject } = Promise.withResolvers<void>();
+  const ws = new WebSocket(new URL("ws://localhost:4242/"));
+  ws.binaryType = "arraybuffer";
+  ws.onerror = (e) => reject(e);
+  // sent in several frames, reassembled by the echo server
+  const data = new Uint8Array(3 * 1024 * 1024 + 1);
+  for (let i = 0; i < data.length; i++) data[i] = i % 251;
+  ws.onopen = () => {
+    ws.send(data);
+  };
+  ws.onmessage = (msg: MessageEvent) => {
+    assertEquals(new Uint8Array(msg.data), data);
+    ws.close();
+  };
+  ws.onclose = () => {
+    resolve();
+  };
+  await promise;
+});
+
 // https://github.com/denoland/deno/pull/17762
 // https://github.com/denoland/deno/issues/17761
 Deno.test(async function websocketPingPong() {
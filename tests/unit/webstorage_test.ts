@@ -55,3 +55,100 @@ Deno.test(function webstorageGetOwnPropertyDescriptorSymbol() {
   localStorage.clear();
   Object.getOwnPropertyDescriptor(localStorage, Symbol("foo"));
 });
+
+Deno.test(function webstorageKeyOrder() {
+  sessionStorage.clear();
+  for (const key of ["c", "a", "b"]) {
+    sessionStorage.setItem(key, "value");
+  }
+  const keys = () =>
+    Array.from(
+      { length: sessionStorage.length },
+      (_, i) => sessionStorage.key(i),
+    );
+  assertEquals(keys(), ["c", "a", "b"]);
+  assertEquals(keys(), ["c", "a", "b"]);
+  assertEquals(sessionStorage.key(1), "a");
+  assertEquals(sessionStorage.key(3), null);
+
+  sessionStorage.setItem("c", "updated");
+  assertEquals(keys(), ["c", "a", "b"]);
+  sessionStorage.removeItem("a");
+  assertEquals(keys(), ["c", "b"]);
+  sessionStorage.setItem("a", "value");
+  assertEquals(keys(), ["c", "b", "a"]);
+  assertEquals(Object.keys(sessionStorage), ["c", "b", "a"]);
+  sessionStorage.clear();
+});
+
+Deno.test(async function webstorageBurstOfWrites() {
+  localStorage.clear();
+  for (let i = 0; i < 1000; i++) {
+    localStorage.setItem(`key${i}`, `${i}`);
+  }
+  // a failed write doesn't affect the others written in the same task
+  assertThrows(
+    () => localStorage.setItem("k", "v".repeat(15 * 1024 * 1024)),
+    Error,
+    "Exceeded maximum storage size",
+  );
+  localStorage.removeItem("key0");
+  assertEquals(localStorage.length, 999);
+  assertEquals(localStorage.getItem("key999"), "999");
+
+  await Promise.resolve();
+  assertEquals(localStorage.length, 999);
+  assertEquals(localStorage.getItem("key0"), null);
+  localStorage.clear();
+});
+
+Deno.test(async function webstorageBatchedWritesKeepOrder() {
+  for (const storage of [localStorage, sessionStorage]) {
+    storage.clear();
+    storage.setItem("a", "1");
+    storage.removeItem("a");
+    storage.setItem("b", "1");
+    storage.setItem("a", "2");
+    storage.setItem("b", "2");
+    storage.removeItem("c");
+    storage.setItem("c", "1");
+    storage.removeItem("c");
+    // made by the microtask
+    await Promise.resolve();
+    assertEquals(Object.keys(storage), ["b", "a"]);
+    assertEquals(storage.getItem("a"), "2");
+    assertEquals(storage.getItem("b"), "2");
+
+    // reads see the writes queued before them
+    storage.setItem("d", "1");
+    assertEquals(storage.getItem("d"), "1");
+    storage.removeItem("d");
+    assertEquals(storage.length, 2);
+    storage.clear();
+  }
+});
+
+Deno.test(function webstorageQuotaAcrossKeys() {
+  localStorage.clear();
+  const chunk = "v".repeat(1024 * 1024);
+  for (let i = 0; i < 4; i++) {
+    localStorage.setItem(`k${i}`, chunk);
+  }
+  // keys count towards the 5 MiB quota as well
+  const err = assertThrows(
+    () => localStorage.setItem("k4", chunk),
+    DOMException,
+    "Exceeded maximum storage size",
+  );
+  assertEquals(err.name, "QuotaExceededError");
+  assertEquals(localStorage.getItem("k4"), null);
+  localStorage.setItem("k4", chunk.slice(4 * 2 + 2));
+
+  // replacing a value only counts the difference
+  localStorage.setItem("k0", chunk);
+  localStorage.removeItem("k1");
+  localStorage.setItem("k5", chunk);
+  localStorage.clear();
+  localStorage.setItem("k", chunk.repeat(4));
+  localStorage.clear();
+});
@@ -4,7 +4,13 @@
 
 // Requires to be run with `--allow-net` flag
 
-import { assert, assertEquals, assertMatch, assertThrows } from "@std/assert";
+import {
+  assert,
+  assertEquals,
+  assertMatch,
+  assertRejects,
+  assertThrows,
+} from "@std/assert";
 import { toFileUrl } from "@std/path/to-file-url";
 
 function resolveWorker(worker: string): string {
@@ -868,3 +874,145 @@ Deno.test({
     w.terminate();
   },
 });
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function workerTerminateIsGracefulWhenWorkerCleansUp() {
+    const path = Deno.makeTempFileSync();
+    const worker = new Worker(
+      resolveWorker("graceful_terminate_worker.js"),
+      { type: "module" },
+    );
+    const { promise, resolve } = Promise.withResolvers<void>();
+    worker.onmessage = () => resolve();
+    worker.postMessage(path);
+    await promise;
+
+    const terminated = worker.terminate();
+    // Calling it again returns the same promise.
+    assert(worker.terminate() === terminated);
+    assertEquals(await terminated, { graceful: true });
+    assertEquals(Deno.readTextFileSync(path), "closed");
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(async function workerTerminateKillsBusyWorkerAtDeadline() {
+  const worker = new Worker(
+    resolveWorker("busy_worker.js"),
+    { type: "module" },
+  );
+  const { promise, resolve } = Promise.withResolvers<void>();
+  worker.onmessage = () => resolve();
+  worker.postMessage(null);
+  await promise;
+
+  const start = performance.now();
+  assertEquals(await worker.terminate({ deadline: 100 }), {
+    graceful: false,
+  });
+  assert(performance.now() - start < 2000);
+});
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function workerTerminateIsDepthFirst() {
+    const path = Deno.makeTempFileSync();
+    const worker = new Worker(
+      resolveWorker("terminate_tree_parent.js"),
+      { type: "module" },
+    );
+    const { promise, resolve } = Promise.withResolvers<void>();
+    worker.onmessage = () => resolve();
+    worker.postMessage(path);
+    await promise;
+
+    assertEquals(await worker.terminate(), { graceful: true });
+    assertEquals(Deno.readTextFileSync(path), "child\nparent\n");
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(function workerTerminateInvalidDeadline() {
+  const worker = new Worker(
+    resolveWorker("test_worker.js"),
+    { type: "module" },
+  );
+  assertThrows(() => worker.terminate({ deadline: -1 }), TypeError);
+  worker.terminate();
+});
+
+Deno.test("Deno.Channel drained by a worker pool", async () => {
+  const items = 10_000;
+  const channel = new Deno.Channel(16);
+  const results = [];
+  for (let i = 0; i < 3; i++) {
+    const worker = new Worker(
+      resolveWorker("channel_consumer.ts"),
+      { type: "module" },
+    );
+    const { promise, resolve } = Promise.withResolvers<
+      { count: number; sum: number }
+    >();
+    worker.onmessage = (e) => resolve(e.data);
+    worker.postMessage(channel.token);
+    results.push(promise);
+  }
+
+  for (let i = 0; i < items; i++) {
+    await channel.send({ value: i });
+  }
+  for (let i = 0; i < 3; i++) {
+    await channel.send(null);
+  }
+
+  const counts = await Promise.all(results);
+  assertEquals(counts.reduce((n, r) => n + r.count, 0), items);
+  assertEquals(
+    counts.reduce((n, r) => n + r.sum, 0),
+    (items * (items - 1)) / 2,
+  );
+  channel.close();
+});
+
+Deno.test("Deno.Channel is FIFO and bounded", async () => {
+  const channel = new Deno.Channel(2);
+  await channel.send(1);
+  await channel.send(2);
+  await assertRejects(
+    () => channel.send(3, { timeoutMs: 10 }),
+    Deno.errors.TimedOut,
+  );
+  assertEquals(await channel.recv(), 1);
+  await channel.send(3);
+  assertEquals(await channel.recv(), 2);
+  assertEquals(await channel.recv(), 3);
+  await assertRejects(
+    () => channel.recv({ timeoutMs: 10 }),
+    Deno.errors.TimedOut,
+  );
+  channel.close();
+});
+
+Deno.test("Deno.Channel close wakes waiters", async () => {
+  const channel = new Deno.Channel(1);
+  const other = Deno.Channel.fromToken(channel.token);
+  const pending = other.recv();
+  channel.close();
+  await assertRejects(() => pending, Deno.errors.BadResource);
+  await assertRejects(() => other.send(1), Deno.errors.BadResource);
+  // Closing twice is a no-op.
+  other.close();
+});
+
+Deno.test("Deno.Channel invalid arguments", async () => {
+  assertThrows(() => new Deno.Channel(0), RangeError);
+  const channel = new Deno.Channel(1);
+  await assertRejects(() => channel.recv({ timeoutMs: -1 }), RangeError);
+  await assertRejects(
+    () => channel.send(() => {}),
+    DOMException,
+    "could not be cloned",
+  );
+  channel.close();
+});
@@ -0,0 +1,128 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertRejects,
+  assertThrows,
+} from "./test_util.ts";
+
+function entries(dir: string): string[] {
+  return Array.from(Deno.readDirSync(dir), (entry) => entry.name).sort();
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function writeFileAtomicReplacesContents() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/config.json`;
+    await Deno.writeFileAtomic(path, "{}");
+    assertEquals(Deno.readTextFileSync(path), "{}");
+    await Deno.writeFileAtomic(path, ['{"a":', new Uint8Array([0x31]), "}"]);
+    assertEquals(Deno.readTextFileSync(path), '{"a":1}');
+    Deno.writeFileAtomicSync(path, new TextEncoder().encode("[]"));
+    assertEquals(Deno.readTextFileSync(path), "[]");
+    assertEquals(entries(dir), ["config.json"]);
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function writeFileAtomicRotatesBackups() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/data`;
+    for (let i = 1; i <= 4; i++) {
+      Deno.writeFileAtomicSync(path, `${i}`, { backup: 2, fsync: false });
+    }
+    assertEquals(Deno.readTextFileSync(path), "4");
+    assertEquals(Deno.readTextFileSync(`${path}.bak.1`), "3");
+    assertEquals(Deno.readTextFileSync(`${path}.bak.2`), "2");
+    assertEquals(entries(dir), ["data", "data.bak.1", "data.bak.2"]);
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  function writeFileAtomicMode() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/secret`;
+    Deno.writeFileAtomicSync(path, "a", { mode: 0o600 });
+    assertEquals(Deno.statSync(path).mode! & 0o777, 0o600);
+    // The permissions of the replaced file are kept.
+    Deno.writeFileAtomicSync(path, "b");
+    assertEquals(Deno.statSync(path).mode! & 0o777, 0o600);
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "windows",
+    permissions: { read: true, write: true, run: true },
+  },
+  async function writeFileAtomicKeepsAttributesOnWindows() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}\\hidden.txt`;
+    Deno.writeTextFileSync(path, "old");
+    const attrib = (...args: string[]) =>
+      new Deno.Command("attrib", { args: [...args, path] }).output();
+    assert((await attrib("+h")).success);
+    await Deno.writeFileAtomic(path, "new", { backup: 1 });
+    assertEquals(Deno.readTextFileSync(path), "new");
+    assertEquals(Deno.readTextFileSync(`${path}.bak.1`), "old");
+    const { stdout } = await attrib();
+    const flags = new TextDecoder().decode(stdout).split(path)[0];
+    assert(flags.includes("H"), flags);
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true, run: true } },
+  async function writeFileAtomicSurvivesKill() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/state`;
+    const size = 1 << 20;
+    const versions = Array.from({ length: 8 }, (_, i) => `${i}`.repeat(size));
+    Deno.writeTextFileSync(path, versions[0]);
+    const script = `
+      const versions = ${JSON.stringify(versions.map((v) => v[0]))};
+      for (let i = 0;; i++) {
+        Deno.writeFileAtomicSync(
+          ${JSON.stringify(path)},
+          versions[i % versions.length].repeat(${size}),
+        );
+      }
+    `;
+    for (let round = 0; round < 5; round++) {
+      const child = new Deno.Command(Deno.execPath(), {
+        args: ["eval", "--unstable-fs", script],
+        stdout: "null",
+        stderr: "null",
+      }).spawn();
+      await new Promise((resolve) => setTimeout(resolve, 100 + round * 50));
+      child.kill("SIGKILL");
+      await child.status;
+      const contents = Deno.readTextFileSync(path);
+      assertEquals(contents.length, size);
+      assert(versions.includes(contents), "target holds a partial write");
+    }
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: false } },
+  async function writeFileAtomicPerm() {
+    await assertRejects(async () => {
+      await Deno.writeFileAtomic("./atomic.txt", "data");
+    }, Deno.errors.NotCapable);
+    assertThrows(() => {
+      Deno.writeFileAtomicSync("./atomic.txt", "data");
+    }, Deno.errors.NotCapable);
+  },
+);
@@ -172,3 +172,39 @@ Deno.test(async function worksWithDynamicImports() {
     assertEquals(data, "data");
   });
 });
+
+Deno.test(async function storeSurvivesTimersAndOpsInServerHandlers() {
+  const als = new AsyncLocalStorage<string>();
+  const ac = new AbortController();
+  const listening = Promise.withResolvers<number>();
+  const seen: string[] = [];
+  const server = Deno.serve({
+    signal: ac.signal,
+    port: 0,
+    onListen: ({ port }) => listening.resolve(port),
+  }, (req) => {
+    const traceId = req.headers.get("x-trace-id")!;
+    return als.run(traceId, async () => {
+      // the slower request resumes after the faster one set its id
+      await new Promise((res) => setTimeout(res, traceId === "a" ? 50 : 10));
+      seen.push(`${traceId}:${als.getStore()}`);
+      // resumed from the completion of an async op
+      await Deno.stat(Deno.cwd());
+      seen.push(`${traceId}:${als.getStore()}`);
+      return new Response(als.getStore());
+    });
+  });
+
+  const port = await listening.promise;
+  const [a, b] = await Promise.all(["a", "b"].map(async (traceId) => {
+    const res = await fetch(`http://localhost:${port}`, {
+      headers: { "x-trace-id": traceId },
+    });
+    return await res.text();
+  }));
+  assertEquals([a, b], ["a", "b"]);
+  assertEquals(seen.sort(), ["a:a", "a:a", "b:b", "b:b"]);
+  assertEquals(als.getStore(), undefined);
+  ac.abort();
+  await server.finished;
+});
@@ -2,11 +2,13 @@
 import {
   cachedDataVersionTag,
   deserialize,
+  getHeapSpaceStatistics,
   getHeapStatistics,
   serialize,
   setFlagsFromString,
+  writeHeapSnapshot,
 } from "node:v8";
-import { assertEquals } from "@std/assert";
+import { assert, assertEquals, assertMatch } from "@std/assert";
 
 // https://github.com/nodejs/node/blob/a2bbe5ff216bc28f8dac1c36a8750025a93c3827/test/parallel/test-v8-version-tag.js#L6
 Deno.test({
@@ -64,3 +66,73 @@ Deno.test({
     assertEquals(d, { a: 1 });
   },
 });
+
+Deno.test({
+  name: "getHeapSpaceStatistics success",
+  fn() {
+    const spaces = getHeapSpaceStatistics();
+    assert(spaces.length > 0);
+    for (const space of spaces) {
+      assertEquals(typeof space.space_name, "string");
+      assert(space.space_used_size <= space.space_size);
+    }
+    assert(spaces.some((space) => space.space_name === "old_space"));
+  },
+});
+
+Deno.test({
+  name: "heap statistics grow after allocating and shrink after GC",
+  fn() {
+    const { core } = Deno[Deno.internal];
+    core.ops.op_v8_low_memory_notification();
+    const before = getHeapStatistics().used_heap_size;
+
+    let array: number[][] | null = [];
+    for (let i = 0; i < 1_000_000; i++) {
+      array.push([i]);
+    }
+    const during = getHeapStatistics().used_heap_size;
+    assert(during > before, `${during} > ${before}`);
+    assertEquals(array.length, 1_000_000);
+
+    array = null;
+    core.ops.op_v8_low_memory_notification();
+    const after = getHeapStatistics().used_heap_size;
+    assert(after < during, `${after} < ${during}`);
+  },
+});
+
+Deno.test({
+  name: "writeHeapSnapshot writes a parseable snapshot",
+  fn() {
+    const dir = Deno.makeTempDirSync();
+    const filename = `${dir}/test.heapsnapshot`;
+    assertEquals(writeHeapSnapshot(filename), filename);
+    const snapshot = JSON.parse(Deno.readTextFileSync(filename));
+    for (const key of ["snapshot", "nodes", "edges", "strings"]) {
+      assert(key in snapshot, `missing ${key}`);
+    }
+    assert(snapshot.snapshot.node_count > 0);
+    Deno.removeSync(dir, { recursive: true });
+  },
+});
+
+Deno.test({
+  name: "writeHeapSnapshot default filename",
+  fn() {
+    const cwd = Deno.cwd();
+    const dir = Deno.makeTempDirSync();
+    Deno.chdir(dir);
+    try {
+      const filename = writeHeapSnapshot();
+      assertMatch(
+        filename,
+        /^Heap\.\d{8}\.\d{6}\.\d+\.\d+\.\d{3}\.heapsnapshot$/,
+      );
+      assert(Deno.statSync(filename).size > 0);
+    } finally {
+      Deno.chdir(cwd);
+      Deno.removeSync(dir, { recursive: true });
+    }
+  },
+});